@@ -0,0 +1,218 @@
+// Transaction batching
+// 交易批处理
+//
+// Market makers and cranks often have many place, cancel and settle operations to send at
+// once. The batcher packs consecutive operations into as few transactions as fit in a packet,
+// sizing each transaction as it will be sent, with the client's transaction options applied
+// and a signature for every signer, and reports the outcome of each operation. A failed
+// transaction only fails the operations packed into it. Given address lookup tables, the
+// batcher sends v0 transactions looking the accounts up in them, which fit many more
+// operations.
+
+use crate::{lookup_table, DexClient, DexInstructions, TransactionOptions};
+use solana_program::{
+    address_lookup_table::AddressLookupTableAccount,
+    hash::Hash,
+    instruction::Instruction,
+    message::{v0, Message, VersionedMessage},
+    pubkey::Pubkey,
+};
+use solana_sdk::{packet::PACKET_DATA_SIZE, signature::Signer, transaction::Transaction};
+
+/// Bytes of one transaction signature
+const SIGNATURE_LEN: usize = 64;
+
+/// Consecutive operations packed into one transaction
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Batch {
+    /// Indices of the operations in the order they were added
+    pub operations: Vec<usize>,
+
+    /// Instructions of the operations, without the transaction options
+    pub instructions: Vec<Instruction>,
+
+    /// Accounts that must sign besides the fee payer
+    pub signers: Vec<Pubkey>,
+}
+
+impl Batch {
+    // Add an operation to the transaction
+    fn push(&mut self, index: usize, operation: &DexInstructions) {
+        self.operations.push(index);
+        self.instructions.extend(operation.instructions.iter().cloned());
+        for signer in &operation.signers {
+            if !self.signers.contains(signer) {
+                self.signers.push(*signer);
+            }
+        }
+    }
+}
+
+/// Packs DEX operations into as few transactions as possible
+#[derive(Debug, Clone)]
+pub struct TransactionBatcher {
+    /// Fee payer of every transaction
+    pub payer: Pubkey,
+
+    /// Options applied to every transaction
+    pub transaction_options: TransactionOptions,
+
+    /// Lookup tables of v0 transactions; legacy transactions are sent without any
+    pub lookup_tables: Vec<AddressLookupTableAccount>,
+
+    /// Operations to send, in order
+    pub operations: Vec<DexInstructions>,
+}
+
+impl TransactionBatcher {
+    /// Create an empty batcher for transactions paid by `payer`
+    pub fn new(payer: Pubkey) -> Self {
+        Self {
+            payer,
+            transaction_options: TransactionOptions::default(),
+            lookup_tables: Vec::new(),
+            operations: Vec::new(),
+        }
+    }
+
+    /// Apply options to every transaction, as the client sending them would
+    pub fn with_transaction_options(mut self, transaction_options: TransactionOptions) -> Self {
+        self.transaction_options = transaction_options;
+        self
+    }
+
+    /// Send v0 transactions looking their accounts up in `lookup_tables`
+    pub fn with_lookup_tables(mut self, lookup_tables: Vec<AddressLookupTableAccount>) -> Self {
+        self.lookup_tables = lookup_tables;
+        self
+    }
+
+    /// Add an operation after the ones already added
+    pub fn add(&mut self, operation: DexInstructions) {
+        self.operations.push(operation);
+    }
+
+    /// Serialized size of a transaction of `instructions` with the options applied
+    ///
+    /// Instructions a v0 message cannot be compiled from take no transaction at all.
+    pub fn transaction_size(&self, instructions: &[Instruction]) -> usize {
+        let instructions = self.transaction_options.apply(instructions);
+        let (num_signatures, message_len) = if self.lookup_tables.is_empty() {
+            let message = Message::new(&instructions, Some(&self.payer));
+            (message.header.num_required_signatures, message.serialize().len())
+        } else {
+            match v0::Message::try_compile(&self.payer, &instructions, &self.lookup_tables, Hash::default()) {
+                Ok(message) => (message.header.num_required_signatures, VersionedMessage::V0(message).serialize().len()),
+                Err(_) => return usize::MAX,
+            }
+        };
+        let num_signatures = num_signatures as usize;
+        short_vec_len(num_signatures) + num_signatures * SIGNATURE_LEN + message_len
+    }
+
+    /// Pack the operations into transactions, keeping their order
+    ///
+    /// An operation joins the transaction of the one before it while the transaction still fits
+    /// in a packet. Operations that fit in no transaction of their own are returned as errors
+    /// by index instead.
+    pub fn pack(&self) -> (Vec<Batch>, Vec<(usize, String)>) {
+        let mut batches: Vec<Batch> = Vec::new();
+        let mut oversized = Vec::new();
+
+        for (index, operation) in self.operations.iter().enumerate() {
+            if let Some(batch) = batches.last_mut() {
+                let mut instructions = batch.instructions.clone();
+                instructions.extend(operation.instructions.iter().cloned());
+                if self.transaction_size(&instructions) <= PACKET_DATA_SIZE {
+                    batch.push(index, operation);
+                    continue;
+                }
+            }
+
+            let size = self.transaction_size(&operation.instructions);
+            if size <= PACKET_DATA_SIZE {
+                let mut batch = Batch::default();
+                batch.push(index, operation);
+                batches.push(batch);
+            } else if size == usize::MAX {
+                oversized.push((index, "Operation does not compile against the lookup tables".to_string()));
+            } else {
+                oversized.push((
+                    index,
+                    format!("Operation takes {} bytes, more than a transaction of {} bytes", size, PACKET_DATA_SIZE),
+                ));
+            }
+        }
+
+        (batches, oversized)
+    }
+
+    /// Send the packed transactions, returning the outcome of each operation by index: the
+    /// signature of its transaction, or why it was not sent or failed
+    ///
+    /// `signers` must hold every signer of the operations besides the payer. A transaction
+    /// missing one of its signers is not sent.
+    pub fn send(&self, client: &DexClient, payer: &dyn Signer, signers: &[&dyn Signer]) -> Vec<Result<String, String>> {
+        let mut results = vec![Err(String::new()); self.operations.len()];
+        let (batches, oversized) = self.pack();
+        for (index, error) in oversized {
+            results[index] = Err(error);
+        }
+
+        for batch in batches {
+            let result = self.send_batch(client, payer, signers, &batch);
+            for index in &batch.operations {
+                results[*index] = result.clone();
+            }
+        }
+        results
+    }
+
+    // Sign and send one packed transaction
+    fn send_batch(
+        &self,
+        client: &DexClient,
+        payer: &dyn Signer,
+        signers: &[&dyn Signer],
+        batch: &Batch,
+    ) -> Result<String, String> {
+        let mut batch_signers: Vec<&dyn Signer> = vec![payer];
+        for pubkey in batch.signers.iter().filter(|pubkey| **pubkey != payer.pubkey()) {
+            let signer = signers
+                .iter()
+                .find(|signer| signer.pubkey() == *pubkey)
+                .ok_or_else(|| format!("Missing signer {}", pubkey))?;
+            batch_signers.push(*signer);
+        }
+
+        let recent_blockhash = client.rpc_client.get_latest_blockhash().map_err(|err| err.to_string())?;
+        let instructions = self.transaction_options.apply(&batch.instructions);
+        let signature = if self.lookup_tables.is_empty() {
+            let mut transaction = Transaction::new_with_payer(&instructions, Some(&payer.pubkey()));
+            transaction
+                .try_sign(&batch_signers, recent_blockhash)
+                .map_err(|err| err.to_string())?;
+            client.rpc_client.send_and_confirm_transaction(&transaction)
+        } else {
+            let transaction = lookup_table::new_versioned_transaction(
+                &instructions,
+                payer,
+                &batch_signers,
+                &self.lookup_tables,
+                recent_blockhash,
+            )
+            .map_err(|err| err.to_string())?;
+            client.rpc_client.send_and_confirm_transaction(&transaction)
+        };
+        signature.map(|signature| signature.to_string()).map_err(|err| err.to_string())
+    }
+}
+
+// Length of the compact-u16 prefix of a serialized array
+fn short_vec_len(len: usize) -> usize {
+    match len {
+        0..=0x7f => 1,
+        0x80..=0x3fff => 2,
+        _ => 3,
+    }
+}
@@ -0,0 +1,842 @@
+// Decoding of raw DEX instructions into a readable description, e.g. to review multisig proposals
+// 将原始 DEX 指令解码为可读的描述，例如用于审查多签提案
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use borsh::BorshDeserialize;
+use solana_program::pubkey::Pubkey;
+use solana_rust_dex::instruction::DexInstruction;
+use solana_sdk::bs58;
+use std::fmt;
+use thiserror::Error;
+
+/// Text encoding of raw instruction data
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DataEncoding {
+    /// Base58, as shown by explorers and most multisig interfaces
+    Base58,
+
+    /// Standard base64
+    Base64,
+}
+
+/// Error decoding an instruction
+#[derive(Debug, Error, PartialEq)]
+pub enum DecodeError {
+    /// Data is not valid in the given encoding
+    #[error("Instruction data is not valid {0:?}")]
+    InvalidEncoding(DataEncoding),
+
+    /// Data is not a DEX instruction
+    #[error("Instruction data is not a DEX instruction")]
+    InvalidInstructionData,
+}
+
+/// Decode instruction data written in base58 or base64
+///
+/// Without an explicit encoding, data using only the base58 alphabet is read as base58 and
+/// anything else as base64.
+pub fn decode_data(data: &str, encoding: Option<DataEncoding>) -> Result<Vec<u8>, DecodeError> {
+    let data = data.trim();
+    let encoding = encoding.unwrap_or_else(|| {
+        if bs58::decode(data).into_vec().is_ok() {
+            DataEncoding::Base58
+        } else {
+            DataEncoding::Base64
+        }
+    });
+    match encoding {
+        DataEncoding::Base58 => bs58::decode(data).into_vec().ok(),
+        DataEncoding::Base64 => STANDARD.decode(data).ok(),
+    }
+    .ok_or(DecodeError::InvalidEncoding(encoding))
+}
+
+/// Roles of the accounts an instruction expects, in order
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AccountRoles {
+    /// Roles of the leading accounts; trailing optional accounts may be omitted
+    pub fixed: &'static [&'static str],
+
+    /// Role of any accounts after the fixed ones, for instructions taking a list of accounts
+    pub remaining: Option<&'static str>,
+}
+
+impl AccountRoles {
+    const fn fixed(fixed: &'static [&'static str]) -> Self {
+        Self { fixed, remaining: None }
+    }
+
+    /// Role of the account at an index, None if the instruction does not expect it
+    pub fn role(&self, index: usize) -> Option<&'static str> {
+        self.fixed.get(index).copied().or(self.remaining)
+    }
+}
+
+const INITIALIZE_MARKET_ACCOUNTS: AccountRoles = AccountRoles::fixed(&[
+    "Market authority (signer)",
+    "Market",
+    "Base token mint",
+    "Quote token mint",
+    "System program",
+    "Program config",
+    "Market registry",
+    "Treasury (creation fee)",
+    "Authority quote token account (creation fee)",
+    "Treasury quote token account (creation fee)",
+    "Token program (creation fee)",
+]);
+
+const PLACE_ORDER_ACCOUNTS: AccountRoles = AccountRoles::fixed(&[
+    "Order owner or delegate (signer)",
+    "Market",
+    "Order",
+    "Owner token account or OpenOrders to debit",
+    "Market escrow vault",
+    "Token program",
+    "System program",
+    "Instructions sysvar",
+    "Referrer quote token account",
+    "Owner whitelist entry",
+    "Market Pyth price account",
+    "SPL Memo program",
+    "Owner delegation",
+    "Market signer",
+]);
+
+/// Name of an instruction variant
+pub fn instruction_name(instruction: &DexInstruction) -> &'static str {
+    match instruction {
+        DexInstruction::InitializeMarket { .. } => "InitializeMarket",
+        DexInstruction::PlaceLimitOrder { .. } => "PlaceLimitOrder",
+        DexInstruction::CancelOrder => "CancelOrder",
+        DexInstruction::SettleFunds => "SettleFunds",
+        DexInstruction::SetInstructionGuard { .. } => "SetInstructionGuard",
+        DexInstruction::InitializeProgramConfig => "InitializeProgramConfig",
+        DexInstruction::UpdateProgramConfig { .. } => "UpdateProgramConfig",
+        DexInstruction::CancelOrdersByIds { .. } => "CancelOrdersByIds",
+        DexInstruction::ReduceOrder { .. } => "ReduceOrder",
+        DexInstruction::SetMarketCreationFee { .. } => "SetMarketCreationFee",
+        DexInstruction::LinkOrders => "LinkOrders",
+        DexInstruction::CommitOpenOrdersRoot => "CommitOpenOrdersRoot",
+        DexInstruction::PlaceIcebergOrder { .. } => "PlaceIcebergOrder",
+        DexInstruction::SetReferralShare { .. } => "SetReferralShare",
+        DexInstruction::SetTradeBusting { .. } => "SetTradeBusting",
+        DexInstruction::BustTrade => "BustTrade",
+        DexInstruction::InitializeFeeVault => "InitializeFeeVault",
+        DexInstruction::SweepFees => "SweepFees",
+        DexInstruction::SetFeeAuthority { .. } => "SetFeeAuthority",
+        DexInstruction::SetMarketStatus { .. } => "SetMarketStatus",
+        DexInstruction::CloseMarket => "CloseMarket",
+        DexInstruction::AdminCancelOrder => "AdminCancelOrder",
+        DexInstruction::SetWhitelistEnabled { .. } => "SetWhitelistEnabled",
+        DexInstruction::AddWhitelistEntry { .. } => "AddWhitelistEntry",
+        DexInstruction::RemoveWhitelistEntry => "RemoveWhitelistEntry",
+        DexInstruction::ExecuteFirmQuote { .. } => "ExecuteFirmQuote",
+        DexInstruction::InitializeMarketWithSeed { .. } => "InitializeMarketWithSeed",
+        DexInstruction::SetOracle { .. } => "SetOracle",
+        DexInstruction::CheckOracle => "CheckOracle",
+        DexInstruction::InitializeInsuranceFund => "InitializeInsuranceFund",
+        DexInstruction::DepositInsuranceFund { .. } => "DepositInsuranceFund",
+        DexInstruction::WithdrawInsuranceFund { .. } => "WithdrawInsuranceFund",
+        DexInstruction::InitializeMarketVaults => "InitializeMarketVaults",
+        DexInstruction::CloseOrderAccount => "CloseOrderAccount",
+        DexInstruction::SetMinQuoteNotional { .. } => "SetMinQuoteNotional",
+        DexInstruction::Delegate { .. } => "Delegate",
+        DexInstruction::Deposit { .. } => "Deposit",
+        DexInstruction::Withdraw { .. } => "Withdraw",
+        DexInstruction::InitializeTwapOracle { .. } => "InitializeTwapOracle",
+        DexInstruction::MigrateAccount => "MigrateAccount",
+        DexInstruction::InitializeEventQueue => "InitializeEventQueue",
+        DexInstruction::ConsumeEvents { .. } => "ConsumeEvents",
+        DexInstruction::RescueTokens { .. } => "RescueTokens",
+        DexInstruction::InitializePool { .. } => "InitializePool",
+        DexInstruction::AddLiquidity { .. } => "AddLiquidity",
+        DexInstruction::RemoveLiquidity { .. } => "RemoveLiquidity",
+        DexInstruction::SwapAgainstPool { .. } => "SwapAgainstPool",
+        DexInstruction::RouteSwap { .. } => "RouteSwap",
+        DexInstruction::InitializeRangePool { .. } => "InitializeRangePool",
+        DexInstruction::AddRangeLiquidity { .. } => "AddRangeLiquidity",
+        DexInstruction::RemoveRangeLiquidity { .. } => "RemoveRangeLiquidity",
+        DexInstruction::SwapAgainstRangePool { .. } => "SwapAgainstRangePool",
+        DexInstruction::InitializeLendingPool { .. } => "InitializeLendingPool",
+        DexInstruction::Supply { .. } => "Supply",
+        DexInstruction::Redeem { .. } => "Redeem",
+        DexInstruction::InitializePerpMarket { .. } => "InitializePerpMarket",
+        DexInstruction::DepositPerpCollateral { .. } => "DepositPerpCollateral",
+        DexInstruction::WithdrawPerpCollateral { .. } => "WithdrawPerpCollateral",
+        DexInstruction::SettlePerpFill => "SettlePerpFill",
+        DexInstruction::UpdateFunding => "UpdateFunding",
+        DexInstruction::SetPerpReduceOnly { .. } => "SetPerpReduceOnly",
+        DexInstruction::InitializeBatchAuction { .. } => "InitializeBatchAuction",
+        DexInstruction::RunAuction { .. } => "RunAuction",
+        DexInstruction::InitializeTokenSale { .. } => "InitializeTokenSale",
+        DexInstruction::ContributeToSale { .. } => "ContributeToSale",
+        DexInstruction::FinalizeSale => "FinalizeSale",
+        DexInstruction::ClaimSale => "ClaimSale",
+        DexInstruction::InitializeStakePool { .. } => "InitializeStakePool",
+        DexInstruction::Stake { .. } => "Stake",
+        DexInstruction::Unstake { .. } => "Unstake",
+        DexInstruction::DistributeStakingRewards => "DistributeStakingRewards",
+        DexInstruction::ClaimStakingRewards => "ClaimStakingRewards",
+        DexInstruction::SetLoyaltySchedule { .. } => "SetLoyaltySchedule",
+        DexInstruction::OpenLoyaltyPoints => "OpenLoyaltyPoints",
+        DexInstruction::SetFeeSplit { .. } => "SetFeeSplit",
+        DexInstruction::SetDynamicFees { .. } => "SetDynamicFees",
+        DexInstruction::PlacePerpOrder { .. } => "PlacePerpOrder",
+        DexInstruction::CancelPerpOrder => "CancelPerpOrder",
+        DexInstruction::PublishPrices { .. } => "PublishPrices",
+    }
+}
+
+/// Roles of the accounts an instruction expects
+pub fn account_roles(instruction: &DexInstruction) -> AccountRoles {
+    match instruction {
+        DexInstruction::InitializeMarket { .. } | DexInstruction::InitializeMarketWithSeed { .. } => {
+            INITIALIZE_MARKET_ACCOUNTS
+        }
+        DexInstruction::PlaceLimitOrder { .. } | DexInstruction::PlaceIcebergOrder { .. } => PLACE_ORDER_ACCOUNTS,
+        DexInstruction::CancelOrder => AccountRoles::fixed(&[
+            "Order owner or delegate (signer)",
+            "Market",
+            "Order",
+            "Owner token account to credit",
+            "Market base vault",
+            "Market quote vault",
+            "Market signer",
+            "Token program",
+            "Linked order",
+            "Owner token account to credit for the linked order",
+            "Owner delegation",
+            "Order owner",
+        ]),
+        DexInstruction::SettleFunds => AccountRoles::fixed(&[
+            "Authority (signer)",
+            "Market",
+            "Taker order",
+            "Maker order",
+            "Taker base token account",
+            "Taker quote token account",
+            "Maker base token account",
+            "Maker quote token account",
+            "Market fee vault",
+            "Market base vault",
+            "Market quote vault",
+            "Market signer",
+            "Taker order owner",
+            "Maker order owner",
+            "Token program",
+            "Instructions sysvar",
+            "Referrer quote token account",
+            "Settlement record",
+            "Market TWAP oracle",
+            "Market event queue",
+        ]),
+        DexInstruction::SetInstructionGuard { .. }
+        | DexInstruction::SetReferralShare { .. }
+        | DexInstruction::SetMarketStatus { .. }
+        | DexInstruction::SetWhitelistEnabled { .. }
+        | DexInstruction::SetOracle { .. }
+        | DexInstruction::SetMinQuoteNotional { .. }
+        | DexInstruction::SetDynamicFees { .. } => AccountRoles::fixed(&["Market authority (signer)", "Market"]),
+        DexInstruction::InitializeProgramConfig => {
+            AccountRoles::fixed(&["Config admin (signer)", "Program config", "System program"])
+        }
+        DexInstruction::Delegate { .. } => {
+            AccountRoles::fixed(&["Owner (signer)", "Market", "Owner delegation", "System program"])
+        }
+        DexInstruction::InitializeTwapOracle { .. } => {
+            AccountRoles::fixed(&["Market authority (signer)", "Market", "Market TWAP oracle", "System program"])
+        }
+        DexInstruction::MigrateAccount => {
+            AccountRoles::fixed(&["Payer (signer)", "Market or order account", "System program"])
+        }
+        DexInstruction::InitializeEventQueue => {
+            AccountRoles::fixed(&["Market authority (signer)", "Market", "Market event queue", "System program"])
+        }
+        DexInstruction::ConsumeEvents { .. } => {
+            AccountRoles::fixed(&["Market authority (signer)", "Market", "Market event queue"])
+        }
+        DexInstruction::RescueTokens { .. } => AccountRoles::fixed(&[
+            "Market authority (signer)",
+            "Market",
+            "Market escrow vault",
+            "Token account to credit",
+            "Market signer",
+            "Token program",
+        ]),
+        DexInstruction::InitializePool { .. } => AccountRoles::fixed(&[
+            "Market authority (signer)",
+            "Market",
+            "Market pool",
+            "Pool base vault",
+            "Pool quote vault",
+            "Pool LP mint",
+            "Base mint",
+            "Quote mint",
+            "Token program",
+            "System program",
+        ]),
+        DexInstruction::AddLiquidity { .. } => AccountRoles::fixed(&[
+            "Owner (signer)",
+            "Market",
+            "Market pool",
+            "Pool base vault",
+            "Pool quote vault",
+            "Pool LP mint",
+            "Owner base token account",
+            "Owner quote token account",
+            "LP token account to credit",
+            "Token program",
+        ]),
+        DexInstruction::RemoveLiquidity { .. } => AccountRoles::fixed(&[
+            "Owner (signer)",
+            "Market",
+            "Market pool",
+            "Pool base vault",
+            "Pool quote vault",
+            "Pool LP mint",
+            "Owner LP token account",
+            "Base token account to credit",
+            "Quote token account to credit",
+            "Token program",
+        ]),
+        DexInstruction::SwapAgainstPool { .. } => AccountRoles::fixed(&[
+            "Owner (signer)",
+            "Market",
+            "Market pool",
+            "Pool base vault",
+            "Pool quote vault",
+            "Owner base token account",
+            "Owner quote token account",
+            "Token program",
+        ]),
+        DexInstruction::RouteSwap { .. } => AccountRoles {
+            fixed: &[
+                "Owner (signer)",
+                "Market",
+                "Market pool",
+                "Pool base vault",
+                "Pool quote vault",
+                "Owner base token account",
+                "Owner quote token account",
+                "Market base escrow vault",
+                "Market quote escrow vault",
+                "Market signer",
+                "Market fee vault",
+                "Token program",
+            ],
+            remaining: Some("Resting order, its owner, or the owner's base or quote token account"),
+        },
+        DexInstruction::InitializeRangePool { .. } => AccountRoles::fixed(&[
+            "Market authority (signer)",
+            "Market",
+            "Market range pool",
+            "Range pool base vault",
+            "Range pool quote vault",
+            "Base mint",
+            "Quote mint",
+            "Token program",
+            "System program",
+        ]),
+        DexInstruction::AddRangeLiquidity { .. } => AccountRoles::fixed(&[
+            "Owner (signer)",
+            "Market",
+            "Market range pool",
+            "Owner range position",
+            "Range pool base vault",
+            "Range pool quote vault",
+            "Owner base token account",
+            "Owner quote token account",
+            "Token program",
+            "System program",
+        ]),
+        DexInstruction::RemoveRangeLiquidity { .. } => AccountRoles::fixed(&[
+            "Owner (signer)",
+            "Market",
+            "Market range pool",
+            "Owner range position",
+            "Range pool base vault",
+            "Range pool quote vault",
+            "Base token account to credit",
+            "Quote token account to credit",
+            "Token program",
+        ]),
+        DexInstruction::SwapAgainstRangePool { .. } => AccountRoles::fixed(&[
+            "Owner (signer)",
+            "Market",
+            "Market range pool",
+            "Range pool base vault",
+            "Range pool quote vault",
+            "Owner base token account",
+            "Owner quote token account",
+            "Token program",
+        ]),
+        DexInstruction::InitializeLendingPool { .. } => AccountRoles::fixed(&[
+            "Config admin (signer)",
+            "Program config",
+            "Lending pool",
+            "Lending pool vault",
+            "Lending pool share mint",
+            "Mint",
+            "Token program",
+            "System program",
+        ]),
+        DexInstruction::Supply { .. } => AccountRoles::fixed(&[
+            "Owner (signer)",
+            "Lending pool",
+            "Lending pool vault",
+            "Lending pool share mint",
+            "Owner token account to debit",
+            "Share token account to credit",
+            "Mint",
+            "Token program",
+        ]),
+        DexInstruction::Redeem { .. } => AccountRoles::fixed(&[
+            "Owner (signer)",
+            "Lending pool",
+            "Lending pool vault",
+            "Lending pool share mint",
+            "Owner share token account",
+            "Token account to credit",
+            "Mint",
+            "Token program",
+        ]),
+        DexInstruction::InitializePerpMarket { .. } => AccountRoles::fixed(&[
+            "Market authority (signer)",
+            "Market",
+            "Perp market",
+            "Perp collateral vault",
+            "Quote mint",
+            "Token program",
+            "System program",
+        ]),
+        DexInstruction::DepositPerpCollateral { .. } => AccountRoles::fixed(&[
+            "Owner (signer)",
+            "Market",
+            "Perp market",
+            "Perp position",
+            "Perp collateral vault",
+            "Owner token account to debit",
+            "Quote mint",
+            "Token program",
+            "System program",
+        ]),
+        DexInstruction::WithdrawPerpCollateral { .. } => AccountRoles::fixed(&[
+            "Owner (signer)",
+            "Market",
+            "Perp market",
+            "Perp position",
+            "Perp collateral vault",
+            "Token account to credit",
+            "Quote mint",
+            "Token program",
+        ]),
+        DexInstruction::SettlePerpFill => AccountRoles::fixed(&[
+            "Market authority (signer)",
+            "Market",
+            "Perp market",
+            "Taker perp order",
+            "Maker perp order",
+            "Taker perp position",
+            "Maker perp position",
+            "Perp collateral vault",
+            "Fee vault",
+            "Quote mint",
+            "Token program",
+            "Oracle",
+            "Taker order owner",
+            "Maker order owner",
+        ]),
+        DexInstruction::UpdateFunding => AccountRoles::fixed(&["Market", "Perp market", "Oracle"]),
+        DexInstruction::PlacePerpOrder { .. } => AccountRoles::fixed(&[
+            "Order owner (signer)",
+            "Market",
+            "Perp market",
+            "Owner perp position",
+            "Perp order (signer)",
+            "System program",
+            "Oracle",
+        ]),
+        DexInstruction::CancelPerpOrder => AccountRoles::fixed(&["Order owner (signer)", "Market", "Perp order"]),
+        DexInstruction::PublishPrices { .. } => AccountRoles::fixed(&[
+            "Payer (signer)",
+            "Market",
+            "TWAP oracle",
+            "Wormhole bridge config",
+            "Price message",
+            "Emitter",
+            "Emitter sequence",
+            "Wormhole fee collector",
+            "Clock sysvar",
+            "Rent sysvar",
+            "System program",
+            "Wormhole core bridge program",
+        ]),
+        DexInstruction::SetPerpReduceOnly { .. } => AccountRoles::fixed(&["Owner", "Market", "Perp order"]),
+        DexInstruction::InitializeBatchAuction { .. } => AccountRoles::fixed(&[
+            "Market authority (signer)",
+            "Market",
+            "Batch auction",
+            "System program",
+        ]),
+        DexInstruction::RunAuction { .. } => AccountRoles {
+            fixed: &[
+                "Market",
+                "Batch auction",
+                "Market base escrow vault",
+                "Market quote escrow vault",
+                "Market signer",
+                "Market fee vault",
+                "Token program",
+            ],
+            remaining: Some("Resting order, its owner, or the owner's base or quote token account"),
+        },
+        DexInstruction::InitializeTokenSale { .. } => AccountRoles::fixed(&[
+            "Market authority (signer)",
+            "Market",
+            "Token sale",
+            "Sale base vault",
+            "Sale quote vault",
+            "Authority base token account to debit",
+            "Base mint",
+            "Quote mint",
+            "Token program",
+            "System program",
+        ]),
+        DexInstruction::ContributeToSale { .. } => AccountRoles::fixed(&[
+            "Contributor (signer)",
+            "Market",
+            "Token sale",
+            "Sale contribution",
+            "Sale quote vault",
+            "Contributor quote token account to debit",
+            "Quote mint",
+            "Token program",
+            "System program",
+        ]),
+        DexInstruction::FinalizeSale => AccountRoles::fixed(&[
+            "Market",
+            "Token sale",
+            "Sale base vault",
+            "Sale quote vault",
+            "Authority base token account",
+            "Authority quote token account",
+            "Base mint",
+            "Quote mint",
+            "Token program",
+        ]),
+        DexInstruction::ClaimSale => AccountRoles::fixed(&[
+            "Contributor (signer)",
+            "Market",
+            "Token sale",
+            "Sale contribution",
+            "Sale base vault",
+            "Sale quote vault",
+            "Contributor token account to credit",
+            "Base mint",
+            "Quote mint",
+            "Token program",
+        ]),
+        DexInstruction::InitializeStakePool { .. } => AccountRoles::fixed(&[
+            "Config admin (signer)",
+            "Program config",
+            "Stake pool",
+            "Stake vault",
+            "Stake reward vault",
+            "Governance mint",
+            "Reward mint",
+            "Governance token program",
+            "Reward token program",
+            "System program",
+        ]),
+        DexInstruction::Stake { .. } => AccountRoles::fixed(&[
+            "Owner (signer)",
+            "Stake pool",
+            "Owner stake account",
+            "Stake vault",
+            "Owner governance token account to debit",
+            "Governance mint",
+            "Token program",
+            "System program",
+        ]),
+        DexInstruction::Unstake { .. } => AccountRoles::fixed(&[
+            "Owner (signer)",
+            "Stake pool",
+            "Owner stake account",
+            "Stake vault",
+            "Owner governance token account to credit",
+            "Governance mint",
+            "Token program",
+        ]),
+        DexInstruction::DistributeStakingRewards => AccountRoles::fixed(&["Stake pool", "Stake reward vault"]),
+        DexInstruction::ClaimStakingRewards => AccountRoles::fixed(&[
+            "Owner (signer)",
+            "Stake pool",
+            "Owner stake account",
+            "Stake reward vault",
+            "Owner reward token account to credit",
+            "Reward mint",
+            "Token program",
+        ]),
+        DexInstruction::SetLoyaltySchedule { .. } => {
+            AccountRoles::fixed(&["Market authority (signer)", "Market", "Loyalty schedule", "System program"])
+        }
+        DexInstruction::OpenLoyaltyPoints => {
+            AccountRoles::fixed(&["Owner (signer)", "Market", "Owner loyalty points", "System program"])
+        }
+        DexInstruction::SetFeeSplit { .. } => AccountRoles::fixed(&[
+            "Config admin (signer)",
+            "Program config",
+            "Market",
+            "Treasury fee token account",
+            "Creator fee token account",
+        ]),
+        DexInstruction::Deposit { .. } => AccountRoles::fixed(&[
+            "Owner (signer)",
+            "Market",
+            "Owner OpenOrders",
+            "Owner token account to debit",
+            "Market escrow vault",
+            "Token program",
+            "System program",
+        ]),
+        DexInstruction::Withdraw { .. } => AccountRoles::fixed(&[
+            "Owner (signer)",
+            "Market",
+            "Owner OpenOrders",
+            "Token account to credit",
+            "Market escrow vault",
+            "Market signer",
+            "Token program",
+        ]),
+        DexInstruction::UpdateProgramConfig { .. }
+        | DexInstruction::SetMarketCreationFee { .. }
+        | DexInstruction::SetTradeBusting { .. } => AccountRoles::fixed(&["Config admin (signer)", "Program config"]),
+        DexInstruction::CancelOrdersByIds { .. } => AccountRoles {
+            fixed: &[
+                "Order owner (signer)",
+                "Market",
+                "Owner base token account to credit",
+                "Owner quote token account to credit",
+                "Market base vault",
+                "Market quote vault",
+                "Market signer",
+                "Token program",
+            ],
+            remaining: Some("Order"),
+        },
+        DexInstruction::ReduceOrder { .. } => AccountRoles::fixed(&[
+            "Order owner (signer)",
+            "Market",
+            "Order",
+            "Owner token account to credit",
+            "Market base vault",
+            "Market quote vault",
+            "Market signer",
+            "Token program",
+        ]),
+        DexInstruction::LinkOrders => {
+            AccountRoles::fixed(&["Order owner (signer)", "Market", "First order", "Second order"])
+        }
+        DexInstruction::CommitOpenOrdersRoot => AccountRoles {
+            fixed: &["Market"],
+            remaining: Some("Open order"),
+        },
+        DexInstruction::BustTrade => AccountRoles::fixed(&[
+            "Market authority (signer)",
+            "Market",
+            "Program config",
+            "Settlement record",
+            "Taker (signer)",
+            "Maker (signer)",
+            "Taker base token account",
+            "Taker quote token account",
+            "Maker base token account",
+            "Maker quote token account",
+            "Token program",
+        ]),
+        DexInstruction::InitializeFeeVault => AccountRoles::fixed(&[
+            "Payer (signer)",
+            "Market",
+            "Market fee vault",
+            "Quote token mint",
+            "Token program",
+            "System program",
+        ]),
+        DexInstruction::SweepFees => AccountRoles::fixed(&[
+            "Market fee authority (signer)",
+            "Market",
+            "Market fee vault",
+            "Destination quote token account",
+            "Token program",
+        ]),
+        DexInstruction::SetFeeAuthority { .. } => AccountRoles::fixed(&["Market fee authority (signer)", "Market"]),
+        DexInstruction::CloseMarket => AccountRoles::fixed(&[
+            "Market authority (signer)",
+            "Market",
+            "Market fee vault",
+            "Lamport recipient",
+            "Token program",
+            "Market base vault",
+            "Market quote vault",
+            "Market signer PDA",
+            "Insurance fund",
+            "TWAP oracle",
+            "Event queue",
+            "Batch auction",
+            "Loyalty schedule",
+            "Pool",
+            "Pool base vault",
+            "Pool quote vault",
+            "Range pool",
+            "Range pool base vault",
+            "Range pool quote vault",
+            "Perp market",
+            "Perp vault",
+            "Token sale",
+            "Token sale base vault",
+            "Token sale quote vault",
+            "Base token program",
+        ]),
+        DexInstruction::AdminCancelOrder => AccountRoles::fixed(&[
+            "Market authority (signer)",
+            "Market",
+            "Order",
+            "Order owner token account to credit",
+            "Market base vault",
+            "Market quote vault",
+            "Market signer",
+            "Token program",
+            "Order owner",
+            "Linked order",
+            "Order owner token account to credit for the linked order",
+        ]),
+        DexInstruction::AddWhitelistEntry { .. } => AccountRoles::fixed(&[
+            "Market authority (signer)",
+            "Market",
+            "Whitelist entry",
+            "System program",
+        ]),
+        DexInstruction::RemoveWhitelistEntry => AccountRoles::fixed(&[
+            "Market authority (signer)",
+            "Market",
+            "Whitelist entry",
+            "Lamport recipient",
+        ]),
+        DexInstruction::ExecuteFirmQuote { .. } => AccountRoles::fixed(&[
+            "Taker (signer)",
+            "Market",
+            "Quote fill record",
+            "Taker base token account",
+            "Taker quote token account",
+            "Maker base token account",
+            "Maker quote token account",
+            "Market fee vault",
+            "Market RFQ authority",
+            "Token program",
+            "System program",
+            "Instructions sysvar",
+            "Taker whitelist entry",
+        ]),
+        DexInstruction::CheckOracle => AccountRoles::fixed(&["Market", "Market Pyth price account"]),
+        DexInstruction::InitializeInsuranceFund => AccountRoles::fixed(&[
+            "Payer (signer)",
+            "Market",
+            "Market insurance fund",
+            "Quote token mint",
+            "Token program",
+            "System program",
+        ]),
+        DexInstruction::DepositInsuranceFund { .. } => AccountRoles::fixed(&[
+            "Depositor (signer)",
+            "Market",
+            "Source quote token account",
+            "Market insurance fund",
+            "Token program",
+        ]),
+        DexInstruction::WithdrawInsuranceFund { .. } => AccountRoles::fixed(&[
+            "Market authority (signer)",
+            "Market",
+            "Market insurance fund",
+            "Destination quote token account",
+            "Token program",
+        ]),
+        DexInstruction::InitializeMarketVaults => AccountRoles::fixed(&[
+            "Payer (signer)",
+            "Market",
+            "Market base vault",
+            "Market quote vault",
+            "Market signer",
+            "Base token mint",
+            "Quote token mint",
+            "Token program",
+            "System program",
+        ]),
+        DexInstruction::CloseOrderAccount => AccountRoles::fixed(&["Order account (signer)", "Rent recipient"]),
+    }
+}
+
+/// Account of a decoded instruction with its role
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedAccount {
+    /// Account address
+    pub pubkey: Pubkey,
+
+    /// Role of the account, None if the instruction does not expect it
+    pub role: Option<&'static str>,
+}
+
+/// Instruction decoded from its raw data and account list
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedInstruction {
+    /// Decoded instruction with its arguments
+    pub instruction: DexInstruction,
+
+    /// Accounts in the order they were given
+    pub accounts: Vec<DecodedAccount>,
+}
+
+impl DecodedInstruction {
+    /// Name of the instruction
+    pub fn name(&self) -> &'static str {
+        instruction_name(&self.instruction)
+    }
+
+    /// Number of accounts past the ones the instruction expects
+    pub fn unexpected_accounts(&self) -> usize {
+        self.accounts.iter().filter(|account| account.role.is_none()).count()
+    }
+}
+
+impl fmt::Display for DecodedInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Instruction: {}", self.name())?;
+        writeln!(f, "Arguments: {:#?}", self.instruction)?;
+        writeln!(f, "Accounts:")?;
+        for (index, account) in self.accounts.iter().enumerate() {
+            writeln!(
+                f,
+                "  {}. {} ({})",
+                index,
+                account.pubkey,
+                account.role.unwrap_or("unexpected account")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Decode raw instruction data and its account keys
+pub fn decode_instruction(data: &[u8], accounts: &[Pubkey]) -> Result<DecodedInstruction, DecodeError> {
+    let instruction = DexInstruction::try_from_slice(data).map_err(|_| DecodeError::InvalidInstructionData)?;
+    let roles = account_roles(&instruction);
+    let accounts = accounts
+        .iter()
+        .enumerate()
+        .map(|(index, pubkey)| DecodedAccount {
+            pubkey: *pubkey,
+            role: roles.role(index),
+        })
+        .collect();
+    Ok(DecodedInstruction { instruction, accounts })
+}
@@ -0,0 +1,176 @@
+// Capital-efficiency report: funds locked in orders resting far from the mid for a long time
+// 资金效率报告：长时间挂在远离中间价位置的订单所锁定的资金
+
+use crate::{orderbook::OrderBook, preflight};
+use solana_program::pubkey::Pubkey;
+use solana_rust_dex::state::{Market, Order};
+
+/// Limits past which a resting order counts as idle capital
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IdleThresholds {
+    /// Minimum distance of the limit price from the mid, in basis points
+    pub min_distance_bps: u64,
+
+    /// Minimum time the order has been resting, in seconds
+    pub min_age_seconds: u64,
+}
+
+/// Resting order whose locked funds are unlikely to trade
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdleOrder {
+    /// Order account
+    pub order: Pubkey,
+
+    /// ID of the order in its market
+    pub order_id: u64,
+
+    /// Side of the order (true for buy, false for sell)
+    pub is_buy: bool,
+
+    /// Limit price in quote lots per base lot
+    pub limit_price: u64,
+
+    /// Remaining quantity in base lots
+    pub remaining_quantity: u64,
+
+    /// Mint the order locks
+    pub locked_mint: Pubkey,
+
+    /// Amount of the locked mint held by the order
+    pub locked_amount: u64,
+
+    /// Distance of the limit price from the mid, in basis points
+    pub distance_bps: u64,
+
+    /// Time the order has been resting, in seconds
+    pub age_seconds: u64,
+}
+
+/// Funds an owner has locked on a market and the orders suggested for cancellation
+#[derive(Debug, Clone, PartialEq)]
+pub struct CapitalEfficiencyReport {
+    /// Market the orders rest on
+    pub market: Pubkey,
+
+    /// Owner of the orders
+    pub owner: Pubkey,
+
+    /// Mid of the best bid and ask, None while either side of the book is empty
+    pub mid_price: Option<u64>,
+
+    /// Base tokens locked by all of the owner's sell orders
+    pub locked_base: u64,
+
+    /// Quote tokens locked by all of the owner's buy orders
+    pub locked_quote: u64,
+
+    /// Idle orders suggested for cancellation, furthest from the mid first
+    pub idle_orders: Vec<IdleOrder>,
+}
+
+impl CapitalEfficiencyReport {
+    /// Base tokens freed by cancelling the idle orders
+    pub fn idle_base(&self) -> u64 {
+        self.idle_total(false)
+    }
+
+    /// Quote tokens freed by cancelling the idle orders
+    pub fn idle_quote(&self) -> u64 {
+        self.idle_total(true)
+    }
+
+    fn idle_total(&self, is_buy: bool) -> u64 {
+        self.idle_orders
+            .iter()
+            .filter(|order| order.is_buy == is_buy)
+            .fold(0u64, |total, order| total.saturating_add(order.locked_amount))
+    }
+}
+
+/// Mid of the best bid and ask of a book
+pub fn mid_price(book: &OrderBook) -> Option<u64> {
+    let best_bid = book.bids.first()?.price;
+    let best_ask = book.asks.first()?.price;
+    Some(((best_bid as u128 + best_ask as u128) / 2) as u64)
+}
+
+/// Distance of a price from the mid, in basis points
+pub fn distance_bps(price: u64, mid_price: u64) -> u64 {
+    if mid_price == 0 {
+        return u64::MAX;
+    }
+    let distance = (price as u128).abs_diff(mid_price as u128) * 10000 / mid_price as u128;
+    u64::try_from(distance).unwrap_or(u64::MAX)
+}
+
+/// Amount of the locked mint an order holds: its remaining notional plus the taker fee on it
+/// for buys, its remaining quantity for sells
+pub fn locked_amount(market: &Market, order: &Order) -> u64 {
+    market
+        .escrow_amount(order.is_buy, order.limit_price, order.remaining_quantity)
+        .unwrap_or(u64::MAX)
+}
+
+/// Report an owner's locked funds on a market, given all open orders of the market
+///
+/// The mid is taken from the whole book. No order is suggested for cancellation while the
+/// mid is unknown, since the distance of an order from it cannot be measured.
+pub fn analyze_orders(
+    market_pubkey: &Pubkey,
+    market: &Market,
+    orders: &[(Pubkey, Order)],
+    owner: &Pubkey,
+    thresholds: &IdleThresholds,
+    now: u64,
+) -> CapitalEfficiencyReport {
+    let book_orders: Vec<Order> = orders.iter().map(|(_, order)| *order).collect();
+    let mid_price = mid_price(&OrderBook::from_orders(*market_pubkey, &book_orders));
+
+    let mut report = CapitalEfficiencyReport {
+        market: *market_pubkey,
+        owner: *owner,
+        mid_price,
+        locked_base: 0,
+        locked_quote: 0,
+        idle_orders: Vec::new(),
+    };
+
+    let owner_orders = orders.iter().filter(|(_, order)| {
+        order.is_initialized
+            && order.market == *market_pubkey
+            && order.owner == *owner
+            && order.remaining_quantity > 0
+    });
+    for (order_pubkey, order) in owner_orders {
+        let locked_amount = locked_amount(market, order);
+        if order.is_buy {
+            report.locked_quote = report.locked_quote.saturating_add(locked_amount);
+        } else {
+            report.locked_base = report.locked_base.saturating_add(locked_amount);
+        }
+
+        let Some(mid_price) = mid_price else {
+            continue;
+        };
+        let distance_bps = distance_bps(order.limit_price, mid_price);
+        let age_seconds = now.saturating_sub(order.creation_timestamp);
+        if distance_bps >= thresholds.min_distance_bps && age_seconds >= thresholds.min_age_seconds {
+            report.idle_orders.push(IdleOrder {
+                order: *order_pubkey,
+                order_id: order.order_id,
+                is_buy: order.is_buy,
+                limit_price: order.limit_price,
+                remaining_quantity: order.remaining_quantity,
+                locked_mint: preflight::locked_mint(market, order.is_buy),
+                locked_amount,
+                distance_bps,
+                age_seconds,
+            });
+        }
+    }
+
+    report
+        .idle_orders
+        .sort_by_key(|order| (std::cmp::Reverse(order.distance_bps), order.order_id));
+    report
+}
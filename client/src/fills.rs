@@ -0,0 +1,160 @@
+// Program events, sequenced fills and a persisted cursor that skips fills already processed
+// 程序事件、带序列号的成交，以及跳过已处理成交的持久化游标
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use solana_program::pubkey::Pubkey;
+use solana_rust_dex::state::{DexEvent, Fill};
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, ErrorKind},
+    path::Path,
+    str::FromStr,
+};
+
+/// Prefix of the log lines written by `sol_log_data`
+const PROGRAM_DATA_PREFIX: &str = "Program data: ";
+
+/// Decode the events logged by the DEX program in a transaction's log messages
+///
+/// Only data logged while the DEX program itself is executing is decoded, so another
+/// program invoked in the same transaction cannot inject events.
+pub fn parse_events(program_id: &Pubkey, logs: &[String]) -> Vec<DexEvent> {
+    let program_id = program_id.to_string();
+    let mut invoke_stack: Vec<&str> = Vec::new();
+    let mut events = Vec::new();
+
+    for log in logs {
+        if let Some(data) = log.strip_prefix(PROGRAM_DATA_PREFIX) {
+            if invoke_stack.last() == Some(&program_id.as_str()) {
+                events.extend(decode_event(data));
+            }
+        } else if let Some(rest) = log.strip_prefix("Program ") {
+            let mut words = rest.split_whitespace();
+            match (words.next(), words.next()) {
+                (Some(program), Some("invoke")) => invoke_stack.push(program),
+                (Some(_), Some("success" | "failed:")) => {
+                    invoke_stack.pop();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    events
+}
+
+/// Decode the fill events logged by the DEX program in a transaction's log messages
+pub fn parse_fill_events(program_id: &Pubkey, logs: &[String]) -> Vec<Fill> {
+    parse_events(program_id, logs)
+        .into_iter()
+        .filter_map(|event| match event {
+            DexEvent::Fill(fill) => Some(fill),
+            _ => None,
+        })
+        .collect()
+}
+
+// Decode an event from the base64 name and data fields of a program data log line
+fn decode_event(data: &str) -> Option<DexEvent> {
+    let mut fields = data.split_whitespace().map(|field| STANDARD.decode(field).ok());
+    let name = fields.next()??;
+    DexEvent::decode(&name, &fields.next()??)
+}
+
+/// Derive the WebSocket URL of an RPC URL: ws(s) scheme and, for an explicit port, the next port
+pub fn websocket_url(rpc_url: &str) -> String {
+    let (scheme, rest) = match rpc_url.split_once("://") {
+        Some(("https", rest)) => ("wss", rest),
+        Some((_, rest)) => ("ws", rest),
+        None => ("ws", rpc_url),
+    };
+    let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    let authority = match authority.rsplit_once(':') {
+        Some((host, port)) => match port.parse::<u16>() {
+            Ok(port) => format!("{}:{}", host, port.wrapping_add(1)),
+            Err(_) => authority.to_string(),
+        },
+        None => authority.to_string(),
+    };
+    format!("{}://{}{}", scheme, authority, path)
+}
+
+/// Last fill sequence number processed per market
+///
+/// Fills are numbered per market in settlement order, so a fill is new exactly when its
+/// sequence number is past the market's cursor. Persisting the cursor after each processed
+/// fill makes replays after a reconnect or restart harmless.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FillCursor {
+    last_seq_nums: BTreeMap<Pubkey, u64>,
+}
+
+impl FillCursor {
+    /// Create an empty cursor
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Last sequence number processed on a market, if any
+    pub fn last_seq_num(&self, market: &Pubkey) -> Option<u64> {
+        self.last_seq_nums.get(market).copied()
+    }
+
+    /// Whether a fill was already processed
+    pub fn is_processed(&self, fill: &Fill) -> bool {
+        self.last_seq_num(&fill.market)
+            .is_some_and(|last_seq_num| fill.seq_num <= last_seq_num)
+    }
+
+    /// Number of fills on the fill's market skipped between the cursor and the fill
+    pub fn missed(&self, fill: &Fill) -> u64 {
+        match self.last_seq_num(&fill.market) {
+            Some(last_seq_num) => fill.seq_num.saturating_sub(last_seq_num.saturating_add(1)),
+            None => 0,
+        }
+    }
+
+    /// Record a fill as processed; returns false if it already was
+    pub fn record(&mut self, fill: &Fill) -> bool {
+        if self.is_processed(fill) {
+            return false;
+        }
+        self.last_seq_nums.insert(fill.market, fill.seq_num);
+        true
+    }
+
+    /// Load a cursor saved by `save`; a missing file is an empty cursor
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Self::new()),
+            Err(err) => return Err(err),
+        };
+
+        let mut cursor = Self::new();
+        for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+            let (market, seq_num) = line
+                .split_once(' ')
+                .and_then(|(market, seq_num)| Some((Pubkey::from_str(market).ok()?, seq_num.parse().ok()?)))
+                .ok_or_else(|| io::Error::new(ErrorKind::InvalidData, format!("Invalid fill cursor line: {}", line)))?;
+            cursor.last_seq_nums.insert(market, seq_num);
+        }
+        Ok(cursor)
+    }
+
+    /// Save the cursor as one `<market> <seq_num>` line per market
+    ///
+    /// The file is replaced atomically so a crash never leaves a partial cursor behind.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let contents: String = self
+            .last_seq_nums
+            .iter()
+            .map(|(market, seq_num)| format!("{} {}\n", market, seq_num))
+            .collect();
+        let mut temp_path = path.as_os_str().to_owned();
+        temp_path.push(".tmp");
+        fs::write(&temp_path, contents)?;
+        fs::rename(&temp_path, path)
+    }
+}
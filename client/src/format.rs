@@ -0,0 +1,230 @@
+// Display formatting and parsing of token amounts and prices
+
+use thiserror::Error;
+
+/// Separators used to render numbers for a locale
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Locale {
+    /// Separator between the integer and fractional digits
+    pub decimal_separator: char,
+
+    /// Separator between groups of three integer digits, if any
+    pub group_separator: Option<char>,
+}
+
+impl Locale {
+    /// `1,234.5` (English, Chinese)
+    pub const EN: Locale = Locale {
+        decimal_separator: '.',
+        group_separator: Some(','),
+    };
+
+    /// `1.234,5` (German)
+    pub const DE: Locale = Locale {
+        decimal_separator: ',',
+        group_separator: Some('.'),
+    };
+
+    /// `1 234,5` (French)
+    pub const FR: Locale = Locale {
+        decimal_separator: ',',
+        group_separator: Some(' '),
+    };
+
+    /// `1234.5`, for machine-readable output
+    pub const PLAIN: Locale = Locale {
+        decimal_separator: '.',
+        group_separator: None,
+    };
+
+    /// Look up a locale by language tag, e.g. `en`, `zh-CN` or `de`
+    pub fn from_tag(tag: &str) -> Option<Locale> {
+        let language = tag.split(['-', '_']).next()?.to_ascii_lowercase();
+        match language.as_str() {
+            "en" | "zh" | "ja" | "ko" => Some(Locale::EN),
+            "de" | "es" | "it" | "nl" | "pt" => Some(Locale::DE),
+            "fr" | "ru" => Some(Locale::FR),
+            "plain" => Some(Locale::PLAIN),
+            _ => None,
+        }
+    }
+}
+
+/// Errors returned when parsing user input
+#[derive(Debug, Error, PartialEq)]
+pub enum FormatError {
+    // No digits in the input
+    #[error("Empty amount")]
+    Empty,
+
+    // Character that is neither a digit nor a separator of the locale
+    #[error("Invalid character '{0}'")]
+    InvalidCharacter(char),
+
+    // More fractional digits than the mint supports
+    #[error("Too many decimal places, at most {0} allowed")]
+    TooManyDecimals(u8),
+
+    // Price between two representable on-chain prices
+    #[error("Price is not a multiple of the smallest on-chain price step")]
+    PriceNotRepresentable,
+
+    // Value does not fit in an on-chain amount
+    #[error("Amount too large")]
+    Overflow,
+}
+
+/// How the amounts of one mint are displayed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AmountFormat {
+    /// Decimals of the mint
+    pub decimals: u8,
+
+    /// Fractional digits shown; extra digits are truncated, never rounded up
+    pub display_decimals: u8,
+
+    /// Separators to render with
+    pub locale: Locale,
+
+    /// Base units (atoms) in one lot of the market the amounts belong to
+    pub lot_size: u64,
+}
+
+impl AmountFormat {
+    /// Show every decimal of a mint with English separators
+    pub fn new(decimals: u8) -> Self {
+        Self {
+            decimals,
+            display_decimals: decimals,
+            locale: Locale::EN,
+            lot_size: 1,
+        }
+    }
+
+    /// Show at most `display_decimals` fractional digits
+    pub fn with_display_decimals(mut self, display_decimals: u8) -> Self {
+        self.display_decimals = display_decimals.min(self.decimals);
+        self
+    }
+
+    /// Render with the separators of a locale
+    pub fn with_locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// Count on-chain quantities and prices in lots of `lot_size` atoms
+    pub fn with_lot_size(mut self, lot_size: u64) -> Self {
+        self.lot_size = lot_size;
+        self
+    }
+
+    /// Render an amount in base units (atoms) as a decimal string
+    pub fn format(&self, atoms: u64) -> String {
+        self.format_scaled(atoms as u128, self.decimals)
+    }
+
+    /// Render an on-chain quantity in lots as a decimal string
+    pub fn format_lots(&self, lots: u64) -> String {
+        self.format_scaled(lots as u128 * self.lot_size as u128, self.decimals)
+    }
+
+    /// Parse a decimal string into base units (atoms)
+    pub fn parse(&self, input: &str) -> Result<u64, FormatError> {
+        let atoms = self.parse_scaled(input, self.decimals)?;
+        u64::try_from(atoms).map_err(|_| FormatError::Overflow)
+    }
+
+    // Render a value with `decimals` implied fractional digits
+    fn format_scaled(&self, value: u128, decimals: u8) -> String {
+        let scale = 10u128.pow(decimals as u32);
+        let integer = (value / scale).to_string();
+
+        let mut output = String::new();
+        for (i, digit) in integer.chars().enumerate() {
+            if i > 0 && (integer.len() - i).is_multiple_of(3) {
+                if let Some(group_separator) = self.locale.group_separator {
+                    output.push(group_separator);
+                }
+            }
+            output.push(digit);
+        }
+
+        let display_decimals = self.display_decimals.min(decimals) as usize;
+        if display_decimals > 0 {
+            let fraction = format!("{:0width$}", value % scale, width = decimals as usize);
+            output.push(self.locale.decimal_separator);
+            output.push_str(&fraction[..display_decimals]);
+        }
+
+        output
+    }
+
+    // Parse a decimal string into a value with `decimals` implied fractional digits
+    fn parse_scaled(&self, input: &str, decimals: u8) -> Result<u128, FormatError> {
+        let input = input.trim();
+        let (integer, fraction) = match input.split_once(self.locale.decimal_separator) {
+            Some((integer, fraction)) => (integer, fraction),
+            None => (input, ""),
+        };
+
+        let integer: String = integer
+            .chars()
+            .filter(|c| Some(*c) != self.locale.group_separator)
+            .collect();
+        if integer.is_empty() && fraction.is_empty() {
+            return Err(FormatError::Empty);
+        }
+        if let Some(c) = integer.chars().chain(fraction.chars()).find(|c| !c.is_ascii_digit()) {
+            return Err(FormatError::InvalidCharacter(c));
+        }
+        if fraction.len() > decimals as usize {
+            return Err(FormatError::TooManyDecimals(decimals));
+        }
+
+        let digits = format!("{}{:0<width$}", integer, fraction, width = decimals as usize);
+        let digits = digits.trim_start_matches('0');
+        if digits.is_empty() {
+            return Ok(0);
+        }
+        digits.parse::<u128>().map_err(|_| FormatError::Overflow)
+    }
+}
+
+/// Render an on-chain limit price (quote lots per base lot) as quote tokens per whole base token
+pub fn format_price(price: u64, base: &AmountFormat, quote: &AmountFormat) -> String {
+    // A price of one quote lot per base lot is quote_lot_size * 10^base_decimals / base_lot_size
+    // quote atoms per base token, truncated to the precision of the quote mint
+    let quote_atoms_per_token = (price as u128 * quote.lot_size as u128 * 10u128.pow(base.decimals as u32))
+        .checked_div(base.lot_size as u128)
+        .unwrap_or(0);
+    quote.format_scaled(quote_atoms_per_token, quote.decimals)
+}
+
+/// Render the average price of a fill of `quote_amount` quote atoms for `base_quantity` base
+/// lots, in quote tokens per whole base token
+pub fn format_average_price(
+    quote_amount: u64,
+    base_quantity: u64,
+    base: &AmountFormat,
+    quote: &AmountFormat,
+) -> String {
+    // Truncated to the precision of the quote mint
+    let quote_atoms_per_token = (quote_amount as u128 * 10u128.pow(base.decimals as u32))
+        .checked_div(base_quantity as u128 * base.lot_size as u128)
+        .unwrap_or(0);
+    quote.format_scaled(quote_atoms_per_token, quote.decimals)
+}
+
+/// Parse a price in quote tokens per whole base token into an on-chain limit price
+pub fn parse_price(input: &str, base: &AmountFormat, quote: &AmountFormat) -> Result<u64, FormatError> {
+    let quote_atoms_per_token = quote.parse_scaled(input, quote.decimals)?;
+    let numerator = quote_atoms_per_token
+        .checked_mul(base.lot_size as u128)
+        .ok_or(FormatError::Overflow)?;
+    let denominator = 10u128.pow(base.decimals as u32) * quote.lot_size as u128;
+    if denominator == 0 || !numerator.is_multiple_of(denominator) {
+        return Err(FormatError::PriceNotRepresentable);
+    }
+    u64::try_from(numerator / denominator).map_err(|_| FormatError::Overflow)
+}
@@ -1,32 +1,198 @@
 // Solana Rust DEX Client Library
 
-use solana_client::rpc_client::RpcClient;
+#![allow(clippy::too_many_arguments)]
+
+pub mod batch;
+pub mod decode;
+pub mod efficiency;
+pub mod fills;
+pub mod format;
+pub mod lookup_table;
+pub mod messages;
+pub mod migration;
+pub mod native;
+pub mod orderbook;
+pub mod preflight;
+pub mod rfq;
+pub mod signer;
+pub mod wormhole;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use batch::TransactionBatcher;
+use borsh::BorshDeserialize;
+use efficiency::{CapitalEfficiencyReport, IdleThresholds};
+use fills::FillCursor;
+use format::AmountFormat;
+use migration::OrderRow;
+use orderbook::{AggregatedOrderBook, FeeConvention, OrderBook, Quote, RouteLeg};
+use preflight::{OrderIntent, OwnerState, PreflightReport};
+use rfq::SignedQuote;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    pubsub_client::PubsubClient,
+    rpc_client::RpcClient,
+    rpc_config::{
+        RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcTransactionConfig, RpcTransactionLogsConfig,
+        RpcTransactionLogsFilter,
+    },
+    rpc_filter::{Memcmp, RpcFilterType},
+};
 use solana_program::{
-    instruction::Instruction,
+    address_lookup_table::{instruction as lookup_table_instruction, AddressLookupTableAccount},
+    clock::Clock,
+    hash::Hash,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
     program_pack::Pack,
     pubkey::Pubkey,
-    system_instruction,
+    system_program, sysvar,
 };
 use solana_sdk::{
+    account::from_account,
     commitment_config::CommitmentConfig,
-    signature::{Keypair, Signer},
+    compute_budget::ComputeBudgetInstruction,
+    signature::{Keypair, Signature, Signer},
+    signers::Signers,
     transaction::Transaction,
 };
 use solana_rust_dex::{
-    instruction::{DexInstruction, SelfTradeBehavior},
-    state::{Market, Order},
+    instruction::{DexInstruction, RouteOrder, SelfTradeBehavior, MAX_CANCEL_ORDER_IDS},
+    merkle,
+    state::{
+        BatchAuction, Delegation, EmissionPhase, EventQueue, FeeTier, Fill, FirmQuote, InstructionGuard, InterestRateCurve, LendingPool, Market, MarketCreationMode, MarketRegistry, MarketRegistryEntry, MarketStatus,
+        OpenOrders, Order, PerpMarket, PerpOrder, PerpPosition, PlacedOrder, Pool, ProgramConfig, QuoteFill, RangePool, RangePosition, SaleContribution, SaleStatus,
+        SettlementRecord, StakeAccount, StakePool, TokenSale, TwapOracle, WhitelistEntry, LoyaltyPoints, LoyaltySchedule,
+        MARKET_FLAG_EVENT_QUEUE, MARKET_FLAG_LOYALTY_POINTS, LOYALTY_SCHEDULE_PHASES, STAKE_FEE_TIERS,
+    },
+    wormhole::{self as core_bridge, PostedMessage},
+};
+use solana_transaction_status::UiTransactionReturnData;
+use spl_token::state::{Account as TokenAccount, Mint};
+use spl_associated_token_account::{
+    get_associated_token_address, get_associated_token_address_with_program_id,
+    instruction::create_associated_token_account_idempotent,
 };
-use spl_token::state::Account as TokenAccount;
-use spl_associated_token_account::get_associated_token_address;
-use std::error::Error;
+use std::{error::Error, path::Path, sync::Arc};
+use wormhole::WrappedAsset;
+
+/// Cost of creating a market
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarketCreationCost {
+    /// Rent-exempt balance of the market account in lamports
+    pub rent_lamports: u64,
+    
+    /// Market creation fee in lamports
+    pub fee_lamports: u64,
+    
+    /// Market creation fee in the market's quote token
+    pub fee_quote: u64,
+}
+
+/// Inclusion proof of an open order in a market's open orders root
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenOrderProof {
+    /// Order account the proof is for
+    pub order_account: Pubkey,
+    
+    /// Leaf hash of the order
+    pub leaf: [u8; 32],
+    
+    /// Position of the leaf in the address-sorted order set
+    pub index: usize,
+    
+    /// Number of leaves in the tree
+    pub num_leaves: usize,
+    
+    /// Sibling hashes from the leaf up to the root
+    pub siblings: Vec<[u8; 32]>,
+}
+
+impl OpenOrderProof {
+    /// Verify the proof against a committed root
+    pub fn verify(&self, root: &[u8; 32]) -> bool {
+        merkle::verify(root, &self.leaf, self.index, self.num_leaves, &self.siblings)
+    }
+}
+
+/// Instructions of a DEX operation and the signers they need, built without sending them
+///
+/// Integrators add the instructions to transactions of their own, next to other instructions
+/// or behind a multisig, and collect the signatures of `signers` besides the fee payer's.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DexInstructions {
+    /// Instructions of the operation, in order
+    pub instructions: Vec<Instruction>,
+    
+    /// Accounts that must sign the instructions
+    pub signers: Vec<Pubkey>,
+}
+
+/// Options applied to every transaction built by the client
+///
+/// Options for a single call, e.g. a higher priority fee for one transaction during
+/// congestion, go through `DexClient::with_options`, which leaves the client's own options
+/// for later calls.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TransactionOptions {
+    /// SPL Memo attached to each transaction, e.g. an order note, compliance tag or client identifier
+    pub memo: Option<String>,
+    
+    /// Priority fee of each transaction in micro-lamports per compute unit
+    pub compute_unit_price: Option<u64>,
+    
+    /// Compute unit limit of each transaction
+    pub compute_unit_limit: Option<u32>,
+}
+
+impl TransactionOptions {
+    /// Attach a memo to each transaction
+    pub fn with_memo(mut self, memo: &str) -> Self {
+        self.memo = Some(memo.to_string());
+        self
+    }
+    
+    /// Pay a priority fee of `micro_lamports` per compute unit on each transaction
+    pub fn with_compute_unit_price(mut self, micro_lamports: u64) -> Self {
+        self.compute_unit_price = Some(micro_lamports);
+        self
+    }
+    
+    /// Limit each transaction to `units` compute units
+    pub fn with_compute_unit_limit(mut self, units: u32) -> Self {
+        self.compute_unit_limit = Some(units);
+        self
+    }
+    
+    /// Instructions of a transaction with the options applied
+    ///
+    /// Compute budget instructions go first and the memo last.
+    pub fn apply(&self, instructions: &[Instruction]) -> Vec<Instruction> {
+        let mut budget = Vec::new();
+        if let Some(units) = self.compute_unit_limit {
+            budget.push(ComputeBudgetInstruction::set_compute_unit_limit(units));
+        }
+        if let Some(micro_lamports) = self.compute_unit_price {
+            budget.push(ComputeBudgetInstruction::set_compute_unit_price(micro_lamports));
+        }
+        let mut instructions = [budget, instructions.to_vec()].concat();
+        if let Some(memo) = &self.memo {
+            instructions.push(spl_memo::build_memo(memo.as_bytes(), &[]));
+        }
+        instructions
+    }
+}
 
 /// DEX client for interacting with the DEX program
 pub struct DexClient {
-    /// RPC client for communicating with the Solana cluster
-    pub rpc_client: RpcClient,
+    /// RPC client for communicating with the Solana cluster, shared with the clients of
+    /// `with_options`
+    pub rpc_client: Arc<RpcClient>,
     
     /// Program ID of the DEX program
     pub program_id: Pubkey,
+    
+    /// Options applied to every transaction
+    pub transaction_options: TransactionOptions,
 }
 
 impl DexClient {
@@ -38,39 +204,139 @@ impl DexClient {
         );
         
         Self {
-            rpc_client,
+            rpc_client: Arc::new(rpc_client),
             program_id,
+            transaction_options: TransactionOptions::default(),
+        }
+    }
+    
+    /// Apply options to every transaction built by this client
+    pub fn with_transaction_options(mut self, transaction_options: TransactionOptions) -> Self {
+        self.transaction_options = transaction_options;
+        self
+    }
+    
+    /// Client sharing this client's RPC connection that applies other options, for calls that
+    /// need them without changing the options of this client's later calls
+    ///
+    /// E.g. `client.with_options(TransactionOptions::default().with_compute_unit_price(50_000))`
+    /// sends one cancel with a priority fee.
+    pub fn with_options(&self, transaction_options: TransactionOptions) -> DexClient {
+        DexClient {
+            rpc_client: Arc::clone(&self.rpc_client),
+            program_id: self.program_id,
+            transaction_options,
         }
     }
     
-    /// Initialize a new market
+    /// Create a batcher packing operations into transactions sent with this client's
+    /// transaction options
+    pub fn transaction_batcher(&self, payer: &Pubkey) -> TransactionBatcher {
+        TransactionBatcher::new(*payer).with_transaction_options(self.transaction_options.clone())
+    }
+    
+    // Build and sign a transaction with the client's transaction options applied
+    fn new_signed_transaction<T: Signers + ?Sized>(
+        &self,
+        instructions: &[Instruction],
+        payer: Option<&Pubkey>,
+        signers: &T,
+        recent_blockhash: Hash,
+    ) -> Transaction {
+        Transaction::new_signed_with_payer(
+            &self.transaction_options.apply(instructions),
+            payer,
+            signers,
+            recent_blockhash,
+        )
+    }
+    
+    /// Initialize a new market; a trustless market only accepts mints without a freeze authority
+    ///
+    /// Order quantities and prices are counted in lots of `base_lot_size` base tokens and
+    /// `quote_lot_size` quote tokens.
     pub fn initialize_market(
         &self,
-        payer: &Keypair,
-        market_authority: &Keypair,
-        market_account: &Keypair,
+        payer: &dyn Signer,
+        market_authority: &dyn Signer,
+        market_account: &dyn Signer,
         base_mint: &Pubkey,
         quote_mint: &Pubkey,
         min_base_order_size: u64,
         tick_size: u64,
-        fee_rate_bps: u16,
+        taker_fee_bps: u16,
+        maker_fee_bps: i16,
+        trustless: bool,
+        base_lot_size: u64,
+        quote_lot_size: u64,
     ) -> Result<String, Box<dyn Error>> {
-        // Create initialize market instruction
-        let instruction = DexInstruction::initialize_market(
+        // Create initialize market instruction, adding the fee accounts when a creation fee is set
+        let base_token_program = self.get_token_program(base_mint)?;
+        let quote_token_program = self.get_token_program(quote_mint)?;
+        let instruction = match self.find_program_config()? {
+            Some(config)
+                if config.market_creation_fee_lamports > 0 || config.market_creation_fee_quote > 0 =>
+            {
+                let mut instruction = DexInstruction::initialize_market_with_fee(
+                    &self.program_id,
+                    &market_authority.pubkey(),
+                    &market_account.pubkey(),
+                    base_mint,
+                    quote_mint,
+                    min_base_order_size,
+                    tick_size,
+                    taker_fee_bps,
+                    maker_fee_bps,
+                    &config.treasury,
+                    &get_associated_token_address_with_program_id(
+                        &market_authority.pubkey(),
+                        quote_mint,
+                        &quote_token_program,
+                    ),
+                    &get_associated_token_address_with_program_id(&config.treasury, quote_mint, &quote_token_program),
+                )?;
+                // The creation fee is paid with the quote mint's token program
+                if let Some(token_program) = instruction.accounts.last_mut() {
+                    *token_program = AccountMeta::new_readonly(quote_token_program, false);
+                }
+                instruction
+            }
+            _ => DexInstruction::initialize_market(
+                &self.program_id,
+                &market_authority.pubkey(),
+                &market_account.pubkey(),
+                base_mint,
+                quote_mint,
+                min_base_order_size,
+                tick_size,
+                taker_fee_bps,
+                maker_fee_bps,
+            )?,
+        };
+        let instruction = if trustless {
+            DexInstruction::trustless(instruction)?
+        } else {
+            instruction
+        };
+        let instruction = DexInstruction::with_lot_sizes(instruction, base_lot_size, quote_lot_size)?;
+        
+        // Create the escrow vaults in the same transaction, each under its mint's token program
+        let mut vaults_instruction = DexInstruction::initialize_market_vaults(
             &self.program_id,
-            &market_authority.pubkey(),
+            &payer.pubkey(),
             &market_account.pubkey(),
             base_mint,
             quote_mint,
-            min_base_order_size,
-            tick_size,
-            fee_rate_bps,
+            &base_token_program,
         )?;
+        if quote_token_program != base_token_program {
+            vaults_instruction.accounts.push(AccountMeta::new_readonly(quote_token_program, false));
+        }
         
         // Create and send transaction
         let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
-        let transaction = Transaction::new_signed_with_payer(
-            &[instruction],
+        let transaction = self.new_signed_transaction(
+            &[instruction, vaults_instruction],
             Some(&payer.pubkey()),
             &[payer, market_authority, market_account],
             recent_blockhash,
@@ -80,13 +346,105 @@ impl DexClient {
         Ok(signature.to_string())
     }
     
+    /// Initialize the market PDA of a mint pair and seed, returning its address
+    pub fn initialize_market_with_seed(
+        &self,
+        payer: &dyn Signer,
+        market_authority: &dyn Signer,
+        base_mint: &Pubkey,
+        quote_mint: &Pubkey,
+        seed: u64,
+        min_base_order_size: u64,
+        tick_size: u64,
+        taker_fee_bps: u16,
+        maker_fee_bps: i16,
+        trustless: bool,
+        base_lot_size: u64,
+        quote_lot_size: u64,
+    ) -> Result<(Pubkey, String), Box<dyn Error>> {
+        // Create initialize market instruction, adding the fee accounts when a creation fee is set
+        let base_token_program = self.get_token_program(base_mint)?;
+        let quote_token_program = self.get_token_program(quote_mint)?;
+        let instruction = match self.find_program_config()? {
+            Some(config)
+                if config.market_creation_fee_lamports > 0 || config.market_creation_fee_quote > 0 =>
+            {
+                let mut instruction = DexInstruction::initialize_market_with_seed_and_fee(
+                    &self.program_id,
+                    &market_authority.pubkey(),
+                    base_mint,
+                    quote_mint,
+                    seed,
+                    min_base_order_size,
+                    tick_size,
+                    taker_fee_bps,
+                    maker_fee_bps,
+                    &config.treasury,
+                    &get_associated_token_address_with_program_id(
+                        &market_authority.pubkey(),
+                        quote_mint,
+                        &quote_token_program,
+                    ),
+                    &get_associated_token_address_with_program_id(&config.treasury, quote_mint, &quote_token_program),
+                )?;
+                // The creation fee is paid with the quote mint's token program
+                if let Some(token_program) = instruction.accounts.last_mut() {
+                    *token_program = AccountMeta::new_readonly(quote_token_program, false);
+                }
+                instruction
+            }
+            _ => DexInstruction::initialize_market_with_seed(
+                &self.program_id,
+                &market_authority.pubkey(),
+                base_mint,
+                quote_mint,
+                seed,
+                min_base_order_size,
+                tick_size,
+                taker_fee_bps,
+                maker_fee_bps,
+            )?,
+        };
+        let instruction = if trustless {
+            DexInstruction::trustless(instruction)?
+        } else {
+            instruction
+        };
+        let instruction = DexInstruction::with_lot_sizes(instruction, base_lot_size, quote_lot_size)?;
+        
+        // Create the escrow vaults in the same transaction, each under its mint's token program
+        let mut vaults_instruction = DexInstruction::initialize_market_vaults(
+            &self.program_id,
+            &payer.pubkey(),
+            &self.get_market_address(base_mint, quote_mint, seed),
+            base_mint,
+            quote_mint,
+            &base_token_program,
+        )?;
+        if quote_token_program != base_token_program {
+            vaults_instruction.accounts.push(AccountMeta::new_readonly(quote_token_program, false));
+        }
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction, vaults_instruction],
+            Some(&payer.pubkey()),
+            &[payer, market_authority],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok((self.get_market_address(base_mint, quote_mint, seed), signature.to_string()))
+    }
+    
     /// Place a limit order
     pub fn place_limit_order(
         &self,
-        payer: &Keypair,
-        owner: &Keypair,
+        payer: &dyn Signer,
+        owner: &dyn Signer,
         market_pubkey: &Pubkey,
-        order_account: &Keypair,
+        order_account: &dyn Signer,
         owner_token_account: &Pubkey,
         token_program: &Pubkey,
         is_buy: bool,
@@ -94,8 +452,85 @@ impl DexClient {
         quantity: u64,
         self_trade_behavior: SelfTradeBehavior,
     ) -> Result<String, Box<dyn Error>> {
+        let place = self.place_limit_order_ix(
+            &owner.pubkey(),
+            market_pubkey,
+            &order_account.pubkey(),
+            owner_token_account,
+            token_program,
+            is_buy,
+            limit_price,
+            quantity,
+            self_trade_behavior,
+        )?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &place.instructions,
+            Some(&payer.pubkey()),
+            &[payer, owner, order_account],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Build the instructions of `place_limit_order` without sending them
+    ///
+    /// The owner and the new order account must sign.
+    pub fn place_limit_order_ix(
+        &self,
+        owner: &Pubkey,
+        market_pubkey: &Pubkey,
+        order_account: &Pubkey,
+        owner_token_account: &Pubkey,
+        token_program: &Pubkey,
+        is_buy: bool,
+        limit_price: u64,
+        quantity: u64,
+        self_trade_behavior: SelfTradeBehavior,
+    ) -> Result<DexInstructions, Box<dyn Error>> {
         // Create place limit order instruction
         let instruction = DexInstruction::place_limit_order(
+            &self.program_id,
+            owner,
+            market_pubkey,
+            order_account,
+            owner_token_account,
+            token_program,
+            is_buy,
+            limit_price,
+            quantity,
+            self_trade_behavior,
+        )?;
+        
+        let instruction = Self::with_token_mints(instruction, &self.get_market(market_pubkey)?)?;
+        
+        Ok(DexInstructions {
+            instructions: vec![instruction],
+            signers: vec![*owner, *order_account],
+        })
+    }
+    
+    /// Place a limit order that pays a share of its taker fees to a referrer
+    pub fn place_limit_order_with_referrer(
+        &self,
+        payer: &dyn Signer,
+        owner: &dyn Signer,
+        market_pubkey: &Pubkey,
+        order_account: &dyn Signer,
+        owner_token_account: &Pubkey,
+        token_program: &Pubkey,
+        is_buy: bool,
+        limit_price: u64,
+        quantity: u64,
+        self_trade_behavior: SelfTradeBehavior,
+        referrer_token_account: &Pubkey,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create place limit order instruction
+        let instruction = DexInstruction::place_limit_order_with_referrer(
             &self.program_id,
             &owner.pubkey(),
             market_pubkey,
@@ -106,11 +541,14 @@ impl DexClient {
             limit_price,
             quantity,
             self_trade_behavior,
+            referrer_token_account,
         )?;
         
+        let instruction = Self::with_token_mints(instruction, &self.get_market(market_pubkey)?)?;
+        
         // Create and send transaction
         let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
-        let transaction = Transaction::new_signed_with_payer(
+        let transaction = self.new_signed_transaction(
             &[instruction],
             Some(&payer.pubkey()),
             &[payer, owner, order_account],
@@ -121,32 +559,44 @@ impl DexClient {
         Ok(signature.to_string())
     }
     
-    /// Cancel an order
-    pub fn cancel_order(
+    /// Place an iceberg order that displays `display_quantity` at a time
+    pub fn place_iceberg_order(
         &self,
-        payer: &Keypair,
-        owner: &Keypair,
+        payer: &dyn Signer,
+        owner: &dyn Signer,
         market_pubkey: &Pubkey,
-        order_account: &Pubkey,
+        order_account: &dyn Signer,
         owner_token_account: &Pubkey,
         token_program: &Pubkey,
+        is_buy: bool,
+        limit_price: u64,
+        quantity: u64,
+        display_quantity: u64,
+        self_trade_behavior: SelfTradeBehavior,
     ) -> Result<String, Box<dyn Error>> {
-        // Create cancel order instruction
-        let instruction = DexInstruction::cancel_order(
+        // Create place iceberg order instruction
+        let instruction = DexInstruction::place_iceberg_order(
             &self.program_id,
             &owner.pubkey(),
             market_pubkey,
-            order_account,
+            &order_account.pubkey(),
             owner_token_account,
             token_program,
+            is_buy,
+            limit_price,
+            quantity,
+            display_quantity,
+            self_trade_behavior,
         )?;
         
+        let instruction = Self::with_token_mints(instruction, &self.get_market(market_pubkey)?)?;
+        
         // Create and send transaction
         let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
-        let transaction = Transaction::new_signed_with_payer(
+        let transaction = self.new_signed_transaction(
             &[instruction],
             Some(&payer.pubkey()),
-            &[payer, owner],
+            &[payer, owner, order_account],
             recent_blockhash,
         );
         
@@ -154,29 +604,4330 @@ impl DexClient {
         Ok(signature.to_string())
     }
     
-    /// Get market information
-    pub fn get_market(&self, market_pubkey: &Pubkey) -> Result<Market, Box<dyn Error>> {
-        let account = self.rpc_client.get_account(market_pubkey)?;
-        let market = Market::unpack_from_slice(&account.data)?;
-        Ok(market)
-    }
-    
-    /// Get order information
-    pub fn get_order(&self, order_pubkey: &Pubkey) -> Result<Order, Box<dyn Error>> {
-        let account = self.rpc_client.get_account(order_pubkey)?;
-        let order = Order::unpack_from_slice(&account.data)?;
-        Ok(order)
+    /// Place a limit order on a permissioned market, passing the owner's whitelist entry
+    pub fn place_limit_order_whitelisted(
+        &self,
+        payer: &dyn Signer,
+        owner: &dyn Signer,
+        market_pubkey: &Pubkey,
+        order_account: &dyn Signer,
+        owner_token_account: &Pubkey,
+        token_program: &Pubkey,
+        is_buy: bool,
+        limit_price: u64,
+        quantity: u64,
+        self_trade_behavior: SelfTradeBehavior,
+        referrer_token_account: Option<&Pubkey>,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create place limit order whitelisted instruction
+        let instruction = DexInstruction::place_limit_order_whitelisted(
+            &self.program_id,
+            &owner.pubkey(),
+            market_pubkey,
+            &order_account.pubkey(),
+            owner_token_account,
+            token_program,
+            is_buy,
+            limit_price,
+            quantity,
+            self_trade_behavior,
+            referrer_token_account,
+        )?;
+        
+        let instruction = Self::with_token_mints(instruction, &self.get_market(market_pubkey)?)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, owner, order_account],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
     }
     
-    /// Get token account information
-    pub fn get_token_account(&self, token_account_pubkey: &Pubkey) -> Result<TokenAccount, Box<dyn Error>> {
-        let account = self.rpc_client.get_account(token_account_pubkey)?;
-        let token_account = TokenAccount::unpack_from_slice(&account.data)?;
-        Ok(token_account)
+    /// Place an iceberg order on a permissioned market, passing the owner's whitelist entry
+    pub fn place_iceberg_order_whitelisted(
+        &self,
+        payer: &dyn Signer,
+        owner: &dyn Signer,
+        market_pubkey: &Pubkey,
+        order_account: &dyn Signer,
+        owner_token_account: &Pubkey,
+        token_program: &Pubkey,
+        is_buy: bool,
+        limit_price: u64,
+        quantity: u64,
+        display_quantity: u64,
+        self_trade_behavior: SelfTradeBehavior,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create place iceberg order whitelisted instruction
+        let instruction = DexInstruction::place_iceberg_order_whitelisted(
+            &self.program_id,
+            &owner.pubkey(),
+            market_pubkey,
+            &order_account.pubkey(),
+            owner_token_account,
+            token_program,
+            is_buy,
+            limit_price,
+            quantity,
+            display_quantity,
+            self_trade_behavior,
+        )?;
+        
+        let instruction = Self::with_token_mints(instruction, &self.get_market(market_pubkey)?)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, owner, order_account],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
     }
     
-    /// Get associated token account address
-    pub fn get_associated_token_account(&self, wallet_pubkey: &Pubkey, token_mint: &Pubkey) -> Pubkey {
-        get_associated_token_address(wallet_pubkey, token_mint)
-    }
-} 
\ No newline at end of file
+    /// Place a limit or iceberg order on a market with an oracle circuit breaker
+    ///
+    /// The market's oracle account is passed, along with the owner's whitelist entry on a
+    /// permissioned market.
+    pub fn place_order_with_oracle(
+        &self,
+        payer: &dyn Signer,
+        owner: &dyn Signer,
+        market_pubkey: &Pubkey,
+        order_account: &dyn Signer,
+        owner_token_account: &Pubkey,
+        token_program: &Pubkey,
+        is_buy: bool,
+        limit_price: u64,
+        quantity: u64,
+        display_quantity: Option<u64>,
+        self_trade_behavior: SelfTradeBehavior,
+        referrer_token_account: Option<&Pubkey>,
+    ) -> Result<String, Box<dyn Error>> {
+        let market = self.get_market(market_pubkey)?;
+        
+        // Create the place order instruction with the oracle account
+        let instruction = self.place_order_instruction(
+            &market,
+            &owner.pubkey(),
+            market_pubkey,
+            &order_account.pubkey(),
+            owner_token_account,
+            token_program,
+            is_buy,
+            limit_price,
+            quantity,
+            display_quantity,
+            self_trade_behavior,
+            referrer_token_account,
+        )?;
+        
+        let instruction = Self::with_token_mints(instruction, &market)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, owner, order_account],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Place a limit or iceberg order tagged with a memo
+    ///
+    /// The memo is recorded with the order and in its fills, and logged through the SPL Memo
+    /// program signed by the owner.
+    pub fn place_order_with_memo(
+        &self,
+        payer: &dyn Signer,
+        owner: &dyn Signer,
+        market_pubkey: &Pubkey,
+        order_account: &dyn Signer,
+        owner_token_account: &Pubkey,
+        token_program: &Pubkey,
+        is_buy: bool,
+        limit_price: u64,
+        quantity: u64,
+        display_quantity: Option<u64>,
+        self_trade_behavior: SelfTradeBehavior,
+        referrer_token_account: Option<&Pubkey>,
+        memo: &str,
+    ) -> Result<String, Box<dyn Error>> {
+        let market = self.get_market(market_pubkey)?;
+        
+        // Create the place order instruction and attach the memo
+        let instruction = DexInstruction::with_memo(
+            self.place_order_instruction(
+                &market,
+                &owner.pubkey(),
+                market_pubkey,
+                &order_account.pubkey(),
+                owner_token_account,
+                token_program,
+                is_buy,
+                limit_price,
+                quantity,
+                display_quantity,
+                self_trade_behavior,
+                referrer_token_account,
+            )?,
+            memo,
+        )?;
+        
+        let instruction = Self::with_token_mints(instruction, &market)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, owner, order_account],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Place a limit or iceberg order for an owner as the owner's delegate
+    ///
+    /// The owner must have delegated trading on the market to the delegate and approved the
+    /// market signer as token delegate of `owner_token_account`; the delegate pays the order
+    /// account's rent.
+    pub fn place_order_as_delegate(
+        &self,
+        payer: &dyn Signer,
+        delegate: &dyn Signer,
+        owner: &Pubkey,
+        market_pubkey: &Pubkey,
+        order_account: &dyn Signer,
+        owner_token_account: &Pubkey,
+        token_program: &Pubkey,
+        is_buy: bool,
+        limit_price: u64,
+        quantity: u64,
+        display_quantity: Option<u64>,
+        self_trade_behavior: SelfTradeBehavior,
+        referrer_token_account: Option<&Pubkey>,
+        memo: Option<&str>,
+    ) -> Result<String, Box<dyn Error>> {
+        let market = self.get_market(market_pubkey)?;
+        
+        // Create the place order instruction for the owner and sign it with the delegate
+        let mut instruction = self.place_order_instruction(
+            &market,
+            owner,
+            market_pubkey,
+            &order_account.pubkey(),
+            owner_token_account,
+            token_program,
+            is_buy,
+            limit_price,
+            quantity,
+            display_quantity,
+            self_trade_behavior,
+            referrer_token_account,
+        )?;
+        if let Some(memo) = memo {
+            instruction = DexInstruction::with_memo(instruction, memo)?;
+        }
+        let instruction = DexInstruction::with_delegate(instruction, &delegate.pubkey())?;
+        
+        let instruction = Self::with_token_mints(instruction, &market)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, delegate, order_account],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Delegate trading on a market to a secondary key until a slot
+    ///
+    /// Each `(token_account, amount)` allowance approves the market signer to lock up to
+    /// `amount` of the owner's tokens in orders the delegate places.
+    pub fn delegate(
+        &self,
+        payer: &dyn Signer,
+        owner: &dyn Signer,
+        market_pubkey: &Pubkey,
+        delegate: &Pubkey,
+        expiry_slot: u64,
+        allowances: &[(Pubkey, u64)],
+    ) -> Result<String, Box<dyn Error>> {
+        // Create delegate instruction, followed by the token approvals
+        let mut instructions = vec![DexInstruction::delegate(
+            &self.program_id,
+            &owner.pubkey(),
+            market_pubkey,
+            delegate,
+            expiry_slot,
+        )?];
+        let market_signer = Market::find_signer_address(&self.program_id, market_pubkey).0;
+        for (token_account, amount) in allowances {
+            instructions.push(spl_token_2022::instruction::approve(
+                &self.get_token_program(token_account)?,
+                token_account,
+                &market_signer,
+                &owner.pubkey(),
+                &[],
+                *amount,
+            )?);
+        }
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &instructions,
+            Some(&payer.pubkey()),
+            &[payer, owner],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Get an owner's trading delegation on a market, if any
+    pub fn get_delegation(&self, market_pubkey: &Pubkey, owner: &Pubkey) -> Result<Option<Delegation>, Box<dyn Error>> {
+        let delegation_pubkey = Delegation::find_address(&self.program_id, market_pubkey, owner).0;
+        let account = self
+            .rpc_client
+            .get_account_with_commitment(&delegation_pubkey, self.rpc_client.commitment())?
+            .value;
+        match account {
+            Some(account) if account.owner == self.program_id => Ok(Some(Delegation::unpack_from_slice(&account.data)?)),
+            _ => Ok(None),
+        }
+    }
+    
+    /// Deposit base or quote tokens into the owner's OpenOrders free balance on a market
+    pub fn deposit(
+        &self,
+        payer: &dyn Signer,
+        owner: &dyn Signer,
+        market_pubkey: &Pubkey,
+        owner_token_account: &Pubkey,
+        is_quote: bool,
+        amount: u64,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create deposit instruction
+        let instruction = DexInstruction::deposit(
+            &self.program_id,
+            &owner.pubkey(),
+            market_pubkey,
+            owner_token_account,
+            &spl_token::id(),
+            is_quote,
+            amount,
+        )?;
+        
+        let instruction = Self::with_token_mints(instruction, &self.get_market(market_pubkey)?)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, owner],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Withdraw base or quote tokens from the owner's OpenOrders free balance on a market
+    pub fn withdraw(
+        &self,
+        payer: &dyn Signer,
+        owner: &dyn Signer,
+        market_pubkey: &Pubkey,
+        destination: &Pubkey,
+        is_quote: bool,
+        amount: u64,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create withdraw instruction
+        let instruction = DexInstruction::withdraw(
+            &self.program_id,
+            &owner.pubkey(),
+            market_pubkey,
+            destination,
+            &spl_token::id(),
+            is_quote,
+            amount,
+        )?;
+        
+        let instruction = Self::with_token_mints(instruction, &self.get_market(market_pubkey)?)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, owner],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Wrap lamports of the owner into its wrapped SOL account, created by the payer if needed
+    pub fn wrap_sol(
+        &self,
+        payer: &dyn Signer,
+        owner: &dyn Signer,
+        token_program: &Pubkey,
+        lamports: u64,
+    ) -> Result<String, Box<dyn Error>> {
+        let instructions =
+            native::create_and_wrap_instructions(&payer.pubkey(), &owner.pubkey(), token_program, lamports)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &instructions,
+            Some(&payer.pubkey()),
+            &[payer, owner],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Unwrap all of the owner's wrapped SOL by closing its wrapped SOL account
+    pub fn unwrap_sol(
+        &self,
+        payer: &dyn Signer,
+        owner: &dyn Signer,
+        token_program: &Pubkey,
+    ) -> Result<String, Box<dyn Error>> {
+        let instruction = native::unwrap_instruction(&owner.pubkey(), token_program)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, owner],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Place a limit or iceberg order on a market trading SOL, paying in and receiving native SOL
+    ///
+    /// The owner's wrapped SOL account is created if needed, and when the order locks SOL the
+    /// lamports its balance is short of are wrapped in the same transaction. Fills and refunds
+    /// pay into the wrapped SOL account until it is unwrapped.
+    pub fn place_order_with_native_sol(
+        &self,
+        payer: &dyn Signer,
+        owner: &dyn Signer,
+        market_pubkey: &Pubkey,
+        order_account: &dyn Signer,
+        is_buy: bool,
+        limit_price: u64,
+        quantity: u64,
+        display_quantity: Option<u64>,
+        self_trade_behavior: SelfTradeBehavior,
+    ) -> Result<String, Box<dyn Error>> {
+        let market = self.get_market(market_pubkey)?;
+        let native_is_quote = native_side(&market, market_pubkey)?;
+        let native_program = *market.token_program(native_is_quote);
+        let mut instructions = vec![create_associated_token_account_idempotent(
+            &payer.pubkey(),
+            &owner.pubkey(),
+            market.mint(native_is_quote),
+            &native_program,
+        )];
+        
+        // Wrap what the order locks beyond the wrapped balance
+        let owner_token_account = market_token_account(&market, &owner.pubkey(), is_buy);
+        if native_is_quote == is_buy {
+            let available = self
+                .rpc_client
+                .get_account_with_commitment(&owner_token_account, self.rpc_client.commitment())?
+                .value
+                .map(|account| TokenAccount::unpack_from_slice(&account.data))
+                .transpose()?
+                .map_or(0, |account| account.amount);
+            let required = market.escrow_amount(is_buy, limit_price, quantity)?;
+            if required > available {
+                instructions.extend(native::wrap_instructions(
+                    &owner.pubkey(),
+                    &native_program,
+                    required - available,
+                )?);
+            }
+        }
+        
+        // Create place order instruction
+        let instruction = self.place_order_instruction(
+            &market,
+            &owner.pubkey(),
+            market_pubkey,
+            &order_account.pubkey(),
+            &owner_token_account,
+            market.token_program(is_buy),
+            is_buy,
+            limit_price,
+            quantity,
+            display_quantity,
+            self_trade_behavior,
+            None,
+        )?;
+        instructions.push(Self::with_token_mints(instruction, &market)?);
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &instructions,
+            Some(&payer.pubkey()),
+            &[payer, owner, order_account],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Cancel an order on a market trading SOL and unwrap the owner's wrapped SOL
+    ///
+    /// The refund goes to the owner's associated token account, created by the payer if needed.
+    /// The wrapped SOL account is only closed when no other order of the owner rests on the
+    /// market, since their fills and refunds pay into it. Closing it unwraps its whole balance,
+    /// including wrapped SOL the owner held before the order.
+    pub fn cancel_order_and_unwrap(
+        &self,
+        payer: &dyn Signer,
+        owner: &dyn Signer,
+        market_pubkey: &Pubkey,
+        order_account: &Pubkey,
+    ) -> Result<String, Box<dyn Error>> {
+        let market = self.get_market(market_pubkey)?;
+        let native_is_quote = native_side(&market, market_pubkey)?;
+        let order = self.get_order(order_account)?;
+        
+        // Create cancel order instruction, refunding the owner's associated token account
+        let owner_token_account = market_token_account(&market, &owner.pubkey(), order.is_buy);
+        let mut instructions = vec![create_associated_token_account_idempotent(
+            &payer.pubkey(),
+            &owner.pubkey(),
+            market.mint(order.is_buy),
+            market.token_program(order.is_buy),
+        )];
+        let instruction = self.cancel_order_instruction(
+            market_pubkey,
+            order_account,
+            &owner_token_account,
+            market.token_program(order.is_buy),
+        )?;
+        instructions.push(Self::with_token_mints(instruction, &market)?);
+        
+        // Unwrap once nothing else pays into the wrapped SOL account
+        let other_orders = self
+            .export_orders(market_pubkey, &owner.pubkey())?
+            .iter()
+            .filter(|row| row.order != *order_account && row.order != order.linked_order)
+            .count();
+        if other_orders == 0 {
+            instructions.push(native::unwrap_instruction(
+                &owner.pubkey(),
+                market.token_program(native_is_quote),
+            )?);
+        }
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &instructions,
+            Some(&payer.pubkey()),
+            &[payer, owner],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Get an owner's OpenOrders free balances on a market, if it has deposited
+    pub fn get_open_orders(&self, market_pubkey: &Pubkey, owner: &Pubkey) -> Result<Option<OpenOrders>, Box<dyn Error>> {
+        let open_orders_pubkey = self.get_open_orders_address(market_pubkey, owner);
+        let account = self
+            .rpc_client
+            .get_account_with_commitment(&open_orders_pubkey, self.rpc_client.commitment())?
+            .value;
+        match account {
+            Some(account) if account.owner == self.program_id => Ok(Some(OpenOrders::unpack_from_slice(&account.data)?)),
+            _ => Ok(None),
+        }
+    }
+    
+    /// Get the order ID assigned by a confirmed order placement transaction
+    ///
+    /// Read from the transaction's return data, so the order account need not be fetched.
+    pub fn get_placed_order(&self, signature: &str) -> Result<Option<PlacedOrder>, Box<dyn Error>> {
+        let config = RpcTransactionConfig {
+            encoding: None,
+            commitment: Some(self.rpc_client.commitment()),
+            max_supported_transaction_version: Some(0),
+        };
+        let transaction = self.rpc_client.get_transaction_with_config(&signature.parse::<Signature>()?, config)?;
+        let return_data = transaction
+            .transaction
+            .meta
+            .and_then(|meta| Option::<UiTransactionReturnData>::from(meta.return_data));
+        match return_data {
+            Some(return_data) if return_data.program_id == self.program_id.to_string() => {
+                Ok(Some(PlacedOrder::try_from_slice(&STANDARD.decode(return_data.data.0)?)?))
+            }
+            _ => Ok(None),
+        }
+    }
+    
+    /// Place a limit or iceberg order funded from the owner's OpenOrders free balance
+    ///
+    /// No tokens move when the order is placed; cancels and fills still pay out to token
+    /// accounts.
+    pub fn place_order_from_deposits(
+        &self,
+        payer: &dyn Signer,
+        owner: &dyn Signer,
+        market_pubkey: &Pubkey,
+        order_account: &dyn Signer,
+        is_buy: bool,
+        limit_price: u64,
+        quantity: u64,
+        display_quantity: Option<u64>,
+        self_trade_behavior: SelfTradeBehavior,
+        referrer_token_account: Option<&Pubkey>,
+    ) -> Result<String, Box<dyn Error>> {
+        let market = self.get_market(market_pubkey)?;
+        
+        // The OpenOrders account takes the place of the token account to debit
+        let open_orders_pubkey = self.get_open_orders_address(market_pubkey, &owner.pubkey());
+        let instruction = self.place_order_instruction(
+            &market,
+            &owner.pubkey(),
+            market_pubkey,
+            &order_account.pubkey(),
+            &open_orders_pubkey,
+            &spl_token::id(),
+            is_buy,
+            limit_price,
+            quantity,
+            display_quantity,
+            self_trade_behavior,
+            referrer_token_account,
+        )?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, owner, order_account],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    // Place order instruction for a market: iceberg or limit, with the whitelist entry on a
+    // permissioned market and the oracle on a market with an oracle
+    fn place_order_instruction(
+        &self,
+        market: &Market,
+        owner: &Pubkey,
+        market_pubkey: &Pubkey,
+        order_account: &Pubkey,
+        owner_token_account: &Pubkey,
+        token_program: &Pubkey,
+        is_buy: bool,
+        limit_price: u64,
+        quantity: u64,
+        display_quantity: Option<u64>,
+        self_trade_behavior: SelfTradeBehavior,
+        referrer_token_account: Option<&Pubkey>,
+    ) -> Result<Instruction, ProgramError> {
+        let instruction = match display_quantity {
+            Some(display_quantity) if market.whitelist_enabled => DexInstruction::place_iceberg_order_whitelisted(
+                &self.program_id,
+                owner,
+                market_pubkey,
+                order_account,
+                owner_token_account,
+                token_program,
+                is_buy,
+                limit_price,
+                quantity,
+                display_quantity,
+                self_trade_behavior,
+            )?,
+            Some(display_quantity) => DexInstruction::place_iceberg_order(
+                &self.program_id,
+                owner,
+                market_pubkey,
+                order_account,
+                owner_token_account,
+                token_program,
+                is_buy,
+                limit_price,
+                quantity,
+                display_quantity,
+                self_trade_behavior,
+            )?,
+            None if market.whitelist_enabled => DexInstruction::place_limit_order_whitelisted(
+                &self.program_id,
+                owner,
+                market_pubkey,
+                order_account,
+                owner_token_account,
+                token_program,
+                is_buy,
+                limit_price,
+                quantity,
+                self_trade_behavior,
+                referrer_token_account,
+            )?,
+            None => DexInstruction::place_limit_order_with_referrer(
+                &self.program_id,
+                owner,
+                market_pubkey,
+                order_account,
+                owner_token_account,
+                token_program,
+                is_buy,
+                limit_price,
+                quantity,
+                self_trade_behavior,
+                referrer_token_account.unwrap_or(&system_program::id()),
+            )?,
+        };
+        Ok(if market.has_oracle() {
+            DexInstruction::with_oracle(instruction, &market.oracle)
+        } else {
+            instruction
+        })
+    }
+    
+    // Add the mints and token programs of a market with a Token-2022 side to an instruction
+    // that moves its tokens, after every other optional account
+    fn with_token_mints(instruction: Instruction, market: &Market) -> Result<Instruction, ProgramError> {
+        if market.uses_token_2022() {
+            DexInstruction::with_token_mints(instruction, market)
+        } else {
+            Ok(instruction)
+        }
+    }
+    
+    /// Cancel an order
+    pub fn cancel_order(
+        &self,
+        payer: &dyn Signer,
+        owner: &dyn Signer,
+        market_pubkey: &Pubkey,
+        order_account: &Pubkey,
+        owner_token_account: &Pubkey,
+        token_program: &Pubkey,
+    ) -> Result<String, Box<dyn Error>> {
+        let cancel = self.cancel_order_ix(market_pubkey, order_account, owner_token_account, token_program)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &cancel.instructions,
+            Some(&payer.pubkey()),
+            &[payer, owner],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Build the instructions of `cancel_order` without sending them
+    ///
+    /// The order's owner must sign.
+    pub fn cancel_order_ix(
+        &self,
+        market_pubkey: &Pubkey,
+        order_account: &Pubkey,
+        owner_token_account: &Pubkey,
+        token_program: &Pubkey,
+    ) -> Result<DexInstructions, Box<dyn Error>> {
+        // Create cancel order instruction
+        let instruction = self.cancel_order_instruction(market_pubkey, order_account, owner_token_account, token_program)?;
+        
+        let instruction = Self::with_token_mints(instruction, &self.get_market(market_pubkey)?)?;
+        
+        Ok(DexInstructions {
+            instructions: vec![instruction],
+            signers: vec![self.get_order(order_account)?.owner],
+        })
+    }
+    
+    /// Cancel an owner's order as the owner's delegate
+    ///
+    /// Funds and rent are refunded to the owner, so `owner_token_account` must be the owner's.
+    pub fn cancel_order_as_delegate(
+        &self,
+        payer: &dyn Signer,
+        delegate: &dyn Signer,
+        market_pubkey: &Pubkey,
+        order_account: &Pubkey,
+        owner_token_account: &Pubkey,
+        token_program: &Pubkey,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create cancel order instruction for the owner and sign it with the delegate
+        let instruction = DexInstruction::with_delegate(
+            self.cancel_order_instruction(market_pubkey, order_account, owner_token_account, token_program)?,
+            &delegate.pubkey(),
+        )?;
+        
+        let instruction = Self::with_token_mints(instruction, &self.get_market(market_pubkey)?)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, delegate],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Settle a crossing taker bid against a maker ask as the market authority
+    ///
+    /// The proceeds go to the order owners' associated token accounts. The referrer, TWAP
+    /// oracle, event queue and loyalty accounts are passed when the orders or the market need
+    /// them, and the stake accounts when the taker has staked for a fee discount.
+    pub fn settle_funds(
+        &self,
+        payer: &dyn Signer,
+        authority: &dyn Signer,
+        market_pubkey: &Pubkey,
+        taker_order_account: &Pubkey,
+        maker_order_account: &Pubkey,
+    ) -> Result<String, Box<dyn Error>> {
+        let settle = self.settle_funds_ix(&authority.pubkey(), market_pubkey, taker_order_account, maker_order_account)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &settle.instructions,
+            Some(&payer.pubkey()),
+            &[payer, authority],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Build the instructions of `settle_funds` without sending them
+    ///
+    /// The market authority must sign.
+    pub fn settle_funds_ix(
+        &self,
+        authority: &Pubkey,
+        market_pubkey: &Pubkey,
+        taker_order_account: &Pubkey,
+        maker_order_account: &Pubkey,
+    ) -> Result<DexInstructions, Box<dyn Error>> {
+        let market = self.get_market(market_pubkey)?;
+        let taker_order = self.get_order(taker_order_account)?;
+        let maker_order = self.get_order(maker_order_account)?;
+        let stake_discount =
+            self.get_stake_pool()?.is_some() && self.get_stake_account(&taker_order.owner)?.is_some();
+        
+        self.settle_funds_ix_with_accounts(
+            authority,
+            market_pubkey,
+            &market,
+            taker_order_account,
+            &taker_order,
+            maker_order_account,
+            &maker_order,
+            stake_discount,
+        )
+    }
+    
+    /// Build the instructions of `settle_funds` from a market and orders already fetched
+    ///
+    /// `stake_discount` passes the stake pool and the taker owner's stake account, which must
+    /// both exist, for the taker fee discount of its stake.
+    pub fn settle_funds_ix_with_accounts(
+        &self,
+        authority: &Pubkey,
+        market_pubkey: &Pubkey,
+        market: &Market,
+        taker_order_account: &Pubkey,
+        taker_order: &Order,
+        maker_order_account: &Pubkey,
+        maker_order: &Order,
+        stake_discount: bool,
+    ) -> Result<DexInstructions, Box<dyn Error>> {
+        // Create settle funds instruction
+        let taker_base_account = market_token_account(market, &taker_order.owner, false);
+        let taker_quote_account = market_token_account(market, &taker_order.owner, true);
+        let maker_base_account = market_token_account(market, &maker_order.owner, false);
+        let maker_quote_account = market_token_account(market, &maker_order.owner, true);
+        let mut instruction = if taker_order.has_referrer() {
+            DexInstruction::settle_funds_with_referrer(
+                &self.program_id,
+                authority,
+                market_pubkey,
+                taker_order_account,
+                maker_order_account,
+                &taker_base_account,
+                &taker_quote_account,
+                &maker_base_account,
+                &maker_quote_account,
+                &taker_order.owner,
+                &maker_order.owner,
+                &spl_token::id(),
+                &taker_order.referrer,
+            )?
+        } else {
+            DexInstruction::settle_funds(
+                &self.program_id,
+                authority,
+                market_pubkey,
+                taker_order_account,
+                maker_order_account,
+                &taker_base_account,
+                &taker_quote_account,
+                &maker_base_account,
+                &maker_quote_account,
+                &taker_order.owner,
+                &maker_order.owner,
+                &spl_token::id(),
+            )?
+        };
+        
+        // The oracle is positional; the other accounts are found by address after it
+        if market.twap_oracle_enabled {
+            instruction = DexInstruction::with_twap_oracle(instruction);
+        }
+        if market.has_flag(MARKET_FLAG_EVENT_QUEUE) {
+            instruction = DexInstruction::with_event_queue(instruction);
+        }
+        if market.has_flag(MARKET_FLAG_LOYALTY_POINTS) {
+            instruction = DexInstruction::with_loyalty_points(instruction, &taker_order.owner, &maker_order.owner);
+        }
+        if stake_discount {
+            instruction = DexInstruction::with_stake_discount(instruction, &taker_order.owner)?;
+        }
+        let linked_orders: Vec<Pubkey> = [taker_order, maker_order]
+            .iter()
+            .filter(|order| order.is_linked())
+            .map(|order| order.linked_order)
+            .collect();
+        if !linked_orders.is_empty() {
+            instruction = DexInstruction::with_linked_orders(instruction, &linked_orders);
+        }
+        
+        let instruction = Self::with_token_mints(instruction, market)?;
+        
+        Ok(DexInstructions {
+            instructions: vec![instruction],
+            signers: vec![*authority],
+        })
+    }
+    
+    // Cancel order instruction for an order's owner, cancelling a linked order along with it
+    fn cancel_order_instruction(
+        &self,
+        market_pubkey: &Pubkey,
+        order_account: &Pubkey,
+        owner_token_account: &Pubkey,
+        token_program: &Pubkey,
+    ) -> Result<Instruction, Box<dyn Error>> {
+        let order = self.get_order(order_account)?;
+        let instruction = if order.is_linked() {
+            let linked_order = self.get_order(&order.linked_order)?;
+            let linked_owner_token_account = if linked_order.is_buy == order.is_buy {
+                *owner_token_account
+            } else {
+                let market = self.get_market(market_pubkey)?;
+                market_token_account(&market, &order.owner, linked_order.is_buy)
+            };
+            DexInstruction::cancel_linked_order(
+                &self.program_id,
+                &order.owner,
+                market_pubkey,
+                order_account,
+                owner_token_account,
+                token_program,
+                &order.linked_order,
+                &linked_owner_token_account,
+            )?
+        } else {
+            DexInstruction::cancel_order(
+                &self.program_id,
+                &order.owner,
+                market_pubkey,
+                order_account,
+                owner_token_account,
+                token_program,
+            )?
+        };
+        Ok(instruction)
+    }
+    
+    /// Force-cancel an order as the market authority, refunding the owner's associated token accounts
+    pub fn admin_cancel_order(
+        &self,
+        payer: &dyn Signer,
+        market_authority: &dyn Signer,
+        market_pubkey: &Pubkey,
+        order_account: &Pubkey,
+    ) -> Result<String, Box<dyn Error>> {
+        // Refunds go to the owner's associated token account of the escrowed mint
+        let market = self.get_market(market_pubkey)?;
+        let refund_account = |order: &Order| market_token_account(&market, &order.owner, order.is_buy);
+        
+        // Create admin cancel order instruction, cancelling a linked order along with it
+        let order = self.get_order(order_account)?;
+        let instruction = if order.is_linked() {
+            let linked_order = self.get_order(&order.linked_order)?;
+            DexInstruction::admin_cancel_linked_order(
+                &self.program_id,
+                &market_authority.pubkey(),
+                market_pubkey,
+                order_account,
+                &order.owner,
+                &refund_account(&order),
+                market.token_program(order.is_buy),
+                &order.linked_order,
+                &refund_account(&linked_order),
+            )?
+        } else {
+            DexInstruction::admin_cancel_order(
+                &self.program_id,
+                &market_authority.pubkey(),
+                market_pubkey,
+                order_account,
+                &order.owner,
+                &refund_account(&order),
+                market.token_program(order.is_buy),
+            )?
+        };
+        
+        let instruction = Self::with_token_mints(instruction, &market)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, market_authority],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Link two orders so that cancelling one cancels the other
+    pub fn link_orders(
+        &self,
+        payer: &dyn Signer,
+        owner: &dyn Signer,
+        market_pubkey: &Pubkey,
+        first_order_account: &Pubkey,
+        second_order_account: &Pubkey,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create link orders instruction
+        let instruction = DexInstruction::link_orders(
+            &self.program_id,
+            &owner.pubkey(),
+            market_pubkey,
+            first_order_account,
+            second_order_account,
+        )?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, owner],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Reduce the remaining quantity of a resting order
+    pub fn reduce_order(
+        &self,
+        payer: &dyn Signer,
+        owner: &dyn Signer,
+        market_pubkey: &Pubkey,
+        order_account: &Pubkey,
+        owner_token_account: &Pubkey,
+        token_program: &Pubkey,
+        new_remaining_quantity: u64,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create reduce order instruction
+        let instruction = DexInstruction::reduce_order(
+            &self.program_id,
+            &owner.pubkey(),
+            market_pubkey,
+            order_account,
+            owner_token_account,
+            token_program,
+            new_remaining_quantity,
+        )?;
+        
+        let instruction = Self::with_token_mints(instruction, &self.get_market(market_pubkey)?)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, owner],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Cancel several orders by ID in one transaction
+    pub fn cancel_orders_by_ids(
+        &self,
+        payer: &dyn Signer,
+        owner: &dyn Signer,
+        market_pubkey: &Pubkey,
+        owner_base_account: &Pubkey,
+        owner_quote_account: &Pubkey,
+        token_program: &Pubkey,
+        order_ids: &[u64],
+        order_accounts: &[Pubkey],
+    ) -> Result<String, Box<dyn Error>> {
+        // Create cancel orders by ids instruction
+        let instruction = DexInstruction::cancel_orders_by_ids(
+            &self.program_id,
+            &owner.pubkey(),
+            market_pubkey,
+            owner_base_account,
+            owner_quote_account,
+            token_program,
+            order_ids,
+            order_accounts,
+        )?;
+        
+        let instruction = Self::with_token_mints(instruction, &self.get_market(market_pubkey)?)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, owner],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Set the instruction guard of a market
+    pub fn set_instruction_guard(
+        &self,
+        payer: &dyn Signer,
+        market_authority: &dyn Signer,
+        market_pubkey: &Pubkey,
+        guard: InstructionGuard,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create set instruction guard instruction
+        let instruction = DexInstruction::set_instruction_guard(
+            &self.program_id,
+            &market_authority.pubkey(),
+            market_pubkey,
+            guard,
+        )?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, market_authority],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Set the trading status of a market
+    pub fn set_market_status(
+        &self,
+        payer: &dyn Signer,
+        market_authority: &dyn Signer,
+        market_pubkey: &Pubkey,
+        status: MarketStatus,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create set market status instruction
+        let instruction = DexInstruction::set_market_status(
+            &self.program_id,
+            &market_authority.pubkey(),
+            market_pubkey,
+            status,
+        )?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, market_authority],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Enable or disable the whitelist of a market
+    pub fn set_whitelist_enabled(
+        &self,
+        payer: &dyn Signer,
+        market_authority: &dyn Signer,
+        market_pubkey: &Pubkey,
+        enabled: bool,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create set whitelist enabled instruction
+        let instruction = DexInstruction::set_whitelist_enabled(
+            &self.program_id,
+            &market_authority.pubkey(),
+            market_pubkey,
+            enabled,
+        )?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, market_authority],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Set or clear the oracle circuit breaker of a market
+    pub fn set_oracle(
+        &self,
+        payer: &dyn Signer,
+        market_authority: &dyn Signer,
+        market_pubkey: &Pubkey,
+        oracle: &Pubkey,
+        band_bps: u16,
+        max_staleness_slots: u64,
+        price_expo: i32,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create set oracle instruction
+        let instruction = DexInstruction::set_oracle(
+            &self.program_id,
+            &market_authority.pubkey(),
+            market_pubkey,
+            oracle,
+            band_bps,
+            max_staleness_slots,
+            price_expo,
+        )?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, market_authority],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Halt a market whose oracle price is stale (permissionless)
+    pub fn check_oracle(&self, payer: &dyn Signer, market_pubkey: &Pubkey) -> Result<String, Box<dyn Error>> {
+        let market = self.get_market(market_pubkey)?;
+        
+        // Create check oracle instruction
+        let instruction = DexInstruction::check_oracle(&self.program_id, market_pubkey, &market.oracle)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Whitelist an owner on a market; the authority pays the entry's rent
+    pub fn add_whitelist_entry(
+        &self,
+        payer: &dyn Signer,
+        market_authority: &dyn Signer,
+        market_pubkey: &Pubkey,
+        owner: &Pubkey,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create add whitelist entry instruction
+        let instruction = DexInstruction::add_whitelist_entry(
+            &self.program_id,
+            &market_authority.pubkey(),
+            market_pubkey,
+            owner,
+        )?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, market_authority],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Remove an owner from the whitelist of a market, returning the entry's rent to the recipient
+    pub fn remove_whitelist_entry(
+        &self,
+        payer: &dyn Signer,
+        market_authority: &dyn Signer,
+        market_pubkey: &Pubkey,
+        owner: &Pubkey,
+        recipient: &Pubkey,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create remove whitelist entry instruction
+        let instruction = DexInstruction::remove_whitelist_entry(
+            &self.program_id,
+            &market_authority.pubkey(),
+            market_pubkey,
+            owner,
+            recipient,
+        )?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, market_authority],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Set the minimum notional in quote tokens of new orders on a market
+    pub fn set_min_quote_notional(
+        &self,
+        payer: &dyn Signer,
+        market_authority: &dyn Signer,
+        market_pubkey: &Pubkey,
+        min_quote_notional: u64,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create set minimum quote notional instruction
+        let instruction = DexInstruction::set_min_quote_notional(
+            &self.program_id,
+            &market_authority.pubkey(),
+            market_pubkey,
+            min_quote_notional,
+        )?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, market_authority],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Create a market's TWAP oracle, updated by every settlement from then on
+    pub fn initialize_twap_oracle(
+        &self,
+        payer: &dyn Signer,
+        market_authority: &dyn Signer,
+        market_pubkey: &Pubkey,
+        ema_period_seconds: u64,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create initialize TWAP oracle instruction
+        let instruction = DexInstruction::initialize_twap_oracle(
+            &self.program_id,
+            &market_authority.pubkey(),
+            market_pubkey,
+            ema_period_seconds,
+        )?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, market_authority],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Create a market's event queue, appended to by every settlement from then on
+    pub fn initialize_event_queue(
+        &self,
+        payer: &dyn Signer,
+        market_authority: &dyn Signer,
+        market_pubkey: &Pubkey,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create initialize event queue instruction
+        let instruction = DexInstruction::initialize_event_queue(
+            &self.program_id,
+            &market_authority.pubkey(),
+            market_pubkey,
+        )?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, market_authority],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Remove up to `limit` processed events from a market's event queue, starting at the
+    /// event with sequence number `seq_num`
+    ///
+    /// With `max_staleness_slots`, the transaction fails if it lands more than that many slots
+    /// after the current slot, read when the transaction is built.
+    pub fn consume_events(
+        &self,
+        payer: &dyn Signer,
+        market_authority: &dyn Signer,
+        market_pubkey: &Pubkey,
+        seq_num: u64,
+        limit: u16,
+        max_staleness_slots: Option<u64>,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create consume events instruction
+        let mut instruction = DexInstruction::consume_events(
+            &self.program_id,
+            &market_authority.pubkey(),
+            market_pubkey,
+            seq_num,
+            limit,
+        )?;
+        if let Some(max_staleness_slots) = max_staleness_slots {
+            instruction = DexInstruction::with_staleness_guard(instruction, self.rpc_client.get_slot()?, max_staleness_slots)?;
+        }
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, market_authority],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Return the tokens of a market escrow vault above what the market owes from it, such as
+    /// tokens sent straight to the vault, to a token account
+    pub fn rescue_tokens(
+        &self,
+        payer: &dyn Signer,
+        market_authority: &dyn Signer,
+        market_pubkey: &Pubkey,
+        destination: &Pubkey,
+        is_quote: bool,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create rescue tokens instruction
+        let instruction = DexInstruction::rescue_tokens(
+            &self.program_id,
+            &market_authority.pubkey(),
+            market_pubkey,
+            destination,
+            &spl_token::id(),
+            is_quote,
+        )?;
+        
+        let instruction = Self::with_token_mints(instruction, &self.get_market(market_pubkey)?)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, market_authority],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Create a market's constant-product pool with a swap fee in basis points
+    pub fn initialize_pool(
+        &self,
+        payer: &dyn Signer,
+        market_authority: &dyn Signer,
+        market_pubkey: &Pubkey,
+        fee_bps: u16,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create initialize pool instruction; the LP mint is an SPL Token mint
+        let market = self.get_market(market_pubkey)?;
+        let instruction = DexInstruction::initialize_pool(
+            &self.program_id,
+            &market_authority.pubkey(),
+            market_pubkey,
+            &market.base_mint,
+            &market.quote_mint,
+            &spl_token::id(),
+            fee_bps,
+        )?;
+        
+        let instruction = Self::with_token_mints(instruction, &market)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, market_authority],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Deposit at most `max_base_amount` and `max_quote_amount` from the owner's associated
+    /// token accounts into a market's pool, crediting the LP tokens to the owner's associated
+    /// LP token account, created by the payer if needed
+    pub fn add_liquidity(
+        &self,
+        payer: &dyn Signer,
+        owner: &dyn Signer,
+        market_pubkey: &Pubkey,
+        max_base_amount: u64,
+        max_quote_amount: u64,
+        min_lp_amount: u64,
+    ) -> Result<String, Box<dyn Error>> {
+        let market = self.get_market(market_pubkey)?;
+        let owner_pubkey = owner.pubkey();
+        let lp_mint = Pool::find_lp_mint_address(&self.program_id, market_pubkey).0;
+        
+        // Create add liquidity instruction
+        let instruction = DexInstruction::add_liquidity(
+            &self.program_id,
+            &owner_pubkey,
+            market_pubkey,
+            &market_token_account(&market, &owner_pubkey, false),
+            &market_token_account(&market, &owner_pubkey, true),
+            &get_associated_token_address(&owner_pubkey, &lp_mint),
+            &spl_token::id(),
+            max_base_amount,
+            max_quote_amount,
+            min_lp_amount,
+        )?;
+        
+        let instruction = Self::with_token_mints(instruction, &market)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[
+                create_associated_token_account_idempotent(&payer.pubkey(), &owner_pubkey, &lp_mint, &spl_token::id()),
+                instruction,
+            ],
+            Some(&payer.pubkey()),
+            &[payer, owner],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Burn LP tokens from the owner's associated LP token account for their share of a
+    /// market's pool, paid to the owner's associated token accounts
+    pub fn remove_liquidity(
+        &self,
+        payer: &dyn Signer,
+        owner: &dyn Signer,
+        market_pubkey: &Pubkey,
+        lp_amount: u64,
+        min_base_amount: u64,
+        min_quote_amount: u64,
+    ) -> Result<String, Box<dyn Error>> {
+        let market = self.get_market(market_pubkey)?;
+        let owner_pubkey = owner.pubkey();
+        let lp_mint = Pool::find_lp_mint_address(&self.program_id, market_pubkey).0;
+        
+        // Create remove liquidity instruction
+        let instruction = DexInstruction::remove_liquidity(
+            &self.program_id,
+            &owner_pubkey,
+            market_pubkey,
+            &get_associated_token_address(&owner_pubkey, &lp_mint),
+            &market_token_account(&market, &owner_pubkey, false),
+            &market_token_account(&market, &owner_pubkey, true),
+            &spl_token::id(),
+            lp_amount,
+            min_base_amount,
+            min_quote_amount,
+        )?;
+        
+        let instruction = Self::with_token_mints(instruction, &market)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, owner],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Swap `amount_in` tokens of the owner through a market's order book and pool, whichever
+    /// is better at each step, offering the program at most `max_orders` of the best resting
+    /// orders of the other side
+    pub fn route_swap(
+        &self,
+        payer: &dyn Signer,
+        owner: &dyn Signer,
+        market_pubkey: &Pubkey,
+        is_buy: bool,
+        amount_in: u64,
+        min_amount_out: u64,
+        max_orders: usize,
+    ) -> Result<String, Box<dyn Error>> {
+        let market = self.get_market(market_pubkey)?;
+        let owner_pubkey = owner.pubkey();
+        
+        // Best price first, oldest first within a price
+        let mut orders: Vec<_> = self
+            .fetch_market_orders(market_pubkey)?
+            .into_iter()
+            .filter(|(_, order)| order.is_buy != is_buy)
+            .collect();
+        orders.sort_by(|(_, a), (_, b)| {
+            let (a_price, b_price) = ({ a.limit_price }, { b.limit_price });
+            let by_price = if is_buy {
+                a_price.cmp(&b_price)
+            } else {
+                b_price.cmp(&a_price)
+            };
+            by_price.then({ a.order_id }.cmp(&{ b.order_id }))
+        });
+        let orders: Vec<_> = orders.into_iter().take(max_orders).collect();
+        let linked_orders: Vec<Pubkey> = orders
+            .iter()
+            .filter(|(_, order)| order.is_linked())
+            .map(|(_, order)| order.linked_order)
+            .collect();
+        let orders: Vec<_> = orders
+            .iter()
+            .map(|(order_pubkey, order)| RouteOrder {
+                order: *order_pubkey,
+                owner: order.owner,
+                owner_base_account: market_token_account(&market, &order.owner, false),
+                owner_quote_account: market_token_account(&market, &order.owner, true),
+            })
+            .collect();
+        
+        // Create route swap instruction
+        let mut instruction = DexInstruction::route_swap(
+            &self.program_id,
+            &owner_pubkey,
+            market_pubkey,
+            &market_token_account(&market, &owner_pubkey, false),
+            &market_token_account(&market, &owner_pubkey, true),
+            &spl_token::id(),
+            &orders,
+            is_buy,
+            amount_in,
+            min_amount_out,
+        )?;
+        
+        // The program finds the accounts the market needs, and the orders linked to the route
+        // orders that a fill cancels, by address after the orders
+        if market.has_flag(MARKET_FLAG_EVENT_QUEUE) {
+            let event_queue = EventQueue::find_address(&self.program_id, market_pubkey).0;
+            instruction.accounts.push(AccountMeta::new(event_queue, false));
+        }
+        if market.twap_oracle_enabled {
+            let twap_oracle = TwapOracle::find_address(&self.program_id, market_pubkey).0;
+            instruction.accounts.push(AccountMeta::new(twap_oracle, false));
+        }
+        for linked_order in linked_orders {
+            if !instruction.accounts.iter().any(|account| account.pubkey == linked_order) {
+                instruction.accounts.push(AccountMeta::new(linked_order, false));
+            }
+        }
+        if market.instruction_guard != InstructionGuard::Disabled {
+            instruction.accounts.push(AccountMeta::new_readonly(sysvar::instructions::id(), false));
+        }
+        if market.whitelist_enabled {
+            let entry = WhitelistEntry::find_address(&self.program_id, market_pubkey, &owner_pubkey).0;
+            instruction.accounts.push(AccountMeta::new_readonly(entry, false));
+        }
+        
+        let instruction = Self::with_token_mints(instruction, &market)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, owner],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Swap `amount_in` tokens of the owner against a market's pool: quote tokens for base
+    /// tokens when buying, base tokens for quote tokens when selling
+    pub fn swap_against_pool(
+        &self,
+        payer: &dyn Signer,
+        owner: &dyn Signer,
+        market_pubkey: &Pubkey,
+        is_buy: bool,
+        amount_in: u64,
+        min_amount_out: u64,
+    ) -> Result<String, Box<dyn Error>> {
+        let market = self.get_market(market_pubkey)?;
+        let owner_pubkey = owner.pubkey();
+        
+        // Create swap against pool instruction
+        let instruction = DexInstruction::swap_against_pool(
+            &self.program_id,
+            &owner_pubkey,
+            market_pubkey,
+            &market_token_account(&market, &owner_pubkey, false),
+            &market_token_account(&market, &owner_pubkey, true),
+            &spl_token::id(),
+            is_buy,
+            amount_in,
+            min_amount_out,
+        )?;
+        
+        let instruction = Self::with_token_mints(instruction, &market)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, owner],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Get a market's pool, if it has one
+    pub fn get_pool(&self, market_pubkey: &Pubkey) -> Result<Option<Pool>, Box<dyn Error>> {
+        let pool_pubkey = Pool::find_address(&self.program_id, market_pubkey).0;
+        let account = self
+            .rpc_client
+            .get_account_with_commitment(&pool_pubkey, self.rpc_client.commitment())?
+            .value;
+        match account {
+            Some(account) if account.owner == self.program_id => Ok(Some(Pool::unpack_from_slice(&account.data)?)),
+            _ => Ok(None),
+        }
+    }
+    
+    /// Create a market's concentrated-liquidity range pool at the price of `tick`
+    pub fn initialize_range_pool(
+        &self,
+        payer: &dyn Signer,
+        market_authority: &dyn Signer,
+        market_pubkey: &Pubkey,
+        fee_bps: u16,
+        tick_spacing: u16,
+        tick: i32,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create initialize range pool instruction
+        let market = self.get_market(market_pubkey)?;
+        let instruction = DexInstruction::initialize_range_pool(
+            &self.program_id,
+            &market_authority.pubkey(),
+            market_pubkey,
+            &market.base_mint,
+            &market.quote_mint,
+            &spl_token::id(),
+            fee_bps,
+            tick_spacing,
+            tick,
+        )?;
+        
+        let instruction = Self::with_token_mints(instruction, &market)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, market_authority],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Add `liquidity` between two ticks to the owner's range position, depositing at most
+    /// `max_base_amount` and `max_quote_amount` from the owner's associated token accounts
+    pub fn add_range_liquidity(
+        &self,
+        payer: &dyn Signer,
+        owner: &dyn Signer,
+        market_pubkey: &Pubkey,
+        tick_lower: i32,
+        tick_upper: i32,
+        liquidity: u64,
+        max_base_amount: u64,
+        max_quote_amount: u64,
+    ) -> Result<String, Box<dyn Error>> {
+        let market = self.get_market(market_pubkey)?;
+        let owner_pubkey = owner.pubkey();
+        
+        // Create add range liquidity instruction
+        let instruction = DexInstruction::add_range_liquidity(
+            &self.program_id,
+            &owner_pubkey,
+            market_pubkey,
+            &market_token_account(&market, &owner_pubkey, false),
+            &market_token_account(&market, &owner_pubkey, true),
+            &spl_token::id(),
+            tick_lower,
+            tick_upper,
+            liquidity,
+            max_base_amount,
+            max_quote_amount,
+        )?;
+        
+        let instruction = Self::with_token_mints(instruction, &market)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, owner],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Remove `liquidity` from the owner's range position between two ticks and collect its
+    /// fees into the owner's associated token accounts; zero liquidity only collects fees
+    pub fn remove_range_liquidity(
+        &self,
+        payer: &dyn Signer,
+        owner: &dyn Signer,
+        market_pubkey: &Pubkey,
+        tick_lower: i32,
+        tick_upper: i32,
+        liquidity: u64,
+        min_base_amount: u64,
+        min_quote_amount: u64,
+    ) -> Result<String, Box<dyn Error>> {
+        let market = self.get_market(market_pubkey)?;
+        let owner_pubkey = owner.pubkey();
+        let (position, _) = RangePosition::find_address(&self.program_id, market_pubkey, &owner_pubkey, tick_lower, tick_upper);
+        
+        // Create remove range liquidity instruction
+        let instruction = DexInstruction::remove_range_liquidity(
+            &self.program_id,
+            &owner_pubkey,
+            market_pubkey,
+            &position,
+            &market_token_account(&market, &owner_pubkey, false),
+            &market_token_account(&market, &owner_pubkey, true),
+            &spl_token::id(),
+            liquidity,
+            min_base_amount,
+            min_quote_amount,
+        )?;
+        
+        let instruction = Self::with_token_mints(instruction, &market)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, owner],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Swap up to `amount_in` tokens of the owner against a market's range pool: quote tokens
+    /// for base tokens when buying, base tokens for quote tokens when selling
+    pub fn swap_against_range_pool(
+        &self,
+        payer: &dyn Signer,
+        owner: &dyn Signer,
+        market_pubkey: &Pubkey,
+        is_buy: bool,
+        amount_in: u64,
+        min_amount_out: u64,
+    ) -> Result<String, Box<dyn Error>> {
+        let market = self.get_market(market_pubkey)?;
+        let owner_pubkey = owner.pubkey();
+        
+        // Create swap against range pool instruction
+        let instruction = DexInstruction::swap_against_range_pool(
+            &self.program_id,
+            &owner_pubkey,
+            market_pubkey,
+            &market_token_account(&market, &owner_pubkey, false),
+            &market_token_account(&market, &owner_pubkey, true),
+            &spl_token::id(),
+            is_buy,
+            amount_in,
+            min_amount_out,
+        )?;
+        
+        let instruction = Self::with_token_mints(instruction, &market)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, owner],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Get a market's range pool, if it has one
+    pub fn get_range_pool(&self, market_pubkey: &Pubkey) -> Result<Option<RangePool>, Box<dyn Error>> {
+        let range_pool_pubkey = RangePool::find_address(&self.program_id, market_pubkey).0;
+        let account = self
+            .rpc_client
+            .get_account_with_commitment(&range_pool_pubkey, self.rpc_client.commitment())?
+            .value;
+        match account {
+            Some(account) if account.owner == self.program_id => Ok(Some(RangePool::unpack_from_slice(&account.data)?)),
+            _ => Ok(None),
+        }
+    }
+    
+    /// Create the lending pool of a mint with a borrow rate curve, signed by the config admin
+    pub fn initialize_lending_pool(
+        &self,
+        payer: &dyn Signer,
+        admin: &dyn Signer,
+        mint: &Pubkey,
+        rate_curve: InterestRateCurve,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create initialize lending pool instruction under the mint's token program
+        let token_program = self.rpc_client.get_account(mint)?.owner;
+        let instruction = DexInstruction::initialize_lending_pool(&self.program_id, &admin.pubkey(), mint, &token_program, rate_curve)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, admin],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Supply `amount` tokens from the owner's associated token account to the lending pool of
+    /// a mint, crediting the shares to the owner's associated share token account, created by
+    /// the payer if needed
+    pub fn supply(
+        &self,
+        payer: &dyn Signer,
+        owner: &dyn Signer,
+        mint: &Pubkey,
+        amount: u64,
+        min_shares: u64,
+    ) -> Result<String, Box<dyn Error>> {
+        let lending_pool = self.get_lending_pool(mint)?.ok_or("Lending pool not found")?;
+        let owner_pubkey = owner.pubkey();
+        let token_program = lending_pool.token_program;
+        let share_account = get_associated_token_address_with_program_id(&owner_pubkey, &lending_pool.share_mint, &token_program);
+        
+        // Create supply instruction
+        let instruction = DexInstruction::supply(
+            &self.program_id,
+            &owner_pubkey,
+            mint,
+            &get_associated_token_address_with_program_id(&owner_pubkey, mint, &token_program),
+            &share_account,
+            &token_program,
+            amount,
+            min_shares,
+        )?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[
+                create_associated_token_account_idempotent(&payer.pubkey(), &owner_pubkey, &lending_pool.share_mint, &token_program),
+                instruction,
+            ],
+            Some(&payer.pubkey()),
+            &[payer, owner],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Burn `shares` from the owner's associated share token account of the lending pool of a
+    /// mint for their tokens, paid to the owner's associated token account
+    pub fn redeem(
+        &self,
+        payer: &dyn Signer,
+        owner: &dyn Signer,
+        mint: &Pubkey,
+        shares: u64,
+        min_amount: u64,
+    ) -> Result<String, Box<dyn Error>> {
+        let lending_pool = self.get_lending_pool(mint)?.ok_or("Lending pool not found")?;
+        let owner_pubkey = owner.pubkey();
+        let token_program = lending_pool.token_program;
+        
+        // Create redeem instruction
+        let instruction = DexInstruction::redeem(
+            &self.program_id,
+            &owner_pubkey,
+            mint,
+            &get_associated_token_address_with_program_id(&owner_pubkey, &lending_pool.share_mint, &token_program),
+            &get_associated_token_address_with_program_id(&owner_pubkey, mint, &token_program),
+            &token_program,
+            shares,
+            min_amount,
+        )?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, owner],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Get the lending pool of a mint, if it has one
+    pub fn get_lending_pool(&self, mint: &Pubkey) -> Result<Option<LendingPool>, Box<dyn Error>> {
+        let lending_pool_pubkey = LendingPool::find_address(&self.program_id, mint).0;
+        let account = self
+            .rpc_client
+            .get_account_with_commitment(&lending_pool_pubkey, self.rpc_client.commitment())?
+            .value;
+        match account {
+            Some(account) if account.owner == self.program_id => Ok(Some(LendingPool::unpack_from_slice(&account.data)?)),
+            _ => Ok(None),
+        }
+    }
+    
+    /// Initialize the perp market of a market, which must have an oracle
+    pub fn initialize_perp_market(
+        &self,
+        payer: &dyn Signer,
+        market_authority: &dyn Signer,
+        market_pubkey: &Pubkey,
+        initial_margin_bps: u16,
+        max_funding_rate_bps: u16,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create initialize perp market instruction
+        let market = self.get_market(market_pubkey)?;
+        let instruction = DexInstruction::initialize_perp_market(
+            &self.program_id,
+            &market_authority.pubkey(),
+            market_pubkey,
+            &market.quote_mint,
+            &market.quote_token_program,
+            initial_margin_bps,
+            max_funding_rate_bps,
+        )?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, market_authority],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Deposit `amount` quote tokens from the owner's associated token account as collateral
+    /// of the owner's perp position, creating the position on the first deposit
+    pub fn deposit_perp_collateral(
+        &self,
+        payer: &dyn Signer,
+        owner: &dyn Signer,
+        market_pubkey: &Pubkey,
+        amount: u64,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create deposit perp collateral instruction
+        let market = self.get_market(market_pubkey)?;
+        let owner_pubkey = owner.pubkey();
+        let instruction = DexInstruction::deposit_perp_collateral(
+            &self.program_id,
+            &owner_pubkey,
+            market_pubkey,
+            &market_token_account(&market, &owner_pubkey, true),
+            &market.quote_mint,
+            &market.quote_token_program,
+            amount,
+        )?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, owner],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Withdraw `amount` quote tokens of the owner's perp position to the owner's associated
+    /// token account
+    pub fn withdraw_perp_collateral(
+        &self,
+        payer: &dyn Signer,
+        owner: &dyn Signer,
+        market_pubkey: &Pubkey,
+        amount: u64,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create withdraw perp collateral instruction
+        let market = self.get_market(market_pubkey)?;
+        let owner_pubkey = owner.pubkey();
+        let instruction = DexInstruction::withdraw_perp_collateral(
+            &self.program_id,
+            &owner_pubkey,
+            market_pubkey,
+            &market_token_account(&market, &owner_pubkey, true),
+            &market.quote_mint,
+            &market.quote_token_program,
+            amount,
+        )?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, owner],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Place an order on a market's perp market for the owner's perp position
+    ///
+    /// `order_account` is a new keypair the order account is created at.
+    pub fn place_perp_order(
+        &self,
+        payer: &dyn Signer,
+        owner: &dyn Signer,
+        market_pubkey: &Pubkey,
+        order_account: &dyn Signer,
+        is_buy: bool,
+        limit_price: u64,
+        quantity: u64,
+        reduce_only: bool,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create place perp order instruction
+        let market = self.get_market(market_pubkey)?;
+        let instruction = DexInstruction::place_perp_order(
+            &self.program_id,
+            &owner.pubkey(),
+            market_pubkey,
+            &order_account.pubkey(),
+            &market.oracle,
+            is_buy,
+            limit_price,
+            quantity,
+            reduce_only,
+        )?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, owner, order_account],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Cancel a perp order, returning its rent to the owner
+    pub fn cancel_perp_order(
+        &self,
+        owner: &dyn Signer,
+        market_pubkey: &Pubkey,
+        order_pubkey: &Pubkey,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create cancel perp order instruction
+        let instruction = DexInstruction::cancel_perp_order(&self.program_id, &owner.pubkey(), market_pubkey, order_pubkey)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(&[instruction], Some(&owner.pubkey()), &[owner], recent_blockhash);
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Fill a taker perp order against a maker perp order of the other side, as the market
+    /// authority; the fill is derived from the orders
+    pub fn settle_perp_fill(
+        &self,
+        payer: &dyn Signer,
+        market_authority: &dyn Signer,
+        market_pubkey: &Pubkey,
+        taker_order: &Pubkey,
+        maker_order: &Pubkey,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create settle perp fill instruction
+        let market = self.get_market(market_pubkey)?;
+        let taker = self.get_perp_order(taker_order)?;
+        let maker = self.get_perp_order(maker_order)?;
+        let instruction = DexInstruction::settle_perp_fill(
+            &self.program_id,
+            &market_authority.pubkey(),
+            market_pubkey,
+            taker_order,
+            &taker.owner,
+            maker_order,
+            &maker.owner,
+            &market.quote_mint,
+            &market.quote_token_program,
+            &market.oracle,
+        )?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, market_authority],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Pay the perp funding of a market for the intervals elapsed since it was last paid
+    pub fn update_funding(&self, payer: &dyn Signer, market_pubkey: &Pubkey) -> Result<String, Box<dyn Error>> {
+        // Create update funding instruction
+        let market = self.get_market(market_pubkey)?;
+        let instruction = DexInstruction::update_funding(&self.program_id, market_pubkey, &market.oracle)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(&[instruction], Some(&payer.pubkey()), &[payer], recent_blockhash);
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Allow or forbid fills of one of the owner's perp orders that do not shrink its position
+    pub fn set_perp_reduce_only(
+        &self,
+        owner: &dyn Signer,
+        market_pubkey: &Pubkey,
+        order_pubkey: &Pubkey,
+        reduce_only: bool,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create set perp reduce-only instruction
+        let instruction =
+            DexInstruction::set_perp_reduce_only(&self.program_id, &owner.pubkey(), market_pubkey, order_pubkey, reduce_only)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(&[instruction], Some(&owner.pubkey()), &[owner], recent_blockhash);
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Make a market match by batch auction every `interval_slots` slots
+    pub fn initialize_batch_auction(
+        &self,
+        payer: &dyn Signer,
+        market_authority: &dyn Signer,
+        market_pubkey: &Pubkey,
+        interval_slots: u64,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create initialize batch auction instruction
+        let instruction = DexInstruction::initialize_batch_auction(
+            &self.program_id,
+            &market_authority.pubkey(),
+            market_pubkey,
+            interval_slots,
+        )?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, market_authority],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Cross every resting order of a batch auction market at its clearing price
+    pub fn run_auction(&self, payer: &dyn Signer, market_pubkey: &Pubkey) -> Result<String, Box<dyn Error>> {
+        let market = self.get_market(market_pubkey)?;
+        
+        // The auction takes every resting order of the market
+        let orders: Vec<_> = self
+            .fetch_market_orders(market_pubkey)?
+            .iter()
+            .map(|(order_pubkey, order)| RouteOrder {
+                order: *order_pubkey,
+                owner: order.owner,
+                owner_base_account: market_token_account(&market, &order.owner, false),
+                owner_quote_account: market_token_account(&market, &order.owner, true),
+            })
+            .collect();
+        
+        // Create run auction instruction
+        let mut instruction = DexInstruction::run_auction(&self.program_id, market_pubkey, &spl_token::id(), &orders)?;
+        
+        // The program finds the accounts the market needs by address after the orders
+        if market.has_flag(MARKET_FLAG_EVENT_QUEUE) {
+            let event_queue = EventQueue::find_address(&self.program_id, market_pubkey).0;
+            instruction.accounts.push(AccountMeta::new(event_queue, false));
+        }
+        if market.twap_oracle_enabled {
+            let twap_oracle = TwapOracle::find_address(&self.program_id, market_pubkey).0;
+            instruction.accounts.push(AccountMeta::new(twap_oracle, false));
+        }
+        if market.has_oracle() {
+            instruction.accounts.push(AccountMeta::new_readonly(market.oracle, false));
+        }
+        
+        let instruction = Self::with_token_mints(instruction, &market)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(&[instruction], Some(&payer.pubkey()), &[payer], recent_blockhash);
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Get the batch auction of a market, if it has one
+    pub fn get_batch_auction(&self, market_pubkey: &Pubkey) -> Result<Option<BatchAuction>, Box<dyn Error>> {
+        let batch_auction_pubkey = BatchAuction::find_address(&self.program_id, market_pubkey).0;
+        let account = self
+            .rpc_client
+            .get_account_with_commitment(&batch_auction_pubkey, self.rpc_client.commitment())?
+            .value;
+        match account {
+            Some(account) if account.owner == self.program_id => Ok(Some(BatchAuction::unpack_from_slice(&account.data)?)),
+            _ => Ok(None),
+        }
+    }
+    
+    /// Sell `base_amount` base tokens from the authority's associated token account for
+    /// `duration_seconds` before a paused, new market opens
+    pub fn initialize_token_sale(
+        &self,
+        payer: &dyn Signer,
+        market_authority: &dyn Signer,
+        market_pubkey: &Pubkey,
+        base_amount: u64,
+        min_price: u64,
+        max_contribution: u64,
+        duration_seconds: u64,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create initialize token sale instruction
+        let market = self.get_market(market_pubkey)?;
+        let authority_pubkey = market_authority.pubkey();
+        let instruction = DexInstruction::initialize_token_sale(
+            &self.program_id,
+            &authority_pubkey,
+            market_pubkey,
+            &market_token_account(&market, &authority_pubkey, false),
+            &market.base_mint,
+            &market.quote_mint,
+            &market.base_token_program,
+            base_amount,
+            min_price,
+            max_contribution,
+            duration_seconds,
+        )?;
+        let instruction = Self::with_token_mints(instruction, &market)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, market_authority],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Contribute `amount` quote tokens from the contributor's associated token account to a
+    /// market's token sale
+    pub fn contribute_to_sale(
+        &self,
+        payer: &dyn Signer,
+        contributor: &dyn Signer,
+        market_pubkey: &Pubkey,
+        amount: u64,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create contribute to sale instruction
+        let market = self.get_market(market_pubkey)?;
+        let contributor_pubkey = contributor.pubkey();
+        let instruction = DexInstruction::contribute_to_sale(
+            &self.program_id,
+            &contributor_pubkey,
+            market_pubkey,
+            &market_token_account(&market, &contributor_pubkey, true),
+            &market.quote_mint,
+            &market.quote_token_program,
+            amount,
+        )?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, contributor],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Finalize an ended token sale, paying the market authority's associated token accounts
+    pub fn finalize_sale(&self, payer: &dyn Signer, market_pubkey: &Pubkey) -> Result<String, Box<dyn Error>> {
+        // Create finalize sale instruction
+        let market = self.get_market(market_pubkey)?;
+        let instruction = DexInstruction::finalize_sale(
+            &self.program_id,
+            market_pubkey,
+            &market_token_account(&market, &market.authority, false),
+            &market_token_account(&market, &market.authority, true),
+            &market.base_mint,
+            &market.quote_mint,
+            &spl_token::id(),
+        )?;
+        let instruction = Self::with_token_mints(instruction, &market)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(&[instruction], Some(&payer.pubkey()), &[payer], recent_blockhash);
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Claim the contributor's base tokens from a successful token sale, or its refund from a
+    /// failed one, into its associated token account
+    pub fn claim_sale(&self, contributor: &dyn Signer, market_pubkey: &Pubkey) -> Result<String, Box<dyn Error>> {
+        let market = self.get_market(market_pubkey)?;
+        let sale = self.get_token_sale(market_pubkey)?.ok_or("Market has no token sale")?;
+        
+        // Create claim sale instruction
+        let contributor_pubkey = contributor.pubkey();
+        let is_refund = sale.status == SaleStatus::Failed;
+        let instruction = DexInstruction::claim_sale(
+            &self.program_id,
+            &contributor_pubkey,
+            market_pubkey,
+            &market_token_account(&market, &contributor_pubkey, is_refund),
+            &market.base_mint,
+            &market.quote_mint,
+            &spl_token::id(),
+        )?;
+        let instruction = Self::with_token_mints(instruction, &market)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(&[instruction], Some(&contributor_pubkey), &[contributor], recent_blockhash);
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Get the token sale of a market, if it has one
+    pub fn get_token_sale(&self, market_pubkey: &Pubkey) -> Result<Option<TokenSale>, Box<dyn Error>> {
+        let sale_pubkey = TokenSale::find_address(&self.program_id, market_pubkey).0;
+        let account = self
+            .rpc_client
+            .get_account_with_commitment(&sale_pubkey, self.rpc_client.commitment())?
+            .value;
+        match account {
+            Some(account) if account.owner == self.program_id => Ok(Some(TokenSale::unpack_from_slice(&account.data)?)),
+            _ => Ok(None),
+        }
+    }
+    
+    /// Get an owner's contribution to a market's token sale, if it has one
+    pub fn get_sale_contribution(
+        &self,
+        market_pubkey: &Pubkey,
+        owner: &Pubkey,
+    ) -> Result<Option<SaleContribution>, Box<dyn Error>> {
+        let contribution_pubkey = SaleContribution::find_address(&self.program_id, market_pubkey, owner).0;
+        let account = self
+            .rpc_client
+            .get_account_with_commitment(&contribution_pubkey, self.rpc_client.commitment())?
+            .value;
+        match account {
+            Some(account) if account.owner == self.program_id => Ok(Some(SaleContribution::unpack_from_slice(&account.data)?)),
+            _ => Ok(None),
+        }
+    }
+    
+    /// Create the program's stake pool, where `governance_mint` tokens are staked for a share
+    /// of swept fees paid in `reward_mint` and taker fee discounts
+    pub fn initialize_stake_pool(
+        &self,
+        admin: &dyn Signer,
+        governance_mint: &Pubkey,
+        reward_mint: &Pubkey,
+        epoch_seconds: u64,
+        fee_share_bps: u16,
+        fee_tiers: [FeeTier; STAKE_FEE_TIERS],
+    ) -> Result<String, Box<dyn Error>> {
+        // The vaults are created by the program owning each mint
+        let governance_token_program = self.rpc_client.get_account(governance_mint)?.owner;
+        let reward_token_program = self.rpc_client.get_account(reward_mint)?.owner;
+        
+        // Create initialize stake pool instruction
+        let instruction = DexInstruction::initialize_stake_pool(
+            &self.program_id,
+            &admin.pubkey(),
+            governance_mint,
+            reward_mint,
+            &governance_token_program,
+            &reward_token_program,
+            epoch_seconds,
+            fee_share_bps,
+            fee_tiers,
+        )?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(&[instruction], Some(&admin.pubkey()), &[admin], recent_blockhash);
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Stake `amount` governance tokens from the owner's associated token account
+    pub fn stake(&self, owner: &dyn Signer, amount: u64) -> Result<String, Box<dyn Error>> {
+        let stake_pool = self.get_stake_pool()?.ok_or("Stake pool not initialized")?;
+        
+        // Create stake instruction
+        let owner_pubkey = owner.pubkey();
+        let instruction = DexInstruction::stake(
+            &self.program_id,
+            &owner_pubkey,
+            &get_associated_token_address_with_program_id(
+                &owner_pubkey,
+                &stake_pool.governance_mint,
+                &stake_pool.governance_token_program,
+            ),
+            &stake_pool.governance_mint,
+            &stake_pool.governance_token_program,
+            amount,
+        )?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(&[instruction], Some(&owner_pubkey), &[owner], recent_blockhash);
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Unstake `amount` governance tokens into the owner's associated token account
+    pub fn unstake(&self, owner: &dyn Signer, amount: u64) -> Result<String, Box<dyn Error>> {
+        let stake_pool = self.get_stake_pool()?.ok_or("Stake pool not initialized")?;
+        
+        // Create unstake instruction
+        let owner_pubkey = owner.pubkey();
+        let instruction = DexInstruction::unstake(
+            &self.program_id,
+            &owner_pubkey,
+            &get_associated_token_address_with_program_id(
+                &owner_pubkey,
+                &stake_pool.governance_mint,
+                &stake_pool.governance_token_program,
+            ),
+            &stake_pool.governance_mint,
+            &stake_pool.governance_token_program,
+            amount,
+        )?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(&[instruction], Some(&owner_pubkey), &[owner], recent_blockhash);
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Close the stake pool's epoch, sharing the rewards that arrived during it among the stakers
+    pub fn distribute_staking_rewards(&self, payer: &dyn Signer) -> Result<String, Box<dyn Error>> {
+        // Create distribute staking rewards instruction
+        let instruction = DexInstruction::distribute_staking_rewards(&self.program_id)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(&[instruction], Some(&payer.pubkey()), &[payer], recent_blockhash);
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Claim the owner's staking rewards into its associated token account
+    pub fn claim_staking_rewards(&self, owner: &dyn Signer) -> Result<String, Box<dyn Error>> {
+        let stake_pool = self.get_stake_pool()?.ok_or("Stake pool not initialized")?;
+        
+        // Create claim staking rewards instruction
+        let owner_pubkey = owner.pubkey();
+        let instruction = DexInstruction::claim_staking_rewards(
+            &self.program_id,
+            &owner_pubkey,
+            &get_associated_token_address_with_program_id(
+                &owner_pubkey,
+                &stake_pool.reward_mint,
+                &stake_pool.reward_token_program,
+            ),
+            &stake_pool.reward_mint,
+            &stake_pool.reward_token_program,
+        )?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(&[instruction], Some(&owner_pubkey), &[owner], recent_blockhash);
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Get the program's stake pool, if it has one
+    pub fn get_stake_pool(&self) -> Result<Option<StakePool>, Box<dyn Error>> {
+        let stake_pool_pubkey = StakePool::find_address(&self.program_id).0;
+        let account = self
+            .rpc_client
+            .get_account_with_commitment(&stake_pool_pubkey, self.rpc_client.commitment())?
+            .value;
+        match account {
+            Some(account) if account.owner == self.program_id => Ok(Some(StakePool::unpack_from_slice(&account.data)?)),
+            _ => Ok(None),
+        }
+    }
+    
+    /// Get an owner's stake account, if it has staked
+    pub fn get_stake_account(&self, owner: &Pubkey) -> Result<Option<StakeAccount>, Box<dyn Error>> {
+        let stake_pubkey = StakeAccount::find_address(&self.program_id, owner).0;
+        let account = self
+            .rpc_client
+            .get_account_with_commitment(&stake_pubkey, self.rpc_client.commitment())?
+            .value;
+        match account {
+            Some(account) if account.owner == self.program_id => Ok(Some(StakeAccount::unpack_from_slice(&account.data)?)),
+            _ => Ok(None),
+        }
+    }
+    
+    /// Set the loyalty points emission schedule of a market to its first `num_phases` phases
+    pub fn set_loyalty_schedule(
+        &self,
+        authority: &dyn Signer,
+        market_pubkey: &Pubkey,
+        num_phases: u8,
+        phases: [EmissionPhase; LOYALTY_SCHEDULE_PHASES],
+    ) -> Result<String, Box<dyn Error>> {
+        // Create set loyalty schedule instruction
+        let instruction =
+            DexInstruction::set_loyalty_schedule(&self.program_id, &authority.pubkey(), market_pubkey, num_phases, phases)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction =
+            self.new_signed_transaction(&[instruction], Some(&authority.pubkey()), &[authority], recent_blockhash);
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Open the owner's loyalty points account on a market, so its fills there earn points
+    pub fn open_loyalty_points(&self, owner: &dyn Signer, market_pubkey: &Pubkey) -> Result<String, Box<dyn Error>> {
+        // Create open loyalty points instruction
+        let instruction = DexInstruction::open_loyalty_points(&self.program_id, &owner.pubkey(), market_pubkey)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(&[instruction], Some(&owner.pubkey()), &[owner], recent_blockhash);
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Get the loyalty schedule of a market, if it has one
+    pub fn get_loyalty_schedule(&self, market_pubkey: &Pubkey) -> Result<Option<LoyaltySchedule>, Box<dyn Error>> {
+        let schedule_pubkey = LoyaltySchedule::find_address(&self.program_id, market_pubkey).0;
+        let account = self
+            .rpc_client
+            .get_account_with_commitment(&schedule_pubkey, self.rpc_client.commitment())?
+            .value;
+        match account {
+            Some(account) if account.owner == self.program_id => Ok(Some(LoyaltySchedule::unpack_from_slice(&account.data)?)),
+            _ => Ok(None),
+        }
+    }
+    
+    /// Get an owner's loyalty points on a market, if it has opened its account
+    pub fn get_loyalty_points(&self, market_pubkey: &Pubkey, owner: &Pubkey) -> Result<Option<LoyaltyPoints>, Box<dyn Error>> {
+        let points_pubkey = LoyaltyPoints::find_address(&self.program_id, market_pubkey, owner).0;
+        let account = self
+            .rpc_client
+            .get_account_with_commitment(&points_pubkey, self.rpc_client.commitment())?
+            .value;
+        match account {
+            Some(account) if account.owner == self.program_id => Ok(Some(LoyaltyPoints::unpack_from_slice(&account.data)?)),
+            _ => Ok(None),
+        }
+    }
+    
+    /// Get the Wormhole origin of a mint, if the token bridge wrapped it
+    ///
+    /// Only a wrapped meta account owned by `token_bridge` whose attestation derives back to
+    /// `mint` counts, so a mint the bridge did not create is reported as not wrapped.
+    pub fn get_wrapped_asset(&self, token_bridge: &Pubkey, mint: &Pubkey) -> Result<Option<WrappedAsset>, Box<dyn Error>> {
+        let meta_pubkey = wormhole::wrapped_meta_address(token_bridge, mint);
+        let account = self
+            .rpc_client
+            .get_account_with_commitment(&meta_pubkey, self.rpc_client.commitment())?
+            .value;
+        match account {
+            Some(account) if account.owner == *token_bridge => Ok(WrappedAsset::unpack(&account.data)
+                .filter(|asset| asset.attests(token_bridge, mint))),
+            _ => Ok(None),
+        }
+    }
+    
+    /// Get the Wormhole origins of a market's base and quote mints
+    pub fn get_market_wrapped_assets(
+        &self,
+        token_bridge: &Pubkey,
+        market_pubkey: &Pubkey,
+    ) -> Result<(Option<WrappedAsset>, Option<WrappedAsset>), Box<dyn Error>> {
+        let market = self.get_market(market_pubkey)?;
+        let base = self.get_wrapped_asset(token_bridge, &{ market.base_mint })?;
+        let quote = self.get_wrapped_asset(token_bridge, &{ market.quote_mint })?;
+        Ok((base, quote))
+    }
+    
+    /// Get the perp market of a market, if it has one
+    pub fn get_perp_market(&self, market_pubkey: &Pubkey) -> Result<Option<PerpMarket>, Box<dyn Error>> {
+        let perp_market_pubkey = PerpMarket::find_address(&self.program_id, market_pubkey).0;
+        let account = self
+            .rpc_client
+            .get_account_with_commitment(&perp_market_pubkey, self.rpc_client.commitment())?
+            .value;
+        match account {
+            Some(account) if account.owner == self.program_id => Ok(Some(PerpMarket::unpack_from_slice(&account.data)?)),
+            _ => Ok(None),
+        }
+    }
+    
+    /// Get an owner's perp position on a market, if it has one
+    pub fn get_perp_position(&self, market_pubkey: &Pubkey, owner: &Pubkey) -> Result<Option<PerpPosition>, Box<dyn Error>> {
+        let position_pubkey = PerpPosition::find_address(&self.program_id, market_pubkey, owner).0;
+        let account = self
+            .rpc_client
+            .get_account_with_commitment(&position_pubkey, self.rpc_client.commitment())?
+            .value;
+        match account {
+            Some(account) if account.owner == self.program_id => Ok(Some(PerpPosition::unpack_from_slice(&account.data)?)),
+            _ => Ok(None),
+        }
+    }
+    
+    /// Get a perp order
+    pub fn get_perp_order(&self, order_pubkey: &Pubkey) -> Result<PerpOrder, Box<dyn Error>> {
+        let account = self.rpc_client.get_account(order_pubkey)?;
+        Ok(PerpOrder::unpack_from_slice(&account.data)?)
+    }
+    
+    /// Get a market's event queue, if it has one
+    pub fn get_event_queue(&self, market_pubkey: &Pubkey) -> Result<Option<EventQueue>, Box<dyn Error>> {
+        let event_queue_pubkey = EventQueue::find_address(&self.program_id, market_pubkey).0;
+        let account = self
+            .rpc_client
+            .get_account_with_commitment(&event_queue_pubkey, self.rpc_client.commitment())?
+            .value;
+        match account {
+            Some(account) if account.owner == self.program_id => Ok(Some(EventQueue::unpack_from_slice(&account.data)?)),
+            _ => Ok(None),
+        }
+    }
+    
+    /// Migrate a market or order account of an earlier layout to the current one
+    pub fn migrate_account(&self, payer: &dyn Signer, account: &Pubkey) -> Result<String, Box<dyn Error>> {
+        // Create migrate account instruction
+        let instruction = DexInstruction::migrate_account(&self.program_id, &payer.pubkey(), account)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Get a market's TWAP oracle, if it has one
+    pub fn get_twap_oracle(&self, market_pubkey: &Pubkey) -> Result<Option<TwapOracle>, Box<dyn Error>> {
+        let twap_oracle_pubkey = TwapOracle::find_address(&self.program_id, market_pubkey).0;
+        let account = self
+            .rpc_client
+            .get_account_with_commitment(&twap_oracle_pubkey, self.rpc_client.commitment())?
+            .value;
+        match account {
+            Some(account) if account.owner == self.program_id => Ok(Some(TwapOracle::unpack_from_slice(&account.data)?)),
+            _ => Ok(None),
+        }
+    }
+    
+    /// Publish a market's best bid and offer and TWAP to other chains through Wormhole,
+    /// returning the signature and the posted message
+    ///
+    /// The message is read back from the market's message account, which the next publication
+    /// rewrites; a message of another nonce means a later publication already replaced it.
+    pub fn publish_prices(
+        &self,
+        payer: &dyn Signer,
+        market_pubkey: &Pubkey,
+        nonce: u32,
+        finalized: bool,
+    ) -> Result<(String, PostedMessage), Box<dyn Error>> {
+        // Create publish prices instruction
+        let instruction = DexInstruction::publish_prices(&self.program_id, &payer.pubkey(), market_pubkey, nonce, finalized)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(&[instruction], Some(&payer.pubkey()), &[payer], recent_blockhash);
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        let message = self
+            .get_price_message(market_pubkey)?
+            .filter(|message| message.nonce == nonce)
+            .ok_or("Price message replaced by a later publication")?;
+        Ok((signature.to_string(), message))
+    }
+    
+    /// Get the last price message a market posted through Wormhole, if it posted any
+    pub fn get_price_message(&self, market_pubkey: &Pubkey) -> Result<Option<PostedMessage>, Box<dyn Error>> {
+        let message_pubkey = core_bridge::find_price_message_address(&self.program_id, market_pubkey).0;
+        let account = self
+            .rpc_client
+            .get_account_with_commitment(&message_pubkey, self.rpc_client.commitment())?
+            .value;
+        match account {
+            Some(account) if account.owner == core_bridge::CORE_BRIDGE_PROGRAM_ID => Ok(PostedMessage::unpack(&account.data)),
+            _ => Ok(None),
+        }
+    }
+    
+    /// Get the number of price messages a market posted through Wormhole, the sequence of its
+    /// next one
+    pub fn get_price_message_count(&self, market_pubkey: &Pubkey) -> Result<u64, Box<dyn Error>> {
+        let emitter = core_bridge::find_emitter_address(&self.program_id, market_pubkey).0;
+        let account = self
+            .rpc_client
+            .get_account_with_commitment(&core_bridge::sequence_address(&emitter), self.rpc_client.commitment())?
+            .value;
+        Ok(account.and_then(|account| core_bridge::next_sequence(&account.data)).unwrap_or(0))
+    }
+    
+    /// Set the share of taker fees paid to referrers on a market
+    pub fn set_referral_share(
+        &self,
+        payer: &dyn Signer,
+        market_authority: &dyn Signer,
+        market_pubkey: &Pubkey,
+        referral_share_bps: u16,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create set referral share instruction
+        let instruction = DexInstruction::set_referral_share(
+            &self.program_id,
+            &market_authority.pubkey(),
+            market_pubkey,
+            referral_share_bps,
+        )?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, market_authority],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Create the fee vault of a market
+    pub fn initialize_fee_vault(
+        &self,
+        payer: &dyn Signer,
+        market_pubkey: &Pubkey,
+    ) -> Result<String, Box<dyn Error>> {
+        // The vault holds the market's quote mint
+        let market = self.get_market(market_pubkey)?;
+        let instruction = DexInstruction::initialize_fee_vault(
+            &self.program_id,
+            &payer.pubkey(),
+            market_pubkey,
+            &market.quote_mint,
+            &market.quote_token_program,
+        )?;
+        
+        let instruction = Self::with_token_mints(instruction, &market)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Sweep the fee vault of a market into a quote token account
+    pub fn sweep_fees(
+        &self,
+        payer: &dyn Signer,
+        fee_authority: &dyn Signer,
+        market_pubkey: &Pubkey,
+        destination: &Pubkey,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create sweep fees instruction
+        let market = self.get_market(market_pubkey)?;
+        let mut instruction = DexInstruction::sweep_fees(
+            &self.program_id,
+            &fee_authority.pubkey(),
+            market_pubkey,
+            destination,
+            &spl_token::id(),
+        )?;
+        
+        // Stakers take their share of fees paid in their reward mint
+        if let Some(stake_pool) = self.get_stake_pool()? {
+            if stake_pool.reward_mint == market.quote_mint {
+                instruction.accounts.extend([
+                    AccountMeta::new_readonly(StakePool::find_address(&self.program_id).0, false),
+                    AccountMeta::new(StakePool::find_vault_address(&self.program_id, true).0, false),
+                ]);
+            }
+        }
+        
+        // The treasury and the creator take their shares of the rest in their fee accounts
+        for (fee_account, share_bps) in [
+            (market.treasury_fee_account, market.treasury_fee_share_bps),
+            (market.creator_fee_account, market.creator_fee_share_bps),
+        ] {
+            if share_bps > 0 {
+                instruction.accounts.push(AccountMeta::new(fee_account, false));
+            }
+        }
+        
+        let instruction = Self::with_token_mints(instruction, &market)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, fee_authority],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Set the shares of a market's swept fees paid to the protocol treasury and the market
+    /// creator, into the associated quote token accounts of the config's treasury and of the
+    /// market authority
+    pub fn set_fee_split(
+        &self,
+        payer: &dyn Signer,
+        admin: &dyn Signer,
+        market_pubkey: &Pubkey,
+        treasury_share_bps: u16,
+        creator_share_bps: u16,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create set fee split instruction
+        let market = self.get_market(market_pubkey)?;
+        let config = self.get_program_config()?;
+        let instruction = DexInstruction::set_fee_split(
+            &self.program_id,
+            &admin.pubkey(),
+            market_pubkey,
+            &market_token_account(&market, &config.treasury, true),
+            &market_token_account(&market, &market.authority, true),
+            treasury_share_bps,
+            creator_share_bps,
+        )?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, admin],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Scale a market's taker fee by the realized volatility of its fills, or turn it off with
+    /// a window of zero
+    pub fn set_dynamic_fees(
+        &self,
+        payer: &dyn Signer,
+        market_authority: &dyn Signer,
+        market_pubkey: &Pubkey,
+        min_fee_bps: u16,
+        volatility_bps: u16,
+        volatility_window: u16,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create set dynamic fees instruction
+        let instruction = DexInstruction::set_dynamic_fees(
+            &self.program_id,
+            &market_authority.pubkey(),
+            market_pubkey,
+            min_fee_bps,
+            volatility_bps,
+            volatility_window,
+        )?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, market_authority],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Create the insurance fund of a market
+    pub fn initialize_insurance_fund(
+        &self,
+        payer: &dyn Signer,
+        market_pubkey: &Pubkey,
+    ) -> Result<String, Box<dyn Error>> {
+        // The fund holds the market's quote mint
+        let market = self.get_market(market_pubkey)?;
+        let instruction = DexInstruction::initialize_insurance_fund(
+            &self.program_id,
+            &payer.pubkey(),
+            market_pubkey,
+            &market.quote_mint,
+            &market.quote_token_program,
+        )?;
+        
+        let instruction = Self::with_token_mints(instruction, &market)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Deposit quote tokens from a token account of the depositor into a market's insurance fund
+    pub fn deposit_insurance_fund(
+        &self,
+        payer: &dyn Signer,
+        depositor: &dyn Signer,
+        market_pubkey: &Pubkey,
+        source: &Pubkey,
+        amount: u64,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create deposit insurance fund instruction
+        let instruction = DexInstruction::deposit_insurance_fund(
+            &self.program_id,
+            &depositor.pubkey(),
+            market_pubkey,
+            source,
+            &spl_token::id(),
+            amount,
+        )?;
+        
+        let instruction = Self::with_token_mints(instruction, &self.get_market(market_pubkey)?)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, depositor],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Draw quote tokens from a market's insurance fund into a quote token account
+    pub fn withdraw_insurance_fund(
+        &self,
+        payer: &dyn Signer,
+        market_authority: &dyn Signer,
+        market_pubkey: &Pubkey,
+        destination: &Pubkey,
+        amount: u64,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create withdraw insurance fund instruction
+        let instruction = DexInstruction::withdraw_insurance_fund(
+            &self.program_id,
+            &market_authority.pubkey(),
+            market_pubkey,
+            destination,
+            &spl_token::id(),
+            amount,
+        )?;
+        
+        let instruction = Self::with_token_mints(instruction, &self.get_market(market_pubkey)?)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, market_authority],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Hand the right to sweep a market's fees to a new fee authority
+    pub fn set_fee_authority(
+        &self,
+        payer: &dyn Signer,
+        fee_authority: &dyn Signer,
+        market_pubkey: &Pubkey,
+        new_fee_authority: &Pubkey,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create set fee authority instruction
+        let instruction = DexInstruction::set_fee_authority(
+            &self.program_id,
+            &fee_authority.pubkey(),
+            market_pubkey,
+            new_fee_authority,
+        )?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, fee_authority],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Close an empty market and every account it owns, returning their rent to a recipient
+    pub fn close_market(
+        &self,
+        payer: &dyn Signer,
+        market_authority: &dyn Signer,
+        market_pubkey: &Pubkey,
+        recipient: &Pubkey,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create close market instruction; each vault is closed by the token program of its mint,
+        // after the transfer fees withheld in Token-2022 vaults are harvested to the mint
+        let market = self.get_market(market_pubkey)?;
+        let mut instructions = Vec::new();
+        for (is_quote, mint) in [(false, market.base_mint), (true, market.quote_mint)] {
+            if *market.token_program(is_quote) != spl_token_2022::id() {
+                continue;
+            }
+            let vaults: Vec<Pubkey> = self
+                .market_vault_addresses(market_pubkey, is_quote)
+                .into_iter()
+                .filter(|vault| matches!(self.rpc_client.get_account(vault), Ok(account) if account.owner == spl_token_2022::id()))
+                .collect();
+            if !vaults.is_empty() {
+                let vaults: Vec<&Pubkey> = vaults.iter().collect();
+                instructions.push(spl_token_2022::extension::transfer_fee::instruction::harvest_withheld_tokens_to_mint(
+                    &spl_token_2022::id(),
+                    &mint,
+                    &vaults,
+                )?);
+            }
+        }
+        let mut instruction = DexInstruction::close_market(
+            &self.program_id,
+            &market_authority.pubkey(),
+            market_pubkey,
+            recipient,
+            &market.quote_token_program,
+        )?;
+        if market.base_token_program != market.quote_token_program {
+            instruction.accounts.push(AccountMeta::new_readonly(market.base_token_program, false));
+        }
+        instructions.push(instruction);
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &instructions,
+            Some(&payer.pubkey()),
+            &[payer, market_authority],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Let the market's RFQ authority move up to `amount` tokens out of a maker's token account
+    ///
+    /// Makers approve the account their firm quotes debit: base tokens for asks, quote tokens for bids.
+    pub fn approve_rfq_authority(
+        &self,
+        payer: &dyn Signer,
+        maker: &dyn Signer,
+        market_pubkey: &Pubkey,
+        token_account: &Pubkey,
+        amount: u64,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create approve instruction
+        let rfq_authority = Market::find_rfq_authority_address(&self.program_id, market_pubkey).0;
+        let instruction = spl_token_2022::instruction::approve(
+            &self.get_token_program(token_account)?,
+            token_account,
+            &rfq_authority,
+            &maker.pubkey(),
+            &[],
+            amount,
+        )?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, maker],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Execute a signed firm quote as the taker, settling between both sides' associated token accounts
+    pub fn execute_firm_quote(
+        &self,
+        payer: &dyn Signer,
+        taker: &dyn Signer,
+        signed_quote: &SignedQuote,
+        base_amount: u64,
+    ) -> Result<String, Box<dyn Error>> {
+        let quote = &signed_quote.quote;
+        let market = self.get_market(&quote.market)?;
+        let taker_pubkey = taker.pubkey();
+        
+        // Create execute firm quote instruction
+        let builder = if market.whitelist_enabled {
+            DexInstruction::execute_firm_quote_whitelisted
+        } else {
+            DexInstruction::execute_firm_quote
+        };
+        let instruction = builder(
+            &self.program_id,
+            &taker_pubkey,
+            quote,
+            &market_token_account(&market, &taker_pubkey, false),
+            &market_token_account(&market, &taker_pubkey, true),
+            &market_token_account(&market, &quote.maker, false),
+            &market_token_account(&market, &quote.maker, true),
+            &market.quote_token_program,
+            base_amount,
+        )?;
+        
+        let instruction = Self::with_token_mints(instruction, &market)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[signed_quote.ed25519_instruction(&self.program_id), instruction],
+            Some(&payer.pubkey()),
+            &[payer, taker],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Get the fill record of a firm quote, if it has been executed
+    pub fn get_quote_fill(&self, quote: &FirmQuote) -> Result<Option<QuoteFill>, Box<dyn Error>> {
+        let fill_pubkey = quote.find_fill_address(&self.program_id).0;
+        let account = self
+            .rpc_client
+            .get_account_with_commitment(&fill_pubkey, self.rpc_client.commitment())?
+            .value;
+        match account {
+            Some(account) if account.owner == self.program_id => Ok(Some(QuoteFill::unpack_from_slice(&account.data)?)),
+            _ => Ok(None),
+        }
+    }
+    
+    /// Check every prerequisite of placing an order and build the instructions fixing what is missing
+    pub fn preflight_order(
+        &self,
+        payer: &Pubkey,
+        owner: &Pubkey,
+        market_pubkey: &Pubkey,
+        intent: &OrderIntent,
+    ) -> Result<PreflightReport, Box<dyn Error>> {
+        let market = self.get_market(market_pubkey)?;
+        let token_account = self
+            .rpc_client
+            .get_account_with_commitment(
+                &market_token_account(&market, owner, intent.is_buy),
+                self.rpc_client.commitment(),
+            )?
+            .value
+            .map(|account| TokenAccount::unpack_from_slice(&account.data))
+            .transpose()?;
+        let owner_state = OwnerState {
+            owner: *owner,
+            token_account,
+            lamports: self.rpc_client.get_balance(owner)?,
+            order_rent_lamports: self.rpc_client.get_minimum_balance_for_rent_exemption(Order::LEN)?,
+        };
+        
+        Ok(preflight::check_order(&market, intent, &owner_state, payer))
+    }
+    
+    /// Send the fix instructions of a pre-flight report
+    pub fn prepare_order_accounts(
+        &self,
+        payer: &dyn Signer,
+        report: &PreflightReport,
+    ) -> Result<Option<String>, Box<dyn Error>> {
+        let instructions = report.fix_instructions();
+        if instructions.is_empty() {
+            return Ok(None);
+        }
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &instructions,
+            Some(&payer.pubkey()),
+            &[payer],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(Some(signature.to_string()))
+    }
+    
+    /// Get the whitelist entry of an owner on a market, if the owner is whitelisted
+    pub fn get_whitelist_entry(
+        &self,
+        market_pubkey: &Pubkey,
+        owner: &Pubkey,
+    ) -> Result<Option<WhitelistEntry>, Box<dyn Error>> {
+        let entry_pubkey = WhitelistEntry::find_address(&self.program_id, market_pubkey, owner).0;
+        let account = self
+            .rpc_client
+            .get_account_with_commitment(&entry_pubkey, self.rpc_client.commitment())?
+            .value;
+        match account {
+            Some(account) if account.owner == self.program_id => {
+                Ok(Some(WhitelistEntry::unpack_from_slice(&account.data)?))
+            }
+            _ => Ok(None),
+        }
+    }
+    
+    /// Get the fee vault address of a market
+    pub fn get_fee_vault_address(&self, market_pubkey: &Pubkey) -> Pubkey {
+        Market::find_fee_vault_address(&self.program_id, market_pubkey).0
+    }
+    
+    /// Get the addresses of the vaults a market and its extensions keep its base or quote tokens in
+    pub fn market_vault_addresses(&self, market_pubkey: &Pubkey, is_quote: bool) -> Vec<Pubkey> {
+        let program_id = &self.program_id;
+        let mut vaults = vec![
+            Market::find_escrow_vault_address(program_id, market_pubkey, is_quote).0,
+            Pool::find_vault_address(program_id, market_pubkey, is_quote).0,
+            RangePool::find_vault_address(program_id, market_pubkey, is_quote).0,
+            TokenSale::find_vault_address(program_id, market_pubkey, is_quote).0,
+        ];
+        if is_quote {
+            vaults.push(self.get_fee_vault_address(market_pubkey));
+            vaults.push(Market::find_insurance_fund_address(program_id, market_pubkey).0);
+            vaults.push(PerpMarket::find_vault_address(program_id, market_pubkey).0);
+        }
+        vaults
+    }
+    
+    /// Get the OpenOrders address of an owner on a market
+    pub fn get_open_orders_address(&self, market_pubkey: &Pubkey, owner: &Pubkey) -> Pubkey {
+        OpenOrders::find_address(&self.program_id, market_pubkey, owner).0
+    }
+    
+    /// Get the insurance fund address of a market
+    pub fn get_insurance_fund_address(&self, market_pubkey: &Pubkey) -> Pubkey {
+        Market::find_insurance_fund_address(&self.program_id, market_pubkey).0
+    }
+    
+    /// Initialize the program config with the admin as its authority
+    pub fn initialize_program_config(
+        &self,
+        payer: &dyn Signer,
+        admin: &dyn Signer,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create initialize program config instruction
+        let instruction = DexInstruction::initialize_program_config(&self.program_id, &admin.pubkey())?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, admin],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Update the program config
+    pub fn update_program_config(
+        &self,
+        payer: &dyn Signer,
+        admin: &dyn Signer,
+        market_creation_mode: MarketCreationMode,
+        allowed_quote_mints: Vec<Pubkey>,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create update program config instruction
+        let instruction = DexInstruction::update_program_config(
+            &self.program_id,
+            &admin.pubkey(),
+            market_creation_mode,
+            allowed_quote_mints,
+        )?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, admin],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Set the market creation fee
+    pub fn set_market_creation_fee(
+        &self,
+        payer: &dyn Signer,
+        admin: &dyn Signer,
+        treasury: &Pubkey,
+        fee_lamports: u64,
+        fee_quote: u64,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create set market creation fee instruction
+        let instruction = DexInstruction::set_market_creation_fee(
+            &self.program_id,
+            &admin.pubkey(),
+            treasury,
+            fee_lamports,
+            fee_quote,
+        )?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, admin],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Enable or disable trade busting
+    pub fn set_trade_busting(
+        &self,
+        payer: &dyn Signer,
+        admin: &dyn Signer,
+        enabled: bool,
+        window_slots: u64,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create set trade busting instruction
+        let instruction = DexInstruction::set_trade_busting(
+            &self.program_id,
+            &admin.pubkey(),
+            enabled,
+            window_slots,
+        )?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, admin],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Bust a recorded settlement; the taker and maker sign to consent
+    pub fn bust_trade(
+        &self,
+        payer: &dyn Signer,
+        authority: &dyn Signer,
+        market: &Pubkey,
+        settlement_record: &Pubkey,
+        taker: &dyn Signer,
+        maker: &dyn Signer,
+    ) -> Result<String, Box<dyn Error>> {
+        // Reverse the token accounts recorded at settlement
+        let record = self.get_settlement_record(settlement_record)?;
+        let instruction = DexInstruction::bust_trade(
+            &self.program_id,
+            &authority.pubkey(),
+            market,
+            settlement_record,
+            &taker.pubkey(),
+            &maker.pubkey(),
+            &record.taker_base_account,
+            &record.taker_quote_account,
+            &record.maker_base_account,
+            &record.maker_quote_account,
+            &spl_token::id(),
+        )?;
+        
+        let instruction = Self::with_token_mints(instruction, &self.get_market(market)?)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, authority, taker, maker],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Get settlement record information
+    pub fn get_settlement_record(&self, settlement_record: &Pubkey) -> Result<SettlementRecord, Box<dyn Error>> {
+        let account = self.rpc_client.get_account(settlement_record)?;
+        let record = SettlementRecord::unpack_from_slice(&account.data)?;
+        Ok(record)
+    }
+    
+    /// Get program config information
+    pub fn get_program_config(&self) -> Result<ProgramConfig, Box<dyn Error>> {
+        let (config_pubkey, _) = ProgramConfig::find_address(&self.program_id);
+        let account = self.rpc_client.get_account(&config_pubkey)?;
+        let config = ProgramConfig::unpack_from_slice(&account.data)?;
+        Ok(config)
+    }
+    
+    /// Get program config information if the config has been initialized
+    pub fn find_program_config(&self) -> Result<Option<ProgramConfig>, Box<dyn Error>> {
+        let (config_pubkey, _) = ProgramConfig::find_address(&self.program_id);
+        let account = self
+            .rpc_client
+            .get_account_with_commitment(&config_pubkey, self.rpc_client.commitment())?
+            .value;
+        match account {
+            Some(account) if account.owner == self.program_id => {
+                Ok(Some(ProgramConfig::unpack_from_slice(&account.data)?))
+            }
+            _ => Ok(None),
+        }
+    }
+    
+    /// Estimate the cost of creating a market, including rent and the creation fee
+    pub fn estimate_market_creation_cost(&self) -> Result<MarketCreationCost, Box<dyn Error>> {
+        let rent_lamports = self
+            .rpc_client
+            .get_minimum_balance_for_rent_exemption(Market::LEN)?;
+        let (fee_lamports, fee_quote) = match self.find_program_config()? {
+            Some(config) => (config.market_creation_fee_lamports, config.market_creation_fee_quote),
+            None => (0, 0),
+        };
+        
+        Ok(MarketCreationCost {
+            rent_lamports,
+            fee_lamports,
+            fee_quote,
+        })
+    }
+    
+    /// Get the market PDA address of a mint pair and seed
+    pub fn get_market_address(&self, base_mint: &Pubkey, quote_mint: &Pubkey, seed: u64) -> Pubkey {
+        Market::find_address(&self.program_id, base_mint, quote_mint, seed).0
+    }
+    
+    /// Get market information
+    pub fn get_market(&self, market_pubkey: &Pubkey) -> Result<Market, Box<dyn Error>> {
+        let account = self.rpc_client.get_account(market_pubkey)?;
+        let market = Market::unpack_from_slice(&account.data)?;
+        Ok(market)
+    }
+    
+    /// List every market recorded in the market registry, in creation order
+    pub fn get_market_registry(&self) -> Result<Vec<MarketRegistryEntry>, Box<dyn Error>> {
+        let (registry_pubkey, _) = MarketRegistry::find_address(&self.program_id);
+        let account = self
+            .rpc_client
+            .get_account_with_commitment(&registry_pubkey, self.rpc_client.commitment())?
+            .value;
+        match account {
+            Some(account) if account.owner == self.program_id => Ok(MarketRegistry::unpack_entries(&account.data)?),
+            _ => Ok(Vec::new()),
+        }
+    }
+    
+    /// Stream a market's fills from a WebSocket endpoint until the subscription ends
+    ///
+    /// Fills already recorded in the cursor are skipped, so the stream can be resubscribed
+    /// after a disconnect without handing a fill to `on_fill` twice. `on_fill` sees the cursor
+    /// before the fill is recorded; the fill is recorded, and the cursor saved to `cursor_path`,
+    /// only once `on_fill` succeeds.
+    pub fn stream_fills(
+        &self,
+        websocket_url: &str,
+        market_pubkey: &Pubkey,
+        cursor: &mut FillCursor,
+        cursor_path: Option<&Path>,
+        mut on_fill: impl FnMut(&Fill, &FillCursor) -> Result<(), Box<dyn Error>>,
+    ) -> Result<(), Box<dyn Error>> {
+        let (_subscription, receiver) = PubsubClient::logs_subscribe(
+            websocket_url,
+            RpcTransactionLogsFilter::Mentions(vec![market_pubkey.to_string()]),
+            RpcTransactionLogsConfig {
+                commitment: Some(self.rpc_client.commitment()),
+            },
+        )?;
+
+        while let Ok(response) = receiver.recv() {
+            if response.value.err.is_some() {
+                continue;
+            }
+            for fill in fills::parse_fill_events(&self.program_id, &response.value.logs) {
+                if fill.market != *market_pubkey || cursor.is_processed(&fill) {
+                    continue;
+                }
+                on_fill(&fill, cursor)?;
+                cursor.record(&fill);
+                if let Some(cursor_path) = cursor_path {
+                    cursor.save(cursor_path)?;
+                }
+            }
+        }
+        Ok(())
+    }
+    
+    /// Get order information
+    pub fn get_order(&self, order_pubkey: &Pubkey) -> Result<Order, Box<dyn Error>> {
+        let account = self.rpc_client.get_account(order_pubkey)?;
+        let order = Order::unpack_from_slice(&account.data)?;
+        Ok(order)
+    }
+    
+    /// Get a wallet's open orders, optionally only those on one market
+    ///
+    /// The orders are found by filtering order accounts on their owner (and market), so no
+    /// indexer is needed. They are returned by market, then by order ID.
+    pub fn get_open_orders_for_owner(
+        &self,
+        owner: &Pubkey,
+        market_pubkey: Option<&Pubkey>,
+    ) -> Result<Vec<(Pubkey, Order)>, Box<dyn Error>> {
+        let mut filters = vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+            Order::OWNER_OFFSET,
+            owner.as_ref(),
+        ))];
+        if let Some(market_pubkey) = market_pubkey {
+            filters.push(RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                Order::MARKET_OFFSET,
+                market_pubkey.as_ref(),
+            )));
+        }
+        
+        let mut orders = self.fetch_orders(filters)?;
+        orders.sort_by_key(|(_, order)| (order.market, order.order_id));
+        Ok(orders)
+    }
+    
+    /// Export an owner's resting orders on a market, ordered by order ID
+    pub fn export_orders(&self, market_pubkey: &Pubkey, owner: &Pubkey) -> Result<Vec<OrderRow>, Box<dyn Error>> {
+        let mut rows: Vec<OrderRow> = self
+            .fetch_market_orders(market_pubkey)?
+            .iter()
+            .filter(|(_, order)| order.owner == *owner && order.remaining_quantity > 0)
+            .map(|(pubkey, order)| OrderRow::from_order(pubkey, order))
+            .collect();
+        rows.sort_by_key(|row| row.order_id);
+        Ok(rows)
+    }
+    
+    /// Re-place an exported order on a market of this client's program
+    ///
+    /// The order is re-validated against the market first and locks funds from the owner's
+    /// associated token account. Returns the new order account and the transaction signature.
+    pub fn import_order(
+        &self,
+        payer: &dyn Signer,
+        owner: &dyn Signer,
+        market_pubkey: &Pubkey,
+        row: &OrderRow,
+        self_trade_behavior: SelfTradeBehavior,
+    ) -> Result<(Pubkey, String), Box<dyn Error>> {
+        let market = self.get_market(market_pubkey)?;
+        let intent = row
+            .revalidate(&market)
+            .map_err(|issues| format!("Order {} rejected by market {}: {:?}", row.order, market_pubkey, issues))?;
+        
+        // Create the place order instruction for a new order account
+        let order_account = Keypair::new();
+        let owner_token_account = market_token_account(&market, &owner.pubkey(), intent.is_buy);
+        let instruction = self.place_order_instruction(
+            &market,
+            &owner.pubkey(),
+            market_pubkey,
+            &order_account.pubkey(),
+            &owner_token_account,
+            market.token_program(intent.is_buy),
+            intent.is_buy,
+            intent.limit_price,
+            intent.quantity,
+            row.iceberg_display_quantity(),
+            self_trade_behavior,
+            None,
+        )?;
+        
+        let instruction = Self::with_token_mints(instruction, &market)?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer, owner, &order_account],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok((order_account.pubkey(), signature.to_string()))
+    }
+    
+    /// Report an owner's funds locked in orders resting far from the mid for a long time
+    pub fn capital_efficiency_report(
+        &self,
+        market_pubkey: &Pubkey,
+        owner: &Pubkey,
+        thresholds: &IdleThresholds,
+    ) -> Result<CapitalEfficiencyReport, Box<dyn Error>> {
+        let market = self.get_market(market_pubkey)?;
+        let orders = self.fetch_market_orders(market_pubkey)?;
+        
+        // Order ages are measured against the cluster clock, which stamped their creation
+        let clock_account = self.rpc_client.get_account(&sysvar::clock::id())?;
+        let clock: Clock = from_account(&clock_account).ok_or("Invalid clock sysvar")?;
+        let now = u64::try_from(clock.unix_timestamp).unwrap_or(0);
+        
+        Ok(efficiency::analyze_orders(market_pubkey, &market, &orders, owner, thresholds, now))
+    }
+    
+    /// Cancel the idle orders of a capital-efficiency report, returning the transaction signatures
+    ///
+    /// Orders are cancelled in batches of `MAX_CANCEL_ORDER_IDS`, refunding the owner's
+    /// associated token accounts.
+    pub fn cancel_idle_orders(
+        &self,
+        payer: &dyn Signer,
+        owner: &dyn Signer,
+        report: &CapitalEfficiencyReport,
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        if owner.pubkey() != report.owner {
+            return Err(format!("Report is for owner {}", report.owner).into());
+        }
+        
+        let market = self.get_market(&report.market)?;
+        let owner_base_account = market_token_account(&market, &owner.pubkey(), false);
+        let owner_quote_account = market_token_account(&market, &owner.pubkey(), true);
+        
+        let mut signatures = Vec::new();
+        for batch in report.idle_orders.chunks(MAX_CANCEL_ORDER_IDS) {
+            let order_ids: Vec<u64> = batch.iter().map(|order| order.order_id).collect();
+            let order_accounts: Vec<Pubkey> = batch.iter().map(|order| order.order).collect();
+            signatures.push(self.cancel_orders_by_ids(
+                payer,
+                owner,
+                &report.market,
+                &owner_base_account,
+                &owner_quote_account,
+                &spl_token::id(),
+                &order_ids,
+                &order_accounts,
+            )?);
+        }
+        Ok(signatures)
+    }
+    
+    /// Get a market's order book, with resting orders aggregated into price levels
+    ///
+    /// Each side is fetched with its own filter on the market and side of the order
+    /// accounts, so neither request returns the orders of the other side.
+    pub fn get_order_book(&self, market_pubkey: &Pubkey) -> Result<OrderBook, Box<dyn Error>> {
+        let market = self.get_market(market_pubkey)?;
+        let mut orders = Vec::new();
+        for is_buy in [true, false] {
+            orders.extend(
+                self.fetch_market_side_orders(market_pubkey, is_buy)?
+                    .into_iter()
+                    .map(|(_, order)| order),
+            );
+        }
+        
+        Ok(OrderBook::from_orders(*market_pubkey, &orders)
+            .with_taker_fee_bps(market.dynamic_taker_fee_bps())
+            .with_quote_lot_size(market.quote_lot_size))
+    }
+    
+    /// Get the merged order book of several markets trading the same pair
+    pub fn get_aggregated_orderbook(&self, market_pubkeys: &[Pubkey]) -> Result<AggregatedOrderBook, Box<dyn Error>> {
+        let mut pair = None;
+        let mut lot_sizes = None;
+        let mut books = Vec::with_capacity(market_pubkeys.len());
+        
+        for market_pubkey in market_pubkeys {
+            // Only markets of one base/quote pair can be merged
+            let market = self.get_market(market_pubkey)?;
+            match pair {
+                None => pair = Some((market.base_mint, market.quote_mint)),
+                Some(pair) if pair != (market.base_mint, market.quote_mint) => {
+                    return Err(format!("Market {} trades a different pair", market_pubkey).into());
+                }
+                Some(_) => {}
+            }
+            
+            // Prices counted in different lots are not comparable
+            match lot_sizes {
+                None => lot_sizes = Some((market.base_lot_size, market.quote_lot_size)),
+                Some(lot_sizes) if lot_sizes != (market.base_lot_size, market.quote_lot_size) => {
+                    return Err(format!("Market {} uses different lot sizes", market_pubkey).into());
+                }
+                Some(_) => {}
+            }
+            
+            let orders: Vec<Order> = self
+                .fetch_market_orders(market_pubkey)?
+                .into_iter()
+                .map(|(_, order)| order)
+                .collect();
+            books.push(
+                OrderBook::from_orders(*market_pubkey, &orders)
+                    .with_taker_fee_bps(market.dynamic_taker_fee_bps())
+                    .with_quote_lot_size(market.quote_lot_size),
+            );
+        }
+        
+        Ok(AggregatedOrderBook::merge(&books))
+    }
+    
+    /// Split a taker order across several markets of the same pair for the best fill
+    pub fn route_order(
+        &self,
+        market_pubkeys: &[Pubkey],
+        is_buy: bool,
+        quantity: u64,
+    ) -> Result<Vec<RouteLeg>, Box<dyn Error>> {
+        let book = self.get_aggregated_orderbook(market_pubkeys)?;
+        Ok(book.route(is_buy, quantity))
+    }
+    
+    /// Quote a taker order of `quantity` base lots across several markets of the same pair
+    pub fn quote_order(
+        &self,
+        market_pubkeys: &[Pubkey],
+        is_buy: bool,
+        quantity: u64,
+        convention: FeeConvention,
+    ) -> Result<Quote, Box<dyn Error>> {
+        let book = self.get_aggregated_orderbook(market_pubkeys)?;
+        Ok(book.quote(is_buy, quantity, convention))
+    }
+    
+    /// Size a taker order so that it pays (buy) or receives (sell) `quote_amount` quote tokens
+    pub fn quote_order_for_amount(
+        &self,
+        market_pubkeys: &[Pubkey],
+        is_buy: bool,
+        quote_amount: u64,
+        convention: FeeConvention,
+    ) -> Result<Quote, Box<dyn Error>> {
+        let book = self.get_aggregated_orderbook(market_pubkeys)?;
+        Ok(book.quote_for_amount(is_buy, quote_amount, convention))
+    }
+    
+    /// Commit a Merkle root of a market's current open orders
+    pub fn commit_open_orders_root(
+        &self,
+        payer: &dyn Signer,
+        market_pubkey: &Pubkey,
+    ) -> Result<String, Box<dyn Error>> {
+        // Create commit open orders root instruction
+        let order_accounts: Vec<Pubkey> = self
+            .fetch_market_orders(market_pubkey)?
+            .into_iter()
+            .map(|(order_account, _)| order_account)
+            .collect();
+        let instruction = DexInstruction::commit_open_orders_root(
+            &self.program_id,
+            market_pubkey,
+            &order_accounts,
+        )?;
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[payer],
+            recent_blockhash,
+        );
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Build an inclusion proof of an order against the market's current open orders
+    ///
+    /// The proof verifies against the committed root only while the open orders
+    /// are unchanged since the last commitment.
+    pub fn get_open_order_proof(
+        &self,
+        market_pubkey: &Pubkey,
+        order_account: &Pubkey,
+    ) -> Result<OpenOrderProof, Box<dyn Error>> {
+        let mut orders = self.fetch_market_orders(market_pubkey)?;
+        orders.sort_by_key(|(address, _)| *address);
+        
+        let leaves: Vec<[u8; 32]> = orders
+            .iter()
+            .map(|(address, order)| merkle::leaf_hash(address, order))
+            .collect();
+        let index = orders
+            .iter()
+            .position(|(address, _)| address == order_account)
+            .ok_or_else(|| format!("Order {} is not open on market {}", order_account, market_pubkey))?;
+        
+        Ok(OpenOrderProof {
+            order_account: *order_account,
+            leaf: leaves[index],
+            index,
+            num_leaves: leaves.len(),
+            siblings: merkle::proof(&leaves, index).unwrap_or_default(),
+        })
+    }
+    
+    /// Verify an inclusion proof against the market's committed open orders root
+    pub fn verify_open_order_proof(
+        &self,
+        market_pubkey: &Pubkey,
+        proof: &OpenOrderProof,
+    ) -> Result<bool, Box<dyn Error>> {
+        let market = self.get_market(market_pubkey)?;
+        Ok(proof.num_leaves as u64 == market.open_orders_root_leaves
+            && proof.verify(&market.open_orders_root))
+    }
+    
+    /// Fetch all order accounts of a market
+    fn fetch_market_orders(&self, market_pubkey: &Pubkey) -> Result<Vec<(Pubkey, Order)>, Box<dyn Error>> {
+        self.fetch_orders(vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+            Order::MARKET_OFFSET,
+            market_pubkey.as_ref(),
+        ))])
+    }
+    
+    /// Fetch the order accounts of one side of a market
+    fn fetch_market_side_orders(
+        &self,
+        market_pubkey: &Pubkey,
+        is_buy: bool,
+    ) -> Result<Vec<(Pubkey, Order)>, Box<dyn Error>> {
+        self.fetch_orders(vec![
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(Order::MARKET_OFFSET, market_pubkey.as_ref())),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(Order::IS_BUY_OFFSET, &[is_buy as u8])),
+        ])
+    }
+    
+    /// Fetch all initialized order accounts matching `filters`
+    fn fetch_orders(&self, filters: Vec<RpcFilterType>) -> Result<Vec<(Pubkey, Order)>, Box<dyn Error>> {
+        let mut all_filters = vec![RpcFilterType::DataSize(Order::LEN as u64)];
+        all_filters.extend(filters);
+        let config = RpcProgramAccountsConfig {
+            filters: Some(all_filters),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        };
+        
+        let accounts = self
+            .rpc_client
+            .get_program_accounts_with_config(&self.program_id, config)?;
+        
+        let mut orders = Vec::with_capacity(accounts.len());
+        for (pubkey, account) in accounts {
+            let order = Order::unpack_from_slice(&account.data)?;
+            if order.is_initialized {
+                orders.push((pubkey, order));
+            }
+        }
+        Ok(orders)
+    }
+    
+    /// Get token account information
+    pub fn get_token_account(&self, token_account_pubkey: &Pubkey) -> Result<TokenAccount, Box<dyn Error>> {
+        let account = self.rpc_client.get_account(token_account_pubkey)?;
+        let token_account = TokenAccount::unpack_from_slice(&account.data)?;
+        Ok(token_account)
+    }
+    
+    /// Get the token program that owns a mint or token account: SPL Token or Token-2022
+    pub fn get_token_program(&self, pubkey: &Pubkey) -> Result<Pubkey, Box<dyn Error>> {
+        Ok(self.rpc_client.get_account(pubkey)?.owner)
+    }
+    
+    /// Get the decimals of a token mint
+    pub fn get_mint_decimals(&self, mint_pubkey: &Pubkey) -> Result<u8, Box<dyn Error>> {
+        let account = self.rpc_client.get_account(mint_pubkey)?;
+        let mint = Mint::unpack_from_slice(&account.data)?;
+        Ok(mint.decimals)
+    }
+    
+    /// Create an address lookup table of the addresses instructions on a market pass
+    ///
+    /// Returns the table and the signatures of the transactions creating and extending it.
+    pub fn create_market_lookup_table(
+        &self,
+        payer: &dyn Signer,
+        authority: &dyn Signer,
+        market_pubkey: &Pubkey,
+    ) -> Result<(Pubkey, Vec<String>), Box<dyn Error>> {
+        let market = self.get_market(market_pubkey)?;
+        let addresses = lookup_table::market_lookup_addresses(&self.program_id, market_pubkey, &market);
+        self.create_lookup_table(payer, authority, &addresses)
+    }
+    
+    /// Create an address lookup table holding `addresses`, extendable by `authority`
+    ///
+    /// The first addresses are added in the creating transaction and the rest in as many
+    /// extending transactions as they need. V0 transactions can use the table from the slot
+    /// after its last extension.
+    pub fn create_lookup_table(
+        &self,
+        payer: &dyn Signer,
+        authority: &dyn Signer,
+        addresses: &[Pubkey],
+    ) -> Result<(Pubkey, Vec<String>), Box<dyn Error>> {
+        // The table is derived from a slot the lookup table program can find in its slot hashes
+        let recent_slot = self.rpc_client.get_slot_with_commitment(CommitmentConfig::finalized())?;
+        let (create_instruction, lookup_table) =
+            lookup_table_instruction::create_lookup_table(authority.pubkey(), payer.pubkey(), recent_slot);
+        
+        let (first, rest) = addresses.split_at(addresses.len().min(lookup_table::MAX_EXTEND_ADDRESSES));
+        let mut instructions = vec![create_instruction];
+        if !first.is_empty() {
+            instructions.push(lookup_table_instruction::extend_lookup_table(
+                lookup_table,
+                authority.pubkey(),
+                Some(payer.pubkey()),
+                first.to_vec(),
+            ));
+        }
+        
+        // Create and send transaction
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = self.new_signed_transaction(
+            &instructions,
+            Some(&payer.pubkey()),
+            &[payer, authority],
+            recent_blockhash,
+        );
+        
+        let mut signatures = vec![self.rpc_client.send_and_confirm_transaction(&transaction)?.to_string()];
+        signatures.extend(self.extend_lookup_table(payer, authority, &lookup_table, rest)?);
+        Ok((lookup_table, signatures))
+    }
+    
+    /// Add addresses to an address lookup table, in as many transactions as they need
+    pub fn extend_lookup_table(
+        &self,
+        payer: &dyn Signer,
+        authority: &dyn Signer,
+        lookup_table: &Pubkey,
+        addresses: &[Pubkey],
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        let mut signatures = Vec::new();
+        for chunk in addresses.chunks(lookup_table::MAX_EXTEND_ADDRESSES) {
+            let instruction = lookup_table_instruction::extend_lookup_table(
+                *lookup_table,
+                authority.pubkey(),
+                Some(payer.pubkey()),
+                chunk.to_vec(),
+            );
+            
+            // Create and send transaction
+            let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+            let transaction = self.new_signed_transaction(
+                &[instruction],
+                Some(&payer.pubkey()),
+                &[payer, authority],
+                recent_blockhash,
+            );
+            
+            signatures.push(self.rpc_client.send_and_confirm_transaction(&transaction)?.to_string());
+        }
+        Ok(signatures)
+    }
+    
+    /// Get the addresses of an address lookup table, for compiling v0 transactions against it
+    pub fn get_lookup_table(&self, lookup_table: &Pubkey) -> Result<AddressLookupTableAccount, Box<dyn Error>> {
+        let account = self.rpc_client.get_account(lookup_table)?;
+        if account.owner != solana_program::address_lookup_table::program::id() {
+            return Err(format!("Account {} is not an address lookup table", lookup_table).into());
+        }
+        lookup_table::unpack_lookup_table(lookup_table, &account.data)
+            .ok_or_else(|| format!("Invalid address lookup table {}", lookup_table).into())
+    }
+    
+    /// Send instructions in a v0 transaction whose accounts are looked up in `lookup_tables`
+    ///
+    /// The client's transaction options are applied. `signers` are the signers of the
+    /// instructions besides the payer.
+    pub fn send_versioned_transaction(
+        &self,
+        payer: &dyn Signer,
+        signers: &[&dyn Signer],
+        instructions: &[Instruction],
+        lookup_tables: &[AddressLookupTableAccount],
+    ) -> Result<String, Box<dyn Error>> {
+        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = lookup_table::new_versioned_transaction(
+            &self.transaction_options.apply(instructions),
+            payer,
+            signers,
+            lookup_tables,
+            recent_blockhash,
+        )?;
+        
+        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
+        Ok(signature.to_string())
+    }
+    
+    /// Get the display formats of a market's base and quote amounts, counting in its lots
+    pub fn get_market_formats(&self, market_pubkey: &Pubkey) -> Result<(AmountFormat, AmountFormat), Box<dyn Error>> {
+        let market = self.get_market(market_pubkey)?;
+        Ok((
+            AmountFormat::new(self.get_mint_decimals(&market.base_mint)?).with_lot_size(market.base_lot_size),
+            AmountFormat::new(self.get_mint_decimals(&market.quote_mint)?).with_lot_size(market.quote_lot_size),
+        ))
+    }
+    
+    /// Get associated token account address
+    pub fn get_associated_token_account(&self, wallet_pubkey: &Pubkey, token_mint: &Pubkey) -> Pubkey {
+        get_associated_token_address(wallet_pubkey, token_mint)
+    }
+    
+    /// Get the associated token account of a wallet for the base or quote side of a market,
+    /// under the token program of its mint
+    pub fn get_market_token_account(&self, market: &Market, wallet_pubkey: &Pubkey, is_quote: bool) -> Pubkey {
+        market_token_account(market, wallet_pubkey, is_quote)
+    }
+} 
+
+// Side of a market that trades wrapped SOL: true for quote, false for base
+fn native_side(market: &Market, market_pubkey: &Pubkey) -> Result<bool, Box<dyn Error>> {
+    if native::is_native_side(market, true) {
+        Ok(true)
+    } else if native::is_native_side(market, false) {
+        Ok(false)
+    } else {
+        Err(format!("Market {} does not trade wrapped SOL", market_pubkey).into())
+    }
+}
+
+// Associated token account of a wallet for one side of a market, under the token program of
+// its mint
+fn market_token_account(market: &Market, wallet: &Pubkey, is_quote: bool) -> Pubkey {
+    get_associated_token_address_with_program_id(wallet, market.mint(is_quote), market.token_program(is_quote))
+}
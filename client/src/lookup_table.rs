@@ -0,0 +1,88 @@
+// Address lookup tables
+// 地址查找表
+//
+// A legacy transaction spells out every account key, so a handful of settles or a long batch
+// cancel fills its packet with the same market, vault and program keys. A v0 transaction
+// refers to the keys of an address lookup table by a one-byte index instead. These helpers
+// list the keys every instruction on a market passes, so one table per market lets the
+// client pack many more of its instructions into a transaction.
+
+use solana_program::{
+    address_lookup_table::{state::AddressLookupTable, AddressLookupTableAccount},
+    hash::Hash,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    system_program, sysvar,
+};
+use solana_rust_dex::state::{EventQueue, Market, TwapOracle, MARKET_FLAG_EVENT_QUEUE};
+use solana_sdk::{
+    message::{v0, VersionedMessage},
+    signature::Signer,
+    transaction::VersionedTransaction,
+};
+use std::error::Error;
+
+/// Most addresses one extend lookup table instruction adds while fitting in a transaction
+pub const MAX_EXTEND_ADDRESSES: usize = 20;
+
+/// Addresses passed by instructions on a market: the market and its PDAs, queue and oracle
+/// when it has them, mints, token programs and the sysvars and programs settles pass
+pub fn market_lookup_addresses(program_id: &Pubkey, market_pubkey: &Pubkey, market: &Market) -> Vec<Pubkey> {
+    let mut addresses = vec![
+        *program_id,
+        *market_pubkey,
+        Market::find_fee_vault_address(program_id, market_pubkey).0,
+        Market::find_base_vault_address(program_id, market_pubkey).0,
+        Market::find_quote_vault_address(program_id, market_pubkey).0,
+        Market::find_signer_address(program_id, market_pubkey).0,
+    ];
+    if market.has_flag(MARKET_FLAG_EVENT_QUEUE) {
+        addresses.push(EventQueue::find_address(program_id, market_pubkey).0);
+    }
+    if market.twap_oracle_enabled {
+        addresses.push(TwapOracle::find_address(program_id, market_pubkey).0);
+    }
+    for address in [
+        market.base_mint,
+        market.quote_mint,
+        *market.token_program(false),
+        *market.token_program(true),
+        sysvar::instructions::id(),
+        system_program::id(),
+    ] {
+        if !addresses.contains(&address) {
+            addresses.push(address);
+        }
+    }
+    addresses
+}
+
+/// Decode a lookup table account into the addresses a v0 message compiles against
+pub fn unpack_lookup_table(key: &Pubkey, data: &[u8]) -> Option<AddressLookupTableAccount> {
+    let table = AddressLookupTable::deserialize(data).ok()?;
+    Some(AddressLookupTableAccount {
+        key: *key,
+        addresses: table.addresses.to_vec(),
+    })
+}
+
+/// Compile and sign a v0 transaction paid by `payer`, looking its accounts up in `lookup_tables`
+///
+/// `payer` signs first; `signers` are the other signers of the instructions, and a signer
+/// listed twice signs once.
+pub fn new_versioned_transaction(
+    instructions: &[Instruction],
+    payer: &dyn Signer,
+    signers: &[&dyn Signer],
+    lookup_tables: &[AddressLookupTableAccount],
+    recent_blockhash: Hash,
+) -> Result<VersionedTransaction, Box<dyn Error>> {
+    let message = v0::Message::try_compile(&payer.pubkey(), instructions, lookup_tables, recent_blockhash)?;
+    let mut all_signers: Vec<&dyn Signer> = vec![payer];
+    for signer in signers {
+        if !all_signers.iter().any(|added| added.pubkey() == signer.pubkey()) {
+            all_signers.push(*signer);
+        }
+    }
+    Ok(VersionedTransaction::try_new(VersionedMessage::V0(message), &all_signers)?)
+}
@@ -1,17 +1,42 @@
 // Solana Rust DEX CLI
 
-use clap::{App, Arg, SubCommand};
+use clap::{App, Arg, ArgMatches, SubCommand};
 use solana_clap_utils::{
-    input_parsers::{keypair_of, pubkey_of},
+    input_parsers::{keypair_of, pubkey_of, pubkeys_of},
     input_validators::{is_keypair, is_pubkey, is_url},
 };
-use solana_program::pubkey::Pubkey;
-use solana_sdk::signature::Keypair;
-use solana_rust_dex_client::DexClient;
-use solana_rust_dex::instruction::SelfTradeBehavior;
-use std::{error::Error, str::FromStr};
+use solana_program::{hash::Hash, pubkey::Pubkey};
+use solana_sdk::signature::{Keypair, Signer};
+use solana_rust_dex_client::{
+    decode::{decode_data, decode_instruction, DataEncoding},
+    efficiency::IdleThresholds,
+    fills::{websocket_url, FillCursor},
+    format::{format_average_price, format_price, Locale},
+    messages::{describe_error, Language},
+    migration::{read_order_csv, write_order_csv},
+    native,
+    orderbook::FeeConvention,
+    preflight::OrderIntent,
+    rfq::SignedQuote,
+    wormhole::TOKEN_BRIDGE_PROGRAM_ID,
+    DexClient, TransactionOptions,
+};
+use solana_rust_dex::{
+    instruction::SelfTradeBehavior,
+    state::{BatchAuction, FirmQuote, InstructionGuard, InterestRateCurve, LendingPool, MarketCreationMode, MarketStatus, PerpMarket, PerpPosition,
+        EmissionPhase, FeeTier, LoyaltyPoints, LoyaltySchedule, SaleContribution, SaleStatus, StakeAccount, StakePool, TokenSale,
+        LOYALTY_SCHEDULE_PHASES, MARKET_FLAG_BEST_ASK_STALE, MARKET_FLAG_BEST_BID_STALE, MARKET_FLAG_DYNAMIC_FEES,
+        STAKE_FEE_TIERS},
+    wormhole::PricePayload,
+};
+use std::{
+    error::Error,
+    path::Path,
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
-fn main() -> Result<(), Box<dyn Error>> {
+fn main() {
     let matches = App::new("Solana Rust DEX CLI")
         .version("0.1.0")
         .author("Your Name <your.email@example.com>")
@@ -36,6 +61,43 @@ fn main() -> Result<(), Box<dyn Error>> {
                 .default_value("DEX1111111111111111111111111111111111111111")
                 .help("DEX program ID"),
         )
+        .arg(
+            Arg::with_name("locale")
+                .long("locale")
+                .value_name("LOCALE")
+                .takes_value(true)
+                .default_value("en")
+                .help("Locale used to display amounts and prices (e.g. en, zh, de, fr, plain)"),
+        )
+        .arg(
+            Arg::with_name("language")
+                .long("language")
+                .value_name("LANGUAGE")
+                .takes_value(true)
+                .default_value("en")
+                .help("Language of error messages (en, zh)"),
+        )
+        .arg(
+            Arg::with_name("memo")
+                .long("memo")
+                .value_name("TEXT")
+                .takes_value(true)
+                .help("SPL Memo attached to every transaction sent, e.g. an order note or client identifier"),
+        )
+        .arg(
+            Arg::with_name("priority_fee")
+                .long("priority-fee")
+                .value_name("MICRO_LAMPORTS")
+                .takes_value(true)
+                .help("Priority fee of every transaction sent, in micro-lamports per compute unit"),
+        )
+        .arg(
+            Arg::with_name("cu_limit")
+                .long("cu-limit")
+                .value_name("UNITS")
+                .takes_value(true)
+                .help("Compute unit limit of every transaction sent"),
+        )
         .arg(
             Arg::with_name("fee_payer")
                 .short("f")
@@ -64,6 +126,14 @@ fn main() -> Result<(), Box<dyn Error>> {
                         .validator(is_keypair)
                         .help("Market account keypair"),
                 )
+                .arg(
+                    Arg::with_name("seed")
+                        .long("seed")
+                        .value_name("NUMBER")
+                        .takes_value(true)
+                        .conflicts_with("market")
+                        .help("Create the market at the PDA of the mint pair and this seed instead of a keypair"),
+                )
                 .arg(
                     Arg::with_name("base_mint")
                         .long("base-mint")
@@ -85,21 +155,51 @@ fn main() -> Result<(), Box<dyn Error>> {
                         .long("min-base-order-size")
                         .value_name("AMOUNT")
                         .takes_value(true)
-                        .help("Minimum base token order size"),
+                        .help("Minimum order size in base lots"),
                 )
                 .arg(
                     Arg::with_name("tick_size")
                         .long("tick-size")
                         .value_name("AMOUNT")
                         .takes_value(true)
-                        .help("Minimum price increment in quote tokens"),
+                        .help("Minimum price increment in quote lots per base lot"),
+                )
+                .arg(
+                    Arg::with_name("taker_fee_bps")
+                        .long("taker-fee-bps")
+                        .value_name("BPS")
+                        .takes_value(true)
+                        .help("Taker fee rate in basis points (1/100 of 1%)"),
                 )
                 .arg(
-                    Arg::with_name("fee_rate_bps")
-                        .long("fee-rate-bps")
+                    Arg::with_name("maker_fee_bps")
+                        .long("maker-fee-bps")
                         .value_name("BPS")
                         .takes_value(true)
-                        .help("Fee rate in basis points (1/100 of 1%)"),
+                        .allow_hyphen_values(true)
+                        .default_value("0")
+                        .help("Maker fee rate in basis points; negative values are rebates"),
+                )
+                .arg(
+                    Arg::with_name("trustless")
+                        .long("trustless")
+                        .help("Only accept base and quote mints without a freeze authority"),
+                )
+                .arg(
+                    Arg::with_name("base_lot_size")
+                        .long("base-lot-size")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .default_value("1")
+                        .help("Base tokens in one base lot"),
+                )
+                .arg(
+                    Arg::with_name("quote_lot_size")
+                        .long("quote-lot-size")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .default_value("1")
+                        .help("Quote tokens in one quote lot"),
                 ),
         )
         .subcommand(
@@ -159,14 +259,57 @@ fn main() -> Result<(), Box<dyn Error>> {
                         .long("price")
                         .value_name("PRICE")
                         .takes_value(true)
-                        .help("Limit price in quote tokens"),
+                        .help("Limit price in quote lots per base lot"),
                 )
                 .arg(
                     Arg::with_name("quantity")
                         .long("quantity")
                         .value_name("QUANTITY")
                         .takes_value(true)
-                        .help("Order quantity in base tokens"),
+                        .help("Order quantity in base lots"),
+                )
+                .arg(
+                    Arg::with_name("display_quantity")
+                        .long("display-quantity")
+                        .value_name("QUANTITY")
+                        .takes_value(true)
+                        .help("Place an iceberg order displaying this quantity at a time"),
+                )
+                .arg(
+                    Arg::with_name("referrer")
+                        .long("referrer")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .conflicts_with("display_quantity")
+                        .help("Referrer's quote token account to receive a share of the fees"),
+                )
+                .arg(
+                    Arg::with_name("memo")
+                        .long("memo")
+                        .value_name("MEMO")
+                        .takes_value(true)
+                        .help("Internal reference recorded with the order and its fills (at most 32 bytes)"),
+                )
+                .arg(
+                    Arg::with_name("from_deposits")
+                        .long("from-deposits")
+                        .conflicts_with("token_account")
+                        .help("Fund the order from the owner's deposited free balance"),
+                )
+                .arg(
+                    Arg::with_name("native_sol")
+                        .long("native-sol")
+                        .conflicts_with_all(&["token_account", "from_deposits", "delegated_by", "referrer", "memo"])
+                        .help("Pay in and receive native SOL, wrapping what the order locks on a market trading SOL"),
+                )
+                .arg(
+                    Arg::with_name("delegated_by")
+                        .long("delegated-by")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Place the order for this owner as their delegate, signing with --owner"),
                 )
                 .arg(
                     Arg::with_name("self_trade_behavior")
@@ -178,6 +321,54 @@ fn main() -> Result<(), Box<dyn Error>> {
                         .help("Self-trade behavior"),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("preflight-order")
+                .about("Check the prerequisites of placing an order and optionally fix them")
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Order owner pubkey"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("side")
+                        .long("side")
+                        .value_name("SIDE")
+                        .takes_value(true)
+                        .possible_values(&["buy", "sell"])
+                        .help("Order side (buy or sell)"),
+                )
+                .arg(
+                    Arg::with_name("price")
+                        .long("price")
+                        .value_name("PRICE")
+                        .takes_value(true)
+                        .help("Limit price in quote lots per base lot"),
+                )
+                .arg(
+                    Arg::with_name("quantity")
+                        .long("quantity")
+                        .value_name("QUANTITY")
+                        .takes_value(true)
+                        .help("Order quantity in base lots"),
+                )
+                .arg(
+                    Arg::with_name("fix")
+                        .long("fix")
+                        .takes_value(false)
+                        .help("Send the instructions fixing the missing steps, paid by the fee payer"),
+                ),
+        )
         .subcommand(
             SubCommand::with_name("cancel-order")
                 .about("Cancel an order")
@@ -187,7 +378,7 @@ fn main() -> Result<(), Box<dyn Error>> {
                         .value_name("KEYPAIR")
                         .takes_value(true)
                         .validator(is_keypair)
-                        .help("Order owner keypair"),
+                        .help("Order owner keypair, or the keypair of the owner's delegate"),
                 )
                 .arg(
                     Arg::with_name("market")
@@ -221,11 +412,25 @@ fn main() -> Result<(), Box<dyn Error>> {
                         .validator(is_pubkey)
                         .default_value("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA")
                         .help("Token program ID"),
+                )
+                .arg(
+                    Arg::with_name("unwrap_sol")
+                        .long("unwrap-sol")
+                        .conflicts_with("token_account")
+                        .help("Refund to the owner's associated token account and unwrap all of its wrapped SOL once no other order rests"),
                 ),
         )
         .subcommand(
-            SubCommand::with_name("get-market")
-                .about("Get market information")
+            SubCommand::with_name("settle-funds")
+                .about("Settle a crossing taker bid against a maker ask as the market authority")
+                .arg(
+                    Arg::with_name("authority")
+                        .long("authority")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Market authority keypair"),
+                )
                 .arg(
                     Arg::with_name("market")
                         .long("market")
@@ -233,11 +438,43 @@ fn main() -> Result<(), Box<dyn Error>> {
                         .takes_value(true)
                         .validator(is_pubkey)
                         .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("taker_order")
+                        .long("taker-order")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Taker order account pubkey (a bid)"),
+                )
+                .arg(
+                    Arg::with_name("maker_order")
+                        .long("maker-order")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Maker order account pubkey (an ask)"),
                 ),
         )
         .subcommand(
-            SubCommand::with_name("get-order")
-                .about("Get order information")
+            SubCommand::with_name("admin-cancel-order")
+                .about("Force-cancel an order as the market authority, refunding its owner")
+                .arg(
+                    Arg::with_name("authority")
+                        .long("authority")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Market authority keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
                 .arg(
                     Arg::with_name("order")
                         .long("order")
@@ -247,148 +484,5085 @@ fn main() -> Result<(), Box<dyn Error>> {
                         .help("Order account pubkey"),
                 ),
         )
-        .get_matches();
-
-    // Get common parameters
-    let url = matches.value_of("url").unwrap();
-    let program_id = Pubkey::from_str(matches.value_of("program_id").unwrap())?;
-    let fee_payer = keypair_of(&matches, "fee_payer").unwrap_or_else(|| {
-        Keypair::new() // Use a new keypair if not provided
-    });
-
-    // Create DEX client
-    let client = DexClient::new(url, program_id);
-
-    // Process subcommands
-    match matches.subcommand() {
-        ("init-market", Some(sub_matches)) => {
-            let authority = keypair_of(sub_matches, "authority").expect("Authority keypair required");
-            let market = keypair_of(sub_matches, "market").expect("Market keypair required");
-            let base_mint = pubkey_of(sub_matches, "base_mint").expect("Base mint required");
-            let quote_mint = pubkey_of(sub_matches, "quote_mint").expect("Quote mint required");
-            let min_base_order_size = sub_matches
-                .value_of("min_base_order_size")
-                .expect("Minimum base order size required")
-                .parse::<u64>()?;
-            let tick_size = sub_matches
-                .value_of("tick_size")
-                .expect("Tick size required")
-                .parse::<u64>()?;
-            let fee_rate_bps = sub_matches
-                .value_of("fee_rate_bps")
-                .expect("Fee rate required")
-                .parse::<u16>()?;
-
-            let signature = client.initialize_market(
-                &fee_payer,
-                &authority,
-                &market,
-                &base_mint,
-                &quote_mint,
-                min_base_order_size,
-                tick_size,
-                fee_rate_bps,
-            )?;
-
-            println!("Market initialized successfully");
-            println!("Market ID: {}", market.pubkey());
-            println!("Transaction signature: {}", signature);
-        }
-        ("place-order", Some(sub_matches)) => {
-            let owner = keypair_of(sub_matches, "owner").expect("Owner keypair required");
-            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
-            let order = keypair_of(sub_matches, "order").expect("Order keypair required");
-            let token_account = pubkey_of(sub_matches, "token_account").expect("Token account required");
-            let token_program = pubkey_of(sub_matches, "token_program").unwrap();
-            
-            let side = sub_matches.value_of("side").expect("Side required");
-            let is_buy = match side {
-                "buy" => true,
-                "sell" => false,
-                _ => panic!("Invalid side"),
-            };
-            
-            let price = sub_matches
-                .value_of("price")
-                .expect("Price required")
-                .parse::<u64>()?;
-                
-            let quantity = sub_matches
-                .value_of("quantity")
-                .expect("Quantity required")
-                .parse::<u64>()?;
-                
-            let self_trade_behavior = match sub_matches.value_of("self_trade_behavior").unwrap() {
-                "decrement-take" => SelfTradeBehavior::DecrementTake,
-                "cancel-provide" => SelfTradeBehavior::CancelProvide,
-                "abort" => SelfTradeBehavior::AbortTransaction,
-                _ => panic!("Invalid self-trade behavior"),
-            };
-
-            let signature = client.place_limit_order(
-                &fee_payer,
-                &owner,
-                &market,
-                &order,
-                &token_account,
-                &token_program,
-                is_buy,
-                price,
-                quantity,
-                self_trade_behavior,
-            )?;
-
-            println!("Order placed successfully");
-            println!("Order ID: {}", order.pubkey());
-            println!("Transaction signature: {}", signature);
-        }
-        ("cancel-order", Some(sub_matches)) => {
-            let owner = keypair_of(sub_matches, "owner").expect("Owner keypair required");
-            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
-            let order = pubkey_of(sub_matches, "order").expect("Order pubkey required");
-            let token_account = pubkey_of(sub_matches, "token_account").expect("Token account required");
-            let token_program = pubkey_of(sub_matches, "token_program").unwrap();
-
-            let signature = client.cancel_order(
-                &fee_payer,
-                &owner,
-                &market,
-                &order,
-                &token_account,
-                &token_program,
-            )?;
-
-            println!("Order cancelled successfully");
-            println!("Transaction signature: {}", signature);
-        }
-        ("get-market", Some(sub_matches)) => {
-            let market_pubkey = pubkey_of(sub_matches, "market").expect("Market pubkey required");
-            let market = client.get_market(&market_pubkey)?;
-
-            println!("Market Information:");
-            println!("  Authority: {}", market.authority);
-            println!("  Base Mint: {}", market.base_mint);
-            println!("  Quote Mint: {}", market.quote_mint);
-            println!("  Min Base Order Size: {}", market.min_base_order_size);
-            println!("  Tick Size: {}", market.tick_size);
-            println!("  Fee Rate (bps): {}", market.fee_rate_bps);
-            println!("  Next Order ID: {}", market.next_order_id);
-            println!("  Number of Bids: {}", market.num_bids);
-            println!("  Number of Asks: {}", market.num_asks);
+        .subcommand(
+            SubCommand::with_name("link-orders")
+                .about("Link two orders so that cancelling one cancels the other")
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Order owner keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("order")
+                        .long("order")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("First order account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("linked_order")
+                        .long("linked-order")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Second order account pubkey"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("reduce-order")
+                .about("Reduce the remaining quantity of an order")
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Order owner keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("order")
+                        .long("order")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Order account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("token_account")
+                        .long("token-account")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Owner's token account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("token_program")
+                        .long("token-program")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .default_value("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA")
+                        .help("Token program ID"),
+                )
+                .arg(
+                    Arg::with_name("quantity")
+                        .long("quantity")
+                        .value_name("QUANTITY")
+                        .takes_value(true)
+                        .help("New remaining quantity in base lots"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("cancel-orders")
+                .about("Cancel several orders in one transaction")
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Order owner keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("orders")
+                        .long("orders")
+                        .value_name("PUBKEYS")
+                        .takes_value(true)
+                        .multiple(true)
+                        .use_delimiter(true)
+                        .validator(is_pubkey)
+                        .help("Comma-separated order account pubkeys"),
+                )
+                .arg(
+                    Arg::with_name("base_token_account")
+                        .long("base-token-account")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Owner's base token account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("quote_token_account")
+                        .long("quote-token-account")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Owner's quote token account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("token_program")
+                        .long("token-program")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .default_value("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA")
+                        .help("Token program ID"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("export-orders")
+                .about("Export an owner's resting orders on a market to CSV")
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Order owner pubkey"),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .long("output")
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .help("CSV file to write (defaults to standard output)"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("import-orders")
+                .about("Re-place orders exported to CSV on a market of the selected program")
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Order owner keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey to place the orders on"),
+                )
+                .arg(
+                    Arg::with_name("input")
+                        .long("input")
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .help("CSV file written by export-orders"),
+                )
+                .arg(
+                    Arg::with_name("self_trade_behavior")
+                        .long("self-trade-behavior")
+                        .value_name("BEHAVIOR")
+                        .takes_value(true)
+                        .possible_values(&["decrement-take", "cancel-provide", "abort"])
+                        .default_value("decrement-take")
+                        .help("Self-trade behavior"),
+                )
+                .arg(
+                    Arg::with_name("dry_run")
+                        .long("dry-run")
+                        .help("Only re-validate the orders against the market"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("decode-ix")
+                .about("Decode raw DEX instruction data and its accounts, e.g. to review a multisig proposal")
+                .arg(
+                    Arg::with_name("data")
+                        .value_name("DATA")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Instruction data in base58 or base64"),
+                )
+                .arg(
+                    Arg::with_name("accounts")
+                        .long("accounts")
+                        .value_name("PUBKEYS")
+                        .takes_value(true)
+                        .multiple(true)
+                        .use_delimiter(true)
+                        .validator(is_pubkey)
+                        .help("Comma-separated account pubkeys of the instruction, in order"),
+                )
+                .arg(
+                    Arg::with_name("encoding")
+                        .long("encoding")
+                        .value_name("ENCODING")
+                        .takes_value(true)
+                        .possible_values(&["base58", "base64"])
+                        .help("Encoding of the data (detected when omitted)"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("capital-report")
+                .about("Find an owner's funds locked in orders resting far from the mid and suggest cancellations")
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Order owner pubkey"),
+                )
+                .arg(
+                    Arg::with_name("min_distance_bps")
+                        .long("min-distance-bps")
+                        .value_name("BPS")
+                        .takes_value(true)
+                        .default_value("500")
+                        .help("Minimum distance from the mid in basis points for an order to count as idle"),
+                )
+                .arg(
+                    Arg::with_name("min_age")
+                        .long("min-age")
+                        .value_name("SECONDS")
+                        .takes_value(true)
+                        .default_value("86400")
+                        .help("Minimum time in seconds an order must have been resting to count as idle"),
+                )
+                .arg(
+                    Arg::with_name("cancel")
+                        .long("cancel")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Cancel the suggested orders, signing with this owner keypair"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("set-instruction-guard")
+                .about("Set the instruction guard of a market")
+                .arg(
+                    Arg::with_name("authority")
+                        .long("authority")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Market authority keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("guard")
+                        .long("guard")
+                        .value_name("GUARD")
+                        .takes_value(true)
+                        .possible_values(&["disabled", "top-level-only", "isolated"])
+                        .help("Instruction guard"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("set-market-status")
+                .about("Pause, resume or restrict trading on a market")
+                .arg(
+                    Arg::with_name("authority")
+                        .long("authority")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Market authority keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("status")
+                        .long("status")
+                        .value_name("STATUS")
+                        .takes_value(true)
+                        .possible_values(&["active", "cancel-only", "paused"])
+                        .help("Market status"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("set-whitelist")
+                .about("Enable or disable the whitelist of a permissioned market")
+                .arg(
+                    Arg::with_name("authority")
+                        .long("authority")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Market authority keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("disable")
+                        .long("disable")
+                        .help("Disable the whitelist instead of enabling it"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("set-oracle")
+                .about("Set or clear the oracle circuit breaker of a market")
+                .arg(
+                    Arg::with_name("authority")
+                        .long("authority")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Market authority keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("oracle")
+                        .long("oracle")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Pyth price account; omit to clear the oracle"),
+                )
+                .arg(
+                    Arg::with_name("band_bps")
+                        .long("band-bps")
+                        .value_name("BPS")
+                        .takes_value(true)
+                        .default_value("500")
+                        .help("Maximum distance of an order's price from the oracle price in basis points"),
+                )
+                .arg(
+                    Arg::with_name("max_staleness_slots")
+                        .long("max-staleness-slots")
+                        .value_name("SLOTS")
+                        .takes_value(true)
+                        .default_value("25")
+                        .help("Maximum age of the oracle price in slots"),
+                )
+                .arg(
+                    Arg::with_name("price_expo")
+                        .long("price-expo")
+                        .value_name("EXPONENT")
+                        .takes_value(true)
+                        .allow_hyphen_values(true)
+                        .default_value("0")
+                        .help("Exponent of one unit of the market's limit prices in the oracle's price units"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("check-oracle")
+                .about("Set a market to cancel-only if its oracle price is stale")
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("whitelist-add")
+                .about("Allow an owner to place orders on a permissioned market")
+                .arg(
+                    Arg::with_name("authority")
+                        .long("authority")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Market authority keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Order owner pubkey"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("whitelist-remove")
+                .about("Remove an owner from the whitelist of a market")
+                .arg(
+                    Arg::with_name("authority")
+                        .long("authority")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Market authority keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Order owner pubkey"),
+                )
+                .arg(
+                    Arg::with_name("recipient")
+                        .long("recipient")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Recipient of the reclaimed rent (defaults to the authority)"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("sign-quote")
+                .about("Sign a firm quote as a maker and print it for the taker")
+                .arg(
+                    Arg::with_name("maker")
+                        .long("maker")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Maker keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("side")
+                        .long("side")
+                        .value_name("SIDE")
+                        .takes_value(true)
+                        .possible_values(&["buy", "sell"])
+                        .help("Maker side (buy or sell)"),
+                )
+                .arg(
+                    Arg::with_name("price")
+                        .long("price")
+                        .value_name("PRICE")
+                        .takes_value(true)
+                        .help("Price in quote tokens"),
+                )
+                .arg(
+                    Arg::with_name("quantity")
+                        .long("quantity")
+                        .value_name("QUANTITY")
+                        .takes_value(true)
+                        .help("Maximum base quantity"),
+                )
+                .arg(
+                    Arg::with_name("expires_in")
+                        .long("expires-in")
+                        .value_name("SECONDS")
+                        .takes_value(true)
+                        .default_value("30")
+                        .help("Seconds until the quote expires"),
+                )
+                .arg(
+                    Arg::with_name("nonce")
+                        .long("nonce")
+                        .value_name("NONCE")
+                        .takes_value(true)
+                        .help("Nonce identifying the quote"),
+                )
+                .arg(
+                    Arg::with_name("taker")
+                        .long("taker")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Taker the quote is addressed to (defaults to any taker)"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("approve-rfq")
+                .about("Let a market's RFQ authority debit a maker token account for firm quotes")
+                .arg(
+                    Arg::with_name("maker")
+                        .long("maker")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Maker keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("token_account")
+                        .long("token-account")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Maker token account to approve"),
+                )
+                .arg(
+                    Arg::with_name("amount")
+                        .long("amount")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .help("Maximum amount the RFQ authority may debit"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("execute-quote")
+                .about("Trade against a signed firm quote as the taker")
+                .arg(
+                    Arg::with_name("taker")
+                        .long("taker")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Taker keypair"),
+                )
+                .arg(
+                    Arg::with_name("quote")
+                        .long("quote")
+                        .value_name("HEX")
+                        .takes_value(true)
+                        .help("Signed quote printed by sign-quote"),
+                )
+                .arg(
+                    Arg::with_name("quantity")
+                        .long("quantity")
+                        .value_name("QUANTITY")
+                        .takes_value(true)
+                        .help("Base quantity to trade"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("set-min-notional")
+                .about("Set the minimum notional of new orders")
+                .arg(
+                    Arg::with_name("authority")
+                        .long("authority")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Market authority keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("min_quote_notional")
+                        .long("min-quote-notional")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .help("Minimum order notional in quote tokens; 0 for none"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("init-twap-oracle")
+                .about("Create a market's TWAP oracle of settled fill prices")
+                .arg(
+                    Arg::with_name("authority")
+                        .long("authority")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Market authority keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("ema_period")
+                        .long("ema-period")
+                        .value_name("SECONDS")
+                        .takes_value(true)
+                        .default_value("600")
+                        .help("Time constant of the exponential moving average in seconds"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("get-twap")
+                .about("Get a market's time-weighted prices")
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("init-event-queue")
+                .about("Create a market's event queue of settled fills")
+                .arg(
+                    Arg::with_name("authority")
+                        .long("authority")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Market authority keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("consume-events")
+                .about("Print the oldest events of a market's event queue and remove them")
+                .arg(
+                    Arg::with_name("authority")
+                        .long("authority")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Market authority keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("limit")
+                        .long("limit")
+                        .value_name("COUNT")
+                        .takes_value(true)
+                        .default_value("16")
+                        .help("Maximum number of events to consume"),
+                )
+                .arg(
+                    Arg::with_name("max_staleness_slots")
+                        .long("max-staleness-slots")
+                        .value_name("SLOTS")
+                        .takes_value(true)
+                        .help("Fail if the transaction lands more than this many slots after it is built"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("rescue-tokens")
+                .about("Return tokens sent straight to a market escrow vault, above what the market owes from it")
+                .arg(
+                    Arg::with_name("authority")
+                        .long("authority")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Market authority keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("token_account")
+                        .long("token-account")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Token account to return the tokens to"),
+                )
+                .arg(
+                    Arg::with_name("side")
+                        .long("side")
+                        .value_name("SIDE")
+                        .takes_value(true)
+                        .possible_values(&["base", "quote"])
+                        .help("Which escrow vault to rescue from (base or quote)"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("initialize-pool")
+                .about("Create a market's constant-product pool")
+                .arg(
+                    Arg::with_name("authority")
+                        .long("authority")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Market authority keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("fee_bps")
+                        .long("fee-bps")
+                        .value_name("BPS")
+                        .takes_value(true)
+                        .help("Swap fee in basis points of the input amount"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("add-liquidity")
+                .about("Deposit base and quote tokens into a market's pool for LP tokens")
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Owner keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("max_base_amount")
+                        .long("max-base-amount")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .help("Maximum base tokens to deposit"),
+                )
+                .arg(
+                    Arg::with_name("max_quote_amount")
+                        .long("max-quote-amount")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .help("Maximum quote tokens to deposit"),
+                )
+                .arg(
+                    Arg::with_name("min_lp_amount")
+                        .long("min-lp-amount")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .default_value("0")
+                        .help("Minimum LP tokens to receive"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("remove-liquidity")
+                .about("Burn LP tokens for their share of a market's pool")
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Owner keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("lp_amount")
+                        .long("lp-amount")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .help("LP tokens to burn"),
+                )
+                .arg(
+                    Arg::with_name("min_base_amount")
+                        .long("min-base-amount")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .default_value("0")
+                        .help("Minimum base tokens to receive"),
+                )
+                .arg(
+                    Arg::with_name("min_quote_amount")
+                        .long("min-quote-amount")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .default_value("0")
+                        .help("Minimum quote tokens to receive"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("swap")
+                .about("Swap tokens against a market's pool")
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Owner keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("side")
+                        .long("side")
+                        .value_name("SIDE")
+                        .takes_value(true)
+                        .possible_values(&["buy", "sell"])
+                        .help("Buy base tokens with quote tokens or sell base tokens for quote tokens"),
+                )
+                .arg(
+                    Arg::with_name("amount_in")
+                        .long("amount-in")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .help("Tokens to pay into the pool"),
+                )
+                .arg(
+                    Arg::with_name("min_amount_out")
+                        .long("min-amount-out")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .default_value("0")
+                        .help("Minimum tokens to receive"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("route-swap")
+                .about("Swap tokens through a market's order book and pool, whichever is better")
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Owner keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("side")
+                        .long("side")
+                        .value_name("SIDE")
+                        .takes_value(true)
+                        .possible_values(&["buy", "sell"])
+                        .help("Buy base tokens with quote tokens or sell base tokens for quote tokens"),
+                )
+                .arg(
+                    Arg::with_name("amount_in")
+                        .long("amount-in")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .help("Most tokens to pay in across the book and the pool"),
+                )
+                .arg(
+                    Arg::with_name("min_amount_out")
+                        .long("min-amount-out")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .default_value("0")
+                        .help("Minimum tokens to receive"),
+                )
+                .arg(
+                    Arg::with_name("max_orders")
+                        .long("max-orders")
+                        .value_name("COUNT")
+                        .takes_value(true)
+                        .default_value("4")
+                        .help("Most resting orders to route through"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("initialize-range-pool")
+                .about("Create a market's concentrated-liquidity range pool")
+                .arg(
+                    Arg::with_name("authority")
+                        .long("authority")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Market authority keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("fee_bps")
+                        .long("fee-bps")
+                        .value_name("BPS")
+                        .takes_value(true)
+                        .help("Swap fee in basis points of the input amount"),
+                )
+                .arg(
+                    Arg::with_name("tick_spacing")
+                        .long("tick-spacing")
+                        .value_name("TICKS")
+                        .takes_value(true)
+                        .help("Spacing of the ticks positions start and end at"),
+                )
+                .arg(
+                    Arg::with_name("tick")
+                        .long("tick")
+                        .value_name("TICK")
+                        .takes_value(true)
+                        .allow_hyphen_values(true)
+                        .help("Tick of the initial price"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("add-range-liquidity")
+                .about("Add liquidity between two ticks to a range position")
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Owner keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("tick_lower")
+                        .long("tick-lower")
+                        .value_name("TICK")
+                        .takes_value(true)
+                        .allow_hyphen_values(true)
+                        .help("Lowest tick of the range"),
+                )
+                .arg(
+                    Arg::with_name("tick_upper")
+                        .long("tick-upper")
+                        .value_name("TICK")
+                        .takes_value(true)
+                        .allow_hyphen_values(true)
+                        .help("Tick the range ends below"),
+                )
+                .arg(
+                    Arg::with_name("liquidity")
+                        .long("liquidity")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .help("Liquidity to add"),
+                )
+                .arg(
+                    Arg::with_name("max_base_amount")
+                        .long("max-base-amount")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .help("Maximum base tokens to deposit"),
+                )
+                .arg(
+                    Arg::with_name("max_quote_amount")
+                        .long("max-quote-amount")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .help("Maximum quote tokens to deposit"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("remove-range-liquidity")
+                .about("Remove liquidity from a range position and collect its fees")
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Owner keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("tick_lower")
+                        .long("tick-lower")
+                        .value_name("TICK")
+                        .takes_value(true)
+                        .allow_hyphen_values(true)
+                        .help("Lowest tick of the range"),
+                )
+                .arg(
+                    Arg::with_name("tick_upper")
+                        .long("tick-upper")
+                        .value_name("TICK")
+                        .takes_value(true)
+                        .allow_hyphen_values(true)
+                        .help("Tick the range ends below"),
+                )
+                .arg(
+                    Arg::with_name("liquidity")
+                        .long("liquidity")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .help("Liquidity to remove, zero to only collect fees"),
+                )
+                .arg(
+                    Arg::with_name("min_base_amount")
+                        .long("min-base-amount")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .default_value("0")
+                        .help("Minimum base tokens to receive for the liquidity"),
+                )
+                .arg(
+                    Arg::with_name("min_quote_amount")
+                        .long("min-quote-amount")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .default_value("0")
+                        .help("Minimum quote tokens to receive for the liquidity"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("range-swap")
+                .about("Swap tokens against a market's range pool")
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Owner keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("side")
+                        .long("side")
+                        .value_name("SIDE")
+                        .takes_value(true)
+                        .possible_values(&["buy", "sell"])
+                        .help("Buy base tokens with quote tokens or sell base tokens for quote tokens"),
+                )
+                .arg(
+                    Arg::with_name("amount_in")
+                        .long("amount-in")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .help("Most tokens to pay into the range pool"),
+                )
+                .arg(
+                    Arg::with_name("min_amount_out")
+                        .long("min-amount-out")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .default_value("0")
+                        .help("Minimum tokens to receive"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("init-lending-pool")
+                .about("Create the lending pool of a mint")
+                .arg(
+                    Arg::with_name("admin")
+                        .long("admin")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Config admin keypair"),
+                )
+                .arg(
+                    Arg::with_name("mint")
+                        .long("mint")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Mint of the lending pool"),
+                )
+                .arg(
+                    Arg::with_name("base_rate_bps")
+                        .long("base-rate-bps")
+                        .value_name("BPS")
+                        .takes_value(true)
+                        .help("Annual borrow rate at zero utilization"),
+                )
+                .arg(
+                    Arg::with_name("optimal_utilization_bps")
+                        .long("optimal-utilization-bps")
+                        .value_name("BPS")
+                        .takes_value(true)
+                        .help("Utilization where the steeper slope starts"),
+                )
+                .arg(
+                    Arg::with_name("slope1_bps")
+                        .long("slope1-bps")
+                        .value_name("BPS")
+                        .takes_value(true)
+                        .help("Annual rate added from zero to optimal utilization"),
+                )
+                .arg(
+                    Arg::with_name("slope2_bps")
+                        .long("slope2-bps")
+                        .value_name("BPS")
+                        .takes_value(true)
+                        .help("Annual rate added from optimal to full utilization"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("supply")
+                .about("Supply tokens to a lending pool for share tokens")
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Owner keypair"),
+                )
+                .arg(
+                    Arg::with_name("mint")
+                        .long("mint")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Mint of the lending pool"),
+                )
+                .arg(
+                    Arg::with_name("amount")
+                        .long("amount")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .help("Tokens to supply"),
+                )
+                .arg(
+                    Arg::with_name("min_shares")
+                        .long("min-shares")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .default_value("0")
+                        .help("Minimum share tokens to receive"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("redeem")
+                .about("Burn lending pool share tokens for their tokens")
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Owner keypair"),
+                )
+                .arg(
+                    Arg::with_name("mint")
+                        .long("mint")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Mint of the lending pool"),
+                )
+                .arg(
+                    Arg::with_name("shares")
+                        .long("shares")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .help("Share tokens to burn"),
+                )
+                .arg(
+                    Arg::with_name("min_amount")
+                        .long("min-amount")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .default_value("0")
+                        .help("Minimum tokens to receive"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("get-lending-pool")
+                .about("Show the lending pool of a mint with its utilization and rates")
+                .arg(
+                    Arg::with_name("mint")
+                        .long("mint")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Mint of the lending pool"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("init-perp-market")
+                .about("Create the perp market of a market with an oracle")
+                .arg(
+                    Arg::with_name("authority")
+                        .long("authority")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Market authority keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("initial_margin_bps")
+                        .long("initial-margin-bps")
+                        .value_name("BPS")
+                        .takes_value(true)
+                        .help("Equity positions must keep against their notional, in basis points"),
+                )
+                .arg(
+                    Arg::with_name("max_funding_rate_bps")
+                        .long("max-funding-rate-bps")
+                        .value_name("BPS")
+                        .takes_value(true)
+                        .help("Largest funding payment per hour against the index price, in basis points"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("deposit-perp-collateral")
+                .about("Deposit quote tokens as collateral of your perp position")
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Owner keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("amount")
+                        .long("amount")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .help("Quote tokens to deposit"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("withdraw-perp-collateral")
+                .about("Withdraw quote tokens from your perp position")
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Owner keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("amount")
+                        .long("amount")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .help("Quote tokens to withdraw"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("place-perp-order")
+                .about("Place an order on a market's perp market for your position")
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Owner keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("order")
+                        .long("order")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Perp order account keypair"),
+                )
+                .arg(
+                    Arg::with_name("side")
+                        .long("side")
+                        .value_name("SIDE")
+                        .takes_value(true)
+                        .possible_values(&["buy", "sell"])
+                        .help("Order side (buy or sell)"),
+                )
+                .arg(
+                    Arg::with_name("price")
+                        .long("price")
+                        .value_name("PRICE")
+                        .takes_value(true)
+                        .help("Limit price in quote lots per base lot"),
+                )
+                .arg(
+                    Arg::with_name("quantity")
+                        .long("quantity")
+                        .value_name("LOTS")
+                        .takes_value(true)
+                        .help("Order quantity in base lots"),
+                )
+                .arg(
+                    Arg::with_name("reduce_only")
+                        .long("reduce-only")
+                        .help("Only fill as far as the order shrinks your position"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("publish-prices")
+                .about("Publish a market's best bid and offer and TWAP to other chains through Wormhole")
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("nonce")
+                        .long("nonce")
+                        .value_name("NONCE")
+                        .takes_value(true)
+                        .help("Nonce of the message; defaults to the current unix time"),
+                )
+                .arg(
+                    Arg::with_name("finalized")
+                        .long("finalized")
+                        .help("Have guardians wait for finality rather than confirmation"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("get-price-message")
+                .about("Get the last price message a market posted through Wormhole")
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("cancel-perp-order")
+                .about("Cancel one of your perp orders")
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Owner keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("order")
+                        .long("order")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Perp order account pubkey"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("settle-perp-fill")
+                .about("Fill a taker perp order against a maker perp order as the market authority")
+                .arg(
+                    Arg::with_name("authority")
+                        .long("authority")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Market authority keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("taker_order")
+                        .long("taker-order")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Taker perp order account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("maker_order")
+                        .long("maker-order")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Maker perp order account pubkey"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("update-funding")
+                .about("Pay a market's perp funding for the hours elapsed since it was last paid")
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("set-perp-reduce-only")
+                .about("Only allow fills of one of your perp orders that shrink your position")
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Owner keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("order")
+                        .long("order")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Perp order account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("disable")
+                        .long("disable")
+                        .help("Allow fills of the order that grow the position again"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("init-batch-auction")
+                .about("Make a market cross its orders in batch auctions")
+                .arg(
+                    Arg::with_name("authority")
+                        .long("authority")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Market authority keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("interval_slots")
+                        .long("interval-slots")
+                        .value_name("SLOTS")
+                        .takes_value(true)
+                        .help("Slots orders accumulate for before each auction"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("run-auction")
+                .about("Cross a batch auction market's resting orders at one price")
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("get-batch-auction")
+                .about("Show a market's batch auction")
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("init-token-sale")
+                .about("Sell base tokens for a fixed duration before a paused market opens")
+                .arg(
+                    Arg::with_name("authority")
+                        .long("authority")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Market authority keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("base_amount")
+                        .long("base-amount")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .help("Base tokens for sale"),
+                )
+                .arg(
+                    Arg::with_name("min_price")
+                        .long("min-price")
+                        .value_name("PRICE")
+                        .takes_value(true)
+                        .help("Lowest clearing price the sale succeeds at, in quote lots per base lot"),
+                )
+                .arg(
+                    Arg::with_name("max_contribution")
+                        .long("max-contribution")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .help("Most quote tokens one contributor may pay in, 0 for no cap"),
+                )
+                .arg(
+                    Arg::with_name("duration_seconds")
+                        .long("duration-seconds")
+                        .value_name("SECONDS")
+                        .takes_value(true)
+                        .help("Seconds contributions are accepted for"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("contribute")
+                .about("Contribute quote tokens to a market's token sale")
+                .arg(
+                    Arg::with_name("contributor")
+                        .long("contributor")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Contributor keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("amount")
+                        .long("amount")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .help("Quote tokens to contribute"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("finalize-sale")
+                .about("Settle an ended token sale and open its market on success")
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("claim-sale")
+                .about("Claim a contribution's base tokens, or its refund from a failed sale")
+                .arg(
+                    Arg::with_name("contributor")
+                        .long("contributor")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Contributor keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("get-token-sale")
+                .about("Show a market's token sale and, optionally, a contribution to it")
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Contributor pubkey"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("init-stake-pool")
+                .about("Create the stake pool sharing swept fees and granting taker fee discounts")
+                .arg(
+                    Arg::with_name("admin")
+                        .long("admin")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Program config admin keypair"),
+                )
+                .arg(
+                    Arg::with_name("governance_mint")
+                        .long("governance-mint")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Mint of the staked governance token"),
+                )
+                .arg(
+                    Arg::with_name("reward_mint")
+                        .long("reward-mint")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Mint of the fees stakers are rewarded in"),
+                )
+                .arg(
+                    Arg::with_name("epoch_seconds")
+                        .long("epoch-seconds")
+                        .value_name("SECONDS")
+                        .takes_value(true)
+                        .help("Shortest time between reward distributions"),
+                )
+                .arg(
+                    Arg::with_name("fee_share_bps")
+                        .long("fee-share-bps")
+                        .value_name("BPS")
+                        .takes_value(true)
+                        .help("Share of swept fees paid to stakers"),
+                )
+                .arg(
+                    Arg::with_name("fee_tiers")
+                        .long("fee-tiers")
+                        .value_name("TIERS")
+                        .takes_value(true)
+                        .help("Comma-separated MIN_STAKE:DISCOUNT_BPS taker fee tiers, at most 4"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("stake")
+                .about("Stake governance tokens")
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Owner keypair"),
+                )
+                .arg(
+                    Arg::with_name("amount")
+                        .long("amount")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .help("Governance tokens to stake"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("unstake")
+                .about("Unstake governance tokens once their epoch is distributed")
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Owner keypair"),
+                )
+                .arg(
+                    Arg::with_name("amount")
+                        .long("amount")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .help("Governance tokens to unstake"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("distribute-staking-rewards")
+                .about("Close the stake pool's epoch, sharing its rewards among the stakers"),
+        )
+        .subcommand(
+            SubCommand::with_name("claim-staking-rewards")
+                .about("Claim staking rewards")
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Owner keypair"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("get-stake")
+                .about("Show the stake pool and, optionally, an owner's stake")
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Owner pubkey"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("set-loyalty-schedule")
+                .about("Set the loyalty points a market's fills earn over time")
+                .arg(
+                    Arg::with_name("authority")
+                        .long("authority")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Market authority keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("phases")
+                        .long("phases")
+                        .value_name("PHASES")
+                        .takes_value(true)
+                        .help("Comma-separated START_TIMESTAMP:TAKER_BPS:MAKER_BPS emission phases, at most 4"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("open-loyalty-points")
+                .about("Open an owner's loyalty points account so its fills on a market earn points")
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Owner keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("get-loyalty-points")
+                .about("Show a market's loyalty schedule and, optionally, an owner's points")
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Owner pubkey"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("get-wrapped-assets")
+                .about("Show which of a market's mints are Wormhole-wrapped and their origin")
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("token_bridge")
+                        .long("token-bridge")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Wormhole token bridge program (defaults to mainnet)"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("get-perp-position")
+                .about("Show a perp market and an owner's position on it")
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Position owner pubkey"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("migrate-account")
+                .about("Migrate a market or order account to the current layout")
+                .arg(
+                    Arg::with_name("account")
+                        .long("account")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market or order account pubkey"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("delegate")
+                .about("Let a secondary key place and cancel your orders on a market until a slot")
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Owner keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("delegate")
+                        .long("delegate")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Key allowed to trade for the owner"),
+                )
+                .arg(
+                    Arg::with_name("expiry_slot")
+                        .long("expiry-slot")
+                        .value_name("SLOT")
+                        .takes_value(true)
+                        .help("First slot at which the delegation no longer applies; a past slot revokes it"),
+                )
+                .arg(
+                    Arg::with_name("base_token_account")
+                        .long("base-token-account")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .requires("base_allowance")
+                        .help("Base token account funding the delegate's sells"),
+                )
+                .arg(
+                    Arg::with_name("base_allowance")
+                        .long("base-allowance")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .requires("base_token_account")
+                        .help("Base tokens the delegate may lock in orders"),
+                )
+                .arg(
+                    Arg::with_name("quote_token_account")
+                        .long("quote-token-account")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .requires("quote_allowance")
+                        .help("Quote token account funding the delegate's buys"),
+                )
+                .arg(
+                    Arg::with_name("quote_allowance")
+                        .long("quote-allowance")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .requires("quote_token_account")
+                        .help("Quote tokens the delegate may lock in orders"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("deposit")
+                .about("Deposit tokens into your free balance on a market for placing orders")
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Owner keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("token_account")
+                        .long("token-account")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Token account to deposit from"),
+                )
+                .arg(
+                    Arg::with_name("side")
+                        .long("side")
+                        .value_name("SIDE")
+                        .takes_value(true)
+                        .possible_values(&["base", "quote"])
+                        .help("Which free balance to move (base or quote)"),
+                )
+                .arg(
+                    Arg::with_name("amount")
+                        .long("amount")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .help("Amount of tokens to move"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("withdraw")
+                .about("Withdraw tokens from your free balance on a market")
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Owner keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("token_account")
+                        .long("token-account")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Token account to withdraw to"),
+                )
+                .arg(
+                    Arg::with_name("side")
+                        .long("side")
+                        .value_name("SIDE")
+                        .takes_value(true)
+                        .possible_values(&["base", "quote"])
+                        .help("Which free balance to move (base or quote)"),
+                )
+                .arg(
+                    Arg::with_name("amount")
+                        .long("amount")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .help("Amount of tokens to move"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("wrap-sol")
+                .about("Wrap native SOL into your wrapped SOL account, creating it if needed")
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Owner keypair"),
+                )
+                .arg(
+                    Arg::with_name("amount")
+                        .long("amount")
+                        .value_name("LAMPORTS")
+                        .takes_value(true)
+                        .help("Lamports to wrap"),
+                )
+                .arg(
+                    Arg::with_name("token_program")
+                        .long("token-program")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .default_value("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA")
+                        .help("Token program ID"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("unwrap-sol")
+                .about("Unwrap all of your wrapped SOL, closing your wrapped SOL account")
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Owner keypair"),
+                )
+                .arg(
+                    Arg::with_name("token_program")
+                        .long("token-program")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .default_value("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA")
+                        .help("Token program ID"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("get-deposits")
+                .about("Get an owner's free balances on a market")
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Owner pubkey"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("set-referral-share")
+                .about("Set the share of taker fees paid to referrers")
+                .arg(
+                    Arg::with_name("authority")
+                        .long("authority")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Market authority keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("share_bps")
+                        .long("share-bps")
+                        .value_name("BPS")
+                        .takes_value(true)
+                        .help("Referral share in basis points of the fee"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("set-fee-split")
+                .about("Set the shares of swept fees paid to the protocol treasury and the market creator")
+                .arg(
+                    Arg::with_name("admin")
+                        .long("admin")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Program config admin keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("treasury_share_bps")
+                        .long("treasury-share-bps")
+                        .value_name("BPS")
+                        .takes_value(true)
+                        .help("Treasury share in basis points of the swept fees"),
+                )
+                .arg(
+                    Arg::with_name("creator_share_bps")
+                        .long("creator-share-bps")
+                        .value_name("BPS")
+                        .takes_value(true)
+                        .help("Market creator share in basis points of the swept fees"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("set-dynamic-fees")
+                .about("Scale the taker fee by the realized volatility of fills, or turn it off with --window 0")
+                .arg(
+                    Arg::with_name("authority")
+                        .long("authority")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Market authority keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("min_fee_bps")
+                        .long("min-fee-bps")
+                        .value_name("BPS")
+                        .takes_value(true)
+                        .default_value("0")
+                        .help("Taker fee of a calm market in basis points, at most the taker fee"),
+                )
+                .arg(
+                    Arg::with_name("volatility_bps")
+                        .long("volatility-bps")
+                        .value_name("BPS")
+                        .takes_value(true)
+                        .default_value("0")
+                        .help("Realized volatility per fill in basis points at which the full taker fee applies"),
+                )
+                .arg(
+                    Arg::with_name("window")
+                        .long("window")
+                        .value_name("FILLS")
+                        .takes_value(true)
+                        .help("Number of fills the volatility estimate averages over, 0 to turn dynamic fees off"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("init-fee-vault")
+                .about("Create the fee vault of a market")
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("sweep-fees")
+                .about("Sweep the fee vault of a market")
+                .arg(
+                    Arg::with_name("fee_authority")
+                        .long("fee-authority")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Market fee authority keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("destination")
+                        .long("destination")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Quote token account receiving the fees (defaults to the fee authority's associated account)"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("init-insurance-fund")
+                .about("Create the insurance fund of a market")
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("deposit-insurance-fund")
+                .about("Deposit quote tokens into the insurance fund of a market")
+                .arg(
+                    Arg::with_name("depositor")
+                        .long("depositor")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Depositor keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("source")
+                        .long("source")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Quote token account to deposit from (defaults to the depositor's associated account)"),
+                )
+                .arg(
+                    Arg::with_name("amount")
+                        .long("amount")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .help("Amount in quote tokens"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("withdraw-insurance-fund")
+                .about("Draw quote tokens from the insurance fund of a market to cover a shortfall")
+                .arg(
+                    Arg::with_name("authority")
+                        .long("authority")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Market authority keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("destination")
+                        .long("destination")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Quote token account that is owed the shortfall"),
+                )
+                .arg(
+                    Arg::with_name("amount")
+                        .long("amount")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .help("Amount in quote tokens"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("set-fee-authority")
+                .about("Hand the right to sweep a market's fees to a new fee authority")
+                .arg(
+                    Arg::with_name("fee_authority")
+                        .long("fee-authority")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Current market fee authority keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("new_fee_authority")
+                        .long("new-fee-authority")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("New fee authority pubkey"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("close-market")
+                .about("Close an empty market and reclaim its rent")
+                .arg(
+                    Arg::with_name("authority")
+                        .long("authority")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Market authority keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("recipient")
+                        .long("recipient")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Recipient of the reclaimed lamports (defaults to the authority)"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("init-config")
+                .about("Initialize the program config")
+                .arg(
+                    Arg::with_name("admin")
+                        .long("admin")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Config admin keypair"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("update-config")
+                .about("Update the program config")
+                .arg(
+                    Arg::with_name("admin")
+                        .long("admin")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Config admin keypair"),
+                )
+                .arg(
+                    Arg::with_name("market_creation")
+                        .long("market-creation")
+                        .value_name("MODE")
+                        .takes_value(true)
+                        .possible_values(&["permissionless", "admin-only"])
+                        .default_value("permissionless")
+                        .help("Who may create markets"),
+                )
+                .arg(
+                    Arg::with_name("allowed_quote_mints")
+                        .long("allowed-quote-mints")
+                        .value_name("PUBKEYS")
+                        .takes_value(true)
+                        .multiple(true)
+                        .use_delimiter(true)
+                        .validator(is_pubkey)
+                        .help("Comma-separated whitelisted quote mints (any mint if omitted)"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("set-creation-fee")
+                .about("Set the market creation fee")
+                .arg(
+                    Arg::with_name("admin")
+                        .long("admin")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Config admin keypair"),
+                )
+                .arg(
+                    Arg::with_name("treasury")
+                        .long("treasury")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Protocol treasury receiving the fee"),
+                )
+                .arg(
+                    Arg::with_name("fee_lamports")
+                        .long("fee-lamports")
+                        .value_name("LAMPORTS")
+                        .takes_value(true)
+                        .default_value("0")
+                        .help("Fee in lamports"),
+                )
+                .arg(
+                    Arg::with_name("fee_quote")
+                        .long("fee-quote")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .default_value("0")
+                        .help("Fee in the new market's quote token"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("set-trade-busting")
+                .about("Enable or disable trade busting")
+                .arg(
+                    Arg::with_name("admin")
+                        .long("admin")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Config admin keypair"),
+                )
+                .arg(
+                    Arg::with_name("disable")
+                        .long("disable")
+                        .help("Disable trade busting instead of enabling it"),
+                )
+                .arg(
+                    Arg::with_name("window_slots")
+                        .long("window-slots")
+                        .value_name("SLOTS")
+                        .takes_value(true)
+                        .default_value("0")
+                        .help("Number of slots after a settlement during which it may be busted"),
+                ),
+        )
+        .subcommand(SubCommand::with_name("get-config").about("Get program config information"))
+        .subcommand(SubCommand::with_name("estimate-market-cost").about("Estimate the cost of creating a market"))
+        .subcommand(
+            SubCommand::with_name("get-market")
+                .about("Get market information")
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("list-markets")
+                .about("List every market recorded in the market registry"),
+        )
+        .subcommand(
+            SubCommand::with_name("watch-fills")
+                .about("Stream a market's fills, skipping fills already recorded in a cursor file")
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .required(true)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("cursor_file")
+                        .long("cursor-file")
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .required(true)
+                        .help("File holding the last fill sequence number processed per market"),
+                )
+                .arg(
+                    Arg::with_name("ws_url")
+                        .long("ws-url")
+                        .value_name("URL")
+                        .takes_value(true)
+                        .validator(is_url)
+                        .help("WebSocket URL of the cluster (derived from the RPC URL by default)"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("commit-orders-root")
+                .about("Commit a Merkle root of a market's open orders")
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("prove-order")
+                .about("Build and check an inclusion proof of an open order")
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                )
+                .arg(
+                    Arg::with_name("order")
+                        .long("order")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Order account pubkey"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("create-lookup-table")
+                .about("Create an address lookup table of the addresses instructions on a market pass")
+                .arg(
+                    Arg::with_name("authority")
+                        .long("authority")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Lookup table authority keypair"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("get-lookup-table")
+                .about("Get the addresses of an address lookup table")
+                .arg(
+                    Arg::with_name("lookup_table")
+                        .long("lookup-table")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Address lookup table pubkey"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("get-order-book")
+                .about("Get the order book of a market")
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Market account pubkey"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("get-aggregated-orderbook")
+                .about("Get the merged order book of several markets of the same pair")
+                .arg(
+                    Arg::with_name("markets")
+                        .long("markets")
+                        .value_name("PUBKEYS")
+                        .takes_value(true)
+                        .multiple(true)
+                        .use_delimiter(true)
+                        .validator(is_pubkey)
+                        .help("Comma-separated market account pubkeys"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("route-order")
+                .about("Split a taker order across several markets of the same pair")
+                .arg(
+                    Arg::with_name("markets")
+                        .long("markets")
+                        .value_name("PUBKEYS")
+                        .takes_value(true)
+                        .multiple(true)
+                        .use_delimiter(true)
+                        .validator(is_pubkey)
+                        .help("Comma-separated market account pubkeys"),
+                )
+                .arg(
+                    Arg::with_name("side")
+                        .long("side")
+                        .value_name("SIDE")
+                        .takes_value(true)
+                        .possible_values(&["buy", "sell"])
+                        .help("Order side (buy or sell)"),
+                )
+                .arg(
+                    Arg::with_name("quantity")
+                        .long("quantity")
+                        .value_name("QUANTITY")
+                        .takes_value(true)
+                        .required_unless("quote_amount")
+                        .help("Order quantity in base lots"),
+                )
+                .arg(
+                    Arg::with_name("quote_amount")
+                        .long("quote-amount")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .conflicts_with("quantity")
+                        .help("Size the order to pay (buy) or receive (sell) this many quote tokens"),
+                )
+                .arg(
+                    Arg::with_name("fees")
+                        .long("fees")
+                        .value_name("CONVENTION")
+                        .takes_value(true)
+                        .possible_values(&["exclusive", "inclusive"])
+                        .default_value("exclusive")
+                        .help("Whether prices and amounts include taker fees"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("get-owner-orders")
+                .about("List a wallet's open orders")
+                .arg(
+                    Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Order owner pubkey"),
+                )
+                .arg(
+                    Arg::with_name("market")
+                        .long("market")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Only list orders on this market"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("get-order")
+                .about("Get order information")
+                .arg(
+                    Arg::with_name("order")
+                        .long("order")
+                        .value_name("PUBKEY")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("Order account pubkey"),
+                ),
+        )
+        .get_matches();
+
+    // Report failures in the selected language
+    let language = Language::from_tag(matches.value_of("language").unwrap()).unwrap_or(Language::En);
+    if let Err(err) = run(&matches) {
+        eprintln!("Error: {}", describe_error(err.as_ref(), language));
+        std::process::exit(1);
+    }
+}
+
+fn run(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    // Get common parameters
+    let url = matches.value_of("url").unwrap();
+    let program_id = Pubkey::from_str(matches.value_of("program_id").unwrap())?;
+    let fee_payer = keypair_of(matches, "fee_payer").unwrap_or_else(|| {
+        Keypair::new() // Use a new keypair if not provided
+    });
+    let locale_tag = matches.value_of("locale").unwrap();
+    let locale = Locale::from_tag(locale_tag).ok_or_else(|| format!("Unknown locale: {}", locale_tag))?;
+
+    let mut transaction_options = TransactionOptions::default();
+    if let Some(memo) = matches.value_of("memo") {
+        transaction_options = transaction_options.with_memo(memo);
+    }
+    if let Some(priority_fee) = matches.value_of("priority_fee") {
+        transaction_options = transaction_options.with_compute_unit_price(priority_fee.parse::<u64>()?);
+    }
+    if let Some(cu_limit) = matches.value_of("cu_limit") {
+        transaction_options = transaction_options.with_compute_unit_limit(cu_limit.parse::<u32>()?);
+    }
+
+    // Create DEX client
+    let client = DexClient::new(url, program_id).with_transaction_options(transaction_options);
+
+    // Process subcommands
+    match matches.subcommand() {
+        ("init-market", Some(sub_matches)) => {
+            let authority = keypair_of(sub_matches, "authority").expect("Authority keypair required");
+            let base_mint = pubkey_of(sub_matches, "base_mint").expect("Base mint required");
+            let quote_mint = pubkey_of(sub_matches, "quote_mint").expect("Quote mint required");
+            let min_base_order_size = sub_matches
+                .value_of("min_base_order_size")
+                .expect("Minimum base order size required")
+                .parse::<u64>()?;
+            let tick_size = sub_matches
+                .value_of("tick_size")
+                .expect("Tick size required")
+                .parse::<u64>()?;
+            let taker_fee_bps = sub_matches
+                .value_of("taker_fee_bps")
+                .expect("Taker fee rate required")
+                .parse::<u16>()?;
+            let maker_fee_bps = sub_matches
+                .value_of("maker_fee_bps")
+                .unwrap()
+                .parse::<i16>()?;
+
+            let trustless = sub_matches.is_present("trustless");
+            let base_lot_size = sub_matches.value_of("base_lot_size").unwrap().parse::<u64>()?;
+            let quote_lot_size = sub_matches.value_of("quote_lot_size").unwrap().parse::<u64>()?;
+
+            let (market, signature) = match sub_matches.value_of("seed") {
+                Some(seed) => client.initialize_market_with_seed(
+                    &fee_payer,
+                    &authority,
+                    &base_mint,
+                    &quote_mint,
+                    seed.parse::<u64>()?,
+                    min_base_order_size,
+                    tick_size,
+                    taker_fee_bps,
+                    maker_fee_bps,
+                    trustless,
+                    base_lot_size,
+                    quote_lot_size,
+                )?,
+                None => {
+                    let market = keypair_of(sub_matches, "market").expect("Market keypair required");
+                    let signature = client.initialize_market(
+                        &fee_payer,
+                        &authority,
+                        &market,
+                        &base_mint,
+                        &quote_mint,
+                        min_base_order_size,
+                        tick_size,
+                        taker_fee_bps,
+                        maker_fee_bps,
+                        trustless,
+                        base_lot_size,
+                        quote_lot_size,
+                    )?;
+                    (market.pubkey(), signature)
+                }
+            };
+
+            println!("Market initialized successfully");
+            println!("Market ID: {}", market);
+            println!("Transaction signature: {}", signature);
+        }
+        ("place-order", Some(sub_matches)) => {
+            let owner = keypair_of(sub_matches, "owner").expect("Owner keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let order = keypair_of(sub_matches, "order").expect("Order keypair required");
+            let delegated_by = pubkey_of(sub_matches, "delegated_by");
+
+            // Orders funded from deposits debit the order owner's OpenOrders account instead, and
+            // native SOL orders the owner's associated token accounts
+            let token_account = if sub_matches.is_present("from_deposits") {
+                client.get_open_orders_address(&market, &delegated_by.unwrap_or_else(|| owner.pubkey()))
+            } else if sub_matches.is_present("native_sol") {
+                Pubkey::default()
+            } else {
+                pubkey_of(sub_matches, "token_account").expect("Token account required")
+            };
+            let token_program = pubkey_of(sub_matches, "token_program").unwrap();
+            
+            let side = sub_matches.value_of("side").expect("Side required");
+            let is_buy = match side {
+                "buy" => true,
+                "sell" => false,
+                _ => panic!("Invalid side"),
+            };
+            
+            let price = sub_matches
+                .value_of("price")
+                .expect("Price required")
+                .parse::<u64>()?;
+                
+            let quantity = sub_matches
+                .value_of("quantity")
+                .expect("Quantity required")
+                .parse::<u64>()?;
+                
+            let self_trade_behavior = match sub_matches.value_of("self_trade_behavior").unwrap() {
+                "decrement-take" => SelfTradeBehavior::DecrementTake,
+                "cancel-provide" => SelfTradeBehavior::CancelProvide,
+                "abort" => SelfTradeBehavior::AbortTransaction,
+                _ => panic!("Invalid self-trade behavior"),
+            };
+
+            // Permissioned markets need the owner's whitelist entry, and oracle markets the oracle
+            let market_state = client.get_market(&market)?;
+            let whitelisted = market_state.whitelist_enabled;
+            let referrer = pubkey_of(sub_matches, "referrer");
+
+            let memo = sub_matches.value_of("memo");
+
+            let signature = match sub_matches.value_of("display_quantity") {
+                display_quantity if sub_matches.is_present("native_sol") => client.place_order_with_native_sol(
+                    &fee_payer,
+                    &owner,
+                    &market,
+                    &order,
+                    is_buy,
+                    price,
+                    quantity,
+                    display_quantity.map(str::parse::<u64>).transpose()?,
+                    self_trade_behavior,
+                )?,
+                display_quantity if delegated_by.is_some() => client.place_order_as_delegate(
+                    &fee_payer,
+                    &owner,
+                    &delegated_by.unwrap(),
+                    &market,
+                    &order,
+                    &token_account,
+                    &token_program,
+                    is_buy,
+                    price,
+                    quantity,
+                    display_quantity.map(str::parse::<u64>).transpose()?,
+                    self_trade_behavior,
+                    referrer.as_ref(),
+                    memo,
+                )?,
+                display_quantity if memo.is_some() => client.place_order_with_memo(
+                    &fee_payer,
+                    &owner,
+                    &market,
+                    &order,
+                    &token_account,
+                    &token_program,
+                    is_buy,
+                    price,
+                    quantity,
+                    display_quantity.map(str::parse::<u64>).transpose()?,
+                    self_trade_behavior,
+                    referrer.as_ref(),
+                    memo.unwrap(),
+                )?,
+                display_quantity if market_state.has_oracle() => client.place_order_with_oracle(
+                    &fee_payer,
+                    &owner,
+                    &market,
+                    &order,
+                    &token_account,
+                    &token_program,
+                    is_buy,
+                    price,
+                    quantity,
+                    display_quantity.map(str::parse::<u64>).transpose()?,
+                    self_trade_behavior,
+                    referrer.as_ref(),
+                )?,
+                Some(display_quantity) if whitelisted => client.place_iceberg_order_whitelisted(
+                    &fee_payer,
+                    &owner,
+                    &market,
+                    &order,
+                    &token_account,
+                    &token_program,
+                    is_buy,
+                    price,
+                    quantity,
+                    display_quantity.parse::<u64>()?,
+                    self_trade_behavior,
+                )?,
+                Some(display_quantity) => client.place_iceberg_order(
+                    &fee_payer,
+                    &owner,
+                    &market,
+                    &order,
+                    &token_account,
+                    &token_program,
+                    is_buy,
+                    price,
+                    quantity,
+                    display_quantity.parse::<u64>()?,
+                    self_trade_behavior,
+                )?,
+                None if whitelisted => client.place_limit_order_whitelisted(
+                    &fee_payer,
+                    &owner,
+                    &market,
+                    &order,
+                    &token_account,
+                    &token_program,
+                    is_buy,
+                    price,
+                    quantity,
+                    self_trade_behavior,
+                    referrer.as_ref(),
+                )?,
+                None => match referrer {
+                    Some(referrer) => client.place_limit_order_with_referrer(
+                        &fee_payer,
+                        &owner,
+                        &market,
+                        &order,
+                        &token_account,
+                        &token_program,
+                        is_buy,
+                        price,
+                        quantity,
+                        self_trade_behavior,
+                        &referrer,
+                    )?,
+                    None => client.place_limit_order(
+                        &fee_payer,
+                        &owner,
+                        &market,
+                        &order,
+                        &token_account,
+                        &token_program,
+                        is_buy,
+                        price,
+                        quantity,
+                        self_trade_behavior,
+                    )?,
+                },
+            };
+
+            println!("Order placed successfully");
+            println!("Order account: {}", order.pubkey());
+            if let Some(placed_order) = client.get_placed_order(&signature)? {
+                println!("Order ID: {}", placed_order.order_id);
+            }
+            println!("Transaction signature: {}", signature);
+        }
+        ("preflight-order", Some(sub_matches)) => {
+            let owner = pubkey_of(sub_matches, "owner").expect("Owner pubkey required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let is_buy = match sub_matches.value_of("side").expect("Side required") {
+                "buy" => true,
+                "sell" => false,
+                _ => panic!("Invalid side"),
+            };
+            let limit_price = sub_matches
+                .value_of("price")
+                .expect("Price required")
+                .parse::<u64>()?;
+            let quantity = sub_matches
+                .value_of("quantity")
+                .expect("Quantity required")
+                .parse::<u64>()?;
+
+            let intent = OrderIntent {
+                is_buy,
+                limit_price,
+                quantity,
+            };
+            let report = client.preflight_order(&fee_payer.pubkey(), &owner, &market, &intent)?;
+            if report.is_ready() {
+                println!("Ready to place the order");
+                return Ok(());
+            }
+
+            println!("Missing steps:");
+            for (i, step) in report.steps.iter().enumerate() {
+                let action = if step.fix.is_empty() {
+                    "action required by the owner"
+                } else {
+                    "fixable"
+                };
+                println!("  {}. {:?} ({})", i + 1, step.issue, action);
+            }
+
+            if sub_matches.is_present("fix") {
+                if let Some(signature) = client.prepare_order_accounts(&fee_payer, &report)? {
+                    println!("Fix instructions sent");
+                    println!("Transaction signature: {}", signature);
+                }
+            }
+        }
+        ("cancel-order", Some(sub_matches)) => {
+            let owner = keypair_of(sub_matches, "owner").expect("Owner keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let order = pubkey_of(sub_matches, "order").expect("Order pubkey required");
+            let token_program = pubkey_of(sub_matches, "token_program").unwrap();
+
+            if sub_matches.is_present("unwrap_sol") {
+                let signature = client.cancel_order_and_unwrap(&fee_payer, &owner, &market, &order)?;
+                println!("Order cancelled successfully");
+                println!("Transaction signature: {}", signature);
+                return Ok(());
+            }
+            let token_account = pubkey_of(sub_matches, "token_account").expect("Token account required");
+
+            // A keypair other than the order owner cancels as the owner's delegate
+            let signature = if client.get_order(&order)?.owner == owner.pubkey() {
+                client.cancel_order(
+                    &fee_payer,
+                    &owner,
+                    &market,
+                    &order,
+                    &token_account,
+                    &token_program,
+                )?
+            } else {
+                client.cancel_order_as_delegate(
+                    &fee_payer,
+                    &owner,
+                    &market,
+                    &order,
+                    &token_account,
+                    &token_program,
+                )?
+            };
+
+            println!("Order cancelled successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("settle-funds", Some(sub_matches)) => {
+            let authority = keypair_of(sub_matches, "authority").expect("Authority keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let taker_order = pubkey_of(sub_matches, "taker_order").expect("Taker order pubkey required");
+            let maker_order = pubkey_of(sub_matches, "maker_order").expect("Maker order pubkey required");
+
+            let signature = client.settle_funds(&fee_payer, &authority, &market, &taker_order, &maker_order)?;
+
+            println!("Funds settled successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("admin-cancel-order", Some(sub_matches)) => {
+            let authority = keypair_of(sub_matches, "authority").expect("Authority keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let order = pubkey_of(sub_matches, "order").expect("Order pubkey required");
+
+            let signature = client.admin_cancel_order(&fee_payer, &authority, &market, &order)?;
+
+            println!("Order cancelled by market authority");
+            println!("Transaction signature: {}", signature);
+        }
+        ("link-orders", Some(sub_matches)) => {
+            let owner = keypair_of(sub_matches, "owner").expect("Owner keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let order = pubkey_of(sub_matches, "order").expect("Order pubkey required");
+            let linked_order = pubkey_of(sub_matches, "linked_order").expect("Linked order pubkey required");
+
+            let signature = client.link_orders(
+                &fee_payer,
+                &owner,
+                &market,
+                &order,
+                &linked_order,
+            )?;
+
+            println!("Orders linked successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("reduce-order", Some(sub_matches)) => {
+            let owner = keypair_of(sub_matches, "owner").expect("Owner keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let order = pubkey_of(sub_matches, "order").expect("Order pubkey required");
+            let token_account = pubkey_of(sub_matches, "token_account").expect("Token account required");
+            let token_program = pubkey_of(sub_matches, "token_program").unwrap();
+            let quantity = sub_matches
+                .value_of("quantity")
+                .expect("Quantity required")
+                .parse::<u64>()?;
+
+            let signature = client.reduce_order(
+                &fee_payer,
+                &owner,
+                &market,
+                &order,
+                &token_account,
+                &token_program,
+                quantity,
+            )?;
+
+            println!("Order reduced successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("cancel-orders", Some(sub_matches)) => {
+            let owner = keypair_of(sub_matches, "owner").expect("Owner keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let orders = pubkeys_of(sub_matches, "orders").expect("Order pubkeys required");
+            let base_token_account = pubkey_of(sub_matches, "base_token_account").expect("Base token account required");
+            let quote_token_account = pubkey_of(sub_matches, "quote_token_account").expect("Quote token account required");
+            let token_program = pubkey_of(sub_matches, "token_program").unwrap();
+
+            // Look up the id stored in each order account
+            let mut order_ids = Vec::with_capacity(orders.len());
+            for order in &orders {
+                order_ids.push(client.get_order(order)?.order_id);
+            }
+
+            let signature = client.cancel_orders_by_ids(
+                &fee_payer,
+                &owner,
+                &market,
+                &base_token_account,
+                &quote_token_account,
+                &token_program,
+                &order_ids,
+                &orders,
+            )?;
+
+            println!("Cancelled {} orders successfully", orders.len());
+            println!("Transaction signature: {}", signature);
+        }
+        ("export-orders", Some(sub_matches)) => {
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let owner = pubkey_of(sub_matches, "owner").expect("Owner pubkey required");
+
+            let rows = client.export_orders(&market, &owner)?;
+            let csv = write_order_csv(&rows);
+
+            match sub_matches.value_of("output") {
+                Some(output) => {
+                    std::fs::write(output, csv)?;
+                    println!("Exported {} orders to {}", rows.len(), output);
+                }
+                None => print!("{}", csv),
+            }
+        }
+        ("import-orders", Some(sub_matches)) => {
+            let owner = keypair_of(sub_matches, "owner").expect("Owner keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let input = sub_matches.value_of("input").expect("Input file required");
+            let rows = read_order_csv(&std::fs::read_to_string(input)?)?;
+            let self_trade_behavior = match sub_matches.value_of("self_trade_behavior").unwrap() {
+                "decrement-take" => SelfTradeBehavior::DecrementTake,
+                "cancel-provide" => SelfTradeBehavior::CancelProvide,
+                "abort" => SelfTradeBehavior::AbortTransaction,
+                _ => panic!("Invalid self-trade behavior"),
+            };
+
+            let dry_run = sub_matches.is_present("dry_run");
+
+            // Keep going past rejected orders and report each of them
+            let market_state = client.get_market(&market)?;
+            let mut imported = 0;
+            for row in &rows {
+                if let Err(issues) = row.revalidate(&market_state) {
+                    println!("Skipped order {}: {:?}", row.order, issues);
+                    continue;
+                }
+                if dry_run {
+                    println!("Order {} is valid", row.order);
+                    imported += 1;
+                    continue;
+                }
+                match client.import_order(&fee_payer, &owner, &market, row, self_trade_behavior.clone()) {
+                    Ok((order, signature)) => {
+                        println!("Order {} placed as {} ({})", row.order, order, signature);
+                        imported += 1;
+                    }
+                    Err(err) => println!("Failed to place order {}: {}", row.order, err),
+                }
+            }
+
+            println!("{} of {} orders {}", imported, rows.len(), if dry_run { "valid" } else { "imported" });
+        }
+        ("decode-ix", Some(sub_matches)) => {
+            let encoding = sub_matches.value_of("encoding").map(|encoding| match encoding {
+                "base58" => DataEncoding::Base58,
+                "base64" => DataEncoding::Base64,
+                _ => panic!("Invalid encoding"),
+            });
+            let data = decode_data(sub_matches.value_of("data").expect("Instruction data required"), encoding)?;
+            let accounts = pubkeys_of(sub_matches, "accounts").unwrap_or_default();
+
+            let decoded = decode_instruction(&data, &accounts)?;
+            print!("{}", decoded);
+            if decoded.unexpected_accounts() > 0 {
+                println!("Warning: {} accounts are not expected by {}", decoded.unexpected_accounts(), decoded.name());
+            }
+        }
+        ("capital-report", Some(sub_matches)) => {
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let owner = pubkey_of(sub_matches, "owner").expect("Owner pubkey required");
+            let thresholds = IdleThresholds {
+                min_distance_bps: sub_matches.value_of("min_distance_bps").unwrap().parse::<u64>()?,
+                min_age_seconds: sub_matches.value_of("min_age").unwrap().parse::<u64>()?,
+            };
+            let (base, quote) = client.get_market_formats(&market)?;
+            let (base, quote) = (base.with_locale(locale), quote.with_locale(locale));
+
+            let report = client.capital_efficiency_report(&market, &owner, &thresholds)?;
+
+            println!("Capital Efficiency Report:");
+            match report.mid_price {
+                Some(mid_price) => println!("  Mid Price: {}", format_price(mid_price, &base, &quote)),
+                None => println!("  Mid Price: unknown (one side of the book is empty)"),
+            }
+            println!("  Locked Base: {}", base.format(report.locked_base));
+            println!("  Locked Quote: {}", quote.format(report.locked_quote));
+            println!("  Idle Base: {}", base.format(report.idle_base()));
+            println!("  Idle Quote: {}", quote.format(report.idle_quote()));
+            println!("Suggested cancellations: {}", report.idle_orders.len());
+            for order in &report.idle_orders {
+                let locked = if order.is_buy { quote.format(order.locked_amount) } else { base.format(order.locked_amount) };
+                println!(
+                    "  {} {} {} @ {}: {} bps from mid, resting {}s, locks {}",
+                    order.order,
+                    if order.is_buy { "Buy" } else { "Sell" },
+                    base.format_lots(order.remaining_quantity),
+                    format_price(order.limit_price, &base, &quote),
+                    order.distance_bps,
+                    order.age_seconds,
+                    locked,
+                );
+            }
+
+            if let Some(owner_keypair) = keypair_of(sub_matches, "cancel") {
+                if report.idle_orders.is_empty() {
+                    println!("Nothing to cancel");
+                } else {
+                    for signature in client.cancel_idle_orders(&fee_payer, &owner_keypair, &report)? {
+                        println!("Transaction signature: {}", signature);
+                    }
+                    println!("Cancelled {} orders successfully", report.idle_orders.len());
+                }
+            }
+        }
+        ("set-instruction-guard", Some(sub_matches)) => {
+            let authority = keypair_of(sub_matches, "authority").expect("Authority keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let guard = match sub_matches.value_of("guard").expect("Guard required") {
+                "disabled" => InstructionGuard::Disabled,
+                "top-level-only" => InstructionGuard::TopLevelOnly,
+                "isolated" => InstructionGuard::Isolated,
+                _ => panic!("Invalid instruction guard"),
+            };
+
+            let signature = client.set_instruction_guard(&fee_payer, &authority, &market, guard)?;
+
+            println!("Instruction guard updated successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("set-market-status", Some(sub_matches)) => {
+            let authority = keypair_of(sub_matches, "authority").expect("Authority keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let status = match sub_matches.value_of("status").expect("Status required") {
+                "active" => MarketStatus::Active,
+                "cancel-only" => MarketStatus::CancelOnly,
+                "paused" => MarketStatus::Paused,
+                _ => panic!("Invalid market status"),
+            };
+
+            let signature = client.set_market_status(&fee_payer, &authority, &market, status)?;
+
+            println!("Market status updated successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("set-whitelist", Some(sub_matches)) => {
+            let authority = keypair_of(sub_matches, "authority").expect("Authority keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let enabled = !sub_matches.is_present("disable");
+
+            let signature = client.set_whitelist_enabled(&fee_payer, &authority, &market, enabled)?;
+
+            println!("Market whitelist {}", if enabled { "enabled" } else { "disabled" });
+            println!("Transaction signature: {}", signature);
+        }
+        ("set-oracle", Some(sub_matches)) => {
+            let authority = keypair_of(sub_matches, "authority").expect("Authority keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let oracle = pubkey_of(sub_matches, "oracle").unwrap_or_default();
+            let band_bps = sub_matches.value_of("band_bps").unwrap().parse::<u16>()?;
+            let max_staleness_slots = sub_matches.value_of("max_staleness_slots").unwrap().parse::<u64>()?;
+            let price_expo = sub_matches.value_of("price_expo").unwrap().parse::<i32>()?;
+
+            let signature = client.set_oracle(
+                &fee_payer,
+                &authority,
+                &market,
+                &oracle,
+                band_bps,
+                max_staleness_slots,
+                price_expo,
+            )?;
+
+            if oracle == Pubkey::default() {
+                println!("Market oracle cleared");
+            } else {
+                println!("Market oracle set to {}", oracle);
+            }
+            println!("Transaction signature: {}", signature);
+        }
+        ("check-oracle", Some(sub_matches)) => {
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+
+            let signature = client.check_oracle(&fee_payer, &market)?;
+
+            println!("Market status: {:?}", client.get_market(&market)?.status);
+            println!("Transaction signature: {}", signature);
+        }
+        ("whitelist-add", Some(sub_matches)) => {
+            let authority = keypair_of(sub_matches, "authority").expect("Authority keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let owner = pubkey_of(sub_matches, "owner").expect("Owner pubkey required");
+
+            let signature = client.add_whitelist_entry(&fee_payer, &authority, &market, &owner)?;
+
+            println!("Owner whitelisted successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("whitelist-remove", Some(sub_matches)) => {
+            let authority = keypair_of(sub_matches, "authority").expect("Authority keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let owner = pubkey_of(sub_matches, "owner").expect("Owner pubkey required");
+            let recipient = pubkey_of(sub_matches, "recipient").unwrap_or_else(|| authority.pubkey());
+
+            let signature = client.remove_whitelist_entry(&fee_payer, &authority, &market, &owner, &recipient)?;
+
+            println!("Owner removed from the whitelist successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("sign-quote", Some(sub_matches)) => {
+            let maker = keypair_of(sub_matches, "maker").expect("Maker keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let is_buy = match sub_matches.value_of("side").expect("Side required") {
+                "buy" => true,
+                "sell" => false,
+                _ => panic!("Invalid side"),
+            };
+            let price = sub_matches
+                .value_of("price")
+                .expect("Price required")
+                .parse::<u64>()?;
+            let quantity = sub_matches
+                .value_of("quantity")
+                .expect("Quantity required")
+                .parse::<u64>()?;
+            let expires_in = sub_matches.value_of("expires_in").unwrap().parse::<i64>()?;
+            let nonce = sub_matches
+                .value_of("nonce")
+                .expect("Nonce required")
+                .parse::<u64>()?;
+
+            let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+            let quote = FirmQuote {
+                market,
+                maker: maker.pubkey(),
+                taker: pubkey_of(sub_matches, "taker").unwrap_or_default(),
+                is_buy,
+                price,
+                quantity,
+                expires_at: now + expires_in,
+                nonce,
+            };
+            let signed_quote = SignedQuote::sign(&maker, &program_id, quote)?;
+
+            println!("Quote expires at: {}", signed_quote.quote.expires_at);
+            println!("Signed quote: {}", signed_quote.to_hex());
+        }
+        ("approve-rfq", Some(sub_matches)) => {
+            let maker = keypair_of(sub_matches, "maker").expect("Maker keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let token_account = pubkey_of(sub_matches, "token_account").expect("Token account required");
+            let amount = sub_matches
+                .value_of("amount")
+                .expect("Amount required")
+                .parse::<u64>()?;
+
+            let signature = client.approve_rfq_authority(&fee_payer, &maker, &market, &token_account, amount)?;
+
+            println!("RFQ authority approved successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("execute-quote", Some(sub_matches)) => {
+            let taker = keypair_of(sub_matches, "taker").expect("Taker keypair required");
+            let signed_quote = SignedQuote::from_hex(sub_matches.value_of("quote").expect("Quote required"))?;
+            let quantity = sub_matches
+                .value_of("quantity")
+                .expect("Quantity required")
+                .parse::<u64>()?;
+
+            if !signed_quote.verify(&program_id) {
+                return Err("Quote is not signed by its maker".into());
+            }
+
+            let signature = client.execute_firm_quote(&fee_payer, &taker, &signed_quote, quantity)?;
+
+            println!("Quote executed successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("set-min-notional", Some(sub_matches)) => {
+            let authority = keypair_of(sub_matches, "authority").expect("Authority keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let min_quote_notional = sub_matches
+                .value_of("min_quote_notional")
+                .expect("Minimum quote notional required")
+                .parse::<u64>()?;
+
+            let signature = client.set_min_quote_notional(&fee_payer, &authority, &market, min_quote_notional)?;
+
+            println!("Minimum quote notional updated successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("init-twap-oracle", Some(sub_matches)) => {
+            let authority = keypair_of(sub_matches, "authority").expect("Authority keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let ema_period_seconds = sub_matches.value_of("ema_period").unwrap().parse::<u64>()?;
+
+            let signature = client.initialize_twap_oracle(&fee_payer, &authority, &market, ema_period_seconds)?;
+
+            println!("TWAP oracle initialized successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("get-twap", Some(sub_matches)) => {
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let twap_oracle = client.get_twap_oracle(&market)?.ok_or("Market has no TWAP oracle")?;
+            let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+
+            println!("TWAP Oracle:");
+            println!("  Fills Recorded: {}", twap_oracle.fill_count);
+            println!("  Last Price: {}", twap_oracle.last_price);
+            println!("  Last Update: {}", twap_oracle.last_update);
+            println!("  EMA Period (seconds): {}", twap_oracle.ema_period_seconds);
+            println!("  EMA Price: {}", twap_oracle.ema_price_at(now));
+            println!("  Cumulative Price: {}", twap_oracle.price_cumulative_at(now));
+        }
+        ("init-event-queue", Some(sub_matches)) => {
+            let authority = keypair_of(sub_matches, "authority").expect("Authority keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+
+            let signature = client.initialize_event_queue(&fee_payer, &authority, &market)?;
+
+            println!("Event queue initialized successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("consume-events", Some(sub_matches)) => {
+            let authority = keypair_of(sub_matches, "authority").expect("Authority keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let limit = sub_matches.value_of("limit").unwrap().parse::<u16>()?;
+            let max_staleness_slots = sub_matches.value_of("max_staleness_slots").map(str::parse::<u64>).transpose()?;
+            let event_queue = client.get_event_queue(&market)?.ok_or("Market has no event queue")?;
+
+            // Print the events before consuming them, so none is removed unseen
+            println!("Events Dropped: {}", { event_queue.dropped });
+            for event in event_queue.iter().take(limit as usize) {
+                println!(
+                    "  #{} slot {}: {} base for {} quote at {} (taker {}, maker {})",
+                    { event.seq_num },
+                    { event.slot },
+                    { event.base_amount },
+                    { event.quote_amount },
+                    { event.price },
+                    { event.taker_order },
+                    { event.maker_order },
+                );
+            }
+            if event_queue.count == 0 {
+                println!("Event queue is empty");
+                return Ok(());
+            }
+
+            let signature = client.consume_events(
+                &fee_payer,
+                &authority,
+                &market,
+                event_queue.first_seq_num(),
+                limit,
+                max_staleness_slots,
+            )?;
+
+            println!("Events consumed successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("rescue-tokens", Some(sub_matches)) => {
+            let authority = keypair_of(sub_matches, "authority").expect("Authority keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let token_account = pubkey_of(sub_matches, "token_account").expect("Token account required");
+            let is_quote = sub_matches.value_of("side").expect("Side required") == "quote";
+
+            let signature = client.rescue_tokens(&fee_payer, &authority, &market, &token_account, is_quote)?;
+
+            println!("Tokens rescued successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("initialize-pool", Some(sub_matches)) => {
+            let authority = keypair_of(sub_matches, "authority").expect("Authority keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let fee_bps = sub_matches
+                .value_of("fee_bps")
+                .expect("Fee required")
+                .parse::<u16>()?;
+
+            let signature = client.initialize_pool(&fee_payer, &authority, &market, fee_bps)?;
+
+            println!("Pool initialized successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("add-liquidity", Some(sub_matches)) => {
+            let owner = keypair_of(sub_matches, "owner").expect("Owner keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let max_base_amount = sub_matches
+                .value_of("max_base_amount")
+                .expect("Maximum base amount required")
+                .parse::<u64>()?;
+            let max_quote_amount = sub_matches
+                .value_of("max_quote_amount")
+                .expect("Maximum quote amount required")
+                .parse::<u64>()?;
+            let min_lp_amount = sub_matches.value_of("min_lp_amount").unwrap().parse::<u64>()?;
+
+            let signature = client.add_liquidity(&fee_payer, &owner, &market, max_base_amount, max_quote_amount, min_lp_amount)?;
+
+            println!("Liquidity added successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("remove-liquidity", Some(sub_matches)) => {
+            let owner = keypair_of(sub_matches, "owner").expect("Owner keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let lp_amount = sub_matches
+                .value_of("lp_amount")
+                .expect("LP amount required")
+                .parse::<u64>()?;
+            let min_base_amount = sub_matches.value_of("min_base_amount").unwrap().parse::<u64>()?;
+            let min_quote_amount = sub_matches.value_of("min_quote_amount").unwrap().parse::<u64>()?;
+
+            let signature = client.remove_liquidity(&fee_payer, &owner, &market, lp_amount, min_base_amount, min_quote_amount)?;
+
+            println!("Liquidity removed successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("swap", Some(sub_matches)) => {
+            let owner = keypair_of(sub_matches, "owner").expect("Owner keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let is_buy = match sub_matches.value_of("side").expect("Side required") {
+                "buy" => true,
+                "sell" => false,
+                _ => panic!("Invalid side"),
+            };
+            let amount_in = sub_matches
+                .value_of("amount_in")
+                .expect("Input amount required")
+                .parse::<u64>()?;
+            let min_amount_out = sub_matches.value_of("min_amount_out").unwrap().parse::<u64>()?;
+
+            let signature = client.swap_against_pool(&fee_payer, &owner, &market, is_buy, amount_in, min_amount_out)?;
+
+            println!("Swap successful");
+            println!("Transaction signature: {}", signature);
+        }
+        ("route-swap", Some(sub_matches)) => {
+            let owner = keypair_of(sub_matches, "owner").expect("Owner keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let is_buy = match sub_matches.value_of("side").expect("Side required") {
+                "buy" => true,
+                "sell" => false,
+                _ => panic!("Invalid side"),
+            };
+            let amount_in = sub_matches
+                .value_of("amount_in")
+                .expect("Input amount required")
+                .parse::<u64>()?;
+            let min_amount_out = sub_matches.value_of("min_amount_out").unwrap().parse::<u64>()?;
+            let max_orders = sub_matches.value_of("max_orders").unwrap().parse::<usize>()?;
+
+            let signature = client.route_swap(&fee_payer, &owner, &market, is_buy, amount_in, min_amount_out, max_orders)?;
+
+            println!("Route swap successful");
+            println!("Transaction signature: {}", signature);
+        }
+        ("initialize-range-pool", Some(sub_matches)) => {
+            let authority = keypair_of(sub_matches, "authority").expect("Authority keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let fee_bps = sub_matches
+                .value_of("fee_bps")
+                .expect("Fee required")
+                .parse::<u16>()?;
+            let tick_spacing = sub_matches
+                .value_of("tick_spacing")
+                .expect("Tick spacing required")
+                .parse::<u16>()?;
+            let tick = sub_matches
+                .value_of("tick")
+                .expect("Initial tick required")
+                .parse::<i32>()?;
+
+            let signature = client.initialize_range_pool(&fee_payer, &authority, &market, fee_bps, tick_spacing, tick)?;
+
+            println!("Range pool initialized successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("add-range-liquidity", Some(sub_matches)) => {
+            let owner = keypair_of(sub_matches, "owner").expect("Owner keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let tick_lower = sub_matches
+                .value_of("tick_lower")
+                .expect("Lower tick required")
+                .parse::<i32>()?;
+            let tick_upper = sub_matches
+                .value_of("tick_upper")
+                .expect("Upper tick required")
+                .parse::<i32>()?;
+            let liquidity = sub_matches
+                .value_of("liquidity")
+                .expect("Liquidity required")
+                .parse::<u64>()?;
+            let max_base_amount = sub_matches
+                .value_of("max_base_amount")
+                .expect("Maximum base amount required")
+                .parse::<u64>()?;
+            let max_quote_amount = sub_matches
+                .value_of("max_quote_amount")
+                .expect("Maximum quote amount required")
+                .parse::<u64>()?;
+
+            let signature = client.add_range_liquidity(
+                &fee_payer,
+                &owner,
+                &market,
+                tick_lower,
+                tick_upper,
+                liquidity,
+                max_base_amount,
+                max_quote_amount,
+            )?;
+
+            println!("Range liquidity added successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("remove-range-liquidity", Some(sub_matches)) => {
+            let owner = keypair_of(sub_matches, "owner").expect("Owner keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let tick_lower = sub_matches
+                .value_of("tick_lower")
+                .expect("Lower tick required")
+                .parse::<i32>()?;
+            let tick_upper = sub_matches
+                .value_of("tick_upper")
+                .expect("Upper tick required")
+                .parse::<i32>()?;
+            let liquidity = sub_matches
+                .value_of("liquidity")
+                .expect("Liquidity required")
+                .parse::<u64>()?;
+            let min_base_amount = sub_matches.value_of("min_base_amount").unwrap().parse::<u64>()?;
+            let min_quote_amount = sub_matches.value_of("min_quote_amount").unwrap().parse::<u64>()?;
+
+            let signature = client.remove_range_liquidity(
+                &fee_payer,
+                &owner,
+                &market,
+                tick_lower,
+                tick_upper,
+                liquidity,
+                min_base_amount,
+                min_quote_amount,
+            )?;
+
+            println!("Range liquidity removed successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("range-swap", Some(sub_matches)) => {
+            let owner = keypair_of(sub_matches, "owner").expect("Owner keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let is_buy = match sub_matches.value_of("side").expect("Side required") {
+                "buy" => true,
+                "sell" => false,
+                _ => panic!("Invalid side"),
+            };
+            let amount_in = sub_matches
+                .value_of("amount_in")
+                .expect("Input amount required")
+                .parse::<u64>()?;
+            let min_amount_out = sub_matches.value_of("min_amount_out").unwrap().parse::<u64>()?;
+
+            let signature = client.swap_against_range_pool(&fee_payer, &owner, &market, is_buy, amount_in, min_amount_out)?;
+
+            println!("Range swap successful");
+            println!("Transaction signature: {}", signature);
+        }
+        ("init-lending-pool", Some(sub_matches)) => {
+            let admin = keypair_of(sub_matches, "admin").expect("Admin keypair required");
+            let mint = pubkey_of(sub_matches, "mint").expect("Mint pubkey required");
+            let rate_curve = InterestRateCurve {
+                base_rate_bps: sub_matches
+                    .value_of("base_rate_bps")
+                    .expect("Base rate required")
+                    .parse::<u16>()?,
+                optimal_utilization_bps: sub_matches
+                    .value_of("optimal_utilization_bps")
+                    .expect("Optimal utilization required")
+                    .parse::<u16>()?,
+                slope1_bps: sub_matches
+                    .value_of("slope1_bps")
+                    .expect("First slope required")
+                    .parse::<u16>()?,
+                slope2_bps: sub_matches
+                    .value_of("slope2_bps")
+                    .expect("Second slope required")
+                    .parse::<u16>()?,
+            };
+
+            let signature = client.initialize_lending_pool(&fee_payer, &admin, &mint, rate_curve)?;
+
+            println!("Lending pool initialized successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("supply", Some(sub_matches)) => {
+            let owner = keypair_of(sub_matches, "owner").expect("Owner keypair required");
+            let mint = pubkey_of(sub_matches, "mint").expect("Mint pubkey required");
+            let amount = sub_matches
+                .value_of("amount")
+                .expect("Amount required")
+                .parse::<u64>()?;
+            let min_shares = sub_matches.value_of("min_shares").unwrap().parse::<u64>()?;
+
+            let signature = client.supply(&fee_payer, &owner, &mint, amount, min_shares)?;
+
+            println!("Tokens supplied successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("redeem", Some(sub_matches)) => {
+            let owner = keypair_of(sub_matches, "owner").expect("Owner keypair required");
+            let mint = pubkey_of(sub_matches, "mint").expect("Mint pubkey required");
+            let shares = sub_matches
+                .value_of("shares")
+                .expect("Shares required")
+                .parse::<u64>()?;
+            let min_amount = sub_matches.value_of("min_amount").unwrap().parse::<u64>()?;
+
+            let signature = client.redeem(&fee_payer, &owner, &mint, shares, min_amount)?;
+
+            println!("Shares redeemed successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("get-lending-pool", Some(sub_matches)) => {
+            let mint = pubkey_of(sub_matches, "mint").expect("Mint pubkey required");
+            let lending_pool = client.get_lending_pool(&mint)?.ok_or("Lending pool not found")?;
+
+            println!("Lending Pool: {}", LendingPool::find_address(&program_id, &mint).0);
+            println!("  Mint: {}", lending_pool.mint);
+            println!("  Share Mint: {}", lending_pool.share_mint);
+            println!("  Available: {}", lending_pool.available);
+            println!("  Borrowed: {}", lending_pool.borrowed);
+            println!("  Share Supply: {}", lending_pool.share_supply);
+            println!("  Utilization (bps): {}", lending_pool.utilization_bps());
+            println!("  Borrow Rate (bps): {}", lending_pool.borrow_rate_bps());
+            println!("  Supply Rate (bps): {}", lending_pool.supply_rate_bps());
+            println!("  Last Accrual: {}", lending_pool.last_accrual_timestamp);
+        }
+        ("init-perp-market", Some(sub_matches)) => {
+            let authority = keypair_of(sub_matches, "authority").expect("Authority keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let initial_margin_bps = sub_matches
+                .value_of("initial_margin_bps")
+                .expect("Initial margin required")
+                .parse::<u16>()?;
+            let max_funding_rate_bps = sub_matches
+                .value_of("max_funding_rate_bps")
+                .expect("Maximum funding rate required")
+                .parse::<u16>()?;
+
+            let signature =
+                client.initialize_perp_market(&fee_payer, &authority, &market, initial_margin_bps, max_funding_rate_bps)?;
+
+            println!("Perp market initialized successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("deposit-perp-collateral", Some(sub_matches)) => {
+            let owner = keypair_of(sub_matches, "owner").expect("Owner keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let amount = sub_matches
+                .value_of("amount")
+                .expect("Amount required")
+                .parse::<u64>()?;
+
+            let signature = client.deposit_perp_collateral(&fee_payer, &owner, &market, amount)?;
+
+            println!("Perp collateral deposited successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("withdraw-perp-collateral", Some(sub_matches)) => {
+            let owner = keypair_of(sub_matches, "owner").expect("Owner keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let amount = sub_matches
+                .value_of("amount")
+                .expect("Amount required")
+                .parse::<u64>()?;
+
+            let signature = client.withdraw_perp_collateral(&fee_payer, &owner, &market, amount)?;
+
+            println!("Perp collateral withdrawn successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("place-perp-order", Some(sub_matches)) => {
+            let owner = keypair_of(sub_matches, "owner").expect("Owner keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let order = keypair_of(sub_matches, "order").expect("Order keypair required");
+            let is_buy = match sub_matches.value_of("side").expect("Side required") {
+                "buy" => true,
+                "sell" => false,
+                _ => panic!("Invalid side"),
+            };
+            let price = sub_matches
+                .value_of("price")
+                .expect("Price required")
+                .parse::<u64>()?;
+            let quantity = sub_matches
+                .value_of("quantity")
+                .expect("Quantity required")
+                .parse::<u64>()?;
+
+            let reduce_only = sub_matches.is_present("reduce_only");
+
+            let signature =
+                client.place_perp_order(&fee_payer, &owner, &market, &order, is_buy, price, quantity, reduce_only)?;
+
+            println!("Perp order placed successfully");
+            println!("Order: {}", order.pubkey());
+            println!("Transaction signature: {}", signature);
+        }
+        ("publish-prices", Some(sub_matches)) => {
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let nonce = match sub_matches.value_of("nonce") {
+                Some(nonce) => nonce.parse::<u32>()?,
+                None => SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as u32,
+            };
+            let finalized = sub_matches.is_present("finalized");
+
+            let (signature, message) = client.publish_prices(&fee_payer, &market, nonce, finalized)?;
+
+            println!("Prices published successfully");
+            println!("Sequence: {}", message.sequence);
+            println!("VAA ID: {}", message.vaa_id());
+            println!("Transaction signature: {}", signature);
+        }
+        ("get-price-message", Some(sub_matches)) => {
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let message = client.get_price_message(&market)?.ok_or("Market has posted no price message")?;
+            let prices = PricePayload::from_bytes(&message.payload).ok_or("Not a price message")?;
+
+            println!("Price Message:");
+            println!("  Sequence: {}", message.sequence);
+            println!("  VAA ID: {}", message.vaa_id());
+            println!("  Messages Posted: {}", client.get_price_message_count(&market)?);
+            println!("  Nonce: {}", message.nonce);
+            println!("  Finalized: {}", message.finalized);
+            println!("  Best Bid: {}", prices.best_bid);
+            println!("  Best Ask: {}", prices.best_ask);
+            println!("  Last Price: {}", prices.last_price);
+            println!("  EMA Price: {}", prices.ema_price);
+            println!("  Cumulative Price: {}", prices.price_cumulative);
+            println!("  Published At: {} (slot {})", prices.timestamp, prices.slot);
+        }
+        ("cancel-perp-order", Some(sub_matches)) => {
+            let owner = keypair_of(sub_matches, "owner").expect("Owner keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let order = pubkey_of(sub_matches, "order").expect("Order pubkey required");
+
+            let signature = client.cancel_perp_order(&owner, &market, &order)?;
+
+            println!("Perp order cancelled successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("settle-perp-fill", Some(sub_matches)) => {
+            let authority = keypair_of(sub_matches, "authority").expect("Authority keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let taker_order = pubkey_of(sub_matches, "taker_order").expect("Taker order pubkey required");
+            let maker_order = pubkey_of(sub_matches, "maker_order").expect("Maker order pubkey required");
+
+            let signature = client.settle_perp_fill(&fee_payer, &authority, &market, &taker_order, &maker_order)?;
+
+            println!("Perp fill settled successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("update-funding", Some(sub_matches)) => {
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+
+            let signature = client.update_funding(&fee_payer, &market)?;
+
+            println!("Funding updated successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("set-perp-reduce-only", Some(sub_matches)) => {
+            let owner = keypair_of(sub_matches, "owner").expect("Owner keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let order = pubkey_of(sub_matches, "order").expect("Order pubkey required");
+            let reduce_only = !sub_matches.is_present("disable");
+
+            let signature = client.set_perp_reduce_only(&owner, &market, &order, reduce_only)?;
+
+            println!("Perp order reduce-only updated successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("init-batch-auction", Some(sub_matches)) => {
+            let authority = keypair_of(sub_matches, "authority").expect("Authority keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let interval_slots = sub_matches
+                .value_of("interval_slots")
+                .expect("Interval slots required")
+                .parse::<u64>()?;
+
+            let signature = client.initialize_batch_auction(&fee_payer, &authority, &market, interval_slots)?;
+
+            println!("Batch auction initialized successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("run-auction", Some(sub_matches)) => {
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+
+            let signature = client.run_auction(&fee_payer, &market)?;
+
+            println!("Auction run successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("get-batch-auction", Some(sub_matches)) => {
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let auction = client.get_batch_auction(&market)?.ok_or("Market has no batch auction")?;
+
+            println!("Batch Auction: {}", BatchAuction::find_address(&program_id, &market).0);
+            println!("  Interval (slots): {}", auction.interval_slots);
+            println!("  Batch Started: {}", auction.batch_start_slot);
+            println!("  Next Auction: {}", auction.next_auction_slot());
+            println!("  Last Clearing Price: {}", auction.last_clearing_price);
+            println!("  Last Volume: {}", auction.last_volume);
+            println!("  Auctions Run: {}", auction.auction_count);
+        }
+        ("init-token-sale", Some(sub_matches)) => {
+            let authority = keypair_of(sub_matches, "authority").expect("Authority keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let base_amount = sub_matches
+                .value_of("base_amount")
+                .expect("Base amount required")
+                .parse::<u64>()?;
+            let min_price = sub_matches
+                .value_of("min_price")
+                .expect("Minimum price required")
+                .parse::<u64>()?;
+            let max_contribution = sub_matches.value_of("max_contribution").unwrap_or("0").parse::<u64>()?;
+            let duration_seconds = sub_matches
+                .value_of("duration_seconds")
+                .expect("Duration required")
+                .parse::<u64>()?;
+
+            let signature = client.initialize_token_sale(
+                &fee_payer,
+                &authority,
+                &market,
+                base_amount,
+                min_price,
+                max_contribution,
+                duration_seconds,
+            )?;
+
+            println!("Token sale initialized successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("contribute", Some(sub_matches)) => {
+            let contributor = keypair_of(sub_matches, "contributor").expect("Contributor keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let amount = sub_matches
+                .value_of("amount")
+                .expect("Amount required")
+                .parse::<u64>()?;
+
+            let signature = client.contribute_to_sale(&fee_payer, &contributor, &market, amount)?;
+
+            println!("Contributed to token sale successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("finalize-sale", Some(sub_matches)) => {
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+
+            let signature = client.finalize_sale(&fee_payer, &market)?;
+
+            println!("Token sale finalized successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("claim-sale", Some(sub_matches)) => {
+            let contributor = keypair_of(sub_matches, "contributor").expect("Contributor keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+
+            let signature = client.claim_sale(&contributor, &market)?;
+
+            println!("Token sale claimed successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("get-token-sale", Some(sub_matches)) => {
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let sale = client.get_token_sale(&market)?.ok_or("Market has no token sale")?;
+
+            println!("Token Sale: {}", TokenSale::find_address(&program_id, &market).0);
+            println!("  Status: {:?}", sale.status);
+            println!("  Base Amount: {}", sale.base_amount);
+            println!("  Minimum Price: {}", sale.min_price);
+            println!("  Max Contribution: {}", sale.max_contribution);
+            println!("  Ends: {}", sale.end_timestamp);
+            println!("  Total Contributed: {}", sale.total_contributed);
+            println!("  Clearing Price: {}", sale.clearing_price);
+            if let Some(owner) = pubkey_of(sub_matches, "owner") {
+                match client.get_sale_contribution(&market, &owner)? {
+                    Some(contribution) => {
+                        println!("Contribution: {}", SaleContribution::find_address(&program_id, &market, &owner).0);
+                        println!("  Amount: {}", contribution.amount);
+                        if sale.status == SaleStatus::Succeeded {
+                            println!("  Allocation: {}", sale.allocation(contribution.amount));
+                        }
+                    }
+                    None => println!("No contribution"),
+                }
+            }
+        }
+        ("init-stake-pool", Some(sub_matches)) => {
+            let admin = keypair_of(sub_matches, "admin").expect("Admin keypair required");
+            let governance_mint = pubkey_of(sub_matches, "governance_mint").expect("Governance mint required");
+            let reward_mint = pubkey_of(sub_matches, "reward_mint").expect("Reward mint required");
+            let epoch_seconds = sub_matches
+                .value_of("epoch_seconds")
+                .expect("Epoch length required")
+                .parse::<u64>()?;
+            let fee_share_bps = sub_matches
+                .value_of("fee_share_bps")
+                .expect("Fee share required")
+                .parse::<u16>()?;
+
+            // Unused tiers stay empty
+            let mut fee_tiers = [FeeTier::default(); STAKE_FEE_TIERS];
+            if let Some(tiers) = sub_matches.value_of("fee_tiers") {
+                let tiers: Vec<&str> = tiers.split(',').collect();
+                if tiers.len() > STAKE_FEE_TIERS {
+                    return Err(format!("At most {} fee tiers", STAKE_FEE_TIERS).into());
+                }
+                for (fee_tier, tier) in fee_tiers.iter_mut().zip(tiers) {
+                    let (min_stake, discount_bps) = tier
+                        .split_once(':')
+                        .ok_or("Fee tiers must be MIN_STAKE:DISCOUNT_BPS")?;
+                    *fee_tier = FeeTier {
+                        min_stake: min_stake.parse()?,
+                        discount_bps: discount_bps.parse()?,
+                    };
+                }
+            }
+
+            let signature = client.initialize_stake_pool(
+                &admin,
+                &governance_mint,
+                &reward_mint,
+                epoch_seconds,
+                fee_share_bps,
+                fee_tiers,
+            )?;
+
+            println!("Stake pool initialized successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("stake", Some(sub_matches)) => {
+            let owner = keypair_of(sub_matches, "owner").expect("Owner keypair required");
+            let amount = sub_matches
+                .value_of("amount")
+                .expect("Amount required")
+                .parse::<u64>()?;
+
+            let signature = client.stake(&owner, amount)?;
+
+            println!("Staked successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("unstake", Some(sub_matches)) => {
+            let owner = keypair_of(sub_matches, "owner").expect("Owner keypair required");
+            let amount = sub_matches
+                .value_of("amount")
+                .expect("Amount required")
+                .parse::<u64>()?;
+
+            let signature = client.unstake(&owner, amount)?;
+
+            println!("Unstaked successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("distribute-staking-rewards", Some(_)) => {
+            let signature = client.distribute_staking_rewards(&fee_payer)?;
+
+            println!("Staking rewards distributed successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("claim-staking-rewards", Some(sub_matches)) => {
+            let owner = keypair_of(sub_matches, "owner").expect("Owner keypair required");
+
+            let signature = client.claim_staking_rewards(&owner)?;
+
+            println!("Staking rewards claimed successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("get-stake", Some(sub_matches)) => {
+            let stake_pool = client.get_stake_pool()?.ok_or("Stake pool not initialized")?;
+
+            println!("Stake Pool: {}", StakePool::find_address(&program_id).0);
+            println!("  Governance Mint: {}", stake_pool.governance_mint);
+            println!("  Reward Mint: {}", stake_pool.reward_mint);
+            println!("  Fee Share (bps): {}", stake_pool.fee_share_bps);
+            println!("  Epoch: {}", stake_pool.epoch);
+            println!("  Epoch Ends: {}", stake_pool.epoch_end_timestamp());
+            println!("  Total Staked: {}", stake_pool.total_staked);
+            println!("  Rewards Distributed: {}", stake_pool.rewards_distributed);
+            for fee_tier in stake_pool.fee_tiers.iter().filter(|fee_tier| fee_tier.min_stake > 0) {
+                println!("  Fee Tier: {} staked for {} bps off", fee_tier.min_stake, fee_tier.discount_bps);
+            }
+            if let Some(owner) = pubkey_of(sub_matches, "owner") {
+                match client.get_stake_account(&owner)? {
+                    Some(stake) => {
+                        println!("Stake Account: {}", StakeAccount::find_address(&program_id, &owner).0);
+                        println!("  Amount: {}", stake.amount);
+                        println!("  Unlocked: {}", stake.is_unlocked(stake_pool.epoch));
+                        println!("  Taker Fee Discount (bps): {}", stake_pool.discount_bps(stake.amount));
+                        let mut pending = stake;
+                        pending.settle_rewards(stake_pool.reward_per_share)?;
+                        println!("  Claimable Rewards: {}", pending.rewards_owed);
+                    }
+                    None => println!("No stake for {}", owner),
+                }
+            }
+        }
+        ("set-loyalty-schedule", Some(sub_matches)) => {
+            let authority = keypair_of(sub_matches, "authority").expect("Authority keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+
+            // Unused phases stay empty
+            let mut phases = [EmissionPhase::default(); LOYALTY_SCHEDULE_PHASES];
+            let phase_args: Vec<&str> = sub_matches
+                .value_of("phases")
+                .map(|phases| phases.split(',').collect())
+                .unwrap_or_default();
+            if phase_args.len() > LOYALTY_SCHEDULE_PHASES {
+                return Err(format!("At most {} emission phases", LOYALTY_SCHEDULE_PHASES).into());
+            }
+            for (phase, phase_arg) in phases.iter_mut().zip(&phase_args) {
+                let fields: Vec<&str> = phase_arg.split(':').collect();
+                if fields.len() != 3 {
+                    return Err("Emission phases must be START_TIMESTAMP:TAKER_BPS:MAKER_BPS".into());
+                }
+                *phase = EmissionPhase {
+                    start_timestamp: fields[0].parse()?,
+                    taker_points_bps: fields[1].parse()?,
+                    maker_points_bps: fields[2].parse()?,
+                };
+            }
+
+            let signature = client.set_loyalty_schedule(&authority, &market, phase_args.len() as u8, phases)?;
+
+            println!("Loyalty schedule set successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("open-loyalty-points", Some(sub_matches)) => {
+            let owner = keypair_of(sub_matches, "owner").expect("Owner keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+
+            let signature = client.open_loyalty_points(&owner, &market)?;
+
+            println!("Loyalty points account opened successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("get-loyalty-points", Some(sub_matches)) => {
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let schedule = client.get_loyalty_schedule(&market)?.ok_or("Market has no loyalty schedule")?;
+
+            println!("Loyalty Schedule: {}", LoyaltySchedule::find_address(&program_id, &market).0);
+            for phase in &schedule.phases[..schedule.num_phases as usize] {
+                println!(
+                    "  Phase from {}: taker {} bps, maker {} bps",
+                    phase.start_timestamp, phase.taker_points_bps, phase.maker_points_bps
+                );
+            }
+            println!("  Total Points: {}", schedule.total_points);
+            if let Some(owner) = pubkey_of(sub_matches, "owner") {
+                match client.get_loyalty_points(&market, &owner)? {
+                    Some(points) => {
+                        println!("Loyalty Points: {}", LoyaltyPoints::find_address(&program_id, &market, &owner).0);
+                        println!("  Taker Points: {}", points.taker_points);
+                        println!("  Maker Points: {}", points.maker_points);
+                        println!("  Total: {}", points.total());
+                        println!("  Last Fill Slot: {}", points.last_fill_slot);
+                    }
+                    None => println!("No loyalty points account for {}", owner),
+                }
+            }
+        }
+        ("get-wrapped-assets", Some(sub_matches)) => {
+            let market_pubkey = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let token_bridge = pubkey_of(sub_matches, "token_bridge").unwrap_or(TOKEN_BRIDGE_PROGRAM_ID);
+            let market = client.get_market(&market_pubkey)?;
+            let (base, quote) = client.get_market_wrapped_assets(&token_bridge, &market_pubkey)?;
+
+            for (side, mint, asset) in [("Base", { market.base_mint }, base), ("Quote", { market.quote_mint }, quote)] {
+                match asset {
+                    Some(asset) => {
+                        println!("{} Mint: {} (Wormhole-wrapped)", side, mint);
+                        println!("  Origin Chain: {}", asset.chain);
+                        println!("  Origin Token: {}", asset.token_address_hex());
+                        println!("  Origin Decimals: {}", asset.original_decimals);
+                    }
+                    None => println!("{} Mint: {} (native)", side, mint),
+                }
+            }
+        }
+        ("get-perp-position", Some(sub_matches)) => {
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let owner = pubkey_of(sub_matches, "owner").expect("Owner pubkey required");
+            let perp_market = client.get_perp_market(&market)?.ok_or("Perp market not found")?;
+
+            println!("Perp Market: {}", PerpMarket::find_address(&program_id, &market).0);
+            println!("  Mark Price: {}", perp_market.mark_price);
+            println!("  Long Open Interest: {}", perp_market.long_open_interest);
+            println!("  Initial Margin (bps): {}", perp_market.initial_margin_bps);
+            println!("  Cumulative Funding: {}", perp_market.cumulative_funding);
+            println!("  Last Funding: {}", perp_market.last_funding_timestamp);
+            match client.get_perp_position(&market, &owner)? {
+                Some(position) => {
+                    println!("Perp Position: {}", PerpPosition::find_address(&program_id, &market, &owner).0);
+                    println!("  Base Position: {}", position.base_position);
+                    println!("  Quote Balance: {}", position.quote_balance);
+                    let quote_lot_size = client.get_market(&market)?.quote_lot_size;
+                    println!("  Equity at Mark: {}", position.equity(perp_market.mark_price, quote_lot_size));
+                }
+                None => println!("No perp position for {}", owner),
+            }
+        }
+        ("migrate-account", Some(sub_matches)) => {
+            let account = pubkey_of(sub_matches, "account").expect("Account pubkey required");
+
+            let signature = client.migrate_account(&fee_payer, &account)?;
+
+            println!("Account migrated successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("delegate", Some(sub_matches)) => {
+            let owner = keypair_of(sub_matches, "owner").expect("Owner keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let delegate = pubkey_of(sub_matches, "delegate").expect("Delegate pubkey required");
+            let expiry_slot = sub_matches
+                .value_of("expiry_slot")
+                .expect("Expiry slot required")
+                .parse::<u64>()?;
+
+            let mut allowances = Vec::new();
+            if let Some(token_account) = pubkey_of(sub_matches, "base_token_account") {
+                allowances.push((token_account, sub_matches.value_of("base_allowance").unwrap().parse::<u64>()?));
+            }
+            if let Some(token_account) = pubkey_of(sub_matches, "quote_token_account") {
+                allowances.push((token_account, sub_matches.value_of("quote_allowance").unwrap().parse::<u64>()?));
+            }
+
+            let signature = client.delegate(&fee_payer, &owner, &market, &delegate, expiry_slot, &allowances)?;
+
+            println!("Trading delegated to {} until slot {}", delegate, expiry_slot);
+            println!("Transaction signature: {}", signature);
+        }
+        ("deposit", Some(sub_matches)) => {
+            let owner = keypair_of(sub_matches, "owner").expect("Owner keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let token_account = pubkey_of(sub_matches, "token_account").expect("Token account required");
+            let is_quote = sub_matches.value_of("side").expect("Side required") == "quote";
+            let amount = sub_matches
+                .value_of("amount")
+                .expect("Amount required")
+                .parse::<u64>()?;
+
+            let signature = client.deposit(&fee_payer, &owner, &market, &token_account, is_quote, amount)?;
+
+            println!("Deposit successful");
+            println!("Open orders: {}", client.get_open_orders_address(&market, &owner.pubkey()));
+            println!("Transaction signature: {}", signature);
+        }
+        ("withdraw", Some(sub_matches)) => {
+            let owner = keypair_of(sub_matches, "owner").expect("Owner keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let token_account = pubkey_of(sub_matches, "token_account").expect("Token account required");
+            let is_quote = sub_matches.value_of("side").expect("Side required") == "quote";
+            let amount = sub_matches
+                .value_of("amount")
+                .expect("Amount required")
+                .parse::<u64>()?;
+
+            let signature = client.withdraw(&fee_payer, &owner, &market, &token_account, is_quote, amount)?;
+
+            println!("Withdrawal successful");
+            println!("Transaction signature: {}", signature);
+        }
+        ("wrap-sol", Some(sub_matches)) => {
+            let owner = keypair_of(sub_matches, "owner").expect("Owner keypair required");
+            let token_program = pubkey_of(sub_matches, "token_program").unwrap();
+            let amount = sub_matches
+                .value_of("amount")
+                .expect("Amount required")
+                .parse::<u64>()?;
+
+            let signature = client.wrap_sol(&fee_payer, &owner, &token_program, amount)?;
+
+            println!("SOL wrapped successfully");
+            println!("Wrapped SOL account: {}", native::wrapped_sol_account(&owner.pubkey(), &token_program));
+            println!("Transaction signature: {}", signature);
+        }
+        ("unwrap-sol", Some(sub_matches)) => {
+            let owner = keypair_of(sub_matches, "owner").expect("Owner keypair required");
+            let token_program = pubkey_of(sub_matches, "token_program").unwrap();
+
+            let signature = client.unwrap_sol(&fee_payer, &owner, &token_program)?;
+
+            println!("SOL unwrapped successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("get-deposits", Some(sub_matches)) => {
+            let owner = pubkey_of(sub_matches, "owner").expect("Owner pubkey required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+
+            match client.get_open_orders(&market, &owner)? {
+                Some(open_orders) => {
+                    println!("Open Orders: {}", client.get_open_orders_address(&market, &owner));
+                    println!("  Free Base: {}", open_orders.base_free);
+                    println!("  Free Quote: {}", open_orders.quote_free);
+                }
+                None => println!("No deposits for {} on market {}", owner, market),
+            }
+        }
+        ("set-referral-share", Some(sub_matches)) => {
+            let authority = keypair_of(sub_matches, "authority").expect("Authority keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let share_bps = sub_matches
+                .value_of("share_bps")
+                .expect("Referral share required")
+                .parse::<u16>()?;
+
+            let signature = client.set_referral_share(&fee_payer, &authority, &market, share_bps)?;
+
+            println!("Referral share updated successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("set-fee-split", Some(sub_matches)) => {
+            let admin = keypair_of(sub_matches, "admin").expect("Admin keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let treasury_share_bps = sub_matches
+                .value_of("treasury_share_bps")
+                .expect("Treasury share required")
+                .parse::<u16>()?;
+            let creator_share_bps = sub_matches
+                .value_of("creator_share_bps")
+                .expect("Creator share required")
+                .parse::<u16>()?;
+
+            let signature = client.set_fee_split(&fee_payer, &admin, &market, treasury_share_bps, creator_share_bps)?;
+
+            println!("Fee split updated successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("set-dynamic-fees", Some(sub_matches)) => {
+            let authority = keypair_of(sub_matches, "authority").expect("Authority keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let min_fee_bps = sub_matches.value_of("min_fee_bps").unwrap().parse::<u16>()?;
+            let volatility_bps = sub_matches.value_of("volatility_bps").unwrap().parse::<u16>()?;
+            let window = sub_matches
+                .value_of("window")
+                .expect("Volatility window required")
+                .parse::<u16>()?;
+
+            let signature =
+                client.set_dynamic_fees(&fee_payer, &authority, &market, min_fee_bps, volatility_bps, window)?;
+
+            println!("Dynamic fees updated successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("init-fee-vault", Some(sub_matches)) => {
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+
+            let signature = client.initialize_fee_vault(&fee_payer, &market)?;
+
+            println!("Fee vault initialized successfully");
+            println!("Fee vault: {}", client.get_fee_vault_address(&market));
+            println!("Transaction signature: {}", signature);
+        }
+        ("sweep-fees", Some(sub_matches)) => {
+            let fee_authority = keypair_of(sub_matches, "fee_authority").expect("Fee authority keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let destination = match pubkey_of(sub_matches, "destination") {
+                Some(destination) => destination,
+                None => {
+                    client.get_market_token_account(&client.get_market(&market)?, &fee_authority.pubkey(), true)
+                }
+            };
+
+            let signature = client.sweep_fees(&fee_payer, &fee_authority, &market, &destination)?;
+
+            println!("Fees swept successfully to {}", destination);
+            println!("Transaction signature: {}", signature);
+        }
+        ("init-insurance-fund", Some(sub_matches)) => {
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+
+            let signature = client.initialize_insurance_fund(&fee_payer, &market)?;
+
+            println!("Insurance fund initialized successfully");
+            println!("Insurance fund: {}", client.get_insurance_fund_address(&market));
+            println!("Transaction signature: {}", signature);
+        }
+        ("deposit-insurance-fund", Some(sub_matches)) => {
+            let depositor = keypair_of(sub_matches, "depositor").expect("Depositor keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let source = match pubkey_of(sub_matches, "source") {
+                Some(source) => source,
+                None => {
+                    client.get_market_token_account(&client.get_market(&market)?, &depositor.pubkey(), true)
+                }
+            };
+            let amount = sub_matches
+                .value_of("amount")
+                .expect("Amount required")
+                .parse::<u64>()?;
+
+            let signature = client.deposit_insurance_fund(&fee_payer, &depositor, &market, &source, amount)?;
+
+            println!("Deposited {} into the insurance fund", amount);
+            println!("Transaction signature: {}", signature);
+        }
+        ("withdraw-insurance-fund", Some(sub_matches)) => {
+            let authority = keypair_of(sub_matches, "authority").expect("Authority keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let destination = pubkey_of(sub_matches, "destination").expect("Destination pubkey required");
+            let amount = sub_matches
+                .value_of("amount")
+                .expect("Amount required")
+                .parse::<u64>()?;
+
+            let signature = client.withdraw_insurance_fund(&fee_payer, &authority, &market, &destination, amount)?;
+
+            println!("Drew {} from the insurance fund to {}", amount, destination);
+            println!("Transaction signature: {}", signature);
+        }
+        ("set-fee-authority", Some(sub_matches)) => {
+            let fee_authority = keypair_of(sub_matches, "fee_authority").expect("Fee authority keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let new_fee_authority = pubkey_of(sub_matches, "new_fee_authority").expect("New fee authority pubkey required");
+
+            let signature = client.set_fee_authority(&fee_payer, &fee_authority, &market, &new_fee_authority)?;
+
+            println!("Fee authority updated successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("close-market", Some(sub_matches)) => {
+            let authority = keypair_of(sub_matches, "authority").expect("Authority keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let recipient = pubkey_of(sub_matches, "recipient").unwrap_or_else(|| authority.pubkey());
+
+            let signature = client.close_market(&fee_payer, &authority, &market, &recipient)?;
+
+            println!("Market closed successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("init-config", Some(sub_matches)) => {
+            let admin = keypair_of(sub_matches, "admin").expect("Admin keypair required");
+
+            let signature = client.initialize_program_config(&fee_payer, &admin)?;
+
+            println!("Program config initialized successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("update-config", Some(sub_matches)) => {
+            let admin = keypair_of(sub_matches, "admin").expect("Admin keypair required");
+            let market_creation_mode = match sub_matches.value_of("market_creation").unwrap() {
+                "permissionless" => MarketCreationMode::Permissionless,
+                "admin-only" => MarketCreationMode::AdminOnly,
+                _ => panic!("Invalid market creation mode"),
+            };
+            let allowed_quote_mints = pubkeys_of(sub_matches, "allowed_quote_mints").unwrap_or_default();
+
+            let signature = client.update_program_config(
+                &fee_payer,
+                &admin,
+                market_creation_mode,
+                allowed_quote_mints,
+            )?;
+
+            println!("Program config updated successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("set-creation-fee", Some(sub_matches)) => {
+            let admin = keypair_of(sub_matches, "admin").expect("Admin keypair required");
+            let treasury = pubkey_of(sub_matches, "treasury").expect("Treasury pubkey required");
+            let fee_lamports = sub_matches.value_of("fee_lamports").unwrap().parse::<u64>()?;
+            let fee_quote = sub_matches.value_of("fee_quote").unwrap().parse::<u64>()?;
+
+            let signature = client.set_market_creation_fee(
+                &fee_payer,
+                &admin,
+                &treasury,
+                fee_lamports,
+                fee_quote,
+            )?;
+
+            println!("Market creation fee updated successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("set-trade-busting", Some(sub_matches)) => {
+            let admin = keypair_of(sub_matches, "admin").expect("Admin keypair required");
+            let enabled = !sub_matches.is_present("disable");
+            let window_slots = sub_matches.value_of("window_slots").unwrap().parse::<u64>()?;
+
+            let signature = client.set_trade_busting(&fee_payer, &admin, enabled, window_slots)?;
+
+            println!("Trade busting updated successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("estimate-market-cost", Some(_)) => {
+            let cost = client.estimate_market_creation_cost()?;
+
+            println!("Market Creation Cost:");
+            println!("  Rent (lamports): {}", cost.rent_lamports);
+            println!("  Creation Fee (lamports): {}", cost.fee_lamports);
+            println!("  Creation Fee (quote tokens): {}", cost.fee_quote);
+        }
+        ("get-config", Some(_)) => {
+            let config = client.get_program_config()?;
+
+            println!("Program Config:");
+            println!("  Admin: {}", config.admin);
+            println!("  Market Creation: {:?}", config.market_creation_mode);
+            if config.num_allowed_quote_mints == 0 {
+                println!("  Allowed Quote Mints: any");
+            } else {
+                println!("  Allowed Quote Mints:");
+                for mint in &config.allowed_quote_mints[..config.num_allowed_quote_mints as usize] {
+                    println!("    {}", mint);
+                }
+            }
+            println!("  Treasury: {}", config.treasury);
+            println!("  Market Creation Fee (lamports): {}", config.market_creation_fee_lamports);
+            println!("  Market Creation Fee (quote tokens): {}", config.market_creation_fee_quote);
+            if config.trade_busting_enabled {
+                println!("  Trade Busting: enabled ({} slot window)", config.trade_bust_window_slots);
+            } else {
+                println!("  Trade Busting: disabled");
+            }
+        }
+        ("get-market", Some(sub_matches)) => {
+            let market_pubkey = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let market = client.get_market(&market_pubkey)?;
+
+            println!("Market Information:");
+            println!("  Authority: {}", market.authority);
+            println!("  Base Mint: {}", market.base_mint);
+            println!("  Quote Mint: {}", market.quote_mint);
+            println!("  Base Decimals: {}", market.base_decimals);
+            println!("  Quote Decimals: {}", market.quote_decimals);
+            println!("  Trustless: {}", if market.trustless { "yes" } else { "no" });
+            println!("  Base Lot Size: {}", { market.base_lot_size });
+            println!("  Quote Lot Size: {}", { market.quote_lot_size });
+            println!("  Min Base Order Size: {}", { market.min_base_order_size });
+            println!("  Tick Size: {}", { market.tick_size });
+            println!("  Min Quote Notional: {}", { market.min_quote_notional });
+            println!("  Taker Fee (bps): {}", { market.taker_fee_bps });
+            if market.has_flag(MARKET_FLAG_DYNAMIC_FEES) {
+                println!(
+                    "  Dynamic Taker Fee (bps): {} (from {} up to the taker fee at {} bps volatility)",
+                    market.dynamic_taker_fee_bps(),
+                    { market.dynamic_fee_min_bps },
+                    { market.dynamic_fee_volatility_bps }
+                );
+                println!(
+                    "  Realized Volatility (bps per fill): {} over {} fills",
+                    market.realized_volatility_bps(),
+                    { market.volatility_window }
+                );
+            }
+            println!("  Maker Fee (bps): {}", { market.maker_fee_bps });
+            println!("  Next Order ID: {}", { market.next_order_id });
+            println!("  Number of Bids: {}", { market.num_bids });
+            println!("  Number of Asks: {}", { market.num_asks });
+            println!("  Resting Bid Base: {}", { market.bid_base_total });
+            println!("  Resting Ask Base: {}", { market.ask_base_total });
+            println!("  Resting Bid Notional: {}", { market.bid_notional });
+            println!("  Resting Ask Notional: {}", { market.ask_notional });
+            println!("  Deposited Free Base: {}", { market.base_free_total });
+            println!("  Deposited Free Quote: {}", { market.quote_free_total });
+            println!("  Book Imbalance (bps): {}", market.book_imbalance_bps());
+            // A stale best price only bounds its side until CommitOpenOrdersRoot recomputes it
+            match market.best_bid_price() {
+                Some(price) => println!("  Best Bid: {}", price),
+                None if market.has_flag(MARKET_FLAG_BEST_BID_STALE) => {
+                    println!("  Best Bid: unknown (stale, at most {})", { market.best_bid })
+                }
+                None => println!("  Best Bid: none"),
+            }
+            match market.best_ask_price() {
+                Some(price) => println!("  Best Ask: {}", price),
+                None if market.has_flag(MARKET_FLAG_BEST_ASK_STALE) => {
+                    println!("  Best Ask: unknown (stale, at least {})", { market.best_ask })
+                }
+                None => println!("  Best Ask: none"),
+            }
+            match market.spread() {
+                Some(spread) => println!("  Spread: {}", spread),
+                None => println!("  Spread: none"),
+            }
+            println!("  Last Price: {}", { market.last_price });
+            println!("  Last Trade Slot: {}", { market.last_trade_slot });
+            let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+            println!("  Open Interest (base lots): {}", market.open_interest());
+            println!("  Base Volume: {}", { market.base_volume });
+            println!("  Quote Volume: {}", { market.quote_volume });
+            println!("  Quote Volume (24h): {}", market.rolling_quote_volume(now));
+            println!("  Status: {:?}", market.status);
+            println!("  Whitelist: {}", if market.whitelist_enabled { "enabled" } else { "disabled" });
+            if market.has_oracle() {
+                println!("  Oracle: {}", market.oracle);
+                println!("  Oracle Band (bps): {}", { market.oracle_band_bps });
+                println!("  Oracle Max Staleness (slots): {}", { market.oracle_max_staleness_slots });
+                println!("  Oracle Price Exponent: {}", { market.oracle_price_expo });
+            }
+            println!("  Instruction Guard: {:?}", market.instruction_guard);
+            println!("  Referral Share (bps): {}", { market.referral_share_bps });
+            println!("  Fee Authority: {}", market.fee_authority);
+            println!("  Fee Vault: {}", client.get_fee_vault_address(&market_pubkey));
+            println!("  Fees Accrued: {}", { market.fees_accrued });
+            println!("  Fees Swept: {}", { market.fees_swept });
+            println!("  Referral Fees Paid: {}", { market.referral_fees_paid });
+            if market.treasury_fee_share_bps > 0 || market.creator_fee_share_bps > 0 {
+                println!("  Treasury Fee Share (bps): {} to {}", { market.treasury_fee_share_bps }, market.treasury_fee_account);
+                println!("  Creator Fee Share (bps): {} to {}", { market.creator_fee_share_bps }, market.creator_fee_account);
+            }
+            println!("  Insurance Fund: {}", client.get_insurance_fund_address(&market_pubkey));
+            println!("  Open Orders Root: {}", Hash::new_from_array(market.open_orders_root));
+            println!("  Open Orders Root Leaves: {}", { market.open_orders_root_leaves });
+            println!("  Open Orders Root Slot: {}", { market.open_orders_root_slot });
+            if let Some(pool) = client.get_pool(&market_pubkey)? {
+                println!("  Pool Fee (bps): {}", pool.fee_bps);
+                println!("  Pool Base Reserve: {}", pool.base_reserve);
+                println!("  Pool Quote Reserve: {}", pool.quote_reserve);
+                println!("  Pool LP Mint: {}", pool.lp_mint);
+                println!("  Pool LP Supply: {}", pool.lp_supply);
+            }
+        }
+        ("list-markets", Some(_)) => {
+            let markets = client.get_market_registry()?;
+
+            println!("Markets: {}", markets.len());
+            for entry in markets {
+                println!("  Market: {}", entry.market);
+                println!("    Base Mint: {}", entry.base_mint);
+                println!("    Quote Mint: {}", entry.quote_mint);
+                println!("    Created Slot: {}", entry.created_slot);
+            }
+        }
+        ("watch-fills", Some(sub_matches)) => {
+            let market_pubkey = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let cursor_path = Path::new(sub_matches.value_of("cursor_file").unwrap());
+            let ws_url = sub_matches
+                .value_of("ws_url")
+                .map(str::to_string)
+                .unwrap_or_else(|| websocket_url(url));
+            let (base, quote) = client.get_market_formats(&market_pubkey)?;
+            let (base, quote) = (base.with_locale(locale), quote.with_locale(locale));
+            let mut cursor = FillCursor::load(cursor_path)?;
+
+            // Resubscribe after every disconnect; the cursor drops fills replayed on reconnect
+            loop {
+                let result = client.stream_fills(
+                    &ws_url,
+                    &market_pubkey,
+                    &mut cursor,
+                    Some(cursor_path),
+                    |fill, cursor| {
+                        let missed = cursor.missed(fill);
+                        if missed > 0 {
+                            eprintln!("Warning: {} fills missed before fill {}", missed, fill.seq_num);
+                        }
+                        println!(
+                            "Fill {}: {} for {} (taker {}, maker {}, slot {})",
+                            fill.seq_num,
+                            base.format(fill.base_amount),
+                            quote.format(fill.quote_amount),
+                            fill.taker_order,
+                            fill.maker_order,
+                            fill.slot,
+                        );
+                        if !fill.taker_memo.is_empty() {
+                            println!("  Taker memo: {}", fill.taker_memo);
+                        }
+                        if !fill.maker_memo.is_empty() {
+                            println!("  Maker memo: {}", fill.maker_memo);
+                        }
+                        Ok(())
+                    },
+                );
+                if let Err(err) = result {
+                    eprintln!("Fill stream interrupted: {}", err);
+                }
+                std::thread::sleep(Duration::from_secs(1));
+            }
+        }
+        ("commit-orders-root", Some(sub_matches)) => {
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+
+            let signature = client.commit_open_orders_root(&fee_payer, &market)?;
+
+            println!("Open orders root committed successfully");
+            println!("Transaction signature: {}", signature);
+        }
+        ("prove-order", Some(sub_matches)) => {
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let order = pubkey_of(sub_matches, "order").expect("Order pubkey required");
+
+            let proof = client.get_open_order_proof(&market, &order)?;
+            let verified = client.verify_open_order_proof(&market, &proof)?;
+
+            println!("Open Order Proof:");
+            println!("  Leaf: {}", Hash::new_from_array(proof.leaf));
+            println!("  Index: {} of {}", proof.index, proof.num_leaves);
+            for sibling in &proof.siblings {
+                println!("  Sibling: {}", Hash::new_from_array(*sibling));
+            }
+            println!("  Matches Committed Root: {}", verified);
+        }
+        ("create-lookup-table", Some(sub_matches)) => {
+            let authority = keypair_of(sub_matches, "authority").expect("Authority keypair required");
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+
+            let (lookup_table, signatures) = client.create_market_lookup_table(&fee_payer, &authority, &market)?;
+
+            println!("Lookup table created successfully");
+            println!("Lookup table: {}", lookup_table);
+            for signature in signatures {
+                println!("Transaction signature: {}", signature);
+            }
+        }
+        ("get-lookup-table", Some(sub_matches)) => {
+            let lookup_table = pubkey_of(sub_matches, "lookup_table").expect("Lookup table pubkey required");
+            let table = client.get_lookup_table(&lookup_table)?;
+
+            println!("Lookup Table {}:", table.key);
+            for (index, address) in table.addresses.iter().enumerate() {
+                println!("  {}: {}", index, address);
+            }
+        }
+        ("get-order-book", Some(sub_matches)) => {
+            let market = pubkey_of(sub_matches, "market").expect("Market pubkey required");
+            let book = client.get_order_book(&market)?;
+            let (base, quote) = client.get_market_formats(&market)?;
+            let (base, quote) = (base.with_locale(locale), quote.with_locale(locale));
+
+            println!("Asks:");
+            for level in book.asks.iter().rev() {
+                println!(
+                    "  {} x {} ({} orders)",
+                    format_price(level.price, &base, &quote),
+                    base.format_lots(level.quantity),
+                    level.num_orders
+                );
+            }
+            println!("Bids:");
+            for level in &book.bids {
+                println!(
+                    "  {} x {} ({} orders)",
+                    format_price(level.price, &base, &quote),
+                    base.format_lots(level.quantity),
+                    level.num_orders
+                );
+            }
+        }
+        ("get-aggregated-orderbook", Some(sub_matches)) => {
+            let markets = pubkeys_of(sub_matches, "markets").expect("Market pubkeys required");
+            let book = client.get_aggregated_orderbook(&markets)?;
+            let (base, quote) = client.get_market_formats(&markets[0])?;
+            let (base, quote) = (base.with_locale(locale), quote.with_locale(locale));
+
+            println!("Asks:");
+            for level in book.asks.iter().rev() {
+                println!("  {} x {}", format_price(level.price, &base, &quote), base.format_lots(level.quantity));
+                for source in &level.sources {
+                    println!("    {}: {}", source.market, base.format_lots(source.quantity));
+                }
+            }
+            println!("Bids:");
+            for level in &book.bids {
+                println!("  {} x {}", format_price(level.price, &base, &quote), base.format_lots(level.quantity));
+                for source in &level.sources {
+                    println!("    {}: {}", source.market, base.format_lots(source.quantity));
+                }
+            }
+        }
+        ("route-order", Some(sub_matches)) => {
+            let markets = pubkeys_of(sub_matches, "markets").expect("Market pubkeys required");
+            let is_buy = match sub_matches.value_of("side").expect("Side required") {
+                "buy" => true,
+                "sell" => false,
+                _ => panic!("Invalid side"),
+            };
+            let convention = match sub_matches.value_of("fees").unwrap() {
+                "inclusive" => FeeConvention::FeeInclusive,
+                _ => FeeConvention::FeeExclusive,
+            };
+            let (base, quote) = client.get_market_formats(&markets[0])?;
+            let (base, quote) = (base.with_locale(locale), quote.with_locale(locale));
+
+            let route = match sub_matches.value_of("quote_amount") {
+                Some(quote_amount) => {
+                    let quote_amount = quote_amount.parse::<u64>()?;
+                    client.quote_order_for_amount(&markets, is_buy, quote_amount, convention)?
+                }
+                None => {
+                    let quantity = sub_matches
+                        .value_of("quantity")
+                        .expect("Quantity required")
+                        .parse::<u64>()?;
+                    client.quote_order(&markets, is_buy, quantity, convention)?
+                }
+            };
+            let total = route.quote_total(convention);
+
+            println!("Route:");
+            for leg in &route.legs {
+                println!(
+                    "  {}: {} up to price {}",
+                    leg.market,
+                    base.format_lots(leg.quantity),
+                    format_price(leg.limit_price, &base, &quote)
+                );
+            }
+            println!("Quantity: {}", base.format_lots(route.base_quantity));
+            println!("Fee: {}", quote.format(route.fee));
+            match convention {
+                FeeConvention::FeeInclusive => {
+                    println!("Total (incl. fees): {}", quote.format(total));
+                    println!(
+                        "Average Price (incl. fees): {}",
+                        format_average_price(total, route.base_quantity, &base, &quote)
+                    );
+                }
+                FeeConvention::FeeExclusive => {
+                    println!("Total (excl. fees): {}", quote.format(total));
+                    println!(
+                        "Average Price (excl. fees): {}",
+                        format_average_price(total, route.base_quantity, &base, &quote)
+                    );
+                }
+            }
+        }
+        ("get-owner-orders", Some(sub_matches)) => {
+            let owner = pubkey_of(sub_matches, "owner").expect("Owner pubkey required");
+            let market = pubkey_of(sub_matches, "market");
+            let orders = client.get_open_orders_for_owner(&owner, market.as_ref())?;
+            if orders.is_empty() {
+                println!("No open orders");
+            }
+
+            let mut formats = None;
+            for (order_pubkey, order) in &orders {
+                // Orders come grouped by market, so each market's formats are fetched once
+                if formats.as_ref().map(|(market, _, _)| *market) != Some(order.market) {
+                    let (base, quote) = client.get_market_formats(&order.market)?;
+                    formats = Some((order.market, base.with_locale(locale), quote.with_locale(locale)));
+                }
+                let (_, base, quote) = formats.as_ref().unwrap();
+                println!(
+                    "{} [{}] {} {} @ {} on {}",
+                    order_pubkey,
+                    { order.order_id },
+                    if order.is_buy { "Buy" } else { "Sell" },
+                    base.format_lots(order.remaining_quantity),
+                    format_price(order.limit_price, base, quote),
+                    order.market
+                );
+            }
         }
         ("get-order", Some(sub_matches)) => {
             let order_pubkey = pubkey_of(sub_matches, "order").expect("Order pubkey required");
             let order = client.get_order(&order_pubkey)?;
+            let (base, quote) = client.get_market_formats(&order.market)?;
+            let (base, quote) = (base.with_locale(locale), quote.with_locale(locale));
 
             println!("Order Information:");
-            println!("  Order ID: {}", order.order_id);
+            println!("  Order ID: {}", { order.order_id });
             println!("  Owner: {}", order.owner);
             println!("  Market: {}", order.market);
             println!("  Side: {}", if order.is_buy { "Buy" } else { "Sell" });
-            println!("  Limit Price: {}", order.limit_price);
-            println!("  Original Quantity: {}", order.original_quantity);
-            println!("  Remaining Quantity: {}", order.remaining_quantity);
-            println!("  Creation Timestamp: {}", order.creation_timestamp);
+            println!("  Limit Price: {}", format_price(order.limit_price, &base, &quote));
+            println!("  Original Quantity: {}", base.format_lots(order.original_quantity));
+            println!("  Remaining Quantity: {}", base.format_lots(order.remaining_quantity));
+            println!("  Creation Timestamp: {}", { order.creation_timestamp });
+            if order.is_linked() {
+                println!("  Linked Order: {}", order.linked_order);
+            }
+            if order.has_referrer() {
+                println!("  Referrer: {}", order.referrer);
+            }
+            if !order.memo().is_empty() {
+                println!("  Memo: {}", order.memo());
+            }
+            if order.is_iceberg() {
+                println!("  Display Quantity: {}", base.format_lots(order.display_quantity));
+                println!("  Visible Quantity: {}", base.format_lots(order.visible_quantity));
+            }
         }
         _ => {
             println!("No command specified. Use --help for usage information.");
@@ -0,0 +1,135 @@
+// Localized error message catalog for client-facing failure reasons
+// 面向客户端失败原因的本地化错误消息目录
+
+use crate::format::FormatError;
+use solana_client::client_error::ClientError;
+use solana_rust_dex::error::DexError;
+use solana_sdk::{instruction::InstructionError, transaction::TransactionError};
+use std::error::Error;
+
+/// Language of the catalog messages
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Language {
+    /// English
+    En,
+    /// Simplified Chinese
+    Zh,
+}
+
+impl Language {
+    /// Language of a tag such as `en`, `zh` or `zh-CN`
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let language = tag.split(['-', '_']).next().unwrap_or_default();
+        match language.to_ascii_lowercase().as_str() {
+            "en" => Some(Language::En),
+            "zh" => Some(Language::Zh),
+            _ => None,
+        }
+    }
+}
+
+/// Localized message of a program error
+///
+/// Error codes are decoded with `DexError::from_u32`, so a new variant only needs its
+/// messages here.
+pub fn dex_error_message(error: DexError, language: Language) -> &'static str {
+    let (en, zh) = match error {
+        DexError::InvalidInstructionData => ("Invalid instruction data", "指令数据无效"),
+        DexError::InvalidAccountData => ("Invalid account data", "账户数据无效"),
+        DexError::AccountNotAuthorized => ("Account not authorized", "账户未获授权"),
+        DexError::InsufficientFunds => ("Insufficient funds", "资金不足"),
+        DexError::OrderNotFound => ("Order not found", "订单不存在"),
+        DexError::InvalidOrderPrice => ("Invalid order price", "订单价格无效"),
+        DexError::InvalidOrderSize => ("Invalid order size", "订单数量无效"),
+        DexError::OrderBookFull => ("Order book is full", "订单簿已满"),
+        DexError::InvalidTokenAccount => ("Invalid token account", "代币账户无效"),
+        DexError::ArithmeticOverflow => ("Arithmetic overflow", "算术溢出"),
+        DexError::InstructionGuardViolation => ("Instruction guard violation", "违反指令保护规则"),
+        DexError::QuoteMintNotAllowed => ("Quote mint not allowed", "报价代币不在允许列表中"),
+        DexError::InvalidOrderLink => ("Invalid order link", "订单关联无效"),
+        DexError::TradeBustingDisabled => ("Trade busting disabled", "交易撤销未启用"),
+        DexError::TradeBustWindowExpired => ("Trade bust window expired", "交易撤销窗口已过期"),
+        DexError::MarketNotActive => ("Market not active", "市场未处于活跃状态"),
+        DexError::MarketNotEmpty => ("Market not empty", "市场仍有挂单或未提取的手续费"),
+        DexError::OwnerNotWhitelisted => ("Owner not whitelisted", "所有者不在市场白名单中"),
+        DexError::QuoteExpired => ("Quote expired", "报价已过期"),
+        DexError::InvalidQuoteSignature => ("Invalid quote signature", "报价签名无效"),
+        DexError::OracleStale => ("Oracle price stale", "预言机价格已过期"),
+        DexError::InvalidOraclePrice => ("Invalid oracle price", "预言机价格无效"),
+        DexError::PriceOutsideOracleBand => ("Price outside oracle band", "价格超出预言机价格区间"),
+        DexError::InsufficientInsuranceFund => ("Insufficient insurance fund", "保险基金余额不足"),
+        DexError::InvalidMint => ("Invalid mint", "无效的代币铸造账户"),
+        DexError::FreezableMint => ("Mint has freeze authority", "代币铸造账户设有冻结权限"),
+        DexError::AlreadyInitialized => ("Account already initialized", "账户已初始化"),
+        DexError::InvalidAccountOwner => ("Invalid account owner", "账户所有者无效"),
+        DexError::AccountMismatch => ("Account mismatch", "账户不匹配"),
+        DexError::NotRentExempt => ("Account not rent exempt", "账户未达到租金豁免"),
+        DexError::DelegationExpired => ("Delegation expired", "交易委托已过期"),
+        DexError::AccountNotMigrated => ("Account not migrated", "账户尚未迁移到当前布局"),
+        DexError::EventSequenceMismatch => ("Event sequence mismatch", "事件序列号不匹配"),
+        DexError::MarketPaused => ("Market paused", "市场已暂停"),
+        DexError::InvalidLotSize => ("Invalid lot size", "无效的最小交易单位"),
+        DexError::TransactionStale => ("Transaction stale", "交易已过期"),
+        DexError::SlippageExceeded => ("Slippage exceeded", "超出滑点限制"),
+        DexError::InvalidTickRange => ("Invalid tick range", "无效的价格刻度区间"),
+        DexError::InsufficientMargin => ("Insufficient margin", "保证金不足"),
+        DexError::FundingNotDue => ("Funding not due", "尚未到资金费率结算时间"),
+        DexError::ReduceOnly => ("Reduce-only position", "仓位仅允许减仓"),
+        DexError::AuctionNotDue => ("Auction not due", "尚未到集合竞价时间"),
+        DexError::BatchAuctionMarket => ("Batch auction market", "该市场采用集合竞价撮合"),
+        DexError::SaleClosed => ("Sale closed", "代币发售已结束"),
+        DexError::SaleNotEnded => ("Sale not ended", "代币发售尚未结束"),
+        DexError::ContributionCapExceeded => ("Contribution cap exceeded", "超出认购上限"),
+        DexError::EpochNotEnded => ("Epoch not ended", "奖励周期尚未结束"),
+        DexError::StakeLocked => ("Stake locked", "质押仍在锁定期"),
+    };
+
+    match language {
+        Language::En => en,
+        Language::Zh => zh,
+    }
+}
+
+/// Localized message of an amount or price formatting error
+pub fn format_error_message(error: &FormatError, language: Language) -> String {
+    match language {
+        Language::En => error.to_string(),
+        Language::Zh => match error {
+            FormatError::Empty => "数量为空".to_string(),
+            FormatError::InvalidCharacter(c) => format!("无效字符 '{}'", c),
+            FormatError::TooManyDecimals(decimals) => format!("小数位过多，最多允许 {} 位", decimals),
+            FormatError::PriceNotRepresentable => "价格不是链上最小价格步长的整数倍".to_string(),
+            FormatError::Overflow => "数量过大".to_string(),
+        },
+    }
+}
+
+/// Program error carried by a failed transaction, if any
+pub fn dex_error_of_transaction(error: &TransactionError) -> Option<DexError> {
+    match error {
+        TransactionError::InstructionError(_, InstructionError::Custom(code)) => DexError::from_u32(*code),
+        _ => None,
+    }
+}
+
+/// Localized description of a client failure
+///
+/// Program errors and formatting errors are translated; any other error keeps its own message.
+pub fn describe_error(error: &(dyn Error + 'static), language: Language) -> String {
+    if let Some(error) = error.downcast_ref::<DexError>() {
+        return dex_error_message(*error, language).to_string();
+    }
+    if let Some(error) = error.downcast_ref::<FormatError>() {
+        return format_error_message(error, language);
+    }
+    if let Some(error) = error
+        .downcast_ref::<ClientError>()
+        .and_then(|error| error.get_transaction_error())
+        .as_ref()
+        .and_then(dex_error_of_transaction)
+    {
+        return dex_error_message(error, language).to_string();
+    }
+
+    error.to_string()
+}
@@ -0,0 +1,156 @@
+// CSV export and import of resting orders for liquidity migration
+// 用于流动性迁移的挂单 CSV 导出与导入
+
+use crate::preflight::{self, OrderIntent, PreflightIssue};
+use solana_program::pubkey::Pubkey;
+use solana_rust_dex::state::{Market, Order};
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Header line of the order CSV format
+pub const ORDER_CSV_HEADER: &str = "market,order,order_id,side,limit_price,quantity,display_quantity";
+
+/// Error reading an order CSV file
+#[derive(Debug, Error, PartialEq)]
+pub enum OrderCsvError {
+    /// First line is not the expected header
+    #[error("Invalid order CSV header")]
+    InvalidHeader,
+
+    /// Line does not hold a valid order row
+    #[error("Invalid order CSV line {line}: {message}")]
+    InvalidRow {
+        /// Line number, starting at 1 for the header
+        line: usize,
+        /// What is wrong with the line
+        message: String,
+    },
+}
+
+/// Resting order as exported to CSV
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderRow {
+    /// Market the order rests on
+    pub market: Pubkey,
+
+    /// Order account
+    pub order: Pubkey,
+
+    /// ID of the order in its market
+    pub order_id: u64,
+
+    /// Side of the order (true for buy, false for sell)
+    pub is_buy: bool,
+
+    /// Limit price in quote lots per base lot
+    pub limit_price: u64,
+
+    /// Remaining quantity in base lots
+    pub quantity: u64,
+
+    /// Visible slice of an iceberg order, 0 for a plain limit order
+    pub display_quantity: u64,
+}
+
+impl OrderRow {
+    /// Row of a resting order account
+    pub fn from_order(order_pubkey: &Pubkey, order: &Order) -> Self {
+        Self {
+            market: order.market,
+            order: *order_pubkey,
+            order_id: order.order_id,
+            is_buy: order.is_buy,
+            limit_price: order.limit_price,
+            quantity: order.remaining_quantity,
+            display_quantity: if order.is_iceberg() { order.display_quantity } else { 0 },
+        }
+    }
+
+    /// Display quantity to re-place the order with, if it is an iceberg order
+    pub fn iceberg_display_quantity(&self) -> Option<u64> {
+        Some(self.display_quantity).filter(|display_quantity| *display_quantity > 0)
+    }
+
+    /// Re-validate the order against the market it is re-placed on
+    ///
+    /// Prices are not rounded to a different tick size, so no order is re-placed at a price
+    /// its owner did not choose.
+    pub fn revalidate(&self, market: &Market) -> Result<OrderIntent, Vec<PreflightIssue>> {
+        let intent = OrderIntent {
+            is_buy: self.is_buy,
+            limit_price: self.limit_price,
+            quantity: self.quantity,
+        };
+        let issues = preflight::check_market_parameters(market, &intent);
+        if issues.is_empty() {
+            Ok(intent)
+        } else {
+            Err(issues)
+        }
+    }
+
+    fn to_csv_line(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{}",
+            self.market,
+            self.order,
+            self.order_id,
+            if self.is_buy { "buy" } else { "sell" },
+            self.limit_price,
+            self.quantity,
+            self.display_quantity,
+        )
+    }
+
+    fn from_csv_line(line: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        if fields.len() != 7 {
+            return Err(format!("expected 7 fields, found {}", fields.len()));
+        }
+        let pubkey = |field: &str| Pubkey::from_str(field).map_err(|_| format!("invalid pubkey {}", field));
+        let number = |field: &str| field.parse::<u64>().map_err(|_| format!("invalid number {}", field));
+
+        Ok(Self {
+            market: pubkey(fields[0])?,
+            order: pubkey(fields[1])?,
+            order_id: number(fields[2])?,
+            is_buy: match fields[3] {
+                "buy" => true,
+                "sell" => false,
+                side => return Err(format!("invalid side {}", side)),
+            },
+            limit_price: number(fields[4])?,
+            quantity: number(fields[5])?,
+            display_quantity: number(fields[6])?,
+        })
+    }
+}
+
+/// Write order rows as CSV, header first
+pub fn write_order_csv(rows: &[OrderRow]) -> String {
+    let mut csv = format!("{}\n", ORDER_CSV_HEADER);
+    for row in rows {
+        csv.push_str(&row.to_csv_line());
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Read the order rows of a CSV file written by `write_order_csv`
+pub fn read_order_csv(csv: &str) -> Result<Vec<OrderRow>, OrderCsvError> {
+    let mut lines = csv.lines().enumerate();
+    match lines.next() {
+        Some((_, header)) if header.trim() == ORDER_CSV_HEADER => {}
+        _ => return Err(OrderCsvError::InvalidHeader),
+    }
+
+    lines
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            OrderRow::from_csv_line(line).map_err(|message| OrderCsvError::InvalidRow {
+                line: index + 1,
+                message,
+            })
+        })
+        .collect()
+}
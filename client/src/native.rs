@@ -0,0 +1,85 @@
+// Native SOL wrapping
+// 原生 SOL 的包装与解包
+//
+// The program only moves SPL tokens, so a market trading SOL uses the wrapped SOL mint. The
+// owner's wrapped SOL lives in the associated token account of the native mint: SOL is wrapped
+// by transferring lamports into it and syncing its token balance, and unwrapped by closing it,
+// which returns every lamport it holds to the owner. Wrapped SOL has no partial unwrap, so closing
+// the account also unwraps whatever the owner wrapped before or through other tools.
+
+use solana_program::{instruction::Instruction, program_error::ProgramError, pubkey::Pubkey, system_instruction};
+use solana_rust_dex::state::Market;
+use spl_associated_token_account::{
+    get_associated_token_address_with_program_id, instruction::create_associated_token_account_idempotent,
+};
+
+/// Check if a mint is the native mint of SPL Token or Token-2022
+pub fn is_native_mint(mint: &Pubkey) -> bool {
+    *mint == spl_token::native_mint::id() || *mint == spl_token_2022::native_mint::id()
+}
+
+/// Check if the base or quote side of a market is wrapped SOL
+pub fn is_native_side(market: &Market, is_quote: bool) -> bool {
+    is_native_mint(market.mint(is_quote))
+}
+
+/// Native mint of a token program
+pub fn native_mint(token_program: &Pubkey) -> Pubkey {
+    if *token_program == spl_token_2022::id() {
+        spl_token_2022::native_mint::id()
+    } else {
+        spl_token::native_mint::id()
+    }
+}
+
+/// Wrapped SOL account of an owner: its associated token account of the native mint
+pub fn wrapped_sol_account(owner: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(owner, &native_mint(token_program), token_program)
+}
+
+/// Instructions wrapping lamports of the owner into its existing wrapped SOL account
+pub fn wrap_instructions(
+    owner: &Pubkey,
+    token_program: &Pubkey,
+    lamports: u64,
+) -> Result<Vec<Instruction>, ProgramError> {
+    let account = wrapped_sol_account(owner, token_program);
+    Ok(vec![
+        system_instruction::transfer(owner, &account, lamports),
+        spl_token_2022::instruction::sync_native(token_program, &account)?,
+    ])
+}
+
+/// Instructions creating the owner's wrapped SOL account if needed, paid by the payer, and
+/// wrapping lamports of the owner into it
+pub fn create_and_wrap_instructions(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    token_program: &Pubkey,
+    lamports: u64,
+) -> Result<Vec<Instruction>, ProgramError> {
+    let mut instructions = vec![create_associated_token_account_idempotent(
+        payer,
+        owner,
+        &native_mint(token_program),
+        token_program,
+    )];
+    instructions.extend(wrap_instructions(owner, token_program, lamports)?);
+    Ok(instructions)
+}
+
+/// Instruction unwrapping all of the owner's wrapped SOL by closing its wrapped SOL account
+///
+/// This sweeps the whole balance, not just what this client wrapped: wrapped SOL the owner kept
+/// in the account before, or received from other tools, is unwrapped with it. Settlements and
+/// refunds pay into this account, so it should only be closed once no order of the owner rests
+/// on a market trading SOL.
+pub fn unwrap_instruction(owner: &Pubkey, token_program: &Pubkey) -> Result<Instruction, ProgramError> {
+    spl_token_2022::instruction::close_account(
+        token_program,
+        &wrapped_sol_account(owner, token_program),
+        owner,
+        owner,
+        &[],
+    )
+}
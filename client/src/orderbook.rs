@@ -0,0 +1,341 @@
+// Order book views built from order accounts
+//
+// Prices and quantities are kept in the market's lots, as stored on the order accounts;
+// quote amounts of quotes are in quote tokens.
+
+use solana_program::pubkey::Pubkey;
+use solana_rust_dex::state::Order;
+use std::{cmp::Reverse, collections::BTreeMap};
+
+/// Aggregate size resting at one price
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceLevel {
+    /// Limit price in quote lots per base lot
+    pub price: u64,
+
+    /// Total displayed quantity in base lots
+    pub quantity: u64,
+
+    /// Number of orders at this price
+    pub num_orders: usize,
+}
+
+/// Order book of a single market with the best prices first
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderBook {
+    /// Market the book belongs to
+    pub market: Pubkey,
+
+    /// Bid levels, highest price first
+    pub bids: Vec<PriceLevel>,
+
+    /// Ask levels, lowest price first
+    pub asks: Vec<PriceLevel>,
+
+    /// Taker fee rate of the market in basis points
+    pub taker_fee_bps: u16,
+
+    /// Quote tokens in one quote lot of the market
+    pub quote_lot_size: u64,
+}
+
+impl OrderBook {
+    /// Build a book from the open orders of a market
+    ///
+    /// Only the displayed tranche of iceberg orders is counted.
+    pub fn from_orders(market: Pubkey, orders: &[Order]) -> Self {
+        let mut bids: BTreeMap<u64, PriceLevel> = BTreeMap::new();
+        let mut asks: BTreeMap<u64, PriceLevel> = BTreeMap::new();
+
+        for order in orders {
+            if !order.is_initialized || order.market != market || order.visible_quantity == 0 {
+                continue;
+            }
+
+            let side = if order.is_buy { &mut bids } else { &mut asks };
+            let level = side.entry(order.limit_price).or_insert(PriceLevel {
+                price: order.limit_price,
+                quantity: 0,
+                num_orders: 0,
+            });
+            level.quantity = level.quantity.saturating_add(order.visible_quantity);
+            level.num_orders += 1;
+        }
+
+        Self {
+            market,
+            bids: bids.into_values().rev().collect(),
+            asks: asks.into_values().collect(),
+            taker_fee_bps: 0,
+            quote_lot_size: 1,
+        }
+    }
+
+    /// Set the taker fee rate used for fee-inclusive quotes
+    pub fn with_taker_fee_bps(mut self, taker_fee_bps: u16) -> Self {
+        self.taker_fee_bps = taker_fee_bps;
+        self
+    }
+
+    /// Set the quote lot size used to turn prices into quote amounts
+    pub fn with_quote_lot_size(mut self, quote_lot_size: u64) -> Self {
+        self.quote_lot_size = quote_lot_size;
+        self
+    }
+}
+
+/// Size contributed by one market to an aggregated price level
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarketDepth {
+    /// Market holding the liquidity
+    pub market: Pubkey,
+
+    /// Remaining quantity in base lots
+    pub quantity: u64,
+
+    /// Taker fee rate of the market in basis points
+    pub taker_fee_bps: u16,
+
+    /// Quote tokens in one quote lot of the market
+    pub quote_lot_size: u64,
+}
+
+/// Price level merged across markets with per-market attribution
+#[derive(Debug, Clone, PartialEq)]
+pub struct AggregatedPriceLevel {
+    /// Limit price in quote lots per base lot
+    pub price: u64,
+
+    /// Total remaining quantity across all markets
+    pub quantity: u64,
+
+    /// Per-market breakdown of the quantity
+    pub sources: Vec<MarketDepth>,
+}
+
+/// Portion of a taker order routed to one market
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteLeg {
+    /// Market to trade on
+    pub market: Pubkey,
+
+    /// Quantity in base lots to take on this market
+    pub quantity: u64,
+
+    /// Worst price reached on this market, usable as the leg's limit price
+    pub limit_price: u64,
+}
+
+/// Whether quoted prices and amounts include taker fees
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FeeConvention {
+    /// Prices as shown on the book, fees reported separately
+    FeeExclusive,
+    /// Buys include the fee in the cost and sells deduct it from the proceeds
+    FeeInclusive,
+}
+
+/// Expected outcome of a taker order against a merged book
+///
+/// Fees are estimated as if each leg settled at once.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quote {
+    /// Side of the taker order (true for buy)
+    pub is_buy: bool,
+
+    /// Base lots filled
+    pub base_quantity: u64,
+
+    /// Quote token amount traded, excluding fees
+    pub quote_amount: u64,
+
+    /// Taker fees in quote tokens
+    pub fee: u64,
+
+    /// Portions of the order routed to each market
+    pub legs: Vec<RouteLeg>,
+}
+
+impl Quote {
+    /// Quote tokens paid for a buy or received for a sell under a fee convention
+    pub fn quote_total(&self, convention: FeeConvention) -> u64 {
+        match (convention, self.is_buy) {
+            (FeeConvention::FeeExclusive, _) => self.quote_amount,
+            (FeeConvention::FeeInclusive, true) => self.quote_amount.saturating_add(self.fee),
+            (FeeConvention::FeeInclusive, false) => self.quote_amount.saturating_sub(self.fee),
+        }
+    }
+}
+
+/// Merged depth of several markets trading the same pair
+#[derive(Debug, Clone, PartialEq)]
+pub struct AggregatedOrderBook {
+    /// Markets included in the view
+    pub markets: Vec<Pubkey>,
+
+    /// Bid levels, highest price first
+    pub bids: Vec<AggregatedPriceLevel>,
+
+    /// Ask levels, lowest price first
+    pub asks: Vec<AggregatedPriceLevel>,
+}
+
+impl AggregatedOrderBook {
+    /// Merge single-market books into one view
+    pub fn merge(books: &[OrderBook]) -> Self {
+        let mut bids: BTreeMap<u64, AggregatedPriceLevel> = BTreeMap::new();
+        let mut asks: BTreeMap<u64, AggregatedPriceLevel> = BTreeMap::new();
+
+        for book in books {
+            Self::merge_side(&mut bids, book, &book.bids);
+            Self::merge_side(&mut asks, book, &book.asks);
+        }
+
+        Self {
+            markets: books.iter().map(|book| book.market).collect(),
+            bids: bids.into_values().rev().collect(),
+            asks: asks.into_values().collect(),
+        }
+    }
+
+    /// Split a taker order across markets, consuming the best prices first
+    ///
+    /// Buy orders walk the asks and sell orders walk the bids. The returned legs
+    /// may add up to less than `quantity` when the merged book is too shallow.
+    pub fn route(&self, is_buy: bool, quantity: u64) -> Vec<RouteLeg> {
+        self.quote(is_buy, quantity, FeeConvention::FeeExclusive).legs
+    }
+
+    /// Quote a taker order of `quantity` base lots
+    ///
+    /// With `FeeInclusive` liquidity is consumed in order of fee-adjusted price, so a
+    /// cheaper market with a higher fee may be skipped for a better all-in fill.
+    pub fn quote(&self, is_buy: bool, quantity: u64, convention: FeeConvention) -> Quote {
+        let mut legs: Vec<RouteLeg> = Vec::new();
+        let mut leg_values: Vec<(u64, u16)> = Vec::new();
+        let mut remaining = quantity;
+
+        for (price, source) in self.fills(is_buy, convention) {
+            if remaining == 0 {
+                break;
+            }
+
+            let take = remaining.min(source.quantity);
+            remaining -= take;
+            let value = price.saturating_mul(take).saturating_mul(source.quote_lot_size);
+
+            match legs.iter().position(|leg| leg.market == source.market) {
+                Some(index) => {
+                    legs[index].quantity += take;
+                    legs[index].limit_price = price;
+                    leg_values[index].0 = leg_values[index].0.saturating_add(value);
+                }
+                None => {
+                    legs.push(RouteLeg {
+                        market: source.market,
+                        quantity: take,
+                        limit_price: price,
+                    });
+                    leg_values.push((value, source.taker_fee_bps));
+                }
+            }
+        }
+
+        // Same rounding as the on-chain taker fee
+        let fee = leg_values
+            .iter()
+            .map(|&(value, taker_fee_bps)| (value as u128 * taker_fee_bps as u128 / 10000) as u64)
+            .fold(0u64, u64::saturating_add);
+
+        Quote {
+            is_buy,
+            base_quantity: quantity - remaining,
+            quote_amount: leg_values.iter().map(|&(value, _)| value).fold(0, u64::saturating_add),
+            fee,
+            legs,
+        }
+    }
+
+    /// Size a taker order by its quote token amount
+    ///
+    /// Buys fill as much as `quote_amount` can pay for, sells fill just enough to receive
+    /// at least `quote_amount`, either amount counted under `convention`. The returned
+    /// quote may fall short of the amount when the merged book is too shallow.
+    pub fn quote_for_amount(&self, is_buy: bool, quote_amount: u64, convention: FeeConvention) -> Quote {
+        let depth = self
+            .fills(is_buy, convention)
+            .iter()
+            .map(|(_, source)| source.quantity)
+            .fold(0u64, u64::saturating_add);
+
+        // The quote total grows with the quantity, so binary search for the boundary
+        let (mut low, mut high) = (0, depth);
+        if is_buy {
+            // Largest quantity whose cost fits the amount
+            while low < high {
+                let mid = low + (high - low).div_ceil(2);
+                if self.quote(is_buy, mid, convention).quote_total(convention) <= quote_amount {
+                    low = mid;
+                } else {
+                    high = mid - 1;
+                }
+            }
+        } else {
+            // Smallest quantity whose proceeds reach the amount
+            while low < high {
+                let mid = low + (high - low) / 2;
+                if self.quote(is_buy, mid, convention).quote_total(convention) >= quote_amount {
+                    high = mid;
+                } else {
+                    low = mid + 1;
+                }
+            }
+        }
+
+        self.quote(is_buy, low, convention)
+    }
+
+    // Liquidity available to a taker, best first under the fee convention
+    fn fills(&self, is_buy: bool, convention: FeeConvention) -> Vec<(u64, &MarketDepth)> {
+        let levels = if is_buy { &self.asks } else { &self.bids };
+        let mut fills: Vec<(u64, &MarketDepth)> = levels
+            .iter()
+            .flat_map(|level| level.sources.iter().map(move |source| (level.price, source)))
+            .collect();
+
+        // Stable sorts keep price-then-market order among equal all-in prices
+        if convention == FeeConvention::FeeInclusive {
+            if is_buy {
+                fills.sort_by_key(|(price, source)| *price as u128 * (10000 + source.taker_fee_bps as u128));
+            } else {
+                fills.sort_by_key(|(price, source)| {
+                    Reverse(*price as u128 * 10000u128.saturating_sub(source.taker_fee_bps as u128))
+                });
+            }
+        }
+
+        fills
+    }
+
+    // Add one market's levels to a merged side
+    fn merge_side(
+        merged: &mut BTreeMap<u64, AggregatedPriceLevel>,
+        book: &OrderBook,
+        levels: &[PriceLevel],
+    ) {
+        for level in levels {
+            let merged_level = merged.entry(level.price).or_insert(AggregatedPriceLevel {
+                price: level.price,
+                quantity: 0,
+                sources: Vec::new(),
+            });
+            merged_level.quantity = merged_level.quantity.saturating_add(level.quantity);
+            merged_level.sources.push(MarketDepth {
+                market: book.market,
+                quantity: level.quantity,
+                taker_fee_bps: book.taker_fee_bps,
+                quote_lot_size: book.quote_lot_size,
+            });
+        }
+    }
+}
@@ -0,0 +1,240 @@
+// Pre-flight checks for order placement
+// 下单前的预检
+
+use crate::native;
+use solana_program::{instruction::Instruction, pubkey::Pubkey};
+use solana_rust_dex::state::{Market, MarketStatus};
+use spl_associated_token_account::{
+    get_associated_token_address_with_program_id, instruction::create_associated_token_account_idempotent,
+};
+use spl_token::state::Account as TokenAccount;
+
+/// Order an owner intends to place
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrderIntent {
+    /// Side of the order (true for buy, false for sell)
+    pub is_buy: bool,
+
+    /// Limit price in quote lots per base lot
+    pub limit_price: u64,
+
+    /// Order quantity in base lots
+    pub quantity: u64,
+}
+
+/// Account state of an owner relevant to placing an order
+#[derive(Debug, Clone, PartialEq)]
+pub struct OwnerState {
+    /// Order owner
+    pub owner: Pubkey,
+
+    /// Owner's associated token account of the mint the order locks, if it exists
+    pub token_account: Option<TokenAccount>,
+
+    /// Lamports held by the owner
+    pub lamports: u64,
+
+    /// Rent-exempt balance of a new order account
+    pub order_rent_lamports: u64,
+}
+
+/// Prerequisite of order placement that is not met
+#[derive(Debug, Clone, PartialEq)]
+pub enum PreflightIssue {
+    /// Market does not accept new orders
+    MarketNotActive(MarketStatus),
+
+    /// Quantity below the market's minimum order size
+    QuantityBelowMinimum {
+        /// Minimum base order size
+        min_base_order_size: u64,
+    },
+
+    /// Price not a multiple of the market's tick size
+    PriceNotOnTick {
+        /// Tick size of the market
+        tick_size: u64,
+    },
+
+    /// Notional in quote tokens below the market's minimum
+    NotionalBelowMinimum {
+        /// Minimum quote notional
+        min_quote_notional: u64,
+    },
+
+    /// Associated token account of the locked mint does not exist
+    MissingTokenAccount {
+        /// Mint the order locks
+        mint: Pubkey,
+        /// Address of the missing associated token account
+        address: Pubkey,
+    },
+
+    /// Token balance below the amount locked plus the worst-case taker fee
+    InsufficientTokenBalance {
+        /// Mint the order locks
+        mint: Pubkey,
+        /// Amount required
+        required: u64,
+        /// Amount available
+        available: u64,
+    },
+
+    /// Lamports below the rent of the new order account
+    InsufficientLamports {
+        /// Lamports required
+        required: u64,
+        /// Lamports available
+        available: u64,
+    },
+}
+
+/// Issue found by the pre-flight check together with the instructions that fix it
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreflightStep {
+    /// Unmet prerequisite
+    pub issue: PreflightIssue,
+
+    /// Instructions resolving the issue; empty when the owner has to act, e.g. by funding the account
+    pub fix: Vec<Instruction>,
+}
+
+/// Result of a pre-flight check
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PreflightReport {
+    /// Missing steps in the order they should be resolved
+    pub steps: Vec<PreflightStep>,
+}
+
+impl PreflightReport {
+    /// Check if the order can be placed as is
+    pub fn is_ready(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Check if every missing step has ready-built fix instructions
+    pub fn is_fixable(&self) -> bool {
+        self.steps.iter().all(|step| !step.fix.is_empty())
+    }
+
+    /// Fix instructions of all steps, in order
+    pub fn fix_instructions(&self) -> Vec<Instruction> {
+        self.steps.iter().flat_map(|step| step.fix.iter().cloned()).collect()
+    }
+}
+
+/// Mint an order locks: quote tokens for buys, base tokens for sells
+pub fn locked_mint(market: &Market, is_buy: bool) -> Pubkey {
+    if is_buy {
+        market.quote_mint
+    } else {
+        market.base_mint
+    }
+}
+
+/// Check an order against the market's status, minimum order size and notional, and tick size
+pub fn check_market_parameters(market: &Market, intent: &OrderIntent) -> Vec<PreflightIssue> {
+    let mut issues = Vec::new();
+    if market.status != MarketStatus::Active {
+        issues.push(PreflightIssue::MarketNotActive(market.status));
+    }
+    if intent.quantity < market.min_base_order_size {
+        issues.push(PreflightIssue::QuantityBelowMinimum {
+            min_base_order_size: market.min_base_order_size,
+        });
+    }
+    if intent.limit_price.checked_rem(market.tick_size) != Some(0) {
+        issues.push(PreflightIssue::PriceNotOnTick {
+            tick_size: market.tick_size,
+        });
+    }
+    // A notional that does not fit in a u64 is above any minimum
+    if market
+        .notional(intent.limit_price, intent.quantity)
+        .is_ok_and(|notional| notional < market.min_quote_notional)
+    {
+        issues.push(PreflightIssue::NotionalBelowMinimum {
+            min_quote_notional: market.min_quote_notional,
+        });
+    }
+    issues
+}
+
+/// Check every prerequisite of placing an order
+///
+/// Buy orders lock the quote amount plus the taker fee on each unit, which covers the fee they pay
+/// if they take liquidity.
+/// Missing token accounts are created by the payer. When the order locks wrapped SOL and the
+/// payer is the owner, a short balance is wrapped from the owner's lamports beyond the order rent.
+pub fn check_order(
+    market: &Market,
+    intent: &OrderIntent,
+    owner_state: &OwnerState,
+    payer: &Pubkey,
+) -> PreflightReport {
+    let mut steps = Vec::new();
+    let mut issue = |issue: PreflightIssue, fix: Vec<Instruction>| steps.push(PreflightStep { issue, fix });
+
+    // Market parameters
+    for market_issue in check_market_parameters(market, intent) {
+        issue(market_issue, Vec::new());
+    }
+
+    // Token account of the locked mint
+    let mint = locked_mint(market, intent.is_buy);
+    if owner_state.token_account.is_none() {
+        issue(
+            PreflightIssue::MissingTokenAccount {
+                mint,
+                address: get_associated_token_address_with_program_id(
+                    &owner_state.owner,
+                    &mint,
+                    market.token_program(intent.is_buy),
+                ),
+            },
+            vec![create_associated_token_account_idempotent(
+                payer,
+                &owner_state.owner,
+                &mint,
+                market.token_program(intent.is_buy),
+            )],
+        );
+    }
+
+    // Balance including fees; an amount that does not fit in a u64 can never be funded
+    let required = market
+        .escrow_amount(intent.is_buy, intent.limit_price, intent.quantity)
+        .unwrap_or(u64::MAX);
+    let available = owner_state.token_account.as_ref().map_or(0, |account| account.amount);
+    if available < required {
+        let shortfall = required - available;
+        let spare_lamports = owner_state.lamports.saturating_sub(owner_state.order_rent_lamports);
+        let fix = if native::is_native_mint(&mint) && *payer == owner_state.owner && shortfall <= spare_lamports {
+            native::wrap_instructions(&owner_state.owner, market.token_program(intent.is_buy), shortfall)
+                .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        issue(
+            PreflightIssue::InsufficientTokenBalance {
+                mint,
+                required,
+                available,
+            },
+            fix,
+        );
+    }
+
+    // Rent of the order account
+    if owner_state.lamports < owner_state.order_rent_lamports {
+        issue(
+            PreflightIssue::InsufficientLamports {
+                required: owner_state.order_rent_lamports,
+                available: owner_state.lamports,
+            },
+            Vec::new(),
+        );
+    }
+
+    PreflightReport { steps }
+}
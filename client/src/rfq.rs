@@ -0,0 +1,82 @@
+// Signed firm quotes exchanged between makers and takers off-chain
+// 做市商与吃单方之间链下传递的已签名确定报价
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{instruction::Instruction, pubkey::Pubkey};
+use solana_rust_dex::{ed25519, state::FirmQuote};
+use solana_sdk::signature::{Signature, Signer, SignerError};
+use thiserror::Error;
+
+/// Error decoding a signed quote
+#[derive(Debug, Error, PartialEq)]
+pub enum SignedQuoteError {
+    /// Input is not an even-length hex string
+    #[error("Invalid hex encoding")]
+    InvalidHex,
+
+    /// Decoded bytes are not a quote followed by a signature
+    #[error("Invalid signed quote")]
+    InvalidQuote,
+}
+
+/// Firm quote together with its maker's signature
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignedQuote {
+    /// Quote terms
+    pub quote: FirmQuote,
+
+    /// Maker's ed25519 signature over the quote's signing message
+    pub signature: Signature,
+}
+
+impl SignedQuote {
+    /// Sign a quote as its maker for a program deployment
+    pub fn sign(maker: &dyn Signer, program_id: &Pubkey, quote: FirmQuote) -> Result<Self, SignerError> {
+        let signature = maker.try_sign_message(&quote.signing_message(program_id))?;
+        Ok(Self { quote, signature })
+    }
+
+    /// Check that the signature was made by the quote's maker
+    pub fn verify(&self, program_id: &Pubkey) -> bool {
+        self.signature
+            .verify(self.quote.maker.as_ref(), &self.quote.signing_message(program_id))
+    }
+
+    /// Ed25519 program instruction proving the signature to `ExecuteFirmQuote`
+    pub fn ed25519_instruction(&self, program_id: &Pubkey) -> Instruction {
+        let signature: &[u8; ed25519::SIGNATURE_LEN] = self.signature.as_ref().try_into().unwrap();
+        ed25519::new_ed25519_instruction(&self.quote.maker, signature, &self.quote.signing_message(program_id))
+    }
+
+    /// Encode as hex: the borsh-serialized quote followed by the 64-byte signature
+    pub fn to_hex(&self) -> String {
+        let mut bytes = self.quote.try_to_vec().unwrap();
+        bytes.extend_from_slice(self.signature.as_ref());
+        bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    /// Decode the hex encoding produced by `to_hex`
+    pub fn from_hex(encoded: &str) -> Result<Self, SignedQuoteError> {
+        if !encoded.len().is_multiple_of(2) {
+            return Err(SignedQuoteError::InvalidHex);
+        }
+        let bytes = (0..encoded.len())
+            .step_by(2)
+            .map(|i| {
+                encoded
+                    .get(i..i + 2)
+                    .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                    .ok_or(SignedQuoteError::InvalidHex)
+            })
+            .collect::<Result<Vec<u8>, _>>()?;
+
+        let split = bytes
+            .len()
+            .checked_sub(ed25519::SIGNATURE_LEN)
+            .ok_or(SignedQuoteError::InvalidQuote)?;
+        let quote = FirmQuote::try_from_slice(&bytes[..split]).map_err(|_| SignedQuoteError::InvalidQuote)?;
+        let signature = Signature::try_from(&bytes[split..]).map_err(|_| SignedQuoteError::InvalidQuote)?;
+
+        Ok(Self { quote, signature })
+    }
+}
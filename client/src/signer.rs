@@ -0,0 +1,57 @@
+// Signer abstraction for local and remote signing
+// 本地和远程签名的签名者抽象
+//
+// Every DexClient method takes `&dyn Signer`, so local keypairs and custodial services plugged
+// in through `RemoteSigner` can be mixed in one transaction. The CLI only reads keypair files.
+
+use solana_program::pubkey::Pubkey;
+use solana_sdk::{
+    signature::Signature,
+    signer::{Signer, SignerError},
+};
+
+/// Service holding a private key outside the client, e.g. an HTTP signing service or a KMS
+pub trait SigningService {
+    /// Sign a serialized transaction message with the key of `pubkey`
+    fn sign_message(&self, pubkey: &Pubkey, message: &[u8]) -> Result<Signature, SignerError>;
+}
+
+/// Signer that delegates signing to a `SigningService`
+pub struct RemoteSigner<S> {
+    /// Public key the service signs for
+    pubkey: Pubkey,
+
+    /// Service producing the signatures
+    service: S,
+}
+
+impl<S: SigningService> RemoteSigner<S> {
+    /// Create a signer for a key held by a signing service
+    pub fn new(pubkey: Pubkey, service: S) -> Self {
+        Self { pubkey, service }
+    }
+}
+
+impl<S: SigningService> Signer for RemoteSigner<S> {
+    fn try_pubkey(&self) -> Result<Pubkey, SignerError> {
+        Ok(self.pubkey)
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature, SignerError> {
+        let signature = self.service.sign_message(&self.pubkey, message)?;
+
+        // A misconfigured service fails here instead of when the cluster rejects the transaction
+        if !signature.verify(self.pubkey.as_ref(), message) {
+            return Err(SignerError::Custom(format!(
+                "Signing service returned an invalid signature for {}",
+                self.pubkey
+            )));
+        }
+
+        Ok(signature)
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,79 @@
+// Wormhole-wrapped assets
+// Wormhole 包装资产
+//
+// Tokens bridged to Solana through the Wormhole token bridge are minted by the bridge at a PDA
+// derived from their origin chain and address, and the bridge records that origin in a wrapped
+// meta account next to the mint. A market quoted in a wrapped asset trades it like any other
+// SPL token; these helpers tell which side of a market is wrapped and where it comes from, and
+// check the attestation against the mint so a look-alike mint is not taken for the bridged one.
+
+use borsh::BorshDeserialize;
+use solana_program::{pubkey, pubkey::Pubkey};
+
+/// Wormhole token bridge program on mainnet
+pub const TOKEN_BRIDGE_PROGRAM_ID: Pubkey = pubkey!("wormDTUJ6AWPNvk59vGQbDvGJmqbDTdgWgAqcLBCgUb");
+
+/// Wormhole token bridge program on devnet
+pub const TOKEN_BRIDGE_DEVNET_PROGRAM_ID: Pubkey = pubkey!("DZnkkTmCiFWfYTfT41X3Rd1kDgozqzxWaHqsw6W4x2oe");
+
+/// Wormhole chain id of Solana
+pub const CHAIN_ID_SOLANA: u16 = 1;
+
+/// Wormhole chain id of Ethereum
+pub const CHAIN_ID_ETHEREUM: u16 = 2;
+
+/// Seed of the token bridge's wrapped mint PDAs
+const WRAPPED_SEED: &[u8] = b"wrapped";
+
+/// Seed of the token bridge's wrapped meta PDAs
+const META_SEED: &[u8] = b"meta";
+
+/// Origin of a wrapped asset, as attested by the token bridge's wrapped meta account
+#[derive(BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WrappedAsset {
+    /// Wormhole chain id of the origin chain
+    pub chain: u16,
+    /// Token address on the origin chain, left-padded to 32 bytes
+    pub token_address: [u8; 32],
+    /// Decimals of the token on the origin chain
+    pub original_decimals: u8,
+}
+
+impl WrappedAsset {
+    /// Decode a wrapped meta account
+    pub fn unpack(data: &[u8]) -> Option<Self> {
+        Self::deserialize(&mut &data[..]).ok()
+    }
+
+    /// Mint the token bridge wraps this asset in
+    pub fn mint(&self, token_bridge: &Pubkey) -> Pubkey {
+        wrapped_mint_address(token_bridge, self.chain, &self.token_address)
+    }
+
+    /// Check that the attestation is the one of `mint`
+    pub fn attests(&self, token_bridge: &Pubkey, mint: &Pubkey) -> bool {
+        self.mint(token_bridge) == *mint
+    }
+
+    /// Origin token address as hex, with the 12 bytes of padding dropped for 20-byte EVM
+    /// addresses
+    pub fn token_address_hex(&self) -> String {
+        let bytes = if self.token_address[..12].iter().all(|byte| *byte == 0) && self.chain != CHAIN_ID_SOLANA {
+            &self.token_address[12..]
+        } else {
+            &self.token_address[..]
+        };
+        let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+        format!("0x{}", hex)
+    }
+}
+
+/// Mint the token bridge wraps a token of another chain in
+pub fn wrapped_mint_address(token_bridge: &Pubkey, chain: u16, token_address: &[u8; 32]) -> Pubkey {
+    Pubkey::find_program_address(&[WRAPPED_SEED, &chain.to_be_bytes(), token_address], token_bridge).0
+}
+
+/// Wrapped meta account recording the origin of a wrapped mint
+pub fn wrapped_meta_address(token_bridge: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[META_SEED, mint.as_ref()], token_bridge).0
+}
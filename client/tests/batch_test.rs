@@ -0,0 +1,97 @@
+// Transaction batching test module
+// 交易批处理测试模块
+
+#[cfg(test)]
+mod batch_tests {
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+    };
+    use solana_rust_dex_client::{batch::TransactionBatcher, DexInstructions, TransactionOptions};
+    use solana_sdk::packet::PACKET_DATA_SIZE;
+
+    fn operation(program_id: &Pubkey, signer: &Pubkey, data_len: usize) -> DexInstructions {
+        let accounts = vec![
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new(Pubkey::new_unique(), false),
+        ];
+        DexInstructions {
+            instructions: vec![Instruction::new_with_bytes(*program_id, &vec![7; data_len], accounts)],
+            signers: vec![*signer],
+        }
+    }
+
+    #[test]
+    fn test_pack_fills_transactions_in_order() {
+        let program_id = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut batcher = TransactionBatcher::new(Pubkey::new_unique());
+        for _ in 0..10 {
+            batcher.add(operation(&program_id, &owner, 300));
+        }
+
+        // Operations are packed in order, each transaction fitting in a packet
+        // 操作按顺序打包，每笔交易都能放入一个数据包
+        let (batches, oversized) = batcher.pack();
+        assert!(oversized.is_empty());
+        assert!(batches.len() > 1 && batches.len() < 10);
+        let indices: Vec<usize> = batches.iter().flat_map(|batch| batch.operations.clone()).collect();
+        assert_eq!(indices, (0..10).collect::<Vec<_>>());
+        for batch in &batches {
+            assert!(batcher.transaction_size(&batch.instructions) <= PACKET_DATA_SIZE);
+            assert_eq!(batch.instructions.len(), batch.operations.len());
+        }
+
+        // A shared signer is listed once per transaction
+        // 共同的签名者在每笔交易中只列出一次
+        assert!(batches.iter().all(|batch| batch.signers == vec![owner]));
+
+        // No two consecutive transactions could have been merged
+        // 相邻的两笔交易都无法合并
+        for pair in batches.windows(2) {
+            let mut instructions = pair[0].instructions.clone();
+            instructions.push(pair[1].instructions[0].clone());
+            assert!(batcher.transaction_size(&instructions) > PACKET_DATA_SIZE);
+        }
+    }
+
+    #[test]
+    fn test_pack_counts_signatures_and_options() {
+        let program_id = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let instructions = operation(&program_id, &owner, 100).instructions;
+
+        // Each signer adds a signature to the transaction
+        // 每个签名者都会为交易增加一个签名
+        let batcher = TransactionBatcher::new(payer);
+        let paid_by_owner = TransactionBatcher::new(owner);
+        assert_eq!(
+            batcher.transaction_size(&instructions),
+            paid_by_owner.transaction_size(&instructions) + 64 + 32
+        );
+
+        // The memo of the transaction options is counted
+        // 交易选项中的备注也计入大小
+        let with_memo = TransactionBatcher::new(payer).with_transaction_options(TransactionOptions::default().with_memo("desk-7"));
+        assert!(with_memo.transaction_size(&instructions) > batcher.transaction_size(&instructions));
+    }
+
+    #[test]
+    fn test_pack_reports_oversized_operations() {
+        let program_id = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut batcher = TransactionBatcher::new(Pubkey::new_unique());
+        batcher.add(operation(&program_id, &owner, 100));
+        batcher.add(operation(&program_id, &owner, PACKET_DATA_SIZE));
+        batcher.add(operation(&program_id, &owner, 100));
+
+        // An operation too large for any transaction is reported, the others are still packed
+        // 任何交易都放不下的操作会被报告，其他操作照常打包
+        let (batches, oversized) = batcher.pack();
+        assert_eq!(oversized.len(), 1);
+        assert_eq!(oversized[0].0, 1);
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].operations, vec![0, 2]);
+    }
+}
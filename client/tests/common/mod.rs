@@ -0,0 +1,81 @@
+// Shared test helpers
+// 共享测试辅助函数
+//
+// A market builder for tests that build market accounts by hand, so each states only the
+// fields it depends on.
+// 为手动构建市场账户的测试提供市场构建函数，使每个测试只需写出其依赖的字段。
+
+use solana_program::pubkey::Pubkey;
+use solana_rust_dex::state::{InstructionGuard, Market, MarketStatus, ACCOUNT_VERSION, MARKET_RESERVED_LEN};
+
+// Market as InitializeMarket leaves it, with default keys, unit order sizes and lots, no fees
+// and 6-decimal SPL Token mints, for tests to override the fields they need with
+// `..common::market()`
+// 与 InitializeMarket 创建后的市场相同，使用默认密钥、单位订单大小和手数、无手续费以及 6 位小数的
+// SPL Token 代币铸造账户，测试通过 `..common::market()` 覆盖所需字段
+pub fn market() -> Market {
+    Market {
+        is_initialized: true,
+        authority: Pubkey::default(),
+        base_mint: Pubkey::default(),
+        quote_mint: Pubkey::default(),
+        min_base_order_size: 1,
+        tick_size: 1,
+        taker_fee_bps: 0,
+        maker_fee_bps: 0,
+        next_order_id: 1,
+        num_bids: 0,
+        num_asks: 0,
+        instruction_guard: InstructionGuard::Disabled,
+        open_orders_root: [0; 32],
+        open_orders_root_leaves: 0,
+        open_orders_root_slot: 0,
+        referral_share_bps: 0,
+        fee_authority: Pubkey::default(),
+        status: MarketStatus::Active,
+        bid_base_total: 0,
+        ask_base_total: 0,
+        bid_notional: 0,
+        ask_notional: 0,
+        whitelist_enabled: false,
+        oracle: Pubkey::default(),
+        oracle_band_bps: 0,
+        oracle_max_staleness_slots: 0,
+        oracle_price_expo: 0,
+        fill_seq_num: 0,
+        base_decimals: 6,
+        quote_decimals: 6,
+        trustless: false,
+        signer_bump: 0,
+        base_lot_size: 1,
+        quote_lot_size: 1,
+        min_quote_notional: 0,
+        base_free_total: 0,
+        quote_free_total: 0,
+        best_bid: 0,
+        best_ask: 0,
+        last_price: 0,
+        last_trade_slot: 0,
+        base_volume: 0,
+        quote_volume: 0,
+        volume_buckets: Default::default(),
+        volume_bucket_hour: 0,
+        twap_oracle_enabled: false,
+        base_token_program: spl_token::id(),
+        quote_token_program: spl_token::id(),
+        version: ACCOUNT_VERSION,
+        flags: 0,
+        fees_accrued: 0,
+        fees_swept: 0,
+        referral_fees_paid: 0,
+        treasury_fee_account: Pubkey::default(),
+        treasury_fee_share_bps: 0,
+        creator_fee_account: Pubkey::default(),
+        creator_fee_share_bps: 0,
+        dynamic_fee_min_bps: 0,
+        dynamic_fee_volatility_bps: 0,
+        volatility_window: 0,
+        realized_variance: 0,
+        reserved: [0; MARKET_RESERVED_LEN],
+    }
+}
@@ -0,0 +1,106 @@
+// Instruction decoder test module
+// 指令解码器测试模块
+
+#[cfg(test)]
+mod decode_tests {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use borsh::BorshSerialize;
+    use solana_program::pubkey::Pubkey;
+    use solana_rust_dex::instruction::{DexInstruction, SelfTradeBehavior};
+    use solana_rust_dex_client::decode::{decode_data, decode_instruction, DataEncoding, DecodeError};
+    use solana_sdk::bs58;
+
+    #[test]
+    fn test_decode_place_order() {
+        let program_id = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let market = Pubkey::new_unique();
+        let instruction = DexInstruction::place_limit_order(
+            &program_id,
+            &owner,
+            &market,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &spl_token::id(),
+            false,
+            2_000,
+            300,
+            SelfTradeBehavior::CancelProvide,
+        )
+        .unwrap();
+        let accounts: Vec<Pubkey> = instruction.accounts.iter().map(|meta| meta.pubkey).collect();
+
+        // Both encodings decode to the instruction built by the SDK
+        // 两种编码都会解码为 SDK 构建的指令
+        let base58 = bs58::encode(&instruction.data).into_string();
+        let base64 = STANDARD.encode(&instruction.data);
+        assert_eq!(decode_data(&base58, None).unwrap(), instruction.data);
+        assert_eq!(decode_data(&base64, Some(DataEncoding::Base64)).unwrap(), instruction.data);
+
+        let decoded = decode_instruction(&instruction.data, &accounts).unwrap();
+        assert_eq!(decoded.name(), "PlaceLimitOrder");
+        assert_eq!(
+            decoded.instruction,
+            DexInstruction::PlaceLimitOrder {
+                is_buy: false,
+                limit_price: 2_000,
+                quantity: 300,
+                self_trade_behavior: SelfTradeBehavior::CancelProvide,
+                memo: String::new(),
+            }
+        );
+
+        // Every account gets its role, in order
+        // 每个账户按顺序获得其角色
+        assert_eq!(decoded.accounts[0].pubkey, owner);
+        assert_eq!(decoded.accounts[0].role, Some("Order owner or delegate (signer)"));
+        assert_eq!(decoded.accounts[1].pubkey, market);
+        assert_eq!(decoded.accounts[1].role, Some("Market"));
+        assert_eq!(decoded.accounts[4].role, Some("Market escrow vault"));
+        assert_eq!(decoded.accounts[5].role, Some("Token program"));
+        assert_eq!(decoded.unexpected_accounts(), 0);
+
+        let description = decoded.to_string();
+        assert!(description.starts_with("Instruction: PlaceLimitOrder\n"));
+        assert!(description.contains(&format!("1. {} (Market)", market)));
+    }
+
+    #[test]
+    fn test_decode_account_lists_and_extra_accounts() {
+        // Order accounts after the fixed ones are listed as orders
+        // 固定账户之后的订单账户被标记为订单
+        let data = DexInstruction::CancelOrdersByIds { order_ids: vec![3, 4] }.try_to_vec().unwrap();
+        let accounts: Vec<Pubkey> = (0..10).map(|_| Pubkey::new_unique()).collect();
+        let decoded = decode_instruction(&data, &accounts).unwrap();
+        assert_eq!(decoded.accounts[7].role, Some("Token program"));
+        assert_eq!(decoded.accounts[8].role, Some("Order"));
+        assert_eq!(decoded.accounts[9].role, Some("Order"));
+        assert_eq!(decoded.unexpected_accounts(), 0);
+
+        // Accounts an instruction does not expect are flagged
+        // 指令不需要的账户会被标记
+        let data = DexInstruction::CheckOracle.try_to_vec().unwrap();
+        let decoded = decode_instruction(&data, &accounts[..3]).unwrap();
+        assert_eq!(decoded.accounts[2].role, None);
+        assert_eq!(decoded.unexpected_accounts(), 1);
+        assert!(decoded.to_string().contains("(unexpected account)"));
+    }
+
+    #[test]
+    fn test_decode_rejects_invalid_data() {
+        // Data that is not a DEX instruction is rejected, including trailing bytes
+        // 非 DEX 指令的数据会被拒绝，包括带有多余字节的数据
+        assert_eq!(decode_instruction(&[250], &[]), Err(DecodeError::InvalidInstructionData));
+        let mut data = DexInstruction::CancelOrder.try_to_vec().unwrap();
+        data.push(0);
+        assert_eq!(decode_instruction(&data, &[]), Err(DecodeError::InvalidInstructionData));
+
+        // Characters outside the chosen alphabet are rejected
+        // 超出所选字母表的字符会被拒绝
+        assert_eq!(
+            decode_data("0OIl", Some(DataEncoding::Base58)),
+            Err(DecodeError::InvalidEncoding(DataEncoding::Base58))
+        );
+        assert_eq!(decode_data("!!", None), Err(DecodeError::InvalidEncoding(DataEncoding::Base64)));
+    }
+}
@@ -0,0 +1,129 @@
+// Capital-efficiency report test module
+// 资金效率报告测试模块
+
+mod common;
+
+#[cfg(test)]
+mod efficiency_tests {
+    use super::common;
+    use solana_program::pubkey::Pubkey;
+    use solana_rust_dex::state::{Market, Order, ACCOUNT_VERSION, ORDER_RESERVED_LEN};
+    use solana_rust_dex_client::efficiency::{analyze_orders, distance_bps, IdleThresholds};
+
+    const NOW: u64 = 1_700_100_000;
+
+    fn market() -> Market {
+        Market {
+            authority: Pubkey::new_unique(),
+            base_mint: Pubkey::new_unique(),
+            quote_mint: Pubkey::new_unique(),
+            taker_fee_bps: 30,
+            ..common::market()
+        }
+    }
+
+    fn order(
+        market: &Pubkey,
+        owner: &Pubkey,
+        order_id: u64,
+        is_buy: bool,
+        limit_price: u64,
+        age_seconds: u64,
+    ) -> (Pubkey, Order) {
+        let order = Order {
+            is_initialized: true,
+            order_id,
+            owner: *owner,
+            market: *market,
+            is_buy,
+            limit_price,
+            original_quantity: 100,
+            remaining_quantity: 100,
+            creation_timestamp: NOW - age_seconds,
+            linked_order: Pubkey::default(),
+            display_quantity: 0,
+            visible_quantity: 100,
+            referrer: Pubkey::default(),
+            memo: [0; 32],
+            version: ACCOUNT_VERSION,
+            reserved: [0; ORDER_RESERVED_LEN],
+        };
+        (Pubkey::new_unique(), order)
+    }
+
+    #[test]
+    fn test_distance_bps() {
+        // Distance is symmetric around the mid
+        // 距离以中间价为中心对称
+        assert_eq!(distance_bps(1_000, 1_000), 0);
+        assert_eq!(distance_bps(1_050, 1_000), 500);
+        assert_eq!(distance_bps(950, 1_000), 500);
+        assert_eq!(distance_bps(1, 0), u64::MAX);
+    }
+
+    #[test]
+    fn test_report_suggests_far_and_stale_orders() {
+        let market_pubkey = Pubkey::new_unique();
+        let market = market();
+        let owner = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        let day = 86_400;
+
+        // Another maker quotes 990 / 1010, so the mid is 1000
+        // 另一个做市商报价 990 / 1010，因此中间价为 1000
+        let far_stale_bid = order(&market_pubkey, &owner, 3, true, 800, 2 * day);
+        let farther_stale_ask = order(&market_pubkey, &owner, 4, false, 1_300, 3 * day);
+        let orders = vec![
+            order(&market_pubkey, &other, 1, true, 990, day),
+            order(&market_pubkey, &other, 2, false, 1_010, day),
+            far_stale_bid,
+            farther_stale_ask,
+            // Far but recent, and stale but close to the mid
+            // 距离远但时间短，以及时间长但接近中间价
+            order(&market_pubkey, &owner, 5, true, 700, 60),
+            order(&market_pubkey, &owner, 6, false, 1_020, 5 * day),
+        ];
+        let thresholds = IdleThresholds {
+            min_distance_bps: 500,
+            min_age_seconds: day,
+        };
+
+        let report = analyze_orders(&market_pubkey, &market, &orders, &owner, &thresholds, NOW);
+        assert_eq!(report.mid_price, Some(1_000));
+
+        // Only the owner's far and stale orders are suggested, furthest first
+        // 只建议取消所有者距离远且时间长的订单，距离最远的排在前面
+        let suggested: Vec<_> = report.idle_orders.iter().map(|order| order.order).collect();
+        assert_eq!(suggested, vec![farther_stale_ask.0, far_stale_bid.0]);
+        assert_eq!(report.idle_orders[0].distance_bps, 3_000);
+        assert_eq!(report.idle_orders[0].age_seconds, 3 * day);
+        assert_eq!(report.idle_orders[0].locked_mint, market.base_mint);
+        assert_eq!(report.idle_orders[1].locked_mint, market.quote_mint);
+
+        // Buys lock their notional plus the taker fee in quote tokens, sells their quantity in
+        // base tokens
+        // 买单锁定报价代币计价的名义金额加吃单手续费，卖单锁定基础代币数量
+        assert_eq!(report.locked_quote, 803 * 100 + 703 * 100);
+        assert_eq!(report.locked_base, 200);
+        assert_eq!(report.idle_quote(), 803 * 100);
+        assert_eq!(report.idle_base(), 100);
+    }
+
+    #[test]
+    fn test_report_without_mid_suggests_nothing() {
+        let market_pubkey = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+
+        // A one-sided book has no mid to measure against
+        // 单边订单簿没有可用于衡量的中间价
+        let orders = vec![order(&market_pubkey, &owner, 1, true, 500, 10 * 86_400)];
+        let thresholds = IdleThresholds {
+            min_distance_bps: 0,
+            min_age_seconds: 0,
+        };
+        let report = analyze_orders(&market_pubkey, &market(), &orders, &owner, &thresholds, NOW);
+        assert_eq!(report.mid_price, None);
+        assert!(report.idle_orders.is_empty());
+        assert_eq!(report.locked_quote, 502 * 100);
+    }
+}
@@ -0,0 +1,200 @@
+// Fill stream deduplication test module
+// 成交流去重测试模块
+
+#[cfg(test)]
+mod fills_tests {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use borsh::BorshSerialize;
+    use solana_program::pubkey::Pubkey;
+    use solana_rust_dex::state::{
+        DexEvent, FeesSwept, Fill, MarketParam, MarketStatus, OrderCancelled, OrderPlaced, ParamsUpdated,
+    };
+    use solana_rust_dex_client::fills::{parse_events, parse_fill_events, websocket_url, FillCursor};
+    use std::{env, fs};
+
+    fn fill(market: &Pubkey, seq_num: u64) -> Fill {
+        Fill {
+            market: *market,
+            seq_num,
+            taker_order: Pubkey::new_unique(),
+            maker_order: Pubkey::new_unique(),
+            base_amount: 200,
+            quote_amount: 400_000,
+            taker_fee: 1_000,
+            maker_fee: -100,
+            slot: 123_456,
+            taker_memo: "desk-7/ref-42".to_string(),
+            maker_memo: String::new(),
+        }
+    }
+
+    // Log line written by `sol_log_data` for a fill
+    // `sol_log_data` 为成交写入的日志行
+    fn data_log(fill: &Fill) -> String {
+        format!(
+            "Program data: {} {}",
+            STANDARD.encode(b"Fill"),
+            STANDARD.encode(fill.try_to_vec().unwrap())
+        )
+    }
+
+    #[test]
+    fn test_parse_fill_events() {
+        let program_id = Pubkey::new_unique();
+        let other_program = Pubkey::new_unique();
+        let market = Pubkey::new_unique();
+        let (first, forged, second) = (fill(&market, 0), fill(&market, 7), fill(&market, 1));
+
+        // Only fills logged by the DEX program itself are decoded
+        // 只解码 DEX 程序自身记录的成交
+        let logs = vec![
+            format!("Program {} invoke [1]", program_id),
+            "Program log: Instruction: Settle funds".to_string(),
+            data_log(&first),
+            format!("Program {} invoke [2]", other_program),
+            data_log(&forged),
+            format!("Program {} consumed 100 of 200000 compute units", other_program),
+            format!("Program {} success", other_program),
+            format!("Program data: {}", STANDARD.encode(b"TradeBust")),
+            data_log(&second),
+            format!("Program {} success", program_id),
+            format!("Program {} invoke [1]", other_program),
+            data_log(&forged),
+            format!("Program {} failed: custom program error: 0x1", other_program),
+        ];
+        assert_eq!(parse_fill_events(&program_id, &logs), vec![first, second]);
+        assert!(parse_fill_events(&other_program, &logs[..3]).is_empty());
+    }
+
+    #[test]
+    fn test_parse_events_decodes_every_event() {
+        let program_id = Pubkey::new_unique();
+        let market = Pubkey::new_unique();
+        let order = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let settled = fill(&market, 0);
+        let events = vec![
+            DexEvent::OrderPlaced(OrderPlaced {
+                market,
+                order,
+                owner,
+                order_id: 7,
+                is_buy: true,
+                limit_price: 1_000,
+                quantity: 500,
+                display_quantity: 0,
+                locked_amount: 501_500,
+                slot: 10,
+            }),
+            DexEvent::Fill(settled.clone()),
+            DexEvent::OrderCancelled(OrderCancelled {
+                market,
+                order,
+                owner,
+                order_id: 7,
+                is_buy: true,
+                limit_price: 1_000,
+                cancelled_quantity: 300,
+                remaining_quantity: 0,
+                refund_amount: 300_900,
+                slot: 11,
+            }),
+            DexEvent::FeesSwept(FeesSwept {
+                market,
+                destination: Pubkey::new_unique(),
+                amount: 750,
+                slot: 12,
+            }),
+            DexEvent::ParamsUpdated(ParamsUpdated {
+                market,
+                authority: Pubkey::new_unique(),
+                param: MarketParam::Status(MarketStatus::CancelOnly),
+                slot: 13,
+            }),
+        ];
+
+        // Every event round-trips through its logged name and Borsh encoding
+        // 每个事件都能通过其记录的名称和 Borsh 编码往返还原
+        let mut logs = vec![format!("Program {} invoke [1]", program_id)];
+        for event in &events {
+            logs.push(format!(
+                "Program data: {} {}",
+                STANDARD.encode(event.name()),
+                STANDARD.encode(event.data().unwrap())
+            ));
+        }
+        logs.push(format!("Program {} success", program_id));
+        assert_eq!(parse_events(&program_id, &logs), events);
+        assert_eq!(parse_fill_events(&program_id, &logs), vec![settled]);
+
+        // Unknown names and data that does not match the named layout are skipped
+        // 未知名称以及与所述布局不符的数据会被跳过
+        let data = events[0].data().unwrap();
+        assert_eq!(DexEvent::decode(b"OrderPlaced", &data), Some(events[0].clone()));
+        assert_eq!(DexEvent::decode(b"OrderMoved", &data), None);
+        assert_eq!(DexEvent::decode(b"OrderPlaced", &data[..data.len() - 1]), None);
+        assert_eq!(DexEvent::decode(b"FeesSwept", &data), None);
+    }
+
+    #[test]
+    fn test_cursor_skips_replayed_fills() {
+        let market = Pubkey::new_unique();
+        let other_market = Pubkey::new_unique();
+        let mut cursor = FillCursor::new();
+
+        // Fills are processed once per market, in sequence order
+        // 每个市场的成交按序列号只处理一次
+        assert!(!cursor.is_processed(&fill(&market, 0)));
+        assert!(cursor.record(&fill(&market, 0)));
+        assert!(cursor.record(&fill(&market, 1)));
+        assert!(!cursor.record(&fill(&market, 1)));
+        assert!(!cursor.record(&fill(&market, 0)));
+        assert_eq!(cursor.last_seq_num(&market), Some(1));
+
+        // Other markets keep their own sequence
+        // 其他市场拥有各自的序列号
+        assert!(cursor.record(&fill(&other_market, 0)));
+        assert_eq!(cursor.last_seq_num(&other_market), Some(0));
+
+        // A jump in the sequence reports the fills missed in between
+        // 序列号跳跃时会报告中间遗漏的成交数
+        assert_eq!(cursor.missed(&fill(&market, 2)), 0);
+        assert_eq!(cursor.missed(&fill(&market, 5)), 3);
+        assert_eq!(cursor.missed(&fill(&Pubkey::new_unique(), 5)), 0);
+    }
+
+    #[test]
+    fn test_cursor_persists_across_restarts() {
+        let path = env::temp_dir().join(format!("fill_cursor_{}", Pubkey::new_unique()));
+
+        // A missing file is an empty cursor
+        // 文件不存在时游标为空
+        assert_eq!(FillCursor::load(&path).unwrap(), FillCursor::new());
+
+        // A saved cursor loads back unchanged
+        // 保存的游标可以原样加载
+        let market = Pubkey::new_unique();
+        let mut cursor = FillCursor::new();
+        cursor.record(&fill(&market, 41));
+        cursor.record(&fill(&Pubkey::new_unique(), 3));
+        cursor.save(&path).unwrap();
+        let loaded = FillCursor::load(&path).unwrap();
+        assert_eq!(loaded, cursor);
+        assert!(loaded.is_processed(&fill(&market, 41)));
+
+        // Corrupt files are rejected
+        // 损坏的文件会被拒绝
+        fs::write(&path, "not-a-pubkey 1\n").unwrap();
+        assert!(FillCursor::load(&path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_websocket_url() {
+        // The WebSocket endpoint follows the RPC endpoint
+        // WebSocket 端点随 RPC 端点推导
+        assert_eq!(websocket_url("https://api.devnet.solana.com"), "wss://api.devnet.solana.com");
+        assert_eq!(websocket_url("http://127.0.0.1:8899"), "ws://127.0.0.1:8900");
+        assert_eq!(websocket_url("https://rpc.example.com/key"), "wss://rpc.example.com/key");
+    }
+}
@@ -0,0 +1,121 @@
+// Amount and price formatting test module
+// 数量与价格格式化测试模块
+
+#[cfg(test)]
+mod format_tests {
+    use solana_rust_dex_client::format::{
+        format_average_price, format_price, parse_price, AmountFormat, FormatError, Locale,
+    };
+
+    #[test]
+    fn test_format_and_parse_amounts() {
+        // A 6-decimal mint renders atoms as whole tokens with grouping
+        // 6 位小数的代币将最小单位渲染为带分组的整币数量
+        let usdc = AmountFormat::new(6);
+        assert_eq!(usdc.format(1_234_567_890), "1,234.567890");
+        assert_eq!(usdc.format(5), "0.000005");
+        assert_eq!(usdc.format(0), "0.000000");
+        assert_eq!(usdc.parse("1,234.56789").unwrap(), 1_234_567_890);
+        assert_eq!(usdc.parse(" 0.000005 ").unwrap(), 5);
+        assert_eq!(usdc.parse("12").unwrap(), 12_000_000);
+        assert_eq!(usdc.parse(".5").unwrap(), 500_000);
+
+        // Every rendered amount parses back to the same atoms
+        // 每个渲染结果都能解析回相同的最小单位数量
+        for atoms in [0, 1, 999, 1_000_000, 123_456_789_012, u64::MAX] {
+            assert_eq!(usdc.parse(&usdc.format(atoms)).unwrap(), atoms);
+        }
+
+        // Fewer display decimals truncate rather than round up
+        // 较少的显示小数位截断而不是向上取整
+        let short = usdc.with_display_decimals(2);
+        assert_eq!(short.format(1_999_999), "1.99");
+        assert_eq!(AmountFormat::new(0).format(1_000), "1,000");
+
+        // Invalid input is rejected with a precise error
+        // 非法输入返回精确的错误
+        assert_eq!(usdc.parse(""), Err(FormatError::Empty));
+        assert_eq!(usdc.parse("1.2x"), Err(FormatError::InvalidCharacter('x')));
+        assert_eq!(usdc.parse("-1"), Err(FormatError::InvalidCharacter('-')));
+        assert_eq!(usdc.parse("0.0000001"), Err(FormatError::TooManyDecimals(6)));
+        assert_eq!(usdc.parse("18446744073709.551616"), Err(FormatError::Overflow));
+    }
+
+    #[test]
+    fn test_locales() {
+        // Locales only change the separators, never the value
+        // 区域设置只改变分隔符，不改变数值
+        let amount = 1_234_567_500;
+        let de = AmountFormat::new(6).with_locale(Locale::DE);
+        let fr = AmountFormat::new(6).with_locale(Locale::FR);
+        let plain = AmountFormat::new(6).with_locale(Locale::PLAIN);
+        assert_eq!(de.format(amount), "1.234,567500");
+        assert_eq!(fr.format(amount), "1 234,567500");
+        assert_eq!(plain.format(amount), "1234.567500");
+        assert_eq!(de.parse("1.234,5675").unwrap(), amount);
+        assert_eq!(fr.parse("1 234,5675").unwrap(), amount);
+
+        // Language tags map onto the known separator styles
+        // 语言标签映射到已知的分隔符风格
+        assert_eq!(Locale::from_tag("zh-CN"), Some(Locale::EN));
+        assert_eq!(Locale::from_tag("de_DE"), Some(Locale::DE));
+        assert_eq!(Locale::from_tag("FR"), Some(Locale::FR));
+        assert_eq!(Locale::from_tag("xx"), None);
+    }
+
+    #[test]
+    fn test_format_and_parse_prices() {
+        // Base has 9 decimals, quote has 6: one quote atom per base atom is 1000 quote tokens
+        // 基础代币 9 位小数，报价代币 6 位：每个基础最小单位 1 个报价最小单位即 1000 报价代币
+        let base = AmountFormat::new(9);
+        let quote = AmountFormat::new(6);
+        assert_eq!(format_price(1, &base, &quote), "1,000.000000");
+        assert_eq!(parse_price("1000", &base, &quote).unwrap(), 1);
+        assert_eq!(parse_price("25,000", &base, &quote).unwrap(), 25);
+        for price in [0, 1, 42, 1_000_000] {
+            assert_eq!(parse_price(&format_price(price, &base, &quote), &base, &quote).unwrap(), price);
+        }
+
+        // Prices between two on-chain price steps cannot be placed
+        // 位于两个链上价格步长之间的价格无法下单
+        assert_eq!(
+            parse_price("1500", &base, &quote),
+            Err(FormatError::PriceNotRepresentable)
+        );
+
+        // Equal decimals make the on-chain price the displayed price
+        // 小数位相同时链上价格即显示价格
+        let base = AmountFormat::new(0);
+        let quote = AmountFormat::new(2);
+        assert_eq!(format_price(12_345, &base, &quote), "123.45");
+        assert_eq!(parse_price("123.45", &base, &quote).unwrap(), 12_345);
+
+        // Average fill prices keep the precision of the quote mint
+        // 平均成交价保留报价代币的精度
+        assert_eq!(format_average_price(10_001, 3, &base, &quote), "33.33");
+        assert_eq!(format_average_price(100, 0, &base, &quote), "0.00");
+    }
+
+    #[test]
+    fn test_format_and_parse_lots() {
+        // Base lots of 0.001 base tokens and quote lots of 0.01 quote tokens: one quote lot
+        // per base lot is 10 quote tokens per base token
+        // 基础代币每手 0.001 个、报价代币每手 0.01 个：每基础手 1 个报价手即每基础代币 10 报价代币
+        let base = AmountFormat::new(9).with_lot_size(1_000_000);
+        let quote = AmountFormat::new(6).with_lot_size(10_000);
+        assert_eq!(base.format_lots(1_500), "1.500000000");
+        assert_eq!(format_price(1, &base, &quote), "10.000000");
+        assert_eq!(parse_price("250", &base, &quote).unwrap(), 25);
+        for price in [0, 1, 42, 1_000_000] {
+            assert_eq!(parse_price(&format_price(price, &base, &quote), &base, &quote).unwrap(), price);
+        }
+        assert_eq!(
+            parse_price("25", &base, &quote),
+            Err(FormatError::PriceNotRepresentable)
+        );
+
+        // Average prices divide by the base tokens of the lots filled
+        // 平均价格除以所成交手数对应的基础代币数量
+        assert_eq!(format_average_price(30_000_000, 3_000, &base, &quote), "10.000000");
+    }
+}
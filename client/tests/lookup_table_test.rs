@@ -0,0 +1,133 @@
+// Address lookup table test module
+// 地址查找表测试模块
+
+mod common;
+
+#[cfg(test)]
+mod lookup_table_tests {
+    use super::common;
+    use solana_program::{
+        address_lookup_table::{
+            state::{AddressLookupTable, LookupTableMeta},
+            AddressLookupTableAccount,
+        },
+        hash::Hash,
+        instruction::{AccountMeta, Instruction},
+        message::VersionedMessage,
+        pubkey::Pubkey,
+        sysvar,
+    };
+    use solana_rust_dex::state::{
+        EventQueue, Market, TwapOracle, MARKET_FLAG_EVENT_QUEUE,
+    };
+    use solana_rust_dex_client::{
+        batch::TransactionBatcher,
+        lookup_table::{market_lookup_addresses, new_versioned_transaction, unpack_lookup_table},
+        DexInstructions,
+    };
+    use solana_sdk::signature::{Keypair, Signer};
+    use std::borrow::Cow;
+
+    fn market() -> Market {
+        Market {
+            authority: Pubkey::new_unique(),
+            base_mint: Pubkey::new_unique(),
+            quote_mint: Pubkey::new_unique(),
+            taker_fee_bps: 30,
+            ..common::market()
+        }
+    }
+
+    #[test]
+    fn test_market_lookup_addresses() {
+        let program_id = Pubkey::new_unique();
+        let market_pubkey = Pubkey::new_unique();
+        let mut market = market();
+
+        // The market, its PDAs, mints, token program and sysvar are listed once each
+        // 市场、其 PDA、铸币、代币程序和系统变量各列出一次
+        let addresses = market_lookup_addresses(&program_id, &market_pubkey, &market);
+        assert!(addresses.contains(&market_pubkey));
+        assert!(addresses.contains(&Market::find_quote_vault_address(&program_id, &market_pubkey).0));
+        assert!(addresses.contains(&market.base_mint));
+        assert!(addresses.contains(&spl_token::id()));
+        assert!(addresses.contains(&sysvar::instructions::id()));
+        assert_eq!(addresses.iter().filter(|address| **address == spl_token::id()).count(), 1);
+        assert!(!addresses.contains(&EventQueue::find_address(&program_id, &market_pubkey).0));
+
+        // The event queue and TWAP oracle are listed once the market has them
+        // 市场拥有事件队列和 TWAP 预言机后，它们也会被列出
+        market.flags |= MARKET_FLAG_EVENT_QUEUE;
+        market.twap_oracle_enabled = true;
+        let addresses = market_lookup_addresses(&program_id, &market_pubkey, &market);
+        assert!(addresses.contains(&EventQueue::find_address(&program_id, &market_pubkey).0));
+        assert!(addresses.contains(&TwapOracle::find_address(&program_id, &market_pubkey).0));
+    }
+
+    #[test]
+    fn test_unpack_lookup_table() {
+        // A lookup table account decodes to its addresses
+        // 查找表账户解码为其地址
+        let key = Pubkey::new_unique();
+        let addresses = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+        let table = AddressLookupTable {
+            meta: LookupTableMeta::new(Pubkey::new_unique()),
+            addresses: Cow::Owned(addresses.clone()),
+        };
+        let data = table.serialize_for_tests().unwrap();
+        assert_eq!(unpack_lookup_table(&key, &data), Some(AddressLookupTableAccount { key, addresses }));
+        assert_eq!(unpack_lookup_table(&key, &[1, 2, 3]), None);
+    }
+
+    #[test]
+    fn test_versioned_batches_fit_more_operations() {
+        let program_id = Pubkey::new_unique();
+        let owner = Keypair::new();
+        let shared: Vec<Pubkey> = (0..6).map(|_| Pubkey::new_unique()).collect();
+        let operation = DexInstructions {
+            instructions: vec![Instruction::new_with_bytes(
+                program_id,
+                &[7; 16],
+                std::iter::once(AccountMeta::new_readonly(owner.pubkey(), true))
+                    .chain(shared.iter().map(|address| AccountMeta::new(*address, false)))
+                    .collect(),
+            )],
+            signers: vec![owner.pubkey()],
+        };
+        let table = AddressLookupTableAccount { key: Pubkey::new_unique(), addresses: shared.clone() };
+
+        // Accounts looked up in a table take an index instead of a key
+        // 在查找表中查找的账户以索引代替密钥
+        let payer = Keypair::new();
+        let mut legacy = TransactionBatcher::new(payer.pubkey());
+        for _ in 0..100 {
+            legacy.add(operation.clone());
+        }
+        let versioned = legacy.clone().with_lookup_tables(vec![table.clone()]);
+        let (legacy_batches, _) = legacy.pack();
+        let (versioned_batches, oversized) = versioned.pack();
+        assert!(oversized.is_empty());
+        assert!(versioned_batches.len() < legacy_batches.len());
+        assert!(versioned.transaction_size(&operation.instructions) < legacy.transaction_size(&operation.instructions));
+
+        // The v0 transaction is signed once by each signer, the payer first
+        // v0 交易由每个签名者各签名一次，付款人在前
+        let transaction = new_versioned_transaction(
+            &operation.instructions,
+            &payer,
+            &[&owner, &payer],
+            &[table],
+            Hash::default(),
+        )
+        .unwrap();
+        assert_eq!(transaction.signatures.len(), 2);
+        assert!(transaction.verify_with_results().iter().all(|valid| *valid));
+        match transaction.message {
+            VersionedMessage::V0(message) => {
+                assert_eq!(message.account_keys[0], payer.pubkey());
+                assert_eq!(message.address_table_lookups.len(), 1);
+            }
+            VersionedMessage::Legacy(_) => panic!("expected a v0 message"),
+        }
+    }
+}
@@ -0,0 +1,79 @@
+// Error message catalog test module
+// 错误消息目录测试模块
+
+#[cfg(test)]
+mod messages_tests {
+    use solana_client::client_error::ClientError;
+    use solana_rust_dex::error::DexError;
+    use solana_rust_dex_client::{
+        format::FormatError,
+        messages::{
+            describe_error, dex_error_message, format_error_message, Language,
+        },
+    };
+    use solana_sdk::{instruction::InstructionError, program_error::ProgramError, transaction::TransactionError};
+    use std::error::Error;
+
+    #[test]
+    fn test_dex_error_catalog() {
+        // Error codes index the catalog and English matches the program's own messages
+        // 错误码索引目录，英文消息与程序自身的消息一致
+        let errors: Vec<DexError> = (0..).map_while(DexError::from_u32).collect();
+        assert_eq!(errors.len() as u32, DexError::StakeLocked as u32 + 1);
+        for (code, error) in errors.iter().enumerate() {
+            assert_eq!(*error as u32, code as u32);
+            assert_eq!(dex_error_message(*error, Language::En), error.to_string());
+            assert!(!dex_error_message(*error, Language::Zh).is_empty());
+            assert_ne!(dex_error_message(*error, Language::Zh), error.to_string());
+        }
+        assert!(DexError::from_u32(errors.len() as u32).is_none());
+        assert_eq!(dex_error_message(DexError::MarketNotActive, Language::Zh), "市场未处于活跃状态");
+
+        // Language tags select the catalog
+        // 语言标签选择目录
+        assert_eq!(Language::from_tag("zh-CN"), Some(Language::Zh));
+        assert_eq!(Language::from_tag("EN_us"), Some(Language::En));
+        assert_eq!(Language::from_tag("de"), None);
+    }
+
+    #[test]
+    fn test_error_codes_are_stable() {
+        // Codes are variant indices and survive the round trip through a program error
+        // 错误码即变体索引，经程序错误往返转换后保持不变
+        assert_eq!(DexError::InvalidInstructionData as u32, 0);
+        assert_eq!(DexError::MarketNotActive as u32, 15);
+        assert_eq!(DexError::AccountNotMigrated as u32, 31);
+        assert_eq!(DexError::from_u32(15), Some(DexError::MarketNotActive));
+        assert_eq!(DexError::from_u32(u32::MAX), None);
+        match ProgramError::from(DexError::OracleStale) {
+            ProgramError::Custom(code) => assert_eq!(DexError::from_u32(code), Some(DexError::OracleStale)),
+            error => panic!("unexpected error: {:?}", error),
+        }
+    }
+
+    #[test]
+    fn test_describe_client_errors() {
+        // Program errors returned by a transaction are translated
+        // 交易返回的程序错误会被翻译
+        let err: Box<dyn Error> = Box::new(ClientError::from(TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(DexError::OrderNotFound as u32),
+        )));
+        assert_eq!(describe_error(err.as_ref(), Language::Zh), "订单不存在");
+        assert_eq!(describe_error(err.as_ref(), Language::En), "Order not found");
+
+        // Formatting errors keep their details
+        // 格式化错误保留其详细信息
+        let err: Box<dyn Error> = Box::new(FormatError::TooManyDecimals(6));
+        assert_eq!(describe_error(err.as_ref(), Language::Zh), "小数位过多，最多允许 6 位");
+        assert_eq!(
+            format_error_message(&FormatError::InvalidCharacter('x'), Language::En),
+            "Invalid character 'x'"
+        );
+
+        // Other errors fall back to their own message
+        // 其他错误回退为其自身的消息
+        let err: Box<dyn Error> = "Unknown locale: xx".into();
+        assert_eq!(describe_error(err.as_ref(), Language::Zh), "Unknown locale: xx");
+    }
+}
@@ -0,0 +1,124 @@
+// Order CSV migration test module
+// 订单 CSV 迁移测试模块
+
+mod common;
+
+#[cfg(test)]
+mod migration_tests {
+    use super::common;
+    use solana_program::pubkey::Pubkey;
+    use solana_rust_dex::state::{Market, MarketStatus, Order, ACCOUNT_VERSION, ORDER_RESERVED_LEN};
+    use solana_rust_dex_client::{
+        migration::{read_order_csv, write_order_csv, OrderCsvError, OrderRow, ORDER_CSV_HEADER},
+        preflight::{OrderIntent, PreflightIssue},
+    };
+
+    fn market(tick_size: u64, min_base_order_size: u64) -> Market {
+        Market {
+            authority: Pubkey::new_unique(),
+            base_mint: Pubkey::new_unique(),
+            quote_mint: Pubkey::new_unique(),
+            min_base_order_size,
+            tick_size,
+            taker_fee_bps: 30,
+            maker_fee_bps: 10,
+            ..common::market()
+        }
+    }
+
+    fn order(is_buy: bool, display_quantity: u64) -> Order {
+        Order {
+            is_initialized: true,
+            order_id: 7,
+            owner: Pubkey::new_unique(),
+            market: Pubkey::new_unique(),
+            is_buy,
+            limit_price: 2_050,
+            original_quantity: 1_000,
+            remaining_quantity: 600,
+            creation_timestamp: 1_700_000_000,
+            linked_order: Pubkey::default(),
+            display_quantity,
+            visible_quantity: display_quantity,
+            referrer: Pubkey::default(),
+            memo: [0; 32],
+            version: ACCOUNT_VERSION,
+            reserved: [0; ORDER_RESERVED_LEN],
+        }
+    }
+
+    #[test]
+    fn test_order_csv_round_trip() {
+        // Rows carry the remaining quantity and the iceberg slice
+        // 行记录剩余数量以及冰山订单的显示数量
+        let order_pubkey = Pubkey::new_unique();
+        let limit_row = OrderRow::from_order(&order_pubkey, &order(true, 0));
+        assert_eq!(limit_row.quantity, 600);
+        assert_eq!(limit_row.iceberg_display_quantity(), None);
+        let iceberg_row = OrderRow::from_order(&Pubkey::new_unique(), &order(false, 200));
+        assert_eq!(iceberg_row.iceberg_display_quantity(), Some(200));
+
+        // The CSV reads back to the same rows
+        // CSV 可以读回相同的行
+        let rows = vec![limit_row, iceberg_row];
+        let csv = write_order_csv(&rows);
+        assert!(csv.starts_with(ORDER_CSV_HEADER));
+        assert!(csv.contains(&format!("{},7,buy,2050,600,0", order_pubkey)));
+        assert_eq!(read_order_csv(&csv).unwrap(), rows);
+        assert_eq!(read_order_csv(ORDER_CSV_HEADER).unwrap(), Vec::new());
+    }
+
+    #[test]
+    fn test_read_rejects_malformed_csv() {
+        // A missing header is rejected
+        // 缺少表头会被拒绝
+        assert_eq!(read_order_csv(""), Err(OrderCsvError::InvalidHeader));
+        assert_eq!(read_order_csv("market,order\n"), Err(OrderCsvError::InvalidHeader));
+
+        // Bad rows are reported with their line number
+        // 错误的行会连同其行号一起报告
+        let csv = write_order_csv(&[OrderRow::from_order(&Pubkey::new_unique(), &order(true, 0))]);
+        let row = csv.lines().nth(1).unwrap();
+        for bad_row in ["x,y".to_string(), row.replace(",buy,", ",bid,"), row.replace(",600,", ",-1,")] {
+            let err = read_order_csv(&format!("{}{}\n", csv, bad_row)).unwrap_err();
+            assert!(matches!(err, OrderCsvError::InvalidRow { line: 3, .. }), "{:?}", err);
+        }
+    }
+
+    #[test]
+    fn test_revalidate_against_destination_market() {
+        let row = OrderRow::from_order(&Pubkey::new_unique(), &order(true, 0));
+
+        // A market with compatible parameters accepts the order unchanged
+        // 参数兼容的市场原样接受订单
+        assert_eq!(
+            row.revalidate(&market(50, 100)),
+            Ok(OrderIntent {
+                is_buy: true,
+                limit_price: 2_050,
+                quantity: 600,
+            })
+        );
+
+        // Prices off the tick and quantities below the minimum are rejected, not adjusted
+        // 不符合最小价格变动单位的价格和低于最小数量的订单会被拒绝，而不是被调整
+        assert_eq!(
+            row.revalidate(&market(100, 1_000)),
+            Err(vec![
+                PreflightIssue::QuantityBelowMinimum {
+                    min_base_order_size: 1_000,
+                },
+                PreflightIssue::PriceNotOnTick { tick_size: 100 },
+            ])
+        );
+
+        // Markets that do not accept orders are rejected
+        // 不接受订单的市场会被拒绝
+        let mut closed = market(50, 100);
+        closed.status = MarketStatus::CancelOnly;
+        assert_eq!(
+            row.revalidate(&closed),
+            Err(vec![PreflightIssue::MarketNotActive(MarketStatus::CancelOnly)])
+        );
+    }
+}
@@ -0,0 +1,66 @@
+// Native SOL wrapping test module
+// 原生 SOL 包装测试模块
+
+#[cfg(test)]
+mod native_tests {
+    use solana_program::{pubkey::Pubkey, system_program};
+    use solana_rust_dex_client::native::{
+        create_and_wrap_instructions, is_native_mint, native_mint, unwrap_instruction, wrap_instructions,
+        wrapped_sol_account,
+    };
+    use spl_associated_token_account::get_associated_token_address;
+    use spl_token_2022::instruction::TokenInstruction;
+
+    #[test]
+    fn test_native_mints() {
+        // Both token programs have their own native mint
+        // 两个代币程序各有自己的原生铸币
+        assert!(is_native_mint(&spl_token::native_mint::id()));
+        assert!(is_native_mint(&spl_token_2022::native_mint::id()));
+        assert!(!is_native_mint(&Pubkey::new_unique()));
+        assert_eq!(native_mint(&spl_token::id()), spl_token::native_mint::id());
+        assert_eq!(native_mint(&spl_token_2022::id()), spl_token_2022::native_mint::id());
+
+        // The wrapped SOL account is the owner's associated token account of the native mint
+        // 包装 SOL 账户是所有者在原生铸币下的关联代币账户
+        let owner = Pubkey::new_unique();
+        assert_eq!(
+            wrapped_sol_account(&owner, &spl_token::id()),
+            get_associated_token_address(&owner, &spl_token::native_mint::id())
+        );
+    }
+
+    #[test]
+    fn test_wrap_and_unwrap_instructions() {
+        let payer = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let account = wrapped_sol_account(&owner, &spl_token::id());
+
+        // Wrapping moves the owner's lamports into the account and syncs its token balance
+        // 包装会把所有者的 lamports 转入账户并同步代币余额
+        let wrap = wrap_instructions(&owner, &spl_token::id(), 5_000).unwrap();
+        assert_eq!(wrap.len(), 2);
+        assert_eq!(wrap[0].program_id, system_program::id());
+        assert_eq!(wrap[0].accounts[0].pubkey, owner);
+        assert_eq!(wrap[0].accounts[1].pubkey, account);
+        assert_eq!(wrap[1].program_id, spl_token::id());
+        assert_eq!(TokenInstruction::unpack(&wrap[1].data).unwrap(), TokenInstruction::SyncNative);
+
+        // The payer creates the account first if needed
+        // 如有需要，付款人会先创建账户
+        let create_and_wrap = create_and_wrap_instructions(&payer, &owner, &spl_token::id(), 5_000).unwrap();
+        assert_eq!(create_and_wrap.len(), 3);
+        assert_eq!(create_and_wrap[0].program_id, spl_associated_token_account::id());
+        assert_eq!(create_and_wrap[0].accounts[0].pubkey, payer);
+        assert_eq!(create_and_wrap[1..], wrap[..]);
+
+        // Unwrapping closes the account, returning its lamports to the owner
+        // 解包会关闭账户，把其中的 lamports 退还给所有者
+        let unwrap = unwrap_instruction(&owner, &spl_token_2022::id()).unwrap();
+        assert_eq!(unwrap.program_id, spl_token_2022::id());
+        assert_eq!(TokenInstruction::unpack(&unwrap.data).unwrap(), TokenInstruction::CloseAccount);
+        assert_eq!(unwrap.accounts[0].pubkey, wrapped_sol_account(&owner, &spl_token_2022::id()));
+        assert_eq!(unwrap.accounts[1].pubkey, owner);
+        assert_eq!(unwrap.accounts[2].pubkey, owner);
+    }
+}
@@ -0,0 +1,152 @@
+// Order book aggregation test module
+// 订单簿聚合测试模块
+
+#[cfg(test)]
+mod orderbook_tests {
+    use solana_program::pubkey::Pubkey;
+    use solana_rust_dex::state::{Order, ACCOUNT_VERSION, ORDER_RESERVED_LEN};
+    use solana_rust_dex_client::orderbook::{AggregatedOrderBook, FeeConvention, OrderBook, RouteLeg};
+
+    fn ask(market: Pubkey, limit_price: u64, remaining_quantity: u64) -> Order {
+        order(market, false, limit_price, remaining_quantity)
+    }
+
+    fn order(market: Pubkey, is_buy: bool, limit_price: u64, remaining_quantity: u64) -> Order {
+        Order {
+            is_initialized: true,
+            order_id: 1,
+            owner: Pubkey::new_unique(),
+            market,
+            is_buy,
+            limit_price,
+            original_quantity: remaining_quantity,
+            remaining_quantity,
+            creation_timestamp: 0,
+            linked_order: Pubkey::default(),
+            display_quantity: 0,
+            visible_quantity: remaining_quantity,
+            referrer: Pubkey::default(),
+            memo: [0; 32],
+            version: ACCOUNT_VERSION,
+            reserved: [0; ORDER_RESERVED_LEN],
+        }
+    }
+
+    #[test]
+    fn test_merge_and_route_across_markets() {
+        // Two markets of the same pair with overlapping asks
+        // 同一交易对的两个市场，卖单价格有重叠
+        let market_a = Pubkey::new_unique();
+        let market_b = Pubkey::new_unique();
+        let book_a = OrderBook::from_orders(
+            market_a,
+            &[ask(market_a, 100, 50), ask(market_a, 110, 50)],
+        );
+        let book_b = OrderBook::from_orders(
+            market_b,
+            &[ask(market_b, 100, 30), ask(market_b, 105, 40)],
+        );
+
+        // Merged depth keeps the per-market breakdown
+        // 合并后的深度保留每个市场的明细
+        let book = AggregatedOrderBook::merge(&[book_a, book_b]);
+        assert_eq!(book.asks.len(), 3);
+        assert_eq!(book.asks[0].price, 100);
+        assert_eq!(book.asks[0].quantity, 80);
+        assert_eq!(book.asks[0].sources.len(), 2);
+        assert!(book.bids.is_empty());
+
+        // A buy of 150 takes 100 @ both markets, then 105 @ B, then 110 @ A
+        // 买入 150：先在两个市场吃 100 价位，再吃 B 的 105，最后吃 A 的 110
+        let legs = book.route(true, 150);
+        assert_eq!(
+            legs,
+            vec![
+                RouteLeg {
+                    market: market_a,
+                    quantity: 80,
+                    limit_price: 110,
+                },
+                RouteLeg {
+                    market: market_b,
+                    quantity: 70,
+                    limit_price: 105,
+                },
+            ]
+        );
+
+        // Routing against an empty side yields no legs
+        // 对空的一侧路由不产生任何分腿
+        assert!(book.route(false, 10).is_empty());
+    }
+
+    #[test]
+    fn test_fee_inclusive_quotes() {
+        // Market A has the better price but charges a 1% taker fee, market B is fee free
+        // 市场 A 价格更优但收取 1% 吃单手续费，市场 B 免手续费
+        let market_a = Pubkey::new_unique();
+        let market_b = Pubkey::new_unique();
+        let book_a = OrderBook::from_orders(
+            market_a,
+            &[order(market_a, false, 1000, 50), order(market_a, true, 1000, 50)],
+        )
+        .with_taker_fee_bps(100);
+        let book_b = OrderBook::from_orders(
+            market_b,
+            &[order(market_b, false, 1005, 40), order(market_b, true, 995, 50)],
+        );
+        let book = AggregatedOrderBook::merge(&[book_a, book_b]);
+
+        // Fee-exclusive quotes walk the book by displayed price
+        // 不含手续费的报价按显示价格遍历订单簿
+        let quote = book.quote(true, 80, FeeConvention::FeeExclusive);
+        assert_eq!(quote.base_quantity, 80);
+        assert_eq!(quote.quote_amount, 50 * 1000 + 30 * 1005);
+        assert_eq!(quote.fee, 500);
+        assert_eq!(quote.quote_total(FeeConvention::FeeExclusive), 80150);
+        assert_eq!(quote.quote_total(FeeConvention::FeeInclusive), 80650);
+        assert_eq!(quote.legs, book.route(true, 80));
+
+        // Fee-inclusive quotes prefer the cheaper all-in price of market B
+        // 含手续费的报价优先选择总价更低的市场 B
+        let quote = book.quote(true, 80, FeeConvention::FeeInclusive);
+        assert_eq!(
+            quote.legs,
+            vec![
+                RouteLeg {
+                    market: market_b,
+                    quantity: 40,
+                    limit_price: 1005,
+                },
+                RouteLeg {
+                    market: market_a,
+                    quantity: 40,
+                    limit_price: 1000,
+                },
+            ]
+        );
+        assert_eq!(quote.fee, 400);
+
+        // A buy budget including fees buys as much as it can pay for
+        // 含手续费的买入预算尽可能多地买入
+        let quote = book.quote_for_amount(true, 50300, FeeConvention::FeeInclusive);
+        assert_eq!(quote.base_quantity, 50);
+        assert_eq!(quote.quote_total(FeeConvention::FeeInclusive), 50300);
+        let quote = book.quote_for_amount(true, 50299, FeeConvention::FeeInclusive);
+        assert_eq!(quote.base_quantity, 49);
+
+        // A sell sized for net proceeds sells just enough after fees
+        // 按净收入确定的卖单在扣除手续费后刚好足够
+        let quote = book.quote_for_amount(false, 9950, FeeConvention::FeeInclusive);
+        assert_eq!(quote.base_quantity, 10);
+        assert_eq!(quote.legs[0].market, market_b);
+        let quote = book.quote_for_amount(false, 10000, FeeConvention::FeeInclusive);
+        assert_eq!(quote.base_quantity, 11);
+        assert!(quote.quote_total(FeeConvention::FeeInclusive) >= 10000);
+
+        // A shallow book fills what it can
+        // 深度不足的订单簿尽量成交
+        let quote = book.quote_for_amount(true, u64::MAX, FeeConvention::FeeInclusive);
+        assert_eq!(quote.base_quantity, 90);
+    }
+}
@@ -0,0 +1,196 @@
+// Order pre-flight check test module
+// 下单预检测试模块
+
+mod common;
+
+#[cfg(test)]
+mod preflight_tests {
+    use super::common;
+    use solana_program::{program_option::COption, pubkey::Pubkey};
+    use solana_rust_dex::state::{Market, MarketStatus};
+    use solana_rust_dex_client::{
+        native::wrap_instructions,
+        preflight::{check_order, OrderIntent, OwnerState, PreflightIssue},
+    };
+    use spl_associated_token_account::get_associated_token_address;
+    use spl_token::state::{Account as TokenAccount, AccountState};
+
+    fn market() -> Market {
+        Market {
+            authority: Pubkey::new_unique(),
+            base_mint: Pubkey::new_unique(),
+            quote_mint: Pubkey::new_unique(),
+            min_base_order_size: 100,
+            tick_size: 10,
+            taker_fee_bps: 30,
+            maker_fee_bps: 10,
+            ..common::market()
+        }
+    }
+
+    fn owner_state(owner: Pubkey, mint: Pubkey, amount: Option<u64>) -> OwnerState {
+        OwnerState {
+            owner,
+            token_account: amount.map(|amount| TokenAccount {
+                mint,
+                owner,
+                amount,
+                delegate: COption::None,
+                state: AccountState::Initialized,
+                is_native: COption::None,
+                delegated_amount: 0,
+                close_authority: COption::None,
+            }),
+            lamports: 10_000_000,
+            order_rent_lamports: 2_000_000,
+        }
+    }
+
+    #[test]
+    fn test_ready_order() {
+        // A funded buy order covering the notional plus the taker fee is ready
+        // 资金覆盖名义价值与吃单手续费的买单可以直接下单
+        let market = market();
+        let owner = Pubkey::new_unique();
+        let intent = OrderIntent {
+            is_buy: true,
+            limit_price: 1_000,
+            quantity: 1_000,
+        };
+        let state = owner_state(owner, market.quote_mint, Some(1_003_000));
+
+        let report = check_order(&market, &intent, &state, &owner);
+        assert!(report.is_ready());
+        assert!(report.fix_instructions().is_empty());
+
+        // One token short of the fee is reported
+        // 比手续费少一个代币时会报告余额不足
+        let state = owner_state(owner, market.quote_mint, Some(1_002_999));
+        let report = check_order(&market, &intent, &state, &owner);
+        assert_eq!(
+            report.steps[0].issue,
+            PreflightIssue::InsufficientTokenBalance {
+                mint: market.quote_mint,
+                required: 1_003_000,
+                available: 1_002_999,
+            }
+        );
+        assert!(!report.is_fixable());
+    }
+
+    #[test]
+    fn test_missing_token_account_is_fixable() {
+        // A missing base token account is created by the payer, but funding it is up to the owner
+        // 缺失的基础代币账户由付款人创建，但充值需要所有者自行完成
+        let market = market();
+        let owner = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+        let intent = OrderIntent {
+            is_buy: false,
+            limit_price: 1_000,
+            quantity: 500,
+        };
+        let state = owner_state(owner, market.base_mint, None);
+
+        let report = check_order(&market, &intent, &state, &payer);
+        assert_eq!(report.steps.len(), 2);
+        assert_eq!(
+            report.steps[0].issue,
+            PreflightIssue::MissingTokenAccount {
+                mint: market.base_mint,
+                address: get_associated_token_address(&owner, &market.base_mint),
+            }
+        );
+        let fix = report.fix_instructions();
+        assert_eq!(fix.len(), 1);
+        assert_eq!(fix[0].program_id, spl_associated_token_account::id());
+        assert_eq!(fix[0].accounts[0].pubkey, payer);
+        assert_eq!(
+            report.steps[1].issue,
+            PreflightIssue::InsufficientTokenBalance {
+                mint: market.base_mint,
+                required: 500,
+                available: 0,
+            }
+        );
+        assert!(!report.is_fixable());
+    }
+
+    #[test]
+    fn test_short_wrapped_sol_is_wrapped() {
+        // A buy on a SOL-quoted market wraps the owner's lamports it is short of
+        // 以 SOL 计价市场上的买单会包装所有者缺少的 lamports
+        let mut market = market();
+        market.quote_mint = spl_token::native_mint::id();
+        let owner = Pubkey::new_unique();
+        let intent = OrderIntent {
+            is_buy: true,
+            limit_price: 1_000,
+            quantity: 1_000,
+        };
+        let state = owner_state(owner, market.quote_mint, Some(1_000_000));
+
+        let report = check_order(&market, &intent, &state, &owner);
+        assert_eq!(
+            report.steps[0].issue,
+            PreflightIssue::InsufficientTokenBalance {
+                mint: market.quote_mint,
+                required: 1_003_000,
+                available: 1_000_000,
+            }
+        );
+        assert!(report.is_fixable());
+        assert_eq!(
+            report.fix_instructions(),
+            wrap_instructions(&owner, &spl_token::id(), 3_000).unwrap()
+        );
+
+        // Another payer cannot move the owner's lamports, and neither can lamports reserved for rent
+        // 其他付款人无法转移所有者的 lamports，为租金预留的 lamports 也不能使用
+        let report = check_order(&market, &intent, &state, &Pubkey::new_unique());
+        assert!(!report.is_fixable());
+        let mut state = state;
+        state.lamports = state.order_rent_lamports + 2_999;
+        let report = check_order(&market, &intent, &state, &owner);
+        assert!(!report.is_fixable());
+    }
+
+    #[test]
+    fn test_market_and_parameter_issues() {
+        // A paused market, an off-tick price, a small quantity and notional and missing rent
+        // are all listed
+        // 暂停的市场、不符合价格步长的价格、过小的数量和名义金额以及不足的租金都会被列出
+        let mut market = market();
+        market.status = MarketStatus::Paused;
+        market.min_quote_notional = 100_000;
+        let owner = Pubkey::new_unique();
+        let intent = OrderIntent {
+            is_buy: false,
+            limit_price: 1_005,
+            quantity: 50,
+        };
+        let mut state = owner_state(owner, market.base_mint, Some(50));
+        state.lamports = 1_000;
+
+        let report = check_order(&market, &intent, &state, &owner);
+        let issues: Vec<_> = report.steps.iter().map(|step| step.issue.clone()).collect();
+        assert_eq!(
+            issues,
+            vec![
+                PreflightIssue::MarketNotActive(MarketStatus::Paused),
+                PreflightIssue::QuantityBelowMinimum {
+                    min_base_order_size: 100
+                },
+                PreflightIssue::PriceNotOnTick { tick_size: 10 },
+                PreflightIssue::NotionalBelowMinimum {
+                    min_quote_notional: 100_000
+                },
+                PreflightIssue::InsufficientLamports {
+                    required: 2_000_000,
+                    available: 1_000,
+                },
+            ]
+        );
+        assert!(report.fix_instructions().is_empty());
+    }
+}
@@ -0,0 +1,75 @@
+// Signed firm quote test module
+// 已签名确定报价测试模块
+
+#[cfg(test)]
+mod rfq_tests {
+    use solana_program::{ed25519_program, pubkey::Pubkey};
+    use solana_rust_dex::state::FirmQuote;
+    use solana_rust_dex_client::rfq::{SignedQuote, SignedQuoteError};
+    use solana_sdk::signature::{Keypair, Signer};
+
+    fn quote(maker: &Keypair) -> FirmQuote {
+        FirmQuote {
+            market: Pubkey::new_unique(),
+            maker: maker.pubkey(),
+            taker: Pubkey::default(),
+            is_buy: true,
+            price: 1_000,
+            quantity: 500,
+            expires_at: 1_700_000_600,
+            nonce: 3,
+        }
+    }
+
+    #[test]
+    fn test_sign_and_encode_quote() {
+        // A signed quote verifies for its deployment and survives the hex round trip
+        // 已签名的报价对其部署有效，并且可以经受十六进制编码往返
+        let program_id = Pubkey::new_unique();
+        let maker = Keypair::new();
+        let signed_quote = SignedQuote::sign(&maker, &program_id, quote(&maker)).unwrap();
+        assert!(signed_quote.verify(&program_id));
+        assert!(!signed_quote.verify(&Pubkey::new_unique()));
+
+        let decoded = SignedQuote::from_hex(&signed_quote.to_hex()).unwrap();
+        assert_eq!(decoded, signed_quote);
+
+        // Changing the terms invalidates the signature
+        // 修改报价条款会使签名失效
+        let mut tampered = signed_quote.clone();
+        tampered.quote.price = 900;
+        assert!(!tampered.verify(&program_id));
+
+        // A quote claiming another maker does not verify
+        // 声称属于其他做市商的报价无法通过验证
+        let mut impersonated = signed_quote.clone();
+        impersonated.quote.maker = Pubkey::new_unique();
+        assert!(!impersonated.verify(&program_id));
+
+        // The ed25519 instruction carries the maker key and the signing message
+        // ed25519 指令携带做市商公钥和签名消息
+        let instruction = signed_quote.ed25519_instruction(&program_id);
+        assert_eq!(instruction.program_id, ed25519_program::id());
+        assert!(instruction.accounts.is_empty());
+        assert_eq!(instruction.data[0], 1);
+        assert_eq!(&instruction.data[16..48], maker.pubkey().as_ref());
+        assert!(instruction.data.ends_with(&signed_quote.quote.signing_message(&program_id)));
+    }
+
+    #[test]
+    fn test_decode_rejects_malformed_quotes() {
+        // Odd lengths and non-hex characters are rejected
+        // 奇数长度和非十六进制字符会被拒绝
+        assert_eq!(SignedQuote::from_hex("abc"), Err(SignedQuoteError::InvalidHex));
+        assert_eq!(SignedQuote::from_hex("zz"), Err(SignedQuoteError::InvalidHex));
+
+        // Truncated quotes are rejected
+        // 被截断的报价会被拒绝
+        let maker = Keypair::new();
+        let encoded = SignedQuote::sign(&maker, &Pubkey::new_unique(), quote(&maker))
+            .unwrap()
+            .to_hex();
+        assert_eq!(SignedQuote::from_hex(&encoded[..120]), Err(SignedQuoteError::InvalidQuote));
+        assert_eq!(SignedQuote::from_hex(""), Err(SignedQuoteError::InvalidQuote));
+    }
+}
@@ -0,0 +1,85 @@
+// Settle funds instruction test module
+// 结算资金指令测试模块
+
+mod common;
+
+#[cfg(test)]
+mod settle_tests {
+    use super::common;
+    use solana_program::pubkey::Pubkey;
+    use solana_rust_dex::state::{Order, StakeAccount, StakePool, ACCOUNT_VERSION, ORDER_RESERVED_LEN};
+    use solana_rust_dex_client::DexClient;
+    use spl_associated_token_account::get_associated_token_address;
+
+    fn order(market: Pubkey, is_buy: bool) -> Order {
+        Order {
+            is_initialized: true,
+            order_id: 1,
+            owner: Pubkey::new_unique(),
+            market,
+            is_buy,
+            limit_price: 100,
+            original_quantity: 10,
+            remaining_quantity: 10,
+            creation_timestamp: 0,
+            linked_order: Pubkey::default(),
+            display_quantity: 0,
+            visible_quantity: 10,
+            referrer: Pubkey::default(),
+            memo: [0; 32],
+            version: ACCOUNT_VERSION,
+            reserved: [0; ORDER_RESERVED_LEN],
+        }
+    }
+
+    #[test]
+    fn test_settle_funds_with_stake_discount() {
+        let program_id = Pubkey::new_unique();
+        let client = DexClient::new("http://127.0.0.1:8899", program_id);
+        let authority = Pubkey::new_unique();
+        let market_pubkey = Pubkey::new_unique();
+        let market = common::market();
+        let (taker_account, maker_account) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (taker, maker) = (order(market_pubkey, true), order(market_pubkey, false));
+        let settle = |stake_discount: bool| {
+            client
+                .settle_funds_ix_with_accounts(
+                    &authority,
+                    &market_pubkey,
+                    &market,
+                    &taker_account,
+                    &taker,
+                    &maker_account,
+                    &maker,
+                    stake_discount,
+                )
+                .unwrap()
+        };
+
+        // Without stake the instruction only carries the matched orders and their owners' accounts
+        // 没有质押时，指令只包含撮合的订单及其所有者的账户
+        let plain = settle(false);
+        assert_eq!(plain.signers, vec![authority]);
+        assert_eq!(plain.instructions.len(), 1);
+        let instruction = &plain.instructions[0];
+        assert_eq!(instruction.accounts[1].pubkey, market_pubkey);
+        assert_eq!(instruction.accounts[2].pubkey, taker_account);
+        assert_eq!(instruction.accounts[3].pubkey, maker_account);
+        assert_eq!(
+            instruction.accounts[4].pubkey,
+            get_associated_token_address(&taker.owner, &market.base_mint)
+        );
+        let stake_pool = StakePool::find_address(&program_id).0;
+        assert!(instruction.accounts.iter().all(|account| account.pubkey != stake_pool));
+
+        // With stake the stake pool and the taker's stake account follow the optional accounts
+        // 有质押时，质押池和吃单方的质押账户位于可选账户之后
+        let discounted = settle(true);
+        let instruction = &discounted.instructions[0];
+        assert_eq!(instruction.accounts.len(), 21);
+        assert_eq!(instruction.accounts[..16], plain.instructions[0].accounts[..]);
+        assert_eq!(instruction.accounts[19].pubkey, stake_pool);
+        assert_eq!(instruction.accounts[20].pubkey, StakeAccount::find_address(&program_id, &taker.owner).0);
+        assert!(instruction.accounts[19..].iter().all(|account| !account.is_writable && !account.is_signer));
+    }
+}
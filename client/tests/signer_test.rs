@@ -0,0 +1,69 @@
+// Signer abstraction test module
+// 签名者抽象测试模块
+
+#[cfg(test)]
+mod signer_tests {
+    use solana_program::{hash::Hash, pubkey::Pubkey, system_instruction};
+    use solana_rust_dex_client::signer::{RemoteSigner, SigningService};
+    use solana_sdk::{
+        signature::{Keypair, Signature, Signer},
+        signer::SignerError,
+        transaction::Transaction,
+    };
+
+    // Signing service backed by a key it keeps to itself, standing in for a KMS
+    // 由自身保存密钥的签名服务，用来模拟 KMS
+    struct KeyVault(Keypair);
+
+    impl SigningService for KeyVault {
+        fn sign_message(&self, pubkey: &Pubkey, message: &[u8]) -> Result<Signature, SignerError> {
+            if *pubkey != self.0.pubkey() {
+                return Err(SignerError::KeypairPubkeyMismatch);
+            }
+            Ok(self.0.sign_message(message))
+        }
+    }
+
+    #[test]
+    fn test_remote_signer_signs_with_local_signers() {
+        // A remote signer and a local keypair sign the same transaction
+        // 远程签名者与本地密钥对签署同一笔交易
+        let payer = Keypair::new();
+        let custodied = Keypair::new();
+        let custodied_pubkey = custodied.pubkey();
+        let remote = RemoteSigner::new(custodied_pubkey, KeyVault(custodied));
+
+        let instruction = system_instruction::transfer(&remote.pubkey(), &payer.pubkey(), 1);
+        let signers: [&dyn Signer; 2] = [&payer, &remote];
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &signers,
+            Hash::default(),
+        );
+        assert!(transaction.is_signed());
+        assert!(transaction.verify().is_ok());
+        assert_eq!(remote.try_pubkey().unwrap(), custodied_pubkey);
+        assert!(!remote.is_interactive());
+    }
+
+    #[test]
+    fn test_remote_signer_rejects_bad_signatures() {
+        // A service signing with the wrong key is caught before the transaction is sent
+        // 使用错误密钥签名的服务会在交易发送前被发现
+        let remote = RemoteSigner::new(Pubkey::new_unique(), KeyVault(Keypair::new()));
+        assert_eq!(
+            remote.try_sign_message(b"message"),
+            Err(SignerError::KeypairPubkeyMismatch)
+        );
+
+        struct Forger;
+        impl SigningService for Forger {
+            fn sign_message(&self, _pubkey: &Pubkey, _message: &[u8]) -> Result<Signature, SignerError> {
+                Ok(Signature::new_unique())
+            }
+        }
+        let remote = RemoteSigner::new(Pubkey::new_unique(), Forger);
+        assert!(matches!(remote.try_sign_message(b"message"), Err(SignerError::Custom(_))));
+    }
+}
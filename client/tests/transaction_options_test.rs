@@ -0,0 +1,77 @@
+// Transaction options test module
+// 交易选项测试模块
+
+#[cfg(test)]
+mod transaction_options_tests {
+    use solana_program::{instruction::Instruction, pubkey::Pubkey};
+    use solana_rust_dex_client::{DexClient, TransactionOptions};
+    use solana_sdk::compute_budget::{self, ComputeBudgetInstruction};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_memo_is_appended() {
+        let instruction = Instruction::new_with_bytes(Pubkey::new_unique(), &[1, 2, 3], vec![]);
+
+        // Without options the instructions are unchanged
+        // 没有选项时指令保持不变
+        let instructions = TransactionOptions::default().apply(std::slice::from_ref(&instruction));
+        assert_eq!(instructions, vec![instruction.clone()]);
+
+        // A memo is appended after the DEX instructions
+        // 备注附加在 DEX 指令之后
+        let options = TransactionOptions::default().with_memo("client:desk-7 tag:kyc-ok");
+        let instructions = options.apply(std::slice::from_ref(&instruction));
+        assert_eq!(instructions.len(), 2);
+        assert_eq!(instructions[0], instruction);
+        assert_eq!(instructions[1].program_id, spl_memo::id());
+        assert_eq!(instructions[1].data, b"client:desk-7 tag:kyc-ok".to_vec());
+        assert!(instructions[1].accounts.is_empty());
+    }
+
+    #[test]
+    fn test_compute_budget_is_prepended() {
+        let instruction = Instruction::new_with_bytes(Pubkey::new_unique(), &[1, 2, 3], vec![]);
+
+        // The compute unit limit and price come before the DEX instructions, the memo after
+        // 计算单元上限和价格位于 DEX 指令之前，备注位于之后
+        let options = TransactionOptions::default()
+            .with_compute_unit_price(5_000)
+            .with_compute_unit_limit(200_000)
+            .with_memo("desk-7");
+        let instructions = options.apply(std::slice::from_ref(&instruction));
+        assert_eq!(instructions.len(), 4);
+        assert_eq!(instructions[0], ComputeBudgetInstruction::set_compute_unit_limit(200_000));
+        assert_eq!(instructions[1], ComputeBudgetInstruction::set_compute_unit_price(5_000));
+        assert_eq!(instructions[2], instruction);
+        assert_eq!(instructions[3].program_id, spl_memo::id());
+
+        // Either can be set alone
+        // 两者都可以单独设置
+        let instructions = TransactionOptions::default()
+            .with_compute_unit_price(1)
+            .apply(std::slice::from_ref(&instruction));
+        assert_eq!(instructions.len(), 2);
+        assert_eq!(instructions[0].program_id, compute_budget::id());
+        assert_eq!(instructions[1], instruction);
+    }
+
+    #[test]
+    fn test_per_call_options_leave_the_client_unchanged() {
+        let options = TransactionOptions::default().with_memo("desk-7");
+        let client = DexClient::new("http://127.0.0.1:8899", Pubkey::new_unique()).with_transaction_options(options.clone());
+
+        // A call's options apply to a client sharing the RPC connection
+        // 单次调用的选项作用于共享 RPC 连接的客户端
+        let urgent = options.clone().with_compute_unit_price(50_000);
+        let scoped = client.with_options(urgent.clone());
+        assert_eq!(scoped.transaction_options, urgent);
+        assert_eq!(scoped.program_id, client.program_id);
+        assert!(Arc::ptr_eq(&scoped.rpc_client, &client.rpc_client));
+
+        // Later calls through the client keep its own options
+        // 之后通过该客户端的调用保留其自身的选项
+        drop(scoped);
+        assert_eq!(client.transaction_options, options);
+        assert_eq!(Arc::strong_count(&client.rpc_client), 1);
+    }
+}
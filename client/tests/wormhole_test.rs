@@ -0,0 +1,81 @@
+// Wormhole-wrapped asset test module
+// Wormhole 包装资产测试模块
+
+#[cfg(test)]
+mod wormhole_tests {
+    use solana_program::pubkey::Pubkey;
+    use solana_rust_dex_client::wormhole::{
+        wrapped_meta_address, wrapped_mint_address, WrappedAsset, CHAIN_ID_ETHEREUM, CHAIN_ID_SOLANA,
+        TOKEN_BRIDGE_DEVNET_PROGRAM_ID, TOKEN_BRIDGE_PROGRAM_ID,
+    };
+
+    fn usdc_on_ethereum() -> [u8; 32] {
+        let mut token_address = [0u8; 32];
+        token_address[12..].copy_from_slice(&[
+            0xa0, 0xb8, 0x69, 0x91, 0xc6, 0x21, 0x8b, 0x36, 0xc1, 0xd1, 0x9d, 0x4a, 0x2e, 0x9e, 0xb0, 0xce, 0x36, 0x06,
+            0xeb, 0x48,
+        ]);
+        token_address
+    }
+
+    fn meta_data(chain: u16, token_address: &[u8; 32], original_decimals: u8) -> Vec<u8> {
+        let mut data = chain.to_le_bytes().to_vec();
+        data.extend_from_slice(token_address);
+        data.push(original_decimals);
+        data
+    }
+
+    #[test]
+    fn test_wrapped_addresses() {
+        // The wrapped mint depends on the bridge, the origin chain and the origin address
+        // 包装铸币取决于桥、来源链和来源地址
+        let token_address = usdc_on_ethereum();
+        let mint = wrapped_mint_address(&TOKEN_BRIDGE_PROGRAM_ID, CHAIN_ID_ETHEREUM, &token_address);
+        assert_eq!(mint, wrapped_mint_address(&TOKEN_BRIDGE_PROGRAM_ID, CHAIN_ID_ETHEREUM, &token_address));
+        assert_ne!(mint, wrapped_mint_address(&TOKEN_BRIDGE_DEVNET_PROGRAM_ID, CHAIN_ID_ETHEREUM, &token_address));
+        assert_ne!(mint, wrapped_mint_address(&TOKEN_BRIDGE_PROGRAM_ID, CHAIN_ID_SOLANA, &token_address));
+        assert_ne!(mint, wrapped_mint_address(&TOKEN_BRIDGE_PROGRAM_ID, CHAIN_ID_ETHEREUM, &[1u8; 32]));
+
+        // Each wrapped mint has its own meta account
+        // 每个包装铸币都有自己的元数据账户
+        let meta = wrapped_meta_address(&TOKEN_BRIDGE_PROGRAM_ID, &mint);
+        assert_ne!(meta, mint);
+        assert_ne!(meta, wrapped_meta_address(&TOKEN_BRIDGE_PROGRAM_ID, &Pubkey::new_unique()));
+    }
+
+    #[test]
+    fn test_wrapped_asset_attestation() {
+        // A meta account decodes to the origin chain, address and decimals
+        // 元数据账户解码为来源链、地址和小数位
+        let token_address = usdc_on_ethereum();
+        let asset = WrappedAsset::unpack(&meta_data(CHAIN_ID_ETHEREUM, &token_address, 6)).unwrap();
+        assert_eq!(asset.chain, CHAIN_ID_ETHEREUM);
+        assert_eq!(asset.token_address, token_address);
+        assert_eq!(asset.original_decimals, 6);
+
+        // The attestation only holds for the mint derived from it
+        // 证明只对由其推导出的铸币成立
+        let mint = wrapped_mint_address(&TOKEN_BRIDGE_PROGRAM_ID, CHAIN_ID_ETHEREUM, &token_address);
+        assert_eq!(asset.mint(&TOKEN_BRIDGE_PROGRAM_ID), mint);
+        assert!(asset.attests(&TOKEN_BRIDGE_PROGRAM_ID, &mint));
+        assert!(!asset.attests(&TOKEN_BRIDGE_PROGRAM_ID, &Pubkey::new_unique()));
+        assert!(!asset.attests(&TOKEN_BRIDGE_DEVNET_PROGRAM_ID, &mint));
+
+        // Truncated data does not decode
+        // 截断的数据无法解码
+        assert!(WrappedAsset::unpack(&meta_data(CHAIN_ID_ETHEREUM, &token_address, 6)[..20]).is_none());
+    }
+
+    #[test]
+    fn test_token_address_hex() {
+        // EVM addresses drop their 12 bytes of padding
+        // EVM 地址会去掉 12 字节的填充
+        let asset = WrappedAsset { chain: CHAIN_ID_ETHEREUM, token_address: usdc_on_ethereum(), original_decimals: 6 };
+        assert_eq!(asset.token_address_hex(), "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48");
+
+        // Other addresses keep all 32 bytes
+        // 其他地址保留全部 32 字节
+        let asset = WrappedAsset { chain: CHAIN_ID_SOLANA, token_address: [0xab; 32], original_decimals: 9 };
+        assert_eq!(asset.token_address_hex(), format!("0x{}", "ab".repeat(32)));
+    }
+}
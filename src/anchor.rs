@@ -0,0 +1,336 @@
+// Anchor integration, behind the `anchor` feature
+//
+// The program keeps its own instruction and account encodings, so it is not an Anchor program:
+// its instructions are tagged by a one-byte variant index rather than an 8-byte sighash, and its
+// accounts carry no discriminator. This module lets Anchor programs work with it anyway:
+//
+// - `SolanaRustDex` is the program type for `Program<'info, SolanaRustDex>`
+// - the account structs implement Anchor's account traits, so `Account<'info, Market>` checks
+//   the owner, the length and the initialized flag, and works with `has_one` and `seeds`
+//   constraints. Accounts have no discriminator, and `OpenOrders` and `QuoteFill` share a
+//   layout, so validate PDAs with `seeds` constraints
+// - `DexError` converts into an Anchor error with the same custom code
+// - `cpi` holds account contexts and functions to call the program from an Anchor program
+
+use crate::{
+    error::DexError,
+    state::{
+        Delegation, Market, OpenOrders, Order, ProgramConfig, QuoteFill, SettlementRecord, TwapOracle,
+        WhitelistEntry,
+    },
+};
+use anchor_lang::{AccountDeserialize, AccountSerialize, Id, Owner};
+use solana_program::{program_error::ProgramError, program_pack::Pack, pubkey::Pubkey};
+
+/// The DEX program, for `Program<'info, SolanaRustDex>`
+#[derive(Clone)]
+pub struct SolanaRustDex;
+
+impl Id for SolanaRustDex {
+    fn id() -> Pubkey {
+        crate::id()
+    }
+}
+
+impl From<DexError> for anchor_lang::error::Error {
+    fn from(e: DexError) -> Self {
+        ProgramError::from(e).into()
+    }
+}
+
+// Anchor account traits for a packed account of the program; the accounts are read-only to
+// other programs, so serializing writes nothing
+macro_rules! impl_anchor_account {
+    ($($account:ty),*) => {
+        $(
+            impl Owner for $account {
+                fn owner() -> Pubkey {
+                    crate::id()
+                }
+            }
+
+            impl AccountDeserialize for $account {
+                fn try_deserialize(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+                    // Checks the length and the initialized flag
+                    Ok(<$account>::unpack(buf)?)
+                }
+
+                fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+                    Ok(<$account>::unpack_unchecked(buf)?)
+                }
+            }
+
+            impl AccountSerialize for $account {}
+        )*
+    };
+}
+
+impl_anchor_account!(
+    ProgramConfig,
+    Market,
+    Order,
+    SettlementRecord,
+    WhitelistEntry,
+    Delegation,
+    OpenOrders,
+    TwapOracle,
+    QuoteFill
+);
+
+/// Cross-program invocations of the DEX from Anchor programs
+///
+/// Each context lists the accounts every call needs, in the program's order; optional accounts,
+/// such as the instructions sysvar or a referrer, follow as the context's remaining accounts.
+pub mod cpi {
+    use crate::instruction::{DexInstruction, SelfTradeBehavior};
+    use anchor_lang::{context::CpiContext, ToAccountInfos, ToAccountMetas};
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        program::invoke_signed,
+    };
+
+    pub mod accounts {
+        use anchor_lang::{ToAccountInfos, ToAccountMetas};
+        use solana_program::{account_info::AccountInfo, instruction::AccountMeta};
+
+        /// Accounts of PlaceLimitOrder
+        pub struct PlaceLimitOrder<'info> {
+            pub owner: AccountInfo<'info>,
+            pub market: AccountInfo<'info>,
+            pub order: AccountInfo<'info>,
+            /// Owner's token account to debit, or the owner's OpenOrders account
+            pub owner_token_account: AccountInfo<'info>,
+            /// Market escrow vault of the locked mint
+            pub vault: AccountInfo<'info>,
+            pub token_program: AccountInfo<'info>,
+            pub system_program: AccountInfo<'info>,
+        }
+
+        impl ToAccountMetas for PlaceLimitOrder<'_> {
+            fn to_account_metas(&self, _is_signer: Option<bool>) -> Vec<AccountMeta> {
+                vec![
+                    AccountMeta::new(*self.owner.key, true),
+                    AccountMeta::new(*self.market.key, false),
+                    AccountMeta::new(*self.order.key, false),
+                    AccountMeta::new(*self.owner_token_account.key, false),
+                    AccountMeta::new(*self.vault.key, false),
+                    AccountMeta::new_readonly(*self.token_program.key, false),
+                    AccountMeta::new_readonly(*self.system_program.key, false),
+                ]
+            }
+        }
+
+        impl<'info> ToAccountInfos<'info> for PlaceLimitOrder<'info> {
+            fn to_account_infos(&self) -> Vec<AccountInfo<'info>> {
+                vec![
+                    self.owner.clone(),
+                    self.market.clone(),
+                    self.order.clone(),
+                    self.owner_token_account.clone(),
+                    self.vault.clone(),
+                    self.token_program.clone(),
+                    self.system_program.clone(),
+                ]
+            }
+        }
+
+        /// Accounts of CancelOrder
+        pub struct CancelOrder<'info> {
+            pub owner: AccountInfo<'info>,
+            pub market: AccountInfo<'info>,
+            pub order: AccountInfo<'info>,
+            /// Owner's token account to credit
+            pub owner_token_account: AccountInfo<'info>,
+            pub base_vault: AccountInfo<'info>,
+            pub quote_vault: AccountInfo<'info>,
+            pub market_signer: AccountInfo<'info>,
+            pub token_program: AccountInfo<'info>,
+        }
+
+        impl ToAccountMetas for CancelOrder<'_> {
+            fn to_account_metas(&self, _is_signer: Option<bool>) -> Vec<AccountMeta> {
+                vec![
+                    AccountMeta::new(*self.owner.key, true),
+                    AccountMeta::new(*self.market.key, false),
+                    AccountMeta::new(*self.order.key, false),
+                    AccountMeta::new(*self.owner_token_account.key, false),
+                    AccountMeta::new(*self.base_vault.key, false),
+                    AccountMeta::new(*self.quote_vault.key, false),
+                    AccountMeta::new_readonly(*self.market_signer.key, false),
+                    AccountMeta::new_readonly(*self.token_program.key, false),
+                ]
+            }
+        }
+
+        impl<'info> ToAccountInfos<'info> for CancelOrder<'info> {
+            fn to_account_infos(&self) -> Vec<AccountInfo<'info>> {
+                vec![
+                    self.owner.clone(),
+                    self.market.clone(),
+                    self.order.clone(),
+                    self.owner_token_account.clone(),
+                    self.base_vault.clone(),
+                    self.quote_vault.clone(),
+                    self.market_signer.clone(),
+                    self.token_program.clone(),
+                ]
+            }
+        }
+
+        /// Accounts of Deposit
+        pub struct Deposit<'info> {
+            pub owner: AccountInfo<'info>,
+            pub market: AccountInfo<'info>,
+            pub open_orders: AccountInfo<'info>,
+            /// Owner's token account to debit
+            pub owner_token_account: AccountInfo<'info>,
+            /// Market escrow vault of the deposited mint
+            pub vault: AccountInfo<'info>,
+            pub token_program: AccountInfo<'info>,
+            pub system_program: AccountInfo<'info>,
+        }
+
+        impl ToAccountMetas for Deposit<'_> {
+            fn to_account_metas(&self, _is_signer: Option<bool>) -> Vec<AccountMeta> {
+                vec![
+                    AccountMeta::new(*self.owner.key, true),
+                    AccountMeta::new(*self.market.key, false),
+                    AccountMeta::new(*self.open_orders.key, false),
+                    AccountMeta::new(*self.owner_token_account.key, false),
+                    AccountMeta::new(*self.vault.key, false),
+                    AccountMeta::new_readonly(*self.token_program.key, false),
+                    AccountMeta::new_readonly(*self.system_program.key, false),
+                ]
+            }
+        }
+
+        impl<'info> ToAccountInfos<'info> for Deposit<'info> {
+            fn to_account_infos(&self) -> Vec<AccountInfo<'info>> {
+                vec![
+                    self.owner.clone(),
+                    self.market.clone(),
+                    self.open_orders.clone(),
+                    self.owner_token_account.clone(),
+                    self.vault.clone(),
+                    self.token_program.clone(),
+                    self.system_program.clone(),
+                ]
+            }
+        }
+
+        /// Accounts of Withdraw
+        pub struct Withdraw<'info> {
+            pub owner: AccountInfo<'info>,
+            pub market: AccountInfo<'info>,
+            pub open_orders: AccountInfo<'info>,
+            /// Token account to credit
+            pub destination: AccountInfo<'info>,
+            /// Market escrow vault of the withdrawn mint
+            pub vault: AccountInfo<'info>,
+            pub market_signer: AccountInfo<'info>,
+            pub token_program: AccountInfo<'info>,
+        }
+
+        impl ToAccountMetas for Withdraw<'_> {
+            fn to_account_metas(&self, _is_signer: Option<bool>) -> Vec<AccountMeta> {
+                vec![
+                    AccountMeta::new_readonly(*self.owner.key, true),
+                    AccountMeta::new(*self.market.key, false),
+                    AccountMeta::new(*self.open_orders.key, false),
+                    AccountMeta::new(*self.destination.key, false),
+                    AccountMeta::new(*self.vault.key, false),
+                    AccountMeta::new_readonly(*self.market_signer.key, false),
+                    AccountMeta::new_readonly(*self.token_program.key, false),
+                ]
+            }
+        }
+
+        impl<'info> ToAccountInfos<'info> for Withdraw<'info> {
+            fn to_account_infos(&self) -> Vec<AccountInfo<'info>> {
+                vec![
+                    self.owner.clone(),
+                    self.market.clone(),
+                    self.open_orders.clone(),
+                    self.destination.clone(),
+                    self.vault.clone(),
+                    self.market_signer.clone(),
+                    self.token_program.clone(),
+                ]
+            }
+        }
+    }
+
+    // Invoke the DEX with a context's accounts, followed by its remaining accounts
+    fn invoke<'info, T: ToAccountMetas + ToAccountInfos<'info>>(
+        ctx: CpiContext<'_, '_, '_, 'info, T>,
+        instruction: DexInstruction,
+    ) -> anchor_lang::Result<()> {
+        let mut accounts = ctx.accounts.to_account_metas(None);
+        let mut account_infos = ctx.accounts.to_account_infos();
+        for account in &ctx.remaining_accounts {
+            accounts.push(if account.is_writable {
+                AccountMeta::new(*account.key, account.is_signer)
+            } else {
+                AccountMeta::new_readonly(*account.key, account.is_signer)
+            });
+        }
+        account_infos.extend(ctx.remaining_accounts.iter().cloned());
+        account_infos.push(ctx.program.clone());
+
+        let instruction = Instruction {
+            program_id: *ctx.program.key,
+            accounts,
+            data: instruction.try_to_vec()?,
+        };
+        invoke_signed(&instruction, &account_infos, ctx.signer_seeds)?;
+        Ok(())
+    }
+
+    /// Place a limit order; pass the instructions sysvar and later optional accounts as
+    /// remaining accounts
+    pub fn place_limit_order<'info>(
+        ctx: CpiContext<'_, '_, '_, 'info, accounts::PlaceLimitOrder<'info>>,
+        is_buy: bool,
+        limit_price: u64,
+        quantity: u64,
+        self_trade_behavior: SelfTradeBehavior,
+        memo: String,
+    ) -> anchor_lang::Result<()> {
+        invoke(
+            ctx,
+            DexInstruction::PlaceLimitOrder {
+                is_buy,
+                limit_price,
+                quantity,
+                self_trade_behavior,
+                memo,
+            },
+        )
+    }
+
+    /// Cancel an order; pass a linked order and its token account as remaining accounts
+    pub fn cancel_order<'info>(
+        ctx: CpiContext<'_, '_, '_, 'info, accounts::CancelOrder<'info>>,
+    ) -> anchor_lang::Result<()> {
+        invoke(ctx, DexInstruction::CancelOrder)
+    }
+
+    /// Deposit into the owner's free balance on a market
+    pub fn deposit<'info>(
+        ctx: CpiContext<'_, '_, '_, 'info, accounts::Deposit<'info>>,
+        is_quote: bool,
+        amount: u64,
+    ) -> anchor_lang::Result<()> {
+        invoke(ctx, DexInstruction::Deposit { is_quote, amount })
+    }
+
+    /// Withdraw from the owner's free balance on a market
+    pub fn withdraw<'info>(
+        ctx: CpiContext<'_, '_, '_, 'info, accounts::Withdraw<'info>>,
+        is_quote: bool,
+        amount: u64,
+    ) -> anchor_lang::Result<()> {
+        invoke(ctx, DexInstruction::Withdraw { is_quote, amount })
+    }
+}
@@ -0,0 +1,111 @@
+// Ed25519 program instructions and their introspection
+
+use solana_program::{
+    account_info::AccountInfo,
+    ed25519_program,
+    instruction::Instruction,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::instructions,
+};
+
+/// Length of an ed25519 public key
+pub const PUBKEY_LEN: usize = 32;
+
+/// Length of an ed25519 signature
+pub const SIGNATURE_LEN: usize = 64;
+
+// Offsets of the single-signature layout: count and padding, one offsets entry, then the data
+const OFFSETS_START: usize = 2;
+const OFFSETS_LEN: usize = 14;
+const DATA_START: usize = OFFSETS_START + OFFSETS_LEN;
+
+// Instruction index marking data held by the ed25519 instruction itself
+const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+/// Create an ed25519 program instruction verifying one signature over a message
+pub fn new_ed25519_instruction(
+    pubkey: &Pubkey,
+    signature: &[u8; SIGNATURE_LEN],
+    message: &[u8],
+) -> Instruction {
+    let public_key_offset = DATA_START;
+    let signature_offset = public_key_offset + PUBKEY_LEN;
+    let message_data_offset = signature_offset + SIGNATURE_LEN;
+
+    let mut data = Vec::with_capacity(message_data_offset + message.len());
+    data.extend_from_slice(&[1, 0]);
+    for value in [
+        signature_offset as u16,
+        CURRENT_INSTRUCTION,
+        public_key_offset as u16,
+        CURRENT_INSTRUCTION,
+        message_data_offset as u16,
+        message.len() as u16,
+        CURRENT_INSTRUCTION,
+    ] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data.extend_from_slice(pubkey.as_ref());
+    data.extend_from_slice(signature);
+    data.extend_from_slice(message);
+
+    Instruction {
+        program_id: ed25519_program::id(),
+        accounts: vec![],
+        data,
+    }
+}
+
+/// Check whether an ed25519 program instruction in the transaction verifies a signature by
+/// `pubkey` over exactly `message`
+///
+/// The runtime rejects transactions whose ed25519 instructions fail, so finding a matching
+/// entry proves the signature. Only entries whose key and message live in the ed25519
+/// instruction itself are considered.
+pub fn is_message_signed(
+    instructions_sysvar: &AccountInfo,
+    pubkey: &Pubkey,
+    message: &[u8],
+) -> Result<bool, ProgramError> {
+    let mut index = 0;
+    while let Ok(instruction) = instructions::load_instruction_at_checked(index, instructions_sysvar) {
+        if instruction.program_id == ed25519_program::id()
+            && signs_message(&instruction.data, pubkey, message)
+        {
+            return Ok(true);
+        }
+        index += 1;
+    }
+
+    Ok(false)
+}
+
+// Check the signature entries of one ed25519 instruction
+fn signs_message(data: &[u8], pubkey: &Pubkey, message: &[u8]) -> bool {
+    let count = match data.first() {
+        Some(count) => *count as usize,
+        None => return false,
+    };
+
+    (0..count).any(|i| {
+        let start = OFFSETS_START + i * OFFSETS_LEN;
+        let field = |n: usize| {
+            data.get(start + 2 * n..start + 2 * n + 2)
+                .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+        };
+        let (Some(public_key_offset), Some(public_key_index), Some(message_offset), Some(message_size), Some(message_index)) =
+            (field(2), field(3), field(4), field(5), field(6))
+        else {
+            return false;
+        };
+        if public_key_index != CURRENT_INSTRUCTION || message_index != CURRENT_INSTRUCTION {
+            return false;
+        }
+
+        let public_key_offset = public_key_offset as usize;
+        let message_offset = message_offset as usize;
+        data.get(public_key_offset..public_key_offset + PUBKEY_LEN) == Some(pubkey.as_ref())
+            && data.get(message_offset..message_offset + message_size as usize) == Some(message)
+    })
+}
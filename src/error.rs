@@ -1,5 +1,8 @@
 // Error module for the DEX program
 
+use num_derive::FromPrimitive;
+use num_traits::FromPrimitive;
+use std::fmt::Display;
 use solana_program::{
     program_error::ProgramError,
     msg,
@@ -7,7 +10,11 @@ use solana_program::{
 use thiserror::Error;
 
 // Custom error enum for the DEX program
-#[derive(Error, Debug, Copy, Clone)]
+//
+// An error's code is its variant index, returned as `ProgramError::Custom(code)`. Codes are
+// stable: variants are only ever appended, so a code names the same error in every program
+// version and clients can decode it with `DexError::from_u32`.
+#[derive(Error, Debug, Copy, Clone, PartialEq, FromPrimitive)]
 pub enum DexError {
     // Invalid instruction data provided
     #[error("Invalid instruction data")]
@@ -48,6 +55,174 @@ pub enum DexError {
     // Arithmetic operation overflow
     #[error("Arithmetic overflow")]
     ArithmeticOverflow,
+
+    // Transaction layout rejected by the market's instruction guard
+    #[error("Instruction guard violation")]
+    InstructionGuardViolation,
+
+    // Quote mint not whitelisted by the program config
+    #[error("Quote mint not allowed")]
+    QuoteMintNotAllowed,
+
+    // Linked order missing, mismatched or already linked
+    #[error("Invalid order link")]
+    InvalidOrderLink,
+
+    // Trade busting not enabled in the program config
+    #[error("Trade busting disabled")]
+    TradeBustingDisabled,
+
+    // Settlement older than the configured trade bust window
+    #[error("Trade bust window expired")]
+    TradeBustWindowExpired,
+
+    // Market status does not allow the operation
+    #[error("Market not active")]
+    MarketNotActive,
+
+    // Market still has resting orders or uncollected fees
+    #[error("Market not empty")]
+    MarketNotEmpty,
+
+    // Order owner is not on the market's whitelist
+    #[error("Owner not whitelisted")]
+    OwnerNotWhitelisted,
+
+    // Firm quote is past its expiry
+    #[error("Quote expired")]
+    QuoteExpired,
+
+    // Firm quote is not signed by its maker in the transaction
+    #[error("Invalid quote signature")]
+    InvalidQuoteSignature,
+
+    // Market oracle price is older than its staleness limit or not trading
+    #[error("Oracle price stale")]
+    OracleStale,
+
+    // Market oracle account is missing or not a usable price account
+    #[error("Invalid oracle price")]
+    InvalidOraclePrice,
+
+    // Order price is further from the oracle price than the market's band
+    #[error("Price outside oracle band")]
+    PriceOutsideOracleBand,
+
+    // Insurance fund balance does not cover the requested draw
+    #[error("Insufficient insurance fund")]
+    InsufficientInsuranceFund,
+
+    // Market mint is not an initialized SPL mint, or base and quote mints are the same
+    #[error("Invalid mint")]
+    InvalidMint,
+
+    // Trustless market mint has a freeze authority
+    #[error("Mint has freeze authority")]
+    FreezableMint,
+
+    // Market or order account is already initialized and would be overwritten
+    #[error("Account already initialized")]
+    AlreadyInitialized,
+
+    // Market, order or record account is not owned by the program
+    #[error("Invalid account owner")]
+    InvalidAccountOwner,
+
+    // Account does not belong to the market or order it is passed with
+    #[error("Account mismatch")]
+    AccountMismatch,
+
+    // Pre-existing program account holds too few lamports to be rent exempt
+    #[error("Account not rent exempt")]
+    NotRentExempt,
+
+    // Delegation used to trade for an owner has passed its expiry slot
+    #[error("Delegation expired")]
+    DelegationExpired,
+
+    // Market or order account has a layout from before versioning and must be migrated first
+    #[error("Account not migrated")]
+    AccountNotMigrated,
+
+    // ConsumeEvents named a sequence number other than that of the oldest queued event
+    #[error("Event sequence mismatch")]
+    EventSequenceMismatch,
+
+    // Market is paused, so it neither takes new orders nor settles matched trades
+    #[error("Market paused")]
+    MarketPaused,
+
+    // Base or quote lot size of a new market is zero
+    #[error("Invalid lot size")]
+    InvalidLotSize,
+
+    // Crank transaction landed more slots after the state it acts on was read than it allows
+    #[error("Transaction stale")]
+    TransactionStale,
+
+    // Swap or liquidity change would pay out less than the minimum the user accepts
+    #[error("Slippage exceeded")]
+    SlippageExceeded,
+
+    // Range position's ticks are out of order, off the tick spacing or would exceed the
+    // range pool's tick capacity
+    #[error("Invalid tick range")]
+    InvalidTickRange,
+
+    // Perp position's equity would not cover its initial margin
+    #[error("Insufficient margin")]
+    InsufficientMargin,
+
+    // Funding interval has not yet passed since the perp market last paid funding
+    #[error("Funding not due")]
+    FundingNotDue,
+
+    // Reduce-only perp position would take none of a fill that does not shrink it
+    #[error("Reduce-only position")]
+    ReduceOnly,
+
+    // Batch auction's interval has not yet passed since its batch started
+    #[error("Auction not due")]
+    AuctionNotDue,
+
+    // Market matches by batch auction, so fills do not settle one by one
+    #[error("Batch auction market")]
+    BatchAuctionMarket,
+
+    // Token sale no longer accepts contributions, or has not yet been finalized
+    #[error("Sale closed")]
+    SaleClosed,
+
+    // Token sale is still open
+    #[error("Sale not ended")]
+    SaleNotEnded,
+
+    // Contribution would take the contributor past the sale's cap
+    #[error("Contribution cap exceeded")]
+    ContributionCapExceeded,
+
+    // Stake pool epoch has not yet run its length
+    #[error("Epoch not ended")]
+    EpochNotEnded,
+
+    // Staked tokens are locked until the epoch they were staked in is distributed
+    #[error("Stake locked")]
+    StakeLocked,
+
+    // Variants are only added once an instruction returns them, since codes cannot be reused:
+    // - OrderExpired: orders carry no expiry; firm quotes and delegations, which do, fail with
+    //   QuoteExpired and DelegationExpired
+    // - SelfTradeAborted: orders do not keep the self-trade behavior they are placed with, so
+    //   no fill can abort on it
+    // - BookFullEviction: only batch auction books are capped, failing with OrderBookFull, and
+    //   no book can find its worst-priced order to evict
+}
+
+impl DexError {
+    /// Error of a custom program error code, None for codes the program does not define
+    pub fn from_u32(code: u32) -> Option<Self> {
+        <Self as FromPrimitive>::from_u32(code)
+    }
 }
 
 // Implement From trait to convert DexError to ProgramError
@@ -62,3 +237,15 @@ pub fn return_dex_error(error: DexError, msg_str: &str) -> ProgramError {
     msg!("Error: {}: {}", msg_str, error.to_string());
     error.into()
 }
+
+// Helper function to log and return errors of a value that failed a check, with the value the
+// check expected and the one it got, so failed transactions can be debugged from their logs
+pub fn return_dex_error_expected(
+    error: DexError,
+    msg_str: &str,
+    expected: impl Display,
+    actual: impl Display,
+) -> ProgramError {
+    msg!("Error: {} (expected {}, got {}): {}", msg_str, expected, actual, error.to_string());
+    error.into()
+}
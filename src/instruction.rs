@@ -1,126 +1,5073 @@
 // Instruction module for the DEX program
 
+use crate::{
+    state::{
+        BatchAuction, Delegation, EmissionPhase, EventQueue, FeeTier, FirmQuote, InstructionGuard, InterestRateCurve, LendingPool,
+        LoyaltyPoints, LoyaltySchedule, Market, MarketCreationMode, MarketRegistry, MarketStatus, OpenOrders, PerpMarket,
+        PerpPosition, Pool, ProgramConfig, RangePool, RangePosition, SaleContribution, StakeAccount, StakePool, TokenSale,
+        TwapOracle, WhitelistEntry, LOYALTY_SCHEDULE_PHASES, STAKE_FEE_TIERS,
+    },
+    wormhole,
+};
 use borsh::{BorshDeserialize, BorshSerialize};
+use shank::ShankInstruction;
 use solana_program::{
     instruction::{AccountMeta, Instruction},
     program_error::ProgramError,
     pubkey::Pubkey,
     system_program,
-    sysvar::rent,
+    sysvar::{self, instructions},
 };
-use std::convert::TryInto;
 
-// Instruction enum for the DEX program
-#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
-pub enum DexInstruction {
-    /// Initialize a new market
+/// Maximum number of orders a single CancelOrdersByIds instruction may cancel
+pub const MAX_CANCEL_ORDER_IDS: usize = 16;
+
+// Instruction enum for the DEX program
+//
+// The `#[account]` attributes mirror the account lists in the doc comments for the IDL. Optional
+// accounts are omitted from the end of the list rather than replaced by the program ID, except
+// where an account notes that the system program stands in for it.
+#[derive(BorshSerialize, BorshDeserialize, ShankInstruction, Clone, Debug, PartialEq)]
+pub enum DexInstruction {
+    /// Initialize a new market
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Market authority account
+    /// 1. `[writable]` Market account (uninitialized)
+    /// 2. `[]` Base token mint
+    /// 3. `[]` Quote token mint
+    /// 4. `[]` System program
+    /// 5. `[]` Program config account (PDA, may be uninitialized)
+    /// 6. `[writable]` Market registry account (PDA, created with the first market)
+    /// 7. `[writable]` Treasury account (required when a lamport creation fee is set)
+    /// 8. `[writable]` Authority's quote token account (required when a quote creation fee is set)
+    /// 9. `[writable]` Treasury quote token account (required when a quote creation fee is set)
+    /// 10. `[]` Token program (required when a quote creation fee is set)
+    ///
+    /// The market is appended to the market registry; the market authority pays the rent of
+    /// the registry's growth. For compatibility with older clients, a Rent sysvar account
+    /// passed before the system program is accepted and ignored.
+    ///
+    /// Order quantities are counted in base lots and prices in quote lots per base lot, so a
+    /// fill of `quantity` lots at `price` moves `quantity * base_lot_size` base tokens and
+    /// `price * quantity * quote_lot_size` quote tokens.
+    #[legacy_optional_accounts_strategy]
+    #[account(0, writable, signer, name = "market_authority", desc = "Market authority account")]
+    #[account(1, writable, name = "market", desc = "Market account (uninitialized)")]
+    #[account(2, name = "base_mint", desc = "Base token mint")]
+    #[account(3, name = "quote_mint", desc = "Quote token mint")]
+    #[account(4, name = "system_program", desc = "System program")]
+    #[account(5, name = "program_config", desc = "Program config account (PDA, may be uninitialized)")]
+    #[account(6, writable, name = "market_registry", desc = "Market registry account (PDA, created with the first market)")]
+    #[account(7, writable, optional, name = "treasury", desc = "Treasury account (required when a lamport creation fee is set)")]
+    #[account(8, writable, optional, name = "authority_token_account", desc = "Authority's quote token account (required when a quote creation fee is set)")]
+    #[account(9, writable, optional, name = "treasury_token_account", desc = "Treasury quote token account (required when a quote creation fee is set)")]
+    #[account(10, optional, name = "token_program", desc = "Token program (required when a quote creation fee is set)")]
+    InitializeMarket {
+        /// Minimum order size in base lots
+        min_base_order_size: u64,
+        /// Tick size in quote lots per base lot (minimum price increment)
+        tick_size: u64,
+        /// Taker fee rate in basis points (1/100 of 1%)
+        taker_fee_bps: u16,
+        /// Maker fee rate in basis points; negative values are rebates paid from the taker fee
+        maker_fee_bps: i16,
+        /// Only accept base and quote mints without a freeze authority
+        trustless: bool,
+        /// Base token amount of one base lot, the unit of order quantities
+        base_lot_size: u64,
+        /// Quote token amount of one quote lot, the unit of limit prices per base lot
+        quote_lot_size: u64,
+    },
+
+    /// Place a limit order
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Order owner account
+    /// 1. `[writable]` Market account
+    /// 2. `[writable]` Order account (uninitialized)
+    /// 3. `[writable]` Owner's token account to debit, or the owner's OpenOrders account (PDA) to
+    ///    debit the free balance of
+    /// 4. `[writable]` Market escrow vault of the locked mint (PDA; quote vault for buys, base
+    ///    vault for sells)
+    /// 5. `[]` Token program
+    /// 6. `[]` System program
+    /// 7. `[]` Instructions sysvar (required when the market has an instruction guard)
+    /// 8. `[]` Referrer's quote token account (optional, requires account 7; pass the system
+    ///    program for no referrer)
+    /// 9. `[]` Owner's whitelist entry (PDA; required when the market is permissioned, requires
+    ///    accounts 7 and 8; pass the system program otherwise)
+    /// 10. `[]` Market's Pyth price account (required when the market has an oracle, requires
+    ///     accounts 7 to 9; pass the system program otherwise)
+    /// 11. `[]` SPL Memo program (required when the order has a memo, requires accounts 7 to 10;
+    ///     pass the system program otherwise)
+    /// 12. `[]` Owner's delegation (PDA; required when account 0 is a delegate, requires
+    ///     accounts 7 to 11)
+    /// 13. `[]` Market signer (PDA; required with account 12)
+    ///
+    /// The locked funds are moved into the market's escrow vault until the order is filled,
+    /// reduced or cancelled. A memo is forwarded to the SPL Memo program, signed by account 0,
+    /// stored with the order and recorded in its fills.
+    ///
+    /// A delegate signs as account 0 and places the order for the owner of the delegation: the
+    /// funds are moved from the owner's token account by the market signer, which the owner has
+    /// approved as token delegate, and the delegate pays the order account's rent.
+    ///
+    /// An order funded from the owner's OpenOrders free balance needs no token transfer; the
+    /// deposited tokens already sit in the escrow vault.
+    ///
+    /// The assigned order ID is set as the instruction's return data, a borsh `PlacedOrder`.
+    #[legacy_optional_accounts_strategy]
+    #[account(0, writable, signer, name = "owner", desc = "Order owner account")]
+    #[account(1, writable, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "order", desc = "Order account (uninitialized)")]
+    #[account(3, writable, name = "owner_token_account", desc = "Owner's token account to debit, or the owner's OpenOrders account (PDA) to debit the free balance of")]
+    #[account(4, writable, name = "vault", desc = "Market escrow vault of the locked mint (PDA; quote vault for buys, base vault for sells)")]
+    #[account(5, name = "token_program", desc = "Token program")]
+    #[account(6, name = "system_program", desc = "System program")]
+    #[account(7, optional, name = "instructions_sysvar", desc = "Instructions sysvar (required when the market has an instruction guard)")]
+    #[account(8, optional, name = "referrer_token_account", desc = "Referrer's quote token account (optional, requires account 7; pass the system program for no referrer)")]
+    #[account(9, optional, name = "whitelist_entry", desc = "Owner's whitelist entry (PDA; required when the market is permissioned, requires accounts 7 and 8; pass the system program otherwise)")]
+    #[account(10, optional, name = "oracle", desc = "Market's Pyth price account (required when the market has an oracle, requires accounts 7 to 9; pass the system program otherwise)")]
+    #[account(11, optional, name = "memo_program", desc = "SPL Memo program (required when the order has a memo, requires accounts 7 to 10; pass the system program otherwise)")]
+    #[account(12, optional, name = "delegation", desc = "Owner's delegation (PDA; required when account 0 is a delegate, requires accounts 7 to 11)")]
+    #[account(13, optional, name = "market_signer", desc = "Market signer (PDA; required with account 12)")]
+    PlaceLimitOrder {
+        /// Side of the order (true for buy, false for sell)
+        is_buy: bool,
+        /// Limit price in quote lots per base lot
+        limit_price: u64,
+        /// Order quantity in base lots
+        quantity: u64,
+        /// Self-trade behavior
+        self_trade_behavior: SelfTradeBehavior,
+        /// Memo of at most `MAX_ORDER_MEMO_LEN` bytes without NUL characters, empty for none
+        memo: String,
+    },
+
+    /// Cancel an order
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Order owner account
+    /// 1. `[writable]` Market account
+    /// 2. `[writable]` Order account
+    /// 3. `[writable]` Owner's token account to credit
+    /// 4. `[writable]` Market base vault (PDA)
+    /// 5. `[writable]` Market quote vault (PDA)
+    /// 6. `[]` Market signer (PDA, owner of the vaults)
+    /// 7. `[]` Token program
+    /// 8. `[writable]` Linked order account (required if the order is linked)
+    /// 9. `[writable]` Owner's token account to credit for the linked order (required if the order is linked)
+    /// 10. `[]` Owner's delegation (PDA; required when account 0 is a delegate, follows account
+    ///     7 for an unlinked order)
+    /// 11. `[writable]` Order owner account (required with account 10)
+    ///
+    /// Locked funds are refunded from the escrow vault, signed by the market signer. The order
+    /// account is closed and its rent returned to the owner. A delegate signs as account 0 and
+    /// can only refund the owner's own token accounts.
+    #[legacy_optional_accounts_strategy]
+    #[account(0, writable, signer, name = "owner", desc = "Order owner account")]
+    #[account(1, writable, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "order", desc = "Order account")]
+    #[account(3, writable, name = "owner_token_account", desc = "Owner's token account to credit")]
+    #[account(4, writable, name = "base_vault", desc = "Market base vault (PDA)")]
+    #[account(5, writable, name = "quote_vault", desc = "Market quote vault (PDA)")]
+    #[account(6, name = "market_signer", desc = "Market signer (PDA, owner of the vaults)")]
+    #[account(7, name = "token_program", desc = "Token program")]
+    #[account(8, writable, optional, name = "linked_order", desc = "Linked order account (required if the order is linked)")]
+    #[account(9, writable, optional, name = "linked_owner_token_account", desc = "Owner's token account to credit for the linked order (required if the order is linked)")]
+    #[account(10, optional, name = "delegation", desc = "Owner's delegation (PDA; required when account 0 is a delegate, follows account 7 for an unlinked order)")]
+    #[account(11, writable, optional, name = "order_owner", desc = "Order owner account (required with account 10)")]
+    CancelOrder,
+
+    /// Settle funds after a trade
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Authority account
+    /// 1. `[writable]` Market account
+    /// 2. `[writable]` Taker order account (a bid)
+    /// 3. `[writable]` Maker order account (an ask)
+    /// 4. `[writable]` Taker base token account
+    /// 5. `[writable]` Taker quote token account
+    /// 6. `[writable]` Maker base token account
+    /// 7. `[writable]` Maker quote token account
+    /// 8. `[writable]` Market fee vault (PDA)
+    /// 9. `[writable]` Market base vault (PDA)
+    /// 10. `[writable]` Market quote vault (PDA)
+    /// 11. `[]` Market signer (PDA)
+    /// 12. `[writable]` Taker order owner, receiving the order account's rent if it fills completely
+    /// 13. `[writable]` Maker order owner, receiving the order account's rent if it fills completely
+    /// 14. `[]` Token program
+    /// 15. `[]` Instructions sysvar (required when the market has an instruction guard)
+    /// 16. `[writable]` Referrer's quote token account (required when the taker order has a referrer)
+    /// 17. `[writable]` Settlement record account (optional, uninitialized and owned by the program;
+    ///     pass the system program for none)
+    /// 18. `[writable]` Market's TWAP oracle (PDA; required when the market has one)
+    /// 19. `[writable]` Market's event queue (PDA; required when the market has one)
+    ///
+    /// Accounts 15 to 18 are positional: to pass a settlement record without a guard or a
+    /// referrer, pass any account in their place, e.g. the instructions sysvar and the fee
+    /// vault. A settlement record allows the trade to be busted later. The fill price is
+    /// recorded in the TWAP oracle. The event queue is found by its address, so it may follow
+    /// the positional accounts in any order, and the fill is appended to it.
+    ///
+    /// The amounts are not supplied by the authority: the taker bid buys the displayed
+    /// quantity of both orders, whichever is smaller, at the maker's limit price, and the two
+    /// orders must cross. Funds come only from the orders' escrow: the maker's base tokens go
+    /// to the taker and the taker's locked quote tokens pay the quote amount plus the taker fee.
+    /// The maker receives the quote amount minus the maker fee, or plus the rebate when the
+    /// maker fee is negative, and the remaining net fee goes to the market's fee vault. When
+    /// the taker order has a referrer, the market's referral share of the net fee is paid to
+    /// the referrer instead. Whatever the taker locked above its cost is returned to it, and
+    /// fully filled orders leave the book and are closed. The token accounts must belong to
+    /// the order owners.
+    ///
+    /// When the stake pool and the taker order owner's stake account are passed, they are
+    /// found by their addresses like the event queue, and the taker fee is cut by the
+    /// discount of the fee tier its stake reaches. Markets with a loyalty schedule must pass
+    /// it and the loyalty points accounts of both order owners the same way, and the fill's
+    /// points accrue to the owners who have opened theirs. On markets with dynamic fees the
+    /// taker fee is the one scaled by realized volatility, which the fill's price then updates.
+    #[legacy_optional_accounts_strategy]
+    #[account(0, signer, name = "authority", desc = "Authority account")]
+    #[account(1, writable, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "taker_order", desc = "Taker order account (a bid)")]
+    #[account(3, writable, name = "maker_order", desc = "Maker order account (an ask)")]
+    #[account(4, writable, name = "taker_base_token_account", desc = "Taker base token account")]
+    #[account(5, writable, name = "taker_quote_token_account", desc = "Taker quote token account")]
+    #[account(6, writable, name = "maker_base_token_account", desc = "Maker base token account")]
+    #[account(7, writable, name = "maker_quote_token_account", desc = "Maker quote token account")]
+    #[account(8, writable, name = "fee_vault", desc = "Market fee vault (PDA)")]
+    #[account(9, writable, name = "base_vault", desc = "Market base vault (PDA)")]
+    #[account(10, writable, name = "quote_vault", desc = "Market quote vault (PDA)")]
+    #[account(11, name = "market_signer", desc = "Market signer (PDA)")]
+    #[account(12, writable, name = "taker_owner", desc = "Taker order owner, receiving the order account's rent if it fills completely")]
+    #[account(13, writable, name = "maker_owner", desc = "Maker order owner, receiving the order account's rent if it fills completely")]
+    #[account(14, name = "token_program", desc = "Token program")]
+    #[account(15, optional, name = "instructions_sysvar", desc = "Instructions sysvar (required when the market has an instruction guard)")]
+    #[account(16, writable, optional, name = "referrer_token_account", desc = "Referrer's quote token account (required when the taker order has a referrer)")]
+    #[account(17, writable, optional, name = "settlement_record", desc = "Settlement record account (optional, uninitialized and owned by the program; pass the system program for none)")]
+    #[account(18, writable, optional, name = "twap_oracle", desc = "Market's TWAP oracle (PDA; required when the market has one)")]
+    #[account(19, writable, optional, name = "event_queue", desc = "Market's event queue (PDA; required when the market has one)")]
+    SettleFunds,
+
+    /// Set the instruction guard of a market
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Market authority account
+    /// 1. `[writable]` Market account
+    #[account(0, signer, name = "market_authority", desc = "Market authority account")]
+    #[account(1, writable, name = "market", desc = "Market account")]
+    SetInstructionGuard {
+        /// New instruction guard
+        guard: InstructionGuard,
+    },
+
+    /// Initialize the program config; the signer becomes its admin
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Config admin account
+    /// 1. `[writable]` Program config account (PDA, uninitialized)
+    /// 2. `[]` System program
+    #[account(0, writable, signer, name = "config_admin", desc = "Config admin account")]
+    #[account(1, writable, name = "program_config", desc = "Program config account (PDA, uninitialized)")]
+    #[account(2, name = "system_program", desc = "System program")]
+    InitializeProgramConfig,
+
+    /// Update the program config
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Config admin account
+    /// 1. `[writable]` Program config account (PDA)
+    #[account(0, signer, name = "config_admin", desc = "Config admin account")]
+    #[account(1, writable, name = "program_config", desc = "Program config account (PDA)")]
+    UpdateProgramConfig {
+        /// Who may create markets
+        market_creation_mode: MarketCreationMode,
+        /// Whitelisted quote mints (empty allows any quote mint)
+        allowed_quote_mints: Vec<Pubkey>,
+    },
+
+    /// Cancel multiple orders by ID in one instruction
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Order owner account, receiving the rent of the closed order accounts
+    /// 1. `[writable]` Market account
+    /// 2. `[writable]` Owner's base token account to credit for sell orders
+    /// 3. `[writable]` Owner's quote token account to credit for buy orders
+    /// 4. `[writable]` Market base vault (PDA)
+    /// 5. `[writable]` Market quote vault (PDA)
+    /// 6. `[]` Market signer (PDA, owner of the vaults)
+    /// 7. `[]` Token program
+    /// 8. `[writable]` Order accounts, one per ID in the same order as `order_ids`;
+    ///    linked orders must be cancelled in the same batch
+    #[account(0, writable, signer, name = "owner", desc = "Order owner account, receiving the rent of the closed order accounts")]
+    #[account(1, writable, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "owner_base_token_account", desc = "Owner's base token account to credit for sell orders")]
+    #[account(3, writable, name = "owner_quote_token_account", desc = "Owner's quote token account to credit for buy orders")]
+    #[account(4, writable, name = "base_vault", desc = "Market base vault (PDA)")]
+    #[account(5, writable, name = "quote_vault", desc = "Market quote vault (PDA)")]
+    #[account(6, name = "market_signer", desc = "Market signer (PDA, owner of the vaults)")]
+    #[account(7, name = "token_program", desc = "Token program")]
+    #[account(8, writable, name = "order", desc = "Order accounts, one per ID in the same order as `order_ids`; linked orders must be cancelled in the same batch")]
+    CancelOrdersByIds {
+        /// IDs of the orders to cancel (at most `MAX_CANCEL_ORDER_IDS`)
+        order_ids: Vec<u64>,
+    },
+
+    /// Reduce the remaining quantity of a resting order without losing its queue priority
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Order owner account
+    /// 1. `[writable]` Market account
+    /// 2. `[writable]` Order account
+    /// 3. `[writable]` Owner's token account to credit
+    /// 4. `[writable]` Market base vault (PDA)
+    /// 5. `[writable]` Market quote vault (PDA)
+    /// 6. `[]` Market signer (PDA, owner of the vaults)
+    /// 7. `[]` Token program
+    #[account(0, signer, name = "owner", desc = "Order owner account")]
+    #[account(1, writable, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "order", desc = "Order account")]
+    #[account(3, writable, name = "owner_token_account", desc = "Owner's token account to credit")]
+    #[account(4, writable, name = "base_vault", desc = "Market base vault (PDA)")]
+    #[account(5, writable, name = "quote_vault", desc = "Market quote vault (PDA)")]
+    #[account(6, name = "market_signer", desc = "Market signer (PDA, owner of the vaults)")]
+    #[account(7, name = "token_program", desc = "Token program")]
+    ReduceOrder {
+        /// New remaining quantity in base lots (must be below the current one)
+        new_remaining_quantity: u64,
+    },
+
+    /// Set the market creation fee charged at InitializeMarket
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Config admin account
+    /// 1. `[writable]` Program config account (PDA)
+    #[account(0, signer, name = "config_admin", desc = "Config admin account")]
+    #[account(1, writable, name = "program_config", desc = "Program config account (PDA)")]
+    SetMarketCreationFee {
+        /// Protocol treasury receiving the fee
+        treasury: Pubkey,
+        /// Fee in lamports
+        fee_lamports: u64,
+        /// Fee in the new market's quote token
+        fee_quote: u64,
+    },
+
+    /// Link two resting orders so that cancelling one cancels the other (one-cancels-other)
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Order owner account
+    /// 1. `[]` Market account
+    /// 2. `[writable]` First order account
+    /// 3. `[writable]` Second order account
+    #[account(0, signer, name = "owner", desc = "Order owner account")]
+    #[account(1, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "first_order", desc = "First order account")]
+    #[account(3, writable, name = "second_order", desc = "Second order account")]
+    LinkOrders,
+
+    /// Commit a Merkle root of the market's open orders (permissionless crank)
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` Market account
+    /// 1. `[]` Every open order account of the market, sorted by address
+    #[account(0, writable, name = "market", desc = "Market account")]
+    #[account(1, name = "open_order", desc = "Every open order account of the market, sorted by address")]
+    CommitOpenOrdersRoot,
+
+    /// Place an iceberg limit order that only displays one tranche at a time
+    ///
+    /// Accounts expected: same as `PlaceLimitOrder`
+    #[legacy_optional_accounts_strategy]
+    #[account(0, writable, signer, name = "owner", desc = "Order owner account")]
+    #[account(1, writable, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "order", desc = "Order account (uninitialized)")]
+    #[account(3, writable, name = "owner_token_account", desc = "Owner's token account to debit, or the owner's OpenOrders account (PDA) to debit the free balance of")]
+    #[account(4, writable, name = "vault", desc = "Market escrow vault of the locked mint (PDA; quote vault for buys, base vault for sells)")]
+    #[account(5, name = "token_program", desc = "Token program")]
+    #[account(6, name = "system_program", desc = "System program")]
+    #[account(7, optional, name = "instructions_sysvar", desc = "Instructions sysvar (required when the market has an instruction guard)")]
+    #[account(8, optional, name = "referrer_token_account", desc = "Referrer's quote token account (optional, requires account 7; pass the system program for no referrer)")]
+    #[account(9, optional, name = "whitelist_entry", desc = "Owner's whitelist entry (PDA; required when the market is permissioned, requires accounts 7 and 8; pass the system program otherwise)")]
+    #[account(10, optional, name = "oracle", desc = "Market's Pyth price account (required when the market has an oracle, requires accounts 7 to 9; pass the system program otherwise)")]
+    #[account(11, optional, name = "memo_program", desc = "SPL Memo program (required when the order has a memo, requires accounts 7 to 10; pass the system program otherwise)")]
+    #[account(12, optional, name = "delegation", desc = "Owner's delegation (PDA; required when account 0 is a delegate, requires accounts 7 to 11)")]
+    #[account(13, optional, name = "market_signer", desc = "Market signer (PDA; required with account 12)")]
+    PlaceIcebergOrder {
+        /// Side of the order (true for buy, false for sell)
+        is_buy: bool,
+        /// Limit price in quote lots per base lot
+        limit_price: u64,
+        /// Total order quantity in base lots
+        quantity: u64,
+        /// Quantity displayed per tranche in base lots
+        display_quantity: u64,
+        /// Self-trade behavior
+        self_trade_behavior: SelfTradeBehavior,
+        /// Memo of at most `MAX_ORDER_MEMO_LEN` bytes without NUL characters, empty for none
+        memo: String,
+    },
+
+    /// Set the share of taker fees paid to referrers
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Market authority account
+    /// 1. `[writable]` Market account
+    #[account(0, signer, name = "market_authority", desc = "Market authority account")]
+    #[account(1, writable, name = "market", desc = "Market account")]
+    SetReferralShare {
+        /// Referral share in basis points of the fee
+        referral_share_bps: u16,
+    },
+
+    /// Enable or disable trade busting for all markets
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Config admin account
+    /// 1. `[writable]` Program config account (PDA)
+    #[account(0, signer, name = "config_admin", desc = "Config admin account")]
+    #[account(1, writable, name = "program_config", desc = "Program config account (PDA)")]
+    SetTradeBusting {
+        /// Whether market authorities may bust settled trades
+        enabled: bool,
+        /// Number of slots after a settlement during which it may be busted
+        window_slots: u64,
+    },
+
+    /// Reverse an erroneous settlement recorded by SettleFunds
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Market authority account
+    /// 1. `[]` Market account
+    /// 2. `[]` Program config account (PDA)
+    /// 3. `[writable]` Settlement record account
+    /// 4. `[signer]` Taker, owner of the taker token accounts, consenting to the bust
+    /// 5. `[signer]` Maker, owner of the maker token accounts, consenting to the bust
+    /// 6. `[writable]` Taker base token account
+    /// 7. `[writable]` Taker quote token account
+    /// 8. `[writable]` Maker base token account
+    /// 9. `[writable]` Maker quote token account
+    /// 10. `[]` Token program
+    ///
+    /// The base amount goes back to the maker and the quote amount the maker received goes
+    /// back to the taker. Fees already paid are not refunded. Requires trade busting to be
+    /// enabled in the program config and the settlement to be within the bust window.
+    #[account(0, signer, name = "market_authority", desc = "Market authority account")]
+    #[account(1, name = "market", desc = "Market account")]
+    #[account(2, name = "program_config", desc = "Program config account (PDA)")]
+    #[account(3, writable, name = "settlement_record", desc = "Settlement record account")]
+    #[account(4, signer, name = "taker", desc = "Taker, owner of the taker token accounts, consenting to the bust")]
+    #[account(5, signer, name = "maker", desc = "Maker, owner of the maker token accounts, consenting to the bust")]
+    #[account(6, writable, name = "taker_base_token_account", desc = "Taker base token account")]
+    #[account(7, writable, name = "taker_quote_token_account", desc = "Taker quote token account")]
+    #[account(8, writable, name = "maker_base_token_account", desc = "Maker base token account")]
+    #[account(9, writable, name = "maker_quote_token_account", desc = "Maker quote token account")]
+    #[account(10, name = "token_program", desc = "Token program")]
+    BustTrade,
+
+    /// Create the fee vault of a market, a quote token account owned by its own PDA
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Payer account
+    /// 1. `[]` Market account
+    /// 2. `[writable]` Market fee vault (PDA, uninitialized)
+    /// 3. `[]` Quote token mint
+    /// 4. `[]` Token program
+    /// 5. `[]` System program
+    #[account(0, writable, signer, name = "payer", desc = "Payer account")]
+    #[account(1, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "fee_vault", desc = "Market fee vault (PDA, uninitialized)")]
+    #[account(3, name = "quote_mint", desc = "Quote token mint")]
+    #[account(4, name = "token_program", desc = "Token program")]
+    #[account(5, name = "system_program", desc = "System program")]
+    InitializeFeeVault,
+
+    /// Transfer the whole balance of the fee vault out of the market
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Market fee authority account
+    /// 1. `[writable]` Market account
+    /// 2. `[writable]` Market fee vault (PDA)
+    /// 3. `[writable]` Destination quote token account
+    /// 4. `[]` Token program
+    ///
+    /// The swept amount is added to the market's `fees_swept`. When the stake pool and its
+    /// reward vault follow the token program (found by their addresses), the pool's fee share
+    /// of the sweep goes to the reward vault for the next epoch's distribution; the market's
+    /// quote mint must then be the pool's reward mint.
+    ///
+    /// The market's fee split then pays its treasury and creator shares of the rest to their
+    /// fee accounts, which must follow (found by their addresses) when their share is set.
+    #[account(0, signer, name = "fee_authority", desc = "Market fee authority account")]
+    #[account(1, writable, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "fee_vault", desc = "Market fee vault (PDA)")]
+    #[account(3, writable, name = "destination_token_account", desc = "Destination quote token account")]
+    #[account(4, name = "token_program", desc = "Token program")]
+    SweepFees,
+
+    /// Hand the right to sweep fees to a new fee authority
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Market fee authority account
+    /// 1. `[writable]` Market account
+    #[account(0, signer, name = "fee_authority", desc = "Market fee authority account")]
+    #[account(1, writable, name = "market", desc = "Market account")]
+    SetFeeAuthority {
+        /// New fee authority
+        fee_authority: Pubkey,
+    },
+
+    /// Set the trading status of a market
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Market authority account
+    /// 1. `[writable]` Market account
+    #[account(0, signer, name = "market_authority", desc = "Market authority account")]
+    #[account(1, writable, name = "market", desc = "Market account")]
+    SetMarketStatus {
+        /// New market status
+        status: MarketStatus,
+    },
+
+    /// Close an empty market and every account it owns, returning their rent
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Market authority account
+    /// 1. `[writable]` Market account
+    /// 2. `[writable]` Fee vault account (PDA; closed if it exists, its balance must be zero)
+    /// 3. `[writable]` Recipient of the reclaimed lamports
+    /// 4. `[]` Token program
+    /// 5. `[writable]` Base escrow vault (PDA)
+    /// 6. `[writable]` Quote escrow vault (PDA)
+    /// 7. `[]` Market signer PDA owning the escrow vaults
+    /// 8. `[writable]` Insurance fund (PDA)
+    /// 9. `[writable]` TWAP oracle (PDA)
+    /// 10. `[writable]` Event queue (PDA; its events must be consumed)
+    /// 11. `[writable]` Batch auction (PDA)
+    /// 12. `[writable]` Loyalty schedule (PDA)
+    /// 13. `[writable]` Pool (PDA)
+    /// 14. `[writable]` Pool base vault (PDA)
+    /// 15. `[writable]` Pool quote vault (PDA)
+    /// 16. `[writable]` Range pool (PDA)
+    /// 17. `[writable]` Range pool base vault (PDA)
+    /// 18. `[writable]` Range pool quote vault (PDA)
+    /// 19. `[writable]` Perp market (PDA)
+    /// 20. `[writable]` Perp collateral vault (PDA)
+    /// 21. `[writable]` Token sale (PDA)
+    /// 22. `[writable]` Token sale base vault (PDA)
+    /// 23. `[writable]` Token sale quote vault (PDA)
+    /// 24. `[]` Token program of the base mint, when it is not the token program above
+    ///
+    /// The market must have no resting bids or asks, deposits or unconsumed events. Sweep the
+    /// fee vault, and empty the escrow vaults, insurance fund, pools, perp collateral vault and
+    /// token sale vaults first; each account is closed if it exists. Accounts keyed by an owner
+    /// as well as the market, and the pool's LP mint, stay open.
+    #[account(0, signer, name = "market_authority", desc = "Market authority account")]
+    #[account(1, writable, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "fee_vault", desc = "Fee vault account (PDA; closed if it exists, its balance must be zero)")]
+    #[account(3, writable, name = "recipient", desc = "Recipient of the reclaimed lamports")]
+    #[account(4, name = "token_program", desc = "Token program")]
+    #[account(5, writable, name = "base_vault", desc = "Base escrow vault (PDA)")]
+    #[account(6, writable, name = "quote_vault", desc = "Quote escrow vault (PDA)")]
+    #[account(7, name = "market_signer", desc = "Market signer PDA owning the escrow vaults")]
+    #[account(8, writable, name = "insurance_fund", desc = "Insurance fund (PDA)")]
+    #[account(9, writable, name = "twap_oracle", desc = "TWAP oracle (PDA)")]
+    #[account(10, writable, name = "event_queue", desc = "Event queue (PDA; its events must be consumed)")]
+    #[account(11, writable, name = "batch_auction", desc = "Batch auction (PDA)")]
+    #[account(12, writable, name = "loyalty_schedule", desc = "Loyalty schedule (PDA)")]
+    #[account(13, writable, name = "pool", desc = "Pool (PDA)")]
+    #[account(14, writable, name = "pool_base_vault", desc = "Pool base vault (PDA)")]
+    #[account(15, writable, name = "pool_quote_vault", desc = "Pool quote vault (PDA)")]
+    #[account(16, writable, name = "range_pool", desc = "Range pool (PDA)")]
+    #[account(17, writable, name = "range_pool_base_vault", desc = "Range pool base vault (PDA)")]
+    #[account(18, writable, name = "range_pool_quote_vault", desc = "Range pool quote vault (PDA)")]
+    #[account(19, writable, name = "perp_market", desc = "Perp market (PDA)")]
+    #[account(20, writable, name = "perp_vault", desc = "Perp collateral vault (PDA)")]
+    #[account(21, writable, name = "token_sale", desc = "Token sale (PDA)")]
+    #[account(22, writable, name = "token_sale_base_vault", desc = "Token sale base vault (PDA)")]
+    #[account(23, writable, name = "token_sale_quote_vault", desc = "Token sale quote vault (PDA)")]
+    #[account(24, optional, name = "base_token_program", desc = "Token program of the base mint, when it is not the token program above")]
+    CloseMarket,
+
+    /// Force-cancel a resting order as the market authority, refunding its owner
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Market authority account
+    /// 1. `[writable]` Market account
+    /// 2. `[writable]` Order account
+    /// 3. `[writable]` Order owner's token account to credit
+    /// 4. `[writable]` Market base vault (PDA)
+    /// 5. `[writable]` Market quote vault (PDA)
+    /// 6. `[]` Market signer (PDA, owner of the vaults)
+    /// 7. `[]` Token program
+    /// 8. `[writable]` Order owner account, receiving the rent of the closed order accounts
+    /// 9. `[writable]` Linked order account (required if the order is linked)
+    /// 10. `[writable]` Order owner's token account to credit for the linked order (required if the order is linked)
+    ///
+    /// Refund accounts must be owned by the order owner and hold the escrowed mint.
+    #[legacy_optional_accounts_strategy]
+    #[account(0, signer, name = "market_authority", desc = "Market authority account")]
+    #[account(1, writable, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "order", desc = "Order account")]
+    #[account(3, writable, name = "owner_token_account", desc = "Order owner's token account to credit")]
+    #[account(4, writable, name = "base_vault", desc = "Market base vault (PDA)")]
+    #[account(5, writable, name = "quote_vault", desc = "Market quote vault (PDA)")]
+    #[account(6, name = "market_signer", desc = "Market signer (PDA, owner of the vaults)")]
+    #[account(7, name = "token_program", desc = "Token program")]
+    #[account(8, writable, name = "owner", desc = "Order owner account, receiving the rent of the closed order accounts")]
+    #[account(9, writable, optional, name = "linked_order", desc = "Linked order account (required if the order is linked)")]
+    #[account(10, writable, optional, name = "linked_owner_token_account", desc = "Order owner's token account to credit for the linked order (required if the order is linked)")]
+    AdminCancelOrder,
+
+    /// Enable or disable the whitelist of a market
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Market authority account
+    /// 1. `[writable]` Market account
+    ///
+    /// While enabled, only owners with a whitelist entry may place orders. Existing orders
+    /// keep resting, settling and cancelling normally.
+    #[account(0, signer, name = "market_authority", desc = "Market authority account")]
+    #[account(1, writable, name = "market", desc = "Market account")]
+    SetWhitelistEnabled {
+        /// Whether the whitelist is enforced
+        enabled: bool,
+    },
+
+    /// Add an owner to the whitelist of a market
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Market authority account (pays the entry's rent)
+    /// 1. `[]` Market account
+    /// 2. `[writable]` Whitelist entry account (PDA, uninitialized)
+    /// 3. `[]` System program
+    #[account(0, writable, signer, name = "market_authority", desc = "Market authority account (pays the entry's rent)")]
+    #[account(1, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "whitelist_entry", desc = "Whitelist entry account (PDA, uninitialized)")]
+    #[account(3, name = "system_program", desc = "System program")]
+    AddWhitelistEntry {
+        /// Owner to whitelist
+        owner: Pubkey,
+    },
+
+    /// Remove an owner from the whitelist of a market, returning the entry's rent
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Market authority account
+    /// 1. `[]` Market account
+    /// 2. `[writable]` Whitelist entry account (PDA)
+    /// 3. `[writable]` Recipient of the reclaimed lamports
+    #[account(0, signer, name = "market_authority", desc = "Market authority account")]
+    #[account(1, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "whitelist_entry", desc = "Whitelist entry account (PDA)")]
+    #[account(3, writable, name = "recipient", desc = "Recipient of the reclaimed lamports")]
+    RemoveWhitelistEntry,
+
+    /// Trade against a firm quote signed off-chain by its maker
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Taker account (pays the fill record's rent on the first execution)
+    /// 1. `[writable]` Market account
+    /// 2. `[writable]` Quote fill record account (PDA)
+    /// 3. `[writable]` Taker base token account
+    /// 4. `[writable]` Taker quote token account
+    /// 5. `[writable]` Maker base token account
+    /// 6. `[writable]` Maker quote token account
+    /// 7. `[writable]` Market fee vault (PDA)
+    /// 8. `[]` Market RFQ authority (PDA, delegate of the maker's token account it debits)
+    /// 9. `[]` Token program
+    /// 10. `[]` System program
+    /// 11. `[]` Instructions sysvar
+    /// 12. `[]` Taker's whitelist entry (PDA; required when the market is permissioned)
+    ///
+    /// The transaction must contain an ed25519 program instruction verifying the maker's
+    /// signature over the quote's signing message. The trade settles at the quote price, the
+    /// taker pays the market's taker fee into the fee vault, added to the market's
+    /// `fees_accrued`, and executions may fill the quote partially until its quantity is used up
+    /// or it expires. Quotes trade off the book, so their prices and quantities are in token
+    /// units rather than the market's lots.
+    #[legacy_optional_accounts_strategy]
+    #[account(0, writable, signer, name = "taker", desc = "Taker account (pays the fill record's rent on the first execution)")]
+    #[account(1, writable, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "quote_fill", desc = "Quote fill record account (PDA)")]
+    #[account(3, writable, name = "taker_base_token_account", desc = "Taker base token account")]
+    #[account(4, writable, name = "taker_quote_token_account", desc = "Taker quote token account")]
+    #[account(5, writable, name = "maker_base_token_account", desc = "Maker base token account")]
+    #[account(6, writable, name = "maker_quote_token_account", desc = "Maker quote token account")]
+    #[account(7, writable, name = "fee_vault", desc = "Market fee vault (PDA)")]
+    #[account(8, name = "rfq_authority", desc = "Market RFQ authority (PDA, delegate of the maker's token account it debits)")]
+    #[account(9, name = "token_program", desc = "Token program")]
+    #[account(10, name = "system_program", desc = "System program")]
+    #[account(11, name = "instructions_sysvar", desc = "Instructions sysvar")]
+    #[account(12, optional, name = "whitelist_entry", desc = "Taker's whitelist entry (PDA; required when the market is permissioned)")]
+    ExecuteFirmQuote {
+        /// Quote signed by the maker
+        quote: FirmQuote,
+        /// Base token amount to trade
+        base_amount: u64,
+    },
+
+    /// Initialize a new market at the PDA `["market", base_mint, quote_mint, seed]`
+    ///
+    /// Accounts expected: same as `InitializeMarket`, with the market PDA as account 1
+    ///
+    /// The market account is created by the program, so no market keypair is needed. Seed 0
+    /// gives the canonical market of a pair. Creation fees set in the program config apply.
+    #[legacy_optional_accounts_strategy]
+    #[account(0, writable, signer, name = "market_authority", desc = "Market authority account")]
+    #[account(1, writable, name = "market", desc = "Market account (uninitialized)")]
+    #[account(2, name = "base_mint", desc = "Base token mint")]
+    #[account(3, name = "quote_mint", desc = "Quote token mint")]
+    #[account(4, name = "system_program", desc = "System program")]
+    #[account(5, name = "program_config", desc = "Program config account (PDA, may be uninitialized)")]
+    #[account(6, writable, name = "market_registry", desc = "Market registry account (PDA, created with the first market)")]
+    #[account(7, writable, optional, name = "treasury", desc = "Treasury account (required when a lamport creation fee is set)")]
+    #[account(8, writable, optional, name = "authority_token_account", desc = "Authority's quote token account (required when a quote creation fee is set)")]
+    #[account(9, writable, optional, name = "treasury_token_account", desc = "Treasury quote token account (required when a quote creation fee is set)")]
+    #[account(10, optional, name = "token_program", desc = "Token program (required when a quote creation fee is set)")]
+    InitializeMarketWithSeed {
+        /// Seed distinguishing markets of the same pair
+        seed: u64,
+        /// Minimum order size in base lots
+        min_base_order_size: u64,
+        /// Tick size in quote lots per base lot (minimum price increment)
+        tick_size: u64,
+        /// Taker fee rate in basis points (1/100 of 1%)
+        taker_fee_bps: u16,
+        /// Maker fee rate in basis points; negative values are rebates paid from the taker fee
+        maker_fee_bps: i16,
+        /// Only accept base and quote mints without a freeze authority
+        trustless: bool,
+        /// Base token amount of one base lot, the unit of order quantities
+        base_lot_size: u64,
+        /// Quote token amount of one quote lot, the unit of limit prices per base lot
+        quote_lot_size: u64,
+    },
+
+    /// Set or clear the oracle circuit breaker of a market
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Market authority account
+    /// 1. `[writable]` Market account
+    ///
+    /// With an oracle set, new orders must be priced within `band_bps` of the oracle price,
+    /// converted to market price units of 10^`price_expo`, and are rejected while the price is
+    /// older than `max_staleness_slots` or not trading. The default pubkey clears the oracle.
+    #[account(0, signer, name = "market_authority", desc = "Market authority account")]
+    #[account(1, writable, name = "market", desc = "Market account")]
+    SetOracle {
+        /// Pyth price account
+        oracle: Pubkey,
+        /// Maximum distance of an order's price from the oracle price in basis points
+        band_bps: u16,
+        /// Maximum age of the oracle price in slots
+        max_staleness_slots: u64,
+        /// Exponent of one unit of the market's limit prices in the oracle's price units
+        price_expo: i32,
+    },
+
+    /// Halt a market whose oracle price is stale (permissionless crank)
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` Market account
+    /// 1. `[]` Market's Pyth price account
+    ///
+    /// An active market is set to `CancelOnly` when its oracle price is stale or not trading.
+    /// The authority reactivates it with `SetMarketStatus` once the oracle recovers.
+    #[account(0, writable, name = "market", desc = "Market account")]
+    #[account(1, name = "oracle", desc = "Market's Pyth price account")]
+    CheckOracle,
+
+    /// Create the insurance fund of a market, a quote token account owned by its own PDA
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Payer account
+    /// 1. `[]` Market account
+    /// 2. `[writable]` Market insurance fund (PDA, uninitialized)
+    /// 3. `[]` Quote token mint
+    /// 4. `[]` Token program
+    /// 5. `[]` System program
+    #[account(0, writable, signer, name = "payer", desc = "Payer account")]
+    #[account(1, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "insurance_fund", desc = "Market insurance fund (PDA, uninitialized)")]
+    #[account(3, name = "quote_mint", desc = "Quote token mint")]
+    #[account(4, name = "token_program", desc = "Token program")]
+    #[account(5, name = "system_program", desc = "System program")]
+    InitializeInsuranceFund,
+
+    /// Add quote tokens to a market's insurance fund
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Depositor, the owner of the source token account
+    /// 1. `[]` Market account
+    /// 2. `[writable]` Source quote token account
+    /// 3. `[writable]` Market insurance fund (PDA)
+    /// 4. `[]` Token program
+    #[account(0, signer, name = "depositor", desc = "Depositor, the owner of the source token account")]
+    #[account(1, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "source_token_account", desc = "Source quote token account")]
+    #[account(3, writable, name = "insurance_fund", desc = "Market insurance fund (PDA)")]
+    #[account(4, name = "token_program", desc = "Token program")]
+    DepositInsuranceFund {
+        /// Quote token amount to deposit
+        amount: u64,
+    },
+
+    /// Draw quote tokens from a market's insurance fund to cover a shortfall
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Market authority account
+    /// 1. `[]` Market account
+    /// 2. `[writable]` Market insurance fund (PDA)
+    /// 3. `[writable]` Destination quote token account
+    /// 4. `[]` Token program
+    #[account(0, signer, name = "market_authority", desc = "Market authority account")]
+    #[account(1, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "insurance_fund", desc = "Market insurance fund (PDA)")]
+    #[account(3, writable, name = "destination_token_account", desc = "Destination quote token account")]
+    #[account(4, name = "token_program", desc = "Token program")]
+    WithdrawInsuranceFund {
+        /// Quote token amount to draw
+        amount: u64,
+    },
+
+    /// Create the escrow vaults of a market, token accounts owned by the market signer PDA
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Payer account
+    /// 1. `[]` Market account
+    /// 2. `[writable]` Market base vault (PDA, uninitialized)
+    /// 3. `[writable]` Market quote vault (PDA, uninitialized)
+    /// 4. `[]` Market signer (PDA)
+    /// 5. `[]` Base token mint
+    /// 6. `[]` Quote token mint
+    /// 7. `[]` Token program
+    /// 8. `[]` System program
+    ///
+    /// Orders lock their funds in these vaults, so they must exist before the first order.
+    #[account(0, writable, signer, name = "payer", desc = "Payer account")]
+    #[account(1, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "base_vault", desc = "Market base vault (PDA, uninitialized)")]
+    #[account(3, writable, name = "quote_vault", desc = "Market quote vault (PDA, uninitialized)")]
+    #[account(4, name = "market_signer", desc = "Market signer (PDA)")]
+    #[account(5, name = "base_mint", desc = "Base token mint")]
+    #[account(6, name = "quote_mint", desc = "Quote token mint")]
+    #[account(7, name = "token_program", desc = "Token program")]
+    #[account(8, name = "system_program", desc = "System program")]
+    InitializeMarketVaults,
+
+    /// Close an empty order account, never used or left behind by an earlier cancel, and
+    /// return its rent
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Order account (owned by the program, no live order)
+    /// 1. `[writable]` Recipient of the reclaimed lamports
+    ///
+    /// The order account signs with the keypair it was created with. Cancelled and fully
+    /// filled orders are closed by CancelOrder, CancelOrdersByIds, AdminCancelOrder and
+    /// SettleFunds, with their rent going back to the order owner.
+    #[account(0, writable, signer, name = "order", desc = "Order account (owned by the program, no live order)")]
+    #[account(1, writable, name = "recipient", desc = "Recipient of the reclaimed lamports")]
+    CloseOrderAccount,
+
+    /// Set the minimum notional of new orders, so dust orders at high prices cannot fill the
+    /// book cheaply
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Market authority account
+    /// 1. `[writable]` Market account
+    ///
+    /// The notional is the order's limit price times its quantity in quote tokens. Resting
+    /// orders are not affected.
+    #[account(0, signer, name = "market_authority", desc = "Market authority account")]
+    #[account(1, writable, name = "market", desc = "Market account")]
+    SetMinQuoteNotional {
+        /// Minimum notional in quote tokens (0 for none)
+        min_quote_notional: u64,
+    },
+
+    /// Authorize a secondary key to place and cancel the owner's orders on a market until a slot
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Owner account (pays the delegation's rent)
+    /// 1. `[]` Market account
+    /// 2. `[writable]` Delegation account (PDA `["delegation", market, owner]`)
+    /// 3. `[]` System program
+    ///
+    /// Replaces the owner's previous delegate on the market; an expiry slot that has passed
+    /// revokes the delegation. The delegate cannot withdraw: placing also needs the owner to
+    /// approve the market signer as token delegate of the accounts that fund orders.
+    #[account(0, writable, signer, name = "owner", desc = "Owner account (pays the delegation's rent)")]
+    #[account(1, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "delegation", desc = "Delegation account (PDA `[\"delegation\", market, owner]`)")]
+    #[account(3, name = "system_program", desc = "System program")]
+    Delegate {
+        /// Key allowed to trade for the owner
+        delegate: Pubkey,
+        /// First slot at which the delegation no longer applies
+        expiry_slot: u64,
+    },
+
+    /// Deposit tokens into the owner's OpenOrders free balance to fund later orders
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Owner account (pays the OpenOrders rent on the first deposit)
+    /// 1. `[writable]` Market account
+    /// 2. `[writable]` OpenOrders account (PDA `["open_orders", market, owner]`)
+    /// 3. `[writable]` Owner's token account to debit
+    /// 4. `[writable]` Market escrow vault of the deposited mint (PDA)
+    /// 5. `[]` Token program
+    /// 6. `[]` System program
+    #[account(0, writable, signer, name = "owner", desc = "Owner account (pays the OpenOrders rent on the first deposit)")]
+    #[account(1, writable, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "open_orders", desc = "OpenOrders account (PDA `[\"open_orders\", market, owner]`)")]
+    #[account(3, writable, name = "owner_token_account", desc = "Owner's token account to debit")]
+    #[account(4, writable, name = "vault", desc = "Market escrow vault of the deposited mint (PDA)")]
+    #[account(5, name = "token_program", desc = "Token program")]
+    #[account(6, name = "system_program", desc = "System program")]
+    Deposit {
+        /// Deposit quote tokens (true) or base tokens (false)
+        is_quote: bool,
+        /// Amount in token units
+        amount: u64,
+    },
+
+    /// Withdraw tokens from the owner's OpenOrders free balance
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Owner account
+    /// 1. `[writable]` Market account
+    /// 2. `[writable]` OpenOrders account (PDA)
+    /// 3. `[writable]` Token account to credit
+    /// 4. `[writable]` Market escrow vault of the withdrawn mint (PDA)
+    /// 5. `[]` Market signer (PDA, owner of the vaults)
+    /// 6. `[]` Token program
+    ///
+    /// Withdrawals are accepted whatever the market status, so deposits can always be recovered.
+    #[account(0, signer, name = "owner", desc = "Owner account")]
+    #[account(1, writable, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "open_orders", desc = "OpenOrders account (PDA)")]
+    #[account(3, writable, name = "token_account", desc = "Token account to credit")]
+    #[account(4, writable, name = "vault", desc = "Market escrow vault of the withdrawn mint (PDA)")]
+    #[account(5, name = "market_signer", desc = "Market signer (PDA, owner of the vaults)")]
+    #[account(6, name = "token_program", desc = "Token program")]
+    Withdraw {
+        /// Withdraw quote tokens (true) or base tokens (false)
+        is_quote: bool,
+        /// Amount in token units
+        amount: u64,
+    },
+
+    /// Create the market's TWAP oracle, updated with the price of every settled fill
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Market authority, paying for the oracle account
+    /// 1. `[writable]` Market account
+    /// 2. `[writable]` TWAP oracle account (PDA)
+    /// 3. `[]` System program
+    ///
+    /// From then on every SettleFunds on the market must pass the oracle.
+    #[account(0, writable, signer, name = "market_authority", desc = "Market authority, paying for the oracle account")]
+    #[account(1, writable, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "twap_oracle", desc = "TWAP oracle account (PDA)")]
+    #[account(3, name = "system_program", desc = "System program")]
+    InitializeTwapOracle {
+        /// Time constant of the oracle's exponential moving average in seconds
+        ema_period_seconds: u64,
+    },
+
+    /// Upgrade a market or order account created before layout versioning to the current layout
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Payer of the rent of the larger account
+    /// 1. `[writable]` Market or order account
+    /// 2. `[]` System program
+    ///
+    /// The account grows by its version byte and the zeroed fields and reserved bytes after it,
+    /// and every existing field keeps its value. Anyone may migrate any account; an account
+    /// already at the current layout is left unchanged.
+    #[account(0, writable, signer, name = "payer", desc = "Payer of the rent of the larger account")]
+    #[account(1, writable, name = "account", desc = "Market or order account")]
+    #[account(2, name = "system_program", desc = "System program")]
+    MigrateAccount,
+
+    /// Create the market's event queue, which every settled fill is appended to
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Market authority, paying for the queue account
+    /// 1. `[writable]` Market account
+    /// 2. `[writable]` Event queue account (PDA)
+    /// 3. `[]` System program
+    ///
+    /// From then on every SettleFunds on the market must pass the queue, which the program
+    /// finds by its address among the instruction's accounts.
+    #[account(0, writable, signer, name = "market_authority", desc = "Market authority, paying for the queue account")]
+    #[account(1, writable, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "event_queue", desc = "Event queue account (PDA)")]
+    #[account(3, name = "system_program", desc = "System program")]
+    InitializeEventQueue,
+
+    /// Remove processed events from the front of a market's event queue
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Market authority
+    /// 1. `[]` Market account
+    /// 2. `[writable]` Event queue account (PDA)
+    ///
+    /// Fails unless `seq_num` is the sequence number of the oldest queued event, so a crank
+    /// that retries a batch, or whose events were overwritten, never removes events it has not
+    /// processed. Sequence numbers only increase, so a delayed transaction of a batch that was
+    /// already consumed never applies again. With a nonzero `max_staleness_slots` it also fails
+    /// once more than that many slots have passed since `observed_slot`, the slot the crank read
+    /// the queue at.
+    #[account(0, signer, name = "market_authority", desc = "Market authority")]
+    #[account(1, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "event_queue", desc = "Event queue account (PDA)")]
+    ConsumeEvents {
+        /// Sequence number of the oldest queued event
+        seq_num: u64,
+        /// Maximum number of events to remove
+        limit: u16,
+        /// Slot the crank read the queue at
+        observed_slot: u64,
+        /// Slots after `observed_slot` the transaction may land in, or 0 for no limit
+        max_staleness_slots: u64,
+    },
+
+    /// Return tokens sent straight to a market escrow vault
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Market authority
+    /// 1. `[]` Market account
+    /// 2. `[writable]` Market escrow vault of the rescued mint (PDA)
+    /// 3. `[writable]` Token account to credit
+    /// 4. `[]` Market signer (PDA, owner of the vaults)
+    /// 5. `[]` Token program
+    ///
+    /// Pays out only the vault balance above what the market owes from it, the locks of resting
+    /// orders and the free balances, so escrowed funds cannot be taken. A Token-2022 mint is
+    /// found by its address among the instruction's accounts.
+    #[account(0, signer, name = "market_authority", desc = "Market authority")]
+    #[account(1, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "vault", desc = "Market escrow vault of the rescued mint (PDA)")]
+    #[account(3, writable, name = "destination", desc = "Token account to credit")]
+    #[account(4, name = "market_signer", desc = "Market signer (PDA, owner of the vaults)")]
+    #[account(5, name = "token_program", desc = "Token program")]
+    RescueTokens {
+        /// Rescue quote tokens (true) or base tokens (false)
+        is_quote: bool,
+    },
+
+    /// Create the market's constant-product pool, its reserve vaults and its LP mint
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Market authority, paying for the pool accounts
+    /// 1. `[]` Market account
+    /// 2. `[writable]` Pool account (PDA)
+    /// 3. `[writable]` Pool base vault (PDA, uninitialized)
+    /// 4. `[writable]` Pool quote vault (PDA, uninitialized)
+    /// 5. `[writable]` LP mint (PDA, uninitialized)
+    /// 6. `[]` Base token mint
+    /// 7. `[]` Quote token mint
+    /// 8. `[]` Token program of the LP mint
+    /// 9. `[]` System program
+    ///
+    /// The LP mint has the decimals of the base mint and no freeze authority. The pool starts
+    /// empty; the first AddLiquidity sets its price.
+    #[account(0, writable, signer, name = "market_authority", desc = "Market authority, paying for the pool accounts")]
+    #[account(1, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "pool", desc = "Pool account (PDA)")]
+    #[account(3, writable, name = "base_vault", desc = "Pool base vault (PDA, uninitialized)")]
+    #[account(4, writable, name = "quote_vault", desc = "Pool quote vault (PDA, uninitialized)")]
+    #[account(5, writable, name = "lp_mint", desc = "LP mint (PDA, uninitialized)")]
+    #[account(6, name = "base_mint", desc = "Base token mint")]
+    #[account(7, name = "quote_mint", desc = "Quote token mint")]
+    #[account(8, name = "token_program", desc = "Token program of the LP mint")]
+    #[account(9, name = "system_program", desc = "System program")]
+    InitializePool {
+        /// Swap fee in basis points of the input amount
+        fee_bps: u16,
+    },
+
+    /// Deposit base and quote tokens into a pool in exchange for LP tokens
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Owner of the deposited tokens
+    /// 1. `[]` Market account
+    /// 2. `[writable]` Pool account (PDA)
+    /// 3. `[writable]` Pool base vault (PDA)
+    /// 4. `[writable]` Pool quote vault (PDA)
+    /// 5. `[writable]` LP mint (PDA)
+    /// 6. `[writable]` Owner's base token account to debit
+    /// 7. `[writable]` Owner's quote token account to debit
+    /// 8. `[writable]` LP token account to credit
+    /// 9. `[]` Token program of the LP mint
+    ///
+    /// Deposits at the pool's ratio, taking the whole of the scarcer amount and as much of the
+    /// other as the ratio needs; fails when fewer than `min_lp_amount` LP tokens are minted. A
+    /// Token-2022 mint is found by its address among the instruction's accounts.
+    #[account(0, signer, name = "owner", desc = "Owner of the deposited tokens")]
+    #[account(1, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "pool", desc = "Pool account (PDA)")]
+    #[account(3, writable, name = "base_vault", desc = "Pool base vault (PDA)")]
+    #[account(4, writable, name = "quote_vault", desc = "Pool quote vault (PDA)")]
+    #[account(5, writable, name = "lp_mint", desc = "LP mint (PDA)")]
+    #[account(6, writable, name = "owner_base_account", desc = "Owner's base token account to debit")]
+    #[account(7, writable, name = "owner_quote_account", desc = "Owner's quote token account to debit")]
+    #[account(8, writable, name = "lp_token_account", desc = "LP token account to credit")]
+    #[account(9, name = "token_program", desc = "Token program of the LP mint")]
+    AddLiquidity {
+        /// Maximum base tokens to deposit
+        max_base_amount: u64,
+        /// Maximum quote tokens to deposit
+        max_quote_amount: u64,
+        /// Minimum LP tokens to receive
+        min_lp_amount: u64,
+    },
+
+    /// Burn LP tokens for their share of a pool's reserves
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Owner of the LP tokens
+    /// 1. `[]` Market account
+    /// 2. `[writable]` Pool account (PDA)
+    /// 3. `[writable]` Pool base vault (PDA)
+    /// 4. `[writable]` Pool quote vault (PDA)
+    /// 5. `[writable]` LP mint (PDA)
+    /// 6. `[writable]` Owner's LP token account to burn from
+    /// 7. `[writable]` Base token account to credit
+    /// 8. `[writable]` Quote token account to credit
+    /// 9. `[]` Token program of the LP mint
+    ///
+    /// Accepted whatever the market status, so liquidity can always be recovered.
+    #[account(0, signer, name = "owner", desc = "Owner of the LP tokens")]
+    #[account(1, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "pool", desc = "Pool account (PDA)")]
+    #[account(3, writable, name = "base_vault", desc = "Pool base vault (PDA)")]
+    #[account(4, writable, name = "quote_vault", desc = "Pool quote vault (PDA)")]
+    #[account(5, writable, name = "lp_mint", desc = "LP mint (PDA)")]
+    #[account(6, writable, name = "lp_token_account", desc = "Owner's LP token account to burn from")]
+    #[account(7, writable, name = "base_destination", desc = "Base token account to credit")]
+    #[account(8, writable, name = "quote_destination", desc = "Quote token account to credit")]
+    #[account(9, name = "token_program", desc = "Token program of the LP mint")]
+    RemoveLiquidity {
+        /// LP tokens to burn
+        lp_amount: u64,
+        /// Minimum base tokens to receive
+        min_base_amount: u64,
+        /// Minimum quote tokens to receive
+        min_quote_amount: u64,
+    },
+
+    /// Swap tokens against a market's pool
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Owner of the swapped tokens
+    /// 1. `[]` Market account
+    /// 2. `[writable]` Pool account (PDA)
+    /// 3. `[writable]` Pool base vault (PDA)
+    /// 4. `[writable]` Pool quote vault (PDA)
+    /// 5. `[writable]` Owner's base token account
+    /// 6. `[writable]` Owner's quote token account
+    /// 7. `[]` Token program
+    ///
+    /// A buy pays `amount_in` quote tokens for base tokens and a sell `amount_in` base tokens
+    /// for quote tokens; fails when less than `min_amount_out` would be paid out. A Token-2022
+    /// mint is found by its address among the instruction's accounts.
+    #[account(0, signer, name = "owner", desc = "Owner of the swapped tokens")]
+    #[account(1, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "pool", desc = "Pool account (PDA)")]
+    #[account(3, writable, name = "base_vault", desc = "Pool base vault (PDA)")]
+    #[account(4, writable, name = "quote_vault", desc = "Pool quote vault (PDA)")]
+    #[account(5, writable, name = "owner_base_account", desc = "Owner's base token account")]
+    #[account(6, writable, name = "owner_quote_account", desc = "Owner's quote token account")]
+    #[account(7, name = "token_program", desc = "Token program")]
+    SwapAgainstPool {
+        /// Buy base tokens with quote tokens (true) or sell base tokens for quote tokens (false)
+        is_buy: bool,
+        /// Tokens paid into the pool
+        amount_in: u64,
+        /// Minimum tokens to receive
+        min_amount_out: u64,
+    },
+
+    /// Swap tokens against a market's resting orders and its pool, whichever is better
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Owner of the swapped tokens
+    /// 1. `[writable]` Market account
+    /// 2. `[writable]` Pool account (PDA)
+    /// 3. `[writable]` Pool base vault (PDA)
+    /// 4. `[writable]` Pool quote vault (PDA)
+    /// 5. `[writable]` Owner's base token account
+    /// 6. `[writable]` Owner's quote token account
+    /// 7. `[writable]` Market base escrow vault (PDA)
+    /// 8. `[writable]` Market quote escrow vault (PDA)
+    /// 9. `[]` Market signer (PDA)
+    /// 10. `[writable]` Market fee vault (PDA)
+    /// 11. `[]` Token program
+    /// 12. `[writable]` For each of `num_orders` resting orders, best price first: the order,
+    ///     its owner (receives the rent of a filled order), and the owner's base and quote
+    ///     token accounts
+    ///
+    /// A buy spends at most `amount_in` quote tokens on asks and the pool, a sell at most
+    /// `amount_in` base tokens on bids and the pool. Before each order it fills, the pool takes
+    /// as much as it can while its marginal price still beats the order's price after the
+    /// taker fee; whatever is left after the orders goes to the pool, and any part neither
+    /// venue fills stays with the owner. Fails when less than `min_amount_out` is paid out in
+    /// total. The event queue, TWAP oracle, instructions sysvar, whitelist entry and Token-2022
+    /// mints the market needs are found by address after the orders.
+    #[account(0, signer, name = "owner", desc = "Owner of the swapped tokens")]
+    #[account(1, writable, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "pool", desc = "Pool account (PDA)")]
+    #[account(3, writable, name = "pool_base_vault", desc = "Pool base vault (PDA)")]
+    #[account(4, writable, name = "pool_quote_vault", desc = "Pool quote vault (PDA)")]
+    #[account(5, writable, name = "owner_base_account", desc = "Owner's base token account")]
+    #[account(6, writable, name = "owner_quote_account", desc = "Owner's quote token account")]
+    #[account(7, writable, name = "base_vault", desc = "Market base escrow vault (PDA)")]
+    #[account(8, writable, name = "quote_vault", desc = "Market quote escrow vault (PDA)")]
+    #[account(9, name = "market_signer", desc = "Market signer (PDA)")]
+    #[account(10, writable, name = "fee_vault", desc = "Market fee vault (PDA)")]
+    #[account(11, name = "token_program", desc = "Token program")]
+    RouteSwap {
+        /// Buy base tokens with quote tokens (true) or sell base tokens for quote tokens (false)
+        is_buy: bool,
+        /// Most tokens to pay in across both venues
+        amount_in: u64,
+        /// Minimum tokens to receive
+        min_amount_out: u64,
+        /// Resting orders passed after the fixed accounts
+        num_orders: u8,
+    },
+
+    /// Create the market's concentrated-liquidity range pool and its vaults
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Market authority, paying for the pool accounts
+    /// 1. `[]` Market account
+    /// 2. `[writable]` Range pool account (PDA)
+    /// 3. `[writable]` Range pool base vault (PDA, uninitialized)
+    /// 4. `[writable]` Range pool quote vault (PDA, uninitialized)
+    /// 5. `[]` Base token mint
+    /// 6. `[]` Quote token mint
+    /// 7. `[]` Token program
+    /// 8. `[]` System program
+    ///
+    /// The pool starts without liquidity at the price of `tick`; positions may only start and
+    /// end at multiples of `tick_spacing`.
+    #[account(0, writable, signer, name = "market_authority", desc = "Market authority, paying for the pool accounts")]
+    #[account(1, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "range_pool", desc = "Range pool account (PDA)")]
+    #[account(3, writable, name = "base_vault", desc = "Range pool base vault (PDA, uninitialized)")]
+    #[account(4, writable, name = "quote_vault", desc = "Range pool quote vault (PDA, uninitialized)")]
+    #[account(5, name = "base_mint", desc = "Base token mint")]
+    #[account(6, name = "quote_mint", desc = "Quote token mint")]
+    #[account(7, name = "token_program", desc = "Token program")]
+    #[account(8, name = "system_program", desc = "System program")]
+    InitializeRangePool {
+        /// Swap fee in basis points of the input amount
+        fee_bps: u16,
+        /// Spacing of the ticks positions start and end at
+        tick_spacing: u16,
+        /// Tick of the initial price
+        tick: i32,
+    },
+
+    /// Add liquidity to the owner's range position, creating it on the first deposit
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Owner of the position, paying for a new position account
+    /// 1. `[]` Market account
+    /// 2. `[writable]` Range pool account (PDA)
+    /// 3. `[writable]` Range position account (PDA)
+    /// 4. `[writable]` Range pool base vault (PDA)
+    /// 5. `[writable]` Range pool quote vault (PDA)
+    /// 6. `[writable]` Owner's base token account to debit
+    /// 7. `[writable]` Owner's quote token account to debit
+    /// 8. `[]` Token program
+    /// 9. `[]` System program
+    ///
+    /// Deposits the base and quote tokens `liquidity` holds over the range at the current
+    /// price: only base tokens below the range and only quote tokens above it. Fails when
+    /// more than `max_base_amount` or `max_quote_amount` would be deposited. A Token-2022 mint
+    /// is found by its address among the instruction's accounts.
+    #[account(0, writable, signer, name = "owner", desc = "Owner of the position, paying for a new position account")]
+    #[account(1, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "range_pool", desc = "Range pool account (PDA)")]
+    #[account(3, writable, name = "position", desc = "Range position account (PDA)")]
+    #[account(4, writable, name = "base_vault", desc = "Range pool base vault (PDA)")]
+    #[account(5, writable, name = "quote_vault", desc = "Range pool quote vault (PDA)")]
+    #[account(6, writable, name = "owner_base_account", desc = "Owner's base token account to debit")]
+    #[account(7, writable, name = "owner_quote_account", desc = "Owner's quote token account to debit")]
+    #[account(8, name = "token_program", desc = "Token program")]
+    #[account(9, name = "system_program", desc = "System program")]
+    AddRangeLiquidity {
+        /// Lowest tick of the range
+        tick_lower: i32,
+        /// Tick the range ends below
+        tick_upper: i32,
+        /// Liquidity to add
+        liquidity: u64,
+        /// Maximum base tokens to deposit
+        max_base_amount: u64,
+        /// Maximum quote tokens to deposit
+        max_quote_amount: u64,
+    },
+
+    /// Remove liquidity from a range position and collect its fees
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Owner of the position, receiving its rent once closed
+    /// 1. `[]` Market account
+    /// 2. `[writable]` Range pool account (PDA)
+    /// 3. `[writable]` Range position account (PDA)
+    /// 4. `[writable]` Range pool base vault (PDA)
+    /// 5. `[writable]` Range pool quote vault (PDA)
+    /// 6. `[writable]` Base token account to credit
+    /// 7. `[writable]` Quote token account to credit
+    /// 8. `[]` Token program
+    ///
+    /// Pays out the tokens `liquidity` holds over the range plus every fee the position has
+    /// earned; a `liquidity` of zero only collects the fees. Fails when less than
+    /// `min_base_amount` or `min_quote_amount` of the liquidity's tokens would be paid out.
+    /// The position is closed once it has no liquidity left. Accepted whatever the market
+    /// status, so liquidity can always be recovered.
+    #[account(0, writable, signer, name = "owner", desc = "Owner of the position, receiving its rent once closed")]
+    #[account(1, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "range_pool", desc = "Range pool account (PDA)")]
+    #[account(3, writable, name = "position", desc = "Range position account (PDA)")]
+    #[account(4, writable, name = "base_vault", desc = "Range pool base vault (PDA)")]
+    #[account(5, writable, name = "quote_vault", desc = "Range pool quote vault (PDA)")]
+    #[account(6, writable, name = "base_destination", desc = "Base token account to credit")]
+    #[account(7, writable, name = "quote_destination", desc = "Quote token account to credit")]
+    #[account(8, name = "token_program", desc = "Token program")]
+    RemoveRangeLiquidity {
+        /// Liquidity to remove
+        liquidity: u64,
+        /// Minimum base tokens to receive for the liquidity
+        min_base_amount: u64,
+        /// Minimum quote tokens to receive for the liquidity
+        min_quote_amount: u64,
+    },
+
+    /// Swap tokens against a market's range pool
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Owner of the swapped tokens
+    /// 1. `[]` Market account
+    /// 2. `[writable]` Range pool account (PDA)
+    /// 3. `[writable]` Range pool base vault (PDA)
+    /// 4. `[writable]` Range pool quote vault (PDA)
+    /// 5. `[writable]` Owner's base token account
+    /// 6. `[writable]` Owner's quote token account
+    /// 7. `[]` Token program
+    ///
+    /// A buy pays up to `amount_in` quote tokens for base tokens and a sell up to `amount_in`
+    /// base tokens for quote tokens, crossing ticks as the price moves; only what the pool
+    /// takes before the end of the price range is paid in. Fails when less than
+    /// `min_amount_out` would be paid out. A Token-2022 mint is found by its address among
+    /// the instruction's accounts.
+    #[account(0, signer, name = "owner", desc = "Owner of the swapped tokens")]
+    #[account(1, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "range_pool", desc = "Range pool account (PDA)")]
+    #[account(3, writable, name = "base_vault", desc = "Range pool base vault (PDA)")]
+    #[account(4, writable, name = "quote_vault", desc = "Range pool quote vault (PDA)")]
+    #[account(5, writable, name = "owner_base_account", desc = "Owner's base token account")]
+    #[account(6, writable, name = "owner_quote_account", desc = "Owner's quote token account")]
+    #[account(7, name = "token_program", desc = "Token program")]
+    SwapAgainstRangePool {
+        /// Buy base tokens with quote tokens (true) or sell base tokens for quote tokens (false)
+        is_buy: bool,
+        /// Most tokens to pay into the pool
+        amount_in: u64,
+        /// Minimum tokens to receive
+        min_amount_out: u64,
+    },
+
+    /// Create the lending pool of a mint, its vault and its share mint
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Config admin, paying for the pool accounts
+    /// 1. `[]` Program config account (PDA)
+    /// 2. `[writable]` Lending pool account (PDA)
+    /// 3. `[writable]` Lending pool vault (PDA, uninitialized)
+    /// 4. `[writable]` Share mint (PDA, uninitialized)
+    /// 5. `[]` Mint to lend
+    /// 6. `[]` Token program of the mint
+    /// 7. `[]` System program
+    ///
+    /// The share mint has the decimals of the mint and is created under its token program.
+    #[account(0, writable, signer, name = "admin", desc = "Config admin, paying for the pool accounts")]
+    #[account(1, name = "config", desc = "Program config account (PDA)")]
+    #[account(2, writable, name = "lending_pool", desc = "Lending pool account (PDA)")]
+    #[account(3, writable, name = "vault", desc = "Lending pool vault (PDA, uninitialized)")]
+    #[account(4, writable, name = "share_mint", desc = "Share mint (PDA, uninitialized)")]
+    #[account(5, name = "mint", desc = "Mint to lend")]
+    #[account(6, name = "token_program", desc = "Token program of the mint")]
+    #[account(7, name = "system_program", desc = "System program")]
+    InitializeLendingPool {
+        /// Borrow rate as a function of utilization
+        rate_curve: InterestRateCurve,
+    },
+
+    /// Supply tokens to a lending pool in exchange for share tokens
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Owner of the supplied tokens
+    /// 1. `[writable]` Lending pool account (PDA)
+    /// 2. `[writable]` Lending pool vault (PDA)
+    /// 3. `[writable]` Share mint (PDA)
+    /// 4. `[writable]` Owner's token account to debit
+    /// 5. `[writable]` Share token account to credit
+    /// 6. `[]` Mint of the pool
+    /// 7. `[]` Token program of the mint
+    ///
+    /// Accrues interest first, then mints shares of what the pool is worth; fails when fewer
+    /// than `min_shares` would be minted.
+    #[account(0, signer, name = "owner", desc = "Owner of the supplied tokens")]
+    #[account(1, writable, name = "lending_pool", desc = "Lending pool account (PDA)")]
+    #[account(2, writable, name = "vault", desc = "Lending pool vault (PDA)")]
+    #[account(3, writable, name = "share_mint", desc = "Share mint (PDA)")]
+    #[account(4, writable, name = "source", desc = "Owner's token account to debit")]
+    #[account(5, writable, name = "share_token_account", desc = "Share token account to credit")]
+    #[account(6, name = "mint", desc = "Mint of the pool")]
+    #[account(7, name = "token_program", desc = "Token program of the mint")]
+    Supply {
+        /// Tokens to supply
+        amount: u64,
+        /// Minimum share tokens to receive
+        min_shares: u64,
+    },
+
+    /// Burn share tokens for their part of a lending pool's tokens
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Owner of the share tokens
+    /// 1. `[writable]` Lending pool account (PDA)
+    /// 2. `[writable]` Lending pool vault (PDA)
+    /// 3. `[writable]` Share mint (PDA)
+    /// 4. `[writable]` Owner's share token account to burn from
+    /// 5. `[writable]` Token account to credit
+    /// 6. `[]` Mint of the pool
+    /// 7. `[]` Token program of the mint
+    ///
+    /// Accrues interest first; fails when less than `min_amount` would be paid out, or when
+    /// more than the tokens not lent out would be.
+    #[account(0, signer, name = "owner", desc = "Owner of the share tokens")]
+    #[account(1, writable, name = "lending_pool", desc = "Lending pool account (PDA)")]
+    #[account(2, writable, name = "vault", desc = "Lending pool vault (PDA)")]
+    #[account(3, writable, name = "share_mint", desc = "Share mint (PDA)")]
+    #[account(4, writable, name = "share_token_account", desc = "Owner's share token account to burn from")]
+    #[account(5, writable, name = "destination", desc = "Token account to credit")]
+    #[account(6, name = "mint", desc = "Mint of the pool")]
+    #[account(7, name = "token_program", desc = "Token program of the mint")]
+    Redeem {
+        /// Share tokens to burn
+        shares: u64,
+        /// Minimum tokens to receive
+        min_amount: u64,
+    },
+
+    /// Initialize the perp market of a market, trading its pair with a virtual base asset
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Market authority account, paying for the perp market accounts
+    /// 1. `[]` Market account
+    /// 2. `[writable]` Perp market account (PDA)
+    /// 3. `[writable]` Perp collateral vault (PDA, uninitialized)
+    /// 4. `[]` Quote mint
+    /// 5. `[]` Quote token program
+    /// 6. `[]` System program
+    ///
+    /// The market must have an oracle, which gives the index price funding is paid against.
+    #[account(0, writable, signer, name = "market_authority", desc = "Market authority account, paying for the perp market accounts")]
+    #[account(1, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "perp_market", desc = "Perp market account (PDA)")]
+    #[account(3, writable, name = "vault", desc = "Perp collateral vault (PDA, uninitialized)")]
+    #[account(4, name = "quote_mint", desc = "Quote mint")]
+    #[account(5, name = "token_program", desc = "Quote token program")]
+    #[account(6, name = "system_program", desc = "System program")]
+    InitializePerpMarket {
+        /// Equity a position must keep against its notional, in basis points
+        initial_margin_bps: u16,
+        /// Largest funding payment per interval against the index price, in basis points
+        max_funding_rate_bps: u16,
+    },
+
+    /// Deposit quote tokens as collateral of a perp position, creating it on the first deposit
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Position owner, paying for the position on the first deposit
+    /// 1. `[]` Market account
+    /// 2. `[]` Perp market account (PDA)
+    /// 3. `[writable]` Perp position account (PDA)
+    /// 4. `[writable]` Perp collateral vault (PDA)
+    /// 5. `[writable]` Owner's quote token account to debit
+    /// 6. `[]` Quote mint
+    /// 7. `[]` Quote token program
+    /// 8. `[]` System program
+    #[account(0, writable, signer, name = "owner", desc = "Position owner, paying for the position on the first deposit")]
+    #[account(1, name = "market", desc = "Market account")]
+    #[account(2, name = "perp_market", desc = "Perp market account (PDA)")]
+    #[account(3, writable, name = "position", desc = "Perp position account (PDA)")]
+    #[account(4, writable, name = "vault", desc = "Perp collateral vault (PDA)")]
+    #[account(5, writable, name = "source", desc = "Owner's quote token account to debit")]
+    #[account(6, name = "quote_mint", desc = "Quote mint")]
+    #[account(7, name = "token_program", desc = "Quote token program")]
+    #[account(8, name = "system_program", desc = "System program")]
+    DepositPerpCollateral {
+        /// Quote tokens to deposit
+        amount: u64,
+    },
+
+    /// Withdraw quote tokens from a perp position
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Position owner
+    /// 1. `[]` Market account
+    /// 2. `[]` Perp market account (PDA)
+    /// 3. `[writable]` Perp position account (PDA)
+    /// 4. `[writable]` Perp collateral vault (PDA)
+    /// 5. `[writable]` Quote token account to credit
+    /// 6. `[]` Quote mint
+    /// 7. `[]` Quote token program
+    ///
+    /// Pays the funding owed first; fails with `InsufficientMargin` when the position's equity
+    /// would no longer cover its initial margin at the mark price.
+    #[account(0, signer, name = "owner", desc = "Position owner")]
+    #[account(1, name = "market", desc = "Market account")]
+    #[account(2, name = "perp_market", desc = "Perp market account (PDA)")]
+    #[account(3, writable, name = "position", desc = "Perp position account (PDA)")]
+    #[account(4, writable, name = "vault", desc = "Perp collateral vault (PDA)")]
+    #[account(5, writable, name = "destination", desc = "Quote token account to credit")]
+    #[account(6, name = "quote_mint", desc = "Quote mint")]
+    #[account(7, name = "token_program", desc = "Quote token program")]
+    WithdrawPerpCollateral {
+        /// Quote tokens to withdraw
+        amount: u64,
+    },
+
+    /// Fill a taker perp order against a maker perp order of the other side
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Market authority account
+    /// 1. `[writable]` Market account
+    /// 2. `[writable]` Perp market account (PDA)
+    /// 3. `[writable]` Taker perp order account
+    /// 4. `[writable]` Maker perp order account
+    /// 5. `[writable]` Taker's perp position (PDA)
+    /// 6. `[writable]` Maker's perp position (PDA)
+    /// 7. `[writable]` Perp collateral vault (PDA)
+    /// 8. `[writable]` Market fee vault (PDA)
+    /// 9. `[]` Quote mint
+    /// 10. `[]` Quote token program
+    /// 11. `[]` Market oracle
+    /// 12. `[writable]` Taker order owner, receiving the order's rent once it is filled
+    /// 13. `[writable]` Maker order owner, receiving the order's rent once it is filled
+    ///
+    /// The fill is not supplied by the authority: the orders must cross, and they trade the
+    /// remaining quantity of both, whichever is smaller, at the maker's limit price, which
+    /// must lie within the market's oracle band. Both positions pay the funding owed first, the
+    /// taker pays the market's taker fee and the maker its maker fee; each position must cover
+    /// its initial margin afterwards unless the fill shrank it. Fully filled orders are closed.
+    #[account(0, signer, name = "market_authority", desc = "Market authority account")]
+    #[account(1, writable, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "perp_market", desc = "Perp market account (PDA)")]
+    #[account(3, writable, name = "taker_order", desc = "Taker perp order account")]
+    #[account(4, writable, name = "maker_order", desc = "Maker perp order account")]
+    #[account(5, writable, name = "taker_position", desc = "Taker's perp position (PDA)")]
+    #[account(6, writable, name = "maker_position", desc = "Maker's perp position (PDA)")]
+    #[account(7, writable, name = "vault", desc = "Perp collateral vault (PDA)")]
+    #[account(8, writable, name = "fee_vault", desc = "Market fee vault (PDA)")]
+    #[account(9, name = "quote_mint", desc = "Quote mint")]
+    #[account(10, name = "token_program", desc = "Quote token program")]
+    #[account(11, name = "oracle", desc = "Market oracle")]
+    #[account(12, writable, name = "taker_owner", desc = "Taker order owner, receiving the order's rent once it is filled")]
+    #[account(13, writable, name = "maker_owner", desc = "Maker order owner, receiving the order's rent once it is filled")]
+    SettlePerpFill,
+
+    /// Pay perp funding for the intervals elapsed since it was last paid; callable by anyone
+    ///
+    /// Accounts expected:
+    /// 0. `[]` Market account
+    /// 1. `[writable]` Perp market account (PDA)
+    /// 2. `[]` Market oracle
+    ///
+    /// Fails with `FundingNotDue` before a whole interval has passed. Positions pay what they
+    /// owe as they are next touched.
+    #[account(0, name = "market", desc = "Market account")]
+    #[account(1, writable, name = "perp_market", desc = "Perp market account (PDA)")]
+    #[account(2, name = "oracle", desc = "Market oracle")]
+    UpdateFunding,
+
+    /// Allow or forbid fills of a perp order that do not shrink the owner's position
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Order owner account
+    /// 1. `[]` Market account
+    /// 2. `[writable]` Perp order account
+    ///
+    /// SettlePerpFill clips the fill of a reduce-only order to the size it closes of the
+    /// owner's position, and fails with `ReduceOnly` when that is nothing.
+    #[account(0, signer, name = "owner", desc = "Order owner account")]
+    #[account(1, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "order", desc = "Perp order account")]
+    SetPerpReduceOnly {
+        /// Whether fills of the order may only shrink the position
+        reduce_only: bool,
+    },
+
+    /// Make the market match by batch auction
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Market authority, paying for the auction account
+    /// 1. `[writable]` Market account
+    /// 2. `[writable]` Batch auction account (PDA)
+    /// 3. `[]` System program
+    ///
+    /// From then on SettleFunds and RouteSwap fail with `BatchAuctionMarket`; orders rest until
+    /// RunAuction crosses them. The first batch starts in the current slot.
+    #[account(0, writable, signer, name = "market_authority", desc = "Market authority, paying for the auction account")]
+    #[account(1, writable, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "batch_auction", desc = "Batch auction account (PDA)")]
+    #[account(3, name = "system_program", desc = "System program")]
+    InitializeBatchAuction {
+        /// Slots orders accumulate for before they are crossed
+        interval_slots: u64,
+    },
+
+    /// Cross every resting order of a batch auction market at one price; callable by anyone
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` Market account
+    /// 1. `[writable]` Batch auction account (PDA)
+    /// 2. `[writable]` Market base escrow vault (PDA)
+    /// 3. `[writable]` Market quote escrow vault (PDA)
+    /// 4. `[]` Market signer (PDA)
+    /// 5. `[writable]` Market fee vault (PDA)
+    /// 6. `[]` Token program
+    /// 7. `[writable]` For each of `num_orders` resting orders, every bid and ask of the market:
+    ///    the order, its owner (receives the rent of a filled order), and the owner's base and
+    ///    quote token accounts
+    ///
+    /// Fails with `AuctionNotDue` until the batch's interval has passed. Bids pay the clearing
+    /// price for base tokens from the asks, and each side pays the market's taker fee; bids
+    /// get back what their lock did not spend. Orders fill best price first and oldest first
+    /// within a price, so only the last order filled on each side can fill in part. The
+    /// clearing price must lie within the oracle band. The event queue, TWAP oracle, oracle and
+    /// Token-2022 mints the market needs are found by address after the orders. The market
+    /// holds at most `MAX_AUCTION_ORDERS` resting orders, so every order fits in one run.
+    #[account(0, writable, name = "market", desc = "Market account")]
+    #[account(1, writable, name = "batch_auction", desc = "Batch auction account (PDA)")]
+    #[account(2, writable, name = "base_vault", desc = "Market base escrow vault (PDA)")]
+    #[account(3, writable, name = "quote_vault", desc = "Market quote escrow vault (PDA)")]
+    #[account(4, name = "market_signer", desc = "Market signer (PDA)")]
+    #[account(5, writable, name = "fee_vault", desc = "Market fee vault (PDA)")]
+    #[account(6, name = "token_program", desc = "Token program")]
+    RunAuction {
+        /// Number of resting orders passed after the fixed accounts
+        num_orders: u8,
+    },
+
+    /// Sell a fixed amount of base tokens for a fixed duration before the market opens
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Market authority, paying for the sale accounts
+    /// 1. `[]` Market account
+    /// 2. `[writable]` Token sale account (PDA)
+    /// 3. `[writable]` Sale base vault (PDA, uninitialized)
+    /// 4. `[writable]` Sale quote vault (PDA, uninitialized)
+    /// 5. `[writable]` Authority's base token account to debit
+    /// 6. `[]` Base token mint
+    /// 7. `[]` Quote token mint
+    /// 8. `[]` Token program
+    /// 9. `[]` System program
+    ///
+    /// The market must be paused and never have traded or held an order. The tokens for sale
+    /// move into the sale's base vault; contributions are accepted until `duration_seconds`
+    /// have passed.
+    #[account(0, writable, signer, name = "market_authority", desc = "Market authority, paying for the sale accounts")]
+    #[account(1, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "token_sale", desc = "Token sale account (PDA)")]
+    #[account(3, writable, name = "base_vault", desc = "Sale base vault (PDA, uninitialized)")]
+    #[account(4, writable, name = "quote_vault", desc = "Sale quote vault (PDA, uninitialized)")]
+    #[account(5, writable, name = "source", desc = "Authority's base token account to debit")]
+    #[account(6, name = "base_mint", desc = "Base token mint")]
+    #[account(7, name = "quote_mint", desc = "Quote token mint")]
+    #[account(8, name = "token_program", desc = "Token program")]
+    #[account(9, name = "system_program", desc = "System program")]
+    InitializeTokenSale {
+        /// Base tokens for sale
+        base_amount: u64,
+        /// Lowest clearing price the sale succeeds at, in quote lots per base lot
+        min_price: u64,
+        /// Most quote tokens one contributor may pay in, zero for no cap
+        max_contribution: u64,
+        /// Seconds contributions are accepted for
+        duration_seconds: u64,
+    },
+
+    /// Pay quote tokens into an open token sale
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Contributor, paying for the contribution on the first payment
+    /// 1. `[]` Market account
+    /// 2. `[writable]` Token sale account (PDA)
+    /// 3. `[writable]` Sale contribution account (PDA)
+    /// 4. `[writable]` Sale quote vault (PDA)
+    /// 5. `[writable]` Contributor's quote token account to debit
+    /// 6. `[]` Quote token mint
+    /// 7. `[]` Token program
+    /// 8. `[]` System program
+    ///
+    /// Fails with `SaleClosed` once the sale has ended and with `ContributionCapExceeded` when
+    /// the contributor's total would pass the sale's cap.
+    #[account(0, writable, signer, name = "contributor", desc = "Contributor, paying for the contribution on the first payment")]
+    #[account(1, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "token_sale", desc = "Token sale account (PDA)")]
+    #[account(3, writable, name = "contribution", desc = "Sale contribution account (PDA)")]
+    #[account(4, writable, name = "quote_vault", desc = "Sale quote vault (PDA)")]
+    #[account(5, writable, name = "source", desc = "Contributor's quote token account to debit")]
+    #[account(6, name = "quote_mint", desc = "Quote token mint")]
+    #[account(7, name = "token_program", desc = "Token program")]
+    #[account(8, name = "system_program", desc = "System program")]
+    ContributeToSale {
+        /// Quote tokens to contribute
+        amount: u64,
+    },
+
+    /// Settle an ended token sale; callable by anyone
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` Market account
+    /// 1. `[writable]` Token sale account (PDA)
+    /// 2. `[writable]` Sale base vault (PDA)
+    /// 3. `[writable]` Sale quote vault (PDA)
+    /// 4. `[writable]` Market authority's base token account
+    /// 5. `[writable]` Market authority's quote token account
+    /// 6. `[]` Base token mint
+    /// 7. `[]` Quote token mint
+    /// 8. `[]` Token program
+    ///
+    /// Fails with `SaleNotEnded` while contributions are accepted. The clearing price is the
+    /// total contributed over the tokens for sale, rounded down to the tick size. When it
+    /// reaches the sale's minimum price the contributions go to the authority and the market
+    /// opens with the clearing price as its last price; otherwise the tokens for sale go back to
+    /// the authority and the market stays paused.
+    #[account(0, writable, name = "market", desc = "Market account")]
+    #[account(1, writable, name = "token_sale", desc = "Token sale account (PDA)")]
+    #[account(2, writable, name = "base_vault", desc = "Sale base vault (PDA)")]
+    #[account(3, writable, name = "quote_vault", desc = "Sale quote vault (PDA)")]
+    #[account(4, writable, name = "authority_base_account", desc = "Market authority's base token account")]
+    #[account(5, writable, name = "authority_quote_account", desc = "Market authority's quote token account")]
+    #[account(6, name = "base_mint", desc = "Base token mint")]
+    #[account(7, name = "quote_mint", desc = "Quote token mint")]
+    #[account(8, name = "token_program", desc = "Token program")]
+    FinalizeSale,
+
+    /// Claim a contribution to a finalized token sale and close it
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Contributor, receiving the contribution's rent
+    /// 1. `[]` Market account
+    /// 2. `[]` Token sale account (PDA)
+    /// 3. `[writable]` Sale contribution account (PDA)
+    /// 4. `[writable]` Sale base vault (PDA)
+    /// 5. `[writable]` Sale quote vault (PDA)
+    /// 6. `[writable]` Contributor's token account to credit: base tokens when the sale
+    ///    succeeded, quote tokens when it failed
+    /// 7. `[]` Base token mint
+    /// 8. `[]` Quote token mint
+    /// 9. `[]` Token program
+    ///
+    /// A successful sale pays the contributor its share of the tokens for sale in proportion to
+    /// its contribution, rounded down; a failed one refunds the contribution. Fails with
+    /// `SaleClosed` before the sale is finalized.
+    #[account(0, writable, signer, name = "contributor", desc = "Contributor, receiving the contribution's rent")]
+    #[account(1, name = "market", desc = "Market account")]
+    #[account(2, name = "token_sale", desc = "Token sale account (PDA)")]
+    #[account(3, writable, name = "contribution", desc = "Sale contribution account (PDA)")]
+    #[account(4, writable, name = "base_vault", desc = "Sale base vault (PDA)")]
+    #[account(5, writable, name = "quote_vault", desc = "Sale quote vault (PDA)")]
+    #[account(6, writable, name = "destination", desc = "Contributor's token account to credit: base tokens when the sale succeeded, quote tokens when it failed")]
+    #[account(7, name = "base_mint", desc = "Base token mint")]
+    #[account(8, name = "quote_mint", desc = "Quote token mint")]
+    #[account(9, name = "token_program", desc = "Token program")]
+    ClaimSale,
+
+    /// Create the stake pool of the governance token
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Config admin, paying for the pool accounts
+    /// 1. `[]` Program config account (PDA)
+    /// 2. `[writable]` Stake pool account (PDA)
+    /// 3. `[writable]` Stake vault (PDA, uninitialized)
+    /// 4. `[writable]` Stake reward vault (PDA, uninitialized)
+    /// 5. `[]` Governance token mint
+    /// 6. `[]` Reward token mint
+    /// 7. `[]` Token program of the governance mint
+    /// 8. `[]` Token program of the reward mint
+    /// 9. `[]` System program
+    ///
+    /// The first epoch starts now. Used fee tiers, those with a discount, must ask for growing
+    /// stakes and grant growing discounts.
+    #[account(0, writable, signer, name = "admin", desc = "Config admin, paying for the pool accounts")]
+    #[account(1, name = "config", desc = "Program config account (PDA)")]
+    #[account(2, writable, name = "stake_pool", desc = "Stake pool account (PDA)")]
+    #[account(3, writable, name = "stake_vault", desc = "Stake vault (PDA, uninitialized)")]
+    #[account(4, writable, name = "reward_vault", desc = "Stake reward vault (PDA, uninitialized)")]
+    #[account(5, name = "governance_mint", desc = "Governance token mint")]
+    #[account(6, name = "reward_mint", desc = "Reward token mint")]
+    #[account(7, name = "governance_token_program", desc = "Token program of the governance mint")]
+    #[account(8, name = "reward_token_program", desc = "Token program of the reward mint")]
+    #[account(9, name = "system_program", desc = "System program")]
+    InitializeStakePool {
+        /// Seconds between reward distributions
+        epoch_seconds: u64,
+        /// Part of each fee sweep paid to stakers, in basis points
+        fee_share_bps: u16,
+        /// Taker fee discounts by stake
+        fee_tiers: [FeeTier; 4],
+    },
+
+    /// Stake governance tokens
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Staker, paying for the stake account on the first stake
+    /// 1. `[writable]` Stake pool account (PDA)
+    /// 2. `[writable]` Stake account (PDA)
+    /// 3. `[writable]` Stake vault (PDA)
+    /// 4. `[writable]` Staker's governance token account to debit
+    /// 5. `[]` Governance token mint
+    /// 6. `[]` Token program
+    /// 7. `[]` System program
+    ///
+    /// The whole stake is locked until the current epoch is distributed, and earns from the
+    /// next distribution.
+    #[account(0, writable, signer, name = "owner", desc = "Staker, paying for the stake account on the first stake")]
+    #[account(1, writable, name = "stake_pool", desc = "Stake pool account (PDA)")]
+    #[account(2, writable, name = "stake_account", desc = "Stake account (PDA)")]
+    #[account(3, writable, name = "stake_vault", desc = "Stake vault (PDA)")]
+    #[account(4, writable, name = "source", desc = "Staker's governance token account to debit")]
+    #[account(5, name = "governance_mint", desc = "Governance token mint")]
+    #[account(6, name = "token_program", desc = "Token program")]
+    #[account(7, name = "system_program", desc = "System program")]
+    Stake {
+        /// Governance tokens to stake
+        amount: u64,
+    },
+
+    /// Withdraw staked governance tokens
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Staker
+    /// 1. `[writable]` Stake pool account (PDA)
+    /// 2. `[writable]` Stake account (PDA)
+    /// 3. `[writable]` Stake vault (PDA)
+    /// 4. `[writable]` Governance token account to credit
+    /// 5. `[]` Governance token mint
+    /// 6. `[]` Token program
+    ///
+    /// Fails with `StakeLocked` until the epoch of the last stake has been distributed. The
+    /// rewards earned so far stay claimable.
+    #[account(0, signer, name = "owner", desc = "Staker")]
+    #[account(1, writable, name = "stake_pool", desc = "Stake pool account (PDA)")]
+    #[account(2, writable, name = "stake_account", desc = "Stake account (PDA)")]
+    #[account(3, writable, name = "stake_vault", desc = "Stake vault (PDA)")]
+    #[account(4, writable, name = "destination", desc = "Governance token account to credit")]
+    #[account(5, name = "governance_mint", desc = "Governance token mint")]
+    #[account(6, name = "token_program", desc = "Token program")]
+    Unstake {
+        /// Governance tokens to withdraw
+        amount: u64,
+    },
+
+    /// Distribute the rewards that arrived during the epoch and start the next; callable by
+    /// anyone
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]` Stake pool account (PDA)
+    /// 1. `[]` Stake reward vault (PDA)
+    ///
+    /// Fails with `EpochNotEnded` before the epoch has run its length. The rewards are shared
+    /// by the tokens staked now, in proportion; while nothing is staked they wait for the next
+    /// epoch.
+    #[account(0, writable, name = "stake_pool", desc = "Stake pool account (PDA)")]
+    #[account(1, name = "reward_vault", desc = "Stake reward vault (PDA)")]
+    DistributeStakingRewards,
+
+    /// Claim a staker's distributed rewards
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Staker
+    /// 1. `[writable]` Stake pool account (PDA)
+    /// 2. `[writable]` Stake account (PDA)
+    /// 3. `[writable]` Stake reward vault (PDA)
+    /// 4. `[writable]` Reward token account to credit
+    /// 5. `[]` Reward token mint
+    /// 6. `[]` Token program
+    #[account(0, signer, name = "owner", desc = "Staker")]
+    #[account(1, writable, name = "stake_pool", desc = "Stake pool account (PDA)")]
+    #[account(2, writable, name = "stake_account", desc = "Stake account (PDA)")]
+    #[account(3, writable, name = "reward_vault", desc = "Stake reward vault (PDA)")]
+    #[account(4, writable, name = "destination", desc = "Reward token account to credit")]
+    #[account(5, name = "reward_mint", desc = "Reward token mint")]
+    #[account(6, name = "token_program", desc = "Token program")]
+    ClaimStakingRewards,
+
+    /// Set the loyalty points emission schedule of a market
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Market authority account, paying for the schedule the first time
+    /// 1. `[writable]` Market account
+    /// 2. `[writable]` Loyalty schedule account (PDA)
+    /// 3. `[]` System program
+    ///
+    /// The first schedule sets `MARKET_FLAG_LOYALTY_POINTS` on the market; from then on every
+    /// SettleFunds on the market must pass the schedule and the loyalty points accounts of both
+    /// order owners. A new schedule replaces the phases and keeps the points accrued.
+    #[account(0, writable, signer, name = "authority", desc = "Market authority account, paying for the schedule the first time")]
+    #[account(1, writable, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "loyalty_schedule", desc = "Loyalty schedule account (PDA)")]
+    #[account(3, name = "system_program", desc = "System program")]
+    SetLoyaltySchedule {
+        /// Number of phases used, from the first
+        num_phases: u8,
+        /// Emission phases, in order of their start
+        phases: [EmissionPhase; 4],
+    },
+
+    /// Open a trader's loyalty points account on a market
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Trader, paying for the account
+    /// 1. `[]` Market account
+    /// 2. `[writable]` Loyalty points account (PDA)
+    /// 3. `[]` System program
+    ///
+    /// Fills only accrue points to traders who have opened their account.
+    #[account(0, writable, signer, name = "owner", desc = "Trader, paying for the account")]
+    #[account(1, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "loyalty_points", desc = "Loyalty points account (PDA)")]
+    #[account(3, name = "system_program", desc = "System program")]
+    OpenLoyaltyPoints,
+
+    /// Set the shares of a market's swept fees paid to the protocol treasury and the market creator
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Program config admin account
+    /// 1. `[]` Program config account (PDA)
+    /// 2. `[writable]` Market account
+    /// 3. `[]` Treasury quote token account, owned by the config's treasury
+    /// 4. `[]` Creator quote token account, owned by the market authority
+    ///
+    /// SweepFees pays the shares, in basis points of the fees left after the stakers' share, to
+    /// the two token accounts and the rest to its destination. Front-end referrers are paid
+    /// their `referral_share_bps` at settlement instead.
+    #[account(0, signer, name = "admin", desc = "Program config admin account")]
+    #[account(1, name = "program_config", desc = "Program config account (PDA)")]
+    #[account(2, writable, name = "market", desc = "Market account")]
+    #[account(3, name = "treasury_token_account", desc = "Treasury quote token account, owned by the config's treasury")]
+    #[account(4, name = "creator_token_account", desc = "Creator quote token account, owned by the market authority")]
+    SetFeeSplit {
+        /// Treasury share in basis points
+        treasury_share_bps: u16,
+        /// Creator share in basis points
+        creator_share_bps: u16,
+    },
+
+    /// Scale the taker fee of a market's book fills by the realized volatility of its fill prices
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` Market authority account
+    /// 1. `[writable]` Market account
+    ///
+    /// Every settled fill weighs its return from the previous fill price into the market's
+    /// realized variance, averaged over about `volatility_window` fills. The taker fee then
+    /// rises linearly from `min_fee_bps` in a calm market to the market's `taker_fee_bps`,
+    /// which orders lock, once the realized volatility per fill reaches `volatility_bps`. A
+    /// window of zero turns dynamic fees off; turning them on starts the estimate from zero.
+    #[account(0, signer, name = "market_authority", desc = "Market authority account")]
+    #[account(1, writable, name = "market", desc = "Market account")]
+    SetDynamicFees {
+        /// Taker fee of a calm market in basis points, at most the market's taker fee
+        min_fee_bps: u16,
+        /// Realized volatility per fill in basis points at which the full taker fee applies
+        volatility_bps: u16,
+        /// Number of fills the volatility estimate averages over, zero to turn dynamic fees off
+        volatility_window: u16,
+    },
+
+    /// Place an order on a market's perp market for the owner's perp position
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Order owner, paying for the order account
+    /// 1. `[writable]` Market account
+    /// 2. `[]` Perp market account (PDA)
+    /// 3. `[]` Owner's perp position (PDA)
+    /// 4. `[signer, writable]` Perp order account (uninitialized)
+    /// 5. `[]` System program
+    /// 6. `[]` Market oracle
+    ///
+    /// The order locks no tokens and takes an order ID of the market. It follows the market's
+    /// tick size and minimum order size and must be priced within its oracle band; margin is
+    /// checked when the order fills. A reduce-only order only fills as far as it shrinks the
+    /// position.
+    #[account(0, writable, signer, name = "owner", desc = "Order owner, paying for the order account")]
+    #[account(1, writable, name = "market", desc = "Market account")]
+    #[account(2, name = "perp_market", desc = "Perp market account (PDA)")]
+    #[account(3, name = "position", desc = "Owner's perp position (PDA)")]
+    #[account(4, writable, signer, name = "order", desc = "Perp order account (uninitialized)")]
+    #[account(5, name = "system_program", desc = "System program")]
+    #[account(6, name = "oracle", desc = "Market oracle")]
+    PlacePerpOrder {
+        /// Is this a buy order
+        is_buy: bool,
+        /// Limit price in quote lots per base lot
+        limit_price: u64,
+        /// Quantity in base lots
+        quantity: u64,
+        /// Whether fills of the order may only shrink the position
+        reduce_only: bool,
+    },
+
+    /// Cancel a perp order, returning its rent to the owner
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Order owner account
+    /// 1. `[]` Market account
+    /// 2. `[writable]` Perp order account
+    #[account(0, writable, signer, name = "owner", desc = "Order owner account")]
+    #[account(1, name = "market", desc = "Market account")]
+    #[account(2, writable, name = "order", desc = "Perp order account")]
+    CancelPerpOrder,
+
+    /// Publish a market's best bid and offer and TWAP to other chains through Wormhole
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Payer of the Wormhole fee and the message rent
+    /// 1. `[]` Market account
+    /// 2. `[]` TWAP oracle (PDA), read when the market has one
+    /// 3. `[writable]` Wormhole bridge config
+    /// 4. `[writable]` Price message account (PDA)
+    /// 5. `[]` Market emitter (PDA)
+    /// 6. `[writable]` Emitter sequence account
+    /// 7. `[writable]` Wormhole fee collector
+    /// 8. `[]` Clock sysvar
+    /// 9. `[]` Rent sysvar
+    /// 10. `[]` System program
+    /// 11. `[]` Wormhole core bridge program
+    ///
+    /// Anyone may crank the instruction. It posts the market's prices as a Wormhole message
+    /// from the market's emitter PDA, reusing the market's message account, so guardians sign
+    /// a new message of the emitter's next sequence each time. Posting needs the program built
+    /// with the `wormhole` feature; otherwise the instruction fails.
+    #[account(0, writable, signer, name = "payer", desc = "Payer of the Wormhole fee and the message rent")]
+    #[account(1, name = "market", desc = "Market account")]
+    #[account(2, name = "twap_oracle", desc = "TWAP oracle (PDA), read when the market has one")]
+    #[account(3, writable, name = "bridge_config", desc = "Wormhole bridge config")]
+    #[account(4, writable, name = "price_message", desc = "Price message account (PDA)")]
+    #[account(5, name = "emitter", desc = "Market emitter (PDA)")]
+    #[account(6, writable, name = "sequence", desc = "Emitter sequence account")]
+    #[account(7, writable, name = "fee_collector", desc = "Wormhole fee collector")]
+    #[account(8, name = "clock", desc = "Clock sysvar")]
+    #[account(9, name = "rent", desc = "Rent sysvar")]
+    #[account(10, name = "system_program", desc = "System program")]
+    #[account(11, name = "core_bridge_program", desc = "Wormhole core bridge program")]
+    PublishPrices {
+        /// Nonce of the message, chosen by the cranker
+        nonce: u32,
+        /// Whether guardians wait for finality rather than confirmation before signing
+        finalized: bool,
+    },
+}
+
+/// Self-trade behavior enum
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub enum SelfTradeBehavior {
+    /// Decrement take (maker gets priority)
+    DecrementTake,
+    /// Cancel provide (taker gets priority)
+    CancelProvide,
+    /// Abort transaction
+    AbortTransaction,
+}
+
+/// Resting order a route swap or batch auction may fill, with the accounts of its owner
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RouteOrder {
+    /// Order account
+    pub order: Pubkey,
+    /// Order owner, paid the rent of a filled order
+    pub owner: Pubkey,
+    /// Owner's base token account
+    pub owner_base_account: Pubkey,
+    /// Owner's quote token account
+    pub owner_quote_account: Pubkey,
+}
+
+// Implementation of DexInstruction
+impl DexInstruction {
+    /// Create an initialize market instruction
+    pub fn initialize_market(
+        program_id: &Pubkey,
+        market_authority: &Pubkey,
+        market_account: &Pubkey,
+        base_mint: &Pubkey,
+        quote_mint: &Pubkey,
+        min_base_order_size: u64,
+        tick_size: u64,
+        taker_fee_bps: u16,
+        maker_fee_bps: i16,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::InitializeMarket {
+            min_base_order_size,
+            tick_size,
+            taker_fee_bps,
+            maker_fee_bps,
+            trustless: false,
+            base_lot_size: 1,
+            quote_lot_size: 1,
+        }
+        .try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new(*market_authority, true),
+            AccountMeta::new(*market_account, false),
+            AccountMeta::new_readonly(*base_mint, false),
+            AccountMeta::new_readonly(*quote_mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(ProgramConfig::find_address(program_id).0, false),
+            AccountMeta::new(MarketRegistry::find_address(program_id).0, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create an initialize market instruction that also pays the market creation fee
+    pub fn initialize_market_with_fee(
+        program_id: &Pubkey,
+        market_authority: &Pubkey,
+        market_account: &Pubkey,
+        base_mint: &Pubkey,
+        quote_mint: &Pubkey,
+        min_base_order_size: u64,
+        tick_size: u64,
+        taker_fee_bps: u16,
+        maker_fee_bps: i16,
+        treasury: &Pubkey,
+        authority_quote_account: &Pubkey,
+        treasury_quote_account: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        let mut instruction = Self::initialize_market(
+            program_id,
+            market_authority,
+            market_account,
+            base_mint,
+            quote_mint,
+            min_base_order_size,
+            tick_size,
+            taker_fee_bps,
+            maker_fee_bps,
+        )?;
+
+        // Append the fee accounts
+        instruction.accounts.extend([
+            AccountMeta::new(*treasury, false),
+            AccountMeta::new(*authority_quote_account, false),
+            AccountMeta::new(*treasury_quote_account, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ]);
+
+        Ok(instruction)
+    }
+
+    /// Create an initialize market instruction for the market PDA of a mint pair and seed
+    pub fn initialize_market_with_seed(
+        program_id: &Pubkey,
+        market_authority: &Pubkey,
+        base_mint: &Pubkey,
+        quote_mint: &Pubkey,
+        seed: u64,
+        min_base_order_size: u64,
+        tick_size: u64,
+        taker_fee_bps: u16,
+        maker_fee_bps: i16,
+    ) -> Result<Instruction, ProgramError> {
+        let market_account = Market::find_address(program_id, base_mint, quote_mint, seed).0;
+        let mut instruction = Self::initialize_market(
+            program_id,
+            market_authority,
+            &market_account,
+            base_mint,
+            quote_mint,
+            min_base_order_size,
+            tick_size,
+            taker_fee_bps,
+            maker_fee_bps,
+        )?;
+        instruction.data = DexInstruction::InitializeMarketWithSeed {
+            seed,
+            min_base_order_size,
+            tick_size,
+            taker_fee_bps,
+            maker_fee_bps,
+            trustless: false,
+            base_lot_size: 1,
+            quote_lot_size: 1,
+        }
+        .try_to_vec()?;
+
+        Ok(instruction)
+    }
+
+    /// Create an initialize market instruction for a market PDA that also pays the market creation fee
+    pub fn initialize_market_with_seed_and_fee(
+        program_id: &Pubkey,
+        market_authority: &Pubkey,
+        base_mint: &Pubkey,
+        quote_mint: &Pubkey,
+        seed: u64,
+        min_base_order_size: u64,
+        tick_size: u64,
+        taker_fee_bps: u16,
+        maker_fee_bps: i16,
+        treasury: &Pubkey,
+        authority_quote_account: &Pubkey,
+        treasury_quote_account: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        let mut instruction = Self::initialize_market_with_seed(
+            program_id,
+            market_authority,
+            base_mint,
+            quote_mint,
+            seed,
+            min_base_order_size,
+            tick_size,
+            taker_fee_bps,
+            maker_fee_bps,
+        )?;
+
+        // Append the fee accounts
+        instruction.accounts.extend([
+            AccountMeta::new(*treasury, false),
+            AccountMeta::new(*authority_quote_account, false),
+            AccountMeta::new(*treasury_quote_account, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ]);
+
+        Ok(instruction)
+    }
+
+    /// Flag the market of an initialize market instruction as trustless
+    ///
+    /// A trustless market only accepts base and quote mints without a freeze authority.
+    pub fn trustless(mut instruction: Instruction) -> Result<Instruction, ProgramError> {
+        let mut data = DexInstruction::try_from_slice(&instruction.data)
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
+        match &mut data {
+            DexInstruction::InitializeMarket { trustless, .. }
+            | DexInstruction::InitializeMarketWithSeed { trustless, .. } => *trustless = true,
+            _ => return Err(ProgramError::InvalidInstructionData),
+        }
+        instruction.data = data.try_to_vec()?;
+
+        Ok(instruction)
+    }
+
+    /// Set the lot sizes of the market of an initialize market instruction
+    ///
+    /// The builders create markets with lot sizes of 1, where order quantities and prices are
+    /// plain token amounts.
+    pub fn with_lot_sizes(
+        mut instruction: Instruction,
+        base_lot_size: u64,
+        quote_lot_size: u64,
+    ) -> Result<Instruction, ProgramError> {
+        let mut data = DexInstruction::try_from_slice(&instruction.data)
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
+        match &mut data {
+            DexInstruction::InitializeMarket {
+                base_lot_size: base,
+                quote_lot_size: quote,
+                ..
+            }
+            | DexInstruction::InitializeMarketWithSeed {
+                base_lot_size: base,
+                quote_lot_size: quote,
+                ..
+            } => {
+                *base = base_lot_size;
+                *quote = quote_lot_size;
+            }
+            _ => return Err(ProgramError::InvalidInstructionData),
+        }
+        instruction.data = data.try_to_vec()?;
+
+        Ok(instruction)
+    }
+
+    /// Create a place limit order instruction
+    pub fn place_limit_order(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        market: &Pubkey,
+        order_account: &Pubkey,
+        owner_token_account: &Pubkey,
+        token_program: &Pubkey,
+        is_buy: bool,
+        limit_price: u64,
+        quantity: u64,
+        self_trade_behavior: SelfTradeBehavior,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::PlaceLimitOrder {
+            is_buy,
+            limit_price,
+            quantity,
+            self_trade_behavior,
+            memo: String::new(),
+        }
+        .try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new(*owner, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new(*order_account, false),
+            AccountMeta::new(*owner_token_account, false),
+            AccountMeta::new(Market::find_escrow_vault_address(program_id, market, is_buy).0, false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(instructions::id(), false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a place limit order instruction routed through a referrer
+    pub fn place_limit_order_with_referrer(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        market: &Pubkey,
+        order_account: &Pubkey,
+        owner_token_account: &Pubkey,
+        token_program: &Pubkey,
+        is_buy: bool,
+        limit_price: u64,
+        quantity: u64,
+        self_trade_behavior: SelfTradeBehavior,
+        referrer_token_account: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        let mut instruction = Self::place_limit_order(
+            program_id,
+            owner,
+            market,
+            order_account,
+            owner_token_account,
+            token_program,
+            is_buy,
+            limit_price,
+            quantity,
+            self_trade_behavior,
+        )?;
+        instruction
+            .accounts
+            .push(AccountMeta::new_readonly(*referrer_token_account, false));
+        Ok(instruction)
+    }
+
+    /// Create a place limit order instruction for a permissioned market
+    pub fn place_limit_order_whitelisted(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        market: &Pubkey,
+        order_account: &Pubkey,
+        owner_token_account: &Pubkey,
+        token_program: &Pubkey,
+        is_buy: bool,
+        limit_price: u64,
+        quantity: u64,
+        self_trade_behavior: SelfTradeBehavior,
+        referrer_token_account: Option<&Pubkey>,
+    ) -> Result<Instruction, ProgramError> {
+        let mut instruction = Self::place_limit_order_with_referrer(
+            program_id,
+            owner,
+            market,
+            order_account,
+            owner_token_account,
+            token_program,
+            is_buy,
+            limit_price,
+            quantity,
+            self_trade_behavior,
+            referrer_token_account.unwrap_or(&system_program::id()),
+        )?;
+        instruction.accounts.push(AccountMeta::new_readonly(
+            WhitelistEntry::find_address(program_id, market, owner).0,
+            false,
+        ));
+        Ok(instruction)
+    }
+
+    /// Create a place iceberg order instruction
+    pub fn place_iceberg_order(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        market: &Pubkey,
+        order_account: &Pubkey,
+        owner_token_account: &Pubkey,
+        token_program: &Pubkey,
+        is_buy: bool,
+        limit_price: u64,
+        quantity: u64,
+        display_quantity: u64,
+        self_trade_behavior: SelfTradeBehavior,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::PlaceIcebergOrder {
+            is_buy,
+            limit_price,
+            quantity,
+            display_quantity,
+            self_trade_behavior,
+            memo: String::new(),
+        }
+        .try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new(*owner, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new(*order_account, false),
+            AccountMeta::new(*owner_token_account, false),
+            AccountMeta::new(Market::find_escrow_vault_address(program_id, market, is_buy).0, false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(instructions::id(), false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a place iceberg order instruction for a permissioned market
+    pub fn place_iceberg_order_whitelisted(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        market: &Pubkey,
+        order_account: &Pubkey,
+        owner_token_account: &Pubkey,
+        token_program: &Pubkey,
+        is_buy: bool,
+        limit_price: u64,
+        quantity: u64,
+        display_quantity: u64,
+        self_trade_behavior: SelfTradeBehavior,
+    ) -> Result<Instruction, ProgramError> {
+        let mut instruction = Self::place_iceberg_order(
+            program_id,
+            owner,
+            market,
+            order_account,
+            owner_token_account,
+            token_program,
+            is_buy,
+            limit_price,
+            quantity,
+            display_quantity,
+            self_trade_behavior,
+        )?;
+        instruction
+            .accounts
+            .push(AccountMeta::new_readonly(system_program::id(), false));
+        instruction.accounts.push(AccountMeta::new_readonly(
+            WhitelistEntry::find_address(program_id, market, owner).0,
+            false,
+        ));
+        Ok(instruction)
+    }
+
+    /// Create a cancel order instruction
+    pub fn cancel_order(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        market: &Pubkey,
+        order_account: &Pubkey,
+        owner_token_account: &Pubkey,
+        token_program: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::CancelOrder.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new(*owner, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new(*order_account, false),
+            AccountMeta::new(*owner_token_account, false),
+            AccountMeta::new(Market::find_base_vault_address(program_id, market).0, false),
+            AccountMeta::new(Market::find_quote_vault_address(program_id, market).0, false),
+            AccountMeta::new_readonly(Market::find_signer_address(program_id, market).0, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a cancel order instruction for an order linked to another order
+    pub fn cancel_linked_order(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        market: &Pubkey,
+        order_account: &Pubkey,
+        owner_token_account: &Pubkey,
+        token_program: &Pubkey,
+        linked_order_account: &Pubkey,
+        linked_owner_token_account: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        let mut instruction = Self::cancel_order(
+            program_id,
+            owner,
+            market,
+            order_account,
+            owner_token_account,
+            token_program,
+        )?;
+        instruction
+            .accounts
+            .push(AccountMeta::new(*linked_order_account, false));
+        instruction
+            .accounts
+            .push(AccountMeta::new(*linked_owner_token_account, false));
+        Ok(instruction)
+    }
+
+    /// Create a link orders instruction
+    pub fn link_orders(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        market: &Pubkey,
+        first_order_account: &Pubkey,
+        second_order_account: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::LinkOrders.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new(*first_order_account, false),
+            AccountMeta::new(*second_order_account, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a commit open orders root instruction
+    ///
+    /// The order accounts are sorted by address before being attached.
+    pub fn commit_open_orders_root(
+        program_id: &Pubkey,
+        market: &Pubkey,
+        order_accounts: &[Pubkey],
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::CommitOpenOrdersRoot.try_to_vec()?;
+
+        // Create account metas
+        let mut sorted_order_accounts = order_accounts.to_vec();
+        sorted_order_accounts.sort();
+        let mut accounts = vec![AccountMeta::new(*market, false)];
+        accounts.extend(
+            sorted_order_accounts
+                .iter()
+                .map(|order_account| AccountMeta::new_readonly(*order_account, false)),
+        );
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a reduce order instruction
+    pub fn reduce_order(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        market: &Pubkey,
+        order_account: &Pubkey,
+        owner_token_account: &Pubkey,
+        token_program: &Pubkey,
+        new_remaining_quantity: u64,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::ReduceOrder {
+            new_remaining_quantity,
+        }
+        .try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new(*order_account, false),
+            AccountMeta::new(*owner_token_account, false),
+            AccountMeta::new(Market::find_base_vault_address(program_id, market).0, false),
+            AccountMeta::new(Market::find_quote_vault_address(program_id, market).0, false),
+            AccountMeta::new_readonly(Market::find_signer_address(program_id, market).0, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a cancel orders by ids instruction
+    pub fn cancel_orders_by_ids(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        market: &Pubkey,
+        owner_base_account: &Pubkey,
+        owner_quote_account: &Pubkey,
+        token_program: &Pubkey,
+        order_ids: &[u64],
+        order_accounts: &[Pubkey],
+    ) -> Result<Instruction, ProgramError> {
+        // Each id needs its matching order account
+        if order_ids.len() != order_accounts.len() {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        // Create instruction data
+        let data = DexInstruction::CancelOrdersByIds {
+            order_ids: order_ids.to_vec(),
+        }
+        .try_to_vec()?;
+
+        // Create account metas
+        let mut accounts = vec![
+            AccountMeta::new(*owner, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new(*owner_base_account, false),
+            AccountMeta::new(*owner_quote_account, false),
+            AccountMeta::new(Market::find_base_vault_address(program_id, market).0, false),
+            AccountMeta::new(Market::find_quote_vault_address(program_id, market).0, false),
+            AccountMeta::new_readonly(Market::find_signer_address(program_id, market).0, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ];
+        accounts.extend(
+            order_accounts
+                .iter()
+                .map(|order_account| AccountMeta::new(*order_account, false)),
+        );
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a settle funds instruction
+    pub fn settle_funds(
+        program_id: &Pubkey,
+        authority: &Pubkey,
+        market: &Pubkey,
+        taker: &Pubkey,
+        maker: &Pubkey,
+        taker_base_account: &Pubkey,
+        taker_quote_account: &Pubkey,
+        maker_base_account: &Pubkey,
+        maker_quote_account: &Pubkey,
+        taker_owner: &Pubkey,
+        maker_owner: &Pubkey,
+        token_program: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::SettleFunds.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new(*taker, false),
+            AccountMeta::new(*maker, false),
+            AccountMeta::new(*taker_base_account, false),
+            AccountMeta::new(*taker_quote_account, false),
+            AccountMeta::new(*maker_base_account, false),
+            AccountMeta::new(*maker_quote_account, false),
+            AccountMeta::new(Market::find_fee_vault_address(program_id, market).0, false),
+            AccountMeta::new(Market::find_base_vault_address(program_id, market).0, false),
+            AccountMeta::new(Market::find_quote_vault_address(program_id, market).0, false),
+            AccountMeta::new_readonly(Market::find_signer_address(program_id, market).0, false),
+            AccountMeta::new(*taker_owner, false),
+            AccountMeta::new(*maker_owner, false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(instructions::id(), false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a settle funds instruction paying the referral share to the taker order's referrer
+    pub fn settle_funds_with_referrer(
+        program_id: &Pubkey,
+        authority: &Pubkey,
+        market: &Pubkey,
+        taker: &Pubkey,
+        maker: &Pubkey,
+        taker_base_account: &Pubkey,
+        taker_quote_account: &Pubkey,
+        maker_base_account: &Pubkey,
+        maker_quote_account: &Pubkey,
+        taker_owner: &Pubkey,
+        maker_owner: &Pubkey,
+        token_program: &Pubkey,
+        referrer_token_account: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        let mut instruction = Self::settle_funds(
+            program_id,
+            authority,
+            market,
+            taker,
+            maker,
+            taker_base_account,
+            taker_quote_account,
+            maker_base_account,
+            maker_quote_account,
+            taker_owner,
+            maker_owner,
+            token_program,
+        )?;
+        instruction
+            .accounts
+            .push(AccountMeta::new(*referrer_token_account, false));
+        Ok(instruction)
+    }
+
+    /// Create a settle funds instruction that records the settlement so it can be busted
+    ///
+    /// The fee vault fills the referrer slot when the taker order has no referrer.
+    pub fn settle_funds_with_record(
+        program_id: &Pubkey,
+        authority: &Pubkey,
+        market: &Pubkey,
+        taker: &Pubkey,
+        maker: &Pubkey,
+        taker_base_account: &Pubkey,
+        taker_quote_account: &Pubkey,
+        maker_base_account: &Pubkey,
+        maker_quote_account: &Pubkey,
+        taker_owner: &Pubkey,
+        maker_owner: &Pubkey,
+        token_program: &Pubkey,
+        referrer_token_account: Option<&Pubkey>,
+        settlement_record: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        let fee_vault = Market::find_fee_vault_address(program_id, market).0;
+        let mut instruction = Self::settle_funds_with_referrer(
+            program_id,
+            authority,
+            market,
+            taker,
+            maker,
+            taker_base_account,
+            taker_quote_account,
+            maker_base_account,
+            maker_quote_account,
+            taker_owner,
+            maker_owner,
+            token_program,
+            referrer_token_account.unwrap_or(&fee_vault),
+        )?;
+        instruction
+            .accounts
+            .push(AccountMeta::new(*settlement_record, false));
+        Ok(instruction)
+    }
+
+    /// Create a set instruction guard instruction
+    pub fn set_instruction_guard(
+        program_id: &Pubkey,
+        market_authority: &Pubkey,
+        market: &Pubkey,
+        guard: InstructionGuard,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::SetInstructionGuard { guard }.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new_readonly(*market_authority, true),
+            AccountMeta::new(*market, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a set referral share instruction
+    pub fn set_referral_share(
+        program_id: &Pubkey,
+        market_authority: &Pubkey,
+        market: &Pubkey,
+        referral_share_bps: u16,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::SetReferralShare { referral_share_bps }.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new_readonly(*market_authority, true),
+            AccountMeta::new(*market, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create an initialize program config instruction
+    pub fn initialize_program_config(
+        program_id: &Pubkey,
+        admin: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::InitializeProgramConfig.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(ProgramConfig::find_address(program_id).0, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create an update program config instruction
+    pub fn update_program_config(
+        program_id: &Pubkey,
+        admin: &Pubkey,
+        market_creation_mode: MarketCreationMode,
+        allowed_quote_mints: Vec<Pubkey>,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::UpdateProgramConfig {
+            market_creation_mode,
+            allowed_quote_mints,
+        }
+        .try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new(ProgramConfig::find_address(program_id).0, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a set market creation fee instruction
+    pub fn set_market_creation_fee(
+        program_id: &Pubkey,
+        admin: &Pubkey,
+        treasury: &Pubkey,
+        fee_lamports: u64,
+        fee_quote: u64,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::SetMarketCreationFee {
+            treasury: *treasury,
+            fee_lamports,
+            fee_quote,
+        }
+        .try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new(ProgramConfig::find_address(program_id).0, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a set trade busting instruction
+    pub fn set_trade_busting(
+        program_id: &Pubkey,
+        admin: &Pubkey,
+        enabled: bool,
+        window_slots: u64,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::SetTradeBusting {
+            enabled,
+            window_slots,
+        }
+        .try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new(ProgramConfig::find_address(program_id).0, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a bust trade instruction
+    pub fn bust_trade(
+        program_id: &Pubkey,
+        market_authority: &Pubkey,
+        market: &Pubkey,
+        settlement_record: &Pubkey,
+        taker: &Pubkey,
+        maker: &Pubkey,
+        taker_base_account: &Pubkey,
+        taker_quote_account: &Pubkey,
+        maker_base_account: &Pubkey,
+        maker_quote_account: &Pubkey,
+        token_program: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::BustTrade.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new_readonly(*market_authority, true),
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new_readonly(ProgramConfig::find_address(program_id).0, false),
+            AccountMeta::new(*settlement_record, false),
+            AccountMeta::new_readonly(*taker, true),
+            AccountMeta::new_readonly(*maker, true),
+            AccountMeta::new(*taker_base_account, false),
+            AccountMeta::new(*taker_quote_account, false),
+            AccountMeta::new(*maker_base_account, false),
+            AccountMeta::new(*maker_quote_account, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create an initialize fee vault instruction
+    pub fn initialize_fee_vault(
+        program_id: &Pubkey,
+        payer: &Pubkey,
+        market: &Pubkey,
+        quote_mint: &Pubkey,
+        token_program: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::InitializeFeeVault.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new(Market::find_fee_vault_address(program_id, market).0, false),
+            AccountMeta::new_readonly(*quote_mint, false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create an initialize market vaults instruction
+    pub fn initialize_market_vaults(
+        program_id: &Pubkey,
+        payer: &Pubkey,
+        market: &Pubkey,
+        base_mint: &Pubkey,
+        quote_mint: &Pubkey,
+        token_program: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::InitializeMarketVaults.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new(Market::find_base_vault_address(program_id, market).0, false),
+            AccountMeta::new(Market::find_quote_vault_address(program_id, market).0, false),
+            AccountMeta::new_readonly(Market::find_signer_address(program_id, market).0, false),
+            AccountMeta::new_readonly(*base_mint, false),
+            AccountMeta::new_readonly(*quote_mint, false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a close order account instruction
+    pub fn close_order_account(
+        program_id: &Pubkey,
+        order_account: &Pubkey,
+        recipient: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::CloseOrderAccount.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new(*order_account, true),
+            AccountMeta::new(*recipient, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a set minimum quote notional instruction
+    pub fn set_min_quote_notional(
+        program_id: &Pubkey,
+        market_authority: &Pubkey,
+        market: &Pubkey,
+        min_quote_notional: u64,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::SetMinQuoteNotional { min_quote_notional }.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new_readonly(*market_authority, true),
+            AccountMeta::new(*market, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a delegate instruction
+    pub fn delegate(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        market: &Pubkey,
+        delegate: &Pubkey,
+        expiry_slot: u64,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::Delegate {
+            delegate: *delegate,
+            expiry_slot,
+        }
+        .try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new(*owner, true),
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new(Delegation::find_address(program_id, market, owner).0, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a deposit instruction
+    pub fn deposit(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        market: &Pubkey,
+        owner_token_account: &Pubkey,
+        token_program: &Pubkey,
+        is_quote: bool,
+        amount: u64,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::Deposit { is_quote, amount }.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new(*owner, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new(OpenOrders::find_address(program_id, market, owner).0, false),
+            AccountMeta::new(*owner_token_account, false),
+            AccountMeta::new(Market::find_escrow_vault_address(program_id, market, is_quote).0, false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a withdraw instruction
+    pub fn withdraw(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        market: &Pubkey,
+        destination: &Pubkey,
+        token_program: &Pubkey,
+        is_quote: bool,
+        amount: u64,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::Withdraw { is_quote, amount }.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new(OpenOrders::find_address(program_id, market, owner).0, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new(Market::find_escrow_vault_address(program_id, market, is_quote).0, false),
+            AccountMeta::new_readonly(Market::find_signer_address(program_id, market).0, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create an initialize TWAP oracle instruction
+    pub fn initialize_twap_oracle(
+        program_id: &Pubkey,
+        market_authority: &Pubkey,
+        market: &Pubkey,
+        ema_period_seconds: u64,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::InitializeTwapOracle { ema_period_seconds }.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new(*market_authority, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new(TwapOracle::find_address(program_id, market).0, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create an initialize event queue instruction
+    pub fn initialize_event_queue(
+        program_id: &Pubkey,
+        market_authority: &Pubkey,
+        market: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::InitializeEventQueue.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new(*market_authority, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new(EventQueue::find_address(program_id, market).0, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a consume events instruction
+    pub fn consume_events(
+        program_id: &Pubkey,
+        market_authority: &Pubkey,
+        market: &Pubkey,
+        seq_num: u64,
+        limit: u16,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::ConsumeEvents {
+            seq_num,
+            limit,
+            observed_slot: 0,
+            max_staleness_slots: 0,
+        }
+        .try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new_readonly(*market_authority, true),
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new(EventQueue::find_address(program_id, market).0, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Limit a consume events instruction to land at most `max_staleness_slots` slots after
+    /// `observed_slot`, the slot its crank read the event queue at
+    pub fn with_staleness_guard(
+        mut instruction: Instruction,
+        observed_slot: u64,
+        max_staleness_slots: u64,
+    ) -> Result<Instruction, ProgramError> {
+        let mut data = DexInstruction::try_from_slice(&instruction.data)
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
+        match &mut data {
+            DexInstruction::ConsumeEvents {
+                observed_slot: observed,
+                max_staleness_slots: max_staleness,
+                ..
+            } => {
+                *observed = observed_slot;
+                *max_staleness = max_staleness_slots;
+            }
+            _ => return Err(ProgramError::InvalidInstructionData),
+        }
+        instruction.data = data.try_to_vec()?;
+
+        Ok(instruction)
+    }
+
+    /// Create a rescue tokens instruction
+    pub fn rescue_tokens(
+        program_id: &Pubkey,
+        market_authority: &Pubkey,
+        market: &Pubkey,
+        destination: &Pubkey,
+        token_program: &Pubkey,
+        is_quote: bool,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::RescueTokens { is_quote }.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new_readonly(*market_authority, true),
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new(Market::find_escrow_vault_address(program_id, market, is_quote).0, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(Market::find_signer_address(program_id, market).0, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create an initialize pool instruction
+    pub fn initialize_pool(
+        program_id: &Pubkey,
+        market_authority: &Pubkey,
+        market: &Pubkey,
+        base_mint: &Pubkey,
+        quote_mint: &Pubkey,
+        token_program: &Pubkey,
+        fee_bps: u16,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::InitializePool { fee_bps }.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new(*market_authority, true),
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new(Pool::find_address(program_id, market).0, false),
+            AccountMeta::new(Pool::find_vault_address(program_id, market, false).0, false),
+            AccountMeta::new(Pool::find_vault_address(program_id, market, true).0, false),
+            AccountMeta::new(Pool::find_lp_mint_address(program_id, market).0, false),
+            AccountMeta::new_readonly(*base_mint, false),
+            AccountMeta::new_readonly(*quote_mint, false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create an add liquidity instruction
+    pub fn add_liquidity(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        market: &Pubkey,
+        owner_base_account: &Pubkey,
+        owner_quote_account: &Pubkey,
+        lp_token_account: &Pubkey,
+        token_program: &Pubkey,
+        max_base_amount: u64,
+        max_quote_amount: u64,
+        min_lp_amount: u64,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::AddLiquidity {
+            max_base_amount,
+            max_quote_amount,
+            min_lp_amount,
+        }
+        .try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new(Pool::find_address(program_id, market).0, false),
+            AccountMeta::new(Pool::find_vault_address(program_id, market, false).0, false),
+            AccountMeta::new(Pool::find_vault_address(program_id, market, true).0, false),
+            AccountMeta::new(Pool::find_lp_mint_address(program_id, market).0, false),
+            AccountMeta::new(*owner_base_account, false),
+            AccountMeta::new(*owner_quote_account, false),
+            AccountMeta::new(*lp_token_account, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a remove liquidity instruction
+    pub fn remove_liquidity(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        market: &Pubkey,
+        lp_token_account: &Pubkey,
+        base_destination: &Pubkey,
+        quote_destination: &Pubkey,
+        token_program: &Pubkey,
+        lp_amount: u64,
+        min_base_amount: u64,
+        min_quote_amount: u64,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::RemoveLiquidity {
+            lp_amount,
+            min_base_amount,
+            min_quote_amount,
+        }
+        .try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new(Pool::find_address(program_id, market).0, false),
+            AccountMeta::new(Pool::find_vault_address(program_id, market, false).0, false),
+            AccountMeta::new(Pool::find_vault_address(program_id, market, true).0, false),
+            AccountMeta::new(Pool::find_lp_mint_address(program_id, market).0, false),
+            AccountMeta::new(*lp_token_account, false),
+            AccountMeta::new(*base_destination, false),
+            AccountMeta::new(*quote_destination, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a swap against pool instruction
+    pub fn swap_against_pool(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        market: &Pubkey,
+        owner_base_account: &Pubkey,
+        owner_quote_account: &Pubkey,
+        token_program: &Pubkey,
+        is_buy: bool,
+        amount_in: u64,
+        min_amount_out: u64,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::SwapAgainstPool {
+            is_buy,
+            amount_in,
+            min_amount_out,
+        }
+        .try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new(Pool::find_address(program_id, market).0, false),
+            AccountMeta::new(Pool::find_vault_address(program_id, market, false).0, false),
+            AccountMeta::new(Pool::find_vault_address(program_id, market, true).0, false),
+            AccountMeta::new(*owner_base_account, false),
+            AccountMeta::new(*owner_quote_account, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a route swap instruction
+    ///
+    /// `orders` are the resting orders to route through, best price first, each with its
+    /// owner and the owner's base and quote token accounts.
+    pub fn route_swap(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        market: &Pubkey,
+        owner_base_account: &Pubkey,
+        owner_quote_account: &Pubkey,
+        token_program: &Pubkey,
+        orders: &[RouteOrder],
+        is_buy: bool,
+        amount_in: u64,
+        min_amount_out: u64,
+    ) -> Result<Instruction, ProgramError> {
+        let num_orders = u8::try_from(orders.len()).map_err(|_| ProgramError::InvalidArgument)?;
+
+        // Create instruction data
+        let data = DexInstruction::RouteSwap {
+            is_buy,
+            amount_in,
+            min_amount_out,
+            num_orders,
+        }
+        .try_to_vec()?;
+
+        // Create account metas
+        let mut accounts = vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new(Pool::find_address(program_id, market).0, false),
+            AccountMeta::new(Pool::find_vault_address(program_id, market, false).0, false),
+            AccountMeta::new(Pool::find_vault_address(program_id, market, true).0, false),
+            AccountMeta::new(*owner_base_account, false),
+            AccountMeta::new(*owner_quote_account, false),
+            AccountMeta::new(Market::find_base_vault_address(program_id, market).0, false),
+            AccountMeta::new(Market::find_quote_vault_address(program_id, market).0, false),
+            AccountMeta::new_readonly(Market::find_signer_address(program_id, market).0, false),
+            AccountMeta::new(Market::find_fee_vault_address(program_id, market).0, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ];
+        for order in orders {
+            accounts.push(AccountMeta::new(order.order, false));
+            accounts.push(AccountMeta::new(order.owner, false));
+            accounts.push(AccountMeta::new(order.owner_base_account, false));
+            accounts.push(AccountMeta::new(order.owner_quote_account, false));
+        }
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create an initialize range pool instruction
+    pub fn initialize_range_pool(
+        program_id: &Pubkey,
+        market_authority: &Pubkey,
+        market: &Pubkey,
+        base_mint: &Pubkey,
+        quote_mint: &Pubkey,
+        token_program: &Pubkey,
+        fee_bps: u16,
+        tick_spacing: u16,
+        tick: i32,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::InitializeRangePool {
+            fee_bps,
+            tick_spacing,
+            tick,
+        }
+        .try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new(*market_authority, true),
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new(RangePool::find_address(program_id, market).0, false),
+            AccountMeta::new(RangePool::find_vault_address(program_id, market, false).0, false),
+            AccountMeta::new(RangePool::find_vault_address(program_id, market, true).0, false),
+            AccountMeta::new_readonly(*base_mint, false),
+            AccountMeta::new_readonly(*quote_mint, false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create an add range liquidity instruction
+    pub fn add_range_liquidity(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        market: &Pubkey,
+        owner_base_account: &Pubkey,
+        owner_quote_account: &Pubkey,
+        token_program: &Pubkey,
+        tick_lower: i32,
+        tick_upper: i32,
+        liquidity: u64,
+        max_base_amount: u64,
+        max_quote_amount: u64,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::AddRangeLiquidity {
+            tick_lower,
+            tick_upper,
+            liquidity,
+            max_base_amount,
+            max_quote_amount,
+        }
+        .try_to_vec()?;
+
+        // Create account metas
+        let (position, _) = RangePosition::find_address(program_id, market, owner, tick_lower, tick_upper);
+        let accounts = vec![
+            AccountMeta::new(*owner, true),
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new(RangePool::find_address(program_id, market).0, false),
+            AccountMeta::new(position, false),
+            AccountMeta::new(RangePool::find_vault_address(program_id, market, false).0, false),
+            AccountMeta::new(RangePool::find_vault_address(program_id, market, true).0, false),
+            AccountMeta::new(*owner_base_account, false),
+            AccountMeta::new(*owner_quote_account, false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a remove range liquidity instruction
+    pub fn remove_range_liquidity(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        market: &Pubkey,
+        position: &Pubkey,
+        base_destination: &Pubkey,
+        quote_destination: &Pubkey,
+        token_program: &Pubkey,
+        liquidity: u64,
+        min_base_amount: u64,
+        min_quote_amount: u64,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::RemoveRangeLiquidity {
+            liquidity,
+            min_base_amount,
+            min_quote_amount,
+        }
+        .try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new(*owner, true),
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new(RangePool::find_address(program_id, market).0, false),
+            AccountMeta::new(*position, false),
+            AccountMeta::new(RangePool::find_vault_address(program_id, market, false).0, false),
+            AccountMeta::new(RangePool::find_vault_address(program_id, market, true).0, false),
+            AccountMeta::new(*base_destination, false),
+            AccountMeta::new(*quote_destination, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a swap against range pool instruction
+    pub fn swap_against_range_pool(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        market: &Pubkey,
+        owner_base_account: &Pubkey,
+        owner_quote_account: &Pubkey,
+        token_program: &Pubkey,
+        is_buy: bool,
+        amount_in: u64,
+        min_amount_out: u64,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::SwapAgainstRangePool {
+            is_buy,
+            amount_in,
+            min_amount_out,
+        }
+        .try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new(RangePool::find_address(program_id, market).0, false),
+            AccountMeta::new(RangePool::find_vault_address(program_id, market, false).0, false),
+            AccountMeta::new(RangePool::find_vault_address(program_id, market, true).0, false),
+            AccountMeta::new(*owner_base_account, false),
+            AccountMeta::new(*owner_quote_account, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create an initialize lending pool instruction
+    pub fn initialize_lending_pool(
+        program_id: &Pubkey,
+        admin: &Pubkey,
+        mint: &Pubkey,
+        token_program: &Pubkey,
+        rate_curve: InterestRateCurve,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::InitializeLendingPool { rate_curve }.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new(*admin, true),
+            AccountMeta::new_readonly(ProgramConfig::find_address(program_id).0, false),
+            AccountMeta::new(LendingPool::find_address(program_id, mint).0, false),
+            AccountMeta::new(LendingPool::find_vault_address(program_id, mint).0, false),
+            AccountMeta::new(LendingPool::find_share_mint_address(program_id, mint).0, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a supply instruction
+    pub fn supply(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        mint: &Pubkey,
+        source: &Pubkey,
+        share_token_account: &Pubkey,
+        token_program: &Pubkey,
+        amount: u64,
+        min_shares: u64,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::Supply { amount, min_shares }.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new(LendingPool::find_address(program_id, mint).0, false),
+            AccountMeta::new(LendingPool::find_vault_address(program_id, mint).0, false),
+            AccountMeta::new(LendingPool::find_share_mint_address(program_id, mint).0, false),
+            AccountMeta::new(*source, false),
+            AccountMeta::new(*share_token_account, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a redeem instruction
+    pub fn redeem(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        mint: &Pubkey,
+        share_token_account: &Pubkey,
+        destination: &Pubkey,
+        token_program: &Pubkey,
+        shares: u64,
+        min_amount: u64,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::Redeem { shares, min_amount }.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new(LendingPool::find_address(program_id, mint).0, false),
+            AccountMeta::new(LendingPool::find_vault_address(program_id, mint).0, false),
+            AccountMeta::new(LendingPool::find_share_mint_address(program_id, mint).0, false),
+            AccountMeta::new(*share_token_account, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create an initialize perp market instruction
+    pub fn initialize_perp_market(
+        program_id: &Pubkey,
+        market_authority: &Pubkey,
+        market: &Pubkey,
+        quote_mint: &Pubkey,
+        token_program: &Pubkey,
+        initial_margin_bps: u16,
+        max_funding_rate_bps: u16,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::InitializePerpMarket {
+            initial_margin_bps,
+            max_funding_rate_bps,
+        }
+        .try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new(*market_authority, true),
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new(PerpMarket::find_address(program_id, market).0, false),
+            AccountMeta::new(PerpMarket::find_vault_address(program_id, market).0, false),
+            AccountMeta::new_readonly(*quote_mint, false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a deposit perp collateral instruction
+    pub fn deposit_perp_collateral(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        market: &Pubkey,
+        source: &Pubkey,
+        quote_mint: &Pubkey,
+        token_program: &Pubkey,
+        amount: u64,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::DepositPerpCollateral { amount }.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new(*owner, true),
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new_readonly(PerpMarket::find_address(program_id, market).0, false),
+            AccountMeta::new(PerpPosition::find_address(program_id, market, owner).0, false),
+            AccountMeta::new(PerpMarket::find_vault_address(program_id, market).0, false),
+            AccountMeta::new(*source, false),
+            AccountMeta::new_readonly(*quote_mint, false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a withdraw perp collateral instruction
+    pub fn withdraw_perp_collateral(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        market: &Pubkey,
+        destination: &Pubkey,
+        quote_mint: &Pubkey,
+        token_program: &Pubkey,
+        amount: u64,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::WithdrawPerpCollateral { amount }.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new_readonly(PerpMarket::find_address(program_id, market).0, false),
+            AccountMeta::new(PerpPosition::find_address(program_id, market, owner).0, false),
+            AccountMeta::new(PerpMarket::find_vault_address(program_id, market).0, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*quote_mint, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a settle perp fill instruction
+    pub fn settle_perp_fill(
+        program_id: &Pubkey,
+        market_authority: &Pubkey,
+        market: &Pubkey,
+        taker_order: &Pubkey,
+        taker_owner: &Pubkey,
+        maker_order: &Pubkey,
+        maker_owner: &Pubkey,
+        quote_mint: &Pubkey,
+        token_program: &Pubkey,
+        oracle: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::SettlePerpFill.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new_readonly(*market_authority, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new(PerpMarket::find_address(program_id, market).0, false),
+            AccountMeta::new(*taker_order, false),
+            AccountMeta::new(*maker_order, false),
+            AccountMeta::new(PerpPosition::find_address(program_id, market, taker_owner).0, false),
+            AccountMeta::new(PerpPosition::find_address(program_id, market, maker_owner).0, false),
+            AccountMeta::new(PerpMarket::find_vault_address(program_id, market).0, false),
+            AccountMeta::new(Market::find_fee_vault_address(program_id, market).0, false),
+            AccountMeta::new_readonly(*quote_mint, false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(*oracle, false),
+            AccountMeta::new(*taker_owner, false),
+            AccountMeta::new(*maker_owner, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create an update funding instruction
+    pub fn update_funding(
+        program_id: &Pubkey,
+        market: &Pubkey,
+        oracle: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::UpdateFunding.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new(PerpMarket::find_address(program_id, market).0, false),
+            AccountMeta::new_readonly(*oracle, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a set perp reduce-only instruction
+    pub fn set_perp_reduce_only(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        market: &Pubkey,
+        order: &Pubkey,
+        reduce_only: bool,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::SetPerpReduceOnly { reduce_only }.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new(*order, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create an initialize batch auction instruction
+    pub fn initialize_batch_auction(
+        program_id: &Pubkey,
+        market_authority: &Pubkey,
+        market: &Pubkey,
+        interval_slots: u64,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::InitializeBatchAuction { interval_slots }.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new(*market_authority, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new(BatchAuction::find_address(program_id, market).0, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a run auction instruction
+    pub fn run_auction(
+        program_id: &Pubkey,
+        market: &Pubkey,
+        token_program: &Pubkey,
+        orders: &[RouteOrder],
+    ) -> Result<Instruction, ProgramError> {
+        let num_orders = u8::try_from(orders.len()).map_err(|_| ProgramError::InvalidArgument)?;
+
+        // Create instruction data
+        let data = DexInstruction::RunAuction { num_orders }.try_to_vec()?;
+
+        // Create account metas
+        let mut accounts = vec![
+            AccountMeta::new(*market, false),
+            AccountMeta::new(BatchAuction::find_address(program_id, market).0, false),
+            AccountMeta::new(Market::find_base_vault_address(program_id, market).0, false),
+            AccountMeta::new(Market::find_quote_vault_address(program_id, market).0, false),
+            AccountMeta::new_readonly(Market::find_signer_address(program_id, market).0, false),
+            AccountMeta::new(Market::find_fee_vault_address(program_id, market).0, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ];
+        for order in orders {
+            accounts.push(AccountMeta::new(order.order, false));
+            accounts.push(AccountMeta::new(order.owner, false));
+            accounts.push(AccountMeta::new(order.owner_base_account, false));
+            accounts.push(AccountMeta::new(order.owner_quote_account, false));
+        }
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create an initialize token sale instruction
+    pub fn initialize_token_sale(
+        program_id: &Pubkey,
+        market_authority: &Pubkey,
+        market: &Pubkey,
+        source: &Pubkey,
+        base_mint: &Pubkey,
+        quote_mint: &Pubkey,
+        token_program: &Pubkey,
+        base_amount: u64,
+        min_price: u64,
+        max_contribution: u64,
+        duration_seconds: u64,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::InitializeTokenSale {
+            base_amount,
+            min_price,
+            max_contribution,
+            duration_seconds,
+        }
+        .try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new(*market_authority, true),
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new(TokenSale::find_address(program_id, market).0, false),
+            AccountMeta::new(TokenSale::find_vault_address(program_id, market, false).0, false),
+            AccountMeta::new(TokenSale::find_vault_address(program_id, market, true).0, false),
+            AccountMeta::new(*source, false),
+            AccountMeta::new_readonly(*base_mint, false),
+            AccountMeta::new_readonly(*quote_mint, false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a contribute to sale instruction
+    pub fn contribute_to_sale(
+        program_id: &Pubkey,
+        contributor: &Pubkey,
+        market: &Pubkey,
+        source: &Pubkey,
+        quote_mint: &Pubkey,
+        token_program: &Pubkey,
+        amount: u64,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::ContributeToSale { amount }.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new(*contributor, true),
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new(TokenSale::find_address(program_id, market).0, false),
+            AccountMeta::new(SaleContribution::find_address(program_id, market, contributor).0, false),
+            AccountMeta::new(TokenSale::find_vault_address(program_id, market, true).0, false),
+            AccountMeta::new(*source, false),
+            AccountMeta::new_readonly(*quote_mint, false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a finalize sale instruction
+    pub fn finalize_sale(
+        program_id: &Pubkey,
+        market: &Pubkey,
+        authority_base_account: &Pubkey,
+        authority_quote_account: &Pubkey,
+        base_mint: &Pubkey,
+        quote_mint: &Pubkey,
+        token_program: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::FinalizeSale.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new(*market, false),
+            AccountMeta::new(TokenSale::find_address(program_id, market).0, false),
+            AccountMeta::new(TokenSale::find_vault_address(program_id, market, false).0, false),
+            AccountMeta::new(TokenSale::find_vault_address(program_id, market, true).0, false),
+            AccountMeta::new(*authority_base_account, false),
+            AccountMeta::new(*authority_quote_account, false),
+            AccountMeta::new_readonly(*base_mint, false),
+            AccountMeta::new_readonly(*quote_mint, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a claim sale instruction
+    pub fn claim_sale(
+        program_id: &Pubkey,
+        contributor: &Pubkey,
+        market: &Pubkey,
+        destination: &Pubkey,
+        base_mint: &Pubkey,
+        quote_mint: &Pubkey,
+        token_program: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::ClaimSale.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new(*contributor, true),
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new_readonly(TokenSale::find_address(program_id, market).0, false),
+            AccountMeta::new(SaleContribution::find_address(program_id, market, contributor).0, false),
+            AccountMeta::new(TokenSale::find_vault_address(program_id, market, false).0, false),
+            AccountMeta::new(TokenSale::find_vault_address(program_id, market, true).0, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*base_mint, false),
+            AccountMeta::new_readonly(*quote_mint, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create an initialize stake pool instruction
+    pub fn initialize_stake_pool(
+        program_id: &Pubkey,
+        admin: &Pubkey,
+        governance_mint: &Pubkey,
+        reward_mint: &Pubkey,
+        governance_token_program: &Pubkey,
+        reward_token_program: &Pubkey,
+        epoch_seconds: u64,
+        fee_share_bps: u16,
+        fee_tiers: [FeeTier; STAKE_FEE_TIERS],
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::InitializeStakePool {
+            epoch_seconds,
+            fee_share_bps,
+            fee_tiers,
+        }
+        .try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new(*admin, true),
+            AccountMeta::new_readonly(ProgramConfig::find_address(program_id).0, false),
+            AccountMeta::new(StakePool::find_address(program_id).0, false),
+            AccountMeta::new(StakePool::find_vault_address(program_id, false).0, false),
+            AccountMeta::new(StakePool::find_vault_address(program_id, true).0, false),
+            AccountMeta::new_readonly(*governance_mint, false),
+            AccountMeta::new_readonly(*reward_mint, false),
+            AccountMeta::new_readonly(*governance_token_program, false),
+            AccountMeta::new_readonly(*reward_token_program, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a stake instruction
+    pub fn stake(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        source: &Pubkey,
+        governance_mint: &Pubkey,
+        token_program: &Pubkey,
+        amount: u64,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::Stake { amount }.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new(*owner, true),
+            AccountMeta::new(StakePool::find_address(program_id).0, false),
+            AccountMeta::new(StakeAccount::find_address(program_id, owner).0, false),
+            AccountMeta::new(StakePool::find_vault_address(program_id, false).0, false),
+            AccountMeta::new(*source, false),
+            AccountMeta::new_readonly(*governance_mint, false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create an unstake instruction
+    pub fn unstake(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        destination: &Pubkey,
+        governance_mint: &Pubkey,
+        token_program: &Pubkey,
+        amount: u64,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::Unstake { amount }.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new(StakePool::find_address(program_id).0, false),
+            AccountMeta::new(StakeAccount::find_address(program_id, owner).0, false),
+            AccountMeta::new(StakePool::find_vault_address(program_id, false).0, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*governance_mint, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a distribute staking rewards instruction
+    pub fn distribute_staking_rewards(program_id: &Pubkey) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::DistributeStakingRewards.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new(StakePool::find_address(program_id).0, false),
+            AccountMeta::new_readonly(StakePool::find_vault_address(program_id, true).0, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a claim staking rewards instruction
+    pub fn claim_staking_rewards(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        destination: &Pubkey,
+        reward_mint: &Pubkey,
+        token_program: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::ClaimStakingRewards.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new(StakePool::find_address(program_id).0, false),
+            AccountMeta::new(StakeAccount::find_address(program_id, owner).0, false),
+            AccountMeta::new(StakePool::find_vault_address(program_id, true).0, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*reward_mint, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a set loyalty schedule instruction
+    pub fn set_loyalty_schedule(
+        program_id: &Pubkey,
+        authority: &Pubkey,
+        market: &Pubkey,
+        num_phases: u8,
+        phases: [EmissionPhase; LOYALTY_SCHEDULE_PHASES],
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::SetLoyaltySchedule { num_phases, phases }.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new(*authority, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new(LoyaltySchedule::find_address(program_id, market).0, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create an open loyalty points instruction
+    pub fn open_loyalty_points(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        market: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::OpenLoyaltyPoints.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new(*owner, true),
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new(LoyaltyPoints::find_address(program_id, market, owner).0, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a set fee split instruction
+    pub fn set_fee_split(
+        program_id: &Pubkey,
+        admin: &Pubkey,
+        market: &Pubkey,
+        treasury_token_account: &Pubkey,
+        creator_token_account: &Pubkey,
+        treasury_share_bps: u16,
+        creator_share_bps: u16,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::SetFeeSplit { treasury_share_bps, creator_share_bps }.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new_readonly(ProgramConfig::find_address(program_id).0, false),
+            AccountMeta::new(*market, false),
+            AccountMeta::new_readonly(*treasury_token_account, false),
+            AccountMeta::new_readonly(*creator_token_account, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a set dynamic fees instruction
+    pub fn set_dynamic_fees(
+        program_id: &Pubkey,
+        market_authority: &Pubkey,
+        market: &Pubkey,
+        min_fee_bps: u16,
+        volatility_bps: u16,
+        volatility_window: u16,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::SetDynamicFees {
+            min_fee_bps,
+            volatility_bps,
+            volatility_window,
+        }
+        .try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new_readonly(*market_authority, true),
+            AccountMeta::new(*market, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a place perp order instruction
+    pub fn place_perp_order(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        market: &Pubkey,
+        order: &Pubkey,
+        oracle: &Pubkey,
+        is_buy: bool,
+        limit_price: u64,
+        quantity: u64,
+        reduce_only: bool,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::PlacePerpOrder {
+            is_buy,
+            limit_price,
+            quantity,
+            reduce_only,
+        }
+        .try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new(*owner, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new_readonly(PerpMarket::find_address(program_id, market).0, false),
+            AccountMeta::new_readonly(PerpPosition::find_address(program_id, market, owner).0, false),
+            AccountMeta::new(*order, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(*oracle, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a cancel perp order instruction
+    pub fn cancel_perp_order(
+        program_id: &Pubkey,
+        owner: &Pubkey,
+        market: &Pubkey,
+        order: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::CancelPerpOrder.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new(*owner, true),
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new(*order, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a publish prices instruction
+    pub fn publish_prices(
+        program_id: &Pubkey,
+        payer: &Pubkey,
+        market: &Pubkey,
+        nonce: u32,
+        finalized: bool,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::PublishPrices { nonce, finalized }.try_to_vec()?;
+
+        // Create account metas
+        let emitter = wormhole::find_emitter_address(program_id, market).0;
+        let accounts = vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new_readonly(TwapOracle::find_address(program_id, market).0, false),
+            AccountMeta::new(wormhole::bridge_config_address(), false),
+            AccountMeta::new(wormhole::find_price_message_address(program_id, market).0, false),
+            AccountMeta::new_readonly(emitter, false),
+            AccountMeta::new(wormhole::sequence_address(&emitter), false),
+            AccountMeta::new(wormhole::fee_collector_address(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(wormhole::CORE_BRIDGE_PROGRAM_ID, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a migrate account instruction
+    pub fn migrate_account(
+        program_id: &Pubkey,
+        payer: &Pubkey,
+        account: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::MigrateAccount.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(*account, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a sweep fees instruction
+    pub fn sweep_fees(
+        program_id: &Pubkey,
+        fee_authority: &Pubkey,
+        market: &Pubkey,
+        destination: &Pubkey,
+        token_program: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::SweepFees.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new_readonly(*fee_authority, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new(Market::find_fee_vault_address(program_id, market).0, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a set fee authority instruction
+    pub fn set_fee_authority(
+        program_id: &Pubkey,
+        fee_authority: &Pubkey,
+        market: &Pubkey,
+        new_fee_authority: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::SetFeeAuthority {
+            fee_authority: *new_fee_authority,
+        }
+        .try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new_readonly(*fee_authority, true),
+            AccountMeta::new(*market, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a set market status instruction
+    pub fn set_market_status(
+        program_id: &Pubkey,
+        market_authority: &Pubkey,
+        market: &Pubkey,
+        status: MarketStatus,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::SetMarketStatus { status }.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new_readonly(*market_authority, true),
+            AccountMeta::new(*market, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a close market instruction
+    pub fn close_market(
+        program_id: &Pubkey,
+        market_authority: &Pubkey,
+        market: &Pubkey,
+        recipient: &Pubkey,
+        token_program: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::CloseMarket.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new_readonly(*market_authority, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new(Market::find_fee_vault_address(program_id, market).0, false),
+            AccountMeta::new(*recipient, false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new(Market::find_base_vault_address(program_id, market).0, false),
+            AccountMeta::new(Market::find_quote_vault_address(program_id, market).0, false),
+            AccountMeta::new_readonly(Market::find_signer_address(program_id, market).0, false),
+            AccountMeta::new(Market::find_insurance_fund_address(program_id, market).0, false),
+            AccountMeta::new(TwapOracle::find_address(program_id, market).0, false),
+            AccountMeta::new(EventQueue::find_address(program_id, market).0, false),
+            AccountMeta::new(BatchAuction::find_address(program_id, market).0, false),
+            AccountMeta::new(LoyaltySchedule::find_address(program_id, market).0, false),
+            AccountMeta::new(Pool::find_address(program_id, market).0, false),
+            AccountMeta::new(Pool::find_vault_address(program_id, market, false).0, false),
+            AccountMeta::new(Pool::find_vault_address(program_id, market, true).0, false),
+            AccountMeta::new(RangePool::find_address(program_id, market).0, false),
+            AccountMeta::new(RangePool::find_vault_address(program_id, market, false).0, false),
+            AccountMeta::new(RangePool::find_vault_address(program_id, market, true).0, false),
+            AccountMeta::new(PerpMarket::find_address(program_id, market).0, false),
+            AccountMeta::new(PerpMarket::find_vault_address(program_id, market).0, false),
+            AccountMeta::new(TokenSale::find_address(program_id, market).0, false),
+            AccountMeta::new(TokenSale::find_vault_address(program_id, market, false).0, false),
+            AccountMeta::new(TokenSale::find_vault_address(program_id, market, true).0, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create an admin cancel order instruction
+    pub fn admin_cancel_order(
+        program_id: &Pubkey,
+        market_authority: &Pubkey,
+        market: &Pubkey,
+        order_account: &Pubkey,
+        order_owner: &Pubkey,
+        owner_token_account: &Pubkey,
+        token_program: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::AdminCancelOrder.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new_readonly(*market_authority, true),
+            AccountMeta::new(*market, false),
+            AccountMeta::new(*order_account, false),
+            AccountMeta::new(*owner_token_account, false),
+            AccountMeta::new(Market::find_base_vault_address(program_id, market).0, false),
+            AccountMeta::new(Market::find_quote_vault_address(program_id, market).0, false),
+            AccountMeta::new_readonly(Market::find_signer_address(program_id, market).0, false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new(*order_owner, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create an admin cancel order instruction for an order linked to another order
+    pub fn admin_cancel_linked_order(
+        program_id: &Pubkey,
+        market_authority: &Pubkey,
+        market: &Pubkey,
+        order_account: &Pubkey,
+        order_owner: &Pubkey,
+        owner_token_account: &Pubkey,
+        token_program: &Pubkey,
+        linked_order_account: &Pubkey,
+        linked_owner_token_account: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        let mut instruction = Self::admin_cancel_order(
+            program_id,
+            market_authority,
+            market,
+            order_account,
+            order_owner,
+            owner_token_account,
+            token_program,
+        )?;
+        instruction
+            .accounts
+            .push(AccountMeta::new(*linked_order_account, false));
+        instruction
+            .accounts
+            .push(AccountMeta::new(*linked_owner_token_account, false));
+        Ok(instruction)
+    }
+
+    /// Create a set oracle instruction
+    pub fn set_oracle(
+        program_id: &Pubkey,
+        market_authority: &Pubkey,
+        market: &Pubkey,
+        oracle: &Pubkey,
+        band_bps: u16,
+        max_staleness_slots: u64,
+        price_expo: i32,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::SetOracle {
+            oracle: *oracle,
+            band_bps,
+            max_staleness_slots,
+            price_expo,
+        }
+        .try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new_readonly(*market_authority, true),
+            AccountMeta::new(*market, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a check oracle instruction
+    pub fn check_oracle(
+        program_id: &Pubkey,
+        market: &Pubkey,
+        oracle: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::CheckOracle.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new(*market, false),
+            AccountMeta::new_readonly(*oracle, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Add a market's oracle account to a place limit or iceberg order instruction
     ///
-    /// Accounts expected:
-    /// 0. `[signer, writable]` Market authority account
-    /// 1. `[writable]` Market account (uninitialized)
-    /// 2. `[]` Base token mint
-    /// 3. `[]` Quote token mint
-    /// 4. `[]` Rent sysvar
-    /// 5. `[]` System program
-    InitializeMarket {
-        /// Minimum order size in base token amount
-        min_base_order_size: u64,
-        /// Tick size in quote token amount (minimum price increment)
-        tick_size: u64,
-        /// Transaction fee rate in basis points (1/100 of 1%)
-        fee_rate_bps: u16,
-    },
+    /// Unused optional accounts before the oracle are filled with the system program.
+    pub fn with_oracle(mut instruction: Instruction, oracle: &Pubkey) -> Instruction {
+        while instruction.accounts.len() < 10 {
+            instruction
+                .accounts
+                .push(AccountMeta::new_readonly(system_program::id(), false));
+        }
+        instruction.accounts.push(AccountMeta::new_readonly(*oracle, false));
+        instruction
+    }
 
-    /// Place a limit order
+    /// Attach a memo to a place limit or iceberg order instruction and add the SPL Memo program
     ///
-    /// Accounts expected:
-    /// 0. `[signer, writable]` Order owner account
-    /// 1. `[writable]` Market account
-    /// 2. `[writable]` Order account (uninitialized)
-    /// 3. `[writable]` Owner's token account to debit
-    /// 4. `[]` Token program
-    /// 5. `[]` System program
-    PlaceLimitOrder {
-        /// Side of the order (true for buy, false for sell)
-        is_buy: bool,
-        /// Limit price in quote tokens
-        limit_price: u64,
-        /// Order quantity in base tokens
-        quantity: u64,
-        /// Self-trade behavior
-        self_trade_behavior: SelfTradeBehavior,
-    },
+    /// Unused optional accounts before the memo program are filled with the system program, so
+    /// an oracle account has to be added first.
+    pub fn with_memo(mut instruction: Instruction, memo: &str) -> Result<Instruction, ProgramError> {
+        let mut data = DexInstruction::try_from_slice(&instruction.data)
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
+        match &mut data {
+            DexInstruction::PlaceLimitOrder { memo: order_memo, .. }
+            | DexInstruction::PlaceIcebergOrder { memo: order_memo, .. } => *order_memo = memo.to_string(),
+            _ => return Err(ProgramError::InvalidInstructionData),
+        }
+        instruction.data = data.try_to_vec()?;
 
-    /// Cancel an order
+        while instruction.accounts.len() < 11 {
+            instruction
+                .accounts
+                .push(AccountMeta::new_readonly(system_program::id(), false));
+        }
+        instruction.accounts.push(AccountMeta::new_readonly(spl_memo::id(), false));
+        Ok(instruction)
+    }
+
+    /// Add a market's TWAP oracle to a settle funds instruction
     ///
-    /// Accounts expected:
-    /// 0. `[signer]` Order owner account
-    /// 1. `[writable]` Market account
-    /// 2. `[writable]` Order account
-    /// 3. `[writable]` Owner's token account to credit
-    /// 4. `[]` Token program
-    CancelOrder,
+    /// The fee vault fills an unused referrer slot and the system program an unused settlement
+    /// record slot.
+    pub fn with_twap_oracle(mut instruction: Instruction) -> Instruction {
+        let market = instruction.accounts[1].pubkey;
+        if instruction.accounts.len() < 17 {
+            let fee_vault = Market::find_fee_vault_address(&instruction.program_id, &market).0;
+            instruction.accounts.push(AccountMeta::new(fee_vault, false));
+        }
+        if instruction.accounts.len() < 18 {
+            instruction
+                .accounts
+                .push(AccountMeta::new_readonly(system_program::id(), false));
+        }
+        let twap_oracle = TwapOracle::find_address(&instruction.program_id, &market).0;
+        instruction.accounts.push(AccountMeta::new(twap_oracle, false));
+        instruction
+    }
 
-    /// Settle funds after a trade
+    /// Add a market's event queue to a settle funds instruction
     ///
-    /// Accounts expected:
-    /// 0. `[signer]` Authority account
-    /// 1. `[writable]` Market account
-    /// 2. `[writable]` Taker account
-    /// 3. `[writable]` Maker account
-    /// 4. `[writable]` Taker base token account
-    /// 5. `[writable]` Taker quote token account
-    /// 6. `[writable]` Maker base token account
-    /// 7. `[writable]` Maker quote token account
-    /// 8. `[writable]` Fee recipient account
-    /// 9. `[]` Token program
-    SettleFunds {
-        /// Base token amount to settle
-        base_amount: u64,
-        /// Quote token amount to settle
-        quote_amount: u64,
-    },
-}
+    /// Unused optional accounts are filled as in `with_twap_oracle`, so the settlement does not
+    /// take the queue for one of them.
+    pub fn with_event_queue(mut instruction: Instruction) -> Instruction {
+        let market = instruction.accounts[1].pubkey;
+        if instruction.accounts.len() < 17 {
+            let fee_vault = Market::find_fee_vault_address(&instruction.program_id, &market).0;
+            instruction.accounts.push(AccountMeta::new(fee_vault, false));
+        }
+        while instruction.accounts.len() < 19 {
+            instruction
+                .accounts
+                .push(AccountMeta::new_readonly(system_program::id(), false));
+        }
+        let event_queue = EventQueue::find_address(&instruction.program_id, &market).0;
+        instruction.accounts.push(AccountMeta::new(event_queue, false));
+        instruction
+    }
 
-/// Self-trade behavior enum
-#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
-pub enum SelfTradeBehavior {
-    /// Decrement take (maker gets priority)
-    DecrementTake,
-    /// Cancel provide (taker gets priority)
-    CancelProvide,
-    /// Abort transaction
-    AbortTransaction,
-}
+    /// Add a market's loyalty schedule and the order owners' loyalty points accounts to a
+    /// settle funds instruction
+    ///
+    /// Unused optional accounts are filled as in `with_event_queue`; the accounts are passed
+    /// whether or not the owners have opened them.
+    pub fn with_loyalty_points(mut instruction: Instruction, taker_owner: &Pubkey, maker_owner: &Pubkey) -> Instruction {
+        let program_id = instruction.program_id;
+        let market = instruction.accounts[1].pubkey;
+        if instruction.accounts.len() < 17 {
+            let fee_vault = Market::find_fee_vault_address(&program_id, &market).0;
+            instruction.accounts.push(AccountMeta::new(fee_vault, false));
+        }
+        while instruction.accounts.len() < 19 {
+            instruction
+                .accounts
+                .push(AccountMeta::new_readonly(system_program::id(), false));
+        }
+        instruction.accounts.push(AccountMeta::new(LoyaltySchedule::find_address(&program_id, &market).0, false));
+        for owner in [taker_owner, maker_owner] {
+            let points = LoyaltyPoints::find_address(&program_id, &market, owner).0;
+            if !instruction.accounts.iter().any(|account| account.pubkey == points) {
+                instruction.accounts.push(AccountMeta::new(points, false));
+            }
+        }
+        instruction
+    }
 
-// Implementation of DexInstruction
-impl DexInstruction {
-    /// Create an initialize market instruction
-    pub fn initialize_market(
+    /// Add the stake pool and the taker order owner's stake account to a settle funds
+    /// instruction, for the taker fee discount of its stake
+    ///
+    /// Unused optional accounts are filled as in `with_event_queue`. Fails with
+    /// `NotEnoughAccountKeys` if the instruction has no market account.
+    pub fn with_stake_discount(mut instruction: Instruction, taker_owner: &Pubkey) -> Result<Instruction, ProgramError> {
+        let program_id = instruction.program_id;
+        let market = instruction
+            .accounts
+            .get(1)
+            .ok_or(ProgramError::NotEnoughAccountKeys)?
+            .pubkey;
+        if instruction.accounts.len() < 17 {
+            let fee_vault = Market::find_fee_vault_address(&program_id, &market).0;
+            instruction.accounts.push(AccountMeta::new(fee_vault, false));
+        }
+        while instruction.accounts.len() < 19 {
+            instruction
+                .accounts
+                .push(AccountMeta::new_readonly(system_program::id(), false));
+        }
+        instruction.accounts.push(AccountMeta::new_readonly(StakePool::find_address(&program_id).0, false));
+        instruction
+            .accounts
+            .push(AccountMeta::new_readonly(StakeAccount::find_address(&program_id, taker_owner).0, false));
+        Ok(instruction)
+    }
+
+    /// Add the orders linked to the matched orders to a settle funds instruction, so that the
+    /// fill of a one-cancels-other order cancels its linked order
+    ///
+    /// Unused optional accounts are filled as in `with_event_queue`.
+    pub fn with_linked_orders(mut instruction: Instruction, linked_orders: &[Pubkey]) -> Instruction {
+        let market = instruction.accounts[1].pubkey;
+        if instruction.accounts.len() < 17 {
+            let fee_vault = Market::find_fee_vault_address(&instruction.program_id, &market).0;
+            instruction.accounts.push(AccountMeta::new(fee_vault, false));
+        }
+        while instruction.accounts.len() < 19 {
+            instruction
+                .accounts
+                .push(AccountMeta::new_readonly(system_program::id(), false));
+        }
+        for linked_order in linked_orders {
+            instruction.accounts.push(AccountMeta::new(*linked_order, false));
+        }
+        instruction
+    }
+
+    /// Add the mints and token programs of a market to an instruction that moves its tokens
+    ///
+    /// Token-2022 sides must pass both so that transfers are checked against the mint, and
+    /// the program looks them up by key. Unused optional accounts of place order, settle funds
+    /// and firm quote instructions are filled first, so this goes after the other `with_`
+    /// helpers.
+    pub fn with_token_mints(mut instruction: Instruction, market: &Market) -> Result<Instruction, ProgramError> {
+        let data = DexInstruction::try_from_slice(&instruction.data)
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
+        let optional_accounts = match data {
+            DexInstruction::PlaceLimitOrder { .. } | DexInstruction::PlaceIcebergOrder { .. } => 14,
+            DexInstruction::SettleFunds => {
+                // The fee vault fills an unused referrer slot, as in `with_twap_oracle`
+                if instruction.accounts.len() < 17 {
+                    let market = instruction.accounts[1].pubkey;
+                    let fee_vault = Market::find_fee_vault_address(&instruction.program_id, &market).0;
+                    instruction.accounts.push(AccountMeta::new(fee_vault, false));
+                }
+                19
+            }
+            DexInstruction::ExecuteFirmQuote { .. } => 13,
+            _ => 0,
+        };
+        while instruction.accounts.len() < optional_accounts {
+            instruction
+                .accounts
+                .push(AccountMeta::new_readonly(system_program::id(), false));
+        }
+
+        instruction.accounts.push(AccountMeta::new_readonly(market.base_mint, false));
+        instruction.accounts.push(AccountMeta::new_readonly(market.quote_mint, false));
+        instruction.accounts.push(AccountMeta::new_readonly(market.base_token_program, false));
+        instruction.accounts.push(AccountMeta::new_readonly(market.quote_token_program, false));
+        Ok(instruction)
+    }
+
+    /// Sign a place order or cancel order instruction built for an owner with the owner's delegate
+    ///
+    /// Adds the owner's delegation and the accounts a delegate needs; unused optional accounts
+    /// of a place order are filled with the system program, so a memo has to be attached first.
+    pub fn with_delegate(mut instruction: Instruction, delegate: &Pubkey) -> Result<Instruction, ProgramError> {
+        let data = DexInstruction::try_from_slice(&instruction.data)
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
+        let owner = instruction.accounts[0].pubkey;
+        let market = instruction.accounts[1].pubkey;
+        let delegation = Delegation::find_address(&instruction.program_id, &market, &owner).0;
+        instruction.accounts[0] = AccountMeta::new(*delegate, true);
+
+        match data {
+            DexInstruction::PlaceLimitOrder { .. } | DexInstruction::PlaceIcebergOrder { .. } => {
+                while instruction.accounts.len() < 12 {
+                    instruction
+                        .accounts
+                        .push(AccountMeta::new_readonly(system_program::id(), false));
+                }
+                instruction.accounts.push(AccountMeta::new_readonly(delegation, false));
+                instruction.accounts.push(AccountMeta::new_readonly(
+                    Market::find_signer_address(&instruction.program_id, &market).0,
+                    false,
+                ));
+            }
+            DexInstruction::CancelOrder => {
+                instruction.accounts.push(AccountMeta::new_readonly(delegation, false));
+                instruction.accounts.push(AccountMeta::new(owner, false));
+            }
+            _ => return Err(ProgramError::InvalidInstructionData),
+        }
+        Ok(instruction)
+    }
+
+    /// Create an initialize insurance fund instruction
+    pub fn initialize_insurance_fund(
         program_id: &Pubkey,
-        market_authority: &Pubkey,
-        market_account: &Pubkey,
-        base_mint: &Pubkey,
+        payer: &Pubkey,
+        market: &Pubkey,
         quote_mint: &Pubkey,
-        min_base_order_size: u64,
-        tick_size: u64,
-        fee_rate_bps: u16,
+        token_program: &Pubkey,
     ) -> Result<Instruction, ProgramError> {
         // Create instruction data
-        let data = DexInstruction::InitializeMarket {
-            min_base_order_size,
-            tick_size,
-            fee_rate_bps,
-        }
-        .try_to_vec()?;
+        let data = DexInstruction::InitializeInsuranceFund.try_to_vec()?;
 
         // Create account metas
         let accounts = vec![
-            AccountMeta::new(*market_authority, true),
-            AccountMeta::new(*market_account, false),
-            AccountMeta::new_readonly(*base_mint, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new(Market::find_insurance_fund_address(program_id, market).0, false),
             AccountMeta::new_readonly(*quote_mint, false),
-            AccountMeta::new_readonly(rent::id(), false),
+            AccountMeta::new_readonly(*token_program, false),
             AccountMeta::new_readonly(system_program::id(), false),
         ];
 
@@ -131,36 +5078,25 @@ impl DexInstruction {
         })
     }
 
-    /// Create a place limit order instruction
-    pub fn place_limit_order(
+    /// Create a deposit insurance fund instruction
+    pub fn deposit_insurance_fund(
         program_id: &Pubkey,
-        owner: &Pubkey,
+        depositor: &Pubkey,
         market: &Pubkey,
-        order_account: &Pubkey,
-        owner_token_account: &Pubkey,
+        source: &Pubkey,
         token_program: &Pubkey,
-        is_buy: bool,
-        limit_price: u64,
-        quantity: u64,
-        self_trade_behavior: SelfTradeBehavior,
+        amount: u64,
     ) -> Result<Instruction, ProgramError> {
         // Create instruction data
-        let data = DexInstruction::PlaceLimitOrder {
-            is_buy,
-            limit_price,
-            quantity,
-            self_trade_behavior,
-        }
-        .try_to_vec()?;
+        let data = DexInstruction::DepositInsuranceFund { amount }.try_to_vec()?;
 
         // Create account metas
         let accounts = vec![
-            AccountMeta::new(*owner, true),
-            AccountMeta::new(*market, false),
-            AccountMeta::new(*order_account, false),
-            AccountMeta::new(*owner_token_account, false),
+            AccountMeta::new_readonly(*depositor, true),
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new(*source, false),
+            AccountMeta::new(Market::find_insurance_fund_address(program_id, market).0, false),
             AccountMeta::new_readonly(*token_program, false),
-            AccountMeta::new_readonly(system_program::id(), false),
         ];
 
         Ok(Instruction {
@@ -170,24 +5106,24 @@ impl DexInstruction {
         })
     }
 
-    /// Create a cancel order instruction
-    pub fn cancel_order(
+    /// Create a withdraw insurance fund instruction
+    pub fn withdraw_insurance_fund(
         program_id: &Pubkey,
-        owner: &Pubkey,
+        market_authority: &Pubkey,
         market: &Pubkey,
-        order_account: &Pubkey,
-        owner_token_account: &Pubkey,
+        destination: &Pubkey,
         token_program: &Pubkey,
+        amount: u64,
     ) -> Result<Instruction, ProgramError> {
         // Create instruction data
-        let data = DexInstruction::CancelOrder.try_to_vec()?;
+        let data = DexInstruction::WithdrawInsuranceFund { amount }.try_to_vec()?;
 
         // Create account metas
         let accounts = vec![
-            AccountMeta::new_readonly(*owner, true),
-            AccountMeta::new(*market, false),
-            AccountMeta::new(*order_account, false),
-            AccountMeta::new(*owner_token_account, false),
+            AccountMeta::new_readonly(*market_authority, true),
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new(Market::find_insurance_fund_address(program_id, market).0, false),
+            AccountMeta::new(*destination, false),
             AccountMeta::new_readonly(*token_program, false),
         ];
 
@@ -198,41 +5134,115 @@ impl DexInstruction {
         })
     }
 
-    /// Create a settle funds instruction
-    pub fn settle_funds(
+    /// Create a set whitelist enabled instruction
+    pub fn set_whitelist_enabled(
         program_id: &Pubkey,
-        authority: &Pubkey,
+        market_authority: &Pubkey,
+        market: &Pubkey,
+        enabled: bool,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::SetWhitelistEnabled { enabled }.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new_readonly(*market_authority, true),
+            AccountMeta::new(*market, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create an add whitelist entry instruction
+    pub fn add_whitelist_entry(
+        program_id: &Pubkey,
+        market_authority: &Pubkey,
+        market: &Pubkey,
+        owner: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::AddWhitelistEntry { owner: *owner }.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new(*market_authority, true),
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new(WhitelistEntry::find_address(program_id, market, owner).0, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create a remove whitelist entry instruction
+    pub fn remove_whitelist_entry(
+        program_id: &Pubkey,
+        market_authority: &Pubkey,
         market: &Pubkey,
+        owner: &Pubkey,
+        recipient: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        // Create instruction data
+        let data = DexInstruction::RemoveWhitelistEntry.try_to_vec()?;
+
+        // Create account metas
+        let accounts = vec![
+            AccountMeta::new_readonly(*market_authority, true),
+            AccountMeta::new_readonly(*market, false),
+            AccountMeta::new(WhitelistEntry::find_address(program_id, market, owner).0, false),
+            AccountMeta::new(*recipient, false),
+        ];
+
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        })
+    }
+
+    /// Create an execute firm quote instruction
+    ///
+    /// Precede it with `ed25519::new_ed25519_instruction` over the quote's signing message.
+    pub fn execute_firm_quote(
+        program_id: &Pubkey,
         taker: &Pubkey,
-        maker: &Pubkey,
+        quote: &FirmQuote,
         taker_base_account: &Pubkey,
         taker_quote_account: &Pubkey,
         maker_base_account: &Pubkey,
         maker_quote_account: &Pubkey,
-        fee_recipient: &Pubkey,
         token_program: &Pubkey,
         base_amount: u64,
-        quote_amount: u64,
     ) -> Result<Instruction, ProgramError> {
         // Create instruction data
-        let data = DexInstruction::SettleFunds {
+        let data = DexInstruction::ExecuteFirmQuote {
+            quote: quote.clone(),
             base_amount,
-            quote_amount,
         }
         .try_to_vec()?;
 
         // Create account metas
         let accounts = vec![
-            AccountMeta::new_readonly(*authority, true),
-            AccountMeta::new(*market, false),
-            AccountMeta::new(*taker, false),
-            AccountMeta::new(*maker, false),
+            AccountMeta::new(*taker, true),
+            AccountMeta::new(quote.market, false),
+            AccountMeta::new(quote.find_fill_address(program_id).0, false),
             AccountMeta::new(*taker_base_account, false),
             AccountMeta::new(*taker_quote_account, false),
             AccountMeta::new(*maker_base_account, false),
             AccountMeta::new(*maker_quote_account, false),
-            AccountMeta::new(*fee_recipient, false),
+            AccountMeta::new(Market::find_fee_vault_address(program_id, &quote.market).0, false),
+            AccountMeta::new_readonly(Market::find_rfq_authority_address(program_id, &quote.market).0, false),
             AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(instructions::id(), false),
         ];
 
         Ok(Instruction {
@@ -241,4 +5251,34 @@ impl DexInstruction {
             data,
         })
     }
+
+    /// Create an execute firm quote instruction for a permissioned market
+    pub fn execute_firm_quote_whitelisted(
+        program_id: &Pubkey,
+        taker: &Pubkey,
+        quote: &FirmQuote,
+        taker_base_account: &Pubkey,
+        taker_quote_account: &Pubkey,
+        maker_base_account: &Pubkey,
+        maker_quote_account: &Pubkey,
+        token_program: &Pubkey,
+        base_amount: u64,
+    ) -> Result<Instruction, ProgramError> {
+        let mut instruction = Self::execute_firm_quote(
+            program_id,
+            taker,
+            quote,
+            taker_base_account,
+            taker_quote_account,
+            maker_base_account,
+            maker_quote_account,
+            token_program,
+            base_amount,
+        )?;
+        instruction.accounts.push(AccountMeta::new_readonly(
+            WhitelistEntry::find_address(program_id, &quote.market, taker).0,
+            false,
+        ));
+        Ok(instruction)
+    }
 }
@@ -1,10 +1,21 @@
 // Solana Rust DEX - Main Library File
 
+// Instruction builders mirror the account lists of their instructions
+#![allow(clippy::too_many_arguments)]
+
+#[cfg(feature = "anchor")]
+pub mod anchor;
+pub mod ed25519;
 pub mod entrypoint;
 pub mod error;
 pub mod instruction;
+pub mod math;
+pub mod merkle;
+pub mod oracle;
 pub mod processor;
 pub mod state;
+pub mod token;
+pub mod wormhole;
 
 // Export the program's entrypoint
 #[cfg(not(feature = "no-entrypoint"))]
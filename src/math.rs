@@ -0,0 +1,197 @@
+// Price, quantity and fee arithmetic
+//
+// Products are taken in u128 so that lamport-scale prices and quantities never overflow an
+// intermediate; only the final token amount has to fit in a u64. Fees round up and amounts
+// paid out (proceeds, rebates, referral shares) round down, so no sequence of fills can create
+// tokens.
+
+use solana_program::program_error::ProgramError;
+
+/// Basis points in a whole
+pub const BPS_DENOMINATOR: u64 = 10000;
+
+/// Direction a division rounds in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    /// Towards zero, for amounts paid out by the program
+    Down,
+    /// Away from zero, for amounts paid to the program
+    Up,
+}
+
+/// Compute `value * numerator / denominator` with a u128 intermediate
+pub fn mul_div(value: u64, numerator: u64, denominator: u64, rounding: Rounding) -> Result<u64, ProgramError> {
+    if denominator == 0 {
+        return Err(ProgramError::ArithmeticOverflow);
+    }
+
+    let product = value as u128 * numerator as u128;
+    let quotient = match rounding {
+        Rounding::Down => product / denominator as u128,
+        Rounding::Up => product.div_ceil(denominator as u128),
+    };
+    u64::try_from(quotient).map_err(|_| ProgramError::ArithmeticOverflow)
+}
+
+/// Quote amount of a base quantity at a price
+pub fn notional(price: u64, quantity: u64) -> Result<u64, ProgramError> {
+    u64::try_from(price as u128 * quantity as u128).map_err(|_| ProgramError::ArithmeticOverflow)
+}
+
+/// Share of an amount given in basis points
+pub fn bps_of(amount: u64, bps: u64, rounding: Rounding) -> Result<u64, ProgramError> {
+    mul_div(amount, bps, BPS_DENOMINATOR, rounding)
+}
+
+/// Integer square root, rounding down
+pub fn isqrt(value: u128) -> u128 {
+    if value < 2 {
+        return value;
+    }
+
+    // Newton's method from an estimate above the root decreases monotonically to it
+    let mut root = 1u128 << (128 - value.leading_zeros()).div_ceil(2);
+    loop {
+        let next = (root + value / root) / 2;
+        if next >= root {
+            return root;
+        }
+        root = next;
+    }
+}
+
+/// Lowest tick of a range pool, where the price is about 2^-32
+pub const MIN_TICK: i32 = -221_818;
+
+/// Highest tick of a range pool, where the price is about 2^32
+pub const MAX_TICK: i32 = 221_818;
+
+/// Fractional bits of a square-root price
+pub const SQRT_PRICE_FRACTION_BITS: u32 = 32;
+
+// `2^64 / sqrt(1.0001)^(2^i)`: the factor a tick of bit `i` set multiplies the square-root
+// price by, below one in Q0.64
+const TICK_RATIOS: [u128; 18] = [
+    0xfffcb933bd6fad37,
+    0xfff97272373d4132,
+    0xfff2e50f5f656932,
+    0xffe5caca7e10e4e6,
+    0xffcb9843d60f6159,
+    0xff973b41fa98c081,
+    0xff2ea16466c96a38,
+    0xfe5dee046a99a2a8,
+    0xfcbe86c7900a88ae,
+    0xf987a7253ac41317,
+    0xf3392b0822b70005,
+    0xe7159475a2c29b74,
+    0xd097f3bdfd2022b8,
+    0xa9f746462d870fdf,
+    0x70d869a156d2a1b8,
+    0x31be135f97d08fd9,
+    0x09aa508b5b7a84e1,
+    0x005d6af8dedb8119,
+];
+
+/// Square root of the price at a tick, `sqrt(1.0001^tick)` in Q32.32 fixed point, rounded up
+///
+/// Quote tokens per base token are the square of the result; every price between `MIN_TICK`
+/// and `MAX_TICK` has a square root that fits a u64.
+pub fn sqrt_price_at_tick(tick: i32) -> Result<u64, ProgramError> {
+    if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Multiply together the factors of the bits of the tick's magnitude, staying below one
+    let magnitude = tick.unsigned_abs();
+    let mut ratio = 1u128 << 64;
+    for (bit, factor) in TICK_RATIOS.iter().enumerate() {
+        if magnitude & (1 << bit) != 0 {
+            ratio = (ratio * factor) >> 64;
+        }
+    }
+
+    // Positive ticks take the reciprocal, then Q64.64 rounds up to Q32.32
+    if tick > 0 {
+        ratio = u128::MAX / ratio;
+    }
+    let sqrt_price = ratio.div_ceil(1 << (64 - SQRT_PRICE_FRACTION_BITS));
+    u64::try_from(sqrt_price).map_err(|_| ProgramError::ArithmeticOverflow)
+}
+
+/// Highest tick whose square-root price is at most `sqrt_price`
+pub fn tick_at_sqrt_price(sqrt_price: u64) -> Result<i32, ProgramError> {
+    if sqrt_price < sqrt_price_at_tick(MIN_TICK)? || sqrt_price > sqrt_price_at_tick(MAX_TICK)? {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // The square-root price increases with the tick, so the tick is found by bisection
+    let (mut low, mut high) = (MIN_TICK, MAX_TICK);
+    while low < high {
+        let mid = low + (high - low + 1) / 2;
+        if sqrt_price_at_tick(mid)? <= sqrt_price {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    Ok(low)
+}
+
+/// Base tokens `liquidity` holds between two square-root prices:
+/// `liquidity * (1 / sqrt_price_a - 1 / sqrt_price_b)`
+pub fn base_delta(sqrt_price_a: u64, sqrt_price_b: u64, liquidity: u64, rounding: Rounding) -> Result<u64, ProgramError> {
+    let (lower, upper) = (sqrt_price_a.min(sqrt_price_b), sqrt_price_a.max(sqrt_price_b));
+    if lower == 0 {
+        return Err(ProgramError::ArithmeticOverflow);
+    }
+
+    // Each reciprocal rounds in the direction that moves the difference the same way
+    let scaled = (liquidity as u128) << SQRT_PRICE_FRACTION_BITS;
+    let delta = match rounding {
+        Rounding::Down => (scaled / lower as u128).saturating_sub(scaled.div_ceil(upper as u128)),
+        Rounding::Up => scaled.div_ceil(lower as u128) - scaled / upper as u128,
+    };
+    u64::try_from(delta).map_err(|_| ProgramError::ArithmeticOverflow)
+}
+
+/// Quote tokens `liquidity` holds between two square-root prices:
+/// `liquidity * (sqrt_price_b - sqrt_price_a)`
+pub fn quote_delta(sqrt_price_a: u64, sqrt_price_b: u64, liquidity: u64, rounding: Rounding) -> Result<u64, ProgramError> {
+    let difference = sqrt_price_a.abs_diff(sqrt_price_b) as u128;
+    let product = liquidity as u128 * difference;
+    let delta = match rounding {
+        Rounding::Down => product >> SQRT_PRICE_FRACTION_BITS,
+        Rounding::Up => product.div_ceil(1 << SQRT_PRICE_FRACTION_BITS),
+    };
+    u64::try_from(delta).map_err(|_| ProgramError::ArithmeticOverflow)
+}
+
+/// Square-root price after `amount` is swapped in at `liquidity`: quote tokens raise the
+/// price, base tokens lower it
+///
+/// The price moves slightly less than exactly, so the tokens paid out for it never exceed the
+/// tokens the amount is worth.
+pub fn next_sqrt_price(sqrt_price: u64, liquidity: u64, amount: u64, is_quote: bool) -> Result<u64, ProgramError> {
+    if liquidity == 0 || sqrt_price == 0 {
+        return Err(ProgramError::ArithmeticOverflow);
+    }
+
+    let next = if is_quote {
+        // sqrt_price + amount / liquidity
+        ((amount as u128) << SQRT_PRICE_FRACTION_BITS)
+            .checked_div(liquidity as u128)
+            .and_then(|step| step.checked_add(sqrt_price as u128))
+    } else {
+        // liquidity / (liquidity / sqrt_price + amount), which rounding must not lift above
+        // the starting price
+        let scaled = (liquidity as u128) << SQRT_PRICE_FRACTION_BITS;
+        let denominator = scaled / sqrt_price as u128 + amount as u128;
+        if denominator == 0 {
+            Some(sqrt_price as u128)
+        } else {
+            Some(scaled.div_ceil(denominator).min(sqrt_price as u128))
+        }
+    };
+    next.and_then(|next| u64::try_from(next).ok())
+        .ok_or(ProgramError::ArithmeticOverflow)
+}
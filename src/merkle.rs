@@ -0,0 +1,108 @@
+// Merkle commitments over a market's open orders
+
+use crate::state::Order;
+use solana_program::{hash::hashv, program_pack::Pack, pubkey::Pubkey};
+
+/// Root of an empty order set
+pub const EMPTY_ROOT: [u8; 32] = [0; 32];
+
+// Domain separation prefixes so a leaf can never be read as an inner node
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+
+/// Hash an open order together with the address of its account
+pub fn leaf_hash(order_address: &Pubkey, order: &Order) -> [u8; 32] {
+    let mut order_data = vec![0; Order::LEN];
+    order.pack_into_slice(&mut order_data);
+    hashv(&[LEAF_PREFIX, order_address.as_ref(), &order_data]).to_bytes()
+}
+
+/// Hash two child nodes into their parent
+pub fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    hashv(&[NODE_PREFIX, left, right]).to_bytes()
+}
+
+/// Compute the root of a list of leaves
+///
+/// Nodes are paired left to right; an unpaired last node is carried up to the
+/// next level unchanged.
+pub fn root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    if leaves.is_empty() {
+        return EMPTY_ROOT;
+    }
+
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level[0]
+}
+
+/// Build the inclusion proof of the leaf at `index`
+///
+/// Returns `None` if the index is out of range.
+pub fn proof(leaves: &[[u8; 32]], index: usize) -> Option<Vec<[u8; 32]>> {
+    if index >= leaves.len() {
+        return None;
+    }
+
+    let mut proof = Vec::new();
+    let mut level = leaves.to_vec();
+    let mut position = index;
+    while level.len() > 1 {
+        let sibling = position ^ 1;
+        if sibling < level.len() {
+            proof.push(level[sibling]);
+        }
+        level = next_level(&level);
+        position /= 2;
+    }
+    Some(proof)
+}
+
+/// Verify that `leaf` sits at `index` in a tree of `num_leaves` leaves with the given root
+pub fn verify(
+    root: &[u8; 32],
+    leaf: &[u8; 32],
+    index: usize,
+    num_leaves: usize,
+    proof: &[[u8; 32]],
+) -> bool {
+    if index >= num_leaves {
+        return false;
+    }
+
+    let mut hash = *leaf;
+    let mut position = index;
+    let mut level_len = num_leaves;
+    let mut siblings = proof.iter();
+    while level_len > 1 {
+        let sibling = position ^ 1;
+        if sibling < level_len {
+            let Some(sibling_hash) = siblings.next() else {
+                return false;
+            };
+            hash = if sibling > position {
+                node_hash(&hash, sibling_hash)
+            } else {
+                node_hash(sibling_hash, &hash)
+            };
+        }
+        position /= 2;
+        level_len = level_len.div_ceil(2);
+    }
+
+    siblings.next().is_none() && hash == *root
+}
+
+// Hash one level of the tree into the next
+fn next_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => node_hash(left, right),
+            [single] => *single,
+            _ => unreachable!(),
+        })
+        .collect()
+}
@@ -0,0 +1,100 @@
+// Pyth price accounts read by the oracle circuit breaker
+
+use solana_program::program_error::ProgramError;
+
+/// Magic number at the start of every Pyth account
+pub const PYTH_MAGIC: u32 = 0xa1b2c3d4;
+
+/// Pyth account layout version supported
+pub const PYTH_VERSION: u32 = 2;
+
+/// Pyth account type of price accounts
+pub const PYTH_PRICE_ACCOUNT_TYPE: u32 = 3;
+
+/// Pyth aggregate price status while the price is being published
+pub const PYTH_STATUS_TRADING: u32 = 1;
+
+// Offsets of the fields read from a Pyth price account
+const MAGIC_OFFSET: usize = 0;
+const VERSION_OFFSET: usize = 4;
+const ACCOUNT_TYPE_OFFSET: usize = 8;
+const EXPONENT_OFFSET: usize = 20;
+const AGGREGATE_PRICE_OFFSET: usize = 208;
+const AGGREGATE_CONF_OFFSET: usize = 216;
+const AGGREGATE_STATUS_OFFSET: usize = 224;
+const AGGREGATE_PUBLISH_SLOT_OFFSET: usize = 232;
+
+/// Minimum length of a Pyth price account holding the fields read here
+pub const PYTH_PRICE_MIN_LEN: usize = 240;
+
+/// Aggregate price of a Pyth price account
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OraclePrice {
+    /// Price in units of 10^expo
+    pub price: i64,
+
+    /// Confidence interval in units of 10^expo
+    pub conf: u64,
+
+    /// Exponent of the price
+    pub expo: i32,
+
+    /// Aggregate price status
+    pub status: u32,
+
+    /// Slot the aggregate price was published in
+    pub publish_slot: u64,
+}
+
+impl OraclePrice {
+    /// Load the aggregate price of a Pyth price account
+    pub fn load(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() < PYTH_PRICE_MIN_LEN
+            || read_u32(data, MAGIC_OFFSET) != PYTH_MAGIC
+            || read_u32(data, VERSION_OFFSET) != PYTH_VERSION
+            || read_u32(data, ACCOUNT_TYPE_OFFSET) != PYTH_PRICE_ACCOUNT_TYPE
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(Self {
+            price: read_u64(data, AGGREGATE_PRICE_OFFSET) as i64,
+            conf: read_u64(data, AGGREGATE_CONF_OFFSET),
+            expo: read_u32(data, EXPONENT_OFFSET) as i32,
+            status: read_u32(data, AGGREGATE_STATUS_OFFSET),
+            publish_slot: read_u64(data, AGGREGATE_PUBLISH_SLOT_OFFSET),
+        })
+    }
+
+    /// Whether the price is trading and was published at most `max_staleness_slots` before `slot`
+    pub fn is_fresh(&self, slot: u64, max_staleness_slots: u64) -> bool {
+        self.status == PYTH_STATUS_TRADING && slot.saturating_sub(self.publish_slot) <= max_staleness_slots
+    }
+
+    /// Convert the price to units of 10^expo, or None if it is not positive or does not fit
+    pub fn price_in(&self, expo: i32) -> Option<u128> {
+        let price = u128::try_from(self.price).ok().filter(|price| *price > 0)?;
+        let shift = self.expo.checked_sub(expo)?;
+        if shift >= 0 {
+            price.checked_mul(10u128.checked_pow(shift as u32)?)
+        } else {
+            Some(price / 10u128.checked_pow(shift.unsigned_abs())?)
+        }
+    }
+}
+
+/// Whether `price` lies within `band_bps` basis points of `reference_price`
+pub fn is_within_band(price: u64, reference_price: u128, band_bps: u16) -> bool {
+    let distance = (price as u128).abs_diff(reference_price);
+    distance
+        .checked_mul(10000)
+        .is_some_and(|distance| distance <= reference_price.saturating_mul(band_bps as u128))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
@@ -1,29 +1,93 @@
 // Processor module for the DEX program
 
 use crate::{
-    error::{return_dex_error, DexError},
-    instruction::DexInstruction,
-    state::{Market, Order},
+    ed25519,
+    error::{return_dex_error, return_dex_error_expected, DexError},
+    instruction::{DexInstruction, MAX_CANCEL_ORDER_IDS},
+    math, merkle,
+    oracle::{self, OraclePrice},
+    state::{
+        Delegation, DexEvent, EventQueue, FeesSwept, Fill, FillEvent, FirmQuote, InstructionGuard, InsuranceFundTransfer, Market, MarketCreationMode,
+        MarketParam, MarketRegistry, MarketRegistryEntry, MarketStatus, OpenOrders, Order, OrderCancelled, OrderPlaced,
+        ParamsUpdated, PlacedOrder, Pool, ProgramConfig, QuoteFill, SettlementRecord, TradeBust, TwapOracle, WhitelistEntry,
+        BASE_VAULT_SEED, FEE_VAULT_SEED, INSURANCE_FUND_SEED, MAX_ALLOWED_QUOTE_MINTS, MAX_ORDER_MEMO_LEN, PROGRAM_CONFIG_SEED, MARKET_REGISTRY_SEED, MARKET_SEED,
+        QUOTE_FILL_SEED, QUOTE_VAULT_SEED, RFQ_AUTHORITY_SEED, WHITELIST_SEED, DELEGATION_SEED, OPEN_ORDERS_SEED,
+        TWAP_ORACLE_SEED, EVENT_QUEUE_CAPACITY, EVENT_QUEUE_SEED, MARKET_FLAG_EVENT_QUEUE, load_zero_copy_mut, ACCOUNT_VERSION, MARKET_RESERVED_LEN, ORDER_RESERVED_LEN,
+        POOL_BASE_VAULT_SEED, POOL_LP_MINT_SEED, POOL_MINIMUM_LIQUIDITY, POOL_QUOTE_VAULT_SEED, POOL_SEED,
+        RangePool, RangePosition, RANGE_POOL_BASE_VAULT_SEED, RANGE_POOL_QUOTE_VAULT_SEED, RANGE_POOL_SEED, RANGE_POSITION_SEED,
+        InterestRateCurve, LendingPool, LENDING_POOL_SEED, LENDING_POOL_SHARE_MINT_SEED, LENDING_POOL_VAULT_SEED,
+        PerpMarket, PerpOrder, PerpPosition, FUNDING_INTERVAL_SECONDS, PERP_MARKET_SEED, PERP_POSITION_SEED, PERP_VAULT_SEED,
+        BatchAuction, BATCH_AUCTION_SEED, MARKET_FLAG_BATCH_AUCTION, MAX_AUCTION_ORDERS,
+        SaleContribution, SaleStatus, TokenSale, SALE_CONTRIBUTION_SEED, TOKEN_SALE_BASE_VAULT_SEED, TOKEN_SALE_QUOTE_VAULT_SEED,
+        TOKEN_SALE_SEED,
+        FeeTier, StakeAccount, StakePool, STAKE_ACCOUNT_SEED, STAKE_POOL_SEED, STAKE_REWARD_VAULT_SEED, STAKE_VAULT_SEED,
+        EmissionPhase, LoyaltyPoints, LoyaltySchedule, LOYALTY_POINTS_SEED, LOYALTY_SCHEDULE_SEED, MARKET_FLAG_LOYALTY_POINTS,
+        MARKET_FLAG_DYNAMIC_FEES, MARKET_FLAG_BEST_BID_STALE, MARKET_FLAG_BEST_ASK_STALE,
+    },
+    token,
+    wormhole::{self, PricePayload, EMITTER_SEED, PRICE_MESSAGE_SEED},
 };
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     clock::Clock,
     entrypoint::ProgramResult,
+    log::sol_log_data,
     msg,
-    program::{invoke, invoke_signed},
+    program::{invoke, invoke_signed, set_return_data},
     program_error::ProgramError,
-    program_pack::{IsInitialized, Pack},
+    program_pack::Pack,
     pubkey::Pubkey,
     rent::Rent,
-    system_instruction,
-    sysvar::Sysvar,
+    system_instruction, system_program,
+    sysvar::{self, instructions, Sysvar},
 };
+use std::cell::RefMut;
 use spl_token::instruction as token_instruction;
+use spl_token_2022::{extension::transfer_fee::instruction as transfer_fee_instruction, instruction as token_2022_instruction};
 
 // Processor struct for handling instructions
 pub struct Processor {}
 
+// The token program of one side of a market and, when passed, its mint; Token-2022 sides
+// must pass the mint so that their transfers are checked
+struct TokenSide<'a, 'b> {
+    token_program: &'b AccountInfo<'a>,
+    mint: Option<&'b AccountInfo<'a>>,
+    decimals: u8,
+}
+
+// A market's escrow vaults and the market signer PDA that owns them
+struct Escrow<'a, 'b> {
+    market: &'b Pubkey,
+    signer_bump: u8,
+    base_vault: &'b AccountInfo<'a>,
+    quote_vault: &'b AccountInfo<'a>,
+    market_signer: &'b AccountInfo<'a>,
+    base: TokenSide<'a, 'b>,
+    quote: TokenSide<'a, 'b>,
+}
+
+impl<'a, 'b> Escrow<'a, 'b> {
+    // Vault holding the funds locked by orders on one side: quote for buys, base for sells
+    fn vault(&self, is_buy: bool) -> &'b AccountInfo<'a> {
+        if is_buy {
+            self.quote_vault
+        } else {
+            self.base_vault
+        }
+    }
+
+    // Token program and mint of the vault locking the funds of one side
+    fn side(&self, is_buy: bool) -> &TokenSide<'a, 'b> {
+        if is_buy {
+            &self.quote
+        } else {
+            &self.base
+        }
+    }
+}
+
 impl Processor {
     // Process instruction entrypoint
     pub fn process(
@@ -40,15 +104,24 @@ impl Processor {
             DexInstruction::InitializeMarket {
                 min_base_order_size,
                 tick_size,
-                fee_rate_bps,
+                taker_fee_bps,
+                maker_fee_bps,
+                trustless,
+                base_lot_size,
+                quote_lot_size,
             } => {
                 msg!("Instruction: Initialize Market");
                 Self::process_initialize_market(
                     program_id,
                     accounts,
+                    None,
                     min_base_order_size,
                     tick_size,
-                    fee_rate_bps,
+                    taker_fee_bps,
+                    maker_fee_bps,
+                    trustless,
+                    base_lot_size,
+                    quote_lot_size,
                 )
             }
             DexInstruction::PlaceLimitOrder {
@@ -56,6 +129,7 @@ impl Processor {
                 limit_price,
                 quantity,
                 self_trade_behavior,
+                memo,
             } => {
                 msg!("Instruction: Place Limit Order");
                 Self::process_place_limit_order(
@@ -64,19 +138,458 @@ impl Processor {
                     is_buy,
                     limit_price,
                     quantity,
+                    0,
                     self_trade_behavior,
+                    &memo,
                 )
             }
             DexInstruction::CancelOrder => {
                 msg!("Instruction: Cancel Order");
                 Self::process_cancel_order(program_id, accounts)
             }
-            DexInstruction::SettleFunds {
+            DexInstruction::SettleFunds => {
+                msg!("Instruction: Settle Funds");
+                Self::process_settle_funds(program_id, accounts)
+            }
+            DexInstruction::SetInstructionGuard { guard } => {
+                msg!("Instruction: Set Instruction Guard");
+                Self::process_set_instruction_guard(program_id, accounts, guard)
+            }
+            DexInstruction::InitializeProgramConfig => {
+                msg!("Instruction: Initialize Program Config");
+                Self::process_initialize_program_config(program_id, accounts)
+            }
+            DexInstruction::UpdateProgramConfig {
+                market_creation_mode,
+                allowed_quote_mints,
+            } => {
+                msg!("Instruction: Update Program Config");
+                Self::process_update_program_config(
+                    program_id,
+                    accounts,
+                    market_creation_mode,
+                    &allowed_quote_mints,
+                )
+            }
+            DexInstruction::CancelOrdersByIds { order_ids } => {
+                msg!("Instruction: Cancel Orders By Ids");
+                Self::process_cancel_orders_by_ids(program_id, accounts, &order_ids)
+            }
+            DexInstruction::ReduceOrder {
+                new_remaining_quantity,
+            } => {
+                msg!("Instruction: Reduce Order");
+                Self::process_reduce_order(program_id, accounts, new_remaining_quantity)
+            }
+            DexInstruction::SetMarketCreationFee {
+                treasury,
+                fee_lamports,
+                fee_quote,
+            } => {
+                msg!("Instruction: Set Market Creation Fee");
+                Self::process_set_market_creation_fee(
+                    program_id,
+                    accounts,
+                    treasury,
+                    fee_lamports,
+                    fee_quote,
+                )
+            }
+            DexInstruction::LinkOrders => {
+                msg!("Instruction: Link Orders");
+                Self::process_link_orders(program_id, accounts)
+            }
+            DexInstruction::CommitOpenOrdersRoot => {
+                msg!("Instruction: Commit Open Orders Root");
+                Self::process_commit_open_orders_root(program_id, accounts)
+            }
+            DexInstruction::PlaceIcebergOrder {
+                is_buy,
+                limit_price,
+                quantity,
+                display_quantity,
+                self_trade_behavior,
+                memo,
+            } => {
+                msg!("Instruction: Place Iceberg Order");
+                Self::process_place_limit_order(
+                    program_id,
+                    accounts,
+                    is_buy,
+                    limit_price,
+                    quantity,
+                    display_quantity,
+                    self_trade_behavior,
+                    &memo,
+                )
+            }
+            DexInstruction::SetReferralShare { referral_share_bps } => {
+                msg!("Instruction: Set Referral Share");
+                Self::process_set_referral_share(program_id, accounts, referral_share_bps)
+            }
+            DexInstruction::SetTradeBusting {
+                enabled,
+                window_slots,
+            } => {
+                msg!("Instruction: Set Trade Busting");
+                Self::process_set_trade_busting(program_id, accounts, enabled, window_slots)
+            }
+            DexInstruction::BustTrade => {
+                msg!("Instruction: Bust Trade");
+                Self::process_bust_trade(program_id, accounts)
+            }
+            DexInstruction::InitializeFeeVault => {
+                msg!("Instruction: Initialize Fee Vault");
+                Self::process_initialize_fee_vault(program_id, accounts)
+            }
+            DexInstruction::SweepFees => {
+                msg!("Instruction: Sweep Fees");
+                Self::process_sweep_fees(program_id, accounts)
+            }
+            DexInstruction::SetFeeAuthority { fee_authority } => {
+                msg!("Instruction: Set Fee Authority");
+                Self::process_set_fee_authority(program_id, accounts, fee_authority)
+            }
+            DexInstruction::SetMarketStatus { status } => {
+                msg!("Instruction: Set Market Status");
+                Self::process_set_market_status(program_id, accounts, status)
+            }
+            DexInstruction::CloseMarket => {
+                msg!("Instruction: Close Market");
+                Self::process_close_market(program_id, accounts)
+            }
+            DexInstruction::AdminCancelOrder => {
+                msg!("Instruction: Admin Cancel Order");
+                Self::process_admin_cancel_order(program_id, accounts)
+            }
+            DexInstruction::SetWhitelistEnabled { enabled } => {
+                msg!("Instruction: Set Whitelist Enabled");
+                Self::process_set_whitelist_enabled(program_id, accounts, enabled)
+            }
+            DexInstruction::AddWhitelistEntry { owner } => {
+                msg!("Instruction: Add Whitelist Entry");
+                Self::process_add_whitelist_entry(program_id, accounts, owner)
+            }
+            DexInstruction::RemoveWhitelistEntry => {
+                msg!("Instruction: Remove Whitelist Entry");
+                Self::process_remove_whitelist_entry(program_id, accounts)
+            }
+            DexInstruction::ExecuteFirmQuote { quote, base_amount } => {
+                msg!("Instruction: Execute Firm Quote");
+                Self::process_execute_firm_quote(program_id, accounts, &quote, base_amount)
+            }
+            DexInstruction::InitializeMarketWithSeed {
+                seed,
+                min_base_order_size,
+                tick_size,
+                taker_fee_bps,
+                maker_fee_bps,
+                trustless,
+                base_lot_size,
+                quote_lot_size,
+            } => {
+                msg!("Instruction: Initialize Market With Seed");
+                Self::process_initialize_market(
+                    program_id,
+                    accounts,
+                    Some(seed),
+                    min_base_order_size,
+                    tick_size,
+                    taker_fee_bps,
+                    maker_fee_bps,
+                    trustless,
+                    base_lot_size,
+                    quote_lot_size,
+                )
+            }
+            DexInstruction::SetOracle {
+                oracle,
+                band_bps,
+                max_staleness_slots,
+                price_expo,
+            } => {
+                msg!("Instruction: Set Oracle");
+                Self::process_set_oracle(program_id, accounts, oracle, band_bps, max_staleness_slots, price_expo)
+            }
+            DexInstruction::CheckOracle => {
+                msg!("Instruction: Check Oracle");
+                Self::process_check_oracle(program_id, accounts)
+            }
+            DexInstruction::InitializeInsuranceFund => {
+                msg!("Instruction: Initialize Insurance Fund");
+                Self::process_initialize_insurance_fund(program_id, accounts)
+            }
+            DexInstruction::DepositInsuranceFund { amount } => {
+                msg!("Instruction: Deposit Insurance Fund");
+                Self::process_deposit_insurance_fund(program_id, accounts, amount)
+            }
+            DexInstruction::WithdrawInsuranceFund { amount } => {
+                msg!("Instruction: Withdraw Insurance Fund");
+                Self::process_withdraw_insurance_fund(program_id, accounts, amount)
+            }
+            DexInstruction::InitializeMarketVaults => {
+                msg!("Instruction: Initialize Market Vaults");
+                Self::process_initialize_market_vaults(program_id, accounts)
+            }
+            DexInstruction::CloseOrderAccount => {
+                msg!("Instruction: Close Order Account");
+                Self::process_close_order_account(program_id, accounts)
+            }
+            DexInstruction::SetMinQuoteNotional { min_quote_notional } => {
+                msg!("Instruction: Set Min Quote Notional");
+                Self::process_set_min_quote_notional(program_id, accounts, min_quote_notional)
+            }
+            DexInstruction::Delegate { delegate, expiry_slot } => {
+                msg!("Instruction: Delegate");
+                Self::process_delegate(program_id, accounts, delegate, expiry_slot)
+            }
+            DexInstruction::Deposit { is_quote, amount } => {
+                msg!("Instruction: Deposit");
+                Self::process_deposit(program_id, accounts, is_quote, amount)
+            }
+            DexInstruction::Withdraw { is_quote, amount } => {
+                msg!("Instruction: Withdraw");
+                Self::process_withdraw(program_id, accounts, is_quote, amount)
+            }
+            DexInstruction::InitializeTwapOracle { ema_period_seconds } => {
+                msg!("Instruction: Initialize TWAP Oracle");
+                Self::process_initialize_twap_oracle(program_id, accounts, ema_period_seconds)
+            }
+            DexInstruction::MigrateAccount => {
+                msg!("Instruction: Migrate Account");
+                Self::process_migrate_account(program_id, accounts)
+            }
+            DexInstruction::InitializeEventQueue => {
+                msg!("Instruction: Initialize Event Queue");
+                Self::process_initialize_event_queue(program_id, accounts)
+            }
+            DexInstruction::ConsumeEvents {
+                seq_num,
+                limit,
+                observed_slot,
+                max_staleness_slots,
+            } => {
+                msg!("Instruction: Consume Events");
+                Self::process_consume_events(program_id, accounts, seq_num, limit, observed_slot, max_staleness_slots)
+            }
+            DexInstruction::RescueTokens { is_quote } => {
+                msg!("Instruction: Rescue Tokens");
+                Self::process_rescue_tokens(program_id, accounts, is_quote)
+            }
+            DexInstruction::InitializePool { fee_bps } => {
+                msg!("Instruction: Initialize Pool");
+                Self::process_initialize_pool(program_id, accounts, fee_bps)
+            }
+            DexInstruction::AddLiquidity {
+                max_base_amount,
+                max_quote_amount,
+                min_lp_amount,
+            } => {
+                msg!("Instruction: Add Liquidity");
+                Self::process_add_liquidity(program_id, accounts, max_base_amount, max_quote_amount, min_lp_amount)
+            }
+            DexInstruction::RemoveLiquidity {
+                lp_amount,
+                min_base_amount,
+                min_quote_amount,
+            } => {
+                msg!("Instruction: Remove Liquidity");
+                Self::process_remove_liquidity(program_id, accounts, lp_amount, min_base_amount, min_quote_amount)
+            }
+            DexInstruction::SwapAgainstPool {
+                is_buy,
+                amount_in,
+                min_amount_out,
+            } => {
+                msg!("Instruction: Swap Against Pool");
+                Self::process_swap_against_pool(program_id, accounts, is_buy, amount_in, min_amount_out)
+            }
+            DexInstruction::RouteSwap {
+                is_buy,
+                amount_in,
+                min_amount_out,
+                num_orders,
+            } => {
+                msg!("Instruction: Route Swap");
+                Self::process_route_swap(program_id, accounts, is_buy, amount_in, min_amount_out, num_orders)
+            }
+            DexInstruction::InitializeRangePool {
+                fee_bps,
+                tick_spacing,
+                tick,
+            } => {
+                msg!("Instruction: Initialize Range Pool");
+                Self::process_initialize_range_pool(program_id, accounts, fee_bps, tick_spacing, tick)
+            }
+            DexInstruction::AddRangeLiquidity {
+                tick_lower,
+                tick_upper,
+                liquidity,
+                max_base_amount,
+                max_quote_amount,
+            } => {
+                msg!("Instruction: Add Range Liquidity");
+                Self::process_add_range_liquidity(
+                    program_id,
+                    accounts,
+                    tick_lower,
+                    tick_upper,
+                    liquidity,
+                    max_base_amount,
+                    max_quote_amount,
+                )
+            }
+            DexInstruction::RemoveRangeLiquidity {
+                liquidity,
+                min_base_amount,
+                min_quote_amount,
+            } => {
+                msg!("Instruction: Remove Range Liquidity");
+                Self::process_remove_range_liquidity(program_id, accounts, liquidity, min_base_amount, min_quote_amount)
+            }
+            DexInstruction::SwapAgainstRangePool {
+                is_buy,
+                amount_in,
+                min_amount_out,
+            } => {
+                msg!("Instruction: Swap Against Range Pool");
+                Self::process_swap_against_range_pool(program_id, accounts, is_buy, amount_in, min_amount_out)
+            }
+            DexInstruction::InitializeLendingPool { rate_curve } => {
+                msg!("Instruction: Initialize Lending Pool");
+                Self::process_initialize_lending_pool(program_id, accounts, rate_curve)
+            }
+            DexInstruction::Supply { amount, min_shares } => {
+                msg!("Instruction: Supply");
+                Self::process_supply(program_id, accounts, amount, min_shares)
+            }
+            DexInstruction::Redeem { shares, min_amount } => {
+                msg!("Instruction: Redeem");
+                Self::process_redeem(program_id, accounts, shares, min_amount)
+            }
+            DexInstruction::InitializePerpMarket {
+                initial_margin_bps,
+                max_funding_rate_bps,
+            } => {
+                msg!("Instruction: Initialize Perp Market");
+                Self::process_initialize_perp_market(program_id, accounts, initial_margin_bps, max_funding_rate_bps)
+            }
+            DexInstruction::DepositPerpCollateral { amount } => {
+                msg!("Instruction: Deposit Perp Collateral");
+                Self::process_deposit_perp_collateral(program_id, accounts, amount)
+            }
+            DexInstruction::WithdrawPerpCollateral { amount } => {
+                msg!("Instruction: Withdraw Perp Collateral");
+                Self::process_withdraw_perp_collateral(program_id, accounts, amount)
+            }
+            DexInstruction::SettlePerpFill => {
+                msg!("Instruction: Settle Perp Fill");
+                Self::process_settle_perp_fill(program_id, accounts)
+            }
+            DexInstruction::UpdateFunding => {
+                msg!("Instruction: Update Funding");
+                Self::process_update_funding(program_id, accounts)
+            }
+            DexInstruction::SetPerpReduceOnly { reduce_only } => {
+                msg!("Instruction: Set Perp Reduce Only");
+                Self::process_set_perp_reduce_only(program_id, accounts, reduce_only)
+            }
+            DexInstruction::InitializeBatchAuction { interval_slots } => {
+                msg!("Instruction: Initialize Batch Auction");
+                Self::process_initialize_batch_auction(program_id, accounts, interval_slots)
+            }
+            DexInstruction::RunAuction { num_orders } => {
+                msg!("Instruction: Run Auction");
+                Self::process_run_auction(program_id, accounts, num_orders)
+            }
+            DexInstruction::InitializeTokenSale {
                 base_amount,
-                quote_amount,
+                min_price,
+                max_contribution,
+                duration_seconds,
             } => {
-                msg!("Instruction: Settle Funds");
-                Self::process_settle_funds(program_id, accounts, base_amount, quote_amount)
+                msg!("Instruction: Initialize Token Sale");
+                Self::process_initialize_token_sale(
+                    program_id,
+                    accounts,
+                    base_amount,
+                    min_price,
+                    max_contribution,
+                    duration_seconds,
+                )
+            }
+            DexInstruction::ContributeToSale { amount } => {
+                msg!("Instruction: Contribute To Sale");
+                Self::process_contribute_to_sale(program_id, accounts, amount)
+            }
+            DexInstruction::FinalizeSale => {
+                msg!("Instruction: Finalize Sale");
+                Self::process_finalize_sale(program_id, accounts)
+            }
+            DexInstruction::ClaimSale => {
+                msg!("Instruction: Claim Sale");
+                Self::process_claim_sale(program_id, accounts)
+            }
+            DexInstruction::InitializeStakePool {
+                epoch_seconds,
+                fee_share_bps,
+                fee_tiers,
+            } => {
+                msg!("Instruction: Initialize Stake Pool");
+                Self::process_initialize_stake_pool(program_id, accounts, epoch_seconds, fee_share_bps, fee_tiers)
+            }
+            DexInstruction::Stake { amount } => {
+                msg!("Instruction: Stake");
+                Self::process_stake(program_id, accounts, amount)
+            }
+            DexInstruction::Unstake { amount } => {
+                msg!("Instruction: Unstake");
+                Self::process_unstake(program_id, accounts, amount)
+            }
+            DexInstruction::DistributeStakingRewards => {
+                msg!("Instruction: Distribute Staking Rewards");
+                Self::process_distribute_staking_rewards(program_id, accounts)
+            }
+            DexInstruction::ClaimStakingRewards => {
+                msg!("Instruction: Claim Staking Rewards");
+                Self::process_claim_staking_rewards(program_id, accounts)
+            }
+            DexInstruction::SetLoyaltySchedule { num_phases, phases } => {
+                msg!("Instruction: Set Loyalty Schedule");
+                Self::process_set_loyalty_schedule(program_id, accounts, num_phases, phases)
+            }
+            DexInstruction::OpenLoyaltyPoints => {
+                msg!("Instruction: Open Loyalty Points");
+                Self::process_open_loyalty_points(program_id, accounts)
+            }
+            DexInstruction::SetFeeSplit { treasury_share_bps, creator_share_bps } => {
+                msg!("Instruction: Set Fee Split");
+                Self::process_set_fee_split(program_id, accounts, treasury_share_bps, creator_share_bps)
+            }
+            DexInstruction::SetDynamicFees {
+                min_fee_bps,
+                volatility_bps,
+                volatility_window,
+            } => {
+                msg!("Instruction: Set Dynamic Fees");
+                Self::process_set_dynamic_fees(program_id, accounts, min_fee_bps, volatility_bps, volatility_window)
+            }
+            DexInstruction::PlacePerpOrder {
+                is_buy,
+                limit_price,
+                quantity,
+                reduce_only,
+            } => {
+                msg!("Instruction: Place Perp Order");
+                Self::process_place_perp_order(program_id, accounts, is_buy, limit_price, quantity, reduce_only)
+            }
+            DexInstruction::CancelPerpOrder => {
+                msg!("Instruction: Cancel Perp Order");
+                Self::process_cancel_perp_order(program_id, accounts)
+            }
+            DexInstruction::PublishPrices { nonce, finalized } => {
+                msg!("Instruction: Publish Prices");
+                Self::process_publish_prices(program_id, accounts, nonce, finalized)
             }
         }
     }
@@ -85,9 +598,14 @@ impl Processor {
     fn process_initialize_market(
         program_id: &Pubkey,
         accounts: &[AccountInfo],
+        market_seed: Option<u64>,
         min_base_order_size: u64,
         tick_size: u64,
-        fee_rate_bps: u16,
+        taker_fee_bps: u16,
+        maker_fee_bps: i16,
+        trustless: bool,
+        base_lot_size: u64,
+        quote_lot_size: u64,
     ) -> ProgramResult {
         // Get accounts
         let account_info_iter = &mut accounts.iter();
@@ -95,8 +613,13 @@ impl Processor {
         let market_account = next_account_info(account_info_iter)?;
         let base_mint = next_account_info(account_info_iter)?;
         let quote_mint = next_account_info(account_info_iter)?;
-        let rent_account = next_account_info(account_info_iter)?;
-        let system_program_account = next_account_info(account_info_iter)?;
+        let mut system_program_account = next_account_info(account_info_iter)?;
+        if *system_program_account.key == sysvar::rent::id() {
+            // Skip the Rent sysvar passed by older clients
+            system_program_account = next_account_info(account_info_iter)?;
+        }
+        let config_account = next_account_info(account_info_iter)?;
+        let registry_account = next_account_info(account_info_iter)?;
 
         // Verify accounts
         if !market_authority.is_signer {
@@ -106,30 +629,145 @@ impl Processor {
             ));
         }
 
+        Self::check_system_program(system_program_account)?;
+
+        let (registry_address, registry_bump) = MarketRegistry::find_address(program_id);
+        if registry_address != *registry_account.key {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Invalid market registry address",
+            ));
+        }
+
+        // Validate fees; maker rebates are paid out of the taker fee
+        if taker_fee_bps > 10000
+            || maker_fee_bps > 10000
+            || (maker_fee_bps as i32) < -(taker_fee_bps as i32)
+        {
+            msg!("Taker fee is {} bps, maker fee {} bps", taker_fee_bps, maker_fee_bps);
+            return Err(return_dex_error(
+                DexError::InvalidInstructionData,
+                "Fees must not exceed 10000 bps and maker rebates must not exceed the taker fee",
+            ));
+        }
+
+        // Quantities and prices are counted in lots, which must hold at least one token atom
+        if base_lot_size == 0 || quote_lot_size == 0 {
+            msg!("Base lot size is {}, quote lot size {}", base_lot_size, quote_lot_size);
+            return Err(return_dex_error(
+                DexError::InvalidLotSize,
+                "Lot sizes must be positive",
+            ));
+        }
+
+        // Both mints must be distinct SPL mints; trustless markets refuse freezable mints
+        if base_mint.key == quote_mint.key {
+            return Err(return_dex_error(
+                DexError::InvalidMint,
+                "Base and quote mints must differ",
+            ));
+        }
+        let base_decimals = Self::load_market_mint(base_mint, trustless)?;
+        let quote_decimals = Self::load_market_mint(quote_mint, trustless)?;
+
+        // Market PDAs are derived from the mint pair and created here, exactly once
+        let market_pda = match market_seed {
+            Some(seed) => {
+                let (market_address, bump) = Market::find_address(program_id, base_mint.key, quote_mint.key, seed);
+                if market_address != *market_account.key {
+                    return Err(return_dex_error_expected(
+                        DexError::InvalidAccountData,
+                        "Invalid market address",
+                        market_address,
+                        market_account.key,
+                    ));
+                }
+                if market_account.owner == program_id {
+                    return Err(return_dex_error(
+                        DexError::InvalidAccountData,
+                        "Market already exists",
+                    ));
+                }
+                Some((seed, bump))
+            }
+            None => None,
+        };
+
+        // Never overwrite a live market, which would reset its order ids and counters, nor
+        // adopt one that the runtime could reclaim for unpaid rent
+        if market_account.owner == program_id {
+            Self::check_uninitialized(market_account, Market::LEN)?;
+            Self::check_rent_exempt(market_account)?;
+        }
+
+        // Enforce the program config when one has been initialized
+        if let Some(config) = Self::load_program_config(program_id, config_account)? {
+            if config.market_creation_mode == MarketCreationMode::AdminOnly
+                && config.admin != *market_authority.key
+            {
+                return Err(return_dex_error(
+                    DexError::AccountNotAuthorized,
+                    "Market creation restricted to config admin",
+                ));
+            }
+
+            if !config.is_quote_mint_allowed(quote_mint.key) {
+                return Err(return_dex_error(
+                    DexError::QuoteMintNotAllowed,
+                    "Quote mint not whitelisted",
+                ));
+            }
+
+            // Pay the market creation fee to the protocol treasury
+            Self::pay_market_creation_fee(
+                &config,
+                market_authority,
+                quote_mint,
+                quote_decimals,
+                system_program_account,
+                account_info_iter,
+            )?;
+        }
+
         // Verify program ownership
         if market_account.owner != program_id {
             // Create market account if it doesn't exist
-            let rent = Rent::from_account_info(rent_account)?;
+            let rent = Rent::get()?;
             let space = Market::LEN;
             let lamports = rent.minimum_balance(space);
 
             // Create account
-            invoke(
-                &system_instruction::create_account(
-                    market_authority.key,
-                    market_account.key,
-                    lamports,
-                    space as u64,
-                    program_id,
-                ),
-                &[
-                    market_authority.clone(),
-                    market_account.clone(),
-                    system_program_account.clone(),
-                ],
-            )?;
+            let create_market_ix = system_instruction::create_account(
+                market_authority.key,
+                market_account.key,
+                lamports,
+                space as u64,
+                program_id,
+            );
+            let create_market_accounts = [
+                market_authority.clone(),
+                market_account.clone(),
+                system_program_account.clone(),
+            ];
+            match market_pda {
+                Some((seed, bump)) => invoke_signed(
+                    &create_market_ix,
+                    &create_market_accounts,
+                    &[&[
+                        MARKET_SEED,
+                        base_mint.key.as_ref(),
+                        quote_mint.key.as_ref(),
+                        &seed.to_le_bytes(),
+                        &[bump],
+                    ]],
+                )?,
+                None => invoke(&create_market_ix, &create_market_accounts)?,
+            }
         }
 
+        // Escrow vaults are owned by the market signer PDA, whose bump is stored for refunds
+        let (_, signer_bump) = Market::find_signer_address(program_id, market_account.key);
+
         // Initialize market state
         let market = Market {
             is_initialized: true,
@@ -138,27 +776,158 @@ impl Processor {
             quote_mint: *quote_mint.key,
             min_base_order_size,
             tick_size,
-            fee_rate_bps,
+            taker_fee_bps,
+            maker_fee_bps,
             next_order_id: 1,
             num_bids: 0,
             num_asks: 0,
+            instruction_guard: InstructionGuard::Disabled,
+            open_orders_root: merkle::EMPTY_ROOT,
+            open_orders_root_leaves: 0,
+            open_orders_root_slot: 0,
+            referral_share_bps: 0,
+            fee_authority: *market_authority.key,
+            status: MarketStatus::Active,
+            bid_base_total: 0,
+            ask_base_total: 0,
+            bid_notional: 0,
+            ask_notional: 0,
+            whitelist_enabled: false,
+            oracle: Pubkey::default(),
+            oracle_band_bps: 0,
+            oracle_max_staleness_slots: 0,
+            oracle_price_expo: 0,
+            fill_seq_num: 0,
+            base_decimals,
+            quote_decimals,
+            trustless,
+            signer_bump,
+            base_lot_size,
+            quote_lot_size,
+            min_quote_notional: 0,
+            base_free_total: 0,
+            quote_free_total: 0,
+            best_bid: 0,
+            best_ask: 0,
+            last_price: 0,
+            last_trade_slot: 0,
+            base_volume: 0,
+            quote_volume: 0,
+            volume_buckets: Default::default(),
+            volume_bucket_hour: 0,
+            twap_oracle_enabled: false,
+            base_token_program: *base_mint.owner,
+            quote_token_program: *quote_mint.owner,
+            version: ACCOUNT_VERSION,
+            flags: 0,
+            fees_accrued: 0,
+            fees_swept: 0,
+            referral_fees_paid: 0,
+            treasury_fee_account: Pubkey::default(),
+            treasury_fee_share_bps: 0,
+            creator_fee_account: Pubkey::default(),
+            creator_fee_share_bps: 0,
+            dynamic_fee_min_bps: 0,
+            dynamic_fee_volatility_bps: 0,
+            volatility_window: 0,
+            realized_variance: 0,
+            reserved: [0; MARKET_RESERVED_LEN],
         };
 
         // Save market state
         market.pack_into_slice(&mut market_account.data.borrow_mut());
 
+        // Record the market in the registry
+        Self::register_market(
+            program_id,
+            &market,
+            market_account,
+            market_authority,
+            registry_account,
+            registry_bump,
+            system_program_account,
+        )?;
+
         msg!("Market initialized successfully");
         Ok(())
     }
 
-    // Process place limit order instruction
+    // Append a new market to the market registry, creating the registry with the first market
+    fn register_market<'a>(
+        program_id: &Pubkey,
+        market: &Market,
+        market_account: &AccountInfo<'a>,
+        market_authority: &AccountInfo<'a>,
+        registry_account: &AccountInfo<'a>,
+        registry_bump: u8,
+        system_program_account: &AccountInfo<'a>,
+    ) -> ProgramResult {
+        let rent = Rent::get()?;
+
+        let mut registry = if registry_account.owner == program_id {
+            MarketRegistry::unpack_from_slice(&registry_account.data.borrow()[..MarketRegistry::LEN])?
+        } else {
+            invoke_signed(
+                &system_instruction::create_account(
+                    market_authority.key,
+                    registry_account.key,
+                    rent.minimum_balance(MarketRegistry::LEN),
+                    MarketRegistry::LEN as u64,
+                    program_id,
+                ),
+                &[
+                    market_authority.clone(),
+                    registry_account.clone(),
+                    system_program_account.clone(),
+                ],
+                &[&[MARKET_REGISTRY_SEED, &[registry_bump]]],
+            )?;
+            MarketRegistry {
+                is_initialized: true,
+                num_markets: 0,
+            }
+        };
+
+        // Grow the registry by one entry, topping up its rent from the market authority
+        let entry_offset = MarketRegistry::account_len(registry.num_markets);
+        let new_len = MarketRegistry::account_len(registry.num_markets + 1);
+        let rent_due = rent.minimum_balance(new_len).saturating_sub(registry_account.lamports());
+        if rent_due > 0 {
+            invoke(
+                &system_instruction::transfer(market_authority.key, registry_account.key, rent_due),
+                &[
+                    market_authority.clone(),
+                    registry_account.clone(),
+                    system_program_account.clone(),
+                ],
+            )?;
+        }
+        registry_account.realloc(new_len, false)?;
+
+        let entry = MarketRegistryEntry {
+            market: *market_account.key,
+            base_mint: market.base_mint,
+            quote_mint: market.quote_mint,
+            created_slot: Clock::get()?.slot,
+        };
+        let mut registry_data = registry_account.data.borrow_mut();
+        entry.pack_into_slice(&mut registry_data[entry_offset..new_len]);
+        registry.num_markets += 1;
+        registry.pack_into_slice(&mut registry_data[..MarketRegistry::LEN]);
+
+        Ok(())
+    }
+
+    // Process place limit order instruction; a non-zero display quantity places an iceberg order
     fn process_place_limit_order(
         program_id: &Pubkey,
         accounts: &[AccountInfo],
         is_buy: bool,
         limit_price: u64,
         quantity: u64,
+        display_quantity: u64,
         _self_trade_behavior: crate::instruction::SelfTradeBehavior,
+        memo: &str,
     ) -> ProgramResult {
         // Get accounts
         let account_info_iter = &mut accounts.iter();
@@ -166,8 +935,22 @@ impl Processor {
         let market_account = next_account_info(account_info_iter)?;
         let order_account = next_account_info(account_info_iter)?;
         let owner_token_account = next_account_info(account_info_iter)?;
+        let escrow_vault = next_account_info(account_info_iter)?;
         let token_program = next_account_info(account_info_iter)?;
         let system_program_account = next_account_info(account_info_iter)?;
+        let instructions_sysvar = next_account_info(account_info_iter).ok();
+        let referrer_token_account = next_account_info(account_info_iter)
+            .ok()
+            .filter(|account| *account.key != system_program::id());
+        let whitelist_entry = next_account_info(account_info_iter)
+            .ok()
+            .filter(|account| *account.key != system_program::id());
+        let oracle_account = next_account_info(account_info_iter).ok();
+        let memo_program = next_account_info(account_info_iter).ok();
+        let delegation = next_account_info(account_info_iter)
+            .ok()
+            .filter(|account| *account.key != system_program::id());
+        let market_signer = next_account_info(account_info_iter).ok();
 
         // Verify accounts
         if !owner.is_signer {
@@ -177,34 +960,140 @@ impl Processor {
             ));
         }
 
+        Self::check_token_program(token_program)?;
+        Self::check_system_program(system_program_account)?;
+
         // Load market
-        let mut market = Market::unpack_from_slice(&market_account.data.borrow())?;
-        if !market.is_initialized {
-            return Err(return_dex_error(
-                DexError::InvalidAccountData,
-                "Market not initialized",
-            ));
+        let mut market = Self::load_market(program_id, market_account)?;
+
+        // A delegate signs for the owner of its delegation, whose funds are moved by the market
+        // signer
+        let (order_owner, delegate_authority) = match delegation {
+            Some(delegation) => {
+                let order_owner = Self::check_delegation(program_id, market_account.key, owner.key, delegation)?;
+                let market_signer = market_signer.ok_or(ProgramError::NotEnoughAccountKeys)?;
+                if market.signer_address(program_id, market_account.key)? != *market_signer.key {
+                    return Err(return_dex_error(
+                        DexError::InvalidAccountData,
+                        "Invalid market signer",
+                    ));
+                }
+                (order_owner, Some(market_signer))
+            }
+            None => (*owner.key, None),
+        };
+
+        // Enforce the market's instruction guard
+        Self::check_instruction_guard(program_id, market.instruction_guard, instructions_sysvar)?;
+
+        // New orders are only accepted while the market is active
+        Self::check_market_active(&market, "Market does not accept new orders")?;
+
+        // Permissioned markets only accept orders from whitelisted owners
+        if market.whitelist_enabled {
+            Self::check_whitelist_entry(program_id, market_account.key, &order_owner, whitelist_entry)?;
+        }
+
+        // Markets with an oracle circuit breaker only accept orders priced near the oracle
+        if market.has_oracle() {
+            Self::check_oracle_band(&market, oracle_account, limit_price)?;
         }
 
+        // Every auction takes all resting orders in one transaction
+        Self::check_auction_capacity(&market, 1)?;
+
         // Validate order parameters
-        if quantity < market.min_base_order_size {
-            return Err(return_dex_error(
+        if quantity == 0 || quantity < market.min_base_order_size {
+            return Err(return_dex_error_expected(
                 DexError::InvalidOrderSize,
                 "Order size below minimum",
+                format_args!("at least {}", { market.min_base_order_size }.max(1)),
+                quantity,
             ));
         }
 
-        if limit_price % market.tick_size != 0 {
-            return Err(return_dex_error(
+        if limit_price.checked_rem(market.tick_size) != Some(0) {
+            return Err(return_dex_error_expected(
                 DexError::InvalidOrderPrice,
                 "Price not a multiple of tick size",
+                format_args!("a multiple of {}", { market.tick_size }),
+                limit_price,
             ));
         }
 
-        // Create order account if needed
-        if order_account.owner != program_id {
-            let rent = Rent::get()?;
-            let space = Order::LEN;
+        // Memos are stored zero-padded with the order, so they cannot contain NUL characters
+        if memo.len() > MAX_ORDER_MEMO_LEN || memo.contains('\0') {
+            return Err(return_dex_error(
+                DexError::InvalidInstructionData,
+                "Memo too long or contains NUL characters",
+            ));
+        }
+
+        // Small quantities at high prices still have to carry a minimum value
+        let notional = market.notional(limit_price, quantity)?;
+        if notional < market.min_quote_notional {
+            return Err(return_dex_error_expected(
+                DexError::InvalidOrderSize,
+                "Order notional below minimum",
+                format_args!("at least {}", { market.min_quote_notional }),
+                notional,
+            ));
+        }
+
+        if display_quantity != 0
+            && (display_quantity < market.min_base_order_size || display_quantity >= quantity)
+        {
+            return Err(return_dex_error(
+                DexError::InvalidOrderSize,
+                "Display quantity must be at least the minimum size and below the order size",
+            ));
+        }
+
+        // Referral fees are paid in quote tokens
+        let referrer = match referrer_token_account {
+            Some(referrer_token_account) => {
+                let referrer_account = token::unpack_account(&referrer_token_account.data.borrow())
+                    .map_err(|_| {
+                        return_dex_error(DexError::InvalidTokenAccount, "Referrer is not a token account")
+                    })?;
+                if referrer_account.mint != market.quote_mint {
+                    return Err(return_dex_error(
+                        DexError::InvalidTokenAccount,
+                        "Referrer token account must hold the quote mint",
+                    ));
+                }
+                *referrer_token_account.key
+            }
+            None => Pubkey::default(),
+        };
+
+        // Funds are only locked from the owner's OpenOrders free balance or its own token account
+        // of the locked mint
+        let mut open_orders = if owner_token_account.owner == program_id {
+            Some(Self::load_open_orders(program_id, market_account.key, &order_owner, owner_token_account)?)
+        } else {
+            Self::check_owner_token_account(&market, &order_owner, is_buy, owner_token_account)?;
+            None
+        };
+
+        // Funds are locked in the market's vault for the order's side
+        let (vault_address, _) = Market::find_escrow_vault_address(program_id, market_account.key, is_buy);
+        if vault_address != *escrow_vault.key {
+            return Err(return_dex_error_expected(
+                DexError::InvalidAccountData,
+                "Invalid escrow vault",
+                vault_address,
+                escrow_vault.key,
+            ));
+        }
+
+        // Create order account if needed, never overwriting a live order
+        if order_account.owner == program_id {
+            Self::check_uninitialized(order_account, Order::LEN)?;
+            Self::check_rent_exempt(order_account)?;
+        } else {
+            let rent = Rent::get()?;
+            let space = Order::LEN;
             let lamports = rent.minimum_balance(space);
 
             // Create account
@@ -229,16 +1118,29 @@ impl Processor {
         let timestamp = clock.unix_timestamp as u64;
 
         // Create order
+        let mut order_memo = [0; MAX_ORDER_MEMO_LEN];
+        order_memo[..memo.len()].copy_from_slice(memo.as_bytes());
         let order = Order {
             is_initialized: true,
             order_id: market.next_order_id,
-            owner: *owner.key,
+            owner: order_owner,
             market: *market_account.key,
             is_buy,
             limit_price,
             original_quantity: quantity,
             remaining_quantity: quantity,
             creation_timestamp: timestamp,
+            linked_order: Pubkey::default(),
+            display_quantity,
+            visible_quantity: if display_quantity == 0 {
+                quantity
+            } else {
+                display_quantity
+            },
+            referrer,
+            memo: order_memo,
+            version: ACCOUNT_VERSION,
+            reserved: [0; ORDER_RESERVED_LEN],
         };
 
         // Save order
@@ -251,195 +1153,339 @@ impl Processor {
         } else {
             market.num_asks += 1;
         }
-        market.pack_into_slice(&mut market_account.data.borrow_mut());
+        market.add_resting(is_buy, limit_price, quantity)?;
 
-        // Lock funds for the order
-        if is_buy {
-            // For buy orders, lock quote tokens (price * quantity)
-            let amount = limit_price
-                .checked_mul(quantity)
-                .ok_or(ProgramError::ArithmeticOverflow)?;
+        // Lock funds for the order: quote tokens for the notional plus the taker fee on buys,
+        // base tokens for the quantity on sells
+        let locked_amount = market.escrow_amount(is_buy, limit_price, quantity)?;
 
-            // Transfer tokens to program account
-            invoke(
-                &token_instruction::transfer(
-                    token_program.key,
-                    owner_token_account.key,
-                    order_account.key,
-                    owner.key,
-                    &[],
-                    amount,
+        // A free balance already sits in the escrow vault, so funding from it moves no tokens
+        if let Some(open_orders) = &mut open_orders {
+            Self::debit_free_balance(&mut market, open_orders, is_buy, locked_amount)?;
+            open_orders.pack_into_slice(&mut owner_token_account.data.borrow_mut());
+        }
+        market.pack_into_slice(&mut market_account.data.borrow_mut());
+
+        // Transfer tokens to the escrow vault, as the owner's token delegate for a delegated order
+        if open_orders.is_none() {
+            let token_side = Self::load_token_side(&market, accounts, is_buy)?;
+            match delegate_authority {
+                Some(market_signer) => Self::transfer_tokens_in(
+                    &token_side,
+                    owner_token_account,
+                    escrow_vault,
+                    market_signer,
+                    locked_amount,
+                    &[&[market_account.key.as_ref(), &[market.signer_bump]]],
                 )?,
-                &[
-                    owner_token_account.clone(),
-                    order_account.clone(),
-                    owner.clone(),
-                    token_program.clone(),
-                ],
-            )?;
-        } else {
-            // For sell orders, lock base tokens (quantity)
-            // Transfer tokens to program account
+                None => Self::transfer_tokens_in(&token_side, owner_token_account, escrow_vault, owner, locked_amount, &[])?,
+            };
+        }
+
+        // Forward the memo to the SPL Memo program, signed by the signer
+        if !memo.is_empty() {
+            let memo_program = memo_program.ok_or(ProgramError::NotEnoughAccountKeys)?;
+            Self::check_memo_program(memo_program)?;
             invoke(
-                &token_instruction::transfer(
-                    token_program.key,
-                    owner_token_account.key,
-                    order_account.key,
-                    owner.key,
-                    &[],
-                    quantity,
-                )?,
-                &[
-                    owner_token_account.clone(),
-                    order_account.clone(),
-                    owner.clone(),
-                    token_program.clone(),
-                ],
+                &spl_memo::build_memo(memo.as_bytes(), &[owner.key]),
+                &[owner.clone(), memo_program.clone()],
             )?;
         }
 
+        Self::emit_event(DexEvent::OrderPlaced(OrderPlaced {
+            market: *market_account.key,
+            order: *order_account.key,
+            owner: order_owner,
+            order_id: order.order_id,
+            is_buy,
+            limit_price,
+            quantity,
+            display_quantity,
+            locked_amount,
+            slot: clock.slot,
+        }))?;
+
+        // Return the order ID last, since every CPI clears the return data
+        let placed_order = PlacedOrder {
+            order_id: order.order_id,
+            fill_seq_num: market.fill_seq_num,
+        };
+        set_return_data(&placed_order.try_to_vec()?);
+
         msg!("Order placed successfully");
         Ok(())
     }
 
-    // Process cancel order instruction
-    fn process_cancel_order(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
-        // Get accounts
-        let account_info_iter = &mut accounts.iter();
-        let owner = next_account_info(account_info_iter)?;
-        let market_account = next_account_info(account_info_iter)?;
-        let order_account = next_account_info(account_info_iter)?;
-        let owner_token_account = next_account_info(account_info_iter)?;
-        let token_program = next_account_info(account_info_iter)?;
+    // Check that an owner has a whitelist entry on a market
+    fn check_whitelist_entry(
+        program_id: &Pubkey,
+        market: &Pubkey,
+        owner: &Pubkey,
+        whitelist_entry: Option<&AccountInfo>,
+    ) -> ProgramResult {
+        let whitelist_entry = whitelist_entry.ok_or_else(|| {
+            return_dex_error(DexError::OwnerNotWhitelisted, "Missing whitelist entry")
+        })?;
 
-        // Verify accounts
-        if !owner.is_signer {
+        let (entry_address, _) = WhitelistEntry::find_address(program_id, market, owner);
+        if entry_address != *whitelist_entry.key || whitelist_entry.owner != program_id {
             return Err(return_dex_error(
-                DexError::AccountNotAuthorized,
-                "Order owner must sign",
+                DexError::OwnerNotWhitelisted,
+                "Invalid whitelist entry",
             ));
         }
 
-        // Load order
-        let order = Order::unpack_from_slice(&order_account.data.borrow())?;
-        if !order.is_initialized {
+        let entry = WhitelistEntry::unpack_from_slice(&whitelist_entry.data.borrow())?;
+        if !entry.is_initialized || entry.market != *market || entry.owner != *owner {
             return Err(return_dex_error(
-                DexError::InvalidAccountData,
-                "Order not initialized",
+                DexError::OwnerNotWhitelisted,
+                "Invalid whitelist entry",
             ));
         }
 
-        // Verify owner
-        if order.owner != *owner.key {
+        Ok(())
+    }
+
+    // Load an owner's OpenOrders account on a market
+    fn load_open_orders(
+        program_id: &Pubkey,
+        market: &Pubkey,
+        owner: &Pubkey,
+        open_orders_account: &AccountInfo,
+    ) -> Result<OpenOrders, ProgramError> {
+        if open_orders_account.owner != program_id {
+            return Err(return_dex_error(
+                DexError::InvalidAccountOwner,
+                "OpenOrders account not owned by the program",
+            ));
+        }
+
+        let (open_orders_address, _) = OpenOrders::find_address(program_id, market, owner);
+        let open_orders = OpenOrders::unpack_from_slice(&open_orders_account.data.borrow())?;
+        if open_orders_address != *open_orders_account.key || !open_orders.is_initialized {
+            return Err(return_dex_error(
+                DexError::AccountMismatch,
+                "OpenOrders account does not belong to the owner on this market",
+            ));
+        }
+
+        Ok(open_orders)
+    }
+
+    // Add to an owner's free balance and the market's total of free balances
+    fn credit_free_balance(market: &mut Market, open_orders: &mut OpenOrders, is_quote: bool, amount: u64) -> ProgramResult {
+        let free = open_orders.free_mut(is_quote);
+        *free = free.checked_add(amount).ok_or(ProgramError::ArithmeticOverflow)?;
+        if is_quote {
+            market.quote_free_total = market
+                .quote_free_total
+                .checked_add(amount)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+        } else {
+            market.base_free_total = market
+                .base_free_total
+                .checked_add(amount)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+        }
+        Ok(())
+    }
+
+    // Take from an owner's free balance and the market's total of free balances
+    fn debit_free_balance(market: &mut Market, open_orders: &mut OpenOrders, is_quote: bool, amount: u64) -> ProgramResult {
+        let free = open_orders.free_mut(is_quote);
+        *free = free.checked_sub(amount).ok_or_else(|| {
+            return_dex_error_expected(
+                DexError::InsufficientFunds,
+                "Free balance too low",
+                format_args!("at least {}", amount),
+                *free,
+            )
+        })?;
+        if is_quote {
+            market.quote_free_total = market.quote_free_total.saturating_sub(amount);
+        } else {
+            market.base_free_total = market.base_free_total.saturating_sub(amount);
+        }
+        Ok(())
+    }
+
+    // Check that a delegation lets the signer trade on the market, returning the owner it trades for
+    fn check_delegation(
+        program_id: &Pubkey,
+        market: &Pubkey,
+        delegate: &Pubkey,
+        delegation_account: &AccountInfo,
+    ) -> Result<Pubkey, ProgramError> {
+        if delegation_account.owner != program_id {
             return Err(return_dex_error(
                 DexError::AccountNotAuthorized,
-                "Not order owner",
+                "Invalid delegation",
             ));
         }
 
-        // Load market
-        let mut market = Market::unpack_from_slice(&market_account.data.borrow())?;
-        if !market.is_initialized {
+        let delegation = Delegation::unpack_from_slice(&delegation_account.data.borrow())?;
+        let (delegation_address, _) = Delegation::find_address(program_id, market, &delegation.owner);
+        if !delegation.is_initialized
+            || delegation_address != *delegation_account.key
+            || delegation.market != *market
+            || delegation.delegate != *delegate
+        {
             return Err(return_dex_error(
-                DexError::InvalidAccountData,
-                "Market not initialized",
+                DexError::AccountNotAuthorized,
+                "Invalid delegation",
             ));
         }
 
-        // Verify market
-        if order.market != *market_account.key {
+        let slot = Clock::get()?.slot;
+        if !delegation.is_active(slot) {
+            return Err(return_dex_error_expected(
+                DexError::DelegationExpired,
+                "Delegation expired",
+                format_args!("a slot before {}", { delegation.expiry_slot }),
+                slot,
+            ));
+        }
+
+        Ok(delegation.owner)
+    }
+
+    // Check that a limit price lies within the market's band around a fresh oracle price
+    fn check_oracle_band(market: &Market, oracle_account: Option<&AccountInfo>, limit_price: u64) -> ProgramResult {
+        let reference_price = Self::load_oracle_price(market, oracle_account)?;
+        if !oracle::is_within_band(limit_price, reference_price, market.oracle_band_bps) {
+            msg!(
+                "Price {} is more than {} bps from oracle price {}",
+                limit_price,
+                { market.oracle_band_bps },
+                reference_price
+            );
             return Err(return_dex_error(
-                DexError::InvalidAccountData,
-                "Order does not belong to this market",
+                DexError::PriceOutsideOracleBand,
+                "Price too far from oracle price",
             ));
         }
 
-        // Return locked funds
-        if order.is_buy {
-            // For buy orders, return quote tokens (price * remaining quantity)
-            let amount = order
-                .limit_price
-                .checked_mul(order.remaining_quantity)
-                .ok_or(ProgramError::ArithmeticOverflow)?;
+        Ok(())
+    }
 
-            // Transfer tokens back to owner
-            invoke_signed(
-                &token_instruction::transfer(
-                    token_program.key,
-                    order_account.key,
-                    owner_token_account.key,
-                    order_account.key,
-                    &[],
-                    amount,
-                )?,
-                &[
-                    order_account.clone(),
-                    owner_token_account.clone(),
-                    order_account.clone(),
-                    token_program.clone(),
-                ],
-                &[&[&order.order_id.to_le_bytes()]],
-            )?;
-        } else {
-            // For sell orders, return base tokens (remaining quantity)
-            // Transfer tokens back to owner
-            invoke_signed(
-                &token_instruction::transfer(
-                    token_program.key,
-                    order_account.key,
-                    owner_token_account.key,
-                    order_account.key,
-                    &[],
-                    order.remaining_quantity,
-                )?,
-                &[
-                    order_account.clone(),
-                    owner_token_account.clone(),
-                    order_account.clone(),
-                    token_program.clone(),
-                ],
-                &[&[&order.order_id.to_le_bytes()]],
-            )?;
+    // Read the fresh price of a market's oracle, in the market's price units
+    fn load_oracle_price(market: &Market, oracle_account: Option<&AccountInfo>) -> Result<u128, ProgramError> {
+        let oracle_account = oracle_account
+            .filter(|account| market.has_oracle() && *account.key == market.oracle)
+            .ok_or_else(|| return_dex_error(DexError::InvalidOraclePrice, "Missing market oracle"))?;
+        let oracle_price = OraclePrice::load(&oracle_account.data.borrow())
+            .map_err(|_| return_dex_error(DexError::InvalidOraclePrice, "Invalid oracle account"))?;
+
+        let slot = Clock::get()?.slot;
+        if !oracle_price.is_fresh(slot, market.oracle_max_staleness_slots) {
+            msg!(
+                "Oracle price has status {} and was published at slot {}; slot is {}, at most {} slots of staleness allowed",
+                oracle_price.status,
+                oracle_price.publish_slot,
+                slot,
+                { market.oracle_max_staleness_slots }
+            );
+            return Err(return_dex_error(
+                DexError::OracleStale,
+                "Oracle price stale",
+            ));
         }
 
-        // Update market
-        if order.is_buy {
-            market.num_bids = market.num_bids.saturating_sub(1);
-        } else {
-            market.num_asks = market.num_asks.saturating_sub(1);
+        oracle_price
+            .price_in(market.oracle_price_expo)
+            .ok_or_else(|| return_dex_error(DexError::InvalidOraclePrice, "Oracle price not usable"))
+    }
+
+    // Process cancel order instruction
+    fn process_cancel_order(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let owner = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let order_account = next_account_info(account_info_iter)?;
+        let owner_token_account = next_account_info(account_info_iter)?;
+        let base_vault = next_account_info(account_info_iter)?;
+        let quote_vault = next_account_info(account_info_iter)?;
+        let market_signer = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !owner.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Order owner must sign",
+            ));
         }
-        market.pack_into_slice(&mut market_account.data.borrow_mut());
 
-        // Close order account
-        // Zero out the data
-        let mut data = order_account.data.borrow_mut();
-        for byte in data.iter_mut() {
-            *byte = 0;
+        Self::check_token_program(token_program)?;
+
+        // Load market and order
+        let mut market = Self::load_market(program_id, market_account)?;
+        let order = Self::load_order(program_id, market_account, order_account)?;
+
+        // A linked order is cancelled together with this one
+        let linked = if order.is_linked() {
+            let linked_order_account = next_account_info(account_info_iter)?;
+            let linked_owner_token_account = next_account_info(account_info_iter)?;
+            Self::load_linked_order(program_id, &order, order_account, linked_order_account)?
+                .map(|linked_order| (linked_order, linked_order_account, linked_owner_token_account))
+        } else {
+            None
+        };
+
+        // Verify owner, or a delegate of the owner that can only refund the owner
+        let rent_recipient = if order.owner == *owner.key {
+            owner
+        } else {
+            let delegation = next_account_info(account_info_iter)
+                .map_err(|_| return_dex_error(DexError::AccountNotAuthorized, "Not order owner"))?;
+            let order_owner = next_account_info(account_info_iter)?;
+            if Self::check_delegation(program_id, market_account.key, owner.key, delegation)? != order.owner {
+                return Err(return_dex_error(
+                    DexError::AccountNotAuthorized,
+                    "Not order owner",
+                ));
+            }
+            Self::check_rent_recipient(&order, order_owner)?;
+            Self::check_refund_account(&market, &order, owner_token_account)?;
+            if let Some((linked_order, _, linked_owner_token_account)) = &linked {
+                Self::check_refund_account(&market, linked_order, linked_owner_token_account)?;
+            }
+            order_owner
+        };
+        let escrow = Self::load_escrow(program_id, accounts, market_account, &market, base_vault, quote_vault, market_signer)?;
+
+        // Return locked funds and close the order, refunding its rent to the owner
+        Self::release_order(&mut market, &escrow, &order, order_account, owner_token_account, rent_recipient)?;
+        if let Some((linked_order, linked_order_account, linked_owner_token_account)) = linked {
+            Self::release_order(
+                &mut market,
+                &escrow,
+                &linked_order,
+                linked_order_account,
+                linked_owner_token_account,
+                rent_recipient,
+            )?;
         }
+        market.pack_into_slice(&mut market_account.data.borrow_mut());
 
         msg!("Order cancelled successfully");
         Ok(())
     }
 
-    // Process settle funds instruction
-    fn process_settle_funds(
-        program_id: &Pubkey,
-        accounts: &[AccountInfo],
-        base_amount: u64,
-        quote_amount: u64,
-    ) -> ProgramResult {
+    // Process admin cancel order instruction
+    fn process_admin_cancel_order(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
         // Get accounts
         let account_info_iter = &mut accounts.iter();
         let authority = next_account_info(account_info_iter)?;
         let market_account = next_account_info(account_info_iter)?;
-        let taker_account = next_account_info(account_info_iter)?;
-        let maker_account = next_account_info(account_info_iter)?;
-        let taker_base_account = next_account_info(account_info_iter)?;
-        let taker_quote_account = next_account_info(account_info_iter)?;
-        let maker_base_account = next_account_info(account_info_iter)?;
-        let maker_quote_account = next_account_info(account_info_iter)?;
-        let fee_recipient_account = next_account_info(account_info_iter)?;
+        let order_account = next_account_info(account_info_iter)?;
+        let owner_token_account = next_account_info(account_info_iter)?;
+        let base_vault = next_account_info(account_info_iter)?;
+        let quote_vault = next_account_info(account_info_iter)?;
+        let market_signer = next_account_info(account_info_iter)?;
         let token_program = next_account_info(account_info_iter)?;
+        let order_owner = next_account_info(account_info_iter)?;
 
         // Verify accounts
         if !authority.is_signer {
@@ -449,14 +1495,10 @@ impl Processor {
             ));
         }
 
+        Self::check_token_program(token_program)?;
+
         // Load market
-        let market = Market::unpack_from_slice(&market_account.data.borrow())?;
-        if !market.is_initialized {
-            return Err(return_dex_error(
-                DexError::InvalidAccountData,
-                "Market not initialized",
-            ));
-        }
+        let mut market = Self::load_market(program_id, market_account)?;
 
         // Verify authority
         if market.authority != *authority.key {
@@ -466,70 +1508,8593 @@ impl Processor {
             ));
         }
 
-        // Calculate fee
-        let fee = market.calculate_fee(quote_amount)?;
-        let quote_amount_after_fee = quote_amount.checked_sub(fee).ok_or(ProgramError::ArithmeticOverflow)?;
+        // Load order
+        let order = Self::load_order(program_id, market_account, order_account)?;
+        Self::check_refund_account(&market, &order, owner_token_account)?;
+        Self::check_rent_recipient(&order, order_owner)?;
+        let escrow = Self::load_escrow(program_id, accounts, market_account, &market, base_vault, quote_vault, market_signer)?;
 
-        // Transfer base tokens from seller to buyer
-        invoke_signed(
-            &token_instruction::transfer(
-                token_program.key,
-                maker_base_account.key,
-                taker_base_account.key,
-                market_account.key,
-                &[],
-                base_amount,
-            )?,
-            &[
-                maker_base_account.clone(),
-                taker_base_account.clone(),
-                market_account.clone(),
-                token_program.clone(),
-            ],
-            &[&[&market.authority.to_bytes()]],
-        )?;
+        // A linked order is cancelled together with this one
+        let linked = if order.is_linked() {
+            let linked_order_account = next_account_info(account_info_iter)?;
+            let linked_owner_token_account = next_account_info(account_info_iter)?;
+            let linked_order = Self::load_linked_order(program_id, &order, order_account, linked_order_account)?;
+            if let Some(linked_order) = &linked_order {
+                Self::check_refund_account(&market, linked_order, linked_owner_token_account)?;
+            }
+            linked_order.map(|linked_order| (linked_order, linked_order_account, linked_owner_token_account))
+        } else {
+            None
+        };
 
-        // Transfer quote tokens from buyer to seller (minus fee)
-        invoke_signed(
-            &token_instruction::transfer(
-                token_program.key,
-                taker_quote_account.key,
-                maker_quote_account.key,
-                market_account.key,
-                &[],
-                quote_amount_after_fee,
-            )?,
-            &[
-                taker_quote_account.clone(),
-                maker_quote_account.clone(),
-                market_account.clone(),
-                token_program.clone(),
-            ],
-            &[&[&market.authority.to_bytes()]],
-        )?;
+        // Return locked funds and close the order, refunding its rent to the owner
+        Self::release_order(&mut market, &escrow, &order, order_account, owner_token_account, order_owner)?;
+        if let Some((linked_order, linked_order_account, linked_owner_token_account)) = linked {
+            Self::release_order(
+                &mut market,
+                &escrow,
+                &linked_order,
+                linked_order_account,
+                linked_owner_token_account,
+                order_owner,
+            )?;
+        }
+        market.pack_into_slice(&mut market_account.data.borrow_mut());
+
+        msg!(
+            "AUDIT: order {} of {} cancelled on market {} by {}",
+            { order.order_id },
+            order.owner,
+            market_account.key,
+            authority.key
+        );
+        Ok(())
+    }
+
+    // Check that an order locks funds from a token account of its owner in the escrowed mint
+    //
+    // The owner signs the transfer, so a delegate of someone else's account could otherwise
+    // lock funds that would later be refunded to an account it does not own.
+    fn check_owner_token_account(
+        market: &Market,
+        owner: &Pubkey,
+        is_buy: bool,
+        owner_token_account: &AccountInfo,
+    ) -> ProgramResult {
+        if owner_token_account.owner != market.token_program(is_buy) {
+            return Err(return_dex_error(
+                DexError::InvalidTokenAccount,
+                "Owner token account not owned by the token program of its mint",
+            ));
+        }
+
+        let token_account = token::unpack_account(&owner_token_account.data.borrow())
+            .map_err(|_| return_dex_error(DexError::InvalidTokenAccount, "Owner token account is not a token account"))?;
+        let mint = if is_buy {
+            market.quote_mint
+        } else {
+            market.base_mint
+        };
+        if token_account.mint != mint {
+            return Err(return_dex_error(
+                DexError::InvalidTokenAccount,
+                "Owner token account must hold the quote mint for buys and the base mint for sells",
+            ));
+        }
+        if token_account.owner != *owner {
+            return Err(return_dex_error(
+                DexError::InvalidTokenAccount,
+                "Owner token account must be owned by the order owner",
+            ));
+        }
+        if token_account.is_frozen() {
+            return Err(return_dex_error(
+                DexError::InvalidTokenAccount,
+                "Owner token account is frozen",
+            ));
+        }
+
+        Ok(())
+    }
+
+    // Check that a refund goes to a token account of the order owner in the escrowed mint
+    fn check_refund_account(market: &Market, order: &Order, owner_token_account: &AccountInfo) -> ProgramResult {
+        let token_account = token::unpack_account(&owner_token_account.data.borrow())
+            .map_err(|_| return_dex_error(DexError::InvalidTokenAccount, "Refund account is not a token account"))?;
+        let mint = if order.is_buy {
+            market.quote_mint
+        } else {
+            market.base_mint
+        };
+        if token_account.owner != order.owner || token_account.mint != mint {
+            return Err(return_dex_error(
+                DexError::InvalidTokenAccount,
+                "Refund account must hold the escrowed mint for the order owner",
+            ));
+        }
+
+        Ok(())
+    }
+
+    // Process reduce order instruction
+    fn process_reduce_order(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        new_remaining_quantity: u64,
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let owner = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let order_account = next_account_info(account_info_iter)?;
+        let owner_token_account = next_account_info(account_info_iter)?;
+        let base_vault = next_account_info(account_info_iter)?;
+        let quote_vault = next_account_info(account_info_iter)?;
+        let market_signer = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !owner.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Order owner must sign",
+            ));
+        }
+
+        Self::check_token_program(token_program)?;
+
+        // Load market and order
+        let mut market = Self::load_market(program_id, market_account)?;
+        let mut order = Self::load_order(program_id, market_account, order_account)?;
+
+        // Verify owner
+        if order.owner != *owner.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Not order owner",
+            ));
+        }
+
+        // Only reductions keep queue priority; cancel to remove the whole order
+        if new_remaining_quantity == 0 || new_remaining_quantity >= order.remaining_quantity {
+            return Err(return_dex_error(
+                DexError::InvalidOrderSize,
+                "New quantity must be below remaining quantity and above zero",
+            ));
+        }
+
+        // Return the funds locked for the removed quantity
+        let escrow = Self::load_escrow(program_id, accounts, market_account, &market, base_vault, quote_vault, market_signer)?;
+        let reduced_quantity = order.remaining_quantity - new_remaining_quantity;
+        let refund_amount = Self::refund_escrow(&market, &escrow, &order, reduced_quantity, owner_token_account)?;
+
+        // Update order in place so its timestamp and position are unchanged
+        order.remaining_quantity = new_remaining_quantity;
+        order.visible_quantity = order.visible_quantity.min(new_remaining_quantity);
+        order.pack_into_slice(&mut order_account.data.borrow_mut());
+        Self::emit_order_cancelled(&order, order_account, reduced_quantity, refund_amount)?;
+
+        // Update market
+        market.remove_resting(order.is_buy, order.limit_price, reduced_quantity, false);
+        market.pack_into_slice(&mut market_account.data.borrow_mut());
+
+        msg!("Order reduced successfully");
+        Ok(())
+    }
+
+    // Process cancel orders by ids instruction
+    fn process_cancel_orders_by_ids(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        order_ids: &[u64],
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let owner = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let owner_base_account = next_account_info(account_info_iter)?;
+        let owner_quote_account = next_account_info(account_info_iter)?;
+        let base_vault = next_account_info(account_info_iter)?;
+        let quote_vault = next_account_info(account_info_iter)?;
+        let market_signer = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !owner.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Order owner must sign",
+            ));
+        }
+
+        Self::check_token_program(token_program)?;
+
+        // Validate the id list
+        if order_ids.is_empty() || order_ids.len() > MAX_CANCEL_ORDER_IDS {
+            return Err(return_dex_error(
+                DexError::InvalidInstructionData,
+                "Order id count out of range",
+            ));
+        }
+
+        // Load market
+        let mut market = Self::load_market(program_id, market_account)?;
+
+        let escrow = Self::load_escrow(program_id, accounts, market_account, &market, base_vault, quote_vault, market_signer)?;
+
+        // Cancel each order; the remaining accounts follow the id list order
+        let order_accounts: Vec<&AccountInfo> = account_info_iter.take(order_ids.len()).collect();
+        if order_accounts.len() != order_ids.len() {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+        for (order_id, order_account) in order_ids.iter().zip(order_accounts.iter().copied()) {
+
+            // Load order
+            if order_account.owner != program_id {
+                return Err(return_dex_error(
+                    DexError::InvalidAccountOwner,
+                    "Order not owned by program",
+                ));
+            }
+            let order = Order::unpack_from_slice(&order_account.data.borrow())?;
+            if !order.is_initialized || order.order_id != *order_id {
+                return Err(return_dex_error(
+                    DexError::OrderNotFound,
+                    "Order account does not match order id",
+                ));
+            }
+
+            // Verify owner
+            if order.owner != *owner.key {
+                return Err(return_dex_error(
+                    DexError::AccountNotAuthorized,
+                    "Not order owner",
+                ));
+            }
+
+            // Verify market
+            if order.market != *market_account.key {
+                return Err(return_dex_error(
+                    DexError::AccountMismatch,
+                    "Order does not belong to this market",
+                ));
+            }
+
+            // Linked orders may only be cancelled together, unless the linked order has already
+            // been filled and closed
+            if order.is_linked()
+                && !order_accounts
+                    .iter()
+                    .any(|account| *account.key == order.linked_order)
+            {
+                let linked_order_account = accounts
+                    .iter()
+                    .find(|account| *account.key == order.linked_order)
+                    .ok_or_else(|| {
+                        return_dex_error(DexError::InvalidOrderLink, "Linked order must be cancelled in the same batch")
+                    })?;
+                if Self::load_linked_order(program_id, &order, order_account, linked_order_account)?.is_some() {
+                    return Err(return_dex_error(
+                        DexError::InvalidOrderLink,
+                        "Linked order must be cancelled in the same batch",
+                    ));
+                }
+            }
+
+            // Buy orders are refunded in quote tokens, sell orders in base tokens
+            let owner_token_account = if order.is_buy {
+                owner_quote_account
+            } else {
+                owner_base_account
+            };
+            Self::release_order(&mut market, &escrow, &order, order_account, owner_token_account, owner)?;
+        }
+
+        market.pack_into_slice(&mut market_account.data.borrow_mut());
+
+        msg!("Cancelled {} orders successfully", order_ids.len());
+        Ok(())
+    }
+
+    // Process link orders instruction
+    fn process_link_orders(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let owner = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let first_order_account = next_account_info(account_info_iter)?;
+        let second_order_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !owner.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Order owner must sign",
+            ));
+        }
+        if first_order_account.key == second_order_account.key {
+            return Err(return_dex_error(
+                DexError::InvalidOrderLink,
+                "Cannot link an order to itself",
+            ));
+        }
+
+        // Load market
+        Self::load_market(program_id, market_account)?;
+
+        // Load orders
+        let mut first_order = Self::load_order(program_id, market_account, first_order_account)?;
+        let mut second_order = Self::load_order(program_id, market_account, second_order_account)?;
+        for order in [&first_order, &second_order] {
+            if order.owner != *owner.key {
+                return Err(return_dex_error(
+                    DexError::AccountNotAuthorized,
+                    "Not order owner",
+                ));
+            }
+            if order.is_linked() {
+                return Err(return_dex_error(
+                    DexError::InvalidOrderLink,
+                    "Order is already linked",
+                ));
+            }
+        }
+
+        // Link the orders to each other
+        first_order.linked_order = *second_order_account.key;
+        second_order.linked_order = *first_order_account.key;
+        first_order.pack_into_slice(&mut first_order_account.data.borrow_mut());
+        second_order.pack_into_slice(&mut second_order_account.data.borrow_mut());
+
+        msg!("Orders linked successfully");
+        Ok(())
+    }
+
+    // Process commit open orders root instruction
+    fn process_commit_open_orders_root(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let market_account = next_account_info(account_info_iter)?;
+        let order_accounts: Vec<&AccountInfo> = account_info_iter.collect();
+
+        // Load market
+        let mut market = Self::load_market(program_id, market_account)?;
+
+        // The commitment must cover every open order exactly once
+        let num_open_orders = market
+            .num_bids
+            .checked_add(market.num_asks)
+            .ok_or(DexError::ArithmeticOverflow)?;
+        if order_accounts.len() as u64 != num_open_orders {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Order accounts do not cover the open orders",
+            ));
+        }
+        if order_accounts.windows(2).any(|pair| pair[0].key >= pair[1].key) {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Order accounts must be sorted by address without duplicates",
+            ));
+        }
+
+        // Hash each open order, and recompute the best prices from the whole book
+        let mut leaves = Vec::with_capacity(order_accounts.len());
+        let (mut best_bid, mut best_ask) = (0, 0);
+        for order_account in order_accounts {
+            let order = Self::load_order(program_id, market_account, order_account)?;
+            if order.is_buy {
+                best_bid = best_bid.max(order.limit_price);
+            } else if best_ask == 0 || order.limit_price < best_ask {
+                best_ask = order.limit_price;
+            }
+            leaves.push(merkle::leaf_hash(order_account.key, &order));
+        }
+        market.best_bid = best_bid;
+        market.best_ask = best_ask;
+        market.flags &= !(MARKET_FLAG_BEST_BID_STALE | MARKET_FLAG_BEST_ASK_STALE);
+
+        // Save the commitment
+        market.open_orders_root = merkle::root(&leaves);
+        market.open_orders_root_leaves = leaves.len() as u64;
+        market.open_orders_root_slot = Clock::get()?.slot;
+        market.pack_into_slice(&mut market_account.data.borrow_mut());
+
+        msg!("Open orders root committed over {} orders", leaves.len());
+        Ok(())
+    }
+
+    // Load the order linked to `order`, checking that the link is mutual; a linked order that
+    // has already been filled and closed is none
+    fn load_linked_order(
+        program_id: &Pubkey,
+        order: &Order,
+        order_account: &AccountInfo,
+        linked_order_account: &AccountInfo,
+    ) -> Result<Option<Order>, ProgramError> {
+        if *linked_order_account.key != order.linked_order {
+            return Err(return_dex_error(
+                DexError::InvalidOrderLink,
+                "Linked order account mismatch",
+            ));
+        }
+        if *linked_order_account.owner == system_program::id() && linked_order_account.data_is_empty() {
+            return Ok(None);
+        }
+        if linked_order_account.owner != program_id {
+            return Err(return_dex_error(
+                DexError::InvalidAccountOwner,
+                "Linked order not owned by program",
+            ));
+        }
+
+        let linked_order = Order::unpack_from_slice(&linked_order_account.data.borrow())?;
+        if !linked_order.is_initialized
+            || linked_order.linked_order != *order_account.key
+            || linked_order.owner != order.owner
+            || linked_order.market != order.market
+        {
+            return Err(return_dex_error(
+                DexError::InvalidOrderLink,
+                "Linked order does not link back",
+            ));
+        }
+
+        Ok(Some(linked_order))
+    }
+
+    // Return an order's locked funds to its owner, remove it from the book and close it
+    fn release_order<'a>(
+        market: &mut Market,
+        escrow: &Escrow<'a, '_>,
+        order: &Order,
+        order_account: &AccountInfo<'a>,
+        owner_token_account: &AccountInfo<'a>,
+        rent_recipient: &AccountInfo<'a>,
+    ) -> ProgramResult {
+        // Return locked funds
+        let refund_amount = Self::refund_escrow(market, escrow, order, order.remaining_quantity, owner_token_account)?;
+
+        // Update market
+        if order.is_buy {
+            market.num_bids = market.num_bids.saturating_sub(1);
+        } else {
+            market.num_asks = market.num_asks.saturating_sub(1);
+        }
+        market.remove_resting(order.is_buy, order.limit_price, order.remaining_quantity, true);
+
+        // Close order account
+        Self::close_order_account(order_account, rent_recipient)?;
+
+        let mut cancelled = *order;
+        cancelled.remaining_quantity = 0;
+        Self::emit_order_cancelled(&cancelled, order_account, order.remaining_quantity, refund_amount)
+    }
+
+    // Cancel the order linked to a one-cancels-other order that is filling, refunding the
+    // owner, and unlink the filling order; the linked order is found among `accounts`
+    #[allow(clippy::too_many_arguments)]
+    fn cancel_linked_order<'a>(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo<'a>],
+        market: &mut Market,
+        escrow: &Escrow<'a, '_>,
+        order: &mut Order,
+        order_account: &AccountInfo,
+        owner_base_account: &AccountInfo<'a>,
+        owner_quote_account: &AccountInfo<'a>,
+        rent_recipient: &AccountInfo<'a>,
+    ) -> Result<Option<Pubkey>, ProgramError> {
+        if !order.is_linked() {
+            return Ok(None);
+        }
+
+        let linked_order_account = accounts
+            .iter()
+            .find(|account| *account.key == order.linked_order)
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        let linked_order = Self::load_linked_order(program_id, order, order_account, linked_order_account)?;
+        order.linked_order = Pubkey::default();
+        let Some(linked_order) = linked_order else {
+            return Ok(None);
+        };
+
+        // Buy orders are refunded in quote tokens, sell orders in base tokens
+        let owner_token_account = if linked_order.is_buy {
+            owner_quote_account
+        } else {
+            owner_base_account
+        };
+        Self::release_order(market, escrow, &linked_order, linked_order_account, owner_token_account, rent_recipient)?;
+        Ok(Some(*linked_order_account.key))
+    }
+
+    // Log the removal of part or all of an order, given the order as it was left
+    fn emit_order_cancelled(
+        order: &Order,
+        order_account: &AccountInfo,
+        cancelled_quantity: u64,
+        refund_amount: u64,
+    ) -> ProgramResult {
+        Self::emit_event(DexEvent::OrderCancelled(OrderCancelled {
+            market: order.market,
+            order: *order_account.key,
+            owner: order.owner,
+            order_id: order.order_id,
+            is_buy: order.is_buy,
+            limit_price: order.limit_price,
+            cancelled_quantity,
+            remaining_quantity: order.remaining_quantity,
+            refund_amount,
+            slot: Clock::get()?.slot,
+        }))
+    }
+
+    // Close an order account, moving its rent to the recipient and handing it back to the system program
+    fn close_order_account(order_account: &AccountInfo, rent_recipient: &AccountInfo) -> ProgramResult {
+        let lamports = order_account.lamports();
+        **rent_recipient.lamports.borrow_mut() = rent_recipient
+            .lamports()
+            .checked_add(lamports)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        **order_account.lamports.borrow_mut() = 0;
+        order_account.data.borrow_mut().fill(0);
+        order_account.realloc(0, false)?;
+        order_account.assign(&system_program::id());
+
+        Ok(())
+    }
+
+    // Check that the account receiving a closed order's rent is the order owner
+    fn check_rent_recipient(order: &Order, rent_recipient: &AccountInfo) -> ProgramResult {
+        if order.owner != *rent_recipient.key {
+            return Err(return_dex_error(
+                DexError::AccountMismatch,
+                "Rent recipient must be the order owner",
+            ));
+        }
+
+        Ok(())
+    }
+
+    // Fill a matched order and keep the book totals in step; a fully filled order leaves the
+    // book and is closed
+    fn fill_resting_order(
+        market: &mut Market,
+        order: &mut Order,
+        order_account: &AccountInfo,
+        rent_recipient: &AccountInfo,
+        quantity: u64,
+        timestamp: u64,
+    ) -> ProgramResult {
+        // Only the displayed quantity can be filled
+        order.fill(quantity, timestamp).map_err(|_| {
+            return_dex_error(DexError::InvalidOrderSize, "Fill exceeds displayed quantity")
+        })?;
+        market.remove_resting(order.is_buy, order.limit_price, quantity, order.remaining_quantity == 0);
+
+        if order.remaining_quantity > 0 {
+            order.pack_into_slice(&mut order_account.data.borrow_mut());
+            return Ok(());
+        }
+
+        if order.is_buy {
+            market.num_bids = market.num_bids.saturating_sub(1);
+        } else {
+            market.num_asks = market.num_asks.saturating_sub(1);
+        }
+        Self::close_order_account(order_account, rent_recipient)
+    }
+
+    // Check the escrow accounts passed to a refund against the market's vaults and signer,
+    // and find the token programs and mints of both vaults
+    fn load_escrow<'a, 'b>(
+        program_id: &Pubkey,
+        accounts: &'b [AccountInfo<'a>],
+        market_account: &'b AccountInfo<'a>,
+        market: &Market,
+        base_vault: &'b AccountInfo<'a>,
+        quote_vault: &'b AccountInfo<'a>,
+        market_signer: &'b AccountInfo<'a>,
+    ) -> Result<Escrow<'a, 'b>, ProgramError> {
+        let (base_vault_address, _) = Market::find_base_vault_address(program_id, market_account.key);
+        let (quote_vault_address, _) = Market::find_quote_vault_address(program_id, market_account.key);
+        if base_vault_address != *base_vault.key || quote_vault_address != *quote_vault.key {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Invalid escrow vault",
+            ));
+        }
+        if market.signer_address(program_id, market_account.key)? != *market_signer.key {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Invalid market signer",
+            ));
+        }
+
+        Ok(Escrow {
+            market: market_account.key,
+            signer_bump: market.signer_bump,
+            base_vault,
+            quote_vault,
+            market_signer,
+            base: Self::load_token_side(market, accounts, false)?,
+            quote: Self::load_token_side(market, accounts, true)?,
+        })
+    }
+
+    // Return the funds locked for part of an order's quantity to its owner
+    fn refund_escrow<'a>(
+        market: &Market,
+        escrow: &Escrow<'a, '_>,
+        order: &Order,
+        quantity: u64,
+        owner_token_account: &AccountInfo<'a>,
+    ) -> Result<u64, ProgramError> {
+        // Buy orders lock quote tokens (price * quantity plus the taker fee), sell orders lock base tokens (quantity)
+        let amount = market.escrow_amount(order.is_buy, order.limit_price, quantity)?;
+        Self::pay_from_escrow(escrow, order.is_buy, owner_token_account, amount)?;
+        Ok(amount)
+    }
+
+    // Transfer tokens out of the quote or base escrow vault, signed by the market signer that
+    // owns it
+    fn pay_from_escrow<'a>(
+        escrow: &Escrow<'a, '_>,
+        is_quote: bool,
+        destination: &AccountInfo<'a>,
+        amount: u64,
+    ) -> ProgramResult {
+        Self::transfer_tokens(
+            escrow.side(is_quote),
+            escrow.vault(is_quote),
+            destination,
+            escrow.market_signer,
+            amount,
+            &[&[escrow.market.as_ref(), &[escrow.signer_bump]]],
+        )
+    }
+
+    // Find the token program and mint of one side of a market among an instruction's accounts
+    //
+    // They are looked up by key rather than position, so every instruction keeps its account
+    // layout; SPL Token sides may leave the mint out and are moved with unchecked transfers.
+    fn load_token_side<'a, 'b>(
+        market: &Market,
+        accounts: &'b [AccountInfo<'a>],
+        is_quote: bool,
+    ) -> Result<TokenSide<'a, 'b>, ProgramError> {
+        let find = |key: &Pubkey| accounts.iter().find(|account| account.key == key);
+        let token_program = find(market.token_program(is_quote)).ok_or(ProgramError::NotEnoughAccountKeys)?;
+        let mint = find(market.mint(is_quote));
+        if mint.is_none() && *token_program.key != spl_token::id() {
+            msg!("Token-2022 mint {} must be passed", market.mint(is_quote));
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+
+        Ok(TokenSide {
+            token_program,
+            mint,
+            decimals: market.decimals(is_quote),
+        })
+    }
+
+    // Transfer tokens of one side of a market, checked against its mint when the mint is passed
+    fn transfer_tokens<'a>(
+        side: &TokenSide<'a, '_>,
+        source: &AccountInfo<'a>,
+        destination: &AccountInfo<'a>,
+        authority: &AccountInfo<'a>,
+        amount: u64,
+        signer_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        let token_program = side.token_program;
+        match side.mint {
+            Some(mint) => invoke_signed(
+                &token_2022_instruction::transfer_checked(
+                    token_program.key,
+                    source.key,
+                    mint.key,
+                    destination.key,
+                    authority.key,
+                    &[],
+                    amount,
+                    side.decimals,
+                )?,
+                &[
+                    source.clone(),
+                    mint.clone(),
+                    destination.clone(),
+                    authority.clone(),
+                    token_program.clone(),
+                ],
+                signer_seeds,
+            ),
+            None => invoke_signed(
+                &token_instruction::transfer(
+                    token_program.key,
+                    source.key,
+                    destination.key,
+                    authority.key,
+                    &[],
+                    amount,
+                )?,
+                &[
+                    source.clone(),
+                    destination.clone(),
+                    authority.clone(),
+                    token_program.clone(),
+                ],
+                signer_seeds,
+            ),
+        }
+    }
+
+    // Transfer tokens of one side of a market into one of its accounts so that exactly `amount`
+    // arrives, the sender paying any transfer fee on top; returns the fee paid
+    fn transfer_tokens_in<'a>(
+        side: &TokenSide<'a, '_>,
+        source: &AccountInfo<'a>,
+        destination: &AccountInfo<'a>,
+        authority: &AccountInfo<'a>,
+        amount: u64,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<u64, ProgramError> {
+        let fee = match side.mint {
+            Some(mint) => token::inverse_transfer_fee(&mint.data.borrow(), Clock::get()?.epoch, amount)
+                .map_err(|_| return_dex_error(DexError::InvalidMint, "Transfer fee leaves no way to receive the amount"))?,
+            None => 0,
+        };
+
+        match side.mint.filter(|_| fee > 0) {
+            // The token program checks the fee, so a fee change cannot short the vault
+            Some(mint) => invoke_signed(
+                &transfer_fee_instruction::transfer_checked_with_fee(
+                    side.token_program.key,
+                    source.key,
+                    mint.key,
+                    destination.key,
+                    authority.key,
+                    &[],
+                    amount + fee,
+                    side.decimals,
+                    fee,
+                )?,
+                &[
+                    source.clone(),
+                    mint.clone(),
+                    destination.clone(),
+                    authority.clone(),
+                    side.token_program.clone(),
+                ],
+                signer_seeds,
+            )?,
+            None => Self::transfer_tokens(side, source, destination, authority, amount, signer_seeds)?,
+        }
+
+        Ok(fee)
+    }
+
+    // Process settle funds instruction
+    fn process_settle_funds(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let authority = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let taker_account = next_account_info(account_info_iter)?;
+        let maker_account = next_account_info(account_info_iter)?;
+        let taker_base_account = next_account_info(account_info_iter)?;
+        let taker_quote_account = next_account_info(account_info_iter)?;
+        let maker_base_account = next_account_info(account_info_iter)?;
+        let maker_quote_account = next_account_info(account_info_iter)?;
+        let fee_recipient_account = next_account_info(account_info_iter)?;
+        let base_vault = next_account_info(account_info_iter)?;
+        let quote_vault = next_account_info(account_info_iter)?;
+        let market_signer = next_account_info(account_info_iter)?;
+        let taker_owner = next_account_info(account_info_iter)?;
+        let maker_owner = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let instructions_sysvar = next_account_info(account_info_iter).ok();
+        let referrer_token_account = next_account_info(account_info_iter).ok();
+        let settlement_record_account = next_account_info(account_info_iter)
+            .ok()
+            .filter(|account| *account.key != system_program::id());
+        let twap_oracle_account = next_account_info(account_info_iter).ok();
+
+        // Verify accounts
+        if !authority.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Authority must sign",
+            ));
+        }
+
+        Self::check_token_program(token_program)?;
+
+        // Load market
+        // Matching updates the market in place
+        let mut market = Self::load_market_mut(program_id, market_account)?;
+
+        // Verify authority
+        if market.authority != *authority.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Not market authority",
+            ));
+        }
+
+        // Fees are collected in the market's fee vault
+        if Market::find_fee_vault_address(program_id, market_account.key).0 != *fee_recipient_account.key {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Fee recipient must be the market fee vault",
+            ));
+        }
+
+        // Enforce the market's instruction guard
+        Self::check_instruction_guard(program_id, market.instruction_guard, instructions_sysvar)?;
+
+        // Matched trades do not settle while the market is paused
+        if market.status == MarketStatus::Paused {
+            return Err(return_dex_error(
+                DexError::MarketPaused,
+                "Market is paused",
+            ));
+        }
+        Self::check_continuous_matching(&market)?;
+
+        // The fill is derived from the matched orders, never from the caller
+        let mut taker_order = Self::load_order(program_id, market_account, taker_account)?;
+        let mut maker_order = Self::load_order(program_id, market_account, maker_account)?;
+        if !taker_order.is_buy || maker_order.is_buy {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Taker order must buy from the maker order",
+            ));
+        }
+        if taker_order.is_linked() && taker_order.linked_order == *maker_account.key {
+            return Err(return_dex_error(
+                DexError::InvalidOrderLink,
+                "Linked orders cannot fill each other",
+            ));
+        }
+        if taker_order.limit_price < maker_order.limit_price {
+            msg!(
+                "Taker buys at {}, maker sells at {}",
+                { taker_order.limit_price },
+                { maker_order.limit_price }
+            );
+            return Err(return_dex_error(
+                DexError::InvalidOrderPrice,
+                "Orders do not cross",
+            ));
+        }
+        let fill_quantity = taker_order.visible_quantity.min(maker_order.visible_quantity);
+        if fill_quantity == 0 {
+            return Err(return_dex_error(
+                DexError::InvalidOrderSize,
+                "Nothing left to fill",
+            ));
+        }
+        let base_amount = market.base_lots_to_native(fill_quantity)?;
+        let quote_amount = market.notional(maker_order.limit_price, fill_quantity)?;
+
+        // Proceeds only go to the owners of the matched orders
+        Self::check_owner_token_account(&market, &taker_order.owner, false, taker_base_account)?;
+        Self::check_owner_token_account(&market, &taker_order.owner, true, taker_quote_account)?;
+        Self::check_owner_token_account(&market, &maker_order.owner, false, maker_base_account)?;
+        Self::check_owner_token_account(&market, &maker_order.owner, true, maker_quote_account)?;
+        Self::check_rent_recipient(&taker_order, taker_owner)?;
+        Self::check_rent_recipient(&maker_order, maker_owner)?;
+        let escrow = Self::load_escrow(program_id, accounts, market_account, &market, base_vault, quote_vault, market_signer)?;
+
+        // Route the referral share of the fee to the taker order's referrer
+        let referrer_token_account = if taker_order.has_referrer() {
+            Some(
+                referrer_token_account
+                    .filter(|account| *account.key == taker_order.referrer)
+                    .ok_or_else(|| {
+                        return_dex_error(DexError::InvalidTokenAccount, "Referrer token account mismatch")
+                    })?,
+            )
+        } else {
+            None
+        };
+
+        // Calculate fees; the maker fee is deducted from the maker's proceeds, a rebate is added,
+        // and a staking taker pays the fee of its tier
+        let discount_bps = Self::taker_fee_discount_bps(program_id, accounts, &taker_order.owner)?;
+        let amounts = market.discounted_settlement_amounts(quote_amount, referrer_token_account.is_some(), discount_bps)?;
+
+        // The taker's lock for the filled quantity covers its cost; the rest is returned to it
+        let taker_lock = market.escrow_amount(true, taker_order.limit_price, fill_quantity)?;
+        let taker_refund = taker_lock
+            .checked_sub(amounts.taker_quote_amount()?)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+
+        // Filling one leg of a one-cancels-other pair cancels the other
+        Self::cancel_linked_order(
+            program_id,
+            accounts,
+            &mut market,
+            &escrow,
+            &mut taker_order,
+            taker_account,
+            taker_base_account,
+            taker_quote_account,
+            taker_owner,
+        )?;
+        Self::cancel_linked_order(
+            program_id,
+            accounts,
+            &mut market,
+            &escrow,
+            &mut maker_order,
+            maker_account,
+            maker_base_account,
+            maker_quote_account,
+            maker_owner,
+        )?;
+
+        // Fill both orders
+        let timestamp = Clock::get()?.unix_timestamp as u64;
+        Self::fill_resting_order(&mut market, &mut taker_order, taker_account, taker_owner, fill_quantity, timestamp)?;
+        Self::fill_resting_order(&mut market, &mut maker_order, maker_account, maker_owner, fill_quantity, timestamp)?;
+        let seq_num = market.fill_seq_num;
+        market.fill_seq_num = seq_num.checked_add(1).ok_or(ProgramError::ArithmeticOverflow)?;
+        market.record_fill_price(maker_order.limit_price);
+        market.last_trade_slot = Clock::get()?.slot;
+        market.record_volume(base_amount, quote_amount, timestamp);
+        market.fees_accrued = market.fees_accrued.saturating_add(amounts.vault_fee);
+        market.referral_fees_paid = market.referral_fees_paid.saturating_add(amounts.referral_fee);
+
+        // Weigh the fill price into the market's TWAP oracle
+        if market.twap_oracle_enabled {
+            let twap_oracle_account = twap_oracle_account.ok_or(ProgramError::NotEnoughAccountKeys)?;
+            let mut twap_oracle = Self::load_twap_oracle(program_id, market_account, twap_oracle_account)?;
+            twap_oracle.record_fill(maker_order.limit_price, timestamp as i64);
+            twap_oracle.pack_into_slice(&mut twap_oracle_account.data.borrow_mut());
+        }
+
+        // Append the fill to the market's event queue
+        if market.has_flag(MARKET_FLAG_EVENT_QUEUE) {
+            let event_queue_address = EventQueue::find_address(program_id, market_account.key).0;
+            let event_queue_account = accounts
+                .iter()
+                .find(|account| *account.key == event_queue_address)
+                .ok_or(ProgramError::NotEnoughAccountKeys)?;
+            let mut event_queue = Self::load_event_queue_mut(program_id, market_account, event_queue_account)?;
+            event_queue.push(FillEvent {
+                seq_num,
+                slot: market.last_trade_slot,
+                taker_order: *taker_account.key,
+                maker_order: *maker_account.key,
+                price: maker_order.limit_price,
+                base_amount,
+                quote_amount,
+            });
+        }
+
+        // Accrue the fill's loyalty points to both order owners
+        if market.has_flag(MARKET_FLAG_LOYALTY_POINTS) {
+            Self::accrue_loyalty_points(
+                program_id,
+                accounts,
+                market_account,
+                &taker_order.owner,
+                &maker_order.owner,
+                quote_amount,
+                timestamp as i64,
+            )?;
+        }
+
+        // Record the settlement so it can be busted
+        if let Some(settlement_record_account) = settlement_record_account {
+            if settlement_record_account.owner != program_id {
+                return Err(return_dex_error(
+                    DexError::InvalidAccountOwner,
+                    "Settlement record not owned by program",
+                ));
+            }
+            if settlement_record_account.data_len() != SettlementRecord::LEN {
+                return Err(return_dex_error(
+                    DexError::InvalidAccountData,
+                    "Invalid settlement record account",
+                ));
+            }
+            let record = SettlementRecord::unpack_from_slice(&settlement_record_account.data.borrow())?;
+            if record.is_initialized {
+                return Err(return_dex_error(
+                    DexError::InvalidAccountData,
+                    "Settlement record already in use",
+                ));
+            }
+            Self::check_rent_exempt(settlement_record_account)?;
+
+            let record = SettlementRecord {
+                is_initialized: true,
+                market: *market_account.key,
+                slot: Clock::get()?.slot,
+                taker_base_account: *taker_base_account.key,
+                taker_quote_account: *taker_quote_account.key,
+                maker_base_account: *maker_base_account.key,
+                maker_quote_account: *maker_quote_account.key,
+                base_amount,
+                maker_quote_amount: amounts.maker_quote_amount,
+                is_busted: false,
+            };
+            record.pack_into_slice(&mut settlement_record_account.data.borrow_mut());
+        }
+
+        // Transfer the maker's escrowed base tokens to the taker
+        Self::pay_from_escrow(&escrow, false, taker_base_account, base_amount)?;
+
+        // Pay the maker the quote amount minus the maker fee, plus any maker rebate
+        Self::pay_from_escrow(&escrow, true, maker_quote_account, amounts.maker_quote_amount)?;
+
+        // Transfer fee to the fee vault
+        if amounts.vault_fee > 0 {
+            Self::pay_from_escrow(&escrow, true, fee_recipient_account, amounts.vault_fee)?;
+        }
+
+        // Transfer referral fee to referrer
+        if let Some(referrer_token_account) = referrer_token_account.filter(|_| amounts.referral_fee > 0) {
+            Self::pay_from_escrow(&escrow, true, referrer_token_account, amounts.referral_fee)?;
+        }
+
+        // Return the part of the taker's lock it did not spend
+        if taker_refund > 0 {
+            Self::pay_from_escrow(&escrow, true, taker_quote_account, taker_refund)?;
+        }
+
+        // Sequenced fill event so consumers can skip fills they have already processed
+        let event = Fill {
+            market: *market_account.key,
+            seq_num,
+            taker_order: *taker_account.key,
+            maker_order: *maker_account.key,
+            base_amount,
+            quote_amount,
+            taker_fee: amounts.taker_fee,
+            maker_fee: amounts.maker_fee,
+            slot: Clock::get()?.slot,
+            taker_memo: taker_order.memo().to_string(),
+            maker_memo: maker_order.memo().to_string(),
+        };
+        Self::emit_event(DexEvent::Fill(event))?;
+
+        msg!("Funds settled successfully");
+        Ok(())
+    }
+
+    // Process set instruction guard instruction
+    fn process_set_instruction_guard(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        guard: InstructionGuard,
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let authority = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !authority.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Authority must sign",
+            ));
+        }
+
+        // Load market
+        let mut market = Self::load_market(program_id, market_account)?;
+
+        // Verify authority
+        if market.authority != *authority.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Not market authority",
+            ));
+        }
+
+        // Update market
+        market.instruction_guard = guard;
+        market.pack_into_slice(&mut market_account.data.borrow_mut());
+        Self::emit_params_updated(market_account, authority.key, MarketParam::InstructionGuard(guard))?;
+
+        msg!("Instruction guard updated successfully");
+        Ok(())
+    }
+
+    // Process set referral share instruction
+    fn process_set_referral_share(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        referral_share_bps: u16,
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let authority = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !authority.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Authority must sign",
+            ));
+        }
+
+        // Validate share
+        if referral_share_bps > 10000 {
+            return Err(return_dex_error_expected(
+                DexError::InvalidInstructionData,
+                "Referral share cannot exceed 10000 bps",
+                "at most 10000",
+                referral_share_bps,
+            ));
+        }
+
+        // Load market
+        let mut market = Self::load_market(program_id, market_account)?;
+
+        // Verify authority
+        if market.authority != *authority.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Not market authority",
+            ));
+        }
+
+        // Update market
+        market.referral_share_bps = referral_share_bps;
+        market.pack_into_slice(&mut market_account.data.borrow_mut());
+        Self::emit_params_updated(market_account, authority.key, MarketParam::ReferralShare(referral_share_bps))?;
+
+        msg!("Referral share updated successfully");
+        Ok(())
+    }
+
+    // Process set fee split instruction
+    fn process_set_fee_split(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        treasury_share_bps: u16,
+        creator_share_bps: u16,
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let admin = next_account_info(account_info_iter)?;
+        let config_account = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let treasury_token_account = next_account_info(account_info_iter)?;
+        let creator_token_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !admin.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Config admin must sign",
+            ));
+        }
+
+        // Validate shares
+        let total_share_bps = treasury_share_bps as u32 + creator_share_bps as u32;
+        if total_share_bps > 10000 {
+            return Err(return_dex_error_expected(
+                DexError::InvalidInstructionData,
+                "Treasury and creator shares cannot exceed 10000 bps together",
+                "at most 10000",
+                total_share_bps,
+            ));
+        }
+
+        // Load config
+        let config = Self::load_program_config(program_id, config_account)?.ok_or_else(|| {
+            return_dex_error(DexError::InvalidAccountData, "Program config not initialized")
+        })?;
+
+        // Verify admin
+        if config.admin != *admin.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Not config admin",
+            ));
+        }
+
+        // Load market
+        let mut market = Self::load_market(program_id, market_account)?;
+
+        // The treasury's account must belong to the config's treasury and the creator's to the
+        // market authority, both in the quote mint
+        for (token_account, owner, name) in [
+            (treasury_token_account, config.treasury, "Treasury"),
+            (creator_token_account, market.authority, "Creator"),
+        ] {
+            let token = token::unpack_account(&token_account.data.borrow())
+                .map_err(|_| return_dex_error(DexError::InvalidTokenAccount, "Fee split account not initialized"))?;
+            if token.owner != owner || token.mint != market.quote_mint {
+                msg!("{} fee account {} must be a quote token account of {}", name, token_account.key, owner);
+                return Err(return_dex_error(
+                    DexError::InvalidTokenAccount,
+                    "Fee split account has the wrong owner or mint",
+                ));
+            }
+        }
+
+        // Update market
+        market.treasury_fee_account = *treasury_token_account.key;
+        market.treasury_fee_share_bps = treasury_share_bps;
+        market.creator_fee_account = *creator_token_account.key;
+        market.creator_fee_share_bps = creator_share_bps;
+        market.pack_into_slice(&mut market_account.data.borrow_mut());
+        Self::emit_params_updated(
+            market_account,
+            admin.key,
+            MarketParam::FeeSplit { treasury_share_bps, creator_share_bps },
+        )?;
+
+        msg!("Fee split updated successfully");
+        Ok(())
+    }
+
+    // Process set dynamic fees instruction
+    fn process_set_dynamic_fees(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        min_fee_bps: u16,
+        volatility_bps: u16,
+        volatility_window: u16,
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let authority = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !authority.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Authority must sign",
+            ));
+        }
+
+        // Load market
+        let mut market = Self::load_market(program_id, market_account)?;
+
+        // Verify authority
+        if market.authority != *authority.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Not market authority",
+            ));
+        }
+
+        // Validate the bounds; orders lock the full taker fee, so it is the ceiling
+        let enabled = volatility_window > 0;
+        if enabled && min_fee_bps > market.taker_fee_bps {
+            return Err(return_dex_error_expected(
+                DexError::InvalidInstructionData,
+                "Minimum fee cannot exceed the taker fee",
+                format_args!("at most {}", { market.taker_fee_bps }),
+                min_fee_bps,
+            ));
+        }
+        if enabled && volatility_bps == 0 {
+            return Err(return_dex_error(
+                DexError::InvalidInstructionData,
+                "Volatility of the full taker fee must be positive",
+            ));
+        }
+
+        // Update market, starting the estimate afresh when dynamic fees are turned on
+        if enabled && !market.has_flag(MARKET_FLAG_DYNAMIC_FEES) {
+            market.realized_variance = 0;
+        }
+        if enabled {
+            market.flags |= MARKET_FLAG_DYNAMIC_FEES;
+        } else {
+            market.flags &= !MARKET_FLAG_DYNAMIC_FEES;
+        }
+        market.dynamic_fee_min_bps = min_fee_bps;
+        market.dynamic_fee_volatility_bps = volatility_bps;
+        market.volatility_window = volatility_window;
+        market.pack_into_slice(&mut market_account.data.borrow_mut());
+        Self::emit_params_updated(
+            market_account,
+            authority.key,
+            MarketParam::DynamicFees {
+                min_fee_bps,
+                volatility_bps,
+                volatility_window,
+            },
+        )?;
+
+        msg!("Dynamic fees updated successfully");
+        Ok(())
+    }
+
+    // Process set market status instruction
+    fn process_set_market_status(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        status: MarketStatus,
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let authority = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !authority.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Authority must sign",
+            ));
+        }
+
+        // Load market
+        let mut market = Self::load_market(program_id, market_account)?;
+
+        // Verify authority
+        if market.authority != *authority.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Not market authority",
+            ));
+        }
+
+        // Update market
+        market.status = status;
+        market.pack_into_slice(&mut market_account.data.borrow_mut());
+        Self::emit_params_updated(market_account, authority.key, MarketParam::Status(status))?;
+
+        msg!("Market status set to {:?}", status);
+        Ok(())
+    }
+
+    // Process set oracle instruction
+    fn process_set_oracle(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        oracle: Pubkey,
+        band_bps: u16,
+        max_staleness_slots: u64,
+        price_expo: i32,
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let authority = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !authority.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Authority must sign",
+            ));
+        }
+
+        // Load market
+        let mut market = Self::load_market(program_id, market_account)?;
+
+        // Verify authority
+        if market.authority != *authority.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Not market authority",
+            ));
+        }
+
+        // Validate the band and staleness limit of a new oracle
+        if oracle != Pubkey::default() && (band_bps == 0 || band_bps > 10000 || max_staleness_slots == 0) {
+            msg!("Oracle band is {} bps, staleness limit {} slots", band_bps, max_staleness_slots);
+            return Err(return_dex_error(
+                DexError::InvalidInstructionData,
+                "Oracle band must be 1-10000 bps and the staleness limit positive",
+            ));
+        }
+
+        // Update market
+        market.oracle = oracle;
+        market.oracle_band_bps = band_bps;
+        market.oracle_max_staleness_slots = max_staleness_slots;
+        market.oracle_price_expo = price_expo;
+        market.pack_into_slice(&mut market_account.data.borrow_mut());
+        Self::emit_params_updated(
+            market_account,
+            authority.key,
+            MarketParam::Oracle {
+                oracle,
+                band_bps,
+                max_staleness_slots,
+                price_expo,
+            },
+        )?;
+
+        msg!("Market oracle set to {}", oracle);
+        Ok(())
+    }
+
+    // Process check oracle instruction
+    fn process_check_oracle(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let market_account = next_account_info(account_info_iter)?;
+        let oracle_account = next_account_info(account_info_iter)?;
+
+        // Load market
+        let mut market = Self::load_market(program_id, market_account)?;
+        if !market.has_oracle() || market.oracle != *oracle_account.key {
+            return Err(return_dex_error(
+                DexError::InvalidOraclePrice,
+                "Not the market oracle",
+            ));
+        }
+
+        let oracle_price = OraclePrice::load(&oracle_account.data.borrow())
+            .map_err(|_| return_dex_error(DexError::InvalidOraclePrice, "Invalid oracle account"))?;
+
+        // Halt new orders on an active market while its oracle is stale
+        if market.status == MarketStatus::Active
+            && !oracle_price.is_fresh(Clock::get()?.slot, market.oracle_max_staleness_slots)
+        {
+            market.status = MarketStatus::CancelOnly;
+            market.pack_into_slice(&mut market_account.data.borrow_mut());
+            Self::emit_params_updated(market_account, &Pubkey::default(), MarketParam::Status(market.status))?;
+            msg!("Oracle price stale, market set to CancelOnly");
+        }
+
+        Ok(())
+    }
+
+    // Process execute firm quote instruction
+    fn process_execute_firm_quote(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        quote: &FirmQuote,
+        base_amount: u64,
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let taker = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let quote_fill_account = next_account_info(account_info_iter)?;
+        let taker_base_account = next_account_info(account_info_iter)?;
+        let taker_quote_account = next_account_info(account_info_iter)?;
+        let maker_base_account = next_account_info(account_info_iter)?;
+        let maker_quote_account = next_account_info(account_info_iter)?;
+        let fee_vault = next_account_info(account_info_iter)?;
+        let rfq_authority = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+        let instructions_sysvar = next_account_info(account_info_iter)?;
+        let whitelist_entry = next_account_info(account_info_iter).ok();
+
+        // Verify accounts
+        if !taker.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Taker must sign",
+            ));
+        }
+
+        Self::check_token_program(token_program)?;
+        Self::check_system_program(system_program_account)?;
+
+        // Load market
+        let mut market = Self::load_market(program_id, market_account)?;
+        if *market_account.key != quote.market {
+            return Err(return_dex_error(
+                DexError::AccountMismatch,
+                "Quote market mismatch",
+            ));
+        }
+
+        // Quotes trade like new orders
+        Self::check_market_active(&market, "Market does not accept new orders")?;
+
+        if market.whitelist_enabled {
+            Self::check_whitelist_entry(program_id, market_account.key, taker.key, whitelist_entry)?;
+        }
+
+        // Verify the quote
+        if quote.taker != Pubkey::default() && quote.taker != *taker.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Quote addressed to another taker",
+            ));
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        if now > quote.expires_at {
+            return Err(return_dex_error_expected(
+                DexError::QuoteExpired,
+                "Quote expired",
+                format_args!("a time at or before {}", { quote.expires_at }),
+                now,
+            ));
+        }
+
+        if !ed25519::is_message_signed(instructions_sysvar, &quote.maker, &quote.signing_message(program_id))? {
+            return Err(return_dex_error(
+                DexError::InvalidQuoteSignature,
+                "Maker signature not verified in transaction",
+            ));
+        }
+
+        if base_amount == 0 {
+            return Err(return_dex_error(
+                DexError::InvalidOrderSize,
+                "Base amount must be positive",
+            ));
+        }
+
+        // Load or create the fill record
+        let (quote_fill_address, quote_fill_bump) = quote.find_fill_address(program_id);
+        if quote_fill_address != *quote_fill_account.key {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Invalid quote fill record address",
+            ));
+        }
+
+        let mut quote_fill = if quote_fill_account.owner == program_id {
+            QuoteFill::unpack_from_slice(&quote_fill_account.data.borrow())?
+        } else {
+            let rent = Rent::get()?;
+            let space = QuoteFill::LEN;
+            let lamports = rent.minimum_balance(space);
+
+            invoke_signed(
+                &system_instruction::create_account(
+                    taker.key,
+                    quote_fill_account.key,
+                    lamports,
+                    space as u64,
+                    program_id,
+                ),
+                &[
+                    taker.clone(),
+                    quote_fill_account.clone(),
+                    system_program_account.clone(),
+                ],
+                &[&[
+                    QUOTE_FILL_SEED,
+                    market_account.key.as_ref(),
+                    quote.maker.as_ref(),
+                    &quote.nonce.to_le_bytes(),
+                    &[quote_fill_bump],
+                ]],
+            )?;
+
+            QuoteFill {
+                is_initialized: true,
+                market: quote.market,
+                maker: quote.maker,
+                nonce: quote.nonce,
+                filled_quantity: 0,
+            }
+        };
+
+        let filled_quantity = quote_fill
+            .filled_quantity
+            .checked_add(base_amount)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        if filled_quantity > quote.quantity {
+            msg!(
+                "Quote of {} is filled to {}, cannot fill {} more",
+                quote.quantity,
+                quote_fill.filled_quantity,
+                base_amount
+            );
+            return Err(return_dex_error(
+                DexError::InvalidOrderSize,
+                "Quote quantity exceeded",
+            ));
+        }
+
+        // Maker accounts must belong to the maker; taker accounts only need the right mints
+        Self::check_quote_token_account(maker_base_account, &market.base_mint, Some(&quote.maker))?;
+        Self::check_quote_token_account(maker_quote_account, &market.quote_mint, Some(&quote.maker))?;
+        Self::check_quote_token_account(taker_base_account, &market.base_mint, None)?;
+        Self::check_quote_token_account(taker_quote_account, &market.quote_mint, None)?;
+
+        let (fee_vault_address, _) = Market::find_fee_vault_address(program_id, market_account.key);
+        if fee_vault_address != *fee_vault.key {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Invalid fee vault address",
+            ));
+        }
+
+        let (rfq_authority_address, rfq_authority_bump) =
+            Market::find_rfq_authority_address(program_id, market_account.key);
+        if rfq_authority_address != *rfq_authority.key {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Invalid RFQ authority address",
+            ));
+        }
+
+        // Calculate amounts
+        let quote_amount = math::notional(quote.price, base_amount)?;
+        let taker_fee = market.calculate_taker_fee(quote_amount)?;
+
+        // The maker's leg is moved by the RFQ authority it delegated to
+        let base = Self::load_token_side(&market, accounts, false)?;
+        let quote_side = Self::load_token_side(&market, accounts, true)?;
+        let (maker_source, taker_destination, maker_amount, maker_side) = if quote.is_buy {
+            (maker_quote_account, taker_quote_account, quote_amount, &quote_side)
+        } else {
+            (maker_base_account, taker_base_account, base_amount, &base)
+        };
+        let (taker_source, maker_destination, taker_amount, taker_side) = if quote.is_buy {
+            (taker_base_account, maker_base_account, base_amount, &base)
+        } else {
+            (taker_quote_account, maker_quote_account, quote_amount, &quote_side)
+        };
+
+        Self::transfer_tokens(taker_side, taker_source, maker_destination, taker, taker_amount, &[])?;
+        Self::transfer_tokens(
+            maker_side,
+            maker_source,
+            taker_destination,
+            rfq_authority,
+            maker_amount,
+            &[&[RFQ_AUTHORITY_SEED, market_account.key.as_ref(), &[rfq_authority_bump]]],
+        )?;
+
+        // Collect the taker fee in quote tokens
+        if taker_fee > 0 {
+            Self::transfer_tokens(&quote_side, taker_quote_account, fee_vault, taker, taker_fee, &[])?;
+            market.fees_accrued = market.fees_accrued.saturating_add(taker_fee);
+            market.pack_into_slice(&mut market_account.data.borrow_mut());
+        }
+
+        // Record the fill
+        quote_fill.filled_quantity = filled_quantity;
+        quote_fill.pack_into_slice(&mut quote_fill_account.data.borrow_mut());
+
+        msg!(
+            "Firm quote {} of {} executed: {} base at {}, {} of {} filled",
+            quote.nonce,
+            quote.maker,
+            base_amount,
+            quote.price,
+            filled_quantity,
+            quote.quantity
+        );
+        Ok(())
+    }
+
+    // Check the mint, and optionally the owner, of a token account used by a firm quote
+    fn check_quote_token_account(
+        token_account: &AccountInfo,
+        mint: &Pubkey,
+        owner: Option<&Pubkey>,
+    ) -> ProgramResult {
+        let account = token::unpack_account(&token_account.data.borrow())
+            .map_err(|_| return_dex_error(DexError::InvalidTokenAccount, "Not a token account"))?;
+        if account.mint != *mint || owner.is_some_and(|owner| account.owner != *owner) {
+            return Err(return_dex_error(
+                DexError::InvalidTokenAccount,
+                "Token account does not match the quote",
+            ));
+        }
+
+        Ok(())
+    }
+
+    // Process set whitelist enabled instruction
+    fn process_set_whitelist_enabled(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        enabled: bool,
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let authority = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !authority.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Authority must sign",
+            ));
+        }
+
+        // Load market
+        let mut market = Self::load_market(program_id, market_account)?;
+
+        // Verify authority
+        if market.authority != *authority.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Not market authority",
+            ));
+        }
+
+        // Update market
+        market.whitelist_enabled = enabled;
+        market.pack_into_slice(&mut market_account.data.borrow_mut());
+        Self::emit_params_updated(market_account, authority.key, MarketParam::WhitelistEnabled(enabled))?;
+
+        msg!("Market whitelist {}", if enabled { "enabled" } else { "disabled" });
+        Ok(())
+    }
+
+    // Process add whitelist entry instruction
+    fn process_add_whitelist_entry(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        owner: Pubkey,
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let authority = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let whitelist_entry = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !authority.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Authority must sign",
+            ));
+        }
+
+        Self::check_system_program(system_program_account)?;
+
+        // Load market
+        let market = Self::load_market(program_id, market_account)?;
+
+        // Verify authority
+        if market.authority != *authority.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Not market authority",
+            ));
+        }
+
+        let (entry_address, bump) = WhitelistEntry::find_address(program_id, market_account.key, &owner);
+        if entry_address != *whitelist_entry.key {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Invalid whitelist entry address",
+            ));
+        }
+
+        if whitelist_entry.owner == program_id {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Owner already whitelisted",
+            ));
+        }
+
+        // Create the entry PDA, paid by the authority
+        let rent = Rent::get()?;
+        let space = WhitelistEntry::LEN;
+        let lamports = rent.minimum_balance(space);
+
+        invoke_signed(
+            &system_instruction::create_account(
+                authority.key,
+                whitelist_entry.key,
+                lamports,
+                space as u64,
+                program_id,
+            ),
+            &[
+                authority.clone(),
+                whitelist_entry.clone(),
+                system_program_account.clone(),
+            ],
+            &[&[WHITELIST_SEED, market_account.key.as_ref(), owner.as_ref(), &[bump]]],
+        )?;
+
+        let entry = WhitelistEntry {
+            is_initialized: true,
+            market: *market_account.key,
+            owner,
+        };
+        entry.pack_into_slice(&mut whitelist_entry.data.borrow_mut());
+
+        msg!("Owner {} whitelisted on market {}", owner, market_account.key);
+        Ok(())
+    }
+
+    // Process remove whitelist entry instruction
+    fn process_remove_whitelist_entry(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let authority = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let whitelist_entry = next_account_info(account_info_iter)?;
+        let recipient = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !authority.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Authority must sign",
+            ));
+        }
+
+        // Load market
+        let market = Self::load_market(program_id, market_account)?;
+
+        // Verify authority
+        if market.authority != *authority.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Not market authority",
+            ));
+        }
+
+        // Load entry
+        if whitelist_entry.owner != program_id {
+            return Err(return_dex_error(
+                DexError::InvalidAccountOwner,
+                "Whitelist entry not owned by program",
+            ));
+        }
+        let entry = WhitelistEntry::unpack_from_slice(&whitelist_entry.data.borrow())?;
+        if !entry.is_initialized {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Whitelist entry not initialized",
+            ));
+        }
+        if entry.market != *market_account.key {
+            return Err(return_dex_error(
+                DexError::AccountMismatch,
+                "Whitelist entry does not belong to market",
+            ));
+        }
+
+        // Close the entry account
+        let lamports = whitelist_entry.lamports();
+        **recipient.lamports.borrow_mut() = recipient
+            .lamports()
+            .checked_add(lamports)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        **whitelist_entry.lamports.borrow_mut() = 0;
+        whitelist_entry.data.borrow_mut().fill(0);
+
+        msg!("Owner {} removed from the whitelist of market {}", entry.owner, market_account.key);
+        Ok(())
+    }
+
+    // Check the current transaction against an instruction guard
+    fn check_instruction_guard(
+        program_id: &Pubkey,
+        guard: InstructionGuard,
+        instructions_sysvar: Option<&AccountInfo>,
+    ) -> ProgramResult {
+        if guard == InstructionGuard::Disabled {
+            return Ok(());
+        }
+
+        let instructions_sysvar = instructions_sysvar.ok_or_else(|| {
+            return_dex_error(
+                DexError::InstructionGuardViolation,
+                "Instructions sysvar required by market",
+            )
+        })?;
+
+        // A CPI shows up as a top-level instruction owned by another program
+        let current_index = instructions::load_current_index_checked(instructions_sysvar)?;
+        let current = instructions::load_instruction_at_checked(current_index as usize, instructions_sysvar)?;
+        if current.program_id != *program_id {
+            return Err(return_dex_error(
+                DexError::InstructionGuardViolation,
+                "Cross-program invocation not allowed",
+            ));
+        }
+
+        // Isolated markets allow no other instruction for this program in the transaction
+        if guard == InstructionGuard::Isolated {
+            let mut index = 0;
+            while let Ok(instruction) = instructions::load_instruction_at_checked(index, instructions_sysvar) {
+                if index != current_index as usize && instruction.program_id == *program_id {
+                    return Err(return_dex_error(
+                        DexError::InstructionGuardViolation,
+                        "Adjacent program instruction not allowed",
+                    ));
+                }
+                index += 1;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Process initialize program config instruction
+    fn process_initialize_program_config(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let admin = next_account_info(account_info_iter)?;
+        let config_account = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !admin.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Config admin must sign",
+            ));
+        }
+
+        Self::check_system_program(system_program_account)?;
+
+        let (config_address, bump) = ProgramConfig::find_address(program_id);
+        if config_address != *config_account.key {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Invalid program config address",
+            ));
+        }
+
+        if config_account.owner == program_id {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Program config already initialized",
+            ));
+        }
+
+        // Create the config PDA
+        let rent = Rent::get()?;
+        let space = ProgramConfig::LEN;
+        let lamports = rent.minimum_balance(space);
+
+        invoke_signed(
+            &system_instruction::create_account(
+                admin.key,
+                config_account.key,
+                lamports,
+                space as u64,
+                program_id,
+            ),
+            &[
+                admin.clone(),
+                config_account.clone(),
+                system_program_account.clone(),
+            ],
+            &[&[PROGRAM_CONFIG_SEED, &[bump]]],
+        )?;
+
+        // Initialize config state with permissionless defaults
+        let config = ProgramConfig {
+            is_initialized: true,
+            admin: *admin.key,
+            market_creation_mode: MarketCreationMode::Permissionless,
+            num_allowed_quote_mints: 0,
+            allowed_quote_mints: [Pubkey::default(); MAX_ALLOWED_QUOTE_MINTS],
+            treasury: *admin.key,
+            market_creation_fee_lamports: 0,
+            market_creation_fee_quote: 0,
+            trade_busting_enabled: false,
+            trade_bust_window_slots: 0,
+        };
+        config.pack_into_slice(&mut config_account.data.borrow_mut());
+
+        msg!("Program config initialized successfully");
+        Ok(())
+    }
+
+    // Process update program config instruction
+    fn process_update_program_config(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        market_creation_mode: MarketCreationMode,
+        allowed_quote_mints: &[Pubkey],
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let admin = next_account_info(account_info_iter)?;
+        let config_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !admin.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Config admin must sign",
+            ));
+        }
+
+        // Load config
+        let mut config = Self::load_program_config(program_id, config_account)?.ok_or_else(|| {
+            return_dex_error(DexError::InvalidAccountData, "Program config not initialized")
+        })?;
+
+        // Verify admin
+        if config.admin != *admin.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Not config admin",
+            ));
+        }
+
+        // Validate the whitelist
+        if allowed_quote_mints.len() > MAX_ALLOWED_QUOTE_MINTS {
+            return Err(return_dex_error(
+                DexError::InvalidInstructionData,
+                "Too many allowed quote mints",
+            ));
+        }
+
+        // Update config
+        config.market_creation_mode = market_creation_mode;
+        config.num_allowed_quote_mints = allowed_quote_mints.len() as u8;
+        config.allowed_quote_mints = [Pubkey::default(); MAX_ALLOWED_QUOTE_MINTS];
+        config.allowed_quote_mints[..allowed_quote_mints.len()].copy_from_slice(allowed_quote_mints);
+        config.pack_into_slice(&mut config_account.data.borrow_mut());
+
+        msg!("Program config updated successfully");
+        Ok(())
+    }
+
+    // Process set market creation fee instruction
+    fn process_set_market_creation_fee(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        treasury: Pubkey,
+        fee_lamports: u64,
+        fee_quote: u64,
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let admin = next_account_info(account_info_iter)?;
+        let config_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !admin.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Config admin must sign",
+            ));
+        }
+
+        // Load config
+        let mut config = Self::load_program_config(program_id, config_account)?.ok_or_else(|| {
+            return_dex_error(DexError::InvalidAccountData, "Program config not initialized")
+        })?;
+
+        // Verify admin
+        if config.admin != *admin.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Not config admin",
+            ));
+        }
+
+        // Update config
+        config.treasury = treasury;
+        config.market_creation_fee_lamports = fee_lamports;
+        config.market_creation_fee_quote = fee_quote;
+        config.pack_into_slice(&mut config_account.data.borrow_mut());
+
+        msg!("Market creation fee updated successfully");
+        Ok(())
+    }
+
+    // Process initialize fee vault instruction
+    fn process_initialize_fee_vault(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let payer = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let fee_vault = next_account_info(account_info_iter)?;
+        let quote_mint = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !payer.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Payer must sign",
+            ));
+        }
+
+        Self::check_token_program(token_program)?;
+        Self::check_system_program(system_program_account)?;
+
+        // Load market
+        let market = Self::load_market(program_id, market_account)?;
+
+        if market.quote_mint != *quote_mint.key {
+            return Err(return_dex_error(
+                DexError::InvalidTokenAccount,
+                "Fee vault must hold the quote mint",
+            ));
+        }
+
+        let (fee_vault_address, bump) = Market::find_fee_vault_address(program_id, market_account.key);
+        if fee_vault_address != *fee_vault.key {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Invalid fee vault address",
+            ));
+        }
+
+        let token_program = Self::load_token_side(&market, accounts, true)?.token_program;
+        if fee_vault.owner == token_program.key {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Fee vault already initialized",
+            ));
+        }
+
+        // Create the fee vault PDA as a token account that is its own owner
+        let rent = Rent::get()?;
+        let space = token::account_len(&quote_mint.data.borrow())?;
+        let lamports = rent.minimum_balance(space);
+
+        invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                fee_vault.key,
+                lamports,
+                space as u64,
+                token_program.key,
+            ),
+            &[
+                payer.clone(),
+                fee_vault.clone(),
+                system_program_account.clone(),
+            ],
+            &[&[FEE_VAULT_SEED, market_account.key.as_ref(), &[bump]]],
+        )?;
+
+        invoke(
+            &token_2022_instruction::initialize_account3(
+                token_program.key,
+                fee_vault.key,
+                quote_mint.key,
+                fee_vault.key,
+            )?,
+            &[fee_vault.clone(), quote_mint.clone(), token_program.clone()],
+        )?;
+
+        msg!("Fee vault initialized successfully");
+        Ok(())
+    }
+
+    // Process initialize market vaults instruction
+    fn process_initialize_market_vaults(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let payer = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let base_vault = next_account_info(account_info_iter)?;
+        let quote_vault = next_account_info(account_info_iter)?;
+        let market_signer = next_account_info(account_info_iter)?;
+        let base_mint = next_account_info(account_info_iter)?;
+        let quote_mint = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !payer.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Payer must sign",
+            ));
+        }
+
+        Self::check_token_program(token_program)?;
+        Self::check_system_program(system_program_account)?;
+
+        // Load market
+        let market = Self::load_market(program_id, market_account)?;
+
+        if market.base_mint != *base_mint.key || market.quote_mint != *quote_mint.key {
+            return Err(return_dex_error(
+                DexError::InvalidMint,
+                "Vault mints must match the market",
+            ));
+        }
+
+        if market.signer_address(program_id, market_account.key)? != *market_signer.key {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Invalid market signer",
+            ));
+        }
+
+        let rent = Rent::get()?;
+        let vaults = [
+            (BASE_VAULT_SEED, Market::find_base_vault_address(program_id, market_account.key), base_vault, base_mint),
+            (QUOTE_VAULT_SEED, Market::find_quote_vault_address(program_id, market_account.key), quote_vault, quote_mint),
+        ];
+        for (is_quote, (seed, (vault_address, bump), vault, mint)) in vaults.into_iter().enumerate() {
+            // Each vault is created under the token program of its mint
+            let token_program = Self::load_token_side(&market, accounts, is_quote == 1)?.token_program;
+            let space = token::account_len(&mint.data.borrow())?;
+            let lamports = rent.minimum_balance(space);
+            if vault_address != *vault.key {
+                return Err(return_dex_error(
+                    DexError::InvalidAccountData,
+                    "Invalid escrow vault address",
+                ));
+            }
+
+            if vault.owner == token_program.key {
+                return Err(return_dex_error(
+                    DexError::InvalidAccountData,
+                    "Escrow vault already initialized",
+                ));
+            }
+
+            // Create the vault PDA as a token account owned by the market signer
+            invoke_signed(
+                &system_instruction::create_account(
+                    payer.key,
+                    vault.key,
+                    lamports,
+                    space as u64,
+                    token_program.key,
+                ),
+                &[
+                    payer.clone(),
+                    vault.clone(),
+                    system_program_account.clone(),
+                ],
+                &[&[seed, market_account.key.as_ref(), &[bump]]],
+            )?;
+
+            invoke(
+                &token_2022_instruction::initialize_account3(
+                    token_program.key,
+                    vault.key,
+                    mint.key,
+                    market_signer.key,
+                )?,
+                &[vault.clone(), mint.clone(), token_program.clone()],
+            )?;
+        }
+
+        msg!("Market vaults initialized successfully");
+        Ok(())
+    }
+
+    // Process set minimum quote notional instruction
+    fn process_set_min_quote_notional(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        min_quote_notional: u64,
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let authority = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !authority.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Authority must sign",
+            ));
+        }
+
+        // Load market
+        let mut market = Self::load_market(program_id, market_account)?;
+
+        // Verify authority
+        if market.authority != *authority.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Not market authority",
+            ));
+        }
+
+        // Update market
+        market.min_quote_notional = min_quote_notional;
+        market.pack_into_slice(&mut market_account.data.borrow_mut());
+        Self::emit_params_updated(market_account, authority.key, MarketParam::MinQuoteNotional(min_quote_notional))?;
+
+        msg!("Minimum quote notional updated successfully");
+        Ok(())
+    }
+
+    // Process deposit instruction
+    fn process_deposit(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        is_quote: bool,
+        amount: u64,
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let owner = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let open_orders_account = next_account_info(account_info_iter)?;
+        let owner_token_account = next_account_info(account_info_iter)?;
+        let escrow_vault = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !owner.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Owner must sign",
+            ));
+        }
+
+        Self::check_token_program(token_program)?;
+        Self::check_system_program(system_program_account)?;
+
+        // Load market
+        let mut market = Self::load_market(program_id, market_account)?;
+
+        // Deposits only fund new orders, so they are only accepted while the market is active
+        Self::check_market_active(&market, "Market does not accept deposits")?;
+
+        if amount == 0 {
+            return Err(return_dex_error(
+                DexError::InvalidInstructionData,
+                "Deposit amount must be positive",
+            ));
+        }
+
+        Self::check_owner_token_account(&market, owner.key, is_quote, owner_token_account)?;
+
+        let (vault_address, _) = Market::find_escrow_vault_address(program_id, market_account.key, is_quote);
+        if vault_address != *escrow_vault.key {
+            return Err(return_dex_error_expected(
+                DexError::InvalidAccountData,
+                "Invalid escrow vault",
+                vault_address,
+                escrow_vault.key,
+            ));
+        }
+
+        // Create the OpenOrders PDA on the first deposit, paid by the owner
+        let mut open_orders = if open_orders_account.owner == program_id {
+            Self::load_open_orders(program_id, market_account.key, owner.key, open_orders_account)?
+        } else {
+            let (open_orders_address, bump) = OpenOrders::find_address(program_id, market_account.key, owner.key);
+            if open_orders_address != *open_orders_account.key {
+                return Err(return_dex_error(
+                    DexError::InvalidAccountData,
+                    "Invalid OpenOrders address",
+                ));
+            }
+
+            let rent = Rent::get()?;
+            let space = OpenOrders::LEN;
+            let lamports = rent.minimum_balance(space);
+
+            invoke_signed(
+                &system_instruction::create_account(
+                    owner.key,
+                    open_orders_account.key,
+                    lamports,
+                    space as u64,
+                    program_id,
+                ),
+                &[
+                    owner.clone(),
+                    open_orders_account.clone(),
+                    system_program_account.clone(),
+                ],
+                &[&[OPEN_ORDERS_SEED, market_account.key.as_ref(), owner.key.as_ref(), &[bump]]],
+            )?;
+
+            OpenOrders {
+                is_initialized: true,
+                market: *market_account.key,
+                owner: *owner.key,
+                base_free: 0,
+                quote_free: 0,
+            }
+        };
+
+        // Credit the free balance and move the tokens into the escrow vault
+        Self::credit_free_balance(&mut market, &mut open_orders, is_quote, amount)?;
+        open_orders.pack_into_slice(&mut open_orders_account.data.borrow_mut());
+        market.pack_into_slice(&mut market_account.data.borrow_mut());
+
+        let token_side = Self::load_token_side(&market, accounts, is_quote)?;
+        Self::transfer_tokens_in(&token_side, owner_token_account, escrow_vault, owner, amount, &[])?;
+
+        msg!("Deposited {} into the free balance", amount);
+        Ok(())
+    }
+
+    // Process withdraw instruction
+    fn process_withdraw(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        is_quote: bool,
+        amount: u64,
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let owner = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let open_orders_account = next_account_info(account_info_iter)?;
+        let destination = next_account_info(account_info_iter)?;
+        let escrow_vault = next_account_info(account_info_iter)?;
+        let market_signer = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !owner.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Owner must sign",
+            ));
+        }
+
+        Self::check_token_program(token_program)?;
+
+        // Load market and the owner's balances; withdrawals work in every market status
+        let mut market = Self::load_market(program_id, market_account)?;
+        let mut open_orders = Self::load_open_orders(program_id, market_account.key, owner.key, open_orders_account)?;
+
+        let (vault_address, _) = Market::find_escrow_vault_address(program_id, market_account.key, is_quote);
+        if vault_address != *escrow_vault.key {
+            return Err(return_dex_error_expected(
+                DexError::InvalidAccountData,
+                "Invalid escrow vault",
+                vault_address,
+                escrow_vault.key,
+            ));
+        }
+        if market.signer_address(program_id, market_account.key)? != *market_signer.key {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Invalid market signer",
+            ));
+        }
+
+        // Debit the free balance and pay it out of the escrow vault
+        Self::debit_free_balance(&mut market, &mut open_orders, is_quote, amount)?;
+        open_orders.pack_into_slice(&mut open_orders_account.data.borrow_mut());
+        market.pack_into_slice(&mut market_account.data.borrow_mut());
+
+        Self::transfer_tokens(
+            &Self::load_token_side(&market, accounts, is_quote)?,
+            escrow_vault,
+            destination,
+            market_signer,
+            amount,
+            &[&[market_account.key.as_ref(), &[market.signer_bump]]],
+        )?;
+
+        msg!("Withdrew {} from the free balance", amount);
+        Ok(())
+    }
+
+    // Process initialize TWAP oracle instruction
+    fn process_initialize_twap_oracle(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        ema_period_seconds: u64,
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let authority = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let twap_oracle_account = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !authority.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Authority must sign",
+            ));
+        }
+
+        Self::check_system_program(system_program_account)?;
+
+        // Load market
+        let mut market = Self::load_market(program_id, market_account)?;
+
+        // Verify authority
+        if market.authority != *authority.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Not market authority",
+            ));
+        }
+        if market.twap_oracle_enabled {
+            return Err(return_dex_error(
+                DexError::AlreadyInitialized,
+                "TWAP oracle already initialized",
+            ));
+        }
+        if ema_period_seconds == 0 {
+            return Err(return_dex_error(
+                DexError::InvalidInstructionData,
+                "EMA period must be positive",
+            ));
+        }
+
+        let (twap_oracle_address, bump) = TwapOracle::find_address(program_id, market_account.key);
+        if twap_oracle_address != *twap_oracle_account.key {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Invalid TWAP oracle address",
+            ));
+        }
+
+        // Create the oracle PDA, paid by the authority
+        let rent = Rent::get()?;
+        let space = TwapOracle::LEN;
+        let lamports = rent.minimum_balance(space);
+
+        invoke_signed(
+            &system_instruction::create_account(
+                authority.key,
+                twap_oracle_account.key,
+                lamports,
+                space as u64,
+                program_id,
+            ),
+            &[
+                authority.clone(),
+                twap_oracle_account.clone(),
+                system_program_account.clone(),
+            ],
+            &[&[TWAP_ORACLE_SEED, market_account.key.as_ref(), &[bump]]],
+        )?;
+
+        let twap_oracle = TwapOracle {
+            is_initialized: true,
+            market: *market_account.key,
+            ema_period_seconds,
+            last_price: 0,
+            last_update: 0,
+            price_cumulative: 0,
+            ema_price: 0,
+            fill_count: 0,
+        };
+        twap_oracle.pack_into_slice(&mut twap_oracle_account.data.borrow_mut());
+
+        market.twap_oracle_enabled = true;
+        market.pack_into_slice(&mut market_account.data.borrow_mut());
+
+        msg!("TWAP oracle initialized with an EMA period of {} seconds", ema_period_seconds);
+        Ok(())
+    }
+
+    // Process migrate account instruction
+    fn process_migrate_account(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let payer = next_account_info(account_info_iter)?;
+        let account = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !payer.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Payer must sign",
+            ));
+        }
+
+        Self::check_system_program(system_program_account)?;
+
+        if account.owner != program_id {
+            return Err(return_dex_error(
+                DexError::InvalidAccountOwner,
+                "Account not owned by program",
+            ));
+        }
+
+        // The version byte follows the fields of the legacy layout
+        let (len, version_offset) = match account.data_len() {
+            Market::LEGACY_LEN => (Market::LEN, Market::LEGACY_LEN),
+            Order::LEGACY_LEN => (Order::LEN, Order::LEGACY_LEN),
+            Market::LEN | Order::LEN => {
+                msg!("Account {} already has the current layout", account.key);
+                return Ok(());
+            }
+            _ => {
+                return Err(return_dex_error(
+                    DexError::InvalidAccountData,
+                    "Account is not a market or order",
+                ));
+            }
+        };
+
+        // Top up the rent of the larger account, paid by the payer
+        let lamports = Rent::get()?.minimum_balance(len).saturating_sub(account.lamports());
+        if lamports > 0 {
+            invoke(
+                &system_instruction::transfer(payer.key, account.key, lamports),
+                &[payer.clone(), account.clone(), system_program_account.clone()],
+            )?;
+        }
+
+        // Grow the account with zeroed reserved bytes and stamp the version
+        account.realloc(len, true)?;
+        account.data.borrow_mut()[version_offset] = ACCOUNT_VERSION;
+
+        msg!("Account {} migrated to layout version {}", account.key, ACCOUNT_VERSION);
+        Ok(())
+    }
+
+    // Load a market's TWAP oracle
+    // Process initialize event queue instruction
+    fn process_initialize_event_queue(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let authority = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let event_queue_account = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !authority.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Authority must sign",
+            ));
+        }
+
+        Self::check_system_program(system_program_account)?;
+
+        // Load market
+        let mut market = Self::load_market(program_id, market_account)?;
+
+        // Verify authority
+        if market.authority != *authority.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Not market authority",
+            ));
+        }
+        if market.has_flag(MARKET_FLAG_EVENT_QUEUE) {
+            return Err(return_dex_error(
+                DexError::AlreadyInitialized,
+                "Event queue already initialized",
+            ));
+        }
+
+        let (event_queue_address, bump) = EventQueue::find_address(program_id, market_account.key);
+        if event_queue_address != *event_queue_account.key {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Invalid event queue address",
+            ));
+        }
+
+        // Create the queue PDA, paid by the authority; the zeroed buffer is an empty queue
+        let rent = Rent::get()?;
+        let space = EventQueue::LEN;
+        let lamports = rent.minimum_balance(space);
+
+        invoke_signed(
+            &system_instruction::create_account(
+                authority.key,
+                event_queue_account.key,
+                lamports,
+                space as u64,
+                program_id,
+            ),
+            &[
+                authority.clone(),
+                event_queue_account.clone(),
+                system_program_account.clone(),
+            ],
+            &[&[EVENT_QUEUE_SEED, market_account.key.as_ref(), &[bump]]],
+        )?;
+
+        // Sequence numbers continue from the market's fills so far
+        {
+            let mut data = event_queue_account.data.borrow_mut();
+            let event_queue = load_zero_copy_mut::<EventQueue>(&mut data)?;
+            event_queue.is_initialized = true;
+            event_queue.market = *market_account.key;
+            event_queue.next_seq_num = market.fill_seq_num;
+        }
+
+        market.flags |= MARKET_FLAG_EVENT_QUEUE;
+        market.pack_into_slice(&mut market_account.data.borrow_mut());
+
+        msg!("Event queue initialized with a capacity of {} events", EVENT_QUEUE_CAPACITY);
+        Ok(())
+    }
+
+    // Process consume events instruction
+    fn process_consume_events(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        seq_num: u64,
+        limit: u16,
+        observed_slot: u64,
+        max_staleness_slots: u64,
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let authority = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let event_queue_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !authority.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Authority must sign",
+            ));
+        }
+
+        // Load market
+        let market = Self::load_market(program_id, market_account)?;
+
+        // Verify authority
+        if market.authority != *authority.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Not market authority",
+            ));
+        }
+
+        // A crank transaction that lands long after the crank read the queue acts on a view of
+        // it that may no longer hold
+        if max_staleness_slots != 0 {
+            let deadline = observed_slot.saturating_add(max_staleness_slots);
+            let slot = Clock::get()?.slot;
+            if slot > deadline {
+                return Err(return_dex_error_expected(
+                    DexError::TransactionStale,
+                    "Crank transaction landed after its staleness limit",
+                    format_args!("a slot at or before {}", deadline),
+                    slot,
+                ));
+            }
+        }
+
+        let mut event_queue = Self::load_event_queue_mut(program_id, market_account, event_queue_account)?;
+
+        // The crank must start where the queue starts, so no event is consumed unprocessed
+        if event_queue.first_seq_num() != seq_num {
+            msg!("Oldest queued event is {}, not {}", event_queue.first_seq_num(), seq_num);
+            return Err(return_dex_error(
+                DexError::EventSequenceMismatch,
+                "Consumed events must start at the oldest queued event",
+            ));
+        }
+
+        let consumed = event_queue.pop(limit as u64);
+
+        msg!("Consumed {} events from sequence number {}", consumed, seq_num);
+        Ok(())
+    }
+
+    // Process rescue tokens instruction
+    fn process_rescue_tokens(program_id: &Pubkey, accounts: &[AccountInfo], is_quote: bool) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let authority = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let escrow_vault = next_account_info(account_info_iter)?;
+        let destination = next_account_info(account_info_iter)?;
+        let market_signer = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !authority.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Authority must sign",
+            ));
+        }
+
+        Self::check_token_program(token_program)?;
+
+        // Load market
+        let market = Self::load_market(program_id, market_account)?;
+
+        // Verify authority
+        if market.authority != *authority.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Not market authority",
+            ));
+        }
+
+        let (vault_address, _) = Market::find_escrow_vault_address(program_id, market_account.key, is_quote);
+        if vault_address != *escrow_vault.key {
+            return Err(return_dex_error_expected(
+                DexError::InvalidAccountData,
+                "Invalid escrow vault",
+                vault_address,
+                escrow_vault.key,
+            ));
+        }
+        if market.signer_address(program_id, market_account.key)? != *market_signer.key {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Invalid market signer",
+            ));
+        }
+
+        // Only the balance above what orders and free balances hold in the vault is surplus
+        let balance = token::unpack_account(&escrow_vault.data.borrow())
+            .map_err(|_| return_dex_error(DexError::InvalidTokenAccount, "Escrow vault not initialized"))?
+            .amount;
+        let obligation = market.escrow_obligation(is_quote)?;
+        let surplus = balance.saturating_sub(obligation);
+        if surplus == 0 {
+            return Err(return_dex_error_expected(
+                DexError::InsufficientFunds,
+                "Escrow vault holds no surplus to rescue",
+                format_args!("a balance above {}", obligation),
+                balance,
+            ));
+        }
+
+        Self::transfer_tokens(
+            &Self::load_token_side(&market, accounts, is_quote)?,
+            escrow_vault,
+            destination,
+            market_signer,
+            surplus,
+            &[&[market_account.key.as_ref(), &[market.signer_bump]]],
+        )?;
+
+        msg!("Rescued {} tokens above the {} owed by the escrow vault", surplus, obligation);
+        Ok(())
+    }
+
+    // Process initialize pool instruction
+    fn process_initialize_pool(program_id: &Pubkey, accounts: &[AccountInfo], fee_bps: u16) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let authority = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let pool_account = next_account_info(account_info_iter)?;
+        let base_vault = next_account_info(account_info_iter)?;
+        let quote_vault = next_account_info(account_info_iter)?;
+        let lp_mint = next_account_info(account_info_iter)?;
+        let base_mint = next_account_info(account_info_iter)?;
+        let quote_mint = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !authority.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Authority must sign",
+            ));
+        }
+
+        Self::check_token_program(token_program)?;
+        Self::check_system_program(system_program_account)?;
+
+        // Load market
+        let market = Self::load_market(program_id, market_account)?;
+
+        // Verify authority
+        if market.authority != *authority.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Not market authority",
+            ));
+        }
+
+        // A fee of the whole input would leave nothing to swap
+        if fee_bps as u64 >= math::BPS_DENOMINATOR {
+            return Err(return_dex_error_expected(
+                DexError::InvalidInstructionData,
+                "Invalid pool fee",
+                format_args!("below {} bps", math::BPS_DENOMINATOR),
+                fee_bps,
+            ));
+        }
+
+        if market.base_mint != *base_mint.key || market.quote_mint != *quote_mint.key {
+            return Err(return_dex_error(
+                DexError::InvalidMint,
+                "Pool mints must match the market",
+            ));
+        }
+
+        let (pool_address, bump) = Pool::find_address(program_id, market_account.key);
+        if pool_address != *pool_account.key {
+            return Err(return_dex_error_expected(
+                DexError::InvalidAccountData,
+                "Invalid pool address",
+                pool_address,
+                pool_account.key,
+            ));
+        }
+        if pool_account.owner == program_id {
+            return Err(return_dex_error(
+                DexError::AlreadyInitialized,
+                "Pool already initialized",
+            ));
+        }
+
+        // Create the pool PDA, paid by the authority
+        let rent = Rent::get()?;
+        invoke_signed(
+            &system_instruction::create_account(
+                authority.key,
+                pool_account.key,
+                rent.minimum_balance(Pool::LEN),
+                Pool::LEN as u64,
+                program_id,
+            ),
+            &[
+                authority.clone(),
+                pool_account.clone(),
+                system_program_account.clone(),
+            ],
+            &[&[POOL_SEED, market_account.key.as_ref(), &[bump]]],
+        )?;
+
+        let vaults = [
+            (POOL_BASE_VAULT_SEED, base_vault, base_mint),
+            (POOL_QUOTE_VAULT_SEED, quote_vault, quote_mint),
+        ];
+        for (is_quote, (seed, vault, mint)) in vaults.into_iter().enumerate() {
+            let (vault_address, vault_bump) = Pool::find_vault_address(program_id, market_account.key, is_quote == 1);
+            if vault_address != *vault.key {
+                return Err(return_dex_error_expected(
+                    DexError::InvalidAccountData,
+                    "Invalid pool vault address",
+                    vault_address,
+                    vault.key,
+                ));
+            }
+
+            // Create the vault PDA as a token account of its mint's program owned by the pool
+            let vault_token_program = Self::load_token_side(&market, accounts, is_quote == 1)?.token_program;
+            let space = token::account_len(&mint.data.borrow())?;
+            invoke_signed(
+                &system_instruction::create_account(
+                    authority.key,
+                    vault.key,
+                    rent.minimum_balance(space),
+                    space as u64,
+                    vault_token_program.key,
+                ),
+                &[
+                    authority.clone(),
+                    vault.clone(),
+                    system_program_account.clone(),
+                ],
+                &[&[seed, market_account.key.as_ref(), &[vault_bump]]],
+            )?;
+
+            invoke(
+                &token_2022_instruction::initialize_account3(
+                    vault_token_program.key,
+                    vault.key,
+                    mint.key,
+                    pool_account.key,
+                )?,
+                &[vault.clone(), mint.clone(), vault_token_program.clone()],
+            )?;
+        }
+
+        // Create the LP mint PDA with the pool as its mint authority
+        let (lp_mint_address, lp_mint_bump) = Pool::find_lp_mint_address(program_id, market_account.key);
+        if lp_mint_address != *lp_mint.key {
+            return Err(return_dex_error_expected(
+                DexError::InvalidAccountData,
+                "Invalid LP mint address",
+                lp_mint_address,
+                lp_mint.key,
+            ));
+        }
+
+        let space = spl_token_2022::state::Mint::LEN;
+        invoke_signed(
+            &system_instruction::create_account(
+                authority.key,
+                lp_mint.key,
+                rent.minimum_balance(space),
+                space as u64,
+                token_program.key,
+            ),
+            &[
+                authority.clone(),
+                lp_mint.clone(),
+                system_program_account.clone(),
+            ],
+            &[&[POOL_LP_MINT_SEED, market_account.key.as_ref(), &[lp_mint_bump]]],
+        )?;
+
+        invoke(
+            &token_2022_instruction::initialize_mint2(
+                token_program.key,
+                lp_mint.key,
+                pool_account.key,
+                None,
+                market.base_decimals,
+            )?,
+            &[lp_mint.clone(), token_program.clone()],
+        )?;
+
+        let pool = Pool {
+            is_initialized: true,
+            market: *market_account.key,
+            lp_mint: *lp_mint.key,
+            bump,
+            fee_bps,
+            base_reserve: 0,
+            quote_reserve: 0,
+            lp_supply: 0,
+        };
+        pool.pack_into_slice(&mut pool_account.data.borrow_mut());
+
+        msg!("Pool initialized with a fee of {} bps", fee_bps);
+        Ok(())
+    }
+
+    // Process add liquidity instruction
+    fn process_add_liquidity(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        max_base_amount: u64,
+        max_quote_amount: u64,
+        min_lp_amount: u64,
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let owner = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let pool_account = next_account_info(account_info_iter)?;
+        let base_vault = next_account_info(account_info_iter)?;
+        let quote_vault = next_account_info(account_info_iter)?;
+        let lp_mint = next_account_info(account_info_iter)?;
+        let owner_base_account = next_account_info(account_info_iter)?;
+        let owner_quote_account = next_account_info(account_info_iter)?;
+        let lp_token_account = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !owner.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Owner must sign",
+            ));
+        }
+
+        Self::check_token_program(token_program)?;
+
+        // Load market and pool
+        let market = Self::load_market(program_id, market_account)?;
+        Self::check_market_active(&market, "Market does not accept liquidity")?;
+        let mut pool = Self::load_pool(program_id, market_account, pool_account)?;
+        Self::check_pool_vaults(program_id, market_account, base_vault, quote_vault)?;
+        Self::check_lp_mint(&pool, lp_mint, token_program)?;
+
+        if max_base_amount == 0 || max_quote_amount == 0 {
+            return Err(return_dex_error(
+                DexError::InvalidInstructionData,
+                "Deposit amounts must be positive",
+            ));
+        }
+
+        Self::check_owner_token_account(&market, owner.key, false, owner_base_account)?;
+        Self::check_owner_token_account(&market, owner.key, true, owner_quote_account)?;
+
+        let (lp_amount, base_amount, quote_amount) = pool.deposit_amounts(max_base_amount, max_quote_amount)?;
+        if lp_amount == 0 || lp_amount < min_lp_amount {
+            return Err(return_dex_error_expected(
+                DexError::SlippageExceeded,
+                "Deposit mints too few LP tokens",
+                format_args!("at least {}", min_lp_amount.max(1)),
+                lp_amount,
+            ));
+        }
+
+        // The first deposit also counts the LP tokens kept unminted
+        let locked_lp_amount = if pool.lp_supply == 0 {
+            POOL_MINIMUM_LIQUIDITY
+        } else {
+            0
+        };
+        pool.base_reserve = pool.base_reserve.checked_add(base_amount).ok_or(DexError::ArithmeticOverflow)?;
+        pool.quote_reserve = pool.quote_reserve.checked_add(quote_amount).ok_or(DexError::ArithmeticOverflow)?;
+        pool.lp_supply = pool
+            .lp_supply
+            .checked_add(lp_amount + locked_lp_amount)
+            .ok_or(DexError::ArithmeticOverflow)?;
+        pool.pack_into_slice(&mut pool_account.data.borrow_mut());
+
+        // Move the deposit into the reserves and mint the LP tokens
+        let base_side = Self::load_token_side(&market, accounts, false)?;
+        Self::transfer_tokens_in(&base_side, owner_base_account, base_vault, owner, base_amount, &[])?;
+        let quote_side = Self::load_token_side(&market, accounts, true)?;
+        Self::transfer_tokens_in(&quote_side, owner_quote_account, quote_vault, owner, quote_amount, &[])?;
+
+        invoke_signed(
+            &token_2022_instruction::mint_to(
+                token_program.key,
+                lp_mint.key,
+                lp_token_account.key,
+                pool_account.key,
+                &[],
+                lp_amount,
+            )?,
+            &[
+                lp_mint.clone(),
+                lp_token_account.clone(),
+                pool_account.clone(),
+                token_program.clone(),
+            ],
+            &[&[POOL_SEED, market_account.key.as_ref(), &[pool.bump]]],
+        )?;
+
+        msg!("Deposited {} base and {} quote for {} LP tokens", base_amount, quote_amount, lp_amount);
+        Ok(())
+    }
+
+    // Process remove liquidity instruction
+    fn process_remove_liquidity(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        lp_amount: u64,
+        min_base_amount: u64,
+        min_quote_amount: u64,
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let owner = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let pool_account = next_account_info(account_info_iter)?;
+        let base_vault = next_account_info(account_info_iter)?;
+        let quote_vault = next_account_info(account_info_iter)?;
+        let lp_mint = next_account_info(account_info_iter)?;
+        let lp_token_account = next_account_info(account_info_iter)?;
+        let base_destination = next_account_info(account_info_iter)?;
+        let quote_destination = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !owner.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Owner must sign",
+            ));
+        }
+
+        Self::check_token_program(token_program)?;
+
+        // Load market and pool; withdrawals work in every market status
+        let market = Self::load_market(program_id, market_account)?;
+        let mut pool = Self::load_pool(program_id, market_account, pool_account)?;
+        Self::check_pool_vaults(program_id, market_account, base_vault, quote_vault)?;
+        Self::check_lp_mint(&pool, lp_mint, token_program)?;
+
+        if lp_amount == 0 {
+            return Err(return_dex_error(
+                DexError::InvalidInstructionData,
+                "LP amount must be positive",
+            ));
+        }
+
+        let (base_amount, quote_amount) = pool.withdraw_amounts(lp_amount)?;
+        if base_amount < min_base_amount || quote_amount < min_quote_amount {
+            return Err(return_dex_error_expected(
+                DexError::SlippageExceeded,
+                "Withdrawal pays out too little",
+                format_args!("at least {} base and {} quote", min_base_amount, min_quote_amount),
+                format_args!("{} base and {} quote", base_amount, quote_amount),
+            ));
+        }
+
+        // The burn fails unless the owner holds the LP tokens, so the supply covers them
+        pool.base_reserve = pool.base_reserve.checked_sub(base_amount).ok_or(DexError::ArithmeticOverflow)?;
+        pool.quote_reserve = pool.quote_reserve.checked_sub(quote_amount).ok_or(DexError::ArithmeticOverflow)?;
+        pool.lp_supply = pool.lp_supply.checked_sub(lp_amount).ok_or(DexError::ArithmeticOverflow)?;
+        pool.pack_into_slice(&mut pool_account.data.borrow_mut());
+
+        invoke(
+            &token_2022_instruction::burn(
+                token_program.key,
+                lp_token_account.key,
+                lp_mint.key,
+                owner.key,
+                &[],
+                lp_amount,
+            )?,
+            &[
+                lp_token_account.clone(),
+                lp_mint.clone(),
+                owner.clone(),
+                token_program.clone(),
+            ],
+        )?;
+
+        // Pay out the share of the reserves, signed by the pool that owns the vaults
+        let signer_seeds: &[&[&[u8]]] = &[&[POOL_SEED, market_account.key.as_ref(), &[pool.bump]]];
+        let base_side = Self::load_token_side(&market, accounts, false)?;
+        Self::transfer_tokens(&base_side, base_vault, base_destination, pool_account, base_amount, signer_seeds)?;
+        let quote_side = Self::load_token_side(&market, accounts, true)?;
+        Self::transfer_tokens(&quote_side, quote_vault, quote_destination, pool_account, quote_amount, signer_seeds)?;
+
+        msg!("Burned {} LP tokens for {} base and {} quote", lp_amount, base_amount, quote_amount);
+        Ok(())
+    }
+
+    // Process swap against pool instruction
+    fn process_swap_against_pool(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        is_buy: bool,
+        amount_in: u64,
+        min_amount_out: u64,
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let owner = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let pool_account = next_account_info(account_info_iter)?;
+        let base_vault = next_account_info(account_info_iter)?;
+        let quote_vault = next_account_info(account_info_iter)?;
+        let owner_base_account = next_account_info(account_info_iter)?;
+        let owner_quote_account = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !owner.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Owner must sign",
+            ));
+        }
+
+        Self::check_token_program(token_program)?;
+
+        // Load market and pool
+        let market = Self::load_market(program_id, market_account)?;
+        Self::check_market_active(&market, "Market does not accept swaps")?;
+        let mut pool = Self::load_pool(program_id, market_account, pool_account)?;
+        Self::check_pool_vaults(program_id, market_account, base_vault, quote_vault)?;
+
+        if amount_in == 0 {
+            return Err(return_dex_error(
+                DexError::InvalidInstructionData,
+                "Swap amount must be positive",
+            ));
+        }
+
+        Self::check_owner_token_account(&market, owner.key, false, owner_base_account)?;
+        Self::check_owner_token_account(&market, owner.key, true, owner_quote_account)?;
+
+        let amount_out = pool.swap(is_buy, amount_in)?;
+        if amount_out == 0 || amount_out < min_amount_out {
+            return Err(return_dex_error_expected(
+                DexError::SlippageExceeded,
+                "Swap pays out too little",
+                format_args!("at least {}", min_amount_out.max(1)),
+                amount_out,
+            ));
+        }
+        pool.pack_into_slice(&mut pool_account.data.borrow_mut());
+
+        let (source, vault_in, vault_out, destination) = if is_buy {
+            (owner_quote_account, quote_vault, base_vault, owner_base_account)
+        } else {
+            (owner_base_account, base_vault, quote_vault, owner_quote_account)
+        };
+        Self::transfer_tokens_in(&Self::load_token_side(&market, accounts, is_buy)?, source, vault_in, owner, amount_in, &[])?;
+        Self::transfer_tokens(
+            &Self::load_token_side(&market, accounts, !is_buy)?,
+            vault_out,
+            destination,
+            pool_account,
+            amount_out,
+            &[&[POOL_SEED, market_account.key.as_ref(), &[pool.bump]]],
+        )?;
+
+        msg!("Swapped {} into the pool for {}", amount_in, amount_out);
+        Ok(())
+    }
+
+    // Process route swap instruction
+    fn process_route_swap(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        is_buy: bool,
+        amount_in: u64,
+        min_amount_out: u64,
+        num_orders: u8,
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let owner = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let pool_account = next_account_info(account_info_iter)?;
+        let pool_base_vault = next_account_info(account_info_iter)?;
+        let pool_quote_vault = next_account_info(account_info_iter)?;
+        let owner_base_account = next_account_info(account_info_iter)?;
+        let owner_quote_account = next_account_info(account_info_iter)?;
+        let base_vault = next_account_info(account_info_iter)?;
+        let quote_vault = next_account_info(account_info_iter)?;
+        let market_signer = next_account_info(account_info_iter)?;
+        let fee_vault = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let order_accounts = accounts
+            .get(12..12 + 4 * num_orders as usize)
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        let find = |key: &Pubkey| accounts.iter().find(|account| account.key == key);
+
+        // Verify accounts
+        if !owner.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Owner must sign",
+            ));
+        }
+
+        Self::check_token_program(token_program)?;
+
+        // Load market and pool
+        // Fills update the market in place
+        let mut market = Self::load_market_mut(program_id, market_account)?;
+        Self::check_market_active(&market, "Market does not accept swaps")?;
+        Self::check_continuous_matching(&market)?;
+        Self::check_instruction_guard(program_id, market.instruction_guard, find(&instructions::id()))?;
+        if market.whitelist_enabled {
+            let (entry_address, _) = WhitelistEntry::find_address(program_id, market_account.key, owner.key);
+            Self::check_whitelist_entry(program_id, market_account.key, owner.key, find(&entry_address))?;
+        }
+        let mut pool = Self::load_pool(program_id, market_account, pool_account)?;
+        Self::check_pool_vaults(program_id, market_account, pool_base_vault, pool_quote_vault)?;
+
+        if amount_in == 0 {
+            return Err(return_dex_error(
+                DexError::InvalidInstructionData,
+                "Swap amount must be positive",
+            ));
+        }
+
+        Self::check_owner_token_account(&market, owner.key, false, owner_base_account)?;
+        Self::check_owner_token_account(&market, owner.key, true, owner_quote_account)?;
+        if Market::find_fee_vault_address(program_id, market_account.key).0 != *fee_vault.key {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Invalid fee vault address",
+            ));
+        }
+        let escrow = Self::load_escrow(program_id, accounts, market_account, &market, base_vault, quote_vault, market_signer)?;
+
+        // Fills are recorded like settled ones
+        let mut event_queue = if market.has_flag(MARKET_FLAG_EVENT_QUEUE) {
+            let event_queue_account = find(&EventQueue::find_address(program_id, market_account.key).0)
+                .ok_or(ProgramError::NotEnoughAccountKeys)?;
+            Some(Self::load_event_queue_mut(program_id, market_account, event_queue_account)?)
+        } else {
+            None
+        };
+        let mut twap_oracle = if market.twap_oracle_enabled {
+            let twap_oracle_account = find(&TwapOracle::find_address(program_id, market_account.key).0)
+                .ok_or(ProgramError::NotEnoughAccountKeys)?;
+            Some((twap_oracle_account, Self::load_twap_oracle(program_id, market_account, twap_oracle_account)?))
+        } else {
+            None
+        };
+
+        let clock = Clock::get()?;
+        let timestamp = clock.unix_timestamp as u64;
+        let mut remaining = amount_in;
+        let mut amount_out: u64 = 0;
+        let (mut pool_amount_in, mut pool_amount_out): (u64, u64) = (0, 0);
+        let mut last_price = None;
+        let mut cancelled_orders = Vec::new();
+        for maker_accounts in order_accounts.chunks_exact(4) {
+            let [order_account, maker_owner, maker_base_account, maker_quote_account] = maker_accounts else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+
+            // An order cancelled by the fill of its linked order is passed over
+            if cancelled_orders.contains(order_account.key) {
+                continue;
+            }
+            let mut order = Self::load_order(program_id, market_account, order_account)?;
+            if order.is_buy == is_buy {
+                return Err(return_dex_error(
+                    DexError::InvalidAccountData,
+                    "Route orders must rest on the opposite side",
+                ));
+            }
+            let price = order.limit_price;
+            if last_price.is_some_and(|last_price| if is_buy { price < last_price } else { price > last_price }) {
+                return Err(return_dex_error(
+                    DexError::InvalidAccountData,
+                    "Route orders must be passed best price first",
+                ));
+            }
+            last_price = Some(price);
+            Self::check_owner_token_account(&market, &order.owner, false, maker_base_account)?;
+            Self::check_owner_token_account(&market, &order.owner, true, maker_quote_account)?;
+            Self::check_rent_recipient(&order, maker_owner)?;
+
+            // The pool goes first for as long as its marginal price beats the order's
+            let (numerator, denominator) = market.taker_price(is_buy, price)?;
+            let pool_share = pool.swap_amount_to_price(is_buy, remaining, numerator, denominator)?;
+            if pool_share > 0 && pool.swap_amount_out(is_buy, pool_share)? > 0 {
+                let pool_out = pool.swap(is_buy, pool_share)?;
+                remaining -= pool_share;
+                pool_amount_in = pool_amount_in.checked_add(pool_share).ok_or(ProgramError::ArithmeticOverflow)?;
+                pool_amount_out = pool_amount_out.checked_add(pool_out).ok_or(ProgramError::ArithmeticOverflow)?;
+            }
+
+            // Then the order fills as many lots as the rest of the input pays for; a buy is
+            // sized by its lock, which covers the cost of a fill with its taker fee
+            let quantity = if is_buy {
+                remaining
+                    .checked_div(market.escrow_amount(true, price, 1)?)
+                    .unwrap_or(0)
+            } else {
+                market.native_to_base_lots(remaining)
+            }
+            .min(order.visible_quantity);
+            if quantity == 0 {
+                break;
+            }
+            let base_amount = market.base_lots_to_native(quantity)?;
+            let quote_amount = market.notional(price, quantity)?;
+            let amounts = market.settlement_amounts(quote_amount, false)?;
+            cancelled_orders.extend(Self::cancel_linked_order(
+                program_id,
+                accounts,
+                &mut market,
+                &escrow,
+                &mut order,
+                order_account,
+                maker_base_account,
+                maker_quote_account,
+                maker_owner,
+            )?);
+            Self::fill_resting_order(&mut market, &mut order, order_account, maker_owner, quantity, timestamp)?;
+
+            let (spent, received) = if is_buy {
+                // The owner pays the ask's owner and the fee vault, and the escrow releases the
+                // ask's base tokens
+                let quote_side = escrow.side(true);
+                Self::transfer_tokens(quote_side, owner_quote_account, maker_quote_account, owner, amounts.maker_quote_amount, &[])?;
+                if amounts.vault_fee > 0 {
+                    Self::transfer_tokens(quote_side, owner_quote_account, fee_vault, owner, amounts.vault_fee, &[])?;
+                }
+                Self::pay_from_escrow(&escrow, false, owner_base_account, base_amount)?;
+                (amounts.taker_quote_amount()?, base_amount)
+            } else {
+                // The owner delivers base tokens to the bid's owner, and the bid's lock pays the
+                // owner, the fee vault and the bid's owner back what it did not spend
+                Self::transfer_tokens(escrow.side(false), owner_base_account, maker_base_account, owner, base_amount, &[])?;
+                let proceeds = quote_amount
+                    .checked_sub(amounts.taker_fee)
+                    .ok_or(ProgramError::ArithmeticOverflow)?;
+                let maker_refund = market
+                    .escrow_amount(true, price, quantity)?
+                    .checked_sub(proceeds)
+                    .and_then(|amount| amount.checked_sub(amounts.vault_fee))
+                    .ok_or(ProgramError::ArithmeticOverflow)?;
+                Self::pay_from_escrow(&escrow, true, owner_quote_account, proceeds)?;
+                if amounts.vault_fee > 0 {
+                    Self::pay_from_escrow(&escrow, true, fee_vault, amounts.vault_fee)?;
+                }
+                if maker_refund > 0 {
+                    Self::pay_from_escrow(&escrow, true, maker_quote_account, maker_refund)?;
+                }
+                (base_amount, proceeds)
+            };
+            remaining = remaining.checked_sub(spent).ok_or(ProgramError::ArithmeticOverflow)?;
+            amount_out = amount_out.checked_add(received).ok_or(ProgramError::ArithmeticOverflow)?;
+
+            // Record the fill, with the owner standing in for the taker order
+            let seq_num = market.fill_seq_num;
+            market.fill_seq_num = seq_num.checked_add(1).ok_or(ProgramError::ArithmeticOverflow)?;
+            market.record_fill_price(price);
+            market.last_trade_slot = clock.slot;
+            market.record_volume(base_amount, quote_amount, timestamp);
+            market.fees_accrued = market.fees_accrued.saturating_add(amounts.vault_fee);
+            if let Some((_, twap_oracle)) = twap_oracle.as_mut() {
+                twap_oracle.record_fill(price, timestamp as i64);
+            }
+            if let Some(event_queue) = event_queue.as_mut() {
+                event_queue.push(FillEvent {
+                    seq_num,
+                    slot: clock.slot,
+                    taker_order: *owner.key,
+                    maker_order: *order_account.key,
+                    price,
+                    base_amount,
+                    quote_amount,
+                });
+            }
+            Self::emit_event(DexEvent::Fill(Fill {
+                market: *market_account.key,
+                seq_num,
+                taker_order: *owner.key,
+                maker_order: *order_account.key,
+                base_amount,
+                quote_amount,
+                taker_fee: amounts.taker_fee,
+                maker_fee: amounts.maker_fee,
+                slot: clock.slot,
+                taker_memo: String::new(),
+                maker_memo: order.memo().to_string(),
+            }))?;
+        }
+
+        // Whatever the orders leave goes to the pool, unless it would pay out nothing
+        if remaining > 0 && pool.swap_amount_out(is_buy, remaining)? > 0 {
+            let pool_out = pool.swap(is_buy, remaining)?;
+            pool_amount_in = pool_amount_in.checked_add(remaining).ok_or(ProgramError::ArithmeticOverflow)?;
+            pool_amount_out = pool_amount_out.checked_add(pool_out).ok_or(ProgramError::ArithmeticOverflow)?;
+            remaining = 0;
+        }
+        amount_out = amount_out.checked_add(pool_amount_out).ok_or(ProgramError::ArithmeticOverflow)?;
+        if amount_out == 0 || amount_out < min_amount_out {
+            return Err(return_dex_error_expected(
+                DexError::SlippageExceeded,
+                "Route pays out too little",
+                format_args!("at least {}", min_amount_out.max(1)),
+                amount_out,
+            ));
+        }
+
+        if let Some((twap_oracle_account, twap_oracle)) = twap_oracle {
+            twap_oracle.pack_into_slice(&mut twap_oracle_account.data.borrow_mut());
+        }
+
+        // Settle the pool's share in one transfer each way
+        if pool_amount_in > 0 {
+            pool.pack_into_slice(&mut pool_account.data.borrow_mut());
+            let (source, vault_in, vault_out, destination) = if is_buy {
+                (owner_quote_account, pool_quote_vault, pool_base_vault, owner_base_account)
+            } else {
+                (owner_base_account, pool_base_vault, pool_quote_vault, owner_quote_account)
+            };
+            Self::transfer_tokens_in(escrow.side(is_buy), source, vault_in, owner, pool_amount_in, &[])?;
+            Self::transfer_tokens(
+                escrow.side(!is_buy),
+                vault_out,
+                destination,
+                pool_account,
+                pool_amount_out,
+                &[&[POOL_SEED, market_account.key.as_ref(), &[pool.bump]]],
+            )?;
+        }
+
+        msg!(
+            "Routed {} through the book and {} through the pool for {}",
+            amount_in - remaining - pool_amount_in,
+            pool_amount_in,
+            amount_out
+        );
+        Ok(())
+    }
+
+    // Process initialize range pool instruction
+    fn process_initialize_range_pool(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        fee_bps: u16,
+        tick_spacing: u16,
+        tick: i32,
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let authority = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let range_pool_account = next_account_info(account_info_iter)?;
+        let base_vault = next_account_info(account_info_iter)?;
+        let quote_vault = next_account_info(account_info_iter)?;
+        let base_mint = next_account_info(account_info_iter)?;
+        let quote_mint = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !authority.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Authority must sign",
+            ));
+        }
+
+        Self::check_token_program(token_program)?;
+        Self::check_system_program(system_program_account)?;
+
+        // Load market
+        let market = Self::load_market(program_id, market_account)?;
+
+        // Verify authority
+        if market.authority != *authority.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Not market authority",
+            ));
+        }
+
+        // A fee of the whole input would leave nothing to swap
+        if fee_bps as u64 >= math::BPS_DENOMINATOR {
+            return Err(return_dex_error_expected(
+                DexError::InvalidInstructionData,
+                "Invalid pool fee",
+                format_args!("below {} bps", math::BPS_DENOMINATOR),
+                fee_bps,
+            ));
+        }
+        if tick_spacing == 0 {
+            return Err(return_dex_error(
+                DexError::InvalidTickRange,
+                "Tick spacing must be positive",
+            ));
+        }
+        let sqrt_price = math::sqrt_price_at_tick(tick)
+            .map_err(|_| return_dex_error_expected(
+                DexError::InvalidTickRange,
+                "Initial tick out of range",
+                format_args!("{} to {}", math::MIN_TICK, math::MAX_TICK),
+                tick,
+            ))?;
+
+        if market.base_mint != *base_mint.key || market.quote_mint != *quote_mint.key {
+            return Err(return_dex_error(
+                DexError::InvalidMint,
+                "Pool mints must match the market",
+            ));
+        }
+
+        let (range_pool_address, bump) = RangePool::find_address(program_id, market_account.key);
+        if range_pool_address != *range_pool_account.key {
+            return Err(return_dex_error_expected(
+                DexError::InvalidAccountData,
+                "Invalid range pool address",
+                range_pool_address,
+                range_pool_account.key,
+            ));
+        }
+        if range_pool_account.owner == program_id {
+            return Err(return_dex_error(
+                DexError::AlreadyInitialized,
+                "Range pool already initialized",
+            ));
+        }
+
+        // Create the range pool PDA with room for every tick, paid by the authority
+        let rent = Rent::get()?;
+        invoke_signed(
+            &system_instruction::create_account(
+                authority.key,
+                range_pool_account.key,
+                rent.minimum_balance(RangePool::LEN),
+                RangePool::LEN as u64,
+                program_id,
+            ),
+            &[
+                authority.clone(),
+                range_pool_account.clone(),
+                system_program_account.clone(),
+            ],
+            &[&[RANGE_POOL_SEED, market_account.key.as_ref(), &[bump]]],
+        )?;
+
+        let vaults = [
+            (RANGE_POOL_BASE_VAULT_SEED, base_vault, base_mint),
+            (RANGE_POOL_QUOTE_VAULT_SEED, quote_vault, quote_mint),
+        ];
+        for (is_quote, (seed, vault, mint)) in vaults.into_iter().enumerate() {
+            let (vault_address, vault_bump) = RangePool::find_vault_address(program_id, market_account.key, is_quote == 1);
+            if vault_address != *vault.key {
+                return Err(return_dex_error_expected(
+                    DexError::InvalidAccountData,
+                    "Invalid range pool vault address",
+                    vault_address,
+                    vault.key,
+                ));
+            }
+
+            // Create the vault PDA as a token account of its mint's program owned by the range pool
+            let vault_token_program = Self::load_token_side(&market, accounts, is_quote == 1)?.token_program;
+            let space = token::account_len(&mint.data.borrow())?;
+            invoke_signed(
+                &system_instruction::create_account(
+                    authority.key,
+                    vault.key,
+                    rent.minimum_balance(space),
+                    space as u64,
+                    vault_token_program.key,
+                ),
+                &[
+                    authority.clone(),
+                    vault.clone(),
+                    system_program_account.clone(),
+                ],
+                &[&[seed, market_account.key.as_ref(), &[vault_bump]]],
+            )?;
+
+            invoke(
+                &token_2022_instruction::initialize_account3(
+                    vault_token_program.key,
+                    vault.key,
+                    mint.key,
+                    range_pool_account.key,
+                )?,
+                &[vault.clone(), mint.clone(), vault_token_program.clone()],
+            )?;
+        }
+
+        let range_pool = RangePool {
+            is_initialized: true,
+            market: *market_account.key,
+            bump,
+            fee_bps,
+            tick_spacing,
+            sqrt_price,
+            tick,
+            liquidity: 0,
+            fee_growth_base: 0,
+            fee_growth_quote: 0,
+            ticks: Vec::new(),
+        };
+        range_pool.pack_into_slice(&mut range_pool_account.data.borrow_mut());
+
+        msg!("Range pool initialized at tick {} with a fee of {} bps", tick, fee_bps);
+        Ok(())
+    }
+
+    // Process add range liquidity instruction
+    fn process_add_range_liquidity(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        tick_lower: i32,
+        tick_upper: i32,
+        liquidity: u64,
+        max_base_amount: u64,
+        max_quote_amount: u64,
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let owner = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let range_pool_account = next_account_info(account_info_iter)?;
+        let position_account = next_account_info(account_info_iter)?;
+        let base_vault = next_account_info(account_info_iter)?;
+        let quote_vault = next_account_info(account_info_iter)?;
+        let owner_base_account = next_account_info(account_info_iter)?;
+        let owner_quote_account = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !owner.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Owner must sign",
+            ));
+        }
+
+        Self::check_token_program(token_program)?;
+        Self::check_system_program(system_program_account)?;
+
+        // Load market and range pool
+        let market = Self::load_market(program_id, market_account)?;
+        Self::check_market_active(&market, "Market does not accept liquidity")?;
+        let mut range_pool = Self::load_range_pool(program_id, market_account, range_pool_account)?;
+        Self::check_range_pool_vaults(program_id, market_account, base_vault, quote_vault)?;
+
+        if liquidity == 0 || liquidity > i64::MAX as u64 {
+            return Err(return_dex_error_expected(
+                DexError::InvalidInstructionData,
+                "Invalid liquidity",
+                format_args!("1 to {}", i64::MAX),
+                liquidity,
+            ));
+        }
+        range_pool.check_range(tick_lower, tick_upper).map_err(|_| {
+            return_dex_error_expected(
+                DexError::InvalidTickRange,
+                "Invalid position range",
+                format_args!("ordered multiples of {}", range_pool.tick_spacing),
+                format_args!("{} to {}", tick_lower, tick_upper),
+            )
+        })?;
+
+        Self::check_owner_token_account(&market, owner.key, false, owner_base_account)?;
+        Self::check_owner_token_account(&market, owner.key, true, owner_quote_account)?;
+
+        let (position_address, position_bump) =
+            RangePosition::find_address(program_id, market_account.key, owner.key, tick_lower, tick_upper);
+        if position_address != *position_account.key {
+            return Err(return_dex_error_expected(
+                DexError::InvalidAccountData,
+                "Invalid range position address",
+                position_address,
+                position_account.key,
+            ));
+        }
+
+        // Create the position on the first deposit, paid by the owner
+        let mut position = if position_account.owner == program_id {
+            RangePosition::unpack_from_slice(&position_account.data.borrow())?
+        } else {
+            invoke_signed(
+                &system_instruction::create_account(
+                    owner.key,
+                    position_account.key,
+                    Rent::get()?.minimum_balance(RangePosition::LEN),
+                    RangePosition::LEN as u64,
+                    program_id,
+                ),
+                &[
+                    owner.clone(),
+                    position_account.clone(),
+                    system_program_account.clone(),
+                ],
+                &[&[
+                    RANGE_POSITION_SEED,
+                    market_account.key.as_ref(),
+                    owner.key.as_ref(),
+                    &tick_lower.to_le_bytes(),
+                    &tick_upper.to_le_bytes(),
+                    &[position_bump],
+                ]],
+            )?;
+            RangePosition {
+                is_initialized: true,
+                market: *market_account.key,
+                owner: *owner.key,
+                tick_lower,
+                tick_upper,
+                liquidity: 0,
+                fee_growth_inside_base: 0,
+                fee_growth_inside_quote: 0,
+                fees_owed_base: 0,
+                fees_owed_quote: 0,
+            }
+        };
+
+        let (base_amount, quote_amount) = range_pool.update_position(&mut position, liquidity as i64)?;
+        if base_amount > max_base_amount || quote_amount > max_quote_amount {
+            return Err(return_dex_error_expected(
+                DexError::SlippageExceeded,
+                "Deposit takes too much",
+                format_args!("at most {} base and {} quote", max_base_amount, max_quote_amount),
+                format_args!("{} base and {} quote", base_amount, quote_amount),
+            ));
+        }
+        range_pool.pack_into_slice(&mut range_pool_account.data.borrow_mut());
+        position.pack_into_slice(&mut position_account.data.borrow_mut());
+
+        // Move the deposit into the vaults
+        let base_side = Self::load_token_side(&market, accounts, false)?;
+        Self::transfer_tokens_in(&base_side, owner_base_account, base_vault, owner, base_amount, &[])?;
+        let quote_side = Self::load_token_side(&market, accounts, true)?;
+        Self::transfer_tokens_in(&quote_side, owner_quote_account, quote_vault, owner, quote_amount, &[])?;
+
+        msg!(
+            "Added {} liquidity from tick {} to {} for {} base and {} quote",
+            liquidity,
+            tick_lower,
+            tick_upper,
+            base_amount,
+            quote_amount
+        );
+        Ok(())
+    }
+
+    // Process remove range liquidity instruction
+    fn process_remove_range_liquidity(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        liquidity: u64,
+        min_base_amount: u64,
+        min_quote_amount: u64,
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let owner = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let range_pool_account = next_account_info(account_info_iter)?;
+        let position_account = next_account_info(account_info_iter)?;
+        let base_vault = next_account_info(account_info_iter)?;
+        let quote_vault = next_account_info(account_info_iter)?;
+        let base_destination = next_account_info(account_info_iter)?;
+        let quote_destination = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !owner.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Owner must sign",
+            ));
+        }
+
+        Self::check_token_program(token_program)?;
+
+        // Load market, range pool and position; withdrawals work in every market status
+        let market = Self::load_market(program_id, market_account)?;
+        let mut range_pool = Self::load_range_pool(program_id, market_account, range_pool_account)?;
+        Self::check_range_pool_vaults(program_id, market_account, base_vault, quote_vault)?;
+
+        if position_account.owner != program_id {
+            return Err(return_dex_error(
+                DexError::InvalidAccountOwner,
+                "Range position not owned by the program",
+            ));
+        }
+        let mut position = RangePosition::unpack_from_slice(&position_account.data.borrow())?;
+        if position.owner != *owner.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Not range position owner",
+            ));
+        }
+        let (position_address, _) = RangePosition::find_address(
+            program_id,
+            market_account.key,
+            owner.key,
+            position.tick_lower,
+            position.tick_upper,
+        );
+        if position_address != *position_account.key {
+            return Err(return_dex_error(
+                DexError::AccountMismatch,
+                "Range position does not belong to the market",
+            ));
+        }
+        if liquidity > position.liquidity {
+            return Err(return_dex_error_expected(
+                DexError::InsufficientFunds,
+                "Position liquidity too low",
+                format_args!("at least {}", liquidity),
+                position.liquidity,
+            ));
+        }
+
+        let (base_amount, quote_amount) = range_pool.update_position(&mut position, -(liquidity as i64))?;
+        if base_amount < min_base_amount || quote_amount < min_quote_amount {
+            return Err(return_dex_error_expected(
+                DexError::SlippageExceeded,
+                "Withdrawal pays out too little",
+                format_args!("at least {} base and {} quote", min_base_amount, min_quote_amount),
+                format_args!("{} base and {} quote", base_amount, quote_amount),
+            ));
+        }
+        range_pool.pack_into_slice(&mut range_pool_account.data.borrow_mut());
+
+        // Pay out the liquidity's tokens together with every fee owed
+        let (fees_base, fees_quote) = (position.fees_owed_base, position.fees_owed_quote);
+        let base_total = base_amount.checked_add(fees_base).ok_or(DexError::ArithmeticOverflow)?;
+        let quote_total = quote_amount.checked_add(fees_quote).ok_or(DexError::ArithmeticOverflow)?;
+        position.fees_owed_base = 0;
+        position.fees_owed_quote = 0;
+        if position.liquidity == 0 {
+            Self::close_order_account(position_account, owner)?;
+        } else {
+            position.pack_into_slice(&mut position_account.data.borrow_mut());
+        }
+
+        let signer_seeds: &[&[&[u8]]] = &[&[RANGE_POOL_SEED, market_account.key.as_ref(), &[range_pool.bump]]];
+        let base_side = Self::load_token_side(&market, accounts, false)?;
+        Self::transfer_tokens(&base_side, base_vault, base_destination, range_pool_account, base_total, signer_seeds)?;
+        let quote_side = Self::load_token_side(&market, accounts, true)?;
+        Self::transfer_tokens(&quote_side, quote_vault, quote_destination, range_pool_account, quote_total, signer_seeds)?;
+
+        msg!(
+            "Removed {} liquidity for {} base and {} quote, with {} base and {} quote in fees",
+            liquidity,
+            base_amount,
+            quote_amount,
+            fees_base,
+            fees_quote
+        );
+        Ok(())
+    }
+
+    // Process swap against range pool instruction
+    fn process_swap_against_range_pool(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        is_buy: bool,
+        amount_in: u64,
+        min_amount_out: u64,
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let owner = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let range_pool_account = next_account_info(account_info_iter)?;
+        let base_vault = next_account_info(account_info_iter)?;
+        let quote_vault = next_account_info(account_info_iter)?;
+        let owner_base_account = next_account_info(account_info_iter)?;
+        let owner_quote_account = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !owner.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Owner must sign",
+            ));
+        }
+
+        Self::check_token_program(token_program)?;
+
+        // Load market and range pool
+        let market = Self::load_market(program_id, market_account)?;
+        Self::check_market_active(&market, "Market does not accept swaps")?;
+        let mut range_pool = Self::load_range_pool(program_id, market_account, range_pool_account)?;
+        Self::check_range_pool_vaults(program_id, market_account, base_vault, quote_vault)?;
+
+        if amount_in == 0 {
+            return Err(return_dex_error(
+                DexError::InvalidInstructionData,
+                "Swap amount must be positive",
+            ));
+        }
+
+        Self::check_owner_token_account(&market, owner.key, false, owner_base_account)?;
+        Self::check_owner_token_account(&market, owner.key, true, owner_quote_account)?;
+
+        let (amount_used, amount_out) = range_pool.swap(is_buy, amount_in)?;
+        if amount_out == 0 || amount_out < min_amount_out {
+            return Err(return_dex_error_expected(
+                DexError::SlippageExceeded,
+                "Swap pays out too little",
+                format_args!("at least {}", min_amount_out.max(1)),
+                amount_out,
+            ));
+        }
+        range_pool.pack_into_slice(&mut range_pool_account.data.borrow_mut());
+
+        let (source, vault_in, vault_out, destination) = if is_buy {
+            (owner_quote_account, quote_vault, base_vault, owner_base_account)
+        } else {
+            (owner_base_account, base_vault, quote_vault, owner_quote_account)
+        };
+        Self::transfer_tokens_in(&Self::load_token_side(&market, accounts, is_buy)?, source, vault_in, owner, amount_used, &[])?;
+        Self::transfer_tokens(
+            &Self::load_token_side(&market, accounts, !is_buy)?,
+            vault_out,
+            destination,
+            range_pool_account,
+            amount_out,
+            &[&[RANGE_POOL_SEED, market_account.key.as_ref(), &[range_pool.bump]]],
+        )?;
+
+        msg!(
+            "Swapped {} into the range pool for {}, ending at tick {}",
+            amount_used,
+            amount_out,
+            range_pool.tick
+        );
+        Ok(())
+    }
+
+    // Process initialize lending pool instruction
+    fn process_initialize_lending_pool(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        rate_curve: InterestRateCurve,
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let admin = next_account_info(account_info_iter)?;
+        let config_account = next_account_info(account_info_iter)?;
+        let lending_pool_account = next_account_info(account_info_iter)?;
+        let vault = next_account_info(account_info_iter)?;
+        let share_mint = next_account_info(account_info_iter)?;
+        let mint = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !admin.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Config admin must sign",
+            ));
+        }
+
+        Self::check_token_program(token_program)?;
+        Self::check_system_program(system_program_account)?;
+
+        // Load config
+        let config = Self::load_program_config(program_id, config_account)?.ok_or_else(|| {
+            return_dex_error(DexError::InvalidAccountData, "Program config not initialized")
+        })?;
+
+        // Verify admin
+        if config.admin != *admin.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Not config admin",
+            ));
+        }
+
+        if !rate_curve.is_valid() {
+            return Err(return_dex_error_expected(
+                DexError::InvalidInstructionData,
+                "Invalid optimal utilization",
+                format_args!("1 to {} bps", math::BPS_DENOMINATOR - 1),
+                rate_curve.optimal_utilization_bps,
+            ));
+        }
+        if mint.owner != token_program.key {
+            return Err(return_dex_error_expected(
+                DexError::InvalidMint,
+                "Token program does not own the mint",
+                mint.owner,
+                token_program.key,
+            ));
+        }
+        let decimals = Self::load_market_mint(mint, false)?;
+
+        let (lending_pool_address, bump) = LendingPool::find_address(program_id, mint.key);
+        if lending_pool_address != *lending_pool_account.key {
+            return Err(return_dex_error_expected(
+                DexError::InvalidAccountData,
+                "Invalid lending pool address",
+                lending_pool_address,
+                lending_pool_account.key,
+            ));
+        }
+        if lending_pool_account.owner == program_id {
+            return Err(return_dex_error(
+                DexError::AlreadyInitialized,
+                "Lending pool already initialized",
+            ));
+        }
+
+        // Create the lending pool PDA, paid by the admin
+        let rent = Rent::get()?;
+        invoke_signed(
+            &system_instruction::create_account(
+                admin.key,
+                lending_pool_account.key,
+                rent.minimum_balance(LendingPool::LEN),
+                LendingPool::LEN as u64,
+                program_id,
+            ),
+            &[
+                admin.clone(),
+                lending_pool_account.clone(),
+                system_program_account.clone(),
+            ],
+            &[&[LENDING_POOL_SEED, mint.key.as_ref(), &[bump]]],
+        )?;
+
+        // Create the vault PDA as a token account owned by the lending pool
+        let (vault_address, vault_bump) = LendingPool::find_vault_address(program_id, mint.key);
+        if vault_address != *vault.key {
+            return Err(return_dex_error_expected(
+                DexError::InvalidAccountData,
+                "Invalid lending pool vault address",
+                vault_address,
+                vault.key,
+            ));
+        }
+
+        let space = token::account_len(&mint.data.borrow())?;
+        invoke_signed(
+            &system_instruction::create_account(
+                admin.key,
+                vault.key,
+                rent.minimum_balance(space),
+                space as u64,
+                token_program.key,
+            ),
+            &[
+                admin.clone(),
+                vault.clone(),
+                system_program_account.clone(),
+            ],
+            &[&[LENDING_POOL_VAULT_SEED, mint.key.as_ref(), &[vault_bump]]],
+        )?;
+
+        invoke(
+            &token_2022_instruction::initialize_account3(
+                token_program.key,
+                vault.key,
+                mint.key,
+                lending_pool_account.key,
+            )?,
+            &[vault.clone(), mint.clone(), token_program.clone()],
+        )?;
+
+        // Create the share mint PDA with the lending pool as its mint authority
+        let (share_mint_address, share_mint_bump) = LendingPool::find_share_mint_address(program_id, mint.key);
+        if share_mint_address != *share_mint.key {
+            return Err(return_dex_error_expected(
+                DexError::InvalidAccountData,
+                "Invalid share mint address",
+                share_mint_address,
+                share_mint.key,
+            ));
+        }
+
+        let space = spl_token_2022::state::Mint::LEN;
+        invoke_signed(
+            &system_instruction::create_account(
+                admin.key,
+                share_mint.key,
+                rent.minimum_balance(space),
+                space as u64,
+                token_program.key,
+            ),
+            &[
+                admin.clone(),
+                share_mint.clone(),
+                system_program_account.clone(),
+            ],
+            &[&[LENDING_POOL_SHARE_MINT_SEED, mint.key.as_ref(), &[share_mint_bump]]],
+        )?;
+
+        invoke(
+            &token_2022_instruction::initialize_mint2(
+                token_program.key,
+                share_mint.key,
+                lending_pool_account.key,
+                None,
+                decimals,
+            )?,
+            &[share_mint.clone(), token_program.clone()],
+        )?;
+
+        let lending_pool = LendingPool {
+            is_initialized: true,
+            mint: *mint.key,
+            token_program: *token_program.key,
+            share_mint: *share_mint.key,
+            bump,
+            decimals,
+            rate_curve,
+            available: 0,
+            borrowed: 0,
+            share_supply: 0,
+            borrow_index: LendingPool::INITIAL_BORROW_INDEX,
+            last_accrual_timestamp: Clock::get()?.unix_timestamp,
+        };
+        lending_pool.pack_into_slice(&mut lending_pool_account.data.borrow_mut());
+
+        msg!("Lending pool initialized for mint {}", mint.key);
+        Ok(())
+    }
+
+    // Process supply instruction
+    fn process_supply(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+        min_shares: u64,
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let owner = next_account_info(account_info_iter)?;
+        let lending_pool_account = next_account_info(account_info_iter)?;
+        let vault = next_account_info(account_info_iter)?;
+        let share_mint = next_account_info(account_info_iter)?;
+        let source = next_account_info(account_info_iter)?;
+        let share_token_account = next_account_info(account_info_iter)?;
+        let mint = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !owner.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Owner must sign",
+            ));
+        }
+
+        // Load the lending pool and bring its interest up to date
+        let mut lending_pool = Self::load_lending_pool(program_id, lending_pool_account, vault, share_mint, mint, token_program)?;
+        lending_pool.accrue_interest(Clock::get()?.unix_timestamp)?;
+
+        if amount == 0 {
+            return Err(return_dex_error(
+                DexError::InvalidInstructionData,
+                "Supply amount must be positive",
+            ));
+        }
+
+        let shares = lending_pool
+            .supply_shares(amount)
+            .map_err(|_| return_dex_error(DexError::InsufficientFunds, "Lending pool shares are worth nothing"))?;
+        if shares == 0 || shares < min_shares {
+            return Err(return_dex_error_expected(
+                DexError::SlippageExceeded,
+                "Supply mints too few shares",
+                format_args!("at least {}", min_shares.max(1)),
+                shares,
+            ));
+        }
+
+        lending_pool.available = lending_pool.available.checked_add(amount).ok_or(DexError::ArithmeticOverflow)?;
+        lending_pool.share_supply = lending_pool.share_supply.checked_add(shares).ok_or(DexError::ArithmeticOverflow)?;
+        lending_pool.pack_into_slice(&mut lending_pool_account.data.borrow_mut());
+
+        // Move the tokens into the vault and mint the shares
+        let side = TokenSide {
+            token_program,
+            mint: Some(mint),
+            decimals: lending_pool.decimals,
+        };
+        Self::transfer_tokens_in(&side, source, vault, owner, amount, &[])?;
+
+        invoke_signed(
+            &token_2022_instruction::mint_to(
+                token_program.key,
+                share_mint.key,
+                share_token_account.key,
+                lending_pool_account.key,
+                &[],
+                shares,
+            )?,
+            &[
+                share_mint.clone(),
+                share_token_account.clone(),
+                lending_pool_account.clone(),
+                token_program.clone(),
+            ],
+            &[&[LENDING_POOL_SEED, mint.key.as_ref(), &[lending_pool.bump]]],
+        )?;
+
+        msg!("Supplied {} tokens for {} shares", amount, shares);
+        Ok(())
+    }
+
+    // Process redeem instruction
+    fn process_redeem(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        shares: u64,
+        min_amount: u64,
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let owner = next_account_info(account_info_iter)?;
+        let lending_pool_account = next_account_info(account_info_iter)?;
+        let vault = next_account_info(account_info_iter)?;
+        let share_mint = next_account_info(account_info_iter)?;
+        let share_token_account = next_account_info(account_info_iter)?;
+        let destination = next_account_info(account_info_iter)?;
+        let mint = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !owner.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Owner must sign",
+            ));
+        }
+
+        // Load the lending pool and bring its interest up to date
+        let mut lending_pool = Self::load_lending_pool(program_id, lending_pool_account, vault, share_mint, mint, token_program)?;
+        lending_pool.accrue_interest(Clock::get()?.unix_timestamp)?;
+
+        if shares == 0 {
+            return Err(return_dex_error(
+                DexError::InvalidInstructionData,
+                "Share amount must be positive",
+            ));
+        }
+
+        let amount = lending_pool.redeem_amount(shares)?;
+        if amount < min_amount {
+            return Err(return_dex_error_expected(
+                DexError::SlippageExceeded,
+                "Redemption pays out too little",
+                format_args!("at least {}", min_amount),
+                amount,
+            ));
+        }
+
+        // Tokens lent out come back only as borrowers repay
+        if amount > lending_pool.available {
+            return Err(return_dex_error_expected(
+                DexError::InsufficientFunds,
+                "Lending pool has too few tokens not lent out",
+                format_args!("at least {}", amount),
+                lending_pool.available,
+            ));
+        }
+
+        // The burn fails unless the owner holds the shares, so the supply covers them
+        lending_pool.available -= amount;
+        lending_pool.share_supply = lending_pool.share_supply.checked_sub(shares).ok_or(DexError::ArithmeticOverflow)?;
+        lending_pool.pack_into_slice(&mut lending_pool_account.data.borrow_mut());
+
+        invoke(
+            &token_2022_instruction::burn(
+                token_program.key,
+                share_token_account.key,
+                share_mint.key,
+                owner.key,
+                &[],
+                shares,
+            )?,
+            &[
+                share_token_account.clone(),
+                share_mint.clone(),
+                owner.clone(),
+                token_program.clone(),
+            ],
+        )?;
+
+        // Pay out the tokens, signed by the lending pool that owns the vault
+        let side = TokenSide {
+            token_program,
+            mint: Some(mint),
+            decimals: lending_pool.decimals,
+        };
+        Self::transfer_tokens(
+            &side,
+            vault,
+            destination,
+            lending_pool_account,
+            amount,
+            &[&[LENDING_POOL_SEED, mint.key.as_ref(), &[lending_pool.bump]]],
+        )?;
+
+        msg!("Redeemed {} shares for {} tokens", shares, amount);
+        Ok(())
+    }
+
+    // Process initialize perp market instruction
+    fn process_initialize_perp_market(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        initial_margin_bps: u16,
+        max_funding_rate_bps: u16,
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let authority = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let perp_market_account = next_account_info(account_info_iter)?;
+        let vault = next_account_info(account_info_iter)?;
+        let quote_mint = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !authority.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Authority must sign",
+            ));
+        }
+
+        Self::check_token_program(token_program)?;
+        Self::check_system_program(system_program_account)?;
+
+        // Load market
+        let market = Self::load_market(program_id, market_account)?;
+
+        // Verify authority
+        if market.authority != *authority.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Not market authority",
+            ));
+        }
+
+        // Funding is paid against the oracle's index price
+        if !market.has_oracle() {
+            return Err(return_dex_error(
+                DexError::InvalidOraclePrice,
+                "Perp markets need a market oracle",
+            ));
+        }
+        if initial_margin_bps == 0 || initial_margin_bps as u64 > math::BPS_DENOMINATOR {
+            return Err(return_dex_error_expected(
+                DexError::InvalidInstructionData,
+                "Invalid initial margin",
+                format_args!("1 to {} bps", math::BPS_DENOMINATOR),
+                initial_margin_bps,
+            ));
+        }
+        if max_funding_rate_bps == 0 || max_funding_rate_bps as u64 > math::BPS_DENOMINATOR {
+            return Err(return_dex_error_expected(
+                DexError::InvalidInstructionData,
+                "Invalid maximum funding rate",
+                format_args!("1 to {} bps", math::BPS_DENOMINATOR),
+                max_funding_rate_bps,
+            ));
+        }
+        if market.quote_mint != *quote_mint.key {
+            return Err(return_dex_error(
+                DexError::InvalidMint,
+                "Perp collateral must be the market's quote mint",
+            ));
+        }
+
+        let (perp_market_address, bump) = PerpMarket::find_address(program_id, market_account.key);
+        if perp_market_address != *perp_market_account.key {
+            return Err(return_dex_error_expected(
+                DexError::InvalidAccountData,
+                "Invalid perp market address",
+                perp_market_address,
+                perp_market_account.key,
+            ));
+        }
+        if perp_market_account.owner == program_id {
+            return Err(return_dex_error(
+                DexError::AlreadyInitialized,
+                "Perp market already initialized",
+            ));
+        }
+        let (vault_address, vault_bump) = PerpMarket::find_vault_address(program_id, market_account.key);
+        if vault_address != *vault.key {
+            return Err(return_dex_error_expected(
+                DexError::InvalidAccountData,
+                "Invalid perp vault address",
+                vault_address,
+                vault.key,
+            ));
+        }
+
+        // Create the perp market PDA, paid by the authority
+        let rent = Rent::get()?;
+        invoke_signed(
+            &system_instruction::create_account(
+                authority.key,
+                perp_market_account.key,
+                rent.minimum_balance(PerpMarket::LEN),
+                PerpMarket::LEN as u64,
+                program_id,
+            ),
+            &[
+                authority.clone(),
+                perp_market_account.clone(),
+                system_program_account.clone(),
+            ],
+            &[&[PERP_MARKET_SEED, market_account.key.as_ref(), &[bump]]],
+        )?;
+
+        // Create the vault PDA as a quote token account owned by the perp market
+        let vault_token_program = Self::load_token_side(&market, accounts, true)?.token_program;
+        let space = token::account_len(&quote_mint.data.borrow())?;
+        invoke_signed(
+            &system_instruction::create_account(
+                authority.key,
+                vault.key,
+                rent.minimum_balance(space),
+                space as u64,
+                vault_token_program.key,
+            ),
+            &[
+                authority.clone(),
+                vault.clone(),
+                system_program_account.clone(),
+            ],
+            &[&[PERP_VAULT_SEED, market_account.key.as_ref(), &[vault_bump]]],
+        )?;
+        invoke(
+            &token_2022_instruction::initialize_account3(
+                vault_token_program.key,
+                vault.key,
+                quote_mint.key,
+                perp_market_account.key,
+            )?,
+            &[vault.clone(), quote_mint.clone(), vault_token_program.clone()],
+        )?;
+
+        let perp_market = PerpMarket {
+            is_initialized: true,
+            market: *market_account.key,
+            bump,
+            initial_margin_bps,
+            max_funding_rate_bps,
+            mark_price: 0,
+            long_open_interest: 0,
+            cumulative_funding: 0,
+            last_funding_timestamp: Clock::get()?.unix_timestamp,
+        };
+        perp_market.pack_into_slice(&mut perp_market_account.data.borrow_mut());
+
+        msg!(
+            "Perp market initialized with an initial margin of {} bps and funding capped at {} bps",
+            initial_margin_bps,
+            max_funding_rate_bps
+        );
+        Ok(())
+    }
+
+    // Process deposit perp collateral instruction
+    fn process_deposit_perp_collateral(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let owner = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let perp_market_account = next_account_info(account_info_iter)?;
+        let position_account = next_account_info(account_info_iter)?;
+        let vault = next_account_info(account_info_iter)?;
+        let source = next_account_info(account_info_iter)?;
+        let _quote_mint = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !owner.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Owner must sign",
+            ));
+        }
+
+        Self::check_token_program(token_program)?;
+        Self::check_system_program(system_program_account)?;
+
+        // Load market and perp market
+        let market = Self::load_market(program_id, market_account)?;
+        Self::check_market_active(&market, "Market does not accept collateral")?;
+        let perp_market = Self::load_perp_market(program_id, market_account, perp_market_account, vault)?;
+
+        if amount == 0 || amount > i64::MAX as u64 {
+            return Err(return_dex_error_expected(
+                DexError::InvalidInstructionData,
+                "Invalid deposit amount",
+                format_args!("1 to {}", i64::MAX),
+                amount,
+            ));
+        }
+
+        let (position_address, position_bump) = PerpPosition::find_address(program_id, market_account.key, owner.key);
+        if position_address != *position_account.key {
+            return Err(return_dex_error_expected(
+                DexError::InvalidAccountData,
+                "Invalid perp position address",
+                position_address,
+                position_account.key,
+            ));
+        }
+
+        // Create the position on the first deposit, paid by the owner
+        let mut position = if position_account.owner == program_id {
+            PerpPosition::unpack_from_slice(&position_account.data.borrow())?
+        } else {
+            invoke_signed(
+                &system_instruction::create_account(
+                    owner.key,
+                    position_account.key,
+                    Rent::get()?.minimum_balance(PerpPosition::LEN),
+                    PerpPosition::LEN as u64,
+                    program_id,
+                ),
+                &[
+                    owner.clone(),
+                    position_account.clone(),
+                    system_program_account.clone(),
+                ],
+                &[&[
+                    PERP_POSITION_SEED,
+                    market_account.key.as_ref(),
+                    owner.key.as_ref(),
+                    &[position_bump],
+                ]],
+            )?;
+            PerpPosition {
+                is_initialized: true,
+                market: *market_account.key,
+                owner: *owner.key,
+                base_position: 0,
+                quote_balance: 0,
+                last_cumulative_funding: perp_market.cumulative_funding,
+            }
+        };
+
+        position.quote_balance = position
+            .quote_balance
+            .checked_add(amount as i64)
+            .ok_or(DexError::ArithmeticOverflow)?;
+        position.pack_into_slice(&mut position_account.data.borrow_mut());
+
+        // Move the collateral into the vault
+        let side = Self::load_token_side(&market, accounts, true)?;
+        Self::transfer_tokens_in(&side, source, vault, owner, amount, &[])?;
+
+        msg!("Deposited {} perp collateral", amount);
+        Ok(())
+    }
+
+    // Process withdraw perp collateral instruction
+    fn process_withdraw_perp_collateral(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let owner = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let perp_market_account = next_account_info(account_info_iter)?;
+        let position_account = next_account_info(account_info_iter)?;
+        let vault = next_account_info(account_info_iter)?;
+        let destination = next_account_info(account_info_iter)?;
+        let _quote_mint = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !owner.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Owner must sign",
+            ));
+        }
+
+        Self::check_token_program(token_program)?;
+
+        // Load market, perp market and position
+        let market = Self::load_market(program_id, market_account)?;
+        let perp_market = Self::load_perp_market(program_id, market_account, perp_market_account, vault)?;
+        let mut position = Self::load_perp_position(program_id, market_account, position_account)?;
+        if position.owner != *owner.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Not the position owner",
+            ));
+        }
+
+        if amount == 0 || amount > i64::MAX as u64 {
+            return Err(return_dex_error_expected(
+                DexError::InvalidInstructionData,
+                "Invalid withdrawal amount",
+                format_args!("1 to {}", i64::MAX),
+                amount,
+            ));
+        }
+
+        // Pay the funding owed, then keep the initial margin after the withdrawal
+        position.settle_funding(perp_market.cumulative_funding)?;
+        position.quote_balance = position
+            .quote_balance
+            .checked_sub(amount as i64)
+            .ok_or(DexError::ArithmeticOverflow)?;
+        let mark_price = perp_market.mark_price;
+        if !position.is_healthy(mark_price, market.quote_lot_size, perp_market.initial_margin_bps) {
+            return Err(return_dex_error_expected(
+                DexError::InsufficientMargin,
+                "Withdrawal leaves too little margin",
+                format_args!(
+                    "equity of at least {}",
+                    position.initial_margin(mark_price, market.quote_lot_size, perp_market.initial_margin_bps)
+                ),
+                position.equity(mark_price, market.quote_lot_size),
+            ));
+        }
+        position.pack_into_slice(&mut position_account.data.borrow_mut());
+
+        // Pay out the collateral, signed by the perp market that owns the vault
+        let side = Self::load_token_side(&market, accounts, true)?;
+        Self::transfer_tokens(
+            &side,
+            vault,
+            destination,
+            perp_market_account,
+            amount,
+            &[&[PERP_MARKET_SEED, market_account.key.as_ref(), &[perp_market.bump]]],
+        )?;
+
+        msg!("Withdrew {} perp collateral", amount);
+        Ok(())
+    }
+
+    // Process place perp order instruction
+    fn process_place_perp_order(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        is_buy: bool,
+        limit_price: u64,
+        quantity: u64,
+        reduce_only: bool,
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let owner = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let perp_market_account = next_account_info(account_info_iter)?;
+        let position_account = next_account_info(account_info_iter)?;
+        let order_account = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+        let oracle_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !owner.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Order owner must sign",
+            ));
+        }
+
+        Self::check_system_program(system_program_account)?;
+
+        // Load market
+        let mut market = Self::load_market(program_id, market_account)?;
+        Self::check_market_active(&market, "Market does not accept new orders")?;
+        if perp_market_account.owner != program_id
+            || PerpMarket::find_address(program_id, market_account.key).0 != *perp_market_account.key
+        {
+            return Err(return_dex_error(
+                DexError::AccountMismatch,
+                "Perp market does not belong to the market",
+            ));
+        }
+
+        // Orders trade for the owner's own position
+        let position = Self::load_perp_position(program_id, market_account, position_account)?;
+        if position.owner != *owner.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Not the position owner",
+            ));
+        }
+
+        // Validate order parameters
+        if quantity == 0 || quantity < market.min_base_order_size || quantity > i64::MAX as u64 {
+            return Err(return_dex_error_expected(
+                DexError::InvalidOrderSize,
+                "Invalid order size",
+                format_args!("{} to {}", { market.min_base_order_size }.max(1), i64::MAX),
+                quantity,
+            ));
+        }
+        if limit_price == 0 || limit_price.checked_rem(market.tick_size) != Some(0) {
+            return Err(return_dex_error_expected(
+                DexError::InvalidOrderPrice,
+                "Price not a positive multiple of the tick size",
+                format_args!("a multiple of {}", { market.tick_size }),
+                limit_price,
+            ));
+        }
+        Self::check_oracle_band(&market, Some(oracle_account), limit_price)?;
+
+        // Create the order account, paid by the owner
+        if order_account.owner == program_id {
+            Self::check_uninitialized(order_account, PerpOrder::LEN)?;
+            Self::check_rent_exempt(order_account)?;
+        } else {
+            invoke(
+                &system_instruction::create_account(
+                    owner.key,
+                    order_account.key,
+                    Rent::get()?.minimum_balance(PerpOrder::LEN),
+                    PerpOrder::LEN as u64,
+                    program_id,
+                ),
+                &[
+                    owner.clone(),
+                    order_account.clone(),
+                    system_program_account.clone(),
+                ],
+            )?;
+        }
+
+        let order = PerpOrder {
+            is_initialized: true,
+            market: *market_account.key,
+            owner: *owner.key,
+            order_id: market.next_order_id,
+            is_buy,
+            limit_price,
+            original_quantity: quantity,
+            remaining_quantity: quantity,
+            creation_timestamp: Clock::get()?.unix_timestamp as u64,
+            reduce_only,
+        };
+        order.pack_into_slice(&mut order_account.data.borrow_mut());
+        market.next_order_id += 1;
+        market.pack_into_slice(&mut market_account.data.borrow_mut());
+
+        msg!(
+            "Perp order {} to {} {} base lots at {} placed",
+            order.order_id,
+            if is_buy { "buy" } else { "sell" },
+            quantity,
+            limit_price
+        );
+        Ok(())
+    }
+
+    // Process cancel perp order instruction
+    fn process_cancel_perp_order(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let owner = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let order_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !owner.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Order owner must sign",
+            ));
+        }
+
+        let order = Self::load_perp_order(program_id, market_account, order_account)?;
+        if order.owner != *owner.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Not the order owner",
+            ));
+        }
+
+        // Perp orders lock no tokens, so only the rent is returned
+        Self::close_order_account(order_account, owner)?;
+
+        msg!("Perp order {} cancelled", order.order_id);
+        Ok(())
+    }
+
+    // Process publish prices instruction
+    fn process_publish_prices(program_id: &Pubkey, accounts: &[AccountInfo], nonce: u32, finalized: bool) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let payer = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let twap_oracle_account = next_account_info(account_info_iter)?;
+        let bridge_config = next_account_info(account_info_iter)?;
+        let price_message = next_account_info(account_info_iter)?;
+        let emitter = next_account_info(account_info_iter)?;
+        let sequence = next_account_info(account_info_iter)?;
+        let fee_collector = next_account_info(account_info_iter)?;
+        let clock_sysvar = next_account_info(account_info_iter)?;
+        let rent_sysvar = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+        let core_bridge_program = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !payer.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Payer must sign",
+            ));
+        }
+
+        if *core_bridge_program.key != wormhole::CORE_BRIDGE_PROGRAM_ID {
+            return Err(return_dex_error_expected(
+                DexError::AccountMismatch,
+                "Not the Wormhole core bridge program",
+                wormhole::CORE_BRIDGE_PROGRAM_ID,
+                core_bridge_program.key,
+            ));
+        }
+
+        if *bridge_config.key != wormhole::bridge_config_address() || *bridge_config.owner != wormhole::CORE_BRIDGE_PROGRAM_ID {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Invalid Wormhole bridge config",
+            ));
+        }
+
+        if *fee_collector.key != wormhole::fee_collector_address() {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Invalid Wormhole fee collector",
+            ));
+        }
+
+        // The core bridge checks the sequence account of the emitter itself
+        let (emitter_address, emitter_bump) = wormhole::find_emitter_address(program_id, market_account.key);
+        if emitter_address != *emitter.key {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Invalid emitter address",
+            ));
+        }
+
+        let (price_message_address, price_message_bump) = wormhole::find_price_message_address(program_id, market_account.key);
+        if price_message_address != *price_message.key {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Invalid price message address",
+            ));
+        }
+
+        let market = Self::load_market(program_id, market_account)?;
+        let clock = Clock::get()?;
+
+        // Markets without a TWAP oracle publish their book and last price only
+        let (ema_price, price_cumulative) = if market.twap_oracle_enabled {
+            let twap_oracle = Self::load_twap_oracle(program_id, market_account, twap_oracle_account)?;
+            (twap_oracle.ema_price_at(clock.unix_timestamp), twap_oracle.price_cumulative_at(clock.unix_timestamp))
+        } else {
+            (0, 0)
+        };
+
+        let prices = PricePayload {
+            market: *market_account.key,
+            best_bid: market.best_bid_price().unwrap_or(0),
+            best_ask: market.best_ask_price().unwrap_or(0),
+            last_price: market.last_price,
+            ema_price,
+            price_cumulative,
+            base_lot_size: market.base_lot_size,
+            quote_lot_size: market.quote_lot_size,
+            timestamp: clock.unix_timestamp,
+            slot: clock.slot,
+        };
+        let fee = wormhole::bridge_fee(&bridge_config.data.borrow()).ok_or_else(|| {
+            return_dex_error(DexError::InvalidAccountData, "Wormhole bridge config not initialized")
+        })?;
+
+        // Post the prices from the market's emitter into its reusable message account
+        let instruction =
+            wormhole::post_message_unreliable(price_message.key, emitter.key, payer.key, nonce, &prices.to_bytes(), finalized)?;
+        wormhole::post_price_message(
+            &[
+                bridge_config.clone(),
+                price_message.clone(),
+                emitter.clone(),
+                sequence.clone(),
+                payer.clone(),
+                fee_collector.clone(),
+                clock_sysvar.clone(),
+                rent_sysvar.clone(),
+                system_program_account.clone(),
+                core_bridge_program.clone(),
+            ],
+            fee,
+            &instruction,
+            &[
+                &[EMITTER_SEED, market_account.key.as_ref(), &[emitter_bump]],
+                &[PRICE_MESSAGE_SEED, market_account.key.as_ref(), &[price_message_bump]],
+            ],
+        )?;
+
+        msg!(
+            "Published prices of market {}: bid {}, ask {}, last {}",
+            market_account.key,
+            prices.best_bid,
+            prices.best_ask,
+            prices.last_price
+        );
+        Ok(())
+    }
+
+    // Process settle perp fill instruction
+    fn process_settle_perp_fill(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let authority = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let perp_market_account = next_account_info(account_info_iter)?;
+        let taker_order_account = next_account_info(account_info_iter)?;
+        let maker_order_account = next_account_info(account_info_iter)?;
+        let taker_position_account = next_account_info(account_info_iter)?;
+        let maker_position_account = next_account_info(account_info_iter)?;
+        let vault = next_account_info(account_info_iter)?;
+        let fee_vault = next_account_info(account_info_iter)?;
+        let _quote_mint = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let oracle_account = next_account_info(account_info_iter)?;
+        let taker_owner = next_account_info(account_info_iter)?;
+        let maker_owner = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !authority.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Authority must sign",
+            ));
+        }
+
+        Self::check_token_program(token_program)?;
+
+        // Load market
+        let mut market = Self::load_market(program_id, market_account)?;
+
+        // Verify authority
+        if market.authority != *authority.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Not market authority",
+            ));
+        }
+
+        // Matched trades do not settle while the market is paused
+        if market.status == MarketStatus::Paused {
+            return Err(return_dex_error(
+                DexError::MarketPaused,
+                "Market is paused",
+            ));
+        }
+
+        // Fees are collected in the market's fee vault
+        if Market::find_fee_vault_address(program_id, market_account.key).0 != *fee_vault.key {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Fee recipient must be the market fee vault",
+            ));
+        }
+
+        let mut perp_market = Self::load_perp_market(program_id, market_account, perp_market_account, vault)?;
+
+        // The fill is derived from the orders the owners placed, never from the caller
+        if taker_order_account.key == maker_order_account.key {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Taker and maker orders must differ",
+            ));
+        }
+        let mut taker_order = Self::load_perp_order(program_id, market_account, taker_order_account)?;
+        let mut maker_order = Self::load_perp_order(program_id, market_account, maker_order_account)?;
+        if taker_order.is_buy == maker_order.is_buy {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Taker and maker orders must be on opposite sides",
+            ));
+        }
+        if taker_order.owner == maker_order.owner {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Buyer and seller must differ",
+            ));
+        }
+        let (bid_price, ask_price) = if taker_order.is_buy {
+            (taker_order.limit_price, maker_order.limit_price)
+        } else {
+            (maker_order.limit_price, taker_order.limit_price)
+        };
+        if bid_price < ask_price {
+            msg!("Bid at {}, ask at {}", bid_price, ask_price);
+            return Err(return_dex_error(
+                DexError::InvalidOrderPrice,
+                "Orders do not cross",
+            ));
+        }
+        let price = maker_order.limit_price;
+        let quantity = taker_order.remaining_quantity.min(maker_order.remaining_quantity);
+        Self::check_oracle_band(&market, Some(oracle_account), price)?;
+
+        // Each order trades for its owner's position, and a filled order's rent goes to its owner
+        let mut taker = Self::load_perp_position(program_id, market_account, taker_position_account)?;
+        let mut maker = Self::load_perp_position(program_id, market_account, maker_position_account)?;
+        if taker.owner != taker_order.owner || maker.owner != maker_order.owner {
+            return Err(return_dex_error(
+                DexError::AccountMismatch,
+                "Perp position does not belong to the order owner",
+            ));
+        }
+        if *taker_owner.key != taker_order.owner || *maker_owner.key != maker_order.owner {
+            return Err(return_dex_error(
+                DexError::AccountMismatch,
+                "Rent recipient must be the order owner",
+            ));
+        }
+
+        // Both sides pay the funding owed before their positions change
+        taker.settle_funding(perp_market.cumulative_funding)?;
+        maker.settle_funding(perp_market.cumulative_funding)?;
+
+        // Reduce-only orders take no more of the fill than closes their owner's position
+        let clipped = taker_order
+            .clip_reduce_only(taker.base_position, quantity)
+            .min(maker_order.clip_reduce_only(maker.base_position, quantity));
+        if clipped == 0 {
+            return Err(return_dex_error(
+                DexError::ReduceOnly,
+                "Fill would not shrink the position of a reduce-only order",
+            ));
+        }
+        if clipped < quantity {
+            msg!("Fill of {} base lots clipped to {} for a reduce-only order", quantity, clipped);
+        }
+        let quantity = clipped;
+
+        let quote_amount = market.notional(price, quantity)?;
+        let taker_fee = market.calculate_taker_fee(quote_amount)?;
+        let maker_fee = market.calculate_maker_fee(quote_amount)?;
+        let taker_fee = i64::try_from(taker_fee).map_err(|_| ProgramError::ArithmeticOverflow)?;
+        let quote_amount = i64::try_from(quote_amount).map_err(|_| ProgramError::ArithmeticOverflow)?;
+        let base_lots = quantity as i64;
+
+        // Buyers pay the notional plus their fee, sellers receive it minus theirs
+        let trade = |is_buy: bool, fee: i64| {
+            if is_buy {
+                quote_amount.checked_add(fee).map(|cost| (base_lots, -cost))
+            } else {
+                quote_amount.checked_sub(fee).map(|proceeds| (-base_lots, proceeds))
+            }
+        };
+        let mark_price = price;
+        for (position, trade) in [
+            (&mut taker, trade(taker_order.is_buy, taker_fee)),
+            (&mut maker, trade(maker_order.is_buy, maker_fee)),
+        ] {
+            let (base_lots, quote_delta) = trade.ok_or(ProgramError::ArithmeticOverflow)?;
+            let before = position.base_position;
+            position.trade(base_lots, quote_delta)?;
+            perp_market.record_position_change(before, position.base_position)?;
+
+            // A fill that shrinks a position is allowed whatever its margin
+            let shrank = position.base_position.unsigned_abs() < before.unsigned_abs()
+                && position.base_position.signum() * before.signum() >= 0;
+            if !shrank && !position.is_healthy(mark_price, market.quote_lot_size, perp_market.initial_margin_bps) {
+                msg!("Perp position of {} lacks margin", position.owner);
+                return Err(return_dex_error_expected(
+                    DexError::InsufficientMargin,
+                    "Fill leaves too little margin",
+                    format_args!(
+                        "equity of at least {}",
+                        position.initial_margin(mark_price, market.quote_lot_size, perp_market.initial_margin_bps)
+                    ),
+                    position.equity(mark_price, market.quote_lot_size),
+                ));
+            }
+        }
+        perp_market.mark_price = mark_price;
+
+        // Fill both orders, closing the ones with nothing left
+        for (order, order_account, owner) in [
+            (&mut taker_order, taker_order_account, taker_owner),
+            (&mut maker_order, maker_order_account, maker_owner),
+        ] {
+            order.remaining_quantity -= quantity;
+            if order.remaining_quantity == 0 {
+                Self::close_order_account(order_account, owner)?;
+            } else {
+                order.pack_into_slice(&mut order_account.data.borrow_mut());
+            }
+        }
+
+        // The fees net of any maker rebate leave the positions for the fee vault
+        let vault_fee = u64::try_from(taker_fee + maker_fee).map_err(|_| ProgramError::ArithmeticOverflow)?;
+        market.fees_accrued = market.fees_accrued.saturating_add(vault_fee);
+        market.last_trade_slot = Clock::get()?.slot;
+        market.pack_into_slice(&mut market_account.data.borrow_mut());
+        perp_market.pack_into_slice(&mut perp_market_account.data.borrow_mut());
+        taker.pack_into_slice(&mut taker_position_account.data.borrow_mut());
+        maker.pack_into_slice(&mut maker_position_account.data.borrow_mut());
+
+        if vault_fee > 0 {
+            let side = Self::load_token_side(&market, accounts, true)?;
+            Self::transfer_tokens(
+                &side,
+                vault,
+                fee_vault,
+                perp_market_account,
+                vault_fee,
+                &[&[PERP_MARKET_SEED, market_account.key.as_ref(), &[perp_market.bump]]],
+            )?;
+        }
+
+        msg!("Perp fill of {} base lots at {} settled", quantity, price);
+        Ok(())
+    }
+
+    // Process update funding instruction
+    fn process_update_funding(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let market_account = next_account_info(account_info_iter)?;
+        let perp_market_account = next_account_info(account_info_iter)?;
+        let oracle_account = next_account_info(account_info_iter)?;
+
+        // Load market and perp market
+        let market = Self::load_market(program_id, market_account)?;
+        if perp_market_account.owner != program_id
+            || PerpMarket::find_address(program_id, market_account.key).0 != *perp_market_account.key
+        {
+            return Err(return_dex_error(
+                DexError::AccountMismatch,
+                "Perp market does not belong to the market",
+            ));
+        }
+        let mut perp_market = PerpMarket::unpack_from_slice(&perp_market_account.data.borrow())?;
+
+        // Funding is paid for whole intervals only
+        let now = Clock::get()?.unix_timestamp;
+        let intervals = now.saturating_sub(perp_market.last_funding_timestamp) / FUNDING_INTERVAL_SECONDS;
+        if intervals <= 0 {
+            return Err(return_dex_error_expected(
+                DexError::FundingNotDue,
+                "Funding interval has not passed",
+                format_args!("at least {}", perp_market.last_funding_timestamp + FUNDING_INTERVAL_SECONDS),
+                now,
+            ));
+        }
+
+        let index_price = u64::try_from(Self::load_oracle_price(&market, Some(oracle_account))?)
+            .map_err(|_| return_dex_error(DexError::InvalidOraclePrice, "Oracle price not usable"))?;
+        let mark_price = perp_market.mark_price_or(index_price);
+        let funding = perp_market
+            .funding_per_interval(mark_price, index_price, market.quote_lot_size)
+            .checked_mul(intervals as i128)
+            .ok_or(DexError::ArithmeticOverflow)?;
+        perp_market.cumulative_funding = perp_market
+            .cumulative_funding
+            .checked_add(funding)
+            .ok_or(DexError::ArithmeticOverflow)?;
+        perp_market.last_funding_timestamp += intervals * FUNDING_INTERVAL_SECONDS;
+        perp_market.pack_into_slice(&mut perp_market_account.data.borrow_mut());
+
+        msg!(
+            "Funding of {} per base lot paid for {} intervals at mark {} and index {}",
+            funding,
+            intervals,
+            mark_price,
+            index_price
+        );
+        Ok(())
+    }
+
+    // Process initialize batch auction instruction
+    fn process_initialize_batch_auction(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        interval_slots: u64,
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let authority = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let batch_auction_account = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !authority.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Authority must sign",
+            ));
+        }
+
+        Self::check_system_program(system_program_account)?;
+
+        // Load market
+        let mut market = Self::load_market(program_id, market_account)?;
+
+        // Verify authority
+        if market.authority != *authority.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Not market authority",
+            ));
+        }
+        if market.has_flag(MARKET_FLAG_BATCH_AUCTION) {
+            return Err(return_dex_error(
+                DexError::AlreadyInitialized,
+                "Batch auction already initialized",
+            ));
+        }
+        if interval_slots == 0 {
+            return Err(return_dex_error(
+                DexError::InvalidInstructionData,
+                "Auction interval must be positive",
+            ));
+        }
+
+        // Every auction takes all resting orders in one transaction
+        let num_orders = market.num_bids.saturating_add(market.num_asks);
+        if num_orders > MAX_AUCTION_ORDERS {
+            return Err(return_dex_error_expected(
+                DexError::OrderBookFull,
+                "Too many resting orders for a batch auction",
+                format_args!("at most {} orders", MAX_AUCTION_ORDERS),
+                num_orders,
+            ));
+        }
+
+        let (batch_auction_address, bump) = BatchAuction::find_address(program_id, market_account.key);
+        if batch_auction_address != *batch_auction_account.key {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Invalid batch auction address",
+            ));
+        }
+
+        // Create the auction PDA, paid by the authority
+        let rent = Rent::get()?;
+        let space = BatchAuction::LEN;
+        let lamports = rent.minimum_balance(space);
+
+        invoke_signed(
+            &system_instruction::create_account(
+                authority.key,
+                batch_auction_account.key,
+                lamports,
+                space as u64,
+                program_id,
+            ),
+            &[
+                authority.clone(),
+                batch_auction_account.clone(),
+                system_program_account.clone(),
+            ],
+            &[&[BATCH_AUCTION_SEED, market_account.key.as_ref(), &[bump]]],
+        )?;
+
+        let auction = BatchAuction {
+            is_initialized: true,
+            market: *market_account.key,
+            interval_slots,
+            batch_start_slot: Clock::get()?.slot,
+            last_clearing_price: 0,
+            last_volume: 0,
+            auction_count: 0,
+        };
+        auction.pack_into_slice(&mut batch_auction_account.data.borrow_mut());
+
+        market.flags |= MARKET_FLAG_BATCH_AUCTION;
+        market.pack_into_slice(&mut market_account.data.borrow_mut());
+
+        msg!("Batch auction initialized every {} slots", interval_slots);
+        Ok(())
+    }
+
+    // Process run auction instruction
+    fn process_run_auction(program_id: &Pubkey, accounts: &[AccountInfo], num_orders: u8) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let market_account = next_account_info(account_info_iter)?;
+        let batch_auction_account = next_account_info(account_info_iter)?;
+        let base_vault = next_account_info(account_info_iter)?;
+        let quote_vault = next_account_info(account_info_iter)?;
+        let market_signer = next_account_info(account_info_iter)?;
+        let fee_vault = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let order_accounts = accounts
+            .get(7..7 + 4 * num_orders as usize)
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        let find = |key: &Pubkey| accounts.iter().find(|account| account.key == key);
+
+        // Verify accounts
+        Self::check_token_program(token_program)?;
+
+        // Load market and auction
+        // Fills update the market in place
+        let mut market = Self::load_market_mut(program_id, market_account)?;
+        if market.status == MarketStatus::Paused {
+            return Err(return_dex_error(
+                DexError::MarketPaused,
+                "Market is paused",
+            ));
+        }
+        let mut auction = Self::load_batch_auction(program_id, market_account, batch_auction_account)?;
+
+        let clock = Clock::get()?;
+        if clock.slot < auction.next_auction_slot() {
+            return Err(return_dex_error_expected(
+                DexError::AuctionNotDue,
+                "Batch has not closed",
+                format_args!("slot {} or later", auction.next_auction_slot()),
+                clock.slot,
+            ));
+        }
+
+        if Market::find_fee_vault_address(program_id, market_account.key).0 != *fee_vault.key {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Invalid fee vault address",
+            ));
+        }
+        let escrow = Self::load_escrow(program_id, accounts, market_account, &market, base_vault, quote_vault, market_signer)?;
+
+        // Every resting order takes part, so leaving orders out cannot move the price
+        let mut bids = Vec::new();
+        let mut asks = Vec::new();
+        for (index, order_accounts_of) in order_accounts.chunks_exact(4).enumerate() {
+            let [order_account, order_owner, owner_base_account, owner_quote_account] = order_accounts_of else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+            if order_accounts[..4 * index].chunks_exact(4).any(|earlier| earlier[0].key == order_account.key) {
+                return Err(return_dex_error(
+                    DexError::InvalidAccountData,
+                    "Order passed twice",
+                ));
+            }
+            let order = Self::load_order(program_id, market_account, order_account)?;
+            Self::check_owner_token_account(&market, &order.owner, false, owner_base_account)?;
+            Self::check_owner_token_account(&market, &order.owner, true, owner_quote_account)?;
+            Self::check_rent_recipient(&order, order_owner)?;
+            let quantity = order.visible_quantity;
+            if order.is_buy {
+                bids.push((order_accounts_of, order, quantity));
+            } else {
+                asks.push((order_accounts_of, order, quantity));
+            }
+        }
+        if bids.len() as u64 != market.num_bids || asks.len() as u64 != market.num_asks {
+            return Err(return_dex_error_expected(
+                DexError::InvalidAccountData,
+                "Auction must pass every resting order",
+                format_args!("{} bids and {} asks", { market.num_bids }, { market.num_asks }),
+                format_args!("{} bids and {} asks", bids.len(), asks.len()),
+            ));
+        }
+
+        // Best price first, oldest first within a price
+        bids.sort_by(|(_, a, _), (_, b, _)| { b.limit_price }.cmp(&{ a.limit_price }).then({ a.order_id }.cmp(&{ b.order_id })));
+        asks.sort_by(|(_, a, _), (_, b, _)| { a.limit_price }.cmp(&{ b.limit_price }).then({ a.order_id }.cmp(&{ b.order_id })));
+        let levels = |orders: &[(&[AccountInfo], Order, u64)]| -> Vec<(u64, u64)> {
+            orders.iter().map(|(_, order, quantity)| (order.limit_price, *quantity)).collect()
+        };
+
+        auction.batch_start_slot = clock.slot;
+        auction.auction_count = auction.auction_count.saturating_add(1);
+        let Some((price, volume)) = BatchAuction::clearing_price(&levels(&bids), &levels(&asks), market.tick_size) else {
+            auction.last_volume = 0;
+            auction.pack_into_slice(&mut batch_auction_account.data.borrow_mut());
+            msg!("No orders cross; the next batch has started");
+            return Ok(());
+        };
+
+        // Markets with an oracle circuit breaker only clear near the oracle
+        if market.has_oracle() {
+            Self::check_oracle_band(&market, find(&{ market.oracle }), price)?;
+        }
+
+        // Fills are recorded like settled ones
+        let mut event_queue = if market.has_flag(MARKET_FLAG_EVENT_QUEUE) {
+            let event_queue_account = find(&EventQueue::find_address(program_id, market_account.key).0)
+                .ok_or(ProgramError::NotEnoughAccountKeys)?;
+            Some(Self::load_event_queue_mut(program_id, market_account, event_queue_account)?)
+        } else {
+            None
+        };
+        let mut twap_oracle = if market.twap_oracle_enabled {
+            let twap_oracle_account = find(&TwapOracle::find_address(program_id, market_account.key).0)
+                .ok_or(ProgramError::NotEnoughAccountKeys)?;
+            Some((twap_oracle_account, Self::load_twap_oracle(program_id, market_account, twap_oracle_account)?))
+        } else {
+            None
+        };
+
+        // Pair bids with asks in priority order until the crossed volume is filled
+        let timestamp = clock.unix_timestamp as u64;
+        let (mut bid_index, mut ask_index) = (0, 0);
+        let mut remaining = volume;
+        let mut filled_orders = Vec::new();
+        while remaining > 0 {
+            let (bid_accounts, bid, bid_left) = &mut bids[bid_index];
+            let (ask_accounts, ask, ask_left) = &mut asks[ask_index];
+            let quantity = remaining.min(*bid_left).min(*ask_left);
+            let base_amount = market.base_lots_to_native(quantity)?;
+            let quote_amount = market.notional(price, quantity)?;
+
+            // Both sides pay the taker fee; the bid's lock at its limit price covers its cost
+            let fee = market.calculate_taker_fee(quote_amount)?;
+            let bid_cost = quote_amount.checked_add(fee).ok_or(ProgramError::ArithmeticOverflow)?;
+            let bid_refund = market
+                .escrow_amount(true, bid.limit_price, quantity)?
+                .checked_sub(bid_cost)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+            let ask_proceeds = quote_amount.checked_sub(fee).ok_or(ProgramError::ArithmeticOverflow)?;
+            let vault_fee = fee.checked_mul(2).ok_or(ProgramError::ArithmeticOverflow)?;
+
+            Self::fill_resting_order(&mut market, bid, &bid_accounts[0], &bid_accounts[1], quantity, timestamp)?;
+            Self::fill_resting_order(&mut market, ask, &ask_accounts[0], &ask_accounts[1], quantity, timestamp)?;
+            filled_orders.extend([*bid_accounts[0].key, *ask_accounts[0].key]);
+            let seq_num = market.fill_seq_num;
+            market.fill_seq_num = seq_num.checked_add(1).ok_or(ProgramError::ArithmeticOverflow)?;
+            market.last_price = price;
+            market.last_trade_slot = clock.slot;
+            market.record_volume(base_amount, quote_amount, timestamp);
+            market.fees_accrued = market.fees_accrued.saturating_add(vault_fee);
+            if let Some((_, twap_oracle)) = twap_oracle.as_mut() {
+                twap_oracle.record_fill(price, timestamp as i64);
+            }
+            if let Some(event_queue) = event_queue.as_mut() {
+                event_queue.push(FillEvent {
+                    seq_num,
+                    slot: clock.slot,
+                    taker_order: *bid_accounts[0].key,
+                    maker_order: *ask_accounts[0].key,
+                    price,
+                    base_amount,
+                    quote_amount,
+                });
+            }
+
+            // The escrow pays the bid base tokens and its refund, the ask its proceeds
+            Self::pay_from_escrow(&escrow, false, &bid_accounts[2], base_amount)?;
+            Self::pay_from_escrow(&escrow, true, &ask_accounts[3], ask_proceeds)?;
+            if vault_fee > 0 {
+                Self::pay_from_escrow(&escrow, true, fee_vault, vault_fee)?;
+            }
+            if bid_refund > 0 {
+                Self::pay_from_escrow(&escrow, true, &bid_accounts[3], bid_refund)?;
+            }
+
+            // The bid stands in for the taker order
+            Self::emit_event(DexEvent::Fill(Fill {
+                market: *market_account.key,
+                seq_num,
+                taker_order: *bid_accounts[0].key,
+                maker_order: *ask_accounts[0].key,
+                base_amount,
+                quote_amount,
+                taker_fee: fee,
+                maker_fee: fee as i64,
+                slot: clock.slot,
+                taker_memo: bid.memo().to_string(),
+                maker_memo: ask.memo().to_string(),
+            }))?;
+
+            *bid_left -= quantity;
+            *ask_left -= quantity;
+            remaining -= quantity;
+            if *bid_left == 0 {
+                bid_index += 1;
+            }
+            if *ask_left == 0 {
+                ask_index += 1;
+            }
+        }
+
+        // Filling one leg of a one-cancels-other pair cancels what is left of the other, and
+        // unlinks a filled leg that is still open
+        for (order_accounts_of, order, _) in bids.iter_mut().chain(asks.iter_mut()) {
+            if !order.is_linked() || order.remaining_quantity == 0 {
+                continue;
+            }
+            if filled_orders.contains(&order.linked_order) {
+                let owner_token_account = if order.is_buy {
+                    &order_accounts_of[3]
+                } else {
+                    &order_accounts_of[2]
+                };
+                Self::release_order(&mut market, &escrow, order, &order_accounts_of[0], owner_token_account, &order_accounts_of[1])?;
+            } else if filled_orders.contains(order_accounts_of[0].key) {
+                order.linked_order = Pubkey::default();
+                order.pack_into_slice(&mut order_accounts_of[0].data.borrow_mut());
+            }
+        }
+
+        if let Some((twap_oracle_account, twap_oracle)) = twap_oracle {
+            twap_oracle.pack_into_slice(&mut twap_oracle_account.data.borrow_mut());
+        }
+        auction.last_clearing_price = price;
+        auction.last_volume = volume;
+        auction.pack_into_slice(&mut batch_auction_account.data.borrow_mut());
+
+        msg!("Auction crossed {} base lots at {}", volume, price);
+        Ok(())
+    }
+
+    // Process set perp reduce-only instruction
+    fn process_set_perp_reduce_only(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        reduce_only: bool,
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let owner = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let order_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !owner.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Owner must sign",
+            ));
+        }
+
+        // Load order
+        let mut order = Self::load_perp_order(program_id, market_account, order_account)?;
+        if order.owner != *owner.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Not the order owner",
+            ));
+        }
+
+        order.reduce_only = reduce_only;
+        order.pack_into_slice(&mut order_account.data.borrow_mut());
+
+        msg!("Perp order {} reduce-only set to {}", order.order_id, reduce_only);
+        Ok(())
+    }
+
+    // Process initialize token sale instruction
+    fn process_initialize_token_sale(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        base_amount: u64,
+        min_price: u64,
+        max_contribution: u64,
+        duration_seconds: u64,
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let authority = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let sale_account = next_account_info(account_info_iter)?;
+        let base_vault = next_account_info(account_info_iter)?;
+        let quote_vault = next_account_info(account_info_iter)?;
+        let source = next_account_info(account_info_iter)?;
+        let base_mint = next_account_info(account_info_iter)?;
+        let quote_mint = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !authority.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Authority must sign",
+            ));
+        }
+
+        Self::check_token_program(token_program)?;
+        Self::check_system_program(system_program_account)?;
+
+        // Load market
+        let market = Self::load_market(program_id, market_account)?;
+
+        // Verify authority
+        if market.authority != *authority.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Not market authority",
+            ));
+        }
+
+        // The sale sets the market's opening price, so nothing may trade before it
+        if market.status != MarketStatus::Paused {
+            return Err(return_dex_error_expected(
+                DexError::MarketNotActive,
+                "Token sales open paused markets",
+                "status Paused",
+                format_args!("{:?}", { market.status }),
+            ));
+        }
+        if market.num_bids != 0 || market.num_asks != 0 || market.last_price != 0 {
+            return Err(return_dex_error(
+                DexError::MarketNotEmpty,
+                "Market has already held orders",
+            ));
+        }
+        if market.base_mint != *base_mint.key || market.quote_mint != *quote_mint.key {
+            return Err(return_dex_error(
+                DexError::InvalidMint,
+                "Sale mints must match the market",
+            ));
+        }
+        if base_amount < market.base_lot_size {
+            return Err(return_dex_error_expected(
+                DexError::InvalidInstructionData,
+                "Too few tokens for sale",
+                format_args!("at least one base lot of {}", { market.base_lot_size }),
+                base_amount,
+            ));
+        }
+        let end_timestamp = i64::try_from(duration_seconds)
+            .ok()
+            .filter(|duration| *duration > 0)
+            .and_then(|duration| Clock::get().ok()?.unix_timestamp.checked_add(duration))
+            .ok_or_else(|| return_dex_error(DexError::InvalidInstructionData, "Invalid sale duration"))?;
+
+        let (sale_address, bump) = TokenSale::find_address(program_id, market_account.key);
+        if sale_address != *sale_account.key {
+            return Err(return_dex_error_expected(
+                DexError::InvalidAccountData,
+                "Invalid token sale address",
+                sale_address,
+                sale_account.key,
+            ));
+        }
+        if sale_account.owner == program_id {
+            return Err(return_dex_error(
+                DexError::AlreadyInitialized,
+                "Token sale already initialized",
+            ));
+        }
+
+        // Create the sale PDA, paid by the authority
+        let rent = Rent::get()?;
+        invoke_signed(
+            &system_instruction::create_account(
+                authority.key,
+                sale_account.key,
+                rent.minimum_balance(TokenSale::LEN),
+                TokenSale::LEN as u64,
+                program_id,
+            ),
+            &[
+                authority.clone(),
+                sale_account.clone(),
+                system_program_account.clone(),
+            ],
+            &[&[TOKEN_SALE_SEED, market_account.key.as_ref(), &[bump]]],
+        )?;
+
+        let vaults = [
+            (TOKEN_SALE_BASE_VAULT_SEED, base_vault, base_mint),
+            (TOKEN_SALE_QUOTE_VAULT_SEED, quote_vault, quote_mint),
+        ];
+        for (is_quote, (seed, vault, mint)) in vaults.into_iter().enumerate() {
+            let (vault_address, vault_bump) = TokenSale::find_vault_address(program_id, market_account.key, is_quote == 1);
+            if vault_address != *vault.key {
+                return Err(return_dex_error_expected(
+                    DexError::InvalidAccountData,
+                    "Invalid token sale vault address",
+                    vault_address,
+                    vault.key,
+                ));
+            }
+
+            // Create the vault PDA as a token account of its mint's program owned by the sale
+            let vault_token_program = Self::load_token_side(&market, accounts, is_quote == 1)?.token_program;
+            let space = token::account_len(&mint.data.borrow())?;
+            invoke_signed(
+                &system_instruction::create_account(
+                    authority.key,
+                    vault.key,
+                    rent.minimum_balance(space),
+                    space as u64,
+                    vault_token_program.key,
+                ),
+                &[
+                    authority.clone(),
+                    vault.clone(),
+                    system_program_account.clone(),
+                ],
+                &[&[seed, market_account.key.as_ref(), &[vault_bump]]],
+            )?;
+
+            invoke(
+                &token_2022_instruction::initialize_account3(
+                    vault_token_program.key,
+                    vault.key,
+                    mint.key,
+                    sale_account.key,
+                )?,
+                &[vault.clone(), mint.clone(), vault_token_program.clone()],
+            )?;
+        }
+
+        let sale = TokenSale {
+            is_initialized: true,
+            market: *market_account.key,
+            bump,
+            base_amount,
+            min_price,
+            max_contribution,
+            end_timestamp,
+            total_contributed: 0,
+            clearing_price: 0,
+            status: SaleStatus::Open,
+        };
+        sale.pack_into_slice(&mut sale_account.data.borrow_mut());
+
+        // Move the tokens for sale into the base vault
+        let side = Self::load_token_side(&market, accounts, false)?;
+        Self::transfer_tokens_in(&side, source, base_vault, authority, base_amount, &[])?;
+
+        msg!(
+            "Token sale of {} base tokens initialized until {} with a minimum price of {}",
+            base_amount,
+            end_timestamp,
+            min_price
+        );
+        Ok(())
+    }
+
+    // Process contribute to sale instruction
+    fn process_contribute_to_sale(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let contributor = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let sale_account = next_account_info(account_info_iter)?;
+        let contribution_account = next_account_info(account_info_iter)?;
+        let quote_vault = next_account_info(account_info_iter)?;
+        let source = next_account_info(account_info_iter)?;
+        let _quote_mint = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !contributor.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Contributor must sign",
+            ));
+        }
+
+        Self::check_token_program(token_program)?;
+        Self::check_system_program(system_program_account)?;
+
+        // Load market and sale
+        let market = Self::load_market(program_id, market_account)?;
+        let mut sale = Self::load_token_sale(program_id, market_account, sale_account)?;
+        Self::check_token_sale_vault(program_id, market_account, quote_vault, true)?;
+
+        let timestamp = Clock::get()?.unix_timestamp;
+        if sale.status != SaleStatus::Open || timestamp >= sale.end_timestamp {
+            return Err(return_dex_error_expected(
+                DexError::SaleClosed,
+                "Sale no longer accepts contributions",
+                format_args!("before {}", sale.end_timestamp),
+                timestamp,
+            ));
+        }
+        if amount == 0 {
+            return Err(return_dex_error(
+                DexError::InvalidInstructionData,
+                "Contribution must be positive",
+            ));
+        }
+
+        let (contribution_address, contribution_bump) =
+            SaleContribution::find_address(program_id, market_account.key, contributor.key);
+        if contribution_address != *contribution_account.key {
+            return Err(return_dex_error_expected(
+                DexError::InvalidAccountData,
+                "Invalid sale contribution address",
+                contribution_address,
+                contribution_account.key,
+            ));
+        }
+
+        // Create the contribution on the first payment, paid by the contributor
+        let mut contribution = if contribution_account.owner == program_id {
+            SaleContribution::unpack_from_slice(&contribution_account.data.borrow())?
+        } else {
+            invoke_signed(
+                &system_instruction::create_account(
+                    contributor.key,
+                    contribution_account.key,
+                    Rent::get()?.minimum_balance(SaleContribution::LEN),
+                    SaleContribution::LEN as u64,
+                    program_id,
+                ),
+                &[
+                    contributor.clone(),
+                    contribution_account.clone(),
+                    system_program_account.clone(),
+                ],
+                &[&[
+                    SALE_CONTRIBUTION_SEED,
+                    market_account.key.as_ref(),
+                    contributor.key.as_ref(),
+                    &[contribution_bump],
+                ]],
+            )?;
+            SaleContribution {
+                is_initialized: true,
+                market: *market_account.key,
+                owner: *contributor.key,
+                amount: 0,
+            }
+        };
+
+        contribution.amount = contribution
+            .amount
+            .checked_add(amount)
+            .ok_or(DexError::ArithmeticOverflow)?;
+        if sale.max_contribution != 0 && contribution.amount > sale.max_contribution {
+            return Err(return_dex_error_expected(
+                DexError::ContributionCapExceeded,
+                "Contribution exceeds the sale's cap",
+                format_args!("at most {}", sale.max_contribution),
+                contribution.amount,
+            ));
+        }
+        sale.total_contributed = sale
+            .total_contributed
+            .checked_add(amount)
+            .ok_or(DexError::ArithmeticOverflow)?;
+        contribution.pack_into_slice(&mut contribution_account.data.borrow_mut());
+        sale.pack_into_slice(&mut sale_account.data.borrow_mut());
+
+        // Move the contribution into the quote vault
+        let side = Self::load_token_side(&market, accounts, true)?;
+        Self::transfer_tokens_in(&side, source, quote_vault, contributor, amount, &[])?;
+
+        msg!("Contributed {} to the token sale, {} in total", amount, contribution.amount);
+        Ok(())
+    }
+
+    // Process finalize sale instruction
+    fn process_finalize_sale(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let market_account = next_account_info(account_info_iter)?;
+        let sale_account = next_account_info(account_info_iter)?;
+        let base_vault = next_account_info(account_info_iter)?;
+        let quote_vault = next_account_info(account_info_iter)?;
+        let authority_base_account = next_account_info(account_info_iter)?;
+        let authority_quote_account = next_account_info(account_info_iter)?;
+        let _base_mint = next_account_info(account_info_iter)?;
+        let _quote_mint = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        Self::check_token_program(token_program)?;
+
+        // Load market and sale
+        let mut market = Self::load_market(program_id, market_account)?;
+        let mut sale = Self::load_token_sale(program_id, market_account, sale_account)?;
+        Self::check_token_sale_vault(program_id, market_account, base_vault, false)?;
+        Self::check_token_sale_vault(program_id, market_account, quote_vault, true)?;
+        Self::check_owner_token_account(&market, &market.authority, false, authority_base_account)?;
+        Self::check_owner_token_account(&market, &market.authority, true, authority_quote_account)?;
+
+        if sale.status != SaleStatus::Open {
+            return Err(return_dex_error(
+                DexError::SaleClosed,
+                "Sale already finalized",
+            ));
+        }
+        let timestamp = Clock::get()?.unix_timestamp;
+        if timestamp < sale.end_timestamp {
+            return Err(return_dex_error_expected(
+                DexError::SaleNotEnded,
+                "Sale still accepts contributions",
+                format_args!("from {}", sale.end_timestamp),
+                timestamp,
+            ));
+        }
+
+        // Every contributor pays the same price, so the price is what the contributions buy
+        let price = sale.implied_price(&market);
+        let price = price - price % market.tick_size;
+        let succeeded = price > 0 && price >= sale.min_price;
+        sale.clearing_price = price;
+        sale.status = if succeeded { SaleStatus::Succeeded } else { SaleStatus::Failed };
+        sale.pack_into_slice(&mut sale_account.data.borrow_mut());
+
+        let seeds: &[&[u8]] = &[TOKEN_SALE_SEED, market_account.key.as_ref(), &[sale.bump]];
+        if succeeded {
+            // Pay the proceeds to the authority and open the market at the clearing price
+            let side = Self::load_token_side(&market, accounts, true)?;
+            Self::transfer_tokens(
+                &side,
+                quote_vault,
+                authority_quote_account,
+                sale_account,
+                sale.total_contributed,
+                &[seeds],
+            )?;
+
+            market.status = MarketStatus::Active;
+            market.last_price = price;
+            market.pack_into_slice(&mut market_account.data.borrow_mut());
+            Self::emit_params_updated(market_account, &market.authority, MarketParam::Status(MarketStatus::Active))?;
+
+            msg!("Token sale raised {} at a clearing price of {}", { sale.total_contributed }, price);
+        } else {
+            // Give the tokens for sale back; contributors claim refunds
+            let side = Self::load_token_side(&market, accounts, false)?;
+            Self::transfer_tokens(
+                &side,
+                base_vault,
+                authority_base_account,
+                sale_account,
+                sale.base_amount,
+                &[seeds],
+            )?;
+
+            msg!(
+                "Token sale failed at a clearing price of {} below the minimum of {}",
+                price,
+                sale.min_price
+            );
+        }
+        Ok(())
+    }
+
+    // Process claim sale instruction
+    fn process_claim_sale(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let contributor = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let sale_account = next_account_info(account_info_iter)?;
+        let contribution_account = next_account_info(account_info_iter)?;
+        let base_vault = next_account_info(account_info_iter)?;
+        let quote_vault = next_account_info(account_info_iter)?;
+        let destination = next_account_info(account_info_iter)?;
+        let _base_mint = next_account_info(account_info_iter)?;
+        let _quote_mint = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !contributor.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Contributor must sign",
+            ));
+        }
+
+        Self::check_token_program(token_program)?;
+
+        // Load market, sale and contribution
+        let market = Self::load_market(program_id, market_account)?;
+        let sale = Self::load_token_sale(program_id, market_account, sale_account)?;
+        Self::check_token_sale_vault(program_id, market_account, base_vault, false)?;
+        Self::check_token_sale_vault(program_id, market_account, quote_vault, true)?;
+        if contribution_account.owner != program_id
+            || SaleContribution::find_address(program_id, market_account.key, contributor.key).0 != *contribution_account.key
+        {
+            return Err(return_dex_error(
+                DexError::AccountMismatch,
+                "Contribution does not belong to the contributor",
+            ));
+        }
+        let contribution = SaleContribution::unpack_from_slice(&contribution_account.data.borrow())?;
+
+        // A successful sale pays base tokens, a failed one refunds the quote tokens
+        let (is_quote, vault, amount) = match sale.status {
+            SaleStatus::Open => {
+                return Err(return_dex_error(
+                    DexError::SaleClosed,
+                    "Sale not finalized",
+                ));
+            }
+            SaleStatus::Succeeded => (false, base_vault, sale.allocation(contribution.amount)),
+            SaleStatus::Failed => (true, quote_vault, contribution.amount),
+        };
+        Self::check_owner_token_account(&market, contributor.key, is_quote, destination)?;
+
+        if amount > 0 {
+            let side = Self::load_token_side(&market, accounts, is_quote)?;
+            Self::transfer_tokens(
+                &side,
+                vault,
+                destination,
+                sale_account,
+                amount,
+                &[&[TOKEN_SALE_SEED, market_account.key.as_ref(), &[sale.bump]]],
+            )?;
+        }
+        Self::close_order_account(contribution_account, contributor)?;
+
+        if is_quote {
+            msg!("Refunded {} contributed to the failed token sale", amount);
+        } else {
+            msg!("Claimed {} base tokens for a contribution of {}", amount, contribution.amount);
+        }
+        Ok(())
+    }
+
+    // Load a market's token sale
+    fn load_token_sale(
+        program_id: &Pubkey,
+        market_account: &AccountInfo,
+        sale_account: &AccountInfo,
+    ) -> Result<TokenSale, ProgramError> {
+        if sale_account.owner != program_id {
+            return Err(return_dex_error(
+                DexError::InvalidAccountOwner,
+                "Token sale not owned by the program",
+            ));
+        }
+        if TokenSale::find_address(program_id, market_account.key).0 != *sale_account.key {
+            return Err(return_dex_error(
+                DexError::AccountMismatch,
+                "Token sale does not belong to the market",
+            ));
+        }
+
+        TokenSale::unpack_from_slice(&sale_account.data.borrow())
+    }
+
+    // Check the address of a token sale's base or quote vault
+    fn check_token_sale_vault(
+        program_id: &Pubkey,
+        market_account: &AccountInfo,
+        vault: &AccountInfo,
+        is_quote: bool,
+    ) -> ProgramResult {
+        let (vault_address, _) = TokenSale::find_vault_address(program_id, market_account.key, is_quote);
+        if vault_address != *vault.key {
+            return Err(return_dex_error_expected(
+                DexError::InvalidAccountData,
+                "Invalid token sale vault",
+                vault_address,
+                vault.key,
+            ));
+        }
+
+        Ok(())
+    }
+
+    // Process initialize stake pool instruction
+    fn process_initialize_stake_pool(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        epoch_seconds: u64,
+        fee_share_bps: u16,
+        fee_tiers: [FeeTier; 4],
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let admin = next_account_info(account_info_iter)?;
+        let config_account = next_account_info(account_info_iter)?;
+        let stake_pool_account = next_account_info(account_info_iter)?;
+        let stake_vault = next_account_info(account_info_iter)?;
+        let reward_vault = next_account_info(account_info_iter)?;
+        let governance_mint = next_account_info(account_info_iter)?;
+        let reward_mint = next_account_info(account_info_iter)?;
+        let governance_token_program = next_account_info(account_info_iter)?;
+        let reward_token_program = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !admin.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Config admin must sign",
+            ));
+        }
+
+        Self::check_token_program(governance_token_program)?;
+        Self::check_token_program(reward_token_program)?;
+        Self::check_system_program(system_program_account)?;
+
+        // Load config
+        let config = Self::load_program_config(program_id, config_account)?.ok_or_else(|| {
+            return_dex_error(DexError::InvalidAccountData, "Program config not initialized")
+        })?;
+
+        // Verify admin
+        if config.admin != *admin.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Not config admin",
+            ));
+        }
+
+        if epoch_seconds == 0 || epoch_seconds > i64::MAX as u64 {
+            return Err(return_dex_error(
+                DexError::InvalidInstructionData,
+                "Epoch length must be positive",
+            ));
+        }
+        if fee_share_bps as u64 > math::BPS_DENOMINATOR {
+            return Err(return_dex_error_expected(
+                DexError::InvalidInstructionData,
+                "Invalid fee share",
+                format_args!("at most {} bps", math::BPS_DENOMINATOR),
+                fee_share_bps,
+            ));
+        }
+        if !StakePool::fee_tiers_valid(&fee_tiers) {
+            return Err(return_dex_error(
+                DexError::InvalidInstructionData,
+                "Fee tiers must ask for growing stakes and grant growing discounts",
+            ));
+        }
+
+        let mints = [
+            (governance_mint, governance_token_program),
+            (reward_mint, reward_token_program),
+        ];
+        for (mint, token_program) in mints {
+            if mint.owner != token_program.key {
+                return Err(return_dex_error_expected(
+                    DexError::InvalidMint,
+                    "Token program does not own the mint",
+                    mint.owner,
+                    token_program.key,
+                ));
+            }
+        }
+        let governance_decimals = Self::load_market_mint(governance_mint, false)?;
+        let reward_decimals = Self::load_market_mint(reward_mint, false)?;
+
+        let (stake_pool_address, bump) = StakePool::find_address(program_id);
+        if stake_pool_address != *stake_pool_account.key {
+            return Err(return_dex_error_expected(
+                DexError::InvalidAccountData,
+                "Invalid stake pool address",
+                stake_pool_address,
+                stake_pool_account.key,
+            ));
+        }
+        if stake_pool_account.owner == program_id {
+            return Err(return_dex_error(
+                DexError::AlreadyInitialized,
+                "Stake pool already initialized",
+            ));
+        }
+
+        // Create the stake pool PDA, paid by the admin
+        let rent = Rent::get()?;
+        invoke_signed(
+            &system_instruction::create_account(
+                admin.key,
+                stake_pool_account.key,
+                rent.minimum_balance(StakePool::LEN),
+                StakePool::LEN as u64,
+                program_id,
+            ),
+            &[
+                admin.clone(),
+                stake_pool_account.clone(),
+                system_program_account.clone(),
+            ],
+            &[&[STAKE_POOL_SEED, &[bump]]],
+        )?;
+
+        let vaults = [
+            (STAKE_VAULT_SEED, stake_vault, governance_mint, governance_token_program),
+            (STAKE_REWARD_VAULT_SEED, reward_vault, reward_mint, reward_token_program),
+        ];
+        for (is_reward, (seed, vault, mint, token_program)) in vaults.into_iter().enumerate() {
+            let (vault_address, vault_bump) = StakePool::find_vault_address(program_id, is_reward == 1);
+            if vault_address != *vault.key {
+                return Err(return_dex_error_expected(
+                    DexError::InvalidAccountData,
+                    "Invalid stake pool vault address",
+                    vault_address,
+                    vault.key,
+                ));
+            }
+
+            // Create the vault PDA as a token account of its mint's program owned by the pool
+            let space = token::account_len(&mint.data.borrow())?;
+            invoke_signed(
+                &system_instruction::create_account(
+                    admin.key,
+                    vault.key,
+                    rent.minimum_balance(space),
+                    space as u64,
+                    token_program.key,
+                ),
+                &[
+                    admin.clone(),
+                    vault.clone(),
+                    system_program_account.clone(),
+                ],
+                &[&[seed, &[vault_bump]]],
+            )?;
+
+            invoke(
+                &token_2022_instruction::initialize_account3(
+                    token_program.key,
+                    vault.key,
+                    mint.key,
+                    stake_pool_account.key,
+                )?,
+                &[vault.clone(), mint.clone(), token_program.clone()],
+            )?;
+        }
+
+        let stake_pool = StakePool {
+            is_initialized: true,
+            bump,
+            governance_mint: *governance_mint.key,
+            governance_token_program: *governance_token_program.key,
+            governance_decimals,
+            reward_mint: *reward_mint.key,
+            reward_token_program: *reward_token_program.key,
+            reward_decimals,
+            fee_share_bps,
+            epoch_seconds,
+            epoch: 0,
+            epoch_start_timestamp: Clock::get()?.unix_timestamp,
+            total_staked: 0,
+            reward_per_share: 0,
+            rewards_unclaimed: 0,
+            rewards_distributed: 0,
+            fee_tiers,
+        };
+        stake_pool.pack_into_slice(&mut stake_pool_account.data.borrow_mut());
+
+        msg!(
+            "Stake pool initialized with {} second epochs and a fee share of {} bps",
+            epoch_seconds,
+            fee_share_bps
+        );
+        Ok(())
+    }
+
+    // Process stake instruction
+    fn process_stake(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let owner = next_account_info(account_info_iter)?;
+        let stake_pool_account = next_account_info(account_info_iter)?;
+        let stake_account = next_account_info(account_info_iter)?;
+        let stake_vault = next_account_info(account_info_iter)?;
+        let source = next_account_info(account_info_iter)?;
+        let governance_mint = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !owner.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Owner must sign",
+            ));
+        }
+
+        Self::check_system_program(system_program_account)?;
+
+        // Load stake pool
+        let mut stake_pool = Self::load_stake_pool(program_id, stake_pool_account, stake_vault, false)?;
+        let side = Self::stake_pool_side(&stake_pool, false, governance_mint, token_program)?;
+
+        if amount == 0 {
+            return Err(return_dex_error(
+                DexError::InvalidInstructionData,
+                "Stake amount must be positive",
+            ));
+        }
+
+        let (stake_address, stake_bump) = StakeAccount::find_address(program_id, owner.key);
+        if stake_address != *stake_account.key {
+            return Err(return_dex_error_expected(
+                DexError::InvalidAccountData,
+                "Invalid stake account address",
+                stake_address,
+                stake_account.key,
+            ));
+        }
+
+        // Create the stake account on the first stake, paid by the owner
+        let mut stake = if stake_account.owner == program_id {
+            StakeAccount::unpack_from_slice(&stake_account.data.borrow())?
+        } else {
+            invoke_signed(
+                &system_instruction::create_account(
+                    owner.key,
+                    stake_account.key,
+                    Rent::get()?.minimum_balance(StakeAccount::LEN),
+                    StakeAccount::LEN as u64,
+                    program_id,
+                ),
+                &[
+                    owner.clone(),
+                    stake_account.clone(),
+                    system_program_account.clone(),
+                ],
+                &[&[STAKE_ACCOUNT_SEED, owner.key.as_ref(), &[stake_bump]]],
+            )?;
+            StakeAccount {
+                is_initialized: true,
+                owner: *owner.key,
+                amount: 0,
+                last_reward_per_share: stake_pool.reward_per_share,
+                rewards_owed: 0,
+                stake_epoch: 0,
+            }
+        };
+
+        // Earnings so far are kept before the stake grows
+        stake.settle_rewards(stake_pool.reward_per_share)?;
+        stake.amount = stake.amount.checked_add(amount).ok_or(DexError::ArithmeticOverflow)?;
+        stake.stake_epoch = stake_pool.epoch;
+        stake_pool.total_staked = stake_pool
+            .total_staked
+            .checked_add(amount)
+            .ok_or(DexError::ArithmeticOverflow)?;
+        stake.pack_into_slice(&mut stake_account.data.borrow_mut());
+        stake_pool.pack_into_slice(&mut stake_pool_account.data.borrow_mut());
+
+        // Move the tokens into the stake vault
+        Self::transfer_tokens_in(&side, source, stake_vault, owner, amount, &[])?;
+
+        msg!("Staked {}, {} in total", amount, stake.amount);
+        Ok(())
+    }
+
+    // Process unstake instruction
+    fn process_unstake(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let owner = next_account_info(account_info_iter)?;
+        let stake_pool_account = next_account_info(account_info_iter)?;
+        let stake_account = next_account_info(account_info_iter)?;
+        let stake_vault = next_account_info(account_info_iter)?;
+        let destination = next_account_info(account_info_iter)?;
+        let governance_mint = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !owner.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Owner must sign",
+            ));
+        }
+
+        // Load stake pool and stake account
+        let mut stake_pool = Self::load_stake_pool(program_id, stake_pool_account, stake_vault, false)?;
+        let side = Self::stake_pool_side(&stake_pool, false, governance_mint, token_program)?;
+        let mut stake = Self::load_stake_account(program_id, stake_account, owner)?;
+
+        if !stake.is_unlocked(stake_pool.epoch) {
+            return Err(return_dex_error_expected(
+                DexError::StakeLocked,
+                "Stake is locked until its epoch is distributed",
+                format_args!("epoch after {}", stake.stake_epoch),
+                stake_pool.epoch,
+            ));
+        }
+        if amount == 0 || amount > stake.amount {
+            return Err(return_dex_error_expected(
+                DexError::InsufficientFunds,
+                "Invalid unstake amount",
+                format_args!("1 to {}", stake.amount),
+                amount,
+            ));
+        }
+
+        // Earnings so far are kept before the stake shrinks
+        stake.settle_rewards(stake_pool.reward_per_share)?;
+        stake.amount -= amount;
+        stake_pool.total_staked = stake_pool
+            .total_staked
+            .checked_sub(amount)
+            .ok_or(DexError::ArithmeticOverflow)?;
+        stake.pack_into_slice(&mut stake_account.data.borrow_mut());
+        stake_pool.pack_into_slice(&mut stake_pool_account.data.borrow_mut());
+
+        // Pay the tokens out, signed by the stake pool that owns the vault
+        Self::transfer_tokens(
+            &side,
+            stake_vault,
+            destination,
+            stake_pool_account,
+            amount,
+            &[&[STAKE_POOL_SEED, &[stake_pool.bump]]],
+        )?;
+
+        msg!("Unstaked {}, {} left", amount, stake.amount);
+        Ok(())
+    }
+
+    // Process distribute staking rewards instruction
+    fn process_distribute_staking_rewards(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let stake_pool_account = next_account_info(account_info_iter)?;
+        let reward_vault = next_account_info(account_info_iter)?;
+
+        // Load stake pool
+        let mut stake_pool = Self::load_stake_pool(program_id, stake_pool_account, reward_vault, true)?;
+
+        let timestamp = Clock::get()?.unix_timestamp;
+        if timestamp < stake_pool.epoch_end_timestamp() {
+            return Err(return_dex_error_expected(
+                DexError::EpochNotEnded,
+                "Epoch has not run its length",
+                format_args!("from {}", stake_pool.epoch_end_timestamp()),
+                timestamp,
+            ));
+        }
+
+        // Whatever the vault holds beyond the unclaimed rewards arrived during the epoch
+        let balance = token::unpack_account(&reward_vault.data.borrow())
+            .map_err(|_| return_dex_error(DexError::InvalidTokenAccount, "Reward vault not initialized"))?
+            .amount;
+        let distributed = stake_pool.distribute(balance.saturating_sub(stake_pool.rewards_unclaimed))?;
+        let epoch = stake_pool.epoch;
+        stake_pool.epoch = epoch.checked_add(1).ok_or(DexError::ArithmeticOverflow)?;
+        stake_pool.epoch_start_timestamp = timestamp;
+        stake_pool.pack_into_slice(&mut stake_pool_account.data.borrow_mut());
+
+        msg!(
+            "Distributed {} rewards over {} staked for epoch {}",
+            distributed,
+            stake_pool.total_staked,
+            epoch
+        );
+        Ok(())
+    }
+
+    // Process claim staking rewards instruction
+    fn process_claim_staking_rewards(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let owner = next_account_info(account_info_iter)?;
+        let stake_pool_account = next_account_info(account_info_iter)?;
+        let stake_account = next_account_info(account_info_iter)?;
+        let reward_vault = next_account_info(account_info_iter)?;
+        let destination = next_account_info(account_info_iter)?;
+        let reward_mint = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !owner.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Owner must sign",
+            ));
+        }
+
+        // Load stake pool and stake account
+        let mut stake_pool = Self::load_stake_pool(program_id, stake_pool_account, reward_vault, true)?;
+        let side = Self::stake_pool_side(&stake_pool, true, reward_mint, token_program)?;
+        let mut stake = Self::load_stake_account(program_id, stake_account, owner)?;
+
+        stake.settle_rewards(stake_pool.reward_per_share)?;
+        let amount = stake.rewards_owed;
+        stake.rewards_owed = 0;
+        stake_pool.rewards_unclaimed = stake_pool.rewards_unclaimed.saturating_sub(amount);
+        stake.pack_into_slice(&mut stake_account.data.borrow_mut());
+        stake_pool.pack_into_slice(&mut stake_pool_account.data.borrow_mut());
+
+        if amount > 0 {
+            Self::transfer_tokens(
+                &side,
+                reward_vault,
+                destination,
+                stake_pool_account,
+                amount,
+                &[&[STAKE_POOL_SEED, &[stake_pool.bump]]],
+            )?;
+        }
+
+        msg!("Claimed {} staking rewards", amount);
+        Ok(())
+    }
+
+    // Process set loyalty schedule instruction
+    fn process_set_loyalty_schedule(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        num_phases: u8,
+        phases: [EmissionPhase; 4],
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let authority = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let schedule_account = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !authority.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Authority must sign",
+            ));
+        }
+
+        Self::check_system_program(system_program_account)?;
+
+        // Load market
+        let mut market = Self::load_market(program_id, market_account)?;
+
+        // Verify authority
+        if market.authority != *authority.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Not market authority",
+            ));
+        }
+
+        if !LoyaltySchedule::phases_valid(num_phases, &phases) {
+            return Err(return_dex_error(
+                DexError::InvalidInstructionData,
+                "Emission phases must start in increasing order",
+            ));
+        }
+
+        let (schedule_address, bump) = LoyaltySchedule::find_address(program_id, market_account.key);
+        if schedule_address != *schedule_account.key {
+            return Err(return_dex_error_expected(
+                DexError::InvalidAccountData,
+                "Invalid loyalty schedule address",
+                schedule_address,
+                schedule_account.key,
+            ));
+        }
+
+        // Create the schedule PDA the first time, paid by the authority
+        let total_points = if schedule_account.owner == program_id {
+            LoyaltySchedule::unpack_from_slice(&schedule_account.data.borrow())?.total_points
+        } else {
+            invoke_signed(
+                &system_instruction::create_account(
+                    authority.key,
+                    schedule_account.key,
+                    Rent::get()?.minimum_balance(LoyaltySchedule::LEN),
+                    LoyaltySchedule::LEN as u64,
+                    program_id,
+                ),
+                &[
+                    authority.clone(),
+                    schedule_account.clone(),
+                    system_program_account.clone(),
+                ],
+                &[&[LOYALTY_SCHEDULE_SEED, market_account.key.as_ref(), &[bump]]],
+            )?;
+            0
+        };
+
+        let schedule = LoyaltySchedule {
+            is_initialized: true,
+            market: *market_account.key,
+            num_phases,
+            phases,
+            total_points,
+        };
+        schedule.pack_into_slice(&mut schedule_account.data.borrow_mut());
+
+        if !market.has_flag(MARKET_FLAG_LOYALTY_POINTS) {
+            market.flags |= MARKET_FLAG_LOYALTY_POINTS;
+            market.pack_into_slice(&mut market_account.data.borrow_mut());
+        }
+
+        msg!("Loyalty schedule set with {} phases", num_phases);
+        Ok(())
+    }
+
+    // Process open loyalty points instruction
+    fn process_open_loyalty_points(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let owner = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let points_account = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !owner.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Owner must sign",
+            ));
+        }
+
+        Self::check_system_program(system_program_account)?;
+
+        // Load market
+        Self::load_market(program_id, market_account)?;
+
+        let (points_address, bump) = LoyaltyPoints::find_address(program_id, market_account.key, owner.key);
+        if points_address != *points_account.key {
+            return Err(return_dex_error_expected(
+                DexError::InvalidAccountData,
+                "Invalid loyalty points address",
+                points_address,
+                points_account.key,
+            ));
+        }
+        if points_account.owner == program_id {
+            return Err(return_dex_error(
+                DexError::AlreadyInitialized,
+                "Loyalty points account already open",
+            ));
+        }
+
+        // Create the points PDA, paid by the owner
+        invoke_signed(
+            &system_instruction::create_account(
+                owner.key,
+                points_account.key,
+                Rent::get()?.minimum_balance(LoyaltyPoints::LEN),
+                LoyaltyPoints::LEN as u64,
+                program_id,
+            ),
+            &[
+                owner.clone(),
+                points_account.clone(),
+                system_program_account.clone(),
+            ],
+            &[&[LOYALTY_POINTS_SEED, market_account.key.as_ref(), owner.key.as_ref(), &[bump]]],
+        )?;
+
+        let points = LoyaltyPoints {
+            is_initialized: true,
+            market: *market_account.key,
+            owner: *owner.key,
+            taker_points: 0,
+            maker_points: 0,
+            last_fill_slot: 0,
+        };
+        points.pack_into_slice(&mut points_account.data.borrow_mut());
+
+        msg!("Loyalty points account opened");
+        Ok(())
+    }
+
+    // Accrue a fill's loyalty points to its taker and maker, whose loyalty points accounts are
+    // found by address among the accounts; owners who have not opened one earn nothing
+    fn accrue_loyalty_points(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        market_account: &AccountInfo,
+        taker: &Pubkey,
+        maker: &Pubkey,
+        quote_amount: u64,
+        timestamp: i64,
+    ) -> ProgramResult {
+        let find = |key: Pubkey| {
+            accounts
+                .iter()
+                .find(|account| *account.key == key)
+                .ok_or(ProgramError::NotEnoughAccountKeys)
+        };
+
+        let schedule_account = find(LoyaltySchedule::find_address(program_id, market_account.key).0)?;
+        if schedule_account.owner != program_id {
+            return Err(return_dex_error(
+                DexError::InvalidAccountOwner,
+                "Loyalty schedule not owned by program",
+            ));
+        }
+        let mut schedule = LoyaltySchedule::unpack_from_slice(&schedule_account.data.borrow())?;
+        let (taker_points, maker_points) = schedule.fill_points(quote_amount, timestamp)?;
+
+        let slot = Clock::get()?.slot;
+        for (owner, taker_points, maker_points) in [(taker, taker_points, 0), (maker, 0, maker_points)] {
+            let points_account = find(LoyaltyPoints::find_address(program_id, market_account.key, owner).0)?;
+            if points_account.owner != program_id {
+                continue;
+            }
+            let mut points = LoyaltyPoints::unpack_from_slice(&points_account.data.borrow())?;
+            points.taker_points = points.taker_points.saturating_add(taker_points);
+            points.maker_points = points.maker_points.saturating_add(maker_points);
+            points.last_fill_slot = slot;
+            points.pack_into_slice(&mut points_account.data.borrow_mut());
+            schedule.total_points = schedule.total_points.saturating_add(taker_points + maker_points);
+        }
+        schedule.pack_into_slice(&mut schedule_account.data.borrow_mut());
+
+        Ok(())
+    }
+
+    // Load the stake pool, checking the address of its staked token or reward vault
+    fn load_stake_pool(
+        program_id: &Pubkey,
+        stake_pool_account: &AccountInfo,
+        vault: &AccountInfo,
+        is_reward: bool,
+    ) -> Result<StakePool, ProgramError> {
+        if stake_pool_account.owner != program_id {
+            return Err(return_dex_error(
+                DexError::InvalidAccountOwner,
+                "Stake pool not owned by the program",
+            ));
+        }
+        if StakePool::find_address(program_id).0 != *stake_pool_account.key {
+            return Err(return_dex_error(
+                DexError::AccountMismatch,
+                "Invalid stake pool address",
+            ));
+        }
+        let (vault_address, _) = StakePool::find_vault_address(program_id, is_reward);
+        if vault_address != *vault.key {
+            return Err(return_dex_error_expected(
+                DexError::InvalidAccountData,
+                "Invalid stake pool vault",
+                vault_address,
+                vault.key,
+            ));
+        }
+
+        StakePool::unpack_from_slice(&stake_pool_account.data.borrow())
+    }
+
+    // Check the mint and token program of the stake pool's governance or reward side
+    fn stake_pool_side<'a, 'b>(
+        stake_pool: &StakePool,
+        is_reward: bool,
+        mint: &'b AccountInfo<'a>,
+        token_program: &'b AccountInfo<'a>,
+    ) -> Result<TokenSide<'a, 'b>, ProgramError> {
+        let (expected_mint, expected_token_program, decimals) = if is_reward {
+            (&stake_pool.reward_mint, &stake_pool.reward_token_program, stake_pool.reward_decimals)
+        } else {
+            (&stake_pool.governance_mint, &stake_pool.governance_token_program, stake_pool.governance_decimals)
+        };
+        if expected_mint != mint.key {
+            return Err(return_dex_error_expected(
+                DexError::InvalidMint,
+                "Invalid stake pool mint",
+                expected_mint,
+                mint.key,
+            ));
+        }
+        if expected_token_program != token_program.key {
+            return Err(return_dex_error_expected(
+                DexError::InvalidAccountData,
+                "Invalid token program",
+                expected_token_program,
+                token_program.key,
+            ));
+        }
+
+        Ok(TokenSide {
+            token_program,
+            mint: Some(mint),
+            decimals,
+        })
+    }
+
+    // Load an owner's stake account
+    fn load_stake_account(
+        program_id: &Pubkey,
+        stake_account: &AccountInfo,
+        owner: &AccountInfo,
+    ) -> Result<StakeAccount, ProgramError> {
+        if stake_account.owner != program_id
+            || StakeAccount::find_address(program_id, owner.key).0 != *stake_account.key
+        {
+            return Err(return_dex_error(
+                DexError::AccountMismatch,
+                "Stake account does not belong to the owner",
+            ));
+        }
+
+        StakeAccount::unpack_from_slice(&stake_account.data.borrow())
+    }
+
+    // Load the taker fee discount of an order owner's stake, when the stake pool and the
+    // owner's stake account are passed among the accounts
+    fn taker_fee_discount_bps(program_id: &Pubkey, accounts: &[AccountInfo], owner: &Pubkey) -> Result<u16, ProgramError> {
+        let find = |key: Pubkey| accounts.iter().find(|account| *account.key == key && account.owner == program_id);
+        let stake_pool_account = find(StakePool::find_address(program_id).0);
+        let stake_account = find(StakeAccount::find_address(program_id, owner).0);
+        match (stake_pool_account, stake_account) {
+            (Some(stake_pool_account), Some(stake_account)) => {
+                let stake_pool = StakePool::unpack_from_slice(&stake_pool_account.data.borrow())?;
+                let stake = StakeAccount::unpack_from_slice(&stake_account.data.borrow())?;
+                Ok(stake_pool.discount_bps(stake.amount))
+            }
+            _ => Ok(0),
+        }
+    }
+
+    // Load a market's pool
+    fn load_pool(program_id: &Pubkey, market_account: &AccountInfo, pool_account: &AccountInfo) -> Result<Pool, ProgramError> {
+        if pool_account.owner != program_id {
+            return Err(return_dex_error(
+                DexError::InvalidAccountOwner,
+                "Pool not owned by the program",
+            ));
+        }
+        if Pool::find_address(program_id, market_account.key).0 != *pool_account.key {
+            return Err(return_dex_error(
+                DexError::AccountMismatch,
+                "Pool does not belong to the market",
+            ));
+        }
+
+        Pool::unpack_from_slice(&pool_account.data.borrow())
+    }
+
+    // Check the addresses of a pool's reserve vaults
+    fn check_pool_vaults(
+        program_id: &Pubkey,
+        market_account: &AccountInfo,
+        base_vault: &AccountInfo,
+        quote_vault: &AccountInfo,
+    ) -> ProgramResult {
+        for (is_quote, vault) in [(false, base_vault.key), (true, quote_vault.key)] {
+            let (vault_address, _) = Pool::find_vault_address(program_id, market_account.key, is_quote);
+            if vault_address != *vault {
+                return Err(return_dex_error_expected(
+                    DexError::InvalidAccountData,
+                    "Invalid pool vault",
+                    vault_address,
+                    vault,
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    // Check a pool's LP mint and that the token program passed is the one it was created under
+    fn check_lp_mint(pool: &Pool, lp_mint: &AccountInfo, token_program: &AccountInfo) -> ProgramResult {
+        if pool.lp_mint != *lp_mint.key {
+            return Err(return_dex_error_expected(
+                DexError::InvalidMint,
+                "Invalid LP mint",
+                pool.lp_mint,
+                lp_mint.key,
+            ));
+        }
+        if lp_mint.owner != token_program.key {
+            return Err(return_dex_error_expected(
+                DexError::InvalidAccountData,
+                "Token program does not own the LP mint",
+                lp_mint.owner,
+                token_program.key,
+            ));
+        }
+
+        Ok(())
+    }
+
+    // Load a market's range pool
+    fn load_range_pool(
+        program_id: &Pubkey,
+        market_account: &AccountInfo,
+        range_pool_account: &AccountInfo,
+    ) -> Result<RangePool, ProgramError> {
+        if range_pool_account.owner != program_id {
+            return Err(return_dex_error(
+                DexError::InvalidAccountOwner,
+                "Range pool not owned by the program",
+            ));
+        }
+        if RangePool::find_address(program_id, market_account.key).0 != *range_pool_account.key {
+            return Err(return_dex_error(
+                DexError::AccountMismatch,
+                "Range pool does not belong to the market",
+            ));
+        }
+
+        RangePool::unpack_from_slice(&range_pool_account.data.borrow())
+    }
+
+    // Check the addresses of a range pool's vaults
+    fn check_range_pool_vaults(
+        program_id: &Pubkey,
+        market_account: &AccountInfo,
+        base_vault: &AccountInfo,
+        quote_vault: &AccountInfo,
+    ) -> ProgramResult {
+        for (is_quote, vault) in [(false, base_vault.key), (true, quote_vault.key)] {
+            let (vault_address, _) = RangePool::find_vault_address(program_id, market_account.key, is_quote);
+            if vault_address != *vault {
+                return Err(return_dex_error_expected(
+                    DexError::InvalidAccountData,
+                    "Invalid range pool vault",
+                    vault_address,
+                    vault,
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    // Load a lending pool, checking its vault, share mint, mint and token program
+    fn load_lending_pool(
+        program_id: &Pubkey,
+        lending_pool_account: &AccountInfo,
+        vault: &AccountInfo,
+        share_mint: &AccountInfo,
+        mint: &AccountInfo,
+        token_program: &AccountInfo,
+    ) -> Result<LendingPool, ProgramError> {
+        if lending_pool_account.owner != program_id {
+            return Err(return_dex_error(
+                DexError::InvalidAccountOwner,
+                "Lending pool not owned by the program",
+            ));
+        }
+        let lending_pool = LendingPool::unpack_from_slice(&lending_pool_account.data.borrow())?;
+        if LendingPool::find_address(program_id, &lending_pool.mint).0 != *lending_pool_account.key {
+            return Err(return_dex_error(
+                DexError::AccountMismatch,
+                "Invalid lending pool address",
+            ));
+        }
+
+        if lending_pool.mint != *mint.key {
+            return Err(return_dex_error_expected(
+                DexError::InvalidMint,
+                "Invalid lending pool mint",
+                lending_pool.mint,
+                mint.key,
+            ));
+        }
+        if lending_pool.share_mint != *share_mint.key {
+            return Err(return_dex_error_expected(
+                DexError::InvalidMint,
+                "Invalid share mint",
+                lending_pool.share_mint,
+                share_mint.key,
+            ));
+        }
+        if lending_pool.token_program != *token_program.key {
+            return Err(return_dex_error_expected(
+                DexError::InvalidAccountData,
+                "Invalid token program for the lending pool",
+                lending_pool.token_program,
+                token_program.key,
+            ));
+        }
+        let (vault_address, _) = LendingPool::find_vault_address(program_id, mint.key);
+        if vault_address != *vault.key {
+            return Err(return_dex_error_expected(
+                DexError::InvalidAccountData,
+                "Invalid lending pool vault",
+                vault_address,
+                vault.key,
+            ));
+        }
+
+        Ok(lending_pool)
+    }
+
+    // Load a market's perp market, checking its vault
+    fn load_perp_market(
+        program_id: &Pubkey,
+        market_account: &AccountInfo,
+        perp_market_account: &AccountInfo,
+        vault: &AccountInfo,
+    ) -> Result<PerpMarket, ProgramError> {
+        if perp_market_account.owner != program_id {
+            return Err(return_dex_error(
+                DexError::InvalidAccountOwner,
+                "Perp market not owned by the program",
+            ));
+        }
+        if PerpMarket::find_address(program_id, market_account.key).0 != *perp_market_account.key {
+            return Err(return_dex_error(
+                DexError::AccountMismatch,
+                "Perp market does not belong to the market",
+            ));
+        }
+        let (vault_address, _) = PerpMarket::find_vault_address(program_id, market_account.key);
+        if vault_address != *vault.key {
+            return Err(return_dex_error_expected(
+                DexError::InvalidAccountData,
+                "Invalid perp vault",
+                vault_address,
+                vault.key,
+            ));
+        }
+
+        PerpMarket::unpack_from_slice(&perp_market_account.data.borrow())
+    }
+
+    // Load a perp position of a market
+    fn load_perp_position(
+        program_id: &Pubkey,
+        market_account: &AccountInfo,
+        position_account: &AccountInfo,
+    ) -> Result<PerpPosition, ProgramError> {
+        if position_account.owner != program_id {
+            return Err(return_dex_error(
+                DexError::InvalidAccountOwner,
+                "Perp position not owned by the program",
+            ));
+        }
+        let position = PerpPosition::unpack_from_slice(&position_account.data.borrow())?;
+        if position.market != *market_account.key
+            || PerpPosition::find_address(program_id, market_account.key, &position.owner).0 != *position_account.key
+        {
+            return Err(return_dex_error(
+                DexError::AccountMismatch,
+                "Perp position does not belong to the market",
+            ));
+        }
+
+        Ok(position)
+    }
+
+    // Load a live perp order of a market
+    fn load_perp_order(
+        program_id: &Pubkey,
+        market_account: &AccountInfo,
+        order_account: &AccountInfo,
+    ) -> Result<PerpOrder, ProgramError> {
+        if order_account.owner != program_id {
+            return Err(return_dex_error(
+                DexError::InvalidAccountOwner,
+                "Perp order not owned by the program",
+            ));
+        }
+        if order_account.data_len() != PerpOrder::LEN {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Invalid perp order account",
+            ));
+        }
+        let order = PerpOrder::unpack_from_slice(&order_account.data.borrow())?;
+        if !order.is_initialized {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Perp order is not live",
+            ));
+        }
+        if order.market != *market_account.key {
+            return Err(return_dex_error(
+                DexError::AccountMismatch,
+                "Perp order does not belong to the market",
+            ));
+        }
+
+        Ok(order)
+    }
+
+    // Load a market's batch auction
+    fn load_batch_auction(
+        program_id: &Pubkey,
+        market_account: &AccountInfo,
+        batch_auction_account: &AccountInfo,
+    ) -> Result<BatchAuction, ProgramError> {
+        if batch_auction_account.owner != program_id {
+            return Err(return_dex_error(
+                DexError::InvalidAccountOwner,
+                "Batch auction not owned by the program",
+            ));
+        }
+        if BatchAuction::find_address(program_id, market_account.key).0 != *batch_auction_account.key {
+            return Err(return_dex_error(
+                DexError::AccountMismatch,
+                "Batch auction does not belong to the market",
+            ));
+        }
+
+        BatchAuction::unpack_from_slice(&batch_auction_account.data.borrow())
+    }
+
+    // Check that a batch auction market has room for `new_orders` more resting orders, so that
+    // RunAuction can still pass every one of them
+    fn check_auction_capacity(market: &Market, new_orders: u64) -> ProgramResult {
+        if !market.has_flag(MARKET_FLAG_BATCH_AUCTION) {
+            return Ok(());
+        }
+
+        let num_orders = market
+            .num_bids
+            .saturating_add(market.num_asks)
+            .saturating_add(new_orders);
+        if num_orders > MAX_AUCTION_ORDERS {
+            return Err(return_dex_error_expected(
+                DexError::OrderBookFull,
+                "Batch auction market holds its most resting orders",
+                format_args!("at most {} orders", MAX_AUCTION_ORDERS),
+                num_orders,
+            ));
+        }
+
+        Ok(())
+    }
+
+    // Markets that match by batch auction settle no fill on its own
+    fn check_continuous_matching(market: &Market) -> ProgramResult {
+        if market.has_flag(MARKET_FLAG_BATCH_AUCTION) {
+            return Err(return_dex_error(
+                DexError::BatchAuctionMarket,
+                "Market matches by batch auction",
+            ));
+        }
+
+        Ok(())
+    }
+
+    // Load a market's event queue in place
+    fn load_event_queue_mut<'a>(
+        program_id: &Pubkey,
+        market_account: &AccountInfo,
+        event_queue_account: &'a AccountInfo,
+    ) -> Result<RefMut<'a, EventQueue>, ProgramError> {
+        if event_queue_account.owner != program_id {
+            return Err(return_dex_error(
+                DexError::InvalidAccountOwner,
+                "Event queue not owned by the program",
+            ));
+        }
+        if EventQueue::find_address(program_id, market_account.key).0 != *event_queue_account.key {
+            return Err(return_dex_error(
+                DexError::AccountMismatch,
+                "Event queue does not belong to the market",
+            ));
+        }
+
+        let event_queue = RefMut::filter_map(event_queue_account.try_borrow_mut_data()?, |data| {
+            load_zero_copy_mut::<EventQueue>(data).ok()
+        })
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+        if !event_queue.is_initialized {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Event queue not initialized",
+            ));
+        }
+
+        Ok(event_queue)
+    }
+
+    fn load_twap_oracle(
+        program_id: &Pubkey,
+        market_account: &AccountInfo,
+        twap_oracle_account: &AccountInfo,
+    ) -> Result<TwapOracle, ProgramError> {
+        if twap_oracle_account.owner != program_id {
+            return Err(return_dex_error(
+                DexError::InvalidAccountOwner,
+                "TWAP oracle not owned by the program",
+            ));
+        }
+        if TwapOracle::find_address(program_id, market_account.key).0 != *twap_oracle_account.key {
+            return Err(return_dex_error(
+                DexError::AccountMismatch,
+                "TWAP oracle does not belong to the market",
+            ));
+        }
+
+        TwapOracle::unpack_from_slice(&twap_oracle_account.data.borrow())
+    }
+
+    // Process delegate instruction
+    fn process_delegate(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        delegate: Pubkey,
+        expiry_slot: u64,
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let owner = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let delegation_account = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !owner.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Owner must sign",
+            ));
+        }
+
+        Self::check_system_program(system_program_account)?;
+
+        // Load market
+        Self::load_market(program_id, market_account)?;
+
+        let (delegation_address, bump) = Delegation::find_address(program_id, market_account.key, owner.key);
+        if delegation_address != *delegation_account.key {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Invalid delegation address",
+            ));
+        }
+
+        // Create the delegation PDA on first use, paid by the owner; later calls replace it
+        if delegation_account.owner != program_id {
+            let rent = Rent::get()?;
+            let space = Delegation::LEN;
+            let lamports = rent.minimum_balance(space);
 
-        // Transfer fee to fee recipient
-        if fee > 0 {
             invoke_signed(
-                &token_instruction::transfer(
-                    token_program.key,
-                    taker_quote_account.key,
-                    fee_recipient_account.key,
-                    market_account.key,
-                    &[],
-                    fee,
-                )?,
+                &system_instruction::create_account(
+                    owner.key,
+                    delegation_account.key,
+                    lamports,
+                    space as u64,
+                    program_id,
+                ),
+                &[
+                    owner.clone(),
+                    delegation_account.clone(),
+                    system_program_account.clone(),
+                ],
+                &[&[DELEGATION_SEED, market_account.key.as_ref(), owner.key.as_ref(), &[bump]]],
+            )?;
+        }
+
+        let delegation = Delegation {
+            is_initialized: true,
+            market: *market_account.key,
+            owner: *owner.key,
+            delegate,
+            expiry_slot,
+        };
+        delegation.pack_into_slice(&mut delegation_account.data.borrow_mut());
+
+        msg!(
+            "Trading of {} on market {} delegated to {} until slot {}",
+            owner.key,
+            market_account.key,
+            delegate,
+            expiry_slot
+        );
+        Ok(())
+    }
+
+    // Process close order account instruction
+    fn process_close_order_account(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let order_account = next_account_info(account_info_iter)?;
+        let rent_recipient = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !order_account.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Order account must sign",
+            ));
+        }
+
+        if order_account.owner != program_id {
+            return Err(return_dex_error(
+                DexError::InvalidAccountOwner,
+                "Order account not owned by program",
+            ));
+        }
+
+        // Closed orders of the legacy layout are closed without migrating them first
+        if order_account.data_len() != Order::LEN && order_account.data_len() != Order::LEGACY_LEN {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Invalid order account size",
+            ));
+        }
+
+        // Live orders still hold escrowed funds
+        if order_account.data.borrow()[0] != 0 {
+            return Err(return_dex_error(
+                DexError::AlreadyInitialized,
+                "Order is still live; cancel it instead",
+            ));
+        }
+
+        Self::close_order_account(order_account, rent_recipient)?;
+
+        msg!("Order account closed successfully");
+        Ok(())
+    }
+
+    // Process sweep fees instruction
+    fn process_sweep_fees(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let fee_authority = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let fee_vault = next_account_info(account_info_iter)?;
+        let destination = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !fee_authority.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Fee authority must sign",
+            ));
+        }
+
+        Self::check_token_program(token_program)?;
+
+        // Load market
+        let mut market = Self::load_market(program_id, market_account)?;
+
+        // Verify fee authority
+        if market.fee_authority != *fee_authority.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Not market fee authority",
+            ));
+        }
+
+        let (fee_vault_address, bump) = Market::find_fee_vault_address(program_id, market_account.key);
+        if fee_vault_address != *fee_vault.key {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Invalid fee vault address",
+            ));
+        }
+
+        // Stakers take their share when the stake pool and its reward vault are passed
+        let find = |key: Pubkey| accounts.iter().find(|account| *account.key == key);
+        let staker_share = match (
+            find(StakePool::find_address(program_id).0),
+            find(StakePool::find_vault_address(program_id, true).0),
+        ) {
+            (Some(stake_pool_account), Some(reward_vault)) => {
+                let stake_pool = Self::load_stake_pool(program_id, stake_pool_account, reward_vault, true)?;
+                if stake_pool.reward_mint != market.quote_mint {
+                    return Err(return_dex_error_expected(
+                        DexError::InvalidMint,
+                        "Stakers are rewarded in another mint",
+                        stake_pool.reward_mint,
+                        market.quote_mint,
+                    ));
+                }
+                Some((reward_vault, stake_pool.fee_share_bps))
+            }
+            _ => None,
+        };
+
+        // The treasury and the creator take their shares of the rest in the accounts set with
+        // the fee split, found by their addresses
+        let mut fee_split = Vec::new();
+        for (fee_account, share_bps, name) in [
+            (market.treasury_fee_account, market.treasury_fee_share_bps, "treasury"),
+            (market.creator_fee_account, market.creator_fee_share_bps, "market creator"),
+        ] {
+            if share_bps > 0 {
+                let recipient = find(fee_account).ok_or_else(|| {
+                    msg!("Fee account {} of the {} must be passed", fee_account, name);
+                    ProgramError::NotEnoughAccountKeys
+                })?;
+                fee_split.push((recipient, share_bps, name));
+            }
+        }
+
+        // Sweep the whole balance
+        let amount = token::unpack_account(&fee_vault.data.borrow())
+            .map_err(|_| return_dex_error(DexError::InvalidTokenAccount, "Fee vault not initialized"))?
+            .amount;
+        if amount > 0 {
+            let side = Self::load_token_side(&market, accounts, true)?;
+            let seeds: &[&[u8]] = &[FEE_VAULT_SEED, market_account.key.as_ref(), &[bump]];
+            let share = match staker_share {
+                Some((reward_vault, fee_share_bps)) => {
+                    let share = math::bps_of(amount, fee_share_bps as u64, math::Rounding::Down)?;
+                    if share > 0 {
+                        Self::transfer_tokens(&side, fee_vault, reward_vault, fee_vault, share, &[seeds])?;
+                        msg!("Paid {} of the fees to stakers", share);
+                    }
+                    share
+                }
+                None => 0,
+            };
+            let mut remaining = amount - share;
+            for (recipient, share_bps, name) in fee_split {
+                let recipient_share = math::bps_of(amount - share, share_bps as u64, math::Rounding::Down)?;
+                if recipient_share > 0 {
+                    Self::transfer_tokens(&side, fee_vault, recipient, fee_vault, recipient_share, &[seeds])?;
+                    msg!("Paid {} of the fees to the {}", recipient_share, name);
+                    remaining -= recipient_share;
+                }
+            }
+            if remaining > 0 {
+                Self::transfer_tokens(&side, fee_vault, destination, fee_vault, remaining, &[seeds])?;
+            }
+            market.fees_swept = market.fees_swept.saturating_add(amount);
+            market.pack_into_slice(&mut market_account.data.borrow_mut());
+        }
+
+        Self::emit_event(DexEvent::FeesSwept(FeesSwept {
+            market: *market_account.key,
+            destination: *destination.key,
+            amount,
+            slot: Clock::get()?.slot,
+        }))?;
+
+        msg!("Swept {} fee tokens from market {} to {}", amount, market_account.key, destination.key);
+        Ok(())
+    }
+
+    // Process set fee authority instruction
+    fn process_set_fee_authority(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        new_fee_authority: Pubkey,
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let fee_authority = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !fee_authority.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Fee authority must sign",
+            ));
+        }
+
+        // Load market
+        let mut market = Self::load_market(program_id, market_account)?;
+
+        // Verify fee authority
+        if market.fee_authority != *fee_authority.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Not market fee authority",
+            ));
+        }
+
+        // Update market
+        market.fee_authority = new_fee_authority;
+        market.pack_into_slice(&mut market_account.data.borrow_mut());
+        Self::emit_params_updated(market_account, fee_authority.key, MarketParam::FeeAuthority(new_fee_authority))?;
+
+        msg!("Fee authority updated successfully");
+        Ok(())
+    }
+
+    // Process close market instruction
+    fn process_close_market(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let authority = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let fee_vault = next_account_info(account_info_iter)?;
+        let recipient = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !authority.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Authority must sign",
+            ));
+        }
+
+        if !token::is_token_program(token_program.key) {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Invalid token program",
+            ));
+        }
+
+        // Load market
+        let market = Self::load_market(program_id, market_account)?;
+
+        // Verify authority
+        if market.authority != *authority.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Not market authority",
+            ));
+        }
+
+        // Resting orders keep escrowed funds tied to the market
+        if market.num_bids != 0 || market.num_asks != 0 {
+            return Err(return_dex_error(
+                DexError::MarketNotEmpty,
+                "Market still has resting orders",
+            ));
+        }
+
+        // Deposits are only recoverable while the market exists
+        if market.base_free_total != 0 || market.quote_free_total != 0 {
+            return Err(return_dex_error(
+                DexError::MarketNotEmpty,
+                "Deposits must be withdrawn before closing",
+            ));
+        }
+
+        let (fee_vault_address, bump) = Market::find_fee_vault_address(program_id, market_account.key);
+        if fee_vault_address != *fee_vault.key {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Invalid fee vault address",
+            ));
+        }
+
+        // Every other account the market owns is passed, found by its address, so that none of
+        // them is left open
+        let find = |address: Pubkey| {
+            accounts
+                .iter()
+                .find(|account| *account.key == address)
+                .ok_or(ProgramError::NotEnoughAccountKeys)
+        };
+        let market_key = market_account.key;
+        let (market_signer, signer_bump) = Market::find_signer_address(program_id, market_key);
+        let (insurance_fund, insurance_fund_bump) = Market::find_insurance_fund_address(program_id, market_key);
+        let (pool, pool_bump) = Pool::find_address(program_id, market_key);
+        let (range_pool, range_pool_bump) = RangePool::find_address(program_id, market_key);
+        let (perp_market, perp_market_bump) = PerpMarket::find_address(program_id, market_key);
+        let (token_sale, token_sale_bump) = TokenSale::find_address(program_id, market_key);
+
+        // Unconsumed fills would be lost with the event queue
+        let event_queue = find(EventQueue::find_address(program_id, market_key).0)?;
+        if event_queue.lamports() > 0 && Self::load_event_queue_mut(program_id, market_account, event_queue)?.count != 0 {
+            return Err(return_dex_error(
+                DexError::MarketNotEmpty,
+                "Event queue must be consumed before closing",
+            ));
+        }
+
+        // Close the vaults that were created once they are empty; the fee vault must be swept,
+        // pools and the perp market emptied of liquidity and collateral, and Token-2022 vaults
+        // must have their withheld transfer fees harvested first
+        let fee_vault_seeds: &[&[u8]] = &[FEE_VAULT_SEED, market_key.as_ref(), &[bump]];
+        let signer_seeds: &[&[u8]] = &[market_key.as_ref(), &[signer_bump]];
+        let insurance_fund_seeds: &[&[u8]] = &[INSURANCE_FUND_SEED, market_key.as_ref(), &[insurance_fund_bump]];
+        let pool_seeds: &[&[u8]] = &[POOL_SEED, market_key.as_ref(), &[pool_bump]];
+        let range_pool_seeds: &[&[u8]] = &[RANGE_POOL_SEED, market_key.as_ref(), &[range_pool_bump]];
+        let perp_market_seeds: &[&[u8]] = &[PERP_MARKET_SEED, market_key.as_ref(), &[perp_market_bump]];
+        let token_sale_seeds: &[&[u8]] = &[TOKEN_SALE_SEED, market_key.as_ref(), &[token_sale_bump]];
+        let vaults = [
+            (fee_vault_address, fee_vault_address, fee_vault_seeds, "Fee vault must be swept before closing"),
+            (
+                Market::find_base_vault_address(program_id, market_key).0,
+                market_signer,
+                signer_seeds,
+                "Escrow vaults must be empty before closing",
+            ),
+            (
+                Market::find_quote_vault_address(program_id, market_key).0,
+                market_signer,
+                signer_seeds,
+                "Escrow vaults must be empty before closing",
+            ),
+            (insurance_fund, insurance_fund, insurance_fund_seeds, "Insurance fund must be empty before closing"),
+            (
+                Pool::find_vault_address(program_id, market_key, false).0,
+                pool,
+                pool_seeds,
+                "Pool must hold no liquidity before closing",
+            ),
+            (
+                Pool::find_vault_address(program_id, market_key, true).0,
+                pool,
+                pool_seeds,
+                "Pool must hold no liquidity before closing",
+            ),
+            (
+                RangePool::find_vault_address(program_id, market_key, false).0,
+                range_pool,
+                range_pool_seeds,
+                "Range pool must hold no liquidity before closing",
+            ),
+            (
+                RangePool::find_vault_address(program_id, market_key, true).0,
+                range_pool,
+                range_pool_seeds,
+                "Range pool must hold no liquidity before closing",
+            ),
+            (
+                PerpMarket::find_vault_address(program_id, market_key).0,
+                perp_market,
+                perp_market_seeds,
+                "Perp collateral must be withdrawn before closing",
+            ),
+            (
+                TokenSale::find_vault_address(program_id, market_key, false).0,
+                token_sale,
+                token_sale_seeds,
+                "Token sale vaults must be empty before closing",
+            ),
+            (
+                TokenSale::find_vault_address(program_id, market_key, true).0,
+                token_sale,
+                token_sale_seeds,
+                "Token sale vaults must be empty before closing",
+            ),
+        ];
+        for (vault, vault_authority, signer_seeds, message) in vaults {
+            Self::close_market_vault(accounts, find(vault)?, find(vault_authority)?, recipient, signer_seeds, message)?;
+        }
+
+        // Then the state accounts of the market's extensions
+        for address in [
+            TwapOracle::find_address(program_id, market_key).0,
+            EventQueue::find_address(program_id, market_key).0,
+            BatchAuction::find_address(program_id, market_key).0,
+            LoyaltySchedule::find_address(program_id, market_key).0,
+            pool,
+            range_pool,
+            perp_market,
+            token_sale,
+        ] {
+            let account = find(address)?;
+            if account.lamports() == 0 {
+                continue;
+            }
+            if account.owner != program_id {
+                return Err(return_dex_error(
+                    DexError::InvalidAccountOwner,
+                    "Market account not owned by program",
+                ));
+            }
+            Self::close_order_account(account, recipient)?;
+        }
+
+        // Close the market account
+        let lamports = market_account.lamports();
+        **recipient.lamports.borrow_mut() = recipient
+            .lamports()
+            .checked_add(lamports)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        **market_account.lamports.borrow_mut() = 0;
+        market_account.data.borrow_mut().fill(0);
+
+        msg!("Market {} closed, rent returned to {}", market_account.key, recipient.key);
+        Ok(())
+    }
+
+    // Close a token account owned by one of a market's PDAs, returning its rent, if it was
+    // created; it must be empty
+    fn close_market_vault<'a>(
+        accounts: &[AccountInfo<'a>],
+        vault: &AccountInfo<'a>,
+        vault_authority: &AccountInfo<'a>,
+        recipient: &AccountInfo<'a>,
+        signer_seeds: &[&[u8]],
+        message: &str,
+    ) -> ProgramResult {
+        if vault.lamports() == 0 {
+            return Ok(());
+        }
+
+        let token_program = accounts
+            .iter()
+            .find(|account| account.key == vault.owner && token::is_token_program(account.key))
+            .ok_or_else(|| return_dex_error(DexError::InvalidTokenAccount, "Vault not owned by a passed token program"))?;
+        let amount = token::unpack_account(&vault.data.borrow())
+            .map_err(|_| return_dex_error(DexError::InvalidTokenAccount, "Invalid vault"))?
+            .amount;
+        if amount != 0 {
+            return Err(return_dex_error(DexError::MarketNotEmpty, message));
+        }
+
+        invoke_signed(
+            &token_2022_instruction::close_account(
+                token_program.key,
+                vault.key,
+                recipient.key,
+                vault_authority.key,
+                &[],
+            )?,
+            &[
+                vault.clone(),
+                recipient.clone(),
+                vault_authority.clone(),
+                token_program.clone(),
+            ],
+            &[signer_seeds],
+        )
+    }
+
+    // Process set trade busting instruction
+    fn process_set_trade_busting(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        enabled: bool,
+        window_slots: u64,
+    ) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let admin = next_account_info(account_info_iter)?;
+        let config_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !admin.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Config admin must sign",
+            ));
+        }
+
+        // Load config
+        let mut config = Self::load_program_config(program_id, config_account)?.ok_or_else(|| {
+            return_dex_error(DexError::InvalidAccountData, "Program config not initialized")
+        })?;
+
+        // Verify admin
+        if config.admin != *admin.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Not config admin",
+            ));
+        }
+
+        // Update config
+        config.trade_busting_enabled = enabled;
+        config.trade_bust_window_slots = window_slots;
+        config.pack_into_slice(&mut config_account.data.borrow_mut());
+
+        msg!("Trade busting updated successfully");
+        Ok(())
+    }
+
+    // Process bust trade instruction
+    fn process_bust_trade(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let authority = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let config_account = next_account_info(account_info_iter)?;
+        let settlement_record_account = next_account_info(account_info_iter)?;
+        let taker = next_account_info(account_info_iter)?;
+        let maker = next_account_info(account_info_iter)?;
+        let taker_base_account = next_account_info(account_info_iter)?;
+        let taker_quote_account = next_account_info(account_info_iter)?;
+        let maker_base_account = next_account_info(account_info_iter)?;
+        let maker_quote_account = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !authority.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Authority must sign",
+            ));
+        }
+
+        Self::check_token_program(token_program)?;
+
+        // Trade busting must be enabled program-wide
+        let config = Self::load_program_config(program_id, config_account)?;
+        let config = config.filter(|config| config.trade_busting_enabled).ok_or_else(|| {
+            return_dex_error(DexError::TradeBustingDisabled, "Trade busting not enabled")
+        })?;
+
+        // Load market
+        let market = Self::load_market(program_id, market_account)?;
+
+        // Verify authority
+        if market.authority != *authority.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Not market authority",
+            ));
+        }
+
+        // Load settlement record
+        if settlement_record_account.owner != program_id {
+            return Err(return_dex_error(
+                DexError::InvalidAccountOwner,
+                "Settlement record not owned by program",
+            ));
+        }
+        let mut record = SettlementRecord::unpack_from_slice(&settlement_record_account.data.borrow())?;
+        if !record.is_initialized {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Settlement record not initialized",
+            ));
+        }
+        if record.market != *market_account.key {
+            return Err(return_dex_error(
+                DexError::AccountMismatch,
+                "Settlement record does not belong to market",
+            ));
+        }
+        if record.is_busted {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Trade already busted",
+            ));
+        }
+
+        let slot = Clock::get()?.slot;
+        let deadline = record.slot.saturating_add(config.trade_bust_window_slots);
+        if slot > deadline {
+            return Err(return_dex_error_expected(
+                DexError::TradeBustWindowExpired,
+                "Settlement is outside the bust window",
+                format_args!("a slot at or before {}", deadline),
+                slot,
+            ));
+        }
+
+        // Token accounts must be the ones that settled
+        if *taker_base_account.key != record.taker_base_account
+            || *taker_quote_account.key != record.taker_quote_account
+            || *maker_base_account.key != record.maker_base_account
+            || *maker_quote_account.key != record.maker_quote_account
+        {
+            return Err(return_dex_error(
+                DexError::InvalidTokenAccount,
+                "Token accounts do not match the settlement",
+            ));
+        }
+
+        // Both parties consent by signing as the owners of the accounts being debited
+        for (party, token_account) in [(taker, taker_base_account), (maker, maker_quote_account)] {
+            let owner = token::unpack_account(&token_account.data.borrow())
+                .map_err(|_| return_dex_error(DexError::InvalidTokenAccount, "Invalid token account"))?
+                .owner;
+            if !party.is_signer || owner != *party.key {
+                return Err(return_dex_error(
+                    DexError::AccountNotAuthorized,
+                    "Affected party must consent to the bust",
+                ));
+            }
+        }
+
+        // Return base tokens from the taker to the maker, and quote tokens from the maker to
+        // the taker; transfer fees withheld by either fill are not returned
+        Self::transfer_tokens(
+            &Self::load_token_side(&market, accounts, false)?,
+            taker_base_account,
+            maker_base_account,
+            taker,
+            record.base_amount,
+            &[],
+        )?;
+        Self::transfer_tokens(
+            &Self::load_token_side(&market, accounts, true)?,
+            maker_quote_account,
+            taker_quote_account,
+            maker,
+            record.maker_quote_amount,
+            &[],
+        )?;
+
+        record.is_busted = true;
+        record.pack_into_slice(&mut settlement_record_account.data.borrow_mut());
+
+        // Audit event
+        let event = TradeBust {
+            market: *market_account.key,
+            settlement: *settlement_record_account.key,
+            authority: *authority.key,
+            base_amount: record.base_amount,
+            quote_amount: record.maker_quote_amount,
+            settlement_slot: record.slot,
+            bust_slot: slot,
+        };
+        msg!(
+            "AUDIT: trade busted on market {} by {}: settlement {} of slot {}",
+            event.market,
+            event.authority,
+            event.settlement,
+            event.settlement_slot
+        );
+        Self::emit_event(DexEvent::TradeBust(event))?;
+
+        Ok(())
+    }
+
+    // Process initialize insurance fund instruction
+    fn process_initialize_insurance_fund(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let payer = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let insurance_fund = next_account_info(account_info_iter)?;
+        let quote_mint = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !payer.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Payer must sign",
+            ));
+        }
+
+        Self::check_token_program(token_program)?;
+        Self::check_system_program(system_program_account)?;
+
+        // Load market
+        let market = Self::load_market(program_id, market_account)?;
+
+        if market.quote_mint != *quote_mint.key {
+            return Err(return_dex_error(
+                DexError::InvalidTokenAccount,
+                "Insurance fund must hold the quote mint",
+            ));
+        }
+
+        let (insurance_fund_address, bump) = Market::find_insurance_fund_address(program_id, market_account.key);
+        if insurance_fund_address != *insurance_fund.key {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Invalid insurance fund address",
+            ));
+        }
+
+        let token_program = Self::load_token_side(&market, accounts, true)?.token_program;
+        if insurance_fund.owner == token_program.key {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Insurance fund already initialized",
+            ));
+        }
+
+        // Create the insurance fund PDA as a token account that is its own owner
+        let rent = Rent::get()?;
+        let space = token::account_len(&quote_mint.data.borrow())?;
+        let lamports = rent.minimum_balance(space);
+
+        invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                insurance_fund.key,
+                lamports,
+                space as u64,
+                token_program.key,
+            ),
+            &[
+                payer.clone(),
+                insurance_fund.clone(),
+                system_program_account.clone(),
+            ],
+            &[&[INSURANCE_FUND_SEED, market_account.key.as_ref(), &[bump]]],
+        )?;
+
+        invoke(
+            &token_2022_instruction::initialize_account3(
+                token_program.key,
+                insurance_fund.key,
+                quote_mint.key,
+                insurance_fund.key,
+            )?,
+            &[insurance_fund.clone(), quote_mint.clone(), token_program.clone()],
+        )?;
+
+        msg!("Insurance fund initialized successfully");
+        Ok(())
+    }
+
+    // Process deposit insurance fund instruction
+    fn process_deposit_insurance_fund(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let depositor = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let source = next_account_info(account_info_iter)?;
+        let insurance_fund = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !depositor.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Depositor must sign",
+            ));
+        }
+
+        Self::check_token_program(token_program)?;
+
+        if amount == 0 {
+            return Err(return_dex_error(
+                DexError::InvalidInstructionData,
+                "Deposit amount must be positive",
+            ));
+        }
+
+        // Load market
+        let market = Self::load_market(program_id, market_account)?;
+
+        if Market::find_insurance_fund_address(program_id, market_account.key).0 != *insurance_fund.key {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Invalid insurance fund address",
+            ));
+        }
+
+        let token_side = Self::load_token_side(&market, accounts, true)?;
+        Self::transfer_tokens_in(&token_side, source, insurance_fund, depositor, amount, &[])?;
+
+        // Audit event
+        let event = InsuranceFundTransfer {
+            market: *market_account.key,
+            token_account: *source.key,
+            is_deposit: true,
+            amount,
+            balance: token::unpack_account(&insurance_fund.data.borrow())?.amount,
+            slot: Clock::get()?.slot,
+        };
+        Self::emit_event(DexEvent::InsuranceFund(event))?;
+
+        msg!("Deposited {} quote tokens into the insurance fund of market {}", amount, market_account.key);
+        Ok(())
+    }
+
+    // Process withdraw insurance fund instruction
+    fn process_withdraw_insurance_fund(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+        // Get accounts
+        let account_info_iter = &mut accounts.iter();
+        let authority = next_account_info(account_info_iter)?;
+        let market_account = next_account_info(account_info_iter)?;
+        let insurance_fund = next_account_info(account_info_iter)?;
+        let destination = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+
+        // Verify accounts
+        if !authority.is_signer {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Authority must sign",
+            ));
+        }
+
+        Self::check_token_program(token_program)?;
+
+        // Load market
+        let market = Self::load_market(program_id, market_account)?;
+
+        // Verify authority
+        if market.authority != *authority.key {
+            return Err(return_dex_error(
+                DexError::AccountNotAuthorized,
+                "Not market authority",
+            ));
+        }
+
+        let token_side = Self::load_token_side(&market, accounts, true)?;
+        Self::draw_insurance_fund(program_id, market_account, insurance_fund, destination, &token_side, amount)?;
+
+        msg!("Drew {} quote tokens from the insurance fund of market {}", amount, market_account.key);
+        Ok(())
+    }
+
+    // Pay a shortfall out of a market's insurance fund and log the draw; settlement paths that
+    // can come up short call this with the account that is owed the difference
+    fn draw_insurance_fund<'a>(
+        program_id: &Pubkey,
+        market_account: &AccountInfo<'a>,
+        insurance_fund: &AccountInfo<'a>,
+        destination: &AccountInfo<'a>,
+        token_side: &TokenSide<'a, '_>,
+        amount: u64,
+    ) -> ProgramResult {
+        let (insurance_fund_address, bump) = Market::find_insurance_fund_address(program_id, market_account.key);
+        if insurance_fund_address != *insurance_fund.key {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Invalid insurance fund address",
+            ));
+        }
+
+        let balance = token::unpack_account(&insurance_fund.data.borrow())
+            .map_err(|_| return_dex_error(DexError::InvalidTokenAccount, "Insurance fund not initialized"))?
+            .amount;
+        if amount == 0 || amount > balance {
+            return Err(return_dex_error_expected(
+                DexError::InsufficientInsuranceFund,
+                "Draw exceeds the insurance fund balance",
+                format_args!("1 to {}", balance),
+                amount,
+            ));
+        }
+
+        Self::transfer_tokens(
+            token_side,
+            insurance_fund,
+            destination,
+            insurance_fund,
+            amount,
+            &[&[INSURANCE_FUND_SEED, market_account.key.as_ref(), &[bump]]],
+        )?;
+
+        // Audit event
+        let event = InsuranceFundTransfer {
+            market: *market_account.key,
+            token_account: *destination.key,
+            is_deposit: false,
+            amount,
+            balance: balance - amount,
+            slot: Clock::get()?.slot,
+        };
+        Self::emit_event(DexEvent::InsuranceFund(event))?;
+
+        Ok(())
+    }
+
+    // Charge the configured market creation fee, reading the fee accounts that follow the config
+    fn pay_market_creation_fee<'a, 'b>(
+        config: &ProgramConfig,
+        market_authority: &AccountInfo<'a>,
+        quote_mint: &AccountInfo<'a>,
+        quote_decimals: u8,
+        system_program_account: &AccountInfo<'a>,
+        account_info_iter: &mut impl Iterator<Item = &'b AccountInfo<'a>>,
+    ) -> ProgramResult
+    where
+        'a: 'b,
+    {
+        if config.market_creation_fee_lamports == 0 && config.market_creation_fee_quote == 0 {
+            return Ok(());
+        }
+
+        let treasury = next_account_info(account_info_iter)?;
+        if *treasury.key != config.treasury {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Treasury does not match program config",
+            ));
+        }
+
+        // Lamport fee
+        if config.market_creation_fee_lamports > 0 {
+            invoke(
+                &system_instruction::transfer(
+                    market_authority.key,
+                    treasury.key,
+                    config.market_creation_fee_lamports,
+                ),
                 &[
-                    taker_quote_account.clone(),
-                    fee_recipient_account.clone(),
-                    market_account.clone(),
-                    token_program.clone(),
+                    market_authority.clone(),
+                    treasury.clone(),
+                    system_program_account.clone(),
                 ],
-                &[&[&market.authority.to_bytes()]],
             )?;
         }
 
-        msg!("Funds settled successfully");
+        // Quote token fee
+        if config.market_creation_fee_quote > 0 {
+            let authority_quote_account = next_account_info(account_info_iter)?;
+            let treasury_quote_account = next_account_info(account_info_iter)?;
+            let token_program = next_account_info(account_info_iter)?;
+
+            if token_program.key != quote_mint.owner {
+                return Err(ProgramError::IncorrectProgramId);
+            }
+
+            let treasury_token = token::unpack_account(&treasury_quote_account.data.borrow())?;
+            if treasury_token.owner != config.treasury || treasury_token.mint != *quote_mint.key {
+                return Err(return_dex_error(
+                    DexError::InvalidTokenAccount,
+                    "Treasury quote account must be a treasury-owned quote token account",
+                ));
+            }
+
+            let token_side = TokenSide {
+                token_program,
+                mint: Some(quote_mint),
+                decimals: quote_decimals,
+            };
+            Self::transfer_tokens(
+                &token_side,
+                authority_quote_account,
+                treasury_quote_account,
+                market_authority,
+                config.market_creation_fee_quote,
+                &[],
+            )?;
+        }
+
+        msg!("Market creation fee paid");
+        Ok(())
+    }
+
+    // Check that a CPI goes to SPL Token or Token-2022 rather than a program the caller chose
+    fn check_token_program(token_program: &AccountInfo) -> ProgramResult {
+        if !token::is_token_program(token_program.key) {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        Ok(())
+    }
+
+    // Check that a memo CPI goes to the SPL Memo program rather than a program the caller chose
+    fn check_memo_program(memo_program: &AccountInfo) -> ProgramResult {
+        if *memo_program.key != spl_memo::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        Ok(())
+    }
+
+    // Check that a CPI goes to the system program rather than a program the caller chose
+    fn check_system_program(system_program_account: &AccountInfo) -> ProgramResult {
+        if *system_program_account.key != system_program::id() {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        Ok(())
+    }
+
+    // Check that a market takes new orders; a paused market fails with its own error so callers
+    // can tell a pause from the other statuses
+    fn check_market_active(market: &Market, msg_str: &str) -> ProgramResult {
+        match market.status {
+            MarketStatus::Active => Ok(()),
+            MarketStatus::Paused => Err(return_dex_error(DexError::MarketPaused, msg_str)),
+            status => Err(return_dex_error_expected(
+                DexError::MarketNotActive,
+                msg_str,
+                "status Active",
+                format_args!("{:?}", status),
+            )),
+        }
+    }
+
+    // Load an initialized market account owned by the program
+    fn load_market(program_id: &Pubkey, market_account: &AccountInfo) -> Result<Market, ProgramError> {
+        Self::check_market_account(program_id, market_account)?;
+        let market = Market::unpack_from_slice(&market_account.data.borrow())?;
+        Self::check_market_initialized(&market)?;
+
+        Ok(market)
+    }
+
+    // Borrow an initialized market account owned by the program in place, without copying it
+    fn load_market_mut<'a>(program_id: &Pubkey, market_account: &'a AccountInfo) -> Result<RefMut<'a, Market>, ProgramError> {
+        Self::check_market_account(program_id, market_account)?;
+        let market = RefMut::filter_map(market_account.try_borrow_mut_data()?, |data| {
+            load_zero_copy_mut::<Market>(data).ok()
+        })
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+        Self::check_market_initialized(&market)?;
+
+        Ok(market)
+    }
+
+    // Check that an account is a market account owned by the program
+    fn check_market_account(program_id: &Pubkey, market_account: &AccountInfo) -> ProgramResult {
+        if market_account.owner != program_id {
+            return Err(return_dex_error(
+                DexError::InvalidAccountOwner,
+                "Market not owned by program",
+            ));
+        }
+        if market_account.data_len() == Market::LEGACY_LEN {
+            return Err(return_dex_error(
+                DexError::AccountNotMigrated,
+                "Market must be migrated to the current layout",
+            ));
+        }
+        if market_account.data_len() != Market::LEN {
+            return Err(return_dex_error_expected(
+                DexError::InvalidAccountData,
+                "Account is not a market",
+                format_args!("{} bytes", Market::LEN),
+                market_account.data_len(),
+            ));
+        }
+        Ok(())
+    }
+
+    // Check that a market has been initialized with the current layout version
+    fn check_market_initialized(market: &Market) -> ProgramResult {
+        if !market.is_initialized {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Market not initialized",
+            ));
+        }
+        if market.version != ACCOUNT_VERSION {
+            return Err(return_dex_error_expected(
+                DexError::AccountNotMigrated,
+                "Unsupported market layout version",
+                ACCOUNT_VERSION,
+                market.version,
+            ));
+        }
+        Ok(())
+    }
+
+    // Load an initialized order account owned by the program that rests on the given market
+    fn load_order(program_id: &Pubkey, market_account: &AccountInfo, order_account: &AccountInfo) -> Result<Order, ProgramError> {
+        if order_account.owner != program_id {
+            return Err(return_dex_error(
+                DexError::InvalidAccountOwner,
+                "Order not owned by program",
+            ));
+        }
+        if order_account.data_len() == Order::LEGACY_LEN {
+            return Err(return_dex_error(
+                DexError::AccountNotMigrated,
+                "Order must be migrated to the current layout",
+            ));
+        }
+        if order_account.data_len() != Order::LEN {
+            return Err(return_dex_error_expected(
+                DexError::InvalidAccountData,
+                "Account is not an order",
+                format_args!("{} bytes", Order::LEN),
+                order_account.data_len(),
+            ));
+        }
+        let order = Order::unpack_from_slice(&order_account.data.borrow())?;
+        if !order.is_initialized {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Order not initialized",
+            ));
+        }
+        if order.version != ACCOUNT_VERSION {
+            return Err(return_dex_error_expected(
+                DexError::AccountNotMigrated,
+                "Unsupported order layout version",
+                ACCOUNT_VERSION,
+                order.version,
+            ));
+        }
+        if order.market != *market_account.key {
+            return Err(return_dex_error(
+                DexError::AccountMismatch,
+                "Order does not belong to this market",
+            ));
+        }
+
+        Ok(order)
+    }
+
+    // Check that a program account about to be initialized is an empty account of the expected type
+    //
+    // State accounts are told apart by their size and start with their `is_initialized` flag.
+    fn check_uninitialized(account: &AccountInfo, len: usize) -> ProgramResult {
+        let data = account.data.borrow();
+        if data.len() != len {
+            return Err(return_dex_error_expected(
+                DexError::InvalidAccountData,
+                "Account size does not match its type",
+                format_args!("{} bytes", len),
+                data.len(),
+            ));
+        }
+        if data[0] != 0 {
+            return Err(return_dex_error(
+                DexError::AlreadyInitialized,
+                "Account already initialized",
+            ));
+        }
+
+        Ok(())
+    }
+
+    // Check that a pre-existing program account holds enough lamports to be rent exempt
+    fn check_rent_exempt(account: &AccountInfo) -> ProgramResult {
+        if !Rent::get()?.is_exempt(account.lamports(), account.data_len()) {
+            return Err(return_dex_error(
+                DexError::NotRentExempt,
+                "Account is not rent exempt",
+            ));
+        }
+
+        Ok(())
+    }
+
+    // Log an event as its name and Borsh encoding, for indexers to decode with `DexEvent::decode`
+    fn emit_event(event: DexEvent) -> ProgramResult {
+        sol_log_data(&[event.name(), &event.data()?]);
         Ok(())
     }
+
+    // Log a change of a market parameter
+    fn emit_params_updated(market_account: &AccountInfo, authority: &Pubkey, param: MarketParam) -> ProgramResult {
+        Self::emit_event(DexEvent::ParamsUpdated(ParamsUpdated {
+            market: *market_account.key,
+            authority: *authority,
+            param,
+            slot: Clock::get()?.slot,
+        }))
+    }
+
+    // Load a market mint and return its decimals; trustless markets refuse freezable mints
+    fn load_market_mint(mint_account: &AccountInfo, trustless: bool) -> Result<u8, ProgramError> {
+        if !token::is_token_program(mint_account.owner) {
+            return Err(return_dex_error(
+                DexError::InvalidMint,
+                "Mint not owned by a token program",
+            ));
+        }
+
+        let mint = token::unpack_mint(&mint_account.data.borrow())
+            .map_err(|_| return_dex_error(DexError::InvalidMint, "Not an initialized mint"))?;
+        if let Some(extension_type) = token::unsupported_mint_extension(&mint_account.data.borrow())? {
+            msg!("Unsupported mint extension {:?}", extension_type);
+            return Err(return_dex_error(
+                DexError::InvalidMint,
+                "Mint has an unsupported extension",
+            ));
+        }
+        if trustless && mint.freeze_authority.is_some() {
+            return Err(return_dex_error(
+                DexError::FreezableMint,
+                "Trustless markets do not accept mints with a freeze authority",
+            ));
+        }
+
+        Ok(mint.decimals)
+    }
+
+    // Load the program config, returning None while it has not been initialized
+    fn load_program_config(
+        program_id: &Pubkey,
+        config_account: &AccountInfo,
+    ) -> Result<Option<ProgramConfig>, ProgramError> {
+        if ProgramConfig::find_address(program_id).0 != *config_account.key {
+            return Err(return_dex_error(
+                DexError::InvalidAccountData,
+                "Invalid program config address",
+            ));
+        }
+
+        if config_account.owner != program_id {
+            return Ok(None);
+        }
+
+        let config = ProgramConfig::unpack_from_slice(&config_account.data.borrow())?;
+        Ok(Some(config).filter(|config| config.is_initialized))
+    }
 }
@@ -1,71 +1,3359 @@
 // State module for the DEX program
 
+use crate::error::DexError;
+use crate::math::{self, Rounding};
 use borsh::{BorshDeserialize, BorshSerialize};
+use bytemuck::{CheckedBitPattern, NoUninit, Pod, Zeroable};
+use shank::{ShankAccount, ShankType};
 use solana_program::{
     program_error::ProgramError,
     program_pack::{IsInitialized, Pack, Sealed},
     pubkey::Pubkey,
 };
-use std::convert::TryFrom;
+
+/// Seed of the program config PDA
+pub const PROGRAM_CONFIG_SEED: &[u8] = b"program_config";
+
+/// Seed of a market PDA derived from its mints
+pub const MARKET_SEED: &[u8] = b"market";
+
+/// Seed of the market registry PDA
+pub const MARKET_REGISTRY_SEED: &[u8] = b"market_registry";
+
+/// Seed of a market's fee vault PDA
+pub const FEE_VAULT_SEED: &[u8] = b"fee_vault";
+
+/// Seed of a market's base token escrow vault PDA
+pub const BASE_VAULT_SEED: &[u8] = b"base_vault";
+
+/// Seed of a market's quote token escrow vault PDA
+pub const QUOTE_VAULT_SEED: &[u8] = b"quote_vault";
+
+/// Seed of a market's insurance fund PDA
+pub const INSURANCE_FUND_SEED: &[u8] = b"insurance_fund";
+
+/// Seed of a market's whitelist entry PDAs
+pub const WHITELIST_SEED: &[u8] = b"whitelist";
+
+/// Seed for the PDA recording an owner's trading delegate on a market
+pub const DELEGATION_SEED: &[u8] = b"delegation";
+
+/// Seed for the PDA holding an owner's free balances on a market
+pub const OPEN_ORDERS_SEED: &[u8] = b"open_orders";
+
+/// Seed for the PDA of a market's TWAP oracle
+pub const TWAP_ORACLE_SEED: &[u8] = b"twap_oracle";
+
+/// Seed for the PDA of a market's event queue
+pub const EVENT_QUEUE_SEED: &[u8] = b"event_queue";
+
+/// Seed of the fill record PDA of a firm quote
+pub const QUOTE_FILL_SEED: &[u8] = b"quote_fill";
+
+/// Seed of a market's RFQ authority PDA, the delegate of makers' token accounts
+pub const RFQ_AUTHORITY_SEED: &[u8] = b"rfq_authority";
+
+/// Seed of a market's constant-product pool PDA
+pub const POOL_SEED: &[u8] = b"pool";
+
+/// Seed of the PDA of a pool's base token reserve
+pub const POOL_BASE_VAULT_SEED: &[u8] = b"pool_base_vault";
+
+/// Seed of the PDA of a pool's quote token reserve
+pub const POOL_QUOTE_VAULT_SEED: &[u8] = b"pool_quote_vault";
+
+/// Seed of the PDA of a pool's LP token mint
+pub const POOL_LP_MINT_SEED: &[u8] = b"pool_lp_mint";
+
+/// Seed of a market's concentrated-liquidity range pool PDA
+pub const RANGE_POOL_SEED: &[u8] = b"range_pool";
+
+/// Seed of the PDA of a range pool's base token vault
+pub const RANGE_POOL_BASE_VAULT_SEED: &[u8] = b"range_pool_base_vault";
+
+/// Seed of the PDA of a range pool's quote token vault
+pub const RANGE_POOL_QUOTE_VAULT_SEED: &[u8] = b"range_pool_quote_vault";
+
+/// Seed of a range position PDA
+pub const RANGE_POSITION_SEED: &[u8] = b"range_position";
+
+/// Seed of a mint's lending pool PDA
+pub const LENDING_POOL_SEED: &[u8] = b"lending_pool";
+
+/// Seed of the PDA of a lending pool's token vault
+pub const LENDING_POOL_VAULT_SEED: &[u8] = b"lending_pool_vault";
+
+/// Seed of the PDA of a lending pool's share mint
+pub const LENDING_POOL_SHARE_MINT_SEED: &[u8] = b"lending_pool_share_mint";
+
+/// Seed of a market's perp market PDA
+pub const PERP_MARKET_SEED: &[u8] = b"perp_market";
+
+/// Seed of the PDA of a perp market's quote collateral vault
+pub const PERP_VAULT_SEED: &[u8] = b"perp_vault";
+
+/// Seed of an owner's perp position PDA
+pub const PERP_POSITION_SEED: &[u8] = b"perp_position";
+
+/// Seed of a market's batch auction PDA
+pub const BATCH_AUCTION_SEED: &[u8] = b"batch_auction";
+
+/// Seed of a market's token sale PDA
+pub const TOKEN_SALE_SEED: &[u8] = b"token_sale";
+
+/// Seed of the PDA of a token sale's vault of the base tokens for sale
+pub const TOKEN_SALE_BASE_VAULT_SEED: &[u8] = b"token_sale_base_vault";
+
+/// Seed of the PDA of a token sale's vault of the quote tokens contributed
+pub const TOKEN_SALE_QUOTE_VAULT_SEED: &[u8] = b"token_sale_quote_vault";
+
+/// Seed of a contributor's token sale contribution PDA
+pub const SALE_CONTRIBUTION_SEED: &[u8] = b"sale_contribution";
+
+/// Seed of the governance token stake pool PDA
+pub const STAKE_POOL_SEED: &[u8] = b"stake_pool";
+
+/// Seed of the PDA of the stake pool's vault of staked governance tokens
+pub const STAKE_VAULT_SEED: &[u8] = b"stake_vault";
+
+/// Seed of the PDA of the stake pool's vault of fee rewards
+pub const STAKE_REWARD_VAULT_SEED: &[u8] = b"stake_reward_vault";
+
+/// Seed of a staker's stake account PDA
+pub const STAKE_ACCOUNT_SEED: &[u8] = b"stake_account";
+
+/// Seed of a market's loyalty schedule PDA
+pub const LOYALTY_SCHEDULE_SEED: &[u8] = b"loyalty_schedule";
+
+/// Seed of a trader's loyalty points PDA on a market
+pub const LOYALTY_POINTS_SEED: &[u8] = b"loyalty_points";
+
+/// Domain separator prepended to the firm quote messages makers sign
+pub const FIRM_QUOTE_DOMAIN: &[u8] = b"solana_rust_dex:firm_quote";
+
+/// Maximum number of quote mints a program config can whitelist
+pub const MAX_ALLOWED_QUOTE_MINTS: usize = 8;
+
+/// Maximum length in bytes of the memo an order carries
+pub const MAX_ORDER_MEMO_LEN: usize = 32;
+
+/// Number of hourly buckets in a market's rolling volume window
+pub const VOLUME_BUCKETS: usize = 24;
+
+/// Seconds covered by one rolling volume bucket
+pub const VOLUME_BUCKET_SECONDS: u64 = 3600;
+
+/// Number of fill events an event queue holds before the oldest is overwritten
+pub const EVENT_QUEUE_CAPACITY: usize = 64;
+
+/// Most orders that rest on a batch auction market at once: RunAuction passes every resting
+/// order, four accounts each, next to its own fixed and optional accounts, within the 64
+/// accounts a transaction can lock
+pub const MAX_AUCTION_ORDERS: u64 = 12;
+
+/// Market flag set once the market has an event queue, which every settlement then appends to
+pub const MARKET_FLAG_EVENT_QUEUE: u64 = 1 << 0;
+
+/// Market flag set once the market matches by batch auction, which settles no fill otherwise
+pub const MARKET_FLAG_BATCH_AUCTION: u64 = 1 << 1;
+
+/// Market flag set once the market has a loyalty schedule, which every settlement then accrues
+/// points by
+pub const MARKET_FLAG_LOYALTY_POINTS: u64 = 1 << 2;
+
+/// Market flag set while the market scales its taker fee by the realized volatility of its fills
+pub const MARKET_FLAG_DYNAMIC_FEES: u64 = 1 << 3;
+
+/// Market flag set while `best_bid` is stale: an order at the best bid left the book while
+/// other bids rest, so `best_bid` only bounds the remaining bids from above
+pub const MARKET_FLAG_BEST_BID_STALE: u64 = 1 << 4;
+
+/// Market flag set while `best_ask` is stale: an order at the best ask left the book while
+/// other asks rest, so `best_ask` only bounds the remaining asks from below
+pub const MARKET_FLAG_BEST_ASK_STALE: u64 = 1 << 5;
+
+/// LP tokens of a pool's first deposit that are never minted, so the supply cannot be driven
+/// down to a few tokens worth arbitrarily much
+pub const POOL_MINIMUM_LIQUIDITY: u64 = 1000;
+
+/// Number of initialized ticks a range pool holds, which bounds the ticks a swap crosses
+pub const RANGE_POOL_MAX_TICKS: usize = 64;
+
+/// Seconds in the year lending pool interest rates are quoted over
+pub const SECONDS_PER_YEAR: u64 = 365 * 24 * 60 * 60;
+
+/// Seconds between perp funding payments
+pub const FUNDING_INTERVAL_SECONDS: i64 = 3600;
+
+/// Funding intervals over which a perp's premium to the index is paid off
+pub const FUNDING_INTERVALS_PER_DAY: i128 = 24;
+
+/// Scale of a perp market's cumulative funding per base lot
+pub const FUNDING_PRECISION: i128 = 1_000_000_000;
+
+/// Number of fee tiers a stake pool grants taker fee discounts by
+pub const STAKE_FEE_TIERS: usize = 4;
+
+/// Scale of a stake pool's rewards per staked token
+pub const STAKE_REWARD_PRECISION: u128 = 1_000_000_000_000;
+
+/// Number of emission phases a loyalty schedule holds
+pub const LOYALTY_SCHEDULE_PHASES: usize = 4;
+
+/// Layout version of market and order accounts
+pub const ACCOUNT_VERSION: u8 = 1;
+
+/// Bytes reserved at the end of a market account for future fields, what is left of the 128
+/// bytes of the versioned layout after the flags, fee ledger, fee split and dynamic fees
+pub const MARKET_RESERVED_LEN: usize = 14;
+
+/// Bytes reserved at the end of an order account for future fields
+pub const ORDER_RESERVED_LEN: usize = 64;
+
+// The IDL generator only reads literal array lengths, so the account structs spell these out
+const _: () = assert!(
+    MAX_ALLOWED_QUOTE_MINTS == 8
+        && MAX_ORDER_MEMO_LEN == 32
+        && VOLUME_BUCKETS == 24
+        && MARKET_RESERVED_LEN == 14
+        && ORDER_RESERVED_LEN == 64
+        && EVENT_QUEUE_CAPACITY == 64
+);
+
+/// Market creation mode enum
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
+pub enum MarketCreationMode {
+    /// Anyone may create markets
+    Permissionless,
+    /// Only the config admin may create markets
+    AdminOnly,
+}
+
+/// Program-wide configuration state
+#[derive(BorshSerialize, BorshDeserialize, ShankAccount, Debug, Clone)]
+pub struct ProgramConfig {
+    /// Is this config initialized
+    pub is_initialized: bool,
+    
+    /// Config admin
+    pub admin: Pubkey,
+    
+    /// Who may create markets
+    pub market_creation_mode: MarketCreationMode,
+    
+    /// Number of whitelisted quote mints (0 allows any quote mint)
+    pub num_allowed_quote_mints: u8,
+    
+    /// Whitelisted quote mints
+    pub allowed_quote_mints: [Pubkey; 8],
+    
+    /// Protocol treasury receiving market creation fees
+    pub treasury: Pubkey,
+    
+    /// Market creation fee in lamports
+    pub market_creation_fee_lamports: u64,
+    
+    /// Market creation fee in the new market's quote token
+    pub market_creation_fee_quote: u64,
+    
+    /// Whether market authorities may bust settled trades
+    pub trade_busting_enabled: bool,
+    
+    /// Number of slots after a settlement during which it may be busted
+    pub trade_bust_window_slots: u64,
+}
+
+impl ProgramConfig {
+    /// Find the program config PDA for a program
+    pub fn find_address(program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[PROGRAM_CONFIG_SEED], program_id)
+    }
+    
+    /// Check whether markets may be quoted in a mint
+    pub fn is_quote_mint_allowed(&self, quote_mint: &Pubkey) -> bool {
+        let count = self.num_allowed_quote_mints as usize;
+        count == 0 || self.allowed_quote_mints[..count].contains(quote_mint)
+    }
+}
+
+impl Sealed for ProgramConfig {}
+
+impl IsInitialized for ProgramConfig {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for ProgramConfig {
+    const LEN: usize = 1 + 32 + 1 + 1 + 32 * MAX_ALLOWED_QUOTE_MINTS + 32 + 8 + 8 + 1 + 8;
+    
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap();
+    }
+    
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Self::try_from_slice(src).map_err(|_| ProgramError::InvalidAccountData)
+    }
+}
 
 /// Market state
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+///
+/// Markets and orders are zero-copy: the struct is the packed little-endian account layout, read
+/// and written in place without (de)serializing the whole account. Fields are laid out without
+/// padding in declaration order, so the layout matches the Borsh encoding of earlier versions.
+#[derive(CheckedBitPattern, NoUninit, ShankAccount, Debug, Clone, Copy)]
+#[repr(C, packed)]
 pub struct Market {
     /// Is this market initialized
     pub is_initialized: bool,
     
-    /// Market authority
-    pub authority: Pubkey,
+    /// Market authority
+    pub authority: Pubkey,
+    
+    /// Base token mint
+    pub base_mint: Pubkey,
+    
+    /// Quote token mint
+    pub quote_mint: Pubkey,
+    
+    /// Minimum base order size in base lots
+    pub min_base_order_size: u64,
+    
+    /// Tick size (minimum price increment) in quote lots per base lot
+    pub tick_size: u64,
+    
+    /// Taker fee rate in basis points (1/100 of 1%)
+    pub taker_fee_bps: u16,
+    
+    /// Maker fee rate in basis points; negative values are rebates paid from the taker fee
+    pub maker_fee_bps: i16,
+    
+    /// Next order ID
+    pub next_order_id: u64,
+    
+    /// Number of bids in the order book
+    pub num_bids: u64,
+    
+    /// Number of asks in the order book
+    pub num_asks: u64,
+    
+    /// Introspection checks applied to settlement-sensitive instructions
+    pub instruction_guard: InstructionGuard,
+    
+    /// Merkle root of the open orders at the last commitment
+    pub open_orders_root: [u8; 32],
+    
+    /// Number of orders covered by the open orders root
+    pub open_orders_root_leaves: u64,
+    
+    /// Slot at which the open orders root was committed
+    pub open_orders_root_slot: u64,
+    
+    /// Share of the net fee paid to the taker order's referrer, in basis points of the fee
+    pub referral_share_bps: u16,
+    
+    /// Authority allowed to sweep the fee vault
+    pub fee_authority: Pubkey,
+    
+    /// Trading status set by the market authority
+    pub status: MarketStatus,
+    
+    /// Total remaining base quantity of resting bids in base lots
+    pub bid_base_total: u64,
+    
+    /// Total remaining base quantity of resting asks in base lots
+    pub ask_base_total: u64,
+    
+    /// Total notional of resting bids in quote lots (price * remaining quantity)
+    pub bid_notional: u128,
+    
+    /// Total notional of resting asks in quote lots (price * remaining quantity)
+    pub ask_notional: u128,
+    
+    /// Whether only whitelisted owners may place orders
+    pub whitelist_enabled: bool,
+    
+    /// Pyth price account of the market's circuit breaker, or the default pubkey for none
+    pub oracle: Pubkey,
+    
+    /// Maximum distance of an order's price from the oracle price in basis points
+    pub oracle_band_bps: u16,
+    
+    /// Maximum age of the oracle price in slots before the market is halted
+    pub oracle_max_staleness_slots: u64,
+    
+    /// Exponent of one unit of the market's limit prices in the oracle's price units
+    pub oracle_price_expo: i32,
+    
+    /// Sequence number of the next fill logged by the market
+    pub fill_seq_num: u64,
+    
+    /// Decimals of the base mint
+    pub base_decimals: u8,
+    
+    /// Decimals of the quote mint
+    pub quote_decimals: u8,
+    
+    /// Whether the market was created trustless, accepting only mints without a freeze authority
+    pub trustless: bool,
+    
+    /// Bump of the market signer PDA that owns the escrow vaults
+    pub signer_bump: u8,
+    
+    /// Base token amount of one base lot; order quantities are in base lots
+    pub base_lot_size: u64,
+    
+    /// Quote token amount of one quote lot; limit prices are in quote lots per base lot
+    pub quote_lot_size: u64,
+    
+    /// Minimum notional of a new order in quote tokens (0 for none)
+    pub min_quote_notional: u64,
+    
+    /// Total free base tokens deposited in owners' OpenOrders accounts
+    pub base_free_total: u64,
+    
+    /// Total free quote tokens deposited in owners' OpenOrders accounts
+    pub quote_free_total: u64,
+    
+    /// Highest resting bid price (0 when there are no bids); read it through `best_bid_price`,
+    /// as it is stale while `MARKET_FLAG_BEST_BID_STALE` is set
+    pub best_bid: u64,
+    
+    /// Lowest resting ask price (0 when there are no asks); read it through `best_ask_price`,
+    /// as it is stale while `MARKET_FLAG_BEST_ASK_STALE` is set
+    pub best_ask: u64,
+    
+    /// Price of the last settled fill (0 before the first fill)
+    pub last_price: u64,
+    
+    /// Slot of the last settled fill
+    pub last_trade_slot: u64,
+    
+    /// Cumulative base tokens traded in settled fills
+    pub base_volume: u128,
+    
+    /// Cumulative quote tokens traded in settled fills
+    pub quote_volume: u128,
+    
+    /// Quote tokens traded in each hour of the rolling window, indexed by hour modulo
+    /// `VOLUME_BUCKETS`
+    pub volume_buckets: [u64; 24],
+    
+    /// Hour, counted from the Unix epoch, of the most recent volume bucket
+    pub volume_bucket_hour: u64,
+    
+    /// Whether the market has a TWAP oracle that every settlement must update
+    pub twap_oracle_enabled: bool,
+    
+    /// Token program of the base mint, SPL Token or Token-2022
+    pub base_token_program: Pubkey,
+    
+    /// Token program of the quote mint, SPL Token or Token-2022
+    pub quote_token_program: Pubkey,
+    
+    /// Layout version of the account, `ACCOUNT_VERSION` once created or migrated
+    pub version: u8,
+    
+    /// Bitfield of per-market feature toggles (`MARKET_FLAG_*`), carved out of the reserved
+    /// space; every bit is zero on markets created or migrated before the feature existed
+    pub flags: u64,
+    
+    /// Quote tokens paid into the fee vault by settlements and firm quotes, net of maker rebates
+    /// and referral shares
+    pub fees_accrued: u64,
+    
+    /// Quote tokens swept out of the fee vault
+    pub fees_swept: u64,
+    
+    /// Quote tokens paid to referrers as their share of settlement fees
+    pub referral_fees_paid: u64,
+    
+    /// Quote token account of the protocol treasury receiving its share of swept fees
+    pub treasury_fee_account: Pubkey,
+    
+    /// Share of swept fees paid to the protocol treasury, in basis points of the fees left
+    /// after the stakers' share
+    pub treasury_fee_share_bps: u16,
+    
+    /// Quote token account of the market creator receiving its share of swept fees
+    pub creator_fee_account: Pubkey,
+    
+    /// Share of swept fees paid to the market creator, in basis points of the fees left after
+    /// the stakers' share
+    pub creator_fee_share_bps: u16,
+    
+    /// Taker fee of a calm market while dynamic fees are on, in basis points; the fee rises
+    /// with realized volatility up to `taker_fee_bps`, which is what orders lock
+    pub dynamic_fee_min_bps: u16,
+    
+    /// Realized volatility per fill, in basis points, at which the dynamic taker fee reaches
+    /// `taker_fee_bps`
+    pub dynamic_fee_volatility_bps: u16,
+    
+    /// Number of fills the realized volatility estimate averages over
+    pub volatility_window: u16,
+    
+    /// Moving average of the squared returns between consecutive fill prices, in squared basis
+    /// points, over about the last `volatility_window` fills
+    pub realized_variance: u64,
+    
+    /// Zeroed bytes that future fields are carved out of
+    #[padding]
+    pub reserved: [u8; 14],
+}
+
+impl Market {
+    /// Size of a market account created before layout versioning, upgraded by MigrateAccount
+    pub const LEGACY_LEN: usize = Self::LEN - 1 - 8 - 8 - 8 - 8 - 32 - 2 - 32 - 2 - 2 - 2 - 2 - 8 - MARKET_RESERVED_LEN;
+    
+    /// Whether a `MARKET_FLAG_*` flag is set
+    pub fn has_flag(&self, flag: u64) -> bool {
+        self.flags & flag != 0
+    }
+    
+    /// Find the market PDA of a mint pair and seed; seed 0 is the canonical market of the pair
+    pub fn find_address(program_id: &Pubkey, base_mint: &Pubkey, quote_mint: &Pubkey, seed: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[MARKET_SEED, base_mint.as_ref(), quote_mint.as_ref(), &seed.to_le_bytes()],
+            program_id,
+        )
+    }
+    
+    /// Find the fee vault PDA of a market
+    pub fn find_fee_vault_address(program_id: &Pubkey, market: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[FEE_VAULT_SEED, market.as_ref()], program_id)
+    }
+    
+    /// Find the escrow vault PDA of a market holding the base tokens locked by sell orders
+    pub fn find_base_vault_address(program_id: &Pubkey, market: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[BASE_VAULT_SEED, market.as_ref()], program_id)
+    }
+    
+    /// Find the escrow vault PDA of a market holding the quote tokens locked by buy orders
+    pub fn find_quote_vault_address(program_id: &Pubkey, market: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[QUOTE_VAULT_SEED, market.as_ref()], program_id)
+    }
+    
+    /// Find the escrow vault PDA holding the funds an order on a side locks
+    pub fn find_escrow_vault_address(program_id: &Pubkey, market: &Pubkey, is_buy: bool) -> (Pubkey, u8) {
+        if is_buy {
+            Self::find_quote_vault_address(program_id, market)
+        } else {
+            Self::find_base_vault_address(program_id, market)
+        }
+    }
+    
+    /// Find the market signer PDA, seeded by the market key alone, that owns the escrow vaults
+    pub fn find_signer_address(program_id: &Pubkey, market: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[market.as_ref()], program_id)
+    }
+    
+    /// Market signer PDA derived from the stored bump
+    pub fn signer_address(&self, program_id: &Pubkey, market: &Pubkey) -> Result<Pubkey, ProgramError> {
+        Pubkey::create_program_address(&[market.as_ref(), &[self.signer_bump]], program_id)
+            .map_err(|_| ProgramError::InvalidSeeds)
+    }
+    
+    /// Find the insurance fund PDA of a market
+    pub fn find_insurance_fund_address(program_id: &Pubkey, market: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[INSURANCE_FUND_SEED, market.as_ref()], program_id)
+    }
+    
+
+    /// Find the RFQ authority PDA of a market
+    pub fn find_rfq_authority_address(program_id: &Pubkey, market: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[RFQ_AUTHORITY_SEED, market.as_ref()], program_id)
+    }
+    
+    /// Base token amount of a quantity in base lots
+    pub fn base_lots_to_native(&self, base_lots: u64) -> Result<u64, ProgramError> {
+        base_lots.checked_mul(self.base_lot_size).ok_or(ProgramError::ArithmeticOverflow)
+    }
+    
+    /// Whole base lots in a base token amount, rounding down
+    pub fn native_to_base_lots(&self, amount: u64) -> u64 {
+        amount.checked_div(self.base_lot_size).unwrap_or(0)
+    }
+    
+    /// Quote token amount of a number of quote lots
+    pub fn quote_lots_to_native(&self, quote_lots: u64) -> Result<u64, ProgramError> {
+        quote_lots.checked_mul(self.quote_lot_size).ok_or(ProgramError::ArithmeticOverflow)
+    }
+    
+    /// Whole quote lots in a quote token amount, rounding down
+    pub fn native_to_quote_lots(&self, amount: u64) -> u64 {
+        amount.checked_div(self.quote_lot_size).unwrap_or(0)
+    }
+    
+    /// Quote token amount of a quantity in base lots at a price in quote lots per base lot
+    pub fn notional(&self, price: u64, base_lots: u64) -> Result<u64, ProgramError> {
+        let quote_lots = price as u128 * base_lots as u128;
+        quote_lots
+            .checked_mul(self.quote_lot_size as u128)
+            .and_then(|amount| u64::try_from(amount).ok())
+            .ok_or(ProgramError::ArithmeticOverflow)
+    }
+    
+    /// Quote tokens per base token a taker pays (buying) or receives (selling) at a book price
+    /// once the taker fee is included, as a numerator and denominator
+    pub fn taker_price(&self, is_buy: bool, price: u64) -> Result<(u128, u128), ProgramError> {
+        let fee_factor = if is_buy {
+            math::BPS_DENOMINATOR + self.taker_fee_bps as u64
+        } else {
+            math::BPS_DENOMINATOR.saturating_sub(self.taker_fee_bps as u64)
+        };
+        let numerator = (price as u128 * self.quote_lot_size as u128)
+            .checked_mul(fee_factor as u128)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        Ok((numerator, self.base_lot_size as u128 * math::BPS_DENOMINATOR as u128))
+    }
+    
+    /// Calculate the taker fee for a trade
+    ///
+    /// The fee rounds up, so splitting a trade into smaller fills never lowers the fee paid.
+    pub fn calculate_taker_fee(&self, trade_value: u64) -> Result<u64, ProgramError> {
+        // Calculate fee based on taker fee rate
+        math::bps_of(trade_value, self.taker_fee_bps as u64, Rounding::Up)
+    }
+    
+    /// Calculate the maker fee for a trade; a negative fee is a rebate owed to the maker
+    pub fn calculate_maker_fee(&self, trade_value: u64) -> Result<i64, ProgramError> {
+        // Calculate the fee magnitude, rounding fees up and rebates down
+        let rounding = if self.maker_fee_bps < 0 {
+            Rounding::Down
+        } else {
+            Rounding::Up
+        };
+        let magnitude = math::bps_of(trade_value, self.maker_fee_bps.unsigned_abs() as u64, rounding)?;
+        let magnitude = i64::try_from(magnitude).map_err(|_| ProgramError::ArithmeticOverflow)?;
+        
+        Ok(if self.maker_fee_bps < 0 { -magnitude } else { magnitude })
+    }
+    
+    /// Add quantity resting at a price to the book totals
+    pub fn add_resting(&mut self, is_buy: bool, price: u64, quantity: u64) -> Result<(), ProgramError> {
+        let notional = price as u128 * quantity as u128;
+        let (base_total, notional_total) = if is_buy {
+            (self.bid_base_total, self.bid_notional)
+        } else {
+            (self.ask_base_total, self.ask_notional)
+        };
+        let base_total = base_total
+            .checked_add(quantity)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        let notional_total = notional_total
+            .checked_add(notional)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        
+        // A new order can only improve the best price of its side. A stale best price bounds
+        // the resting orders, so a new order at or through it is the best again
+        if is_buy {
+            self.bid_base_total = base_total;
+            self.bid_notional = notional_total;
+            if price >= self.best_bid {
+                self.best_bid = price;
+                self.flags &= !MARKET_FLAG_BEST_BID_STALE;
+            }
+        } else {
+            self.ask_base_total = base_total;
+            self.ask_notional = notional_total;
+            if self.best_ask == 0 || price <= self.best_ask {
+                self.best_ask = price;
+                self.flags &= !MARKET_FLAG_BEST_ASK_STALE;
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Remove quantity resting at a price from the book totals; `leaves_book` is whether the
+    /// order the quantity belongs to leaves the book with it
+    ///
+    /// The best price of a side is cleared once the side is empty. When an order at the best
+    /// price leaves a side that keeps other orders, the next best price is only known after
+    /// scanning the open orders, so the best price is marked stale until CommitOpenOrdersRoot
+    /// recomputes it or a new order at or through it arrives.
+    pub fn remove_resting(&mut self, is_buy: bool, price: u64, quantity: u64, leaves_book: bool) {
+        let notional = price as u128 * quantity as u128;
+        if is_buy {
+            self.bid_base_total = self.bid_base_total.saturating_sub(quantity);
+            self.bid_notional = self.bid_notional.saturating_sub(notional);
+            if self.bid_base_total == 0 {
+                self.best_bid = 0;
+                self.flags &= !MARKET_FLAG_BEST_BID_STALE;
+            } else if leaves_book && price >= self.best_bid {
+                self.flags |= MARKET_FLAG_BEST_BID_STALE;
+            }
+        } else {
+            self.ask_base_total = self.ask_base_total.saturating_sub(quantity);
+            self.ask_notional = self.ask_notional.saturating_sub(notional);
+            if self.ask_base_total == 0 {
+                self.best_ask = 0;
+                self.flags &= !MARKET_FLAG_BEST_ASK_STALE;
+            } else if leaves_book && price <= self.best_ask {
+                self.flags |= MARKET_FLAG_BEST_ASK_STALE;
+            }
+        }
+    }
+    
+    /// Highest resting bid price, if there are bids and it is not stale
+    pub fn best_bid_price(&self) -> Option<u64> {
+        if self.best_bid == 0 || self.has_flag(MARKET_FLAG_BEST_BID_STALE) {
+            return None;
+        }
+        Some(self.best_bid)
+    }
+    
+    /// Lowest resting ask price, if there are asks and it is not stale
+    pub fn best_ask_price(&self) -> Option<u64> {
+        if self.best_ask == 0 || self.has_flag(MARKET_FLAG_BEST_ASK_STALE) {
+            return None;
+        }
+        Some(self.best_ask)
+    }
+    
+    /// Difference between the best ask and the best bid, if both sides have orders and
+    /// neither best price is stale
+    pub fn spread(&self) -> Option<u64> {
+        self.best_ask_price()?.checked_sub(self.best_bid_price()?)
+    }
+    
+    /// Total resting base quantity on both sides of the book
+    pub fn open_interest(&self) -> u64 {
+        self.bid_base_total.saturating_add(self.ask_base_total)
+    }
+    
+    /// Record the price of a settled fill, weighing its return from the last fill price into
+    /// the realized variance while dynamic fees are on
+    pub fn record_fill_price(&mut self, price: u64) {
+        if self.has_flag(MARKET_FLAG_DYNAMIC_FEES) && self.last_price > 0 {
+            let return_bps = (price.abs_diff(self.last_price) as u128 * 10000) / self.last_price as u128;
+            let squared_return = return_bps.saturating_mul(return_bps).min(u64::MAX as u128) as i128;
+            let variance = self.realized_variance as i128;
+            let window = self.volatility_window.max(1) as i128;
+            self.realized_variance = (variance + (squared_return - variance) / window) as u64;
+        }
+        self.last_price = price;
+    }
+    
+    /// Realized volatility per fill in basis points, the square root of the realized variance
+    pub fn realized_volatility_bps(&self) -> u64 {
+        math::isqrt(self.realized_variance as u128) as u64
+    }
+    
+    /// Taker fee rate charged on book fills: `taker_fee_bps`, or while dynamic fees are on, a
+    /// rate between `dynamic_fee_min_bps` and `taker_fee_bps` rising linearly with realized
+    /// volatility up to `dynamic_fee_volatility_bps`
+    ///
+    /// The rate never drops below the maker rebate rate, so the rebate stays funded.
+    pub fn dynamic_taker_fee_bps(&self) -> u16 {
+        if !self.has_flag(MARKET_FLAG_DYNAMIC_FEES) || self.dynamic_fee_volatility_bps == 0 {
+            return self.taker_fee_bps;
+        }
+        let min_fee_bps = self
+            .dynamic_fee_min_bps
+            .max(self.maker_fee_bps.min(0).unsigned_abs())
+            .min(self.taker_fee_bps);
+        let volatility = self.realized_volatility_bps().min(self.dynamic_fee_volatility_bps as u64);
+        let increase = (self.taker_fee_bps - min_fee_bps) as u64 * volatility / self.dynamic_fee_volatility_bps as u64;
+        min_fee_bps + increase as u16
+    }
+    
+    /// Add a fill to the cumulative volume and to the bucket of the hour it settled in
+    pub fn record_volume(&mut self, base_amount: u64, quote_amount: u64, timestamp: u64) {
+        self.base_volume = self.base_volume.saturating_add(base_amount as u128);
+        self.quote_volume = self.quote_volume.saturating_add(quote_amount as u128);
+        
+        // Clear the buckets of the hours without fills since the most recent one
+        let hour = timestamp / VOLUME_BUCKET_SECONDS;
+        if hour > self.volume_bucket_hour {
+            let elapsed = (hour - self.volume_bucket_hour).min(VOLUME_BUCKETS as u64);
+            for passed in 0..elapsed {
+                self.volume_buckets[((hour - passed) % VOLUME_BUCKETS as u64) as usize] = 0;
+            }
+            self.volume_bucket_hour = hour;
+        }
+        
+        // A clock running behind the most recent bucket counts towards that bucket
+        let mut volume_buckets = self.volume_buckets;
+        let bucket = &mut volume_buckets[(self.volume_bucket_hour % VOLUME_BUCKETS as u64) as usize];
+        *bucket = bucket.saturating_add(quote_amount);
+        self.volume_buckets = volume_buckets;
+    }
+    
+    /// Quote tokens traded in the `VOLUME_BUCKETS` hours up to and including the hour of `timestamp`
+    pub fn rolling_quote_volume(&self, timestamp: u64) -> u64 {
+        let hour = timestamp / VOLUME_BUCKET_SECONDS;
+        (0..VOLUME_BUCKETS as u64)
+            .filter(|age| self.volume_bucket_hour >= *age)
+            .map(|age| self.volume_bucket_hour - age)
+            .filter(|bucket_hour| bucket_hour + VOLUME_BUCKETS as u64 > hour)
+            .map(|bucket_hour| self.volume_buckets[(bucket_hour % VOLUME_BUCKETS as u64) as usize])
+            .fold(0, u64::saturating_add)
+    }
+    
+    /// Imbalance of resting base quantity in basis points, from -10000 (asks only) to 10000 (bids only)
+    pub fn book_imbalance_bps(&self) -> i64 {
+        let total = self.bid_base_total as i128 + self.ask_base_total as i128;
+        if total == 0 {
+            return 0;
+        }
+        ((self.bid_base_total as i128 - self.ask_base_total as i128) * 10000 / total) as i64
+    }
+    
+    /// Whether the market has an oracle circuit breaker
+    pub fn has_oracle(&self) -> bool {
+        self.oracle != Pubkey::default()
+    }
+    
+    /// Mint of one side: quote for buys, base for sells
+    pub fn mint(&self, is_quote: bool) -> &Pubkey {
+        if is_quote {
+            &self.quote_mint
+        } else {
+            &self.base_mint
+        }
+    }
+    
+    /// Token program of the mint of one side
+    pub fn token_program(&self, is_quote: bool) -> &Pubkey {
+        if is_quote {
+            &self.quote_token_program
+        } else {
+            &self.base_token_program
+        }
+    }
+    
+    /// Decimals of the mint of one side
+    pub fn decimals(&self, is_quote: bool) -> u8 {
+        if is_quote {
+            self.quote_decimals
+        } else {
+            self.base_decimals
+        }
+    }
+    
+    /// Whether either mint is a Token-2022 mint
+    pub fn uses_token_2022(&self) -> bool {
+        self.base_token_program == spl_token_2022::id() || self.quote_token_program == spl_token_2022::id()
+    }
+    
+    /// Calculate the part of a fee owed to a referrer, rounding down
+    pub fn calculate_referral_fee(&self, fee: u64) -> Result<u64, ProgramError> {
+        math::bps_of(fee, self.referral_share_bps as u64, Rounding::Down)
+    }
+    
+    /// Tokens an order locks in escrow for a quantity in base lots
+    ///
+    /// Sells lock the base quantity. Buys lock the notional at the limit price plus the taker
+    /// fee on each lot of it, so the lock stays linear in the quantity and any part of it
+    /// covers the fills, reductions and cancels of that part.
+    pub fn escrow_amount(&self, is_buy: bool, limit_price: u64, quantity: u64) -> Result<u64, ProgramError> {
+        if !is_buy {
+            return self.base_lots_to_native(quantity);
+        }
+        let fee = math::bps_of(limit_price, self.taker_fee_bps as u64, Rounding::Up)?;
+        let unit_price = limit_price.checked_add(fee).ok_or(ProgramError::ArithmeticOverflow)?;
+        self.notional(unit_price, quantity)
+    }
+    
+    /// Tokens the escrow vault of one side owes: the locks of resting orders plus the free
+    /// balances of that mint
+    ///
+    /// Buys round their taker fee up per lot of their limit price, so the quote side adds one
+    /// quote lot per resting base lot to the fee on the book's notional; the result never falls
+    /// below what the orders locked.
+    pub fn escrow_obligation(&self, is_quote: bool) -> Result<u64, ProgramError> {
+        if !is_quote {
+            return self
+                .base_lots_to_native(self.ask_base_total)?
+                .checked_add(self.base_free_total)
+                .ok_or(ProgramError::ArithmeticOverflow);
+        }
+        let fee = self
+            .bid_notional
+            .checked_mul(self.taker_fee_bps as u128)
+            .map(|fee| fee.div_ceil(10000))
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        self.bid_notional
+            .checked_add(fee)
+            .and_then(|lots| lots.checked_add(self.bid_base_total as u128))
+            .and_then(|lots| lots.checked_mul(self.quote_lot_size as u128))
+            .and_then(|amount| amount.checked_add(self.quote_free_total as u128))
+            .and_then(|amount| u64::try_from(amount).ok())
+            .ok_or(ProgramError::ArithmeticOverflow)
+    }
+    
+    /// Split the quote side of a fill between the maker, the fee vault and a referrer
+    ///
+    /// The taker pays the quote amount plus the taker fee at `dynamic_taker_fee_bps`, and every
+    /// token it pays ends up with exactly one of the recipients.
+    pub fn settlement_amounts(&self, quote_amount: u64, has_referrer: bool) -> Result<SettlementAmounts, ProgramError> {
+        self.discounted_settlement_amounts(quote_amount, has_referrer, 0)
+    }
+    
+    /// Split the quote side of a fill as `settlement_amounts` does, waiving `discount_bps` of
+    /// the taker fee, rounded down
+    ///
+    /// The discounted taker fee never drops below a maker rebate, so the rebate stays funded.
+    pub fn discounted_settlement_amounts(
+        &self,
+        quote_amount: u64,
+        has_referrer: bool,
+        discount_bps: u16,
+    ) -> Result<SettlementAmounts, ProgramError> {
+        let maker_fee = self.calculate_maker_fee(quote_amount)?;
+        let taker_fee = math::bps_of(quote_amount, self.dynamic_taker_fee_bps() as u64, Rounding::Up)?;
+        let discount = math::bps_of(taker_fee, discount_bps as u64, Rounding::Down)?;
+        let rebate = maker_fee.min(0).unsigned_abs().min(taker_fee);
+        let taker_fee = (taker_fee - discount).max(rebate);
+
+        // The maker fee is deducted from the maker's proceeds, a rebate is added
+        let maker_quote_amount = u64::try_from(quote_amount as i128 - maker_fee as i128)
+            .map_err(|_| ProgramError::ArithmeticOverflow)?;
+
+        // Net fee kept after paying any maker rebate, less the referrer's share
+        let net_fee = u64::try_from(taker_fee as i128 + maker_fee as i128)
+            .map_err(|_| ProgramError::ArithmeticOverflow)?;
+        let referral_fee = if has_referrer {
+            self.calculate_referral_fee(net_fee)?
+        } else {
+            0
+        };
+
+        Ok(SettlementAmounts {
+            taker_fee,
+            maker_fee,
+            maker_quote_amount,
+            vault_fee: net_fee - referral_fee,
+            referral_fee,
+        })
+    }
+}
+
+/// Quote token movements of a fill
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SettlementAmounts {
+    /// Fee charged to the taker on top of the quote amount
+    pub taker_fee: u64,
+    /// Fee charged to the maker, negative for a rebate
+    pub maker_fee: i64,
+    /// Quote tokens paid to the maker
+    pub maker_quote_amount: u64,
+    /// Quote tokens paid to the market's fee vault
+    pub vault_fee: u64,
+    /// Quote tokens paid to the taker's referrer
+    pub referral_fee: u64,
+}
+
+impl SettlementAmounts {
+    /// Quote tokens paid by the taker
+    pub fn taker_quote_amount(&self) -> Result<u64, ProgramError> {
+        self.maker_quote_amount
+            .checked_add(self.vault_fee)
+            .and_then(|amount| amount.checked_add(self.referral_fee))
+            .ok_or(ProgramError::ArithmeticOverflow)
+    }
+}
+
+impl Sealed for Market {}
+
+impl IsInitialized for Market {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for Market {
+    const LEN: usize = 1 + 32 + 32 + 32 + 8 + 8 + 2 + 2 + 8 + 8 + 8 + 1 + 32 + 8 + 8 + 2 + 32 + 1 + 8 + 8 + 16 + 16 + 1 + 32 + 2 + 8 + 4 + 8 + 1 + 1 + 1 + 1 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 16 + 16 + 8 * VOLUME_BUCKETS + 8 + 1 + 32 + 32 + 1 + 8 + 8 + 8 + 8 + 32 + 2 + 32 + 2 + 2 + 2 + 2 + 8 + MARKET_RESERVED_LEN;
+    
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst[..Self::LEN].copy_from_slice(bytemuck::bytes_of(self));
+    }
+    
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        load_zero_copy(src).copied()
+    }
+}
+
+const _: () = assert!(std::mem::size_of::<Market>() == Market::LEN);
+
+/// Market trading status enum
+#[derive(BorshSerialize, BorshDeserialize, CheckedBitPattern, NoUninit, Debug, Clone, Copy, PartialEq)]
+#[repr(u8)]
+pub enum MarketStatus {
+    /// Orders are placed and matched normally
+    Active,
+    /// No new orders; matched trades still settle and orders can be cancelled
+    CancelOnly,
+    /// No new orders and no settlement; orders can still be cancelled
+    Paused,
+}
+
+/// Instruction introspection guard enum
+#[derive(BorshSerialize, BorshDeserialize, CheckedBitPattern, NoUninit, Debug, Clone, Copy, PartialEq)]
+#[repr(u8)]
+pub enum InstructionGuard {
+    /// No introspection checks
+    Disabled,
+    /// Reject invocations made through CPI
+    TopLevelOnly,
+    /// Reject CPI and any other instruction for this program in the same transaction
+    Isolated,
+}
+
+/// Registry of every market created by the program
+///
+/// The registry lives at the PDA `["market_registry"]`. This header is followed by one
+/// `MarketRegistryEntry` per market in creation order; the account grows by one entry for
+/// each market initialized.
+#[derive(BorshSerialize, BorshDeserialize, ShankAccount, Debug, Clone, Default)]
+pub struct MarketRegistry {
+    /// Is this registry initialized
+    pub is_initialized: bool,
+    
+    /// Number of markets recorded
+    pub num_markets: u64,
+}
+
+impl MarketRegistry {
+    /// Find the market registry PDA
+    pub fn find_address(program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[MARKET_REGISTRY_SEED], program_id)
+    }
+
+    /// Account size of a registry holding `num_markets` entries
+    pub fn account_len(num_markets: u64) -> usize {
+        Self::LEN + num_markets as usize * MarketRegistryEntry::LEN
+    }
+
+    /// Unpack the entries following the header of a registry account
+    pub fn unpack_entries(data: &[u8]) -> Result<Vec<MarketRegistryEntry>, ProgramError> {
+        let registry = Self::unpack_from_slice(data.get(..Self::LEN).ok_or(ProgramError::InvalidAccountData)?)?;
+        let entries = data
+            .get(Self::LEN..Self::account_len(registry.num_markets))
+            .ok_or(ProgramError::InvalidAccountData)?;
+        entries
+            .chunks_exact(MarketRegistryEntry::LEN)
+            .map(MarketRegistryEntry::unpack_from_slice)
+            .collect()
+    }
+}
+
+impl Sealed for MarketRegistry {}
+
+impl IsInitialized for MarketRegistry {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for MarketRegistry {
+    const LEN: usize = 1 + 8;
+    
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap();
+    }
+    
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Self::try_from_slice(src).map_err(|_| ProgramError::InvalidAccountData)
+    }
+}
+
+/// Market recorded in the market registry
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct MarketRegistryEntry {
+    /// Market address
+    pub market: Pubkey,
+    
+    /// Base token mint
+    pub base_mint: Pubkey,
+    
+    /// Quote token mint
+    pub quote_mint: Pubkey,
+    
+    /// Slot the market was created in
+    pub created_slot: u64,
+}
+
+impl Sealed for MarketRegistryEntry {}
+
+impl Pack for MarketRegistryEntry {
+    const LEN: usize = 32 + 32 + 32 + 8;
+    
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap();
+    }
+    
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Self::try_from_slice(src).map_err(|_| ProgramError::InvalidAccountData)
+    }
+}
+
+/// Order state
+/// Zero-copy, see `Market`.
+#[derive(CheckedBitPattern, NoUninit, ShankAccount, Debug, Clone, Copy)]
+#[repr(C, packed)]
+pub struct Order {
+    /// Is this order initialized
+    pub is_initialized: bool,
+    
+    /// Order ID
+    pub order_id: u64,
+    
+    /// Owner of the order
+    pub owner: Pubkey,
+    
+    /// Market this order belongs to
+    pub market: Pubkey,
+    
+    /// Is this a buy order
+    pub is_buy: bool,
+    
+    /// Limit price in quote lots per base lot
+    pub limit_price: u64,
+    
+    /// Original quantity in base lots
+    pub original_quantity: u64,
+    
+    /// Remaining quantity in base lots
+    pub remaining_quantity: u64,
+    
+    /// Creation timestamp
+    pub creation_timestamp: u64,
+    
+    /// Order account cancelled together with this one (one-cancels-other), default if unlinked
+    pub linked_order: Pubkey,
+    
+    /// Size of each displayed tranche of an iceberg order, 0 if the full size is displayed
+    pub display_quantity: u64,
+    
+    /// Part of the remaining quantity currently displayed on the book
+    pub visible_quantity: u64,
+    
+    /// Quote token account receiving the referral share of fees, default if none
+    pub referrer: Pubkey,
+    
+    /// Memo attached by the owner, zero-padded; all zeros if none
+    pub memo: [u8; 32],
+    
+    /// Layout version of the account, `ACCOUNT_VERSION` once created or migrated
+    pub version: u8,
+    
+    /// Zeroed bytes that future fields are carved out of
+    #[padding]
+    pub reserved: [u8; 64],
+}
+
+impl Order {
+    /// Size of an order account created before layout versioning, upgraded by MigrateAccount
+    pub const LEGACY_LEN: usize = Self::LEN - 1 - ORDER_RESERVED_LEN;
+    
+    /// Byte offset of `owner` in the packed layout
+    pub const OWNER_OFFSET: usize = 1 + 8;
+    
+    /// Byte offset of `market` in the packed layout
+    pub const MARKET_OFFSET: usize = 1 + 8 + 32;
+    
+    /// Byte offset of `is_buy` in the packed layout
+    pub const IS_BUY_OFFSET: usize = 1 + 8 + 32 + 32;
+    
+    /// Check if the order is linked to another order
+    pub fn is_linked(&self) -> bool {
+        self.linked_order != Pubkey::default()
+    }
+    
+    /// Check if the order was placed through a referrer
+    pub fn has_referrer(&self) -> bool {
+        self.referrer != Pubkey::default()
+    }
+    
+    /// Check if only a tranche of the order is displayed on the book
+    pub fn is_iceberg(&self) -> bool {
+        self.display_quantity != 0
+    }
+    
+    /// Remaining quantity not displayed on the book
+    pub fn hidden_quantity(&self) -> u64 {
+        self.remaining_quantity.saturating_sub(self.visible_quantity)
+    }
+    
+    /// Memo attached by the owner, empty if none
+    pub fn memo(&self) -> &str {
+        let len = self.memo.iter().rposition(|byte| *byte != 0).map_or(0, |last| last + 1);
+        std::str::from_utf8(&self.memo[..len]).unwrap_or_default()
+    }
+    
+    /// Fill part of the displayed quantity
+    ///
+    /// When the displayed tranche of an iceberg order is used up the next tranche
+    /// is revealed and the order moves to the back of the queue at `timestamp`.
+    pub fn fill(&mut self, quantity: u64, timestamp: u64) -> Result<(), ProgramError> {
+        if quantity > self.visible_quantity {
+            return Err(ProgramError::InvalidArgument);
+        }
+        
+        self.remaining_quantity -= quantity;
+        self.visible_quantity -= quantity;
+        
+        if self.visible_quantity == 0 && self.remaining_quantity > 0 {
+            self.visible_quantity = self.display_quantity.min(self.remaining_quantity);
+            self.creation_timestamp = timestamp;
+        }
+        
+        Ok(())
+    }
+}
+
+impl Sealed for Order {}
+
+impl IsInitialized for Order {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for Order {
+    const LEN: usize = 1 + 8 + 32 + 32 + 1 + 8 + 8 + 8 + 8 + 32 + 8 + 8 + 32 + MAX_ORDER_MEMO_LEN + 1 + ORDER_RESERVED_LEN;
+    
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst[..Self::LEN].copy_from_slice(bytemuck::bytes_of(self));
+    }
+    
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        load_zero_copy(src).copied()
+    }
+}
+
+const _: () = assert!(std::mem::size_of::<Order>() == Order::LEN);
+
+/// View the start of an account's data as a zero-copy account, checking its bool and enum fields
+pub fn load_zero_copy<T: CheckedBitPattern + Pack>(data: &[u8]) -> Result<&T, ProgramError> {
+    let data = data.get(..T::LEN).ok_or(ProgramError::InvalidAccountData)?;
+    bytemuck::checked::try_from_bytes(data).map_err(|_| ProgramError::InvalidAccountData)
+}
+
+/// Mutable view of the start of an account's data as a zero-copy account
+pub fn load_zero_copy_mut<T: CheckedBitPattern + NoUninit + Pack>(data: &mut [u8]) -> Result<&mut T, ProgramError> {
+    let data = data.get_mut(..T::LEN).ok_or(ProgramError::InvalidAccountData)?;
+    bytemuck::checked::try_from_bytes_mut(data).map_err(|_| ProgramError::InvalidAccountData)
+}
+
+/// Order book side enum
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub enum OrderBookSide {
+    /// Bids (buy orders)
+    Bids,
+    /// Asks (sell orders)
+    Asks,
+}
+
+/// Trade information
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct Trade {
+    /// Maker order ID
+    pub maker_order_id: u64,
+    
+    /// Taker order ID
+    pub taker_order_id: u64,
+    
+    /// Maker
+    pub maker: Pubkey,
+    
+    /// Taker
+    pub taker: Pubkey,
+    
+    /// Trade price
+    pub price: u64,
+    
+    /// Trade quantity
+    pub quantity: u64,
+    
+    /// Trade side (true if taker is buyer)
+    pub taker_side: bool,
+    
+    /// Timestamp
+    pub timestamp: u64,
+}
+
+/// Record of a settlement, kept so the trade can be busted within the window
+#[derive(BorshSerialize, BorshDeserialize, ShankAccount, Debug, Clone)]
+pub struct SettlementRecord {
+    /// Is this record initialized
+    pub is_initialized: bool,
+    
+    /// Market the trade settled on
+    pub market: Pubkey,
+    
+    /// Slot of the settlement
+    pub slot: u64,
+    
+    /// Taker base token account
+    pub taker_base_account: Pubkey,
+    
+    /// Taker quote token account
+    pub taker_quote_account: Pubkey,
+    
+    /// Maker base token account
+    pub maker_base_account: Pubkey,
+    
+    /// Maker quote token account
+    pub maker_quote_account: Pubkey,
+    
+    /// Base token amount the taker received
+    pub base_amount: u64,
+    
+    /// Quote token amount the maker received
+    pub maker_quote_amount: u64,
+    
+    /// Whether the trade has been busted
+    pub is_busted: bool,
+}
+
+impl Sealed for SettlementRecord {}
+
+impl IsInitialized for SettlementRecord {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for SettlementRecord {
+    const LEN: usize = 1 + 32 + 8 + 32 + 32 + 32 + 32 + 8 + 8 + 1;
+    
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap();
+    }
+    
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Self::try_from_slice(src).map_err(|_| ProgramError::InvalidAccountData)
+    }
+}
+
+/// Whitelist entry allowing an owner to place orders on a permissioned market
+///
+/// Entries live at the PDA `["whitelist", market, owner]` and are issued by the market authority,
+/// so an entry is the authority's attestation that the owner may trade.
+#[derive(BorshSerialize, BorshDeserialize, ShankAccount, Debug, Clone)]
+pub struct WhitelistEntry {
+    /// Is this entry initialized
+    pub is_initialized: bool,
+    
+    /// Market the entry belongs to
+    pub market: Pubkey,
+    
+    /// Whitelisted order owner
+    pub owner: Pubkey,
+}
+
+impl WhitelistEntry {
+    /// Find the whitelist entry PDA of an owner on a market
+    pub fn find_address(program_id: &Pubkey, market: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[WHITELIST_SEED, market.as_ref(), owner.as_ref()], program_id)
+    }
+}
+
+impl Sealed for WhitelistEntry {}
+
+impl IsInitialized for WhitelistEntry {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for WhitelistEntry {
+    const LEN: usize = 1 + 32 + 32;
+    
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap();
+    }
+    
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Self::try_from_slice(src).map_err(|_| ProgramError::InvalidAccountData)
+    }
+}
+
+/// Delegation letting a secondary key place and cancel orders for an owner on a market
+///
+/// Delegations live at the PDA `["delegation", market, owner]`, so an owner has at most one
+/// delegate per market. A delegate never moves funds anywhere but the owner's orders and token
+/// accounts: order funds are locked through the market signer, which the owner approves as
+/// token delegate of their accounts, and cancels refund the owner.
+#[derive(BorshSerialize, BorshDeserialize, ShankAccount, Debug, Clone, PartialEq)]
+pub struct Delegation {
+    /// Is this delegation initialized
+    pub is_initialized: bool,
+    
+    /// Market the delegation belongs to
+    pub market: Pubkey,
+    
+    /// Owner the delegate trades for
+    pub owner: Pubkey,
+    
+    /// Key allowed to place and cancel the owner's orders
+    pub delegate: Pubkey,
+    
+    /// First slot at which the delegation no longer applies
+    pub expiry_slot: u64,
+}
+
+impl Delegation {
+    /// Find the delegation PDA of an owner on a market
+    pub fn find_address(program_id: &Pubkey, market: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[DELEGATION_SEED, market.as_ref(), owner.as_ref()], program_id)
+    }
+    
+    /// Whether the delegate may still trade at a slot
+    pub fn is_active(&self, slot: u64) -> bool {
+        slot < self.expiry_slot
+    }
+}
+
+impl Sealed for Delegation {}
+
+impl IsInitialized for Delegation {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for Delegation {
+    const LEN: usize = 1 + 32 + 32 + 32 + 8;
+    
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap();
+    }
+    
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Self::try_from_slice(src).map_err(|_| ProgramError::InvalidAccountData)
+    }
+}
+
+/// Free balances an owner has deposited on a market to fund orders without a token transfer
+///
+/// OpenOrders accounts live at the PDA `["open_orders", market, owner]`. The deposited tokens
+/// sit in the market's escrow vaults alongside the funds locked by orders.
+#[derive(BorshSerialize, BorshDeserialize, ShankAccount, Debug, Clone, PartialEq)]
+pub struct OpenOrders {
+    /// Is this account initialized
+    pub is_initialized: bool,
+    
+    /// Market the balances belong to
+    pub market: Pubkey,
+    
+    /// Owner of the balances
+    pub owner: Pubkey,
+    
+    /// Base tokens available to new sell orders and withdrawals
+    pub base_free: u64,
+    
+    /// Quote tokens available to new buy orders and withdrawals
+    pub quote_free: u64,
+}
+
+impl OpenOrders {
+    /// Find the OpenOrders PDA of an owner on a market
+    pub fn find_address(program_id: &Pubkey, market: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[OPEN_ORDERS_SEED, market.as_ref(), owner.as_ref()], program_id)
+    }
+    
+    /// Free balance of one mint: quote tokens fund buys, base tokens sells
+    pub fn free(&self, is_quote: bool) -> u64 {
+        if is_quote {
+            self.quote_free
+        } else {
+            self.base_free
+        }
+    }
+    
+    /// Mutable free balance of one mint
+    pub fn free_mut(&mut self, is_quote: bool) -> &mut u64 {
+        if is_quote {
+            &mut self.quote_free
+        } else {
+            &mut self.base_free
+        }
+    }
+}
+
+impl Sealed for OpenOrders {}
+
+impl IsInitialized for OpenOrders {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for OpenOrders {
+    const LEN: usize = 1 + 32 + 32 + 8 + 8;
+    
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap();
+    }
+    
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Self::try_from_slice(src).map_err(|_| ProgramError::InvalidAccountData)
+    }
+}
+
+/// Time-weighted prices of a market's settled fills, for other programs to read
+///
+/// TWAP oracles live at the PDA `["twap_oracle", market]`. Each price is weighted by the time
+/// it stood until the next fill, so a single trade at an outlying price moves the averages
+/// only as long as it goes unanswered. The cumulative price wraps around; a TWAP over a window
+/// is the difference of two cumulative prices divided by the seconds between them.
+#[derive(BorshSerialize, BorshDeserialize, ShankAccount, Debug, Clone, PartialEq)]
+pub struct TwapOracle {
+    /// Is this oracle initialized
+    pub is_initialized: bool,
+    
+    /// Market whose fills the oracle tracks
+    pub market: Pubkey,
+    
+    /// Time constant of the exponential moving average in seconds
+    pub ema_period_seconds: u64,
+    
+    /// Price of the last fill
+    pub last_price: u64,
+    
+    /// Unix timestamp of the last fill
+    pub last_update: i64,
+    
+    /// Sum of each price multiplied by the seconds it stood, up to the last fill
+    pub price_cumulative: u128,
+    
+    /// Exponential moving average of the price up to the last fill
+    pub ema_price: u64,
+    
+    /// Number of fills recorded
+    pub fill_count: u64,
+}
+
+impl TwapOracle {
+    /// Find the TWAP oracle PDA of a market
+    pub fn find_address(program_id: &Pubkey, market: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[TWAP_ORACLE_SEED, market.as_ref()], program_id)
+    }
+    
+    /// Seconds from the last fill to `timestamp`, never negative
+    fn elapsed(&self, timestamp: i64) -> u64 {
+        timestamp.saturating_sub(self.last_update).max(0) as u64
+    }
+    
+    /// Cumulative price at `timestamp`, counting the last price as standing until then
+    pub fn price_cumulative_at(&self, timestamp: i64) -> u128 {
+        self.price_cumulative
+            .wrapping_add(self.last_price as u128 * self.elapsed(timestamp) as u128)
+    }
+    
+    /// Exponential moving average at `timestamp`, moved towards the last price for the time it
+    /// has stood
+    pub fn ema_price_at(&self, timestamp: i64) -> u64 {
+        let elapsed = self.elapsed(timestamp) as i128;
+        let ema = self.ema_price as i128;
+        let moved = (self.last_price as i128 - ema) * elapsed / (elapsed + self.ema_period_seconds.max(1) as i128);
+        (ema + moved) as u64
+    }
+    
+    /// Average price between an earlier observation of the cumulative price and `timestamp`
+    pub fn twap_since(&self, price_cumulative: u128, observed_at: i64, timestamp: i64) -> Option<u64> {
+        let seconds = timestamp.checked_sub(observed_at).filter(|seconds| *seconds > 0)?;
+        let average = self.price_cumulative_at(timestamp).wrapping_sub(price_cumulative) / seconds as u128;
+        u64::try_from(average).ok()
+    }
+    
+    /// Record a fill's price at its timestamp
+    ///
+    /// The first fill seeds both averages. A timestamp before the last fill counts as the time
+    /// of the last fill.
+    pub fn record_fill(&mut self, price: u64, timestamp: i64) {
+        if self.fill_count == 0 {
+            self.ema_price = price;
+        } else {
+            let timestamp = timestamp.max(self.last_update);
+            self.price_cumulative = self.price_cumulative_at(timestamp);
+            self.ema_price = self.ema_price_at(timestamp);
+        }
+        self.last_price = price;
+        self.last_update = timestamp.max(self.last_update);
+        self.fill_count = self.fill_count.saturating_add(1);
+    }
+}
+
+impl Sealed for TwapOracle {}
+
+impl IsInitialized for TwapOracle {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for TwapOracle {
+    const LEN: usize = 1 + 32 + 8 + 8 + 8 + 16 + 8 + 8;
+    
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap();
+    }
+    
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Self::try_from_slice(src).map_err(|_| ProgramError::InvalidAccountData)
+    }
+}
+
+/// Fill recorded in a market's event queue
+#[derive(Pod, Zeroable, ShankType, Debug, Clone, Copy, PartialEq)]
+#[repr(C, packed)]
+pub struct FillEvent {
+    /// Sequence number of the fill within the market, as in its `Fill` log
+    pub seq_num: u64,
+    
+    /// Slot of the settlement
+    pub slot: u64,
+    
+    /// Taker order account
+    pub taker_order: Pubkey,
+    
+    /// Maker order account
+    pub maker_order: Pubkey,
+    
+    /// Price of the fill, the maker's limit price
+    pub price: u64,
+    
+    /// Base token amount filled
+    pub base_amount: u64,
+    
+    /// Quote token amount of the fill before fees
+    pub quote_amount: u64,
+}
+
+/// Fixed-capacity ring buffer of a market's settled fills, consumed in order by a crank
+///
+/// Event queues live at the PDA `["event_queue", market]` and are zero-copy like markets.
+/// Events are kept from `head` for `count` slots, wrapping around the buffer. A settlement on a
+/// full queue overwrites the oldest event rather than failing, so consumers detect dropped
+/// events as a gap in the sequence numbers, and ConsumeEvents names the first sequence number
+/// it consumes so that a crank retrying a batch cannot consume events it has not processed.
+#[derive(CheckedBitPattern, NoUninit, ShankAccount, Debug, Clone, Copy)]
+#[repr(C, packed)]
+pub struct EventQueue {
+    /// Is this queue initialized
+    pub is_initialized: bool,
+    
+    /// Market whose fills the queue records
+    pub market: Pubkey,
+    
+    /// Index of the oldest event in the buffer
+    pub head: u64,
+    
+    /// Number of events in the queue
+    pub count: u64,
+    
+    /// Sequence number the next event pushed will carry
+    pub next_seq_num: u64,
+    
+    /// Events overwritten before they were consumed
+    pub dropped: u64,
+    
+    /// Ring buffer of events, `EVENT_QUEUE_CAPACITY` long
+    pub events: [FillEvent; 64],
+}
+
+impl EventQueue {
+    /// Find the event queue PDA of a market
+    pub fn find_address(program_id: &Pubkey, market: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[EVENT_QUEUE_SEED, market.as_ref()], program_id)
+    }
+    
+    /// Sequence number of the oldest event in the queue, or of the next event if it is empty
+    pub fn first_seq_num(&self) -> u64 {
+        self.next_seq_num - self.count
+    }
+    
+    /// Events in the queue, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &FillEvent> {
+        let head = self.head as usize;
+        (0..self.count as usize).map(move |i| &self.events[(head + i) % EVENT_QUEUE_CAPACITY])
+    }
+    
+    /// Append an event, overwriting the oldest one when the queue is full
+    ///
+    /// The event's sequence number becomes the queue's next sequence number, so sequence numbers
+    /// keep increasing across overwrites.
+    pub fn push(&mut self, event: FillEvent) {
+        let tail = (self.head as usize + self.count as usize) % EVENT_QUEUE_CAPACITY;
+        self.events[tail] = event;
+        if self.count as usize == EVENT_QUEUE_CAPACITY {
+            self.head = (self.head + 1) % EVENT_QUEUE_CAPACITY as u64;
+            self.dropped = self.dropped.saturating_add(1);
+        } else {
+            self.count += 1;
+        }
+        self.next_seq_num = event.seq_num.saturating_add(1);
+    }
+    
+    /// Remove up to `limit` of the oldest events, returning how many were removed
+    pub fn pop(&mut self, limit: u64) -> u64 {
+        let consumed = limit.min(self.count);
+        self.head = (self.head + consumed) % EVENT_QUEUE_CAPACITY as u64;
+        self.count -= consumed;
+        consumed
+    }
+}
+
+impl Sealed for EventQueue {}
+
+impl IsInitialized for EventQueue {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for EventQueue {
+    const LEN: usize = 1 + 32 + 8 + 8 + 8 + 8 + (8 + 8 + 32 + 32 + 8 + 8 + 8) * EVENT_QUEUE_CAPACITY;
+    
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst[..Self::LEN].copy_from_slice(bytemuck::bytes_of(self));
+    }
+    
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        load_zero_copy(src).copied()
+    }
+}
+
+const _: () = assert!(std::mem::size_of::<EventQueue>() == EventQueue::LEN);
+
+/// Firm quote signed off-chain by a maker and executed against by a taker
+///
+/// The maker signs `signing_message` with ed25519; the taker submits the signature through the
+/// ed25519 program in the same transaction as `ExecuteFirmQuote`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct FirmQuote {
+    /// Market the quote trades on
+    pub market: Pubkey,
+    
+    /// Maker issuing the quote
+    pub maker: Pubkey,
+    
+    /// Taker the quote is addressed to (default pubkey for any taker)
+    pub taker: Pubkey,
+    
+    /// Side of the maker (true if the maker buys base tokens)
+    pub is_buy: bool,
+    
+    /// Price in quote tokens
+    pub price: u64,
+    
+    /// Maximum base quantity that can be filled across executions
+    pub quantity: u64,
+    
+    /// Unix timestamp after which the quote can no longer be executed
+    pub expires_at: i64,
+    
+    /// Maker-chosen nonce identifying the quote's fill record
+    pub nonce: u64,
+}
+
+impl FirmQuote {
+    /// Message the maker signs for a program deployment
+    pub fn signing_message(&self, program_id: &Pubkey) -> Vec<u8> {
+        let mut message = FIRM_QUOTE_DOMAIN.to_vec();
+        message.extend_from_slice(program_id.as_ref());
+        message.extend_from_slice(&self.try_to_vec().unwrap());
+        message
+    }
+    
+    /// Find the fill record PDA of the quote
+    pub fn find_fill_address(&self, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                QUOTE_FILL_SEED,
+                self.market.as_ref(),
+                self.maker.as_ref(),
+                &self.nonce.to_le_bytes(),
+            ],
+            program_id,
+        )
+    }
+}
+
+/// Quantity filled so far of a firm quote
+#[derive(BorshSerialize, BorshDeserialize, ShankAccount, Debug, Clone)]
+pub struct QuoteFill {
+    /// Is this record initialized
+    pub is_initialized: bool,
+    
+    /// Market the quote trades on
+    pub market: Pubkey,
+    
+    /// Maker of the quote
+    pub maker: Pubkey,
+    
+    /// Nonce of the quote
+    pub nonce: u64,
+    
+    /// Base quantity filled so far
+    pub filled_quantity: u64,
+}
+
+impl Sealed for QuoteFill {}
+
+impl IsInitialized for QuoteFill {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for QuoteFill {
+    const LEN: usize = 1 + 32 + 32 + 8 + 8;
+    
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap();
+    }
+    
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Self::try_from_slice(src).map_err(|_| ProgramError::InvalidAccountData)
+    }
+}
+
+/// Constant-product liquidity pool trading a market's base and quote mints
+///
+/// Pools live at the PDA `["pool", market]`, which owns the reserve vaults
+/// `["pool_base_vault", market]` and `["pool_quote_vault", market]` and is the mint authority
+/// of the LP mint `["pool_lp_mint", market]`. Swaps keep `base_reserve * quote_reserve` from
+/// decreasing; the swap fee stays in the reserves, so it accrues to the LP tokens. The reserves
+/// are tracked here rather than read from the vaults, so tokens sent to a vault directly do not
+/// move the price.
+#[derive(BorshSerialize, BorshDeserialize, ShankAccount, Debug, Clone, PartialEq)]
+pub struct Pool {
+    /// Is this pool initialized
+    pub is_initialized: bool,
+    
+    /// Market whose mints the pool trades
+    pub market: Pubkey,
+    
+    /// Mint of the pool's LP tokens
+    pub lp_mint: Pubkey,
+    
+    /// Bump of the pool PDA, which signs for the vaults and the LP mint
+    pub bump: u8,
+    
+    /// Swap fee in basis points of the input amount, left in the reserves
+    pub fee_bps: u16,
+    
+    /// Base tokens in the pool
+    pub base_reserve: u64,
+    
+    /// Quote tokens in the pool
+    pub quote_reserve: u64,
+    
+    /// LP tokens outstanding, including the `POOL_MINIMUM_LIQUIDITY` never minted
+    pub lp_supply: u64,
+}
+
+impl Pool {
+    /// Find the pool PDA of a market
+    pub fn find_address(program_id: &Pubkey, market: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[POOL_SEED, market.as_ref()], program_id)
+    }
+    
+    /// Find the PDA of a pool's base or quote reserve vault
+    pub fn find_vault_address(program_id: &Pubkey, market: &Pubkey, is_quote: bool) -> (Pubkey, u8) {
+        let seed = if is_quote {
+            POOL_QUOTE_VAULT_SEED
+        } else {
+            POOL_BASE_VAULT_SEED
+        };
+        Pubkey::find_program_address(&[seed, market.as_ref()], program_id)
+    }
+    
+    /// Find the PDA of a pool's LP mint
+    pub fn find_lp_mint_address(program_id: &Pubkey, market: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[POOL_LP_MINT_SEED, market.as_ref()], program_id)
+    }
+    
+    /// Reserve of one mint
+    pub fn reserve(&self, is_quote: bool) -> u64 {
+        if is_quote {
+            self.quote_reserve
+        } else {
+            self.base_reserve
+        }
+    }
+    
+    /// Mutable reserve of one mint
+    pub fn reserve_mut(&mut self, is_quote: bool) -> &mut u64 {
+        if is_quote {
+            &mut self.quote_reserve
+        } else {
+            &mut self.base_reserve
+        }
+    }
+    
+    /// LP tokens minted for a deposit of at most `max_base` and `max_quote`, and the base and
+    /// quote amounts it takes
+    ///
+    /// The first deposit sets the price and mints the geometric mean of its amounts, less the
+    /// `POOL_MINIMUM_LIQUIDITY` kept unminted. Later deposits mint in proportion to the scarcer
+    /// of the two amounts and take the other at the pool's ratio, rounded up.
+    pub fn deposit_amounts(&self, max_base: u64, max_quote: u64) -> Result<(u64, u64, u64), ProgramError> {
+        if self.lp_supply == 0 {
+            let liquidity = u64::try_from(math::isqrt(max_base as u128 * max_quote as u128))
+                .map_err(|_| ProgramError::ArithmeticOverflow)?;
+            return Ok((liquidity.saturating_sub(POOL_MINIMUM_LIQUIDITY), max_base, max_quote));
+        }
+        
+        let lp_amount = math::mul_div(max_base, self.lp_supply, self.base_reserve, Rounding::Down)?
+            .min(math::mul_div(max_quote, self.lp_supply, self.quote_reserve, Rounding::Down)?);
+        Ok((
+            lp_amount,
+            math::mul_div(lp_amount, self.base_reserve, self.lp_supply, Rounding::Up)?,
+            math::mul_div(lp_amount, self.quote_reserve, self.lp_supply, Rounding::Up)?,
+        ))
+    }
+    
+    /// Base and quote amounts paid out for burning `lp_amount` LP tokens, rounded down
+    pub fn withdraw_amounts(&self, lp_amount: u64) -> Result<(u64, u64), ProgramError> {
+        Ok((
+            math::mul_div(lp_amount, self.base_reserve, self.lp_supply, Rounding::Down)?,
+            math::mul_div(lp_amount, self.quote_reserve, self.lp_supply, Rounding::Down)?,
+        ))
+    }
+    
+    /// Amount paid out for swapping `amount_in` into the pool: base tokens for quote tokens
+    /// when buying, quote tokens for base tokens when selling
+    ///
+    /// The fee is taken from the input before the constant-product curve, and the output
+    /// rounds down, so the product of the reserves never decreases.
+    pub fn swap_amount_out(&self, is_buy: bool, amount_in: u64) -> Result<u64, ProgramError> {
+        let amount_in = amount_in
+            .checked_sub(math::bps_of(amount_in, self.fee_bps as u64, Rounding::Up)?)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        let reserve_in = self.reserve(is_buy);
+        let reserve_out = self.reserve(!is_buy);
+        let denominator = reserve_in.checked_add(amount_in).ok_or(ProgramError::ArithmeticOverflow)?;
+        math::mul_div(reserve_out, amount_in, denominator, Rounding::Down)
+    }
+    
+    /// Swap `amount_in` into the pool and return the amount paid out
+    pub fn swap(&mut self, is_buy: bool, amount_in: u64) -> Result<u64, ProgramError> {
+        let amount_out = self.swap_amount_out(is_buy, amount_in)?;
+        
+        // Buys pay quote tokens in and take base tokens out, sells the reverse
+        let reserve_in = self.reserve_mut(is_buy);
+        *reserve_in = reserve_in.checked_add(amount_in).ok_or(ProgramError::ArithmeticOverflow)?;
+        let reserve_out = self.reserve_mut(!is_buy);
+        *reserve_out = reserve_out.checked_sub(amount_out).ok_or(ProgramError::ArithmeticOverflow)?;
+        Ok(amount_out)
+    }
+    
+    /// Whether the pool's marginal price beats `numerator / denominator` quote tokens per base
+    /// token: costs less to buy from, or pays more to sell to
+    ///
+    /// The marginal price is that of the next infinitesimal swap after the fee; an empty pool
+    /// beats no price.
+    pub fn beats_price(&self, is_buy: bool, numerator: u128, denominator: u128) -> Result<bool, ProgramError> {
+        if self.base_reserve == 0 || self.quote_reserve == 0 {
+            return Ok(false);
+        }
+        
+        // Buying costs quote / (base * (1 - fee)), selling pays quote * (1 - fee) / base
+        let fee_kept = math::BPS_DENOMINATOR.saturating_sub(self.fee_bps as u64) as u128;
+        let (quote_factor, base_factor) = if is_buy {
+            (math::BPS_DENOMINATOR as u128, fee_kept)
+        } else {
+            (fee_kept, math::BPS_DENOMINATOR as u128)
+        };
+        let pool_side = (self.quote_reserve as u128 * quote_factor)
+            .checked_mul(denominator)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        let price_side = (self.base_reserve as u128 * base_factor)
+            .checked_mul(numerator)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        Ok(if is_buy {
+            pool_side < price_side
+        } else {
+            pool_side > price_side
+        })
+    }
+    
+    /// Largest part of `max_amount_in` the pool can take while its marginal price still beats
+    /// `numerator / denominator` afterwards
+    ///
+    /// Each swap moves the marginal price against the swapper, so the amount is found by
+    /// bisection.
+    pub fn swap_amount_to_price(
+        &self,
+        is_buy: bool,
+        max_amount_in: u64,
+        numerator: u128,
+        denominator: u128,
+    ) -> Result<u64, ProgramError> {
+        let beats_after = |amount_in: u64| -> Result<bool, ProgramError> {
+            let mut pool = self.clone();
+            pool.swap(is_buy, amount_in)?;
+            pool.beats_price(is_buy, numerator, denominator)
+        };
+        if !self.beats_price(is_buy, numerator, denominator)? {
+            return Ok(0);
+        }
+        if beats_after(max_amount_in)? {
+            return Ok(max_amount_in);
+        }
+        
+        // The pool beats the price after swapping `low` and not after swapping `high`
+        let (mut low, mut high) = (0, max_amount_in);
+        while high - low > 1 {
+            let mid = low + (high - low) / 2;
+            if beats_after(mid)? {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        Ok(low)
+    }
+}
+
+impl Sealed for Pool {}
+
+impl IsInitialized for Pool {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for Pool {
+    const LEN: usize = 1 + 32 + 32 + 1 + 2 + 8 + 8 + 8;
+    
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap();
+    }
+    
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Self::try_from_slice(src).map_err(|_| ProgramError::InvalidAccountData)
+    }
+}
+
+/// Concentrated-liquidity pool trading a market's base and quote mints
+///
+/// Range pools live at the PDA `["range_pool", market]`, which owns the vaults
+/// `["range_pool_base_vault", market]` and `["range_pool_quote_vault", market]`. Liquidity is
+/// provided over tick ranges by `RangePosition` accounts and only trades while the price is
+/// inside the range. The price is kept as its square root in Q32.32 fixed point; the ticks
+/// that positions start or end at follow the header, sorted by index, and a swap crossing one
+/// adds or removes the liquidity of the positions it bounds. Swap fees stay in the vaults and
+/// accrue to the positions in range, per unit of liquidity, in the fee growth fields.
+#[derive(BorshSerialize, BorshDeserialize, ShankAccount, Debug, Clone, PartialEq)]
+pub struct RangePool {
+    /// Is this pool initialized
+    pub is_initialized: bool,
+    
+    /// Market whose mints the pool trades
+    pub market: Pubkey,
+    
+    /// Bump of the range pool PDA, which signs for the vaults
+    pub bump: u8,
+    
+    /// Swap fee in basis points of the input amount
+    pub fee_bps: u16,
+    
+    /// Ticks positions start and end at are multiples of this spacing
+    pub tick_spacing: u16,
+    
+    /// Square root of the price in quote tokens per base token, Q32.32
+    pub sqrt_price: u64,
+    
+    /// Highest tick at or below the price
+    pub tick: i32,
+    
+    /// Liquidity of the positions whose range contains the price
+    pub liquidity: u64,
+    
+    /// Base token fees earned per unit of liquidity since creation, Q64.64
+    pub fee_growth_base: u128,
+    
+    /// Quote token fees earned per unit of liquidity since creation, Q64.64
+    pub fee_growth_quote: u128,
+    
+    /// Initialized ticks, sorted by index; at most `RANGE_POOL_MAX_TICKS`
+    pub ticks: Vec<RangeTick>,
+}
+
+/// Tick a range position starts or ends at
+#[derive(BorshSerialize, BorshDeserialize, ShankType, Debug, Clone, PartialEq)]
+pub struct RangeTick {
+    /// Tick index
+    pub index: i32,
+    
+    /// Liquidity of every position starting or ending at the tick
+    pub liquidity_gross: u64,
+    
+    /// Liquidity added when the price crosses the tick upwards, removed when it crosses down
+    pub liquidity_net: i64,
+    
+    /// Base token fee growth on the other side of the tick from the price
+    pub fee_growth_outside_base: u128,
+    
+    /// Quote token fee growth on the other side of the tick from the price
+    pub fee_growth_outside_quote: u128,
+}
+
+impl RangeTick {
+    /// Packed size of a tick
+    pub const LEN: usize = 4 + 8 + 8 + 16 + 16;
+}
+
+impl RangePool {
+    /// Find the range pool PDA of a market
+    pub fn find_address(program_id: &Pubkey, market: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[RANGE_POOL_SEED, market.as_ref()], program_id)
+    }
+    
+    /// Find the PDA of a range pool's base or quote vault
+    pub fn find_vault_address(program_id: &Pubkey, market: &Pubkey, is_quote: bool) -> (Pubkey, u8) {
+        let seed = if is_quote {
+            RANGE_POOL_QUOTE_VAULT_SEED
+        } else {
+            RANGE_POOL_BASE_VAULT_SEED
+        };
+        Pubkey::find_program_address(&[seed, market.as_ref()], program_id)
+    }
+    
+    /// Check that a tick range is ordered, inside the price range and on the tick spacing
+    pub fn check_range(&self, tick_lower: i32, tick_upper: i32) -> Result<(), ProgramError> {
+        let spacing = self.tick_spacing as i32;
+        let on_spacing = |tick: i32| spacing > 0 && tick % spacing == 0;
+        if tick_lower >= tick_upper
+            || tick_lower < math::MIN_TICK
+            || tick_upper > math::MAX_TICK
+            || !on_spacing(tick_lower)
+            || !on_spacing(tick_upper)
+        {
+            return Err(DexError::InvalidTickRange.into());
+        }
+        Ok(())
+    }
+    
+    /// Fees earned per unit of liquidity inside a tick range since its ticks were initialized
+    ///
+    /// Growth wraps around like Uniswap v3's, so only differences between two readings mean
+    /// anything.
+    pub fn fee_growth_inside(&self, tick_lower: i32, tick_upper: i32) -> (u128, u128) {
+        let outside = |index: i32| {
+            self.ticks
+                .iter()
+                .find(|tick| tick.index == index)
+                .map_or((0, 0), |tick| (tick.fee_growth_outside_base, tick.fee_growth_outside_quote))
+        };
+        let global = (self.fee_growth_base, self.fee_growth_quote);
+        let (lower, upper) = (outside(tick_lower), outside(tick_upper));
+        let below = if self.tick >= tick_lower {
+            lower
+        } else {
+            (global.0.wrapping_sub(lower.0), global.1.wrapping_sub(lower.1))
+        };
+        let above = if self.tick < tick_upper {
+            upper
+        } else {
+            (global.0.wrapping_sub(upper.0), global.1.wrapping_sub(upper.1))
+        };
+        (
+            global.0.wrapping_sub(below.0).wrapping_sub(above.0),
+            global.1.wrapping_sub(below.1).wrapping_sub(above.1),
+        )
+    }
+    
+    /// Base and quote tokens `liquidity` holds over a tick range at the current price
+    ///
+    /// Below the range it is all base tokens, above it all quote tokens, and inside it both.
+    pub fn range_amounts(
+        &self,
+        tick_lower: i32,
+        tick_upper: i32,
+        liquidity: u64,
+        rounding: Rounding,
+    ) -> Result<(u64, u64), ProgramError> {
+        let sqrt_lower = math::sqrt_price_at_tick(tick_lower)?;
+        let sqrt_upper = math::sqrt_price_at_tick(tick_upper)?;
+        if self.tick < tick_lower {
+            Ok((math::base_delta(sqrt_lower, sqrt_upper, liquidity, rounding)?, 0))
+        } else if self.tick >= tick_upper {
+            Ok((0, math::quote_delta(sqrt_lower, sqrt_upper, liquidity, rounding)?))
+        } else {
+            Ok((
+                math::base_delta(self.sqrt_price, sqrt_upper, liquidity, rounding)?,
+                math::quote_delta(sqrt_lower, self.sqrt_price, liquidity, rounding)?,
+            ))
+        }
+    }
+    
+    /// Add liquidity to (positive delta) or remove it from a position, returning the base and
+    /// quote tokens paid in, rounded up, or paid out, rounded down
+    ///
+    /// The position is credited the fees earned inside its range first. New ticks are
+    /// inserted in order, and ticks no position uses any more are removed.
+    pub fn update_position(&mut self, position: &mut RangePosition, liquidity_delta: i64) -> Result<(u64, u64), ProgramError> {
+        let (tick_lower, tick_upper) = (position.tick_lower, position.tick_upper);
+        self.check_range(tick_lower, tick_upper)?;
+        for (index, is_upper) in [(tick_lower, false), (tick_upper, true)] {
+            self.update_tick(index, liquidity_delta, is_upper)?;
+        }
+        
+        // Credit the fees earned since the position last changed, then resize it
+        position.collect_fees(self.fee_growth_inside(tick_lower, tick_upper))?;
+        position.liquidity = position
+            .liquidity
+            .checked_add_signed(liquidity_delta)
+            .filter(|liquidity| *liquidity <= i64::MAX as u64)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        self.ticks.retain(|tick| tick.liquidity_gross > 0);
+        
+        if (tick_lower..tick_upper).contains(&self.tick) {
+            self.liquidity = self
+                .liquidity
+                .checked_add_signed(liquidity_delta)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+        }
+        
+        let rounding = if liquidity_delta >= 0 {
+            Rounding::Up
+        } else {
+            Rounding::Down
+        };
+        self.range_amounts(tick_lower, tick_upper, liquidity_delta.unsigned_abs(), rounding)
+    }
+    
+    // Change the liquidity starting (lower) or ending (upper) at a tick, inserting it when new
+    fn update_tick(&mut self, index: i32, liquidity_delta: i64, is_upper: bool) -> Result<(), ProgramError> {
+        let position = match self.ticks.binary_search_by_key(&index, |tick| tick.index) {
+            Ok(position) => position,
+            Err(position) => {
+                if self.ticks.len() >= RANGE_POOL_MAX_TICKS {
+                    return Err(DexError::InvalidTickRange.into());
+                }
+                
+                // Fees so far count as earned below the price
+                let (fee_growth_outside_base, fee_growth_outside_quote) = if index <= self.tick {
+                    (self.fee_growth_base, self.fee_growth_quote)
+                } else {
+                    (0, 0)
+                };
+                self.ticks.insert(
+                    position,
+                    RangeTick {
+                        index,
+                        liquidity_gross: 0,
+                        liquidity_net: 0,
+                        fee_growth_outside_base,
+                        fee_growth_outside_quote,
+                    },
+                );
+                position
+            }
+        };
+        
+        let tick = &mut self.ticks[position];
+        tick.liquidity_gross = tick
+            .liquidity_gross
+            .checked_add_signed(liquidity_delta)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        let net_delta = if is_upper {
+            liquidity_delta.checked_neg()
+        } else {
+            Some(liquidity_delta)
+        };
+        tick.liquidity_net = net_delta
+            .and_then(|delta| tick.liquidity_net.checked_add(delta))
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        Ok(())
+    }
+    
+    /// Swap up to `amount_in` into the pool, returning the amount taken and the amount paid
+    /// out: base tokens for quote tokens when buying, quote tokens for base tokens when selling
+    ///
+    /// The swap moves the price one initialized tick at a time. Within a step the fee is taken
+    /// from the input and credited to the liquidity in range; reaching a tick crosses it,
+    /// changing the liquidity in range by the tick's net liquidity. Ranges without liquidity
+    /// are skipped, and the swap stops early at the end of the price range.
+    pub fn swap(&mut self, is_buy: bool, amount_in: u64) -> Result<(u64, u64), ProgramError> {
+        let fee_kept = math::BPS_DENOMINATOR - self.fee_bps as u64;
+        let (mut remaining, mut amount_out) = (amount_in, 0u64);
+        while remaining > 0 {
+            // Next initialized tick in the direction of the swap, or the end of the price range
+            let next_tick = if is_buy {
+                self.ticks.iter().position(|tick| tick.index > self.tick)
+            } else {
+                self.ticks.iter().rposition(|tick| tick.index <= self.tick)
+            };
+            let target_tick = match next_tick {
+                Some(position) => self.ticks[position].index,
+                None if is_buy => math::MAX_TICK,
+                None => math::MIN_TICK,
+            };
+            let target = math::sqrt_price_at_tick(target_tick)?;
+            
+            if self.liquidity > 0 {
+                // Input needed to reach the target, after the fee and grossed up by it; more
+                // than a u64 holds is more than any swap has
+                let needed = if is_buy {
+                    math::quote_delta(self.sqrt_price, target, self.liquidity, Rounding::Up)
+                } else {
+                    math::base_delta(target, self.sqrt_price, self.liquidity, Rounding::Up)
+                };
+                let gross_needed = needed
+                    .clone()
+                    .and_then(|needed| math::mul_div(needed, math::BPS_DENOMINATOR, fee_kept, Rounding::Up))
+                    .unwrap_or(u64::MAX);
+                let (step_in, net_in, next_sqrt_price) = if remaining >= gross_needed {
+                    (gross_needed, needed?, target)
+                } else {
+                    let net_in = remaining - math::bps_of(remaining, self.fee_bps as u64, Rounding::Up)?;
+                    let next = math::next_sqrt_price(self.sqrt_price, self.liquidity, net_in, is_buy)?;
+                    let next = if is_buy { next.min(target) } else { next.max(target) };
+                    (remaining, net_in, next)
+                };
+                let step_out = if is_buy {
+                    math::base_delta(self.sqrt_price, next_sqrt_price, self.liquidity, Rounding::Down)?
+                } else {
+                    math::quote_delta(next_sqrt_price, self.sqrt_price, self.liquidity, Rounding::Down)?
+                };
+                
+                // The fee accrues to the liquidity in range, in the input token
+                let fee_growth = (((step_in - net_in) as u128) << 64) / self.liquidity as u128;
+                if is_buy {
+                    self.fee_growth_quote = self.fee_growth_quote.wrapping_add(fee_growth);
+                } else {
+                    self.fee_growth_base = self.fee_growth_base.wrapping_add(fee_growth);
+                }
+                remaining -= step_in;
+                amount_out = amount_out.checked_add(step_out).ok_or(ProgramError::ArithmeticOverflow)?;
+                self.sqrt_price = next_sqrt_price;
+            } else {
+                // Nothing trades until the next tick with liquidity
+                self.sqrt_price = target;
+            }
+            
+            if self.sqrt_price != target {
+                self.tick = math::tick_at_sqrt_price(self.sqrt_price)?;
+                break;
+            }
+            let Some(position) = next_tick else {
+                // The end of the price range
+                self.tick = if is_buy { math::MAX_TICK } else { math::MIN_TICK };
+                break;
+            };
+            
+            // Cross the tick: fees outside it flip sides, and its liquidity enters or leaves
+            let tick = &mut self.ticks[position];
+            tick.fee_growth_outside_base = self.fee_growth_base.wrapping_sub(tick.fee_growth_outside_base);
+            tick.fee_growth_outside_quote = self.fee_growth_quote.wrapping_sub(tick.fee_growth_outside_quote);
+            let liquidity_net = if is_buy {
+                Some(tick.liquidity_net)
+            } else {
+                tick.liquidity_net.checked_neg()
+            };
+            self.liquidity = liquidity_net
+                .and_then(|net| self.liquidity.checked_add_signed(net))
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+            self.tick = if is_buy { target_tick } else { target_tick - 1 };
+        }
+        
+        Ok((amount_in - remaining, amount_out))
+    }
+}
+
+impl Sealed for RangePool {}
+
+impl IsInitialized for RangePool {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for RangePool {
+    const LEN: usize = 1 + 32 + 1 + 2 + 2 + 8 + 4 + 8 + 16 + 16 + 4 + RANGE_POOL_MAX_TICKS * RangeTick::LEN;
+    
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap();
+    }
+    
+    // The account is sized for the most ticks, so it usually ends in unused bytes
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Self::deserialize(&mut &src[..]).map_err(|_| ProgramError::InvalidAccountData)
+    }
+}
+
+/// Liquidity an owner provides to a range pool between two ticks
+///
+/// Positions live at the PDA `["range_position", market, owner, tick_lower, tick_upper]`,
+/// with the ticks as little-endian bytes, so an owner has one position per range. Fees are
+/// credited to `fees_owed_*` whenever the position changes and paid out when liquidity is
+/// removed.
+#[derive(BorshSerialize, BorshDeserialize, ShankAccount, Debug, Clone, PartialEq)]
+pub struct RangePosition {
+    /// Is this position initialized
+    pub is_initialized: bool,
+    
+    /// Market of the range pool
+    pub market: Pubkey,
+    
+    /// Owner of the position
+    pub owner: Pubkey,
+    
+    /// Lowest tick of the range
+    pub tick_lower: i32,
+    
+    /// Tick the range ends below
+    pub tick_upper: i32,
+    
+    /// Liquidity provided over the range
+    pub liquidity: u64,
+    
+    /// Base token fee growth inside the range when fees were last credited
+    pub fee_growth_inside_base: u128,
+    
+    /// Quote token fee growth inside the range when fees were last credited
+    pub fee_growth_inside_quote: u128,
+    
+    /// Base token fees credited and not yet paid out
+    pub fees_owed_base: u64,
+    
+    /// Quote token fees credited and not yet paid out
+    pub fees_owed_quote: u64,
+}
+
+impl RangePosition {
+    /// Find the PDA of an owner's position over a tick range
+    pub fn find_address(program_id: &Pubkey, market: &Pubkey, owner: &Pubkey, tick_lower: i32, tick_upper: i32) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                RANGE_POSITION_SEED,
+                market.as_ref(),
+                owner.as_ref(),
+                &tick_lower.to_le_bytes(),
+                &tick_upper.to_le_bytes(),
+            ],
+            program_id,
+        )
+    }
+    
+    /// Credit the fees the position's liquidity earned since the last reading of the fee
+    /// growth inside its range, rounding down
+    pub fn collect_fees(&mut self, fee_growth_inside: (u128, u128)) -> Result<(), ProgramError> {
+        let earned = |growth: u128, last: u128| {
+            growth
+                .wrapping_sub(last)
+                .checked_mul(self.liquidity as u128)
+                .and_then(|fees| u64::try_from(fees >> 64).ok())
+                .ok_or(ProgramError::ArithmeticOverflow)
+        };
+        let base = earned(fee_growth_inside.0, self.fee_growth_inside_base)?;
+        let quote = earned(fee_growth_inside.1, self.fee_growth_inside_quote)?;
+        self.fees_owed_base = self.fees_owed_base.checked_add(base).ok_or(ProgramError::ArithmeticOverflow)?;
+        self.fees_owed_quote = self.fees_owed_quote.checked_add(quote).ok_or(ProgramError::ArithmeticOverflow)?;
+        (self.fee_growth_inside_base, self.fee_growth_inside_quote) = fee_growth_inside;
+        Ok(())
+    }
+}
+
+impl Sealed for RangePosition {}
+
+impl IsInitialized for RangePosition {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for RangePosition {
+    const LEN: usize = 1 + 32 + 32 + 4 + 4 + 8 + 16 + 16 + 8 + 8;
+    
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap();
+    }
+    
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Self::try_from_slice(src).map_err(|_| ProgramError::InvalidAccountData)
+    }
+}
+
+/// Borrow rate of a lending pool as a function of its utilization, in the style of Compound's
+/// jump rate model
+///
+/// The annual rate starts at `base_rate_bps`, rises linearly by `slope1_bps` up to
+/// `optimal_utilization_bps` and by a further `slope2_bps` from there to full utilization,
+/// so borrowing gets expensive before suppliers are locked out of their tokens.
+#[derive(BorshSerialize, BorshDeserialize, ShankType, Debug, Clone, Copy, PartialEq)]
+pub struct InterestRateCurve {
+    /// Annual borrow rate at zero utilization, in basis points
+    pub base_rate_bps: u16,
+    
+    /// Utilization where the steeper slope starts, in basis points
+    pub optimal_utilization_bps: u16,
+    
+    /// Annual rate added from zero to optimal utilization, in basis points
+    pub slope1_bps: u16,
+    
+    /// Annual rate added from optimal to full utilization, in basis points
+    pub slope2_bps: u16,
+}
+
+impl InterestRateCurve {
+    /// Does the kink lie strictly between zero and full utilization
+    pub fn is_valid(&self) -> bool {
+        self.optimal_utilization_bps > 0 && (self.optimal_utilization_bps as u64) < math::BPS_DENOMINATOR
+    }
+    
+    /// Annual borrow rate at a utilization, both in basis points
+    pub fn borrow_rate_bps(&self, utilization_bps: u64) -> u64 {
+        let optimal = self.optimal_utilization_bps as u64;
+        let utilization = utilization_bps.min(math::BPS_DENOMINATOR);
+        let slope1 = self.slope1_bps as u64 * utilization.min(optimal) / optimal;
+        let slope2 = self.slope2_bps as u64 * utilization.saturating_sub(optimal) / (math::BPS_DENOMINATOR - optimal);
+        self.base_rate_bps as u64 + slope1 + slope2
+    }
+}
+
+/// Pool of one mint that suppliers lend to and margin borrowers draw on
+///
+/// Lending pools live at the PDA `["lending_pool", mint]`, which owns the vault
+/// `["lending_pool_vault", mint]` and is the mint authority of the share mint
+/// `["lending_pool_share_mint", mint]`. Suppliers receive shares of the tokens supplied plus
+/// the interest borrowers owe; interest accrues on the borrowed tokens at the rate curve's
+/// rate for the pool's utilization whenever the pool is touched, and the borrow index grows
+/// with it so each borrow can be scaled to what is owed now.
+#[derive(BorshSerialize, BorshDeserialize, ShankAccount, Debug, Clone, PartialEq)]
+pub struct LendingPool {
+    /// Is this pool initialized
+    pub is_initialized: bool,
+    
+    /// Mint the pool lends
+    pub mint: Pubkey,
+    
+    /// Token program of the mint and the share mint
+    pub token_program: Pubkey,
+    
+    /// Mint of the pool's share tokens
+    pub share_mint: Pubkey,
+    
+    /// Bump of the lending pool PDA, which signs for the vault and the share mint
+    pub bump: u8,
+    
+    /// Decimals of the mint, which the share mint shares
+    pub decimals: u8,
+    
+    /// Borrow rate curve
+    pub rate_curve: InterestRateCurve,
+    
+    /// Tokens in the vault that are not lent out, tracked in the account rather than read
+    /// from the vault
+    pub available: u64,
+    
+    /// Tokens lent out, with the interest accrued on them
+    pub borrowed: u64,
+    
+    /// Share tokens outstanding
+    pub share_supply: u64,
+    
+    /// Debt of one token borrowed at creation, Q64.64
+    pub borrow_index: u128,
+    
+    /// Unix timestamp interest was last accrued at
+    pub last_accrual_timestamp: i64,
+}
+
+impl LendingPool {
+    /// Borrow index of a new pool, one in Q64.64
+    pub const INITIAL_BORROW_INDEX: u128 = 1 << 64;
+    
+    /// Find the lending pool PDA of a mint
+    pub fn find_address(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[LENDING_POOL_SEED, mint.as_ref()], program_id)
+    }
+    
+    /// Find the PDA of a lending pool's vault
+    pub fn find_vault_address(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[LENDING_POOL_VAULT_SEED, mint.as_ref()], program_id)
+    }
+    
+    /// Find the PDA of a lending pool's share mint
+    pub fn find_share_mint_address(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[LENDING_POOL_SHARE_MINT_SEED, mint.as_ref()], program_id)
+    }
+    
+    /// Tokens the shares are worth: those available and those lent out
+    pub fn total_supplied(&self) -> u64 {
+        self.available.saturating_add(self.borrowed)
+    }
+    
+    /// Part of the supplied tokens lent out, in basis points
+    pub fn utilization_bps(&self) -> u64 {
+        let total = self.available as u128 + self.borrowed as u128;
+        if total == 0 {
+            return 0;
+        }
+        (self.borrowed as u128 * math::BPS_DENOMINATOR as u128 / total) as u64
+    }
+    
+    /// Annual rate borrowers pay, in basis points
+    pub fn borrow_rate_bps(&self) -> u64 {
+        self.rate_curve.borrow_rate_bps(self.utilization_bps())
+    }
+    
+    /// Annual rate suppliers earn, in basis points: the borrow rate on the lent-out part
+    pub fn supply_rate_bps(&self) -> u64 {
+        self.borrow_rate_bps() * self.utilization_bps() / math::BPS_DENOMINATOR
+    }
+    
+    /// Accrue interest on the borrowed tokens from the last accrual to `now`
+    ///
+    /// Interest is simple over the elapsed time and compounds at each accrual. It rounds up,
+    /// against borrowers, and is added to what the shares are worth.
+    pub fn accrue_interest(&mut self, now: i64) -> Result<(), ProgramError> {
+        let elapsed = now.saturating_sub(self.last_accrual_timestamp).max(0) as u128;
+        if elapsed == 0 {
+            return Ok(());
+        }
+        
+        // The rate over the elapsed time, as a fraction of `denominator`
+        let rate = self.borrow_rate_bps() as u128 * elapsed;
+        let denominator = math::BPS_DENOMINATOR as u128 * SECONDS_PER_YEAR as u128;
+        let interest = (self.borrowed as u128 * rate).div_ceil(denominator);
+        self.borrowed = u64::try_from(self.borrowed as u128 + interest).map_err(|_| ProgramError::ArithmeticOverflow)?;
+        let index_growth = self
+            .borrow_index
+            .checked_mul(rate)
+            .ok_or(ProgramError::ArithmeticOverflow)?
+            .div_ceil(denominator);
+        self.borrow_index = self.borrow_index.checked_add(index_growth).ok_or(ProgramError::ArithmeticOverflow)?;
+        self.last_accrual_timestamp = now;
+        Ok(())
+    }
+    
+    /// Shares minted for supplying `amount` tokens, rounded down; the first supply mints one
+    /// share per token
+    pub fn supply_shares(&self, amount: u64) -> Result<u64, ProgramError> {
+        if self.share_supply == 0 {
+            return Ok(amount);
+        }
+        let total = self.total_supplied();
+        if total == 0 {
+            // Every token the shares were worth is gone, so new shares would price at nothing
+            return Err(ProgramError::InsufficientFunds);
+        }
+        math::mul_div(amount, self.share_supply, total, Rounding::Down)
+    }
+    
+    /// Tokens `shares` redeem for, rounded down
+    pub fn redeem_amount(&self, shares: u64) -> Result<u64, ProgramError> {
+        if self.share_supply == 0 {
+            return Ok(0);
+        }
+        math::mul_div(shares, self.total_supplied(), self.share_supply, Rounding::Down)
+    }
+    
+    /// Lend `amount` of the available tokens out
+    pub fn borrow(&mut self, amount: u64) -> Result<(), ProgramError> {
+        self.available = self.available.checked_sub(amount).ok_or(ProgramError::InsufficientFunds)?;
+        self.borrowed = self.borrowed.checked_add(amount).ok_or(ProgramError::ArithmeticOverflow)?;
+        Ok(())
+    }
+    
+    /// Take back `amount` lent-out tokens
+    pub fn repay(&mut self, amount: u64) -> Result<(), ProgramError> {
+        self.borrowed = self.borrowed.checked_sub(amount).ok_or(ProgramError::InsufficientFunds)?;
+        self.available = self.available.checked_add(amount).ok_or(ProgramError::ArithmeticOverflow)?;
+        Ok(())
+    }
+}
+
+impl Sealed for LendingPool {}
+
+impl IsInitialized for LendingPool {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for LendingPool {
+    const LEN: usize = 1 + 32 + 32 + 32 + 1 + 1 + 8 + 8 + 8 + 8 + 16 + 8;
+    
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap();
+    }
+    
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Self::try_from_slice(src).map_err(|_| ProgramError::InvalidAccountData)
+    }
+}
+
+/// Perpetual futures market on a spot market's pair, settled in its quote token
+///
+/// Perp markets live at the PDA `["perp_market", market]` and own the quote collateral vault
+/// `["perp_vault", market]`. The base asset is virtual: positions only hold a signed size in
+/// base lots against a quote balance, and the market authority crosses the owners' perp
+/// orders as SettleFunds crosses spot orders. Every funding interval longs pay shorts a twenty-fourth of the premium of the
+/// mark price, the last fill price, to the index price read from the market's oracle, or
+/// shorts pay longs when the mark is below the index.
+#[derive(BorshSerialize, BorshDeserialize, ShankAccount, Debug, Clone, PartialEq)]
+pub struct PerpMarket {
+    /// Is this perp market initialized
+    pub is_initialized: bool,
+    
+    /// Spot market whose authority, quote mint, lot sizes, fees and oracle the perp uses
+    pub market: Pubkey,
+    
+    /// Bump of the perp market PDA, which signs for the vault
+    pub bump: u8,
+    
+    /// Equity a position must keep against its notional at the mark price, in basis points
+    pub initial_margin_bps: u16,
+    
+    /// Largest funding payment per interval against the index price, in basis points
+    pub max_funding_rate_bps: u16,
+    
+    /// Price of the last fill, in quote lots per base lot; zero before the first fill
+    pub mark_price: u64,
+    
+    /// Base lots held long, which equals the base lots held short
+    pub long_open_interest: u64,
+    
+    /// Funding paid per base lot held long since creation, in quote tokens scaled by
+    /// `FUNDING_PRECISION`
+    pub cumulative_funding: i128,
+    
+    /// Timestamp funding was last paid up to
+    pub last_funding_timestamp: i64,
+}
+
+impl PerpMarket {
+    /// Find the perp market PDA of a market
+    pub fn find_address(program_id: &Pubkey, market: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[PERP_MARKET_SEED, market.as_ref()], program_id)
+    }
+    
+    /// Find the PDA of a perp market's collateral vault
+    pub fn find_vault_address(program_id: &Pubkey, market: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[PERP_VAULT_SEED, market.as_ref()], program_id)
+    }
+    
+    /// Price positions are valued at: the mark price, or `index_price` before the first fill
+    pub fn mark_price_or(&self, index_price: u64) -> u64 {
+        if self.mark_price == 0 {
+            index_price
+        } else {
+            self.mark_price
+        }
+    }
+    
+    /// Funding per base lot held long for one interval, in quote tokens scaled by
+    /// `FUNDING_PRECISION`: a twenty-fourth of the premium, capped at `max_funding_rate_bps`
+    /// of the index price
+    pub fn funding_per_interval(&self, mark_price: u64, index_price: u64, quote_lot_size: u64) -> i128 {
+        let scale = quote_lot_size as i128 * FUNDING_PRECISION;
+        let premium = (mark_price as i128 - index_price as i128) * scale / FUNDING_INTERVALS_PER_DAY;
+        let cap = index_price as i128 * scale * self.max_funding_rate_bps as i128 / math::BPS_DENOMINATOR as i128;
+        premium.clamp(-cap, cap)
+    }
+    
+    /// Update the long open interest for a position going from `before` to `after` base lots
+    pub fn record_position_change(&mut self, before: i64, after: i64) -> Result<(), ProgramError> {
+        let long = |base: i64| base.max(0) as u64;
+        self.long_open_interest = self
+            .long_open_interest
+            .checked_sub(long(before))
+            .and_then(|interest| interest.checked_add(long(after)))
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        Ok(())
+    }
+}
+
+impl Sealed for PerpMarket {}
+
+impl IsInitialized for PerpMarket {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for PerpMarket {
+    const LEN: usize = 1 + 32 + 1 + 2 + 2 + 8 + 8 + 16 + 8;
+    
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap();
+    }
+    
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Self::try_from_slice(src).map_err(|_| ProgramError::InvalidAccountData)
+    }
+}
+
+/// An owner's position on a perp market, at the PDA `["perp_position", market, owner]`
+///
+/// The quote balance holds the collateral deposited together with the quote tokens paid and
+/// received for fills, fees and funding, so it goes negative once a long has spent more than
+/// its collateral. Equity is the quote balance plus the base position valued at the mark
+/// price.
+#[derive(BorshSerialize, BorshDeserialize, ShankAccount, Debug, Clone, PartialEq)]
+pub struct PerpPosition {
+    /// Is this position initialized
+    pub is_initialized: bool,
+    
+    /// Spot market of the perp market
+    pub market: Pubkey,
+    
+    /// Owner of the position
+    pub owner: Pubkey,
+    
+    /// Size in base lots, positive when long and negative when short
+    pub base_position: i64,
+    
+    /// Quote tokens held against the position
+    pub quote_balance: i64,
+    
+    /// Cumulative funding of the perp market when the position last paid funding
+    pub last_cumulative_funding: i128,
+}
+
+impl PerpPosition {
+    /// Find the PDA of an owner's position on a market's perp market
+    pub fn find_address(program_id: &Pubkey, market: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[PERP_POSITION_SEED, market.as_ref(), owner.as_ref()], program_id)
+    }
+    
+    /// Pay the funding owed since the position last paid, which is positive when it pays and
+    /// negative when it receives; payments round up and receipts down
+    pub fn settle_funding(&mut self, cumulative_funding: i128) -> Result<i64, ProgramError> {
+        let owed = (cumulative_funding - self.last_cumulative_funding)
+            .checked_mul(self.base_position as i128)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        let payment = i64::try_from(-(-owed).div_euclid(FUNDING_PRECISION)).map_err(|_| ProgramError::ArithmeticOverflow)?;
+        self.quote_balance = self.quote_balance.checked_sub(payment).ok_or(ProgramError::ArithmeticOverflow)?;
+        self.last_cumulative_funding = cumulative_funding;
+        Ok(payment)
+    }
+    
+    /// Quote balance plus the base position valued at `mark_price`
+    pub fn equity(&self, mark_price: u64, quote_lot_size: u64) -> i128 {
+        self.quote_balance as i128 + self.base_position as i128 * mark_price as i128 * quote_lot_size as i128
+    }
+    
+    /// Equity the position must keep at `mark_price`, rounded up
+    pub fn initial_margin(&self, mark_price: u64, quote_lot_size: u64, initial_margin_bps: u16) -> u128 {
+        let notional = self.base_position.unsigned_abs() as u128 * mark_price as u128 * quote_lot_size as u128;
+        (notional * initial_margin_bps as u128).div_ceil(math::BPS_DENOMINATOR as u128)
+    }
+    
+    /// Whether the position's equity covers its initial margin at `mark_price`
+    pub fn is_healthy(&self, mark_price: u64, quote_lot_size: u64, initial_margin_bps: u16) -> bool {
+        let equity = self.equity(mark_price, quote_lot_size);
+        equity >= 0 && equity as u128 >= self.initial_margin(mark_price, quote_lot_size, initial_margin_bps)
+    }
+    
+    /// Add `base_lots` to the position for `quote_amount` quote tokens, which are paid when
+    /// the position buys and received when it sells
+    pub fn trade(&mut self, base_lots: i64, quote_amount: i64) -> Result<(), ProgramError> {
+        self.base_position = self.base_position.checked_add(base_lots).ok_or(ProgramError::ArithmeticOverflow)?;
+        self.quote_balance = self.quote_balance.checked_add(quote_amount).ok_or(ProgramError::ArithmeticOverflow)?;
+        Ok(())
+    }
+}
+
+impl Sealed for PerpPosition {}
+
+impl IsInitialized for PerpPosition {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for PerpPosition {
+    const LEN: usize = 1 + 32 + 32 + 8 + 8 + 16;
+    
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap();
+    }
+    
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Self::try_from_slice(src).map_err(|_| ProgramError::InvalidAccountData)
+    }
+}
+
+/// An owner's resting order on a perp market
+///
+/// Perp orders lock no tokens: the owner signs for the side, price and size it will trade at
+/// when placing the order, and SettlePerpFill fills a taker order against a maker order of the
+/// other side at the maker's price, checking the margin of both positions as they change.
+/// A reduce-only order only fills as far as it shrinks the position. Fully filled orders are
+/// closed, returning their rent to the owner.
+#[derive(BorshSerialize, BorshDeserialize, ShankAccount, Debug, Clone, PartialEq)]
+pub struct PerpOrder {
+    /// Is this order initialized
+    pub is_initialized: bool,
+    
+    /// Spot market of the perp market
+    pub market: Pubkey,
+    
+    /// Owner of the order and of the position it trades for
+    pub owner: Pubkey,
+    
+    /// Order ID, drawn from the market's order IDs
+    pub order_id: u64,
+    
+    /// Is this a buy order
+    pub is_buy: bool,
+    
+    /// Limit price in quote lots per base lot
+    pub limit_price: u64,
+    
+    /// Original quantity in base lots
+    pub original_quantity: u64,
+    
+    /// Remaining quantity in base lots
+    pub remaining_quantity: u64,
+    
+    /// Creation timestamp
+    pub creation_timestamp: u64,
+    
+    /// Whether the order may only shrink the owner's position
+    pub reduce_only: bool,
+}
+
+impl PerpOrder {
+    /// Base lots of a `quantity` fill the order may take against a position of `base_position`
+    /// base lots; a reduce-only order takes no more than the short it can buy back or the long
+    /// it can sell
+    pub fn clip_reduce_only(&self, base_position: i64, quantity: u64) -> u64 {
+        if !self.reduce_only {
+            return quantity;
+        }
+        let reducible = if self.is_buy == (base_position < 0) {
+            base_position.unsigned_abs()
+        } else {
+            0
+        };
+        quantity.min(reducible)
+    }
+}
+
+impl Sealed for PerpOrder {}
+
+impl IsInitialized for PerpOrder {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for PerpOrder {
+    const LEN: usize = 1 + 32 + 32 + 8 + 1 + 8 + 8 + 8 + 8 + 1;
+    
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap();
+    }
+    
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Self::try_from_slice(src).map_err(|_| ProgramError::InvalidAccountData)
+    }
+}
+
+/// Batch auction of a market whose orders are crossed together at one price
+///
+/// Batch auctions live at the PDA `["batch_auction", market]`. Orders rest on the market as
+/// usual; once `interval_slots` have passed since the batch started, RunAuction crosses every
+/// resting order at the uniform price that fills the most base lots and starts the next batch.
+#[derive(BorshSerialize, BorshDeserialize, ShankAccount, Debug, Clone, PartialEq)]
+pub struct BatchAuction {
+    /// Is this auction initialized
+    pub is_initialized: bool,
+    
+    /// Market the auction crosses
+    pub market: Pubkey,
+    
+    /// Slots orders accumulate for before they are crossed
+    pub interval_slots: u64,
+    
+    /// Slot the current batch started in
+    pub batch_start_slot: u64,
+    
+    /// Clearing price of the last auction that crossed any orders
+    pub last_clearing_price: u64,
+    
+    /// Base lots crossed by the last auction
+    pub last_volume: u64,
+    
+    /// Number of auctions run
+    pub auction_count: u64,
+}
+
+impl BatchAuction {
+    /// Find the batch auction PDA of a market
+    pub fn find_address(program_id: &Pubkey, market: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[BATCH_AUCTION_SEED, market.as_ref()], program_id)
+    }
+    
+    /// First slot the current batch can be crossed in
+    pub fn next_auction_slot(&self) -> u64 {
+        self.batch_start_slot.saturating_add(self.interval_slots)
+    }
+    
+    /// Uniform price and base lots of crossing bids and asks given as limit prices and
+    /// quantities, None when no bid meets an ask
+    ///
+    /// The price fills the most base lots: every bid at or above it and every ask at or below
+    /// it can take part. When a range of prices fills as much, the midpoint of the range is
+    /// taken, rounded down to a multiple of `tick_size` above its low end.
+    pub fn clearing_price(bids: &[(u64, u64)], asks: &[(u64, u64)], tick_size: u64) -> Option<(u64, u64)> {
+        let volume_at = |price: u64| {
+            let demand: u128 = bids.iter().filter(|(limit, _)| *limit >= price).map(|(_, quantity)| *quantity as u128).sum();
+            let supply: u128 = asks.iter().filter(|(limit, _)| *limit <= price).map(|(_, quantity)| *quantity as u128).sum();
+            demand.min(supply).min(u64::MAX as u128) as u64
+        };
+        
+        let mut prices: Vec<u64> = bids.iter().chain(asks).map(|(limit, _)| *limit).collect();
+        prices.sort_unstable();
+        prices.dedup();
+        
+        // Crossed volume rises and then falls with the price, so the best prices are adjacent
+        let mut best: Option<(u64, u64, u64)> = None;
+        for price in prices {
+            let volume = volume_at(price);
+            match best.as_mut() {
+                _ if volume == 0 => {}
+                Some((_, high, best_volume)) if volume == *best_volume => *high = price,
+                Some((_, _, best_volume)) if volume < *best_volume => {}
+                _ => best = Some((price, price, volume)),
+            }
+        }
+        
+        let (low, high, volume) = best?;
+        let tick_size = tick_size.max(1);
+        Some((low + (high - low) / 2 / tick_size * tick_size, volume))
+    }
+}
+
+impl Sealed for BatchAuction {}
+
+impl IsInitialized for BatchAuction {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for BatchAuction {
+    const LEN: usize = 1 + 32 + 8 + 8 + 8 + 8 + 8;
+    
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap();
+    }
+    
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Self::try_from_slice(src).map_err(|_| ProgramError::InvalidAccountData)
+    }
+}
+
+/// Outcome of a token sale
+#[derive(BorshSerialize, BorshDeserialize, ShankType, Debug, Clone, Copy, PartialEq)]
+pub enum SaleStatus {
+    /// Contributions are accepted until the sale ends
+    Open,
+    /// The clearing price met the reserve; contributors claim base tokens
+    Succeeded,
+    /// The clearing price fell short of the reserve; contributors claim their quote tokens back
+    Failed,
+}
+
+/// Fixed-supply sale of a market's base token ahead of trading
+///
+/// Token sales live at the PDA `["token_sale", market]`, which owns the vaults
+/// `["token_sale_base_vault", market]` of the tokens for sale and
+/// `["token_sale_quote_vault", market]` of the contributions. Contributors pay quote tokens
+/// until the sale ends; the clearing price is then the total contributed over the tokens for
+/// sale, every contributor pays that price for a share of the tokens in proportion to its
+/// contribution, and the market opens at it.
+#[derive(BorshSerialize, BorshDeserialize, ShankAccount, Debug, Clone, PartialEq)]
+pub struct TokenSale {
+    /// Is this sale initialized
+    pub is_initialized: bool,
+    
+    /// Market the sale opens
+    pub market: Pubkey,
+    
+    /// Bump of the token sale PDA, which signs for the vaults
+    pub bump: u8,
+    
+    /// Base tokens for sale
+    pub base_amount: u64,
+    
+    /// Lowest clearing price the sale succeeds at, in quote lots per base lot
+    pub min_price: u64,
+    
+    /// Most quote tokens one contributor may pay in, zero for no cap
+    pub max_contribution: u64,
+    
+    /// Unix timestamp contributions close at
+    pub end_timestamp: i64,
+    
+    /// Quote tokens contributed
+    pub total_contributed: u64,
+    
+    /// Clearing price in quote lots per base lot, set when the sale is finalized
+    pub clearing_price: u64,
+    
+    /// Outcome of the sale
+    pub status: SaleStatus,
+}
+
+impl TokenSale {
+    /// Find the token sale PDA of a market
+    pub fn find_address(program_id: &Pubkey, market: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[TOKEN_SALE_SEED, market.as_ref()], program_id)
+    }
+    
+    /// Find the PDA of a token sale's base or quote vault
+    pub fn find_vault_address(program_id: &Pubkey, market: &Pubkey, is_quote: bool) -> (Pubkey, u8) {
+        let seed = if is_quote { TOKEN_SALE_QUOTE_VAULT_SEED } else { TOKEN_SALE_BASE_VAULT_SEED };
+        Pubkey::find_program_address(&[seed, market.as_ref()], program_id)
+    }
+    
+    /// Price the contributions pay for the tokens for sale, in quote lots per base lot of
+    /// `market`, rounded down
+    pub fn implied_price(&self, market: &Market) -> u64 {
+        let quote = self.total_contributed as u128 * market.base_lot_size as u128;
+        let base = self.base_amount as u128 * market.quote_lot_size as u128;
+        quote.checked_div(base).unwrap_or(0).min(u64::MAX as u128) as u64
+    }
+    
+    /// Base tokens a contribution of `amount` quote tokens buys, rounded down
+    pub fn allocation(&self, amount: u64) -> u64 {
+        (amount as u128 * self.base_amount as u128)
+            .checked_div(self.total_contributed as u128)
+            .unwrap_or(0) as u64
+    }
+}
+
+impl Sealed for TokenSale {}
+
+impl IsInitialized for TokenSale {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for TokenSale {
+    const LEN: usize = 1 + 32 + 1 + 8 + 8 + 8 + 8 + 8 + 8 + 1;
+    
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap();
+    }
+    
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Self::try_from_slice(src).map_err(|_| ProgramError::InvalidAccountData)
+    }
+}
+
+/// A contributor's payment into a token sale, at the PDA
+/// `["sale_contribution", market, owner]`
+#[derive(BorshSerialize, BorshDeserialize, ShankAccount, Debug, Clone, PartialEq)]
+pub struct SaleContribution {
+    /// Is this contribution initialized
+    pub is_initialized: bool,
+    
+    /// Market of the sale
+    pub market: Pubkey,
+    
+    /// Contributor
+    pub owner: Pubkey,
+    
+    /// Quote tokens contributed
+    pub amount: u64,
+}
+
+impl SaleContribution {
+    /// Find the PDA of an owner's contribution to a market's token sale
+    pub fn find_address(program_id: &Pubkey, market: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[SALE_CONTRIBUTION_SEED, market.as_ref(), owner.as_ref()], program_id)
+    }
+}
+
+impl Sealed for SaleContribution {}
+
+impl IsInitialized for SaleContribution {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for SaleContribution {
+    const LEN: usize = 1 + 32 + 32 + 8;
+    
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap();
+    }
+    
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Self::try_from_slice(src).map_err(|_| ProgramError::InvalidAccountData)
+    }
+}
+
+/// Taker fee discount granted to stakers of at least a minimum stake
+#[derive(BorshSerialize, BorshDeserialize, ShankType, Debug, Default, Clone, Copy, PartialEq)]
+pub struct FeeTier {
+    /// Governance tokens a staker needs for the tier
+    pub min_stake: u64,
+    
+    /// Part of the taker fee waived, in basis points; zero for an unused tier
+    pub discount_bps: u16,
+}
+
+/// Vault staking the governance token for a share of swept fees and taker fee discounts
+///
+/// The stake pool lives at the PDA `["stake_pool"]`, which owns the vaults
+/// `["stake_vault"]` of the staked governance tokens and `["stake_reward_vault"]` of the
+/// rewards. SweepFees pays `fee_share_bps` of markets quoted in the reward mint into the reward
+/// vault; at the end of each epoch the rewards that arrived are distributed to the stakers of
+/// the time in proportion to their stakes.
+#[derive(BorshSerialize, BorshDeserialize, ShankAccount, Debug, Clone, PartialEq)]
+pub struct StakePool {
+    /// Is this stake pool initialized
+    pub is_initialized: bool,
+    
+    /// Bump of the stake pool PDA, which signs for the vaults
+    pub bump: u8,
     
-    /// Base token mint
-    pub base_mint: Pubkey,
+    /// Governance token staked
+    pub governance_mint: Pubkey,
     
-    /// Quote token mint
-    pub quote_mint: Pubkey,
+    /// Token program of the governance mint
+    pub governance_token_program: Pubkey,
     
-    /// Minimum base order size
-    pub min_base_order_size: u64,
+    /// Decimals of the governance mint
+    pub governance_decimals: u8,
     
-    /// Tick size (minimum price increment)
-    pub tick_size: u64,
+    /// Mint rewards are paid in
+    pub reward_mint: Pubkey,
     
-    /// Fee rate in basis points (1/100 of 1%)
-    pub fee_rate_bps: u16,
+    /// Token program of the reward mint
+    pub reward_token_program: Pubkey,
     
-    /// Next order ID
-    pub next_order_id: u64,
+    /// Decimals of the reward mint
+    pub reward_decimals: u8,
     
-    /// Number of bids in the order book
-    pub num_bids: u64,
+    /// Part of each fee sweep paid to stakers, in basis points
+    pub fee_share_bps: u16,
     
-    /// Number of asks in the order book
-    pub num_asks: u64,
+    /// Seconds between reward distributions
+    pub epoch_seconds: u64,
+    
+    /// Number of epochs distributed
+    pub epoch: u64,
+    
+    /// Unix timestamp the current epoch started at
+    pub epoch_start_timestamp: i64,
+    
+    /// Governance tokens staked
+    pub total_staked: u64,
+    
+    /// Rewards distributed per staked token over the pool's life, scaled by
+    /// `STAKE_REWARD_PRECISION`
+    pub reward_per_share: u128,
+    
+    /// Rewards in the reward vault that are distributed but not yet claimed
+    pub rewards_unclaimed: u64,
+    
+    /// Rewards distributed over the pool's life
+    pub rewards_distributed: u64,
+    
+    /// Taker fee discounts by stake, with growing minimum stakes
+    pub fee_tiers: [FeeTier; 4],
 }
 
-impl Market {
-    /// Calculate fee for a trade
-    pub fn calculate_fee(&self, trade_value: u64) -> Result<u64, ProgramError> {
-        // Calculate fee based on fee rate
-        let fee = trade_value
-            .checked_mul(self.fee_rate_bps as u64)
-            .ok_or(ProgramError::ArithmeticOverflow)?
-            .checked_div(10000)
-            .ok_or(ProgramError::ArithmeticOverflow)?;
-        
-        Ok(fee)
+impl StakePool {
+    /// Find the stake pool PDA of a program
+    pub fn find_address(program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[STAKE_POOL_SEED], program_id)
+    }
+    
+    /// Find the PDA of the stake pool's vault of staked tokens or of rewards
+    pub fn find_vault_address(program_id: &Pubkey, is_reward: bool) -> (Pubkey, u8) {
+        let seed = if is_reward { STAKE_REWARD_VAULT_SEED } else { STAKE_VAULT_SEED };
+        Pubkey::find_program_address(&[seed], program_id)
+    }
+    
+    /// Check that the used tiers ask for growing stakes and grant growing discounts of at most
+    /// the whole fee
+    pub fn fee_tiers_valid(fee_tiers: &[FeeTier]) -> bool {
+        let used: Vec<_> = fee_tiers.iter().filter(|tier| tier.discount_bps > 0).collect();
+        used.iter().all(|tier| tier.min_stake > 0 && tier.discount_bps as u64 <= math::BPS_DENOMINATOR)
+            && used
+                .windows(2)
+                .all(|pair| pair[0].min_stake < pair[1].min_stake && pair[0].discount_bps < pair[1].discount_bps)
+    }
+    
+    /// Taker fee discount in basis points of the highest tier a stake reaches
+    pub fn discount_bps(&self, stake: u64) -> u16 {
+        self.fee_tiers
+            .iter()
+            .filter(|tier| tier.discount_bps > 0 && stake >= tier.min_stake)
+            .map(|tier| tier.discount_bps)
+            .max()
+            .unwrap_or(0)
+    }
+    
+    /// Unix timestamp the current epoch's rewards may be distributed from
+    pub fn epoch_end_timestamp(&self) -> i64 {
+        self.epoch_start_timestamp.saturating_add(self.epoch_seconds as i64)
+    }
+    
+    /// Distribute `amount` new rewards to the tokens staked
+    ///
+    /// Nothing is distributed while nothing is staked; the rewards wait for the next epoch.
+    /// Returns the rewards distributed.
+    pub fn distribute(&mut self, amount: u64) -> Result<u64, ProgramError> {
+        if self.total_staked == 0 || amount == 0 {
+            return Ok(0);
+        }
+        let per_share = (amount as u128)
+            .checked_mul(STAKE_REWARD_PRECISION)
+            .ok_or(DexError::ArithmeticOverflow)?
+            / self.total_staked as u128;
+        self.reward_per_share = self
+            .reward_per_share
+            .checked_add(per_share)
+            .ok_or(DexError::ArithmeticOverflow)?;
+        self.rewards_unclaimed = self.rewards_unclaimed.checked_add(amount).ok_or(DexError::ArithmeticOverflow)?;
+        self.rewards_distributed = self.rewards_distributed.saturating_add(amount);
+        Ok(amount)
     }
 }
 
-impl Sealed for Market {}
+impl Sealed for StakePool {}
 
-impl IsInitialized for Market {
+impl IsInitialized for StakePool {
     fn is_initialized(&self) -> bool {
         self.is_initialized
     }
 }
 
-impl Pack for Market {
-    const LEN: usize = 1 + 32 + 32 + 32 + 8 + 8 + 2 + 8 + 8 + 8;
+impl Pack for StakePool {
+    const LEN: usize = 1 + 1 + 32 + 32 + 1 + 32 + 32 + 1 + 2 + 8 + 8 + 8 + 8 + 16 + 8 + 8 + STAKE_FEE_TIERS * (8 + 2);
     
     fn pack_into_slice(&self, dst: &mut [u8]) {
         let mut slice = dst;
@@ -77,47 +3365,159 @@ impl Pack for Market {
     }
 }
 
-/// Order state
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
-pub struct Order {
-    /// Is this order initialized
+/// A staker's governance tokens in the stake pool, at the PDA `["stake_account", owner]`
+#[derive(BorshSerialize, BorshDeserialize, ShankAccount, Debug, Clone, PartialEq)]
+pub struct StakeAccount {
+    /// Is this stake account initialized
     pub is_initialized: bool,
     
-    /// Order ID
-    pub order_id: u64,
-    
-    /// Owner of the order
+    /// Staker
     pub owner: Pubkey,
     
-    /// Market this order belongs to
+    /// Governance tokens staked
+    pub amount: u64,
+    
+    /// Pool's rewards per staked token when the rewards owed were last brought up to date
+    pub last_reward_per_share: u128,
+    
+    /// Rewards earned and not yet claimed
+    pub rewards_owed: u64,
+    
+    /// Epoch of the last stake, which locks the tokens until it has been distributed
+    pub stake_epoch: u64,
+}
+
+impl StakeAccount {
+    /// Find the stake account PDA of an owner
+    pub fn find_address(program_id: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[STAKE_ACCOUNT_SEED, owner.as_ref()], program_id)
+    }
+    
+    /// Add the rewards earned since the last update at the pool's `reward_per_share`,
+    /// rounded down
+    pub fn settle_rewards(&mut self, reward_per_share: u128) -> Result<(), ProgramError> {
+        let earned = reward_per_share
+            .checked_sub(self.last_reward_per_share)
+            .and_then(|per_share| per_share.checked_mul(self.amount as u128))
+            .ok_or(DexError::ArithmeticOverflow)?
+            / STAKE_REWARD_PRECISION;
+        self.rewards_owed = u64::try_from(earned)
+            .ok()
+            .and_then(|earned| self.rewards_owed.checked_add(earned))
+            .ok_or(DexError::ArithmeticOverflow)?;
+        self.last_reward_per_share = reward_per_share;
+        Ok(())
+    }
+    
+    /// Whether the tokens may leave the pool in `epoch`
+    pub fn is_unlocked(&self, epoch: u64) -> bool {
+        self.stake_epoch < epoch
+    }
+}
+
+impl Sealed for StakeAccount {}
+
+impl IsInitialized for StakeAccount {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for StakeAccount {
+    const LEN: usize = 1 + 32 + 8 + 16 + 8 + 8;
+    
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap();
+    }
+    
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Self::try_from_slice(src).map_err(|_| ProgramError::InvalidAccountData)
+    }
+}
+
+/// Points a loyalty schedule emits per filled notional from a start time on
+#[derive(BorshSerialize, BorshDeserialize, ShankType, Debug, Default, Clone, Copy, PartialEq)]
+pub struct EmissionPhase {
+    /// Unix timestamp the phase starts at
+    pub start_timestamp: i64,
+    
+    /// Points per quote token a taker fills, in basis points
+    pub taker_points_bps: u32,
+    
+    /// Points per quote token a maker fills, in basis points
+    pub maker_points_bps: u32,
+}
+
+/// Trading incentive schedule of a market, at the PDA `["loyalty_schedule", market]`
+///
+/// The market authority sets the schedule; each fill settled on the market then accrues
+/// points to the taker and the maker in proportion to its quote notional, at the rates of the
+/// phase in force.
+#[derive(BorshSerialize, BorshDeserialize, ShankAccount, Debug, Clone, PartialEq)]
+pub struct LoyaltySchedule {
+    /// Is this loyalty schedule initialized
+    pub is_initialized: bool,
+    
+    /// Market the schedule rewards trading on
     pub market: Pubkey,
     
-    /// Is this a buy order
-    pub is_buy: bool,
+    /// Number of phases used, from the first
+    pub num_phases: u8,
     
-    /// Limit price
-    pub limit_price: u64,
+    /// Emission phases, in order of their start
+    pub phases: [EmissionPhase; 4],
     
-    /// Original quantity
-    pub original_quantity: u64,
+    /// Points accrued on the market over the schedule's life
+    pub total_points: u64,
+}
+
+impl LoyaltySchedule {
+    /// Find the loyalty schedule PDA of a market
+    pub fn find_address(program_id: &Pubkey, market: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[LOYALTY_SCHEDULE_SEED, market.as_ref()], program_id)
+    }
     
-    /// Remaining quantity
-    pub remaining_quantity: u64,
+    /// Check that the used phases start in increasing order
+    pub fn phases_valid(num_phases: u8, phases: &[EmissionPhase]) -> bool {
+        let num_phases = num_phases as usize;
+        num_phases <= phases.len()
+            && phases[..num_phases]
+                .windows(2)
+                .all(|pair| pair[0].start_timestamp < pair[1].start_timestamp)
+    }
     
-    /// Creation timestamp
-    pub creation_timestamp: u64,
+    /// Phase in force at a Unix timestamp, if one has started
+    pub fn phase_at(&self, timestamp: i64) -> Option<&EmissionPhase> {
+        self.phases[..self.num_phases as usize]
+            .iter()
+            .rev()
+            .find(|phase| phase.start_timestamp <= timestamp)
+    }
+    
+    /// Points a fill of `quote_amount` earns its taker and its maker at a Unix timestamp,
+    /// rounded down
+    pub fn fill_points(&self, quote_amount: u64, timestamp: i64) -> Result<(u64, u64), ProgramError> {
+        match self.phase_at(timestamp) {
+            Some(phase) => Ok((
+                math::bps_of(quote_amount, phase.taker_points_bps as u64, Rounding::Down)?,
+                math::bps_of(quote_amount, phase.maker_points_bps as u64, Rounding::Down)?,
+            )),
+            None => Ok((0, 0)),
+        }
+    }
 }
 
-impl Sealed for Order {}
+impl Sealed for LoyaltySchedule {}
 
-impl IsInitialized for Order {
+impl IsInitialized for LoyaltySchedule {
     fn is_initialized(&self) -> bool {
         self.is_initialized
     }
 }
 
-impl Pack for Order {
-    const LEN: usize = 1 + 8 + 32 + 32 + 1 + 8 + 8 + 8 + 8;
+impl Pack for LoyaltySchedule {
+    const LEN: usize = 1 + 32 + 1 + LOYALTY_SCHEDULE_PHASES * (8 + 4 + 4) + 8;
     
     fn pack_into_slice(&self, dst: &mut [u8]) {
         let mut slice = dst;
@@ -129,39 +3529,364 @@ impl Pack for Order {
     }
 }
 
-/// Order book side enum
+/// A trader's loyalty points on a market, at the PDA `["loyalty_points", market, owner]`
+#[derive(BorshSerialize, BorshDeserialize, ShankAccount, Debug, Clone, PartialEq)]
+pub struct LoyaltyPoints {
+    /// Is this loyalty points account initialized
+    pub is_initialized: bool,
+    
+    /// Market the points were earned on
+    pub market: Pubkey,
+    
+    /// Trader
+    pub owner: Pubkey,
+    
+    /// Points earned by taking liquidity
+    pub taker_points: u64,
+    
+    /// Points earned by making liquidity
+    pub maker_points: u64,
+    
+    /// Slot of the last fill that earned points
+    pub last_fill_slot: u64,
+}
+
+impl LoyaltyPoints {
+    /// Find the loyalty points PDA of a trader on a market
+    pub fn find_address(program_id: &Pubkey, market: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[LOYALTY_POINTS_SEED, market.as_ref(), owner.as_ref()], program_id)
+    }
+    
+    /// Points earned on both sides
+    pub fn total(&self) -> u64 {
+        self.taker_points.saturating_add(self.maker_points)
+    }
+}
+
+impl Sealed for LoyaltyPoints {}
+
+impl IsInitialized for LoyaltyPoints {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for LoyaltyPoints {
+    const LEN: usize = 1 + 32 + 32 + 8 + 8 + 8;
+    
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap();
+    }
+    
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Self::try_from_slice(src).map_err(|_| ProgramError::InvalidAccountData)
+    }
+}
+
+/// Audit event logged when a settled trade is busted
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
-pub enum OrderBookSide {
-    /// Bids (buy orders)
-    Bids,
-    /// Asks (sell orders)
-    Asks,
+pub struct TradeBust {
+    /// Market the trade settled on
+    pub market: Pubkey,
+    
+    /// Settlement record of the busted trade
+    pub settlement: Pubkey,
+    
+    /// Market authority that busted the trade
+    pub authority: Pubkey,
+    
+    /// Base token amount returned to the maker
+    pub base_amount: u64,
+    
+    /// Quote token amount returned to the taker
+    pub quote_amount: u64,
+    
+    /// Slot of the original settlement
+    pub settlement_slot: u64,
+    
+    /// Slot of the bust
+    pub bust_slot: u64,
 }
 
-/// Trade information
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
-pub struct Trade {
-    /// Maker order ID
-    pub maker_order_id: u64,
+/// Event logged for every settled fill, keyed by the market's fill sequence number
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct Fill {
+    /// Market the fill settled on
+    pub market: Pubkey,
     
-    /// Taker order ID
-    pub taker_order_id: u64,
+    /// Sequence number of the fill within the market, starting at 0
+    pub seq_num: u64,
     
-    /// Maker
-    pub maker: Pubkey,
+    /// Taker order account
+    pub taker_order: Pubkey,
     
-    /// Taker
-    pub taker: Pubkey,
+    /// Maker order account
+    pub maker_order: Pubkey,
     
-    /// Trade price
-    pub price: u64,
+    /// Base token amount filled
+    pub base_amount: u64,
     
-    /// Trade quantity
+    /// Quote token amount of the fill before fees
+    pub quote_amount: u64,
+    
+    /// Fee paid by the taker
+    pub taker_fee: u64,
+    
+    /// Fee paid by the maker; negative for a rebate
+    pub maker_fee: i64,
+    
+    /// Slot of the settlement
+    pub slot: u64,
+    
+    /// Memo of the taker order, empty if none
+    pub taker_memo: String,
+    
+    /// Memo of the maker order, empty if none
+    pub maker_memo: String,
+}
+
+/// Event logged for every deposit into or draw from a market's insurance fund
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct InsuranceFundTransfer {
+    /// Market of the insurance fund
+    pub market: Pubkey,
+    
+    /// Token account the tokens came from or went to
+    pub token_account: Pubkey,
+    
+    /// Whether the transfer was a deposit rather than a draw
+    pub is_deposit: bool,
+    
+    /// Quote token amount transferred
+    pub amount: u64,
+    
+    /// Insurance fund balance after the transfer
+    pub balance: u64,
+    
+    /// Slot of the transfer
+    pub slot: u64,
+}
+
+/// Event logged when an order is placed on the book
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct OrderPlaced {
+    /// Market of the order
+    pub market: Pubkey,
+    
+    /// Order account
+    pub order: Pubkey,
+    
+    /// Order owner
+    pub owner: Pubkey,
+    
+    /// Order ID assigned by the market
+    pub order_id: u64,
+    
+    /// Is this a buy order
+    pub is_buy: bool,
+    
+    /// Limit price
+    pub limit_price: u64,
+    
+    /// Order quantity
     pub quantity: u64,
     
-    /// Trade side (true if taker is buyer)
-    pub taker_side: bool,
+    /// Iceberg tranche size, 0 for a fully displayed order
+    pub display_quantity: u64,
     
-    /// Timestamp
-    pub timestamp: u64,
+    /// Amount locked in the escrow vault, in quote tokens for buys and base tokens for sells
+    pub locked_amount: u64,
+    
+    /// Slot of the placement
+    pub slot: u64,
+}
+
+/// Return data set by PlaceLimitOrder and PlaceIcebergOrder
+///
+/// CPI callers read it with `get_return_data` and clients from a simulation or the
+/// transaction's metadata, without fetching the order account.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct PlacedOrder {
+    /// Order ID assigned by the market
+    pub order_id: u64,
+    
+    /// Sequence number the market gives its next fill, so fills of the order are numbered
+    /// from it on
+    pub fill_seq_num: u64,
+}
+
+/// Event logged when an order is cancelled or reduced
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct OrderCancelled {
+    /// Market of the order
+    pub market: Pubkey,
+    
+    /// Order account
+    pub order: Pubkey,
+    
+    /// Order owner
+    pub owner: Pubkey,
+    
+    /// Order ID assigned by the market
+    pub order_id: u64,
+    
+    /// Is this a buy order
+    pub is_buy: bool,
+    
+    /// Limit price
+    pub limit_price: u64,
+    
+    /// Quantity removed from the book
+    pub cancelled_quantity: u64,
+    
+    /// Quantity still resting; 0 once the order is closed
+    pub remaining_quantity: u64,
+    
+    /// Amount refunded from the escrow vault
+    pub refund_amount: u64,
+    
+    /// Slot of the cancel
+    pub slot: u64,
+}
+
+/// Event logged when a market's fee vault is swept
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct FeesSwept {
+    /// Market of the fee vault
+    pub market: Pubkey,
+    
+    /// Token account the fees were swept to
+    pub destination: Pubkey,
+    
+    /// Quote token amount swept
+    pub amount: u64,
+    
+    /// Slot of the sweep
+    pub slot: u64,
+}
+
+/// Market parameter changed by a `ParamsUpdated` event, with its new value
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub enum MarketParam {
+    /// Instruction introspection guard
+    InstructionGuard(InstructionGuard),
+    /// Share of the taker fee paid to referrers, in basis points
+    ReferralShare(u16),
+    /// Trading status
+    Status(MarketStatus),
+    /// Whether only whitelisted owners may place orders
+    WhitelistEnabled(bool),
+    /// Fee authority
+    FeeAuthority(Pubkey),
+    /// Reference price oracle and its limits
+    Oracle {
+        /// Pyth price account, or the default pubkey for none
+        oracle: Pubkey,
+        /// Maximum distance of an order's price from the oracle price in basis points
+        band_bps: u16,
+        /// Maximum age of the oracle price in slots
+        max_staleness_slots: u64,
+        /// Exponent of one unit of the market's limit prices in the oracle's price units
+        price_expo: i32,
+    },
+    /// Minimum notional of a new order in quote tokens
+    MinQuoteNotional(u64),
+    /// Shares of swept fees paid to the protocol treasury and the market creator
+    FeeSplit {
+        /// Treasury share in basis points
+        treasury_share_bps: u16,
+        /// Creator share in basis points
+        creator_share_bps: u16,
+    },
+    /// Volatility-scaled taker fee
+    DynamicFees {
+        /// Taker fee of a calm market in basis points
+        min_fee_bps: u16,
+        /// Realized volatility per fill in basis points at which the full taker fee applies
+        volatility_bps: u16,
+        /// Number of fills the volatility estimate averages over, zero for off
+        volatility_window: u16,
+    },
+}
+
+/// Event logged when a market parameter changes
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct ParamsUpdated {
+    /// Market whose parameter changed
+    pub market: Pubkey,
+    
+    /// Signer that made the change; the default key when a permissionless oracle check halted the market
+    pub authority: Pubkey,
+    
+    /// Parameter and its new value
+    pub param: MarketParam,
+    
+    /// Slot of the change
+    pub slot: u64,
+}
+
+/// Event logged by the program with `sol_log_data`
+///
+/// Each event is logged as two fields: its name and its Borsh encoding.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DexEvent {
+    /// An order was placed
+    OrderPlaced(OrderPlaced),
+    /// An order was cancelled or reduced
+    OrderCancelled(OrderCancelled),
+    /// A trade settled
+    Fill(Fill),
+    /// A fee vault was swept
+    FeesSwept(FeesSwept),
+    /// A market parameter changed
+    ParamsUpdated(ParamsUpdated),
+    /// A settled trade was busted
+    TradeBust(TradeBust),
+    /// An insurance fund was deposited into or drawn from
+    InsuranceFund(InsuranceFundTransfer),
+}
+
+impl DexEvent {
+    /// Name logged as the first field of the event
+    pub fn name(&self) -> &'static [u8] {
+        match self {
+            Self::OrderPlaced(_) => b"OrderPlaced",
+            Self::OrderCancelled(_) => b"OrderCancelled",
+            Self::Fill(_) => b"Fill",
+            Self::FeesSwept(_) => b"FeesSwept",
+            Self::ParamsUpdated(_) => b"ParamsUpdated",
+            Self::TradeBust(_) => b"TradeBust",
+            Self::InsuranceFund(_) => b"InsuranceFund",
+        }
+    }
+
+    /// Borsh encoding logged as the second field of the event
+    pub fn data(&self) -> Result<Vec<u8>, ProgramError> {
+        let data = match self {
+            Self::OrderPlaced(event) => event.try_to_vec(),
+            Self::OrderCancelled(event) => event.try_to_vec(),
+            Self::Fill(event) => event.try_to_vec(),
+            Self::FeesSwept(event) => event.try_to_vec(),
+            Self::ParamsUpdated(event) => event.try_to_vec(),
+            Self::TradeBust(event) => event.try_to_vec(),
+            Self::InsuranceFund(event) => event.try_to_vec(),
+        };
+        data.map_err(|_| ProgramError::InvalidAccountData)
+    }
+
+    /// Decode an event from its logged name and data; unknown names and malformed data are `None`
+    pub fn decode(name: &[u8], data: &[u8]) -> Option<Self> {
+        let event = match name {
+            b"OrderPlaced" => Self::OrderPlaced(OrderPlaced::try_from_slice(data).ok()?),
+            b"OrderCancelled" => Self::OrderCancelled(OrderCancelled::try_from_slice(data).ok()?),
+            b"Fill" => Self::Fill(Fill::try_from_slice(data).ok()?),
+            b"FeesSwept" => Self::FeesSwept(FeesSwept::try_from_slice(data).ok()?),
+            b"ParamsUpdated" => Self::ParamsUpdated(ParamsUpdated::try_from_slice(data).ok()?),
+            b"TradeBust" => Self::TradeBust(TradeBust::try_from_slice(data).ok()?),
+            b"InsuranceFund" => Self::InsuranceFund(InsuranceFundTransfer::try_from_slice(data).ok()?),
+            _ => return None,
+        };
+        Some(event)
+    }
 }
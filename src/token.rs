@@ -0,0 +1,87 @@
+// SPL Token and Token-2022 helpers
+//
+// A market side can use a mint of either token program. Token-2022 mints and accounts may
+// carry extensions after the base state, so both are read through `StateWithExtensions`,
+// which also reads plain SPL Token accounts. A mint with a transfer fee withholds part of
+// every transfer in the receiving account: transfers into the program's vaults are grossed up
+// by the fee so that exactly the amount the program accounts for arrives, and transfers out
+// of them deliver the amount less the fee.
+
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use spl_token_2022::{
+    extension::{transfer_fee::TransferFeeConfig, BaseStateWithExtensions, ExtensionType, StateWithExtensions},
+    state::{Account, Mint},
+};
+
+/// Mint extensions a market accepts; the others can move, freeze or refuse the tokens in its
+/// vaults, or need accounts the program does not pass
+pub const SUPPORTED_MINT_EXTENSIONS: [ExtensionType; 9] = [
+    ExtensionType::TransferFeeConfig,
+    ExtensionType::MintCloseAuthority,
+    ExtensionType::InterestBearingConfig,
+    ExtensionType::MetadataPointer,
+    ExtensionType::TokenMetadata,
+    ExtensionType::GroupPointer,
+    ExtensionType::TokenGroup,
+    ExtensionType::GroupMemberPointer,
+    ExtensionType::TokenGroupMember,
+];
+
+/// Whether a program is SPL Token or Token-2022
+pub fn is_token_program(program_id: &Pubkey) -> bool {
+    *program_id == spl_token::id() || *program_id == spl_token_2022::id()
+}
+
+/// Unpack the base state of a token account of either program
+pub fn unpack_account(data: &[u8]) -> Result<Account, ProgramError> {
+    Ok(StateWithExtensions::<Account>::unpack(data)?.base)
+}
+
+/// Unpack the base state of a mint of either program
+pub fn unpack_mint(data: &[u8]) -> Result<Mint, ProgramError> {
+    Ok(StateWithExtensions::<Mint>::unpack(data)?.base)
+}
+
+/// First extension of a mint that markets do not accept
+pub fn unsupported_mint_extension(data: &[u8]) -> Result<Option<ExtensionType>, ProgramError> {
+    let extension_types = StateWithExtensions::<Mint>::unpack(data)?.get_extension_types()?;
+    Ok(extension_types
+        .into_iter()
+        .find(|extension_type| !SUPPORTED_MINT_EXTENSIONS.contains(extension_type)))
+}
+
+/// Size of a token account of a mint, with room for the extensions the mint requires
+pub fn account_len(mint_data: &[u8]) -> Result<usize, ProgramError> {
+    let mint_extension_types = StateWithExtensions::<Mint>::unpack(mint_data)?.get_extension_types()?;
+    let account_extension_types = ExtensionType::get_required_init_account_extensions(&mint_extension_types);
+    ExtensionType::try_calculate_account_len::<Account>(&account_extension_types)
+}
+
+/// Fee withheld from a transfer of `amount` in an epoch (0 for mints without a transfer fee)
+pub fn transfer_fee(mint_data: &[u8], epoch: u64, amount: u64) -> Result<u64, ProgramError> {
+    let mint = StateWithExtensions::<Mint>::unpack(mint_data)?;
+    match mint.get_extension::<TransferFeeConfig>() {
+        Ok(config) => config
+            .calculate_epoch_fee(epoch, amount)
+            .ok_or(ProgramError::ArithmeticOverflow),
+        Err(_) => Ok(0),
+    }
+}
+
+/// Fee to add to a transfer in an epoch so that exactly `amount` arrives
+///
+/// Fails when no transfer delivers exactly `amount`, as with a fee of 100%.
+pub fn inverse_transfer_fee(mint_data: &[u8], epoch: u64, amount: u64) -> Result<u64, ProgramError> {
+    let mint = StateWithExtensions::<Mint>::unpack(mint_data)?;
+    let Ok(config) = mint.get_extension::<TransferFeeConfig>() else {
+        return Ok(0);
+    };
+    let fee = config
+        .calculate_inverse_epoch_fee(epoch, amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    let gross = amount.checked_add(fee).ok_or(ProgramError::ArithmeticOverflow)?;
+    if config.calculate_epoch_fee(epoch, gross) != Some(fee) {
+        return Err(ProgramError::ArithmeticOverflow);
+    }
+    Ok(fee)
+}
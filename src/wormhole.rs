@@ -0,0 +1,303 @@
+// Wormhole core bridge messages publishing market prices to other chains
+
+use solana_program::{
+    account_info::AccountInfo,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey,
+    pubkey::Pubkey,
+    system_program, sysvar,
+};
+#[cfg(feature = "wormhole")]
+use solana_program::{
+    program::{invoke, invoke_signed},
+    system_instruction,
+};
+#[cfg(not(feature = "wormhole"))]
+use crate::error::{return_dex_error, DexError};
+
+/// Wormhole core bridge program the program posts its messages through
+#[cfg(not(feature = "wormhole-devnet"))]
+pub const CORE_BRIDGE_PROGRAM_ID: Pubkey = pubkey!("worm2ZoG2kUd4vFXhvjh93UUH596ayRfgQ2MgjNMTth");
+
+/// Wormhole core bridge program the program posts its messages through
+#[cfg(feature = "wormhole-devnet")]
+pub const CORE_BRIDGE_PROGRAM_ID: Pubkey = pubkey!("3u8hJUVTA4jH1wYAyUur7FFZVQ8H635K3tSHHF4ssjQ5");
+
+/// Wormhole chain id of Solana, the emitter chain of the program's messages
+pub const CHAIN_ID_SOLANA: u16 = 1;
+
+/// Seed of a market's emitter PDA, the address other chains know its messages by
+pub const EMITTER_SEED: &[u8] = b"emitter";
+
+/// Seed of a market's price message PDA, rewritten by every publication
+pub const PRICE_MESSAGE_SEED: &[u8] = b"price_message";
+
+// Seeds of the core bridge's own PDAs
+const BRIDGE_CONFIG_SEED: &[u8] = b"Bridge";
+const FEE_COLLECTOR_SEED: &[u8] = b"fee_collector";
+const SEQUENCE_SEED: &[u8] = b"Sequence";
+
+// Core bridge instruction posting a message into a reusable message account
+const POST_MESSAGE_UNRELIABLE: u8 = 8;
+
+// Offset of the message fee in the core bridge config
+const BRIDGE_FEE_OFFSET: usize = 16;
+
+// Offsets of the fields read from a posted message account, after its magic
+const POSTED_MESSAGE_MAGIC: &[u8] = b"msu";
+const POSTED_CONSISTENCY_LEVEL_OFFSET: usize = 4;
+const POSTED_SUBMISSION_TIME_OFFSET: usize = 41;
+const POSTED_NONCE_OFFSET: usize = 45;
+const POSTED_SEQUENCE_OFFSET: usize = 49;
+const POSTED_EMITTER_CHAIN_OFFSET: usize = 57;
+const POSTED_EMITTER_OFFSET: usize = 59;
+const POSTED_PAYLOAD_OFFSET: usize = 91;
+
+/// Payload id of a price publication
+pub const PRICE_PAYLOAD_ID: u8 = 1;
+
+/// Length of a price publication payload
+pub const PRICE_PAYLOAD_LEN: usize = 113;
+
+/// Prices of a market as published to other chains
+///
+/// Prices are in quote lots per base lot, as on the market. The payload is big-endian, as
+/// contracts on other chains decode Wormhole payloads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PricePayload {
+    /// Market the prices are of
+    pub market: Pubkey,
+    /// Highest resting bid price (0 when there are no bids or the market's best bid is stale)
+    pub best_bid: u64,
+    /// Lowest resting ask price (0 when there are no asks or the market's best ask is stale)
+    pub best_ask: u64,
+    /// Price of the last settled fill (0 before the first fill)
+    pub last_price: u64,
+    /// Exponential moving average of the TWAP oracle at publication (0 without an oracle)
+    pub ema_price: u64,
+    /// Cumulative price of the TWAP oracle at publication (0 without an oracle); the TWAP
+    /// between two publications is the difference of their cumulative prices over the seconds
+    /// between them
+    pub price_cumulative: u128,
+    /// Base token amount of one base lot
+    pub base_lot_size: u64,
+    /// Quote token amount of one quote lot
+    pub quote_lot_size: u64,
+    /// Unix timestamp of the publication
+    pub timestamp: i64,
+    /// Slot of the publication
+    pub slot: u64,
+}
+
+impl PricePayload {
+    /// Encode the payload of a price message
+    pub fn to_bytes(&self) -> [u8; PRICE_PAYLOAD_LEN] {
+        let mut data = [0u8; PRICE_PAYLOAD_LEN];
+        data[0] = PRICE_PAYLOAD_ID;
+        data[1..33].copy_from_slice(self.market.as_ref());
+        data[33..41].copy_from_slice(&self.best_bid.to_be_bytes());
+        data[41..49].copy_from_slice(&self.best_ask.to_be_bytes());
+        data[49..57].copy_from_slice(&self.last_price.to_be_bytes());
+        data[57..65].copy_from_slice(&self.ema_price.to_be_bytes());
+        data[65..81].copy_from_slice(&self.price_cumulative.to_be_bytes());
+        data[81..89].copy_from_slice(&self.base_lot_size.to_be_bytes());
+        data[89..97].copy_from_slice(&self.quote_lot_size.to_be_bytes());
+        data[97..105].copy_from_slice(&self.timestamp.to_be_bytes());
+        data[105..113].copy_from_slice(&self.slot.to_be_bytes());
+        data
+    }
+
+    /// Decode the payload of a price message, None for any other payload
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        if data.len() != PRICE_PAYLOAD_LEN || data[0] != PRICE_PAYLOAD_ID {
+            return None;
+        }
+        Some(Self {
+            market: Pubkey::new_from_array(data[1..33].try_into().ok()?),
+            best_bid: u64::from_be_bytes(data[33..41].try_into().ok()?),
+            best_ask: u64::from_be_bytes(data[41..49].try_into().ok()?),
+            last_price: u64::from_be_bytes(data[49..57].try_into().ok()?),
+            ema_price: u64::from_be_bytes(data[57..65].try_into().ok()?),
+            price_cumulative: u128::from_be_bytes(data[65..81].try_into().ok()?),
+            base_lot_size: u64::from_be_bytes(data[81..89].try_into().ok()?),
+            quote_lot_size: u64::from_be_bytes(data[89..97].try_into().ok()?),
+            timestamp: i64::from_be_bytes(data[97..105].try_into().ok()?),
+            slot: u64::from_be_bytes(data[105..113].try_into().ok()?),
+        })
+    }
+}
+
+/// Message of the core bridge as posted into a message account
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PostedMessage {
+    /// Whether guardians wait for the message to be finalized rather than confirmed
+    pub finalized: bool,
+    /// Unix timestamp the message was posted at
+    pub submission_time: u32,
+    /// Nonce chosen by the publisher
+    pub nonce: u32,
+    /// Sequence of the message among its emitter's messages
+    pub sequence: u64,
+    /// Wormhole chain id of the emitter
+    pub emitter_chain: u16,
+    /// Emitter of the message
+    pub emitter: Pubkey,
+    /// Payload of the message
+    pub payload: Vec<u8>,
+}
+
+impl PostedMessage {
+    /// Decode a message account of the core bridge written by an unreliable post
+    pub fn unpack(data: &[u8]) -> Option<Self> {
+        if data.len() < POSTED_PAYLOAD_OFFSET + 4 || !data.starts_with(POSTED_MESSAGE_MAGIC) {
+            return None;
+        }
+        let payload_len = read_u32(data, POSTED_PAYLOAD_OFFSET) as usize;
+        let payload = data.get(POSTED_PAYLOAD_OFFSET + 4..POSTED_PAYLOAD_OFFSET + 4 + payload_len)?;
+        Some(Self {
+            finalized: data[POSTED_CONSISTENCY_LEVEL_OFFSET] == CONSISTENCY_LEVEL_FINALIZED,
+            submission_time: read_u32(data, POSTED_SUBMISSION_TIME_OFFSET),
+            nonce: read_u32(data, POSTED_NONCE_OFFSET),
+            sequence: u64::from_le_bytes(data[POSTED_SEQUENCE_OFFSET..POSTED_SEQUENCE_OFFSET + 8].try_into().ok()?),
+            emitter_chain: u16::from_le_bytes(
+                data[POSTED_EMITTER_CHAIN_OFFSET..POSTED_EMITTER_CHAIN_OFFSET + 2].try_into().ok()?,
+            ),
+            emitter: Pubkey::new_from_array(data[POSTED_EMITTER_OFFSET..POSTED_EMITTER_OFFSET + 32].try_into().ok()?),
+            payload: payload.to_vec(),
+        })
+    }
+
+    /// Id guardians and explorers know the signed message (VAA) by
+    pub fn vaa_id(&self) -> String {
+        vaa_id(self.emitter_chain, &self.emitter, self.sequence)
+    }
+}
+
+/// Id of the signed message (VAA) of an emitter's message: chain, emitter as hex and sequence
+pub fn vaa_id(emitter_chain: u16, emitter: &Pubkey, sequence: u64) -> String {
+    let emitter_hex: String = emitter.as_ref().iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("{}/{}/{}", emitter_chain, emitter_hex, sequence)
+}
+
+// Consistency levels the guardians wait for before signing a message
+const CONSISTENCY_LEVEL_CONFIRMED: u8 = 0;
+const CONSISTENCY_LEVEL_FINALIZED: u8 = 1;
+
+/// Find a market's emitter PDA
+pub fn find_emitter_address(program_id: &Pubkey, market: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[EMITTER_SEED, market.as_ref()], program_id)
+}
+
+/// Find a market's price message PDA
+pub fn find_price_message_address(program_id: &Pubkey, market: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PRICE_MESSAGE_SEED, market.as_ref()], program_id)
+}
+
+/// Core bridge config account
+pub fn bridge_config_address() -> Pubkey {
+    Pubkey::find_program_address(&[BRIDGE_CONFIG_SEED], &CORE_BRIDGE_PROGRAM_ID).0
+}
+
+/// Core bridge account collecting message fees
+pub fn fee_collector_address() -> Pubkey {
+    Pubkey::find_program_address(&[FEE_COLLECTOR_SEED], &CORE_BRIDGE_PROGRAM_ID).0
+}
+
+/// Core bridge account tracking the next sequence of an emitter's messages
+pub fn sequence_address(emitter: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[SEQUENCE_SEED, emitter.as_ref()], &CORE_BRIDGE_PROGRAM_ID).0
+}
+
+/// Message fee of the core bridge config, None for data too short to be a config
+pub fn bridge_fee(data: &[u8]) -> Option<u64> {
+    Some(u64::from_le_bytes(data.get(BRIDGE_FEE_OFFSET..BRIDGE_FEE_OFFSET + 8)?.try_into().ok()?))
+}
+
+/// Next sequence of a core bridge sequence account, None for data too short to be one
+pub fn next_sequence(data: &[u8]) -> Option<u64> {
+    Some(u64::from_le_bytes(data.get(..8)?.try_into().ok()?))
+}
+
+/// Create a core bridge instruction posting `payload` from `emitter` into the reusable
+/// `message` account, which only takes payloads of the length of its first one
+pub fn post_message_unreliable(
+    message: &Pubkey,
+    emitter: &Pubkey,
+    payer: &Pubkey,
+    nonce: u32,
+    payload: &[u8],
+    finalized: bool,
+) -> Result<Instruction, ProgramError> {
+    let payload_len = u32::try_from(payload.len()).map_err(|_| ProgramError::InvalidArgument)?;
+
+    // Borsh encoding of the instruction: its index, the nonce, the payload and the level
+    let mut data = Vec::with_capacity(10 + payload.len());
+    data.push(POST_MESSAGE_UNRELIABLE);
+    data.extend_from_slice(&nonce.to_le_bytes());
+    data.extend_from_slice(&payload_len.to_le_bytes());
+    data.extend_from_slice(payload);
+    data.push(if finalized {
+        CONSISTENCY_LEVEL_FINALIZED
+    } else {
+        CONSISTENCY_LEVEL_CONFIRMED
+    });
+
+    Ok(Instruction {
+        program_id: CORE_BRIDGE_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(bridge_config_address(), false),
+            AccountMeta::new(*message, true),
+            AccountMeta::new_readonly(*emitter, true),
+            AccountMeta::new(sequence_address(emitter), false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(fee_collector_address(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data,
+    })
+}
+
+/// Pay the bridge fee and post a price message through the core bridge, signing for the
+/// market's emitter and message PDAs
+///
+/// `accounts` are those of the post message instruction followed by the core bridge program.
+#[cfg(feature = "wormhole")]
+pub fn post_price_message(
+    accounts: &[AccountInfo],
+    fee: u64,
+    instruction: &Instruction,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<(), ProgramError> {
+    // Payer at 4, fee collector at 5 and system program at 8, as the core bridge takes them
+    if fee > 0 {
+        invoke(
+            &system_instruction::transfer(accounts[4].key, accounts[5].key, fee),
+            &[accounts[4].clone(), accounts[5].clone(), accounts[8].clone()],
+        )?;
+    }
+    invoke_signed(instruction, accounts, signer_seeds)
+}
+
+/// Pay the bridge fee and post a price message through the core bridge, signing for the
+/// market's emitter and message PDAs
+///
+/// The program is built without the `wormhole` feature, so nothing is posted.
+#[cfg(not(feature = "wormhole"))]
+pub fn post_price_message(
+    _accounts: &[AccountInfo],
+    _fee: u64,
+    _instruction: &Instruction,
+    _signer_seeds: &[&[&[u8]]],
+) -> Result<(), ProgramError> {
+    Err(return_dex_error(
+        DexError::InvalidInstructionData,
+        "Program built without the wormhole feature",
+    ))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
@@ -0,0 +1,391 @@
+// Adversary test module: economic attacks that must be unprofitable or rejected
+// 对抗测试模块：必须无利可图或被拒绝的经济攻击
+
+mod common;
+
+#[cfg(test)]
+mod adversary_tests {
+    use super::common;
+    use solana_program::{
+        program_pack::Pack,
+        pubkey::Pubkey,
+    };
+    use solana_program_test::*;
+    use solana_sdk::{
+        account::Account,
+        instruction::InstructionError,
+        signature::{Keypair, Signer},
+        transaction::{Transaction, TransactionError},
+    };
+    use solana_rust_dex::{
+        error::DexError,
+        instruction::{DexInstruction, SelfTradeBehavior},
+        state::{Market, Order, ACCOUNT_VERSION, ORDER_RESERVED_LEN},
+    };
+
+    fn market(authority: &Pubkey, taker_fee_bps: u16, maker_fee_bps: i16) -> Market {
+        Market {
+            authority: *authority,
+            base_mint: Pubkey::new_unique(),
+            quote_mint: Pubkey::new_unique(),
+            min_base_order_size: 100,
+            tick_size: 10,
+            taker_fee_bps,
+            maker_fee_bps,
+            next_order_id: 3,
+            num_bids: 1,
+            num_asks: 1,
+            fee_authority: *authority,
+            ..common::market()
+        }
+    }
+
+    fn order(market: &Pubkey, order_id: u64, is_buy: bool, quantity: u64, remaining_quantity: u64) -> Order {
+        Order {
+            is_initialized: true,
+            order_id,
+            owner: Pubkey::new_unique(),
+            market: *market,
+            is_buy,
+            limit_price: 1000,
+            original_quantity: quantity,
+            remaining_quantity,
+            creation_timestamp: 0,
+            linked_order: Pubkey::default(),
+            display_quantity: 0,
+            visible_quantity: remaining_quantity,
+            referrer: Pubkey::default(),
+            memo: [0; 32],
+            version: ACCOUNT_VERSION,
+            reserved: [0; ORDER_RESERVED_LEN],
+        }
+    }
+
+    fn program_account<T: Pack>(state: &T, owner: &Pubkey) -> Account {
+        let mut data = vec![0; T::LEN];
+        state.pack_into_slice(&mut data);
+        Account {
+            lamports: 1000000000,
+            data,
+            owner: *owner,
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    fn custom_error(err: TransactionError) -> u32 {
+        match err {
+            TransactionError::InstructionError(0, InstructionError::Custom(code)) => code,
+            err => panic!("unexpected error: {:?}", err),
+        }
+    }
+
+    #[test]
+    fn test_fee_rounding_extraction_is_unprofitable() {
+        // Invariant: fees round up, so splitting a trade into fills never pays less than the
+        // fee on the whole trade, and no trade of a fee-charging market is free
+        // 不变量：手续费向上取整，因此把交易拆分为多笔成交所付的手续费不会低于
+        // 整笔交易的手续费，收费市场中也没有免手续费的交易
+        let total = 1_000_000;
+        for taker_fee_bps in [1, 25, 30, 100] {
+            let market = market(&Pubkey::new_unique(), taker_fee_bps, 0);
+            let whole_fee = market.calculate_taker_fee(total).unwrap();
+            assert_eq!(market.calculate_taker_fee(1).unwrap(), 1);
+
+            for piece in [1, 33, 399, 3_999] {
+                let mut split_fee = market.calculate_taker_fee(total % piece).unwrap();
+                for _ in 0..total / piece {
+                    split_fee += market.calculate_taker_fee(piece).unwrap();
+                }
+                assert!(split_fee >= whole_fee, "splitting into {} saves fees at {} bps", piece, taker_fee_bps);
+            }
+        }
+    }
+
+    #[test]
+    fn test_self_trade_cannot_farm_rebates() {
+        // Invariant: a wash trade between two orders of one owner pays the taker fee and earns
+        // the maker rebate; rebates never exceed the taker fee and round down, so the net fee
+        // of every trade is never negative
+        // 不变量：同一所有者两笔订单之间的对敲交易支付吃单手续费并获得挂单返佣；
+        // 返佣不超过吃单手续费且向下取整，因此每笔交易的净手续费都不为负
+        for taker_fee_bps in [0, 1, 7, 25, 100, 10000] {
+            for maker_fee_bps in [-(taker_fee_bps as i16), -(taker_fee_bps as i16) / 2, 0] {
+                let market = market(&Pubkey::new_unique(), taker_fee_bps, maker_fee_bps);
+                for trade_value in (1..20_000).step_by(7) {
+                    let taker_fee = market.calculate_taker_fee(trade_value).unwrap() as i64;
+                    let maker_fee = market.calculate_maker_fee(trade_value).unwrap();
+                    assert!(
+                        taker_fee + maker_fee >= 0,
+                        "wash trade of {} earns {} at {}/{} bps",
+                        trade_value,
+                        -(taker_fee + maker_fee),
+                        taker_fee_bps,
+                        maker_fee_bps,
+                    );
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dust_orders_are_rejected() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+        let base_mint = common::add_mint(&mut program_test, 9, None);
+        let quote_mint = common::add_mint(&mut program_test, 6, None);
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        // One market with a minimum order size and one without
+        // 一个设有最小订单数量的市场和一个没有设置的市场
+        let init_market_ix = |seed: u64, min_base_order_size: u64| {
+            DexInstruction::initialize_market_with_seed(
+                &program_id,
+                &payer.pubkey(),
+                &base_mint,
+                &quote_mint,
+                seed,
+                min_base_order_size,
+                10,
+                25,
+                0,
+            )
+            .unwrap()
+        };
+        let mut transaction = Transaction::new_with_payer(&[init_market_ix(0, 100), init_market_ix(1, 0)], Some(&payer.pubkey()));
+        transaction.sign(&[&payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+        let (market, _) = Market::find_address(&program_id, &base_mint, &quote_mint, 0);
+        let (unbounded_market, _) = Market::find_address(&program_id, &base_mint, &quote_mint, 1);
+
+        // Invariant: every resting order is at least the minimum size, on the tick grid and
+        // never empty, so the book cannot be flooded with orders nobody can trade against
+        // 不变量：每笔挂单都不小于最小数量、价格位于最小变动价位上且数量不为零，
+        // 因此订单簿无法被无人可成交的订单淹没
+        let owner = Keypair::new();
+        let place_order_ix = |market: &Pubkey, limit_price: u64, quantity: u64, display_quantity: u64| {
+            let order_account = Pubkey::new_unique();
+            let token_account = Pubkey::new_unique();
+            if display_quantity == 0 {
+                DexInstruction::place_limit_order(
+                    &program_id,
+                    &owner.pubkey(),
+                    market,
+                    &order_account,
+                    &token_account,
+                    &spl_token::id(),
+                    true,
+                    limit_price,
+                    quantity,
+                    SelfTradeBehavior::DecrementTake,
+                )
+            } else {
+                DexInstruction::place_iceberg_order(
+                    &program_id,
+                    &owner.pubkey(),
+                    market,
+                    &order_account,
+                    &token_account,
+                    &spl_token::id(),
+                    true,
+                    limit_price,
+                    quantity,
+                    display_quantity,
+                    SelfTradeBehavior::DecrementTake,
+                )
+            }
+            .unwrap()
+        };
+        let attempts = [
+            (place_order_ix(&market, 1000, 99, 0), DexError::InvalidOrderSize),
+            (place_order_ix(&market, 1005, 100, 0), DexError::InvalidOrderPrice),
+            (place_order_ix(&market, 1000, 500, 1), DexError::InvalidOrderSize),
+            (place_order_ix(&unbounded_market, 1000, 0, 0), DexError::InvalidOrderSize),
+        ];
+        for (ix, expected) in attempts {
+            let mut transaction = Transaction::new_with_payer(&[ix], Some(&payer.pubkey()));
+            transaction.sign(&[&payer, &owner], recent_blockhash);
+            let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+            assert_eq!(custom_error(err), expected as u32);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fill_cannot_outrun_cancel() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // A resting bid and an ask whose quantity was already consumed by a cancel or a fill
+        // 一笔挂着的买单，以及一笔数量已被撤单或成交消耗的卖单
+        let authority = Keypair::new();
+        let market_account = Pubkey::new_unique();
+        let taker = Pubkey::new_unique();
+        let maker = Pubkey::new_unique();
+        program_test.add_account(market_account, program_account(&market(&authority.pubkey(), 25, 0), &program_id));
+        program_test.add_account(taker, program_account(&order(&market_account, 1, true, 100, 100), &program_id));
+        program_test.add_account(maker, program_account(&order(&market_account, 2, false, 100, 0), &program_id));
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        // Invariant: a fill only consumes quantity the order still displays, so a settlement
+        // racing a cancel or an earlier fill cannot trade funds that were already released
+        // 不变量：成交只会消耗订单仍在展示的数量，因此与撤单或先前成交竞争的结算
+        // 无法交易已经释放的资金
+        let settle_ix = DexInstruction::settle_funds(
+            &program_id,
+            &authority.pubkey(),
+            &market_account,
+            &taker,
+            &maker,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &spl_token::id(),
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(&[settle_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &authority], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(custom_error(err), DexError::InvalidOrderSize as u32);
+
+        // The bid keeps its full quantity, so a cancel still refunds all of it
+        // 买单保留全部数量，因此撤单仍会退还全部资金
+        let taker_data = banks_client.get_account(taker).await.unwrap().unwrap();
+        assert_eq!({ Order::unpack_from_slice(&taker_data.data).unwrap().remaining_quantity }, 100);
+
+        // After a partial fill only the unfilled remainder is left to refund
+        // 部分成交后只剩未成交的部分可以退还
+        let mut partially_filled = order(&market_account, 3, true, 100, 100);
+        partially_filled.fill(60, 1).unwrap();
+        assert_eq!({ partially_filled.remaining_quantity }, 40);
+        assert!(partially_filled.fill(41, 2).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_market_closure_cannot_steal_rent() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+        let authority = Keypair::new();
+        let market_account = Pubkey::new_unique();
+        let mut empty_market = market(&authority.pubkey(), 25, 0);
+        empty_market.num_bids = 0;
+        empty_market.num_asks = 0;
+        program_test.add_account(market_account, program_account(&empty_market, &program_id));
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        // Invariant: only the market authority can close a market, so its rent can only be
+        // reclaimed by the account that paid for it
+        // 不变量：只有市场权限账户可以关闭市场，因此其租金只能由支付租金的账户取回
+        let attacker = Keypair::new();
+        let close_ix = DexInstruction::close_market(
+            &program_id,
+            &attacker.pubkey(),
+            &market_account,
+            &attacker.pubkey(),
+            &spl_token::id(),
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(&[close_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &attacker], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(custom_error(err), DexError::AccountNotAuthorized as u32);
+
+        let market_data = banks_client.get_account(market_account).await.unwrap().unwrap();
+        assert_eq!(market_data.lamports, 1000000000);
+        assert!(banks_client.get_account(attacker.pubkey()).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_spoofed_and_foreign_accounts_are_rejected() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // A market with a resting order, another market, and copies of both owned by an
+        // attacker's program
+        // 一个带有挂单的市场、另一个市场，以及由攻击者程序拥有的二者副本
+        let authority = Keypair::new();
+        let attacker_program = Pubkey::new_unique();
+        let [market_account, other_market, spoofed_market, order_account, spoofed_order, foreign_order] =
+            [(); 6].map(|_| Pubkey::new_unique());
+        let market_state = market(&authority.pubkey(), 25, 0);
+        program_test.add_account(market_account, program_account(&market_state, &program_id));
+        program_test.add_account(other_market, program_account(&market_state, &program_id));
+        program_test.add_account(spoofed_market, program_account(&market_state, &attacker_program));
+        let order_state = order(&market_account, 1, false, 100, 100);
+        program_test.add_account(order_account, program_account(&order_state, &program_id));
+        program_test.add_account(spoofed_order, program_account(&order_state, &attacker_program));
+        program_test.add_account(foreign_order, program_account(&order(&other_market, 2, true, 100, 100), &program_id));
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        // Invariant: every market, order and record account is owned by the program, and every
+        // order belongs to the market it is passed with
+        // 不变量：每个市场、订单和记录账户都由程序拥有，且每个订单都属于与其一同传入的市场
+        let owner = Keypair::new();
+        let cancel_ix = |market: &Pubkey, order: &Pubkey| {
+            DexInstruction::cancel_order(
+                &program_id,
+                &owner.pubkey(),
+                market,
+                order,
+                &Pubkey::new_unique(),
+                &spl_token::id(),
+            )
+            .unwrap()
+        };
+        let settle_ix = |taker: &Pubkey| {
+            DexInstruction::settle_funds(
+                &program_id,
+                &authority.pubkey(),
+                &market_account,
+                taker,
+                &order_account,
+                &Pubkey::new_unique(),
+                &Pubkey::new_unique(),
+                &Pubkey::new_unique(),
+                &Pubkey::new_unique(),
+                &Pubkey::new_unique(),
+                &Pubkey::new_unique(),
+                &spl_token::id(),
+            )
+            .unwrap()
+        };
+        let attempts = [
+            (cancel_ix(&spoofed_market, &order_account), &owner, DexError::InvalidAccountOwner),
+            (cancel_ix(&market_account, &spoofed_order), &owner, DexError::InvalidAccountOwner),
+            (cancel_ix(&other_market, &order_account), &owner, DexError::AccountMismatch),
+            (settle_ix(&foreign_order), &authority, DexError::AccountMismatch),
+        ];
+        for (ix, signer, expected) in attempts {
+            let mut transaction = Transaction::new_with_payer(&[ix], Some(&payer.pubkey()));
+            transaction.sign(&[&payer, signer], recent_blockhash);
+            let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+            assert_eq!(custom_error(err), expected as u32);
+        }
+    }
+}
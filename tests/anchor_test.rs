@@ -0,0 +1,200 @@
+// Anchor integration test module
+// Anchor 集成测试模块
+//
+// Run with `cargo test --features anchor --test anchor_test`.
+// 使用 `cargo test --features anchor --test anchor_test` 运行。
+
+#![cfg(feature = "anchor")]
+
+mod common;
+
+#[cfg(test)]
+mod anchor_tests {
+    use super::common;
+    use anchor_lang::{context::CpiContext, prelude::Account};
+    use solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        entrypoint::ProgramResult,
+        program_error::ProgramError,
+        program_pack::Pack,
+        pubkey::Pubkey,
+        rent::Rent,
+        system_instruction, system_program,
+    };
+    use solana_program_test::*;
+    use solana_sdk::{
+        instruction::{AccountMeta, Instruction},
+        signature::{Keypair, Signer},
+        transaction::Transaction,
+    };
+    use solana_rust_dex::{
+        anchor::cpi,
+        error::DexError,
+        instruction::DexInstruction,
+        state::{InstructionGuard, Market, MarketStatus, OpenOrders, ACCOUNT_VERSION, MARKET_RESERVED_LEN},
+    };
+
+    fn market(authority: &Pubkey) -> Market {
+        Market {
+            authority: *authority,
+            base_mint: Pubkey::new_unique(),
+            quote_mint: Pubkey::new_unique(),
+            min_base_order_size: 100,
+            tick_size: 10,
+            taker_fee_bps: 25,
+            next_order_id: 0,
+            fee_authority: *authority,
+            ..common::market()
+        }
+    }
+
+    #[test]
+    fn test_anchor_accounts_and_errors() {
+        let authority = Pubkey::new_unique();
+        let key = Pubkey::new_unique();
+        let program_id = solana_rust_dex::id();
+        let mut lamports = 1_000_000_000;
+        let mut data = vec![0; Market::LEN];
+        market(&authority).pack_into_slice(&mut data);
+
+        // A market owned by the program loads as an Anchor account
+        // 由程序拥有的市场可以作为 Anchor 账户加载
+        let info = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &program_id, false, 0);
+        let account = Account::<Market>::try_from(&info).unwrap();
+        assert_eq!(account.authority, authority);
+        assert_eq!({ account.tick_size }, 10);
+
+        // Accounts of another program or of another type are refused
+        // 其他程序的账户或其他类型的账户会被拒绝
+        let other_program = Pubkey::new_unique();
+        let mut lamports = 1_000_000_000;
+        let mut data = vec![0; Market::LEN];
+        market(&authority).pack_into_slice(&mut data);
+        let info = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &other_program, false, 0);
+        assert!(Account::<Market>::try_from(&info).is_err());
+
+        let mut lamports = 1_000_000_000;
+        let mut data = vec![0; OpenOrders::LEN];
+        let info = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &program_id, false, 0);
+        assert!(Account::<Market>::try_from(&info).is_err());
+        assert!(Account::<OpenOrders>::try_from(&info).is_err());
+
+        // Program errors keep their custom code
+        // 程序错误保留其自定义错误码
+        let error: anchor_lang::error::Error = DexError::MarketNotActive.into();
+        assert_eq!(
+            ProgramError::from(error),
+            ProgramError::Custom(DexError::MarketNotActive as u32)
+        );
+    }
+
+    // A program depositing for its caller through the Anchor CPI helpers
+    // 通过 Anchor CPI 辅助函数为调用者存款的程序
+    fn caller_process_instruction(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let dex_program = next_account_info(account_info_iter)?;
+        let deposit = cpi::accounts::Deposit {
+            owner: next_account_info(account_info_iter)?.clone(),
+            market: next_account_info(account_info_iter)?.clone(),
+            open_orders: next_account_info(account_info_iter)?.clone(),
+            owner_token_account: next_account_info(account_info_iter)?.clone(),
+            vault: next_account_info(account_info_iter)?.clone(),
+            token_program: next_account_info(account_info_iter)?.clone(),
+            system_program: next_account_info(account_info_iter)?.clone(),
+        };
+        let amount = u64::from_le_bytes(data.try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
+        cpi::deposit(CpiContext::new(dex_program.clone(), deposit), true, amount)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_anchor_cpi_deposit() {
+        // Create program test environment with the DEX and a calling program
+        // 创建包含 DEX 和调用程序的程序测试环境
+        let program_id = solana_rust_dex::id();
+        let caller_program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+        program_test.add_program("caller", caller_program_id, processor!(caller_process_instruction));
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        // Set up a market with its escrow vaults
+        // 设置带有托管金库的市场
+        let market_account = Keypair::new();
+        let market_authority = Keypair::new();
+        let base_mint = common::create_mint(&mut banks_client, &payer, recent_blockhash, 9).await;
+        let quote_mint = common::create_mint(&mut banks_client, &payer, recent_blockhash, 6).await;
+        let create_market_ix = system_instruction::create_account(
+            &payer.pubkey(),
+            &market_account.pubkey(),
+            Rent::default().minimum_balance(Market::LEN),
+            Market::LEN as u64,
+            &program_id,
+        );
+        let fund_authority_ix = system_instruction::transfer(&payer.pubkey(), &market_authority.pubkey(), 1_000_000_000);
+        let init_market_ix = DexInstruction::initialize_market(
+            &program_id,
+            &market_authority.pubkey(),
+            &market_account.pubkey(),
+            &base_mint,
+            &quote_mint,
+            100,
+            10,
+            25,
+            0,
+        )
+        .unwrap();
+        let init_vaults_ix = DexInstruction::initialize_market_vaults(
+            &program_id,
+            &payer.pubkey(),
+            &market_account.pubkey(),
+            &base_mint,
+            &quote_mint,
+            &spl_token::id(),
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(
+            &[create_market_ix, fund_authority_ix, init_market_ix, init_vaults_ix],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &market_authority, &market_account], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // Fund the owner, who pays for its OpenOrders account
+        // 为所有者注资，由其支付 OpenOrders 账户的费用
+        let owner = Keypair::new();
+        let owner_quote =
+            common::create_token_account(&mut banks_client, &payer, recent_blockhash, &quote_mint, &owner.pubkey()).await;
+        common::mint_to(&mut banks_client, &payer, recent_blockhash, &quote_mint, &owner_quote, 2_000_000).await;
+        let open_orders = OpenOrders::find_address(&program_id, &market_account.pubkey(), &owner.pubkey()).0;
+        let quote_vault = Market::find_quote_vault_address(&program_id, &market_account.pubkey()).0;
+
+        // The calling program deposits into the owner's free balance through the CPI helper
+        // 调用程序通过 CPI 辅助函数存入所有者的可用余额
+        let fund_owner_ix = system_instruction::transfer(&payer.pubkey(), &owner.pubkey(), 1_000_000_000);
+        let caller_ix = Instruction {
+            program_id: caller_program_id,
+            accounts: vec![
+                AccountMeta::new_readonly(program_id, false),
+                AccountMeta::new(owner.pubkey(), true),
+                AccountMeta::new(market_account.pubkey(), false),
+                AccountMeta::new(open_orders, false),
+                AccountMeta::new(owner_quote, false),
+                AccountMeta::new(quote_vault, false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+            data: 750_000u64.to_le_bytes().to_vec(),
+        };
+        let mut transaction = Transaction::new_with_payer(&[fund_owner_ix, caller_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &owner], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        let open_orders_data = banks_client.get_account(open_orders).await.unwrap().unwrap();
+        assert_eq!(OpenOrders::unpack_from_slice(&open_orders_data.data).unwrap().quote_free, 750_000);
+        assert_eq!(common::token_balance(&mut banks_client, &owner_quote).await, 1_250_000);
+    }
+}
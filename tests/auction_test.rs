@@ -0,0 +1,461 @@
+// Batch auction test module
+// 集合竞价测试模块
+
+mod common;
+
+#[cfg(test)]
+mod auction_tests {
+    use super::common;
+    use solana_program::{program_pack::Pack, pubkey::Pubkey, system_instruction};
+    use solana_program_test::*;
+    use solana_sdk::{
+        account::Account,
+        instruction::{Instruction, InstructionError},
+        signature::{Keypair, Signer},
+        transaction::{Transaction, TransactionError},
+    };
+    use solana_rust_dex::{
+        error::DexError,
+        instruction::{DexInstruction, RouteOrder, SelfTradeBehavior},
+        state::{
+            BatchAuction, Market, Order, MAX_AUCTION_ORDERS,
+        },
+    };
+
+    fn custom_error(err: TransactionError) -> u32 {
+        match err {
+            TransactionError::InstructionError(0, InstructionError::Custom(code)) => code,
+            err => panic!("unexpected error: {:?}", err),
+        }
+    }
+
+    fn market(authority: &Pubkey, base_mint: &Pubkey, quote_mint: &Pubkey) -> Market {
+        Market {
+            authority: *authority,
+            base_mint: *base_mint,
+            quote_mint: *quote_mint,
+            taker_fee_bps: 25,
+            next_order_id: 0,
+            fee_authority: *authority,
+            ..common::market()
+        }
+    }
+
+    #[test]
+    fn test_clearing_price_fills_the_most() {
+        // Bids of 100 at 12 and 50 at 10 against asks of 80 at 9 and 100 at 11 cross 100 lots
+        // at 11 or 12, so the midpoint rounds down to 11
+        // 12 价格 100 手和 10 价格 50 手的买单对 9 价格 80 手和 11 价格 100 手的卖单，在 11 或 12
+        // 成交 100 手，因此中点向下取整为 11
+        let bids = [(12, 100), (10, 50)];
+        let asks = [(9, 80), (11, 100)];
+        assert_eq!(BatchAuction::clearing_price(&bids, &asks, 1), Some((11, 100)));
+
+        // A single crossing pair clears at the midpoint of its limits, on a tick
+        // 单个交叉的订单对在其限价的中点成交，并落在价格刻度上
+        assert_eq!(BatchAuction::clearing_price(&[(110, 5)], &[(100, 5)], 1), Some((105, 5)));
+        assert_eq!(BatchAuction::clearing_price(&[(110, 5)], &[(100, 5)], 10), Some((100, 5)));
+        assert_eq!(BatchAuction::clearing_price(&[(120, 5)], &[(100, 5)], 10), Some((110, 5)));
+    }
+
+    #[test]
+    fn test_clearing_price_without_a_cross() {
+        // Nothing clears when the best bid is below the best ask or a side is empty
+        // 最优买价低于最优卖价或某一方为空时不成交
+        assert_eq!(BatchAuction::clearing_price(&[(9, 10)], &[(10, 10)], 1), None);
+        assert_eq!(BatchAuction::clearing_price(&[(9, 10)], &[], 1), None);
+        assert_eq!(BatchAuction::clearing_price(&[], &[], 1), None);
+    }
+
+    #[tokio::test]
+    async fn test_run_auction() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // Add the mints, a market with its signer bump, and two buyers and two sellers with an
+        // empty order account each
+        // 添加代币铸造账户、带签名者 bump 的市场，以及各有一个空订单账户的两个买家和两个卖家
+        let base_mint = common::add_mint(&mut program_test, 6, None);
+        let quote_mint = common::add_mint(&mut program_test, 6, None);
+        let authority = Keypair::new();
+        let market_account = Pubkey::new_unique();
+        let mut market_state = market(&authority.pubkey(), &base_mint, &quote_mint);
+        market_state.signer_bump = Market::find_signer_address(&program_id, &market_account).1;
+        let mut market_data = vec![0; Market::LEN];
+        market_state.pack_into_slice(&mut market_data);
+        program_test.add_account(
+            market_account,
+            Account {
+                lamports: 1000000000,
+                data: market_data,
+                owner: program_id,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+
+        let traders = [(); 4].map(|_| {
+            let trader = Keypair::new();
+            let base = common::add_token_account(&mut program_test, &base_mint, &trader.pubkey(), None, 100_000);
+            let quote = common::add_token_account(&mut program_test, &quote_mint, &trader.pubkey(), None, 2_000_000);
+            let order_account = Pubkey::new_unique();
+            program_test.add_account(
+                order_account,
+                Account {
+                    lamports: 1000000000,
+                    data: vec![0; Order::LEN],
+                    owner: program_id,
+                    executable: false,
+                    rent_epoch: 0,
+                },
+            );
+            (trader, base, quote, order_account)
+        });
+        let [alice, carol, bob, dave] = &traders;
+
+        // Start the test environment
+        // 启动测试环境
+        let mut context = program_test.start_with_context().await;
+        let payer = context.payer.insecure_clone();
+        let blockhash = context.last_blockhash;
+        let send = |instructions: &[Instruction], signer: &Keypair, blockhash| {
+            let mut transaction = Transaction::new_with_payer(instructions, Some(&payer.pubkey()));
+            transaction.sign(&[&payer, signer], blockhash);
+            transaction
+        };
+
+        // Create the market's vaults and make it auction every 10 slots
+        // 创建市场金库，并使其每 10 个槽位进行一次集合竞价
+        let fund_authority_ix = system_instruction::transfer(&payer.pubkey(), &authority.pubkey(), 1_000_000_000);
+        let init_vaults_ix =
+            DexInstruction::initialize_market_vaults(&program_id, &payer.pubkey(), &market_account, &base_mint, &quote_mint, &spl_token::id())
+                .unwrap();
+        let init_fee_vault_ix =
+            DexInstruction::initialize_fee_vault(&program_id, &payer.pubkey(), &market_account, &quote_mint, &spl_token::id()).unwrap();
+        let init_auction_ix = DexInstruction::initialize_batch_auction(&program_id, &authority.pubkey(), &market_account, 10).unwrap();
+        context
+            .banks_client
+            .process_transaction(send(
+                &[fund_authority_ix, init_vaults_ix, init_fee_vault_ix, init_auction_ix],
+                &authority,
+                blockhash,
+            ))
+            .await
+            .unwrap();
+
+        // Alice bids 100000 at 12 and Carol 50000 at 10; Bob asks 80000 at 9 and Dave 100000 at 11
+        // Alice 以 12 买入 100000，Carol 以 10 买入 50000；Bob 以 9 卖出 80000，Dave 以 11 卖出 100000
+        for ((trader, base, quote, order_account), is_buy, limit_price, quantity) in [
+            (alice, true, 12, 100_000),
+            (carol, true, 10, 50_000),
+            (bob, false, 9, 80_000),
+            (dave, false, 11, 100_000),
+        ] {
+            let place_ix = DexInstruction::place_limit_order(
+                &program_id,
+                &trader.pubkey(),
+                &market_account,
+                order_account,
+                if is_buy { quote } else { base },
+                &spl_token::id(),
+                is_buy,
+                limit_price,
+                quantity,
+                SelfTradeBehavior::DecrementTake,
+            )
+            .unwrap();
+            context
+                .banks_client
+                .process_transaction(send(&[place_ix], trader, blockhash))
+                .await
+                .unwrap();
+        }
+
+        // Crossed orders do not settle one by one on an auction market
+        // 集合竞价市场上交叉的订单不会逐笔结算
+        let settle_ix = DexInstruction::settle_funds(
+            &program_id,
+            &authority.pubkey(),
+            &market_account,
+            &alice.3,
+            &bob.3,
+            &alice.1,
+            &alice.2,
+            &bob.1,
+            &bob.2,
+            &alice.0.pubkey(),
+            &bob.0.pubkey(),
+            &spl_token::id(),
+        )
+        .unwrap();
+        let err = context
+            .banks_client
+            .process_transaction(send(&[settle_ix], &authority, blockhash))
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(custom_error(err), DexError::BatchAuctionMarket as u32);
+
+        let run_ix = |orders: &[&(Keypair, Pubkey, Pubkey, Pubkey)]| {
+            let orders: Vec<_> = orders
+                .iter()
+                .map(|(trader, base, quote, order_account)| RouteOrder {
+                    order: *order_account,
+                    owner: trader.pubkey(),
+                    owner_base_account: *base,
+                    owner_quote_account: *quote,
+                })
+                .collect();
+            DexInstruction::run_auction(&program_id, &market_account, &spl_token::id(), &orders).unwrap()
+        };
+        let run = |instruction: Instruction, blockhash| {
+            let mut transaction = Transaction::new_with_payer(&[instruction], Some(&payer.pubkey()));
+            transaction.sign(&[&payer], blockhash);
+            transaction
+        };
+
+        // The batch stays open for its interval
+        // 批次在其间隔内保持开放
+        let err = context
+            .banks_client
+            .process_transaction(run(run_ix(&[alice, carol, bob, dave]), blockhash))
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(custom_error(err), DexError::AuctionNotDue as u32);
+
+        // Leaving an order out, or passing one twice, is rejected
+        // 遗漏订单或重复传入订单会被拒绝
+        let blockhash = common::warp_slots(&mut context, 10).await;
+        for orders in [[alice, bob, dave].as_slice(), &[alice, bob, dave, dave]] {
+            let err = context
+                .banks_client
+                .process_transaction(run(run_ix(orders), blockhash))
+                .await
+                .unwrap_err()
+                .unwrap();
+            assert_eq!(custom_error(err), DexError::InvalidAccountData as u32);
+        }
+
+        // 100000 lots cross at 11: Alice buys Bob's 80000 and 20000 of Dave's, Carol's bid is
+        // below the price; each side pays a taker fee of 0.25%
+        // 100000 手在 11 成交：Alice 买入 Bob 的 80000 手和 Dave 的 20000 手，Carol 的买价低于成交价；
+        // 双方各支付 0.25% 的吃单手续费
+        context
+            .banks_client
+            .process_transaction(run(run_ix(&[dave, carol, bob, alice]), blockhash))
+            .await
+            .unwrap();
+        assert_eq!(common::token_balance(&mut context.banks_client, &alice.1).await, 200_000);
+        assert_eq!(
+            common::token_balance(&mut context.banks_client, &alice.2).await,
+            2_000_000 - 880_000 - 2_200 - 220_000 - 550
+        );
+        assert_eq!(common::token_balance(&mut context.banks_client, &bob.2).await, 2_000_000 + 880_000 - 2_200);
+        assert_eq!(common::token_balance(&mut context.banks_client, &dave.2).await, 2_000_000 + 220_000 - 550);
+        let fee_vault = Market::find_fee_vault_address(&program_id, &market_account).0;
+        assert_eq!(common::token_balance(&mut context.banks_client, &fee_vault).await, 2 * (2_200 + 550));
+
+        // Filled orders are closed; Carol's bid and the rest of Dave's ask rest for the next batch
+        // 已成交的订单被关闭；Carol 的买单和 Dave 卖单的剩余部分留待下一批次
+        for order_account in [alice.3, bob.3] {
+            assert!(context.banks_client.get_account(order_account).await.unwrap().is_none());
+        }
+        let dave_order = context.banks_client.get_account(dave.3).await.unwrap().unwrap();
+        assert_eq!({ Order::unpack_from_slice(&dave_order.data).unwrap().remaining_quantity }, 80_000);
+        let market_account_data = context.banks_client.get_account(market_account).await.unwrap().unwrap();
+        let market_state = Market::unpack_from_slice(&market_account_data.data).unwrap();
+        assert_eq!(({ market_state.num_bids }, { market_state.num_asks }), (1, 1));
+        assert_eq!({ market_state.fill_seq_num }, 2);
+        assert_eq!({ market_state.last_price }, 11);
+        assert_eq!({ market_state.fees_accrued }, 5_500);
+
+        let batch_auction_address = BatchAuction::find_address(&program_id, &market_account).0;
+        let auction = context.banks_client.get_account(batch_auction_address).await.unwrap().unwrap();
+        let auction = BatchAuction::unpack_from_slice(&auction.data).unwrap();
+        assert_eq!((auction.last_clearing_price, auction.last_volume, auction.auction_count), (11, 100_000, 1));
+
+        // The next batch closes 10 slots later, when the remaining orders do not cross
+        // 下一批次在 10 个槽位后结束，此时剩余订单不交叉
+        let blockhash = context.get_new_latest_blockhash().await.unwrap();
+        let err = context
+            .banks_client
+            .process_transaction(run(run_ix(&[carol, dave]), blockhash))
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(custom_error(err), DexError::AuctionNotDue as u32);
+        let blockhash = common::warp_slots(&mut context, 10).await;
+        context
+            .banks_client
+            .process_transaction(run(run_ix(&[carol, dave]), blockhash))
+            .await
+            .unwrap();
+        let auction = context.banks_client.get_account(batch_auction_address).await.unwrap().unwrap();
+        let auction = BatchAuction::unpack_from_slice(&auction.data).unwrap();
+        assert_eq!((auction.last_clearing_price, auction.last_volume, auction.auction_count), (11, 0, 2));
+    }
+
+    #[tokio::test]
+    async fn test_auction_caps_resting_orders() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // Add the mints, a market with its signer bump, a market with more resting orders than
+        // an auction takes, and a trader with an empty order account for one order past the cap
+        // 添加代币铸造账户、带签名者 bump 的市场、挂单数超过集合竞价上限的市场，
+        // 以及一个交易者和比上限多一个的空订单账户
+        let base_mint = common::add_mint(&mut program_test, 6, None);
+        let quote_mint = common::add_mint(&mut program_test, 6, None);
+        let authority = Keypair::new();
+        let market_account = Pubkey::new_unique();
+        let full_market_account = Pubkey::new_unique();
+        for (address, num_bids) in [(market_account, 0), (full_market_account, MAX_AUCTION_ORDERS + 1)] {
+            let mut market_state = market(&authority.pubkey(), &base_mint, &quote_mint);
+            market_state.signer_bump = Market::find_signer_address(&program_id, &address).1;
+            market_state.num_bids = num_bids;
+            let mut market_data = vec![0; Market::LEN];
+            market_state.pack_into_slice(&mut market_data);
+            program_test.add_account(
+                address,
+                Account {
+                    lamports: 1000000000,
+                    data: market_data,
+                    owner: program_id,
+                    executable: false,
+                    rent_epoch: 0,
+                },
+            );
+        }
+        let trader = Keypair::new();
+        let trader_base = common::add_token_account(&mut program_test, &base_mint, &trader.pubkey(), None, 0);
+        let trader_quote = common::add_token_account(&mut program_test, &quote_mint, &trader.pubkey(), None, 2_000_000);
+        let order_accounts: Vec<Pubkey> = (0..=MAX_AUCTION_ORDERS)
+            .map(|_| {
+                let order_account = Pubkey::new_unique();
+                program_test.add_account(
+                    order_account,
+                    Account {
+                        lamports: 1000000000,
+                        data: vec![0; Order::LEN],
+                        owner: program_id,
+                        executable: false,
+                        rent_epoch: 0,
+                    },
+                );
+                order_account
+            })
+            .collect();
+
+        // Start the test environment
+        // 启动测试环境
+        let mut context = program_test.start_with_context().await;
+        let payer = context.payer.insecure_clone();
+        let blockhash = context.last_blockhash;
+        let send = |instructions: &[Instruction], signer: &Keypair, blockhash| {
+            let mut transaction = Transaction::new_with_payer(instructions, Some(&payer.pubkey()));
+            transaction.sign(&[&payer, signer], blockhash);
+            transaction
+        };
+
+        // A market with more resting orders than an auction takes cannot become an auction market
+        // 挂单数超过集合竞价上限的市场不能成为集合竞价市场
+        let init_full_auction_ix =
+            DexInstruction::initialize_batch_auction(&program_id, &authority.pubkey(), &full_market_account, 10).unwrap();
+        let err = context
+            .banks_client
+            .process_transaction(send(&[init_full_auction_ix], &authority, blockhash))
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(custom_error(err), DexError::OrderBookFull as u32);
+
+        // Create the other market's vaults and make it auction every 10 slots
+        // 创建另一个市场的金库，并使其每 10 个槽位进行一次集合竞价
+        let fund_authority_ix = system_instruction::transfer(&payer.pubkey(), &authority.pubkey(), 1_000_000_000);
+        let init_vaults_ix =
+            DexInstruction::initialize_market_vaults(&program_id, &payer.pubkey(), &market_account, &base_mint, &quote_mint, &spl_token::id())
+                .unwrap();
+        let init_fee_vault_ix =
+            DexInstruction::initialize_fee_vault(&program_id, &payer.pubkey(), &market_account, &quote_mint, &spl_token::id()).unwrap();
+        let init_auction_ix = DexInstruction::initialize_batch_auction(&program_id, &authority.pubkey(), &market_account, 10).unwrap();
+        context
+            .banks_client
+            .process_transaction(send(
+                &[fund_authority_ix, init_vaults_ix, init_fee_vault_ix, init_auction_ix],
+                &authority,
+                blockhash,
+            ))
+            .await
+            .unwrap();
+
+        // The trader bids up to the cap; one more order is rejected
+        // 交易者挂出买单直到上限；再多一个订单会被拒绝
+        let place_ix = |order_account: &Pubkey| {
+            DexInstruction::place_limit_order(
+                &program_id,
+                &trader.pubkey(),
+                &market_account,
+                order_account,
+                &trader_quote,
+                &spl_token::id(),
+                true,
+                10,
+                100,
+                SelfTradeBehavior::DecrementTake,
+            )
+            .unwrap()
+        };
+        let (resting_orders, [extra_order]) = order_accounts.split_at(MAX_AUCTION_ORDERS as usize) else {
+            unreachable!()
+        };
+        for order_account in resting_orders {
+            context
+                .banks_client
+                .process_transaction(send(&[place_ix(order_account)], &trader, blockhash))
+                .await
+                .unwrap();
+        }
+        let err = context
+            .banks_client
+            .process_transaction(send(&[place_ix(extra_order)], &trader, blockhash))
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(custom_error(err), DexError::OrderBookFull as u32);
+
+        // An auction over every resting order at the cap fits in the accounts a transaction locks
+        // 上限数量挂单的集合竞价所需账户不超过一笔交易可锁定的账户数
+        let orders: Vec<_> = resting_orders
+            .iter()
+            .map(|order_account| RouteOrder {
+                order: *order_account,
+                owner: trader.pubkey(),
+                owner_base_account: trader_base,
+                owner_quote_account: trader_quote,
+            })
+            .collect();
+        let run_ix = DexInstruction::run_auction(&program_id, &market_account, &spl_token::id(), &orders).unwrap();
+        assert!(run_ix.accounts.len() <= 64);
+        let blockhash = common::warp_slots(&mut context, 10).await;
+        let mut transaction = Transaction::new_with_payer(&[run_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer], blockhash);
+        context.banks_client.process_transaction(transaction).await.unwrap();
+        let batch_auction_address = BatchAuction::find_address(&program_id, &market_account).0;
+        let auction = context.banks_client.get_account(batch_auction_address).await.unwrap().unwrap();
+        let auction = BatchAuction::unpack_from_slice(&auction.data).unwrap();
+        assert_eq!((auction.last_volume, auction.auction_count), (0, 1));
+    }
+}
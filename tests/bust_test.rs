@@ -0,0 +1,228 @@
+// Trade bust test module
+// 交易撤销测试模块
+
+mod common;
+
+#[cfg(test)]
+mod bust_tests {
+    use super::common;
+    use solana_program::{
+        program_pack::Pack,
+        pubkey::Pubkey,
+    };
+    use solana_program_test::*;
+    use solana_sdk::{
+        account::Account,
+        instruction::{Instruction, InstructionError},
+        signature::{Keypair, Signer},
+        transaction::{Transaction, TransactionError},
+    };
+    use solana_rust_dex::{
+        error::DexError,
+        instruction::DexInstruction,
+        state::{
+            Market, MarketCreationMode, ProgramConfig, SettlementRecord,
+            MAX_ALLOWED_QUOTE_MINTS,
+        },
+    };
+
+    fn token_account(mint: &Pubkey, owner: &Pubkey, amount: u64) -> Account {
+        let token_account = spl_token::state::Account {
+            mint: *mint,
+            owner: *owner,
+            amount,
+            state: spl_token::state::AccountState::Initialized,
+            ..spl_token::state::Account::default()
+        };
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        token_account.pack_into_slice(&mut data);
+        Account {
+            lamports: 1000000000,
+            data,
+            owner: spl_token::id(),
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    fn program_account(program_id: &Pubkey, data: Vec<u8>) -> Account {
+        Account {
+            lamports: 1000000000,
+            data,
+            owner: *program_id,
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    fn custom_error(err: TransactionError) -> u32 {
+        match err {
+            TransactionError::InstructionError(0, InstructionError::Custom(code)) => code,
+            err => panic!("unexpected error: {:?}", err),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bust_trade() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // Add a market, a settled trade and the token accounts of both parties
+        // 添加市场、一笔已结算的交易以及双方的代币账户
+        let authority = Keypair::new();
+        let taker = Keypair::new();
+        let maker = Keypair::new();
+        let market_account = Pubkey::new_unique();
+        let base_mint = Pubkey::new_unique();
+        let quote_mint = Pubkey::new_unique();
+        let market = Market {
+            authority: authority.pubkey(),
+            base_mint,
+            quote_mint,
+            min_base_order_size: 100,
+            tick_size: 10,
+            taker_fee_bps: 25,
+            next_order_id: 0,
+            fee_authority: Pubkey::new_unique(),
+            ..common::market()
+        };
+        let mut market_data = vec![0; Market::LEN];
+        market.pack_into_slice(&mut market_data);
+        program_test.add_account(market_account, program_account(&program_id, market_data));
+
+        let taker_base_account = Pubkey::new_unique();
+        let taker_quote_account = Pubkey::new_unique();
+        let maker_base_account = Pubkey::new_unique();
+        let maker_quote_account = Pubkey::new_unique();
+        program_test.add_account(taker_base_account, token_account(&base_mint, &taker.pubkey(), 500));
+        program_test.add_account(taker_quote_account, token_account(&quote_mint, &taker.pubkey(), 0));
+        program_test.add_account(maker_base_account, token_account(&base_mint, &maker.pubkey(), 0));
+        program_test.add_account(maker_quote_account, token_account(&quote_mint, &maker.pubkey(), 5000));
+
+        let settlement_record = Pubkey::new_unique();
+        let expired_record = Pubkey::new_unique();
+        for address in [settlement_record, expired_record] {
+            let record = SettlementRecord {
+                is_initialized: true,
+                market: market_account,
+                slot: 0,
+                taker_base_account,
+                taker_quote_account,
+                maker_base_account,
+                maker_quote_account,
+                base_amount: 500,
+                maker_quote_amount: 5000,
+                is_busted: false,
+            };
+            let mut record_data = vec![0; SettlementRecord::LEN];
+            record.pack_into_slice(&mut record_data);
+            program_test.add_account(address, program_account(&program_id, record_data));
+        }
+
+        // Add a program config with trade busting disabled
+        // 添加禁用交易撤销的程序配置
+        let admin = Keypair::new();
+        let config = ProgramConfig {
+            is_initialized: true,
+            admin: admin.pubkey(),
+            market_creation_mode: MarketCreationMode::Permissionless,
+            num_allowed_quote_mints: 0,
+            allowed_quote_mints: [Pubkey::default(); MAX_ALLOWED_QUOTE_MINTS],
+            treasury: admin.pubkey(),
+            market_creation_fee_lamports: 0,
+            market_creation_fee_quote: 0,
+            trade_busting_enabled: false,
+            trade_bust_window_slots: 0,
+        };
+        let mut config_data = vec![0; ProgramConfig::LEN];
+        config.pack_into_slice(&mut config_data);
+        let (config_address, _) = ProgramConfig::find_address(&program_id);
+        program_test.add_account(config_address, program_account(&program_id, config_data));
+
+        // Start the test environment
+        // 启动测试环境
+        let mut context = program_test.start_with_context().await;
+        let payer = context.payer.insecure_clone();
+
+        let bust_ix = |record: &Pubkey| -> Instruction {
+            DexInstruction::bust_trade(
+                &program_id,
+                &authority.pubkey(),
+                &market_account,
+                record,
+                &taker.pubkey(),
+                &maker.pubkey(),
+                &taker_base_account,
+                &taker_quote_account,
+                &maker_base_account,
+                &maker_quote_account,
+                &spl_token::id(),
+            )
+            .unwrap()
+        };
+
+        // Busting is rejected while disabled in the program config
+        // 程序配置禁用时撤销被拒绝
+        let mut transaction = Transaction::new_with_payer(&[bust_ix(&settlement_record)], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &authority, &taker, &maker], context.last_blockhash);
+        let err = context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(custom_error(err), DexError::TradeBustingDisabled as u32);
+
+        // Enable busting with a 100 slot window
+        // 启用交易撤销，窗口为 100 个槽位
+        let enable_ix = DexInstruction::set_trade_busting(&program_id, &admin.pubkey(), true, 100).unwrap();
+        let mut transaction = Transaction::new_with_payer(&[enable_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &admin], context.last_blockhash);
+        context.banks_client.process_transaction(transaction).await.unwrap();
+
+        // The maker must consent by signing
+        // 做市方必须签名表示同意
+        let mut unconsented_ix = bust_ix(&settlement_record);
+        unconsented_ix.accounts[5].is_signer = false;
+        let mut transaction = Transaction::new_with_payer(&[unconsented_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &authority, &taker], context.last_blockhash);
+        let err = context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(custom_error(err), DexError::AccountNotAuthorized as u32);
+
+        // With both parties' consent the trade is reversed
+        // 双方同意后交易被撤销
+        let blockhash = context.get_new_latest_blockhash().await.unwrap();
+        let mut transaction = Transaction::new_with_payer(&[bust_ix(&settlement_record)], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &authority, &taker, &maker], blockhash);
+        context.banks_client.process_transaction(transaction).await.unwrap();
+
+        for (address, expected) in [
+            (taker_base_account, 0),
+            (taker_quote_account, 5000),
+            (maker_base_account, 500),
+            (maker_quote_account, 0),
+        ] {
+            let account = context.banks_client.get_account(address).await.unwrap().unwrap();
+            assert_eq!(spl_token::state::Account::unpack(&account.data).unwrap().amount, expected);
+        }
+        let record_account = context.banks_client.get_account(settlement_record).await.unwrap().unwrap();
+        assert!(SettlementRecord::unpack_from_slice(&record_account.data).unwrap().is_busted);
+
+        // A trade can only be busted once
+        // 一笔交易只能撤销一次
+        let blockhash = context.get_new_latest_blockhash().await.unwrap();
+        let mut transaction = Transaction::new_with_payer(&[bust_ix(&settlement_record)], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &authority, &taker, &maker], blockhash);
+        let err = context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(custom_error(err), DexError::InvalidAccountData as u32);
+
+        // Settlements outside the window can no longer be busted
+        // 窗口之外的结算无法再被撤销
+        let blockhash = common::warp_to_slot(&mut context, 500).await;
+        let mut transaction = Transaction::new_with_payer(&[bust_ix(&expired_record)], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &authority, &taker, &maker], blockhash);
+        let err = context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(custom_error(err), DexError::TradeBustWindowExpired as u32);
+    }
+}
@@ -0,0 +1,277 @@
+// Shared test helpers
+// 共享测试辅助函数
+//
+// Initialized SPL mints for tests that create markets, which only accept real mints.
+// 为创建市场的测试提供已初始化的 SPL 代币铸造账户，市场只接受真实的代币铸造账户。
+//
+// Deterministic clock control for time-dependent tests. Each helper returns a fresh
+// blockhash so that a transaction repeated after moving the clock is processed again
+// instead of returning the cached result of the earlier one.
+// 为依赖时间的测试提供确定性的时钟控制。每个辅助函数都会返回新的区块哈希，
+// 使得在调整时钟后重复发送的交易会被重新处理，而不是返回之前交易的缓存结果。
+//
+// A market builder for tests that write market accounts directly, so each states only the
+// fields it depends on.
+// 为直接写入市场账户的测试提供市场构建函数，使每个测试只需写出其依赖的字段。
+
+// Each test file only uses some of the helpers
+// 每个测试文件只使用其中一部分辅助函数
+#![allow(dead_code)]
+
+use solana_program::{
+    clock::Clock, hash::Hash, program_option::COption, program_pack::Pack, pubkey::Pubkey, rent::Rent,
+    system_instruction,
+};
+use solana_program_test::{BanksClient, ProgramTest, ProgramTestContext};
+use solana_rust_dex::state::{InstructionGuard, Market, MarketStatus, ACCOUNT_VERSION, MARKET_RESERVED_LEN};
+use solana_sdk::{
+    account::Account,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+
+// Add an initialized SPL mint to the test environment
+// 将已初始化的 SPL 代币铸造账户添加到测试环境
+pub fn add_mint(program_test: &mut ProgramTest, decimals: u8, freeze_authority: Option<Pubkey>) -> Pubkey {
+    let mint_address = Pubkey::new_unique();
+    let mint = spl_token::state::Mint {
+        mint_authority: COption::Some(Pubkey::new_unique()),
+        decimals,
+        is_initialized: true,
+        freeze_authority: freeze_authority.into(),
+        ..spl_token::state::Mint::default()
+    };
+    let mut data = vec![0; spl_token::state::Mint::LEN];
+    mint.pack_into_slice(&mut data);
+    program_test.add_account(
+        mint_address,
+        Account {
+            lamports: 1000000000,
+            data,
+            owner: spl_token::id(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    mint_address
+}
+
+// Add an initialized SPL token account to the test environment
+// 将已初始化的 SPL 代币账户添加到测试环境
+pub fn add_token_account(
+    program_test: &mut ProgramTest,
+    mint: &Pubkey,
+    owner: &Pubkey,
+    delegate: Option<Pubkey>,
+    amount: u64,
+) -> Pubkey {
+    let token_account_address = Pubkey::new_unique();
+    let token_account = spl_token::state::Account {
+        mint: *mint,
+        owner: *owner,
+        amount,
+        delegate: delegate.into(),
+        delegated_amount: if delegate.is_some() { amount } else { 0 },
+        state: spl_token::state::AccountState::Initialized,
+        ..spl_token::state::Account::default()
+    };
+    let mut data = vec![0; spl_token::state::Account::LEN];
+    token_account.pack_into_slice(&mut data);
+    program_test.add_account(
+        token_account_address,
+        Account {
+            lamports: 1000000000,
+            data,
+            owner: spl_token::id(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    token_account_address
+}
+
+// Create an initialized SPL mint in a running test environment
+// 在运行中的测试环境中创建已初始化的 SPL 代币铸造账户
+pub async fn create_mint(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+    decimals: u8,
+) -> Pubkey {
+    let mint = Keypair::new();
+    let instructions = [
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &mint.pubkey(),
+            Rent::default().minimum_balance(spl_token::state::Mint::LEN),
+            spl_token::state::Mint::LEN as u64,
+            &spl_token::id(),
+        ),
+        spl_token::instruction::initialize_mint(&spl_token::id(), &mint.pubkey(), &payer.pubkey(), None, decimals)
+            .unwrap(),
+    ];
+    let mut transaction = Transaction::new_with_payer(&instructions, Some(&payer.pubkey()));
+    transaction.sign(&[payer, &mint], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    mint.pubkey()
+}
+
+// Create an initialized SPL token account in a running test environment
+// 在运行中的测试环境中创建已初始化的 SPL 代币账户
+pub async fn create_token_account(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+    mint: &Pubkey,
+    owner: &Pubkey,
+) -> Pubkey {
+    let token_account = Keypair::new();
+    let instructions = [
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &token_account.pubkey(),
+            Rent::default().minimum_balance(spl_token::state::Account::LEN),
+            spl_token::state::Account::LEN as u64,
+            &spl_token::id(),
+        ),
+        spl_token::instruction::initialize_account3(&spl_token::id(), &token_account.pubkey(), mint, owner).unwrap(),
+    ];
+    let mut transaction = Transaction::new_with_payer(&instructions, Some(&payer.pubkey()));
+    transaction.sign(&[payer, &token_account], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    token_account.pubkey()
+}
+
+// Mint tokens of a mint created by `create_mint` into a token account
+// 向代币账户铸造由 `create_mint` 创建的代币
+pub async fn mint_to(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+    mint: &Pubkey,
+    token_account: &Pubkey,
+    amount: u64,
+) {
+    let instruction =
+        spl_token::instruction::mint_to(&spl_token::id(), mint, token_account, &payer.pubkey(), &[], amount).unwrap();
+    let mut transaction = Transaction::new_with_payer(&[instruction], Some(&payer.pubkey()));
+    transaction.sign(&[payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+}
+
+// Balance of a token account
+// 代币账户的余额
+pub async fn token_balance(banks_client: &mut BanksClient, token_account: &Pubkey) -> u64 {
+    let account = banks_client.get_account(*token_account).await.unwrap().unwrap();
+    spl_token::state::Account::unpack(&account.data).unwrap().amount
+}
+
+// Current clock of the test validator
+// 测试验证器的当前时钟
+pub async fn clock(context: &mut ProgramTestContext) -> Clock {
+    context.banks_client.get_sysvar::<Clock>().await.unwrap()
+}
+
+// Warp to an absolute slot
+// 跳转到指定的绝对槽位
+pub async fn warp_to_slot(context: &mut ProgramTestContext, slot: u64) -> Hash {
+    context.warp_to_slot(slot).unwrap();
+    context.get_new_latest_blockhash().await.unwrap()
+}
+
+// Warp forward by a number of slots
+// 向前跳转若干个槽位
+pub async fn warp_slots(context: &mut ProgramTestContext, slots: u64) -> Hash {
+    let slot = clock(context).await.slot;
+    warp_to_slot(context, slot + slots).await
+}
+
+// Set the clock's unix timestamp, keeping the current slot
+// 设置时钟的 Unix 时间戳，保持当前槽位不变
+pub async fn set_unix_timestamp(context: &mut ProgramTestContext, unix_timestamp: i64) -> Hash {
+    let clock = Clock {
+        unix_timestamp,
+        ..clock(context).await
+    };
+    context.set_sysvar(&clock);
+    context.get_new_latest_blockhash().await.unwrap()
+}
+
+// Move the clock's unix timestamp forward by a number of seconds
+// 将时钟的 Unix 时间戳向前推进若干秒
+pub async fn advance_unix_timestamp(context: &mut ProgramTestContext, seconds: i64) -> Hash {
+    let unix_timestamp = clock(context).await.unix_timestamp;
+    set_unix_timestamp(context, unix_timestamp + seconds).await
+}
+
+// Market as InitializeMarket leaves it, with default keys, unit order sizes and lots, no fees
+// and 6-decimal SPL Token mints, for tests to override the fields they need with
+// `..common::market()`
+// 与 InitializeMarket 创建后的市场相同，使用默认密钥、单位订单大小和手数、无手续费以及 6 位小数的
+// SPL Token 代币铸造账户，测试通过 `..common::market()` 覆盖所需字段
+pub fn market() -> Market {
+    Market {
+        is_initialized: true,
+        authority: Pubkey::default(),
+        base_mint: Pubkey::default(),
+        quote_mint: Pubkey::default(),
+        min_base_order_size: 1,
+        tick_size: 1,
+        taker_fee_bps: 0,
+        maker_fee_bps: 0,
+        next_order_id: 1,
+        num_bids: 0,
+        num_asks: 0,
+        instruction_guard: InstructionGuard::Disabled,
+        open_orders_root: [0; 32],
+        open_orders_root_leaves: 0,
+        open_orders_root_slot: 0,
+        referral_share_bps: 0,
+        fee_authority: Pubkey::default(),
+        status: MarketStatus::Active,
+        bid_base_total: 0,
+        ask_base_total: 0,
+        bid_notional: 0,
+        ask_notional: 0,
+        whitelist_enabled: false,
+        oracle: Pubkey::default(),
+        oracle_band_bps: 0,
+        oracle_max_staleness_slots: 0,
+        oracle_price_expo: 0,
+        fill_seq_num: 0,
+        base_decimals: 6,
+        quote_decimals: 6,
+        trustless: false,
+        signer_bump: 0,
+        base_lot_size: 1,
+        quote_lot_size: 1,
+        min_quote_notional: 0,
+        base_free_total: 0,
+        quote_free_total: 0,
+        best_bid: 0,
+        best_ask: 0,
+        last_price: 0,
+        last_trade_slot: 0,
+        base_volume: 0,
+        quote_volume: 0,
+        volume_buckets: Default::default(),
+        volume_bucket_hour: 0,
+        twap_oracle_enabled: false,
+        base_token_program: spl_token::id(),
+        quote_token_program: spl_token::id(),
+        version: ACCOUNT_VERSION,
+        flags: 0,
+        fees_accrued: 0,
+        fees_swept: 0,
+        referral_fees_paid: 0,
+        treasury_fee_account: Pubkey::default(),
+        treasury_fee_share_bps: 0,
+        creator_fee_account: Pubkey::default(),
+        creator_fee_share_bps: 0,
+        dynamic_fee_min_bps: 0,
+        dynamic_fee_volatility_bps: 0,
+        volatility_window: 0,
+        realized_variance: 0,
+        reserved: [0; MARKET_RESERVED_LEN],
+    }
+}
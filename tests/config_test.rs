@@ -0,0 +1,381 @@
+// Program config test module
+// 程序配置测试模块
+
+mod common;
+
+#[cfg(test)]
+mod config_tests {
+    use super::common;
+    use solana_program::{
+        program_pack::Pack,
+        pubkey::Pubkey,
+    };
+    use solana_program_test::*;
+    use solana_sdk::{
+        instruction::InstructionError,
+        signature::{Keypair, Signer},
+        transaction::{Transaction, TransactionError},
+    };
+    use solana_rust_dex::{
+        error::DexError,
+        instruction::DexInstruction,
+        state::{Market, MarketCreationMode, ProgramConfig},
+    };
+
+    #[tokio::test]
+    async fn test_curated_market_creation() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // Add market account to the test environment
+        // 将市场账户添加到测试环境
+        let market_account = Keypair::new();
+        program_test.add_account(
+            market_account.pubkey(),
+            solana_sdk::account::Account {
+                lamports: 1000000000,
+                data: vec![0; Market::LEN],
+                owner: program_id,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+
+        // Add the mints to the test environment
+        // 将代币铸造账户添加到测试环境
+        let base_mint = common::add_mint(&mut program_test, 9, None);
+        let usdc_mint = common::add_mint(&mut program_test, 6, None);
+        let other_quote_mint = common::add_mint(&mut program_test, 6, None);
+
+        // Start the test environment
+        // 启动测试环境
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        // Initialize the config with the payer as admin and whitelist one quote mint
+        // 以付款人为管理员初始化配置，并将一个报价代币加入白名单
+        let init_config_ix = DexInstruction::initialize_program_config(&program_id, &payer.pubkey()).unwrap();
+        let update_config_ix = DexInstruction::update_program_config(
+            &program_id,
+            &payer.pubkey(),
+            MarketCreationMode::AdminOnly,
+            vec![usdc_mint],
+        )
+        .unwrap();
+
+        let mut transaction = Transaction::new_with_payer(
+            &[init_config_ix, update_config_ix],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        let (config_address, _) = ProgramConfig::find_address(&program_id);
+        let config_account = banks_client.get_account(config_address).await.unwrap().unwrap();
+        let config = ProgramConfig::unpack_from_slice(&config_account.data).unwrap();
+        assert_eq!(config.admin, payer.pubkey());
+        assert_eq!(config.market_creation_mode, MarketCreationMode::AdminOnly);
+        assert!(config.is_quote_mint_allowed(&usdc_mint));
+
+        // A non-admin may not create markets
+        // 非管理员不能创建市场
+        let outsider = Keypair::new();
+        let init_market_ix = DexInstruction::initialize_market(
+            &program_id,
+            &outsider.pubkey(),
+            &market_account.pubkey(),
+            &base_mint,
+            &usdc_mint,
+            100,
+            10,
+            25,
+            0,
+        )
+        .unwrap();
+
+        let mut transaction = Transaction::new_with_payer(&[init_market_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &outsider], recent_blockhash);
+        let err = banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(DexError::AccountNotAuthorized as u32),
+            )
+        );
+
+        // The admin may not use a quote mint outside the whitelist
+        // 管理员不能使用白名单之外的报价代币
+        let init_market_ix = DexInstruction::initialize_market(
+            &program_id,
+            &payer.pubkey(),
+            &market_account.pubkey(),
+            &base_mint,
+            &other_quote_mint,
+            100,
+            10,
+            25,
+            0,
+        )
+        .unwrap();
+
+        let mut transaction = Transaction::new_with_payer(&[init_market_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer], recent_blockhash);
+        let err = banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(DexError::QuoteMintNotAllowed as u32),
+            )
+        );
+
+        // The admin may create a market quoted in the whitelisted mint
+        // 管理员可以创建以白名单代币报价的市场
+        let init_market_ix = DexInstruction::initialize_market(
+            &program_id,
+            &payer.pubkey(),
+            &market_account.pubkey(),
+            &base_mint,
+            &usdc_mint,
+            100,
+            10,
+            25,
+            0,
+        )
+        .unwrap();
+
+        let mut transaction = Transaction::new_with_payer(&[init_market_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        let market_account_data = banks_client
+            .get_account(market_account.pubkey())
+            .await
+            .unwrap()
+            .unwrap();
+        let market = Market::unpack_from_slice(&market_account_data.data).unwrap();
+        assert!(market.is_initialized);
+        assert_eq!(market.quote_mint, usdc_mint);
+    }
+
+    #[tokio::test]
+    async fn test_market_creation_fee_paid_to_treasury() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // Add market account to the test environment
+        // 将市场账户添加到测试环境
+        let market_account = Keypair::new();
+        program_test.add_account(
+            market_account.pubkey(),
+            solana_sdk::account::Account {
+                lamports: 1000000000,
+                data: vec![0; Market::LEN],
+                owner: program_id,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+
+        // Add the mints to the test environment
+        // 将代币铸造账户添加到测试环境
+        let base_mint = common::add_mint(&mut program_test, 9, None);
+        let quote_mint = common::add_mint(&mut program_test, 6, None);
+
+        // Start the test environment
+        // 启动测试环境
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        // Charge a lamport fee for every new market
+        // 为每个新市场收取 lamport 费用
+        let treasury = Pubkey::new_unique();
+        let fee_lamports = 5_000_000;
+        let init_config_ix = DexInstruction::initialize_program_config(&program_id, &payer.pubkey()).unwrap();
+        let set_fee_ix = DexInstruction::set_market_creation_fee(
+            &program_id,
+            &payer.pubkey(),
+            &treasury,
+            fee_lamports,
+            0,
+        )
+        .unwrap();
+
+        let mut transaction = Transaction::new_with_payer(
+            &[init_config_ix, set_fee_ix],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // Create a market and pay the fee
+        // 创建市场并支付费用
+        let init_market_ix = DexInstruction::initialize_market_with_fee(
+            &program_id,
+            &payer.pubkey(),
+            &market_account.pubkey(),
+            &base_mint,
+            &quote_mint,
+            100,
+            10,
+            25,
+            0,
+            &treasury,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+        )
+        .unwrap();
+
+        let mut transaction = Transaction::new_with_payer(&[init_market_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // Verify the treasury received the fee
+        // 验证金库收到了费用
+        let treasury_account = banks_client.get_account(treasury).await.unwrap().unwrap();
+        assert_eq!(treasury_account.lamports, fee_lamports);
+    }
+    #[tokio::test]
+    async fn test_market_pda_created_by_anyone() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // Add the mints to the test environment
+        // 将代币铸造账户添加到测试环境
+        let base_mint = common::add_mint(&mut program_test, 9, None);
+        let quote_mint = common::add_mint(&mut program_test, 6, None);
+
+        // Start the test environment
+        // 启动测试环境
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        // The config admin charges a lamport fee for every new market
+        // 配置管理员为每个新市场收取 lamport 费用
+        let treasury = Pubkey::new_unique();
+        let fee_lamports = 5_000_000;
+        let init_config_ix = DexInstruction::initialize_program_config(&program_id, &payer.pubkey()).unwrap();
+        let set_fee_ix = DexInstruction::set_market_creation_fee(
+            &program_id,
+            &payer.pubkey(),
+            &treasury,
+            fee_lamports,
+            0,
+        )
+        .unwrap();
+        let fund_creator_ix = |creator: &Pubkey| {
+            solana_sdk::system_instruction::transfer(&payer.pubkey(), creator, 1_000_000_000)
+        };
+        let creator = Keypair::new();
+
+        let mut transaction = Transaction::new_with_payer(
+            &[init_config_ix, set_fee_ix, fund_creator_ix(&creator.pubkey())],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // Anyone creates the canonical market of a pair without a market keypair
+        // 任何人都可以在没有市场密钥对的情况下创建交易对的标准市场
+        let init_market_ix = |creator: &Pubkey, seed: u64| {
+            DexInstruction::initialize_market_with_seed_and_fee(
+                &program_id,
+                creator,
+                &base_mint,
+                &quote_mint,
+                seed,
+                100,
+                10,
+                25,
+                0,
+                &treasury,
+                &Pubkey::new_unique(),
+                &Pubkey::new_unique(),
+            )
+            .unwrap()
+        };
+
+        let mut transaction = Transaction::new_with_payer(&[init_market_ix(&creator.pubkey(), 0)], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &creator], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        let (market_address, _) = Market::find_address(&program_id, &base_mint, &quote_mint, 0);
+        let market_account = banks_client.get_account(market_address).await.unwrap().unwrap();
+        assert_eq!(market_account.owner, program_id);
+        let market = Market::unpack_from_slice(&market_account.data).unwrap();
+        assert!(market.is_initialized);
+        assert_eq!(market.authority, creator.pubkey());
+        assert_eq!(market.base_mint, base_mint);
+        assert_eq!(market.quote_mint, quote_mint);
+
+        let treasury_account = banks_client.get_account(treasury).await.unwrap().unwrap();
+        assert_eq!(treasury_account.lamports, fee_lamports);
+
+        // The canonical market cannot be created twice
+        // 标准市场不能被重复创建
+        let other_creator = Keypair::new();
+        let mut transaction = Transaction::new_with_payer(
+            &[fund_creator_ix(&other_creator.pubkey()), init_market_ix(&other_creator.pubkey(), 0)],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &other_creator], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(1, InstructionError::Custom(DexError::InvalidAccountData as u32))
+        );
+
+        // A market at an address not derived from the pair is rejected
+        // 不是由交易对派生的市场地址会被拒绝
+        let mut wrong_address_ix = init_market_ix(&creator.pubkey(), 1);
+        wrong_address_ix.accounts[1].pubkey = Pubkey::new_unique();
+        let mut transaction = Transaction::new_with_payer(&[wrong_address_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &creator], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(0, InstructionError::Custom(DexError::InvalidAccountData as u32))
+        );
+
+        // Another seed gives a second market of the same pair
+        // 使用其他种子可以为同一交易对创建第二个市场
+        let mut transaction = Transaction::new_with_payer(
+            &[fund_creator_ix(&other_creator.pubkey()), init_market_ix(&other_creator.pubkey(), 1)],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &other_creator], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        let (second_market, _) = Market::find_address(&program_id, &base_mint, &quote_mint, 1);
+        let market_account = banks_client.get_account(second_market).await.unwrap().unwrap();
+        let market = Market::unpack_from_slice(&market_account.data).unwrap();
+        assert_eq!(market.authority, other_creator.pubkey());
+
+        let treasury_account = banks_client.get_account(treasury).await.unwrap().unwrap();
+        assert_eq!(treasury_account.lamports, 2 * fee_lamports);
+    }
+}
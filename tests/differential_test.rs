@@ -0,0 +1,609 @@
+// Differential fill test module
+// 差分成交测试模块
+//
+// Runs random order sequences through the program and through an off-chain model built from
+// the pure fill math (`Market::escrow_amount`, `Market::discounted_settlement_amounts`,
+// `Market::calculate_taker_fee` and `BatchAuction::clearing_price`), and compares token
+// balances, resting orders, book totals and fill events after every step.
+// 将随机订单序列分别交给程序和基于纯成交计算（`Market::escrow_amount`、
+// `Market::discounted_settlement_amounts`、`Market::calculate_taker_fee` 和
+// `BatchAuction::clearing_price`）构建的链下模型执行，并在每一步之后比较代币余额、挂单、
+// 订单簿汇总和成交事件。
+
+mod common;
+
+#[cfg(test)]
+mod differential_tests {
+    use super::common;
+    use solana_program::{program_pack::Pack, pubkey::Pubkey, system_instruction};
+    use solana_program_test::*;
+    use solana_sdk::{
+        account::Account,
+        instruction::{AccountMeta, Instruction},
+        signature::{Keypair, Signer},
+        transaction::Transaction,
+    };
+    use solana_rust_dex::{
+        instruction::{DexInstruction, RouteOrder, SelfTradeBehavior},
+        state::{BatchAuction, EventQueue, Market, Order, SettlementAmounts, MAX_AUCTION_ORDERS},
+    };
+
+    const SEEDS: u64 = 6;
+    const STEPS: usize = 60;
+    const AUCTION_ROUNDS: usize = 4;
+
+    // Xorshift generator, so every run replays the same sequences
+    // Xorshift 生成器，使每次运行都重放相同的序列
+    struct Rng(u64);
+
+    impl Rng {
+        // Spread the seed over all bits, as nearby xorshift states start out correlated
+        // 将种子分散到所有位上，因为相近的 xorshift 状态起初是相关的
+        fn new(seed: u64) -> Self {
+            let mut rng = Rng(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1);
+            for _ in 0..16 {
+                rng.next();
+            }
+            rng
+        }
+
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, bound: u64) -> u64 {
+            self.next() % bound
+        }
+    }
+
+    struct Trader {
+        keypair: Keypair,
+        base_account: Pubkey,
+        quote_account: Pubkey,
+    }
+
+    #[derive(Clone, Copy)]
+    struct ModelOrder {
+        account: Pubkey,
+        trader: usize,
+        order_id: u64,
+        is_buy: bool,
+        price: u64,
+        remaining: u64,
+    }
+
+    // Expected state of the market, its vaults and its traders
+    // 市场、其金库及其交易者的预期状态
+    struct Model {
+        market: Market,
+        orders: Vec<ModelOrder>,
+        base_balances: Vec<u64>,
+        quote_balances: Vec<u64>,
+        base_vault: u64,
+        quote_vault: u64,
+        fee_vault: u64,
+        fills: Vec<(u64, Pubkey, Pubkey, u64, u64, u64)>,
+        last_price: u64,
+        base_volume: u128,
+        quote_volume: u128,
+    }
+
+    impl Model {
+        fn new(market: Market, traders: usize, base_balance: u64, quote_balance: u64) -> Self {
+            Model {
+                market,
+                orders: Vec::new(),
+                base_balances: vec![base_balance; traders],
+                quote_balances: vec![quote_balance; traders],
+                base_vault: 0,
+                quote_vault: 0,
+                fee_vault: 0,
+                fills: Vec::new(),
+                last_price: 0,
+                base_volume: 0,
+                quote_volume: 0,
+            }
+        }
+
+        fn place(&mut self, order: ModelOrder) {
+            let lock = self.market.escrow_amount(order.is_buy, order.price, order.remaining).unwrap();
+            if order.is_buy {
+                self.quote_balances[order.trader] -= lock;
+                self.quote_vault += lock;
+            } else {
+                self.base_balances[order.trader] -= lock;
+                self.base_vault += lock;
+            }
+            self.orders.push(order);
+        }
+
+        fn cancel(&mut self, index: usize) {
+            let order = self.orders.remove(index);
+            let refund = self.market.escrow_amount(order.is_buy, order.price, order.remaining).unwrap();
+            if order.is_buy {
+                self.quote_balances[order.trader] += refund;
+                self.quote_vault -= refund;
+            } else {
+                self.base_balances[order.trader] += refund;
+                self.base_vault -= refund;
+            }
+        }
+
+        // Record a fill, unlocking the bid's lock for the quantity and paying it out as the
+        // fee split decides
+        // 记录一笔成交，解锁买单对应数量的锁定资金，并按手续费分配结果支付
+        fn fill(&mut self, bid: usize, ask: usize, price: u64, quantity: u64, amounts: &SettlementAmounts) {
+            let (bid_order, ask_order) = (self.orders[bid], self.orders[ask]);
+            let base_amount = self.market.base_lots_to_native(quantity).unwrap();
+            let quote_amount = self.market.notional(price, quantity).unwrap();
+            let bid_lock = self.market.escrow_amount(true, bid_order.price, quantity).unwrap();
+
+            self.base_vault -= base_amount;
+            self.base_balances[bid_order.trader] += base_amount;
+            self.quote_vault -= bid_lock;
+            self.quote_balances[bid_order.trader] += bid_lock - amounts.taker_quote_amount().unwrap();
+            self.quote_balances[ask_order.trader] += amounts.maker_quote_amount;
+            self.fee_vault += amounts.vault_fee;
+
+            self.fills.push((self.fills.len() as u64, bid_order.account, ask_order.account, price, base_amount, quote_amount));
+            self.last_price = price;
+            self.base_volume += base_amount as u128;
+            self.quote_volume += quote_amount as u128;
+            self.orders[bid].remaining -= quantity;
+            self.orders[ask].remaining -= quantity;
+        }
+
+        // Drop the filled orders, whose accounts the program closes
+        // 移除已完全成交的订单，程序会关闭其账户
+        fn remove_filled(&mut self) -> Vec<Pubkey> {
+            let filled = self.orders.iter().filter(|order| order.remaining == 0).map(|order| order.account).collect();
+            self.orders.retain(|order| order.remaining > 0);
+            filled
+        }
+    }
+
+    struct Harness {
+        context: ProgramTestContext,
+        program_id: Pubkey,
+        authority: Keypair,
+        market_account: Pubkey,
+        traders: Vec<Trader>,
+        order_accounts: Vec<Pubkey>,
+        closed_orders: Vec<Pubkey>,
+    }
+
+    impl Harness {
+        // Start a market with random fees and lot sizes, its vaults and event queue, and funded
+        // traders
+        // 启动一个手续费和手数随机的市场，以及其金库、事件队列和已注资的交易者
+        async fn start(rng: &mut Rng, traders: usize, order_accounts: usize, base_balance: u64, quote_balance: u64) -> (Self, Market) {
+            let program_id = Pubkey::new_unique();
+            let mut program_test = ProgramTest::new(
+                "solana_rust_dex",
+                program_id,
+                processor!(solana_rust_dex::entrypoint::process_instruction),
+            );
+
+            let base_mint = common::add_mint(&mut program_test, 6, None);
+            let quote_mint = common::add_mint(&mut program_test, 6, None);
+            let authority = Keypair::new();
+            let market_account = Pubkey::new_unique();
+            let taker_fee_bps = rng.below(60) as u16;
+            let market = Market {
+                authority: authority.pubkey(),
+                base_mint,
+                quote_mint,
+                tick_size: [1, 5][rng.below(2) as usize],
+                taker_fee_bps,
+                maker_fee_bps: rng.below(taker_fee_bps as u64 + 30) as i16 - taker_fee_bps as i16,
+                fee_authority: authority.pubkey(),
+                base_lot_size: [1, 10][rng.below(2) as usize],
+                quote_lot_size: [1, 3][rng.below(2) as usize],
+                signer_bump: Market::find_signer_address(&program_id, &market_account).1,
+                ..common::market()
+            };
+            let mut market_data = vec![0; Market::LEN];
+            market.pack_into_slice(&mut market_data);
+            program_test.add_account(
+                market_account,
+                Account {
+                    lamports: 1000000000,
+                    data: market_data,
+                    owner: program_id,
+                    executable: false,
+                    rent_epoch: 0,
+                },
+            );
+
+            let traders: Vec<Trader> = (0..traders)
+                .map(|_| {
+                    let keypair = Keypair::new();
+                    let base_account = common::add_token_account(&mut program_test, &base_mint, &keypair.pubkey(), None, base_balance);
+                    let quote_account = common::add_token_account(&mut program_test, &quote_mint, &keypair.pubkey(), None, quote_balance);
+                    Trader { keypair, base_account, quote_account }
+                })
+                .collect();
+            let order_accounts = (0..order_accounts)
+                .map(|_| {
+                    let order_account = Pubkey::new_unique();
+                    program_test.add_account(
+                        order_account,
+                        Account {
+                            lamports: 1000000000,
+                            data: vec![0; Order::LEN],
+                            owner: program_id,
+                            executable: false,
+                            rent_epoch: 0,
+                        },
+                    );
+                    order_account
+                })
+                .collect();
+
+            let context = program_test.start_with_context().await;
+            let mut harness = Harness {
+                context,
+                program_id,
+                authority,
+                market_account,
+                traders,
+                order_accounts,
+                closed_orders: Vec::new(),
+            };
+
+            let payer = harness.context.payer.pubkey();
+            let fund_authority_ix = system_instruction::transfer(&payer, &harness.authority.pubkey(), 1_000_000_000);
+            let init_vaults_ix =
+                DexInstruction::initialize_market_vaults(&program_id, &payer, &market_account, &base_mint, &quote_mint, &spl_token::id())
+                    .unwrap();
+            let init_fee_vault_ix =
+                DexInstruction::initialize_fee_vault(&program_id, &payer, &market_account, &quote_mint, &spl_token::id()).unwrap();
+            let init_event_queue_ix =
+                DexInstruction::initialize_event_queue(&program_id, &harness.authority.pubkey(), &market_account).unwrap();
+            let authority = harness.authority.insecure_clone();
+            harness
+                .send(&[fund_authority_ix, init_vaults_ix, init_fee_vault_ix, init_event_queue_ix], &authority)
+                .await;
+
+            (harness, market)
+        }
+
+        // Send a transaction under the latest blockhash, which the test bank keeps advancing on
+        // wall-clock time; no two steps send the same instructions, as every placement takes a
+        // fresh order account and every settlement or cancellation closes an order
+        // 以最新区块哈希发送交易，测试银行会按真实时间推进区块哈希；任意两步都不会发送相同的指令，
+        // 因为每次下单都使用新的订单账户，每次结算或取消都会关闭一个订单
+        async fn send(&mut self, instructions: &[Instruction], signer: &Keypair) {
+            let payer = self.context.payer.insecure_clone();
+            let mut transaction = Transaction::new_with_payer(instructions, Some(&payer.pubkey()));
+            let recent_blockhash = self.context.banks_client.get_latest_blockhash().await.unwrap();
+            transaction.sign(&[&payer, signer], recent_blockhash);
+            self.context.banks_client.process_transaction(transaction).await.unwrap();
+        }
+
+        async fn place(&mut self, order: &ModelOrder) {
+            let trader = &self.traders[order.trader];
+            let place_ix = DexInstruction::place_limit_order(
+                &self.program_id,
+                &trader.keypair.pubkey(),
+                &self.market_account,
+                &order.account,
+                if order.is_buy { &trader.quote_account } else { &trader.base_account },
+                &spl_token::id(),
+                order.is_buy,
+                order.price,
+                order.remaining,
+                SelfTradeBehavior::DecrementTake,
+            )
+            .unwrap();
+            let keypair = trader.keypair.insecure_clone();
+            self.send(&[place_ix], &keypair).await;
+        }
+
+        async fn cancel(&mut self, order: &ModelOrder) {
+            let trader = &self.traders[order.trader];
+            let cancel_ix = DexInstruction::cancel_order(
+                &self.program_id,
+                &trader.keypair.pubkey(),
+                &self.market_account,
+                &order.account,
+                if order.is_buy { &trader.quote_account } else { &trader.base_account },
+                &spl_token::id(),
+            )
+            .unwrap();
+            let keypair = trader.keypair.insecure_clone();
+            self.send(&[cancel_ix], &keypair).await;
+        }
+
+        async fn settle(&mut self, bid: &ModelOrder, ask: &ModelOrder) {
+            let (taker, maker) = (&self.traders[bid.trader], &self.traders[ask.trader]);
+            let settle_ix = DexInstruction::settle_funds(
+                &self.program_id,
+                &self.authority.pubkey(),
+                &self.market_account,
+                &bid.account,
+                &ask.account,
+                &taker.base_account,
+                &taker.quote_account,
+                &maker.base_account,
+                &maker.quote_account,
+                &taker.keypair.pubkey(),
+                &maker.keypair.pubkey(),
+                &spl_token::id(),
+            )
+            .unwrap();
+            let settle_ix = DexInstruction::with_event_queue(settle_ix);
+            let authority = self.authority.insecure_clone();
+            self.send(&[settle_ix], &authority).await;
+        }
+
+        async fn balance(&mut self, token_account: &Pubkey) -> u64 {
+            common::token_balance(&mut self.context.banks_client, token_account).await
+        }
+
+        async fn market(&mut self) -> Market {
+            let data = self.context.banks_client.get_account(self.market_account).await.unwrap().unwrap();
+            Market::unpack_from_slice(&data.data).unwrap()
+        }
+
+        // Compare every balance, order, book total and fill event with the model
+        // 将每个余额、订单、订单簿汇总和成交事件与模型比较
+        async fn check(&mut self, model: &Model, step: &str) {
+            for (index, trader) in self.traders.iter().map(|trader| (trader.base_account, trader.quote_account)).enumerate().collect::<Vec<_>>() {
+                assert_eq!(self.balance(&trader.0).await, model.base_balances[index], "{step}: base balance of trader {index}");
+                assert_eq!(self.balance(&trader.1).await, model.quote_balances[index], "{step}: quote balance of trader {index}");
+            }
+            let base_vault = Market::find_base_vault_address(&self.program_id, &self.market_account).0;
+            let quote_vault = Market::find_quote_vault_address(&self.program_id, &self.market_account).0;
+            let fee_vault = Market::find_fee_vault_address(&self.program_id, &self.market_account).0;
+            assert_eq!(self.balance(&base_vault).await, model.base_vault, "{step}: base vault");
+            assert_eq!(self.balance(&quote_vault).await, model.quote_vault, "{step}: quote vault");
+            assert_eq!(self.balance(&fee_vault).await, model.fee_vault, "{step}: fee vault");
+
+            for order in &model.orders {
+                let data = self.context.banks_client.get_account(order.account).await.unwrap().unwrap();
+                let onchain = Order::unpack_from_slice(&data.data).unwrap();
+                assert_eq!(
+                    (onchain.order_id, onchain.is_buy, onchain.limit_price, onchain.remaining_quantity),
+                    (order.order_id, order.is_buy, order.price, order.remaining),
+                    "{step}: order {}",
+                    order.account
+                );
+            }
+            for order_account in self.closed_orders.clone() {
+                assert!(self.context.banks_client.get_account(order_account).await.unwrap().is_none(), "{step}: order {order_account} not closed");
+            }
+
+            let market = self.market().await;
+            let side = |is_buy: bool| model.orders.iter().filter(move |order| order.is_buy == is_buy);
+            assert_eq!({ market.num_bids }, side(true).count() as u64, "{step}: bids");
+            assert_eq!({ market.num_asks }, side(false).count() as u64, "{step}: asks");
+            assert_eq!({ market.bid_base_total }, side(true).map(|order| order.remaining).sum::<u64>(), "{step}: bid total");
+            assert_eq!({ market.ask_base_total }, side(false).map(|order| order.remaining).sum::<u64>(), "{step}: ask total");
+            assert_eq!(
+                { market.bid_notional },
+                side(true).map(|order| order.price as u128 * order.remaining as u128).sum::<u128>(),
+                "{step}: bid notional"
+            );
+            assert_eq!(
+                { market.ask_notional },
+                side(false).map(|order| order.price as u128 * order.remaining as u128).sum::<u128>(),
+                "{step}: ask notional"
+            );
+            assert_eq!({ market.fill_seq_num }, model.fills.len() as u64, "{step}: fill sequence");
+            assert_eq!({ market.last_price }, model.last_price, "{step}: last price");
+            assert_eq!(({ market.base_volume }, { market.quote_volume }), (model.base_volume, model.quote_volume), "{step}: volume");
+            assert_eq!({ market.fees_accrued }, model.fee_vault, "{step}: fees accrued");
+
+            // A known best price is the model's; a stale one only bounds its side
+            // 已知的最优价格与模型一致；过期的最优价格只限定其一侧的范围
+            let best_bid = side(true).map(|order| order.price).max();
+            let best_ask = side(false).map(|order| order.price).min();
+            match market.best_bid_price() {
+                Some(price) => assert_eq!(Some(price), best_bid, "{step}: best bid"),
+                None => assert!(best_bid.is_none_or(|best_bid| { market.best_bid } >= best_bid), "{step}: stale best bid"),
+            }
+            match market.best_ask_price() {
+                Some(price) => assert_eq!(Some(price), best_ask, "{step}: best ask"),
+                None => assert!(best_ask.is_none_or(|best_ask| { market.best_ask } <= best_ask), "{step}: stale best ask"),
+            }
+            if best_bid.is_none() {
+                assert_eq!({ market.best_bid }, 0, "{step}: empty bids");
+            }
+            if best_ask.is_none() {
+                assert_eq!({ market.best_ask }, 0, "{step}: empty asks");
+            }
+
+            // Fill events carry the model's fills in order
+            // 成交事件按顺序与模型的成交一致
+            let event_queue = EventQueue::find_address(&self.program_id, &self.market_account).0;
+            if let Some(data) = self.context.banks_client.get_account(event_queue).await.unwrap() {
+                let event_queue = EventQueue::unpack_from_slice(&data.data).unwrap();
+                let events: Vec<_> = event_queue
+                    .iter()
+                    .map(|event| (event.seq_num, event.taker_order, event.maker_order, event.price, event.base_amount, event.quote_amount))
+                    .collect();
+                assert_eq!(events, model.fills, "{step}: fill events");
+            }
+        }
+    }
+
+    // A random order of a random trader, priced around 100 ticks
+    // 随机交易者的随机订单，价格在 100 个刻度附近
+    fn random_order(rng: &mut Rng, model: &Model, account: Pubkey, traders: usize) -> ModelOrder {
+        let tick_size = model.market.tick_size;
+        ModelOrder {
+            account,
+            trader: rng.below(traders as u64) as usize,
+            order_id: 0,
+            is_buy: rng.below(2) == 0,
+            price: (95 + rng.below(11)) * tick_size,
+            remaining: 1 + rng.below(50),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_settle_funds_matches_model() {
+        for seed in 1..=SEEDS {
+            let mut rng = Rng::new(seed);
+            let (mut harness, market) = Harness::start(&mut rng, 3, STEPS, 10_000_000, 100_000_000).await;
+            let mut model = Model::new(market, 3, 10_000_000, 100_000_000);
+            let mut next_order_id = { market.next_order_id };
+            let mut next_account = 0;
+
+            for step in 0..STEPS {
+                let crossing: Vec<(usize, usize)> = (0..model.orders.len())
+                    .flat_map(|bid| (0..model.orders.len()).map(move |ask| (bid, ask)))
+                    .filter(|&(bid, ask)| {
+                        let (bid, ask) = (&model.orders[bid], &model.orders[ask]);
+                        bid.is_buy && !ask.is_buy && bid.price >= ask.price
+                    })
+                    .collect();
+                let action = rng.below(10);
+                let step = format!("seed {seed} step {step}");
+
+                if action < 4 && !crossing.is_empty() {
+                    // Settle a random crossing pair at the ask's price
+                    // 以卖单价格结算随机一对交叉订单
+                    let (bid, ask) = crossing[rng.below(crossing.len() as u64) as usize];
+                    let (bid_order, ask_order) = (model.orders[bid], model.orders[ask]);
+                    let quantity = bid_order.remaining.min(ask_order.remaining);
+                    let quote_amount = model.market.notional(ask_order.price, quantity).unwrap();
+                    let amounts = model.market.discounted_settlement_amounts(quote_amount, false, 0).unwrap();
+                    harness.settle(&bid_order, &ask_order).await;
+                    model.fill(bid, ask, ask_order.price, quantity, &amounts);
+                    let filled = model.remove_filled();
+                    harness.closed_orders.extend(filled);
+                } else if (4..6).contains(&action) && !model.orders.is_empty() {
+                    // Cancel a random resting order
+                    // 取消随机一个挂单
+                    let index = rng.below(model.orders.len() as u64) as usize;
+                    let order = model.orders[index];
+                    harness.cancel(&order).await;
+                    model.cancel(index);
+                    harness.closed_orders.push(order.account);
+                } else {
+                    // Place a random order
+                    // 下一个随机订单
+                    let account = harness.order_accounts[next_account];
+                    next_account += 1;
+                    let mut order = random_order(&mut rng, &model, account, 3);
+                    order.order_id = next_order_id;
+                    next_order_id += 1;
+                    harness.place(&order).await;
+                    model.place(order);
+                }
+
+                harness.check(&model, &step).await;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_auction_matches_model() {
+        for seed in 1..=SEEDS {
+            let mut rng = Rng::new(SEEDS + seed);
+            let order_count = AUCTION_ROUNDS * MAX_AUCTION_ORDERS as usize;
+            let (mut harness, market) = Harness::start(&mut rng, 4, order_count, 10_000_000, 100_000_000).await;
+            let mut model = Model::new(market, 4, 10_000_000, 100_000_000);
+            let mut next_order_id = { market.next_order_id };
+            let mut next_account = 0;
+
+            let interval_slots = 10;
+            let init_auction_ix =
+                DexInstruction::initialize_batch_auction(&harness.program_id, &harness.authority.pubkey(), &harness.market_account, interval_slots)
+                    .unwrap();
+            let authority = harness.authority.insecure_clone();
+            harness.send(&[init_auction_ix], &authority).await;
+
+            for round in 0..AUCTION_ROUNDS {
+                // Fill the book up to the auction capacity with random orders
+                // 用随机订单将订单簿填满到集合竞价容量
+                let new_orders = MAX_AUCTION_ORDERS as usize - model.orders.len();
+                for _ in 0..1 + rng.below(new_orders as u64) {
+                    let account = harness.order_accounts[next_account];
+                    next_account += 1;
+                    let mut order = random_order(&mut rng, &model, account, 4);
+                    order.order_id = next_order_id;
+                    next_order_id += 1;
+                    harness.place(&order).await;
+                    model.place(order);
+                }
+
+                // Cross the book at the clearing price, pairing orders best price first and
+                // oldest first within a price
+                // 以清算价格撮合订单簿，按价格优先、同价格时间优先配对订单
+                let mut bids: Vec<usize> = (0..model.orders.len()).filter(|&index| model.orders[index].is_buy).collect();
+                let mut asks: Vec<usize> = (0..model.orders.len()).filter(|&index| !model.orders[index].is_buy).collect();
+                bids.sort_by_key(|&index| (std::cmp::Reverse(model.orders[index].price), model.orders[index].order_id));
+                asks.sort_by_key(|&index| (model.orders[index].price, model.orders[index].order_id));
+                let levels = |orders: &[usize]| -> Vec<(u64, u64)> {
+                    orders.iter().map(|&index| (model.orders[index].price, model.orders[index].remaining)).collect()
+                };
+                let clearing = BatchAuction::clearing_price(&levels(&bids), &levels(&asks), model.market.tick_size);
+                if let Some((price, volume)) = clearing {
+                    let (mut bid_index, mut ask_index, mut remaining) = (0, 0, volume);
+                    while remaining > 0 {
+                        let (bid, ask) = (bids[bid_index], asks[ask_index]);
+                        let quantity = remaining.min(model.orders[bid].remaining).min(model.orders[ask].remaining);
+                        let quote_amount = model.market.notional(price, quantity).unwrap();
+                        // Both sides pay the taker fee
+                        // 双方都支付吃单手续费
+                        let fee = model.market.calculate_taker_fee(quote_amount).unwrap();
+                        let amounts = SettlementAmounts {
+                            taker_fee: fee,
+                            maker_fee: fee as i64,
+                            maker_quote_amount: quote_amount - fee,
+                            vault_fee: 2 * fee,
+                            referral_fee: 0,
+                        };
+                        model.fill(bid, ask, price, quantity, &amounts);
+                        remaining -= quantity;
+                        if model.orders[bid].remaining == 0 {
+                            bid_index += 1;
+                        }
+                        if model.orders[ask].remaining == 0 {
+                            ask_index += 1;
+                        }
+                    }
+                }
+
+                let orders: Vec<RouteOrder> = model
+                    .orders
+                    .iter()
+                    .map(|order| {
+                        let trader = &harness.traders[order.trader];
+                        RouteOrder {
+                            order: order.account,
+                            owner: trader.keypair.pubkey(),
+                            owner_base_account: trader.base_account,
+                            owner_quote_account: trader.quote_account,
+                        }
+                    })
+                    .collect();
+                let mut run_ix = DexInstruction::run_auction(&harness.program_id, &harness.market_account, &spl_token::id(), &orders).unwrap();
+                let event_queue = EventQueue::find_address(&harness.program_id, &harness.market_account).0;
+                run_ix.accounts.push(AccountMeta::new(event_queue, false));
+                common::warp_slots(&mut harness.context, interval_slots).await;
+                let payer = harness.context.payer.insecure_clone();
+                harness.send(&[run_ix], &payer).await;
+                let filled = model.remove_filled();
+                harness.closed_orders.extend(filled);
+
+                let step = format!("seed {seed} round {round}");
+                harness.check(&model, &step).await;
+                let auction_address = BatchAuction::find_address(&harness.program_id, &harness.market_account).0;
+                let auction_data = harness.context.banks_client.get_account(auction_address).await.unwrap().unwrap();
+                let auction = BatchAuction::unpack_from_slice(&auction_data.data).unwrap();
+                if let Some((price, volume)) = clearing {
+                    assert_eq!((auction.last_clearing_price, auction.last_volume), (price, volume), "{step}: clearing");
+                } else {
+                    assert_eq!(auction.last_volume, 0, "{step}: clearing");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,747 @@
+// Fee vault test module
+// 手续费金库测试模块
+
+mod common;
+
+#[cfg(test)]
+mod fee_vault_tests {
+    use super::common;
+    use solana_program::{
+        program_option::COption,
+        program_pack::Pack,
+        pubkey::Pubkey,
+    };
+    use solana_program_test::*;
+    use solana_sdk::{
+        account::Account,
+        instruction::{AccountMeta, InstructionError},
+        signature::{Keypair, Signer},
+        transaction::{Transaction, TransactionError},
+    };
+    use solana_rust_dex::{
+        error::DexError,
+        instruction::DexInstruction,
+        state::{
+            EventQueue, Market, PerpMarket, Pool, RangePool, TwapOracle, ACCOUNT_VERSION,
+        },
+    };
+
+    fn token_account(mint: &Pubkey, owner: &Pubkey) -> Account {
+        let token_account = spl_token::state::Account {
+            mint: *mint,
+            owner: *owner,
+            state: spl_token::state::AccountState::Initialized,
+            ..spl_token::state::Account::default()
+        };
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        token_account.pack_into_slice(&mut data);
+        Account {
+            lamports: 1000000000,
+            data,
+            owner: spl_token::id(),
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    fn custom_error(err: TransactionError) -> u32 {
+        match err {
+            TransactionError::InstructionError(0, InstructionError::Custom(code)) => code,
+            err => panic!("unexpected error: {:?}", err),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fee_vault_and_sweep_fees() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // Add a quote mint and a market to the test environment
+        // 将报价代币和市场添加到测试环境
+        let mint_authority = Keypair::new();
+        let quote_mint = Pubkey::new_unique();
+        let mint = spl_token::state::Mint {
+            mint_authority: COption::Some(mint_authority.pubkey()),
+            decimals: 6,
+            is_initialized: true,
+            ..spl_token::state::Mint::default()
+        };
+        let mut mint_data = vec![0; spl_token::state::Mint::LEN];
+        mint.pack_into_slice(&mut mint_data);
+        program_test.add_account(
+            quote_mint,
+            Account {
+                lamports: 1000000000,
+                data: mint_data,
+                owner: spl_token::id(),
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+
+        let authority = Keypair::new();
+        let fee_authority = Keypair::new();
+        let market_account = Pubkey::new_unique();
+        let market = Market {
+            authority: authority.pubkey(),
+            base_mint: Pubkey::new_unique(),
+            quote_mint,
+            min_base_order_size: 100,
+            tick_size: 10,
+            taker_fee_bps: 25,
+            next_order_id: 0,
+            fee_authority: fee_authority.pubkey(),
+            ..common::market()
+        };
+        let mut market_data = vec![0; Market::LEN];
+        market.pack_into_slice(&mut market_data);
+        program_test.add_account(
+            market_account,
+            Account {
+                lamports: 1000000000,
+                data: market_data,
+                owner: program_id,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+
+        let destination = Pubkey::new_unique();
+        program_test.add_account(destination, token_account(&quote_mint, &fee_authority.pubkey()));
+
+        // Start the test environment
+        // 启动测试环境
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        // Create the fee vault and deposit some fees into it
+        // 创建手续费金库并存入一些手续费
+        let (fee_vault, _) = Market::find_fee_vault_address(&program_id, &market_account);
+        let init_vault_ix = DexInstruction::initialize_fee_vault(
+            &program_id,
+            &payer.pubkey(),
+            &market_account,
+            &quote_mint,
+            &spl_token::id(),
+        )
+        .unwrap();
+        let mint_to_ix = spl_token::instruction::mint_to(
+            &spl_token::id(),
+            &quote_mint,
+            &fee_vault,
+            &mint_authority.pubkey(),
+            &[],
+            1000,
+        )
+        .unwrap();
+
+        let mut transaction = Transaction::new_with_payer(&[init_vault_ix, mint_to_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &mint_authority], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        let vault_account = banks_client.get_account(fee_vault).await.unwrap().unwrap();
+        let vault = spl_token::state::Account::unpack(&vault_account.data).unwrap();
+        assert_eq!(vault.owner, fee_vault);
+        assert_eq!(vault.mint, quote_mint);
+        assert_eq!(vault.amount, 1000);
+
+        // Settlement fees may only go to the fee vault
+        // 结算手续费只能进入手续费金库
+        let mut settle_ix = DexInstruction::settle_funds(
+            &program_id,
+            &authority.pubkey(),
+            &market_account,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &spl_token::id(),
+        )
+        .unwrap();
+        settle_ix.accounts[8].pubkey = destination;
+
+        let mut transaction = Transaction::new_with_payer(&[settle_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &authority], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(custom_error(err), DexError::InvalidAccountData as u32);
+
+        // Only the fee authority may sweep, not the market authority
+        // 只有手续费权限账户可以提取，市场权限账户不行
+        let sweep_ix = DexInstruction::sweep_fees(
+            &program_id,
+            &authority.pubkey(),
+            &market_account,
+            &destination,
+            &spl_token::id(),
+        )
+        .unwrap();
+
+        let mut transaction = Transaction::new_with_payer(&[sweep_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &authority], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(custom_error(err), DexError::AccountNotAuthorized as u32);
+
+        // The fee authority sweeps the whole balance
+        // 手续费权限账户提取全部余额
+        let sweep_ix = DexInstruction::sweep_fees(
+            &program_id,
+            &fee_authority.pubkey(),
+            &market_account,
+            &destination,
+            &spl_token::id(),
+        )
+        .unwrap();
+
+        let mut transaction = Transaction::new_with_payer(std::slice::from_ref(&sweep_ix), Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &fee_authority], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        for (address, expected) in [(fee_vault, 0), (destination, 1000)] {
+            let account = banks_client.get_account(address).await.unwrap().unwrap();
+            assert_eq!(spl_token::state::Account::unpack(&account.data).unwrap().amount, expected);
+        }
+
+        // The sweep is recorded in the market's fee ledger
+        // 提取记录在市场的手续费账本中
+        let market_data = banks_client.get_account(market_account).await.unwrap().unwrap();
+        assert_eq!({ Market::unpack_from_slice(&market_data.data).unwrap().fees_swept }, 1000);
+
+        // After handing over the fee authority the old one can no longer sweep
+        // 移交手续费权限后，旧的权限账户无法再提取
+        let new_fee_authority = Pubkey::new_unique();
+        let set_authority_ix = DexInstruction::set_fee_authority(
+            &program_id,
+            &fee_authority.pubkey(),
+            &market_account,
+            &new_fee_authority,
+        )
+        .unwrap();
+
+        let mut transaction = Transaction::new_with_payer(&[set_authority_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &fee_authority], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        let market_data = banks_client.get_account(market_account).await.unwrap().unwrap();
+        let market = Market::unpack_from_slice(&market_data.data).unwrap();
+        assert_eq!(market.fee_authority, new_fee_authority);
+
+        let recent_blockhash = banks_client.get_latest_blockhash().await.unwrap();
+        let mut transaction = Transaction::new_with_payer(&[sweep_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &fee_authority], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(custom_error(err), DexError::AccountNotAuthorized as u32);
+    }
+
+    #[tokio::test]
+    async fn test_close_market_reclaims_rent() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // Add a quote mint, an empty market and a market with a resting bid
+        // 添加报价代币、一个空市场和一个有挂单的市场
+        let mint_authority = Keypair::new();
+        let quote_mint = Pubkey::new_unique();
+        let mint = spl_token::state::Mint {
+            mint_authority: COption::Some(mint_authority.pubkey()),
+            decimals: 6,
+            is_initialized: true,
+            ..spl_token::state::Mint::default()
+        };
+        let mut mint_data = vec![0; spl_token::state::Mint::LEN];
+        mint.pack_into_slice(&mut mint_data);
+        program_test.add_account(
+            quote_mint,
+            Account {
+                lamports: 1000000000,
+                data: mint_data,
+                owner: spl_token::id(),
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+
+        let authority = Keypair::new();
+        let market_account = Pubkey::new_unique();
+        let busy_market_account = Pubkey::new_unique();
+        for (address, num_bids) in [(market_account, 0), (busy_market_account, 1)] {
+            let market = Market {
+                authority: authority.pubkey(),
+                base_mint: Pubkey::new_unique(),
+                quote_mint,
+                min_base_order_size: 100,
+                tick_size: 10,
+                taker_fee_bps: 25,
+                next_order_id: 0,
+                num_bids,
+                fee_authority: authority.pubkey(),
+                ..common::market()
+            };
+            let mut market_data = vec![0; Market::LEN];
+            market.pack_into_slice(&mut market_data);
+            program_test.add_account(
+                address,
+                Account {
+                    lamports: 1000000000,
+                    data: market_data,
+                    owner: program_id,
+                    executable: false,
+                    rent_epoch: 0,
+                },
+            );
+        }
+
+        let destination = Pubkey::new_unique();
+        program_test.add_account(destination, token_account(&quote_mint, &authority.pubkey()));
+
+        // Start the test environment
+        // 启动测试环境
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        // A market with resting orders cannot be closed
+        // 有挂单的市场无法关闭
+        let recipient = Pubkey::new_unique();
+        let close_busy_ix = DexInstruction::close_market(
+            &program_id,
+            &authority.pubkey(),
+            &busy_market_account,
+            &recipient,
+            &spl_token::id(),
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(&[close_busy_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &authority], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(custom_error(err), DexError::MarketNotEmpty as u32);
+
+        // Create the fee vault and deposit some fees into it
+        // 创建手续费金库并存入一些手续费
+        let (fee_vault, _) = Market::find_fee_vault_address(&program_id, &market_account);
+        let init_vault_ix = DexInstruction::initialize_fee_vault(
+            &program_id,
+            &payer.pubkey(),
+            &market_account,
+            &quote_mint,
+            &spl_token::id(),
+        )
+        .unwrap();
+        let mint_to_ix = spl_token::instruction::mint_to(
+            &spl_token::id(),
+            &quote_mint,
+            &fee_vault,
+            &mint_authority.pubkey(),
+            &[],
+            1000,
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(&[init_vault_ix, mint_to_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &mint_authority], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // Uncollected fees must be swept first
+        // 未提取的手续费必须先提取
+        let close_ix = DexInstruction::close_market(
+            &program_id,
+            &authority.pubkey(),
+            &market_account,
+            &recipient,
+            &spl_token::id(),
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(std::slice::from_ref(&close_ix), Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &authority], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(custom_error(err), DexError::MarketNotEmpty as u32);
+
+        // After sweeping, the market and its fee vault are closed
+        // 提取之后，市场及其手续费金库被关闭
+        let vault_lamports = banks_client.get_account(fee_vault).await.unwrap().unwrap().lamports;
+        let sweep_ix = DexInstruction::sweep_fees(
+            &program_id,
+            &authority.pubkey(),
+            &market_account,
+            &destination,
+            &spl_token::id(),
+        )
+        .unwrap();
+        let recent_blockhash = banks_client.get_latest_blockhash().await.unwrap();
+        let mut transaction = Transaction::new_with_payer(&[sweep_ix, close_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &authority], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        assert!(banks_client.get_account(market_account).await.unwrap().is_none());
+        assert!(banks_client.get_account(fee_vault).await.unwrap().is_none());
+        let recipient_account = banks_client.get_account(recipient).await.unwrap().unwrap();
+        assert_eq!(recipient_account.lamports, 1000000000 + vault_lamports);
+    }
+
+    #[tokio::test]
+    async fn test_close_market_checks_and_closes_every_market_account() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+        let authority = Keypair::new();
+        let empty_market = |program_test: &mut ProgramTest| {
+            let market_account = Pubkey::new_unique();
+            let mut market = Market::unpack_unchecked(&vec![0; Market::LEN]).unwrap();
+            market.is_initialized = true;
+            market.authority = authority.pubkey();
+            market.fee_authority = authority.pubkey();
+            market.base_mint = Pubkey::new_unique();
+            market.quote_mint = Pubkey::new_unique();
+            market.base_lot_size = 1;
+            market.quote_lot_size = 1;
+            market.base_token_program = spl_token::id();
+            market.quote_token_program = spl_token::id();
+            market.version = ACCOUNT_VERSION;
+            let mut market_data = vec![0; Market::LEN];
+            market.pack_into_slice(&mut market_data);
+            program_test.add_account(
+                market_account,
+                Account {
+                    lamports: 1000000000,
+                    data: market_data,
+                    owner: program_id,
+                    executable: false,
+                    rent_epoch: 0,
+                },
+            );
+            (market_account, market)
+        };
+        let vault = |mint: &Pubkey, owner: &Pubkey, amount: u64| {
+            let mut account = token_account(mint, owner);
+            let mut token_account = spl_token::state::Account::unpack(&account.data).unwrap();
+            token_account.amount = amount;
+            token_account.pack_into_slice(&mut account.data);
+            account
+        };
+        let state_account = |data: Vec<u8>| Account {
+            lamports: 1000000000,
+            data,
+            owner: program_id,
+            executable: false,
+            rent_epoch: 0,
+        };
+        let event_queue = |market_account: &Pubkey, count: u64| {
+            let mut queue = EventQueue::unpack_unchecked(&vec![0; EventQueue::LEN]).unwrap();
+            queue.is_initialized = true;
+            queue.market = *market_account;
+            queue.count = count;
+            let mut data = vec![0; EventQueue::LEN];
+            queue.pack_into_slice(&mut data);
+            state_account(data)
+        };
+
+        // A market blocked by each of: unconsumed events, tokens in an escrow vault, the
+        // insurance fund, pool liquidity, range pool liquidity and perp collateral
+        // 分别被以下之一阻止关闭的市场：未消费的事件、托管金库中的代币、保险基金、
+        // 资金池流动性、区间资金池流动性和永续合约抵押品
+        let mut blocked_markets = Vec::new();
+        for blocker in 0..6 {
+            let (market_account, market) = empty_market(&mut program_test);
+            let (address, account) = match blocker {
+                0 => (EventQueue::find_address(&program_id, &market_account).0, event_queue(&market_account, 1)),
+                1 => (
+                    Market::find_base_vault_address(&program_id, &market_account).0,
+                    vault(&market.base_mint, &Market::find_signer_address(&program_id, &market_account).0, 5),
+                ),
+                2 => {
+                    let insurance_fund = Market::find_insurance_fund_address(&program_id, &market_account).0;
+                    (insurance_fund, vault(&market.quote_mint, &insurance_fund, 5))
+                }
+                3 => (
+                    Pool::find_vault_address(&program_id, &market_account, false).0,
+                    vault(&market.base_mint, &Pool::find_address(&program_id, &market_account).0, 5),
+                ),
+                4 => (
+                    RangePool::find_vault_address(&program_id, &market_account, true).0,
+                    vault(&market.quote_mint, &RangePool::find_address(&program_id, &market_account).0, 5),
+                ),
+                _ => (
+                    PerpMarket::find_vault_address(&program_id, &market_account).0,
+                    vault(&market.quote_mint, &PerpMarket::find_address(&program_id, &market_account).0, 5),
+                ),
+            };
+            program_test.add_account(address, account);
+            blocked_markets.push(market_account);
+        }
+
+        // An empty market with its escrow vaults, insurance fund, event queue, TWAP oracle, pool
+        // and perp market
+        // 一个空市场，带有托管金库、保险基金、事件队列、TWAP 预言机、资金池和永续合约市场
+        let (market_account, market) = empty_market(&mut program_test);
+        let market_signer = Market::find_signer_address(&program_id, &market_account).0;
+        let insurance_fund = Market::find_insurance_fund_address(&program_id, &market_account).0;
+        let pool = Pool::find_address(&program_id, &market_account).0;
+        let perp_market = PerpMarket::find_address(&program_id, &market_account).0;
+        let market_accounts = [
+            (Market::find_base_vault_address(&program_id, &market_account).0, vault(&market.base_mint, &market_signer, 0)),
+            (Market::find_quote_vault_address(&program_id, &market_account).0, vault(&market.quote_mint, &market_signer, 0)),
+            (insurance_fund, vault(&market.quote_mint, &insurance_fund, 0)),
+            (EventQueue::find_address(&program_id, &market_account).0, event_queue(&market_account, 0)),
+            (TwapOracle::find_address(&program_id, &market_account).0, state_account(vec![0; TwapOracle::LEN])),
+            (pool, state_account(vec![0; Pool::LEN])),
+            (Pool::find_vault_address(&program_id, &market_account, false).0, vault(&market.base_mint, &pool, 0)),
+            (Pool::find_vault_address(&program_id, &market_account, true).0, vault(&market.quote_mint, &pool, 0)),
+            (perp_market, state_account(vec![0; PerpMarket::LEN])),
+            (PerpMarket::find_vault_address(&program_id, &market_account).0, vault(&market.quote_mint, &perp_market, 0)),
+        ];
+        for (address, account) in &market_accounts {
+            program_test.add_account(*address, account.clone());
+        }
+
+        // Start the test environment
+        // 启动测试环境
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let recipient = Pubkey::new_unique();
+        let close_ix = |market_account: &Pubkey| {
+            DexInstruction::close_market(&program_id, &authority.pubkey(), market_account, &recipient, &spl_token::id()).unwrap()
+        };
+
+        // None of the blocked markets can be closed
+        // 被阻止的市场都无法关闭
+        for blocked_market in &blocked_markets {
+            let mut transaction = Transaction::new_with_payer(&[close_ix(blocked_market)], Some(&payer.pubkey()));
+            transaction.sign(&[&payer, &authority], recent_blockhash);
+            let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+            assert_eq!(custom_error(err), DexError::MarketNotEmpty as u32);
+        }
+
+        // The market account list must include every account the market owns
+        // 账户列表必须包含市场拥有的每个账户
+        let mut partial_close_ix = close_ix(&market_account);
+        partial_close_ix.accounts.truncate(10);
+        let mut transaction = Transaction::new_with_payer(&[partial_close_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &authority], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(err, TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys));
+
+        // The empty market closes with every account it owns, their rent going to the recipient
+        // 空市场连同其拥有的每个账户一起关闭，租金归接收方所有
+        let mut transaction = Transaction::new_with_payer(&[close_ix(&market_account)], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &authority], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+        assert!(banks_client.get_account(market_account).await.unwrap().is_none());
+        for (address, _) in &market_accounts {
+            assert!(banks_client.get_account(*address).await.unwrap().is_none());
+        }
+        assert_eq!(
+            banks_client.get_balance(recipient).await.unwrap(),
+            1000000000 * (1 + market_accounts.len() as u64)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fee_split_sweep() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // Add a quote mint and a market to the test environment
+        // 将报价代币和市场添加到测试环境
+        let mint_authority = Keypair::new();
+        let quote_mint = Pubkey::new_unique();
+        let mint = spl_token::state::Mint {
+            mint_authority: COption::Some(mint_authority.pubkey()),
+            decimals: 6,
+            is_initialized: true,
+            ..spl_token::state::Mint::default()
+        };
+        let mut mint_data = vec![0; spl_token::state::Mint::LEN];
+        mint.pack_into_slice(&mut mint_data);
+        program_test.add_account(
+            quote_mint,
+            Account {
+                lamports: 1000000000,
+                data: mint_data,
+                owner: spl_token::id(),
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+
+        let authority = Keypair::new();
+        let fee_authority = Keypair::new();
+        let market_account = Pubkey::new_unique();
+        let market = Market {
+            authority: authority.pubkey(),
+            base_mint: Pubkey::new_unique(),
+            quote_mint,
+            min_base_order_size: 100,
+            tick_size: 10,
+            taker_fee_bps: 25,
+            next_order_id: 0,
+            fee_authority: fee_authority.pubkey(),
+            ..common::market()
+        };
+        let mut market_data = vec![0; Market::LEN];
+        market.pack_into_slice(&mut market_data);
+        program_test.add_account(
+            market_account,
+            Account {
+                lamports: 1000000000,
+                data: market_data,
+                owner: program_id,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+
+        let destination = Pubkey::new_unique();
+        program_test.add_account(destination, token_account(&quote_mint, &fee_authority.pubkey()));
+        let creator_account = Pubkey::new_unique();
+        program_test.add_account(creator_account, token_account(&quote_mint, &authority.pubkey()));
+
+        // Start the test environment
+        // 启动测试环境
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        // The payer administers the program config and is its treasury
+        // 付款人管理程序配置，同时是其金库
+        let (fee_vault, _) = Market::find_fee_vault_address(&program_id, &market_account);
+        let config_ix = DexInstruction::initialize_program_config(&program_id, &payer.pubkey()).unwrap();
+        let init_vault_ix = DexInstruction::initialize_fee_vault(
+            &program_id,
+            &payer.pubkey(),
+            &market_account,
+            &quote_mint,
+            &spl_token::id(),
+        )
+        .unwrap();
+        let mint_to_ix = spl_token::instruction::mint_to(
+            &spl_token::id(),
+            &quote_mint,
+            &fee_vault,
+            &mint_authority.pubkey(),
+            &[],
+            1000,
+        )
+        .unwrap();
+        let treasury = Keypair::new();
+        let rent = banks_client.get_rent().await.unwrap();
+        let create_treasury_ix = solana_program::system_instruction::create_account(
+            &payer.pubkey(),
+            &treasury.pubkey(),
+            rent.minimum_balance(spl_token::state::Account::LEN),
+            spl_token::state::Account::LEN as u64,
+            &spl_token::id(),
+        );
+        let init_treasury_ix = spl_token::instruction::initialize_account3(
+            &spl_token::id(),
+            &treasury.pubkey(),
+            &quote_mint,
+            &payer.pubkey(),
+        )
+        .unwrap();
+
+        let mut transaction = Transaction::new_with_payer(
+            &[config_ix, init_vault_ix, mint_to_ix, create_treasury_ix, init_treasury_ix],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &mint_authority, &treasury], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // Only the config admin may set the split, into accounts of the treasury and the creator
+        // 只有配置管理员可以设置分成，且收款账户须属于金库和创建者
+        let split_ix = |admin: &Pubkey, treasury_account: &Pubkey, creator_account: &Pubkey| {
+            DexInstruction::set_fee_split(
+                &program_id,
+                admin,
+                &market_account,
+                treasury_account,
+                creator_account,
+                2_000,
+                5_000,
+            )
+            .unwrap()
+        };
+        for (ix, signer, expected) in [
+            (
+                split_ix(&authority.pubkey(), &treasury.pubkey(), &creator_account),
+                &authority,
+                DexError::AccountNotAuthorized,
+            ),
+            (split_ix(&payer.pubkey(), &creator_account, &creator_account), &payer, DexError::InvalidTokenAccount),
+            (split_ix(&payer.pubkey(), &treasury.pubkey(), &destination), &payer, DexError::InvalidTokenAccount),
+        ] {
+            let mut transaction = Transaction::new_with_payer(&[ix], Some(&payer.pubkey()));
+            transaction.sign(&[&payer, signer], recent_blockhash);
+            let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+            assert_eq!(custom_error(err), expected as u32);
+        }
+
+        let mut transaction = Transaction::new_with_payer(
+            &[split_ix(&payer.pubkey(), &treasury.pubkey(), &creator_account)],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        let market_data = banks_client.get_account(market_account).await.unwrap().unwrap();
+        let market = Market::unpack_from_slice(&market_data.data).unwrap();
+        assert_eq!(market.treasury_fee_account, treasury.pubkey());
+        assert_eq!({ market.treasury_fee_share_bps }, 2_000);
+        assert_eq!(market.creator_fee_account, creator_account);
+        assert_eq!({ market.creator_fee_share_bps }, 5_000);
+
+        // A sweep without the split's accounts is refused
+        // 缺少分成账户的提取会被拒绝
+        let sweep_ix = DexInstruction::sweep_fees(
+            &program_id,
+            &fee_authority.pubkey(),
+            &market_account,
+            &destination,
+            &spl_token::id(),
+        )
+        .unwrap();
+
+        let mut transaction = Transaction::new_with_payer(std::slice::from_ref(&sweep_ix), Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &fee_authority], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(err, TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys));
+
+        // With them the treasury and the creator take their shares and the destination the rest
+        // 传入后金库和创建者获得各自份额，目标账户获得其余部分
+        let mut sweep_ix = sweep_ix;
+        sweep_ix.accounts.extend([
+            AccountMeta::new(treasury.pubkey(), false),
+            AccountMeta::new(creator_account, false),
+        ]);
+
+        let mut transaction = Transaction::new_with_payer(&[sweep_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &fee_authority], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        for (address, expected) in [(fee_vault, 0), (treasury.pubkey(), 200), (creator_account, 500), (destination, 300)] {
+            let account = banks_client.get_account(address).await.unwrap().unwrap();
+            assert_eq!(spl_token::state::Account::unpack(&account.data).unwrap().amount, expected);
+        }
+
+        let market_data = banks_client.get_account(market_account).await.unwrap().unwrap();
+        assert_eq!({ Market::unpack_from_slice(&market_data.data).unwrap().fees_swept }, 1000);
+    }
+}
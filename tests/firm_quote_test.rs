@@ -0,0 +1,334 @@
+// Firm quote test module
+// 确定报价测试模块
+
+mod common;
+
+#[cfg(test)]
+mod firm_quote_tests {
+    use super::common;
+    use solana_program::{
+        program_option::COption,
+        program_pack::Pack,
+        pubkey::Pubkey,
+    };
+    use solana_program_test::*;
+    use solana_sdk::{
+        account::Account,
+        instruction::{Instruction, InstructionError},
+        signature::{Keypair, Signer},
+        transaction::{Transaction, TransactionError},
+    };
+    use solana_rust_dex::{
+        ed25519,
+        error::DexError,
+        instruction::DexInstruction,
+        state::{FirmQuote, Market, QuoteFill},
+    };
+
+    fn token_account(mint: &Pubkey, owner: &Pubkey, amount: u64, delegate: Option<&Pubkey>) -> Account {
+        let token_account = spl_token::state::Account {
+            mint: *mint,
+            owner: *owner,
+            amount,
+            delegate: delegate.copied().map_or(COption::None, COption::Some),
+            state: spl_token::state::AccountState::Initialized,
+            delegated_amount: if delegate.is_some() { amount } else { 0 },
+            ..spl_token::state::Account::default()
+        };
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        token_account.pack_into_slice(&mut data);
+        Account {
+            lamports: 1000000000,
+            data,
+            owner: spl_token::id(),
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    fn market_account_data(program_id: &Pubkey, base_mint: &Pubkey, quote_mint: &Pubkey) -> Account {
+        let market = Market {
+            authority: Pubkey::new_unique(),
+            base_mint: *base_mint,
+            quote_mint: *quote_mint,
+            min_base_order_size: 100,
+            tick_size: 10,
+            taker_fee_bps: 100,
+            fee_authority: Pubkey::new_unique(),
+            ..common::market()
+        };
+        let mut data = vec![0; Market::LEN];
+        market.pack_into_slice(&mut data);
+        Account {
+            lamports: 1000000000,
+            data,
+            owner: *program_id,
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    fn custom_error(err: TransactionError) -> u32 {
+        match err {
+            TransactionError::InstructionError(_, InstructionError::Custom(code)) => code,
+            err => panic!("unexpected error: {:?}", err),
+        }
+    }
+
+    fn signed_quote_instruction(program_id: &Pubkey, signer: &Keypair, quote: &FirmQuote) -> Instruction {
+        let signature = signer.sign_message(&quote.signing_message(program_id));
+        ed25519::new_ed25519_instruction(
+            &signer.pubkey(),
+            signature.as_ref().try_into().unwrap(),
+            &quote.signing_message(program_id),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_execute_firm_quote() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // Add a market, its fee vault and the token accounts of both sides
+        // 添加市场、其手续费金库以及双方的代币账户
+        let base_mint = Pubkey::new_unique();
+        let quote_mint = Pubkey::new_unique();
+        let market_account = Pubkey::new_unique();
+        program_test.add_account(market_account, market_account_data(&program_id, &base_mint, &quote_mint));
+
+        let (fee_vault, _) = Market::find_fee_vault_address(&program_id, &market_account);
+        program_test.add_account(fee_vault, token_account(&quote_mint, &fee_vault, 0, None));
+
+        // The maker delegates its base tokens to the market's RFQ authority
+        // 做市商将其基础代币委托给市场的询价权限账户
+        let maker = Keypair::new();
+        let (rfq_authority, _) = Market::find_rfq_authority_address(&program_id, &market_account);
+        let maker_base_account = Pubkey::new_unique();
+        let maker_quote_account = Pubkey::new_unique();
+        program_test.add_account(
+            maker_base_account,
+            token_account(&base_mint, &maker.pubkey(), 1000, Some(&rfq_authority)),
+        );
+        program_test.add_account(maker_quote_account, token_account(&quote_mint, &maker.pubkey(), 0, None));
+
+        let taker = Keypair::new();
+        let taker_base_account = Pubkey::new_unique();
+        let taker_quote_account = Pubkey::new_unique();
+        program_test.add_account(taker_base_account, token_account(&base_mint, &taker.pubkey(), 0, None));
+        program_test.add_account(taker_quote_account, token_account(&quote_mint, &taker.pubkey(), 10_000, None));
+        program_test.add_account(
+            taker.pubkey(),
+            Account {
+                lamports: 1000000000,
+                ..Account::default()
+            },
+        );
+
+        // Start the test environment
+        // 启动测试环境
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        // The maker offers 500 base tokens at 10 to any taker
+        // 做市商以价格 10 向任意吃单方报价 500 个基础代币
+        let quote = FirmQuote {
+            market: market_account,
+            maker: maker.pubkey(),
+            taker: Pubkey::default(),
+            is_buy: false,
+            price: 10,
+            quantity: 500,
+            expires_at: i64::MAX,
+            nonce: 7,
+        };
+        let execute_ix = |quote: &FirmQuote, base_amount: u64| {
+            DexInstruction::execute_firm_quote(
+                &program_id,
+                &taker.pubkey(),
+                quote,
+                &taker_base_account,
+                &taker_quote_account,
+                &maker_base_account,
+                &maker_quote_account,
+                &spl_token::id(),
+                base_amount,
+            )
+            .unwrap()
+        };
+
+        // Without the maker's signature the quote is rejected
+        // 没有做市商签名时报价会被拒绝
+        let mut transaction = Transaction::new_with_payer(&[execute_ix(&quote, 300)], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &taker], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(custom_error(err), DexError::InvalidQuoteSignature as u32);
+
+        // A signature by someone else is rejected as well
+        // 其他人的签名同样会被拒绝
+        let impostor = Keypair::new();
+        let mut transaction = Transaction::new_with_payer(
+            &[signed_quote_instruction(&program_id, &impostor, &quote), execute_ix(&quote, 300)],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &taker], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(custom_error(err), DexError::InvalidQuoteSignature as u32);
+
+        // A forged signature under the maker's key is rejected by the runtime's precompile check
+        // 使用做市商公钥伪造的签名会被运行时的预编译检查拒绝
+        let forged_ix = ed25519::new_ed25519_instruction(
+            &maker.pubkey(),
+            &[1; ed25519::SIGNATURE_LEN],
+            &quote.signing_message(&program_id),
+        );
+        let mut transaction = Transaction::new_with_payer(&[forged_ix, execute_ix(&quote, 300)], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &taker], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(err, TransactionError::InvalidAccountIndex);
+
+        // An expired quote is rejected
+        // 过期的报价会被拒绝
+        let expired_quote = FirmQuote {
+            expires_at: 0,
+            ..quote.clone()
+        };
+        let mut transaction = Transaction::new_with_payer(
+            &[
+                signed_quote_instruction(&program_id, &maker, &expired_quote),
+                execute_ix(&expired_quote, 300),
+            ],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &taker], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(custom_error(err), DexError::QuoteExpired as u32);
+
+        // The taker buys 300 base tokens at the quoted price and pays the taker fee
+        // 吃单方以报价价格买入 300 个基础代币并支付吃单手续费
+        let mut transaction = Transaction::new_with_payer(
+            &[signed_quote_instruction(&program_id, &maker, &quote), execute_ix(&quote, 300)],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &taker], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        for (address, expected) in [
+            (maker_base_account, 700),
+            (maker_quote_account, 3_000),
+            (taker_base_account, 300),
+            (taker_quote_account, 6_970),
+            (fee_vault, 30),
+        ] {
+            let account = banks_client.get_account(address).await.unwrap().unwrap();
+            assert_eq!(spl_token::state::Account::unpack(&account.data).unwrap().amount, expected);
+        }
+
+        let (quote_fill_address, _) = quote.find_fill_address(&program_id);
+        let quote_fill_account = banks_client.get_account(quote_fill_address).await.unwrap().unwrap();
+        let quote_fill = QuoteFill::unpack_from_slice(&quote_fill_account.data).unwrap();
+        assert_eq!(quote_fill.maker, maker.pubkey());
+        assert_eq!(quote_fill.nonce, 7);
+        assert_eq!(quote_fill.filled_quantity, 300);
+
+        // The quote cannot be filled beyond its quantity
+        // 报价的成交量不能超过其数量
+        let mut transaction = Transaction::new_with_payer(
+            &[signed_quote_instruction(&program_id, &maker, &quote), execute_ix(&quote, 201)],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &taker], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(custom_error(err), DexError::InvalidOrderSize as u32);
+
+        // A quote addressed to another taker cannot be executed
+        // 指定给其他吃单方的报价无法被执行
+        let private_quote = FirmQuote {
+            taker: Pubkey::new_unique(),
+            nonce: 8,
+            ..quote.clone()
+        };
+        let mut transaction = Transaction::new_with_payer(
+            &[
+                signed_quote_instruction(&program_id, &maker, &private_quote),
+                execute_ix(&private_quote, 100),
+            ],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &taker], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(custom_error(err), DexError::AccountNotAuthorized as u32);
+    }
+    #[tokio::test]
+    async fn test_firm_quote_expiry_boundary() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // Add a market to the test environment
+        // 将一个市场添加到测试环境
+        let base_mint = Pubkey::new_unique();
+        let quote_mint = Pubkey::new_unique();
+        let market_account = Pubkey::new_unique();
+        program_test.add_account(market_account, market_account_data(&program_id, &base_mint, &quote_mint));
+
+        // Start the test environment
+        // 启动测试环境
+        let mut context = program_test.start_with_context().await;
+        let payer = context.payer.insecure_clone();
+
+        // A quote expiring at a fixed time, submitted without a signature
+        // 在固定时间过期的报价，提交时不附带签名
+        let expires_at = 1_700_000_000;
+        let quote = FirmQuote {
+            market: market_account,
+            maker: Pubkey::new_unique(),
+            taker: Pubkey::default(),
+            is_buy: false,
+            price: 10,
+            quantity: 500,
+            expires_at,
+            nonce: 1,
+        };
+        let taker = Keypair::new();
+        let execute_ix = DexInstruction::execute_firm_quote(
+            &program_id,
+            &taker.pubkey(),
+            &quote,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &spl_token::id(),
+            100,
+        )
+        .unwrap();
+        let send = |blockhash| {
+            let mut transaction = Transaction::new_with_payer(std::slice::from_ref(&execute_ix), Some(&payer.pubkey()));
+            transaction.sign(&[&payer, &taker], blockhash);
+            transaction
+        };
+
+        // At its expiry the quote is still live and only the missing signature is reported
+        // 到达过期时间时报价仍然有效，只会报告缺少签名
+        let blockhash = common::set_unix_timestamp(&mut context, expires_at).await;
+        let err = context.banks_client.process_transaction(send(blockhash)).await.unwrap_err().unwrap();
+        assert_eq!(custom_error(err), DexError::InvalidQuoteSignature as u32);
+
+        // One second later it has expired
+        // 一秒之后报价已过期
+        let blockhash = common::advance_unix_timestamp(&mut context, 1).await;
+        let err = context.banks_client.process_transaction(send(blockhash)).await.unwrap_err().unwrap();
+        assert_eq!(custom_error(err), DexError::QuoteExpired as u32);
+        assert_eq!(common::clock(&mut context).await.unix_timestamp, expires_at + 1);
+    }
+}
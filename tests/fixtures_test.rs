@@ -0,0 +1,1354 @@
+// Test vector fixture module
+// 测试向量夹具模块
+//
+// Builds deterministic encodings of every instruction, account state and event and
+// compares them against the JSON fixtures in `tests/fixtures`, which third-party SDKs
+// use to validate their decoders. Run with `UPDATE_FIXTURES=1` to regenerate them.
+// 构建每个指令、账户状态和事件的确定性编码，并与 `tests/fixtures` 中的 JSON 夹具比较，
+// 第三方 SDK 用这些夹具验证其解码器。使用 `UPDATE_FIXTURES=1` 运行可重新生成夹具。
+
+// The account fixtures are a single large `json!` literal
+// 账户夹具是一个很大的 `json!` 字面量
+#![recursion_limit = "256"]
+
+mod common;
+
+#[cfg(test)]
+mod fixtures_tests {
+    use super::common;
+    use borsh::{BorshDeserialize, BorshSerialize};
+    use serde_json::{json, Value};
+    use solana_program::{program_pack::Pack, pubkey::Pubkey};
+    use solana_rust_dex::{
+        instruction::{DexInstruction, SelfTradeBehavior},
+        state::{
+            Delegation, InstructionGuard, Market, MarketCreationMode, MarketRegistry, MarketRegistryEntry, MarketStatus, OpenOrders, Order,
+            EmissionPhase, FeeTier, InterestRateCurve, ProgramConfig,
+            Fill, FirmQuote, InsuranceFundTransfer, QuoteFill, SettlementRecord, Trade, TradeBust, TwapOracle, WhitelistEntry,
+            FeesSwept, MarketParam, OrderCancelled, OrderPlaced, ParamsUpdated,
+            MAX_ALLOWED_QUOTE_MINTS, load_zero_copy, load_zero_copy_mut, ACCOUNT_VERSION, ORDER_RESERVED_LEN,
+        },
+    };
+    use std::{env, fs, path::PathBuf};
+
+    // Fixed, recognizable public key so fixtures never change between runs
+    // 固定且易于识别的公钥，保证夹具在多次运行之间不变
+    fn key(byte: u8) -> Pubkey {
+        Pubkey::new_from_array([byte; 32])
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    // Name of an instruction variant; the exhaustive match makes new variants fail to
+    // compile until they are added here and given a fixture
+    // 指令变体名称；穷尽匹配使新增变体在加入此处并提供夹具之前无法编译
+    fn variant_name(instruction: &DexInstruction) -> &'static str {
+        match instruction {
+            DexInstruction::InitializeMarket { .. } => "InitializeMarket",
+            DexInstruction::PlaceLimitOrder { .. } => "PlaceLimitOrder",
+            DexInstruction::CancelOrder => "CancelOrder",
+            DexInstruction::SettleFunds => "SettleFunds",
+            DexInstruction::SetInstructionGuard { .. } => "SetInstructionGuard",
+            DexInstruction::InitializeProgramConfig => "InitializeProgramConfig",
+            DexInstruction::UpdateProgramConfig { .. } => "UpdateProgramConfig",
+            DexInstruction::CancelOrdersByIds { .. } => "CancelOrdersByIds",
+            DexInstruction::ReduceOrder { .. } => "ReduceOrder",
+            DexInstruction::SetMarketCreationFee { .. } => "SetMarketCreationFee",
+            DexInstruction::LinkOrders => "LinkOrders",
+            DexInstruction::CommitOpenOrdersRoot => "CommitOpenOrdersRoot",
+            DexInstruction::PlaceIcebergOrder { .. } => "PlaceIcebergOrder",
+            DexInstruction::SetReferralShare { .. } => "SetReferralShare",
+            DexInstruction::SetTradeBusting { .. } => "SetTradeBusting",
+            DexInstruction::BustTrade => "BustTrade",
+            DexInstruction::InitializeFeeVault => "InitializeFeeVault",
+            DexInstruction::SweepFees => "SweepFees",
+            DexInstruction::SetFeeAuthority { .. } => "SetFeeAuthority",
+            DexInstruction::SetMarketStatus { .. } => "SetMarketStatus",
+            DexInstruction::CloseMarket => "CloseMarket",
+            DexInstruction::AdminCancelOrder => "AdminCancelOrder",
+            DexInstruction::SetWhitelistEnabled { .. } => "SetWhitelistEnabled",
+            DexInstruction::AddWhitelistEntry { .. } => "AddWhitelistEntry",
+            DexInstruction::RemoveWhitelistEntry => "RemoveWhitelistEntry",
+            DexInstruction::ExecuteFirmQuote { .. } => "ExecuteFirmQuote",
+            DexInstruction::InitializeMarketWithSeed { .. } => "InitializeMarketWithSeed",
+            DexInstruction::SetOracle { .. } => "SetOracle",
+            DexInstruction::CheckOracle => "CheckOracle",
+            DexInstruction::InitializeInsuranceFund => "InitializeInsuranceFund",
+            DexInstruction::DepositInsuranceFund { .. } => "DepositInsuranceFund",
+            DexInstruction::WithdrawInsuranceFund { .. } => "WithdrawInsuranceFund",
+            DexInstruction::InitializeMarketVaults => "InitializeMarketVaults",
+            DexInstruction::CloseOrderAccount => "CloseOrderAccount",
+            DexInstruction::SetMinQuoteNotional { .. } => "SetMinQuoteNotional",
+            DexInstruction::Delegate { .. } => "Delegate",
+            DexInstruction::Deposit { .. } => "Deposit",
+            DexInstruction::Withdraw { .. } => "Withdraw",
+            DexInstruction::InitializeTwapOracle { .. } => "InitializeTwapOracle",
+            DexInstruction::MigrateAccount => "MigrateAccount",
+            DexInstruction::InitializeEventQueue => "InitializeEventQueue",
+            DexInstruction::ConsumeEvents { .. } => "ConsumeEvents",
+            DexInstruction::RescueTokens { .. } => "RescueTokens",
+            DexInstruction::InitializePool { .. } => "InitializePool",
+            DexInstruction::AddLiquidity { .. } => "AddLiquidity",
+            DexInstruction::RemoveLiquidity { .. } => "RemoveLiquidity",
+            DexInstruction::SwapAgainstPool { .. } => "SwapAgainstPool",
+            DexInstruction::RouteSwap { .. } => "RouteSwap",
+            DexInstruction::InitializeRangePool { .. } => "InitializeRangePool",
+            DexInstruction::AddRangeLiquidity { .. } => "AddRangeLiquidity",
+            DexInstruction::RemoveRangeLiquidity { .. } => "RemoveRangeLiquidity",
+            DexInstruction::SwapAgainstRangePool { .. } => "SwapAgainstRangePool",
+            DexInstruction::InitializeLendingPool { .. } => "InitializeLendingPool",
+            DexInstruction::Supply { .. } => "Supply",
+            DexInstruction::Redeem { .. } => "Redeem",
+            DexInstruction::InitializePerpMarket { .. } => "InitializePerpMarket",
+            DexInstruction::DepositPerpCollateral { .. } => "DepositPerpCollateral",
+            DexInstruction::WithdrawPerpCollateral { .. } => "WithdrawPerpCollateral",
+            DexInstruction::SettlePerpFill => "SettlePerpFill",
+            DexInstruction::UpdateFunding => "UpdateFunding",
+            DexInstruction::SetPerpReduceOnly { .. } => "SetPerpReduceOnly",
+            DexInstruction::InitializeBatchAuction { .. } => "InitializeBatchAuction",
+            DexInstruction::RunAuction { .. } => "RunAuction",
+            DexInstruction::InitializeTokenSale { .. } => "InitializeTokenSale",
+            DexInstruction::ContributeToSale { .. } => "ContributeToSale",
+            DexInstruction::FinalizeSale => "FinalizeSale",
+            DexInstruction::ClaimSale => "ClaimSale",
+            DexInstruction::InitializeStakePool { .. } => "InitializeStakePool",
+            DexInstruction::Stake { .. } => "Stake",
+            DexInstruction::Unstake { .. } => "Unstake",
+            DexInstruction::DistributeStakingRewards => "DistributeStakingRewards",
+            DexInstruction::ClaimStakingRewards => "ClaimStakingRewards",
+            DexInstruction::SetLoyaltySchedule { .. } => "SetLoyaltySchedule",
+            DexInstruction::OpenLoyaltyPoints => "OpenLoyaltyPoints",
+            DexInstruction::SetFeeSplit { .. } => "SetFeeSplit",
+            DexInstruction::SetDynamicFees { .. } => "SetDynamicFees",
+            DexInstruction::PlacePerpOrder { .. } => "PlacePerpOrder",
+            DexInstruction::CancelPerpOrder => "CancelPerpOrder",
+            DexInstruction::PublishPrices { .. } => "PublishPrices",
+        }
+    }
+
+    // Instruction vectors with the decoded fields an SDK should produce
+    // 指令向量以及 SDK 应解码出的字段
+    fn instruction_vectors() -> Vec<(DexInstruction, Value)> {
+        vec![
+            (
+                DexInstruction::InitializeMarket {
+                    min_base_order_size: 100,
+                    tick_size: 10,
+                    taker_fee_bps: 25,
+                    maker_fee_bps: -5,
+                    trustless: false,
+                    base_lot_size: 1_000,
+                    quote_lot_size: 10,
+                },
+                json!({
+                    "min_base_order_size": 100,
+                    "tick_size": 10,
+                    "taker_fee_bps": 25,
+                    "maker_fee_bps": -5,
+                    "trustless": false,
+                    "base_lot_size": 1000,
+                    "quote_lot_size": 10
+                }),
+            ),
+            (
+                DexInstruction::PlaceLimitOrder {
+                    is_buy: true,
+                    limit_price: 1_000,
+                    quantity: 500,
+                    self_trade_behavior: SelfTradeBehavior::DecrementTake,
+                    memo: "desk-7/ref-42".to_string(),
+                },
+                json!({
+                    "is_buy": true,
+                    "limit_price": 1000,
+                    "quantity": 500,
+                    "self_trade_behavior": "DecrementTake",
+                    "memo": "desk-7/ref-42",
+                }),
+            ),
+            (DexInstruction::CancelOrder, json!({})),
+            (DexInstruction::SettleFunds, json!({})),
+            (
+                DexInstruction::SetInstructionGuard {
+                    guard: InstructionGuard::Isolated,
+                },
+                json!({ "guard": "Isolated" }),
+            ),
+            (DexInstruction::InitializeProgramConfig, json!({})),
+            (
+                DexInstruction::UpdateProgramConfig {
+                    market_creation_mode: MarketCreationMode::AdminOnly,
+                    allowed_quote_mints: vec![key(1), key(2)],
+                },
+                json!({
+                    "market_creation_mode": "AdminOnly",
+                    "allowed_quote_mints": [key(1).to_string(), key(2).to_string()],
+                }),
+            ),
+            (
+                DexInstruction::CancelOrdersByIds {
+                    order_ids: vec![1, 2, 3],
+                },
+                json!({ "order_ids": [1, 2, 3] }),
+            ),
+            (
+                DexInstruction::ReduceOrder {
+                    new_remaining_quantity: 250,
+                },
+                json!({ "new_remaining_quantity": 250 }),
+            ),
+            (
+                DexInstruction::SetMarketCreationFee {
+                    treasury: key(3),
+                    fee_lamports: 5_000_000,
+                    fee_quote: 1_000_000,
+                },
+                json!({ "treasury": key(3).to_string(), "fee_lamports": 5000000, "fee_quote": 1000000 }),
+            ),
+            (DexInstruction::LinkOrders, json!({})),
+            (DexInstruction::CommitOpenOrdersRoot, json!({})),
+            (
+                DexInstruction::PlaceIcebergOrder {
+                    is_buy: false,
+                    limit_price: 2_000,
+                    quantity: 1_000,
+                    display_quantity: 200,
+                    self_trade_behavior: SelfTradeBehavior::CancelProvide,
+                    memo: String::new(),
+                },
+                json!({
+                    "is_buy": false,
+                    "limit_price": 2000,
+                    "quantity": 1000,
+                    "display_quantity": 200,
+                    "self_trade_behavior": "CancelProvide",
+                    "memo": "",
+                }),
+            ),
+            (
+                DexInstruction::SetReferralShare {
+                    referral_share_bps: 2_000,
+                },
+                json!({ "referral_share_bps": 2000 }),
+            ),
+            (
+                DexInstruction::SetTradeBusting {
+                    enabled: true,
+                    window_slots: 150,
+                },
+                json!({ "enabled": true, "window_slots": 150 }),
+            ),
+            (DexInstruction::BustTrade, json!({})),
+            (DexInstruction::InitializeFeeVault, json!({})),
+            (DexInstruction::SweepFees, json!({})),
+            (
+                DexInstruction::SetFeeAuthority {
+                    fee_authority: key(15),
+                },
+                json!({ "fee_authority": key(15).to_string() }),
+            ),
+            (
+                DexInstruction::SetMarketStatus {
+                    status: MarketStatus::CancelOnly,
+                },
+                json!({ "status": "CancelOnly" }),
+            ),
+            (DexInstruction::CloseMarket, json!({})),
+            (DexInstruction::AdminCancelOrder, json!({})),
+            (
+                DexInstruction::SetWhitelistEnabled { enabled: true },
+                json!({ "enabled": true }),
+            ),
+            (
+                DexInstruction::AddWhitelistEntry { owner: key(4) },
+                json!({ "owner": key(4).to_string() }),
+            ),
+            (DexInstruction::RemoveWhitelistEntry, json!({})),
+            (
+                DexInstruction::ExecuteFirmQuote {
+                    quote: FirmQuote {
+                        market: key(5),
+                        maker: key(4),
+                        taker: key(9),
+                        is_buy: false,
+                        price: 2_000,
+                        quantity: 500,
+                        expires_at: 1_700_000_600,
+                        nonce: 42,
+                    },
+                    base_amount: 200,
+                },
+                json!({
+                    "quote": {
+                        "market": key(5).to_string(),
+                        "maker": key(4).to_string(),
+                        "taker": key(9).to_string(),
+                        "is_buy": false,
+                        "price": 2_000,
+                        "quantity": 500,
+                        "expires_at": 1_700_000_600,
+                        "nonce": 42,
+                    },
+                    "base_amount": 200,
+                }),
+            ),
+            (
+                DexInstruction::InitializeMarketWithSeed {
+                    seed: 3,
+                    min_base_order_size: 100,
+                    tick_size: 10,
+                    taker_fee_bps: 25,
+                    maker_fee_bps: -5,
+                    trustless: true,
+                    base_lot_size: 1_000,
+                    quote_lot_size: 10,
+                },
+                json!({
+                    "seed": 3,
+                    "min_base_order_size": 100,
+                    "tick_size": 10,
+                    "taker_fee_bps": 25,
+                    "maker_fee_bps": -5,
+                    "trustless": true,
+                    "base_lot_size": 1000,
+                    "quote_lot_size": 10
+                }),
+            ),
+            (
+                DexInstruction::SetOracle {
+                    oracle: key(16),
+                    band_bps: 500,
+                    max_staleness_slots: 25,
+                    price_expo: -6,
+                },
+                json!({
+                    "oracle": key(16).to_string(),
+                    "band_bps": 500,
+                    "max_staleness_slots": 25,
+                    "price_expo": -6,
+                }),
+            ),
+            (DexInstruction::CheckOracle, json!({})),
+            (DexInstruction::InitializeInsuranceFund, json!({})),
+            (
+                DexInstruction::DepositInsuranceFund { amount: 50_000 },
+                json!({ "amount": 50000 }),
+            ),
+            (
+                DexInstruction::WithdrawInsuranceFund { amount: 20_000 },
+                json!({ "amount": 20000 }),
+            ),
+            (DexInstruction::InitializeMarketVaults, json!({})),
+            (DexInstruction::CloseOrderAccount, json!({})),
+            (
+                DexInstruction::SetMinQuoteNotional { min_quote_notional: 5_000 },
+                json!({ "min_quote_notional": 5000 }),
+            ),
+            (
+                DexInstruction::Delegate {
+                    delegate: key(8),
+                    expiry_slot: 124_000,
+                },
+                json!({ "delegate": key(8).to_string(), "expiry_slot": 124000 }),
+            ),
+            (
+                DexInstruction::Deposit {
+                    is_quote: true,
+                    amount: 1_000_000,
+                },
+                json!({ "is_quote": true, "amount": 1000000 }),
+            ),
+            (
+                DexInstruction::Withdraw {
+                    is_quote: false,
+                    amount: 250_000,
+                },
+                json!({ "is_quote": false, "amount": 250000 }),
+            ),
+            (
+                DexInstruction::InitializeTwapOracle { ema_period_seconds: 600 },
+                json!({ "ema_period_seconds": 600 }),
+            ),
+            (DexInstruction::MigrateAccount, json!({})),
+            (DexInstruction::InitializeEventQueue, json!({})),
+            (
+                DexInstruction::ConsumeEvents {
+                    seq_num: 42,
+                    limit: 16,
+                    observed_slot: 1_000,
+                    max_staleness_slots: 150,
+                },
+                json!({ "seq_num": 42, "limit": 16, "observed_slot": 1000, "max_staleness_slots": 150 }),
+            ),
+            (DexInstruction::RescueTokens { is_quote: true }, json!({ "is_quote": true })),
+            (DexInstruction::InitializePool { fee_bps: 30 }, json!({ "fee_bps": 30 })),
+            (
+                DexInstruction::AddLiquidity {
+                    max_base_amount: 1_000_000,
+                    max_quote_amount: 2_000_000,
+                    min_lp_amount: 1_400_000,
+                },
+                json!({ "max_base_amount": 1000000, "max_quote_amount": 2000000, "min_lp_amount": 1400000 }),
+            ),
+            (
+                DexInstruction::RemoveLiquidity {
+                    lp_amount: 500_000,
+                    min_base_amount: 350_000,
+                    min_quote_amount: 700_000,
+                },
+                json!({ "lp_amount": 500000, "min_base_amount": 350000, "min_quote_amount": 700000 }),
+            ),
+            (
+                DexInstruction::SwapAgainstPool {
+                    is_buy: true,
+                    amount_in: 10_000,
+                    min_amount_out: 4_900,
+                },
+                json!({ "is_buy": true, "amount_in": 10000, "min_amount_out": 4900 }),
+            ),
+            (
+                DexInstruction::RouteSwap {
+                    is_buy: false,
+                    amount_in: 25_000,
+                    min_amount_out: 48_000,
+                    num_orders: 3,
+                },
+                json!({ "is_buy": false, "amount_in": 25000, "min_amount_out": 48000, "num_orders": 3 }),
+            ),
+            (
+                DexInstruction::InitializeRangePool {
+                    fee_bps: 5,
+                    tick_spacing: 10,
+                    tick: -23_030,
+                },
+                json!({ "fee_bps": 5, "tick_spacing": 10, "tick": -23030 }),
+            ),
+            (
+                DexInstruction::AddRangeLiquidity {
+                    tick_lower: -23_100,
+                    tick_upper: -22_900,
+                    liquidity: 1_000_000,
+                    max_base_amount: 60_000,
+                    max_quote_amount: 700,
+                },
+                json!({
+                    "tick_lower": -23100,
+                    "tick_upper": -22900,
+                    "liquidity": 1000000,
+                    "max_base_amount": 60000,
+                    "max_quote_amount": 700
+                }),
+            ),
+            (
+                DexInstruction::RemoveRangeLiquidity {
+                    liquidity: 400_000,
+                    min_base_amount: 20_000,
+                    min_quote_amount: 250,
+                },
+                json!({ "liquidity": 400000, "min_base_amount": 20000, "min_quote_amount": 250 }),
+            ),
+            (
+                DexInstruction::SwapAgainstRangePool {
+                    is_buy: true,
+                    amount_in: 500,
+                    min_amount_out: 4_900,
+                },
+                json!({ "is_buy": true, "amount_in": 500, "min_amount_out": 4900 }),
+            ),
+            (
+                DexInstruction::InitializeLendingPool {
+                    rate_curve: InterestRateCurve {
+                        base_rate_bps: 100,
+                        optimal_utilization_bps: 8_000,
+                        slope1_bps: 400,
+                        slope2_bps: 7_500,
+                    },
+                },
+                json!({
+                    "rate_curve": {
+                        "base_rate_bps": 100,
+                        "optimal_utilization_bps": 8000,
+                        "slope1_bps": 400,
+                        "slope2_bps": 7500
+                    }
+                }),
+            ),
+            (
+                DexInstruction::Supply {
+                    amount: 1_000_000,
+                    min_shares: 990_000,
+                },
+                json!({ "amount": 1000000, "min_shares": 990000 }),
+            ),
+            (
+                DexInstruction::Redeem {
+                    shares: 500_000,
+                    min_amount: 505_000,
+                },
+                json!({ "shares": 500000, "min_amount": 505000 }),
+            ),
+            (
+                DexInstruction::InitializePerpMarket {
+                    initial_margin_bps: 1_000,
+                    max_funding_rate_bps: 50,
+                },
+                json!({ "initial_margin_bps": 1000, "max_funding_rate_bps": 50 }),
+            ),
+            (
+                DexInstruction::DepositPerpCollateral { amount: 1_000_000 },
+                json!({ "amount": 1000000 }),
+            ),
+            (
+                DexInstruction::WithdrawPerpCollateral { amount: 250_000 },
+                json!({ "amount": 250000 }),
+            ),
+            (
+                DexInstruction::SettlePerpFill,
+                json!({}),
+            ),
+            (DexInstruction::UpdateFunding, json!({})),
+            (
+                DexInstruction::SetPerpReduceOnly { reduce_only: true },
+                json!({ "reduce_only": true }),
+            ),
+            (
+                DexInstruction::InitializeBatchAuction { interval_slots: 10 },
+                json!({ "interval_slots": 10 }),
+            ),
+            (DexInstruction::RunAuction { num_orders: 4 }, json!({ "num_orders": 4 })),
+            (
+                DexInstruction::InitializeTokenSale {
+                    base_amount: 1_000_000,
+                    min_price: 5,
+                    max_contribution: 250_000,
+                    duration_seconds: 86_400,
+                },
+                json!({
+                    "base_amount": 1_000_000,
+                    "min_price": 5,
+                    "max_contribution": 250_000,
+                    "duration_seconds": 86_400
+                }),
+            ),
+            (DexInstruction::ContributeToSale { amount: 50_000 }, json!({ "amount": 50_000 })),
+            (DexInstruction::FinalizeSale, json!({})),
+            (DexInstruction::ClaimSale, json!({})),
+            (
+                DexInstruction::InitializeStakePool {
+                    epoch_seconds: 604_800,
+                    fee_share_bps: 5_000,
+                    fee_tiers: [
+                        FeeTier { min_stake: 1_000, discount_bps: 500 },
+                        FeeTier { min_stake: 10_000, discount_bps: 1_000 },
+                        FeeTier { min_stake: 100_000, discount_bps: 2_000 },
+                        FeeTier::default(),
+                    ],
+                },
+                json!({
+                    "epoch_seconds": 604_800,
+                    "fee_share_bps": 5_000,
+                    "fee_tiers": [
+                        { "min_stake": 1_000, "discount_bps": 500 },
+                        { "min_stake": 10_000, "discount_bps": 1_000 },
+                        { "min_stake": 100_000, "discount_bps": 2_000 },
+                        { "min_stake": 0, "discount_bps": 0 }
+                    ]
+                }),
+            ),
+            (DexInstruction::Stake { amount: 5_000 }, json!({ "amount": 5_000 })),
+            (DexInstruction::Unstake { amount: 2_000 }, json!({ "amount": 2_000 })),
+            (DexInstruction::DistributeStakingRewards, json!({})),
+            (DexInstruction::ClaimStakingRewards, json!({})),
+            (
+                DexInstruction::SetLoyaltySchedule {
+                    num_phases: 2,
+                    phases: [
+                        EmissionPhase { start_timestamp: 1_700_000_000, taker_points_bps: 100, maker_points_bps: 200 },
+                        EmissionPhase { start_timestamp: 1_702_592_000, taker_points_bps: 50, maker_points_bps: 100 },
+                        EmissionPhase::default(),
+                        EmissionPhase::default(),
+                    ],
+                },
+                json!({
+                    "num_phases": 2,
+                    "phases": [
+                        { "start_timestamp": 1_700_000_000, "taker_points_bps": 100, "maker_points_bps": 200 },
+                        { "start_timestamp": 1_702_592_000, "taker_points_bps": 50, "maker_points_bps": 100 },
+                        { "start_timestamp": 0, "taker_points_bps": 0, "maker_points_bps": 0 },
+                        { "start_timestamp": 0, "taker_points_bps": 0, "maker_points_bps": 0 }
+                    ]
+                }),
+            ),
+            (DexInstruction::OpenLoyaltyPoints, json!({})),
+            (
+                DexInstruction::SetFeeSplit { treasury_share_bps: 2_000, creator_share_bps: 3_000 },
+                json!({ "treasury_share_bps": 2_000, "creator_share_bps": 3_000 }),
+            ),
+            (
+                DexInstruction::SetDynamicFees { min_fee_bps: 5, volatility_bps: 150, volatility_window: 30 },
+                json!({ "min_fee_bps": 5, "volatility_bps": 150, "volatility_window": 30 }),
+            ),
+            (
+                DexInstruction::PlacePerpOrder {
+                    is_buy: true,
+                    limit_price: 1_000,
+                    quantity: 50,
+                    reduce_only: false,
+                },
+                json!({ "is_buy": true, "limit_price": 1000, "quantity": 50, "reduce_only": false }),
+            ),
+            (DexInstruction::CancelPerpOrder, json!({})),
+            (
+                DexInstruction::PublishPrices { nonce: 7, finalized: true },
+                json!({ "nonce": 7, "finalized": true }),
+            ),
+        ]
+    }
+
+    fn sample_market() -> Market {
+        Market {
+            authority: key(1),
+            base_mint: key(2),
+            quote_mint: key(3),
+            min_base_order_size: 100,
+            tick_size: 10,
+            taker_fee_bps: 25,
+            maker_fee_bps: -5,
+            next_order_id: 7,
+            num_bids: 2,
+            num_asks: 3,
+            instruction_guard: InstructionGuard::TopLevelOnly,
+            open_orders_root: [0xab; 32],
+            open_orders_root_leaves: 5,
+            open_orders_root_slot: 123_456,
+            referral_share_bps: 2_000,
+            fee_authority: key(15),
+            status: MarketStatus::CancelOnly,
+            bid_base_total: 1_500,
+            ask_base_total: 500,
+            bid_notional: 3_000_000,
+            ask_notional: 1_100_000,
+            whitelist_enabled: true,
+            oracle: key(16),
+            oracle_band_bps: 500,
+            oracle_max_staleness_slots: 25,
+            oracle_price_expo: -6,
+            fill_seq_num: 42,
+            base_decimals: 9,
+            trustless: true,
+            signer_bump: 254,
+            base_lot_size: 1_000,
+            quote_lot_size: 10,
+            min_quote_notional: 5_000,
+            base_free_total: 3_000,
+            quote_free_total: 450_000,
+            best_bid: 990,
+            best_ask: 1_010,
+            last_price: 1_000,
+            last_trade_slot: 123_400,
+            base_volume: 8_000_000,
+            quote_volume: 80_000,
+            volume_buckets: {
+                let mut volume_buckets = [0; 24];
+                volume_buckets[472_250 % 24] = 50_000;
+                volume_buckets[472_249 % 24] = 30_000;
+                volume_buckets
+            },
+            volume_bucket_hour: 472_250,
+            twap_oracle_enabled: true,
+            quote_token_program: spl_token_2022::id(),
+            fees_accrued: 12_500,
+            fees_swept: 10_000,
+            referral_fees_paid: 2_500,
+            treasury_fee_account: key(12),
+            treasury_fee_share_bps: 2_000,
+            creator_fee_account: key(13),
+            creator_fee_share_bps: 3_000,
+            dynamic_fee_min_bps: 5,
+            dynamic_fee_volatility_bps: 150,
+            volatility_window: 30,
+            realized_variance: 4_900,
+            ..common::market()
+        }
+    }
+
+    fn sample_order() -> Order {
+        Order {
+            is_initialized: true,
+            order_id: 6,
+            owner: key(4),
+            market: key(5),
+            is_buy: false,
+            limit_price: 2_000,
+            original_quantity: 1_000,
+            remaining_quantity: 800,
+            creation_timestamp: 1_700_000_000,
+            linked_order: key(6),
+            display_quantity: 200,
+            visible_quantity: 200,
+            referrer: key(7),
+            memo: *b"desk-7/ref-42\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0",
+            version: ACCOUNT_VERSION,
+            reserved: [0; ORDER_RESERVED_LEN],
+        }
+    }
+
+    fn sample_program_config() -> ProgramConfig {
+        let mut allowed_quote_mints = [Pubkey::default(); MAX_ALLOWED_QUOTE_MINTS];
+        allowed_quote_mints[0] = key(3);
+        ProgramConfig {
+            is_initialized: true,
+            admin: key(1),
+            market_creation_mode: MarketCreationMode::AdminOnly,
+            num_allowed_quote_mints: 1,
+            allowed_quote_mints,
+            treasury: key(8),
+            market_creation_fee_lamports: 5_000_000,
+            market_creation_fee_quote: 1_000_000,
+            trade_busting_enabled: true,
+            trade_bust_window_slots: 150,
+        }
+    }
+
+    fn sample_settlement_record() -> SettlementRecord {
+        SettlementRecord {
+            is_initialized: true,
+            market: key(5),
+            slot: 123_456,
+            taker_base_account: key(10),
+            taker_quote_account: key(11),
+            maker_base_account: key(12),
+            maker_quote_account: key(13),
+            base_amount: 200,
+            maker_quote_amount: 399_900,
+            is_busted: false,
+        }
+    }
+
+    fn sample_whitelist_entry() -> WhitelistEntry {
+        WhitelistEntry {
+            is_initialized: true,
+            market: key(5),
+            owner: key(4),
+        }
+    }
+
+    fn sample_delegation() -> Delegation {
+        Delegation {
+            is_initialized: true,
+            market: key(5),
+            owner: key(4),
+            delegate: key(8),
+            expiry_slot: 124_000,
+        }
+    }
+
+    fn sample_open_orders() -> OpenOrders {
+        OpenOrders {
+            is_initialized: true,
+            market: key(5),
+            owner: key(4),
+            base_free: 3_000,
+            quote_free: 450_000,
+        }
+    }
+
+    fn sample_twap_oracle() -> TwapOracle {
+        TwapOracle {
+            is_initialized: true,
+            market: key(5),
+            ema_period_seconds: 600,
+            last_price: 1_000,
+            last_update: 1_700_100_000,
+            price_cumulative: 98_765_432_100,
+            ema_price: 995,
+            fill_count: 42,
+        }
+    }
+
+    fn sample_quote_fill() -> QuoteFill {
+        QuoteFill {
+            is_initialized: true,
+            market: key(5),
+            maker: key(4),
+            nonce: 42,
+            filled_quantity: 200,
+        }
+    }
+
+    fn sample_market_registry() -> MarketRegistry {
+        MarketRegistry {
+            is_initialized: true,
+            num_markets: 1,
+        }
+    }
+
+    fn sample_market_registry_entry() -> MarketRegistryEntry {
+        MarketRegistryEntry {
+            market: key(5),
+            base_mint: key(2),
+            quote_mint: key(3),
+            created_slot: 123_456,
+        }
+    }
+
+    fn pack<T: Pack>(state: &T) -> Vec<u8> {
+        let mut data = vec![0; T::LEN];
+        state.pack_into_slice(&mut data);
+        data
+    }
+
+    fn build_instructions_fixture() -> Value {
+        let vectors: Vec<Value> = instruction_vectors()
+            .into_iter()
+            .map(|(instruction, fields)| {
+                let data = instruction.try_to_vec().unwrap();
+                json!({
+                    "name": variant_name(&instruction),
+                    "discriminant": data[0],
+                    "fields": fields,
+                    "data": hex(&data),
+                })
+            })
+            .collect();
+        json!({ "encoding": "borsh", "instructions": vectors })
+    }
+
+    // Copy a field out of a packed account struct, which cannot be borrowed
+    fn field<T: Copy>(value: T) -> T {
+        value
+    }
+
+    fn build_accounts_fixture() -> Value {
+        let market = sample_market();
+        let order = sample_order();
+        let config = sample_program_config();
+        let record = sample_settlement_record();
+        let entry = sample_whitelist_entry();
+        let delegation = sample_delegation();
+        let open_orders = sample_open_orders();
+        let twap_oracle = sample_twap_oracle();
+        let quote_fill = sample_quote_fill();
+        let registry = sample_market_registry();
+        let registry_entry = sample_market_registry_entry();
+        json!({
+            "encoding": "borsh",
+            "accounts": [
+                {
+                    "name": "Market",
+                    "len": Market::LEN,
+                    "fields": {
+                        "is_initialized": field(market.is_initialized),
+                        "authority": market.authority.to_string(),
+                        "base_mint": market.base_mint.to_string(),
+                        "quote_mint": market.quote_mint.to_string(),
+                        "min_base_order_size": field(market.min_base_order_size),
+                        "tick_size": field(market.tick_size),
+                        "taker_fee_bps": field(market.taker_fee_bps),
+                        "maker_fee_bps": field(market.maker_fee_bps),
+                        "next_order_id": field(market.next_order_id),
+                        "num_bids": field(market.num_bids),
+                        "num_asks": field(market.num_asks),
+                        "instruction_guard": "TopLevelOnly",
+                        "open_orders_root": hex(&market.open_orders_root),
+                        "open_orders_root_leaves": field(market.open_orders_root_leaves),
+                        "open_orders_root_slot": field(market.open_orders_root_slot),
+                        "referral_share_bps": field(market.referral_share_bps),
+                        "fee_authority": market.fee_authority.to_string(),
+                        "status": "CancelOnly",
+                        "bid_base_total": field(market.bid_base_total),
+                        "ask_base_total": field(market.ask_base_total),
+                        "bid_notional": field(market.bid_notional),
+                        "ask_notional": field(market.ask_notional),
+                        "whitelist_enabled": field(market.whitelist_enabled),
+                        "oracle": market.oracle.to_string(),
+                        "oracle_band_bps": field(market.oracle_band_bps),
+                        "oracle_max_staleness_slots": field(market.oracle_max_staleness_slots),
+                        "oracle_price_expo": field(market.oracle_price_expo),
+                        "fill_seq_num": field(market.fill_seq_num),
+                        "base_decimals": field(market.base_decimals),
+                        "quote_decimals": field(market.quote_decimals),
+                        "trustless": field(market.trustless),
+                        "signer_bump": field(market.signer_bump),
+                        "base_lot_size": field(market.base_lot_size),
+                        "quote_lot_size": field(market.quote_lot_size),
+                        "min_quote_notional": field(market.min_quote_notional),
+                        "base_free_total": field(market.base_free_total),
+                        "quote_free_total": field(market.quote_free_total),
+                        "best_bid": field(market.best_bid),
+                        "best_ask": field(market.best_ask),
+                        "last_price": field(market.last_price),
+                        "last_trade_slot": field(market.last_trade_slot),
+                        "base_volume": field(market.base_volume),
+                        "quote_volume": field(market.quote_volume),
+                        "volume_buckets": field(market.volume_buckets),
+                        "volume_bucket_hour": field(market.volume_bucket_hour),
+                        "twap_oracle_enabled": field(market.twap_oracle_enabled),
+                        "base_token_program": market.base_token_program.to_string(),
+                        "quote_token_program": market.quote_token_program.to_string(),
+                        "version": field(market.version),
+                        "flags": field(market.flags),
+                        "fees_accrued": field(market.fees_accrued),
+                        "fees_swept": field(market.fees_swept),
+                        "referral_fees_paid": field(market.referral_fees_paid),
+                        "treasury_fee_account": market.treasury_fee_account.to_string(),
+                        "treasury_fee_share_bps": field(market.treasury_fee_share_bps),
+                        "creator_fee_account": market.creator_fee_account.to_string(),
+                        "creator_fee_share_bps": field(market.creator_fee_share_bps),
+                        "dynamic_fee_min_bps": field(market.dynamic_fee_min_bps),
+                        "dynamic_fee_volatility_bps": field(market.dynamic_fee_volatility_bps),
+                        "volatility_window": field(market.volatility_window),
+                        "realized_variance": field(market.realized_variance),
+                    },
+                    "data": hex(&pack(&market)),
+                },
+                {
+                    "name": "Order",
+                    "len": Order::LEN,
+                    "fields": {
+                        "is_initialized": field(order.is_initialized),
+                        "order_id": field(order.order_id),
+                        "owner": order.owner.to_string(),
+                        "market": order.market.to_string(),
+                        "is_buy": field(order.is_buy),
+                        "limit_price": field(order.limit_price),
+                        "original_quantity": field(order.original_quantity),
+                        "remaining_quantity": field(order.remaining_quantity),
+                        "creation_timestamp": field(order.creation_timestamp),
+                        "linked_order": order.linked_order.to_string(),
+                        "display_quantity": field(order.display_quantity),
+                        "visible_quantity": field(order.visible_quantity),
+                        "referrer": order.referrer.to_string(),
+                        "memo": order.memo(),
+                        "version": field(order.version),
+                    },
+                    "data": hex(&pack(&order)),
+                },
+                {
+                    "name": "ProgramConfig",
+                    "len": ProgramConfig::LEN,
+                    "fields": {
+                        "is_initialized": config.is_initialized,
+                        "admin": config.admin.to_string(),
+                        "market_creation_mode": "AdminOnly",
+                        "num_allowed_quote_mints": config.num_allowed_quote_mints,
+                        "allowed_quote_mints": config
+                            .allowed_quote_mints
+                            .iter()
+                            .map(|mint| mint.to_string())
+                            .collect::<Vec<_>>(),
+                        "treasury": config.treasury.to_string(),
+                        "market_creation_fee_lamports": config.market_creation_fee_lamports,
+                        "market_creation_fee_quote": config.market_creation_fee_quote,
+                        "trade_busting_enabled": config.trade_busting_enabled,
+                        "trade_bust_window_slots": config.trade_bust_window_slots,
+                    },
+                    "data": hex(&pack(&config)),
+                },
+                {
+                    "name": "SettlementRecord",
+                    "len": SettlementRecord::LEN,
+                    "fields": {
+                        "is_initialized": record.is_initialized,
+                        "market": record.market.to_string(),
+                        "slot": record.slot,
+                        "taker_base_account": record.taker_base_account.to_string(),
+                        "taker_quote_account": record.taker_quote_account.to_string(),
+                        "maker_base_account": record.maker_base_account.to_string(),
+                        "maker_quote_account": record.maker_quote_account.to_string(),
+                        "base_amount": record.base_amount,
+                        "maker_quote_amount": record.maker_quote_amount,
+                        "is_busted": record.is_busted,
+                    },
+                    "data": hex(&pack(&record)),
+                },
+                {
+                    "name": "WhitelistEntry",
+                    "len": WhitelistEntry::LEN,
+                    "fields": {
+                        "is_initialized": entry.is_initialized,
+                        "market": entry.market.to_string(),
+                        "owner": entry.owner.to_string(),
+                    },
+                    "data": hex(&pack(&entry)),
+                },
+                {
+                    "name": "Delegation",
+                    "len": Delegation::LEN,
+                    "fields": {
+                        "is_initialized": delegation.is_initialized,
+                        "market": delegation.market.to_string(),
+                        "owner": delegation.owner.to_string(),
+                        "delegate": delegation.delegate.to_string(),
+                        "expiry_slot": delegation.expiry_slot,
+                    },
+                    "data": hex(&pack(&delegation)),
+                },
+                {
+                    "name": "OpenOrders",
+                    "len": OpenOrders::LEN,
+                    "fields": {
+                        "is_initialized": open_orders.is_initialized,
+                        "market": open_orders.market.to_string(),
+                        "owner": open_orders.owner.to_string(),
+                        "base_free": open_orders.base_free,
+                        "quote_free": open_orders.quote_free,
+                    },
+                    "data": hex(&pack(&open_orders)),
+                },
+                {
+                    "name": "TwapOracle",
+                    "len": TwapOracle::LEN,
+                    "fields": {
+                        "is_initialized": twap_oracle.is_initialized,
+                        "market": twap_oracle.market.to_string(),
+                        "ema_period_seconds": twap_oracle.ema_period_seconds,
+                        "last_price": twap_oracle.last_price,
+                        "last_update": twap_oracle.last_update,
+                        "price_cumulative": twap_oracle.price_cumulative,
+                        "ema_price": twap_oracle.ema_price,
+                        "fill_count": twap_oracle.fill_count,
+                    },
+                    "data": hex(&pack(&twap_oracle)),
+                },
+                {
+                    "name": "QuoteFill",
+                    "len": QuoteFill::LEN,
+                    "fields": {
+                        "is_initialized": quote_fill.is_initialized,
+                        "market": quote_fill.market.to_string(),
+                        "maker": quote_fill.maker.to_string(),
+                        "nonce": quote_fill.nonce,
+                        "filled_quantity": quote_fill.filled_quantity,
+                    },
+                    "data": hex(&pack(&quote_fill)),
+                },
+                {
+                    "name": "MarketRegistry",
+                    "len": MarketRegistry::LEN,
+                    "fields": {
+                        "is_initialized": registry.is_initialized,
+                        "num_markets": registry.num_markets,
+                    },
+                    "data": hex(&pack(&registry)),
+                },
+                {
+                    "name": "MarketRegistryEntry",
+                    "len": MarketRegistryEntry::LEN,
+                    "fields": {
+                        "market": registry_entry.market.to_string(),
+                        "base_mint": registry_entry.base_mint.to_string(),
+                        "quote_mint": registry_entry.quote_mint.to_string(),
+                        "created_slot": registry_entry.created_slot,
+                    },
+                    "data": hex(&pack(&registry_entry)),
+                },
+            ],
+        })
+    }
+
+    fn build_events_fixture() -> Value {
+        let trade = Trade {
+            maker_order_id: 6,
+            taker_order_id: 7,
+            maker: key(4),
+            taker: key(9),
+            price: 2_000,
+            quantity: 200,
+            taker_side: true,
+            timestamp: 1_700_000_100,
+        };
+        let bust = TradeBust {
+            market: key(5),
+            settlement: key(14),
+            authority: key(1),
+            base_amount: 200,
+            quote_amount: 399_900,
+            settlement_slot: 123_456,
+            bust_slot: 123_500,
+        };
+        let fill = Fill {
+            market: key(5),
+            seq_num: 41,
+            taker_order: key(7),
+            maker_order: key(6),
+            base_amount: 200,
+            quote_amount: 400_000,
+            taker_fee: 1_000,
+            maker_fee: -100,
+            slot: 123_456,
+            taker_memo: "desk-7/ref-42".to_string(),
+            maker_memo: String::new(),
+        };
+        let insurance_fund_transfer = InsuranceFundTransfer {
+            market: key(5),
+            token_account: key(10),
+            is_deposit: false,
+            amount: 20_000,
+            balance: 30_000,
+            slot: 123_600,
+        };
+        let order_placed = OrderPlaced {
+            market: key(5),
+            order: key(6),
+            owner: key(4),
+            order_id: 6,
+            is_buy: false,
+            limit_price: 2_000,
+            quantity: 300,
+            display_quantity: 100,
+            locked_amount: 300,
+            slot: 123_400,
+        };
+        let order_cancelled = OrderCancelled {
+            market: key(5),
+            order: key(6),
+            owner: key(4),
+            order_id: 6,
+            is_buy: false,
+            limit_price: 2_000,
+            cancelled_quantity: 100,
+            remaining_quantity: 0,
+            refund_amount: 100,
+            slot: 123_700,
+        };
+        let fees_swept = FeesSwept {
+            market: key(5),
+            destination: key(11),
+            amount: 600,
+            slot: 123_800,
+        };
+        let params_updated = ParamsUpdated {
+            market: key(5),
+            authority: key(1),
+            param: MarketParam::Oracle {
+                oracle: key(12),
+                band_bps: 500,
+                max_staleness_slots: 25,
+                price_expo: -3,
+            },
+            slot: 123_900,
+        };
+        json!({
+            "encoding": "borsh",
+            "events": [
+                {
+                    "name": "Trade",
+                    "fields": {
+                        "maker_order_id": trade.maker_order_id,
+                        "taker_order_id": trade.taker_order_id,
+                        "maker": trade.maker.to_string(),
+                        "taker": trade.taker.to_string(),
+                        "price": trade.price,
+                        "quantity": trade.quantity,
+                        "taker_side": trade.taker_side,
+                        "timestamp": trade.timestamp,
+                    },
+                    "data": hex(&trade.try_to_vec().unwrap()),
+                },
+                {
+                    "name": "TradeBust",
+                    "log_prefix": "TradeBust",
+                    "fields": {
+                        "market": bust.market.to_string(),
+                        "settlement": bust.settlement.to_string(),
+                        "authority": bust.authority.to_string(),
+                        "base_amount": bust.base_amount,
+                        "quote_amount": bust.quote_amount,
+                        "settlement_slot": bust.settlement_slot,
+                        "bust_slot": bust.bust_slot,
+                    },
+                    "data": hex(&bust.try_to_vec().unwrap()),
+                },
+                {
+                    "name": "Fill",
+                    "log_prefix": "Fill",
+                    "fields": {
+                        "market": fill.market.to_string(),
+                        "seq_num": fill.seq_num,
+                        "taker_order": fill.taker_order.to_string(),
+                        "maker_order": fill.maker_order.to_string(),
+                        "base_amount": fill.base_amount,
+                        "quote_amount": fill.quote_amount,
+                        "taker_fee": fill.taker_fee,
+                        "maker_fee": fill.maker_fee,
+                        "slot": fill.slot,
+                        "taker_memo": fill.taker_memo,
+                        "maker_memo": fill.maker_memo,
+                    },
+                    "data": hex(&fill.try_to_vec().unwrap()),
+                },
+                {
+                    "name": "InsuranceFundTransfer",
+                    "log_prefix": "InsuranceFund",
+                    "fields": {
+                        "market": insurance_fund_transfer.market.to_string(),
+                        "token_account": insurance_fund_transfer.token_account.to_string(),
+                        "is_deposit": insurance_fund_transfer.is_deposit,
+                        "amount": insurance_fund_transfer.amount,
+                        "balance": insurance_fund_transfer.balance,
+                        "slot": insurance_fund_transfer.slot,
+                    },
+                    "data": hex(&insurance_fund_transfer.try_to_vec().unwrap()),
+                },
+                {
+                    "name": "OrderPlaced",
+                    "log_prefix": "OrderPlaced",
+                    "fields": {
+                        "market": order_placed.market.to_string(),
+                        "order": order_placed.order.to_string(),
+                        "owner": order_placed.owner.to_string(),
+                        "order_id": order_placed.order_id,
+                        "is_buy": order_placed.is_buy,
+                        "limit_price": order_placed.limit_price,
+                        "quantity": order_placed.quantity,
+                        "display_quantity": order_placed.display_quantity,
+                        "locked_amount": order_placed.locked_amount,
+                        "slot": order_placed.slot,
+                    },
+                    "data": hex(&order_placed.try_to_vec().unwrap()),
+                },
+                {
+                    "name": "OrderCancelled",
+                    "log_prefix": "OrderCancelled",
+                    "fields": {
+                        "market": order_cancelled.market.to_string(),
+                        "order": order_cancelled.order.to_string(),
+                        "owner": order_cancelled.owner.to_string(),
+                        "order_id": order_cancelled.order_id,
+                        "is_buy": order_cancelled.is_buy,
+                        "limit_price": order_cancelled.limit_price,
+                        "cancelled_quantity": order_cancelled.cancelled_quantity,
+                        "remaining_quantity": order_cancelled.remaining_quantity,
+                        "refund_amount": order_cancelled.refund_amount,
+                        "slot": order_cancelled.slot,
+                    },
+                    "data": hex(&order_cancelled.try_to_vec().unwrap()),
+                },
+                {
+                    "name": "FeesSwept",
+                    "log_prefix": "FeesSwept",
+                    "fields": {
+                        "market": fees_swept.market.to_string(),
+                        "destination": fees_swept.destination.to_string(),
+                        "amount": fees_swept.amount,
+                        "slot": fees_swept.slot,
+                    },
+                    "data": hex(&fees_swept.try_to_vec().unwrap()),
+                },
+                {
+                    "name": "ParamsUpdated",
+                    "log_prefix": "ParamsUpdated",
+                    "fields": {
+                        "market": params_updated.market.to_string(),
+                        "authority": params_updated.authority.to_string(),
+                        "param": {
+                            "Oracle": {
+                                "oracle": key(12).to_string(),
+                                "band_bps": 500,
+                                "max_staleness_slots": 25,
+                                "price_expo": -3,
+                            },
+                        },
+                        "slot": params_updated.slot,
+                    },
+                    "data": hex(&params_updated.try_to_vec().unwrap()),
+                },
+            ],
+        })
+    }
+
+    // Compare a fixture with the checked-in file, or rewrite it when requested
+    // 将夹具与已提交的文件比较，或在需要时重写该文件
+    fn check_fixture(file_name: &str, fixture: Value) {
+        let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "fixtures", file_name]
+            .iter()
+            .collect();
+        let generated = serde_json::to_string_pretty(&fixture).unwrap() + "\n";
+
+        if env::var_os("UPDATE_FIXTURES").is_some() {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, generated).unwrap();
+            return;
+        }
+
+        let existing = fs::read_to_string(&path).unwrap_or_default();
+        assert!(
+            existing == generated,
+            "{} is out of date, regenerate it with `UPDATE_FIXTURES=1 cargo test --test fixtures_test`",
+            path.display()
+        );
+    }
+
+    #[test]
+    fn test_instruction_fixtures() {
+        // Discriminants follow declaration order and every vector round-trips
+        // 判别值遵循声明顺序，且每个向量都能往返编解码
+        for (index, (instruction, _)) in instruction_vectors().iter().enumerate() {
+            let data = instruction.try_to_vec().unwrap();
+            assert_eq!(data[0] as usize, index);
+            assert_eq!(&DexInstruction::try_from_slice(&data).unwrap(), instruction);
+        }
+
+        check_fixture("instructions.json", build_instructions_fixture());
+    }
+
+    #[test]
+    fn test_account_fixtures() {
+        // Packed states fill their accounts exactly
+        // 打包后的状态正好填满账户
+        assert_eq!(bytemuck::bytes_of(&sample_market()).len(), Market::LEN);
+        assert_eq!(bytemuck::bytes_of(&sample_order()).len(), Order::LEN);
+        assert_eq!(sample_program_config().try_to_vec().unwrap().len(), ProgramConfig::LEN);
+        assert_eq!(sample_settlement_record().try_to_vec().unwrap().len(), SettlementRecord::LEN);
+        assert_eq!(sample_whitelist_entry().try_to_vec().unwrap().len(), WhitelistEntry::LEN);
+        assert_eq!(sample_delegation().try_to_vec().unwrap().len(), Delegation::LEN);
+        assert_eq!(sample_open_orders().try_to_vec().unwrap().len(), OpenOrders::LEN);
+        assert_eq!(sample_twap_oracle().try_to_vec().unwrap().len(), TwapOracle::LEN);
+        assert_eq!(sample_quote_fill().try_to_vec().unwrap().len(), QuoteFill::LEN);
+        assert_eq!(sample_market_registry().try_to_vec().unwrap().len(), MarketRegistry::LEN);
+        assert_eq!(
+            sample_market_registry_entry().try_to_vec().unwrap().len(),
+            MarketRegistryEntry::LEN
+        );
+
+        check_fixture("accounts.json", build_accounts_fixture());
+    }
+
+    #[test]
+    fn test_zero_copy_accounts() {
+        // Markets and orders are read and updated in place in their packed layout
+        // 市场和订单以打包布局原地读取和更新
+        let mut data = pack(&sample_market());
+        let market = load_zero_copy_mut::<Market>(&mut data).unwrap();
+        market.fill_seq_num += 1;
+        market.status = MarketStatus::Paused;
+        let mut expected = sample_market();
+        expected.fill_seq_num += 1;
+        expected.status = MarketStatus::Paused;
+        assert_eq!(data, pack(&expected));
+        let order = sample_order();
+        assert_eq!(
+            { load_zero_copy::<Order>(&pack(&order)).unwrap().order_id },
+            { order.order_id }
+        );
+
+        // Short data and invalid bool or enum bytes are rejected
+        // 过短的数据以及无效的布尔值或枚举字节会被拒绝
+        assert!(load_zero_copy::<Market>(&data[..Market::LEN - 1]).is_err());
+        let status_offset = 1 + 32 * 3 + 8 * 2 + 2 * 2 + 8 * 3 + 1 + 32 + 8 * 2 + 2 + 32;
+        assert_eq!(data[status_offset], MarketStatus::Paused as u8);
+        data[status_offset] = 3;
+        assert!(Market::unpack_from_slice(&data).is_err());
+        let mut order_data = pack(&order);
+        order_data[0] = 2;
+        assert!(Order::unpack_from_slice(&order_data).is_err());
+    }
+
+    #[test]
+    fn test_event_fixtures() {
+        check_fixture("events.json", build_events_fixture());
+    }
+}
@@ -0,0 +1,134 @@
+// IDL test module
+// IDL 测试模块
+//
+// The shipped IDL is generated from the source with `shank idl`; these tests check it against
+// the Borsh test vectors so that a stale IDL is caught before a client decodes with it.
+// 随附的 IDL 由 `shank idl` 从源码生成；这些测试将其与 Borsh 测试向量进行比对，
+// 以便在客户端使用过时的 IDL 解码之前发现问题。
+
+#[cfg(test)]
+mod idl_tests {
+    use serde_json::Value;
+    use solana_rust_dex::error::DexError;
+    use std::{fs, path::PathBuf};
+
+    fn read_json(path: &[&str]) -> Value {
+        let path: PathBuf = path.iter().fold(PathBuf::from(env!("CARGO_MANIFEST_DIR")), |path, part| path.join(part));
+        serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap()
+    }
+
+    fn idl() -> Value {
+        read_json(&["idl", "solana_rust_dex.json"])
+    }
+
+    // Names in the IDL are camelCase
+    // IDL 中的名称使用小驼峰命名
+    fn camel_case(name: &str) -> String {
+        let mut parts = name.split('_');
+        let mut camel = parts.next().unwrap().to_string();
+        for part in parts {
+            let mut chars = part.chars();
+            if let Some(first) = chars.next() {
+                camel.extend(first.to_uppercase());
+                camel.push_str(chars.as_str());
+            }
+        }
+        camel
+    }
+
+    // Encoded size of a fixed-size IDL type; defined types are the program's fieldless enums
+    // IDL 定长类型的编码大小；自定义类型均为程序中无字段的枚举
+    fn type_len(ty: &Value) -> usize {
+        match ty {
+            Value::String(name) => match name.as_str() {
+                "bool" | "u8" | "i8" => 1,
+                "u16" | "i16" => 2,
+                "u32" | "i32" => 4,
+                "u64" | "i64" => 8,
+                "u128" | "i128" => 16,
+                "publicKey" => 32,
+                name => panic!("unexpected type {}", name),
+            },
+            Value::Object(object) if object.contains_key("array") => {
+                let array = object["array"].as_array().unwrap();
+                type_len(&array[0]) * array[1].as_u64().unwrap() as usize
+            }
+            Value::Object(object) if object.contains_key("defined") => 1,
+            ty => panic!("unexpected type {}", ty),
+        }
+    }
+
+    #[test]
+    fn test_idl_instructions_match_fixtures() {
+        let idl = idl();
+        let fixtures = read_json(&["tests", "fixtures", "instructions.json"]);
+        assert_eq!(idl["metadata"]["address"], solana_rust_dex::id().to_string());
+
+        // Every instruction has the discriminant and arguments of its test vector
+        // 每条指令的判别值和参数都与其测试向量一致
+        let instructions = idl["instructions"].as_array().unwrap();
+        let vectors = fixtures["instructions"].as_array().unwrap();
+        assert_eq!(instructions.len(), vectors.len());
+        for (instruction, vector) in instructions.iter().zip(vectors) {
+            assert_eq!(instruction["name"], vector["name"]);
+            assert_eq!(instruction["discriminant"]["value"], vector["discriminant"]);
+
+            let mut args: Vec<String> = instruction["args"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|arg| arg["name"].as_str().unwrap().to_string())
+                .collect();
+            let mut fields: Vec<String> = vector["fields"].as_object().unwrap().keys().map(|field| camel_case(field)).collect();
+            args.sort();
+            fields.sort();
+            assert_eq!(args, fields, "{}", instruction["name"]);
+            assert!(!instruction["accounts"].as_array().unwrap().is_empty(), "{}", instruction["name"]);
+        }
+    }
+
+    #[test]
+    fn test_idl_accounts_match_fixtures() {
+        let idl = idl();
+        let fixtures = read_json(&["tests", "fixtures", "accounts.json"]);
+
+        // Fixed-size accounts have the fields and length of their test vectors
+        // 定长账户的字段和长度与其测试向量一致
+        let accounts = idl["accounts"].as_array().unwrap();
+        for name in ["Market", "Order", "ProgramConfig", "SettlementRecord", "WhitelistEntry", "Delegation", "OpenOrders", "TwapOracle", "QuoteFill"] {
+            let account = accounts.iter().find(|account| account["name"] == name).unwrap();
+            let vector = fixtures["accounts"].as_array().unwrap().iter().find(|vector| vector["name"] == name).unwrap();
+            let fields = account["type"]["fields"].as_array().unwrap();
+
+            let len: usize = fields.iter().map(|field| type_len(&field["type"])).sum();
+            assert_eq!(len as u64, vector["len"], "{}", name);
+
+            // Padding is not a field of the test vectors
+            // 填充字节不属于测试向量的字段
+            let mut names: Vec<String> = fields
+                .iter()
+                .filter(|field| field.get("attrs").is_none())
+                .map(|field| field["name"].as_str().unwrap().to_string())
+                .collect();
+            let mut vector_names: Vec<String> = vector["fields"].as_object().unwrap().keys().map(|field| camel_case(field)).collect();
+            names.sort();
+            vector_names.sort();
+            assert_eq!(names, vector_names, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_idl_errors_match_program() {
+        // Error codes count up from zero and carry the program's messages
+        // 错误码从零开始递增，并带有程序自身的消息
+        let idl = idl();
+        let errors = idl["errors"].as_array().unwrap();
+        for (code, error) in errors.iter().enumerate() {
+            assert_eq!(error["code"], code as u64);
+        }
+        assert_eq!(errors[0]["msg"], DexError::InvalidInstructionData.to_string());
+        let error = &errors[DexError::AccountNotMigrated as usize];
+        assert_eq!(error["name"], "AccountNotMigrated");
+        assert_eq!(error["msg"], DexError::AccountNotMigrated.to_string());
+    }
+}
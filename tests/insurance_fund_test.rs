@@ -0,0 +1,206 @@
+// Insurance fund test module
+// 保险基金测试模块
+
+mod common;
+
+#[cfg(test)]
+mod insurance_fund_tests {
+    use super::common;
+    use solana_program::{
+        program_option::COption,
+        program_pack::Pack,
+        pubkey::Pubkey,
+    };
+    use solana_program_test::*;
+    use solana_sdk::{
+        account::Account,
+        instruction::{Instruction, InstructionError},
+        signature::{Keypair, Signer},
+        transaction::{Transaction, TransactionError},
+    };
+    use solana_rust_dex::{
+        error::DexError,
+        instruction::DexInstruction,
+        state::Market,
+    };
+
+    fn token_account(mint: &Pubkey, owner: &Pubkey, amount: u64) -> Account {
+        let token_account = spl_token::state::Account {
+            mint: *mint,
+            owner: *owner,
+            amount,
+            state: spl_token::state::AccountState::Initialized,
+            ..spl_token::state::Account::default()
+        };
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        token_account.pack_into_slice(&mut data);
+        Account {
+            lamports: 1000000000,
+            data,
+            owner: spl_token::id(),
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    fn custom_error(err: TransactionError) -> u32 {
+        match err {
+            TransactionError::InstructionError(0, InstructionError::Custom(code)) => code,
+            err => panic!("unexpected error: {:?}", err),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_insurance_fund_deposit_and_withdraw() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // Add a quote mint and a market to the test environment
+        // 将报价代币和市场添加到测试环境
+        let quote_mint = Pubkey::new_unique();
+        let mint = spl_token::state::Mint {
+            mint_authority: COption::Some(Pubkey::new_unique()),
+            decimals: 6,
+            is_initialized: true,
+            ..spl_token::state::Mint::default()
+        };
+        let mut mint_data = vec![0; spl_token::state::Mint::LEN];
+        mint.pack_into_slice(&mut mint_data);
+        program_test.add_account(
+            quote_mint,
+            Account {
+                lamports: 1000000000,
+                data: mint_data,
+                owner: spl_token::id(),
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+
+        let authority = Keypair::new();
+        let market_account = Pubkey::new_unique();
+        let market = Market {
+            authority: authority.pubkey(),
+            base_mint: Pubkey::new_unique(),
+            quote_mint,
+            min_base_order_size: 100,
+            tick_size: 10,
+            taker_fee_bps: 25,
+            next_order_id: 0,
+            fee_authority: authority.pubkey(),
+            ..common::market()
+        };
+        let mut market_data = vec![0; Market::LEN];
+        market.pack_into_slice(&mut market_data);
+        program_test.add_account(
+            market_account,
+            Account {
+                lamports: 1000000000,
+                data: market_data,
+                owner: program_id,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+
+        // A depositor holding quote tokens and a destination for draws
+        // 持有报价代币的存款人以及提取的目标账户
+        let depositor = Keypair::new();
+        let source = Pubkey::new_unique();
+        program_test.add_account(source, token_account(&quote_mint, &depositor.pubkey(), 1000));
+        let destination = Pubkey::new_unique();
+        program_test.add_account(destination, token_account(&quote_mint, &Pubkey::new_unique(), 0));
+
+        // Start the test environment
+        // 启动测试环境
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let send = |instruction: Instruction, signer: &Keypair| {
+            let mut transaction = Transaction::new_with_payer(&[instruction], Some(&payer.pubkey()));
+            transaction.sign(&[&payer, signer], recent_blockhash);
+            transaction
+        };
+
+        // Anyone creates the insurance fund, once
+        // 任何人都可以创建保险基金，且只能创建一次
+        let (insurance_fund, _) = Market::find_insurance_fund_address(&program_id, &market_account);
+        let init_fund_ix = DexInstruction::initialize_insurance_fund(
+            &program_id,
+            &payer.pubkey(),
+            &market_account,
+            &quote_mint,
+            &spl_token::id(),
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(std::slice::from_ref(&init_fund_ix), Some(&payer.pubkey()));
+        transaction.sign(&[&payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        let fund_account = banks_client.get_account(insurance_fund).await.unwrap().unwrap();
+        let fund = spl_token::state::Account::unpack(&fund_account.data).unwrap();
+        assert_eq!(fund.owner, insurance_fund);
+        assert_eq!(fund.mint, quote_mint);
+
+        let mut transaction = Transaction::new_with_payer(&[init_fund_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer], banks_client.get_latest_blockhash().await.unwrap());
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(custom_error(err), DexError::InvalidAccountData as u32);
+
+        // Anyone deposits into the fund
+        // 任何人都可以向基金存款
+        let deposit_ix = DexInstruction::deposit_insurance_fund(
+            &program_id,
+            &depositor.pubkey(),
+            &market_account,
+            &source,
+            &spl_token::id(),
+            600,
+        )
+        .unwrap();
+        banks_client.process_transaction(send(deposit_ix, &depositor)).await.unwrap();
+
+        // Only the market authority draws from the fund
+        // 只有市场权限账户可以从基金中提取
+        let withdraw_ix = |authority: &Pubkey, amount: u64| {
+            DexInstruction::withdraw_insurance_fund(
+                &program_id,
+                authority,
+                &market_account,
+                &destination,
+                &spl_token::id(),
+                amount,
+            )
+            .unwrap()
+        };
+        let err = banks_client
+            .process_transaction(send(withdraw_ix(&depositor.pubkey(), 250), &depositor))
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(custom_error(err), DexError::AccountNotAuthorized as u32);
+
+        // Draws are limited to the fund balance
+        // 提取金额不能超过基金余额
+        let err = banks_client
+            .process_transaction(send(withdraw_ix(&authority.pubkey(), 700), &authority))
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(custom_error(err), DexError::InsufficientInsuranceFund as u32);
+
+        banks_client
+            .process_transaction(send(withdraw_ix(&authority.pubkey(), 250), &authority))
+            .await
+            .unwrap();
+
+        for (address, expected) in [(source, 400), (insurance_fund, 350), (destination, 250)] {
+            let account = banks_client.get_account(address).await.unwrap().unwrap();
+            assert_eq!(spl_token::state::Account::unpack(&account.data).unwrap().amount, expected);
+        }
+    }
+}
@@ -0,0 +1,309 @@
+// Token sale test module
+// 代币发售测试模块
+
+mod common;
+
+#[cfg(test)]
+mod launchpad_tests {
+    use super::common;
+    use solana_program::{program_pack::Pack, pubkey::Pubkey, system_instruction};
+    use solana_program_test::*;
+    use solana_sdk::{
+        account::Account,
+        instruction::{Instruction, InstructionError},
+        signature::{Keypair, Signer},
+        transaction::{Transaction, TransactionError},
+    };
+    use solana_rust_dex::{
+        error::DexError,
+        instruction::DexInstruction,
+        state::{
+            Market, MarketStatus, SaleContribution, SaleStatus, TokenSale,
+        },
+    };
+
+    fn custom_error(err: TransactionError) -> u32 {
+        match err {
+            TransactionError::InstructionError(0, InstructionError::Custom(code)) => code,
+            err => panic!("unexpected error: {:?}", err),
+        }
+    }
+
+    fn market(authority: &Pubkey, base_mint: &Pubkey, quote_mint: &Pubkey) -> Market {
+        Market {
+            authority: *authority,
+            base_mint: *base_mint,
+            quote_mint: *quote_mint,
+            taker_fee_bps: 25,
+            next_order_id: 0,
+            fee_authority: *authority,
+            status: MarketStatus::Paused,
+            ..common::market()
+        }
+    }
+
+    fn sale(base_amount: u64, total_contributed: u64) -> TokenSale {
+        TokenSale {
+            is_initialized: true,
+            market: Pubkey::new_unique(),
+            bump: 0,
+            base_amount,
+            min_price: 0,
+            max_contribution: 0,
+            end_timestamp: 0,
+            total_contributed,
+            clearing_price: 0,
+            status: SaleStatus::Open,
+        }
+    }
+
+    #[test]
+    fn test_implied_price_in_lots() {
+        let mut market = market(&Pubkey::new_unique(), &Pubkey::new_unique(), &Pubkey::new_unique());
+
+        // 400000 quote tokens for 100000 base tokens is 4 per token
+        // 用 400000 报价代币购买 100000 基础代币，即每个代币 4
+        assert_eq!(sale(100_000, 400_000).implied_price(&market), 4);
+
+        // With base lots of 100 and quote lots of 10 that is 40 quote lots per base lot
+        // 基础手数为 100、报价手数为 10 时，即每基础手 40 报价手
+        market.base_lot_size = 100;
+        market.quote_lot_size = 10;
+        assert_eq!(sale(100_000, 400_000).implied_price(&market), 40);
+
+        // Prices round down, and nothing contributed prices at zero
+        // 价格向下取整，无人认购时价格为零
+        assert_eq!(sale(100_000, 399_999).implied_price(&market), 39);
+        assert_eq!(sale(100_000, 0).implied_price(&market), 0);
+    }
+
+    #[test]
+    fn test_allocation_is_pro_rata() {
+        // Contributors share the tokens for sale in proportion to what they paid, rounded down
+        // 认购者按其支付金额的比例分配发售代币，向下取整
+        let sale = sale(100_000, 300_000);
+        assert_eq!(sale.allocation(150_000), 50_000);
+        assert_eq!(sale.allocation(100_000), 33_333);
+        assert_eq!(sale.allocation(50_000), 16_666);
+        assert_eq!(sale.allocation(0), 0);
+    }
+
+    #[tokio::test]
+    async fn test_token_sale() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // Add the mints, a paused market, the authority's tokens for sale and two contributors
+        // 添加代币铸造账户、暂停的市场、管理员待售的代币以及两个认购者
+        let base_mint = common::add_mint(&mut program_test, 6, None);
+        let quote_mint = common::add_mint(&mut program_test, 6, None);
+        let authority = Keypair::new();
+        let market_account = Pubkey::new_unique();
+        let mut market_data = vec![0; Market::LEN];
+        market(&authority.pubkey(), &base_mint, &quote_mint).pack_into_slice(&mut market_data);
+        program_test.add_account(
+            market_account,
+            Account {
+                lamports: 1000000000,
+                data: market_data,
+                owner: program_id,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+        let authority_base = common::add_token_account(&mut program_test, &base_mint, &authority.pubkey(), None, 100_000);
+        let authority_quote = common::add_token_account(&mut program_test, &quote_mint, &authority.pubkey(), None, 0);
+
+        let contributors = [(); 2].map(|_| {
+            let contributor = Keypair::new();
+            let base = common::add_token_account(&mut program_test, &base_mint, &contributor.pubkey(), None, 0);
+            let quote = common::add_token_account(&mut program_test, &quote_mint, &contributor.pubkey(), None, 500_000);
+            (contributor, base, quote)
+        });
+        let [alice, bob] = &contributors;
+
+        // Start the test environment
+        // 启动测试环境
+        let mut context = program_test.start_with_context().await;
+        let payer = context.payer.insecure_clone();
+        let blockhash = context.last_blockhash;
+        let send = |instructions: &[Instruction], signer: &Keypair, blockhash| {
+            let mut transaction = Transaction::new_with_payer(instructions, Some(&payer.pubkey()));
+            transaction.sign(&[&payer, signer], blockhash);
+            transaction
+        };
+
+        // Sell 100000 base tokens for an hour at no less than 2, capping contributions at 300000
+        // 以不低于 2 的价格发售 100000 基础代币一小时，每人认购上限为 300000
+        let fund_ixs: Vec<_> = [&authority, &alice.0, &bob.0]
+            .iter()
+            .map(|account| system_instruction::transfer(&payer.pubkey(), &account.pubkey(), 1_000_000_000))
+            .collect();
+        let init_sale_ix = DexInstruction::initialize_token_sale(
+            &program_id,
+            &authority.pubkey(),
+            &market_account,
+            &authority_base,
+            &base_mint,
+            &quote_mint,
+            &spl_token::id(),
+            100_000,
+            2,
+            300_000,
+            3_600,
+        )
+        .unwrap();
+        context
+            .banks_client
+            .process_transaction(send(&[fund_ixs, vec![init_sale_ix]].concat(), &authority, blockhash))
+            .await
+            .unwrap();
+        let base_vault = TokenSale::find_vault_address(&program_id, &market_account, false).0;
+        let quote_vault = TokenSale::find_vault_address(&program_id, &market_account, true).0;
+        assert_eq!(common::token_balance(&mut context.banks_client, &base_vault).await, 100_000);
+        assert_eq!(common::token_balance(&mut context.banks_client, &authority_base).await, 0);
+
+        let contribute_ix = |(contributor, _, quote): &(Keypair, Pubkey, Pubkey), amount| {
+            DexInstruction::contribute_to_sale(
+                &program_id,
+                &contributor.pubkey(),
+                &market_account,
+                quote,
+                &quote_mint,
+                &spl_token::id(),
+                amount,
+            )
+            .unwrap()
+        };
+        let finalize_ix = DexInstruction::finalize_sale(
+            &program_id,
+            &market_account,
+            &authority_base,
+            &authority_quote,
+            &base_mint,
+            &quote_mint,
+            &spl_token::id(),
+        )
+        .unwrap();
+        let claim_ix = |(contributor, base, _): &(Keypair, Pubkey, Pubkey)| {
+            DexInstruction::claim_sale(
+                &program_id,
+                &contributor.pubkey(),
+                &market_account,
+                base,
+                &base_mint,
+                &quote_mint,
+                &spl_token::id(),
+            )
+            .unwrap()
+        };
+
+        // Alice pays 150000 and Bob 250000 in two payments; a third would pass Bob's cap
+        // Alice 支付 150000，Bob 分两次支付 250000；第三次支付将超过 Bob 的上限
+        for (contributor, amount) in [(alice, 150_000), (bob, 200_000), (bob, 50_000)] {
+            context
+                .banks_client
+                .process_transaction(send(&[contribute_ix(contributor, amount)], &contributor.0, blockhash))
+                .await
+                .unwrap();
+        }
+        let err = context
+            .banks_client
+            .process_transaction(send(&[contribute_ix(bob, 60_000)], &bob.0, blockhash))
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(custom_error(err), DexError::ContributionCapExceeded as u32);
+        let contribution_address = SaleContribution::find_address(&program_id, &market_account, &bob.0.pubkey()).0;
+        let contribution = context.banks_client.get_account(contribution_address).await.unwrap().unwrap();
+        assert_eq!(SaleContribution::unpack_from_slice(&contribution.data).unwrap().amount, 250_000);
+
+        // The sale cannot be finalized, nor claimed, while it is open
+        // 发售开放期间既不能结算也不能领取
+        let err = context
+            .banks_client
+            .process_transaction(send(std::slice::from_ref(&finalize_ix), &payer, blockhash))
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(custom_error(err), DexError::SaleNotEnded as u32);
+        let err = context
+            .banks_client
+            .process_transaction(send(&[claim_ix(alice)], &alice.0, blockhash))
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(custom_error(err), DexError::SaleClosed as u32);
+
+        // After the hour contributions close
+        // 一小时后停止认购
+        let blockhash = common::advance_unix_timestamp(&mut context, 3_600).await;
+        let err = context
+            .banks_client
+            .process_transaction(send(&[contribute_ix(alice, 10_000)], &alice.0, blockhash))
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(custom_error(err), DexError::SaleClosed as u32);
+
+        // 400000 for 100000 tokens clears at 4: the authority gets the proceeds and the market
+        // opens at that price
+        // 400000 购买 100000 代币，成交价为 4：管理员获得募集资金，市场以该价格开放
+        context
+            .banks_client
+            .process_transaction(send(std::slice::from_ref(&finalize_ix), &payer, blockhash))
+            .await
+            .unwrap();
+        assert_eq!(common::token_balance(&mut context.banks_client, &authority_quote).await, 400_000);
+        let market_account_data = context.banks_client.get_account(market_account).await.unwrap().unwrap();
+        let market_state = Market::unpack_from_slice(&market_account_data.data).unwrap();
+        assert_eq!(market_state.status, MarketStatus::Active);
+        assert_eq!({ market_state.last_price }, 4);
+        let sale_address = TokenSale::find_address(&program_id, &market_account).0;
+        let sale = context.banks_client.get_account(sale_address).await.unwrap().unwrap();
+        let sale = TokenSale::unpack_from_slice(&sale.data).unwrap();
+        assert_eq!((sale.status, sale.clearing_price, sale.total_contributed), (SaleStatus::Succeeded, 4, 400_000));
+
+        // A sale is finalized once
+        // 发售只能结算一次
+        let blockhash = context.get_new_latest_blockhash().await.unwrap();
+        let err = context
+            .banks_client
+            .process_transaction(send(&[finalize_ix], &payer, blockhash))
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(custom_error(err), DexError::SaleClosed as u32);
+
+        // Each contributor claims its share of the tokens, closing its contribution
+        // 每个认购者领取其份额的代币，并关闭其认购账户
+        for (contributor, allocation) in [(alice, 37_500), (bob, 62_500)] {
+            context
+                .banks_client
+                .process_transaction(send(&[claim_ix(contributor)], &contributor.0, blockhash))
+                .await
+                .unwrap();
+            assert_eq!(common::token_balance(&mut context.banks_client, &contributor.1).await, allocation);
+        }
+        assert!(context.banks_client.get_account(contribution_address).await.unwrap().is_none());
+        assert_eq!(common::token_balance(&mut context.banks_client, &base_vault).await, 0);
+        assert_eq!(common::token_balance(&mut context.banks_client, &quote_vault).await, 0);
+
+        // A closed contribution has nothing left to claim
+        // 已关闭的认购账户无可领取
+        let blockhash = context.get_new_latest_blockhash().await.unwrap();
+        let err = context
+            .banks_client
+            .process_transaction(send(&[claim_ix(bob)], &bob.0, blockhash))
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(custom_error(err), DexError::AccountMismatch as u32);
+    }
+}
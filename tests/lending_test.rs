@@ -0,0 +1,306 @@
+// Lending pool test module
+// 借贷资金池测试模块
+
+mod common;
+
+#[cfg(test)]
+mod lending_tests {
+    use super::common;
+    use solana_program::{program_pack::Pack, pubkey::Pubkey};
+    use solana_program_test::*;
+    use solana_sdk::{
+        account::AccountSharedData,
+        instruction::InstructionError,
+        signature::{Keypair, Signer},
+        transaction::{Transaction, TransactionError},
+    };
+    use solana_rust_dex::{
+        error::DexError,
+        instruction::DexInstruction,
+        state::{InterestRateCurve, LendingPool, SECONDS_PER_YEAR},
+    };
+
+    const RATE_CURVE: InterestRateCurve = InterestRateCurve {
+        base_rate_bps: 200,
+        optimal_utilization_bps: 8_000,
+        slope1_bps: 400,
+        slope2_bps: 6_000,
+    };
+
+    fn custom_error(err: TransactionError) -> u32 {
+        match err {
+            TransactionError::InstructionError(0, InstructionError::Custom(code)) => code,
+            err => panic!("unexpected error: {:?}", err),
+        }
+    }
+
+    fn lending_pool(available: u64, borrowed: u64, share_supply: u64) -> LendingPool {
+        LendingPool {
+            is_initialized: true,
+            mint: Pubkey::new_unique(),
+            token_program: spl_token::id(),
+            share_mint: Pubkey::new_unique(),
+            bump: 255,
+            decimals: 6,
+            rate_curve: RATE_CURVE,
+            available,
+            borrowed,
+            share_supply,
+            borrow_index: LendingPool::INITIAL_BORROW_INDEX,
+            last_accrual_timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn test_rate_curve_kinks_at_optimal_utilization() {
+        // Below the kink the rate climbs along the first slope, above it along the second
+        // 拐点以下利率沿第一斜率上升，拐点以上沿第二斜率上升
+        assert_eq!(RATE_CURVE.borrow_rate_bps(0), 200);
+        assert_eq!(RATE_CURVE.borrow_rate_bps(5_000), 450);
+        assert_eq!(RATE_CURVE.borrow_rate_bps(8_000), 600);
+        assert_eq!(RATE_CURVE.borrow_rate_bps(9_000), 3_600);
+        assert_eq!(RATE_CURVE.borrow_rate_bps(10_000), 6_600);
+        assert_eq!(RATE_CURVE.borrow_rate_bps(20_000), 6_600);
+
+        // The kink must lie strictly inside the utilization range
+        // 拐点必须严格位于利用率范围之内
+        assert!(RATE_CURVE.is_valid());
+        assert!(!InterestRateCurve { optimal_utilization_bps: 0, ..RATE_CURVE }.is_valid());
+        assert!(!InterestRateCurve { optimal_utilization_bps: 10_000, ..RATE_CURVE }.is_valid());
+    }
+
+    #[test]
+    fn test_accrue_interest_grows_borrows_and_shares() {
+        // Half the pool is lent out at 4.5% a year
+        // 资金池一半被借出，年利率为 4.5%
+        let mut pool = lending_pool(500_000, 500_000, 1_000_000);
+        assert_eq!(pool.utilization_bps(), 5_000);
+        assert_eq!(pool.borrow_rate_bps(), 450);
+        assert_eq!(pool.supply_rate_bps(), 225);
+
+        // Accruing twice at the same timestamp charges nothing the second time
+        // 在同一时间戳重复计息，第二次不收取利息
+        pool.accrue_interest(SECONDS_PER_YEAR as i64).unwrap();
+        pool.accrue_interest(SECONDS_PER_YEAR as i64).unwrap();
+        assert_eq!(pool.borrowed, 522_500);
+        assert_eq!(pool.available, 500_000);
+        assert_eq!(pool.total_supplied(), 1_022_500);
+        assert_eq!(
+            pool.borrow_index,
+            LendingPool::INITIAL_BORROW_INDEX + (LendingPool::INITIAL_BORROW_INDEX * 450).div_ceil(10_000)
+        );
+
+        // Interest rounds up against borrowers even over a single second
+        // 即使只经过一秒，利息也按不利于借款人的方向向上取整
+        pool.accrue_interest(SECONDS_PER_YEAR as i64 + 1).unwrap();
+        assert_eq!(pool.borrowed, 522_501);
+    }
+
+    #[test]
+    fn test_shares_round_against_the_supplier() {
+        // The first supply mints one share per token
+        // 首次存入时每个代币铸造一份份额
+        assert_eq!(lending_pool(0, 0, 0).supply_shares(1_000).unwrap(), 1_000);
+
+        // Once interest has accrued a share is worth more than a token
+        // 计息之后每份份额价值超过一个代币
+        let pool = lending_pool(500_000, 522_500, 1_000_000);
+        assert_eq!(pool.supply_shares(100).unwrap(), 97);
+        assert_eq!(pool.redeem_amount(97).unwrap(), 99);
+        assert_eq!(pool.redeem_amount(1_000_000).unwrap(), 1_022_500);
+
+        // Shares worth nothing cannot price new supplies
+        // 价值为零的份额无法为新存入定价
+        assert!(lending_pool(0, 0, 1_000).supply_shares(100).is_err());
+
+        // Lending and repaying move tokens between available and borrowed
+        // 借出和偿还在可用与已借出之间转移代币
+        let mut pool = lending_pool(1_000, 0, 1_000);
+        pool.borrow(400).unwrap();
+        assert_eq!((pool.available, pool.borrowed), (600, 400));
+        assert!(pool.borrow(601).is_err());
+        pool.repay(400).unwrap();
+        assert_eq!((pool.available, pool.borrowed), (1_000, 0));
+        assert!(pool.repay(1).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_supply_and_redeem() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+        let mut context = program_test.start_with_context().await;
+        let payer = context.payer.insecure_clone();
+        let recent_blockhash = context.last_blockhash;
+
+        // Create the lent mint and a supplier holding some of it
+        // 创建出借的代币铸造账户以及持有该代币的存款人
+        let mint = common::create_mint(&mut context.banks_client, &payer, recent_blockhash, 6).await;
+        let supplier = Keypair::new();
+        let source =
+            common::create_token_account(&mut context.banks_client, &payer, recent_blockhash, &mint, &supplier.pubkey())
+                .await;
+        common::mint_to(&mut context.banks_client, &payer, recent_blockhash, &mint, &source, 1_000_000).await;
+
+        // Initialize the config with the payer as admin
+        // 以付款人为管理员初始化配置
+        let init_config_ix = DexInstruction::initialize_program_config(&program_id, &payer.pubkey()).unwrap();
+        let mut transaction = Transaction::new_with_payer(&[init_config_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer], recent_blockhash);
+        context.banks_client.process_transaction(transaction).await.unwrap();
+
+        // A non-admin may not create lending pools
+        // 非管理员不能创建借贷资金池
+        let outsider = Keypair::new();
+        let init_pool_ix =
+            DexInstruction::initialize_lending_pool(&program_id, &outsider.pubkey(), &mint, &spl_token::id(), RATE_CURVE)
+                .unwrap();
+        let mut transaction = Transaction::new_with_payer(&[init_pool_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &outsider], recent_blockhash);
+        let err = context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(custom_error(err), DexError::AccountNotAuthorized as u32);
+
+        // The admin creates the pool
+        // 管理员创建资金池
+        let init_pool_ix =
+            DexInstruction::initialize_lending_pool(&program_id, &payer.pubkey(), &mint, &spl_token::id(), RATE_CURVE)
+                .unwrap();
+        let mut transaction = Transaction::new_with_payer(&[init_pool_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer], recent_blockhash);
+        context.banks_client.process_transaction(transaction).await.unwrap();
+
+        let (pool_address, _) = LendingPool::find_address(&program_id, &mint);
+        let (vault, _) = LendingPool::find_vault_address(&program_id, &mint);
+        let (share_mint, _) = LendingPool::find_share_mint_address(&program_id, &mint);
+        let pool_account = context.banks_client.get_account(pool_address).await.unwrap().unwrap();
+        let pool = LendingPool::unpack_from_slice(&pool_account.data).unwrap();
+        assert_eq!(pool.mint, mint);
+        assert_eq!(pool.share_mint, share_mint);
+        assert_eq!(pool.rate_curve, RATE_CURVE);
+        assert_eq!(pool.decimals, 6);
+        assert_eq!(pool.borrow_index, LendingPool::INITIAL_BORROW_INDEX);
+
+        let share_token_account = common::create_token_account(
+            &mut context.banks_client,
+            &payer,
+            recent_blockhash,
+            &share_mint,
+            &supplier.pubkey(),
+        )
+        .await;
+
+        // Asking for more shares than the supply mints fails
+        // 要求的份额多于存入所铸造的份额时失败
+        let supply_ix = DexInstruction::supply(
+            &program_id,
+            &supplier.pubkey(),
+            &mint,
+            &source,
+            &share_token_account,
+            &spl_token::id(),
+            600_000,
+            600_001,
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(&[supply_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &supplier], recent_blockhash);
+        let err = context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(custom_error(err), DexError::SlippageExceeded as u32);
+
+        // The first supply mints one share per token
+        // 首次存入时每个代币铸造一份份额
+        let supply_ix = DexInstruction::supply(
+            &program_id,
+            &supplier.pubkey(),
+            &mint,
+            &source,
+            &share_token_account,
+            &spl_token::id(),
+            600_000,
+            600_000,
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(&[supply_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &supplier], recent_blockhash);
+        context.banks_client.process_transaction(transaction).await.unwrap();
+        assert_eq!(common::token_balance(&mut context.banks_client, &vault).await, 600_000);
+        assert_eq!(common::token_balance(&mut context.banks_client, &share_token_account).await, 600_000);
+        assert_eq!(common::token_balance(&mut context.banks_client, &source).await, 400_000);
+
+        // Lend half the pool out behind the program's back
+        // 在程序之外将资金池的一半借出
+        let mut pool_account = context.banks_client.get_account(pool_address).await.unwrap().unwrap();
+        let mut pool = LendingPool::unpack_from_slice(&pool_account.data).unwrap();
+        assert_eq!((pool.available, pool.share_supply), (600_000, 600_000));
+        pool.borrow(300_000).unwrap();
+        pool.last_accrual_timestamp = common::clock(&mut context).await.unix_timestamp;
+        pool.pack_into_slice(&mut pool_account.data);
+        context.set_account(&pool_address, &AccountSharedData::from(pool_account));
+
+        // Only the tokens not lent out can be redeemed
+        // 只有未借出的代币可以赎回
+        let redeem_ix = DexInstruction::redeem(
+            &program_id,
+            &supplier.pubkey(),
+            &mint,
+            &share_token_account,
+            &source,
+            &spl_token::id(),
+            400_000,
+            0,
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(&[redeem_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &supplier], recent_blockhash);
+        let err = context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(custom_error(err), DexError::InsufficientFunds as u32);
+
+        // A year of interest at 4.5% makes each share worth more
+        // 一年 4.5% 的利息使每份份额价值更高
+        let recent_blockhash = common::advance_unix_timestamp(&mut context, SECONDS_PER_YEAR as i64).await;
+        let redeem_ix = DexInstruction::redeem(
+            &program_id,
+            &supplier.pubkey(),
+            &mint,
+            &share_token_account,
+            &source,
+            &spl_token::id(),
+            200_000,
+            204_501,
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(&[redeem_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &supplier], recent_blockhash);
+        let err = context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(custom_error(err), DexError::SlippageExceeded as u32);
+
+        let redeem_ix = DexInstruction::redeem(
+            &program_id,
+            &supplier.pubkey(),
+            &mint,
+            &share_token_account,
+            &source,
+            &spl_token::id(),
+            200_000,
+            204_500,
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(&[redeem_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &supplier], recent_blockhash);
+        context.banks_client.process_transaction(transaction).await.unwrap();
+
+        // 200_000 of 600_000 shares claim a third of 613_500 tokens
+        // 600_000 份额中的 200_000 份可领取 613_500 个代币的三分之一
+        assert_eq!(common::token_balance(&mut context.banks_client, &source).await, 604_500);
+        assert_eq!(common::token_balance(&mut context.banks_client, &vault).await, 395_500);
+        assert_eq!(common::token_balance(&mut context.banks_client, &share_token_account).await, 400_000);
+        let pool_account = context.banks_client.get_account(pool_address).await.unwrap().unwrap();
+        let pool = LendingPool::unpack_from_slice(&pool_account.data).unwrap();
+        assert_eq!((pool.available, pool.borrowed, pool.share_supply), (95_500, 313_500, 400_000));
+    }
+}
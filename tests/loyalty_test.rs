@@ -0,0 +1,292 @@
+// Loyalty points test module
+// 忠诚度积分测试模块
+
+mod common;
+
+#[cfg(test)]
+mod loyalty_tests {
+    use super::common;
+    use solana_program::{program_pack::Pack, pubkey::Pubkey, rent::Rent, system_instruction};
+    use solana_program_test::*;
+    use solana_sdk::{
+        instruction::InstructionError,
+        signature::{Keypair, Signer},
+        transaction::{Transaction, TransactionError},
+    };
+    use solana_rust_dex::{
+        error::DexError,
+        instruction::{DexInstruction, SelfTradeBehavior},
+        state::{EmissionPhase, LoyaltyPoints, LoyaltySchedule, Market, Order, MARKET_FLAG_LOYALTY_POINTS},
+    };
+
+    fn custom_error(err: TransactionError) -> u32 {
+        match err {
+            TransactionError::InstructionError(0, InstructionError::Custom(code)) => code,
+            err => panic!("unexpected error: {:?}", err),
+        }
+    }
+
+    fn phase(start_timestamp: i64, taker_points_bps: u32, maker_points_bps: u32) -> EmissionPhase {
+        EmissionPhase {
+            start_timestamp,
+            taker_points_bps,
+            maker_points_bps,
+        }
+    }
+
+    fn schedule(num_phases: u8, phases: [EmissionPhase; 4]) -> LoyaltySchedule {
+        LoyaltySchedule {
+            is_initialized: true,
+            market: Pubkey::new_unique(),
+            num_phases,
+            phases,
+            total_points: 0,
+        }
+    }
+
+    #[test]
+    fn test_emission_phases() {
+        // Used phases must start in increasing order; unused ones are ignored
+        // 使用的阶段必须按开始时间递增排列；未使用的阶段会被忽略
+        let phases = [phase(100, 100, 200), phase(200, 50, 100), EmissionPhase::default(), EmissionPhase::default()];
+        assert!(LoyaltySchedule::phases_valid(2, &phases));
+        assert!(LoyaltySchedule::phases_valid(0, &phases));
+        assert!(!LoyaltySchedule::phases_valid(3, &phases));
+        assert!(!LoyaltySchedule::phases_valid(5, &phases));
+
+        // The phase in force is the last one that has started
+        // 生效的阶段是最后一个已经开始的阶段
+        let schedule = schedule(2, phases);
+        assert_eq!(schedule.phase_at(99), None);
+        assert_eq!(schedule.phase_at(100), Some(&phases[0]));
+        assert_eq!(schedule.phase_at(199), Some(&phases[0]));
+        assert_eq!(schedule.phase_at(1_000), Some(&phases[1]));
+
+        // Points are proportional to the filled notional, rounded down
+        // 积分与成交名义金额成正比，向下取整
+        assert_eq!(schedule.fill_points(300_000, 99).unwrap(), (0, 0));
+        assert_eq!(schedule.fill_points(300_000, 150).unwrap(), (3_000, 6_000));
+        assert_eq!(schedule.fill_points(199, 1_000).unwrap(), (0, 1));
+    }
+
+    #[tokio::test]
+    async fn test_settlements_accrue_loyalty_points() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+        let order_accounts: Vec<Pubkey> = (0..2)
+            .map(|_| {
+                let order_account = Pubkey::new_unique();
+                program_test.add_account(
+                    order_account,
+                    solana_sdk::account::Account {
+                        lamports: 1000000000,
+                        data: vec![0; Order::LEN],
+                        owner: program_id,
+                        executable: false,
+                        rent_epoch: 0,
+                    },
+                );
+                order_account
+            })
+            .collect();
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let token_program = spl_token::id();
+
+        // Create a market with its vaults and fee vault
+        // 创建市场及其金库和手续费金库
+        let market_account = Keypair::new();
+        let market_authority = Keypair::new();
+        let base_mint = common::create_mint(&mut banks_client, &payer, recent_blockhash, 9).await;
+        let quote_mint = common::create_mint(&mut banks_client, &payer, recent_blockhash, 6).await;
+        let instructions = [
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &market_account.pubkey(),
+                Rent::default().minimum_balance(Market::LEN),
+                Market::LEN as u64,
+                &program_id,
+            ),
+            system_instruction::transfer(&payer.pubkey(), &market_authority.pubkey(), 1_000_000_000),
+            DexInstruction::initialize_market(
+                &program_id,
+                &market_authority.pubkey(),
+                &market_account.pubkey(),
+                &base_mint,
+                &quote_mint,
+                100,
+                10,
+                25,
+                -5,
+            )
+            .unwrap(),
+            DexInstruction::initialize_market_vaults(
+                &program_id,
+                &payer.pubkey(),
+                &market_account.pubkey(),
+                &base_mint,
+                &quote_mint,
+                &token_program,
+            )
+            .unwrap(),
+            DexInstruction::initialize_fee_vault(&program_id, &payer.pubkey(), &market_account.pubkey(), &quote_mint, &token_program)
+                .unwrap(),
+        ];
+        let mut transaction = Transaction::new_with_payer(&instructions, Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &market_authority, &market_account], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // Fund a buyer and a seller with tokens and lamports
+        // 为买方和卖方提供代币和 lamports
+        let buyer = Keypair::new();
+        let seller = Keypair::new();
+        let mut token_accounts = Vec::new();
+        for (mint, owner) in [(base_mint, &buyer), (quote_mint, &buyer), (base_mint, &seller), (quote_mint, &seller)] {
+            token_accounts.push(
+                common::create_token_account(&mut banks_client, &payer, recent_blockhash, &mint, &owner.pubkey()).await,
+            );
+        }
+        let [buyer_base, buyer_quote, seller_base, seller_quote] = token_accounts[..] else {
+            unreachable!()
+        };
+        common::mint_to(&mut banks_client, &payer, recent_blockhash, &quote_mint, &buyer_quote, 1_000_000).await;
+        common::mint_to(&mut banks_client, &payer, recent_blockhash, &base_mint, &seller_base, 1_000).await;
+        let instructions = [
+            system_instruction::transfer(&payer.pubkey(), &buyer.pubkey(), 1_000_000_000),
+            system_instruction::transfer(&payer.pubkey(), &seller.pubkey(), 1_000_000_000),
+        ];
+        let mut transaction = Transaction::new_with_payer(&instructions, Some(&payer.pubkey()));
+        transaction.sign(&[&payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // Only the market authority sets the schedule, and its phases must start in order
+        // 只有市场权限账户可以设置排期，且其阶段必须按顺序开始
+        let phases = [phase(0, 100, 200), phase(i64::MAX, 0, 0), EmissionPhase::default(), EmissionPhase::default()];
+        let set_schedule_ix =
+            DexInstruction::set_loyalty_schedule(&program_id, &buyer.pubkey(), &market_account.pubkey(), 2, phases).unwrap();
+        let mut transaction = Transaction::new_with_payer(&[set_schedule_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &buyer], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(custom_error(err), DexError::AccountNotAuthorized as u32);
+
+        let set_schedule_ix = DexInstruction::set_loyalty_schedule(
+            &program_id,
+            &market_authority.pubkey(),
+            &market_account.pubkey(),
+            3,
+            phases,
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(&[set_schedule_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &market_authority], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(custom_error(err), DexError::InvalidInstructionData as u32);
+
+        // Takers earn 1% and makers 2% of the filled notional in points
+        // 吃单方获得成交名义金额 1% 的积分，挂单方获得 2%
+        let set_schedule_ix = DexInstruction::set_loyalty_schedule(
+            &program_id,
+            &market_authority.pubkey(),
+            &market_account.pubkey(),
+            2,
+            phases,
+        )
+        .unwrap();
+        let open_points_ix = DexInstruction::open_loyalty_points(&program_id, &buyer.pubkey(), &market_account.pubkey()).unwrap();
+        let mut transaction =
+            Transaction::new_with_payer(&[set_schedule_ix, open_points_ix.clone()], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &market_authority, &buyer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+        let market_data = banks_client.get_account(market_account.pubkey()).await.unwrap().unwrap();
+        assert!(Market::unpack_from_slice(&market_data.data).unwrap().has_flag(MARKET_FLAG_LOYALTY_POINTS));
+
+        // A points account is opened once
+        // 积分账户只能开立一次
+        let recent_blockhash = banks_client.get_new_latest_blockhash(&recent_blockhash).await.unwrap();
+        let mut transaction = Transaction::new_with_payer(&[open_points_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &buyer], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(custom_error(err), DexError::AlreadyInitialized as u32);
+
+        // The buyer bids 500 at 1010 and the seller asks 300 at 1000
+        // 买方以 1010 出价 500，卖方以 1000 要价 300
+        let instructions = [
+            DexInstruction::place_limit_order(
+                &program_id,
+                &buyer.pubkey(),
+                &market_account.pubkey(),
+                &order_accounts[0],
+                &buyer_quote,
+                &token_program,
+                true,
+                1010,
+                500,
+                SelfTradeBehavior::DecrementTake,
+            )
+            .unwrap(),
+            DexInstruction::place_limit_order(
+                &program_id,
+                &seller.pubkey(),
+                &market_account.pubkey(),
+                &order_accounts[1],
+                &seller_base,
+                &token_program,
+                false,
+                1000,
+                300,
+                SelfTradeBehavior::DecrementTake,
+            )
+            .unwrap(),
+        ];
+        let mut transaction = Transaction::new_with_payer(&instructions, Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &buyer, &seller], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // Once the market has a schedule, settlements must pass it and the owners' points accounts
+        // 市场拥有排期后，结算必须传入排期以及订单所有者的积分账户
+        let settle_ix = DexInstruction::settle_funds(
+            &program_id,
+            &market_authority.pubkey(),
+            &market_account.pubkey(),
+            &order_accounts[0],
+            &order_accounts[1],
+            &buyer_base,
+            &buyer_quote,
+            &seller_base,
+            &seller_quote,
+            &buyer.pubkey(),
+            &seller.pubkey(),
+            &token_program,
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(std::slice::from_ref(&settle_ix), Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &market_authority], recent_blockhash);
+        assert_eq!(
+            banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+            TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys)
+        );
+
+        // The taker earns points on the 300000 notional; the maker never opened an account
+        // 吃单方就 300000 的名义金额获得积分；挂单方从未开立账户
+        let settle_ix = DexInstruction::with_loyalty_points(settle_ix, &buyer.pubkey(), &seller.pubkey());
+        let mut transaction = Transaction::new_with_payer(&[settle_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &market_authority], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        let points_address = LoyaltyPoints::find_address(&program_id, &market_account.pubkey(), &buyer.pubkey()).0;
+        let points_data = banks_client.get_account(points_address).await.unwrap().unwrap();
+        let points = LoyaltyPoints::unpack_from_slice(&points_data.data).unwrap();
+        assert_eq!((points.owner, points.taker_points, points.maker_points), (buyer.pubkey(), 3_000, 0));
+        let seller_points = LoyaltyPoints::find_address(&program_id, &market_account.pubkey(), &seller.pubkey()).0;
+        assert!(banks_client.get_account(seller_points).await.unwrap().is_none());
+
+        let schedule_address = LoyaltySchedule::find_address(&program_id, &market_account.pubkey()).0;
+        let schedule_data = banks_client.get_account(schedule_address).await.unwrap().unwrap();
+        assert_eq!(LoyaltySchedule::unpack_from_slice(&schedule_data.data).unwrap().total_points, 3_000);
+    }
+}
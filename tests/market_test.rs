@@ -1,20 +1,26 @@
 // Market test module
 // 市场测试模块
 
+mod common;
+
 #[cfg(test)]
 mod market_tests {
+    use super::common;
     use solana_program::{
         program_pack::Pack,
         pubkey::Pubkey,
+        sysvar,
     };
     use solana_program_test::*;
     use solana_sdk::{
+        instruction::{AccountMeta, InstructionError},
         signature::{Keypair, Signer},
-        transaction::Transaction,
+        transaction::{Transaction, TransactionError},
     };
     use solana_rust_dex::{
-        instruction::DexInstruction,
-        state::Market,
+        error::DexError,
+        instruction::{DexInstruction, SelfTradeBehavior},
+        state::{EventQueue, FillEvent, InstructionGuard, Market, MarketRegistry, MarketStatus, TwapOracle, WhitelistEntry, EVENT_QUEUE_CAPACITY, VOLUME_BUCKET_SECONDS, MARKET_FLAG_BEST_ASK_STALE, MARKET_FLAG_BEST_BID_STALE, MARKET_FLAG_DYNAMIC_FEES},
     };
 
     #[tokio::test]
@@ -32,8 +38,8 @@ mod market_tests {
         // 为测试创建账户
         let market_authority = Keypair::new();
         let market_account = Keypair::new();
-        let base_mint = Pubkey::new_unique();
-        let quote_mint = Pubkey::new_unique();
+        let base_mint = common::add_mint(&mut program_test, 9, None);
+        let quote_mint = common::add_mint(&mut program_test, 6, None);
 
         // Add market account to the test environment
         // 将市场账户添加到测试环境
@@ -48,6 +54,16 @@ mod market_tests {
             },
         );
 
+        // Fund the market authority, which pays for its market registry entry
+        // 为市场权限账户注资，由其支付市场注册表条目的费用
+        program_test.add_account(
+            market_authority.pubkey(),
+            solana_sdk::account::Account {
+                lamports: 1000000000,
+                ..solana_sdk::account::Account::default()
+            },
+        );
+
         // Start the test environment
         // 启动测试环境
         let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
@@ -56,7 +72,8 @@ mod market_tests {
         // 创建初始化市场指令
         let min_base_order_size = 100;
         let tick_size = 10;
-        let fee_rate_bps = 25; // 0.25%
+        let taker_fee_bps = 25; // 0.25%
+        let maker_fee_bps = -5; // 0.05% rebate
 
         let init_market_ix = DexInstruction::initialize_market(
             &program_id,
@@ -66,7 +83,8 @@ mod market_tests {
             &quote_mint,
             min_base_order_size,
             tick_size,
-            fee_rate_bps,
+            taker_fee_bps,
+            maker_fee_bps,
         )
         .unwrap();
 
@@ -95,11 +113,1242 @@ mod market_tests {
         assert_eq!(market.authority, market_authority.pubkey());
         assert_eq!(market.base_mint, base_mint);
         assert_eq!(market.quote_mint, quote_mint);
-        assert_eq!(market.min_base_order_size, min_base_order_size);
-        assert_eq!(market.tick_size, tick_size);
-        assert_eq!(market.fee_rate_bps, fee_rate_bps);
-        assert_eq!(market.next_order_id, 1);
-        assert_eq!(market.num_bids, 0);
-        assert_eq!(market.num_asks, 0);
+        assert_eq!({ market.min_base_order_size }, min_base_order_size);
+        assert_eq!({ market.tick_size }, tick_size);
+        assert_eq!({ market.taker_fee_bps }, taker_fee_bps);
+        assert_eq!({ market.maker_fee_bps }, maker_fee_bps);
+        assert_eq!({ market.next_order_id }, 1);
+        assert_eq!({ market.num_bids }, 0);
+        assert_eq!({ market.num_asks }, 0);
+        assert_eq!(market.instruction_guard, InstructionGuard::Disabled);
+        assert_eq!(market.status, MarketStatus::Active);
+        assert_eq!(market.base_decimals, 9);
+        assert_eq!(market.quote_decimals, 6);
+        assert!(!market.trustless);
+    }
+
+    #[tokio::test]
+    async fn test_isolated_guard_rejects_adjacent_instructions() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // Create accounts for the test
+        // 为测试创建账户
+        let market_authority = Keypair::new();
+        let market_account = Keypair::new();
+        let order_owner = Keypair::new();
+
+        // Add market account to the test environment
+        // 将市场账户添加到测试环境
+        program_test.add_account(
+            market_account.pubkey(),
+            solana_sdk::account::Account {
+                lamports: 1000000000,
+                data: vec![0; Market::LEN],
+                owner: program_id,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+
+        // Fund the market authority, which pays for its market registry entry
+        // 为市场权限账户注资，由其支付市场注册表条目的费用
+        program_test.add_account(
+            market_authority.pubkey(),
+            solana_sdk::account::Account {
+                lamports: 1000000000,
+                ..solana_sdk::account::Account::default()
+            },
+        );
+
+        // Start the test environment
+        // 启动测试环境
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        // Initialize the market and make it isolated
+        // 初始化市场并设置为隔离模式
+        let base_mint = common::create_mint(&mut banks_client, &payer, recent_blockhash, 9).await;
+        let quote_mint = common::create_mint(&mut banks_client, &payer, recent_blockhash, 6).await;
+        let init_market_ix = DexInstruction::initialize_market(
+            &program_id,
+            &market_authority.pubkey(),
+            &market_account.pubkey(),
+            &base_mint,
+            &quote_mint,
+            100,
+            10,
+            25,
+            0,
+        )
+        .unwrap();
+        let set_guard_ix = DexInstruction::set_instruction_guard(
+            &program_id,
+            &market_authority.pubkey(),
+            &market_account.pubkey(),
+            InstructionGuard::Isolated,
+        )
+        .unwrap();
+
+        let mut transaction = Transaction::new_with_payer(
+            &[init_market_ix, set_guard_ix],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &market_authority], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        let market_account_data = banks_client
+            .get_account(market_account.pubkey())
+            .await
+            .unwrap()
+            .unwrap();
+        let market = Market::unpack_from_slice(&market_account_data.data).unwrap();
+        assert_eq!(market.instruction_guard, InstructionGuard::Isolated);
+
+        // Two orders in one transaction must be rejected by the guard
+        // 同一交易中的两个订单必须被守卫拒绝
+        let place_order_ixs: Vec<_> = (0..2)
+            .map(|_| {
+                DexInstruction::place_limit_order(
+                    &program_id,
+                    &order_owner.pubkey(),
+                    &market_account.pubkey(),
+                    &Pubkey::new_unique(),
+                    &Pubkey::new_unique(),
+                    &spl_token::id(),
+                    true,
+                    1000,
+                    500,
+                    SelfTradeBehavior::DecrementTake,
+                )
+                .unwrap()
+            })
+            .collect();
+
+        let mut transaction = Transaction::new_with_payer(
+            &place_order_ixs,
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &order_owner], recent_blockhash);
+
+        let err = banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(DexError::InstructionGuardViolation as u32),
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_initialize_market_accepts_legacy_rent_account() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // Start the test environment
+        // 启动测试环境
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        // Build the instruction the way older clients did, with the Rent sysvar before the system program
+        // 按旧客户端的方式构建指令，在系统程序之前传入 Rent 系统变量
+        let market_account = Keypair::new();
+        let base_mint = common::create_mint(&mut banks_client, &payer, recent_blockhash, 9).await;
+        let quote_mint = common::create_mint(&mut banks_client, &payer, recent_blockhash, 6).await;
+        let mut init_market_ix = DexInstruction::initialize_market(
+            &program_id,
+            &payer.pubkey(),
+            &market_account.pubkey(),
+            &base_mint,
+            &quote_mint,
+            100,
+            10,
+            25,
+            0,
+        )
+        .unwrap();
+        init_market_ix
+            .accounts
+            .insert(4, AccountMeta::new_readonly(sysvar::rent::id(), false));
+
+        // The program creates the market account itself, so it must sign
+        // 由程序自行创建市场账户，因此市场账户需要签名
+        init_market_ix.accounts[1].is_signer = true;
+        let mut transaction = Transaction::new_with_payer(
+            &[init_market_ix],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &market_account], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // Verify market state
+        // 验证市场状态
+        let market_account = banks_client
+            .get_account(market_account.pubkey())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(market_account.owner, program_id);
+
+        let market = Market::unpack_from_slice(&market_account.data).unwrap();
+        assert!(market.is_initialized);
+        assert_eq!(market.authority, payer.pubkey());
+    }
+
+    #[tokio::test]
+    async fn test_maker_rebate_bounded_by_taker_fee() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // Start the test environment
+        // 启动测试环境
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        // A maker rebate larger than the taker fee cannot be funded
+        // 大于吃单手续费的挂单返佣无法支付
+        let market_account = Keypair::new();
+        let mut init_market_ix = DexInstruction::initialize_market(
+            &program_id,
+            &payer.pubkey(),
+            &market_account.pubkey(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            100,
+            10,
+            25,
+            -30,
+        )
+        .unwrap();
+        init_market_ix.accounts[1].is_signer = true;
+
+        let mut transaction = Transaction::new_with_payer(
+            &[init_market_ix],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &market_account], recent_blockhash);
+        let err = banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(DexError::InvalidInstructionData as u32),
+            )
+        );
+
+        // Fees on a trade worth 1,000,000 quote tokens
+        // 成交额为 1,000,000 报价代币时的手续费
+        let market = Market {
+            authority: Pubkey::new_unique(),
+            base_mint: Pubkey::new_unique(),
+            quote_mint: Pubkey::new_unique(),
+            min_base_order_size: 100,
+            tick_size: 10,
+            taker_fee_bps: 25,
+            maker_fee_bps: -5,
+            fee_authority: Pubkey::new_unique(),
+            ..common::market()
+        };
+        assert_eq!(market.calculate_taker_fee(1_000_000).unwrap(), 2_500);
+        assert_eq!(market.calculate_maker_fee(1_000_000).unwrap(), -500);
+    }
+
+    #[tokio::test]
+    async fn test_set_dynamic_fees() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // Add a market with a 25 bps taker fee to the test environment
+        // 将吃单手续费为 25 个基点的市场添加到测试环境
+        let market_authority = Keypair::new();
+        let market_account = Pubkey::new_unique();
+        let market = Market {
+            authority: market_authority.pubkey(),
+            base_mint: Pubkey::new_unique(),
+            quote_mint: Pubkey::new_unique(),
+            min_base_order_size: 100,
+            tick_size: 10,
+            taker_fee_bps: 25,
+            fee_authority: market_authority.pubkey(),
+            realized_variance: 400,
+            ..common::market()
+        };
+        let mut market_data = vec![0; Market::LEN];
+        market.pack_into_slice(&mut market_data);
+        program_test.add_account(
+            market_account,
+            solana_sdk::account::Account {
+                lamports: 1000000000,
+                data: market_data,
+                owner: program_id,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+
+        // Start the test environment
+        // 启动测试环境
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        // Only the market authority sets dynamic fees, with a minimum at most the taker fee and
+        // a positive volatility for the full fee
+        // 只有市场权限账户可以设置动态手续费，最低手续费不得超过吃单手续费，且满额手续费的波动率须为正
+        let intruder = Keypair::new();
+        for (signer, min_fee_bps, volatility_bps, expected) in [
+            (&intruder, 5, 100, DexError::AccountNotAuthorized),
+            (&market_authority, 26, 100, DexError::InvalidInstructionData),
+            (&market_authority, 5, 0, DexError::InvalidInstructionData),
+        ] {
+            let ix = DexInstruction::set_dynamic_fees(
+                &program_id,
+                &signer.pubkey(),
+                &market_account,
+                min_fee_bps,
+                volatility_bps,
+                20,
+            )
+            .unwrap();
+            let mut transaction = Transaction::new_with_payer(&[ix], Some(&payer.pubkey()));
+            transaction.sign(&[&payer, signer], recent_blockhash);
+            let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+            assert_eq!(err, TransactionError::InstructionError(0, InstructionError::Custom(expected as u32)));
+        }
+
+        // Turning dynamic fees on starts the volatility estimate from zero at the minimum fee
+        // 开启动态手续费时，波动率估计从零开始，手续费为最低值
+        let ix = DexInstruction::set_dynamic_fees(&program_id, &market_authority.pubkey(), &market_account, 5, 100, 20)
+            .unwrap();
+        let mut transaction = Transaction::new_with_payer(&[ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &market_authority], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        let market_data = banks_client.get_account(market_account).await.unwrap().unwrap();
+        let market = Market::unpack_from_slice(&market_data.data).unwrap();
+        assert!(market.has_flag(MARKET_FLAG_DYNAMIC_FEES));
+        assert_eq!({ market.dynamic_fee_min_bps }, 5);
+        assert_eq!({ market.dynamic_fee_volatility_bps }, 100);
+        assert_eq!({ market.volatility_window }, 20);
+        assert_eq!({ market.realized_variance }, 0);
+        assert_eq!(market.dynamic_taker_fee_bps(), 5);
+
+        // A window of zero turns them off again, back to the taker fee
+        // 窗口为零时再次关闭，恢复吃单手续费
+        let ix = DexInstruction::set_dynamic_fees(&program_id, &market_authority.pubkey(), &market_account, 0, 0, 0)
+            .unwrap();
+        let mut transaction = Transaction::new_with_payer(&[ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &market_authority], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        let market_data = banks_client.get_account(market_account).await.unwrap().unwrap();
+        let market = Market::unpack_from_slice(&market_data.data).unwrap();
+        assert!(!market.has_flag(MARKET_FLAG_DYNAMIC_FEES));
+        assert_eq!(market.dynamic_taker_fee_bps(), 25);
+    }
+
+    #[tokio::test]
+    async fn test_market_status_halts_trading() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // Add an active market to the test environment
+        // 将一个活跃市场添加到测试环境
+        let market_authority = Keypair::new();
+        let market_account = Pubkey::new_unique();
+        let market = Market {
+            authority: market_authority.pubkey(),
+            base_mint: Pubkey::new_unique(),
+            quote_mint: Pubkey::new_unique(),
+            min_base_order_size: 100,
+            tick_size: 10,
+            taker_fee_bps: 25,
+            fee_authority: market_authority.pubkey(),
+            ..common::market()
+        };
+        let mut market_data = vec![0; Market::LEN];
+        market.pack_into_slice(&mut market_data);
+        program_test.add_account(
+            market_account,
+            solana_sdk::account::Account {
+                lamports: 1000000000,
+                data: market_data,
+                owner: program_id,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+
+        // Start the test environment
+        // 启动测试环境
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        // Only the market authority can change the status
+        // 只有市场权限账户可以修改状态
+        let intruder = Keypair::new();
+        let set_status_ix = DexInstruction::set_market_status(
+            &program_id,
+            &intruder.pubkey(),
+            &market_account,
+            MarketStatus::Paused,
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(&[set_status_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &intruder], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(DexError::AccountNotAuthorized as u32),
+            )
+        );
+
+        // A cancel-only market rejects new orders
+        // 仅允许撤单的市场拒绝新订单
+        let set_status_ix = DexInstruction::set_market_status(
+            &program_id,
+            &market_authority.pubkey(),
+            &market_account,
+            MarketStatus::CancelOnly,
+        )
+        .unwrap();
+        let order_owner = Keypair::new();
+        let place_order_ix = DexInstruction::place_limit_order(
+            &program_id,
+            &order_owner.pubkey(),
+            &market_account,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &spl_token::id(),
+            true,
+            1000,
+            100,
+            SelfTradeBehavior::DecrementTake,
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(
+            &[set_status_ix, place_order_ix],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &market_authority, &order_owner], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                1,
+                InstructionError::Custom(DexError::MarketNotActive as u32),
+            )
+        );
+
+        // A paused market also stops settlement
+        // 暂停的市场同样停止结算
+        let set_status_ix = DexInstruction::set_market_status(
+            &program_id,
+            &market_authority.pubkey(),
+            &market_account,
+            MarketStatus::Paused,
+        )
+        .unwrap();
+        let settle_ix = DexInstruction::settle_funds(
+            &program_id,
+            &market_authority.pubkey(),
+            &market_account,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &spl_token::id(),
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(
+            &[set_status_ix, settle_ix],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &market_authority], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                1,
+                InstructionError::Custom(DexError::MarketPaused as u32),
+            )
+        );
+
+        let recent_blockhash = banks_client.get_latest_blockhash().await.unwrap();
+        let set_status_ix = DexInstruction::set_market_status(
+            &program_id,
+            &market_authority.pubkey(),
+            &market_account,
+            MarketStatus::Paused,
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(&[set_status_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &market_authority], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        let market_data = banks_client.get_account(market_account).await.unwrap().unwrap();
+        let market = Market::unpack_from_slice(&market_data.data).unwrap();
+        assert_eq!(market.status, MarketStatus::Paused);
+    }
+
+    #[tokio::test]
+    async fn test_whitelist_gates_placement() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // Add a market to the test environment
+        // 将一个市场添加到测试环境
+        let market_authority = Keypair::new();
+        let market_account = Pubkey::new_unique();
+        let market = Market {
+            authority: market_authority.pubkey(),
+            base_mint: Pubkey::new_unique(),
+            quote_mint: Pubkey::new_unique(),
+            min_base_order_size: 100,
+            tick_size: 10,
+            taker_fee_bps: 25,
+            fee_authority: market_authority.pubkey(),
+            ..common::market()
+        };
+        let mut market_data = vec![0; Market::LEN];
+        market.pack_into_slice(&mut market_data);
+        program_test.add_account(
+            market_account,
+            solana_sdk::account::Account {
+                lamports: 1000000000,
+                data: market_data,
+                owner: program_id,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+        let order_owner = Keypair::new();
+        for funded in [market_authority.pubkey(), order_owner.pubkey()] {
+            program_test.add_account(
+                funded,
+                solana_sdk::account::Account {
+                    lamports: 1000000000,
+                    ..solana_sdk::account::Account::default()
+                },
+            );
+        }
+
+        // Start the test environment
+        // 启动测试环境
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        // Enable the whitelist
+        // 启用白名单
+        let enable_ix = DexInstruction::set_whitelist_enabled(
+            &program_id,
+            &market_authority.pubkey(),
+            &market_account,
+            true,
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(&[enable_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &market_authority], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        let market_data = banks_client.get_account(market_account).await.unwrap().unwrap().data;
+        assert!(Market::unpack_from_slice(&market_data).unwrap().whitelist_enabled);
+
+        // An owner without a whitelist entry cannot place orders
+        // 没有白名单条目的所有者无法下单
+        let place_order_ix = |order_account: &Pubkey| {
+            DexInstruction::place_limit_order_whitelisted(
+                &program_id,
+                &order_owner.pubkey(),
+                &market_account,
+                order_account,
+                &Pubkey::new_unique(),
+                &spl_token::id(),
+                true,
+                1000,
+                100,
+                SelfTradeBehavior::DecrementTake,
+                None,
+            )
+            .unwrap()
+        };
+        let mut transaction = Transaction::new_with_payer(
+            &[place_order_ix(&Pubkey::new_unique())],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &order_owner], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(DexError::OwnerNotWhitelisted as u32),
+            )
+        );
+
+        // Only the market authority can whitelist owners
+        // 只有市场权限账户可以将所有者加入白名单
+        let intruder = Keypair::new();
+        let add_ix = DexInstruction::add_whitelist_entry(
+            &program_id,
+            &intruder.pubkey(),
+            &market_account,
+            &order_owner.pubkey(),
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(&[add_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &intruder], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(DexError::AccountNotAuthorized as u32),
+            )
+        );
+
+        // The authority whitelists the owner
+        // 权限账户将所有者加入白名单
+        let add_ix = DexInstruction::add_whitelist_entry(
+            &program_id,
+            &market_authority.pubkey(),
+            &market_account,
+            &order_owner.pubkey(),
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(&[add_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &market_authority], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        let entry_address = WhitelistEntry::find_address(&program_id, &market_account, &order_owner.pubkey()).0;
+        let entry_account = banks_client.get_account(entry_address).await.unwrap().unwrap();
+        assert_eq!(entry_account.owner, program_id);
+        let entry = WhitelistEntry::unpack_from_slice(&entry_account.data).unwrap();
+        assert!(entry.is_initialized);
+        assert_eq!(entry.market, market_account);
+        assert_eq!(entry.owner, order_owner.pubkey());
+
+        // The whitelisted owner passes the check and fails later, on the missing token account
+        // 白名单内的所有者通过检查，随后因代币账户不存在而失败
+        let order_account = Keypair::new();
+        let mut place_whitelisted_ix = place_order_ix(&order_account.pubkey());
+        place_whitelisted_ix.accounts[2].is_signer = true;
+        let mut transaction = Transaction::new_with_payer(&[place_whitelisted_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &order_owner, &order_account], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_ne!(
+            err,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(DexError::OwnerNotWhitelisted as u32),
+            )
+        );
+
+        // Another owner's entry is rejected
+        // 其他所有者的白名单条目会被拒绝
+        let other_owner = Keypair::new();
+        let mut place_other_ix = DexInstruction::place_limit_order(
+            &program_id,
+            &other_owner.pubkey(),
+            &market_account,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &spl_token::id(),
+            true,
+            1000,
+            100,
+            SelfTradeBehavior::DecrementTake,
+        )
+        .unwrap();
+        place_other_ix
+            .accounts
+            .push(AccountMeta::new_readonly(solana_program::system_program::id(), false));
+        place_other_ix.accounts.push(AccountMeta::new_readonly(entry_address, false));
+        let mut transaction = Transaction::new_with_payer(&[place_other_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &other_owner], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(DexError::OwnerNotWhitelisted as u32),
+            )
+        );
+
+        // Removing the entry returns its rent and revokes access
+        // 移除条目会退还租金并撤销权限
+        let recipient = Pubkey::new_unique();
+        let remove_ix = DexInstruction::remove_whitelist_entry(
+            &program_id,
+            &market_authority.pubkey(),
+            &market_account,
+            &order_owner.pubkey(),
+            &recipient,
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(&[remove_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &market_authority], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        assert!(banks_client.get_account(entry_address).await.unwrap().is_none());
+        let recipient_account = banks_client.get_account(recipient).await.unwrap().unwrap();
+        assert_eq!(recipient_account.lamports, entry_account.lamports);
+
+        let mut transaction = Transaction::new_with_payer(
+            &[place_order_ix(&Pubkey::new_unique())],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &order_owner], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(DexError::OwnerNotWhitelisted as u32),
+            )
+        );
+    }
+
+    #[test]
+    fn test_book_totals_and_imbalance() {
+        // Book totals follow placements, partial fills and cancels
+        // 订单簿汇总随下单、部分成交和撤单更新
+        let mut market = Market {
+            authority: Pubkey::new_unique(),
+            base_mint: Pubkey::new_unique(),
+            quote_mint: Pubkey::new_unique(),
+            min_base_order_size: 100,
+            tick_size: 10,
+            taker_fee_bps: 25,
+            fee_authority: Pubkey::new_unique(),
+            ..common::market()
+        };
+        assert_eq!(market.book_imbalance_bps(), 0);
+        assert_eq!(market.spread(), None);
+
+        market.add_resting(true, 1_000, 300).unwrap();
+        market.add_resting(true, 990, 100).unwrap();
+        market.add_resting(false, 1_010, 100).unwrap();
+        assert_eq!((market.best_bid, market.best_ask), (1_000, 1_010));
+        assert_eq!(market.spread(), Some(10));
+        assert_eq!({ market.bid_base_total }, 400);
+        assert_eq!({ market.ask_base_total }, 100);
+        assert_eq!({ market.bid_notional }, 399_000);
+        assert_eq!({ market.ask_notional }, 101_000);
+        assert_eq!(market.book_imbalance_bps(), 6_000);
+
+        market.remove_resting(true, 1_000, 300, true);
+        market.remove_resting(false, 1_010, 40, false);
+        assert_eq!({ market.bid_base_total }, 100);
+        assert_eq!({ market.ask_base_total }, 60);
+        assert_eq!({ market.bid_notional }, 99_000);
+        assert_eq!({ market.ask_notional }, 60_600);
+        assert_eq!(market.book_imbalance_bps(), 2_500);
+
+        // The best price of a side is only cleared once the side is empty; before, the order
+        // at the best price leaving marks it stale, and a partial fill or reduction does not
+        // 只有当一侧为空时才会清除该侧的最优价格；在此之前，最优价格的订单离开会将其标记为过期，而部分成交或减量不会
+        assert_eq!({ market.best_bid }, 1_000);
+        assert!(market.has_flag(MARKET_FLAG_BEST_BID_STALE));
+        assert!(!market.has_flag(MARKET_FLAG_BEST_ASK_STALE));
+        assert_eq!((market.best_bid_price(), market.best_ask_price()), (None, Some(1_010)));
+        assert_eq!(market.spread(), None);
+        market.add_resting(true, 995, 100).unwrap();
+        assert_eq!(market.best_bid_price(), None);
+        market.add_resting(true, 1_000, 100).unwrap();
+        assert_eq!(market.best_bid_price(), Some(1_000));
+        assert_eq!(market.spread(), Some(10));
+        market.remove_resting(false, 1_010, 60, true);
+        assert_eq!({ market.best_ask }, 0);
+        assert!(!market.has_flag(MARKET_FLAG_BEST_ASK_STALE));
+        assert_eq!(market.spread(), None);
+        market.remove_resting(true, 995, 100, true);
+        market.remove_resting(true, 1_000, 100, true);
+        market.add_resting(false, 1_010, 60).unwrap();
+
+        // Notional of huge orders does not overflow
+        // 巨额订单的名义价值不会溢出
+        market.add_resting(false, u64::MAX, 1_000).unwrap();
+        assert_eq!(market.book_imbalance_bps(), -8_275);
+        assert_eq!({ market.ask_notional }, 60_600 + u64::MAX as u128 * 1_000);
+    }
+
+    #[test]
+    fn test_volume_statistics() {
+        let mut market = Market {
+            authority: Pubkey::new_unique(),
+            base_mint: Pubkey::new_unique(),
+            quote_mint: Pubkey::new_unique(),
+            min_base_order_size: 100,
+            tick_size: 10,
+            taker_fee_bps: 25,
+            fee_authority: Pubkey::new_unique(),
+            ..common::market()
+        };
+        let hour = 472_250 * VOLUME_BUCKET_SECONDS;
+
+        // Fills add to the cumulative volume and to the bucket of their hour
+        // 成交计入累计成交量及其所在小时的分桶
+        market.record_volume(1_000, 50_000, hour);
+        market.record_volume(2_000, 70_000, hour + 3_599);
+        market.record_volume(500, 10_000, hour + 3_600);
+        assert_eq!((market.base_volume, market.quote_volume), (3_500, 130_000));
+        assert_eq!(market.rolling_quote_volume(hour + 3_600), 130_000);
+
+        // Hours fall out of the window a day later, and quiet hours are cleared when the
+        // window moves past them
+        // 小时分桶在一天后移出窗口，空闲小时在窗口越过时被清零
+        assert_eq!(market.rolling_quote_volume(hour + 24 * 3_600), 10_000);
+        market.record_volume(100, 1_000, hour + 24 * 3_600);
+        assert_eq!(market.rolling_quote_volume(hour + 24 * 3_600), 11_000);
+        assert_eq!(market.rolling_quote_volume(hour + 60 * 3_600), 0);
+        market.record_volume(100, 2_000, hour + 60 * 3_600);
+        assert_eq!(market.rolling_quote_volume(hour + 60 * 3_600), 2_000);
+        assert_eq!({ market.quote_volume }, 133_000);
+
+        // A fill timed before the most recent bucket counts towards it
+        // 时间早于最新分桶的成交计入最新分桶
+        market.record_volume(100, 3_000, hour);
+        assert_eq!(market.rolling_quote_volume(hour + 60 * 3_600), 5_000);
+
+        // Open interest is the resting base quantity of both sides
+        // 未平仓量为买卖双方挂单的基础数量之和
+        market.add_resting(true, 1_000, 300).unwrap();
+        market.add_resting(false, 1_010, 200).unwrap();
+        assert_eq!(market.open_interest(), 500);
+    }
+
+    #[test]
+    fn test_twap_oracle_averages() {
+        let mut oracle = TwapOracle {
+            is_initialized: true,
+            market: Pubkey::new_unique(),
+            ema_period_seconds: 600,
+            last_price: 0,
+            last_update: 0,
+            price_cumulative: 0,
+            ema_price: 0,
+            fill_count: 0,
+        };
+        let start = 1_700_000_000;
+
+        // The first fill seeds the EMA, and its price accumulates from its own time
+        // 第一笔成交初始化 EMA，其价格从其自身时间开始累计
+        oracle.record_fill(1_000, start);
+        assert_eq!((oracle.ema_price, oracle.price_cumulative), (1_000, 0));
+        assert_eq!(oracle.price_cumulative_at(start + 600), 600_000);
+
+        // A new price moves the EMA by elapsed / (elapsed + period) of the gap as it holds
+        // 新价格持续期间，EMA 按 elapsed / (elapsed + period) 的比例向其靠拢
+        oracle.record_fill(1_100, start + 600);
+        assert_eq!((oracle.ema_price, oracle.price_cumulative), (1_000, 600_000));
+        assert_eq!(oracle.ema_price_at(start + 600), 1_000);
+        assert_eq!(oracle.ema_price_at(start + 1_200), 1_050);
+
+        // Consumers average over any window starting at a stored cumulative price
+        // 使用方可以从已保存的累计价格开始对任意窗口求平均
+        assert_eq!(oracle.twap_since(0, start, start + 1_200), Some(1_050));
+        assert_eq!(oracle.twap_since(600_000, start + 600, start + 1_200), Some(1_100));
+        assert_eq!(oracle.twap_since(600_000, start + 600, start + 600), None);
+
+        // A fill timed before the last one counts at the time of the last one
+        // 时间早于上一笔的成交按上一笔的时间计
+        oracle.record_fill(900, start + 300);
+        assert_eq!((oracle.last_update, oracle.price_cumulative, oracle.fill_count), (start + 600, 600_000, 3));
+        assert_eq!(oracle.ema_price_at(start + 1_200), 950);
+    }
+
+    #[test]
+    fn test_event_queue_ring_buffer() {
+        let mut queue = EventQueue::unpack_unchecked(&vec![0; EventQueue::LEN]).unwrap();
+        queue.next_seq_num = 10;
+        let event = |seq_num: u64| FillEvent {
+            seq_num,
+            slot: seq_num * 2,
+            taker_order: Pubkey::new_unique(),
+            maker_order: Pubkey::new_unique(),
+            price: 1_000,
+            base_amount: 100,
+            quote_amount: 100_000,
+        };
+        let seq_nums = |queue: &EventQueue| queue.iter().map(|event| event.seq_num).collect::<Vec<_>>();
+
+        // Events are kept in order and numbered from the market's fills
+        // 事件按顺序保存，并以市场的成交序号编号
+        assert_eq!(queue.first_seq_num(), 10);
+        for seq_num in 10..13 {
+            queue.push(event(seq_num));
+        }
+        assert_eq!(seq_nums(&queue), vec![10, 11, 12]);
+        assert_eq!(({ queue.count }, { queue.next_seq_num }), (3, 13));
+
+        // Consuming removes at most the queued events from the front
+        // 消费从队首移除事件，最多移除队列中已有的事件
+        assert_eq!(queue.pop(2), 2);
+        assert_eq!(seq_nums(&queue), vec![12]);
+        assert_eq!(queue.first_seq_num(), 12);
+        assert_eq!(queue.pop(5), 1);
+        assert_eq!(queue.first_seq_num(), 13);
+
+        // A full queue overwrites its oldest events, leaving a gap in the sequence numbers
+        // 满队列会覆盖最旧的事件，在序号中留下空缺
+        let capacity = EVENT_QUEUE_CAPACITY as u64;
+        for seq_num in 13..13 + capacity + 3 {
+            queue.push(event(seq_num));
+        }
+        assert_eq!(({ queue.count }, { queue.dropped }), (capacity, 3));
+        assert_eq!(queue.first_seq_num(), 16);
+        assert_eq!(seq_nums(&queue), (16..16 + capacity).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_market_registry_records_markets() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // Start the test environment
+        // 启动测试环境
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        // A registry at any other address is rejected
+        // 其他地址的注册表会被拒绝
+        let base_mint = common::create_mint(&mut banks_client, &payer, recent_blockhash, 9).await;
+        let quote_mint = common::create_mint(&mut banks_client, &payer, recent_blockhash, 6).await;
+        let mut init_market_ix = DexInstruction::initialize_market_with_seed(
+            &program_id,
+            &payer.pubkey(),
+            &base_mint,
+            &quote_mint,
+            0,
+            100,
+            10,
+            25,
+            0,
+        )
+        .unwrap();
+        init_market_ix.accounts[6].pubkey = Pubkey::new_unique();
+        let mut transaction = Transaction::new_with_payer(&[init_market_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(0, InstructionError::Custom(DexError::InvalidAccountData as u32))
+        );
+
+        // The first market creates the registry and the second one grows it
+        // 第一个市场创建注册表，第二个市场扩展注册表
+        let keypair_market = Keypair::new();
+        let other_quote_mint = common::create_mint(&mut banks_client, &payer, recent_blockhash, 6).await;
+        let mut instructions = [
+            DexInstruction::initialize_market_with_seed(
+                &program_id,
+                &payer.pubkey(),
+                &base_mint,
+                &quote_mint,
+                0,
+                100,
+                10,
+                25,
+                0,
+            )
+            .unwrap(),
+            DexInstruction::initialize_market(
+                &program_id,
+                &payer.pubkey(),
+                &keypair_market.pubkey(),
+                &base_mint,
+                &other_quote_mint,
+                100,
+                10,
+                25,
+                0,
+            )
+            .unwrap(),
+        ];
+        // The program creates the keypair market account itself, so it must sign
+        // 由程序自行创建密钥对市场账户，因此市场账户需要签名
+        instructions[1].accounts[1].is_signer = true;
+        let mut transaction = Transaction::new_with_payer(&instructions, Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &keypair_market], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // Both markets are listed in creation order with their pairs
+        // 两个市场按创建顺序及其交易对被列出
+        let (registry_address, _) = MarketRegistry::find_address(&program_id);
+        let registry_account = banks_client.get_account(registry_address).await.unwrap().unwrap();
+        assert_eq!(registry_account.owner, program_id);
+        assert_eq!(registry_account.data.len(), MarketRegistry::account_len(2));
+        let registry = MarketRegistry::unpack_from_slice(&registry_account.data[..MarketRegistry::LEN]).unwrap();
+        assert_eq!(registry.num_markets, 2);
+
+        let entries = MarketRegistry::unpack_entries(&registry_account.data).unwrap();
+        let listed: Vec<_> = entries
+            .iter()
+            .map(|entry| (entry.market, entry.base_mint, entry.quote_mint))
+            .collect();
+        assert_eq!(
+            listed,
+            vec![
+                (Market::find_address(&program_id, &base_mint, &quote_mint, 0).0, base_mint, quote_mint),
+                (keypair_market.pubkey(), base_mint, other_quote_mint),
+            ]
+        );
+        assert_eq!(entries[0].created_slot, entries[1].created_slot);
+
+        // The registry stays rent exempt as it grows
+        // 注册表在扩展时保持租金豁免
+        let rent = banks_client.get_rent().await.unwrap();
+        assert!(rent.is_exempt(registry_account.lamports, registry_account.data.len()));
+    }
+
+    #[tokio::test]
+    async fn test_initialize_market_validates_mints() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // Add plain mints and a mint with a freeze authority
+        // 添加普通代币铸造账户以及设有冻结权限的代币铸造账户
+        let base_mint = common::add_mint(&mut program_test, 9, None);
+        let quote_mint = common::add_mint(&mut program_test, 6, None);
+        let freezable_mint = common::add_mint(&mut program_test, 6, Some(Pubkey::new_unique()));
+
+        // Start the test environment
+        // 启动测试环境
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        let init_market_ix = |base_mint: &Pubkey, quote_mint: &Pubkey, trustless: bool| {
+            let instruction = DexInstruction::initialize_market_with_seed(
+                &program_id,
+                &payer.pubkey(),
+                base_mint,
+                quote_mint,
+                0,
+                100,
+                10,
+                25,
+                0,
+            )
+            .unwrap();
+            if trustless {
+                DexInstruction::trustless(instruction).unwrap()
+            } else {
+                instruction
+            }
+        };
+        let custom_error = |err: TransactionError| match err {
+            TransactionError::InstructionError(0, InstructionError::Custom(code)) => code,
+            err => panic!("unexpected error: {:?}", err),
+        };
+
+        // Identical mints and accounts that are not mints are rejected
+        // 相同的代币铸造账户以及非代币铸造账户会被拒绝
+        let not_a_mint = Pubkey::new_unique();
+        for (base, quote) in [(base_mint, base_mint), (not_a_mint, quote_mint), (base_mint, not_a_mint)] {
+            let mut transaction = Transaction::new_with_payer(&[init_market_ix(&base, &quote, false)], Some(&payer.pubkey()));
+            transaction.sign(&[&payer], recent_blockhash);
+            let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+            assert_eq!(custom_error(err), DexError::InvalidMint as u32);
+        }
+
+        // Trustless markets reject mints with a freeze authority
+        // 去信任市场拒绝设有冻结权限的代币铸造账户
+        let mut transaction = Transaction::new_with_payer(
+            &[init_market_ix(&base_mint, &freezable_mint, true)],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(custom_error(err), DexError::FreezableMint as u32);
+
+        // Other markets accept them, and both record the mint decimals
+        // 其他市场可以接受它们，并且都会记录代币精度
+        let mut transaction = Transaction::new_with_payer(
+            &[
+                init_market_ix(&base_mint, &freezable_mint, false),
+                init_market_ix(&base_mint, &quote_mint, true),
+            ],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        for (quote_mint, trustless) in [(freezable_mint, false), (quote_mint, true)] {
+            let (market_address, _) = Market::find_address(&program_id, &base_mint, &quote_mint, 0);
+            let market_account = banks_client.get_account(market_address).await.unwrap().unwrap();
+            let market = Market::unpack_from_slice(&market_account.data).unwrap();
+            assert_eq!(market.base_decimals, 9);
+            assert_eq!(market.quote_decimals, 6);
+            assert_eq!(market.trustless, trustless);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_initialize_market_rejects_reinitialization() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // Add an empty market account, a program account of another size and an empty market
+        // account too poor to be rent exempt
+        // 添加空的市场账户、另一种大小的程序账户以及余额不足以豁免租金的空市场账户
+        let market_account = Keypair::new();
+        let wrong_size_account = Keypair::new();
+        let underfunded_account = Keypair::new();
+        let base_mint = common::add_mint(&mut program_test, 9, None);
+        let quote_mint = common::add_mint(&mut program_test, 6, None);
+        for (account, len, lamports) in [
+            (&market_account, Market::LEN, 1000000000),
+            (&wrong_size_account, Market::LEN - 1, 1000000000),
+            (&underfunded_account, Market::LEN, 1),
+        ] {
+            program_test.add_account(
+                account.pubkey(),
+                solana_sdk::account::Account {
+                    lamports,
+                    data: vec![0; len],
+                    owner: program_id,
+                    executable: false,
+                    rent_epoch: 0,
+                },
+            );
+        }
+
+        // Start the test environment
+        // 启动测试环境
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        let init_market_ix = |authority: &Pubkey, market: &Pubkey| {
+            DexInstruction::initialize_market(
+                &program_id,
+                authority,
+                market,
+                &base_mint,
+                &quote_mint,
+                100,
+                10,
+                25,
+                0,
+            )
+            .unwrap()
+        };
+        let mut transaction = Transaction::new_with_payer(
+            &[init_market_ix(&payer.pubkey(), &market_account.pubkey())],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // Another signer cannot reset the live market
+        // 其他签名者无法重置已上线的市场
+        let attacker = Keypair::new();
+        let mut transaction = Transaction::new_with_payer(
+            &[init_market_ix(&attacker.pubkey(), &market_account.pubkey())],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &attacker], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(DexError::AlreadyInitialized as u32),
+            )
+        );
+        let market_data = banks_client.get_account(market_account.pubkey()).await.unwrap().unwrap();
+        let market = Market::unpack_from_slice(&market_data.data).unwrap();
+        assert_eq!(market.authority, payer.pubkey());
+
+        // Program accounts of another type are not taken for markets
+        // 其他类型的程序账户不会被当作市场
+        let mut transaction = Transaction::new_with_payer(
+            &[init_market_ix(&payer.pubkey(), &wrong_size_account.pubkey())],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(DexError::InvalidAccountData as u32),
+            )
+        );
+
+        // Nor are accounts the runtime could reclaim for unpaid rent
+        // 可能因未付租金而被运行时回收的账户也不会被采用
+        let mut transaction = Transaction::new_with_payer(
+            &[init_market_ix(&payer.pubkey(), &underfunded_account.pubkey())],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(DexError::NotRentExempt as u32),
+            )
+        );
     }
-} 
\ No newline at end of file
+}
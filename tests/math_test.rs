@@ -0,0 +1,405 @@
+// Price and fee math test module
+// 价格与手续费计算测试模块
+
+mod common;
+
+#[cfg(test)]
+mod math_tests {
+    use super::common;
+    use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+    use solana_rust_dex::{
+        math::{self, Rounding},
+        state::{Market, MARKET_FLAG_DYNAMIC_FEES},
+    };
+
+    const CASES: usize = 20_000;
+
+    // Deterministic xorshift generator, so a failing case can be replayed
+    // 确定性的 xorshift 生成器，便于重现失败的用例
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        // Value of a random bit width, so small and huge values are both covered
+        // 随机位宽的值，使小值和极大值都能被覆盖
+        fn amount(&mut self) -> u64 {
+            let bits = self.next() % 65;
+            if bits == 0 {
+                0
+            } else {
+                self.next() >> (64 - bits)
+            }
+        }
+
+        fn below(&mut self, bound: u64) -> u64 {
+            self.next() % bound
+        }
+    }
+
+    fn market(taker_fee_bps: u16, maker_fee_bps: i16, referral_share_bps: u16) -> Market {
+        Market {
+            authority: Pubkey::new_unique(),
+            base_mint: Pubkey::new_unique(),
+            quote_mint: Pubkey::new_unique(),
+            taker_fee_bps,
+            maker_fee_bps,
+            referral_share_bps,
+            base_decimals: 9,
+            quote_decimals: 9,
+            ..common::market()
+        }
+    }
+
+    // Random market with valid fees and lot sizes: rebates never exceed the taker fee
+    // 具有有效费率和手数大小的随机市场：返佣不超过吃单手续费
+    fn random_market(rng: &mut Rng) -> Market {
+        let taker_fee_bps = rng.below(10001) as u16;
+        let maker_fee_bps = rng.below(taker_fee_bps as u64 + 10001) as i16 - taker_fee_bps as i16;
+        let referral_share_bps = rng.below(10001) as u16;
+        Market {
+            base_lot_size: rng.below(1000) + 1,
+            quote_lot_size: rng.below(1000) + 1,
+            ..market(taker_fee_bps, maker_fee_bps, referral_share_bps)
+        }
+    }
+
+    #[test]
+    fn test_mul_div_rounding() {
+        // Both directions bracket the exact quotient and differ only on a remainder
+        // 两个方向的结果都夹住精确商，且只在有余数时相差一
+        let mut rng = Rng(0x5eed_0001);
+        for _ in 0..CASES {
+            let value = rng.amount();
+            let numerator = rng.amount();
+            let denominator = rng.amount().max(1);
+            let product = value as u128 * numerator as u128;
+            let down = math::mul_div(value, numerator, denominator, Rounding::Down);
+            let up = math::mul_div(value, numerator, denominator, Rounding::Up);
+
+            let exact_down = product / denominator as u128;
+            let exact_up = product.div_ceil(denominator as u128);
+            assert_eq!(down.clone().ok(), u64::try_from(exact_down).ok(), "{} * {} / {}", value, numerator, denominator);
+            assert_eq!(up.clone().ok(), u64::try_from(exact_up).ok(), "{} * {} / {}", value, numerator, denominator);
+            if let (Ok(down), Ok(up)) = (down, up) {
+                assert_eq!(up - down, u64::from(!product.is_multiple_of(denominator as u128)));
+            }
+        }
+
+        // Division by zero is an error, never a panic
+        // 除以零返回错误而不会 panic
+        assert_eq!(math::mul_div(1, 1, 0, Rounding::Down), Err(ProgramError::ArithmeticOverflow));
+    }
+
+    #[test]
+    fn test_lamport_scale_values_do_not_overflow() {
+        // Notionals whose fee product exceeds a u64 are still charged exactly
+        // 手续费乘积超出 u64 的名义金额仍能被精确计费
+        let market = market(30, -10, 2_000);
+        let notional = math::notional(4_000_000_000, 4_000_000_000).unwrap();
+        assert_eq!(notional, 16_000_000_000_000_000_000);
+        assert!(notional.checked_mul(30).is_none());
+        assert_eq!(market.calculate_taker_fee(notional).unwrap(), 48_000_000_000_000_000);
+        assert_eq!(market.calculate_maker_fee(notional).unwrap(), -16_000_000_000_000_000);
+        assert_eq!(
+            market.calculate_taker_fee(u64::MAX).unwrap(),
+            (u64::MAX as u128 * 30).div_ceil(10000) as u64
+        );
+
+        // Only a result that does not fit in a token amount is rejected
+        // 只有无法放入代币数量的结果才会被拒绝
+        assert_eq!(math::notional(u64::MAX, 2), Err(ProgramError::ArithmeticOverflow));
+        assert_eq!(math::notional(u64::MAX, 1), Ok(u64::MAX));
+    }
+
+    #[test]
+    fn test_fees_round_up_and_payouts_round_down() {
+        // Fees are never below the exact fee, rebates and referral shares never above theirs
+        // 手续费不低于精确值，返佣和推荐分成不高于精确值
+        let mut rng = Rng(0x5eed_0002);
+        for _ in 0..CASES {
+            let market = random_market(&mut rng);
+            let quote_amount = rng.amount() >> 1;
+            let exact_taker_fee = quote_amount as u128 * market.taker_fee_bps as u128;
+            let exact_maker_fee = quote_amount as i128 * market.maker_fee_bps as i128;
+
+            let taker_fee = market.calculate_taker_fee(quote_amount).unwrap();
+            assert!(taker_fee as u128 * 10000 >= exact_taker_fee);
+            assert!(taker_fee as u128 * 10000 < exact_taker_fee + 10000);
+
+            let maker_fee = market.calculate_maker_fee(quote_amount).unwrap();
+            assert!(maker_fee as i128 * 10000 >= exact_maker_fee);
+            assert!((maker_fee as i128 * 10000) < exact_maker_fee + 10000);
+
+            let referral_fee = market.calculate_referral_fee(taker_fee).unwrap();
+            assert!(referral_fee as u128 * 10000 <= taker_fee as u128 * market.referral_share_bps as u128);
+        }
+    }
+
+    #[test]
+    fn test_fills_neither_create_nor_destroy_value() {
+        // Every quote token the taker pays reaches exactly one recipient, and the taker pays
+        // the quote amount plus the taker fee
+        // 吃单方支付的每个报价代币都恰好到达一个接收方，且吃单方支付报价金额加吃单手续费
+        let mut rng = Rng(0x5eed_0003);
+        for _ in 0..CASES {
+            let market = random_market(&mut rng);
+            let quote_amount = rng.amount() >> 1;
+            let has_referrer = rng.next().is_multiple_of(2);
+            let amounts = market.settlement_amounts(quote_amount, has_referrer).unwrap();
+
+            let paid = amounts.taker_quote_amount().unwrap() as u128;
+            assert_eq!(paid, quote_amount as u128 + amounts.taker_fee as u128);
+            assert_eq!(
+                paid,
+                amounts.maker_quote_amount as u128 + amounts.vault_fee as u128 + amounts.referral_fee as u128
+            );
+
+            // The maker receives the quote amount less its fee, or plus its rebate
+            // 挂单方收到报价金额减去其手续费，或加上其返佣
+            assert_eq!(
+                amounts.maker_quote_amount as i128,
+                quote_amount as i128 - amounts.maker_fee as i128
+            );
+            if !has_referrer {
+                assert_eq!(amounts.referral_fee, 0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_bid_lock_covers_every_fill() {
+        // A bid's lock for a quantity pays for filling it at any price up to the limit, and
+        // the locks of the parts of a quantity add up to the lock of the whole
+        // 买单为某数量锁定的资金足以支付其在不高于限价的任意价格成交，
+        // 且各部分数量的锁定资金之和等于整体的锁定资金
+        let mut rng = Rng(0x5eed_0004);
+        for _ in 0..CASES {
+            let market = random_market(&mut rng);
+            let limit_price = rng.amount() >> 40;
+            let price = rng.below(limit_price + 1);
+            let quantity = rng.amount() >> 40;
+            let part = rng.below(quantity + 1);
+
+            let lock = market.escrow_amount(true, limit_price, quantity).unwrap();
+            let amounts = market
+                .settlement_amounts(market.notional(price, quantity).unwrap(), rng.next().is_multiple_of(2))
+                .unwrap();
+            assert!(amounts.taker_quote_amount().unwrap() <= lock);
+            assert_eq!(
+                market.escrow_amount(true, limit_price, part).unwrap()
+                    + market.escrow_amount(true, limit_price, quantity - part).unwrap(),
+                lock
+            );
+        }
+
+        // Asks lock their base quantity
+        // 卖单锁定其基础代币数量
+        assert_eq!(market(30, 0, 0).escrow_amount(false, 1000, 7).unwrap(), 7);
+    }
+
+    #[test]
+    fn test_dynamic_taker_fee() {
+        // Without dynamic fees fills pay the taker fee whatever the volatility
+        // 未开启动态手续费时，无论波动率如何，成交都支付吃单手续费
+        let mut market = Market {
+            dynamic_fee_min_bps: 10,
+            dynamic_fee_volatility_bps: 200,
+            volatility_window: 4,
+            last_price: 1000,
+            ..market(50, 0, 0)
+        };
+        market.record_fill_price(1100);
+        assert_eq!({ market.realized_variance }, 0);
+        assert_eq!(market.dynamic_taker_fee_bps(), 50);
+
+        // A calm market charges the minimum fee
+        // 平静的市场收取最低手续费
+        market.flags |= MARKET_FLAG_DYNAMIC_FEES;
+        market.record_fill_price(1100);
+        assert_eq!({ market.realized_variance }, 0);
+        assert_eq!(market.dynamic_taker_fee_bps(), 10);
+
+        // Each fill weighs its squared return into the variance over the window
+        // 每笔成交将其收益率平方按窗口计入方差
+        market.record_fill_price(1111);
+        assert_eq!({ market.last_price }, 1111);
+        assert_eq!({ market.realized_variance }, 100 * 100 / 4);
+        assert_eq!(market.realized_volatility_bps(), 50);
+        assert_eq!(market.dynamic_taker_fee_bps(), 10 + 40 * 50 / 200);
+
+        // The fee reaches the taker fee at the configured volatility and stays there
+        // 手续费在设定的波动率处达到吃单手续费并保持不变
+        market.realized_variance = 200 * 200;
+        assert_eq!(market.dynamic_taker_fee_bps(), 50);
+        market.realized_variance = u64::MAX;
+        assert_eq!(market.dynamic_taker_fee_bps(), 50);
+        let amounts = market.settlement_amounts(10_000, false).unwrap();
+        assert_eq!(amounts.taker_fee, 50);
+
+        // The estimate decays as prices settle down
+        // 价格趋稳后估计值逐渐衰减
+        market.realized_variance = 200 * 200;
+        for _ in 0..32 {
+            market.record_fill_price(1111);
+        }
+        assert_eq!(market.dynamic_taker_fee_bps(), 10);
+
+        // A maker rebate above the minimum fee raises the floor to the rebate
+        // 挂单返佣高于最低手续费时，下限提高到返佣费率
+        let rebate_market = Market { maker_fee_bps: -20, ..market };
+        assert_eq!(rebate_market.dynamic_taker_fee_bps(), 20);
+
+        // Bids lock the full taker fee, so their lock covers a fill at any dynamic fee
+        // 买单锁定完整的吃单手续费，因此其锁定资金足以覆盖任意动态手续费下的成交
+        let mut rng = Rng(0x5eed_0009);
+        for _ in 0..CASES {
+            let mut market = random_market(&mut rng);
+            market.flags |= MARKET_FLAG_DYNAMIC_FEES;
+            market.dynamic_fee_min_bps = rng.below(market.taker_fee_bps as u64 + 1) as u16;
+            market.dynamic_fee_volatility_bps = 1 + rng.below(10_000) as u16;
+            market.volatility_window = 1 + rng.below(100) as u16;
+            market.realized_variance = rng.amount();
+            let fee_bps = market.dynamic_taker_fee_bps();
+            assert!(fee_bps >= market.dynamic_fee_min_bps && fee_bps <= market.taker_fee_bps);
+            assert!(fee_bps >= market.maker_fee_bps.min(0).unsigned_abs());
+
+            let limit_price = rng.amount() >> 40;
+            let quantity = rng.amount() >> 40;
+            let lock = market.escrow_amount(true, limit_price, quantity).unwrap();
+            let amounts = market
+                .settlement_amounts(market.notional(rng.below(limit_price + 1), quantity).unwrap(), false)
+                .unwrap();
+            assert!(amounts.taker_quote_amount().unwrap() <= lock);
+        }
+    }
+
+    #[test]
+    fn test_lot_conversions() {
+        // Lots convert to token amounts exactly, and amounts back to whole lots rounding down
+        // 手数精确换算为代币数量，代币数量向下取整换算回整手
+        let mut rng = Rng(0x5eed_0005);
+        for _ in 0..CASES {
+            let market = random_market(&mut rng);
+            let lots = rng.amount() >> 24;
+            let base_amount = market.base_lots_to_native(lots).unwrap();
+            let quote_amount = market.quote_lots_to_native(lots).unwrap();
+            assert_eq!(base_amount, lots * market.base_lot_size);
+            assert_eq!(quote_amount, lots * market.quote_lot_size);
+            assert_eq!(market.native_to_base_lots(base_amount), lots);
+            assert_eq!(market.native_to_quote_lots(quote_amount), lots);
+            assert_eq!(market.native_to_base_lots(base_amount + market.base_lot_size - 1), lots);
+
+            // The notional of a price in quote lots per base lot is counted in quote lots
+            // 以每基础手的报价手数计的价格，其名义金额以报价手计
+            let price = rng.amount() >> 40;
+            let quantity = rng.amount() >> 40;
+            assert_eq!(
+                market.notional(price, quantity).unwrap(),
+                market.quote_lots_to_native(price * quantity).unwrap()
+            );
+        }
+
+        // Amounts that do not fit in a token amount are rejected
+        // 无法放入代币数量的结果会被拒绝
+        let market = Market {
+            base_lot_size: 1_000,
+            quote_lot_size: 10,
+            ..market(30, 0, 0)
+        };
+        assert_eq!(market.base_lots_to_native(u64::MAX), Err(ProgramError::ArithmeticOverflow));
+        assert_eq!(market.quote_lots_to_native(u64::MAX), Err(ProgramError::ArithmeticOverflow));
+        assert_eq!(market.notional(u64::MAX, 1), Err(ProgramError::ArithmeticOverflow));
+        assert_eq!(market.escrow_amount(false, 1000, 7).unwrap(), 7_000);
+        assert_eq!(market.escrow_amount(true, 1000, 7).unwrap(), (1000 + 3) * 7 * 10);
+    }
+
+    #[test]
+    fn test_isqrt_rounds_down() {
+        let check = |value: u128| {
+            let root = math::isqrt(value);
+            assert!(root * root <= value, "{}", value);
+            assert!((root + 1).checked_mul(root + 1).is_none_or(|square| square > value), "{}", value);
+        };
+        for value in [0, 1, 2, 3, 4, 99, 100, u64::MAX as u128, u128::MAX] {
+            check(value);
+        }
+
+        // Products of two token amounts, as a pool's first deposit takes
+        // 两个代币数量的乘积，即资金池首次存入所取的值
+        let mut rng = Rng(0x5eed_0006);
+        for _ in 0..CASES {
+            check(rng.amount() as u128 * rng.amount() as u128);
+        }
+    }
+
+    #[test]
+    fn test_sqrt_price_ticks() {
+        // Tick zero is a price of one, and the extreme ticks still fit
+        // 刻度零对应价格 1，极端刻度的结果仍可容纳
+        assert_eq!(math::sqrt_price_at_tick(0).unwrap(), 1 << 32);
+        assert!(math::sqrt_price_at_tick(math::MIN_TICK).unwrap() > 0);
+        assert!(math::sqrt_price_at_tick(math::MAX_TICK).is_ok());
+        assert_eq!(math::sqrt_price_at_tick(math::MAX_TICK + 1), Err(ProgramError::InvalidArgument));
+        assert_eq!(math::sqrt_price_at_tick(math::MIN_TICK - 1), Err(ProgramError::InvalidArgument));
+
+        let span = (math::MAX_TICK - math::MIN_TICK) as u64;
+        let mut rng = Rng(0x5eed_0007);
+        for _ in 0..2_000 {
+            let tick = math::MIN_TICK + rng.below(span) as i32;
+            let sqrt_price = math::sqrt_price_at_tick(tick).unwrap();
+            let next = math::sqrt_price_at_tick(tick + 1).unwrap();
+
+            // Every tick raises the price, close to sqrt(1.0001) per tick
+            // 每个刻度都会提高价格，每个刻度约为 sqrt(1.0001) 倍
+            assert!(next > sqrt_price, "{}", tick);
+            let exact = 1.0001f64.powf(tick as f64 / 2.0) * (1u64 << 32) as f64;
+            assert!((sqrt_price as f64 - exact).abs() <= exact * 1e-9 + 1.0, "{}", tick);
+
+            // The tick of a square-root price is the highest tick at or below it
+            // 平方根价格所在的刻度是不高于它的最高刻度
+            assert_eq!(math::tick_at_sqrt_price(sqrt_price).unwrap(), tick);
+            assert_eq!(math::tick_at_sqrt_price(next - 1).unwrap(), tick);
+        }
+    }
+
+    #[test]
+    fn test_range_deltas_round_against_the_trader() {
+        let mut rng = Rng(0x5eed_0008);
+        for _ in 0..CASES {
+            let a = math::sqrt_price_at_tick(rng.below(200_000) as i32 - 100_000).unwrap();
+            let b = math::sqrt_price_at_tick(rng.below(200_000) as i32 - 100_000).unwrap();
+            let liquidity = rng.amount() >> 16;
+
+            // Rounding up never gives less, and at most the two rounded reciprocals more
+            // 向上取整的结果不会更少，最多多出两个取整倒数的误差
+            let base_down = math::base_delta(a, b, liquidity, Rounding::Down).unwrap();
+            let base_up = math::base_delta(a, b, liquidity, Rounding::Up).unwrap();
+            assert!(base_down <= base_up && base_up - base_down <= 2);
+            let quote_down = math::quote_delta(a, b, liquidity, Rounding::Down).unwrap();
+            let quote_up = math::quote_delta(a, b, liquidity, Rounding::Up).unwrap();
+            assert!(quote_down <= quote_up && quote_up - quote_down <= 1);
+
+            // Swapping an amount in moves the price no further than the amount pays for
+            // 换入的数量使价格移动的幅度不超过该数量足以支付的幅度
+            if liquidity == 0 {
+                continue;
+            }
+            let amount = rng.amount() >> 24;
+            // A price past what a u64 holds is rejected rather than wrapped
+            // 超出 u64 范围的价格会被拒绝而不是回绕
+            if let Ok(up) = math::next_sqrt_price(a, liquidity, amount, true) {
+                assert!(up >= a);
+                assert!(math::quote_delta(a, up, liquidity, Rounding::Up).unwrap() <= amount);
+            }
+            let down = math::next_sqrt_price(a, liquidity, amount, false).unwrap();
+            assert!(down <= a);
+            assert!(math::base_delta(down, a, liquidity, Rounding::Down).unwrap() <= amount);
+        }
+    }
+}
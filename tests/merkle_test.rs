@@ -0,0 +1,159 @@
+// Open orders commitment test module
+// 挂单默克尔承诺测试模块
+
+mod common;
+
+#[cfg(test)]
+mod merkle_tests {
+    use super::common;
+    use solana_program::{
+        program_pack::Pack,
+        pubkey::Pubkey,
+    };
+    use solana_program_test::*;
+    use solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::Transaction,
+    };
+    use solana_rust_dex::{
+        instruction::DexInstruction,
+        merkle,
+        state::{Market, Order, ACCOUNT_VERSION, ORDER_RESERVED_LEN},
+    };
+
+    #[tokio::test]
+    async fn test_commit_open_orders_root_and_verify_proofs() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // Add a market with three resting asks to the test environment
+        // 将一个带有三个卖出挂单的市场添加到测试环境
+        let market_account = Keypair::new();
+        let market = Market {
+            authority: Pubkey::new_unique(),
+            base_mint: Pubkey::new_unique(),
+            quote_mint: Pubkey::new_unique(),
+            min_base_order_size: 100,
+            tick_size: 10,
+            taker_fee_bps: 25,
+            next_order_id: 4,
+            num_asks: 3,
+            open_orders_root: merkle::EMPTY_ROOT,
+            fee_authority: Pubkey::new_unique(),
+            // Left behind by an ask that has since been cancelled
+            // 由一个已被取消的卖单遗留
+            best_ask: 1_000,
+            ..common::market()
+        };
+        let mut market_data = vec![0; Market::LEN];
+        market.pack_into_slice(&mut market_data);
+        program_test.add_account(
+            market_account.pubkey(),
+            solana_sdk::account::Account {
+                lamports: 1000000000,
+                data: market_data,
+                owner: program_id,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+
+        let mut orders = Vec::new();
+        for order_id in 1..=3 {
+            let order_account = Pubkey::new_unique();
+            let order = Order {
+                is_initialized: true,
+                order_id,
+                owner: Pubkey::new_unique(),
+                market: market_account.pubkey(),
+                is_buy: false,
+                limit_price: 1000 + order_id * 10,
+                original_quantity: 500,
+                remaining_quantity: 500,
+                creation_timestamp: 0,
+                linked_order: Pubkey::default(),
+                display_quantity: 0,
+                visible_quantity: 500,
+                referrer: Pubkey::default(),
+                memo: [0; 32],
+                version: ACCOUNT_VERSION,
+                reserved: [0; ORDER_RESERVED_LEN],
+            };
+
+            let mut order_data = vec![0; Order::LEN];
+            order.pack_into_slice(&mut order_data);
+            program_test.add_account(
+                order_account,
+                solana_sdk::account::Account {
+                    lamports: 1000000000,
+                    data: order_data,
+                    owner: program_id,
+                    executable: false,
+                    rent_epoch: 0,
+                },
+            );
+            orders.push((order_account, order));
+        }
+
+        // Start the test environment
+        // 启动测试环境
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        // Crank the commitment over all open orders
+        // 对所有挂单执行承诺
+        let order_accounts: Vec<Pubkey> = orders.iter().map(|(address, _)| *address).collect();
+        let commit_ix = DexInstruction::commit_open_orders_root(
+            &program_id,
+            &market_account.pubkey(),
+            &order_accounts,
+        )
+        .unwrap();
+
+        let mut transaction = Transaction::new_with_payer(&[commit_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // The stored root matches the one computed off-chain
+        // 链上存储的根与链下计算的根一致
+        orders.sort_by_key(|(address, _)| *address);
+        let leaves: Vec<[u8; 32]> = orders
+            .iter()
+            .map(|(address, order)| merkle::leaf_hash(address, order))
+            .collect();
+
+        let market_account_data = banks_client
+            .get_account(market_account.pubkey())
+            .await
+            .unwrap()
+            .unwrap();
+        let market = Market::unpack_from_slice(&market_account_data.data).unwrap();
+        assert_eq!(market.open_orders_root, merkle::root(&leaves));
+        assert_eq!({ market.open_orders_root_leaves }, 3);
+
+        // The crank also recomputes the best prices from the whole book
+        // 该操作还会根据整个订单簿重新计算最优价格
+        assert_eq!({ market.best_bid }, 0);
+        assert_eq!({ market.best_ask }, 1_010);
+
+        // Every order has a valid inclusion proof, and proofs do not transfer between positions
+        // 每个订单都有有效的包含证明，且证明不能挪用到其他位置
+        for (index, leaf) in leaves.iter().enumerate() {
+            let proof = merkle::proof(&leaves, index).unwrap();
+            assert!(merkle::verify(&market.open_orders_root, leaf, index, leaves.len(), &proof));
+            assert!(!merkle::verify(
+                &market.open_orders_root,
+                leaf,
+                (index + 1) % leaves.len(),
+                leaves.len(),
+                &proof,
+            ));
+        }
+        assert!(merkle::proof(&leaves, leaves.len()).is_none());
+    }
+}
@@ -0,0 +1,244 @@
+// Account migration test module
+// 账户迁移测试模块
+
+mod common;
+
+#[cfg(test)]
+mod migrate_tests {
+    use super::common;
+    use solana_program::{program_pack::Pack, pubkey::Pubkey, rent::Rent};
+    use solana_program_test::*;
+    use solana_sdk::{
+        account::Account,
+        instruction::InstructionError,
+        signature::{Keypair, Signer},
+        transaction::{Transaction, TransactionError},
+    };
+    use solana_rust_dex::{
+        error::DexError,
+        instruction::DexInstruction,
+        state::{
+            Market, MarketStatus, Order, ACCOUNT_VERSION, MARKET_RESERVED_LEN,
+            ORDER_RESERVED_LEN,
+        },
+    };
+
+    fn custom_error(err: TransactionError) -> u32 {
+        match err {
+            TransactionError::InstructionError(0, InstructionError::Custom(code)) => code,
+            err => panic!("unexpected error: {:?}", err),
+        }
+    }
+
+    fn market(authority: &Pubkey) -> Market {
+        Market {
+            authority: *authority,
+            base_mint: Pubkey::new_unique(),
+            quote_mint: Pubkey::new_unique(),
+            min_base_order_size: 100,
+            tick_size: 10,
+            taker_fee_bps: 25,
+            next_order_id: 2,
+            num_bids: 1,
+            fee_authority: *authority,
+            bid_base_total: 100,
+            bid_notional: 100_000,
+            fill_seq_num: 7,
+            best_bid: 1_000,
+            last_price: 990,
+            last_trade_slot: 12,
+            base_volume: 500,
+            quote_volume: 495_000,
+            ..common::market()
+        }
+    }
+
+    fn order(owner: &Pubkey, market: &Pubkey) -> Order {
+        Order {
+            is_initialized: true,
+            order_id: 1,
+            owner: *owner,
+            market: *market,
+            is_buy: true,
+            limit_price: 1_000,
+            original_quantity: 100,
+            remaining_quantity: 100,
+            creation_timestamp: 1_700_000_000,
+            linked_order: Pubkey::default(),
+            display_quantity: 0,
+            visible_quantity: 100,
+            referrer: Pubkey::default(),
+            memo: [0; 32],
+            version: ACCOUNT_VERSION,
+            reserved: [0; ORDER_RESERVED_LEN],
+        }
+    }
+
+    // Account holding the legacy layout of a packed account: its fields without the version
+    // and the reserved space, rent-exempt for that size only
+    // 保存打包账户旧布局的账户：不含版本号和预留空间的字段，租金仅按该大小豁免
+    fn legacy_account(data: &[u8], legacy_len: usize, program_id: &Pubkey) -> Account {
+        Account {
+            lamports: Rent::default().minimum_balance(legacy_len),
+            data: data[..legacy_len].to_vec(),
+            owner: *program_id,
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_migrate_legacy_market_and_order() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // Add a market and an order of the legacy layout
+        // 添加旧布局的市场和订单
+        let authority = Keypair::new();
+        let owner = Keypair::new();
+        let market_account = Pubkey::new_unique();
+        let order_account = Pubkey::new_unique();
+        let market = market(&authority.pubkey());
+        let order = order(&owner.pubkey(), &market_account);
+
+        let mut market_data = vec![0; Market::LEN];
+        market.pack_into_slice(&mut market_data);
+        program_test.add_account(
+            market_account,
+            legacy_account(&market_data, Market::LEGACY_LEN, &program_id),
+        );
+        let mut order_data = vec![0; Order::LEN];
+        order.pack_into_slice(&mut order_data);
+        program_test.add_account(
+            order_account,
+            legacy_account(&order_data, Order::LEGACY_LEN, &program_id),
+        );
+
+        // Start the test environment
+        // 启动测试环境
+        let mut context = program_test.start_with_context().await;
+        let payer = context.payer.insecure_clone();
+        let recent_blockhash = context.last_blockhash;
+
+        // Legacy accounts are refused until they are migrated
+        // 旧账户在迁移之前会被拒绝
+        let status_ix = DexInstruction::set_market_status(
+            &program_id,
+            &authority.pubkey(),
+            &market_account,
+            MarketStatus::Paused,
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(std::slice::from_ref(&status_ix), Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &authority], recent_blockhash);
+        let err = context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(custom_error(err), DexError::AccountNotMigrated as u32);
+
+        // Anyone can migrate the market, paying for its larger size
+        // 任何人都可以迁移市场，并为其增大的空间付费
+        let migrate_market_ix = DexInstruction::migrate_account(&program_id, &payer.pubkey(), &market_account).unwrap();
+        let mut transaction = Transaction::new_with_payer(std::slice::from_ref(&migrate_market_ix), Some(&payer.pubkey()));
+        transaction.sign(&[&payer], recent_blockhash);
+        context.banks_client.process_transaction(transaction).await.unwrap();
+
+        let account = context.banks_client.get_account(market_account).await.unwrap().unwrap();
+        assert_eq!(account.data.len(), Market::LEN);
+        assert!(account.lamports >= Rent::default().minimum_balance(Market::LEN));
+        let migrated = Market::unpack_from_slice(&account.data).unwrap();
+        assert_eq!({ migrated.version }, ACCOUNT_VERSION);
+        assert_eq!({ migrated.flags }, 0);
+        assert_eq!({ migrated.fees_accrued }, 0);
+        assert_eq!(migrated.reserved, [0; MARKET_RESERVED_LEN]);
+        assert_eq!(&account.data[..Market::LEGACY_LEN], &market_data[..Market::LEGACY_LEN]);
+
+        // The migrated market loads again
+        // 迁移后的市场可以再次加载
+        let recent_blockhash = common::warp_slots(&mut context, 1).await;
+        let mut transaction = Transaction::new_with_payer(&[status_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &authority], recent_blockhash);
+        context.banks_client.process_transaction(transaction).await.unwrap();
+
+        // The legacy order is refused, then migrated
+        // 旧订单会被拒绝，然后被迁移
+        let cancel_ix = DexInstruction::cancel_order(
+            &program_id,
+            &owner.pubkey(),
+            &market_account,
+            &order_account,
+            &Pubkey::new_unique(),
+            &spl_token::id(),
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(&[cancel_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &owner], recent_blockhash);
+        let err = context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(custom_error(err), DexError::AccountNotMigrated as u32);
+
+        let migrate_order_ix = DexInstruction::migrate_account(&program_id, &payer.pubkey(), &order_account).unwrap();
+        let mut transaction = Transaction::new_with_payer(&[migrate_order_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer], recent_blockhash);
+        context.banks_client.process_transaction(transaction).await.unwrap();
+
+        let account = context.banks_client.get_account(order_account).await.unwrap().unwrap();
+        assert_eq!(account.data.len(), Order::LEN);
+        let migrated = Order::unpack_from_slice(&account.data).unwrap();
+        assert_eq!({ migrated.version }, ACCOUNT_VERSION);
+        assert_eq!({ migrated.remaining_quantity }, 100);
+        assert_eq!(migrated.owner, owner.pubkey());
+
+        // Migrating a current account changes nothing
+        // 迁移当前布局的账户不会改变任何内容
+        let before = context.banks_client.get_account(market_account).await.unwrap().unwrap();
+        let recent_blockhash = common::warp_slots(&mut context, 1).await;
+        let mut transaction = Transaction::new_with_payer(&[migrate_market_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer], recent_blockhash);
+        context.banks_client.process_transaction(transaction).await.unwrap();
+        let after = context.banks_client.get_account(market_account).await.unwrap().unwrap();
+        assert_eq!(after.data, before.data);
+        assert_eq!(after.lamports, before.lamports);
+    }
+
+    #[tokio::test]
+    async fn test_migrate_rejects_foreign_accounts() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // An account of another program, and a program account of neither layout
+        // 其他程序的账户，以及不属于任何布局的程序账户
+        let foreign_account = Pubkey::new_unique();
+        let mut market_data = vec![0; Market::LEN];
+        market(&Pubkey::new_unique()).pack_into_slice(&mut market_data);
+        program_test.add_account(
+            foreign_account,
+            legacy_account(&market_data, Market::LEGACY_LEN, &Pubkey::new_unique()),
+        );
+        let unknown_account = Pubkey::new_unique();
+        program_test.add_account(unknown_account, legacy_account(&[0; 10], 10, &program_id));
+
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        let migrate_ix = DexInstruction::migrate_account(&program_id, &payer.pubkey(), &foreign_account).unwrap();
+        let mut transaction = Transaction::new_with_payer(&[migrate_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(custom_error(err), DexError::InvalidAccountOwner as u32);
+
+        let migrate_ix = DexInstruction::migrate_account(&program_id, &payer.pubkey(), &unknown_account).unwrap();
+        let mut transaction = Transaction::new_with_payer(&[migrate_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(custom_error(err), DexError::InvalidAccountData as u32);
+    }
+}
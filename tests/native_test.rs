@@ -0,0 +1,71 @@
+// Native SOL wrapping test module
+// 原生 SOL 包装测试模块
+
+#[cfg(test)]
+mod native_tests {
+    use solana_program::{native_token::LAMPORTS_PER_SOL, system_instruction};
+    use solana_program_test::*;
+    use solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::Transaction,
+    };
+    use spl_associated_token_account::{
+        get_associated_token_address, instruction::create_associated_token_account_idempotent,
+    };
+
+    #[tokio::test]
+    async fn test_unwrap_sweeps_all_wrapped_sol() {
+        // Create program test environment, which has the token programs built in
+        // 创建程序测试环境，其中已内置代币程序
+        let (mut banks_client, payer, recent_blockhash) = ProgramTest::default().start().await;
+        let owner = Keypair::new();
+        let account = get_associated_token_address(&owner.pubkey(), &spl_token::native_mint::id());
+        let wrap = |lamports: u64| {
+            [
+                system_instruction::transfer(&owner.pubkey(), &account, lamports),
+                spl_token::instruction::sync_native(&spl_token::id(), &account).unwrap(),
+            ]
+        };
+
+        // The owner already holds 2 SOL wrapped outside the client, then the client wraps 1 SOL
+        // 所有者已在客户端之外包装了 2 SOL，随后客户端再包装 1 SOL
+        let mut instructions = vec![
+            system_instruction::transfer(&payer.pubkey(), &owner.pubkey(), 10 * LAMPORTS_PER_SOL),
+            create_associated_token_account_idempotent(
+                &payer.pubkey(),
+                &owner.pubkey(),
+                &spl_token::native_mint::id(),
+                &spl_token::id(),
+            ),
+        ];
+        instructions.extend(wrap(2 * LAMPORTS_PER_SOL));
+        instructions.extend(wrap(LAMPORTS_PER_SOL));
+        let transaction = Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&payer.pubkey()),
+            &[&payer, &owner],
+            recent_blockhash,
+        );
+        banks_client.process_transaction(transaction).await.unwrap();
+        let rent = banks_client.get_account(account).await.unwrap().unwrap().lamports - 3 * LAMPORTS_PER_SOL;
+
+        // Unwrapping closes the account, returning all 3 SOL and its rent, not just the 1 SOL
+        // 解包会关闭账户，退还全部 3 SOL 及其租金，而不仅是那 1 SOL
+        let unwrap = spl_token::instruction::close_account(
+            &spl_token::id(),
+            &account,
+            &owner.pubkey(),
+            &owner.pubkey(),
+            &[],
+        )
+        .unwrap();
+        let transaction =
+            Transaction::new_signed_with_payer(&[unwrap], Some(&payer.pubkey()), &[&payer, &owner], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+        assert!(banks_client.get_account(account).await.unwrap().is_none());
+        assert_eq!(
+            banks_client.get_balance(owner.pubkey()).await.unwrap(),
+            10 * LAMPORTS_PER_SOL + rent
+        );
+    }
+}
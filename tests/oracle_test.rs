@@ -0,0 +1,245 @@
+// Oracle circuit breaker test module
+// 预言机熔断机制测试模块
+
+mod common;
+
+#[cfg(test)]
+mod oracle_tests {
+    use super::common;
+    use solana_program::{program_pack::Pack, pubkey::Pubkey};
+    use solana_program_test::*;
+    use solana_sdk::{
+        account::Account,
+        instruction::{Instruction, InstructionError},
+        signature::{Keypair, Signer},
+        transaction::{Transaction, TransactionError},
+    };
+    use solana_rust_dex::{
+        error::DexError,
+        instruction::{DexInstruction, SelfTradeBehavior},
+        oracle::{self, OraclePrice, PYTH_MAGIC, PYTH_PRICE_ACCOUNT_TYPE, PYTH_STATUS_TRADING, PYTH_VERSION},
+        state::{Market, MarketStatus},
+    };
+
+    // Pyth price account with an aggregate price published in a slot
+    // 在指定槽位发布聚合价格的 Pyth 价格账户
+    fn pyth_price_data(price: i64, expo: i32, status: u32, publish_slot: u64) -> Vec<u8> {
+        let mut data = vec![0; 3312];
+        data[0..4].copy_from_slice(&PYTH_MAGIC.to_le_bytes());
+        data[4..8].copy_from_slice(&PYTH_VERSION.to_le_bytes());
+        data[8..12].copy_from_slice(&PYTH_PRICE_ACCOUNT_TYPE.to_le_bytes());
+        data[20..24].copy_from_slice(&expo.to_le_bytes());
+        data[208..216].copy_from_slice(&price.to_le_bytes());
+        data[216..224].copy_from_slice(&1_000_000u64.to_le_bytes());
+        data[224..228].copy_from_slice(&status.to_le_bytes());
+        data[232..240].copy_from_slice(&publish_slot.to_le_bytes());
+        data
+    }
+
+    fn custom_error(err: TransactionError) -> u32 {
+        match err {
+            TransactionError::InstructionError(_, InstructionError::Custom(code)) => code,
+            err => panic!("unexpected error: {:?}", err),
+        }
+    }
+
+    #[test]
+    fn test_oracle_price_conversion_and_band() {
+        // A trading price converts to market price units
+        // 交易中的价格可以转换为市场价格单位
+        let price = OraclePrice::load(&pyth_price_data(150_00000000, -8, PYTH_STATUS_TRADING, 90)).unwrap();
+        assert_eq!(price.price, 150_00000000);
+        assert_eq!(price.expo, -8);
+        assert_eq!(price.price_in(-2), Some(15_000));
+        assert_eq!(price.price_in(-10), Some(1_500_000_000_000));
+        assert!(price.is_fresh(100, 10));
+        assert!(!price.is_fresh(101, 10));
+
+        // Halted and non-positive prices are not usable
+        // 暂停的价格和非正价格不可用
+        let halted = OraclePrice::load(&pyth_price_data(150_00000000, -8, 2, 90)).unwrap();
+        assert!(!halted.is_fresh(90, 10));
+        let negative = OraclePrice::load(&pyth_price_data(-1, -8, PYTH_STATUS_TRADING, 90)).unwrap();
+        assert_eq!(negative.price_in(-2), None);
+
+        // Other accounts are rejected
+        // 其他账户会被拒绝
+        let mut data = pyth_price_data(150_00000000, -8, PYTH_STATUS_TRADING, 90);
+        data[8] = 2;
+        assert!(OraclePrice::load(&data).is_err());
+        assert!(OraclePrice::load(&[0; 100]).is_err());
+
+        // The band is inclusive on both sides
+        // 价格区间两端均包含在内
+        assert!(oracle::is_within_band(15_750, 15_000, 500));
+        assert!(oracle::is_within_band(14_250, 15_000, 500));
+        assert!(!oracle::is_within_band(15_760, 15_000, 500));
+        assert!(!oracle::is_within_band(14_240, 15_000, 500));
+    }
+
+    #[tokio::test]
+    async fn test_oracle_band_and_breaker() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // Add a market and a Pyth price of 150.00 published at the first slot
+        // 添加一个市场以及在第一个槽位发布的 150.00 的 Pyth 价格
+        let market_authority = Keypair::new();
+        let market_account = Pubkey::new_unique();
+        let market = Market {
+            authority: market_authority.pubkey(),
+            base_mint: Pubkey::new_unique(),
+            quote_mint: Pubkey::new_unique(),
+            min_base_order_size: 100,
+            tick_size: 10,
+            taker_fee_bps: 25,
+            fee_authority: market_authority.pubkey(),
+            ..common::market()
+        };
+        let mut market_data = vec![0; Market::LEN];
+        market.pack_into_slice(&mut market_data);
+        program_test.add_account(
+            market_account,
+            Account {
+                lamports: 1000000000,
+                data: market_data,
+                owner: program_id,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+        let oracle_account = Pubkey::new_unique();
+        program_test.add_account(
+            oracle_account,
+            Account {
+                lamports: 1000000000,
+                data: pyth_price_data(150_00000000, -8, PYTH_STATUS_TRADING, 1),
+                owner: Pubkey::new_unique(),
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+
+        // Start the test environment
+        // 启动测试环境
+        let mut context = program_test.start_with_context().await;
+        let payer = context.payer.insecure_clone();
+
+        // Only the authority sets the oracle, and the band must be positive
+        // 只有权限账户可以设置预言机，且价格区间必须为正
+        let set_oracle_ix = |authority: &Pubkey, band_bps: u16| {
+            DexInstruction::set_oracle(&program_id, authority, &market_account, &oracle_account, band_bps, 10, -2)
+                .unwrap()
+        };
+        let intruder = Keypair::new();
+        let mut transaction = Transaction::new_with_payer(
+            &[set_oracle_ix(&intruder.pubkey(), 500)],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &intruder], context.last_blockhash);
+        let err = context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(custom_error(err), DexError::AccountNotAuthorized as u32);
+
+        let mut transaction = Transaction::new_with_payer(
+            &[set_oracle_ix(&market_authority.pubkey(), 0)],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &market_authority], context.last_blockhash);
+        let err = context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(custom_error(err), DexError::InvalidInstructionData as u32);
+
+        // Orders must stay within 5% of the oracle price
+        // 订单价格必须在预言机价格的 5% 以内
+        let mut transaction = Transaction::new_with_payer(
+            &[set_oracle_ix(&market_authority.pubkey(), 500)],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &market_authority], context.last_blockhash);
+        context.banks_client.process_transaction(transaction).await.unwrap();
+
+        let market_data = context.banks_client.get_account(market_account).await.unwrap().unwrap().data;
+        let market = Market::unpack_from_slice(&market_data).unwrap();
+        assert_eq!(market.oracle, oracle_account);
+        assert_eq!({ market.oracle_band_bps }, 500);
+
+        let order_owner = Keypair::new();
+        let place_order_ix = |limit_price: u64| {
+            DexInstruction::place_limit_order(
+                &program_id,
+                &order_owner.pubkey(),
+                &market_account,
+                &Pubkey::new_unique(),
+                &Pubkey::new_unique(),
+                &spl_token::id(),
+                true,
+                limit_price,
+                100,
+                SelfTradeBehavior::DecrementTake,
+            )
+            .unwrap()
+        };
+        let send = |instruction: Instruction, blockhash| {
+            let mut transaction = Transaction::new_with_payer(&[instruction], Some(&payer.pubkey()));
+            transaction.sign(&[&payer, &order_owner], blockhash);
+            transaction
+        };
+
+        // An order without the oracle account is rejected
+        // 未提供预言机账户的订单会被拒绝
+        let err = context
+            .banks_client
+            .process_transaction(send(place_order_ix(15_000), context.last_blockhash))
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(custom_error(err), DexError::InvalidOraclePrice as u32);
+
+        // Fat-finger prices on either side are rejected
+        // 任意一侧的错误报价都会被拒绝
+        for limit_price in [15_760, 14_240] {
+            let instruction = DexInstruction::with_oracle(place_order_ix(limit_price), &oracle_account);
+            let err = context
+                .banks_client
+                .process_transaction(send(instruction, context.last_blockhash))
+                .await
+                .unwrap_err()
+                .unwrap();
+            assert_eq!(custom_error(err), DexError::PriceOutsideOracleBand as u32);
+        }
+
+        // The breaker leaves the market active while the price is fresh
+        // 价格有效时熔断机制保持市场为活跃状态
+        let check_ix = DexInstruction::check_oracle(&program_id, &market_account, &oracle_account).unwrap();
+        let mut transaction = Transaction::new_with_payer(std::slice::from_ref(&check_ix), Some(&payer.pubkey()));
+        transaction.sign(&[&payer], context.last_blockhash);
+        context.banks_client.process_transaction(transaction).await.unwrap();
+
+        let market_data = context.banks_client.get_account(market_account).await.unwrap().unwrap().data;
+        assert_eq!(Market::unpack_from_slice(&market_data).unwrap().status, MarketStatus::Active);
+
+        // Once the price is stale orders are rejected and the crank halts the market
+        // 价格过期后订单会被拒绝，并且清算程序会暂停市场
+        let blockhash = common::warp_to_slot(&mut context, 100).await;
+        let instruction = DexInstruction::with_oracle(place_order_ix(15_000), &oracle_account);
+        let err = context
+            .banks_client
+            .process_transaction(send(instruction, blockhash))
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(custom_error(err), DexError::OracleStale as u32);
+
+        let mut transaction = Transaction::new_with_payer(&[check_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer], blockhash);
+        context.banks_client.process_transaction(transaction).await.unwrap();
+
+        let market_data = context.banks_client.get_account(market_account).await.unwrap().unwrap().data;
+        assert_eq!(Market::unpack_from_slice(&market_data).unwrap().status, MarketStatus::CancelOnly);
+    }
+}
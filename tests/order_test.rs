@@ -1,40 +1,67 @@
 // Order test module
 // 订单测试模块
 
+mod common;
+
 #[cfg(test)]
 mod order_tests {
+    use super::common;
     use solana_program::{
         program_pack::Pack,
         pubkey::Pubkey,
+        rent::Rent,
+        system_instruction,
     };
     use solana_program_test::*;
     use solana_sdk::{
+        instruction::InstructionError,
         signature::{Keypair, Signer},
-        transaction::Transaction,
+        transaction::{Transaction, TransactionError},
     };
     use solana_rust_dex::{
+        error::DexError,
         instruction::{DexInstruction, SelfTradeBehavior},
-        state::{Market, Order},
+        state::{
+            EventQueue, Market, OpenOrders, Order, PlacedOrder, TwapOracle, ACCOUNT_VERSION, MARKET_FLAG_BEST_ASK_STALE,
+            MARKET_FLAG_BEST_BID_STALE, ORDER_RESERVED_LEN,
+        },
     };
+    use borsh::BorshDeserialize;
 
     async fn setup_market(
         program_id: &Pubkey,
         banks_client: &mut BanksClient,
         payer: &Keypair,
         recent_blockhash: &solana_sdk::hash::Hash,
-    ) -> (Keypair, Keypair, Pubkey, Pubkey) {
+        market_account: &Keypair,
+    ) -> (Keypair, Pubkey, Pubkey) {
+        setup_market_with_lot_sizes(program_id, banks_client, payer, recent_blockhash, market_account, 1, 1).await
+    }
+
+    // Set up a market counting quantities in lots of `base_lot_size` and prices in lots of
+    // `quote_lot_size`
+    // 设置以 `base_lot_size` 为一手计数量、以 `quote_lot_size` 为一手计价格的市场
+    async fn setup_market_with_lot_sizes(
+        program_id: &Pubkey,
+        banks_client: &mut BanksClient,
+        payer: &Keypair,
+        recent_blockhash: &solana_sdk::hash::Hash,
+        market_account: &Keypair,
+        base_lot_size: u64,
+        quote_lot_size: u64,
+    ) -> (Keypair, Pubkey, Pubkey) {
         // Create accounts for the test
         // 为测试创建账户
         let market_authority = Keypair::new();
-        let market_account = Keypair::new();
-        let base_mint = Pubkey::new_unique();
-        let quote_mint = Pubkey::new_unique();
+        let base_mint = common::create_mint(banks_client, payer, *recent_blockhash, 9).await;
+        let quote_mint = common::create_mint(banks_client, payer, *recent_blockhash, 6).await;
 
         // Create initialize market instruction
         // 创建初始化市场指令
         let min_base_order_size = 100;
         let tick_size = 10;
-        let fee_rate_bps = 25; // 0.25%
+        let taker_fee_bps = 25; // 0.25%
+        let maker_fee_bps = -5; // 0.05% rebate
 
         let init_market_ix = DexInstruction::initialize_market(
             program_id,
@@ -44,23 +71,72 @@ mod order_tests {
             &quote_mint,
             min_base_order_size,
             tick_size,
-            fee_rate_bps,
+            taker_fee_bps,
+            maker_fee_bps,
+        )
+        .unwrap();
+        let init_market_ix = DexInstruction::with_lot_sizes(init_market_ix, base_lot_size, quote_lot_size).unwrap();
+
+        // Create the market account owned by the program
+        // 创建由程序拥有的市场账户
+        let create_market_ix = system_instruction::create_account(
+            &payer.pubkey(),
+            &market_account.pubkey(),
+            Rent::default().minimum_balance(Market::LEN),
+            Market::LEN as u64,
+            program_id,
+        );
+
+        // Fund the market authority, which pays for its market registry entry
+        // 为市场权限账户注资，由其支付市场注册表条目的费用
+        let fund_authority_ix = system_instruction::transfer(&payer.pubkey(), &market_authority.pubkey(), 1_000_000_000);
+
+        // Create the escrow vaults locking order funds
+        // 创建锁定订单资金的托管金库
+        let init_vaults_ix = DexInstruction::initialize_market_vaults(
+            program_id,
+            &payer.pubkey(),
+            &market_account.pubkey(),
+            &base_mint,
+            &quote_mint,
+            &spl_token::id(),
         )
         .unwrap();
 
         // Create and sign transaction
         // 创建并签名交易
         let mut transaction = Transaction::new_with_payer(
-            &[init_market_ix],
+            &[create_market_ix, fund_authority_ix, init_market_ix, init_vaults_ix],
             Some(&payer.pubkey()),
         );
-        transaction.sign(&[payer, &market_authority], *recent_blockhash);
+        transaction.sign(&[payer, &market_authority, market_account], *recent_blockhash);
 
         // Process transaction
         // 处理交易
         banks_client.process_transaction(transaction).await.unwrap();
 
-        (market_authority, market_account, base_mint, quote_mint)
+        (market_authority, base_mint, quote_mint)
+    }
+
+    // Add empty order accounts owned by the program, to be filled by new orders
+    // 添加由程序拥有的空订单账户，供新订单使用
+    fn add_order_accounts(program_test: &mut ProgramTest, program_id: &Pubkey, count: usize) -> Vec<Pubkey> {
+        (0..count)
+            .map(|_| {
+                let order_account = Pubkey::new_unique();
+                program_test.add_account(
+                    order_account,
+                    solana_sdk::account::Account {
+                        lamports: 1000000000,
+                        data: vec![0; Order::LEN],
+                        owner: *program_id,
+                        executable: false,
+                        rent_epoch: 0,
+                    },
+                );
+                order_account
+            })
+            .collect()
     }
 
     #[tokio::test]
@@ -74,38 +150,30 @@ mod order_tests {
             processor!(solana_rust_dex::entrypoint::process_instruction),
         );
 
+        // Create order accounts
+        // 创建订单账户
+        let market_account = Keypair::new();
+        let order_owner = Keypair::new();
+        let order_account = add_order_accounts(&mut program_test, &program_id, 1)[0];
+        let token_program = spl_token::id();
+
         // Start the test environment
         // 启动测试环境
         let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
 
-        // Setup market
-        // 设置市场
-        let (_, market_account, _, _) = setup_market(
+        // Setup market and fund the owner's quote account
+        // 设置市场并为所有者的报价代币账户注资
+        let (_, _, quote_mint) = setup_market(
             &program_id,
             &mut banks_client,
             &payer,
             &recent_blockhash,
+            &market_account,
         ).await;
-
-        // Create order accounts
-        // 创建订单账户
-        let order_owner = Keypair::new();
-        let order_account = Keypair::new();
-        let owner_token_account = Pubkey::new_unique();
-        let token_program = Pubkey::new_unique();
-
-        // Add order account to the test environment
-        // 将订单账户添加到测试环境
-        program_test.add_account(
-            order_account.pubkey(),
-            solana_sdk::account::Account {
-                lamports: 1000000000,
-                data: vec![0; Order::LEN],
-                owner: program_id,
-                executable: false,
-                rent_epoch: 0,
-            },
-        );
+        let owner_token_account =
+            common::create_token_account(&mut banks_client, &payer, recent_blockhash, &quote_mint, &order_owner.pubkey())
+                .await;
+        common::mint_to(&mut banks_client, &payer, recent_blockhash, &quote_mint, &owner_token_account, 1_000_000).await;
 
         // Create place limit order instruction
         // 创建下限价单指令
@@ -118,7 +186,7 @@ mod order_tests {
             &program_id,
             &order_owner.pubkey(),
             &market_account.pubkey(),
-            &order_account.pubkey(),
+            &order_account,
             &owner_token_account,
             &token_program,
             is_buy,
@@ -136,27 +204,40 @@ mod order_tests {
         );
         transaction.sign(&[&payer, &order_owner], recent_blockhash);
 
-        // Process transaction
-        // 处理交易
-        banks_client.process_transaction(transaction).await.unwrap();
+        // Process transaction, which returns the assigned order ID
+        // 处理交易，交易返回分配的订单 ID
+        let result = banks_client.process_transaction_with_metadata(transaction).await.unwrap();
+        result.result.unwrap();
+        let return_data = result.metadata.unwrap().return_data.unwrap();
+        assert_eq!(return_data.program_id, program_id);
+        assert_eq!(
+            PlacedOrder::try_from_slice(&return_data.data).unwrap(),
+            PlacedOrder { order_id: 1, fill_seq_num: 0 }
+        );
 
         // Verify order state
         // 验证订单状态
         let order_account_data = banks_client
-            .get_account(order_account.pubkey())
+            .get_account(order_account)
             .await
             .unwrap()
             .unwrap();
 
         let order = Order::unpack_from_slice(&order_account_data.data).unwrap();
         assert!(order.is_initialized);
-        assert_eq!(order.order_id, 1);
+        assert_eq!({ order.order_id }, 1);
         assert_eq!(order.owner, order_owner.pubkey());
         assert_eq!(order.market, market_account.pubkey());
         assert_eq!(order.is_buy, is_buy);
-        assert_eq!(order.limit_price, limit_price);
-        assert_eq!(order.original_quantity, quantity);
-        assert_eq!(order.remaining_quantity, quantity);
+        assert_eq!({ order.limit_price }, limit_price);
+        assert_eq!({ order.original_quantity }, quantity);
+        assert_eq!({ order.remaining_quantity }, quantity);
+
+        // The notional plus the 0.25% taker fee on each unit is locked in the market's quote vault
+        // 名义金额加上每单位 0.25% 的吃单手续费被锁定在市场的报价代币金库中
+        let (quote_vault, _) = Market::find_quote_vault_address(&program_id, &market_account.pubkey());
+        assert_eq!(common::token_balance(&mut banks_client, &owner_token_account).await, 498_500);
+        assert_eq!(common::token_balance(&mut banks_client, &quote_vault).await, 501_500);
     }
 
     #[tokio::test]
@@ -170,38 +251,457 @@ mod order_tests {
             processor!(solana_rust_dex::entrypoint::process_instruction),
         );
 
+        // Create order accounts
+        // 创建订单账户
+        let market_account = Keypair::new();
+        let order_owner = Keypair::new();
+        let order_accounts = add_order_accounts(&mut program_test, &program_id, 2);
+        let (bid_account, ask_account) = (order_accounts[0], order_accounts[1]);
+        let token_program = spl_token::id();
+
         // Start the test environment
         // 启动测试环境
         let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
 
-        // Setup market
-        // 设置市场
-        let (_, market_account, _, _) = setup_market(
+        // Setup market and fund the owner's token accounts
+        // 设置市场并为所有者的代币账户注资
+        let (_, base_mint, quote_mint) = setup_market(
+            &program_id,
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &market_account,
+        ).await;
+        let owner_base_account =
+            common::create_token_account(&mut banks_client, &payer, recent_blockhash, &base_mint, &order_owner.pubkey())
+                .await;
+        let owner_quote_account =
+            common::create_token_account(&mut banks_client, &payer, recent_blockhash, &quote_mint, &order_owner.pubkey())
+                .await;
+        common::mint_to(&mut banks_client, &payer, recent_blockhash, &base_mint, &owner_base_account, 1_000).await;
+        common::mint_to(&mut banks_client, &payer, recent_blockhash, &quote_mint, &owner_quote_account, 1_000_000).await;
+
+        // The vaults are token accounts of the market signer PDA
+        // 金库是由市场签名者 PDA 拥有的代币账户
+        let (base_vault, _) = Market::find_base_vault_address(&program_id, &market_account.pubkey());
+        let (quote_vault, _) = Market::find_quote_vault_address(&program_id, &market_account.pubkey());
+        let (market_signer, _) = Market::find_signer_address(&program_id, &market_account.pubkey());
+        for vault in [base_vault, quote_vault] {
+            let vault_data = banks_client.get_account(vault).await.unwrap().unwrap();
+            let vault_account = spl_token::state::Account::unpack(&vault_data.data).unwrap();
+            assert_eq!(vault_account.owner, market_signer);
+        }
+
+        // Lock a bid and an ask in the vaults
+        // 将一个买单和一个卖单锁定在金库中
+        let instructions = [
+            (bid_account, owner_quote_account, true),
+            (ask_account, owner_base_account, false),
+        ]
+        .map(|(order_account, owner_token_account, is_buy)| {
+            DexInstruction::place_limit_order(
+                &program_id,
+                &order_owner.pubkey(),
+                &market_account.pubkey(),
+                &order_account,
+                &owner_token_account,
+                &token_program,
+                is_buy,
+                1000,
+                500,
+                SelfTradeBehavior::DecrementTake,
+            )
+            .unwrap()
+        });
+        let mut transaction = Transaction::new_with_payer(&instructions, Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &order_owner], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+        assert_eq!(common::token_balance(&mut banks_client, &quote_vault).await, 501_500);
+        assert_eq!(common::token_balance(&mut banks_client, &base_vault).await, 500);
+
+        // Reducing the bid refunds the removed quantity
+        // 减少买单数量会退还被移除部分的资金
+        let reduce_order_ix = DexInstruction::reduce_order(
+            &program_id,
+            &order_owner.pubkey(),
+            &market_account.pubkey(),
+            &bid_account,
+            &owner_quote_account,
+            &token_program,
+            200,
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(&[reduce_order_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &order_owner], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+        assert_eq!(common::token_balance(&mut banks_client, &owner_quote_account).await, 799_400);
+        assert_eq!(common::token_balance(&mut banks_client, &quote_vault).await, 200_600);
+
+        // Cancelling the bid, and the ask by id, refunds the rest signed by the market signer
+        // 取消买单以及按 ID 取消卖单，由市场签名者签名退还剩余资金
+        let cancel_order_ix = DexInstruction::cancel_order(
+            &program_id,
+            &order_owner.pubkey(),
+            &market_account.pubkey(),
+            &bid_account,
+            &owner_quote_account,
+            &token_program,
+        )
+        .unwrap();
+        let cancel_orders_ix = DexInstruction::cancel_orders_by_ids(
+            &program_id,
+            &order_owner.pubkey(),
+            &market_account.pubkey(),
+            &owner_base_account,
+            &owner_quote_account,
+            &token_program,
+            &[2],
+            &[ask_account],
+        )
+        .unwrap();
+
+        // Create and sign transaction
+        // 创建并签名交易
+        let mut transaction = Transaction::new_with_payer(
+            &[cancel_order_ix, cancel_orders_ix],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &order_owner], recent_blockhash);
+
+        // Process transaction
+        // 处理交易
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // Every locked token is back with the owner
+        // 所有锁定的代币都已退还给所有者
+        assert_eq!(common::token_balance(&mut banks_client, &owner_quote_account).await, 1_000_000);
+        assert_eq!(common::token_balance(&mut banks_client, &owner_base_account).await, 1_000);
+        assert_eq!(common::token_balance(&mut banks_client, &quote_vault).await, 0);
+        assert_eq!(common::token_balance(&mut banks_client, &base_vault).await, 0);
+
+        // Both order accounts are closed and their rent is back with the owner
+        // 两个订单账户都已关闭，其租金已退还给所有者
+        for order_account in [bid_account, ask_account] {
+            assert!(banks_client.get_account(order_account).await.unwrap().is_none());
+        }
+        assert_eq!(banks_client.get_balance(order_owner.pubkey()).await.unwrap(), 2 * 1000000000);
+
+        // Verify market state (order counts should be decremented)
+        // 验证市场状态（订单数量应该减少）
+        let market_account_data = banks_client
+            .get_account(market_account.pubkey())
+            .await
+            .unwrap()
+            .unwrap();
+
+        let market = Market::unpack_from_slice(&market_account_data.data).unwrap();
+        assert_eq!({ market.num_bids }, 0);
+        assert_eq!({ market.num_asks }, 0);
+        assert_eq!({ market.bid_base_total }, 0);
+        assert_eq!({ market.ask_base_total }, 0);
+    }
+
+    #[tokio::test]
+    async fn test_cancelling_best_bid_marks_it_stale() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // Create order accounts for two bids, an ask and two later bids
+        // 为两个买单、一个卖单和两个后续买单创建订单账户
+        let market_account = Keypair::new();
+        let order_owner = Keypair::new();
+        let order_accounts = add_order_accounts(&mut program_test, &program_id, 5);
+        let token_program = spl_token::id();
+
+        // Start the test environment
+        // 启动测试环境
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        // Setup market and fund the owner's token accounts
+        // 设置市场并为所有者的代币账户注资
+        let (_, base_mint, quote_mint) = setup_market(
+            &program_id,
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &market_account,
+        ).await;
+        let owner_base_account =
+            common::create_token_account(&mut banks_client, &payer, recent_blockhash, &base_mint, &order_owner.pubkey())
+                .await;
+        let owner_quote_account =
+            common::create_token_account(&mut banks_client, &payer, recent_blockhash, &quote_mint, &order_owner.pubkey())
+                .await;
+        common::mint_to(&mut banks_client, &payer, recent_blockhash, &base_mint, &owner_base_account, 1_000).await;
+        common::mint_to(&mut banks_client, &payer, recent_blockhash, &quote_mint, &owner_quote_account, 1_000_000).await;
+
+        // Rest bids at 1000 and 990 and an ask at 1020
+        // 挂出价格为 1000 和 990 的买单以及价格为 1020 的卖单
+        let place_order_ix = |order_account: &Pubkey, is_buy: bool, price: u64| {
+            let owner_token_account = if is_buy { &owner_quote_account } else { &owner_base_account };
+            DexInstruction::place_limit_order(
+                &program_id,
+                &order_owner.pubkey(),
+                &market_account.pubkey(),
+                order_account,
+                owner_token_account,
+                &token_program,
+                is_buy,
+                price,
+                100,
+                SelfTradeBehavior::DecrementTake,
+            )
+            .unwrap()
+        };
+        let instructions = [
+            place_order_ix(&order_accounts[0], true, 1000),
+            place_order_ix(&order_accounts[1], true, 990),
+            place_order_ix(&order_accounts[2], false, 1020),
+        ];
+        let mut transaction = Transaction::new_with_payer(&instructions, Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &order_owner], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+        let market_data = banks_client.get_account(market_account.pubkey()).await.unwrap().unwrap();
+        let market = Market::unpack_from_slice(&market_data.data).unwrap();
+        assert_eq!((market.best_bid_price(), market.best_ask_price()), (Some(1000), Some(1020)));
+        assert_eq!(market.spread(), Some(20));
+
+        // Cancelling the best bid while the 990 bid rests leaves the best bid stale, so no
+        // reader sees the cancelled price as the best bid
+        // 在 990 买单仍挂单时取消最优买单会使最优买价过期，因此读取方不会把已取消的价格视为最优买价
+        let cancel_order_ix = DexInstruction::cancel_order(
+            &program_id,
+            &order_owner.pubkey(),
+            &market_account.pubkey(),
+            &order_accounts[0],
+            &owner_quote_account,
+            &token_program,
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(&[cancel_order_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &order_owner], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+        let market_data = banks_client.get_account(market_account.pubkey()).await.unwrap().unwrap();
+        let market = Market::unpack_from_slice(&market_data.data).unwrap();
+        assert!(market.has_flag(MARKET_FLAG_BEST_BID_STALE));
+        assert!(!market.has_flag(MARKET_FLAG_BEST_ASK_STALE));
+        assert_eq!((market.best_bid_price(), market.best_ask_price()), (None, Some(1020)));
+        assert_eq!(market.spread(), None);
+        assert_eq!({ market.bid_base_total }, 100);
+
+        // Committing the open orders root recomputes the best bid from the resting orders
+        // 提交未成交订单根会根据挂单重新计算最优买价
+        let mut resting_orders = vec![order_accounts[1], order_accounts[2]];
+        resting_orders.sort();
+        let commit_ix = DexInstruction::commit_open_orders_root(
+            &program_id,
+            &market_account.pubkey(),
+            &resting_orders,
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(&[commit_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+        let market_data = banks_client.get_account(market_account.pubkey()).await.unwrap().unwrap();
+        let market = Market::unpack_from_slice(&market_data.data).unwrap();
+        assert!(!market.has_flag(MARKET_FLAG_BEST_BID_STALE));
+        assert_eq!((market.best_bid_price(), market.best_ask_price()), (Some(990), Some(1020)));
+        assert_eq!(market.spread(), Some(30));
+
+        // A bid at or above a stale best bid is the best bid again
+        // 价格不低于过期最优买价的买单会重新成为最优买单
+        let cancel_order_ix = DexInstruction::cancel_order(
+            &program_id,
+            &order_owner.pubkey(),
+            &market_account.pubkey(),
+            &order_accounts[1],
+            &owner_quote_account,
+            &token_program,
+        )
+        .unwrap();
+        let instructions = [
+            place_order_ix(&order_accounts[3], true, 980),
+            cancel_order_ix,
+            place_order_ix(&order_accounts[4], true, 990),
+        ];
+        let mut transaction = Transaction::new_with_payer(&instructions, Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &order_owner], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+        let market_data = banks_client.get_account(market_account.pubkey()).await.unwrap().unwrap();
+        let market = Market::unpack_from_slice(&market_data.data).unwrap();
+        assert!(!market.has_flag(MARKET_FLAG_BEST_BID_STALE));
+        assert_eq!(market.best_bid_price(), Some(990));
+        assert_eq!((market.num_bids, market.num_asks), (2, 1));
+    }
+
+    #[tokio::test]
+    async fn test_escrow_rejects_spoofed_vaults_and_signer() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+        let market_account = Keypair::new();
+        let order_owner = Keypair::new();
+        let order_account = add_order_accounts(&mut program_test, &program_id, 1)[0];
+
+        // Start the test environment
+        // 启动测试环境
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let (_, base_mint, quote_mint) = setup_market(
             &program_id,
             &mut banks_client,
             &payer,
             &recent_blockhash,
+            &market_account,
         ).await;
+        let owner_quote_account =
+            common::create_token_account(&mut banks_client, &payer, recent_blockhash, &quote_mint, &order_owner.pubkey())
+                .await;
+        let attacker_quote_account =
+            common::create_token_account(&mut banks_client, &payer, recent_blockhash, &quote_mint, &payer.pubkey())
+                .await;
+        common::mint_to(&mut banks_client, &payer, recent_blockhash, &quote_mint, &owner_quote_account, 1_000_000).await;
+
+        // The vaults can only be created once
+        // 金库只能创建一次
+        let init_vaults_ix = DexInstruction::initialize_market_vaults(
+            &program_id,
+            &payer.pubkey(),
+            &market_account.pubkey(),
+            &base_mint,
+            &quote_mint,
+            &spl_token::id(),
+        )
+        .unwrap();
+
+        // Funds cannot be locked in an account other than the market's vault
+        // 资金不能锁定在市场金库以外的账户中
+        let place_order_ix = DexInstruction::place_limit_order(
+            &program_id,
+            &order_owner.pubkey(),
+            &market_account.pubkey(),
+            &order_account,
+            &owner_quote_account,
+            &spl_token::id(),
+            true,
+            1000,
+            500,
+            SelfTradeBehavior::DecrementTake,
+        )
+        .unwrap();
+        let mut spoofed_vault_ix = place_order_ix.clone();
+        spoofed_vault_ix.accounts[4].pubkey = attacker_quote_account;
+
+        for (ix, signers) in [(init_vaults_ix, vec![&payer]), (spoofed_vault_ix, vec![&payer, &order_owner])] {
+            let mut transaction = Transaction::new_with_payer(&[ix], Some(&payer.pubkey()));
+            transaction.sign(&signers, recent_blockhash);
+            let err = banks_client
+                .process_transaction(transaction)
+                .await
+                .unwrap_err()
+                .unwrap();
+            assert_eq!(
+                err,
+                TransactionError::InstructionError(
+                    0,
+                    InstructionError::Custom(DexError::InvalidAccountData as u32),
+                )
+            );
+        }
+
+        // Place a real order, then try to refund it through spoofed escrow accounts
+        // 下一个真实订单，然后尝试通过伪造的托管账户退款
+        let mut transaction = Transaction::new_with_payer(&[place_order_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &order_owner], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        let cancel_order_ix = DexInstruction::cancel_order(
+            &program_id,
+            &order_owner.pubkey(),
+            &market_account.pubkey(),
+            &order_account,
+            &owner_quote_account,
+            &spl_token::id(),
+        )
+        .unwrap();
+        let mut swapped_vaults_ix = cancel_order_ix.clone();
+        swapped_vaults_ix.accounts.swap(4, 5);
+        let mut spoofed_signer_ix = cancel_order_ix;
+        spoofed_signer_ix.accounts[6].pubkey = payer.pubkey();
+
+        for ix in [swapped_vaults_ix, spoofed_signer_ix] {
+            let mut transaction = Transaction::new_with_payer(&[ix], Some(&payer.pubkey()));
+            transaction.sign(&[&payer, &order_owner], recent_blockhash);
+            let err = banks_client
+                .process_transaction(transaction)
+                .await
+                .unwrap_err()
+                .unwrap();
+            assert_eq!(
+                err,
+                TransactionError::InstructionError(
+                    0,
+                    InstructionError::Custom(DexError::InvalidAccountData as u32),
+                )
+            );
+        }
+
+        // The locked funds stay in the vault
+        // 锁定的资金仍留在金库中
+        let (quote_vault, _) = Market::find_quote_vault_address(&program_id, &market_account.pubkey());
+        assert_eq!(common::token_balance(&mut banks_client, &quote_vault).await, 501_500);
+        assert_eq!(common::token_balance(&mut banks_client, &attacker_quote_account).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_orders_by_ids_rejects_mismatched_id() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
 
         // Create order accounts
         // 创建订单账户
+        let market_account = Keypair::new();
         let order_owner = Keypair::new();
         let order_account = Keypair::new();
-        let owner_token_account = Pubkey::new_unique();
-        let token_program = Pubkey::new_unique();
+        let owner_base_account = Pubkey::new_unique();
+        let owner_quote_account = Pubkey::new_unique();
+        let token_program = spl_token::id();
 
-        // Add order account to the test environment with pre-initialized data
-        // 将带有预初始化数据的订单账户添加到测试环境
+        // Add an order with id 1 to the test environment
+        // 将 ID 为 1 的订单添加到测试环境
         let order = Order {
             is_initialized: true,
             order_id: 1,
             owner: order_owner.pubkey(),
             market: market_account.pubkey(),
-            is_buy: true,
+            is_buy: false,
             limit_price: 1000,
             original_quantity: 500,
             remaining_quantity: 500,
             creation_timestamp: 0,
+            linked_order: Pubkey::default(),
+            display_quantity: 0,
+            visible_quantity: 500,
+            referrer: Pubkey::default(),
+            memo: [0; 32],
+            version: ACCOUNT_VERSION,
+            reserved: [0; ORDER_RESERVED_LEN],
         };
 
         let mut order_data = vec![0; Order::LEN];
@@ -218,39 +718,2477 @@ mod order_tests {
             },
         );
 
-        // Create cancel order instruction
-        // 创建取消订单指令
-        let cancel_order_ix = DexInstruction::cancel_order(
+        // Start the test environment
+        // 启动测试环境
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        // Setup market
+        // 设置市场
+        setup_market(
+            &program_id,
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &market_account,
+        ).await;
+
+        // Ask to cancel order id 2 using the account of order id 1
+        // 使用订单 1 的账户请求取消订单 2
+        let cancel_orders_ix = DexInstruction::cancel_orders_by_ids(
             &program_id,
             &order_owner.pubkey(),
             &market_account.pubkey(),
-            &order_account.pubkey(),
-            &owner_token_account,
+            &owner_base_account,
+            &owner_quote_account,
             &token_program,
+            &[2],
+            &[order_account.pubkey()],
         )
         .unwrap();
 
         // Create and sign transaction
         // 创建并签名交易
         let mut transaction = Transaction::new_with_payer(
-            &[cancel_order_ix],
+            &[cancel_orders_ix],
             Some(&payer.pubkey()),
         );
         transaction.sign(&[&payer, &order_owner], recent_blockhash);
 
-        // Process transaction
-        // 处理交易
-        banks_client.process_transaction(transaction).await.unwrap();
-
-        // Verify market state (bid count should be decremented)
-        // 验证市场状态（买单数量应该减少）
-        let market_account_data = banks_client
-            .get_account(market_account.pubkey())
+        // The mismatch must be rejected
+        // 不匹配的请求必须被拒绝
+        let err = banks_client
+            .process_transaction(transaction)
             .await
-            .unwrap()
+            .unwrap_err()
             .unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(DexError::OrderNotFound as u32),
+            )
+        );
+    }
 
-        let market = Market::unpack_from_slice(&market_account_data.data).unwrap();
-        assert_eq!(market.num_bids, 0);
+    #[tokio::test]
+    async fn test_reduce_order_rejects_increase() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // Create order accounts
+        // 创建订单账户
+        let market_account = Keypair::new();
+        let order_owner = Keypair::new();
+        let order_account = Keypair::new();
+        let owner_token_account = Pubkey::new_unique();
+        let token_program = spl_token::id();
+
+        // Add a resting order to the test environment
+        // 将挂单添加到测试环境
+        let order = Order {
+            is_initialized: true,
+            order_id: 1,
+            owner: order_owner.pubkey(),
+            market: market_account.pubkey(),
+            is_buy: true,
+            limit_price: 1000,
+            original_quantity: 500,
+            remaining_quantity: 500,
+            creation_timestamp: 0,
+            linked_order: Pubkey::default(),
+            display_quantity: 0,
+            visible_quantity: 500,
+            referrer: Pubkey::default(),
+            memo: [0; 32],
+            version: ACCOUNT_VERSION,
+            reserved: [0; ORDER_RESERVED_LEN],
+        };
+
+        let mut order_data = vec![0; Order::LEN];
+        order.pack_into_slice(&mut order_data);
+
+        program_test.add_account(
+            order_account.pubkey(),
+            solana_sdk::account::Account {
+                lamports: 1000000000,
+                data: order_data,
+                owner: program_id,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+
+        // Start the test environment
+        // 启动测试环境
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        // Setup market
+        // 设置市场
+        setup_market(
+            &program_id,
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &market_account,
+        ).await;
+
+        // Amending up is not a reduction and must be rejected
+        // 增加数量不属于减少，必须被拒绝
+        let reduce_order_ix = DexInstruction::reduce_order(
+            &program_id,
+            &order_owner.pubkey(),
+            &market_account.pubkey(),
+            &order_account.pubkey(),
+            &owner_token_account,
+            &token_program,
+            600,
+        )
+        .unwrap();
+
+        let mut transaction = Transaction::new_with_payer(
+            &[reduce_order_ix],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &order_owner], recent_blockhash);
+
+        let err = banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(DexError::InvalidOrderSize as u32),
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_admin_cancel_order_checks_authority_and_refund_account() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // Add a resting order and two refund accounts that do not fit it
+        // 添加一个挂单以及两个与其不匹配的退款账户
+        let market_account = Keypair::new();
+        let order_owner = Keypair::new();
+        let order_account = Pubkey::new_unique();
+        let order = Order {
+            is_initialized: true,
+            order_id: 1,
+            owner: order_owner.pubkey(),
+            market: market_account.pubkey(),
+            is_buy: true,
+            limit_price: 1000,
+            original_quantity: 500,
+            remaining_quantity: 500,
+            creation_timestamp: 0,
+            linked_order: Pubkey::default(),
+            display_quantity: 0,
+            visible_quantity: 500,
+            referrer: Pubkey::default(),
+            memo: [0; 32],
+            version: ACCOUNT_VERSION,
+            reserved: [0; ORDER_RESERVED_LEN],
+        };
+        let mut order_data = vec![0; Order::LEN];
+        order.pack_into_slice(&mut order_data);
+        program_test.add_account(
+            order_account,
+            solana_sdk::account::Account {
+                lamports: 1000000000,
+                data: order_data,
+                owner: program_id,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+
+        let foreign_token_account = Pubkey::new_unique();
+        let wrong_mint_token_account = Pubkey::new_unique();
+        for (address, owner) in [
+            (foreign_token_account, Pubkey::new_unique()),
+            (wrong_mint_token_account, order_owner.pubkey()),
+        ] {
+            let token_account = spl_token::state::Account {
+                mint: Pubkey::new_unique(),
+                owner,
+                state: spl_token::state::AccountState::Initialized,
+                ..spl_token::state::Account::default()
+            };
+            let mut data = vec![0; spl_token::state::Account::LEN];
+            token_account.pack_into_slice(&mut data);
+            program_test.add_account(
+                address,
+                solana_sdk::account::Account {
+                    lamports: 1000000000,
+                    data,
+                    owner: spl_token::id(),
+                    executable: false,
+                    rent_epoch: 0,
+                },
+            );
+        }
+
+        // Start the test environment
+        // 启动测试环境
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        // Setup market
+        // 设置市场
+        let (market_authority, _, _) = setup_market(
+            &program_id,
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &market_account,
+        ).await;
+
+        // Only the market authority can force-cancel, and never into someone else's account
+        // 只有市场权限账户可以强制撤单，且退款不能进入他人账户
+        let intruder = Keypair::new();
+        for (authority, refund_account, expected) in [
+            (&intruder, foreign_token_account, DexError::AccountNotAuthorized),
+            (&market_authority, foreign_token_account, DexError::InvalidTokenAccount),
+            (&market_authority, wrong_mint_token_account, DexError::InvalidTokenAccount),
+        ] {
+            let admin_cancel_ix = DexInstruction::admin_cancel_order(
+                &program_id,
+                &authority.pubkey(),
+                &market_account.pubkey(),
+                &order_account,
+                &order_owner.pubkey(),
+                &refund_account,
+                &spl_token::id(),
+            )
+            .unwrap();
+
+            let mut transaction = Transaction::new_with_payer(
+                &[admin_cancel_ix],
+                Some(&payer.pubkey()),
+            );
+            transaction.sign(&[&payer, authority], recent_blockhash);
+
+            let err = banks_client
+                .process_transaction(transaction)
+                .await
+                .unwrap_err()
+                .unwrap();
+            assert_eq!(
+                err,
+                TransactionError::InstructionError(
+                    0,
+                    InstructionError::Custom(expected as u32),
+                )
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_link_orders_and_cancel_checks_link() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // Create order accounts
+        // 创建订单账户
+        let market_account = Keypair::new();
+        let order_owner = Keypair::new();
+        let take_profit_account = Keypair::new();
+        let stop_account = Keypair::new();
+        let owner_token_account = Pubkey::new_unique();
+        let token_program = spl_token::id();
+
+        // Add two resting sell orders to the test environment
+        // 将两个卖出挂单添加到测试环境
+        for (order_id, order_account, limit_price) in [
+            (1, &take_profit_account, 1200),
+            (2, &stop_account, 900),
+        ] {
+            let order = Order {
+                is_initialized: true,
+                order_id,
+                owner: order_owner.pubkey(),
+                market: market_account.pubkey(),
+                is_buy: false,
+                limit_price,
+                original_quantity: 500,
+                remaining_quantity: 500,
+                creation_timestamp: 0,
+                linked_order: Pubkey::default(),
+                display_quantity: 0,
+                visible_quantity: 500,
+                referrer: Pubkey::default(),
+                memo: [0; 32],
+                version: ACCOUNT_VERSION,
+                reserved: [0; ORDER_RESERVED_LEN],
+            };
+
+            let mut order_data = vec![0; Order::LEN];
+            order.pack_into_slice(&mut order_data);
+
+            program_test.add_account(
+                order_account.pubkey(),
+                solana_sdk::account::Account {
+                    lamports: 1000000000,
+                    data: order_data,
+                    owner: program_id,
+                    executable: false,
+                    rent_epoch: 0,
+                },
+            );
+        }
+
+        // Start the test environment
+        // 启动测试环境
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        // Setup market
+        // 设置市场
+        setup_market(
+            &program_id,
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &market_account,
+        ).await;
+
+        // Link the take-profit and the stop order
+        // 关联止盈单和止损单
+        let link_orders_ix = DexInstruction::link_orders(
+            &program_id,
+            &order_owner.pubkey(),
+            &market_account.pubkey(),
+            &take_profit_account.pubkey(),
+            &stop_account.pubkey(),
+        )
+        .unwrap();
+
+        let mut transaction = Transaction::new_with_payer(
+            &[link_orders_ix],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &order_owner], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // Verify both orders point at each other
+        // 验证两个订单互相关联
+        let take_profit_data = banks_client
+            .get_account(take_profit_account.pubkey())
+            .await
+            .unwrap()
+            .unwrap();
+        let take_profit = Order::unpack_from_slice(&take_profit_data.data).unwrap();
+        assert_eq!(take_profit.linked_order, stop_account.pubkey());
+
+        let stop_data = banks_client
+            .get_account(stop_account.pubkey())
+            .await
+            .unwrap()
+            .unwrap();
+        let stop = Order::unpack_from_slice(&stop_data.data).unwrap();
+        assert_eq!(stop.linked_order, take_profit_account.pubkey());
+
+        // Cancelling with the wrong linked order account must be rejected
+        // 使用错误的关联订单账户取消必须被拒绝
+        let cancel_order_ix = DexInstruction::cancel_linked_order(
+            &program_id,
+            &order_owner.pubkey(),
+            &market_account.pubkey(),
+            &take_profit_account.pubkey(),
+            &owner_token_account,
+            &token_program,
+            &Pubkey::new_unique(),
+            &owner_token_account,
+        )
+        .unwrap();
+
+        let mut transaction = Transaction::new_with_payer(
+            &[cancel_order_ix],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &order_owner], recent_blockhash);
+
+        let err = banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(DexError::InvalidOrderLink as u32),
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_filling_linked_order_cancels_its_partner() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+        let market_account = Keypair::new();
+        let buyer = Keypair::new();
+        let seller = Keypair::new();
+        let order_accounts = add_order_accounts(&mut program_test, &program_id, 5);
+        let [bid_account, take_profit_account, stop_account, first_leg_account, second_leg_account] = order_accounts[..] else {
+            unreachable!()
+        };
+        let token_program = spl_token::id();
+        let mut context = program_test.start_with_context().await;
+        let payer = context.payer.insecure_clone();
+        let recent_blockhash = context.last_blockhash;
+
+        // Setup market, its fee vault and the traders' token accounts
+        // 设置市场、其手续费金库以及交易者的代币账户
+        let (market_authority, base_mint, quote_mint) = setup_market(
+            &program_id,
+            &mut context.banks_client,
+            &payer,
+            &recent_blockhash,
+            &market_account,
+        ).await;
+        let mut token_accounts = Vec::new();
+        for (mint, owner) in [(base_mint, &buyer), (quote_mint, &buyer), (base_mint, &seller), (quote_mint, &seller)] {
+            token_accounts.push(
+                common::create_token_account(&mut context.banks_client, &payer, recent_blockhash, &mint, &owner.pubkey()).await,
+            );
+        }
+        let [buyer_base, buyer_quote, seller_base, seller_quote] = token_accounts[..] else {
+            unreachable!()
+        };
+        common::mint_to(&mut context.banks_client, &payer, recent_blockhash, &quote_mint, &buyer_quote, 1_000_000).await;
+        common::mint_to(&mut context.banks_client, &payer, recent_blockhash, &base_mint, &seller_base, 1_000).await;
+        let mut instructions = vec![DexInstruction::initialize_fee_vault(
+            &program_id,
+            &payer.pubkey(),
+            &market_account.pubkey(),
+            &quote_mint,
+            &token_program,
+        )
+        .unwrap()];
+
+        // The seller links a take-profit ask at 1200 to a stop ask at 1000, and a second pair
+        // of asks at 1100 and 1300; the buyer bids 300 at 1010
+        // 卖方将 1200 的止盈卖单与 1000 的止损卖单关联，并关联另一对 1100 和 1300 的卖单；
+        // 买方以 1010 出价 300
+        for (order_account, limit_price, quantity) in [
+            (take_profit_account, 1200, 300),
+            (stop_account, 1000, 300),
+            (first_leg_account, 1100, 200),
+            (second_leg_account, 1300, 200),
+        ] {
+            instructions.push(
+                DexInstruction::place_limit_order(
+                    &program_id,
+                    &seller.pubkey(),
+                    &market_account.pubkey(),
+                    &order_account,
+                    &seller_base,
+                    &token_program,
+                    false,
+                    limit_price,
+                    quantity,
+                    SelfTradeBehavior::DecrementTake,
+                )
+                .unwrap(),
+            );
+        }
+        for (first, second) in [(take_profit_account, stop_account), (first_leg_account, second_leg_account)] {
+            instructions.push(
+                DexInstruction::link_orders(&program_id, &seller.pubkey(), &market_account.pubkey(), &first, &second).unwrap(),
+            );
+        }
+        instructions.push(
+            DexInstruction::place_limit_order(
+                &program_id,
+                &buyer.pubkey(),
+                &market_account.pubkey(),
+                &bid_account,
+                &buyer_quote,
+                &token_program,
+                true,
+                1010,
+                300,
+                SelfTradeBehavior::DecrementTake,
+            )
+            .unwrap(),
+        );
+        let mut transaction = Transaction::new_with_payer(&instructions, Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &buyer, &seller], recent_blockhash);
+        context.banks_client.process_transaction(transaction).await.unwrap();
+        assert_eq!(common::token_balance(&mut context.banks_client, &seller_base).await, 0);
+
+        let settle_ix = DexInstruction::settle_funds(
+            &program_id,
+            &market_authority.pubkey(),
+            &market_account.pubkey(),
+            &bid_account,
+            &stop_account,
+            &buyer_base,
+            &buyer_quote,
+            &seller_base,
+            &seller_quote,
+            &buyer.pubkey(),
+            &seller.pubkey(),
+            &token_program,
+        )
+        .unwrap();
+
+        // Filling the stop needs the take-profit it cancels
+        // 止损单成交时需要传入其要取消的止盈单
+        let mut transaction = Transaction::new_with_payer(std::slice::from_ref(&settle_ix), Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &market_authority], recent_blockhash);
+        let err = context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(err, TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys));
+
+        // The stop fills and the take-profit is cancelled, its base tokens and rent returned to
+        // the seller
+        // 止损单成交，止盈单被取消，其基础代币和租金退还给卖方
+        let seller_lamports = context.banks_client.get_balance(seller.pubkey()).await.unwrap();
+        let take_profit_rent = context.banks_client.get_balance(take_profit_account).await.unwrap();
+        let stop_rent = context.banks_client.get_balance(stop_account).await.unwrap();
+        let settle_ix = DexInstruction::with_linked_orders(settle_ix, &[take_profit_account]);
+        let mut transaction = Transaction::new_with_payer(&[settle_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &market_authority], recent_blockhash);
+        context.banks_client.process_transaction(transaction).await.unwrap();
+        assert_eq!(common::token_balance(&mut context.banks_client, &buyer_base).await, 300);
+        assert_eq!(common::token_balance(&mut context.banks_client, &seller_base).await, 300);
+        assert!(context.banks_client.get_account(stop_account).await.unwrap().is_none());
+        assert!(context.banks_client.get_account(take_profit_account).await.unwrap().is_none());
+        assert_eq!(
+            context.banks_client.get_balance(seller.pubkey()).await.unwrap(),
+            seller_lamports + take_profit_rent + stop_rent
+        );
+        let market_data = context.banks_client.get_account(market_account.pubkey()).await.unwrap().unwrap();
+        let market = Market::unpack_from_slice(&market_data.data).unwrap();
+        assert_eq!((market.num_bids, market.num_asks), (0, 2));
+        assert_eq!({ market.ask_base_total }, 400);
+
+        // A leg whose linked order was closed without cancelling it can still be cancelled
+        // 关联订单已关闭但未被取消的订单仍然可以取消
+        context.set_account(&second_leg_account, &solana_sdk::account::Account::default().into());
+        let cancel_order_ix = DexInstruction::cancel_linked_order(
+            &program_id,
+            &seller.pubkey(),
+            &market_account.pubkey(),
+            &first_leg_account,
+            &seller_base,
+            &token_program,
+            &second_leg_account,
+            &seller_base,
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(&[cancel_order_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &seller], recent_blockhash);
+        context.banks_client.process_transaction(transaction).await.unwrap();
+        assert_eq!(common::token_balance(&mut context.banks_client, &seller_base).await, 500);
+        assert!(context.banks_client.get_account(first_leg_account).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_place_iceberg_order_rejects_invalid_display() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // Create order accounts
+        // 创建订单账户
+        let market_account = Keypair::new();
+        let order_owner = Keypair::new();
+        let order_account = Keypair::new();
+        let owner_token_account = Pubkey::new_unique();
+        let token_program = spl_token::id();
+
+        // Start the test environment
+        // 启动测试环境
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        // Setup market
+        // 设置市场
+        setup_market(
+            &program_id,
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &market_account,
+        ).await;
+
+        // A tranche as large as the order would hide nothing, one below the minimum size is too small
+        // 与订单同样大的分段没有隐藏任何数量，低于最小下单量的分段又太小
+        for display_quantity in [500, 50] {
+            let place_order_ix = DexInstruction::place_iceberg_order(
+                &program_id,
+                &order_owner.pubkey(),
+                &market_account.pubkey(),
+                &order_account.pubkey(),
+                &owner_token_account,
+                &token_program,
+                false,
+                1000,
+                500,
+                display_quantity,
+                SelfTradeBehavior::DecrementTake,
+            )
+            .unwrap();
+
+            let mut transaction = Transaction::new_with_payer(
+                &[place_order_ix],
+                Some(&payer.pubkey()),
+            );
+            transaction.sign(&[&payer, &order_owner], recent_blockhash);
+
+            let err = banks_client
+                .process_transaction(transaction)
+                .await
+                .unwrap_err()
+                .unwrap();
+            assert_eq!(
+                err,
+                TransactionError::InstructionError(
+                    0,
+                    InstructionError::Custom(DexError::InvalidOrderSize as u32),
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn test_iceberg_fill_reveals_next_tranche() {
+        // An iceberg sell of 500 showing 200 at a time
+        // 总量 500、每次显示 200 的冰山卖单
+        let mut order = Order {
+            is_initialized: true,
+            order_id: 1,
+            owner: Pubkey::new_unique(),
+            market: Pubkey::new_unique(),
+            is_buy: false,
+            limit_price: 1000,
+            original_quantity: 500,
+            remaining_quantity: 500,
+            creation_timestamp: 10,
+            linked_order: Pubkey::default(),
+            display_quantity: 200,
+            visible_quantity: 200,
+            referrer: Pubkey::default(),
+            memo: [0; 32],
+            version: ACCOUNT_VERSION,
+            reserved: [0; ORDER_RESERVED_LEN],
+        };
+        assert!(order.is_iceberg());
+        assert_eq!(order.hidden_quantity(), 300);
+
+        // A partial fill keeps the order's place in the queue
+        // 部分成交保留订单的排队位置
+        order.fill(50, 20).unwrap();
+        assert_eq!({ order.visible_quantity }, 150);
+        assert_eq!({ order.creation_timestamp }, 10);
+
+        // Fills beyond the displayed tranche are rejected
+        // 超过显示分段的成交被拒绝
+        assert!(order.fill(151, 20).is_err());
+
+        // Exhausting the tranche reveals the next one at the back of the queue
+        // 分段成交完后显示下一段，并排到队尾
+        order.fill(150, 30).unwrap();
+        assert_eq!({ order.remaining_quantity }, 300);
+        assert_eq!({ order.visible_quantity }, 200);
+        assert_eq!({ order.creation_timestamp }, 30);
+
+        // The last tranche is capped at the remaining quantity
+        // 最后一段以剩余数量为上限
+        order.fill(200, 40).unwrap();
+        assert_eq!({ order.visible_quantity }, 100);
+        assert_eq!(order.hidden_quantity(), 0);
+    }
+
+    #[test]
+    fn test_order_filter_offsets() {
+        // Clients find orders by comparing bytes at these offsets of the packed account
+        // 客户端通过比较打包账户中这些偏移处的字节来查找订单
+        let order = Order {
+            is_initialized: true,
+            order_id: 7,
+            owner: Pubkey::new_unique(),
+            market: Pubkey::new_unique(),
+            is_buy: true,
+            limit_price: 1000,
+            original_quantity: 500,
+            remaining_quantity: 500,
+            creation_timestamp: 10,
+            linked_order: Pubkey::default(),
+            display_quantity: 0,
+            visible_quantity: 500,
+            referrer: Pubkey::default(),
+            memo: [0; 32],
+            version: ACCOUNT_VERSION,
+            reserved: [0; ORDER_RESERVED_LEN],
+        };
+        let mut data = vec![0u8; Order::LEN];
+        order.pack_into_slice(&mut data);
+
+        assert_eq!(&data[Order::OWNER_OFFSET..Order::OWNER_OFFSET + 32], order.owner.as_ref());
+        assert_eq!(&data[Order::MARKET_OFFSET..Order::MARKET_OFFSET + 32], order.market.as_ref());
+        assert_eq!(data[Order::IS_BUY_OFFSET], 1);
+    }
+
+    #[tokio::test]
+    async fn test_referral_share_and_referrer_validation() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // Create order accounts
+        // 创建订单账户
+        let market_account = Keypair::new();
+        let order_owner = Keypair::new();
+        let order_account = Keypair::new();
+        let owner_token_account = Pubkey::new_unique();
+        let token_program = spl_token::id();
+
+        // Add a referrer token account of an unrelated mint
+        // 添加一个其他代币的推荐人代币账户
+        let referrer_token_account = Pubkey::new_unique();
+        let referrer_account = spl_token::state::Account {
+            mint: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            state: spl_token::state::AccountState::Initialized,
+            ..spl_token::state::Account::default()
+        };
+        let mut referrer_data = vec![0; spl_token::state::Account::LEN];
+        referrer_account.pack_into_slice(&mut referrer_data);
+        program_test.add_account(
+            referrer_token_account,
+            solana_sdk::account::Account {
+                lamports: 1000000000,
+                data: referrer_data,
+                owner: spl_token::id(),
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+
+        // Start the test environment
+        // 启动测试环境
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        // Setup market
+        // 设置市场
+        let (market_authority, _, _) = setup_market(
+            &program_id,
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &market_account,
+        ).await;
+
+        // The authority gives referrers a fifth of the fee
+        // 市场管理者将五分之一的手续费分给推荐人
+        let set_share_ix = DexInstruction::set_referral_share(
+            &program_id,
+            &market_authority.pubkey(),
+            &market_account.pubkey(),
+            2000,
+        )
+        .unwrap();
+
+        let mut transaction = Transaction::new_with_payer(&[set_share_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &market_authority], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        let market_account_data = banks_client
+            .get_account(market_account.pubkey())
+            .await
+            .unwrap()
+            .unwrap();
+        let market = Market::unpack_from_slice(&market_account_data.data).unwrap();
+        assert_eq!({ market.referral_share_bps }, 2000);
+        assert_eq!(market.calculate_referral_fee(250).unwrap(), 50);
+
+        // A referrer account that does not hold the quote mint is rejected
+        // 不持有报价代币的推荐人账户被拒绝
+        let place_order_ix = DexInstruction::place_limit_order_with_referrer(
+            &program_id,
+            &order_owner.pubkey(),
+            &market_account.pubkey(),
+            &order_account.pubkey(),
+            &owner_token_account,
+            &token_program,
+            true,
+            1000,
+            500,
+            SelfTradeBehavior::DecrementTake,
+            &referrer_token_account,
+        )
+        .unwrap();
+
+        let mut transaction = Transaction::new_with_payer(
+            &[place_order_ix],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &order_owner], recent_blockhash);
+
+        let err = banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(DexError::InvalidTokenAccount as u32),
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_min_quote_notional_rejects_dust_orders() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+        let market_account = Keypair::new();
+        let owner = Keypair::new();
+        let order_accounts = add_order_accounts(&mut program_test, &program_id, 2);
+        let token_program = spl_token::id();
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        // Setup market and fund the owner
+        // 设置市场并为所有者注资
+        let (market_authority, _, quote_mint) = setup_market(
+            &program_id,
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &market_account,
+        ).await;
+        let owner_quote =
+            common::create_token_account(&mut banks_client, &payer, recent_blockhash, &quote_mint, &owner.pubkey()).await;
+        common::mint_to(&mut banks_client, &payer, recent_blockhash, &quote_mint, &owner_quote, 2_000_000).await;
+
+        // Only the market authority sets the minimum notional
+        // 只有市场管理者可以设置最小名义金额
+        for (signer, expected) in [(&owner, Some(DexError::AccountNotAuthorized)), (&market_authority, None)] {
+            let set_min_ix = DexInstruction::set_min_quote_notional(
+                &program_id,
+                &signer.pubkey(),
+                &market_account.pubkey(),
+                1_000_000,
+            )
+            .unwrap();
+            let mut transaction = Transaction::new_with_payer(&[set_min_ix], Some(&payer.pubkey()));
+            transaction.sign(&[&payer, signer], recent_blockhash);
+            let result = banks_client.process_transaction(transaction).await;
+            match expected {
+                Some(error) => assert_eq!(
+                    result.unwrap_err().unwrap(),
+                    TransactionError::InstructionError(0, InstructionError::Custom(error as u32))
+                ),
+                None => result.unwrap(),
+            }
+        }
+        let market_data = banks_client.get_account(market_account.pubkey()).await.unwrap().unwrap();
+        assert_eq!({ Market::unpack_from_slice(&market_data.data).unwrap().min_quote_notional }, 1_000_000);
+
+        // A bid of 100 at 1000 is above the minimum size but below the minimum notional,
+        // while 1000 at 1000 meets it
+        // 以 1000 出价 100 满足最小数量但低于最小名义金额，以 1000 出价 1000 则满足要求
+        for (order_account, quantity, expected) in [
+            (order_accounts[0], 100, Some(DexError::InvalidOrderSize)),
+            (order_accounts[1], 1000, None),
+        ] {
+            let place_order_ix = DexInstruction::place_limit_order(
+                &program_id,
+                &owner.pubkey(),
+                &market_account.pubkey(),
+                &order_account,
+                &owner_quote,
+                &token_program,
+                true,
+                1000,
+                quantity,
+                SelfTradeBehavior::DecrementTake,
+            )
+            .unwrap();
+            let mut transaction = Transaction::new_with_payer(&[place_order_ix], Some(&payer.pubkey()));
+            transaction.sign(&[&payer, &owner], recent_blockhash);
+            let result = banks_client.process_transaction(transaction).await;
+            match expected {
+                Some(error) => assert_eq!(
+                    result.unwrap_err().unwrap(),
+                    TransactionError::InstructionError(0, InstructionError::Custom(error as u32))
+                ),
+                None => result.unwrap(),
+            }
+        }
+        assert_eq!(common::token_balance(&mut banks_client, &owner_quote).await, 2_000_000 - 1000 * 1003);
+    }
+
+    #[tokio::test]
+    async fn test_place_order_with_memo() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+        let market_account = Keypair::new();
+        let owner = Keypair::new();
+        let order_accounts = add_order_accounts(&mut program_test, &program_id, 3);
+        let token_program = spl_token::id();
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        // Setup market and fund the owner
+        // 设置市场并为所有者注资
+        let (_, _, quote_mint) = setup_market(
+            &program_id,
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &market_account,
+        ).await;
+        let owner_quote =
+            common::create_token_account(&mut banks_client, &payer, recent_blockhash, &quote_mint, &owner.pubkey()).await;
+        common::mint_to(&mut banks_client, &payer, recent_blockhash, &quote_mint, &owner_quote, 2_000_000).await;
+
+        let place_order_ix = |order_account: &Pubkey, memo: &str| {
+            let instruction = DexInstruction::place_limit_order(
+                &program_id,
+                &owner.pubkey(),
+                &market_account.pubkey(),
+                order_account,
+                &owner_quote,
+                &token_program,
+                true,
+                1000,
+                500,
+                SelfTradeBehavior::DecrementTake,
+            )
+            .unwrap();
+            DexInstruction::with_memo(instruction, memo).unwrap()
+        };
+
+        // A memo over the length limit, or sent to a program other than SPL Memo, is rejected
+        // 超过长度限制或发送给 SPL Memo 以外程序的备注会被拒绝
+        let mut wrong_program_ix = place_order_ix(&order_accounts[1], "desk-7/ref-42");
+        wrong_program_ix.accounts.last_mut().unwrap().pubkey = Pubkey::new_unique();
+        for (instruction, expected) in [
+            (
+                place_order_ix(&order_accounts[0], &"x".repeat(33)),
+                InstructionError::Custom(DexError::InvalidInstructionData as u32),
+            ),
+            (wrong_program_ix, InstructionError::IncorrectProgramId),
+        ] {
+            let mut transaction = Transaction::new_with_payer(&[instruction], Some(&payer.pubkey()));
+            transaction.sign(&[&payer, &owner], recent_blockhash);
+            assert_eq!(
+                banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+                TransactionError::InstructionError(0, expected)
+            );
+        }
+
+        // A memo within the limit is forwarded and recorded with the order, and the order ID is
+        // still returned after the memo CPI
+        // 长度限制内的备注会被转发并随订单记录，且备注 CPI 之后仍会返回订单 ID
+        let mut transaction =
+            Transaction::new_with_payer(&[place_order_ix(&order_accounts[2], "desk-7/ref-42")], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &owner], recent_blockhash);
+        let result = banks_client.process_transaction_with_metadata(transaction).await.unwrap();
+        result.result.unwrap();
+        let return_data = result.metadata.unwrap().return_data.unwrap();
+        assert_eq!(PlacedOrder::try_from_slice(&return_data.data).unwrap().order_id, 1);
+
+        let order_data = banks_client.get_account(order_accounts[2]).await.unwrap().unwrap();
+        let order = Order::unpack_from_slice(&order_data.data).unwrap();
+        assert_eq!(order.memo(), "desk-7/ref-42");
+    }
+
+    #[tokio::test]
+    async fn test_delegate_places_and_cancels_orders() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+        let market_account = Keypair::new();
+        let owner = Keypair::new();
+        let delegate = Keypair::new();
+        let stranger = Keypair::new();
+        let order_accounts = add_order_accounts(&mut program_test, &program_id, 2);
+        let token_program = spl_token::id();
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        // Setup market, fund the owner and give the delegate a token account of its own
+        // 设置市场，为所有者注资，并为委托人创建其自己的代币账户
+        let (_, _, quote_mint) = setup_market(
+            &program_id,
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &market_account,
+        ).await;
+        let owner_quote =
+            common::create_token_account(&mut banks_client, &payer, recent_blockhash, &quote_mint, &owner.pubkey()).await;
+        let delegate_quote =
+            common::create_token_account(&mut banks_client, &payer, recent_blockhash, &quote_mint, &delegate.pubkey()).await;
+        common::mint_to(&mut banks_client, &payer, recent_blockhash, &quote_mint, &owner_quote, 2_000_000).await;
+
+        // The owner delegates trading and approves the market signer to lock its quote tokens
+        // 所有者委托交易，并批准市场签名者锁定其报价代币
+        let delegate_ix = |expiry_slot| {
+            DexInstruction::delegate(
+                &program_id,
+                &owner.pubkey(),
+                &market_account.pubkey(),
+                &delegate.pubkey(),
+                expiry_slot,
+            )
+            .unwrap()
+        };
+        let fund_owner_ix = system_instruction::transfer(&payer.pubkey(), &owner.pubkey(), 1_000_000_000);
+        let approve_ix = spl_token::instruction::approve(
+            &token_program,
+            &owner_quote,
+            &Market::find_signer_address(&program_id, &market_account.pubkey()).0,
+            &owner.pubkey(),
+            &[],
+            1_000_000,
+        )
+        .unwrap();
+        let mut transaction =
+            Transaction::new_with_payer(&[fund_owner_ix, delegate_ix(u64::MAX), approve_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &owner], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        let place_order_ix = |order_account: &Pubkey, signer: &Keypair| {
+            let instruction = DexInstruction::place_limit_order(
+                &program_id,
+                &owner.pubkey(),
+                &market_account.pubkey(),
+                order_account,
+                &owner_quote,
+                &token_program,
+                true,
+                1000,
+                500,
+                SelfTradeBehavior::DecrementTake,
+            )
+            .unwrap();
+            DexInstruction::with_delegate(instruction, &signer.pubkey()).unwrap()
+        };
+
+        // Only the delegate named in the owner's delegation can trade for the owner
+        // 只有所有者委托中指定的委托人才能代表所有者交易
+        let mut transaction = Transaction::new_with_payer(&[place_order_ix(&order_accounts[0], &stranger)], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &stranger], recent_blockhash);
+        assert_eq!(
+            banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+            TransactionError::InstructionError(0, InstructionError::Custom(DexError::AccountNotAuthorized as u32))
+        );
+
+        // The delegate's order belongs to the owner and is funded through the approval
+        // 委托人的订单属于所有者，并通过批准额度注资
+        let mut transaction = Transaction::new_with_payer(&[place_order_ix(&order_accounts[0], &delegate)], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &delegate], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        let order_data = banks_client.get_account(order_accounts[0]).await.unwrap().unwrap();
+        assert_eq!(Order::unpack_from_slice(&order_data.data).unwrap().owner, owner.pubkey());
+        assert_eq!(common::token_balance(&mut banks_client, &owner_quote).await, 2_000_000 - 500 * 1003);
+
+        // The delegate cannot cancel into its own token account, only back to the owner
+        // 委托人不能将资金取消到自己的代币账户，只能退还给所有者
+        let cancel_order_ix = |refund_account: &Pubkey| {
+            let instruction = DexInstruction::cancel_order(
+                &program_id,
+                &owner.pubkey(),
+                &market_account.pubkey(),
+                &order_accounts[0],
+                refund_account,
+                &token_program,
+            )
+            .unwrap();
+            DexInstruction::with_delegate(instruction, &delegate.pubkey()).unwrap()
+        };
+        let mut transaction = Transaction::new_with_payer(&[cancel_order_ix(&delegate_quote)], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &delegate], recent_blockhash);
+        assert_eq!(
+            banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+            TransactionError::InstructionError(0, InstructionError::Custom(DexError::InvalidTokenAccount as u32))
+        );
+
+        let owner_lamports = banks_client.get_balance(owner.pubkey()).await.unwrap();
+        let mut transaction = Transaction::new_with_payer(&[cancel_order_ix(&owner_quote)], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &delegate], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+        assert_eq!(common::token_balance(&mut banks_client, &owner_quote).await, 2_000_000);
+        assert_eq!(banks_client.get_balance(owner.pubkey()).await.unwrap(), owner_lamports + 1000000000);
+        assert_eq!(common::token_balance(&mut banks_client, &delegate_quote).await, 0);
+
+        // Once the expiry slot has passed the delegate can no longer trade
+        // 到期槽位过后委托人不能再交易
+        let mut transaction = Transaction::new_with_payer(&[delegate_ix(0)], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &owner], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        let mut transaction = Transaction::new_with_payer(&[place_order_ix(&order_accounts[1], &delegate)], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &delegate], recent_blockhash);
+        assert_eq!(
+            banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+            TransactionError::InstructionError(0, InstructionError::Custom(DexError::DelegationExpired as u32))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_deposits_fund_orders_without_token_transfers() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+        let market_account = Keypair::new();
+        let owner = Keypair::new();
+        let stranger = Keypair::new();
+        let order_accounts = add_order_accounts(&mut program_test, &program_id, 2);
+        let token_program = spl_token::id();
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        // Setup market and fund the owner, who pays for its OpenOrders account
+        // 设置市场并为所有者注资，由其支付 OpenOrders 账户的费用
+        let (_, _, quote_mint) = setup_market(
+            &program_id,
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &market_account,
+        ).await;
+        let owner_quote =
+            common::create_token_account(&mut banks_client, &payer, recent_blockhash, &quote_mint, &owner.pubkey()).await;
+        common::mint_to(&mut banks_client, &payer, recent_blockhash, &quote_mint, &owner_quote, 2_000_000).await;
+        let open_orders = OpenOrders::find_address(&program_id, &market_account.pubkey(), &owner.pubkey()).0;
+
+        // Deposit quote tokens into the owner's free balance
+        // 将报价代币存入所有者的可用余额
+        let fund_owner_ix = system_instruction::transfer(&payer.pubkey(), &owner.pubkey(), 1_000_000_000);
+        let deposit_ix = DexInstruction::deposit(
+            &program_id,
+            &owner.pubkey(),
+            &market_account.pubkey(),
+            &owner_quote,
+            &token_program,
+            true,
+            1_000_000,
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(&[fund_owner_ix, deposit_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &owner], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        let open_orders_data = banks_client.get_account(open_orders).await.unwrap().unwrap();
+        assert_eq!(OpenOrders::unpack_from_slice(&open_orders_data.data).unwrap().quote_free, 1_000_000);
+        assert_eq!(common::token_balance(&mut banks_client, &owner_quote).await, 1_000_000);
+
+        let place_order_ix = |signer: &Keypair, order_account: &Pubkey| {
+            DexInstruction::place_limit_order(
+                &program_id,
+                &signer.pubkey(),
+                &market_account.pubkey(),
+                order_account,
+                &open_orders,
+                &token_program,
+                true,
+                1000,
+                500,
+                SelfTradeBehavior::DecrementTake,
+            )
+            .unwrap()
+        };
+
+        // Another owner cannot spend the owner's free balance
+        // 其他所有者不能使用该所有者的可用余额
+        let mut transaction = Transaction::new_with_payer(&[place_order_ix(&stranger, &order_accounts[0])], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &stranger], recent_blockhash);
+        assert_eq!(
+            banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+            TransactionError::InstructionError(0, InstructionError::Custom(DexError::AccountMismatch as u32))
+        );
+
+        // The order is funded by debiting the free balance, leaving the wallet untouched
+        // 订单通过扣减可用余额注资，钱包余额保持不变
+        let mut transaction = Transaction::new_with_payer(&[place_order_ix(&owner, &order_accounts[0])], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &owner], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        let open_orders_data = banks_client.get_account(open_orders).await.unwrap().unwrap();
+        let free_quote = 1_000_000 - 500 * 1003;
+        assert_eq!(OpenOrders::unpack_from_slice(&open_orders_data.data).unwrap().quote_free, free_quote);
+        assert_eq!(common::token_balance(&mut banks_client, &owner_quote).await, 1_000_000);
+        let market_data = banks_client.get_account(market_account.pubkey()).await.unwrap().unwrap();
+        assert_eq!({ Market::unpack_from_slice(&market_data.data).unwrap().quote_free_total }, free_quote);
+
+        // A second order would need more than the remaining free balance
+        // 第二个订单所需资金超过剩余的可用余额
+        let mut transaction = Transaction::new_with_payer(&[place_order_ix(&owner, &order_accounts[1])], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &owner], recent_blockhash);
+        assert_eq!(
+            banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+            TransactionError::InstructionError(0, InstructionError::Custom(DexError::InsufficientFunds as u32))
+        );
+
+        // Withdrawing the rest empties the free balance, and no more can be withdrawn
+        // 提取剩余部分后可用余额清零，且无法继续提取
+        let withdraw_ix = |amount| {
+            DexInstruction::withdraw(
+                &program_id,
+                &owner.pubkey(),
+                &market_account.pubkey(),
+                &owner_quote,
+                &token_program,
+                true,
+                amount,
+            )
+            .unwrap()
+        };
+        let mut transaction = Transaction::new_with_payer(&[withdraw_ix(free_quote)], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &owner], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+        assert_eq!(common::token_balance(&mut banks_client, &owner_quote).await, 1_000_000 + free_quote);
+        let market_data = banks_client.get_account(market_account.pubkey()).await.unwrap().unwrap();
+        assert_eq!({ Market::unpack_from_slice(&market_data.data).unwrap().quote_free_total }, 0);
+
+        let mut transaction = Transaction::new_with_payer(&[withdraw_ix(1)], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &owner], recent_blockhash);
+        assert_eq!(
+            banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+            TransactionError::InstructionError(0, InstructionError::Custom(DexError::InsufficientFunds as u32))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rescue_tokens_returns_only_surplus() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+        let market_account = Keypair::new();
+        let owner = Keypair::new();
+        let order_accounts = add_order_accounts(&mut program_test, &program_id, 1);
+        let token_program = spl_token::id();
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        // The owner deposits 1_000_000 quote tokens and locks 501_500 of them in a bid
+        // 所有者存入 1_000_000 报价代币，并将其中 501_500 锁定在买单中
+        let (market_authority, _, quote_mint) = setup_market(
+            &program_id,
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &market_account,
+        ).await;
+        let owner_quote =
+            common::create_token_account(&mut banks_client, &payer, recent_blockhash, &quote_mint, &owner.pubkey()).await;
+        common::mint_to(&mut banks_client, &payer, recent_blockhash, &quote_mint, &owner_quote, 1_000_000).await;
+        let open_orders = OpenOrders::find_address(&program_id, &market_account.pubkey(), &owner.pubkey()).0;
+        let mut transaction = Transaction::new_with_payer(
+            &[
+                system_instruction::transfer(&payer.pubkey(), &owner.pubkey(), 1_000_000_000),
+                DexInstruction::deposit(
+                    &program_id,
+                    &owner.pubkey(),
+                    &market_account.pubkey(),
+                    &owner_quote,
+                    &token_program,
+                    true,
+                    1_000_000,
+                )
+                .unwrap(),
+                DexInstruction::place_limit_order(
+                    &program_id,
+                    &owner.pubkey(),
+                    &market_account.pubkey(),
+                    &order_accounts[0],
+                    &open_orders,
+                    &token_program,
+                    true,
+                    1000,
+                    500,
+                    SelfTradeBehavior::DecrementTake,
+                )
+                .unwrap(),
+            ],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &owner], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        let rescue_ix = |authority: &Keypair, is_quote| {
+            DexInstruction::rescue_tokens(
+                &program_id,
+                &authority.pubkey(),
+                &market_account.pubkey(),
+                &owner_quote,
+                &token_program,
+                is_quote,
+            )
+            .unwrap()
+        };
+
+        // A vault holding only what the market owes has nothing to rescue
+        // 仅持有市场应付资金的金库没有可救回的代币
+        let mut transaction = Transaction::new_with_payer(&[rescue_ix(&market_authority, false)], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &market_authority], recent_blockhash);
+        assert_eq!(
+            banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+            TransactionError::InstructionError(0, InstructionError::Custom(DexError::InsufficientFunds as u32))
+        );
+
+        // Tokens sent straight to the vault are returned by the market authority only
+        // 直接转入金库的代币只能由市场权限账户退回
+        let quote_vault = Market::find_quote_vault_address(&program_id, &market_account.pubkey()).0;
+        common::mint_to(&mut banks_client, &payer, recent_blockhash, &quote_mint, &quote_vault, 5_000).await;
+        let mut transaction = Transaction::new_with_payer(&[rescue_ix(&owner, true)], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &owner], recent_blockhash);
+        assert_eq!(
+            banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+            TransactionError::InstructionError(0, InstructionError::Custom(DexError::AccountNotAuthorized as u32))
+        );
+
+        // The market bounds the bid's lock with one quote lot per base lot for its fee rounding,
+        // so 250 of the 5_000 stay in the vault
+        // 市场为买单手续费的取整按每个基础手数多计一个报价手数来估计其锁定金额，因此 5_000 中有 250 留在金库中
+        let mut transaction = Transaction::new_with_payer(&[rescue_ix(&market_authority, true)], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &market_authority], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+        assert_eq!(common::token_balance(&mut banks_client, &owner_quote).await, 4_750);
+        assert_eq!(common::token_balance(&mut banks_client, &quote_vault).await, 1_000_250);
+
+        // The owner's free balance and lock are still fully backed
+        // 所有者的可用余额和锁定资金仍然完全有保障
+        let market_data = banks_client.get_account(market_account.pubkey()).await.unwrap().unwrap();
+        let market = Market::unpack_from_slice(&market_data.data).unwrap();
+        assert_eq!(market.escrow_obligation(true).unwrap(), 1_000_250);
+        assert_eq!(market.escrow_obligation(false).unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_place_order_validates_owner_token_account() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // Add the mints and token accounts that do not fit a buy order of the owner
+        // 添加代币铸造账户以及不适用于所有者买单的代币账户
+        let order_owner = Keypair::new();
+        let base_mint = common::add_mint(&mut program_test, 9, None);
+        let quote_mint = common::add_mint(&mut program_test, 6, None);
+        let base_account = common::add_token_account(&mut program_test, &base_mint, &order_owner.pubkey(), None, 1_000_000);
+        let foreign_account = common::add_token_account(&mut program_test, &quote_mint, &Pubkey::new_unique(), None, 1_000_000);
+        let delegated_account = common::add_token_account(
+            &mut program_test,
+            &quote_mint,
+            &Pubkey::new_unique(),
+            Some(order_owner.pubkey()),
+            1_000_000,
+        );
+        let fake_account = Pubkey::new_unique();
+        let fake_token_account = spl_token::state::Account {
+            mint: quote_mint,
+            owner: order_owner.pubkey(),
+            amount: 1_000_000,
+            state: spl_token::state::AccountState::Initialized,
+            ..spl_token::state::Account::default()
+        };
+        let mut fake_data = vec![0; spl_token::state::Account::LEN];
+        fake_token_account.pack_into_slice(&mut fake_data);
+        program_test.add_account(
+            fake_account,
+            solana_sdk::account::Account {
+                lamports: 1000000000,
+                data: fake_data,
+                owner: Pubkey::new_unique(),
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+
+        // Start the test environment
+        // 启动测试环境
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        // Create the market of the pair
+        // 创建该交易对的市场
+        let init_market_ix = DexInstruction::initialize_market_with_seed(
+            &program_id,
+            &payer.pubkey(),
+            &base_mint,
+            &quote_mint,
+            0,
+            100,
+            10,
+            25,
+            0,
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(&[init_market_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+        let (market_account, _) = Market::find_address(&program_id, &base_mint, &quote_mint, 0);
+
+        // Wrong mint, another wallet's account, a delegated account and a forged account are rejected
+        // 错误的代币、其他钱包的账户、被委托的账户以及伪造的账户都会被拒绝
+        for owner_token_account in [base_account, foreign_account, delegated_account, fake_account] {
+            let place_order_ix = DexInstruction::place_limit_order(
+                &program_id,
+                &order_owner.pubkey(),
+                &market_account,
+                &Keypair::new().pubkey(),
+                &owner_token_account,
+                &spl_token::id(),
+                true,
+                1000,
+                500,
+                SelfTradeBehavior::DecrementTake,
+            )
+            .unwrap();
+
+            let mut transaction = Transaction::new_with_payer(&[place_order_ix], Some(&payer.pubkey()));
+            transaction.sign(&[&payer, &order_owner], recent_blockhash);
+            let err = banks_client
+                .process_transaction(transaction)
+                .await
+                .unwrap_err()
+                .unwrap();
+            assert_eq!(
+                err,
+                TransactionError::InstructionError(
+                    0,
+                    InstructionError::Custom(DexError::InvalidTokenAccount as u32),
+                )
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handlers_reject_spoofed_programs() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        let owner = Keypair::new();
+        let market = Pubkey::new_unique();
+        let fake_token_program = Pubkey::new_unique();
+
+        // Place, cancel and settle refuse a token program other than SPL Token
+        // 下单、撤单和结算拒绝 SPL Token 以外的代币程序
+        let place_order_ix = DexInstruction::place_limit_order(
+            &program_id,
+            &owner.pubkey(),
+            &market,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &fake_token_program,
+            true,
+            1000,
+            500,
+            SelfTradeBehavior::DecrementTake,
+        )
+        .unwrap();
+
+        // Placing an order also refuses a system program other than the real one
+        // 下单同样拒绝非真实系统程序的账户
+        let mut spoofed_system_ix = place_order_ix.clone();
+        spoofed_system_ix.accounts[5].pubkey = spl_token::id();
+        spoofed_system_ix.accounts[6].pubkey = Pubkey::new_unique();
+
+        let cancel_order_ix = DexInstruction::cancel_order(
+            &program_id,
+            &owner.pubkey(),
+            &market,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &fake_token_program,
+        )
+        .unwrap();
+        let settle_funds_ix = DexInstruction::settle_funds(
+            &program_id,
+            &owner.pubkey(),
+            &market,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &fake_token_program,
+        )
+        .unwrap();
+
+        for ix in [place_order_ix, spoofed_system_ix, cancel_order_ix, settle_funds_ix] {
+            let mut transaction = Transaction::new_with_payer(&[ix], Some(&payer.pubkey()));
+            transaction.sign(&[&payer, &owner], recent_blockhash);
+            let err = banks_client
+                .process_transaction(transaction)
+                .await
+                .unwrap_err()
+                .unwrap();
+            assert_eq!(
+                err,
+                TransactionError::InstructionError(0, InstructionError::IncorrectProgramId)
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_place_order_rejects_live_order_account() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // Add the owner's quote account and a live order of another owner
+        // 添加所有者的报价代币账户以及另一所有者的有效订单
+        let order_owner = Keypair::new();
+        let base_mint = common::add_mint(&mut program_test, 9, None);
+        let quote_mint = common::add_mint(&mut program_test, 6, None);
+        let quote_account = common::add_token_account(&mut program_test, &quote_mint, &order_owner.pubkey(), None, 1_000_000);
+        let (market_account, _) = Market::find_address(&program_id, &base_mint, &quote_mint, 0);
+        let live_order = Order {
+            is_initialized: true,
+            order_id: 1,
+            owner: Pubkey::new_unique(),
+            market: market_account,
+            is_buy: true,
+            limit_price: 1000,
+            original_quantity: 500,
+            remaining_quantity: 500,
+            creation_timestamp: 0,
+            linked_order: Pubkey::default(),
+            display_quantity: 0,
+            visible_quantity: 500,
+            referrer: Pubkey::default(),
+            memo: [0; 32],
+            version: ACCOUNT_VERSION,
+            reserved: [0; ORDER_RESERVED_LEN],
+        };
+        let mut order_data = vec![0; Order::LEN];
+        live_order.pack_into_slice(&mut order_data);
+        let order_account = Pubkey::new_unique();
+        program_test.add_account(
+            order_account,
+            solana_sdk::account::Account {
+                lamports: 1000000000,
+                data: order_data.clone(),
+                owner: program_id,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+
+        // And an empty order account too poor to be rent exempt
+        // 以及一个余额不足以豁免租金的空订单账户
+        let underfunded_order_account = Pubkey::new_unique();
+        program_test.add_account(
+            underfunded_order_account,
+            solana_sdk::account::Account {
+                lamports: 1,
+                data: vec![0; Order::LEN],
+                owner: program_id,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+
+        // Start the test environment
+        // 启动测试环境
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        // Create the market of the pair
+        // 创建该交易对的市场
+        let init_market_ix = DexInstruction::initialize_market_with_seed(
+            &program_id,
+            &payer.pubkey(),
+            &base_mint,
+            &quote_mint,
+            0,
+            100,
+            10,
+            25,
+            0,
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(&[init_market_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // Placing an order into the live order account, or into one the runtime could reclaim,
+        // is rejected
+        // 向有效订单账户或可能被运行时回收的账户下单会被拒绝
+        let place_order_ix = |order_account: &Pubkey| {
+            DexInstruction::place_limit_order(
+                &program_id,
+                &order_owner.pubkey(),
+                &market_account,
+                order_account,
+                &quote_account,
+                &spl_token::id(),
+                true,
+                1000,
+                500,
+                SelfTradeBehavior::DecrementTake,
+            )
+            .unwrap()
+        };
+        let attempts = [
+            (order_account, DexError::AlreadyInitialized),
+            (underfunded_order_account, DexError::NotRentExempt),
+        ];
+        for (order_account, expected) in attempts {
+            let mut transaction = Transaction::new_with_payer(&[place_order_ix(&order_account)], Some(&payer.pubkey()));
+            transaction.sign(&[&payer, &order_owner], recent_blockhash);
+            let err = banks_client
+                .process_transaction(transaction)
+                .await
+                .unwrap_err()
+                .unwrap();
+            assert_eq!(
+                err,
+                TransactionError::InstructionError(0, InstructionError::Custom(expected as u32))
+            );
+        }
+
+        // The live order is left untouched
+        // 有效订单保持不变
+        let order = banks_client.get_account(order_account).await.unwrap().unwrap();
+        assert_eq!(order.data, order_data);
+    }
+
+    #[tokio::test]
+    async fn test_settle_funds_pays_out_of_escrow() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+        let market_account = Keypair::new();
+        let buyer = Keypair::new();
+        let seller = Keypair::new();
+        let order_accounts = add_order_accounts(&mut program_test, &program_id, 2);
+        let (bid_account, ask_account) = (order_accounts[0], order_accounts[1]);
+        let token_program = spl_token::id();
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        // Setup market, its fee vault and the traders' token accounts
+        // 设置市场、其手续费金库以及交易者的代币账户
+        let (market_authority, base_mint, quote_mint) = setup_market(
+            &program_id,
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &market_account,
+        ).await;
+        let mut token_accounts = Vec::new();
+        for (mint, owner) in [(base_mint, &buyer), (quote_mint, &buyer), (base_mint, &seller), (quote_mint, &seller)] {
+            token_accounts.push(
+                common::create_token_account(&mut banks_client, &payer, recent_blockhash, &mint, &owner.pubkey()).await,
+            );
+        }
+        let [buyer_base, buyer_quote, seller_base, seller_quote] = token_accounts[..] else {
+            unreachable!()
+        };
+        common::mint_to(&mut banks_client, &payer, recent_blockhash, &quote_mint, &buyer_quote, 1_000_000).await;
+        common::mint_to(&mut banks_client, &payer, recent_blockhash, &base_mint, &seller_base, 1_000).await;
+        let init_fee_vault_ix = DexInstruction::initialize_fee_vault(
+            &program_id,
+            &payer.pubkey(),
+            &market_account.pubkey(),
+            &quote_mint,
+            &token_program,
+        )
+        .unwrap();
+
+        // The buyer bids 500 at 1010 and the seller asks 300 at 1000
+        // 买方以 1010 出价 500，卖方以 1000 要价 300
+        let place_bid_ix = DexInstruction::place_limit_order(
+            &program_id,
+            &buyer.pubkey(),
+            &market_account.pubkey(),
+            &bid_account,
+            &buyer_quote,
+            &token_program,
+            true,
+            1010,
+            500,
+            SelfTradeBehavior::DecrementTake,
+        )
+        .unwrap();
+        let place_ask_ix = DexInstruction::place_limit_order(
+            &program_id,
+            &seller.pubkey(),
+            &market_account.pubkey(),
+            &ask_account,
+            &seller_base,
+            &token_program,
+            false,
+            1000,
+            300,
+            SelfTradeBehavior::DecrementTake,
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(
+            &[init_fee_vault_ix, place_bid_ix, place_ask_ix],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &buyer, &seller], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // The bid locks 1013 per unit: its price plus the 0.25% taker fee, rounded up
+        // 买单每单位锁定 1013：其价格加上向上取整的 0.25% 吃单手续费
+        assert_eq!(common::token_balance(&mut banks_client, &buyer_quote).await, 1_000_000 - 500 * 1013);
+
+        let settle_ix = |taker: &Pubkey, maker: &Pubkey, taker_base: &Pubkey| {
+            DexInstruction::settle_funds(
+                &program_id,
+                &market_authority.pubkey(),
+                &market_account.pubkey(),
+                taker,
+                maker,
+                taker_base,
+                &buyer_quote,
+                &seller_base,
+                &seller_quote,
+                &buyer.pubkey(),
+                &seller.pubkey(),
+                &token_program,
+            )
+            .unwrap()
+        };
+
+        // The authority can neither swap the sides nor route the base tokens to another owner
+        // 权限账户既不能交换买卖方向，也不能将基础代币转给其他所有者
+        let attempts = [
+            (settle_ix(&ask_account, &bid_account, &buyer_base), DexError::InvalidAccountData),
+            (settle_ix(&bid_account, &ask_account, &seller_base), DexError::InvalidTokenAccount),
+        ];
+        for (ix, expected) in attempts {
+            let mut transaction = Transaction::new_with_payer(&[ix], Some(&payer.pubkey()));
+            transaction.sign(&[&payer, &market_authority], recent_blockhash);
+            let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+            assert_eq!(
+                err,
+                TransactionError::InstructionError(0, InstructionError::Custom(expected as u32))
+            );
+        }
+
+        // The whole ask fills at the maker's price of 1000
+        // 整个卖单以挂单方价格 1000 成交
+        let ask_rent = banks_client.get_account(ask_account).await.unwrap().unwrap().lamports;
+        let mut transaction = Transaction::new_with_payer(
+            &[settle_ix(&bid_account, &ask_account, &buyer_base)],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &market_authority], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // The taker pays 300_000 plus a 750 fee and gets back the rest of its lock for the fill,
+        // the maker receives a 150 rebate and the fee vault keeps the net fee
+        // 吃单方支付 300_000 加 750 手续费，并取回该成交锁定资金的剩余部分，
+        // 挂单方获得 150 返佣，手续费金库保留净手续费
+        let (fee_vault, _) = Market::find_fee_vault_address(&program_id, &market_account.pubkey());
+        let (base_vault, _) = Market::find_base_vault_address(&program_id, &market_account.pubkey());
+        let (quote_vault, _) = Market::find_quote_vault_address(&program_id, &market_account.pubkey());
+        assert_eq!(common::token_balance(&mut banks_client, &buyer_base).await, 300);
+        assert_eq!(common::token_balance(&mut banks_client, &buyer_quote).await, 1_000_000 - 500 * 1013 + 300 * 1013 - 300_750);
+        assert_eq!(common::token_balance(&mut banks_client, &seller_quote).await, 300_150);
+        assert_eq!(common::token_balance(&mut banks_client, &fee_vault).await, 600);
+        assert_eq!(common::token_balance(&mut banks_client, &base_vault).await, 0);
+        assert_eq!(common::token_balance(&mut banks_client, &quote_vault).await, 200 * 1013);
+
+        // The ask leaves the book with its account closed and its rent returned to the seller,
+        // and the bid rests with its remainder
+        // 卖单离开订单簿，其账户被关闭且租金退还给卖方，买单以剩余数量继续挂单
+        assert!(banks_client.get_account(ask_account).await.unwrap().is_none());
+        assert_eq!(banks_client.get_balance(seller.pubkey()).await.unwrap(), ask_rent);
+        let bid_data = banks_client.get_account(bid_account).await.unwrap().unwrap();
+        assert_eq!({ Order::unpack_from_slice(&bid_data.data).unwrap().remaining_quantity }, 200);
+        let market_data = banks_client.get_account(market_account.pubkey()).await.unwrap().unwrap();
+        let market = Market::unpack_from_slice(&market_data.data).unwrap();
+        assert_eq!((market.num_bids, market.num_asks), (1, 0));
+        assert_eq!((market.bid_base_total, market.ask_base_total), (200, 0));
+        assert_eq!((market.best_bid, market.best_ask), (1010, 0));
+        assert_eq!({ market.last_price }, 1000);
+        assert_eq!((market.base_volume, market.quote_volume), (300, 300_000));
+        assert!(market.last_trade_slot > 0);
+        assert_eq!((market.fees_accrued, market.fees_swept, market.referral_fees_paid), (600, 0, 0));
+
+        // The closed ask cannot settle again
+        // 已关闭的卖单不能再次结算
+        let recent_blockhash = banks_client.get_latest_blockhash().await.unwrap();
+        let mut transaction = Transaction::new_with_payer(
+            &[settle_ix(&bid_account, &ask_account, &buyer_base)],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &market_authority], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(0, InstructionError::Custom(DexError::InvalidAccountOwner as u32))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_settle_funds_updates_twap_oracle() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+        let market_account = Keypair::new();
+        let buyer = Keypair::new();
+        let seller = Keypair::new();
+        let order_accounts = add_order_accounts(&mut program_test, &program_id, 3);
+        let token_program = spl_token::id();
+        let mut context = program_test.start_with_context().await;
+        let payer = context.payer.insecure_clone();
+        let recent_blockhash = context.last_blockhash;
+
+        // Setup market, its fee vault and the traders' token accounts
+        // 设置市场、其手续费金库以及交易者的代币账户
+        let (market_authority, base_mint, quote_mint) = setup_market(
+            &program_id,
+            &mut context.banks_client,
+            &payer,
+            &recent_blockhash,
+            &market_account,
+        ).await;
+        let banks_client = &mut context.banks_client;
+        let mut token_accounts = Vec::new();
+        for (mint, owner) in [(base_mint, &buyer), (quote_mint, &buyer), (base_mint, &seller), (quote_mint, &seller)] {
+            token_accounts.push(common::create_token_account(banks_client, &payer, recent_blockhash, &mint, &owner.pubkey()).await);
+        }
+        let [buyer_base, buyer_quote, seller_base, seller_quote] = token_accounts[..] else {
+            unreachable!()
+        };
+        common::mint_to(banks_client, &payer, recent_blockhash, &quote_mint, &buyer_quote, 1_000_000).await;
+        common::mint_to(banks_client, &payer, recent_blockhash, &base_mint, &seller_base, 1_000).await;
+
+        // The buyer bids 500 at 1010 and the seller asks 300 at 1000 and 200 at 1010
+        // 买方以 1010 出价 500，卖方以 1000 要价 300、以 1010 要价 200
+        let place_order_ix = |owner: &Keypair, order_account: &Pubkey, token_account: &Pubkey, is_buy, price, quantity| {
+            DexInstruction::place_limit_order(
+                &program_id,
+                &owner.pubkey(),
+                &market_account.pubkey(),
+                order_account,
+                token_account,
+                &token_program,
+                is_buy,
+                price,
+                quantity,
+                SelfTradeBehavior::DecrementTake,
+            )
+            .unwrap()
+        };
+        let init_fee_vault_ix = DexInstruction::initialize_fee_vault(
+            &program_id,
+            &payer.pubkey(),
+            &market_account.pubkey(),
+            &quote_mint,
+            &token_program,
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(
+            &[
+                init_fee_vault_ix,
+                place_order_ix(&buyer, &order_accounts[0], &buyer_quote, true, 1010, 500),
+                place_order_ix(&seller, &order_accounts[1], &seller_base, false, 1000, 300),
+                place_order_ix(&seller, &order_accounts[2], &seller_base, false, 1010, 200),
+            ],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &buyer, &seller], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // Only the market authority can create the TWAP oracle
+        // 只有市场权限账户可以创建 TWAP 预言机
+        let init_twap_oracle_ix = |authority: &Keypair| {
+            DexInstruction::initialize_twap_oracle(&program_id, &authority.pubkey(), &market_account.pubkey(), 600).unwrap()
+        };
+        let mut transaction = Transaction::new_with_payer(&[init_twap_oracle_ix(&payer)], Some(&payer.pubkey()));
+        transaction.sign(&[&payer], recent_blockhash);
+        assert_eq!(
+            banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+            TransactionError::InstructionError(0, InstructionError::Custom(DexError::AccountNotAuthorized as u32))
+        );
+        let mut transaction = Transaction::new_with_payer(&[init_twap_oracle_ix(&market_authority)], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &market_authority], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        let settle_ix = |maker: &Pubkey| {
+            DexInstruction::settle_funds(
+                &program_id,
+                &market_authority.pubkey(),
+                &market_account.pubkey(),
+                &order_accounts[0],
+                maker,
+                &buyer_base,
+                &buyer_quote,
+                &seller_base,
+                &seller_quote,
+                &buyer.pubkey(),
+                &seller.pubkey(),
+                &token_program,
+            )
+            .unwrap()
+        };
+
+        // Once the market has a TWAP oracle, settlements must update it
+        // 市场拥有 TWAP 预言机后，结算必须更新它
+        let mut transaction = Transaction::new_with_payer(&[settle_ix(&order_accounts[1])], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &market_authority], recent_blockhash);
+        assert_eq!(
+            banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+            TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys)
+        );
+
+        // The first fill seeds the averages with its price
+        // 第一笔成交以其价格初始化平均值
+        let start = 1_700_000_000;
+        let blockhash = common::set_unix_timestamp(&mut context, start).await;
+        let mut transaction = Transaction::new_with_payer(
+            &[DexInstruction::with_twap_oracle(settle_ix(&order_accounts[1]))],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &market_authority], blockhash);
+        context.banks_client.process_transaction(transaction).await.unwrap();
+
+        let twap_oracle_address = TwapOracle::find_address(&program_id, &market_account.pubkey()).0;
+        let twap_oracle_data = context.banks_client.get_account(twap_oracle_address).await.unwrap().unwrap();
+        let twap_oracle = TwapOracle::unpack_from_slice(&twap_oracle_data.data).unwrap();
+        assert_eq!((twap_oracle.fill_count, twap_oracle.last_price, twap_oracle.ema_price), (1, 1000, 1000));
+        assert_eq!(twap_oracle.last_update, start);
+
+        // The first price stood for 300 seconds before the second fill at 1010
+        // 第一个价格在第二笔 1010 的成交之前持续了 300 秒
+        let blockhash = common::advance_unix_timestamp(&mut context, 300).await;
+        let mut transaction = Transaction::new_with_payer(
+            &[DexInstruction::with_twap_oracle(settle_ix(&order_accounts[2]))],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &market_authority], blockhash);
+        context.banks_client.process_transaction(transaction).await.unwrap();
+
+        let twap_oracle_data = context.banks_client.get_account(twap_oracle_address).await.unwrap().unwrap();
+        let twap_oracle = TwapOracle::unpack_from_slice(&twap_oracle_data.data).unwrap();
+        assert_eq!((twap_oracle.fill_count, twap_oracle.last_price), (2, 1010));
+        assert_eq!(twap_oracle.price_cumulative, 1000 * 300);
+        assert_eq!(twap_oracle.twap_since(0, start, start + 400), Some((1000 * 300 + 1010 * 100) / 400));
+    }
+
+    #[tokio::test]
+    async fn test_settle_funds_appends_to_event_queue() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+        let market_account = Keypair::new();
+        let buyer = Keypair::new();
+        let seller = Keypair::new();
+        let order_accounts = add_order_accounts(&mut program_test, &program_id, 3);
+        let token_program = spl_token::id();
+        let mut context = program_test.start_with_context().await;
+        let payer = context.payer.insecure_clone();
+        let recent_blockhash = context.last_blockhash;
+
+        // Setup market, its fee vault and the traders' token accounts
+        // 设置市场、其手续费金库以及交易者的代币账户
+        let (market_authority, base_mint, quote_mint) = setup_market(
+            &program_id,
+            &mut context.banks_client,
+            &payer,
+            &recent_blockhash,
+            &market_account,
+        ).await;
+        let banks_client = &mut context.banks_client;
+        let mut token_accounts = Vec::new();
+        for (mint, owner) in [(base_mint, &buyer), (quote_mint, &buyer), (base_mint, &seller), (quote_mint, &seller)] {
+            token_accounts.push(common::create_token_account(banks_client, &payer, recent_blockhash, &mint, &owner.pubkey()).await);
+        }
+        let [buyer_base, buyer_quote, seller_base, seller_quote] = token_accounts[..] else {
+            unreachable!()
+        };
+        common::mint_to(banks_client, &payer, recent_blockhash, &quote_mint, &buyer_quote, 1_000_000).await;
+        common::mint_to(banks_client, &payer, recent_blockhash, &base_mint, &seller_base, 1_000).await;
+
+        // The buyer bids 500 at 1010 and the seller asks 300 at 1000 and 200 at 1010
+        // 买方以 1010 出价 500，卖方以 1000 要价 300、以 1010 要价 200
+        let place_order_ix = |owner: &Keypair, order_account: &Pubkey, token_account: &Pubkey, is_buy, price, quantity| {
+            DexInstruction::place_limit_order(
+                &program_id,
+                &owner.pubkey(),
+                &market_account.pubkey(),
+                order_account,
+                token_account,
+                &token_program,
+                is_buy,
+                price,
+                quantity,
+                SelfTradeBehavior::DecrementTake,
+            )
+            .unwrap()
+        };
+        let init_fee_vault_ix = DexInstruction::initialize_fee_vault(
+            &program_id,
+            &payer.pubkey(),
+            &market_account.pubkey(),
+            &quote_mint,
+            &token_program,
+        )
+        .unwrap();
+        let init_event_queue_ix =
+            DexInstruction::initialize_event_queue(&program_id, &market_authority.pubkey(), &market_account.pubkey()).unwrap();
+        let mut transaction = Transaction::new_with_payer(
+            &[
+                init_fee_vault_ix,
+                init_event_queue_ix,
+                place_order_ix(&buyer, &order_accounts[0], &buyer_quote, true, 1010, 500),
+                place_order_ix(&seller, &order_accounts[1], &seller_base, false, 1000, 300),
+                place_order_ix(&seller, &order_accounts[2], &seller_base, false, 1010, 200),
+            ],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &market_authority, &buyer, &seller], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        let settle_ix = |maker: &Pubkey| {
+            DexInstruction::settle_funds(
+                &program_id,
+                &market_authority.pubkey(),
+                &market_account.pubkey(),
+                &order_accounts[0],
+                maker,
+                &buyer_base,
+                &buyer_quote,
+                &seller_base,
+                &seller_quote,
+                &buyer.pubkey(),
+                &seller.pubkey(),
+                &token_program,
+            )
+            .unwrap()
+        };
+
+        // Once the market has an event queue, settlements must append to it
+        // 市场拥有事件队列后，结算必须向其追加事件
+        let mut transaction = Transaction::new_with_payer(&[settle_ix(&order_accounts[1])], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &market_authority], recent_blockhash);
+        assert_eq!(
+            banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+            TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys)
+        );
+
+        let mut transaction = Transaction::new_with_payer(
+            &[
+                DexInstruction::with_event_queue(settle_ix(&order_accounts[1])),
+                DexInstruction::with_event_queue(settle_ix(&order_accounts[2])),
+            ],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &market_authority], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // Both fills are queued with the sequence numbers of their fill logs
+        // 两笔成交均以其成交日志的序号入队
+        let event_queue_address = EventQueue::find_address(&program_id, &market_account.pubkey()).0;
+        let event_queue_data = banks_client.get_account(event_queue_address).await.unwrap().unwrap();
+        let event_queue = EventQueue::unpack_from_slice(&event_queue_data.data).unwrap();
+        assert_eq!(event_queue.market, market_account.pubkey());
+        let events: Vec<_> = event_queue
+            .iter()
+            .map(|event| (event.seq_num, event.maker_order, event.price, event.base_amount, event.quote_amount))
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                (0, order_accounts[1], 1000, 300, 300_000),
+                (1, order_accounts[2], 1010, 200, 202_000),
+            ]
+        );
+        assert_eq!(event_queue.iter().next().unwrap().taker_order, order_accounts[0]);
+
+        // Only the market authority consumes events, starting at the oldest one
+        // 只有市场权限账户可以消费事件，且必须从最旧的事件开始
+        let consume_ix = |authority: &Keypair, seq_num, limit| {
+            DexInstruction::consume_events(&program_id, &authority.pubkey(), &market_account.pubkey(), seq_num, limit).unwrap()
+        };
+        let mut transaction = Transaction::new_with_payer(&[consume_ix(&buyer, 0, 1)], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &buyer], recent_blockhash);
+        assert_eq!(
+            banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+            TransactionError::InstructionError(0, InstructionError::Custom(DexError::AccountNotAuthorized as u32))
+        );
+        let mut transaction = Transaction::new_with_payer(&[consume_ix(&market_authority, 1, 1)], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &market_authority], recent_blockhash);
+        assert_eq!(
+            banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+            TransactionError::InstructionError(0, InstructionError::Custom(DexError::EventSequenceMismatch as u32))
+        );
+
+        let mut transaction = Transaction::new_with_payer(&[consume_ix(&market_authority, 0, 1)], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &market_authority], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // A crank retrying the same batch cannot consume the next, unprocessed event
+        // 重试同一批次的 crank 无法消费下一个尚未处理的事件
+        let recent_blockhash = common::warp_slots(&mut context, 1).await;
+        let mut transaction = Transaction::new_with_payer(&[consume_ix(&market_authority, 0, 1)], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &market_authority], recent_blockhash);
+        assert_eq!(
+            context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+            TransactionError::InstructionError(0, InstructionError::Custom(DexError::EventSequenceMismatch as u32))
+        );
+
+        // A crank transaction landing after its staleness limit is refused
+        // 超过过期限制才落地的 crank 交易会被拒绝
+        let recent_blockhash = common::warp_slots(&mut context, 20).await;
+        let slot = common::clock(&mut context).await.slot;
+        let stale_ix = DexInstruction::with_staleness_guard(consume_ix(&market_authority, 1, 16), slot - 10, 5).unwrap();
+        let mut transaction = Transaction::new_with_payer(&[stale_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &market_authority], recent_blockhash);
+        assert_eq!(
+            context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+            TransactionError::InstructionError(0, InstructionError::Custom(DexError::TransactionStale as u32))
+        );
+
+        // A limit above the queued events consumes what is left, within the staleness limit
+        // 在过期限制内，超过队列事件数量的上限会消费剩余的全部事件
+        let consume_ix = DexInstruction::with_staleness_guard(consume_ix(&market_authority, 1, 16), slot, 5).unwrap();
+        let mut transaction = Transaction::new_with_payer(&[consume_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &market_authority], recent_blockhash);
+        context.banks_client.process_transaction(transaction).await.unwrap();
+
+        let event_queue_data = context.banks_client.get_account(event_queue_address).await.unwrap().unwrap();
+        let event_queue = EventQueue::unpack_from_slice(&event_queue_data.data).unwrap();
+        assert_eq!(({ event_queue.count }, event_queue.first_seq_num(), { event_queue.dropped }), (0, 2, 0));
+    }
+
+    #[tokio::test]
+    async fn test_lot_sizes_scale_escrow_and_settlement() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+        let market_account = Keypair::new();
+        let buyer = Keypair::new();
+        let seller = Keypair::new();
+        let order_accounts = add_order_accounts(&mut program_test, &program_id, 2);
+        let (bid_account, ask_account) = (order_accounts[0], order_accounts[1]);
+        let token_program = spl_token::id();
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        // A market must count in lots of at least one token
+        // 市场的每手必须至少包含一个代币单位
+        let empty_lot_market = Keypair::new();
+        let init_market_ix = DexInstruction::initialize_market(
+            &program_id,
+            &payer.pubkey(),
+            &empty_lot_market.pubkey(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            1,
+            1,
+            25,
+            -5,
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(
+            &[
+                system_instruction::create_account(
+                    &payer.pubkey(),
+                    &empty_lot_market.pubkey(),
+                    Rent::default().minimum_balance(Market::LEN),
+                    Market::LEN as u64,
+                    &program_id,
+                ),
+                DexInstruction::with_lot_sizes(init_market_ix, 0, 10).unwrap(),
+            ],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &empty_lot_market], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(1, InstructionError::Custom(DexError::InvalidLotSize as u32))
+        );
+
+        // Base lots of 1_000 tokens and quote lots of 10 tokens
+        // 基础代币每手 1_000 个单位，报价代币每手 10 个单位
+        let (market_authority, base_mint, quote_mint) = setup_market_with_lot_sizes(
+            &program_id,
+            &mut banks_client,
+            &payer,
+            &recent_blockhash,
+            &market_account,
+            1_000,
+            10,
+        ).await;
+        let mut token_accounts = Vec::new();
+        for (mint, owner) in [(base_mint, &buyer), (quote_mint, &buyer), (base_mint, &seller), (quote_mint, &seller)] {
+            token_accounts.push(
+                common::create_token_account(&mut banks_client, &payer, recent_blockhash, &mint, &owner.pubkey()).await,
+            );
+        }
+        let [buyer_base, buyer_quote, seller_base, seller_quote] = token_accounts[..] else {
+            unreachable!()
+        };
+        common::mint_to(&mut banks_client, &payer, recent_blockhash, &quote_mint, &buyer_quote, 10_000_000).await;
+        common::mint_to(&mut banks_client, &payer, recent_blockhash, &base_mint, &seller_base, 300_000).await;
+        let init_fee_vault_ix = DexInstruction::initialize_fee_vault(
+            &program_id,
+            &payer.pubkey(),
+            &market_account.pubkey(),
+            &quote_mint,
+            &token_program,
+        )
+        .unwrap();
+
+        // The buyer bids 500 lots at 1010 and the seller asks 300 lots at 1000
+        // 买方以 1010 出价 500 手，卖方以 1000 要价 300 手
+        let place_bid_ix = DexInstruction::place_limit_order(
+            &program_id,
+            &buyer.pubkey(),
+            &market_account.pubkey(),
+            &bid_account,
+            &buyer_quote,
+            &token_program,
+            true,
+            1010,
+            500,
+            SelfTradeBehavior::DecrementTake,
+        )
+        .unwrap();
+        let place_ask_ix = DexInstruction::place_limit_order(
+            &program_id,
+            &seller.pubkey(),
+            &market_account.pubkey(),
+            &ask_account,
+            &seller_base,
+            &token_program,
+            false,
+            1000,
+            300,
+            SelfTradeBehavior::DecrementTake,
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(
+            &[init_fee_vault_ix, place_bid_ix, place_ask_ix],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &buyer, &seller], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // The bid locks 1013 quote lots of 10 tokens per base lot, the ask 1_000 tokens per lot
+        // 买单每基础手锁定 1013 个报价手（每手 10 个单位），卖单每手锁定 1_000 个单位
+        let (base_vault, _) = Market::find_base_vault_address(&program_id, &market_account.pubkey());
+        let (quote_vault, _) = Market::find_quote_vault_address(&program_id, &market_account.pubkey());
+        assert_eq!(common::token_balance(&mut banks_client, &buyer_quote).await, 10_000_000 - 500 * 1013 * 10);
+        assert_eq!(common::token_balance(&mut banks_client, &base_vault).await, 300_000);
+
+        // The whole ask fills at the maker's price of 1000 quote lots per base lot
+        // 整个卖单以挂单方价格每基础手 1000 个报价手成交
+        let settle_ix = DexInstruction::settle_funds(
+            &program_id,
+            &market_authority.pubkey(),
+            &market_account.pubkey(),
+            &bid_account,
+            &ask_account,
+            &buyer_base,
+            &buyer_quote,
+            &seller_base,
+            &seller_quote,
+            &buyer.pubkey(),
+            &seller.pubkey(),
+            &token_program,
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(&[settle_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &market_authority], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // The taker receives 300_000 base tokens for 3_000_000 quote tokens plus a 7_500 fee,
+        // and the maker receives a 1_500 rebate
+        // 吃单方以 3_000_000 报价代币加 7_500 手续费获得 300_000 基础代币，挂单方获得 1_500 返佣
+        let (fee_vault, _) = Market::find_fee_vault_address(&program_id, &market_account.pubkey());
+        assert_eq!(common::token_balance(&mut banks_client, &buyer_base).await, 300_000);
+        assert_eq!(
+            common::token_balance(&mut banks_client, &buyer_quote).await,
+            10_000_000 - 3_007_500 - 200 * 1013 * 10
+        );
+        assert_eq!(common::token_balance(&mut banks_client, &seller_quote).await, 3_001_500);
+        assert_eq!(common::token_balance(&mut banks_client, &fee_vault).await, 6_000);
+        assert_eq!(common::token_balance(&mut banks_client, &base_vault).await, 0);
+        assert_eq!(common::token_balance(&mut banks_client, &quote_vault).await, 200 * 1013 * 10);
+
+        // The bid rests with 200 lots
+        // 买单以 200 手剩余数量继续挂单
+        let bid_data = banks_client.get_account(bid_account).await.unwrap().unwrap();
+        assert_eq!({ Order::unpack_from_slice(&bid_data.data).unwrap().remaining_quantity }, 200);
+        let market_data = banks_client.get_account(market_account.pubkey()).await.unwrap().unwrap();
+        let market = Market::unpack_from_slice(&market_data.data).unwrap();
+        assert_eq!((market.base_lot_size, market.quote_lot_size), (1_000, 10));
+        assert_eq!((market.bid_base_total, market.ask_base_total), (200, 0));
+    }
+
+    #[tokio::test]
+    async fn test_close_order_account_reclaims_empty_accounts() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // Add an empty order account and one holding a live order
+        // 添加一个空订单账户和一个持有有效订单的账户
+        let empty_account = Keypair::new();
+        let live_account = Keypair::new();
+        let live_order = Order {
+            is_initialized: true,
+            order_id: 1,
+            owner: Pubkey::new_unique(),
+            market: Pubkey::new_unique(),
+            is_buy: true,
+            limit_price: 1000,
+            original_quantity: 500,
+            remaining_quantity: 500,
+            creation_timestamp: 0,
+            linked_order: Pubkey::default(),
+            display_quantity: 0,
+            visible_quantity: 500,
+            referrer: Pubkey::default(),
+            memo: [0; 32],
+            version: ACCOUNT_VERSION,
+            reserved: [0; ORDER_RESERVED_LEN],
+        };
+        let mut live_data = vec![0; Order::LEN];
+        live_order.pack_into_slice(&mut live_data);
+        for (account, data) in [(&empty_account, vec![0; Order::LEN]), (&live_account, live_data)] {
+            program_test.add_account(
+                account.pubkey(),
+                solana_sdk::account::Account {
+                    lamports: 1000000000,
+                    data,
+                    owner: program_id,
+                    executable: false,
+                    rent_epoch: 0,
+                },
+            );
+        }
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let recipient = Pubkey::new_unique();
+
+        // A live order still holds escrowed funds and must be cancelled instead
+        // 有效订单仍持有托管资金，必须改为撤单
+        let close_ix = DexInstruction::close_order_account(&program_id, &live_account.pubkey(), &recipient).unwrap();
+        let mut transaction = Transaction::new_with_payer(&[close_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &live_account], recent_blockhash);
+        let err = banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(0, InstructionError::Custom(DexError::AlreadyInitialized as u32))
+        );
+
+        // The empty account is closed and its lamports go to the recipient
+        // 空账户被关闭，其 lamports 转给接收方
+        let close_ix = DexInstruction::close_order_account(&program_id, &empty_account.pubkey(), &recipient).unwrap();
+        let mut transaction = Transaction::new_with_payer(&[close_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &empty_account], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+        assert!(banks_client.get_account(empty_account.pubkey()).await.unwrap().is_none());
+        assert_eq!(banks_client.get_balance(recipient).await.unwrap(), 1000000000);
     }
-} 
\ No newline at end of file
+}
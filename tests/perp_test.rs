@@ -0,0 +1,599 @@
+// Perpetual futures market test module
+// 永续合约市场测试模块
+
+mod common;
+
+#[cfg(test)]
+mod perp_tests {
+    use super::common;
+    use solana_program::{program_pack::Pack, pubkey::Pubkey};
+    use solana_program_test::*;
+    use solana_sdk::{
+        account::Account,
+        instruction::{Instruction, InstructionError},
+        signature::{Keypair, Signer},
+        transaction::{Transaction, TransactionError},
+    };
+    use solana_rust_dex::{
+        error::DexError,
+        instruction::DexInstruction,
+        oracle::{PYTH_MAGIC, PYTH_PRICE_ACCOUNT_TYPE, PYTH_STATUS_TRADING, PYTH_VERSION},
+        state::{
+            Market, PerpMarket, PerpOrder, PerpPosition, FUNDING_INTERVAL_SECONDS,
+            FUNDING_PRECISION,
+        },
+    };
+
+    // Pyth price account with an aggregate price published in a slot
+    // 在指定槽位发布聚合价格的 Pyth 价格账户
+    fn pyth_price_data(price: i64, expo: i32, publish_slot: u64) -> Vec<u8> {
+        let mut data = vec![0; 3312];
+        data[0..4].copy_from_slice(&PYTH_MAGIC.to_le_bytes());
+        data[4..8].copy_from_slice(&PYTH_VERSION.to_le_bytes());
+        data[8..12].copy_from_slice(&PYTH_PRICE_ACCOUNT_TYPE.to_le_bytes());
+        data[20..24].copy_from_slice(&expo.to_le_bytes());
+        data[208..216].copy_from_slice(&price.to_le_bytes());
+        data[216..224].copy_from_slice(&1_000_000u64.to_le_bytes());
+        data[224..228].copy_from_slice(&PYTH_STATUS_TRADING.to_le_bytes());
+        data[232..240].copy_from_slice(&publish_slot.to_le_bytes());
+        data
+    }
+
+    fn custom_error(err: TransactionError) -> u32 {
+        match err {
+            TransactionError::InstructionError(0, InstructionError::Custom(code)) => code,
+            err => panic!("unexpected error: {:?}", err),
+        }
+    }
+
+    fn market(authority: &Pubkey, quote_mint: &Pubkey, oracle: &Pubkey) -> Market {
+        Market {
+            authority: *authority,
+            base_mint: Pubkey::new_unique(),
+            quote_mint: *quote_mint,
+            taker_fee_bps: 25,
+            next_order_id: 0,
+            fee_authority: *authority,
+            oracle: *oracle,
+            oracle_band_bps: 1_000,
+            oracle_max_staleness_slots: 1_000,
+            oracle_price_expo: -2,
+            ..common::market()
+        }
+    }
+
+    fn perp_market(max_funding_rate_bps: u16) -> PerpMarket {
+        PerpMarket {
+            is_initialized: true,
+            market: Pubkey::new_unique(),
+            bump: 255,
+            initial_margin_bps: 1_000,
+            max_funding_rate_bps,
+            mark_price: 0,
+            long_open_interest: 0,
+            cumulative_funding: 0,
+            last_funding_timestamp: 0,
+        }
+    }
+
+    fn position(base_position: i64, quote_balance: i64) -> PerpPosition {
+        PerpPosition {
+            is_initialized: true,
+            market: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            base_position,
+            quote_balance,
+            last_cumulative_funding: 0,
+        }
+    }
+
+    #[test]
+    fn test_funding_follows_the_premium_within_the_cap() {
+        // A twenty-fourth of the premium is paid each interval, by longs above the index
+        // 每个周期支付溢价的二十四分之一，标记价格高于指数价格时由多头支付
+        let perp_market = perp_market(100);
+        assert_eq!(perp_market.funding_per_interval(1_050, 1_000, 1), 50 * FUNDING_PRECISION / 24);
+        assert_eq!(perp_market.funding_per_interval(950, 1_000, 1), -50 * FUNDING_PRECISION / 24);
+        assert_eq!(perp_market.funding_per_interval(1_000, 1_000, 10), 0);
+
+        // Large premiums are capped at the maximum rate of the index price
+        // 较大的溢价被限制在指数价格的最高费率以内
+        assert_eq!(perp_market.funding_per_interval(2_000, 1_000, 1), 10 * FUNDING_PRECISION);
+        assert_eq!(perp_market.funding_per_interval(1, 1_000, 1), -10 * FUNDING_PRECISION);
+
+        // Before the first fill positions are valued at the index price
+        // 首次成交之前按指数价格为仓位估值
+        assert_eq!(perp_market.mark_price_or(1_000), 1_000);
+        assert_eq!(PerpMarket { mark_price: 1_050, ..perp_market }.mark_price_or(1_000), 1_050);
+    }
+
+    #[test]
+    fn test_funding_rounds_against_the_position() {
+        // Longs pay and shorts receive while funding is positive
+        // 资金费率为正时多头支付、空头收取
+        let cumulative_funding = FUNDING_PRECISION * 3 / 2;
+        let mut long = position(3, 1_000);
+        assert_eq!(long.settle_funding(cumulative_funding).unwrap(), 5);
+        assert_eq!(long.quote_balance, 995);
+        let mut short = position(-3, 1_000);
+        assert_eq!(short.settle_funding(cumulative_funding).unwrap(), -4);
+        assert_eq!(short.quote_balance, 1_004);
+
+        // Settling again without new funding pays nothing
+        // 没有新的资金费用时再次结算不产生支付
+        assert_eq!(long.settle_funding(cumulative_funding).unwrap(), 0);
+        assert_eq!(long.last_cumulative_funding, cumulative_funding);
+    }
+
+    fn order(is_buy: bool, reduce_only: bool) -> PerpOrder {
+        PerpOrder {
+            is_initialized: true,
+            market: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            order_id: 1,
+            is_buy,
+            limit_price: 1_000,
+            original_quantity: 500,
+            remaining_quantity: 500,
+            creation_timestamp: 0,
+            reduce_only,
+        }
+    }
+
+    #[test]
+    fn test_reduce_only_clips_to_the_position() {
+        // Orders that are not reduce-only take the whole fill
+        // 非只减仓的订单接受全部成交
+        assert_eq!(order(true, false).clip_reduce_only(100, 500), 500);
+        assert_eq!(order(false, false).clip_reduce_only(100, 500), 500);
+
+        // A reduce-only order of a long sells at most its size and buys nothing
+        // 多头的只减仓订单最多卖出其仓位大小，且不能买入
+        assert_eq!(order(false, true).clip_reduce_only(100, 500), 100);
+        assert_eq!(order(false, true).clip_reduce_only(100, 60), 60);
+        assert_eq!(order(true, true).clip_reduce_only(100, 500), 0);
+
+        // A reduce-only order of a short buys back at most its size, of a flat position nothing
+        // 空头的只减仓订单最多买回其仓位大小，空仓的订单不接受成交
+        assert_eq!(order(true, true).clip_reduce_only(-40, 500), 40);
+        assert_eq!(order(false, true).clip_reduce_only(-40, 500), 0);
+        assert_eq!(order(true, true).clip_reduce_only(0, 1), 0);
+        assert_eq!(order(false, true).clip_reduce_only(0, 1), 0);
+    }
+
+    #[test]
+    fn test_margin_and_open_interest() {
+        // A long of 100 lots bought at 1000 with 20000 of collateral
+        // 以 20000 保证金在 1000 价格买入 100 手的多头仓位
+        let mut long = position(0, 20_000);
+        long.trade(100, -100_000).unwrap();
+        assert_eq!(long.equity(1_000, 1), 20_000);
+        assert_eq!(long.initial_margin(1_000, 1, 1_000), 10_000);
+        assert!(long.is_healthy(1_000, 1, 1_000));
+
+        // At a mark of 890 the equity of 9000 still covers the margin of 8900, at 880 it does not
+        // 标记价格为 890 时 9000 的权益仍可覆盖 8900 的保证金，为 880 时则不能
+        assert!(long.is_healthy(890, 1, 1_000));
+        assert!(!long.is_healthy(880, 1, 1_000));
+        assert!(!position(0, -1).is_healthy(1_000, 1, 1_000));
+
+        // Open interest counts the long side only
+        // 未平仓量只统计多头一方
+        let mut perp_market = perp_market(100);
+        perp_market.record_position_change(0, 100).unwrap();
+        perp_market.record_position_change(0, -100).unwrap();
+        assert_eq!(perp_market.long_open_interest, 100);
+        perp_market.record_position_change(100, -50).unwrap();
+        assert_eq!(perp_market.long_open_interest, 0);
+        assert!(perp_market.record_position_change(100, 0).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_perp_collateral_fills_and_funding() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // Add a market with an oracle at 10.00 and a fee vault
+        // 添加带有 10.00 预言机价格的市场以及手续费金库
+        let authority = Keypair::new();
+        let market_account = Pubkey::new_unique();
+        let oracle = Pubkey::new_unique();
+        let quote_mint = common::add_mint(&mut program_test, 6, None);
+        let mut market_data = vec![0; Market::LEN];
+        market(&authority.pubkey(), &quote_mint, &oracle).pack_into_slice(&mut market_data);
+        program_test.add_account(
+            market_account,
+            Account {
+                lamports: 1_000_000_000,
+                data: market_data,
+                owner: program_id,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+        program_test.add_account(
+            oracle,
+            Account {
+                lamports: 1_000_000_000,
+                data: pyth_price_data(10_00000000, -8, 0),
+                owner: Pubkey::new_unique(),
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+        let (fee_vault, _) = Market::find_fee_vault_address(&program_id, &market_account);
+        let mut fee_vault_data = vec![0; spl_token::state::Account::LEN];
+        spl_token::state::Account {
+            mint: quote_mint,
+            owner: fee_vault,
+            state: spl_token::state::AccountState::Initialized,
+            ..spl_token::state::Account::default()
+        }
+        .pack_into_slice(&mut fee_vault_data);
+        program_test.add_account(
+            fee_vault,
+            Account {
+                lamports: 1_000_000_000,
+                data: fee_vault_data,
+                owner: spl_token::id(),
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+
+        // Two traders holding quote tokens, who pay for their positions as the authority does
+        // for the perp market
+        // 两个持有报价代币的交易者，与管理员支付永续合约市场一样由其支付各自的仓位
+        let alice = Keypair::new();
+        let bob = Keypair::new();
+        let alice_tokens = common::add_token_account(&mut program_test, &quote_mint, &alice.pubkey(), None, 1_000_000);
+        let bob_tokens = common::add_token_account(&mut program_test, &quote_mint, &bob.pubkey(), None, 1_000_000);
+        for trader in [&authority, &alice, &bob] {
+            program_test.add_account(
+                trader.pubkey(),
+                Account {
+                    lamports: 1_000_000_000,
+                    data: vec![],
+                    owner: solana_program::system_program::id(),
+                    executable: false,
+                    rent_epoch: 0,
+                },
+            );
+        }
+
+        let mut context = program_test.start_with_context().await;
+        let payer = context.payer.insecure_clone();
+        let recent_blockhash = context.last_blockhash;
+        let (vault, _) = PerpMarket::find_vault_address(&program_id, &market_account);
+        let (perp_market_address, _) = PerpMarket::find_address(&program_id, &market_account);
+        let alice_position_address = PerpPosition::find_address(&program_id, &market_account, &alice.pubkey()).0;
+
+        let place_ix = |owner: &Keypair, order: &Keypair, is_buy: bool, price: u64, quantity: u64, reduce_only: bool| {
+            DexInstruction::place_perp_order(
+                &program_id,
+                &owner.pubkey(),
+                &market_account,
+                &order.pubkey(),
+                &oracle,
+                is_buy,
+                price,
+                quantity,
+                reduce_only,
+            )
+            .unwrap()
+        };
+        let fill_ix = |taker_order: &Keypair, taker: &Keypair, maker_order: &Keypair, maker: &Keypair| {
+            DexInstruction::settle_perp_fill(
+                &program_id,
+                &authority.pubkey(),
+                &market_account,
+                &taker_order.pubkey(),
+                &taker.pubkey(),
+                &maker_order.pubkey(),
+                &maker.pubkey(),
+                &quote_mint,
+                &spl_token::id(),
+                &oracle,
+            )
+            .unwrap()
+        };
+        let withdraw_ix = |owner: &Keypair, destination: &Pubkey, amount: u64| {
+            DexInstruction::withdraw_perp_collateral(
+                &program_id,
+                &owner.pubkey(),
+                &market_account,
+                destination,
+                &quote_mint,
+                &spl_token::id(),
+                amount,
+            )
+            .unwrap()
+        };
+        let send = |instruction: Instruction, signer: &Keypair, recent_blockhash| {
+            let mut transaction = Transaction::new_with_payer(&[instruction], Some(&payer.pubkey()));
+            transaction.sign(&[&payer, signer], recent_blockhash);
+            transaction
+        };
+        let place = |owner: &Keypair, order: &Keypair, is_buy: bool, price: u64, quantity: u64, recent_blockhash| {
+            let instruction = place_ix(owner, order, is_buy, price, quantity, false);
+            let mut transaction = Transaction::new_with_payer(&[instruction], Some(&payer.pubkey()));
+            transaction.sign(&[&payer, owner, order], recent_blockhash);
+            transaction
+        };
+
+        // Only the market authority creates the perp market
+        // 只有市场管理员可以创建永续合约市场
+        let outsider = Keypair::new();
+        let init_ix = |authority: &Pubkey| {
+            DexInstruction::initialize_perp_market(&program_id, authority, &market_account, &quote_mint, &spl_token::id(), 1_000, 100)
+                .unwrap()
+        };
+        let err = context
+            .banks_client
+            .process_transaction(send(init_ix(&outsider.pubkey()), &outsider, recent_blockhash))
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(custom_error(err), DexError::AccountNotAuthorized as u32);
+        context
+            .banks_client
+            .process_transaction(send(init_ix(&authority.pubkey()), &authority, recent_blockhash))
+            .await
+            .unwrap();
+
+        // Both traders deposit collateral
+        // 两个交易者都存入保证金
+        for (trader, source) in [(&alice, alice_tokens), (&bob, bob_tokens)] {
+            let deposit_ix = DexInstruction::deposit_perp_collateral(
+                &program_id,
+                &trader.pubkey(),
+                &market_account,
+                &source,
+                &quote_mint,
+                &spl_token::id(),
+                100_000,
+            )
+            .unwrap();
+            context
+                .banks_client
+                .process_transaction(send(deposit_ix, trader, recent_blockhash))
+                .await
+                .unwrap();
+        }
+        assert_eq!(common::token_balance(&mut context.banks_client, &vault).await, 200_000);
+
+        // Orders must lie within the oracle band
+        // 订单价格必须位于预言机价格区间之内
+        let alice_bid = Keypair::new();
+        let err = context
+            .banks_client
+            .process_transaction(place(&alice, &alice_bid, true, 1_101, 500, recent_blockhash))
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(custom_error(err), DexError::PriceOutsideOracleBand as u32);
+
+        // Alice bids for 500 lots at 10.60 and Bob offers 1000 at 10.50
+        // Alice 以 10.60 买入 500 手，Bob 以 10.50 卖出 1000 手
+        let bob_ask = Keypair::new();
+        for (owner, order, is_buy, price, quantity) in [(&alice, &alice_bid, true, 1_060, 500), (&bob, &bob_ask, false, 1_050, 1_000)] {
+            context
+                .banks_client
+                .process_transaction(place(owner, order, is_buy, price, quantity, recent_blockhash))
+                .await
+                .unwrap();
+        }
+
+        // The authority cannot fill a position without an order of its owner
+        // 没有仓位所有者的订单，管理员无法为其仓位成交
+        let mut unsolicited = fill_ix(&alice_bid, &alice, &bob_ask, &bob);
+        unsolicited.accounts[3].pubkey = alice_position_address;
+        let err = context
+            .banks_client
+            .process_transaction(send(unsolicited, &authority, recent_blockhash))
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(custom_error(err), DexError::InvalidAccountData as u32);
+
+        // Nor pair an order with another owner's position
+        // 也不能将订单与其他所有者的仓位配对
+        let mut unsolicited = fill_ix(&alice_bid, &alice, &bob_ask, &bob);
+        unsolicited.accounts.swap(5, 6);
+        let err = context
+            .banks_client
+            .process_transaction(send(unsolicited, &authority, recent_blockhash))
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(custom_error(err), DexError::AccountMismatch as u32);
+
+        // Alice's bid takes 500 lots at Bob's 10.50, paying the taker fee of 1313, and is closed
+        // Alice 的买单以 Bob 的 10.50 成交 500 手，支付 1313 的吃单手续费，并被关闭
+        context
+            .banks_client
+            .process_transaction(send(fill_ix(&alice_bid, &alice, &bob_ask, &bob), &authority, recent_blockhash))
+            .await
+            .unwrap();
+        assert_eq!(common::token_balance(&mut context.banks_client, &fee_vault).await, 1_313);
+        let alice_position = context.banks_client.get_account(alice_position_address).await.unwrap().unwrap();
+        let alice_position = PerpPosition::unpack_from_slice(&alice_position.data).unwrap();
+        assert_eq!((alice_position.base_position, alice_position.quote_balance), (500, 100_000 - 525_000 - 1_313));
+        assert!(context.banks_client.get_account(alice_bid.pubkey()).await.unwrap().is_none());
+        let bob_order = context.banks_client.get_account(bob_ask.pubkey()).await.unwrap().unwrap();
+        assert_eq!(PerpOrder::unpack_from_slice(&bob_order.data).unwrap().remaining_quantity, 500);
+
+        // Growing the long past ten times its equity fails
+        // 多头仓位超过其权益的十倍时失败
+        let blockhash = context.get_new_latest_blockhash().await.unwrap();
+        let alice_bid = Keypair::new();
+        context
+            .banks_client
+            .process_transaction(place(&alice, &alice_bid, true, 1_050, 500, blockhash))
+            .await
+            .unwrap();
+        let err = context
+            .banks_client
+            .process_transaction(send(fill_ix(&alice_bid, &alice, &bob_ask, &bob), &authority, blockhash))
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(custom_error(err), DexError::InsufficientMargin as u32);
+
+        // Only the owner cancels a perp order, which returns its rent
+        // 只有所有者可以取消永续合约订单，取消后退还租金
+        for (owner, order) in [(&alice, &alice_bid), (&bob, &bob_ask)] {
+            let mut cancel_ix =
+                DexInstruction::cancel_perp_order(&program_id, &owner.pubkey(), &market_account, &order.pubkey()).unwrap();
+            cancel_ix.accounts[0].pubkey = outsider.pubkey();
+            let err = context
+                .banks_client
+                .process_transaction(send(cancel_ix, &outsider, blockhash))
+                .await
+                .unwrap_err()
+                .unwrap();
+            assert_eq!(custom_error(err), DexError::AccountNotAuthorized as u32);
+            let cancel_ix =
+                DexInstruction::cancel_perp_order(&program_id, &owner.pubkey(), &market_account, &order.pubkey()).unwrap();
+            context
+                .banks_client
+                .process_transaction(send(cancel_ix, owner, blockhash))
+                .await
+                .unwrap();
+            assert!(context.banks_client.get_account(order.pubkey()).await.unwrap().is_none());
+        }
+
+        // Funding is not due before an interval has passed
+        // 一个周期结束之前不需要支付资金费用
+        let update_funding_ix = DexInstruction::update_funding(&program_id, &market_account, &oracle).unwrap();
+        let mut transaction = Transaction::new_with_payer(std::slice::from_ref(&update_funding_ix), Some(&payer.pubkey()));
+        transaction.sign(&[&payer], blockhash);
+        let err = context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(custom_error(err), DexError::FundingNotDue as u32);
+
+        // Two intervals at a premium of 0.50 make the long pay the short
+        // 两个周期 0.50 的溢价使多头向空头支付
+        let blockhash = common::advance_unix_timestamp(&mut context, 2 * FUNDING_INTERVAL_SECONDS).await;
+        let mut transaction = Transaction::new_with_payer(&[update_funding_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer], blockhash);
+        context.banks_client.process_transaction(transaction).await.unwrap();
+        let perp_market = context.banks_client.get_account(perp_market_address).await.unwrap().unwrap();
+        let perp_market = PerpMarket::unpack_from_slice(&perp_market.data).unwrap();
+        assert_eq!(perp_market.cumulative_funding, 2 * (50 * FUNDING_PRECISION / 24));
+        assert_eq!(perp_market.mark_price, 1_050);
+        assert_eq!(perp_market.long_open_interest, 500);
+
+        // Alice pays 2084 of funding and may withdraw down to her initial margin of 52500
+        // Alice 支付 2084 的资金费用，并可提取至 52500 的初始保证金
+        let alice_equity = 100_000 - 1_313 - 2_084;
+        let err = context
+            .banks_client
+            .process_transaction(send(withdraw_ix(&alice, &alice_tokens, alice_equity - 52_499), &alice, blockhash))
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(custom_error(err), DexError::InsufficientMargin as u32);
+        context
+            .banks_client
+            .process_transaction(send(withdraw_ix(&alice, &alice_tokens, alice_equity - 52_500), &alice, blockhash))
+            .await
+            .unwrap();
+        assert_eq!(
+            common::token_balance(&mut context.banks_client, &alice_tokens).await,
+            900_000 + alice_equity - 52_500
+        );
+
+        // Bob receives 2083 of funding, rounded down
+        // Bob 收取 2083 的资金费用，向下取整
+        let bob_equity = 100_000 + 2_083;
+        context
+            .banks_client
+            .process_transaction(send(withdraw_ix(&bob, &bob_tokens, bob_equity - 52_500), &bob, blockhash))
+            .await
+            .unwrap();
+
+        // Bob bids for 800 lots at 10.00 and Alice offers 800
+        // Bob 以 10.00 买入 800 手，Alice 卖出 800 手
+        let blockhash = context.get_new_latest_blockhash().await.unwrap();
+        let bob_bid = Keypair::new();
+        let alice_ask = Keypair::new();
+        for (owner, order, is_buy) in [(&bob, &bob_bid, true), (&alice, &alice_ask, false)] {
+            context
+                .banks_client
+                .process_transaction(place(owner, order, is_buy, 1_000, 800, blockhash))
+                .await
+                .unwrap();
+        }
+
+        // Only Alice makes her order reduce-only
+        // 只有 Alice 可以将其订单设为只减仓
+        let reduce_only_ix = |signer: &Keypair| {
+            let mut instruction =
+                DexInstruction::set_perp_reduce_only(&program_id, &alice.pubkey(), &market_account, &alice_ask.pubkey(), true)
+                    .unwrap();
+            instruction.accounts[0].pubkey = signer.pubkey();
+            instruction
+        };
+        let err = context
+            .banks_client
+            .process_transaction(send(reduce_only_ix(&bob), &bob, blockhash))
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(custom_error(err), DexError::AccountNotAuthorized as u32);
+        context
+            .banks_client
+            .process_transaction(send(reduce_only_ix(&alice), &alice, blockhash))
+            .await
+            .unwrap();
+
+        // Her offer takes Bob's bid clipped to her long of 500, and the rest of it stays on the
+        // book; closing a position is allowed whatever its margin
+        // 她的卖单与 Bob 的买单成交，被截断为其 500 手的多头仓位，其余部分留在订单簿上；
+        // 且无论保证金多少都允许平仓
+        context
+            .banks_client
+            .process_transaction(send(fill_ix(&alice_ask, &alice, &bob_bid, &bob), &authority, blockhash))
+            .await
+            .unwrap();
+        let alice_order = context.banks_client.get_account(alice_ask.pubkey()).await.unwrap().unwrap();
+        let alice_order = PerpOrder::unpack_from_slice(&alice_order.data).unwrap();
+        assert_eq!(alice_order.remaining_quantity, 300);
+        assert!(alice_order.reduce_only);
+        let alice_position = context.banks_client.get_account(alice_position_address).await.unwrap().unwrap();
+        let alice_position = PerpPosition::unpack_from_slice(&alice_position.data).unwrap();
+        assert_eq!(alice_position.base_position, 0);
+        assert_eq!(alice_position.quote_balance, 52_500 - 25_000 - 1_250);
+        let perp_market = context.banks_client.get_account(perp_market_address).await.unwrap().unwrap();
+        let perp_market = PerpMarket::unpack_from_slice(&perp_market.data).unwrap();
+        assert_eq!(perp_market.long_open_interest, 0);
+        assert_eq!(perp_market.mark_price, 1_000);
+
+        // With nothing left to close, her reduce-only order takes no further fill
+        // 没有可平的仓位时，只减仓订单不再接受成交
+        let blockhash = context.get_new_latest_blockhash().await.unwrap();
+        let err = context
+            .banks_client
+            .process_transaction(send(fill_ix(&alice_ask, &alice, &bob_bid, &bob), &authority, blockhash))
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(custom_error(err), DexError::ReduceOnly as u32);
+
+        // Orders are reduce-only from the start when placed so
+        // 下单时即可将订单设为只减仓
+        let reduce_only_ask = Keypair::new();
+        let instruction = place_ix(&alice, &reduce_only_ask, false, 1_000, 100, true);
+        let mut transaction = Transaction::new_with_payer(&[instruction], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &alice, &reduce_only_ask], blockhash);
+        context.banks_client.process_transaction(transaction).await.unwrap();
+        let order = context.banks_client.get_account(reduce_only_ask.pubkey()).await.unwrap().unwrap();
+        assert!(PerpOrder::unpack_from_slice(&order.data).unwrap().reduce_only);
+    }
+}
@@ -0,0 +1,467 @@
+// Constant-product pool test module
+// 恒定乘积资金池测试模块
+
+mod common;
+
+#[cfg(test)]
+mod pool_tests {
+    use super::common;
+    use solana_program::{program_pack::Pack, pubkey::Pubkey, system_instruction};
+    use solana_program_test::*;
+    use solana_sdk::{
+        account::Account,
+        instruction::{Instruction, InstructionError},
+        signature::{Keypair, Signer},
+        transaction::{Transaction, TransactionError},
+    };
+    use solana_rust_dex::{
+        error::DexError,
+        instruction::{DexInstruction, RouteOrder, SelfTradeBehavior},
+        state::{Market, Order, Pool, POOL_MINIMUM_LIQUIDITY},
+    };
+
+    fn custom_error(err: TransactionError) -> u32 {
+        match err {
+            TransactionError::InstructionError(0, InstructionError::Custom(code)) => code,
+            err => panic!("unexpected error: {:?}", err),
+        }
+    }
+
+    fn market(authority: &Pubkey, base_mint: &Pubkey, quote_mint: &Pubkey) -> Market {
+        Market {
+            authority: *authority,
+            base_mint: *base_mint,
+            quote_mint: *quote_mint,
+            taker_fee_bps: 25,
+            next_order_id: 0,
+            fee_authority: *authority,
+            ..common::market()
+        }
+    }
+
+    fn pool(fee_bps: u16, base_reserve: u64, quote_reserve: u64, lp_supply: u64) -> Pool {
+        Pool {
+            is_initialized: true,
+            market: Pubkey::new_unique(),
+            lp_mint: Pubkey::new_unique(),
+            bump: 255,
+            fee_bps,
+            base_reserve,
+            quote_reserve,
+            lp_supply,
+        }
+    }
+
+    #[test]
+    fn test_pool_amounts() {
+        // The first deposit mints the geometric mean less the locked liquidity
+        // 首次存入铸造几何平均数减去锁定的流动性
+        let empty = pool(30, 0, 0, 0);
+        assert_eq!(empty.deposit_amounts(1_000_000, 4_000_000).unwrap(), (1_999_000, 1_000_000, 4_000_000));
+        assert_eq!(empty.deposit_amounts(10, 10).unwrap().0, 0);
+
+        // Later deposits follow the scarcer side and take the other at the pool's ratio
+        // 后续存入以较少的一侧为准，另一侧按资金池比例收取
+        let pool = pool(30, 1_000_000, 4_000_000, 2_000_000);
+        assert_eq!(pool.deposit_amounts(100_000, 1_000_000).unwrap(), (200_000, 100_000, 400_000));
+        assert_eq!(pool.deposit_amounts(1_000_000, 400_001).unwrap(), (200_000, 100_000, 400_000));
+        assert_eq!(pool.withdraw_amounts(200_000).unwrap(), (100_000, 400_000));
+
+        // Swaps take the fee from the input and never decrease the product of the reserves
+        // 兑换从输入中扣除手续费，且储备的乘积永不减少
+        assert_eq!(pool.swap_amount_out(true, 40_000).unwrap(), 9_871);
+        for (is_buy, amount_in) in [(true, 1), (true, 4_000_000), (false, 3), (false, 999_999), (true, u64::MAX / 2)] {
+            let amount_out = pool.swap_amount_out(is_buy, amount_in).unwrap();
+            let (base, quote) = if is_buy {
+                (pool.base_reserve - amount_out, pool.quote_reserve as u128 + amount_in as u128)
+            } else {
+                (pool.base_reserve + amount_in, pool.quote_reserve as u128 - amount_out as u128)
+            };
+            assert!(base as u128 * quote >= pool.base_reserve as u128 * pool.quote_reserve as u128);
+        }
+    }
+
+    #[test]
+    fn test_pool_marginal_price() {
+        // A 4 quote per base pool with a 0.3% fee costs about 4.012 to buy from and pays about
+        // 3.988 to sell to
+        // 每基础代币 4 报价代币、手续费 0.3% 的资金池，买入成本约 4.012，卖出所得约 3.988
+        let pool = pool(30, 1_000_000, 4_000_000, 2_000_000);
+        assert!(pool.beats_price(true, 4_013, 1_000).unwrap());
+        assert!(!pool.beats_price(true, 4_012, 1_000).unwrap());
+        assert!(pool.beats_price(false, 3_987, 1_000).unwrap());
+        assert!(!pool.beats_price(false, 3_988, 1_000).unwrap());
+        assert!(!self::pool(30, 0, 0, 0).beats_price(true, u64::MAX as u128, 1).unwrap());
+
+        // The pool takes input until its marginal price stops beating the target, and no further
+        // 资金池接收输入直到其边际价格不再优于目标价格，且不会更多
+        let (numerator, denominator) = (5_000, 1_000);
+        let amount_in = pool.swap_amount_to_price(true, u64::MAX / 4, numerator, denominator).unwrap();
+        let mut after = pool.clone();
+        after.swap(true, amount_in).unwrap();
+        assert!(after.beats_price(true, numerator, denominator).unwrap());
+        let mut past = pool.clone();
+        past.swap(true, amount_in + 1).unwrap();
+        assert!(!past.beats_price(true, numerator, denominator).unwrap());
+        assert_eq!(pool.swap_amount_to_price(true, 1_000, numerator, denominator).unwrap(), 1_000);
+        assert_eq!(pool.swap_amount_to_price(true, 1_000, 4_000, 1_000).unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_pool_liquidity_and_swaps() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // Add the mints, a market and the token accounts of a liquidity provider and a trader
+        // 添加代币铸造账户、市场以及流动性提供者和交易者的代币账户
+        let base_mint = common::add_mint(&mut program_test, 6, None);
+        let quote_mint = common::add_mint(&mut program_test, 6, None);
+        let authority = Keypair::new();
+        let market_account = Pubkey::new_unique();
+        let mut market_data = vec![0; Market::LEN];
+        market(&authority.pubkey(), &base_mint, &quote_mint).pack_into_slice(&mut market_data);
+        program_test.add_account(
+            market_account,
+            Account {
+                lamports: 1000000000,
+                data: market_data,
+                owner: program_id,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+
+        let provider = Keypair::new();
+        let provider_base = common::add_token_account(&mut program_test, &base_mint, &provider.pubkey(), None, 2_000_000);
+        let provider_quote = common::add_token_account(&mut program_test, &quote_mint, &provider.pubkey(), None, 8_000_000);
+        let trader = Keypair::new();
+        let trader_base = common::add_token_account(&mut program_test, &base_mint, &trader.pubkey(), None, 0);
+        let trader_quote = common::add_token_account(&mut program_test, &quote_mint, &trader.pubkey(), None, 40_000);
+
+        // Start the test environment
+        // 启动测试环境
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let send = |instruction: Instruction, signer: &Keypair| {
+            let mut transaction = Transaction::new_with_payer(&[instruction], Some(&payer.pubkey()));
+            transaction.sign(&[&payer, signer], recent_blockhash);
+            transaction
+        };
+
+        // The market authority pays for the pool accounts
+        // 市场权限账户支付资金池账户的费用
+        let fund_authority_ix = system_instruction::transfer(&payer.pubkey(), &authority.pubkey(), 1_000_000_000);
+        let mut transaction = Transaction::new_with_payer(&[fund_authority_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // Only the market authority creates the pool, with a fee below the whole input
+        // 只有市场权限账户可以创建资金池，且手续费必须低于全部输入
+        let init_pool_ix = |authority: &Pubkey, fee_bps: u16| {
+            DexInstruction::initialize_pool(&program_id, authority, &market_account, &base_mint, &quote_mint, &spl_token::id(), fee_bps)
+                .unwrap()
+        };
+        let err = banks_client
+            .process_transaction(send(init_pool_ix(&provider.pubkey(), 30), &provider))
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(custom_error(err), DexError::AccountNotAuthorized as u32);
+        let err = banks_client
+            .process_transaction(send(init_pool_ix(&authority.pubkey(), 10_000), &authority))
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(custom_error(err), DexError::InvalidInstructionData as u32);
+        banks_client
+            .process_transaction(send(init_pool_ix(&authority.pubkey(), 30), &authority))
+            .await
+            .unwrap();
+
+        let (pool_address, _) = Pool::find_address(&program_id, &market_account);
+        let (lp_mint, _) = Pool::find_lp_mint_address(&program_id, &market_account);
+        let (base_vault, _) = Pool::find_vault_address(&program_id, &market_account, false);
+        let (quote_vault, _) = Pool::find_vault_address(&program_id, &market_account, true);
+        let lp_mint_account = banks_client.get_account(lp_mint).await.unwrap().unwrap();
+        let lp_mint_state = spl_token::state::Mint::unpack(&lp_mint_account.data).unwrap();
+        assert_eq!(lp_mint_state.mint_authority, Some(pool_address).into());
+        assert_eq!(lp_mint_state.decimals, 6);
+
+        // The first deposit sets the price and locks the minimum liquidity
+        // 首次存入设定价格并锁定最低流动性
+        let provider_lp = common::create_token_account(&mut banks_client, &payer, recent_blockhash, &lp_mint, &provider.pubkey()).await;
+        let add_ix = DexInstruction::add_liquidity(
+            &program_id,
+            &provider.pubkey(),
+            &market_account,
+            &provider_base,
+            &provider_quote,
+            &provider_lp,
+            &spl_token::id(),
+            1_000_000,
+            4_000_000,
+            1_999_000,
+        )
+        .unwrap();
+        banks_client.process_transaction(send(add_ix, &provider)).await.unwrap();
+        assert_eq!(common::token_balance(&mut banks_client, &provider_lp).await, 2_000_000 - POOL_MINIMUM_LIQUIDITY);
+
+        // A swap paying out less than its minimum fails, then the trader buys base tokens
+        // 输出低于最低值的兑换会失败，然后交易者买入基础代币
+        let swap_ix = |min_amount_out: u64| {
+            DexInstruction::swap_against_pool(
+                &program_id,
+                &trader.pubkey(),
+                &market_account,
+                &trader_base,
+                &trader_quote,
+                &spl_token::id(),
+                true,
+                40_000,
+                min_amount_out,
+            )
+            .unwrap()
+        };
+        let err = banks_client
+            .process_transaction(send(swap_ix(9_872), &trader))
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(custom_error(err), DexError::SlippageExceeded as u32);
+        banks_client.process_transaction(send(swap_ix(9_871), &trader)).await.unwrap();
+        assert_eq!(common::token_balance(&mut banks_client, &trader_base).await, 9_871);
+        assert_eq!(common::token_balance(&mut banks_client, &trader_quote).await, 0);
+
+        let pool_account = banks_client.get_account(pool_address).await.unwrap().unwrap();
+        let pool = Pool::unpack_from_slice(&pool_account.data).unwrap();
+        assert_eq!((pool.base_reserve, pool.quote_reserve, pool.lp_supply), (990_129, 4_040_000, 2_000_000));
+        assert_eq!(common::token_balance(&mut banks_client, &base_vault).await, 990_129);
+        assert_eq!(common::token_balance(&mut banks_client, &quote_vault).await, 4_040_000);
+
+        // Burning half the minted LP tokens pays out their share, fee included
+        // 销毁一半已铸造的 LP 代币会支付其份额，包括手续费
+        let remove_ix = DexInstruction::remove_liquidity(
+            &program_id,
+            &provider.pubkey(),
+            &market_account,
+            &provider_lp,
+            &provider_base,
+            &provider_quote,
+            &spl_token::id(),
+            999_500,
+            494_816,
+            2_018_990,
+        )
+        .unwrap();
+        banks_client.process_transaction(send(remove_ix, &provider)).await.unwrap();
+        assert_eq!(common::token_balance(&mut banks_client, &provider_lp).await, 999_500);
+        assert_eq!(common::token_balance(&mut banks_client, &provider_base).await, 1_000_000 + 494_816);
+        assert_eq!(common::token_balance(&mut banks_client, &provider_quote).await, 4_000_000 + 2_018_990);
+    }
+
+    #[tokio::test]
+    async fn test_route_swap() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // Add the mints, a market with its signer bump, and the accounts of a liquidity
+        // provider, a maker with two empty order accounts and a trader
+        // 添加代币铸造账户、带签名者 bump 的市场，以及流动性提供者、拥有两个空订单账户的做市商和交易者的账户
+        let base_mint = common::add_mint(&mut program_test, 6, None);
+        let quote_mint = common::add_mint(&mut program_test, 6, None);
+        let authority = Keypair::new();
+        let market_account = Pubkey::new_unique();
+        let mut market_state = market(&authority.pubkey(), &base_mint, &quote_mint);
+        market_state.signer_bump = Market::find_signer_address(&program_id, &market_account).1;
+        let mut market_data = vec![0; Market::LEN];
+        market_state.pack_into_slice(&mut market_data);
+        program_test.add_account(
+            market_account,
+            Account {
+                lamports: 1000000000,
+                data: market_data,
+                owner: program_id,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+
+        let provider = Keypair::new();
+        let provider_base = common::add_token_account(&mut program_test, &base_mint, &provider.pubkey(), None, 1_000_000);
+        let provider_quote = common::add_token_account(&mut program_test, &quote_mint, &provider.pubkey(), None, 4_000_000);
+        let maker = Keypair::new();
+        let maker_base = common::add_token_account(&mut program_test, &base_mint, &maker.pubkey(), None, 20_000);
+        let maker_quote = common::add_token_account(&mut program_test, &quote_mint, &maker.pubkey(), None, 0);
+        let [ask, worse_ask] = [(); 2].map(|_| {
+            let order_account = Pubkey::new_unique();
+            program_test.add_account(
+                order_account,
+                Account {
+                    lamports: 1000000000,
+                    data: vec![0; Order::LEN],
+                    owner: program_id,
+                    executable: false,
+                    rent_epoch: 0,
+                },
+            );
+            order_account
+        });
+        let trader = Keypair::new();
+        let trader_base = common::add_token_account(&mut program_test, &base_mint, &trader.pubkey(), None, 0);
+        let trader_quote = common::add_token_account(&mut program_test, &quote_mint, &trader.pubkey(), None, 600_000);
+
+        // Start the test environment
+        // 启动测试环境
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let send = |instructions: &[Instruction], signer: &Keypair| {
+            let mut transaction = Transaction::new_with_payer(instructions, Some(&payer.pubkey()));
+            transaction.sign(&[&payer, signer], recent_blockhash);
+            transaction
+        };
+
+        // Create the pool at 4 quote tokens per base token, and the market's vaults
+        // 以每基础代币 4 报价代币的价格创建资金池，并创建市场金库
+        let fund_authority_ix = system_instruction::transfer(&payer.pubkey(), &authority.pubkey(), 1_000_000_000);
+        let init_pool_ix =
+            DexInstruction::initialize_pool(&program_id, &authority.pubkey(), &market_account, &base_mint, &quote_mint, &spl_token::id(), 30)
+                .unwrap();
+        banks_client
+            .process_transaction(send(&[fund_authority_ix, init_pool_ix], &authority))
+            .await
+            .unwrap();
+        let (lp_mint, _) = Pool::find_lp_mint_address(&program_id, &market_account);
+        let provider_lp = common::create_token_account(&mut banks_client, &payer, recent_blockhash, &lp_mint, &provider.pubkey()).await;
+        let add_ix = DexInstruction::add_liquidity(
+            &program_id,
+            &provider.pubkey(),
+            &market_account,
+            &provider_base,
+            &provider_quote,
+            &provider_lp,
+            &spl_token::id(),
+            1_000_000,
+            4_000_000,
+            0,
+        )
+        .unwrap();
+        banks_client.process_transaction(send(&[add_ix], &provider)).await.unwrap();
+        let init_vaults_ix =
+            DexInstruction::initialize_market_vaults(&program_id, &payer.pubkey(), &market_account, &base_mint, &quote_mint, &spl_token::id())
+                .unwrap();
+        let init_fee_vault_ix =
+            DexInstruction::initialize_fee_vault(&program_id, &payer.pubkey(), &market_account, &quote_mint, &spl_token::id()).unwrap();
+        let mut transaction = Transaction::new_with_payer(&[init_vaults_ix, init_fee_vault_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // The maker rests an ask just inside the pool's price and one above it
+        // 做市商挂出一个略优于资金池价格的卖单和一个高于该价格的卖单
+        let place_ixs = [(ask, 4), (worse_ask, 5)].map(|(order_account, limit_price)| {
+            DexInstruction::place_limit_order(
+                &program_id,
+                &maker.pubkey(),
+                &market_account,
+                &order_account,
+                &maker_base,
+                &spl_token::id(),
+                false,
+                limit_price,
+                10_000,
+                SelfTradeBehavior::DecrementTake,
+            )
+            .unwrap()
+        });
+        banks_client.process_transaction(send(&place_ixs, &maker)).await.unwrap();
+
+        let route_ix = |orders: [Pubkey; 2], min_amount_out: u64| {
+            let orders = orders.map(|order| RouteOrder {
+                order,
+                owner: maker.pubkey(),
+                owner_base_account: maker_base,
+                owner_quote_account: maker_quote,
+            });
+            DexInstruction::route_swap(
+                &program_id,
+                &trader.pubkey(),
+                &market_account,
+                &trader_base,
+                &trader_quote,
+                &spl_token::id(),
+                &orders,
+                true,
+                600_000,
+                min_amount_out,
+            )
+            .unwrap()
+        };
+
+        // Orders passed out of price order are rejected
+        // 未按价格顺序传入的订单会被拒绝
+        let err = banks_client
+            .process_transaction(send(&[route_ix([worse_ask, ask], 0)], &trader))
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(custom_error(err), DexError::InvalidAccountData as u32);
+
+        // The ask at 4 costs 4.01 with the taker fee, less than the pool's 4.012, so it fills
+        // first; the pool then takes what it sells below 5.0125, the second ask fills, and the
+        // pool takes the rest
+        // 价格为 4 的卖单加上吃单手续费成本为 4.01，低于资金池的 4.012，因此先成交；随后资金池
+        // 接收其低于 5.0125 卖出的部分，第二个卖单成交，剩余部分由资金池接收
+        let pool_address = Pool::find_address(&program_id, &market_account).0;
+        let mut expected_pool = Pool::unpack_from_slice(&banks_client.get_account(pool_address).await.unwrap().unwrap().data).unwrap();
+        let pool_only_out = expected_pool.swap_amount_out(true, 600_000).unwrap();
+        let (numerator, denominator) = market_state.taker_price(true, 4).unwrap();
+        assert!(!expected_pool.beats_price(true, numerator, denominator).unwrap());
+        let (numerator, denominator) = market_state.taker_price(true, 5).unwrap();
+        let pool_share = expected_pool.swap_amount_to_price(true, 600_000 - 40_100, numerator, denominator).unwrap();
+        assert!(pool_share > 0 && pool_share < 600_000 - 40_100 - 50_125);
+        let mut expected_out = 20_000 + expected_pool.swap(true, pool_share).unwrap();
+        expected_out += expected_pool.swap(true, 600_000 - 40_100 - 50_125 - pool_share).unwrap();
+        assert!(expected_out > pool_only_out);
+
+        // Paying out less than the minimum fails, then the route goes through
+        // 支付低于最低值会失败，然后路由成交
+        let err = banks_client
+            .process_transaction(send(&[route_ix([ask, worse_ask], expected_out + 1)], &trader))
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(custom_error(err), DexError::SlippageExceeded as u32);
+        banks_client
+            .process_transaction(send(&[route_ix([ask, worse_ask], expected_out)], &trader))
+            .await
+            .unwrap();
+        assert_eq!(common::token_balance(&mut banks_client, &trader_base).await, expected_out);
+        assert_eq!(common::token_balance(&mut banks_client, &trader_quote).await, 0);
+
+        // Both asks filled and closed, the maker was paid, and the taker fees reached the fee vault
+        // 两个卖单都已成交并关闭，做市商已收款，吃单手续费已进入手续费金库
+        for order_account in [ask, worse_ask] {
+            assert!(banks_client.get_account(order_account).await.unwrap().is_none());
+        }
+        assert_eq!(common::token_balance(&mut banks_client, &maker_quote).await, 90_000);
+        let fee_vault = Market::find_fee_vault_address(&program_id, &market_account).0;
+        assert_eq!(common::token_balance(&mut banks_client, &fee_vault).await, 225);
+        let pool_account = banks_client.get_account(pool_address).await.unwrap().unwrap();
+        assert_eq!(Pool::unpack_from_slice(&pool_account.data).unwrap(), expected_pool);
+
+        let market_account_data = banks_client.get_account(market_account).await.unwrap().unwrap();
+        let market_state = Market::unpack_from_slice(&market_account_data.data).unwrap();
+        assert_eq!({ market_state.num_asks }, 0);
+        assert_eq!({ market_state.fill_seq_num }, 2);
+        assert_eq!({ market_state.fees_accrued }, 225);
+    }
+}
@@ -0,0 +1,375 @@
+// Concentrated-liquidity range pool test module
+// 集中流动性区间资金池测试模块
+
+mod common;
+
+#[cfg(test)]
+mod range_pool_tests {
+    use super::common;
+    use solana_program::{program_error::ProgramError, program_pack::Pack, pubkey::Pubkey, system_instruction};
+    use solana_program_test::*;
+    use solana_sdk::{
+        account::Account,
+        instruction::{Instruction, InstructionError},
+        signature::{Keypair, Signer},
+        transaction::{Transaction, TransactionError},
+    };
+    use solana_rust_dex::{
+        error::DexError,
+        instruction::DexInstruction,
+        math,
+        state::{Market, RangePool, RangePosition},
+    };
+
+    fn custom_error(err: TransactionError) -> u32 {
+        match err {
+            TransactionError::InstructionError(0, InstructionError::Custom(code)) => code,
+            err => panic!("unexpected error: {:?}", err),
+        }
+    }
+
+    fn market(authority: &Pubkey, base_mint: &Pubkey, quote_mint: &Pubkey) -> Market {
+        Market {
+            authority: *authority,
+            base_mint: *base_mint,
+            quote_mint: *quote_mint,
+            taker_fee_bps: 25,
+            next_order_id: 0,
+            fee_authority: *authority,
+            ..common::market()
+        }
+    }
+
+    fn range_pool(fee_bps: u16, tick_spacing: u16, tick: i32) -> RangePool {
+        RangePool {
+            is_initialized: true,
+            market: Pubkey::new_unique(),
+            bump: 255,
+            fee_bps,
+            tick_spacing,
+            sqrt_price: math::sqrt_price_at_tick(tick).unwrap(),
+            tick,
+            liquidity: 0,
+            fee_growth_base: 0,
+            fee_growth_quote: 0,
+            ticks: Vec::new(),
+        }
+    }
+
+    fn position(tick_lower: i32, tick_upper: i32) -> RangePosition {
+        RangePosition {
+            is_initialized: true,
+            market: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            tick_lower,
+            tick_upper,
+            liquidity: 0,
+            fee_growth_inside_base: 0,
+            fee_growth_inside_quote: 0,
+            fees_owed_base: 0,
+            fees_owed_quote: 0,
+        }
+    }
+
+    #[test]
+    fn test_range_positions() {
+        // Ranges must be ordered and on the tick spacing
+        // 区间必须有序且位于刻度间距上
+        let mut pool = range_pool(30, 10, 0);
+        let invalid = ProgramError::Custom(DexError::InvalidTickRange as u32);
+        assert_eq!(pool.check_range(100, -100), Err(invalid.clone()));
+        assert_eq!(pool.check_range(-100, 105), Err(invalid.clone()));
+        assert_eq!(pool.check_range(-300_000, 100), Err(invalid.clone()));
+        assert!(pool.check_range(-100, 100).is_ok());
+
+        // A range around the price takes both tokens, one beside it only one
+        // 包含当前价格的区间收取两种代币，位于一侧的区间只收取一种
+        let mut wide = position(-1_000, 1_000);
+        assert_eq!(pool.update_position(&mut wide, 1_000_000).unwrap(), (48_769, 48_769));
+        let mut above = position(2_000, 3_000);
+        let (base, quote) = pool.update_position(&mut above, 1_000_000).unwrap();
+        assert!(base > 0 && quote == 0);
+        let mut below = position(-3_000, -2_000);
+        let (base, quote) = pool.update_position(&mut below, 1_000_000).unwrap();
+        assert!(base == 0 && quote > 0);
+
+        // Only the range around the price is in range, and ticks stay sorted
+        // 只有包含当前价格的区间处于活跃状态，且刻度保持有序
+        assert_eq!(pool.liquidity, 1_000_000);
+        let indexes: Vec<i32> = pool.ticks.iter().map(|tick| tick.index).collect();
+        assert_eq!(indexes, vec![-3_000, -2_000, -1_000, 1_000, 2_000, 3_000]);
+
+        // Removing liquidity rounds down against the provider and drops unused ticks
+        // 移除流动性向下取整，不利于提供者，并移除不再使用的刻度
+        assert_eq!(pool.update_position(&mut wide, -1_000_000).unwrap(), (48_768, 48_768));
+        assert_eq!(pool.liquidity, 0);
+        assert_eq!(pool.ticks.len(), 4);
+        assert_eq!(pool.update_position(&mut wide, -1), Err(ProgramError::ArithmeticOverflow));
+    }
+
+    #[test]
+    fn test_range_swaps_cross_ticks_and_accrue_fees() {
+        let mut pool = range_pool(30, 10, 0);
+        let mut wide = position(-1_000, 1_000);
+        let mut narrow = position(-100, 100);
+        let (wide_base, wide_quote) = pool.update_position(&mut wide, 1_000_000).unwrap();
+        let (narrow_base, narrow_quote) = pool.update_position(&mut narrow, 4_000_000).unwrap();
+        assert_eq!(pool.liquidity, 5_000_000);
+
+        // A buy past the narrow range crosses its upper tick, leaving only the wide liquidity
+        // 越过窄区间的买入会穿过其上限刻度，只剩下宽区间的流动性
+        assert_eq!(pool.swap(true, 50_000).unwrap(), (50_000, 48_884));
+        assert_eq!((pool.tick, pool.liquidity), (587, 1_000_000));
+
+        // Selling back crosses both ticks again
+        // 卖回时再次穿过两个刻度
+        assert_eq!(pool.swap(false, 60_000).unwrap(), (60_000, 60_755));
+        assert_eq!((pool.tick, pool.liquidity), (-44, 5_000_000));
+
+        // Fees accrue to the liquidity that was in range while they were paid
+        // 手续费归属于支付时处于区间内的流动性
+        assert_eq!(pool.update_position(&mut narrow, -4_000_000).unwrap(), (28_694, 11_222));
+        assert_eq!((narrow.fees_owed_base, narrow.fees_owed_quote), (86, 60));
+        assert_eq!(pool.update_position(&mut wide, -1_000_000).unwrap(), (50_954, 46_586));
+        assert_eq!((wide.fees_owed_base, wide.fees_owed_quote), (94, 90));
+        assert!(pool.ticks.is_empty());
+
+        // The vaults cover everything paid out, fees included
+        // 金库足以支付所有款项，包括手续费
+        let base_in = wide_base + narrow_base + 60_000 - 48_884;
+        let quote_in = wide_quote + narrow_quote + 50_000 - 60_755;
+        assert!(28_694 + 86 + 50_954 + 94 <= base_in);
+        assert!(11_222 + 60 + 46_586 + 90 <= quote_in);
+    }
+
+    #[test]
+    fn test_range_swap_stops_at_the_end_of_the_price_range() {
+        // Past the last position there is no liquidity, so only part of the input is taken
+        // 最后一个头寸之外没有流动性，因此只接收部分输入
+        let mut pool = range_pool(30, 10, 0);
+        let mut wide = position(-1_000, 1_000);
+        pool.update_position(&mut wide, 1_000_000).unwrap();
+        let (amount_used, amount_out) = pool.swap(true, u64::MAX / 2).unwrap();
+        assert_eq!((amount_used, amount_out), (51_424, 48_768));
+        assert_eq!((pool.tick, pool.liquidity), (math::MAX_TICK, 0));
+        assert_eq!(pool.swap(true, 1_000).unwrap(), (0, 0));
+
+        // A swap back jumps over the empty range to the liquidity again
+        // 反向兑换会跳过空区间，重新回到有流动性的区间
+        let (amount_used, amount_out) = pool.swap(false, 1_000).unwrap();
+        assert_eq!(amount_used, 1_000);
+        assert!(amount_out > 1_000);
+        assert_eq!(pool.liquidity, 1_000_000);
+        assert!(pool.tick < 1_000);
+    }
+
+    #[tokio::test]
+    async fn test_range_pool_liquidity_and_swaps() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+
+        // Add the mints, a market and the token accounts of a liquidity provider and a trader
+        // 添加代币铸造账户、市场以及流动性提供者和交易者的代币账户
+        let base_mint = common::add_mint(&mut program_test, 6, None);
+        let quote_mint = common::add_mint(&mut program_test, 6, None);
+        let authority = Keypair::new();
+        let market_account = Pubkey::new_unique();
+        let mut market_data = vec![0; Market::LEN];
+        market(&authority.pubkey(), &base_mint, &quote_mint).pack_into_slice(&mut market_data);
+        program_test.add_account(
+            market_account,
+            Account {
+                lamports: 1000000000,
+                data: market_data,
+                owner: program_id,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+
+        let provider = Keypair::new();
+        let provider_base = common::add_token_account(&mut program_test, &base_mint, &provider.pubkey(), None, 1_000_000);
+        let provider_quote = common::add_token_account(&mut program_test, &quote_mint, &provider.pubkey(), None, 1_000_000);
+        let trader = Keypair::new();
+        let trader_base = common::add_token_account(&mut program_test, &base_mint, &trader.pubkey(), None, 20_000);
+        let trader_quote = common::add_token_account(&mut program_test, &quote_mint, &trader.pubkey(), None, 50_000);
+
+        // Start the test environment
+        // 启动测试环境
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+        let send = |instruction: Instruction, signer: &Keypair| {
+            let mut transaction = Transaction::new_with_payer(&[instruction], Some(&payer.pubkey()));
+            transaction.sign(&[&payer, signer], recent_blockhash);
+            transaction
+        };
+
+        // The market authority pays for the range pool accounts, the provider for its positions
+        // 市场权限账户支付区间资金池账户的费用，提供者支付其头寸的费用
+        for account in [authority.pubkey(), provider.pubkey()] {
+            let fund_ix = system_instruction::transfer(&payer.pubkey(), &account, 1_000_000_000);
+            let mut transaction = Transaction::new_with_payer(&[fund_ix], Some(&payer.pubkey()));
+            transaction.sign(&[&payer], recent_blockhash);
+            banks_client.process_transaction(transaction).await.unwrap();
+        }
+
+        // Only the market authority creates the range pool, with a positive tick spacing
+        // 只有市场权限账户可以创建区间资金池，且刻度间距必须为正
+        let init_ix = |authority: &Pubkey, tick_spacing: u16| {
+            DexInstruction::initialize_range_pool(
+                &program_id,
+                authority,
+                &market_account,
+                &base_mint,
+                &quote_mint,
+                &spl_token::id(),
+                30,
+                tick_spacing,
+                0,
+            )
+            .unwrap()
+        };
+        let err = banks_client
+            .process_transaction(send(init_ix(&provider.pubkey(), 10), &provider))
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(custom_error(err), DexError::AccountNotAuthorized as u32);
+        let err = banks_client
+            .process_transaction(send(init_ix(&authority.pubkey(), 0), &authority))
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(custom_error(err), DexError::InvalidTickRange as u32);
+        banks_client
+            .process_transaction(send(init_ix(&authority.pubkey(), 10), &authority))
+            .await
+            .unwrap();
+
+        let (range_pool_address, _) = RangePool::find_address(&program_id, &market_account);
+        let (base_vault, _) = RangePool::find_vault_address(&program_id, &market_account, false);
+        let (quote_vault, _) = RangePool::find_vault_address(&program_id, &market_account, true);
+        let base_vault_account = banks_client.get_account(base_vault).await.unwrap().unwrap();
+        let base_vault_state = spl_token::state::Account::unpack(&base_vault_account.data).unwrap();
+        assert_eq!(base_vault_state.owner, range_pool_address);
+
+        // Positions must sit on the tick spacing and deposit no more than their maximums
+        // 头寸必须位于刻度间距上，且存入数量不得超过其上限
+        let add_ix = |tick_lower: i32, tick_upper: i32, liquidity: u64, max_amount: u64| {
+            DexInstruction::add_range_liquidity(
+                &program_id,
+                &provider.pubkey(),
+                &market_account,
+                &provider_base,
+                &provider_quote,
+                &spl_token::id(),
+                tick_lower,
+                tick_upper,
+                liquidity,
+                max_amount,
+                max_amount,
+            )
+            .unwrap()
+        };
+        let err = banks_client
+            .process_transaction(send(add_ix(-1_000, 1_005, 1_000_000, 50_000), &provider))
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(custom_error(err), DexError::InvalidTickRange as u32);
+        let err = banks_client
+            .process_transaction(send(add_ix(-1_000, 1_000, 1_000_000, 48_768), &provider))
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(custom_error(err), DexError::SlippageExceeded as u32);
+        banks_client
+            .process_transaction(send(add_ix(-1_000, 1_000, 1_000_000, 48_769), &provider))
+            .await
+            .unwrap();
+        banks_client
+            .process_transaction(send(add_ix(-100, 100, 4_000_000, 19_950), &provider))
+            .await
+            .unwrap();
+        assert_eq!(common::token_balance(&mut banks_client, &provider_base).await, 1_000_000 - 68_719);
+        assert_eq!(common::token_balance(&mut banks_client, &base_vault).await, 68_719);
+        assert_eq!(common::token_balance(&mut banks_client, &quote_vault).await, 68_719);
+
+        // The trader buys past the narrow range and sells back across both of its ticks
+        // 交易者买入越过窄区间，然后卖回穿过其两个刻度
+        let swap_ix = |is_buy: bool, amount_in: u64, min_amount_out: u64| {
+            DexInstruction::swap_against_range_pool(
+                &program_id,
+                &trader.pubkey(),
+                &market_account,
+                &trader_base,
+                &trader_quote,
+                &spl_token::id(),
+                is_buy,
+                amount_in,
+                min_amount_out,
+            )
+            .unwrap()
+        };
+        let err = banks_client
+            .process_transaction(send(swap_ix(true, 50_000, 48_885), &trader))
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(custom_error(err), DexError::SlippageExceeded as u32);
+        banks_client.process_transaction(send(swap_ix(true, 50_000, 48_884), &trader)).await.unwrap();
+        let range_pool_account = banks_client.get_account(range_pool_address).await.unwrap().unwrap();
+        let range_pool = RangePool::unpack_from_slice(&range_pool_account.data).unwrap();
+        assert_eq!((range_pool.tick, range_pool.liquidity), (587, 1_000_000));
+        banks_client.process_transaction(send(swap_ix(false, 60_000, 60_755), &trader)).await.unwrap();
+        assert_eq!(common::token_balance(&mut banks_client, &trader_base).await, 20_000 + 48_884 - 60_000);
+        assert_eq!(common::token_balance(&mut banks_client, &trader_quote).await, 60_755);
+
+        // Removing the narrow position pays out its tokens and fees and closes it
+        // 移除窄区间头寸会支付其代币和手续费并关闭头寸
+        let remove_ix = |tick_lower: i32, tick_upper: i32, liquidity: u64| {
+            let (position, _) = RangePosition::find_address(&program_id, &market_account, &provider.pubkey(), tick_lower, tick_upper);
+            DexInstruction::remove_range_liquidity(
+                &program_id,
+                &provider.pubkey(),
+                &market_account,
+                &position,
+                &provider_base,
+                &provider_quote,
+                &spl_token::id(),
+                liquidity,
+                0,
+                0,
+            )
+            .unwrap()
+        };
+        banks_client.process_transaction(send(remove_ix(-100, 100, 4_000_000), &provider)).await.unwrap();
+        assert_eq!(
+            common::token_balance(&mut banks_client, &provider_base).await,
+            1_000_000 - 68_719 + 28_694 + 86
+        );
+        assert_eq!(
+            common::token_balance(&mut banks_client, &provider_quote).await,
+            1_000_000 - 68_719 + 11_222 + 60
+        );
+        let (narrow_position, _) = RangePosition::find_address(&program_id, &market_account, &provider.pubkey(), -100, 100);
+        assert!(banks_client.get_account(narrow_position).await.unwrap().is_none());
+
+        // Removing no liquidity only collects the wide position's fees and keeps it open
+        // 不移除流动性时只收取宽区间头寸的手续费，头寸保持打开
+        banks_client.process_transaction(send(remove_ix(-1_000, 1_000, 0), &provider)).await.unwrap();
+        assert_eq!(
+            common::token_balance(&mut banks_client, &provider_base).await,
+            1_000_000 - 68_719 + 28_694 + 86 + 94
+        );
+        let (wide_position, _) = RangePosition::find_address(&program_id, &market_account, &provider.pubkey(), -1_000, 1_000);
+        let wide_account = banks_client.get_account(wide_position).await.unwrap().unwrap();
+        let wide = RangePosition::unpack_from_slice(&wide_account.data).unwrap();
+        assert_eq!((wide.liquidity, wide.fees_owed_base, wide.fees_owed_quote), (1_000_000, 0, 0));
+    }
+}
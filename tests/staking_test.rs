@@ -0,0 +1,450 @@
+// Governance token staking test module
+// 治理代币质押测试模块
+
+mod common;
+
+#[cfg(test)]
+mod staking_tests {
+    use super::common;
+    use solana_program::{
+        instruction::AccountMeta, program_error::ProgramError, program_pack::Pack, pubkey::Pubkey, system_instruction,
+    };
+    use solana_program_test::*;
+    use solana_sdk::{
+        account::{Account, AccountSharedData},
+        instruction::InstructionError,
+        signature::{Keypair, Signer},
+        transaction::{Transaction, TransactionError},
+    };
+    use solana_rust_dex::{
+        error::DexError,
+        instruction::DexInstruction,
+        state::{
+            FeeTier, Market, StakeAccount, StakePool, STAKE_REWARD_PRECISION,
+        },
+    };
+
+    const FEE_TIERS: [FeeTier; 4] = [
+        FeeTier { min_stake: 1_000, discount_bps: 1_000 },
+        FeeTier { min_stake: 10_000, discount_bps: 2_000 },
+        FeeTier { min_stake: 0, discount_bps: 0 },
+        FeeTier { min_stake: 0, discount_bps: 0 },
+    ];
+
+    fn custom_error(err: TransactionError) -> u32 {
+        match err {
+            TransactionError::InstructionError(0, InstructionError::Custom(code)) => code,
+            err => panic!("unexpected error: {:?}", err),
+        }
+    }
+
+    fn stake_pool(total_staked: u64) -> StakePool {
+        StakePool {
+            is_initialized: true,
+            bump: 0,
+            governance_mint: Pubkey::new_unique(),
+            governance_token_program: spl_token::id(),
+            governance_decimals: 6,
+            reward_mint: Pubkey::new_unique(),
+            reward_token_program: spl_token::id(),
+            reward_decimals: 6,
+            fee_share_bps: 5_000,
+            epoch_seconds: 3_600,
+            epoch: 0,
+            epoch_start_timestamp: 0,
+            total_staked,
+            reward_per_share: 0,
+            rewards_unclaimed: 0,
+            rewards_distributed: 0,
+            fee_tiers: FEE_TIERS,
+        }
+    }
+
+    fn stake_account(amount: u64) -> StakeAccount {
+        StakeAccount {
+            is_initialized: true,
+            owner: Pubkey::new_unique(),
+            amount,
+            last_reward_per_share: 0,
+            rewards_owed: 0,
+            stake_epoch: 0,
+        }
+    }
+
+    fn market(fee_authority: &Pubkey, quote_mint: &Pubkey) -> Market {
+        Market {
+            authority: *fee_authority,
+            base_mint: Pubkey::new_unique(),
+            quote_mint: *quote_mint,
+            taker_fee_bps: 25,
+            maker_fee_bps: -5,
+            next_order_id: 0,
+            fee_authority: *fee_authority,
+            ..common::market()
+        }
+    }
+
+    #[test]
+    fn test_fee_tiers() {
+        // A stake earns the discount of the highest tier it reaches
+        // 质押获得其达到的最高档位的折扣
+        let pool = stake_pool(0);
+        assert_eq!(pool.discount_bps(999), 0);
+        assert_eq!(pool.discount_bps(1_000), 1_000);
+        assert_eq!(pool.discount_bps(9_999), 1_000);
+        assert_eq!(pool.discount_bps(1_000_000), 2_000);
+
+        // Tiers must ask for growing stakes and grant growing discounts, unused tiers are empty
+        // 档位要求的质押量和给予的折扣必须递增，未使用的档位为空
+        assert!(StakePool::fee_tiers_valid(&FEE_TIERS));
+        assert!(StakePool::fee_tiers_valid(&[FeeTier::default(); 4]));
+        let mut fee_tiers = FEE_TIERS;
+        fee_tiers[1].discount_bps = 1_000;
+        assert!(!StakePool::fee_tiers_valid(&fee_tiers));
+        let mut fee_tiers = FEE_TIERS;
+        fee_tiers[1].min_stake = 1_000;
+        assert!(!StakePool::fee_tiers_valid(&fee_tiers));
+        let mut fee_tiers = FEE_TIERS;
+        fee_tiers[0].min_stake = 0;
+        assert!(!StakePool::fee_tiers_valid(&fee_tiers));
+        let mut fee_tiers = FEE_TIERS;
+        fee_tiers[1].discount_bps = 10_001;
+        assert!(!StakePool::fee_tiers_valid(&fee_tiers));
+    }
+
+    #[test]
+    fn test_rewards_are_shared_by_stake() {
+        // Rewards distributed while nothing is staked wait for the next epoch
+        // 无人质押时分配的奖励留待下一个周期
+        let mut pool = stake_pool(0);
+        assert_eq!(pool.distribute(1_000).unwrap(), 0);
+        assert_eq!((pool.reward_per_share, pool.rewards_unclaimed), (0, 0));
+
+        // Each staked token earns the same share of an epoch's rewards
+        // 每个质押的代币获得周期奖励的相同份额
+        let mut pool = stake_pool(4_000);
+        let mut small = stake_account(1_000);
+        let mut large = stake_account(3_000);
+        assert_eq!(pool.distribute(1_000).unwrap(), 1_000);
+        assert_eq!(pool.reward_per_share, STAKE_REWARD_PRECISION / 4);
+        small.settle_rewards(pool.reward_per_share).unwrap();
+        large.settle_rewards(pool.reward_per_share).unwrap();
+        assert_eq!((small.rewards_owed, large.rewards_owed), (250, 750));
+
+        // Settling again without new rewards earns nothing more
+        // 没有新奖励时再次结算不会获得更多
+        small.settle_rewards(pool.reward_per_share).unwrap();
+        assert_eq!(small.rewards_owed, 250);
+        assert_eq!((pool.rewards_unclaimed, pool.rewards_distributed), (1_000, 1_000));
+
+        // A stake may leave only once the epoch it joined is distributed
+        // 质押只有在其加入的周期分配后才能退出
+        assert!(!small.is_unlocked(0));
+        assert!(small.is_unlocked(1));
+    }
+
+    #[test]
+    fn test_discounted_taker_fee() {
+        // 25 bps taker fee, -5 bps maker rebate
+        // 吃单手续费 25 个基点，挂单返佣 5 个基点
+        let market = market(&Pubkey::new_unique(), &Pubkey::new_unique());
+        let full = market.settlement_amounts(1_000_000, false).unwrap();
+        assert_eq!((full.taker_fee, full.vault_fee), (2_500, 2_000));
+
+        // A 20% discount cuts the taker fee and the vault's share of it
+        // 20% 的折扣减少吃单手续费以及金库从中获得的部分
+        let discounted = market.discounted_settlement_amounts(1_000_000, false, 2_000).unwrap();
+        assert_eq!((discounted.taker_fee, discounted.vault_fee), (2_000, 1_500));
+        assert_eq!(discounted.maker_quote_amount, full.maker_quote_amount);
+
+        // The taker fee never drops below the maker rebate it funds
+        // 吃单手续费永远不会低于其支付的挂单返佣
+        let waived = market.discounted_settlement_amounts(1_000_000, false, 10_000).unwrap();
+        assert_eq!((waived.taker_fee, waived.vault_fee), (500, 0));
+    }
+
+    #[test]
+    fn test_settle_funds_with_stake_discount() {
+        let program_id = Pubkey::new_unique();
+        let market = Pubkey::new_unique();
+        let taker_owner = Pubkey::new_unique();
+        let settle_ix = DexInstruction::settle_funds(
+            &program_id,
+            &Pubkey::new_unique(),
+            &market,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &taker_owner,
+            &Pubkey::new_unique(),
+            &spl_token::id(),
+        )
+        .unwrap();
+        let settle_ix = DexInstruction::with_stake_discount(settle_ix, &taker_owner).unwrap();
+
+        // The stake accounts follow the positional optional accounts, so none of them is taken
+        // for a referrer, settlement record or oracle
+        // 质押账户位于按位置传递的可选账户之后，因此不会被当作推荐人、结算记录或预言机
+        assert_eq!(settle_ix.accounts.len(), 21);
+        assert_eq!(settle_ix.accounts[16].pubkey, Market::find_fee_vault_address(&program_id, &market).0);
+        assert_eq!(settle_ix.accounts[17].pubkey, solana_program::system_program::id());
+        assert_eq!(settle_ix.accounts[18].pubkey, solana_program::system_program::id());
+        assert_eq!(settle_ix.accounts[19].pubkey, StakePool::find_address(&program_id).0);
+        assert_eq!(settle_ix.accounts[20].pubkey, StakeAccount::find_address(&program_id, &taker_owner).0);
+
+        // An instruction without a market account is rejected instead of indexed out of bounds
+        // 缺少市场账户的指令会被拒绝，而不会越界访问
+        let mut truncated = settle_ix.clone();
+        truncated.accounts.truncate(1);
+        assert_eq!(
+            DexInstruction::with_stake_discount(truncated, &taker_owner).unwrap_err(),
+            ProgramError::NotEnoughAccountKeys
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stake_share_fees_and_unstake() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+        let mut context = program_test.start_with_context().await;
+        let payer = context.payer.insecure_clone();
+        let recent_blockhash = context.last_blockhash;
+
+        // Create the governance and reward mints and two funded stakers holding governance tokens
+        // 创建治理代币和奖励代币的铸造账户，以及两位有资金并持有治理代币的质押者
+        let governance_mint = common::create_mint(&mut context.banks_client, &payer, recent_blockhash, 6).await;
+        let reward_mint = common::create_mint(&mut context.banks_client, &payer, recent_blockhash, 6).await;
+        let mut stakers = Vec::new();
+        for _ in 0..2 {
+            let staker = Keypair::new();
+            let governance_account = common::create_token_account(
+                &mut context.banks_client,
+                &payer,
+                recent_blockhash,
+                &governance_mint,
+                &staker.pubkey(),
+            )
+            .await;
+            let reward_account = common::create_token_account(
+                &mut context.banks_client,
+                &payer,
+                recent_blockhash,
+                &reward_mint,
+                &staker.pubkey(),
+            )
+            .await;
+            common::mint_to(&mut context.banks_client, &payer, recent_blockhash, &governance_mint, &governance_account, 10_000)
+                .await;
+            let fund_ix = system_instruction::transfer(&payer.pubkey(), &staker.pubkey(), 1_000_000_000);
+            let mut transaction = Transaction::new_with_payer(&[fund_ix], Some(&payer.pubkey()));
+            transaction.sign(&[&payer], recent_blockhash);
+            context.banks_client.process_transaction(transaction).await.unwrap();
+            stakers.push((staker, governance_account, reward_account));
+        }
+
+        // Initialize the config with the payer as admin
+        // 以付款人为管理员初始化配置
+        let init_config_ix = DexInstruction::initialize_program_config(&program_id, &payer.pubkey()).unwrap();
+        let mut transaction = Transaction::new_with_payer(&[init_config_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer], recent_blockhash);
+        context.banks_client.process_transaction(transaction).await.unwrap();
+
+        // Fee tiers granting a smaller discount for a larger stake are rejected
+        // 质押越多折扣越少的手续费档位会被拒绝
+        let mut fee_tiers = FEE_TIERS;
+        fee_tiers.swap(0, 1);
+        let init_pool_ix = DexInstruction::initialize_stake_pool(
+            &program_id,
+            &payer.pubkey(),
+            &governance_mint,
+            &reward_mint,
+            &spl_token::id(),
+            &spl_token::id(),
+            3_600,
+            4_000,
+            fee_tiers,
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(&[init_pool_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer], recent_blockhash);
+        let err = context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(custom_error(err), DexError::InvalidInstructionData as u32);
+
+        // The admin creates the stake pool with hourly epochs and a 40% fee share
+        // 管理员创建按小时划分周期、分享 40% 手续费的质押池
+        let init_pool_ix = DexInstruction::initialize_stake_pool(
+            &program_id,
+            &payer.pubkey(),
+            &governance_mint,
+            &reward_mint,
+            &spl_token::id(),
+            &spl_token::id(),
+            3_600,
+            4_000,
+            FEE_TIERS,
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(&[init_pool_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer], recent_blockhash);
+        context.banks_client.process_transaction(transaction).await.unwrap();
+
+        // The stakers stake 1000 and 3000 tokens
+        // 质押者分别质押 1000 和 3000 个代币
+        for ((staker, governance_account, _), amount) in stakers.iter().zip([1_000, 3_000]) {
+            let stake_ix = DexInstruction::stake(
+                &program_id,
+                &staker.pubkey(),
+                governance_account,
+                &governance_mint,
+                &spl_token::id(),
+                amount,
+            )
+            .unwrap();
+            let mut transaction = Transaction::new_with_payer(&[stake_ix], Some(&payer.pubkey()));
+            transaction.sign(&[&payer, staker], recent_blockhash);
+            context.banks_client.process_transaction(transaction).await.unwrap();
+        }
+        let (stake_vault, _) = StakePool::find_vault_address(&program_id, false);
+        assert_eq!(common::token_balance(&mut context.banks_client, &stake_vault).await, 4_000);
+
+        // The stake may not leave during the epoch it joined
+        // 质押在其加入的周期内不能退出
+        let (staker, governance_account, _) = &stakers[0];
+        let unstake_ix = DexInstruction::unstake(
+            &program_id,
+            &staker.pubkey(),
+            governance_account,
+            &governance_mint,
+            &spl_token::id(),
+            1_000,
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(std::slice::from_ref(&unstake_ix), Some(&payer.pubkey()));
+        transaction.sign(&[&payer, staker], recent_blockhash);
+        let err = context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(custom_error(err), DexError::StakeLocked as u32);
+
+        // A market quoted in the reward mint collects 1000 fees
+        // 以奖励代币报价的市场收取 1000 手续费
+        let fee_authority = Keypair::new();
+        let market_account = Pubkey::new_unique();
+        let mut market_data = vec![0; Market::LEN];
+        market(&fee_authority.pubkey(), &reward_mint).pack_into_slice(&mut market_data);
+        context.set_account(
+            &market_account,
+            &AccountSharedData::from(Account {
+                lamports: 1000000000,
+                data: market_data,
+                owner: program_id,
+                executable: false,
+                rent_epoch: 0,
+            }),
+        );
+        let destination = common::create_token_account(
+            &mut context.banks_client,
+            &payer,
+            recent_blockhash,
+            &reward_mint,
+            &fee_authority.pubkey(),
+        )
+        .await;
+        let init_vault_ix =
+            DexInstruction::initialize_fee_vault(&program_id, &payer.pubkey(), &market_account, &reward_mint, &spl_token::id())
+                .unwrap();
+        let mut transaction = Transaction::new_with_payer(&[init_vault_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer], recent_blockhash);
+        context.banks_client.process_transaction(transaction).await.unwrap();
+        let (fee_vault, _) = Market::find_fee_vault_address(&program_id, &market_account);
+        common::mint_to(&mut context.banks_client, &payer, recent_blockhash, &reward_mint, &fee_vault, 1_000).await;
+
+        // Sweeping with the stake pool pays the stakers' share into the reward vault
+        // 带上质押池提取时，质押者的份额支付到奖励金库
+        let (reward_vault, _) = StakePool::find_vault_address(&program_id, true);
+        let mut sweep_ix = DexInstruction::sweep_fees(
+            &program_id,
+            &fee_authority.pubkey(),
+            &market_account,
+            &destination,
+            &spl_token::id(),
+        )
+        .unwrap();
+        sweep_ix.accounts.extend([
+            AccountMeta::new_readonly(StakePool::find_address(&program_id).0, false),
+            AccountMeta::new(reward_vault, false),
+        ]);
+        let mut transaction = Transaction::new_with_payer(&[sweep_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, &fee_authority], recent_blockhash);
+        context.banks_client.process_transaction(transaction).await.unwrap();
+        for (address, expected) in [(fee_vault, 0), (reward_vault, 400), (destination, 600)] {
+            assert_eq!(common::token_balance(&mut context.banks_client, &address).await, expected);
+        }
+        let market_data = context.banks_client.get_account(market_account).await.unwrap().unwrap();
+        assert_eq!({ Market::unpack_from_slice(&market_data.data).unwrap().fees_swept }, 1_000);
+
+        // The rewards may not be distributed before the epoch has run its length
+        // 周期结束之前不能分配奖励
+        let distribute_ix = DexInstruction::distribute_staking_rewards(&program_id).unwrap();
+        let mut transaction = Transaction::new_with_payer(std::slice::from_ref(&distribute_ix), Some(&payer.pubkey()));
+        transaction.sign(&[&payer], recent_blockhash);
+        let err = context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(custom_error(err), DexError::EpochNotEnded as u32);
+
+        // An hour later anyone may distribute them, and the epoch advances
+        // 一小时后任何人都可以分配奖励，周期随之推进
+        let recent_blockhash = common::advance_unix_timestamp(&mut context, 3_600).await;
+        let mut transaction = Transaction::new_with_payer(&[distribute_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer], recent_blockhash);
+        context.banks_client.process_transaction(transaction).await.unwrap();
+        let pool_data = context
+            .banks_client
+            .get_account(StakePool::find_address(&program_id).0)
+            .await
+            .unwrap()
+            .unwrap();
+        let pool = StakePool::unpack_from_slice(&pool_data.data).unwrap();
+        assert_eq!((pool.epoch, pool.rewards_unclaimed, pool.rewards_distributed), (1, 400, 400));
+
+        // The stakers claim rewards in proportion to their stakes
+        // 质押者按质押比例领取奖励
+        for ((staker, _, reward_account), expected) in stakers.iter().zip([100, 300]) {
+            let claim_ix = DexInstruction::claim_staking_rewards(
+                &program_id,
+                &staker.pubkey(),
+                reward_account,
+                &reward_mint,
+                &spl_token::id(),
+            )
+            .unwrap();
+            let mut transaction = Transaction::new_with_payer(&[claim_ix], Some(&payer.pubkey()));
+            transaction.sign(&[&payer, staker], recent_blockhash);
+            context.banks_client.process_transaction(transaction).await.unwrap();
+            assert_eq!(common::token_balance(&mut context.banks_client, reward_account).await, expected);
+        }
+        assert_eq!(common::token_balance(&mut context.banks_client, &reward_vault).await, 0);
+
+        // Once its epoch is distributed the stake may leave
+        // 其所在周期分配后，质押可以退出
+        let (staker, governance_account, _) = &stakers[0];
+        let mut transaction = Transaction::new_with_payer(&[unstake_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer, staker], recent_blockhash);
+        context.banks_client.process_transaction(transaction).await.unwrap();
+        assert_eq!(common::token_balance(&mut context.banks_client, governance_account).await, 10_000);
+        let stake_data = context
+            .banks_client
+            .get_account(StakeAccount::find_address(&program_id, &staker.pubkey()).0)
+            .await
+            .unwrap()
+            .unwrap();
+        let stake = StakeAccount::unpack_from_slice(&stake_data.data).unwrap();
+        assert_eq!((stake.amount, stake.rewards_owed), (0, 0));
+    }
+}
@@ -0,0 +1,451 @@
+// Token-2022 test module
+// Token-2022 测试模块
+
+mod common;
+
+#[cfg(test)]
+mod token_tests {
+    use super::common;
+    use solana_program::{program_error::ProgramError, program_pack::Pack, pubkey::Pubkey, rent::Rent, system_instruction};
+    use solana_program_test::*;
+    use solana_sdk::{
+        instruction::InstructionError,
+        signature::{Keypair, Signer},
+        transaction::{Transaction, TransactionError},
+    };
+    use solana_rust_dex::{
+        error::DexError,
+        instruction::{DexInstruction, SelfTradeBehavior},
+        state::{Market, Order},
+        token,
+    };
+    use spl_token_2022::{
+        extension::{
+            transfer_fee::{instruction as transfer_fee_instruction, TransferFee, TransferFeeConfig},
+            ExtensionType, StateWithExtensionsMut,
+        },
+        state::{Account, Mint},
+    };
+
+    // Mint data with a transfer fee of `basis_points`, capped at `maximum_fee`
+    // 带有 `basis_points` 转账手续费、上限为 `maximum_fee` 的代币铸造账户数据
+    fn transfer_fee_mint_data(basis_points: u16, maximum_fee: u64) -> Vec<u8> {
+        let len = ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::TransferFeeConfig]).unwrap();
+        let mut data = vec![0; len];
+        let mut mint = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut data).unwrap();
+        let transfer_fee = TransferFee {
+            epoch: 0.into(),
+            maximum_fee: maximum_fee.into(),
+            transfer_fee_basis_points: basis_points.into(),
+        };
+        let config = mint.init_extension::<TransferFeeConfig>(true).unwrap();
+        config.older_transfer_fee = transfer_fee;
+        config.newer_transfer_fee = transfer_fee;
+        mint.base = Mint {
+            decimals: 6,
+            is_initialized: true,
+            ..Mint::default()
+        };
+        mint.pack_base();
+        mint.init_account_type().unwrap();
+        data
+    }
+
+    // Create a Token-2022 mint with a transfer fee, with the payer as its authorities
+    // 创建带有转账手续费的 Token-2022 代币铸造账户，由付款方担任其权限账户
+    async fn create_transfer_fee_mint(
+        banks_client: &mut BanksClient,
+        payer: &Keypair,
+        recent_blockhash: solana_sdk::hash::Hash,
+        basis_points: u16,
+        maximum_fee: u64,
+    ) -> Pubkey {
+        let mint = Keypair::new();
+        let len = ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::TransferFeeConfig]).unwrap();
+        let instructions = [
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &mint.pubkey(),
+                Rent::default().minimum_balance(len),
+                len as u64,
+                &spl_token_2022::id(),
+            ),
+            transfer_fee_instruction::initialize_transfer_fee_config(
+                &spl_token_2022::id(),
+                &mint.pubkey(),
+                Some(&payer.pubkey()),
+                Some(&payer.pubkey()),
+                basis_points,
+                maximum_fee,
+            )
+            .unwrap(),
+            spl_token_2022::instruction::initialize_mint2(&spl_token_2022::id(), &mint.pubkey(), &payer.pubkey(), None, 6)
+                .unwrap(),
+        ];
+        let mut transaction = Transaction::new_with_payer(&instructions, Some(&payer.pubkey()));
+        transaction.sign(&[payer, &mint], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+        mint.pubkey()
+    }
+
+    // Create a Token-2022 token account with room for the extensions its mint requires
+    // 创建 Token-2022 代币账户，并为其代币铸造账户要求的扩展预留空间
+    async fn create_token_2022_account(
+        banks_client: &mut BanksClient,
+        payer: &Keypair,
+        recent_blockhash: solana_sdk::hash::Hash,
+        mint: &Pubkey,
+        owner: &Pubkey,
+    ) -> Pubkey {
+        let mint_account = banks_client.get_account(*mint).await.unwrap().unwrap();
+        let len = token::account_len(&mint_account.data).unwrap();
+        let token_account = Keypair::new();
+        let instructions = [
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &token_account.pubkey(),
+                Rent::default().minimum_balance(len),
+                len as u64,
+                &spl_token_2022::id(),
+            ),
+            spl_token_2022::instruction::initialize_account3(&spl_token_2022::id(), &token_account.pubkey(), mint, owner)
+                .unwrap(),
+        ];
+        let mut transaction = Transaction::new_with_payer(&instructions, Some(&payer.pubkey()));
+        transaction.sign(&[payer, &token_account], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+        token_account.pubkey()
+    }
+
+    // Balance of a token account of either token program
+    // 任一代币程序的代币账户余额
+    async fn balance(banks_client: &mut BanksClient, token_account: &Pubkey) -> u64 {
+        let account = banks_client.get_account(*token_account).await.unwrap().unwrap();
+        token::unpack_account(&account.data).unwrap().amount
+    }
+
+    #[test]
+    fn test_transfer_fee_math() {
+        // A 1% fee rounds up, and the inverse fee delivers exactly the amount
+        // 1% 的手续费向上取整，反向计算的手续费恰好送达目标数量
+        let data = transfer_fee_mint_data(100, 1_000_000);
+        assert_eq!(token::transfer_fee(&data, 0, 1_000).unwrap(), 10);
+        assert_eq!(token::transfer_fee(&data, 0, 1_001).unwrap(), 11);
+        for amount in [0, 1, 99, 990, 100_250, 123_456_789] {
+            let fee = token::inverse_transfer_fee(&data, 0, amount).unwrap();
+            assert_eq!(token::transfer_fee(&data, 0, amount + fee).unwrap(), fee, "amount {}", amount);
+        }
+
+        // The maximum fee caps large transfers
+        // 最高手续费限制大额转账的手续费
+        let capped = transfer_fee_mint_data(100, 50);
+        assert_eq!(token::transfer_fee(&capped, 0, 1_000_000).unwrap(), 50);
+        assert_eq!(token::inverse_transfer_fee(&capped, 0, 1_000_000).unwrap(), 50);
+
+        // No transfer delivers anything under a 100% fee
+        // 在 100% 的手续费下任何转账都无法送达
+        let confiscatory = transfer_fee_mint_data(10_000, u64::MAX);
+        assert_eq!(token::inverse_transfer_fee(&confiscatory, 0, 10), Err(ProgramError::ArithmeticOverflow));
+
+        // Mints without the extension charge nothing, and SPL Token data reads the same way
+        // 没有该扩展的代币铸造账户不收取手续费，SPL Token 数据也以相同方式读取
+        let mut plain = vec![0; spl_token::state::Mint::LEN];
+        spl_token::state::Mint {
+            decimals: 9,
+            is_initialized: true,
+            ..spl_token::state::Mint::default()
+        }
+        .pack_into_slice(&mut plain);
+        assert_eq!(token::transfer_fee(&plain, 0, 1_000).unwrap(), 0);
+        assert_eq!(token::inverse_transfer_fee(&plain, 0, 1_000).unwrap(), 0);
+        assert_eq!(token::unpack_mint(&plain).unwrap().decimals, 9);
+        assert_eq!(token::account_len(&plain).unwrap(), spl_token::state::Account::LEN);
+
+        // Accounts of a transfer fee mint hold their withheld fees
+        // 转账手续费代币的账户需保存其被扣留的手续费
+        assert_eq!(
+            token::account_len(&data).unwrap(),
+            ExtensionType::try_calculate_account_len::<Account>(&[ExtensionType::TransferFeeAmount]).unwrap()
+        );
+        assert_eq!(token::unsupported_mint_extension(&data).unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_transfer_fee_quote_mint_reconciles() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+        let order_accounts: Vec<Pubkey> = (0..2)
+            .map(|_| {
+                let order_account = Pubkey::new_unique();
+                program_test.add_account(
+                    order_account,
+                    solana_sdk::account::Account {
+                        lamports: 1000000000,
+                        data: vec![0; Order::LEN],
+                        owner: program_id,
+                        executable: false,
+                        rent_epoch: 0,
+                    },
+                );
+                order_account
+            })
+            .collect();
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        // An SPL Token base mint and a Token-2022 quote mint with a 1% transfer fee
+        // SPL Token 基础代币和带有 1% 转账手续费的 Token-2022 报价代币
+        let base_mint = common::create_mint(&mut banks_client, &payer, recent_blockhash, 9).await;
+        let quote_mint = create_transfer_fee_mint(&mut banks_client, &payer, recent_blockhash, 100, 1_000_000).await;
+
+        // Create the market, its escrow vaults and fee vault, each under its mint's token program
+        // 创建市场及其托管金库和手续费金库，每个金库使用其代币铸造账户的代币程序
+        let market_authority = Keypair::new();
+        let market_account = Keypair::new();
+        let init_market_ix = DexInstruction::initialize_market(
+            &program_id,
+            &market_authority.pubkey(),
+            &market_account.pubkey(),
+            &base_mint,
+            &quote_mint,
+            100,
+            10,
+            25,
+            -5,
+        )
+        .unwrap();
+        let mut init_vaults_ix = DexInstruction::initialize_market_vaults(
+            &program_id,
+            &payer.pubkey(),
+            &market_account.pubkey(),
+            &base_mint,
+            &quote_mint,
+            &spl_token::id(),
+        )
+        .unwrap();
+        init_vaults_ix
+            .accounts
+            .push(solana_sdk::instruction::AccountMeta::new_readonly(spl_token_2022::id(), false));
+        let init_fee_vault_ix = DexInstruction::initialize_fee_vault(
+            &program_id,
+            &payer.pubkey(),
+            &market_account.pubkey(),
+            &quote_mint,
+            &spl_token_2022::id(),
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(
+            &[
+                system_instruction::create_account(
+                    &payer.pubkey(),
+                    &market_account.pubkey(),
+                    Rent::default().minimum_balance(Market::LEN),
+                    Market::LEN as u64,
+                    &program_id,
+                ),
+                system_instruction::transfer(&payer.pubkey(), &market_authority.pubkey(), 1_000_000_000),
+                init_market_ix,
+                init_vaults_ix,
+                init_fee_vault_ix,
+            ],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &market_authority, &market_account], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        let market_data = banks_client.get_account(market_account.pubkey()).await.unwrap().unwrap();
+        let market = Market::unpack_from_slice(&market_data.data).unwrap();
+        assert_eq!(market.base_token_program, spl_token::id());
+        assert_eq!(market.quote_token_program, spl_token_2022::id());
+        assert!(market.uses_token_2022());
+        let quote_vault = Market::find_quote_vault_address(&program_id, &market_account.pubkey()).0;
+        let base_vault = Market::find_base_vault_address(&program_id, &market_account.pubkey()).0;
+        let fee_vault = Market::find_fee_vault_address(&program_id, &market_account.pubkey()).0;
+        let quote_vault_account = banks_client.get_account(quote_vault).await.unwrap().unwrap();
+        assert_eq!(quote_vault_account.owner, spl_token_2022::id());
+
+        // Fund the traders
+        // 为交易者注资
+        let buyer = Keypair::new();
+        let seller = Keypair::new();
+        let buyer_base = common::create_token_account(&mut banks_client, &payer, recent_blockhash, &base_mint, &buyer.pubkey()).await;
+        let seller_base = common::create_token_account(&mut banks_client, &payer, recent_blockhash, &base_mint, &seller.pubkey()).await;
+        let buyer_quote = create_token_2022_account(&mut banks_client, &payer, recent_blockhash, &quote_mint, &buyer.pubkey()).await;
+        let seller_quote = create_token_2022_account(&mut banks_client, &payer, recent_blockhash, &quote_mint, &seller.pubkey()).await;
+        common::mint_to(&mut banks_client, &payer, recent_blockhash, &base_mint, &seller_base, 1_000).await;
+        let mint_quote_ix = spl_token_2022::instruction::mint_to(
+            &spl_token_2022::id(),
+            &quote_mint,
+            &buyer_quote,
+            &payer.pubkey(),
+            &[],
+            1_000_000,
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(&[mint_quote_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // The buyer bids 100 at 1000 and the seller asks 100 at 1000
+        // 买方以 1000 出价 100，卖方以 1000 要价 100
+        let place_order_ix = |owner: &Keypair, order_account: &Pubkey, token_account: &Pubkey, is_buy| {
+            DexInstruction::place_limit_order(
+                &program_id,
+                &owner.pubkey(),
+                &market_account.pubkey(),
+                order_account,
+                token_account,
+                &spl_token::id(),
+                is_buy,
+                1000,
+                100,
+                SelfTradeBehavior::DecrementTake,
+            )
+            .unwrap()
+        };
+
+        // A Token-2022 side cannot move tokens without its mint
+        // Token-2022 一侧在没有其代币铸造账户时无法转移代币
+        let mut transaction = Transaction::new_with_payer(
+            &[place_order_ix(&buyer, &order_accounts[0], &buyer_quote, true)],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &buyer], recent_blockhash);
+        assert_eq!(
+            banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+            TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys)
+        );
+
+        let mut transaction = Transaction::new_with_payer(
+            &[
+                DexInstruction::with_token_mints(place_order_ix(&buyer, &order_accounts[0], &buyer_quote, true), &market)
+                    .unwrap(),
+                DexInstruction::with_token_mints(place_order_ix(&seller, &order_accounts[1], &seller_base, false), &market)
+                    .unwrap(),
+            ],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &buyer, &seller], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // The vault receives exactly the bid's lock, the buyer paying the transfer fee on top
+        // 金库恰好收到买单的锁定金额，买方额外支付转账手续费
+        let quote_mint_data = banks_client.get_account(quote_mint).await.unwrap().unwrap().data;
+        let locked = market.escrow_amount(true, 1000, 100).unwrap();
+        let transfer_fee = token::inverse_transfer_fee(&quote_mint_data, 0, locked).unwrap();
+        assert_eq!((locked, transfer_fee), (100_300, 1014));
+        assert_eq!(balance(&mut banks_client, &quote_vault).await, locked);
+        assert_eq!(balance(&mut banks_client, &buyer_quote).await, 1_000_000 - locked - transfer_fee);
+
+        // Settling empties both vaults; payouts out of them bear the transfer fee
+        // 结算清空两个金库；从金库转出的款项承担转账手续费
+        let settle_ix = DexInstruction::settle_funds(
+            &program_id,
+            &market_authority.pubkey(),
+            &market_account.pubkey(),
+            &order_accounts[0],
+            &order_accounts[1],
+            &buyer_base,
+            &buyer_quote,
+            &seller_base,
+            &seller_quote,
+            &buyer.pubkey(),
+            &seller.pubkey(),
+            &spl_token::id(),
+        )
+        .unwrap();
+        let mut transaction = Transaction::new_with_payer(
+            &[DexInstruction::with_token_mints(settle_ix, &market).unwrap()],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &market_authority], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        assert_eq!(balance(&mut banks_client, &quote_vault).await, 0);
+        assert_eq!(balance(&mut banks_client, &base_vault).await, 0);
+        assert_eq!(balance(&mut banks_client, &buyer_base).await, 100);
+        assert_eq!(
+            balance(&mut banks_client, &buyer_quote).await,
+            1_000_000 - locked - transfer_fee + 50 - token::transfer_fee(&quote_mint_data, 0, 50).unwrap()
+        );
+        let maker_quote_amount = 100 * 1000 + 50;
+        assert_eq!(
+            balance(&mut banks_client, &seller_quote).await,
+            maker_quote_amount - token::transfer_fee(&quote_mint_data, 0, maker_quote_amount).unwrap()
+        );
+        assert_eq!(balance(&mut banks_client, &fee_vault).await, 200 - 2);
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_mint_extension_rejected() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+        let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        // Non-transferable tokens could never leave the vaults
+        // 不可转让的代币永远无法离开金库
+        let quote_mint = Keypair::new();
+        let len = ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::NonTransferable]).unwrap();
+        let base_mint = common::create_mint(&mut banks_client, &payer, recent_blockhash, 9).await;
+        let market_authority = Keypair::new();
+        let market_account = Keypair::new();
+        let mut transaction = Transaction::new_with_payer(
+            &[
+                system_instruction::create_account(
+                    &payer.pubkey(),
+                    &quote_mint.pubkey(),
+                    Rent::default().minimum_balance(len),
+                    len as u64,
+                    &spl_token_2022::id(),
+                ),
+                spl_token_2022::instruction::initialize_non_transferable_mint(&spl_token_2022::id(), &quote_mint.pubkey())
+                    .unwrap(),
+                spl_token_2022::instruction::initialize_mint2(
+                    &spl_token_2022::id(),
+                    &quote_mint.pubkey(),
+                    &payer.pubkey(),
+                    None,
+                    6,
+                )
+                .unwrap(),
+                system_instruction::create_account(
+                    &payer.pubkey(),
+                    &market_account.pubkey(),
+                    Rent::default().minimum_balance(Market::LEN),
+                    Market::LEN as u64,
+                    &program_id,
+                ),
+                system_instruction::transfer(&payer.pubkey(), &market_authority.pubkey(), 1_000_000_000),
+                DexInstruction::initialize_market(
+                    &program_id,
+                    &market_authority.pubkey(),
+                    &market_account.pubkey(),
+                    &base_mint,
+                    &quote_mint.pubkey(),
+                    100,
+                    10,
+                    25,
+                    -5,
+                )
+                .unwrap(),
+            ],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, &quote_mint, &market_authority, &market_account], recent_blockhash);
+        assert_eq!(
+            banks_client.process_transaction(transaction).await.unwrap_err().unwrap(),
+            TransactionError::InstructionError(5, InstructionError::Custom(DexError::InvalidMint as u32))
+        );
+    }
+}
@@ -0,0 +1,324 @@
+// Wormhole price publication test module
+// Wormhole 价格发布测试模块
+
+mod common;
+
+#[cfg(test)]
+mod wormhole_tests {
+    use super::common;
+    use solana_program::{program_pack::Pack, pubkey::Pubkey};
+    use solana_program_test::*;
+    use solana_sdk::{
+        account::Account,
+        instruction::InstructionError,
+        signature::Signer,
+        transaction::{Transaction, TransactionError},
+    };
+    use solana_rust_dex::{
+        error::DexError,
+        instruction::DexInstruction,
+        state::{Market, TwapOracle},
+        wormhole::{self, PostedMessage, PricePayload, CORE_BRIDGE_PROGRAM_ID, PRICE_PAYLOAD_LEN},
+    };
+    #[cfg(feature = "wormhole")]
+    use solana_program::{
+        account_info::AccountInfo, entrypoint::ProgramResult, program::invoke, program_error::ProgramError,
+        rent::Rent, system_instruction, sysvar::Sysvar,
+    };
+
+    // Message fee of the test bridge
+    // 测试桥的消息费用
+    const BRIDGE_FEE: u64 = 100;
+
+    fn sample_prices() -> PricePayload {
+        PricePayload {
+            market: Pubkey::new_unique(),
+            best_bid: 990,
+            best_ask: 1_010,
+            last_price: 1_000,
+            ema_price: 1_002,
+            price_cumulative: u64::MAX as u128 + 7,
+            base_lot_size: 10,
+            quote_lot_size: 1,
+            timestamp: 1_700_000_000,
+            slot: 42,
+        }
+    }
+
+    // Message account as the core bridge writes it after an unreliable post
+    // 核心桥在不可靠发布后写入的消息账户
+    fn posted_message_data(finalized: bool, nonce: u32, sequence: u64, emitter: &Pubkey, payload: &[u8]) -> Vec<u8> {
+        let mut data = b"msu".to_vec();
+        data.push(1);
+        data.push(finalized as u8);
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(&[0; 32]);
+        data.extend_from_slice(&1_700_000_000u32.to_le_bytes());
+        data.extend_from_slice(&nonce.to_le_bytes());
+        data.extend_from_slice(&sequence.to_le_bytes());
+        data.extend_from_slice(&wormhole::CHAIN_ID_SOLANA.to_le_bytes());
+        data.extend_from_slice(emitter.as_ref());
+        data.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        data.extend_from_slice(payload);
+        data
+    }
+
+    // Core bridge standing in for Wormhole: checks the post and writes the message account
+    // 代替 Wormhole 的核心桥：检查发布请求并写入消息账户
+    #[cfg(feature = "wormhole")]
+    fn mock_core_bridge(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+        let (message, emitter, payer, system_program) = (&accounts[1], &accounts[2], &accounts[4], &accounts[8]);
+        if data[0] != 8 || !message.is_signer || !emitter.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        let nonce = u32::from_le_bytes(data[1..5].try_into().unwrap());
+        let payload_len = u32::from_le_bytes(data[5..9].try_into().unwrap()) as usize;
+        let payload = &data[9..9 + payload_len];
+        let finalized = data[9 + payload_len] == 1;
+
+        let posted = posted_message_data(finalized, nonce, 0, emitter.key, payload);
+        invoke(
+            &system_instruction::create_account(
+                payer.key,
+                message.key,
+                Rent::get()?.minimum_balance(posted.len()),
+                posted.len() as u64,
+                program_id,
+            ),
+            &[payer.clone(), message.clone(), system_program.clone()],
+        )?;
+        message.data.borrow_mut().copy_from_slice(&posted);
+        Ok(())
+    }
+
+    #[test]
+    fn test_price_payload_and_posted_message() {
+        // The payload round-trips and is big-endian
+        // 负载可以往返编解码，且为大端序
+        let prices = sample_prices();
+        let payload = prices.to_bytes();
+        assert_eq!(payload.len(), PRICE_PAYLOAD_LEN);
+        assert_eq!(payload[0], wormhole::PRICE_PAYLOAD_ID);
+        assert_eq!(&payload[33..41], &990u64.to_be_bytes());
+        assert_eq!(PricePayload::from_bytes(&payload), Some(prices));
+
+        // Other payloads are not taken for prices
+        // 其他负载不会被当作价格
+        let mut other = payload;
+        other[0] = 2;
+        assert_eq!(PricePayload::from_bytes(&other), None);
+        assert_eq!(PricePayload::from_bytes(&payload[..PRICE_PAYLOAD_LEN - 1]), None);
+
+        // The post instruction is borsh encoded, with the message and emitter signing
+        // 发布指令采用 borsh 编码，消息账户和发送者账户需要签名
+        let message = Pubkey::new_unique();
+        let emitter = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+        let instruction = wormhole::post_message_unreliable(&message, &emitter, &payer, 9, &payload, true).unwrap();
+        assert_eq!(instruction.program_id, CORE_BRIDGE_PROGRAM_ID);
+        assert_eq!(instruction.data[0], 8);
+        assert_eq!(&instruction.data[1..5], &9u32.to_le_bytes());
+        assert_eq!(&instruction.data[5..9], &(PRICE_PAYLOAD_LEN as u32).to_le_bytes());
+        assert_eq!(&instruction.data[9..9 + PRICE_PAYLOAD_LEN], &payload);
+        assert_eq!(instruction.data[9 + PRICE_PAYLOAD_LEN], 1);
+        assert_eq!(instruction.accounts[1].pubkey, message);
+        assert!(instruction.accounts[1].is_signer && instruction.accounts[2].is_signer);
+        assert_eq!(instruction.accounts[3].pubkey, wormhole::sequence_address(&emitter));
+
+        // A posted message decodes with its sequence and VAA ID
+        // 已发布的消息可以解码出序号和 VAA ID
+        let emitter = Pubkey::new_from_array([0xab; 32]);
+        let posted = PostedMessage::unpack(&posted_message_data(false, 9, 5, &emitter, &payload)).unwrap();
+        assert!(!posted.finalized);
+        assert_eq!(posted.nonce, 9);
+        assert_eq!(posted.sequence, 5);
+        assert_eq!(posted.emitter, emitter);
+        assert_eq!(posted.payload, payload.to_vec());
+        assert_eq!(posted.vaa_id(), format!("1/{}/5", "ab".repeat(32)));
+        assert_eq!(PostedMessage::unpack(&[0; 120]), None);
+
+        // The bridge fee and next sequence are read from the bridge's accounts
+        // 桥费用和下一个序号从桥的账户中读取
+        let mut config = vec![0; 24];
+        config[16..24].copy_from_slice(&BRIDGE_FEE.to_le_bytes());
+        assert_eq!(wormhole::bridge_fee(&config), Some(BRIDGE_FEE));
+        assert_eq!(wormhole::bridge_fee(&config[..20]), None);
+        assert_eq!(wormhole::next_sequence(&6u64.to_le_bytes()), Some(6));
+    }
+
+    #[tokio::test]
+    async fn test_publish_prices() {
+        // Create program test environment
+        // 创建程序测试环境
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_rust_dex",
+            program_id,
+            processor!(solana_rust_dex::entrypoint::process_instruction),
+        );
+        #[cfg(feature = "wormhole")]
+        program_test.add_program("wormhole_core_bridge", CORE_BRIDGE_PROGRAM_ID, processor!(mock_core_bridge));
+
+        // Add a market with a book, a last price and a TWAP oracle
+        // 添加一个有订单簿、最新价格和 TWAP 预言机的市场
+        let market_account = Pubkey::new_unique();
+        let market = Market {
+            authority: Pubkey::new_unique(),
+            base_mint: Pubkey::new_unique(),
+            quote_mint: Pubkey::new_unique(),
+            min_base_order_size: 100,
+            tick_size: 10,
+            taker_fee_bps: 25,
+            num_bids: 1,
+            num_asks: 1,
+            fee_authority: Pubkey::new_unique(),
+            base_lot_size: 10,
+            best_bid: 990,
+            best_ask: 1_010,
+            last_price: 1_000,
+            twap_oracle_enabled: true,
+            ..common::market()
+        };
+        let mut market_data = vec![0; Market::LEN];
+        market.pack_into_slice(&mut market_data);
+        program_test.add_account(
+            market_account,
+            Account {
+                lamports: 1000000000,
+                data: market_data,
+                owner: program_id,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+        let twap_oracle = TwapOracle {
+            is_initialized: true,
+            market: market_account,
+            ema_period_seconds: 600,
+            last_price: 1_000,
+            last_update: 1_700_000_000,
+            price_cumulative: 5_000,
+            ema_price: 1_000,
+            fill_count: 3,
+        };
+        let mut twap_oracle_data = vec![0; TwapOracle::LEN];
+        twap_oracle.pack_into_slice(&mut twap_oracle_data);
+        program_test.add_account(
+            TwapOracle::find_address(&program_id, &market_account).0,
+            Account {
+                lamports: 1000000000,
+                data: twap_oracle_data,
+                owner: program_id,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+
+        // Add the bridge config with its message fee and the fee collector
+        // 添加带有消息费用的桥配置账户和费用收集账户
+        let mut config_data = vec![0; 24];
+        config_data[16..24].copy_from_slice(&BRIDGE_FEE.to_le_bytes());
+        program_test.add_account(
+            wormhole::bridge_config_address(),
+            Account {
+                lamports: 1000000000,
+                data: config_data,
+                owner: CORE_BRIDGE_PROGRAM_ID,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+        program_test.add_account(
+            wormhole::fee_collector_address(),
+            Account {
+                lamports: 1000000000,
+                data: vec![],
+                owner: solana_program::system_program::id(),
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+
+        // Start the test environment
+        // 启动测试环境
+        let mut context = program_test.start_with_context().await;
+        let payer = context.payer.insecure_clone();
+        let recent_blockhash = common::set_unix_timestamp(&mut context, 1_700_000_060).await;
+
+        // Only the pinned core bridge program is called
+        // 只会调用指定的核心桥程序
+        let publish_ix = DexInstruction::publish_prices(&program_id, &payer.pubkey(), &market_account, 7, false).unwrap();
+        let mut impostor_ix = publish_ix.clone();
+        impostor_ix.accounts[11].pubkey = Pubkey::new_unique();
+        let mut transaction = Transaction::new_with_payer(&[impostor_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer], recent_blockhash);
+        let err = context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(0, InstructionError::Custom(DexError::AccountMismatch as u32))
+        );
+
+        // The emitter must be the market's
+        // 发送者必须是该市场的发送者
+        let mut foreign_emitter_ix = publish_ix.clone();
+        foreign_emitter_ix.accounts[5].pubkey = wormhole::find_emitter_address(&program_id, &Pubkey::new_unique()).0;
+        let mut transaction = Transaction::new_with_payer(&[foreign_emitter_ix], Some(&payer.pubkey()));
+        transaction.sign(&[&payer], recent_blockhash);
+        let err = context.banks_client.process_transaction(transaction).await.unwrap_err().unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(0, InstructionError::Custom(DexError::InvalidAccountData as u32))
+        );
+
+        let mut transaction = Transaction::new_with_payer(std::slice::from_ref(&publish_ix), Some(&payer.pubkey()));
+        transaction.sign(&[&payer], recent_blockhash);
+        let result = context.banks_client.process_transaction(transaction).await;
+
+        // Without the wormhole feature the program posts nothing
+        // 未启用 wormhole 功能时程序不会发布任何消息
+        #[cfg(not(feature = "wormhole"))]
+        assert_eq!(
+            result.unwrap_err().unwrap(),
+            TransactionError::InstructionError(0, InstructionError::Custom(DexError::InvalidInstructionData as u32))
+        );
+
+        // The bridge fee is paid and the prices are posted from the market's emitter
+        // 支付桥费用，并由市场的发送者发布价格
+        #[cfg(feature = "wormhole")]
+        {
+            result.unwrap();
+            let fee_collector = context
+                .banks_client
+                .get_account(wormhole::fee_collector_address())
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(fee_collector.lamports, 1000000000 + BRIDGE_FEE);
+
+            let message_address = wormhole::find_price_message_address(&program_id, &market_account).0;
+            let message = context.banks_client.get_account(message_address).await.unwrap().unwrap();
+            let posted = PostedMessage::unpack(&message.data).unwrap();
+            assert_eq!(posted.emitter, wormhole::find_emitter_address(&program_id, &market_account).0);
+            assert_eq!(posted.nonce, 7);
+            assert!(!posted.finalized);
+
+            let prices = PricePayload::from_bytes(&posted.payload).unwrap();
+            let clock = common::clock(&mut context).await;
+            assert_eq!(
+                prices,
+                PricePayload {
+                    market: market_account,
+                    best_bid: 990,
+                    best_ask: 1_010,
+                    last_price: 1_000,
+                    ema_price: twap_oracle.ema_price_at(1_700_000_060),
+                    price_cumulative: twap_oracle.price_cumulative_at(1_700_000_060),
+                    base_lot_size: 10,
+                    quote_lot_size: 1,
+                    timestamp: 1_700_000_060,
+                    slot: clock.slot,
+                }
+            );
+        }
+    }
+}